@@ -2,8 +2,11 @@ use rkyv::{rancor::Failure, with::Boxed, Archive, Deserialize, Serialize};
 
 // This is the version used by the older client, which can read newer versions
 // from senders.
+//
+// Both versions preserve their field order so that the fields of the older
+// version remain a prefix of the newer version.
 #[derive(Archive, Deserialize, Serialize)]
-#[archive(check_bytes)]
+#[archive(check_bytes, preserve_order)]
 struct ExampleV1 {
     a: i32,
     b: u32,
@@ -12,7 +15,7 @@ struct ExampleV1 {
 // This is the version used by the newer client, which can send newer versions
 // to receivers.
 #[derive(Archive, Deserialize, Serialize)]
-#[archive(check_bytes)]
+#[archive(check_bytes, preserve_order)]
 struct ExampleV2 {
     a: i32,
     b: i32,
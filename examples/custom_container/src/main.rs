@@ -0,0 +1,290 @@
+//! A custom archived container built from the public building blocks in
+//! `rkyv::collections::raw`.
+//!
+//! `U64Map` is a hash map keyed by `u64`s. Its archived form stores its keys
+//! and values inline in the buckets of an `ArchivedHashTable`.
+
+use std::{collections::HashMap, pin::Pin};
+
+use rkyv::{
+    access, access_mut,
+    bytecheck::CheckBytes,
+    collections::raw::{ArchivedHashTable, HashTableResolver},
+    deserialize,
+    hash::{hash_value, FxHasher64},
+    out_field,
+    rancor::{Error, Failure, Fallible},
+    ser::{Allocator, Writer},
+    to_bytes, Archive, Archived, Deserialize, Portable, Serialize,
+};
+
+/// The load factor of archived `U64Map`s.
+///
+/// The same load factor must be used to serialize and resolve the table.
+const LOAD_FACTOR: (usize, usize) = (7, 8);
+
+#[inline]
+fn hash_key(key: u64) -> u64 {
+    hash_value::<u64, FxHasher64>(&key)
+}
+
+/// A hash map keyed by `u64`s.
+#[derive(Debug, PartialEq)]
+pub struct U64Map<V> {
+    inner: HashMap<u64, V>,
+}
+
+impl<V> FromIterator<(u64, V)> for U64Map<V> {
+    fn from_iter<I: IntoIterator<Item = (u64, V)>>(iter: I) -> Self {
+        Self {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// An entry of an archived `U64Map`.
+#[derive(CheckBytes, Portable)]
+#[archive(crate = rkyv)]
+#[check_bytes(crate = "rkyv::bytecheck")]
+#[repr(C)]
+pub struct ArchivedEntry<V> {
+    key: Archived<u64>,
+    value: V,
+}
+
+/// An archived `U64Map`.
+#[derive(CheckBytes, Portable)]
+#[archive(crate = rkyv)]
+#[check_bytes(crate = "rkyv::bytecheck")]
+#[repr(transparent)]
+pub struct ArchivedU64Map<V> {
+    table: ArchivedHashTable<ArchivedEntry<V>>,
+}
+
+impl<V> ArchivedU64Map<V> {
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Returns the value corresponding to the given key.
+    pub fn get(&self, key: u64) -> Option<&V> {
+        let entry = self
+            .table
+            .get_with(hash_key(key), |entry| entry.key.to_native() == key)?;
+        Some(&entry.value)
+    }
+
+    /// Returns the pinned value corresponding to the given key.
+    pub fn get_pin(self: Pin<&mut Self>, key: u64) -> Option<Pin<&mut V>> {
+        // SAFETY: `table` is structurally pinned.
+        let table = unsafe { self.map_unchecked_mut(|map| &mut map.table) };
+        let entry = table.get_with_mut(hash_key(key), |entry| {
+            entry.key.to_native() == key
+        })?;
+        // SAFETY: `value` is structurally pinned.
+        Some(unsafe { entry.map_unchecked_mut(|entry| &mut entry.value) })
+    }
+
+    /// Returns an iterator over the entries of the map in an arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &V)> {
+        self.table.raw_iter().map(|entry| {
+            // SAFETY: The entry pointers of a table are valid for as long as
+            // the table is borrowed.
+            let entry = unsafe { entry.as_ref() };
+            (entry.key.to_native(), &entry.value)
+        })
+    }
+}
+
+/// Adapts a native key-value pair so it can be serialized into the buckets of
+/// the hash table.
+struct EntryAdapter<'a, V> {
+    key: u64,
+    value: &'a V,
+}
+
+impl<V: Archive> Archive for EntryAdapter<'_, V> {
+    type Archived = ArchivedEntry<V::Archived>;
+    type Resolver = V::Resolver;
+
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        let (fp, fo) = out_field!(out.key);
+        self.key.resolve(pos + fp, (), fo);
+        let (fp, fo) = out_field!(out.value);
+        self.value.resolve(pos + fp, resolver, fo);
+    }
+}
+
+impl<V, S> Serialize<S> for EntryAdapter<'_, V>
+where
+    V: Serialize<S>,
+    S: Fallible + ?Sized,
+{
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+pub struct U64MapResolver {
+    table: HashTableResolver,
+}
+
+impl<V: Archive> Archive for U64Map<V> {
+    type Archived = ArchivedU64Map<V::Archived>;
+    type Resolver = U64MapResolver;
+
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        let (fp, fo) = out_field!(out.table);
+        ArchivedHashTable::resolve_from_len(
+            self.inner.len(),
+            LOAD_FACTOR,
+            pos + fp,
+            resolver.table,
+            fo,
+        );
+    }
+}
+
+impl<V, S> Serialize<S> for U64Map<V>
+where
+    V: Serialize<S>,
+    S: Fallible + Writer + Allocator + ?Sized,
+    S::Error: Error,
+{
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        let entries = self
+            .inner
+            .iter()
+            .map(|(key, value)| EntryAdapter { key: *key, value });
+        let hashes = self.inner.keys().map(|key| hash_key(*key));
+        Ok(U64MapResolver {
+            table: ArchivedHashTable::serialize_from_iter(
+                entries,
+                hashes,
+                LOAD_FACTOR,
+                serializer,
+            )?,
+        })
+    }
+}
+
+impl<V, D> Deserialize<U64Map<V>, D> for ArchivedU64Map<V::Archived>
+where
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<U64Map<V>, D::Error> {
+        let mut inner = HashMap::with_capacity(self.len());
+        for (key, value) in self.iter() {
+            inner.insert(key, value.deserialize(deserializer)?);
+        }
+        Ok(U64Map { inner })
+    }
+}
+
+#[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+#[archive(check_bytes)]
+pub struct Inventory {
+    name: String,
+    stock: U64Map<u32>,
+}
+
+fn main() {
+    let inventory = Inventory {
+        name: "warehouse".to_string(),
+        stock: (0..100u64).map(|sku| (sku * 1009, sku as u32)).collect(),
+    };
+
+    let mut bytes = to_bytes::<_, 4096, Failure>(&inventory).unwrap();
+
+    // Access and validate the archive
+    let archived = access::<ArchivedInventory, Failure>(&bytes).unwrap();
+    assert_eq!(archived.name, "warehouse");
+    assert_eq!(archived.stock.len(), 100);
+    assert_eq!(archived.stock.get(42 * 1009).unwrap().to_native(), 42);
+    assert!(archived.stock.get(42).is_none());
+    println!("sku 42 * 1009: {:?}", archived.stock.get(42 * 1009));
+
+    // Mutate a value in place
+    {
+        let archived =
+            access_mut::<ArchivedInventory, Failure>(&mut bytes).unwrap();
+        // SAFETY: `stock` is structurally pinned.
+        let stock = unsafe {
+            archived.map_unchecked_mut(|inventory| &mut inventory.stock)
+        };
+        let mut value = stock.get_pin(42 * 1009).unwrap();
+        *value = 1000.into();
+    }
+
+    let archived = access::<ArchivedInventory, Failure>(&bytes).unwrap();
+    assert_eq!(archived.stock.get(42 * 1009).unwrap().to_native(), 1000);
+
+    let deserialized =
+        deserialize::<Inventory, _, Failure>(archived, &mut ()).unwrap();
+    assert_eq!(deserialized.stock.inner[&(42 * 1009)], 1000);
+    println!("deserialized {} entries", deserialized.stock.inner.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use rkyv::{access, from_bytes, rancor::Failure, to_bytes};
+
+    use super::{ArchivedU64Map, U64Map};
+
+    #[test]
+    fn round_trip() {
+        for len in [0, 1, 15, 16, 17, 1000] {
+            let map = (0..len).map(|key| (key, key * 2)).collect::<U64Map<_>>();
+            let bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+
+            let archived =
+                access::<ArchivedU64Map<rkyv::Archived<u64>>, Failure>(&bytes)
+                    .unwrap();
+            assert_eq!(archived.len(), len as usize);
+            for key in 0..len {
+                assert_eq!(archived.get(key).unwrap().to_native(), key * 2);
+            }
+            assert!(archived.get(len).is_none());
+
+            let deserialized =
+                from_bytes::<U64Map<u64>, Failure>(&bytes).unwrap();
+            assert_eq!(deserialized, map);
+        }
+    }
+
+    #[test]
+    fn invalid_values_fail_validation() {
+        let map = [(1, true), (2, false)].into_iter().collect::<U64Map<_>>();
+        let mut bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+        let archived = access::<ArchivedU64Map<bool>, Failure>(&bytes).unwrap();
+        let value = archived.get(1).unwrap() as *const bool as usize
+            - bytes.as_ptr() as usize;
+
+        bytes[value] = 2;
+        assert!(access::<ArchivedU64Map<bool>, Failure>(&bytes).is_err());
+    }
+}
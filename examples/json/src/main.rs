@@ -24,8 +24,11 @@ use rkyv::{access, rancor::Failure, Archive, Deserialize, Serialize};
 // This is because those bounds are required by HashMap and Vec, but we removed
 // the default generated bounds to prevent a recursive impl.
 // We can fix this by manually specifying the bounds required by HashMap and Vec
-// in an attribute, and then everything will compile:
+// in an attribute. Deserializing HashMap and Vec also requires the
+// deserializer to implement `AllocationLimiter`, so we specify that bound as
+// well, and then everything will compile:
 #[archive(serialize_bounds(__S: rkyv::ser::Writer + rkyv::ser::Allocator, __S::Error: rkyv::rancor::Error))]
+#[archive(deserialize_bounds(__D: rkyv::de::AllocationLimiter))]
 // We'll also add support for validating our archived type. Validation will
 // allow us to check an arbitrary buffer of bytes before accessing it so we can
 // avoid using any unsafe code.
@@ -0,0 +1,317 @@
+//! Export of archived numeric vectors to Arrow.
+//!
+//! The values of an [`ArchivedVec`] of archived primitives are laid out
+//! contiguously. When the byte order of the archive matches the byte order of
+//! the target and the values are aligned, they are already a valid Arrow
+//! buffer. [`ArchivedVec::to_arrow_buffer`] and [`ArchivedVec::to_arrow_array`]
+//! share them with Arrow without copying, and keep the archive alive for as
+//! long as Arrow uses it. Otherwise, the values are copied and the returned
+//! [`ArrowExport`] records why.
+//!
+//! # Example
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use rkyv::{
+//!     access_unchecked, rancor::Failure, to_bytes, Archived,
+//! };
+//!
+//! let bytes = to_bytes::<_, 256, Failure>(&vec![1u64, 2, 3]).unwrap();
+//! let bytes = Arc::new(bytes);
+//! let archived = unsafe { access_unchecked::<Archived<Vec<u64>>>(&bytes) };
+//!
+//! let array = archived.to_arrow_array(bytes.clone());
+//! # #[cfg(not(feature = "big_endian"))]
+//! assert!(array.is_zero_copy());
+//! assert_eq!(array.into_inner().values(), &[1, 2, 3]);
+//! ```
+
+use core::{
+    fmt,
+    mem::{align_of, size_of_val},
+    ptr::NonNull,
+};
+use std::sync::Arc;
+
+use arrow_array::{
+    types::{
+        Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+        UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+    },
+    ArrowPrimitiveType, PrimitiveArray,
+};
+use arrow_buffer::{alloc::Allocation, Buffer, ScalarBuffer};
+
+use crate::{
+    option::ArchivedOption,
+    primitive::{
+        ArchivedF32, ArchivedF64, ArchivedI16, ArchivedI32, ArchivedI64,
+        ArchivedU16, ArchivedU32, ArchivedU64,
+    },
+    vec::ArchivedVec,
+    Portable,
+};
+
+/// The Arrow native type of an [`ArrowPrimitive`].
+pub type ArrowNative<T> =
+    <<T as ArrowPrimitive>::ArrowType as ArrowPrimitiveType>::Native;
+
+/// An archived primitive which can be exported to Arrow.
+///
+/// # Safety
+///
+/// `Self` must have the same size as its [`ArrowNative`] type. If
+/// `NATIVE_LAYOUT` is `true`, then every `Self` must also be a valid
+/// `ArrowNative<Self>` with the same value as returned by `to_arrow_native`.
+pub unsafe trait ArrowPrimitive: Portable {
+    /// The Arrow type of the primitive.
+    type ArrowType: ArrowPrimitiveType;
+
+    /// Whether `Self` has the same representation as its Arrow native type.
+    ///
+    /// This is `false` for multi-byte primitives when the byte order of the
+    /// archive doesn't match the byte order of the target.
+    const NATIVE_LAYOUT: bool;
+
+    /// Returns the value of the primitive as its Arrow native type.
+    fn to_arrow_native(&self) -> ArrowNative<Self>;
+}
+
+macro_rules! impl_arrow_primitive {
+    ($($archived:ty: $arrow:ty, $native_layout:expr);* $(;)?) => {
+        $(
+            // SAFETY: Archived primitives have the same size as the native
+            // type they archive, and have the same representation when the
+            // byte order matches.
+            unsafe impl ArrowPrimitive for $archived {
+                type ArrowType = $arrow;
+
+                const NATIVE_LAYOUT: bool = $native_layout;
+
+                #[inline]
+                fn to_arrow_native(&self) -> ArrowNative<Self> {
+                    (*self).into()
+                }
+            }
+        )*
+    };
+}
+
+// The byte order of archived multi-byte primitives matches the target.
+const MULTIBYTE_NATIVE_LAYOUT: bool =
+    cfg!(feature = "big_endian") == cfg!(target_endian = "big");
+
+impl_arrow_primitive! {
+    i8: Int8Type, true;
+    u8: UInt8Type, true;
+    ArchivedI16: Int16Type, MULTIBYTE_NATIVE_LAYOUT;
+    ArchivedI32: Int32Type, MULTIBYTE_NATIVE_LAYOUT;
+    ArchivedI64: Int64Type, MULTIBYTE_NATIVE_LAYOUT;
+    ArchivedU16: UInt16Type, MULTIBYTE_NATIVE_LAYOUT;
+    ArchivedU32: UInt32Type, MULTIBYTE_NATIVE_LAYOUT;
+    ArchivedU64: UInt64Type, MULTIBYTE_NATIVE_LAYOUT;
+    ArchivedF32: Float32Type, MULTIBYTE_NATIVE_LAYOUT;
+    ArchivedF64: Float64Type, MULTIBYTE_NATIVE_LAYOUT;
+}
+
+/// The reason that archived values were copied instead of being shared with
+/// Arrow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyReason {
+    /// The byte order of the archive doesn't match the byte order of the
+    /// target.
+    Endianness,
+    /// The values are not aligned for their Arrow native type.
+    ///
+    /// This can only happen when the `unaligned` feature is enabled, since
+    /// archived primitives are otherwise aligned like their native types.
+    Unaligned {
+        /// The address of the first value.
+        address: usize,
+        /// The alignment required by the Arrow native type.
+        align: usize,
+    },
+    /// The values are not located inside the bytes of the owner.
+    NotOwned,
+}
+
+impl fmt::Display for CopyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyReason::Endianness => write!(
+                f,
+                "the byte order of the archive doesn't match the target",
+            ),
+            CopyReason::Unaligned { address, align } => write!(
+                f,
+                "values at {:#x} are not aligned to {} bytes",
+                address, align,
+            ),
+            CopyReason::NotOwned => {
+                write!(f, "values are not located inside the owner")
+            }
+        }
+    }
+}
+
+/// A value exported to Arrow, which either shares its memory with the archive
+/// or was copied out of it.
+#[derive(Debug)]
+pub enum ArrowExport<T> {
+    /// The value shares its memory with the archive.
+    ZeroCopy(T),
+    /// The value was copied out of the archive.
+    Copied {
+        /// The copied value.
+        value: T,
+        /// Why the value could not share its memory with the archive.
+        reason: CopyReason,
+    },
+}
+
+impl<T> ArrowExport<T> {
+    /// Returns whether the value shares its memory with the archive.
+    #[inline]
+    pub fn is_zero_copy(&self) -> bool {
+        matches!(self, ArrowExport::ZeroCopy(_))
+    }
+
+    /// Returns why the value was copied, if it was.
+    #[inline]
+    pub fn copy_reason(&self) -> Option<CopyReason> {
+        match self {
+            ArrowExport::ZeroCopy(_) => None,
+            ArrowExport::Copied { reason, .. } => Some(*reason),
+        }
+    }
+
+    /// Returns a reference to the exported value.
+    #[inline]
+    pub fn get(&self) -> &T {
+        match self {
+            ArrowExport::ZeroCopy(value) => value,
+            ArrowExport::Copied { value, .. } => value,
+        }
+    }
+
+    /// Returns the exported value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        match self {
+            ArrowExport::ZeroCopy(value) => value,
+            ArrowExport::Copied { value, .. } => value,
+        }
+    }
+
+    /// Maps the exported value with the given function.
+    #[inline]
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ArrowExport<U> {
+        match self {
+            ArrowExport::ZeroCopy(value) => ArrowExport::ZeroCopy(f(value)),
+            ArrowExport::Copied { value, reason } => ArrowExport::Copied {
+                value: f(value),
+                reason,
+            },
+        }
+    }
+}
+
+/// Returns why `values` can't be shared with Arrow, if they can't.
+fn copy_reason<T: ArrowPrimitive>(
+    values: &[T],
+    owner: &[u8],
+) -> Option<CopyReason> {
+    if !T::NATIVE_LAYOUT {
+        return Some(CopyReason::Endianness);
+    }
+
+    let address = values.as_ptr().addr();
+    let align = align_of::<ArrowNative<T>>();
+    if address & (align - 1) != 0 {
+        return Some(CopyReason::Unaligned { address, align });
+    }
+
+    let start = owner.as_ptr().addr();
+    let end = start + owner.len();
+    let is_owned = (start..=end).contains(&address)
+        && end - address >= size_of_val(values);
+    if !is_owned {
+        return Some(CopyReason::NotOwned);
+    }
+
+    None
+}
+
+impl<T: ArrowPrimitive> ArchivedVec<T> {
+    /// Exports the values of the vec as an Arrow buffer.
+    ///
+    /// `owner` must contain the bytes of the archive. If the values have the
+    /// same layout as their Arrow native type, the returned buffer points to
+    /// them directly and keeps `owner` alive. Otherwise, the values are copied
+    /// into a new buffer.
+    pub fn to_arrow_buffer<O>(&self, owner: Arc<O>) -> ArrowExport<Buffer>
+    where
+        O: AsRef<[u8]> + Allocation + 'static,
+    {
+        let values = self.as_slice();
+        match copy_reason(values, (*owner).as_ref()) {
+            None => {
+                let ptr = NonNull::from(values).cast::<u8>();
+                // SAFETY: The values are located inside of `owner`, which
+                // will be kept alive for as long as the buffer is. They are
+                // aligned and have the same layout as their Arrow native type.
+                let buffer = unsafe {
+                    Buffer::from_custom_allocation(
+                        ptr,
+                        size_of_val(values),
+                        owner,
+                    )
+                };
+                ArrowExport::ZeroCopy(buffer)
+            }
+            Some(reason) => ArrowExport::Copied {
+                value: Buffer::from_vec(
+                    values.iter().map(T::to_arrow_native).collect::<Vec<_>>(),
+                ),
+                reason,
+            },
+        }
+    }
+
+    /// Exports the values of the vec as an Arrow array without any nulls.
+    ///
+    /// See [`to_arrow_buffer`](ArchivedVec::to_arrow_buffer) for when the
+    /// values are copied.
+    pub fn to_arrow_array<O>(
+        &self,
+        owner: Arc<O>,
+    ) -> ArrowExport<PrimitiveArray<T::ArrowType>>
+    where
+        O: AsRef<[u8]> + Allocation + 'static,
+    {
+        let len = self.len();
+        self.to_arrow_buffer(owner).map(|buffer| {
+            PrimitiveArray::new(ScalarBuffer::new(buffer, 0, len), None)
+        })
+    }
+}
+
+impl<T: ArrowPrimitive> ArchivedVec<ArchivedOption<T>> {
+    /// Exports the values of the vec as a nullable Arrow array.
+    ///
+    /// `None`s become nulls in the validity bitmap of the array. Archived
+    /// options store each tag next to its value, so the values are always
+    /// copied into a new contiguous buffer along with the bitmap.
+    pub fn to_arrow_nullable_array(&self) -> PrimitiveArray<T::ArrowType> {
+        let values = self
+            .iter()
+            .map(|value| match value {
+                ArchivedOption::Some(value) => value.to_arrow_native(),
+                ArchivedOption::None => ArrowNative::<T>::default(),
+            })
+            .collect::<Vec<_>>();
+        let nulls = self.iter().map(ArchivedOption::is_some).collect();
+        PrimitiveArray::new(ScalarBuffer::from(values), Some(nulls))
+    }
+}
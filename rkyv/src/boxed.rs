@@ -1,6 +1,6 @@
 //! An archived version of `Box`.
 
-use core::{borrow::Borrow, cmp, fmt, hash, ops::Deref, pin::Pin};
+use core::{borrow::Borrow, cmp, fmt, hash, ops::Deref, pin::Pin, slice};
 
 use rancor::Fallible;
 
@@ -32,10 +32,17 @@ impl<T: ArchivePointee + ?Sized> ArchivedBox<T> {
         unsafe { &*self.ptr.as_ptr() }
     }
 
+    /// Returns a pointer to the value of this archived box, calculated with
+    /// wrapping arithmetic.
+    #[inline]
+    pub(crate) fn as_ptr_wrapping(&self) -> *const T {
+        self.ptr.as_ptr_wrapping().cast_const()
+    }
+
     /// Returns a pinned mutable reference to the value of this archived box
     #[inline]
     pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut T> {
-        unsafe { self.map_unchecked_mut(|s| &mut *s.ptr.as_ptr()) }
+        unsafe { self.map_unchecked_mut(|s| &mut *s.ptr.as_mut_ptr()) }
     }
 
     /// Resolves an archived box from the given value and parameters.
@@ -194,6 +201,16 @@ impl<T: ArchivePointee + hash::Hash + ?Sized> hash::Hash for ArchivedBox<T> {
     }
 }
 
+impl<'a, T> IntoIterator for &'a ArchivedBox<[T]> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.get().iter()
+    }
+}
+
 impl<T: ArchivePointee + Ord + ?Sized> Ord for ArchivedBox<T> {
     #[inline]
     fn cmp(&self, other: &Self) -> cmp::Ordering {
@@ -263,10 +280,14 @@ mod verify {
     {
         #[inline]
         fn verify(&self, context: &mut C) -> Result<(), C::Error> {
-            let ptr =
-                unsafe { context.bounds_check_subtree_rel_ptr(&self.ptr)? };
-
-            let range = unsafe { context.push_prefix_subtree(ptr)? };
+            context.record_offset(self.ptr.offset_slot())?;
+            let (ptr, range) = unsafe {
+                context.push_aliasable_subtree_base_offset::<T>(
+                    self.ptr.base(),
+                    self.ptr.offset(),
+                    T::pointer_metadata(self.ptr.metadata()),
+                )?
+            };
             unsafe {
                 T::check_bytes(ptr, context)?;
             }
@@ -278,3 +299,40 @@ mod verify {
         }
     }
 }
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use crate::{
+        boxed::ArchivedBox,
+        reflect::{descriptor, DescriptorKind, Reflect, TypeDescriptor},
+        ArchivePointee,
+    };
+
+    impl<T: ArchivePointee + Reflect + ?Sized> Reflect for ArchivedBox<T> {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "Box",
+            DescriptorKind::Pointer {
+                target: descriptor::<T>,
+            },
+        );
+    }
+}
+
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+mod patch {
+    use crate::{
+        boxed::ArchivedBox,
+        patch::{Patchable, Relocation},
+        ArchivePointee, Portable,
+    };
+
+    unsafe impl<T: ArchivePointee + Portable + ?Sized> Patchable
+        for ArchivedBox<T>
+    {
+        #[inline]
+        fn relocations(&self, f: &mut dyn FnMut(Relocation)) {
+            // `ArchivedBox` is a transparent wrapper around its pointer.
+            f(Relocation::Offset(0));
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! A stable C interface for accessing archives from programs written in other
+//! languages.
+//!
+//! Adding `#[archive(c_api(prefix = "..."))]` to a struct with named fields
+//! generates `extern "C"` functions which validate archives of the struct and
+//! read its fields. The archived type must implement `CheckBytes`, for example
+//! with `check_bytes`. For a struct with the prefix `myty`, the functions are:
+//!
+//! - `int myty_check(const uint8_t *ptr, size_t len)`: Validates the archive
+//!   in the given bytes.
+//! - `int myty_root(const uint8_t *ptr, size_t len, const void **out)`:
+//!   Validates the archive and writes a handle to its root to `out`.
+//! - `int myty_get_<field>(const void *handle, T *out)`: Writes the value of
+//!   a field to `out`. See [`CValue`] for the value written for each type.
+//! - `int myty_<field>_get(const void *table, const uint8_t *key, size_t
+//!   key_len, V *out)`: For fields declared as a `HashMap` with `String`
+//!   keys, looks up the value for a key in the table returned by the field
+//!   getter.
+//!
+//! All functions return [`OK`] on success and one of the negative error codes
+//! in this module otherwise. Null pointers and misaligned handles are reported
+//! as errors. Handles must point into a buffer which passed `check` or `root`
+//! and is still alive. Getters never allocate.
+//!
+//! [`emit_c_header`] and [`CHeader`] generate a C header declaring these
+//! functions. Vec elements are returned in their archived layout, which can be
+//! described with [`to_c_header`](crate::layout::to_c_header).
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{c_api::emit_c_header, Archive};
+//!
+//! #[derive(Archive)]
+//! #[archive(check_bytes, c_api(prefix = "point"))]
+//! struct Point {
+//!     x: f32,
+//!     y: f32,
+//! }
+//!
+//! let header = emit_c_header::<ArchivedPoint>();
+//! assert!(header.contains("int point_get_x(const void *handle, float *out);"));
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String};
+use core::{
+    ffi::{c_int, c_void},
+    fmt::Write as _,
+    hash::Hasher,
+    mem::{align_of, size_of},
+    slice, str,
+};
+
+use bytecheck::CheckBytes;
+use rancor::{Failure, Strategy};
+
+use crate::{
+    collections::swiss_table::ArchivedHashMap,
+    primitive::{
+        ArchivedChar, ArchivedF32, ArchivedF64, ArchivedI16, ArchivedI32,
+        ArchivedI64, ArchivedU16, ArchivedU32, ArchivedU64,
+    },
+    string::ArchivedString,
+    validation::{util::access, validators::DefaultValidator},
+    vec::ArchivedVec,
+    Portable,
+};
+
+/// The operation succeeded.
+pub const OK: c_int = 0;
+/// A required pointer argument was null.
+pub const ERROR_NULL: c_int = -1;
+/// A pointer argument was not aligned for the archived type.
+pub const ERROR_ALIGN: c_int = -2;
+/// The bytes did not contain a valid archive.
+pub const ERROR_INVALID: c_int = -3;
+/// The key was not found in the table.
+pub const ERROR_NOT_FOUND: c_int = -4;
+
+/// A pointer and length returned for strings, vecs, tables, and nested
+/// structs.
+///
+/// This is declared as `rkyv_slice` in generated headers.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct CSlice {
+    /// A pointer to the first byte or element.
+    pub ptr: *const c_void,
+    /// The length of the value.
+    ///
+    /// This is the number of bytes for strings and nested structs, the number
+    /// of elements for vecs, and the number of entries for tables.
+    pub len: usize,
+}
+
+/// An archived type which can be returned from a generated C getter.
+///
+/// Primitives are returned by value with the native endianness. Strings and
+/// vecs are returned as a [`CSlice`] of their bytes or elements, and tables
+/// as a `CSlice` pointing to the table. Structs with `c_api` are returned as
+/// a `CSlice` of their bytes whose pointer is a handle for their own getters.
+///
+/// # Safety
+///
+/// `Out` must have the same layout as the C type named by `C_TYPE`.
+pub unsafe trait CValue {
+    /// The type written to the output of a getter.
+    type Out: Copy;
+
+    /// The name of the C type of `Out`.
+    const C_TYPE: &'static str;
+
+    /// Returns the value written to the output of a getter.
+    fn to_c(&self) -> Self::Out;
+}
+
+macro_rules! impl_scalar {
+    ($($ty:ty: $out:ty, $name:literal),* $(,)?) => {
+        $(
+            unsafe impl CValue for $ty {
+                type Out = $out;
+
+                const C_TYPE: &'static str = $name;
+
+                #[inline]
+                fn to_c(&self) -> Self::Out {
+                    (*self).into()
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar! {
+    bool: bool, "bool",
+    i8: i8, "int8_t",
+    u8: u8, "uint8_t",
+    ArchivedI16: i16, "int16_t",
+    ArchivedI32: i32, "int32_t",
+    ArchivedI64: i64, "int64_t",
+    ArchivedU16: u16, "uint16_t",
+    ArchivedU32: u32, "uint32_t",
+    ArchivedU64: u64, "uint64_t",
+    ArchivedF32: f32, "float",
+    ArchivedF64: f64, "double",
+}
+
+unsafe impl CValue for ArchivedChar {
+    type Out = u32;
+
+    const C_TYPE: &'static str = "uint32_t";
+
+    #[inline]
+    fn to_c(&self) -> Self::Out {
+        self.to_native() as u32
+    }
+}
+
+unsafe impl CValue for ArchivedString {
+    type Out = CSlice;
+
+    const C_TYPE: &'static str = "rkyv_slice";
+
+    #[inline]
+    fn to_c(&self) -> Self::Out {
+        CSlice {
+            ptr: self.as_ptr().cast(),
+            len: self.len(),
+        }
+    }
+}
+
+unsafe impl<T> CValue for ArchivedVec<T> {
+    type Out = CSlice;
+
+    const C_TYPE: &'static str = "rkyv_slice";
+
+    #[inline]
+    fn to_c(&self) -> Self::Out {
+        CSlice {
+            ptr: self.as_ptr().cast(),
+            len: self.len(),
+        }
+    }
+}
+
+unsafe impl<V, H> CValue for ArchivedHashMap<ArchivedString, V, H> {
+    type Out = CSlice;
+
+    const C_TYPE: &'static str = "rkyv_slice";
+
+    #[inline]
+    fn to_c(&self) -> Self::Out {
+        CSlice {
+            ptr: (self as *const Self).cast(),
+            len: self.len(),
+        }
+    }
+}
+
+/// An archived table which can be searched from a generated C lookup
+/// function.
+pub trait CMap: CValue {
+    /// The archived type of the values of the table.
+    type Value: CValue;
+
+    /// Returns the value for the given key, or `None` if the key is not in the
+    /// table.
+    fn get_str(&self, key: &str) -> Option<&Self::Value>;
+}
+
+impl<V, H> CMap for ArchivedHashMap<ArchivedString, V, H>
+where
+    V: CValue,
+    H: Hasher + Default,
+{
+    type Value = V;
+
+    #[inline]
+    fn get_str(&self, key: &str) -> Option<&Self::Value> {
+        self.get(key)
+    }
+}
+
+/// A field of a struct with a generated C interface.
+#[derive(Clone, Copy, Debug)]
+pub struct CField {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The C type written by the getter of the field.
+    pub c_type: &'static str,
+    /// The C type of the values of the field if it has a lookup function.
+    pub value_c_type: Option<&'static str>,
+}
+
+/// An archived struct with a generated C interface.
+///
+/// This is implemented by `#[archive(c_api(prefix = "..."))]`.
+pub trait CApi: Portable {
+    /// The prefix of the names of the generated functions.
+    const PREFIX: &'static str;
+    /// The name of the archived type.
+    const NAME: &'static str;
+    /// The fields of the archived type.
+    const FIELDS: &'static [CField];
+}
+
+/// Validates the archived `T` in the given bytes.
+///
+/// This implements the generated `check` function.
+///
+/// # Safety
+///
+/// If `ptr` is not null, it must be valid for reads of `len` bytes.
+#[inline]
+pub unsafe fn check<T>(ptr: *const u8, len: usize) -> c_int
+where
+    T: Portable + CheckBytes<Strategy<DefaultValidator, Failure>>,
+{
+    if ptr.is_null() {
+        return ERROR_NULL;
+    }
+    if !ptr.addr().is_multiple_of(align_of::<T>()) {
+        return ERROR_ALIGN;
+    }
+    if len < size_of::<T>() {
+        return ERROR_INVALID;
+    }
+    // SAFETY: The caller has guaranteed that `ptr` is valid for reads of `len`
+    // bytes.
+    let bytes = unsafe { slice::from_raw_parts(ptr, len) };
+    match access::<T, Failure>(bytes) {
+        Ok(_) => OK,
+        Err(_) => ERROR_INVALID,
+    }
+}
+
+/// Validates the archived `T` in the given bytes and writes a handle to it to
+/// `out`.
+///
+/// This implements the generated `root` function.
+///
+/// # Safety
+///
+/// If `ptr` is not null, it must be valid for reads of `len` bytes. If `out`
+/// is not null, it must be valid for writes.
+#[inline]
+pub unsafe fn root<T>(
+    ptr: *const u8,
+    len: usize,
+    out: *mut *const c_void,
+) -> c_int
+where
+    T: Portable + CheckBytes<Strategy<DefaultValidator, Failure>>,
+{
+    if out.is_null() {
+        return ERROR_NULL;
+    }
+    // SAFETY: The caller has guaranteed that `ptr` is valid for reads of `len`
+    // bytes.
+    let result = unsafe { check::<T>(ptr, len) };
+    if result == OK {
+        // SAFETY: `check` succeeded, so `len` is at least the size of `T`, and
+        // the caller has guaranteed that `out` is valid for writes.
+        unsafe {
+            out.write(ptr.add(len - size_of::<T>()).cast());
+        }
+    }
+    result
+}
+
+/// Writes the C value of a field of the archived `T` at `handle` to `out`.
+///
+/// This implements the generated getters.
+///
+/// # Safety
+///
+/// If `handle` is not null and is aligned, it must point to an archived `T`
+/// which has been validated. If `out` is not null, it must be valid for
+/// writes.
+#[inline]
+pub unsafe fn get_field<T, F>(
+    handle: *const c_void,
+    out: *mut F::Out,
+    field: fn(&T) -> &F,
+) -> c_int
+where
+    F: CValue,
+{
+    if handle.is_null() || out.is_null() {
+        return ERROR_NULL;
+    }
+    if !handle.addr().is_multiple_of(align_of::<T>()) {
+        return ERROR_ALIGN;
+    }
+    // SAFETY: The caller has guaranteed that `handle` points to a validated
+    // `T` and that `out` is valid for writes.
+    unsafe {
+        out.write(field(&*handle.cast::<T>()).to_c());
+    }
+    OK
+}
+
+/// Looks up `key` in the archived table `M` at `table` and writes the C value
+/// of the found value to `out`.
+///
+/// This implements the generated lookup functions. Keys which are not valid
+/// UTF-8 are never found.
+///
+/// # Safety
+///
+/// If `table` is not null and is aligned, it must point to an archived `M`
+/// which has been validated. If `key` is not null, it must be valid for reads
+/// of `key_len` bytes. If `out` is not null, it must be valid for writes.
+#[inline]
+pub unsafe fn map_get<M: CMap>(
+    table: *const c_void,
+    key: *const u8,
+    key_len: usize,
+    out: *mut <M::Value as CValue>::Out,
+) -> c_int {
+    if table.is_null() || key.is_null() || out.is_null() {
+        return ERROR_NULL;
+    }
+    if !table.addr().is_multiple_of(align_of::<M>()) {
+        return ERROR_ALIGN;
+    }
+    // SAFETY: The caller has guaranteed that `key` is valid for reads of
+    // `key_len` bytes.
+    let key = unsafe { slice::from_raw_parts(key, key_len) };
+    let Ok(key) = str::from_utf8(key) else {
+        return ERROR_NOT_FOUND;
+    };
+    // SAFETY: The caller has guaranteed that `table` points to a validated
+    // `M`.
+    let table = unsafe { &*table.cast::<M>() };
+    match table.get_str(key) {
+        Some(value) => {
+            // SAFETY: The caller has guaranteed that `out` is valid for
+            // writes.
+            unsafe {
+                out.write(value.to_c());
+            }
+            OK
+        }
+        None => ERROR_NOT_FOUND,
+    }
+}
+
+/// A builder for C headers declaring the generated functions of one or more
+/// types.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{c_api::CHeader, Archive};
+///
+/// #[derive(Archive)]
+/// #[archive(check_bytes, c_api(prefix = "inner"))]
+/// struct Inner {
+///     value: u32,
+/// }
+///
+/// #[derive(Archive)]
+/// #[archive(check_bytes, c_api(prefix = "outer"))]
+/// struct Outer {
+///     name: String,
+///     inner: Inner,
+/// }
+///
+/// let header = CHeader::new("MY_HEADER_H")
+///     .add::<ArchivedInner>()
+///     .add::<ArchivedOuter>()
+///     .finish();
+/// assert!(header.contains("int inner_get_value("));
+/// assert!(header.contains("int outer_get_inner("));
+/// ```
+#[derive(Debug)]
+pub struct CHeader {
+    guard: String,
+    declarations: String,
+}
+
+impl CHeader {
+    /// Returns a new header with the given include guard.
+    pub fn new(guard: &str) -> Self {
+        Self {
+            guard: guard.into(),
+            declarations: String::new(),
+        }
+    }
+
+    /// Adds the declarations of the generated functions of `T`.
+    pub fn add<T: CApi>(&mut self) -> &mut Self {
+        let out = &mut self.declarations;
+        let prefix = T::PREFIX;
+        writeln!(out, "/* {} */", T::NAME).unwrap();
+        writeln!(out, "int {}_check(const uint8_t *ptr, size_t len);", prefix)
+            .unwrap();
+        writeln!(
+            out,
+            "int {}_root(const uint8_t *ptr, size_t len, const void **out);",
+            prefix,
+        )
+        .unwrap();
+        for field in T::FIELDS {
+            writeln!(
+                out,
+                "int {}_get_{}(const void *handle, {} *out);",
+                prefix, field.name, field.c_type,
+            )
+            .unwrap();
+            if let Some(value_c_type) = field.value_c_type {
+                writeln!(
+                    out,
+                    "int {}_{}_get(const void *table, const uint8_t *key, \
+                     size_t key_len, {} *out);",
+                    prefix, field.name, value_c_type,
+                )
+                .unwrap();
+            }
+        }
+        out.push('\n');
+        self
+    }
+
+    /// Returns the text of the header.
+    pub fn finish(&self) -> String {
+        let mut result = String::new();
+        writeln!(result, "/* Generated by rkyv. Do not edit. */").unwrap();
+        writeln!(result, "#ifndef {}", self.guard).unwrap();
+        writeln!(result, "#define {}\n", self.guard).unwrap();
+        result.push_str(PREAMBLE);
+        result.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+        result.push_str(&self.declarations);
+        result.push_str("#ifdef __cplusplus\n}\n#endif\n\n");
+        writeln!(result, "#endif /* {} */", self.guard).unwrap();
+        result
+    }
+}
+
+const PREAMBLE: &str = "\
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef RKYV_C_API
+#define RKYV_C_API
+
+#define RKYV_OK 0
+#define RKYV_ERROR_NULL -1
+#define RKYV_ERROR_ALIGN -2
+#define RKYV_ERROR_INVALID -3
+#define RKYV_ERROR_NOT_FOUND -4
+
+typedef struct rkyv_slice {
+    const void *ptr;
+    size_t len;
+} rkyv_slice;
+
+#endif /* RKYV_C_API */
+
+";
+
+/// Returns a C header declaring the generated functions of `T`.
+///
+/// The include guard is the uppercase prefix of `T` followed by `_H`. Use
+/// [`CHeader`] to declare the functions of multiple types in one header.
+pub fn emit_c_header<T: CApi>() -> String {
+    CHeader::new(&format!("{}_H", T::PREFIX.to_uppercase()))
+        .add::<T>()
+        .finish()
+}
@@ -0,0 +1,694 @@
+//! Archived values which can be called through a registry of functions.
+//!
+//! Closures can't be archived, but a struct which pairs some captured data
+//! with a known function can be. A [`Registry`] maps the stable IDs of these
+//! callable types to their functions, and an [`ArchivedCallable`] stores the ID
+//! of its value alongside it so that it can be called without knowing its type.
+//!
+//! Callable types are declared by deriving `Archive` with
+//! `#[archive(callable(registry = ..., function = ...))]`, where `function` is
+//! a function taking the archived type and the arguments of the registry. The
+//! ID of a callable type is a hash of its type path. Registries are declared
+//! with [`callable_registry!`](crate::callable_registry), which lists each of
+//! the callable types that may be dispatched through it.
+//!
+//! Validating an `ArchivedCallable` rejects IDs which are not registered, then
+//! validates the value as the registered type.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     access,
+//!     callable::{ArchivedCallable, CallableBox},
+//!     callable_registry,
+//!     rancor::Failure,
+//!     to_bytes,
+//!     vec::ArchivedVec,
+//!     Archive, Serialize,
+//! };
+//!
+//! callable_registry! {
+//!     /// Rules which adjust a price.
+//!     pub struct Rules: fn(u32) -> u32 = [Discount, Surcharge];
+//! }
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(check_bytes, callable(registry = Rules, function = discount))]
+//! struct Discount {
+//!     percent: u32,
+//! }
+//!
+//! fn discount(rule: &ArchivedDiscount, price: u32) -> u32 {
+//!     price - price * rule.percent.to_native() / 100
+//! }
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(check_bytes, callable(registry = Rules, function = surcharge))]
+//! struct Surcharge {
+//!     amount: u32,
+//! }
+//!
+//! fn surcharge(rule: &ArchivedSurcharge, price: u32) -> u32 {
+//!     price + rule.amount.to_native()
+//! }
+//!
+//! let rules = vec![
+//!     CallableBox::<Rules>::new(Discount { percent: 10 }),
+//!     CallableBox::new(Surcharge { amount: 5 }),
+//! ];
+//! let bytes = to_bytes::<_, 256, Failure>(&rules).unwrap();
+//! let archived =
+//!     access::<ArchivedVec<ArchivedCallable<Rules>>, Failure>(&bytes)
+//!         .unwrap();
+//!
+//! let price = archived.iter().fold(200, |price, rule| rule.call(price));
+//! assert_eq!(price, 185);
+//! ```
+
+use core::{any::Any, fmt, marker::PhantomData};
+
+use rancor::Fallible;
+
+#[cfg(feature = "alloc")]
+use self::alloc_box::Box;
+#[cfg(feature = "alloc")]
+use crate::Deserialize;
+use crate::{
+    primitive::ArchivedU64, ser::Writer, Archive, Portable, RawRelPtr,
+    Serialize, SerializeUnsized,
+};
+
+/// The type of callable IDs.
+pub type CallableId = u64;
+
+/// Computes the callable ID of a type from its type path.
+///
+/// The derive macro uses the module path and name of the type, so moving or
+/// renaming a callable type changes its ID.
+pub const fn callable_id(type_path: &str) -> CallableId {
+    // 64-bit FNV-1a
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let bytes = type_path.as_bytes();
+    let mut hash = OFFSET_BASIS;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(PRIME);
+        i += 1;
+    }
+    hash
+}
+
+/// Returns whether all of the given IDs are different from each other.
+#[doc(hidden)]
+pub const fn ids_are_unique(ids: &[CallableId]) -> bool {
+    let mut i = 0;
+    while i < ids.len() {
+        let mut j = i + 1;
+        while j < ids.len() {
+            if ids[i] == ids[j] {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// A type whose archived values can be called through the registry `R`.
+///
+/// This is implemented by deriving `Archive` with
+/// `#[archive(callable(registry = ..., function = ...))]`.
+pub trait Callable<R: Registry>: Archive + Any {
+    /// The ID of the type, which must be unique within `R`.
+    const ID: CallableId;
+
+    /// Calls the archived value with the given arguments.
+    fn call(archived: &Self::Archived, args: R::Args) -> R::Output;
+}
+
+/// A registry of callable types which all take the same arguments and return
+/// the same output.
+///
+/// Registries are declared with
+/// [`callable_registry!`](crate::callable_registry).
+///
+/// # Safety
+///
+/// `call` must only dispatch to a registered type when `id` is its ID, and
+/// `IDS` must not contain any duplicate IDs.
+pub unsafe trait Registry: 'static {
+    /// The arguments that callable values are called with.
+    type Args;
+    /// The output of calling a callable value.
+    type Output;
+
+    /// The IDs of the registered callable types.
+    const IDS: &'static [CallableId];
+
+    /// Calls an archived value of the registered type with the given ID.
+    ///
+    /// Returns `None` if no type with the given ID is registered.
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to a valid archived value of the registered type
+    /// with the given ID.
+    unsafe fn call(
+        id: CallableId,
+        value: *const u8,
+        args: Self::Args,
+    ) -> Option<Self::Output>;
+}
+
+/// A registry whose callable types can all be serialized with `S`.
+///
+/// # Safety
+///
+/// `serialize` must serialize `value` as the registered type with the ID `id`
+/// and return the position of the archived value.
+pub unsafe trait SerializeRegistry<S: Fallible + ?Sized>:
+    Registry
+{
+    /// Serializes the value of the registered type with the given ID and
+    /// returns its position.
+    fn serialize(
+        id: CallableId,
+        value: &dyn Any,
+        serializer: &mut S,
+    ) -> Result<usize, S::Error>;
+}
+
+/// A registry whose callable types can all be deserialized with `D`.
+///
+/// # Safety
+///
+/// `deserialize` must return a value of the registered type with the ID `id`.
+#[cfg(feature = "alloc")]
+pub unsafe trait DeserializeRegistry<D: Fallible + ?Sized>:
+    Registry
+{
+    /// Deserializes the archived value of the registered type with the given
+    /// ID.
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to a valid archived value of the registered type
+    /// with the given ID.
+    unsafe fn deserialize(
+        id: CallableId,
+        value: *const u8,
+        deserializer: &mut D,
+    ) -> Result<Box<dyn Any>, D::Error>;
+}
+
+/// An error resulting from a callable ID which is not registered.
+#[derive(Debug)]
+pub struct UnknownCallableId {
+    id: CallableId,
+}
+
+impl fmt::Display for UnknownCallableId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "callable ID {:#018x} is not registered", self.id)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownCallableId {}
+
+/// An archived callable value of some type registered with `R`.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedCallable<R> {
+    id: ArchivedU64,
+    value: RawRelPtr,
+    _phantom: PhantomData<R>,
+}
+
+impl<R: Registry> ArchivedCallable<R> {
+    /// Returns the callable ID of the type of the value.
+    #[inline]
+    pub fn id(&self) -> CallableId {
+        self.id.to_native()
+    }
+
+    /// Calls the value with the given arguments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ID of the value is not registered with `R`. Validated
+    /// archives always have registered IDs.
+    #[inline]
+    pub fn call(&self, args: R::Args) -> R::Output {
+        // SAFETY: The value was serialized as the type with its ID.
+        unsafe { R::call(self.id(), self.value.as_ptr().cast(), args) }
+            .expect("callable ID is not registered")
+    }
+
+    /// Returns the archived value if it is a `T`.
+    #[inline]
+    pub fn downcast_ref<T: Callable<R>>(&self) -> Option<&T::Archived> {
+        if self.id() == T::ID {
+            Some(unsafe { &*self.value.as_ptr().cast() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<R> fmt::Debug for ArchivedCallable<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchivedCallable")
+            .field("id", &self.id.to_native())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A boxed value of some callable type registered with `R`.
+#[cfg(feature = "alloc")]
+pub struct CallableBox<R> {
+    id: CallableId,
+    value: Box<dyn Any>,
+    _phantom: PhantomData<R>,
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Registry> CallableBox<R> {
+    /// Boxes the given callable value.
+    #[inline]
+    pub fn new<T: Callable<R>>(value: T) -> Self {
+        Self {
+            id: T::ID,
+            value: Box::new(value),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the callable ID of the type of the value.
+    #[inline]
+    pub fn id(&self) -> CallableId {
+        self.id
+    }
+
+    /// Returns the value if it is a `T`.
+    #[inline]
+    pub fn downcast_ref<T: Callable<R>>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R> fmt::Debug for CallableBox<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallableBox")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The resolver for [`ArchivedCallable`].
+#[cfg(feature = "alloc")]
+pub struct CallableResolver {
+    pos: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Registry> Archive for CallableBox<R> {
+    type Archived = ArchivedCallable<R>;
+    type Resolver = CallableResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        let (_, fo) = out_field!(out.id);
+        fo.write(ArchivedU64::from_native(self.id));
+        let (fp, fo) = out_field!(out.value);
+        RawRelPtr::emplace(pos + fp, resolver.pos, fo);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R, S> Serialize<S> for CallableBox<R>
+where
+    R: SerializeRegistry<S>,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        Ok(CallableResolver {
+            pos: R::serialize(self.id, &*self.value, serializer)?,
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R, D> Deserialize<CallableBox<R>, D> for ArchivedCallable<R>
+where
+    R: DeserializeRegistry<D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<CallableBox<R>, D::Error> {
+        let id = self.id();
+        // SAFETY: The value was serialized as the type with its ID.
+        let value = unsafe {
+            R::deserialize(id, self.value.as_ptr().cast(), deserializer)?
+        };
+        Ok(CallableBox {
+            id,
+            value,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// Serializes a registered callable value and returns its position.
+#[doc(hidden)]
+#[inline]
+pub fn serialize_value<T, S>(
+    value: &dyn Any,
+    serializer: &mut S,
+) -> Result<usize, S::Error>
+where
+    T: Serialize<S> + Any,
+    S: Fallible + Writer + ?Sized,
+{
+    value
+        .downcast_ref::<T>()
+        .expect("callable value does not match its ID")
+        .serialize_unsized(serializer)
+}
+
+/// Returns an error for a callable ID which is not registered.
+#[doc(hidden)]
+pub fn unknown_id<E: rancor::Error>(id: CallableId) -> E {
+    E::new(UnknownCallableId { id })
+}
+
+/// Declares a [`Registry`] of callable types.
+///
+/// The registry is declared as a unit struct followed by the signature of the
+/// functions of its callable types and a list of the types. Each type must
+/// implement [`Callable`] for the registry, usually by deriving `Archive` with
+/// `#[archive(callable(registry = ..., function = ...))]`. The IDs of the
+/// types are checked for collisions at compile time.
+///
+/// # Example
+///
+/// ```ignore
+/// callable_registry! {
+///     /// Rules which adjust a price.
+///     pub struct Rules: fn(u32) -> u32 = [Discount, Surcharge];
+/// }
+/// ```
+#[macro_export]
+macro_rules! callable_registry {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident: fn($args:ty) -> $output:ty = [
+            $($ty:ty),* $(,)?
+        ];
+    ) => {
+        $(#[$attr])*
+        $vis struct $name;
+
+        unsafe impl $crate::callable::Registry for $name {
+            type Args = $args;
+            type Output = $output;
+
+            const IDS: &'static [$crate::callable::CallableId] = &[
+                $(<$ty as $crate::callable::Callable<$name>>::ID,)*
+            ];
+
+            #[allow(unused_variables)]
+            unsafe fn call(
+                id: $crate::callable::CallableId,
+                value: *const u8,
+                args: $args,
+            ) -> ::core::option::Option<$output> {
+                $(
+                    if id == <$ty as $crate::callable::Callable<$name>>::ID {
+                        return ::core::option::Option::Some(
+                            <$ty as $crate::callable::Callable<$name>>::call(
+                                unsafe {
+                                    &*value.cast::<
+                                        <$ty as $crate::Archive>::Archived,
+                                    >()
+                                },
+                                args,
+                            ),
+                        );
+                    }
+                )*
+                ::core::option::Option::None
+            }
+        }
+
+        const _: () = ::core::assert!(
+            $crate::callable::ids_are_unique(
+                <$name as $crate::callable::Registry>::IDS,
+            ),
+            ::core::concat!(
+                "the callable registry `",
+                ::core::stringify!($name),
+                "` contains types with the same ID",
+            ),
+        );
+
+        unsafe impl<__S> $crate::callable::SerializeRegistry<__S> for $name
+        where
+            __S: $crate::rancor::Fallible + $crate::ser::Writer + ?Sized,
+            <__S as $crate::rancor::Fallible>::Error: $crate::rancor::Error,
+            $($ty: $crate::Serialize<__S>,)*
+        {
+            #[allow(unused_variables)]
+            fn serialize(
+                id: $crate::callable::CallableId,
+                value: &dyn ::core::any::Any,
+                serializer: &mut __S,
+            ) -> ::core::result::Result<
+                usize,
+                <__S as $crate::rancor::Fallible>::Error,
+            > {
+                $(
+                    if id == <$ty as $crate::callable::Callable<$name>>::ID {
+                        return $crate::callable::serialize_value::<$ty, __S>(
+                            value,
+                            serializer,
+                        );
+                    }
+                )*
+                ::core::result::Result::Err(
+                    $crate::callable::unknown_id(id),
+                )
+            }
+        }
+
+        $crate::__callable_deserialize_registry!($name, $($ty),*);
+        $crate::__callable_check_registry!($name, $($ty),*);
+    };
+}
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __callable_deserialize_registry {
+    ($name:ident, $($ty:ty),*) => {
+        unsafe impl<__D> $crate::callable::DeserializeRegistry<__D> for $name
+        where
+            __D: $crate::rancor::Fallible + ?Sized,
+            <__D as $crate::rancor::Fallible>::Error: $crate::rancor::Error,
+            $(<$ty as $crate::Archive>::Archived: $crate::Deserialize<$ty, __D>,)*
+        {
+            #[allow(unused_variables)]
+            unsafe fn deserialize(
+                id: $crate::callable::CallableId,
+                value: *const u8,
+                deserializer: &mut __D,
+            ) -> ::core::result::Result<
+                $crate::callable::__Box<dyn ::core::any::Any>,
+                <__D as $crate::rancor::Fallible>::Error,
+            > {
+                $(
+                    if id == <$ty as $crate::callable::Callable<$name>>::ID {
+                        let archived = unsafe {
+                            &*value.cast::<<$ty as $crate::Archive>::Archived>()
+                        };
+                        let value: $ty = $crate::Deserialize::deserialize(
+                            archived,
+                            deserializer,
+                        )?;
+                        return ::core::result::Result::Ok(
+                            $crate::callable::__Box::new(value),
+                        );
+                    }
+                )*
+                ::core::result::Result::Err(
+                    $crate::callable::unknown_id(id),
+                )
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __callable_deserialize_registry {
+    ($name:ident, $($ty:ty),*) => {};
+}
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub use self::alloc_box::Box as __Box;
+
+#[cfg(feature = "alloc")]
+mod alloc_box {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::boxed::Box;
+    #[cfg(feature = "std")]
+    pub use std::boxed::Box;
+}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use bytecheck::{CheckBytes, Verify};
+    use rancor::{Error, Fallible};
+
+    use super::{ArchivedCallable, CallableId};
+    use crate::{
+        validation::{ArchiveContext, ArchiveContextExt},
+        Portable, RawRelPtr,
+    };
+
+    /// A registry whose archived callable types can all be checked with `C`.
+    ///
+    /// # Safety
+    ///
+    /// `check_bytes` must only succeed if `value` points to a valid archived
+    /// value of the registered type with the ID `id`.
+    pub unsafe trait CheckRegistry<C: Fallible + ?Sized>:
+        super::Registry
+    {
+        /// Checks that `value` points to a valid archived value of the
+        /// registered type with the given ID.
+        ///
+        /// Fails if no type with the given ID is registered.
+        fn check_bytes(
+            id: CallableId,
+            value: &RawRelPtr,
+            context: &mut C,
+        ) -> Result<(), C::Error>;
+    }
+
+    /// Checks that a relative pointer points to a valid `T` in a subtree of
+    /// the archive.
+    #[doc(hidden)]
+    pub fn check_value<T, C>(
+        value: &RawRelPtr,
+        context: &mut C,
+    ) -> Result<(), C::Error>
+    where
+        T: Portable + CheckBytes<C>,
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        context.record_offset(value.offset_slot())?;
+        // SAFETY: `value` is inside the archive being checked, and the
+        // metadata of a sized type is always `()`.
+        unsafe {
+            let ptr = context.bounds_check_subtree_base_offset::<T>(
+                value.base(),
+                value.offset(),
+                (),
+            )?;
+            let range = context.push_prefix_subtree(ptr)?;
+            T::check_bytes(ptr, context)?;
+            context.pop_subtree_range(range)?;
+        }
+        Ok(())
+    }
+
+    unsafe impl<R, C> Verify<C> for ArchivedCallable<R>
+    where
+        R: CheckRegistry<C>,
+        C: Fallible + ?Sized,
+    {
+        #[inline]
+        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            R::check_bytes(self.id.to_native(), &self.value, context)
+        }
+    }
+}
+
+#[cfg(feature = "bytecheck")]
+pub use verify::{check_value, CheckRegistry};
+
+#[cfg(feature = "bytecheck")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __callable_check_registry {
+    ($name:ident, $($ty:ty),*) => {
+        unsafe impl<__C> $crate::callable::CheckRegistry<__C> for $name
+        where
+            __C: $crate::rancor::Fallible
+                + $crate::validation::ArchiveContext
+                + ?Sized,
+            <__C as $crate::rancor::Fallible>::Error: $crate::rancor::Error,
+            $(
+                <$ty as $crate::Archive>::Archived:
+                    $crate::bytecheck::CheckBytes<__C>,
+            )*
+        {
+            #[allow(unused_variables)]
+            fn check_bytes(
+                id: $crate::callable::CallableId,
+                value: &$crate::RawRelPtr,
+                context: &mut __C,
+            ) -> ::core::result::Result<
+                (),
+                <__C as $crate::rancor::Fallible>::Error,
+            > {
+                $(
+                    if id == <$ty as $crate::callable::Callable<$name>>::ID {
+                        return $crate::callable::check_value::<
+                            <$ty as $crate::Archive>::Archived,
+                            __C,
+                        >(value, context);
+                    }
+                )*
+                ::core::result::Result::Err(
+                    $crate::callable::unknown_id(id),
+                )
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "bytecheck"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __callable_check_registry {
+    ($name:ident, $($ty:ty),*) => {};
+}
@@ -0,0 +1,268 @@
+//! Iterative archiving for linked chains of boxed nodes.
+//!
+//! Linked lists written as `Option<Box<Node>>` next-pointers are archived
+//! recursively by default, so each node adds a few frames to the stack while
+//! serializing, deserializing, and validating. Long chains overflow the stack.
+//!
+//! The [`IterateChain`](crate::with::IterateChain) wrapper archives the head of
+//! a chain as an [`ArchivedChain`] instead. The nodes are written one after
+//! another into an [`ArchivedVec`], and they are serialized, deserialized, and
+//! validated in a loop. The archived chain can still be traversed like a
+//! linked list with [`ArchivedChain::head`] and [`ChainCursor::next`].
+//!
+//! Node types implement [`ChainLink`] to expose their next-pointer, and must
+//! skip it when archiving with [`Skip`](crate::with::Skip) so that archiving a
+//! single node doesn't recurse into the rest of the chain.
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+use core::{fmt, iter::FusedIterator, ops::Deref, slice};
+
+use crate::{
+    vec::{ArchivedVec, VecResolver},
+    Portable,
+};
+
+/// A node in a linked chain of boxed nodes.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{chain::ChainLink, with::Skip, Archive};
+///
+/// #[derive(Archive)]
+/// struct Node {
+///     value: u32,
+///     #[with(Skip)]
+///     next: Option<Box<Node>>,
+/// }
+///
+/// impl ChainLink for Node {
+///     fn link(&self) -> &Option<Box<Self>> {
+///         &self.next
+///     }
+///
+///     fn link_mut(&mut self) -> &mut Option<Box<Self>> {
+///         &mut self.next
+///     }
+/// }
+/// ```
+pub trait ChainLink: Sized {
+    /// Returns the next-pointer of this node.
+    fn link(&self) -> &Option<Box<Self>>;
+
+    /// Returns a mutable reference to the next-pointer of this node.
+    fn link_mut(&mut self) -> &mut Option<Box<Self>>;
+}
+
+/// Returns an iterator over the nodes of the chain starting at `head`.
+#[inline]
+pub fn iter_chain<T: ChainLink>(head: &Option<Box<T>>) -> Links<'_, T> {
+    let mut len = 0;
+    let mut current = head.as_deref();
+    while let Some(node) = current {
+        len += 1;
+        current = node.link().as_deref();
+    }
+    Links {
+        current: head.as_deref(),
+        len,
+    }
+}
+
+/// Drops the chain starting at `head` one node at a time.
+///
+/// Dropping a boxed node drops its next-pointer, so dropping a long chain
+/// normally recurses once per node. Types which own a chain should call this
+/// in their `Drop` implementation.
+#[inline]
+pub fn drop_chain<T: ChainLink>(head: &mut Option<Box<T>>) {
+    let mut current = head.take();
+    while let Some(mut node) = current {
+        current = node.link_mut().take();
+    }
+}
+
+/// An iterator over the nodes of a chain.
+///
+/// This is created by [`iter_chain`].
+pub struct Links<'a, T> {
+    current: Option<&'a T>,
+    len: usize,
+}
+
+impl<'a, T: ChainLink> Iterator for Links<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.current?;
+        self.current = node.link().as_deref();
+        self.len -= 1;
+        Some(node)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T: ChainLink> ExactSizeIterator for Links<'_, T> {}
+
+impl<T: ChainLink> FusedIterator for Links<'_, T> {}
+
+/// An archived linked chain of nodes.
+///
+/// This is the archived form of chains archived with
+/// [`IterateChain`](crate::with::IterateChain). The nodes are stored in order
+/// from the head of the chain.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+pub struct ArchivedChain<T> {
+    nodes: ArchivedVec<T>,
+}
+
+impl<T> ArchivedChain<T> {
+    /// Returns the number of nodes in the chain.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether the chain has no nodes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns a cursor to the head of the chain, or `None` if the chain is
+    /// empty.
+    #[inline]
+    pub fn head(&self) -> Option<ChainCursor<'_, T>> {
+        ChainCursor::new(self.nodes.as_slice())
+    }
+
+    /// Returns the nodes of the chain in order from the head.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self.nodes.as_slice()
+    }
+
+    /// Returns an iterator over the nodes of the chain.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.nodes.iter()
+    }
+
+    /// Resolves an archived chain from a resolver.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing a chain
+    #[inline]
+    pub unsafe fn resolve_from_resolver(
+        pos: usize,
+        resolver: ChainResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.nodes);
+        ArchivedVec::resolve_from_len(
+            resolver.len,
+            pos + fp,
+            resolver.nodes,
+            fo,
+        );
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArchivedChain<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedChain<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A cursor to a node of an [`ArchivedChain`].
+///
+/// The cursor dereferences to its node, and [`next`](ChainCursor::next) moves
+/// to the following node like a next-pointer.
+pub struct ChainCursor<'a, T> {
+    nodes: &'a [T],
+}
+
+impl<'a, T> ChainCursor<'a, T> {
+    #[inline]
+    fn new(nodes: &'a [T]) -> Option<Self> {
+        if nodes.is_empty() {
+            None
+        } else {
+            Some(Self { nodes })
+        }
+    }
+
+    /// Returns the node this cursor points to.
+    #[inline]
+    pub fn get(&self) -> &'a T {
+        &self.nodes[0]
+    }
+
+    /// Returns a cursor to the next node in the chain, or `None` if this is
+    /// the last node.
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&self) -> Option<Self> {
+        Self::new(&self.nodes[1..])
+    }
+}
+
+impl<T> Clone for ChainCursor<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ChainCursor<'_, T> {}
+
+impl<T> Deref for ChainCursor<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ChainCursor<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ChainCursor").field(self.get()).finish()
+    }
+}
+
+/// The resolver for an [`ArchivedChain`].
+pub struct ChainResolver {
+    len: usize,
+    nodes: VecResolver,
+}
+
+impl ChainResolver {
+    /// Creates a resolver from the number of nodes in a chain and the resolver
+    /// for the vec of its nodes.
+    #[inline]
+    pub fn new(len: usize, nodes: VecResolver) -> Self {
+        Self { len, nodes }
+    }
+}
@@ -0,0 +1,21 @@
+//! Archived versions of `cmp` types.
+
+use crate::Portable;
+
+/// An archived [`Ordering`](::core::cmp::Ordering).
+///
+/// The discriminants of the archived variants are fixed and do not depend on
+/// the representation of `Ordering`.
+#[derive(Portable)]
+#[archive(crate)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum ArchivedOrdering {
+    /// An ordering where a compared value is less than another.
+    Less = 0,
+    /// An ordering where a compared value is equal to another.
+    Equal = 1,
+    /// An ordering where a compared value is greater than another.
+    Greater = 2,
+}
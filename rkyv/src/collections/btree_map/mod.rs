@@ -1,7 +1,7 @@
 //! [`Archive`] implementation for B-tree maps.
 
-// #[cfg(feature = "bytecheck")]
-// pub mod validation;
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+mod validation;
 
 use core::{
     borrow::Borrow,
@@ -15,10 +15,12 @@ use core::{
 };
 
 use ptr_meta::Pointee;
+use rancor::Fallible;
 
 use crate::{
+    de::DeserializeEntries,
     primitive::{ArchivedU16, ArchivedUsize},
-    Archive, ArchivePointee, Portable, RelPtr,
+    Archive, ArchivePointee, Deserialize, Portable, RelPtr,
 };
 
 #[derive(Portable)]
@@ -133,12 +135,14 @@ impl<T> ArchivePointee for Node<[T]> {
 type InnerNode<K> = Node<[InnerNodeEntry<K>]>;
 type LeafNode<K, V> = Node<[LeafNodeEntry<K, V>]>;
 
+#[cfg(feature = "alloc")]
 struct NodeHeaderData {
     meta: u16,
     size: usize,
     pos: Option<usize>,
 }
 
+#[cfg(feature = "alloc")]
 impl Archive for NodeHeaderData {
     type Archived = NodeHeader;
     type Resolver = ();
@@ -161,10 +165,12 @@ impl Archive for NodeHeaderData {
     }
 }
 
+#[cfg(feature = "alloc")]
 struct InnerNodeEntryData<'a, UK> {
     key: &'a UK,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, UK: Archive> Archive for InnerNodeEntryData<'a, UK> {
     type Archived = InnerNodeEntry<UK::Archived>;
     type Resolver = (usize, UK::Resolver);
@@ -222,6 +228,12 @@ impl NodeHeader {
 }
 
 /// An archived [`BTreeMap`](std::collections::BTreeMap).
+///
+/// Empty maps have a null root and no nodes. Entries with zero-sized values
+/// (such as those in an [`ArchivedBTreeSet`]) only take up space for their
+/// keys.
+///
+/// [`ArchivedBTreeSet`]: crate::collections::btree_set::ArchivedBTreeSet
 #[derive(Portable)]
 #[archive(crate)]
 #[repr(C)]
@@ -233,7 +245,7 @@ pub struct ArchivedBTreeMap<K, V> {
 
 /// The resolver for an [`ArchivedBTreeMap`].
 pub struct BTreeMapResolver {
-    root_pos: usize,
+    root_pos: Option<usize>,
 }
 
 /// The minimum number of entries to place in a leaf node.
@@ -246,6 +258,11 @@ pub const MIN_ENTRIES_PER_LEAF_NODE: usize = 1;
 /// This value must be greater than 1
 pub const MIN_ENTRIES_PER_INNER_NODE: usize = 2;
 
+/// The maximum number of entries to place in a node.
+///
+/// This is the largest length that can be stored in a node header.
+pub const MAX_ENTRIES_PER_NODE: usize = 0x7F_FF;
+
 impl<K, V> ArchivedBTreeMap<K, V> {
     #[inline]
     fn root(&self) -> Option<ClassifiedNode<K, V>> {
@@ -352,6 +369,64 @@ impl<K, V> ArchivedBTreeMap<K, V> {
         }
     }
 
+    /// Returns the key-value pairs corresponding to each of the supplied
+    /// keys.
+    ///
+    /// The keys must be sorted. Instead of starting each lookup from the root,
+    /// the lookups share a [`Cursor`] which continues from the position of the
+    /// previous key. This is much faster than calling
+    /// [`get_key_value`](Self::get_key_value) for each key when the keys are
+    /// close together in the map.
+    ///
+    /// # Panics
+    ///
+    /// With debug assertions enabled, panics if the keys are not sorted.
+    /// Otherwise, the results for unsorted keys are unspecified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use rkyv::{access_unchecked, rancor::Failure, to_bytes, Archived};
+    ///
+    /// let map = (0..100u32).map(|i| (i * 2, i)).collect::<BTreeMap<_, _>>();
+    /// let bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+    /// let archived = unsafe {
+    ///     access_unchecked::<Archived<BTreeMap<u32, u32>>>(&bytes)
+    /// };
+    ///
+    /// let keys = [4, 5, 100, 300].map(Archived::<u32>::from_native);
+    /// let values = archived
+    ///     .get_many_sorted(&keys)
+    ///     .map(|entry| entry.map(|(_, v)| v.to_native()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(values, [Some(2), None, Some(50), None]);
+    /// ```
+    #[inline]
+    pub fn get_many_sorted<'a, 'q, Q: Ord>(
+        &'a self,
+        keys: &'q [Q],
+    ) -> GetManySorted<'a, 'q, K, V, Q>
+    where
+        K: Borrow<Q> + Ord,
+    {
+        debug_assert!(
+            keys.windows(2).all(|w| w[0] <= w[1]),
+            "keys passed to `get_many_sorted` must be sorted",
+        );
+        GetManySorted {
+            cursor: self.cursor(),
+            keys: keys.iter(),
+        }
+    }
+
+    /// Returns a cursor pointing to the first entry of the map.
+    #[inline]
+    pub fn cursor(&self) -> Cursor<'_, K, V> {
+        Cursor::new(self)
+    }
+
     /// Returns `true` if the map contains no elements.
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -366,6 +441,26 @@ impl<K, V> ArchivedBTreeMap<K, V> {
         }
     }
 
+    /// Gets an iterator which deserializes the entries of the map one at a
+    /// time, sorted by key.
+    ///
+    /// All of the entries are deserialized with the same deserializer, and
+    /// entries are only deserialized when they are reached. Use
+    /// [`collect_deserialized`](DeserializeEntries::collect_deserialized) to
+    /// deserialize all of the remaining entries into a collection.
+    #[inline]
+    pub fn entries_deserialized<'d, K2, V2, D>(
+        &self,
+        deserializer: &'d mut D,
+    ) -> DeserializeEntries<'d, Iter<'_, K, V>, K2, V2, D>
+    where
+        K: Deserialize<K2, D>,
+        V: Deserialize<V2, D>,
+        D: Fallible + ?Sized,
+    {
+        DeserializeEntries::new(self.iter(), deserializer)
+    }
+
     /// Gets an iterator over the keys of the map, in sorted order.
     #[inline]
     pub fn keys(&self) -> Keys<'_, K, V> {
@@ -406,7 +501,10 @@ impl<K, V> ArchivedBTreeMap<K, V> {
         len.resolve(pos + fp, (), fo);
 
         let (fp, fo) = out_field!(out.root);
-        RelPtr::emplace(pos + fp, resolver.root_pos, fo);
+        match resolver.root_pos {
+            Some(root_pos) => RelPtr::emplace(pos + fp, root_pos, fo),
+            None => RelPtr::emplace_null(pos + fp, fo),
+        }
     }
 }
 
@@ -441,7 +539,7 @@ const _: () = {
             I: ExactSizeIterator<Item = (&'a UK, &'a UV)>,
         {
             if iter.len() == 0 {
-                Ok(BTreeMapResolver { root_pos: 0 })
+                Ok(BTreeMapResolver { root_pos: None })
             } else {
                 // The memory span of a single node should not exceed 4kb to
                 // keep everything within the distance of a
@@ -476,10 +574,12 @@ const _: () = {
                                 * mem::size_of::<LeafNodeEntry<K, V>>();
 
                         // If we've reached or exceeded the maximum node size
-                        // and have put enough
-                        // entries in this node, then break
-                        if estimated_block_size >= MAX_NODE_SIZE
-                            && resolvers.len() >= MIN_ENTRIES_PER_LEAF_NODE
+                        // and have put enough entries in this node, or if the
+                        // node is full, then break. Entries with a size of
+                        // zero never reach the maximum node size.
+                        if (estimated_block_size >= MAX_NODE_SIZE
+                            && resolvers.len() >= MIN_ENTRIES_PER_LEAF_NODE)
+                            || resolvers.len() >= MAX_ENTRIES_PER_NODE
                         {
                             break;
                         }
@@ -563,10 +663,13 @@ const _: () = {
                                     * mem::size_of::<InnerNodeEntry<K>>();
 
                             // If we've reached or exceeded the maximum node
-                            // size and have put enough
-                            // keys in this node, then break
-                            if estimated_block_size >= MAX_NODE_SIZE
-                                && resolvers.len() >= MIN_ENTRIES_PER_INNER_NODE
+                            // size and have put enough keys in this node, or if
+                            // the node is full, then break. One more key may be
+                            // added below, so inner nodes stop one short.
+                            if (estimated_block_size >= MAX_NODE_SIZE
+                                && resolvers.len()
+                                    >= MIN_ENTRIES_PER_INNER_NODE)
+                                || resolvers.len() >= MAX_ENTRIES_PER_NODE - 1
                             {
                                 break;
                             }
@@ -631,7 +734,7 @@ const _: () = {
 
                 // The root is only node in the final level
                 Ok(BTreeMapResolver {
-                    root_pos: next_level[0].1,
+                    root_pos: Some(next_level[0].1),
                 })
             }
         }
@@ -670,6 +773,8 @@ impl<K: Eq, V: Eq> Eq for ArchivedBTreeMap<K, V> {}
 impl<K: Hash, V: Hash> Hash for ArchivedBTreeMap<K, V> {
     #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hashes the same as a `BTreeMap` with the same entries
+        state.write_usize(self.len());
         for pair in self.iter() {
             pair.hash(state);
         }
@@ -701,6 +806,280 @@ impl<K: PartialOrd, V: PartialOrd> PartialOrd for ArchivedBTreeMap<K, V> {
     }
 }
 
+// Cursor
+
+/// The maximum number of inner node levels in a B-tree.
+///
+/// Every inner node has at least two children, so a tree with fewer than
+/// `usize::MAX` entries has fewer inner node levels than this.
+const MAX_DEPTH: usize = usize::BITS as usize;
+
+#[inline]
+fn child_ptr<K>(node: &InnerNode<K>, index: usize) -> NonNull<NodeHeader> {
+    // SAFETY: the children of inner nodes are never null
+    unsafe {
+        let ptr = if index == 0 {
+            node.header.ptr.as_ptr()
+        } else {
+            node.tail[index - 1].ptr.as_ptr()
+        };
+        NonNull::new_unchecked(ptr as *mut _)
+    }
+}
+
+/// A cursor over the entries of an archived B-tree map.
+///
+/// A cursor points to an entry of the map or past the end of the map, and only
+/// moves forward. Iterating a cursor returns the entry it points to and moves
+/// it to the next entry. [`seek`](Cursor::seek) moves the cursor forward to the
+/// first entry with a key greater than or equal to a given key. Because the
+/// cursor remembers its path through the tree, it continues from its current
+/// position instead of starting from the root.
+///
+/// # Example
+///
+/// Two cursors can be used to merge join two maps:
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use rkyv::{access_unchecked, rancor::Failure, to_bytes, Archived};
+///
+/// let a = (0..100u32).map(|i| (i * 2, i)).collect::<BTreeMap<_, _>>();
+/// let b = (0..100u32).map(|i| (i * 3, i)).collect::<BTreeMap<_, _>>();
+/// let a_bytes = to_bytes::<_, 256, Failure>(&a).unwrap();
+/// let b_bytes = to_bytes::<_, 256, Failure>(&b).unwrap();
+/// let (a, b) = unsafe {
+///     (
+///         access_unchecked::<Archived<BTreeMap<u32, u32>>>(&a_bytes),
+///         access_unchecked::<Archived<BTreeMap<u32, u32>>>(&b_bytes),
+///     )
+/// };
+///
+/// let mut joined = Vec::new();
+/// let (mut left, mut right) = (a.cursor(), b.cursor());
+/// while let (Some((lk, lv)), Some((rk, rv))) = (left.peek(), right.peek()) {
+///     if lk < rk {
+///         left.seek(rk);
+///     } else if rk < lk {
+///         right.seek(lk);
+///     } else {
+///         joined.push((lk.to_native(), lv.to_native(), rv.to_native()));
+///         left.next();
+///         right.next();
+///     }
+/// }
+///
+/// assert_eq!(joined.len(), 34);
+/// assert_eq!(joined[1], (6, 3, 2));
+/// ```
+pub struct Cursor<'a, K, V> {
+    // The inner nodes from the root to the current leaf, and the index of the
+    // child taken from each of them
+    path: [(NonNull<NodeHeader>, usize); MAX_DEPTH],
+    depth: usize,
+    leaf: Option<NonNull<NodeHeader>>,
+    index: usize,
+    _phantom: PhantomData<(&'a K, &'a V)>,
+}
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    fn new(map: &'a ArchivedBTreeMap<K, V>) -> Self {
+        let mut result = Self {
+            path: [(NonNull::dangling(), 0); MAX_DEPTH],
+            depth: 0,
+            leaf: None,
+            index: 0,
+            _phantom: PhantomData,
+        };
+        if !map.is_empty() {
+            // SAFETY: the root of a non-empty map is never null
+            let root =
+                unsafe { NonNull::new_unchecked(map.root.as_ptr() as *mut _) };
+            result.descend_first(root);
+        }
+        result
+    }
+
+    #[inline]
+    fn node(ptr: NonNull<NodeHeader>) -> &'a NodeHeader {
+        // SAFETY: all of the nodes reachable from the map live as long as it
+        unsafe { &*ptr.as_ptr() }
+    }
+
+    #[inline]
+    fn current_leaf(&self) -> Option<&'a LeafNode<K, V>> {
+        self.leaf.map(|leaf| Self::node(leaf).classify_leaf())
+    }
+
+    /// Moves to the first entry of the subtree rooted at `node`, which is at
+    /// the current depth.
+    fn descend_first(&mut self, mut node: NonNull<NodeHeader>) {
+        while Self::node(node).is_inner() {
+            assert!(self.depth < MAX_DEPTH, "B-tree map is too deep");
+            self.path[self.depth] = (node, 0);
+            self.depth += 1;
+            node = child_ptr(Self::node(node).classify_inner::<K>(), 0);
+        }
+        self.leaf = Some(node);
+        self.index = 0;
+    }
+
+    /// Moves to the first entry of the next leaf, or past the end of the map
+    /// if there are no more leaves.
+    fn next_leaf(&mut self) {
+        while self.depth > 0 {
+            let (node, child) = self.path[self.depth - 1];
+            let inner = Self::node(node).classify_inner::<K>();
+            if child < inner.tail.len() {
+                self.path[self.depth - 1].1 = child + 1;
+                self.descend_first(child_ptr(inner, child + 1));
+                return;
+            }
+            self.depth -= 1;
+        }
+        self.leaf = None;
+    }
+
+    /// Returns the entry that the cursor points to without moving it, or
+    /// `None` if the cursor is past the end of the map.
+    #[inline]
+    pub fn peek(&self) -> Option<(&'a K, &'a V)> {
+        self.current_leaf().map(|leaf| {
+            let entry = &leaf.tail[self.index];
+            (&entry.key, &entry.value)
+        })
+    }
+
+    /// Moves the cursor forward to the first entry with a key greater than or
+    /// equal to the given key, and returns that entry.
+    ///
+    /// If the cursor already points to such an entry, it doesn't move. If
+    /// there is no such entry, the cursor moves past the end of the map and
+    /// `None` is returned.
+    ///
+    /// The key may be any borrowed form of the map's key type, but the ordering
+    /// on the borrowed form must match the ordering on the key type.
+    pub fn seek<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q> + Ord,
+    {
+        let leaf = self.current_leaf()?;
+        if leaf.tail[self.index].key.borrow().cmp(key) != Ordering::Less {
+            return self.peek();
+        }
+
+        // Climb to the lowest node which may contain the key. The key is
+        // greater than the current key, so only the upper bounds of the
+        // nodes on the path need to be checked.
+        let mut level = self.depth;
+        while level > 0 {
+            let (node, child) = self.path[level - 1];
+            let inner = Self::node(node).classify_inner::<K>();
+            if child < inner.tail.len()
+                && key.cmp(inner.tail[child].key.borrow()) == Ordering::Less
+            {
+                break;
+            }
+            level -= 1;
+        }
+
+        // Descend to the leaf which may contain the key, only searching the
+        // children after the ones already visited
+        if level < self.depth {
+            let mut node = self.path[level].0;
+            for depth in level..self.depth {
+                let start = self.path[depth].1;
+                let inner = Self::node(node).classify_inner::<K>();
+                let child = start
+                    + inner.tail[start..].partition_point(|entry| {
+                        entry.key.borrow().cmp(key) != Ordering::Greater
+                    });
+                self.path[depth].1 = child;
+                node = child_ptr(inner, child);
+                if depth + 1 < self.depth {
+                    self.path[depth + 1] = (node, 0);
+                }
+            }
+            self.leaf = Some(node);
+            self.index = 0;
+        }
+
+        let leaf = self.current_leaf().unwrap();
+        self.index += leaf.tail[self.index..].partition_point(|entry| {
+            entry.key.borrow().cmp(key) == Ordering::Less
+        });
+        if self.index == leaf.tail.len() {
+            self.next_leaf();
+        }
+
+        self.peek()
+    }
+}
+
+impl<'a, K, V> Iterator for Cursor<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let leaf = self.current_leaf()?;
+        let entry = &leaf.tail[self.index];
+        self.index += 1;
+        if self.index == leaf.tail.len() {
+            self.next_leaf();
+        }
+        Some((&entry.key, &entry.value))
+    }
+}
+
+impl<'a, K, V> FusedIterator for Cursor<'a, K, V> {}
+
+/// An iterator over the entries of an archived B-tree map corresponding to a
+/// sorted slice of keys.
+///
+/// This is returned by [`ArchivedBTreeMap::get_many_sorted`].
+pub struct GetManySorted<'a, 'q, K, V, Q> {
+    cursor: Cursor<'a, K, V>,
+    keys: core::slice::Iter<'q, Q>,
+}
+
+impl<'a, 'q, K, V, Q> Iterator for GetManySorted<'a, 'q, K, V, Q>
+where
+    K: Borrow<Q> + Ord,
+    Q: Ord,
+{
+    type Item = Option<(&'a K, &'a V)>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.keys.next()?;
+        Some(
+            self.cursor
+                .seek(key)
+                .filter(|(k, _)| (*k).borrow().cmp(key) == Ordering::Equal),
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'a, 'q, K, V, Q> ExactSizeIterator for GetManySorted<'a, 'q, K, V, Q>
+where
+    K: Borrow<Q> + Ord,
+    Q: Ord,
+{
+}
+
+impl<'a, 'q, K, V, Q> FusedIterator for GetManySorted<'a, 'q, K, V, Q>
+where
+    K: Borrow<Q> + Ord,
+    Q: Ord,
+{
+}
+
 // RawIter
 
 struct RawIter<'a, K, V> {
@@ -824,3 +1203,21 @@ impl<'a, K, V> Iterator for Values<'a, K, V> {
 
 impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
 impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use crate::{
+        collections::btree_map::ArchivedBTreeMap,
+        reflect::{descriptor, DescriptorKind, Reflect, TypeDescriptor},
+    };
+
+    impl<K: Reflect, V: Reflect> Reflect for ArchivedBTreeMap<K, V> {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "BTreeMap",
+            DescriptorKind::BTreeMap {
+                key: descriptor::<K>,
+                value: descriptor::<V>,
+            },
+        );
+    }
+}
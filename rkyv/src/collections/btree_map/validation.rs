@@ -1,601 +1,522 @@
-//! Validation implementation for BTreeMap.
+//! Validation implementation for archived B-tree maps.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+use core::{
+    alloc::Layout, any::type_name, borrow::Borrow, cmp::Ordering, fmt,
+    ptr::addr_of,
+};
+
+use bytecheck::{
+    rancor::{Error, Fallible},
+    CheckBytes,
+};
+use rancor::{fail, ResultExt as _, Strategy};
 
 use super::{
-    ArchivedBTreeMap, ClassifiedNode, InnerNode, InnerNodeEntry, LeafNode,
-    LeafNodeEntry, Node, NodeHeader, MIN_ENTRIES_PER_INNER_NODE,
-    MIN_ENTRIES_PER_LEAF_NODE,
+    ArchivedBTreeMap, InnerNode, InnerNodeEntry, LeafNode, LeafNodeEntry,
+    NodeHeader, MIN_ENTRIES_PER_INNER_NODE, MIN_ENTRIES_PER_LEAF_NODE,
 };
 use crate::{
-    rel_ptr::RelPtr,
-    validation::{ArchiveContext, LayoutRaw},
-};
-use bytecheck::CheckBytes;
-use core::{
-    alloc::{Layout, LayoutError},
-    convert::{Infallible, TryFrom},
-    fmt,
-    ptr,
+    primitive::{ArchivedU16, ArchivedUsize},
+    validation::{
+        lazy::{Lazy, Shell},
+        overflow::{offset_address, range_start},
+        validators::DefaultValidator,
+        ArchiveContext,
+    },
+    Portable, RelPtr,
 };
-use ptr_meta::Pointee;
 
-/// Errors that can occur while checking an archived B-tree.
 #[derive(Debug)]
-pub enum ArchivedBTreeMapError<K, V, C> {
-    /// An error occurred while checking the bytes of a key
-    KeyCheckError(K),
-    /// An error occurred while checking the bytes of a value
-    ValueCheckError(V),
-    /// The number of entries in the inner node is less than the minimum number of entries required
-    TooFewInnerNodeEntries(usize),
-    /// The number of entries in the leaf node is less than the minimum number of entries
-    TooFewLeafNodeEntries(usize),
-    /// An error occurred while checking the entries of an inner node
-    CheckInnerNodeEntryError {
-        /// The index of the inner node entry
-        index: usize,
-        /// The inner error that occurred
-        inner: K,
+enum BTreeMapError {
+    TooFewEntries {
+        is_inner: bool,
+        len: usize,
+        min: usize,
     },
-    /// The size of an inner node was invalid
-    InvalidNodeSize(usize),
-    /// The child of an inner node had a first key that did not match the inner node's key
-    MismatchedInnerChildKey,
-    /// The leaf level of the B-tree contained an inner node
     InnerNodeInLeafLevel,
-    /// The leaves of the B-tree were not all located at the same depth
     InvalidLeafNodeDepth {
-        /// The depth of the first leaf node in the tree
         expected: usize,
-        /// The depth of the invalid leaf node
         actual: usize,
     },
-    /// A leaf node did not contain entries in sorted order
-    UnsortedLeafNodeEntries,
-    /// A leaf node is not linked after a node despite being the next leaf node
+    UnsortedKeys,
     UnlinkedLeafNode,
-    /// A leaf node with lesser keys is linked after a leaf node with greater keys
-    UnsortedLeafNode,
-    /// The forward pointer of the last leaf did not have an offset of 0
-    LastLeafForwardPointerNotNull,
-    /// The number of entries the B-tree claims to have does not match the actual number of entries
+    LastLeafNodeNotNull,
+    MismatchedInnerKey,
     LengthMismatch {
-        /// The number of entries the B-tree claims to have
         expected: usize,
-        /// The actual number of entries in the B-tree
         actual: usize,
     },
-    /// The keys for an inner node were incorrect
-    IncorrectChildKey,
-    /// An context error occurred
-    ContextError(C),
-}
-
-impl<K, V, C> From<Infallible> for ArchivedBTreeMapError<K, V, C> {
-    fn from(_: Infallible) -> Self {
-        unsafe { core::hint::unreachable_unchecked() }
-    }
+    ChildNodeAfterParent,
 }
 
-impl<K, V, C> fmt::Display for ArchivedBTreeMapError<K, V, C>
-where
-    K: fmt::Display,
-    V: fmt::Display,
-    C: fmt::Display,
-{
+impl fmt::Display for BTreeMapError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::KeyCheckError(e) => write!(f, "key check error: {}", e),
-            Self::ValueCheckError(e) => write!(f, "value check error: {}", e),
-            Self::TooFewInnerNodeEntries(n) => write!(
+            BTreeMapError::TooFewEntries { is_inner, len, min } => write!(
                 f,
-                "too few inner node entries (expected at least {}): {}",
-                MIN_ENTRIES_PER_INNER_NODE, n
+                "{} node has {} entries but must have at least {}",
+                if *is_inner { "inner" } else { "leaf" },
+                len,
+                min,
             ),
-            Self::TooFewLeafNodeEntries(n) => write!(
-                f,
-                "too few leaf node entries (expected at least {}): {}",
-                MIN_ENTRIES_PER_LEAF_NODE, n,
-            ),
-            Self::CheckInnerNodeEntryError { index, inner } => write!(
-                f,
-                "inner node entry check error: index {}, error {}",
-                index, inner
-            ),
-            Self::CheckLeafNodeEntryError { index, inner } => write!(
+            BTreeMapError::InnerNodeInLeafLevel => {
+                write!(f, "inner node found in the leaf level of the B-tree")
+            }
+            BTreeMapError::InvalidLeafNodeDepth { expected, actual } => {
+                write!(
+                    f,
+                    "expected leaf node depth {} but found leaf node depth {}",
+                    expected, actual,
+                )
+            }
+            BTreeMapError::UnsortedKeys => {
+                write!(f, "B-tree keys are not in strictly increasing order")
+            }
+            BTreeMapError::UnlinkedLeafNode => write!(
                 f,
-                "leaf node entry check error: index {}, error {}",
-                index, inner
+                "leaf node is not linked to the leaf node that follows it",
             ),
-            Self::InvalidNodeSize(n) => write!(f, "invalid node size: {}", n),
-            Self::MismatchedInnerChildKey => {
-                write!(f, "mismatched inner child key")
+            BTreeMapError::LastLeafNodeNotNull => {
+                write!(f, "last leaf node has a non-null next pointer")
             }
-            Self::InnerNodeInLeafLevel => write!(f, "inner node in leaf level"),
-            Self::InvalidLeafNodeDepth { expected, actual } => write!(
+            BTreeMapError::MismatchedInnerKey => write!(
                 f,
-                "expected leaf node depth {} but found leaf node depth {}",
-                expected, actual,
+                "inner node key does not match the first key of its child",
             ),
-            Self::UnsortedLeafNodeEntries => {
-                write!(f, "leaf node contains keys in unsorted order")
-            }
-            Self::UnlinkedLeafNode => {
-                write!(f, "leaf nodes are not linked in the sorted order")
-            }
-            Self::UnsortedLeafNode => {
-                write!(f, "leaf nodes are not linked in sorted order")
-            }
-            Self::LastLeafForwardPointerNotNull => {
-                write!(f, "the forward pointer of the last leaf was not null")
-            }
-            Self::LengthMismatch { expected, actual } => write!(
+            BTreeMapError::LengthMismatch { expected, actual } => write!(
                 f,
-                "expected {} entries but there were actually {} entries",
+                "B-tree claims to have {} entries but contains {}",
                 expected, actual,
             ),
-            Self::IncorrectChildKey => {
-                write!(f, "incorrect child key in inner node")
+            BTreeMapError::ChildNodeAfterParent => {
+                write!(f, "child node is not located before its parent node")
             }
-            Self::ContextError(e) => write!(f, "context error: {}", e),
         }
     }
 }
 
 #[cfg(feature = "std")]
-const _: () = {
-    use std::error::Error;
-
-    impl<K, V, C> Error for ArchivedBTreeMapError<K, V, C>
-    where
-        K: Error + 'static,
-        V: Error + 'static,
-        C: Error + 'static,
-    {
-        fn source(&self) -> Option<&(dyn Error + 'static)> {
-            match self {
-                Self::KeyCheckError(e) => Some(e as &dyn Error),
-                Self::ValueCheckError(e) => Some(e as &dyn Error),
-                Self::TooFewInnerNodeEntries(_) => None,
-                Self::TooFewLeafNodeEntries(_) => None,
-                Self::CheckInnerNodeEntryError { inner, .. } => {
-                    Some(inner as &dyn Error)
-                }
-                Self::CheckLeafNodeEntryError { inner, .. } => {
-                    Some(inner as &dyn Error)
-                }
-                Self::InvalidNodeSize(_) => None,
-                Self::MismatchedInnerChildKey => None,
-                Self::InnerNodeInLeafLevel => None,
-                Self::InvalidLeafNodeDepth { .. } => None,
-                Self::UnsortedLeafNodeEntries => None,
-                Self::UnlinkedLeafNode => None,
-                Self::UnsortedLeafNode => None,
-                Self::LastLeafForwardPointerNotNull => None,
-                Self::LengthMismatch { .. } => None,
-                Self::IncorrectChildKey => None,
-                Self::ContextError(e) => Some(e as &dyn Error),
-            }
-        }
-    }
-};
-
-/// An error occurred while checking the entries of a leaf node
-#[derive(Debug)]
-struct CheckLeafNodeEntryContext {
-    /// The index of the leaf node entry
-    index: usize,
-}
-
-impl fmt::Display for CheckLeafNodeEntryContext {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "while checking leaf node entry {}", self.index)
-    }
-}
-
-impl<T> LayoutRaw for Node<[T]> {
-    fn layout_raw(
-        metadata: <Self as Pointee>::Metadata,
-    ) -> Result<Layout, LayoutError> {
-        Ok(Layout::new::<NodeHeader>()
-            .extend(Layout::array::<T>(metadata).unwrap())?
-            .0
-            .pad_to_align())
-    }
+impl std::error::Error for BTreeMapError {}
+
+#[inline]
+fn node_layout<T>(len: usize) -> Result<Layout, core::alloc::LayoutError> {
+    Ok(Layout::new::<NodeHeader>()
+        .extend(Layout::array::<T>(len)?)?
+        .0
+        .pad_to_align())
 }
 
 impl NodeHeader {
     #[inline]
-    unsafe fn manual_check_bytes<K, V, C, E>(
-        value: *const Self,
-        context: &mut C,
-    ) -> Result<(), E>
-    where
-        K: CheckBytes<C, E>,
-        V: CheckBytes<C, E>,
-        C: ArchiveContext<E> + ?Sized,
-    {
-        let raw_node = Self::manual_check_header(value, context)
-            .map_err(ArchivedBTreeMapError::ContextError)?;
-
-        let node_layout = if raw_node.is_inner() {
-            InnerNode::<K>::layout_raw(ptr_meta::metadata(
-                raw_node.classify_inner_ptr::<K>(),
-            ))
-            .map_err(C::wrap_layout_error)
-            .map_err(ArchivedBTreeMapError::ContextError)?
-        } else {
-            LeafNode::<K, V>::layout_raw(ptr_meta::metadata(
-                raw_node.classify_leaf_ptr::<K, V>(),
-            ))
-            .map_err(C::wrap_layout_error)
-            .map_err(ArchivedBTreeMapError::ContextError)?
-        };
-
-        context
-            .check_subtree_ptr(
-                (raw_node as *const NodeHeader).cast(),
-                &node_layout,
-            )
-            .map_err(ArchivedBTreeMapError::ContextError)?;
-
-        Self::manual_check_contents::<K, V, C>(raw_node, context)?;
-
-        Ok(raw_node)
+    fn inner_entries<K>(node: *const Self) -> *const InnerNodeEntry<K> {
+        let len = unsafe { (*node).len() };
+        let node = ptr_meta::from_raw_parts::<InnerNode<K>>(node.cast(), len);
+        unsafe { addr_of!((*node).tail).cast() }
     }
 
     #[inline]
-    unsafe fn manual_check_header<C: ArchiveContext<E> + ?Sized, E>(
-        value: *const Self,
-        context: &mut C,
-    ) -> Result<(), E> {
-        CheckBytes::check_bytes(ptr::addr_of!((*value).meta), context)?;
-        CheckBytes::check_bytes(ptr::addr_of!((*value).size), context)?;
-        RelPtr::manual_check_bytes(ptr::addr_of!((*value).ptr), context)?;
-
-        // All the fields have been checked and this is a valid RawNode
-        Ok(())
+    fn leaf_entries<K, V>(node: *const Self) -> *const LeafNodeEntry<K, V> {
+        let len = unsafe { (*node).len() };
+        let node = ptr_meta::from_raw_parts::<LeafNode<K, V>>(node.cast(), len);
+        unsafe { addr_of!((*node).tail).cast() }
     }
 
-    #[inline]
-    unsafe fn manual_check_contents<K, V, C, E>(
-        raw_node: &Self,
+    /// Checks that the header and entries of the node at `node` are located
+    /// in the subtree range, and returns the layout of the node.
+    ///
+    /// The contents of the entries are not checked.
+    unsafe fn check_bounds<K, V, C>(
+        node: *const Self,
         context: &mut C,
-    ) -> Result<(), E>
+    ) -> Result<Layout, C::Error>
     where
-        K: CheckBytes<C, E>,
-        V: CheckBytes<C, E>,
-        C: ArchiveContext<E> + ?Sized,
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
     {
-        // Now that the fields have been checked, we can start checking the specific subtype
-        let root = (raw_node as *const Self).cast();
-        let size = raw_node.size.to_native() as usize;
-        let offset = -isize::try_from(size)
-            .map_err(|_| ArchivedBTreeMapError::InvalidNodeSize(size))?;
-        let start = context
-            .check_ptr(root, offset, ())
-            .map_err(ArchivedBTreeMapError::ContextError)?;
-
-        // Push a new suffix range and check the inner or leaf part
-        let range = context
-            .push_suffix_subtree_range(start, root)
-            .map_err(ArchivedBTreeMapError::ContextError)?;
-        if raw_node.is_inner() {
-            InnerNode::manual_check_bytes::<V, C>(
-                raw_node.classify_inner_ptr::<K>(),
-                context,
-            )?;
+        context.check_subtree_ptr(node.cast(), &Layout::new::<Self>())?;
+        ArchivedU16::check_bytes(addr_of!((*node).meta), context)?;
+        ArchivedUsize::check_bytes(addr_of!((*node).size), context)?;
+        RelPtr::<Self>::check_bytes(addr_of!((*node).ptr), context)?;
+
+        let header = &*node;
+        let layout = if header.is_inner() {
+            node_layout::<InnerNodeEntry<K>>(header.len())
         } else {
-            CheckBytes::check_bytes(
-                raw_node.classify_leaf_ptr::<K, V>(),
-                context,
-            )?;
+            node_layout::<LeafNodeEntry<K, V>>(header.len())
         }
-        context
-            .pop_suffix_range(range)
-            .map_err(ArchivedBTreeMapError::ContextError)?;
+        .into_error()?;
+        context.check_subtree_ptr(node.cast(), &layout)?;
 
-        Ok(())
+        Ok(layout)
     }
-}
 
-impl<K> InnerNode<K> {
-    #[allow(clippy::type_complexity)]
-    fn verify_integrity<'a, V, C, E>(
-        &'a self,
-    ) -> Result<&K, E>
+    /// Checks the entries of the node at `node` and claims the memory of the
+    /// block that precedes it.
+    ///
+    /// The entries of leaf nodes are checked as elements of the map, starting
+    /// at index `first_index`. Returns whether every entry was valid.
+    unsafe fn check_entries<K, V, C>(
+        node: *const Self,
+        first_index: usize,
+        context: &mut C,
+    ) -> Result<bool, C::Error>
     where
-        K: CheckBytes<C, E> + PartialEq,
-        V: CheckBytes<C, E> + 'a,
-        C: ?Sized,
+        K: CheckBytes<C>,
+        V: CheckBytes<C>,
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
     {
-        for entry in self.tail.iter() {
-            let child = unsafe { &*entry.ptr.as_ptr() }.classify::<K, V>();
-            let first_key = match child {
-                ClassifiedNode::Inner(c) => c.verify_integrity::<V, C>()?,
-                ClassifiedNode::Leaf(c) => &c.tail[0].key,
-            };
-            if !entry.key.eq(first_key) {
-                return Err(ArchivedBTreeMapError::IncorrectChildKey);
+        let header = &*node;
+        let len = header.len();
+        if header.is_inner() {
+            // Inner nodes have one more child than their length (the least
+            // child pointer)
+            if len + 1 < MIN_ENTRIES_PER_INNER_NODE {
+                fail!(BTreeMapError::TooFewEntries {
+                    is_inner: true,
+                    len: len + 1,
+                    min: MIN_ENTRIES_PER_INNER_NODE,
+                });
             }
+        } else if len < MIN_ENTRIES_PER_LEAF_NODE {
+            fail!(BTreeMapError::TooFewEntries {
+                is_inner: false,
+                len,
+                min: MIN_ENTRIES_PER_LEAF_NODE,
+            });
         }
 
-        let least_child =
-            unsafe { &*self.header.ptr.as_ptr() }.classify::<K, V>();
-        let first_key = match least_child {
-            ClassifiedNode::Inner(c) => c.verify_integrity::<V, C>()?,
-            ClassifiedNode::Leaf(c) => &c.tail[0].key,
-        };
+        // The out-of-line data for the entries is located in the block before
+        // the node
+        let size = header.size.to_native() as usize;
+        let root = node.cast::<u8>();
+        range_start(root.addr(), size).into_error()?;
+        let start = root.wrapping_sub(size);
+        context.check_subtree_ptr(
+            start,
+            &Layout::from_size_align(size, 1).into_error()?,
+        )?;
+
+        let range = context.push_suffix_subtree_range(start, root)?;
+        // Entries are serialized in reverse order, so they are checked in
+        // reverse order
+        let mut all_valid = true;
+        if header.is_inner() {
+            let entries = Self::inner_entries::<K>(node);
+            for index in (0..len).rev() {
+                InnerNodeEntry::check_bytes(entries.add(index), context)?;
+            }
+        } else {
+            let entries = Self::leaf_entries::<K, V>(node);
+            for index in (0..len).rev() {
+                let entry = entries.add(index);
+                context.start_element()?;
+                let result = LeafNodeEntry::check_bytes(entry, context);
+                all_valid &= result.is_ok();
+                context.finish_element(
+                    first_index + index,
+                    entry.cast(),
+                    result,
+                )?;
+            }
+        }
+        context.pop_subtree_range(range)?;
 
-        Ok(first_key)
+        Ok(all_valid)
     }
 
-    #[inline]
-    unsafe fn manual_check_bytes<'a, V, C, E>(
-        value: *const Self,
-        context: &mut C,
-    ) -> Result<(), E>
-    where
-        K: CheckBytes<C, E>,
-        V: CheckBytes<C, E>,
-        C: ArchiveContext<E> + ?Sized,
-    {
-        // meta, size, and ptr have already been checked by the check_bytes for RawNode
-        let len = ptr_meta::metadata(value);
-
-        // Each inner node actually contains one more entry that the length indicates (the least
-        // child pointer)
-        if len + 1 < MIN_ENTRIES_PER_INNER_NODE {
-            return Err(ArchivedBTreeMapError::TooFewInnerNodeEntries(len + 1));
+    /// Returns the first key in the subtree rooted at `node`.
+    ///
+    /// # Safety
+    ///
+    /// `node` and all of its descendants must have been checked.
+    unsafe fn first_key<'a, K, V>(mut node: *const Self) -> &'a K {
+        while (*node).is_inner() {
+            node = (*node).ptr.as_ptr_wrapping();
         }
-
-        // The subtree range has already been set up for us so we can just check our tail
-        let tail_ptr = ptr::addr_of!((*value).tail) as *const InnerNodeEntry<K>;
-        for index in (0..len).rev() {
-            CheckBytes::check_bytes(tail_ptr.add(index), context).map_err(
-                |inner| ArchivedBTreeMapError::CheckInnerNodeEntryError {
-                    index,
-                    inner,
-                },
-            )?;
-        }
-
-        Ok(&*value)
+        &(*Self::leaf_entries::<K, V>(node)).key
     }
 }
 
-unsafe impl<K, V, C> CheckBytes<C, E> for LeafNode<K, V>
+/// Returns the target of a relative pointer to a node, checking that
+/// computing it does not overflow. The target has not been bounds checked.
+fn node_ptr<E: Error>(
+    ptr: &RelPtr<NodeHeader>,
+) -> Result<*const NodeHeader, E> {
+    offset_address(ptr.base().addr(), ptr.offset()).into_error()?;
+    Ok(ptr.as_ptr_wrapping().cast_const())
+}
+
+unsafe impl<K, V, C> CheckBytes<C> for ArchivedBTreeMap<K, V>
 where
-    K: CheckBytes<C, E>,
-    V: CheckBytes<C, E>,
-    C: ArchiveContext<E> + ?Sized,
+    K: CheckBytes<C> + Ord,
+    V: CheckBytes<C>,
+    C: Fallible + ArchiveContext + ?Sized,
+    C::Error: Error,
 {
-    #[inline]
     unsafe fn check_bytes(
         value: *const Self,
         context: &mut C,
-    ) -> Result<(), E> {
-        // meta, size, and ptr have already been checked by the check_bytes for RawNode
-        let len = ptr_meta::metadata(value);
-
-        if len < MIN_ENTRIES_PER_LEAF_NODE {
-            return Err(ArchivedBTreeMapError::TooFewLeafNodeEntries(len));
-        }
-
-        // The subtree range has already been set up for us so we can just check our tail
-        let tail_ptr =
-            ptr::addr_of!((*value).tail) as *const LeafNodeEntry<K, V>;
-        for index in (0..len).rev() {
-            CheckBytes::check_bytes(tail_ptr.add(index), context).map_err(
-                |inner| ArchivedBTreeMapError::CheckLeafNodeEntryError {
-                    index,
-                    inner,
-                },
-            )?;
+    ) -> Result<(), C::Error> {
+        ArchivedUsize::check_bytes(addr_of!((*value).len), context)?;
+        RelPtr::<NodeHeader>::check_bytes(addr_of!((*value).root), context)?;
+
+        let map = &*value;
+        let len = map.len();
+        if len == 0 {
+            return Ok(());
         }
 
-        Ok(&*value)
-    }
-}
-
-#[cfg(feature = "alloc")]
-const _: () = {
-    #[cfg(not(feature = "std"))]
-    use alloc::collections::VecDeque;
-    #[cfg(feature = "std")]
-    use std::collections::VecDeque;
-
-    use crate::primitive::ArchivedUsize;
-
-    unsafe impl<K, V, C> CheckBytes<C, E> for ArchivedBTreeMap<K, V>
-    where
-        K: CheckBytes<C, E> + Ord,
-        V: CheckBytes<C, E>,
-        C: ArchiveContext<E> + ?Sized,
-    {
-        unsafe fn check_bytes(
-            value: *const Self,
-            context: &mut C,
-        ) -> Result<(), E> {
-            let len = ArchivedUsize::check_bytes(
-                ptr::addr_of!((*value).len),
-                context,
-            )?
-            .to_native() as usize;
-
-            if len > 0 {
-                let root_rel_ptr = RelPtr::manual_check_bytes(
-                    ptr::addr_of!((*value).root),
-                    context,
+        // Every node is located before the root node, so claim the root and
+        // then check all of the nodes inside of its prefix range.
+        let root = node_ptr(&map.root)?;
+        context.record_offset(map.root.offset_slot())?;
+        context.record_subtree_ptr(
+            map.root.base(),
+            root.cast(),
+            &Layout::new::<NodeHeader>(),
+            type_name::<NodeHeader>(),
+        )?;
+        let root_layout = NodeHeader::check_bounds::<K, V, C>(root, context)?;
+        let nodes_range = context.push_prefix_subtree_range(
+            root.cast(),
+            root.cast::<u8>().add(root_layout.size()),
+        )?;
+
+        // Nodes are serialized level by level from the leaves up, and in
+        // reverse order within each level. Checking them in breadth-first
+        // order claims the memory of each node and the block before it from
+        // back to front. Any node which is not located before the previous
+        // block (including any node reachable more than once) fails the bounds
+        // check.
+        let mut nodes = vec![(root, 0, &map.root)];
+        let mut leaf_depth = None;
+        // Leaves are checked in order, so this is the index of the first entry
+        // in the next leaf
+        let mut leaf_entries = 0;
+        let mut all_valid = true;
+        let mut index = 0;
+        while index < nodes.len() {
+            let (node, depth, ptr) = nodes[index];
+            if index != 0 {
+                context.record_offset(ptr.offset_slot())?;
+                context.record_subtree_ptr(
+                    ptr.base(),
+                    node.cast(),
+                    &Layout::new::<NodeHeader>(),
+                    type_name::<NodeHeader>(),
                 )?;
+                NodeHeader::check_bounds::<K, V, C>(node, context)?;
+            }
+            all_valid &= NodeHeader::check_entries::<K, V, C>(
+                node,
+                leaf_entries,
+                context,
+            )?;
 
-                // Walk all the inner nodes, claim their memory, and check their contents
-                let mut nodes = VecDeque::new();
-                let root_ptr = context
-                    .check_subtree_rel_ptr(root_rel_ptr)
-                    .map_err(ArchivedBTreeMapError::ContextError)?;
-
-                // Before checking all the nodes, we have to push an additional prefix subtree with
-                // the root node
-                // Otherwise, when the suffix subtree of the root node is popped it will remove any
-                // trailing suffix space that should be checked by subsequent fields
-                let root = NodeHeader::manual_check_header(root_ptr, context)
-                    .map_err(ArchivedBTreeMapError::ContextError)?;
-
-                // To push the subtree, we need to know the real size of the root node
-                // Since the header is checked, we can just classify the pointer and use layout_raw
-                let root_layout = if root.is_inner() {
-                    InnerNode::<K>::layout_raw(ptr_meta::metadata(
-                        root.classify_inner_ptr::<K>(),
-                    ))
-                    .map_err(C::wrap_layout_error)
-                    .map_err(ArchivedBTreeMapError::ContextError)?
-                } else {
-                    LeafNode::<K, V>::layout_raw(ptr_meta::metadata(
-                        root.classify_leaf_ptr::<K, V>(),
-                    ))
-                    .map_err(C::wrap_layout_error)
-                    .map_err(ArchivedBTreeMapError::ContextError)?
-                };
+            let header = &*node;
+            if header.is_inner() {
+                if leaf_depth.is_some() {
+                    fail!(BTreeMapError::InnerNodeInLeafLevel);
+                }
 
-                // Because the layout of the subtree is dynamic, we need to bounds check the layout
-                // declared by the root node.
-                context
-                    .check_subtree_ptr(
-                        root_ptr.cast(),
-                        &root_layout,
-                    )
-                    .map_err(ArchivedBTreeMapError::ContextError)?;
-
-                // Now we can push the prefix subtree range.
-                let nodes_range = context
-                    .push_prefix_subtree_range(
-                        root_ptr.cast(),
-                        root_ptr.cast::<u8>().add(root_layout.size()),
-                    )
-                    .map_err(ArchivedBTreeMapError::ContextError)?;
-
-                // Now we're finally ready to check node subtrees
-                NodeHeader::manual_check_contents::<K, V, C>(root, context)?;
-
-                nodes.push_back((root, 0));
-
-                while let Some(&(node, depth)) = nodes.front() {
-                    // Break when a leaf is found
-                    if !node.is_inner() {
-                        break;
-                    }
-                    nodes.pop_front();
-                    let inner = node.classify_inner::<K>();
-
-                    let child_ptr = context
-                        .check_subtree_rel_ptr(&inner.header.ptr)
-                        .map_err(ArchivedBTreeMapError::ContextError)?;
-                    let child = NodeHeader::manual_check_bytes::<K, V, C>(
-                        child_ptr, context,
-                    )?;
-                    nodes.push_back((child, depth + 1));
-
-                    // The invariant that this node contains keys less than the first key of this node will
-                    // be checked when we iterate through the leaf nodes in order and check ordering
-                    for entry in inner.tail.iter() {
-                        let child_ptr = context
-                            .check_subtree_rel_ptr(&entry.ptr)
-                            .map_err(ArchivedBTreeMapError::ContextError)?;
-                        let child = NodeHeader::manual_check_bytes::<K, V, C>(
-                            child_ptr, context,
-                        )?;
-                        nodes.push_back((child, depth + 1));
+                nodes.push((node_ptr(&header.ptr)?, depth + 1, &header.ptr));
+                let entries = NodeHeader::inner_entries::<K>(node);
+                for i in 0..header.len() {
+                    let ptr = &(*entries.add(i)).ptr;
+                    nodes.push((node_ptr(ptr)?, depth + 1, ptr));
+                }
+            } else {
+                match leaf_depth {
+                    None => leaf_depth = Some(depth),
+                    Some(expected) if expected != depth => {
+                        fail!(BTreeMapError::InvalidLeafNodeDepth {
+                            expected,
+                            actual: depth,
+                        });
                     }
+                    Some(_) => (),
                 }
+                leaf_entries += header.len();
+            }
 
-                // We're done checking node subtrees now
-                context
-                    .pop_prefix_range(nodes_range)
-                    .map_err(ArchivedBTreeMapError::ContextError)?;
-
-                // The remaining nodes must all be leaf nodes
-                let mut entry_count = 0;
-                for (node, depth) in nodes.iter() {
-                    if !node.is_leaf() {
-                        return Err(
-                            ArchivedBTreeMapError::InnerNodeInLeafLevel,
-                        );
-                    }
-                    let leaf = node.classify_leaf::<K, V>();
-
-                    // Leaf nodes must all be the same depth
-                    let expected_depth = nodes.front().unwrap().1;
-                    if *depth != expected_depth {
-                        return Err(
-                            ArchivedBTreeMapError::InvalidLeafNodeDepth {
-                                expected: expected_depth,
-                                actual: *depth,
-                            },
-                        );
-                    }
+            index += 1;
+        }
 
-                    // They must contain entries in sorted order
-                    for (prev, next) in
-                        leaf.tail.iter().zip(leaf.tail.iter().skip(1))
-                    {
-                        if next.key < prev.key {
-                            return Err(
-                                ArchivedBTreeMapError::UnsortedLeafNodeEntries,
-                            );
-                        }
+        context.pop_subtree_range(nodes_range)?;
+
+        // The leaves are the last level of nodes, and must be linked together
+        // in order and contain sorted keys
+        let first_leaf = nodes
+            .iter()
+            .position(|&(node, _, _)| (*node).is_leaf())
+            .unwrap();
+        let leaves = &nodes[first_leaf..];
+        let mut entry_count = 0;
+        let mut prev_key = None::<&K>;
+        for (i, &(leaf, _, _)) in leaves.iter().enumerate() {
+            let header = &*leaf;
+            match leaves.get(i + 1) {
+                Some(&(next, _, _)) => {
+                    if header.ptr.as_ptr_wrapping().cast_const() != next {
+                        fail!(BTreeMapError::UnlinkedLeafNode);
                     }
-
-                    // Keep track of the number of entries found
-                    entry_count += leaf.tail.len();
+                    context.record_offset(header.ptr.offset_slot())?;
                 }
+                None => {
+                    if !header.ptr.is_null() {
+                        fail!(BTreeMapError::LastLeafNodeNotNull);
+                    }
+                }
+            }
 
-                for (i, (node, _)) in nodes.iter().enumerate() {
-                    let leaf = node.classify_leaf::<K, V>();
-
-                    // And they must link together in sorted order
-                    if i < nodes.len() - 1 {
-                        let next_ptr = context
-                            .check_rel_ptr(&leaf.header.ptr)
-                            .map_err(ArchivedBTreeMapError::ContextError)?;
-                        let next_node = nodes[i + 1].0.classify_leaf();
-
-                        if next_ptr
-                            != (next_node as *const LeafNode<K, V>).cast()
-                        {
-                            return Err(
-                                ArchivedBTreeMapError::UnlinkedLeafNode,
-                            );
-                        }
-                        if next_node.tail[0].key
-                            < leaf.tail[leaf.tail.len() - 1].key
-                        {
-                            return Err(
-                                ArchivedBTreeMapError::UnsortedLeafNode,
-                            );
-                        }
-                    } else {
-                        // The last node must have a null pointer forward
-                        if !leaf.header.ptr.is_null() {
-                            return Err(ArchivedBTreeMapError::LastLeafForwardPointerNotNull);
+            // Invalid entries may have invalid keys, so keys can only be
+            // compared if every entry is valid
+            if all_valid {
+                let entries = NodeHeader::leaf_entries::<K, V>(leaf);
+                for i in 0..header.len() {
+                    let key = &(*entries.add(i)).key;
+                    if let Some(prev_key) = prev_key {
+                        if prev_key >= key {
+                            fail!(BTreeMapError::UnsortedKeys);
                         }
                     }
+                    prev_key = Some(key);
                 }
+            }
+            entry_count += header.len();
+        }
 
-                // Make sure that the number of entries matches the length
-                if entry_count != len {
-                    return Err(ArchivedBTreeMapError::LengthMismatch {
-                        expected: len,
-                        actual: entry_count,
-                    });
-                }
+        if entry_count != len {
+            fail!(BTreeMapError::LengthMismatch {
+                expected: len,
+                actual: entry_count,
+            });
+        }
 
-                // Make sure that inner nodes are constructed appropriately
-                if root.is_inner() {
-                    root.classify_inner::<K>().verify_integrity::<V, C>()?;
+        if !all_valid {
+            return Ok(());
+        }
+
+        // The key of each inner node entry must be the first key of its child
+        for &(node, _, _) in nodes[..first_leaf].iter() {
+            let entries = NodeHeader::inner_entries::<K>(node);
+            for i in 0..(*node).len() {
+                let entry = &*entries.add(i);
+                let child = entry.ptr.as_ptr_wrapping().cast_const();
+                if &entry.key != NodeHeader::first_key::<K, V>(child) {
+                    fail!(BTreeMapError::MismatchedInnerKey);
                 }
             }
+        }
+
+        Ok(())
+    }
+}
 
-            Ok(&*value)
+/// Binary searches the `len` entries of a node with a fallible comparator.
+fn search_node<E>(
+    len: usize,
+    mut cmp: impl FnMut(usize) -> Result<Ordering, E>,
+) -> Result<Result<usize, usize>, E> {
+    let mut low = 0;
+    let mut high = len;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match cmp(mid)? {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return Ok(Ok(mid)),
         }
     }
-};
+    Ok(Err(low))
+}
+
+impl<'a, K: 'static, V: 'static> Lazy<'a, ArchivedBTreeMap<K, V>> {
+    /// Returns the number of entries in the B-tree map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        // The length is valid for any bit pattern
+        unsafe { (*self.as_ptr()).len() }
+    }
+
+    /// Returns whether the B-tree map is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a lazy handle to the value corresponding to the key.
+    ///
+    /// Only the nodes along the path to the key and the keys which are compared
+    /// while searching them are validated. The value is not validated.
+    pub fn value<Q, E>(&self, key: &Q) -> Result<Option<Lazy<'a, V>>, E>
+    where
+        K: Borrow<Q>
+            + Ord
+            + Portable
+            + CheckBytes<Strategy<DefaultValidator, E>>,
+        Q: Ord + ?Sized,
+        E: Error,
+    {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        // The fields of the map are valid for any bit pattern
+        let map = unsafe { &*self.as_ptr() };
+        let mut parent = self.as_ptr().cast::<u8>();
+        let mut node_ptr = map.root.as_ptr_wrapping().cast_const();
+        loop {
+            // Every node is located before its parent, so searching always
+            // terminates
+            if node_ptr.addr() >= parent.addr() {
+                fail!(BTreeMapError::ChildNodeAfterParent);
+            }
+
+            let node = unsafe { self.child(node_ptr) };
+            unsafe {
+                node.validate_before::<Shell<LeafNode<K, V>>, E>(
+                    parent,
+                    |context| {
+                        NodeHeader::check_bounds::<K, V, _>(node_ptr, context)
+                            .map(|_| ())
+                    },
+                )?;
+            }
+
+            let header = unsafe { &*node_ptr };
+            if header.is_inner() {
+                let entries = NodeHeader::inner_entries::<K>(node_ptr);
+                let entry = |index| unsafe { entries.add(index) };
+                let index = search_node(header.len(), |index| {
+                    let entry_key = unsafe {
+                        node.child(addr_of!((*entry(index)).key)).get::<E>()?
+                    };
+                    Ok(entry_key.borrow().cmp(key))
+                })?;
+                let next = match index {
+                    Ok(index) => unsafe { &(*entry(index)).ptr },
+                    Err(0) => &header.ptr,
+                    Err(index) => unsafe { &(*entry(index - 1)).ptr },
+                };
+                parent = node_ptr.cast();
+                node_ptr = next.as_ptr_wrapping().cast_const();
+            } else {
+                let entries = NodeHeader::leaf_entries::<K, V>(node_ptr);
+                let entry = |index| unsafe { entries.add(index) };
+                let index = search_node(header.len(), |index| {
+                    let entry_key = unsafe {
+                        node.child(addr_of!((*entry(index)).key)).get::<E>()?
+                    };
+                    Ok(entry_key.borrow().cmp(key))
+                })?;
+                return Ok(index.ok().map(|index| unsafe {
+                    node.child(addr_of!((*entry(index)).value))
+                }));
+            }
+        }
+    }
+}
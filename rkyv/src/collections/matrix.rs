@@ -0,0 +1,379 @@
+//! An archived two-dimensional matrix.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+use core::{
+    borrow::Borrow, fmt, iter::FusedIterator, ops::Index, ops::Range, pin::Pin,
+};
+
+use rancor::{fail, Error, Fallible};
+
+use crate::{
+    primitive::ArchivedUsize,
+    ser::{Allocator, Writer},
+    vec::{ArchivedVec, VecResolver},
+    Archive, Portable, Serialize, SerializeUnsized,
+};
+
+/// An archived two-dimensional matrix.
+///
+/// The elements are stored contiguously in row-major order, so each row is a
+/// slice of the archived data. This is the archived form of matrices
+/// serialized with [`AsMatrix`](crate::with::AsMatrix), and of
+/// `ndarray::Array2` and `nalgebra::DMatrix` when the `ndarray` and
+/// `nalgebra` features are enabled.
+///
+/// Validation checks that the number of elements is exactly `rows * cols`.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedMatrix<T> {
+    rows: ArchivedUsize,
+    cols: ArchivedUsize,
+    data: ArchivedVec<T>,
+}
+
+impl<T> ArchivedMatrix<T> {
+    /// Returns the number of rows in the matrix.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows.to_native() as usize
+    }
+
+    /// Returns the number of columns in the matrix.
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols.to_native() as usize
+    }
+
+    /// Returns the number of rows and columns in the matrix.
+    #[inline]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows(), self.cols())
+    }
+
+    /// Returns the number of elements in the matrix.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns whether the matrix has no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the elements of the matrix in row-major order.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self.data.as_slice()
+    }
+
+    /// Returns the pinned elements of the matrix in row-major order.
+    #[inline]
+    pub fn pin_mut_slice(self: Pin<&mut Self>) -> Pin<&mut [T]> {
+        // SAFETY: `data` is structurally pinned.
+        unsafe { self.map_unchecked_mut(|s| &mut s.data).pin_mut_slice() }
+    }
+
+    /// Returns the element at the given row and column, or `None` if either
+    /// is out of bounds.
+    #[inline]
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row < self.rows() && col < self.cols() {
+            self.as_slice().get(row * self.cols() + col)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the row at the given index, or `None` if it is out of bounds.
+    #[inline]
+    pub fn get_row(&self, row: usize) -> Option<&[T]> {
+        if row < self.rows() {
+            let start = row * self.cols();
+            self.as_slice().get(start..start + self.cols())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the row at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is out of bounds.
+    #[inline]
+    pub fn row(&self, row: usize) -> &[T] {
+        match self.get_row(row) {
+            Some(row) => row,
+            None => panic!(
+                "row index {} out of bounds for matrix with {} rows",
+                row,
+                self.rows(),
+            ),
+        }
+    }
+
+    /// Returns an iterator over the rows of the matrix.
+    #[inline]
+    pub fn iter_rows(&self) -> Rows<'_, T> {
+        Rows {
+            matrix: self,
+            rows: 0..self.rows(),
+        }
+    }
+
+    /// Resolves an archived matrix from a resolver.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing a matrix
+    #[inline]
+    pub unsafe fn resolve_from_resolver(
+        pos: usize,
+        resolver: MatrixResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.rows);
+        usize::resolve(&resolver.rows, pos + fp, (), fo);
+        let (fp, fo) = out_field!(out.cols);
+        usize::resolve(&resolver.cols, pos + fp, (), fo);
+        let (fp, fo) = out_field!(out.data);
+        ArchivedVec::resolve_from_len(
+            resolver.rows * resolver.cols,
+            pos + fp,
+            resolver.data,
+            fo,
+        );
+    }
+
+    /// Serializes an archived matrix from a slice of elements in row-major
+    /// order.
+    ///
+    /// Fails if the length of the slice is not `rows * cols`.
+    #[inline]
+    pub fn serialize_from_slice<U, S>(
+        rows: usize,
+        cols: usize,
+        slice: &[U],
+        serializer: &mut S,
+    ) -> Result<MatrixResolver, S::Error>
+    where
+        U: Serialize<S, Archived = T>,
+        S: Fallible + Writer + ?Sized,
+        S::Error: Error,
+        [U]: SerializeUnsized<S>,
+    {
+        check_shape(rows, cols, slice.len())?;
+        Ok(MatrixResolver {
+            rows,
+            cols,
+            data: ArchivedVec::serialize_from_slice(slice, serializer)?,
+        })
+    }
+
+    /// Serializes an archived matrix from an iterator of elements in
+    /// row-major order.
+    ///
+    /// Fails if the length of the iterator is not `rows * cols`.
+    #[inline]
+    pub fn serialize_from_iter<U, I, S>(
+        rows: usize,
+        cols: usize,
+        iter: I,
+        serializer: &mut S,
+    ) -> Result<MatrixResolver, S::Error>
+    where
+        U: Serialize<S, Archived = T>,
+        I: ExactSizeIterator,
+        I::Item: Borrow<U>,
+        S: Fallible + Allocator + Writer + ?Sized,
+        S::Error: Error,
+    {
+        check_shape(rows, cols, iter.len())?;
+        Ok(MatrixResolver {
+            rows,
+            cols,
+            data: ArchivedVec::serialize_from_iter(iter, serializer)?,
+        })
+    }
+}
+
+fn check_shape<E: Error>(
+    rows: usize,
+    cols: usize,
+    len: usize,
+) -> Result<(), E> {
+    if rows.checked_mul(cols) != Some(len) {
+        fail!(MatrixShapeError::ShapeMismatch { rows, cols, len });
+    }
+    Ok(())
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedMatrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter_rows()).finish()
+    }
+}
+
+impl<T> Index<(usize, usize)> for ArchivedMatrix<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        match self.get(row, col) {
+            Some(value) => value,
+            None => panic!(
+                "index ({}, {}) out of bounds for matrix with shape ({}, {})",
+                row,
+                col,
+                self.rows(),
+                self.cols(),
+            ),
+        }
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedMatrix<U>> for ArchivedMatrix<T> {
+    #[inline]
+    fn eq(&self, other: &ArchivedMatrix<U>) -> bool {
+        self.shape() == other.shape() && self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq> Eq for ArchivedMatrix<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T: PartialEq<U>, U> PartialEq<Vec<Vec<U>>> for ArchivedMatrix<T> {
+    #[inline]
+    fn eq(&self, other: &Vec<Vec<U>>) -> bool {
+        self.rows() == other.len()
+            && self
+                .iter_rows()
+                .zip(other.iter())
+                .all(|(a, b)| a == b.as_slice())
+    }
+}
+
+/// An iterator over the rows of an [`ArchivedMatrix`].
+pub struct Rows<'a, T> {
+    matrix: &'a ArchivedMatrix<T>,
+    rows: Range<usize>,
+}
+
+impl<'a, T> Iterator for Rows<'a, T> {
+    type Item = &'a [T];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next().map(|row| self.matrix.row(row))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Rows<'_, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rows.next_back().map(|row| self.matrix.row(row))
+    }
+}
+
+impl<T> ExactSizeIterator for Rows<'_, T> {}
+
+impl<T> FusedIterator for Rows<'_, T> {}
+
+/// The resolver for [`ArchivedMatrix`].
+pub struct MatrixResolver {
+    rows: usize,
+    cols: usize,
+    data: VecResolver,
+}
+
+/// An error resulting from a matrix with an invalid shape.
+#[derive(Debug)]
+pub enum MatrixShapeError {
+    /// A row had a different length than the first row.
+    Ragged {
+        /// The index of the row.
+        row: usize,
+        /// The length of the first row.
+        expected: usize,
+        /// The length of the row.
+        found: usize,
+    },
+    /// The number of elements was not the number of rows times the number of
+    /// columns.
+    ShapeMismatch {
+        /// The number of rows.
+        rows: usize,
+        /// The number of columns.
+        cols: usize,
+        /// The number of elements.
+        len: usize,
+    },
+}
+
+impl fmt::Display for MatrixShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixShapeError::Ragged {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "matrix row {} has {} columns but the first row has {}",
+                row, found, expected,
+            ),
+            MatrixShapeError::ShapeMismatch { rows, cols, len } => write!(
+                f,
+                "matrix with {} rows and {} columns has {} elements",
+                rows, cols, len,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MatrixShapeError {}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        Verify,
+    };
+    use rancor::fail;
+
+    use super::{ArchivedMatrix, MatrixShapeError};
+
+    unsafe impl<T, C> Verify<C> for ArchivedMatrix<T>
+    where
+        C: Fallible + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+            let (rows, cols) = self.shape();
+            if rows.checked_mul(cols) != Some(self.len()) {
+                fail!(MatrixShapeError::ShapeMismatch {
+                    rows,
+                    cols,
+                    len: self.len(),
+                });
+            }
+            Ok(())
+        }
+    }
+}
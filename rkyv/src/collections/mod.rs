@@ -2,5 +2,8 @@
 
 pub mod btree_map;
 pub mod btree_set;
+pub mod matrix;
+pub mod raw;
+pub mod string_dict;
 pub mod swiss_table;
 pub mod util;
@@ -0,0 +1,34 @@
+//! Building blocks for custom archived containers.
+//!
+//! This module collects the low-level types that rkyv's own containers are
+//! built from, so that other crates can build archived containers without
+//! depending on crate internals:
+//!
+//! - [`ArchivedHashTable`] is an archived SwissTable with explicit hashing.
+//!   Containers store their own entry type in it, serialize it with
+//!   [`serialize_from_iter`](ArchivedHashTable::serialize_from_iter), resolve
+//!   it with [`resolve_from_len`](ArchivedHashTable::resolve_from_len), and
+//!   find entries with [`probe`](ArchivedHashTable::probe) or
+//!   [`get_with`](ArchivedHashTable::get_with). See the
+//!   [`table`](super::swiss_table::table) module for the invariants that its
+//!   storage upholds.
+//! - [`ArchivedVec`] is a length-prefixed array of archived values, which can
+//!   be serialized from an iterator with
+//!   [`serialize_from_iter`](ArchivedVec::serialize_from_iter) and resolved
+//!   with [`resolve_from_len`](ArchivedVec::resolve_from_len).
+//! - [`ScratchVec`] is a vector allocated from the scratch space of an
+//!   [`Allocator`](crate::ser::Allocator). Scratch vectors must be freed in
+//!   the reverse order they were allocated.
+//!
+//! An archived container wraps these types in a `#[repr(transparent)]` or
+//! `#[repr(C)]` struct deriving [`Portable`](crate::Portable) and `CheckBytes`,
+//! and resolves them from the length of the native container. See the
+//! `custom_container` example for a complete container.
+
+pub use super::swiss_table::table::{
+    ArchivedHashTable, HashTableResolver, RawIter, RawOrderedIter,
+};
+pub use crate::{
+    util::ScratchVec,
+    vec::{ArchivedVec, VecResolver},
+};
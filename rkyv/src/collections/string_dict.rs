@@ -0,0 +1,597 @@
+//! An archived dictionary of deduplicated strings.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{cmp::Ordering, fmt, iter::FusedIterator, slice, str};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+#[cfg(feature = "alloc")]
+use rancor::{fail, Error, Fallible};
+
+#[cfg(feature = "alloc")]
+use crate::ser::{Writer, WriterExt as _};
+use crate::{
+    primitive::ArchivedU32,
+    vec::{ArchivedVec, VecResolver},
+    Portable,
+};
+
+/// An archived dictionary of deduplicated strings.
+///
+/// Each distinct string is assigned a `u32` code in the order it was first
+/// seen during serialization. The strings are stored back-to-back in a single
+/// byte buffer and located through an array of offsets, so each string costs
+/// only four bytes of overhead. Strings can be looked up by code with
+/// [`get`](ArchivedStringDict::get) and codes can be looked up by string with
+/// [`lookup`](ArchivedStringDict::lookup).
+///
+/// This is useful for archiving low-cardinality string columns as a dictionary
+/// and a column of codes. See
+/// [`serialize_dict_returning_codes`](ArchivedStringDict::serialize_dict_returning_codes)
+/// for an example.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedStringDict {
+    bytes: ArchivedVec<u8>,
+    // The start of each string followed by the end of the last string
+    offsets: ArchivedVec<ArchivedU32>,
+    // The codes of each string, sorted by string
+    sorted: ArchivedVec<ArchivedU32>,
+}
+
+impl ArchivedStringDict {
+    /// Returns the number of distinct strings in the dictionary.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    /// Returns whether the dictionary is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    #[inline]
+    fn get_unchecked(&self, code: usize) -> &str {
+        let offsets = self.offsets.as_slice();
+        let start = offsets[code].to_native() as usize;
+        let end = offsets[code + 1].to_native() as usize;
+        // SAFETY: Each string in the dictionary is valid UTF-8.
+        unsafe { str::from_utf8_unchecked(&self.bytes[start..end]) }
+    }
+
+    /// Returns the string with the given code, or `None` if the code is out
+    /// of bounds.
+    #[inline]
+    pub fn get(&self, code: u32) -> Option<&str> {
+        let code = code as usize;
+        if code < self.len() {
+            Some(self.get_unchecked(code))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the code of the given string, or `None` if the string is not in
+    /// the dictionary.
+    pub fn lookup(&self, value: &str) -> Option<u32> {
+        self.sorted
+            .binary_search_by(|code| {
+                self.get_unchecked(code.to_native() as usize).cmp(value)
+            })
+            .ok()
+            .map(|index| self.sorted[index].to_native())
+    }
+
+    /// Returns an iterator over the strings in the dictionary in code order.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bytes: self.bytes.as_slice(),
+            offsets: self.offsets.windows(2),
+        }
+    }
+
+    /// Resolves an archived string dictionary from a resolver.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing a string dictionary
+    #[inline]
+    pub unsafe fn resolve_from_resolver(
+        pos: usize,
+        resolver: StringDictResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.bytes);
+        ArchivedVec::resolve_from_len(
+            resolver.bytes_len,
+            pos + fp,
+            resolver.bytes,
+            fo,
+        );
+        let (fp, fo) = out_field!(out.offsets);
+        ArchivedVec::resolve_from_len(
+            resolver.len + 1,
+            pos + fp,
+            resolver.offsets,
+            fo,
+        );
+        let (fp, fo) = out_field!(out.sorted);
+        ArchivedVec::resolve_from_len(
+            resolver.len,
+            pos + fp,
+            resolver.sorted,
+            fo,
+        );
+    }
+
+    /// Serializes a string dictionary from an iterator of strings.
+    ///
+    /// Duplicate strings are only written once. Use
+    /// [`serialize_dict_returning_codes`](ArchivedStringDict::serialize_dict_returning_codes)
+    /// to also get the code assigned to each string.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn serialize_from_iter<'a, I, S>(
+        iter: I,
+        serializer: &mut S,
+    ) -> Result<StringDictResolver, S::Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+        S: Fallible + Writer + ?Sized,
+        S::Error: Error,
+    {
+        Self::serialize_dict_returning_codes(iter, serializer)
+            .map(|(resolver, _)| resolver)
+    }
+
+    /// Serializes a string dictionary from an iterator of strings and returns
+    /// the code assigned to each string in iteration order.
+    ///
+    /// Codes are assigned in the order that distinct strings are first seen,
+    /// starting from zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rkyv::{
+    ///     access_unchecked,
+    ///     collections::string_dict::{ArchivedStringDict, StringDictResolver},
+    ///     rancor::{Error, Fallible, Failure},
+    ///     ser::Writer,
+    ///     to_bytes,
+    ///     vec::{ArchivedVec, VecResolver},
+    ///     Archive, Archived, Portable, Serialize,
+    /// };
+    ///
+    /// struct Column {
+    ///     values: Vec<&'static str>,
+    /// }
+    ///
+    /// #[derive(Portable)]
+    /// #[repr(C)]
+    /// struct ArchivedColumn {
+    ///     dict: ArchivedStringDict,
+    ///     codes: ArchivedVec<Archived<u32>>,
+    /// }
+    ///
+    /// struct ColumnResolver {
+    ///     dict: StringDictResolver,
+    ///     codes: VecResolver,
+    ///     len: usize,
+    /// }
+    ///
+    /// impl Archive for Column {
+    ///     type Archived = ArchivedColumn;
+    ///     type Resolver = ColumnResolver;
+    ///
+    ///     unsafe fn resolve(
+    ///         &self,
+    ///         pos: usize,
+    ///         resolver: Self::Resolver,
+    ///         out: *mut Self::Archived,
+    ///     ) {
+    ///         let (fp, fo) = rkyv::out_field!(out.dict);
+    ///         ArchivedStringDict::resolve_from_resolver(
+    ///             pos + fp,
+    ///             resolver.dict,
+    ///             fo,
+    ///         );
+    ///         let (fp, fo) = rkyv::out_field!(out.codes);
+    ///         ArchivedVec::resolve_from_len(
+    ///             resolver.len,
+    ///             pos + fp,
+    ///             resolver.codes,
+    ///             fo,
+    ///         );
+    ///     }
+    /// }
+    ///
+    /// impl<S> Serialize<S> for Column
+    /// where
+    ///     S: Fallible + Writer + ?Sized,
+    ///     S::Error: Error,
+    ///     [u32]: rkyv::SerializeUnsized<S>,
+    /// {
+    ///     fn serialize(
+    ///         &self,
+    ///         serializer: &mut S,
+    ///     ) -> Result<Self::Resolver, S::Error> {
+    ///         let (dict, codes) =
+    ///             ArchivedStringDict::serialize_dict_returning_codes(
+    ///                 self.values.iter().copied(),
+    ///                 serializer,
+    ///             )?;
+    ///         Ok(ColumnResolver {
+    ///             dict,
+    ///             codes: ArchivedVec::serialize_from_slice(&codes, serializer)?,
+    ///             len: codes.len(),
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let column = Column {
+    ///     values: vec!["red", "green", "red", "blue", "green", "red"],
+    /// };
+    /// let bytes = to_bytes::<_, 256, Failure>(&column).unwrap();
+    /// let archived = unsafe { access_unchecked::<ArchivedColumn>(&bytes) };
+    ///
+    /// assert_eq!(archived.dict.len(), 3);
+    /// assert_eq!(archived.codes, [0, 1, 0, 2, 1, 0]);
+    /// assert_eq!(archived.dict.get(2), Some("blue"));
+    /// assert_eq!(archived.dict.lookup("green"), Some(1));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn serialize_dict_returning_codes<'a, I, S>(
+        iter: I,
+        serializer: &mut S,
+    ) -> Result<(StringDictResolver, Vec<u32>), S::Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+        S: Fallible + Writer + ?Sized,
+        S::Error: Error,
+    {
+        let iter = iter.into_iter();
+
+        let mut distinct = BTreeMap::<&'a str, u32>::new();
+        let mut strings = Vec::new();
+        let mut codes = Vec::with_capacity(iter.size_hint().0);
+        for value in iter {
+            let code = match distinct.get(value) {
+                Some(code) => *code,
+                None => {
+                    let code = match u32::try_from(strings.len()) {
+                        Ok(code) => code,
+                        Err(_) => fail!(StringDictOverflow::TooManyStrings),
+                    };
+                    distinct.insert(value, code);
+                    strings.push(value);
+                    code
+                }
+            };
+            codes.push(code);
+        }
+
+        let bytes_pos = serializer.pos();
+        let mut bytes_len = 0usize;
+        for value in strings.iter() {
+            serializer.write(value.as_bytes())?;
+            bytes_len += value.len();
+        }
+        if u32::try_from(bytes_len).is_err() {
+            fail!(StringDictOverflow::TooManyBytes);
+        }
+
+        let offsets_pos = serializer.align_for::<ArchivedU32>()?;
+        let mut offset = 0u32;
+        for value in strings.iter() {
+            unsafe {
+                serializer.resolve_aligned(&offset, ())?;
+            }
+            offset += value.len() as u32;
+        }
+        unsafe {
+            serializer.resolve_aligned(&offset, ())?;
+        }
+
+        let sorted_pos = serializer.pos();
+        for code in distinct.values() {
+            unsafe {
+                serializer.resolve_aligned(code, ())?;
+            }
+        }
+
+        Ok((
+            StringDictResolver {
+                len: strings.len(),
+                bytes_len,
+                bytes: VecResolver::from_pos(bytes_pos),
+                offsets: VecResolver::from_pos(offsets_pos),
+                sorted: VecResolver::from_pos(sorted_pos),
+            },
+            codes,
+        ))
+    }
+}
+
+impl fmt::Debug for ArchivedStringDict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a ArchivedStringDict {
+    type Item = &'a str;
+    type IntoIter = Iter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl PartialEq for ArchivedStringDict {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for ArchivedStringDict {}
+
+impl PartialOrd for ArchivedStringDict {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArchivedStringDict {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+/// The resolver for [`ArchivedStringDict`].
+pub struct StringDictResolver {
+    len: usize,
+    bytes_len: usize,
+    bytes: VecResolver,
+    offsets: VecResolver,
+    sorted: VecResolver,
+}
+
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+enum StringDictOverflow {
+    TooManyStrings,
+    TooManyBytes,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for StringDictOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StringDictOverflow::TooManyStrings => write!(
+                f,
+                "string dictionary has more distinct strings than can be \
+                 assigned u32 codes",
+            ),
+            StringDictOverflow::TooManyBytes => write!(
+                f,
+                "string dictionary has more bytes than can be addressed by \
+                 u32 offsets",
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StringDictOverflow {}
+
+/// An iterator over the strings of an [`ArchivedStringDict`].
+pub struct Iter<'a> {
+    bytes: &'a [u8],
+    offsets: slice::Windows<'a, ArchivedU32>,
+}
+
+impl<'a> Iter<'a> {
+    #[inline]
+    fn get(&self, offsets: &[ArchivedU32]) -> &'a str {
+        let start = offsets[0].to_native() as usize;
+        let end = offsets[1].to_native() as usize;
+        // SAFETY: Each string in the dictionary is valid UTF-8.
+        unsafe { str::from_utf8_unchecked(&self.bytes[start..end]) }
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.offsets.next().map(|offsets| self.get(offsets))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.offsets.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.offsets.next_back().map(|offsets| self.get(offsets))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use core::{fmt, str};
+
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        Verify,
+    };
+    use rancor::fail;
+
+    use super::ArchivedStringDict;
+
+    #[derive(Debug)]
+    enum InvalidStringDict {
+        LengthMismatch {
+            offsets: usize,
+            codes: usize,
+        },
+        NonZeroStart {
+            start: u32,
+        },
+        DecreasingOffset {
+            index: usize,
+        },
+        OffsetOutOfBounds {
+            index: usize,
+            offset: u32,
+            len: usize,
+        },
+        InvalidUtf8 {
+            index: usize,
+        },
+        CodeOutOfBounds {
+            index: usize,
+            code: u32,
+        },
+        Unsorted {
+            index: usize,
+        },
+    }
+
+    impl fmt::Display for InvalidStringDict {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                InvalidStringDict::LengthMismatch { offsets, codes } => write!(
+                    f,
+                    "string dictionary must have one more offset than codes \
+                     (offsets: {}, codes: {})",
+                    offsets, codes,
+                ),
+                InvalidStringDict::NonZeroStart { start } => write!(
+                    f,
+                    "string dictionary offsets must start at zero but start \
+                     at {}",
+                    start,
+                ),
+                InvalidStringDict::DecreasingOffset { index } => write!(
+                    f,
+                    "string dictionary offset at index {} is less than the \
+                     previous offset",
+                    index,
+                ),
+                InvalidStringDict::OffsetOutOfBounds { index, offset, len } => {
+                    write!(
+                        f,
+                        "string dictionary offset {} at index {} is out of \
+                         bounds for {} bytes",
+                        offset, index, len,
+                    )
+                }
+                InvalidStringDict::InvalidUtf8 { index } => write!(
+                    f,
+                    "string dictionary entry {} is not valid UTF-8",
+                    index,
+                ),
+                InvalidStringDict::CodeOutOfBounds { index, code } => write!(
+                    f,
+                    "string dictionary sorted code {} at index {} is out of \
+                     bounds",
+                    code, index,
+                ),
+                InvalidStringDict::Unsorted { index } => write!(
+                    f,
+                    "string dictionary sorted codes are not strictly \
+                     increasing at index {}",
+                    index,
+                ),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for InvalidStringDict {}
+
+    unsafe impl<C> Verify<C> for ArchivedStringDict
+    where
+        C: Fallible + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+            let bytes = self.bytes.as_slice();
+            let offsets = self.offsets.as_slice();
+            let sorted = self.sorted.as_slice();
+
+            if offsets.len() != sorted.len() + 1 {
+                fail!(InvalidStringDict::LengthMismatch {
+                    offsets: offsets.len(),
+                    codes: sorted.len(),
+                });
+            }
+
+            let start = offsets[0].to_native();
+            if start != 0 {
+                fail!(InvalidStringDict::NonZeroStart { start });
+            }
+
+            for (index, window) in offsets.windows(2).enumerate() {
+                let start = window[0].to_native();
+                let end = window[1].to_native();
+                if end < start {
+                    fail!(InvalidStringDict::DecreasingOffset {
+                        index: index + 1
+                    });
+                }
+                if end as usize > bytes.len() {
+                    fail!(InvalidStringDict::OffsetOutOfBounds {
+                        index: index + 1,
+                        offset: end,
+                        len: bytes.len(),
+                    });
+                }
+                if str::from_utf8(&bytes[start as usize..end as usize]).is_err()
+                {
+                    fail!(InvalidStringDict::InvalidUtf8 { index });
+                }
+            }
+
+            let mut prev = None;
+            for (index, code) in sorted.iter().enumerate() {
+                let code = code.to_native();
+                if code as usize >= sorted.len() {
+                    fail!(InvalidStringDict::CodeOutOfBounds { index, code });
+                }
+                let value = self.get_unchecked(code as usize);
+                if matches!(prev, Some(prev) if prev >= value) {
+                    fail!(InvalidStringDict::Unsorted { index });
+                }
+                prev = Some(value);
+            }
+
+            Ok(())
+        }
+    }
+}
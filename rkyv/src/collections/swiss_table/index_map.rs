@@ -51,7 +51,7 @@ impl<K, V, H> ArchivedIndexMap<K, V, H> {
         let len = self.len();
         unsafe {
             Pin::map_unchecked_mut(self, |s| {
-                from_raw_parts_mut(s.entries.as_ptr(), len)
+                from_raw_parts_mut(s.entries.as_mut_ptr(), len)
             })
         }
     }
@@ -406,6 +406,16 @@ where
 
 impl<K: Eq, V: Eq, H> Eq for ArchivedIndexMap<K, V, H> {}
 
+impl<'a, K, V, H> IntoIterator for &'a ArchivedIndexMap<K, V, H> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 struct RawIter<'a, K, V> {
     current: *const Entry<K, V>,
     remaining: usize,
@@ -547,6 +557,7 @@ mod verify {
             &self,
             context: &mut C,
         ) -> Result<(), <C as Fallible>::Error> {
+            context.record_offset(self.entries.offset_slot())?;
             let ptr = unsafe {
                 context.bounds_check_subtree_base_offset::<[Entry<K, V>]>(
                     self.entries.base(),
@@ -157,5 +157,15 @@ impl<K: PartialEq, H> PartialEq for ArchivedIndexSet<K, H> {
 
 impl<K: Eq, H> Eq for ArchivedIndexSet<K, H> {}
 
+impl<'a, K, H> IntoIterator for &'a ArchivedIndexSet<K, H> {
+    type Item = &'a K;
+    type IntoIter = Keys<'a, K, ()>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// The resolver for archived index sets.
 pub struct IndexSetResolver(IndexMapResolver);
@@ -14,19 +14,27 @@ use rancor::{Error, Fallible};
 
 use crate::{
     collections::swiss_table::{
-        table::{ArchivedHashTable, HashTableResolver, RawIter},
+        table::{
+            ArchivedHashTable, HashTableResolver, RawIter, RawOrderedIter,
+        },
         Entry, EntryAdapter,
     },
+    de::DeserializeEntries,
     hash::{hash_value, FxHasher64},
     ser::{Allocator, Writer},
-    Portable, Serialize,
+    unpin::{unpin_mut, ArchivedNoRelPtrs},
+    Deserialize, Portable, Serialize,
 };
 
 /// An archived SwissTable hash map.
 #[derive(Portable)]
 #[archive(crate)]
 #[repr(transparent)]
-#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
 pub struct ArchivedHashMap<K, V, H = FxHasher64> {
     table: ArchivedHashTable<Entry<K, V>>,
     _phantom: PhantomData<H>,
@@ -51,6 +59,15 @@ impl<K, V, H> ArchivedHashMap<K, V, H> {
         self.table.capacity()
     }
 
+    /// Returns the number of buckets in the hash map.
+    ///
+    /// This is the same as the capacity, including the buckets which are
+    /// empty.
+    #[inline]
+    pub fn buckets(&self) -> usize {
+        self.table.buckets()
+    }
+
     /// Returns an iterator over the key-value entries in the hash map.
     #[inline]
     pub fn iter(&self) -> Iter<'_, K, V, H> {
@@ -60,11 +77,32 @@ impl<K, V, H> ArchivedHashMap<K, V, H> {
         }
     }
 
+    /// Returns an iterator which deserializes the key-value entries in the
+    /// hash map one at a time.
+    ///
+    /// All of the entries are deserialized with the same deserializer, and
+    /// entries are only deserialized when they are reached. Use
+    /// [`collect_deserialized`](DeserializeEntries::collect_deserialized) to
+    /// deserialize all of the remaining entries into a collection.
+    #[inline]
+    pub fn entries_deserialized<'d, K2, V2, D>(
+        &self,
+        deserializer: &'d mut D,
+    ) -> DeserializeEntries<'d, Iter<'_, K, V, H>, K2, V2, D>
+    where
+        K: Deserialize<K2, D>,
+        V: Deserialize<V2, D>,
+        D: Fallible + ?Sized,
+    {
+        DeserializeEntries::new(self.iter(), deserializer)
+    }
+
     /// Returns an iterator over the mutable key-value entries in the hash map.
     #[inline]
     pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, K, V, H> {
+        let table = unsafe { Pin::map_unchecked_mut(self, |s| &mut s.table) };
         IterMut {
-            raw: self.table.raw_iter(),
+            raw: table.raw_iter_mut(),
             _phantom: PhantomData,
         }
     }
@@ -90,11 +128,46 @@ impl<K, V, H> ArchivedHashMap<K, V, H> {
     /// Returns an iterator over the mutable values in the hash map.
     #[inline]
     pub fn values_mut(self: Pin<&mut Self>) -> ValuesMut<'_, K, V, H> {
+        let table = unsafe { Pin::map_unchecked_mut(self, |s| &mut s.table) };
         ValuesMut {
-            raw: self.table.raw_iter(),
+            raw: table.raw_iter_mut(),
             _phantom: PhantomData,
         }
     }
+
+    /// Returns whether the hash map stores the order that its entries were
+    /// serialized in.
+    ///
+    /// Hash maps serialized with
+    /// [`serialize_from_iter_ordered`](Self::serialize_from_iter_ordered) are
+    /// ordered.
+    #[inline]
+    pub fn is_ordered(&self) -> bool {
+        self.table.is_ordered()
+    }
+
+    /// Returns an iterator over the key-value entries in the hash map in the
+    /// order that they were serialized in.
+    ///
+    /// Returns `None` if the hash map is not ordered.
+    #[inline]
+    pub fn iter_ordered(&self) -> Option<IterOrdered<'_, K, V, H>> {
+        Some(IterOrdered {
+            raw: self.table.raw_iter_ordered()?,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the `n`th key-value entry in the order that the entries were
+    /// serialized in.
+    ///
+    /// Returns `None` if the hash map is not ordered or `n` is out of bounds.
+    #[inline]
+    pub fn get_index_ordered(&self, n: usize) -> Option<(&K, &V)> {
+        let entry = self.table.get_index_ordered(n)?;
+        let entry = unsafe { entry.as_ref() };
+        Some((&entry.key, &entry.value))
+    }
 }
 
 impl<K, V, H: Hasher + Default> ArchivedHashMap<K, V, H> {
@@ -205,6 +278,21 @@ impl<K, V, H: Hasher + Default> ArchivedHashMap<K, V, H> {
         Some(self.get_key_value_mut(key)?.1)
     }
 
+    /// Returns a mutable reference to the value corresponding to the supplied
+    /// key without pinning it.
+    ///
+    /// This is only available for values which can be mutated without
+    /// pinning.
+    #[inline]
+    pub fn get_mut_unpinned<Q>(self: Pin<&mut Self>, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: ArchivedNoRelPtrs,
+    {
+        Self::get_mut(self, key).map(unpin_mut)
+    }
+
     /// Returns whether the hash map contains the given key.
     #[inline]
     pub fn contains_key<Q>(&self, key: &Q) -> bool
@@ -215,6 +303,100 @@ impl<K, V, H: Hasher + Default> ArchivedHashMap<K, V, H> {
         self.get(key).is_some()
     }
 
+    /// Returns an iterator over the keys which are in only one of the two hash
+    /// maps, or which have different values in each.
+    ///
+    /// Every entry of both hash maps is visited once, and each is looked up in
+    /// the other hash map. Keys only in `self` and changed keys are yielded
+    /// before keys only in `other`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use rkyv::{
+    ///     access_unchecked, collections::swiss_table::map::DiffItem,
+    ///     rancor::Failure, to_bytes, Archived,
+    /// };
+    ///
+    /// let before = HashMap::from([(1u32, 10u32), (2, 20), (3, 30)]);
+    /// let after = HashMap::from([(2u32, 20u32), (3, 31), (4, 40)]);
+    ///
+    /// let before_bytes = to_bytes::<_, 256, Failure>(&before).unwrap();
+    /// let after_bytes = to_bytes::<_, 256, Failure>(&after).unwrap();
+    /// let (before, after) = unsafe {
+    ///     (
+    ///         access_unchecked::<Archived<HashMap<u32, u32>>>(&before_bytes),
+    ///         access_unchecked::<Archived<HashMap<u32, u32>>>(&after_bytes),
+    ///     )
+    /// };
+    ///
+    /// let mut diff = before.diff(after).collect::<Vec<_>>();
+    /// diff.sort_by_key(|item| *item.key());
+    /// assert_eq!(diff.len(), 3);
+    /// assert!(matches!(diff[0], DiffItem::OnlyLeft(_, v) if *v == 10));
+    /// assert!(matches!(diff[1], DiffItem::Changed(_, a, b) if *a != *b));
+    /// assert!(matches!(diff[2], DiffItem::OnlyRight(k, _) if *k == 4));
+    /// ```
+    #[inline]
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Diff<'a, K, V, H>
+    where
+        K: Hash + Eq,
+        V: PartialEq,
+    {
+        Diff {
+            left: self,
+            right: other,
+            left_iter: self.iter(),
+            right_iter: other.iter(),
+        }
+    }
+
+    /// Returns an iterator over the keys which are in both hash maps.
+    ///
+    /// The entries of the smaller hash map are iterated and looked up in the
+    /// larger one, and the keys are returned from the smaller hash map.
+    #[inline]
+    pub fn intersection_keys<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> IntersectionKeys<'a, K, V, H>
+    where
+        K: Hash + Eq,
+    {
+        let (smaller, larger) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        IntersectionKeys {
+            keys: smaller.keys(),
+            larger,
+        }
+    }
+
+    /// Returns the number of keys which are in either hash map.
+    ///
+    /// The keys of the smaller hash map are iterated and looked up in the
+    /// larger one.
+    #[inline]
+    pub fn union_len(&self, other: &Self) -> usize
+    where
+        K: Hash + Eq,
+    {
+        let (smaller, larger) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        larger.len()
+            + smaller
+                .keys()
+                .filter(|key| !larger.contains_key(*key))
+                .count()
+    }
+
     /// Serializes an iterator of key-value pairs as a hash map.
     pub fn serialize_from_iter<'a, I, KU, VU, S>(
         iter: I,
@@ -237,6 +419,73 @@ impl<K, V, H: Hasher + Default> ArchivedHashMap<K, V, H> {
         .map(HashMapResolver)
     }
 
+    /// Serializes an iterator of key-value pairs as a hash map which also
+    /// stores the order of the pairs.
+    ///
+    /// The entries of the archived hash map can be iterated in the same order
+    /// as `iter` with [`iter_ordered`](Self::iter_ordered). Lookups are the
+    /// same as for hash maps serialized with
+    /// [`serialize_from_iter`](Self::serialize_from_iter), and the order
+    /// takes an additional four bytes for each entry.
+    pub fn serialize_from_iter_ordered<'a, I, KU, VU, S>(
+        iter: I,
+        load_factor: (usize, usize),
+        serializer: &mut S,
+    ) -> Result<HashMapResolver, S::Error>
+    where
+        I: Clone + ExactSizeIterator<Item = (&'a KU, &'a VU)>,
+        KU: 'a + Serialize<S, Archived = K> + Hash + Eq,
+        VU: 'a + Serialize<S, Archived = V>,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+    {
+        ArchivedHashTable::<Entry<K, V>>::serialize_from_iter_ordered(
+            iter.clone().map(|(key, value)| EntryAdapter { key, value }),
+            iter.map(|(key, _)| hash_value::<KU, H>(key)),
+            load_factor,
+            serializer,
+        )
+        .map(HashMapResolver)
+    }
+
+    /// Serializes an iterator of hashes and key-value pairs as a hash map.
+    ///
+    /// This is the same as [`serialize_from_iter`](Self::serialize_from_iter),
+    /// but uses the given hash of each key instead of hashing the key again.
+    /// Keys which cache their hash, like [`Hashed`](crate::hash::Hashed), can
+    /// be serialized without hashing them at all.
+    ///
+    /// Each hash must be the same as `hash_value::<KU, H>(key)`, which is the
+    /// hash that lookups in the archived hash map use. If a hash is different,
+    /// then lookups for its key will fail. Debug builds check the hashes of a
+    /// sample of the keys and panic if any of them are different.
+    pub fn serialize_from_hashed_iter<'a, I, KU, VU, S>(
+        iter: I,
+        load_factor: (usize, usize),
+        serializer: &mut S,
+    ) -> Result<HashMapResolver, S::Error>
+    where
+        I: Clone + ExactSizeIterator<Item = (u64, &'a KU, &'a VU)>,
+        KU: 'a + Serialize<S, Archived = K> + Hash + Eq,
+        VU: 'a + Serialize<S, Archived = V>,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+    {
+        #[cfg(debug_assertions)]
+        check_hash_sample::<_, H>(
+            iter.clone().map(|(hash, key, _)| (hash, key)),
+        );
+
+        ArchivedHashTable::<Entry<K, V>>::serialize_from_iter(
+            iter.clone()
+                .map(|(_, key, value)| EntryAdapter { key, value }),
+            iter.map(|(hash, _, _)| hash),
+            load_factor,
+            serializer,
+        )
+        .map(HashMapResolver)
+    }
+
     /// Resolves an archived hash map from a given length and parameters.
     ///
     /// # Safety
@@ -259,6 +508,29 @@ impl<K, V, H: Hasher + Default> ArchivedHashMap<K, V, H> {
     }
 }
 
+/// Checks that the precomputed hashes of a sample of keys are the same as
+/// their hashes with `H`.
+#[cfg(debug_assertions)]
+fn check_hash_sample<'a, K, H>(
+    hashes: impl ExactSizeIterator<Item = (u64, &'a K)>,
+) where
+    K: 'a + Hash + ?Sized,
+    H: Hasher + Default,
+{
+    const SAMPLES: usize = 64;
+
+    let step = usize::max(hashes.len() / SAMPLES, 1);
+    for (i, (hash, key)) in hashes.enumerate().step_by(step) {
+        assert_eq!(
+            hash,
+            hash_value::<K, H>(key),
+            "the precomputed hash of key #{} is different from the hash used \
+             for lookups",
+            i,
+        );
+    }
+}
+
 impl<K, V, H> fmt::Debug for ArchivedHashMap<K, V, H>
 where
     K: fmt::Debug,
@@ -310,6 +582,16 @@ where
     }
 }
 
+impl<'a, K, V, H> IntoIterator for &'a ArchivedHashMap<K, V, H> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, H>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// The resolver for [`ArchivedHashMap`].
 pub struct HashMapResolver(HashTableResolver);
 
@@ -319,6 +601,15 @@ pub struct Iter<'a, K, V, H> {
     _phantom: PhantomData<&'a ArchivedHashMap<K, V, H>>,
 }
 
+impl<K, V, H> Clone for Iter<'_, K, V, H> {
+    fn clone(&self) -> Self {
+        Self {
+            raw: self.raw.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
 impl<'a, K, V, H> Iterator for Iter<'a, K, V, H> {
     type Item = (&'a K, &'a V);
 
@@ -340,6 +631,44 @@ impl<K, V, H> ExactSizeIterator for Iter<'_, K, V, H> {
 
 impl<K, V, H> FusedIterator for Iter<'_, K, V, H> {}
 
+/// An iterator over the key-value pairs of an ordered [`ArchivedHashMap`] in
+/// the order that they were serialized in.
+pub struct IterOrdered<'a, K, V, H> {
+    raw: RawOrderedIter<'a, Entry<K, V>>,
+    _phantom: PhantomData<&'a ArchivedHashMap<K, V, H>>,
+}
+
+impl<'a, K, V, H> Iterator for IterOrdered<'a, K, V, H> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw.next().map(|entry| {
+            let entry = unsafe { entry.as_ref() };
+            (&entry.key, &entry.value)
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.raw.size_hint()
+    }
+}
+
+impl<K, V, H> DoubleEndedIterator for IterOrdered<'_, K, V, H> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.raw.next_back().map(|entry| {
+            let entry = unsafe { entry.as_ref() };
+            (&entry.key, &entry.value)
+        })
+    }
+}
+
+impl<K, V, H> ExactSizeIterator for IterOrdered<'_, K, V, H> {}
+
+impl<K, V, H> FusedIterator for IterOrdered<'_, K, V, H> {}
+
 /// An iterator over the mutable key-value pairs of an [`ArchivedHashMap`].
 pub struct IterMut<'a, K, V, H> {
     raw: RawIter<Entry<K, V>>,
@@ -373,6 +702,15 @@ pub struct Keys<'a, K, V, H> {
     _phantom: PhantomData<&'a ArchivedHashMap<K, V, H>>,
 }
 
+impl<K, V, H> Clone for Keys<'_, K, V, H> {
+    fn clone(&self) -> Self {
+        Self {
+            raw: self.raw.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
 impl<'a, K, V, H> Iterator for Keys<'a, K, V, H> {
     type Item = &'a K;
 
@@ -444,3 +782,330 @@ impl<K, V, H> ExactSizeIterator for ValuesMut<'_, K, V, H> {
 }
 
 impl<K, V, H> FusedIterator for ValuesMut<'_, K, V, H> {}
+
+/// A key which differs between two archived hash maps.
+///
+/// See [`ArchivedHashMap::diff`] for more information.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffItem<'a, K, V> {
+    /// The key and value are only in the left hash map.
+    OnlyLeft(&'a K, &'a V),
+    /// The key and value are only in the right hash map.
+    OnlyRight(&'a K, &'a V),
+    /// The key is in both hash maps with different values. The left value is
+    /// first.
+    Changed(&'a K, &'a V, &'a V),
+}
+
+impl<'a, K, V> DiffItem<'a, K, V> {
+    /// Returns the key which differs.
+    #[inline]
+    pub fn key(&self) -> &'a K {
+        match self {
+            Self::OnlyLeft(key, _)
+            | Self::OnlyRight(key, _)
+            | Self::Changed(key, _, _) => key,
+        }
+    }
+}
+
+impl<K, V> Clone for DiffItem<'_, K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for DiffItem<'_, K, V> {}
+
+/// An iterator over the keys which differ between two [`ArchivedHashMap`]s.
+pub struct Diff<'a, K, V, H> {
+    left: &'a ArchivedHashMap<K, V, H>,
+    right: &'a ArchivedHashMap<K, V, H>,
+    left_iter: Iter<'a, K, V, H>,
+    right_iter: Iter<'a, K, V, H>,
+}
+
+impl<'a, K, V, H> Iterator for Diff<'a, K, V, H>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    H: Hasher + Default,
+{
+    type Item = DiffItem<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, value) in self.left_iter.by_ref() {
+            match self.right.get(key) {
+                None => return Some(DiffItem::OnlyLeft(key, value)),
+                Some(right) if value != right => {
+                    return Some(DiffItem::Changed(key, value, right))
+                }
+                Some(_) => (),
+            }
+        }
+        for (key, value) in self.right_iter.by_ref() {
+            if !self.left.contains_key(key) {
+                return Some(DiffItem::OnlyRight(key, value));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let max = self.left_iter.len() + self.right_iter.len();
+        (0, Some(max))
+    }
+}
+
+impl<K, V, H> FusedIterator for Diff<'_, K, V, H>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    H: Hasher + Default,
+{
+}
+
+/// An iterator over the keys which are in both of two [`ArchivedHashMap`]s.
+pub struct IntersectionKeys<'a, K, V, H> {
+    keys: Keys<'a, K, V, H>,
+    larger: &'a ArchivedHashMap<K, V, H>,
+}
+
+impl<'a, K, V, H> Iterator for IntersectionKeys<'a, K, V, H>
+where
+    K: Hash + Eq,
+    H: Hasher + Default,
+{
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let larger = self.larger;
+        self.keys.by_ref().find(|key| larger.contains_key(*key))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.keys.len()))
+    }
+}
+
+impl<K, V, H> FusedIterator for IntersectionKeys<'_, K, V, H>
+where
+    K: Hash + Eq,
+    H: Hasher + Default,
+{
+}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use core::hash::{Hash, Hasher};
+
+    use bytecheck::Verify;
+    use rancor::{Error, Fallible};
+
+    use super::ArchivedHashMap;
+    use crate::{hash::hash_value, validation::ArchiveContext};
+
+    unsafe impl<C, K, V, H> Verify<C> for ArchivedHashMap<K, V, H>
+    where
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+        K: Hash + Eq,
+        H: Hasher + Default,
+    {
+        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            if !context.check_hashes() {
+                return Ok(());
+            }
+
+            // SAFETY: The table and its entries are checked before the hash
+            // map is verified.
+            unsafe {
+                self.table.verify_hashes(
+                    |entry| hash_value::<K, H>(&entry.key),
+                    |a, b| a.key == b.key,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+mod lazy {
+    use core::{
+        borrow::Borrow,
+        hash::{Hash, Hasher},
+        ptr::addr_of,
+    };
+
+    use bytecheck::CheckBytes;
+    use rancor::{Error, Strategy};
+
+    use super::ArchivedHashMap;
+    use crate::{
+        hash::hash_value,
+        validation::{lazy::Lazy, validators::DefaultValidator},
+        Portable,
+    };
+
+    impl<'a, K, V, H> Lazy<'a, ArchivedHashMap<K, V, H>>
+    where
+        K: 'static,
+        V: 'static,
+        H: Hasher + Default,
+    {
+        /// Returns the number of elements in the hash map.
+        #[inline]
+        pub fn len(&self) -> usize {
+            // The length is valid for any bit pattern
+            unsafe { (*self.as_ptr()).len() }
+        }
+
+        /// Returns whether the hash map is empty.
+        #[inline]
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// Returns a lazy handle to the value corresponding to the key.
+        ///
+        /// Only the keys which are compared while probing the hash map are
+        /// validated. The value is not validated.
+        pub fn value<Q, E>(&self, key: &Q) -> Result<Option<Lazy<'a, V>>, E>
+        where
+            K: Borrow<Q> + Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+            Q: Hash + Eq + ?Sized,
+            E: Error,
+        {
+            let table = self.project(|map| unsafe { addr_of!((*map).table) });
+            let entry = table.entry_with(hash_value::<Q, H>(key), |entry| {
+                let entry_key = entry
+                    .project(|entry| unsafe { addr_of!((*entry).key) })
+                    .get::<E>()?;
+                Ok(entry_key.borrow() == key)
+            })?;
+            Ok(entry.map(|entry| {
+                entry.project(|entry| unsafe { addr_of!((*entry).value) })
+            }))
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod layout {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::String, vec};
+    use core::mem::offset_of;
+
+    use crate::{
+        collections::swiss_table::{
+            table::ArchivedHashTable, ArchivedHashMap, Entry,
+        },
+        layout::{
+            hash_structure, DescribeLayout, FieldLayout, LayoutKind,
+            LayoutRegistry, StructuralHash, TypeLayout,
+        },
+    };
+
+    impl<K: DescribeLayout, V: DescribeLayout> DescribeLayout for Entry<K, V> {
+        fn layout_name() -> String {
+            format!(
+                "ArchivedHashMapEntry_{}_{}",
+                K::layout_name(),
+                V::layout_name(),
+            )
+        }
+
+        fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+            let fields = vec![
+                FieldLayout::new(
+                    "key",
+                    offset_of!(Self, key),
+                    registry.register::<K>(),
+                ),
+                FieldLayout::new(
+                    "value",
+                    offset_of!(Self, value),
+                    registry.register::<V>(),
+                ),
+            ];
+            TypeLayout::of::<Self>(
+                Self::layout_name(),
+                LayoutKind::Struct { fields },
+            )
+        }
+    }
+
+    impl<K: DescribeLayout, V: DescribeLayout, H> DescribeLayout
+        for ArchivedHashMap<K, V, H>
+    {
+        fn layout_name() -> String {
+            format!("ArchivedHashMap_{}_{}", K::layout_name(), V::layout_name())
+        }
+
+        fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+            let fields = vec![FieldLayout::new(
+                "table",
+                offset_of!(Self, table),
+                registry.register::<ArchivedHashTable<Entry<K, V>>>(),
+            )];
+            TypeLayout::of::<Self>(
+                Self::layout_name(),
+                LayoutKind::Struct { fields },
+            )
+        }
+    }
+
+    // SAFETY: The hash covers the offsets and structures of the fields.
+    unsafe impl<K: StructuralHash, V: StructuralHash> StructuralHash
+        for Entry<K, V>
+    {
+        const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+            "struct",
+            &[
+                offset_of!(Self, key) as u64,
+                K::STRUCTURAL_HASH,
+                offset_of!(Self, value) as u64,
+                V::STRUCTURAL_HASH,
+            ],
+        );
+    }
+
+    // SAFETY: The hash covers the offset and structure of the table.
+    unsafe impl<K: StructuralHash, V: StructuralHash, H> StructuralHash
+        for ArchivedHashMap<K, V, H>
+    {
+        const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+            "struct",
+            &[
+                offset_of!(Self, table) as u64,
+                ArchivedHashTable::<Entry<K, V>>::STRUCTURAL_HASH,
+            ],
+        );
+    }
+}
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use core::mem::{offset_of, size_of};
+
+    use crate::{
+        collections::swiss_table::{ArchivedHashMap, Entry},
+        reflect::{descriptor, DescriptorKind, Reflect, TypeDescriptor},
+    };
+
+    impl<K: Reflect, V: Reflect, H> Reflect for ArchivedHashMap<K, V, H> {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "HashMap",
+            DescriptorKind::HashMap {
+                key: descriptor::<K>,
+                value: descriptor::<V>,
+                entry_size: size_of::<Entry<K, V>>(),
+                value_offset: offset_of!(Entry<K, V>, value),
+            },
+        );
+    }
+}
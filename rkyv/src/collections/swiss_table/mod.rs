@@ -3,6 +3,8 @@
 pub mod index_map;
 pub mod index_set;
 pub mod map;
+#[cfg(feature = "runtime-simd")]
+pub mod scanner;
 pub mod set;
 pub mod table;
 
@@ -10,10 +12,12 @@ pub use index_map::{ArchivedIndexMap, IndexMapResolver};
 pub use index_set::{ArchivedIndexSet, IndexSetResolver};
 pub use map::{ArchivedHashMap, HashMapResolver};
 use rancor::Fallible;
+#[cfg(feature = "runtime-simd")]
+pub use scanner::Scanner;
 pub use set::{ArchivedHashSet, HashSetResolver};
-pub use table::{ArchivedHashTable, HashTableResolver};
+pub use table::{ArchivedHashTable, Density, HashTableResolver};
 
-use crate::{Archive, Portable, Serialize};
+use crate::{unpin::ArchivedNoRelPtrs, Archive, Portable, Serialize};
 
 struct EntryAdapter<'a, K, V> {
     key: &'a K,
@@ -67,3 +71,11 @@ struct Entry<K, V> {
     key: K,
     value: V,
 }
+
+// SAFETY: `Entry` only contains its key and value.
+unsafe impl<K, V> ArchivedNoRelPtrs for Entry<K, V>
+where
+    K: ArchivedNoRelPtrs,
+    V: ArchivedNoRelPtrs,
+{
+}
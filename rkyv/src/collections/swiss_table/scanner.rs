@@ -0,0 +1,227 @@
+//! Runtime selection of the control byte scanner for hash table lookups.
+//!
+//! Without the `runtime-simd` feature, archived hash tables scan their control
+//! bytes with the implementation selected at compile time. With the feature,
+//! the [`Scanner`] is selected at runtime from the instruction sets that the
+//! CPU supports. It is selected the first time a hash table is probed, or when
+//! [`Scanner::init`] is called.
+//!
+//! Every scanner reads the same `MAX_GROUP_WIDTH` control bytes for each
+//! probe, so they all find the same entries and the archived format doesn't
+//! depend on the scanner. Scans wider than the maximum group width (like a
+//! 32-byte AVX2 scan) are not offered because they would read past the end of
+//! the control bytes.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// An implementation for scanning the control bytes of a hash table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Scanner {
+    /// Scans a machine word of control bytes at a time with integer
+    /// operations. This is the scanner used without the `runtime-simd`
+    /// feature, and is supported on every target.
+    Generic = 1,
+    /// Scans sixteen control bytes at a time with SSE2 instructions.
+    Sse2 = 2,
+}
+
+// 0 if no scanner has been selected yet.
+static SELECTED: AtomicU8 = AtomicU8::new(0);
+
+impl Scanner {
+    /// All of the scanners, including the ones not supported by this CPU.
+    pub const ALL: [Scanner; 2] = [Scanner::Generic, Scanner::Sse2];
+
+    /// Returns whether this scanner is supported by this CPU.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Scanner::Generic => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Scanner::Sse2 => std::is_x86_feature_detected!("sse2"),
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            Scanner::Sse2 => false,
+        }
+    }
+
+    /// Returns the fastest scanner supported by this CPU.
+    pub fn detect() -> Self {
+        if Scanner::Sse2.is_supported() {
+            Scanner::Sse2
+        } else {
+            Scanner::Generic
+        }
+    }
+
+    /// Selects the fastest scanner supported by this CPU if no scanner has
+    /// been selected yet, and returns the selected scanner.
+    ///
+    /// Hash tables call this the first time they're probed. Calling it ahead
+    /// of time keeps feature detection out of the first lookup.
+    #[cold]
+    pub fn init() -> Self {
+        let detected = Self::detect();
+        match SELECTED.compare_exchange(
+            0,
+            detected as u8,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => detected,
+            Err(selected) => Self::from_u8(selected),
+        }
+    }
+
+    /// Returns the selected scanner, selecting one if none has been selected
+    /// yet.
+    #[inline]
+    pub fn current() -> Self {
+        match SELECTED.load(Ordering::Relaxed) {
+            0 => Self::init(),
+            selected => Self::from_u8(selected),
+        }
+    }
+
+    /// Selects this scanner for all hash table lookups.
+    ///
+    /// This is mostly useful for comparing scanners in tests and benchmarks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this scanner is not supported by this CPU.
+    pub fn select(self) {
+        assert!(
+            self.is_supported(),
+            "the {:?} scanner is not supported by this CPU",
+            self,
+        );
+        SELECTED.store(self as u8, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn from_u8(value: u8) -> Self {
+        match value {
+            2 => Scanner::Sse2,
+            _ => Scanner::Generic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scanner;
+    use crate::simd::{ControlGroup, Group, MAX_GROUP_WIDTH};
+
+    const EMPTY: u8 = 0xff;
+
+    /// Scans a probe's worth of control bytes, returning the indices which
+    /// match `byte` and whether any are empty.
+    fn scan_with<G: ControlGroup>(
+        control: &[u8; MAX_GROUP_WIDTH],
+        byte: u8,
+    ) -> (u32, bool) {
+        let mut matches = 0;
+        let mut any_empty = false;
+        for start in (0..MAX_GROUP_WIDTH).step_by(G::WIDTH) {
+            let group = unsafe { G::read(control.as_ptr().add(start)) };
+            for bit in group.match_byte(byte) {
+                // Drop false positives, which probing filters out by
+                // comparing entries
+                if control[start + bit] == byte {
+                    matches |= 1 << (start + bit);
+                }
+            }
+            any_empty |= group.any_empty();
+        }
+        (matches, any_empty)
+    }
+
+    fn scan(
+        scanner: Scanner,
+        control: &[u8; MAX_GROUP_WIDTH],
+        byte: u8,
+    ) -> (u32, bool) {
+        match scanner {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Scanner::Sse2 => {
+                scan_with::<crate::simd::sse2::Group>(control, byte)
+            }
+            _ => scan_with::<Group>(control, byte),
+        }
+    }
+
+    fn reference(control: &[u8; MAX_GROUP_WIDTH], byte: u8) -> (u32, bool) {
+        let mut matches = 0;
+        for (i, &control_byte) in control.iter().enumerate() {
+            if control_byte == byte {
+                matches |= 1 << i;
+            }
+        }
+        (matches, control.iter().any(|&b| b & 0x80 != 0))
+    }
+
+    fn patterns() -> Vec<[u8; MAX_GROUP_WIDTH]> {
+        let mut patterns = vec![[EMPTY; MAX_GROUP_WIDTH], [0; MAX_GROUP_WIDTH]];
+        for byte in [0x00, 0x01, 0x7e, 0x7f] {
+            // Full groups of one byte with a single empty byte
+            for i in 0..MAX_GROUP_WIDTH {
+                let mut pattern = [byte; MAX_GROUP_WIDTH];
+                pattern[i] = EMPTY;
+                patterns.push(pattern);
+            }
+            // Alternating bytes, and bytes which differ from `byte` in their
+            // lowest bit, which cause false positives in word-wise scans
+            let mut alternating = [0; MAX_GROUP_WIDTH];
+            let mut adjacent = [0; MAX_GROUP_WIDTH];
+            for i in 0..MAX_GROUP_WIDTH {
+                alternating[i] = if i % 2 == 0 { byte } else { EMPTY };
+                adjacent[i] = if i % 3 == 0 { byte } else { byte ^ 1 };
+            }
+            patterns.push(alternating);
+            patterns.push(adjacent);
+        }
+        // Pseudorandom patterns with few distinct bytes
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..256 {
+            let mut pattern = [0; MAX_GROUP_WIDTH];
+            for byte in pattern.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte =
+                    [0x00, 0x01, 0x02, 0x7f, 0x80, EMPTY][state as usize % 6];
+            }
+            patterns.push(pattern);
+        }
+        patterns
+    }
+
+    #[test]
+    fn scanners_match_reference() {
+        for scanner in Scanner::ALL {
+            if !scanner.is_supported() {
+                continue;
+            }
+            for control in patterns() {
+                for byte in 0..0x80 {
+                    assert_eq!(
+                        scan(scanner, &control, byte),
+                        reference(&control, byte),
+                        "{:?} scanning {:02x?} for {:02x}",
+                        scanner,
+                        control,
+                        byte,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn detect_selects_supported_scanner() {
+        assert!(Scanner::Generic.is_supported());
+        assert!(Scanner::detect().is_supported());
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(Scanner::detect(), Scanner::Sse2);
+    }
+}
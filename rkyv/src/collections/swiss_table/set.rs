@@ -1,12 +1,13 @@
 //! Archived hash set implementation using an archived SwissTable.
 
 use core::hash::Hasher;
+use core::iter::FusedIterator;
 use core::{borrow::Borrow, fmt, hash::Hash};
 
 use rancor::{Error, Fallible};
 
 use crate::collections::swiss_table::map::{
-    ArchivedHashMap, HashMapResolver, Keys,
+    ArchivedHashMap, HashMapResolver, IntersectionKeys, IterOrdered, Keys,
 };
 use crate::hash::FxHasher64;
 use crate::{
@@ -37,11 +38,44 @@ impl<K, H> ArchivedHashSet<K, H> {
         self.inner.is_empty()
     }
 
+    /// Returns the number of buckets in the hash set, including the buckets
+    /// which are empty.
+    #[inline]
+    pub fn buckets(&self) -> usize {
+        self.inner.buckets()
+    }
+
     /// Gets an iterator over the keys of the underlying hash map.
     #[inline]
     pub fn iter(&self) -> Keys<K, (), H> {
         self.inner.keys()
     }
+
+    /// Returns whether the hash set stores the order that its items were
+    /// serialized in.
+    #[inline]
+    pub fn is_ordered(&self) -> bool {
+        self.inner.is_ordered()
+    }
+
+    /// Gets an iterator over the items of the hash set in the order that they
+    /// were serialized in.
+    ///
+    /// Returns `None` if the hash set is not ordered.
+    #[inline]
+    pub fn iter_ordered(&self) -> Option<SetIterOrdered<'_, K, H>> {
+        Some(SetIterOrdered {
+            inner: self.inner.iter_ordered()?,
+        })
+    }
+
+    /// Gets the `n`th item in the order that the items were serialized in.
+    ///
+    /// Returns `None` if the hash set is not ordered or `n` is out of bounds.
+    #[inline]
+    pub fn get_index_ordered(&self, n: usize) -> Option<&K> {
+        self.inner.get_index_ordered(n).map(|(k, _)| k)
+    }
 }
 
 impl<K, H: Hasher + Default> ArchivedHashSet<K, H> {
@@ -65,6 +99,43 @@ impl<K, H: Hasher + Default> ArchivedHashSet<K, H> {
         self.inner.contains_key(k)
     }
 
+    /// Returns an iterator over the items which are in `self` but not in
+    /// `other`.
+    #[inline]
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, K, H>
+    where
+        K: Hash + Eq,
+    {
+        Difference {
+            items: self.iter(),
+            other,
+        }
+    }
+
+    /// Returns an iterator over the items which are in both hash sets.
+    ///
+    /// The items of the smaller hash set are iterated and looked up in the
+    /// larger one, and the items are returned from the smaller hash set.
+    #[inline]
+    pub fn intersection<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> IntersectionKeys<'a, K, (), H>
+    where
+        K: Hash + Eq,
+    {
+        self.inner.intersection_keys(&other.inner)
+    }
+
+    /// Returns the number of items which are in either hash set.
+    #[inline]
+    pub fn union_len(&self, other: &Self) -> usize
+    where
+        K: Hash + Eq,
+    {
+        self.inner.union_len(&other.inner)
+    }
+
     /// Resolves an archived hash set from the given length and parameters.
     ///
     /// # Safety
@@ -109,6 +180,32 @@ impl<K, H: Hasher + Default> ArchivedHashSet<K, H> {
             )?,
         ))
     }
+
+    /// Serializes an iterator of keys as a hash set which also stores the
+    /// order of the keys.
+    ///
+    /// The items of the archived hash set can be iterated in the same order as
+    /// `iter` with [`iter_ordered`](Self::iter_ordered).
+    #[inline]
+    pub fn serialize_from_iter_ordered<'a, KU, S, I>(
+        iter: I,
+        load_factor: (usize, usize),
+        serializer: &mut S,
+    ) -> Result<HashSetResolver, S::Error>
+    where
+        KU: 'a + Serialize<S, Archived = K> + Hash + Eq,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+        I: Clone + ExactSizeIterator<Item = &'a KU>,
+    {
+        Ok(HashSetResolver(
+            ArchivedHashMap::<K, (), H>::serialize_from_iter_ordered(
+                iter.map(|x| (x, &())),
+                load_factor,
+                serializer,
+            )?,
+        ))
+    }
 }
 
 impl<K: fmt::Debug, H> fmt::Debug for ArchivedHashSet<K, H> {
@@ -126,5 +223,96 @@ impl<K: Hash + Eq, H: Hasher + Default> PartialEq for ArchivedHashSet<K, H> {
 
 impl<K: Hash + Eq, H: Hasher + Default> Eq for ArchivedHashSet<K, H> {}
 
+impl<'a, K, H> IntoIterator for &'a ArchivedHashSet<K, H> {
+    type Item = &'a K;
+    type IntoIter = Keys<'a, K, (), H>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// The resolver for archived hash sets.
 pub struct HashSetResolver(HashMapResolver);
+
+/// An iterator over the items of an [`ArchivedHashSet`] which are not in
+/// another.
+pub struct Difference<'a, K, H> {
+    items: Keys<'a, K, (), H>,
+    other: &'a ArchivedHashSet<K, H>,
+}
+
+impl<'a, K, H> Iterator for Difference<'a, K, H>
+where
+    K: Hash + Eq,
+    H: Hasher + Default,
+{
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.items.by_ref().find(|item| !other.contains(*item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.items.len()))
+    }
+}
+
+impl<K, H> FusedIterator for Difference<'_, K, H>
+where
+    K: Hash + Eq,
+    H: Hasher + Default,
+{
+}
+
+/// An iterator over the items of an ordered [`ArchivedHashSet`] in the order
+/// that they were serialized in.
+pub struct SetIterOrdered<'a, K, H> {
+    inner: IterOrdered<'a, K, (), H>,
+}
+
+impl<'a, K, H> Iterator for SetIterOrdered<'a, K, H> {
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, H> DoubleEndedIterator for SetIterOrdered<'_, K, H> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, H> ExactSizeIterator for SetIterOrdered<'_, K, H> {}
+
+impl<K, H> FusedIterator for SetIterOrdered<'_, K, H> {}
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use crate::{
+        collections::swiss_table::ArchivedHashSet,
+        reflect::{descriptor, DescriptorKind, Reflect, TypeDescriptor},
+    };
+
+    impl<K: Reflect, H> Reflect for ArchivedHashSet<K, H> {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "HashSet",
+            DescriptorKind::HashSet {
+                element: descriptor::<K>,
+            },
+        );
+    }
+}
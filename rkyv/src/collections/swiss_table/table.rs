@@ -7,18 +7,55 @@
 //!   instead of the next power of two. This reduces the number of empty buckets
 //!   on the wire. Since this collection is immutable after writing, we'll never
 //!   benefit from having more buckets than we need.
-//! - Because the bucket count is not a power of two, the triangular probing
-//!   sequence simply skips any indices larger than the actual size of the
-//!   buckets array.
+//! - The bucket count is the smallest count which keeps the table at or under
+//!   its load factor. Tables can be made denser or sparser by serializing them
+//!   with a different [`Density`].
+//! - Because the bucket count is not a power of two, groups are probed linearly
+//!   and the probe position wraps around at the end of the buckets array. This
+//!   guarantees that every bucket is eventually visited.
 //! - Instead of the final control bytes always being marked EMPTY, the last
 //!   control bytes repeat the first few. This helps reduce the number of
 //!   lookups when probing at the end of the control bytes.
 //! - Because the available SIMD group width may be less than the maximum group
-//!   width, each probe reads N groups before striding where N is the maximum
+//!   width, each probe reads N groups before moving on where N is the maximum
 //!   group width divided by the SIMD group width.
+//! - Entries are serialized in bucket order, so their out-of-line data is laid
+//!   out in the same order that validation checks it. Empty tables have a
+//!   capacity of zero and no storage.
+//! - Tables serialized with `serialize_from_iter_ordered` additionally store
+//!   the bucket index of each entry in the order the entries were serialized,
+//!   immediately after the control bytes. These tables are marked by setting
+//!   the high bit of their capacity.
+//!
+//! # Invariants
+//!
+//! [`ArchivedHashTable`] can be used to build custom archived containers. Its
+//! storage upholds these invariants, which are checked during validation:
+//!
+//! - The length of a non-empty table is strictly less than its capacity and
+//!   equal to its number of full buckets, so every probe sequence eventually
+//!   reaches an empty bucket.
+//! - The capacity is computed from the length and load factor by
+//!   [`capacity_from_len`](ArchivedHashTable::capacity_from_len). The same
+//!   load factor must be passed to `serialize_from_iter` and
+//!   [`resolve_from_len`](ArchivedHashTable::resolve_from_len).
+//! - The buckets are located immediately before the control bytes in reverse
+//!   order. A bucket is full if the high bit of its control byte is clear, in
+//!   which case the control byte holds the top seven bits of its hash.
+//! - Full buckets are laid out in bucket order, and so is their out-of-line
+//!   data.
+//! - Probing for the hash of an entry finds no equal entries before it.
+//!   Archived hash maps and sets check this and the control bytes of their
+//!   entries when their validator
+//!   [checks hashes](crate::validation::ArchiveContext::check_hashes).
+//!
+//! Entries are located with [`probe`](ArchivedHashTable::probe), which scans
+//! the control bytes for candidates so that containers don't depend on the
+//! group width or probing strategy of the table.
 
 use core::{
     alloc::Layout,
+    convert::Infallible,
     fmt,
     marker::PhantomData,
     mem::size_of,
@@ -29,10 +66,14 @@ use core::{
 
 use rancor::{fail, Error, Fallible, OptionExt, Panic, ResultExt as _};
 
+#[cfg(feature = "runtime-simd")]
+use super::Scanner;
+
 use crate::{
-    primitive::ArchivedUsize,
+    primitive::{ArchivedU32, ArchivedUsize, FixedUsize},
     ser::{Allocator, Writer, WriterExt},
-    simd::{Bitmask, Group, MAX_GROUP_WIDTH},
+    simd::{Bitmask, ControlGroup, Group, MAX_GROUP_WIDTH},
+    unpin::{unpin_mut, ArchivedNoRelPtrs},
     util::ScratchVec,
     Archive as _, Portable, RawRelPtr, Serialize,
 };
@@ -53,6 +94,10 @@ pub struct ArchivedHashTable<T> {
     _phantom: PhantomData<T>,
 }
 
+/// The bit of the capacity which is set when the hash table stores the order
+/// of its entries.
+const ORDERED_FLAG: FixedUsize = 1 << (FixedUsize::BITS - 1);
+
 #[inline]
 fn h1(hash: u64) -> usize {
     hash as usize
@@ -65,7 +110,6 @@ fn h2(hash: u64) -> u8 {
 
 struct ProbeSeq {
     pos: usize,
-    stride: usize,
 }
 
 impl ProbeSeq {
@@ -74,13 +118,13 @@ impl ProbeSeq {
         self.pos += Group::WIDTH;
     }
 
+    /// Wraps the probe position back into the table.
+    ///
+    /// Capacities are not always powers of two, so groups are probed linearly
+    /// to guarantee that every bucket is eventually visited.
     #[inline]
-    fn move_next(&mut self, bucket_mask: usize) {
-        loop {
-            self.pos += self.stride;
-            self.pos &= bucket_mask;
-            self.stride += MAX_GROUP_WIDTH;
-        }
+    fn move_next(&mut self, capacity: usize) {
+        self.pos %= capacity;
     }
 }
 
@@ -88,7 +132,6 @@ impl<T> ArchivedHashTable<T> {
     fn probe_seq(hash: u64, capacity: usize) -> ProbeSeq {
         ProbeSeq {
             pos: h1(hash) % capacity,
-            stride: 0,
         }
     }
 
@@ -104,59 +147,96 @@ impl<T> ArchivedHashTable<T> {
         }
     }
 
-    #[inline]
-    fn bucket_mask(capacity: usize) -> usize {
-        capacity.checked_next_power_of_two().unwrap() - 1
+    /// Probes the hash table for an entry with the given hash.
+    ///
+    /// `cmp` is called with a pointer to each full bucket whose control byte
+    /// matches `hash`, in probing order. Returns the first bucket for which
+    /// `cmp` returns `true`, or `None` if an empty bucket is reached first.
+    /// Errors returned by `cmp` stop probing and are returned immediately.
+    ///
+    /// The bucket pointers are valid for reads for as long as the hash table
+    /// is borrowed.
+    #[inline(always)]
+    pub fn probe<C, E>(
+        &self,
+        hash: u64,
+        cmp: C,
+    ) -> Result<Option<NonNull<T>>, E>
+    where
+        C: FnMut(NonNull<T>) -> Result<bool, E>,
+    {
+        #[cfg(feature = "runtime-simd")]
+        match Scanner::current() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Scanner::Sse2 => {
+                self.probe_groups::<crate::simd::sse2::Group, C, E>(hash, cmp)
+            }
+            _ => self.probe_groups::<Group, C, E>(hash, cmp),
+        }
+        #[cfg(not(feature = "runtime-simd"))]
+        self.probe_groups::<Group, C, E>(hash, cmp)
     }
 
     #[inline(always)]
-    fn get_entry<C>(&self, hash: u64, cmp: C) -> Option<NonNull<T>>
+    fn probe_groups<G, C, E>(
+        &self,
+        hash: u64,
+        mut cmp: C,
+    ) -> Result<Option<NonNull<T>>, E>
     where
-        C: Fn(&T) -> bool,
+        G: ControlGroup,
+        C: FnMut(NonNull<T>) -> Result<bool, E>,
     {
         if self.len.to_native() == 0 {
-            return None;
+            return Ok(None);
         }
 
         let h2_hash = h2(hash);
         let mut probe_seq = Self::probe_seq(hash, self.capacity());
 
         let capacity = self.capacity();
-        let bucket_mask = Self::bucket_mask(capacity);
 
         loop {
             let mut any_empty = false;
 
-            for _ in 0..MAX_GROUP_WIDTH / Group::WIDTH {
-                let group = unsafe { Group::read(self.control(probe_seq.pos)) };
+            for _ in 0..MAX_GROUP_WIDTH / G::WIDTH {
+                let group = unsafe { G::read(self.control(probe_seq.pos)) };
 
                 for bit in group.match_byte(h2_hash) {
                     let index = (probe_seq.pos + bit) % capacity;
                     let bucket_ptr = unsafe { self.bucket(index) };
-                    let bucket = unsafe { bucket_ptr.as_ref() };
 
                     // TODO: likely
-                    if cmp(bucket) {
-                        return Some(bucket_ptr);
+                    if cmp(bucket_ptr)? {
+                        return Ok(Some(bucket_ptr));
                     }
                 }
 
                 // TODO: likely
-                any_empty = any_empty || group.match_empty().any_bit_set();
+                any_empty = any_empty || group.any_empty();
 
-                probe_seq.next_group();
+                probe_seq.pos += G::WIDTH;
             }
 
             if any_empty {
-                return None;
+                return Ok(None);
             }
 
-            loop {
-                probe_seq.move_next(bucket_mask);
-                if probe_seq.pos < self.capacity() {
-                    break;
-                }
-            }
+            probe_seq.move_next(capacity);
+        }
+    }
+
+    #[inline(always)]
+    fn get_entry<C>(&self, hash: u64, cmp: C) -> Option<NonNull<T>>
+    where
+        C: Fn(&T) -> bool,
+    {
+        let result = self.probe(hash, |bucket_ptr| {
+            Ok::<_, Infallible>(cmp(unsafe { bucket_ptr.as_ref() }))
+        });
+        match result {
+            Ok(entry) => entry,
+            Err(never) => match never {},
         }
     }
 
@@ -180,10 +260,34 @@ impl<T> ArchivedHashTable<T> {
     where
         C: Fn(&T) -> bool,
     {
-        let mut ptr = self.get_entry(hash, |e| cmp(e))?;
+        let entry = self.get_entry(hash, |e| cmp(e))?;
+        // The entry pointer was derived from a shared borrow, so move its
+        // address onto a pointer derived from the mutable borrow.
+        let entries = unsafe { self.entries_mut() };
+        let mut ptr = unsafe {
+            NonNull::new_unchecked(entries.with_addr(entry.as_ptr().addr()))
+        };
         Some(unsafe { Pin::new_unchecked(ptr.as_mut()) })
     }
 
+    /// Returns the mutable key-value pair corresponding to the supplied key
+    /// without pinning it.
+    ///
+    /// This is only available for entries which can be mutated without
+    /// pinning.
+    #[inline]
+    pub fn get_with_mut_unpinned<C>(
+        self: Pin<&mut Self>,
+        hash: u64,
+        cmp: C,
+    ) -> Option<&mut T>
+    where
+        C: Fn(&T) -> bool,
+        T: ArchivedNoRelPtrs,
+    {
+        self.get_with_mut(hash, cmp).map(unpin_mut)
+    }
+
     /// Returns whether the hash table is empty.
     #[inline]
     pub const fn is_empty(&self) -> bool {
@@ -199,7 +303,60 @@ impl<T> ArchivedHashTable<T> {
     /// Returns the total capacity of the hash table.
     #[inline]
     pub fn capacity(&self) -> usize {
-        self.cap.to_native() as usize
+        (self.cap.to_native() & !ORDERED_FLAG) as usize
+    }
+
+    /// Returns the number of buckets in the hash table.
+    ///
+    /// This is the same as the capacity, including the buckets which are
+    /// empty.
+    #[inline]
+    pub fn buckets(&self) -> usize {
+        self.capacity()
+    }
+
+    /// Returns whether the hash table stores the order that its entries were
+    /// serialized in.
+    #[inline]
+    pub fn is_ordered(&self) -> bool {
+        self.cap.to_native() & ORDERED_FLAG != 0
+    }
+
+    /// Returns the bucket indices of the entries in the order that they were
+    /// serialized in, or `None` if the hash table is not ordered.
+    fn order(&self) -> Option<&[ArchivedU32]> {
+        if !self.is_ordered() {
+            None
+        } else if self.is_empty() {
+            Some(&[])
+        } else {
+            let cap = self.capacity();
+            let control_count = Self::control_count::<Panic>(cap).always_ok();
+            let (_, control_offset, order_offset) =
+                Self::memory_layout::<Panic>(cap, control_count, self.len())
+                    .always_ok();
+            Some(unsafe {
+                slice::from_raw_parts(
+                    self.ptr
+                        .as_ptr()
+                        .cast::<u8>()
+                        .add(order_offset - control_offset)
+                        .cast(),
+                    self.len(),
+                )
+            })
+        }
+    }
+
+    /// Returns the entry pointer of the `n`th entry in the order that the
+    /// entries were serialized in.
+    ///
+    /// Returns `None` if the hash table is not ordered or `n` is out of
+    /// bounds.
+    #[inline]
+    pub fn get_index_ordered(&self, n: usize) -> Option<NonNull<T>> {
+        let index = self.order()?.get(n)?.to_native() as usize;
+        Some(unsafe { self.bucket(index) })
     }
 
     fn control_iter(&self) -> ControlIter {
@@ -228,17 +385,56 @@ impl<T> ArchivedHashTable<T> {
         }
     }
 
+    /// Returns an iterator over the entry pointers in the hash table which may
+    /// be written through.
+    pub fn raw_iter_mut(self: Pin<&mut Self>) -> RawIter<T> {
+        let mut result = self.as_ref().get_ref().raw_iter();
+        if !self.is_empty() {
+            result.entries =
+                unsafe { NonNull::new_unchecked(self.entries_mut()) };
+        }
+        result
+    }
+
+    /// Returns a pointer to the end of the buckets derived from a mutable
+    /// borrow of the hash table.
+    ///
+    /// # Safety
+    ///
+    /// The hash table must not be empty.
+    #[inline]
+    unsafe fn entries_mut(self: Pin<&mut Self>) -> *mut T {
+        unsafe { self.get_unchecked_mut().ptr.as_mut_ptr().cast() }
+    }
+
+    /// Returns an iterator over the entry pointers in the hash table in the
+    /// order that they were serialized in, or `None` if the hash table is not
+    /// ordered.
+    pub fn raw_iter_ordered(&self) -> Option<RawOrderedIter<'_, T>> {
+        Some(RawOrderedIter {
+            table: self,
+            order: self.order()?.iter(),
+        })
+    }
+
+    /// Returns the capacity of a hash table with the given length and load
+    /// factor.
+    ///
+    /// The load factor is the ratio `(numerator, denominator)` of the maximum
+    /// number of items to the capacity of the table. The capacity is the
+    /// smallest number of buckets which keeps the table at or under its load
+    /// factor, and is always greater than the length. It isn't rounded up to
+    /// a power of two or a multiple of the group width.
     #[inline]
-    fn capacity_from_len<E: Error>(
+    pub fn capacity_from_len<E: Error>(
         len: usize,
         load_factor: (usize, usize),
     ) -> Result<usize, E> {
-        Ok(usize::max(
-            len.checked_mul(load_factor.1)
-                .into_trace("overflow while adjusting capacity")?
-                / load_factor.0,
-            len + 1,
-        ))
+        let scaled = len
+            .checked_mul(load_factor.1)
+            .and_then(|n| n.checked_add(load_factor.0 - 1))
+            .into_trace("overflow while adjusting capacity")?;
+        Ok(usize::max(scaled / load_factor.0, len + 1))
     }
 
     #[inline]
@@ -248,13 +444,29 @@ impl<T> ArchivedHashTable<T> {
         )
     }
 
+    /// Returns the layout of the table memory, the offset of the control bytes
+    /// from its start, and the offset of the order indices from its start.
+    ///
+    /// `order_len` is the number of order indices stored after the control
+    /// bytes, which is zero for tables that are not ordered.
     fn memory_layout<E: Error>(
         capacity: usize,
         control_count: usize,
-    ) -> Result<(Layout, usize), E> {
+        order_len: usize,
+    ) -> Result<(Layout, usize, usize), E> {
         let buckets_layout = Layout::array::<T>(capacity).into_error()?;
         let control_layout = Layout::array::<u8>(control_count).into_error()?;
-        buckets_layout.extend(control_layout).into_error()
+        let (layout, control_offset) =
+            buckets_layout.extend(control_layout).into_error()?;
+        if order_len == 0 {
+            Ok((layout, control_offset, layout.size()))
+        } else {
+            let order_layout =
+                Layout::array::<ArchivedU32>(order_len).into_error()?;
+            let (layout, order_offset) =
+                layout.extend(order_layout).into_error()?;
+            Ok((layout, control_offset, order_offset))
+        }
     }
 
     /// Serializes an iterator of items as a hash table.
@@ -264,6 +476,57 @@ impl<T> ArchivedHashTable<T> {
         load_factor: (usize, usize),
         serializer: &mut S,
     ) -> Result<HashTableResolver, S::Error>
+    where
+        I: Clone + ExactSizeIterator,
+        I::Item: Serialize<S, Archived = T>,
+        H: ExactSizeIterator<Item = u64>,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+    {
+        Self::serialize_from_iter_impl(
+            items,
+            hashes,
+            load_factor,
+            false,
+            serializer,
+        )
+    }
+
+    /// Serializes an iterator of items as a hash table which also stores the
+    /// order of the items.
+    ///
+    /// The entries of the archived table can be iterated in the same order as
+    /// `items` with [`raw_iter_ordered`](Self::raw_iter_ordered). This stores
+    /// an additional four bytes for each item.
+    pub fn serialize_from_iter_ordered<I, H, S>(
+        items: I,
+        hashes: H,
+        load_factor: (usize, usize),
+        serializer: &mut S,
+    ) -> Result<HashTableResolver, S::Error>
+    where
+        I: Clone + ExactSizeIterator,
+        I::Item: Serialize<S, Archived = T>,
+        H: ExactSizeIterator<Item = u64>,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+    {
+        Self::serialize_from_iter_impl(
+            items,
+            hashes,
+            load_factor,
+            true,
+            serializer,
+        )
+    }
+
+    fn serialize_from_iter_impl<I, H, S>(
+        items: I,
+        hashes: H,
+        load_factor: (usize, usize),
+        ordered: bool,
+        serializer: &mut S,
+    ) -> Result<HashTableResolver, S::Error>
     where
         I: Clone + ExactSizeIterator,
         I::Item: Serialize<S, Archived = T>,
@@ -293,6 +556,26 @@ impl<T> ArchivedHashTable<T> {
         #[cfg(feature = "std")]
         impl std::error::Error for IteratorLengthMismatch {}
 
+        #[derive(Debug)]
+        struct CapacityOverflow {
+            capacity: usize,
+            max: usize,
+        }
+
+        impl fmt::Display for CapacityOverflow {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(
+                    f,
+                    "hash table capacity {} is greater than the maximum \
+                     capacity {}",
+                    self.capacity, self.max,
+                )
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl std::error::Error for CapacityOverflow {}
+
         let len = items.len();
 
         if len == 0 {
@@ -304,28 +587,28 @@ impl<T> ArchivedHashTable<T> {
                 });
             }
 
-            return Ok(HashTableResolver { pos: 0 });
-        }
-
-        // Serialize all items
-        let mut resolvers = unsafe { ScratchVec::new(serializer, len)? };
-        for i in items.clone() {
-            if resolvers.len() == len {
-                fail!(IteratorLengthMismatch {
-                    expected: len,
-                    actual: len + items.count(),
-                });
-            }
-
-            resolvers.push(i.serialize(serializer)?);
+            return Ok(HashTableResolver { pos: 0, ordered });
         }
 
         // Allocate scratch space for the hash table storage
         let capacity = Self::capacity_from_len(len, load_factor)?;
         let control_count = Self::control_count(capacity)?;
+        // The high bit of the capacity marks ordered tables, and the order
+        // indices are 32 bits
+        let mut max = (ORDERED_FLAG - 1) as u64;
+        if ordered {
+            max = max.min(u32::MAX as u64);
+        }
+        if capacity as u64 > max {
+            fail!(CapacityOverflow {
+                capacity,
+                max: max as usize,
+            });
+        }
+        let order_len = if ordered { len } else { 0 };
 
-        let (layout, control_offset) =
-            Self::memory_layout(capacity, control_count)?;
+        let (layout, control_offset, order_offset) =
+            Self::memory_layout(capacity, control_count, order_len)?;
 
         let alloc = unsafe { serializer.push_alloc(layout)?.cast::<u8>() };
 
@@ -341,16 +624,46 @@ impl<T> ArchivedHashTable<T> {
             ptr::write_bytes(ptr, 0xff, control_count);
         }
 
-        let bucket_mask = Self::bucket_mask(capacity);
+        // Initialize the padding before the order indices to zero
+        unsafe {
+            ptr::write_bytes(
+                ptr.add(control_count),
+                0,
+                order_offset - control_offset - control_count,
+            );
+        }
 
-        let pos = serializer.align(layout.align())?;
+        // Collect the hash of each item
+        let mut entries = unsafe { ScratchVec::new(serializer, len)? };
+        for (i, hash) in items.clone().zip(hashes) {
+            if entries.len() == len {
+                fail!(IteratorLengthMismatch {
+                    expected: len,
+                    actual: len + items.count(),
+                });
+            }
 
-        for ((i, resolver), hash) in items.zip(resolvers.drain(..)).zip(hashes)
-        {
+            entries.push((hash, entries.len(), i));
+        }
+
+        if entries.len() != len {
+            fail!(IteratorLengthMismatch {
+                expected: len,
+                actual: entries.len(),
+            });
+        }
+
+        // Insert items in hash order so that the layout of the table does not
+        // depend on the iteration order of the items.
+        entries.sort_unstable_by_key(|(hash, _, _)| *hash);
+
+        // Find the bucket index of each item and replace its hash with it
+        for (entry, _, _) in entries.iter_mut() {
+            let hash = *entry;
             let h2_hash = h2(hash);
             let mut probe_seq = Self::probe_seq(hash, capacity);
 
-            'insert: loop {
+            let index = 'insert: loop {
                 for _ in 0..MAX_GROUP_WIDTH / Group::WIDTH {
                     let group = unsafe { Group::read(ptr.add(probe_seq.pos)) };
 
@@ -369,45 +682,66 @@ impl<T> ArchivedHashTable<T> {
                             }
                         }
 
-                        let entry_offset =
-                            control_offset - (index + 1) * size_of::<T>();
-                        let out = unsafe {
-                            alloc.as_ptr().add(entry_offset).cast::<T>()
-                        };
-                        unsafe {
-                            i.resolve(pos + entry_offset, resolver, out);
-                        }
-
-                        break 'insert;
+                        break 'insert index;
                     }
 
                     probe_seq.next_group();
                 }
 
-                loop {
-                    probe_seq.move_next(bucket_mask);
-                    if probe_seq.pos < capacity {
-                        break;
-                    }
+                probe_seq.move_next(capacity);
+            };
+
+            *entry = index as u64;
+        }
+
+        // Serialize all items in bucket order. Validation checks buckets in
+        // order, so their out-of-line data must be laid out in the same order.
+        entries.sort_unstable_by_key(|(index, _, _)| *index);
+
+        // Record the bucket index of each item in the order it was yielded
+        if ordered {
+            let order = unsafe { alloc.as_ptr().add(order_offset) }
+                .cast::<ArchivedU32>();
+            for (index, position, _) in entries.iter() {
+                unsafe {
+                    order
+                        .add(*position)
+                        .write(ArchivedU32::from_native(*index as u32));
                 }
             }
         }
 
+        let mut resolvers = unsafe { ScratchVec::new(serializer, len)? };
+        for (_, _, i) in entries.iter() {
+            resolvers.push(i.serialize(serializer)?);
+        }
+
+        let pos = serializer.align(layout.align())?;
+
+        for ((index, _, i), resolver) in entries.iter().zip(resolvers.drain(..))
+        {
+            let entry_offset =
+                control_offset - (*index as usize + 1) * size_of::<T>();
+            let out = unsafe { alloc.as_ptr().add(entry_offset).cast::<T>() };
+            unsafe {
+                i.resolve(pos + entry_offset, resolver, out);
+            }
+        }
+
         // Write out-of-line data
         let slice =
             unsafe { slice::from_raw_parts(alloc.as_ptr(), layout.size()) };
         serializer.write(slice)?;
 
-        unsafe {
-            serializer.pop_alloc(alloc, layout)?;
-        }
-
         unsafe {
             resolvers.free(serializer)?;
+            entries.free(serializer)?;
+            serializer.pop_alloc(alloc, layout)?;
         }
 
         Ok(HashTableResolver {
             pos: pos + control_offset,
+            ordered,
         })
     }
 
@@ -415,7 +749,10 @@ impl<T> ArchivedHashTable<T> {
     ///
     /// # Safety
     ///
-    /// `out` must point to a `Self` that properly aligned and valid for writes.
+    /// - `out` must point to a `Self` that properly aligned and valid for
+    ///   writes.
+    /// - `len` and `load_factor` must be the same as those used to serialize
+    ///   the hash table which produced `resolver`.
     pub unsafe fn resolve_from_len(
         len: usize,
         load_factor: (usize, usize),
@@ -429,10 +766,15 @@ impl<T> ArchivedHashTable<T> {
         let (fp, fo) = out_field!(out.len);
         len.resolve(pos + fp, (), fo);
 
-        let (fp, fo) = out_field!(out.cap);
-        let capacity =
-            Self::capacity_from_len::<Panic>(len, load_factor).always_ok();
-        capacity.resolve(pos + fp, (), fo);
+        let (_, fo) = out_field!(out.cap);
+        // Empty hash tables are serialized without any storage
+        let capacity = if len == 0 {
+            0
+        } else {
+            Self::capacity_from_len::<Panic>(len, load_factor).always_ok()
+        };
+        let flag = if resolver.ordered { ORDERED_FLAG } else { 0 };
+        fo.write(ArchivedUsize::from_native(capacity as FixedUsize | flag));
 
         // PhantomData doesn't need to be initialized
     }
@@ -441,8 +783,44 @@ impl<T> ArchivedHashTable<T> {
 /// The resolver for [`ArchivedHashTable`].
 pub struct HashTableResolver {
     pos: usize,
+    ordered: bool,
 }
 
+/// How densely the buckets of an archived hash table are filled.
+///
+/// Denser tables are smaller, but lookups probe more buckets on average,
+/// especially for keys which aren't in the table. Each density corresponds to
+/// a [`load_factor`](Density::load_factor) which can be passed when
+/// serializing and resolving hash tables.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Density {
+    /// Fills up to fifteen out of every sixteen buckets.
+    ///
+    /// This is the densest load factor which still keeps probe sequences
+    /// short.
+    Max,
+    /// Fills up to seven out of every eight buckets.
+    ///
+    /// This is the load factor used by the hash maps and sets in this crate
+    /// unless otherwise specified.
+    #[default]
+    Balanced,
+}
+
+impl Density {
+    /// Returns the load factor for this density as a ratio
+    /// `(numerator, denominator)` of the maximum number of items to the number
+    /// of buckets.
+    #[inline]
+    pub const fn load_factor(self) -> (usize, usize) {
+        match self {
+            Self::Max => (15, 16),
+            Self::Balanced => (7, 8),
+        }
+    }
+}
+
+#[derive(Clone)]
 struct ControlIter {
     current_mask: Bitmask,
     next_group: *const u8,
@@ -481,6 +859,16 @@ pub struct RawIter<T> {
     items_left: usize,
 }
 
+impl<T> Clone for RawIter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            controls: self.controls.clone(),
+            entries: self.entries,
+            items_left: self.items_left,
+        }
+    }
+}
+
 impl<T> Iterator for RawIter<T> {
     type Item = NonNull<T>;
 
@@ -515,17 +903,52 @@ impl<T> ExactSizeIterator for RawIter<T> {
     }
 }
 
+/// An iterator over the entry pointers of an ordered [`ArchivedHashTable`] in
+/// the order that they were serialized in.
+pub struct RawOrderedIter<'a, T> {
+    table: &'a ArchivedHashTable<T>,
+    order: slice::Iter<'a, ArchivedU32>,
+}
+
+impl<T> Iterator for RawOrderedIter<'_, T> {
+    type Item = NonNull<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.order.next()?.to_native() as usize;
+        Some(unsafe { self.table.bucket(index) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.order.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for RawOrderedIter<'_, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let index = self.order.next_back()?.to_native() as usize;
+        Some(unsafe { self.table.bucket(index) })
+    }
+}
+
+impl<T> ExactSizeIterator for RawOrderedIter<'_, T> {}
+
 #[cfg(feature = "bytecheck")]
 mod verify {
-    use core::fmt;
+    use core::{any::type_name, fmt};
 
     use bytecheck::{CheckBytes, Verify};
-    use rancor::{fail, Error, Fallible};
+    use rancor::{fail, Error, Fallible, ResultExt as _};
 
-    use super::ArchivedHashTable;
+    use super::{h2, ArchivedHashTable};
     use crate::{
         simd::Group,
-        validation::{ArchiveContext, ArchiveContextExt},
+        validation::{
+            overflow::{offset_address, range_start},
+            ArchiveContext, ArchiveContextExt,
+        },
     };
 
     #[derive(Debug)]
@@ -562,18 +985,103 @@ mod verify {
     #[cfg(feature = "std")]
     impl std::error::Error for UnwrappedControlByte {}
 
-    unsafe impl<C, T> Verify<C> for ArchivedHashTable<T>
-    where
-        C: Fallible + ArchiveContext + ?Sized,
-        C::Error: Error,
-        T: CheckBytes<C>,
-    {
-        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+    #[derive(Debug)]
+    struct InvalidOrderIndex {
+        position: usize,
+        index: usize,
+    }
+
+    impl fmt::Display for InvalidOrderIndex {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "order index {} at position {} does not refer to a full bucket",
+                self.index, self.position,
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for InvalidOrderIndex {}
+
+    #[derive(Debug)]
+    struct MismatchedFullCount {
+        len: usize,
+        full: usize,
+    }
+
+    impl fmt::Display for MismatchedFullCount {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "hash table length does not match its number of full buckets \
+                 (length: {}, full buckets: {})",
+                self.len, self.full,
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for MismatchedFullCount {}
+
+    #[derive(Debug)]
+    struct MismatchedControlByte {
+        index: usize,
+        control: u8,
+        expected: u8,
+    }
+
+    impl fmt::Display for MismatchedControlByte {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "control byte {:#04x} of bucket {} does not match the hash of \
+                 its entry (expected {:#04x})",
+                self.control, self.index, self.expected,
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for MismatchedControlByte {}
+
+    #[derive(Debug)]
+    struct UnreachableEntry {
+        index: usize,
+    }
+
+    impl fmt::Display for UnreachableEntry {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "entry in bucket {} can not be found by probing for it",
+                self.index,
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for UnreachableEntry {}
+
+    impl<T> ArchivedHashTable<T> {
+        /// Checks the length, capacity, and control bytes of the hash table,
+        /// and that its buckets are located inside of the subtree range.
+        ///
+        /// Returns a pointer to the start of the table memory, or `None` if the
+        /// table is empty.
+        pub(super) fn verify_layout<C>(
+            &self,
+            context: &mut C,
+        ) -> Result<Option<*const u8>, C::Error>
+        where
+            C: Fallible + ArchiveContext + ?Sized,
+            C::Error: Error,
+        {
             let len = self.len();
             let cap = self.capacity();
 
             if len == 0 && cap == 0 {
-                return Ok(());
+                return Ok(None);
             }
 
             if self.len() >= cap {
@@ -582,18 +1090,115 @@ mod verify {
 
             // Check memory allocation
             let control_count = Self::control_count(cap)?;
-            let (layout, control_offset) =
-                Self::memory_layout(cap, control_count)?;
+            let order_len = if self.is_ordered() { len } else { 0 };
+            let (layout, control_offset, _) =
+                Self::memory_layout(cap, control_count, order_len)?;
+            let control =
+                offset_address(self.ptr.base().addr(), self.ptr.offset())
+                    .into_error()?;
+            range_start(control, control_offset).into_error()?;
             let ptr = self
                 .ptr
                 .as_ptr_wrapping()
                 .cast::<u8>()
                 .wrapping_sub(control_offset);
+            context.record_offset(self.ptr.offset_slot())?;
+            context.record_subtree_ptr(
+                self.ptr.base(),
+                ptr,
+                &layout,
+                type_name::<Self>(),
+            )?;
             context.check_subtree_ptr(ptr, &layout)?;
 
+            // Verify that wrapped bytes are set correctly
+            for i in cap..usize::min(2 * cap, control_count) {
+                let byte = unsafe { *self.control(i) };
+                let wrapped = unsafe { *self.control(i % cap) };
+                if wrapped != byte {
+                    fail!(UnwrappedControlByte { index: i })
+                }
+            }
+
+            // Verify that the length is the number of full buckets. Iterators
+            // rely on this to stop, and probes rely on there being an empty
+            // bucket to stop at.
+            let full = (0..cap)
+                .filter(|&i| unsafe { *self.control(i) } & 0x80 == 0)
+                .count();
+            if full != len {
+                fail!(MismatchedFullCount { len, full });
+            }
+
+            Ok(Some(ptr.cast_const()))
+        }
+
+        /// Checks that the control byte of each full bucket holds the top
+        /// seven bits of the hash of its entry, and that probing for each
+        /// entry with `eq` finds its own bucket first.
+        ///
+        /// Entries which are equal to an earlier entry in their probe sequence
+        /// can't be found, so this also rejects duplicate entries.
+        ///
+        /// # Safety
+        ///
+        /// The hash table and its entries must have been checked.
+        pub(crate) unsafe fn verify_hashes<E, H, Q>(
+            &self,
+            hash: H,
+            eq: Q,
+        ) -> Result<(), E>
+        where
+            E: Error,
+            H: Fn(&T) -> u64,
+            Q: Fn(&T, &T) -> bool,
+        {
+            for index in 0..self.capacity() {
+                let control = unsafe { *self.control(index) };
+                if control & 0x80 != 0 {
+                    continue;
+                }
+
+                let bucket = unsafe { self.bucket(index) };
+                let entry = unsafe { bucket.as_ref() };
+                let hash = hash(entry);
+                let expected = h2(hash);
+                if control != expected {
+                    fail!(MismatchedControlByte {
+                        index,
+                        control,
+                        expected,
+                    });
+                }
+
+                let found = self.probe(hash, |candidate| {
+                    Ok::<_, E>(eq(unsafe { candidate.as_ref() }, entry))
+                })?;
+                if found != Some(bucket) {
+                    fail!(UnreachableEntry { index });
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    unsafe impl<C, T> Verify<C> for ArchivedHashTable<T>
+    where
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+        T: CheckBytes<C>,
+    {
+        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            let ptr = match self.verify_layout(context)? {
+                Some(ptr) => ptr,
+                None => return Ok(()),
+            };
+
             let range = unsafe { context.push_prefix_subtree(ptr)? };
 
             // Check each non-empty bucket
+            let cap = self.capacity();
             let mut controls = self.control_iter();
             let mut base_index = 0;
             'outer: while base_index < cap {
@@ -604,7 +1209,10 @@ mod verify {
                     }
 
                     unsafe {
-                        T::check_bytes(self.bucket(index).as_ptr(), context)?;
+                        let bucket = self.bucket(index).as_ptr();
+                        context.start_element()?;
+                        let result = T::check_bytes(bucket, context);
+                        context.finish_element(index, bucket.cast(), result)?;
                     }
                 }
 
@@ -612,20 +1220,140 @@ mod verify {
                 base_index += Group::WIDTH;
             }
 
-            // Verify that wrapped bytes are set correctly
-            for i in cap..usize::min(2 * cap, control_count) {
-                let byte = unsafe { *self.control(i) };
-                let wrapped = unsafe { *self.control(i % cap) };
-                if wrapped != byte {
-                    fail!(UnwrappedControlByte { index: i })
+            unsafe {
+                context.pop_subtree_range(range)?;
+            }
+
+            // Check that each order index refers to a full bucket
+            if let Some(order) = self.order() {
+                for (position, index) in order.iter().enumerate() {
+                    let index = index.to_native() as usize;
+                    if index >= cap
+                        || unsafe { *self.control(index) } & 0x80 != 0
+                    {
+                        fail!(InvalidOrderIndex { position, index });
+                    }
                 }
             }
 
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+mod lazy {
+    use rancor::Error;
+
+    use super::ArchivedHashTable;
+    use crate::validation::lazy::{Lazy, Shell};
+
+    impl<'a, T: 'static> Lazy<'a, ArchivedHashTable<T>> {
+        /// Returns a lazy handle to the entry with the given hash for which
+        /// `cmp` returns `true`.
+        ///
+        /// This checks the layout of the hash table, but only validates the
+        /// entries which `cmp` validates while probing.
+        pub fn entry_with<C, E>(
+            &self,
+            hash: u64,
+            mut cmp: C,
+        ) -> Result<Option<Lazy<'a, T>>, E>
+        where
+            C: FnMut(Lazy<'a, T>) -> Result<bool, E>,
+            E: Error,
+        {
             unsafe {
-                context.pop_subtree_range(range)?;
+                self.validate::<Shell<ArchivedHashTable<T>>, E>(|context| {
+                    (*self.as_ptr()).verify_layout(context).map(|_| ())
+                })?;
+
+                // The fields of the hash table are valid for any bit pattern
+                let table = &*self.as_ptr();
+                let entry = table.probe(hash, |bucket_ptr| {
+                    cmp(self.child(bucket_ptr.as_ptr()))
+                })?;
+                Ok(entry.map(|bucket_ptr| self.child(bucket_ptr.as_ptr())))
             }
+        }
+    }
+}
 
-            Ok(())
+#[cfg(feature = "alloc")]
+mod layout {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::String, vec};
+    use core::mem::offset_of;
+
+    use super::ArchivedHashTable;
+    use crate::{
+        layout::{
+            hash_structure, DescribeLayout, FieldLayout, LayoutKind,
+            LayoutRegistry, StructuralHash, TypeLayout,
+        },
+        primitive::ArchivedUsize,
+        simd::MAX_GROUP_WIDTH,
+        RawRelPtr,
+    };
+
+    impl<T: DescribeLayout> DescribeLayout for ArchivedHashTable<T> {
+        fn layout_name() -> String {
+            format!("ArchivedHashTable_{}", T::layout_name())
         }
+
+        fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+            let fields = vec![
+                FieldLayout::new(
+                    "ptr",
+                    offset_of!(Self, ptr),
+                    registry.register::<RawRelPtr>(),
+                ),
+                FieldLayout::new(
+                    "len",
+                    offset_of!(Self, len),
+                    registry.register::<ArchivedUsize>(),
+                ),
+                FieldLayout::new(
+                    "cap",
+                    offset_of!(Self, cap),
+                    registry.register::<ArchivedUsize>(),
+                ),
+            ];
+            let description = format!(
+                "`ptr` points to `cap + {}` control bytes, and the `cap` \
+                 buckets of `{}` are located immediately before them in \
+                 reverse order. The bucket for control byte `i < cap` ends at \
+                 `ptr - i * sizeof(bucket)`, and is full if the high bit of \
+                 the control byte is clear. If the high bit of `cap` is set, \
+                 the table is ordered: the capacity is `cap` without that \
+                 bit, and `len` 32-bit bucket indices follow the control \
+                 bytes, aligned to 4 bytes from the start of the buckets, in \
+                 the order the entries were serialized.",
+                MAX_GROUP_WIDTH - 1,
+                registry.register::<T>(),
+            );
+            TypeLayout::of::<Self>(
+                Self::layout_name(),
+                LayoutKind::Struct { fields },
+            )
+            .with_description(&description)
+        }
+    }
+
+    // SAFETY: The hash covers the offsets and structures of the fields, and
+    // the structure of the buckets which `ptr` points near.
+    unsafe impl<T: StructuralHash> StructuralHash for ArchivedHashTable<T> {
+        const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+            "struct",
+            &[
+                offset_of!(Self, ptr) as u64,
+                RawRelPtr::STRUCTURAL_HASH,
+                offset_of!(Self, len) as u64,
+                ArchivedUsize::STRUCTURAL_HASH,
+                offset_of!(Self, cap) as u64,
+                ArchivedUsize::STRUCTURAL_HASH,
+                T::STRUCTURAL_HASH,
+            ],
+        );
     }
 }
@@ -2,7 +2,7 @@
 
 use rancor::Fallible;
 
-use crate::{Archive, Portable, Serialize};
+use crate::{unpin::ArchivedNoRelPtrs, Archive, Portable, Serialize};
 
 /// A simple key-value pair.
 ///
@@ -19,6 +19,14 @@ pub struct Entry<K, V> {
     pub value: V,
 }
 
+// SAFETY: `Entry` only contains its key and value.
+unsafe impl<K, V> ArchivedNoRelPtrs for Entry<K, V>
+where
+    K: ArchivedNoRelPtrs,
+    V: ArchivedNoRelPtrs,
+{
+}
+
 impl<K: Archive, V: Archive> Archive for Entry<&'_ K, &'_ V> {
     type Archived = Entry<K::Archived, V::Archived>;
     type Resolver = (K::Resolver, V::Resolver);
@@ -0,0 +1,121 @@
+//! Column-oriented archiving for vecs of structs.
+//!
+//! Structs which derive `Archive` with `#[archive(columnar)]` implement
+//! [`ArchiveColumns`]. A `Vec` of them can be archived with the
+//! [`AsColumns`](crate::with::AsColumns) wrapper, which writes each field of
+//! the struct as its own [`ArchivedVec`](crate::vec::ArchivedVec) instead of
+//! writing the structs one after another. Scanning a single field of every
+//! row then only touches the bytes of that field.
+//!
+//! For a struct named `Record`, the derive generates:
+//!
+//! - `ArchivedRecordColumns`: the archived columns. It has a `len` and a
+//!   method for each field which returns that field's column, as well as
+//!   `row`, `get_row`, and `rows` methods which return row views.
+//! - `RecordRowRef<'a>`: a view of a single row. It has a method for each field
+//!   which returns that field's value in the row.
+//! - `RecordColumnsResolver`: the resolver for the columns.
+//!
+//! Fields which aren't primitives are stored as columns of their archived
+//! type.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::fmt;
+
+use rancor::{fail, Error, Fallible};
+
+use crate::Portable;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "std")]
+    pub use std::vec::Vec;
+}
+
+/// A type which can be archived as columns.
+///
+/// This is usually implemented by deriving `Archive` with
+/// `#[archive(columnar)]`.
+pub trait ArchiveColumns: Sized {
+    /// The archived columns of a slice of this type.
+    type ArchivedColumns: Portable;
+    /// The resolver for the archived columns.
+    type ColumnsResolver;
+
+    /// Resolves the archived columns of a slice of rows.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing `rows`
+    unsafe fn resolve_columns(
+        rows: &[Self],
+        pos: usize,
+        resolver: Self::ColumnsResolver,
+        out: *mut Self::ArchivedColumns,
+    );
+}
+
+/// A type which can be serialized as columns.
+pub trait SerializeColumns<S: Fallible + ?Sized>: ArchiveColumns {
+    /// Writes the columns of a slice of rows and returns a resolver for them.
+    fn serialize_columns(
+        rows: &[Self],
+        serializer: &mut S,
+    ) -> Result<Self::ColumnsResolver, S::Error>;
+}
+
+/// A type which can be deserialized from archived columns.
+pub trait DeserializeColumns<D: Fallible + ?Sized>: ArchiveColumns {
+    /// Reassembles the rows stored in some archived columns.
+    fn deserialize_columns(
+        columns: &Self::ArchivedColumns,
+        deserializer: &mut D,
+    ) -> Result<Vec<Self>, D::Error>;
+}
+
+/// An error resulting from a column which has a different length than the
+/// number of rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnLengthMismatch {
+    /// The name of the column.
+    pub column: &'static str,
+    /// The number of rows.
+    pub expected: usize,
+    /// The length of the column.
+    pub actual: usize,
+}
+
+impl fmt::Display for ColumnLengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "column '{}' has length {} but there are {} rows",
+            self.column, self.actual, self.expected,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ColumnLengthMismatch {}
+
+/// Checks that the column named `column` has one element per row.
+#[doc(hidden)]
+#[inline]
+pub fn check_column_len<E: Error>(
+    column: &'static str,
+    expected: usize,
+    actual: usize,
+) -> Result<(), E> {
+    if actual != expected {
+        fail!(ColumnLengthMismatch {
+            column,
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
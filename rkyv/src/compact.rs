@@ -0,0 +1,675 @@
+//! Compact archived types which use 16-bit lengths and relative pointers.
+//!
+//! These types are produced by the [`Compact`](crate::with::Compact) wrapper
+//! and are useful for small archives where pointer-width offsets and lengths
+//! take up a significant amount of space.
+
+use core::{borrow::Borrow, cmp, fmt, hash, ops::Deref, pin::Pin, slice, str};
+
+use rancor::{fail, Error, Fallible};
+
+use crate::{
+    primitive::{ArchivedI16, ArchivedU16},
+    rel_ptr::RelPtr,
+    ser::Writer,
+    Archive, ArchivePointee, ArchiveUnsized, Portable, Serialize,
+    SerializeUnsized,
+};
+
+/// The maximum length of a compact vec or string.
+pub const MAX_COMPACT_LEN: usize = u16::MAX as usize;
+
+/// The maximum number of bytes that a compact pointer can point backwards.
+pub const MAX_COMPACT_DISTANCE: usize = -(i16::MIN as isize) as usize;
+
+/// An error resulting from a value which does not fit in a compact
+/// representation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompactOverflow {
+    /// The length of the value does not fit in 16 bits.
+    Length(usize),
+    /// The serialized value is too large to be reached by a 16-bit relative
+    /// pointer.
+    Distance(usize),
+}
+
+impl fmt::Display for CompactOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompactOverflow::Length(len) => write!(
+                f,
+                "length {} exceeds the maximum compact length of {}",
+                len, MAX_COMPACT_LEN,
+            ),
+            CompactOverflow::Distance(distance) => write!(
+                f,
+                "serialized value spans {} bytes, which exceeds the maximum \
+                 compact distance of {}",
+                distance, MAX_COMPACT_DISTANCE,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CompactOverflow {}
+
+/// Checks that a value written at `pos` can be referenced by a compact pointer
+/// written at the current position of `serializer`.
+///
+/// Compact pointers are always written after the values they point to, so this
+/// catches any value which is too large to point to. Values which are followed
+/// by enough other data to push their compact pointers out of range will panic
+/// when resolved, just like other relative pointers.
+fn check_distance<S>(
+    pos: usize,
+    serializer: &mut S,
+) -> Result<CompactResolver, S::Error>
+where
+    S: Fallible + Writer + ?Sized,
+    S::Error: Error,
+{
+    let distance = serializer.pos() - pos;
+    if distance > MAX_COMPACT_DISTANCE {
+        fail!(CompactOverflow::Distance(distance));
+    }
+    Ok(CompactResolver { pos })
+}
+
+/// An archived [`Vec`] with a 16-bit length and relative pointer.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedCompactVec<T> {
+    ptr: RelPtr<T, ArchivedI16>,
+    len: ArchivedU16,
+}
+
+impl<T> ArchivedCompactVec<T> {
+    /// Returns a pointer to the first element of the archived vec.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        unsafe { self.ptr.as_ptr().cast_const() }
+    }
+
+    /// Returns the number of elements in the archived vec.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.to_native() as usize
+    }
+
+    /// Returns whether the archived vec is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the elements of the archived vec as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) }
+    }
+
+    /// Gets the elements of the archived vec as a pinned mutable slice.
+    #[inline]
+    pub fn pin_mut_slice(self: Pin<&mut Self>) -> Pin<&mut [T]> {
+        unsafe {
+            self.map_unchecked_mut(|s| {
+                slice::from_raw_parts_mut(s.ptr.as_mut_ptr(), s.len())
+            })
+        }
+    }
+
+    /// Resolves an archived compact vec from a given length.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing a slice of length `len`
+    #[inline]
+    pub unsafe fn resolve_from_len(
+        len: usize,
+        pos: usize,
+        resolver: CompactResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.ptr);
+        RelPtr::emplace(pos + fp, resolver.pos, fo);
+        let (fp, fo) = out_field!(out.len);
+        u16::resolve(&(len as u16), pos + fp, (), fo);
+    }
+
+    /// Serializes an archived compact vec from a given slice.
+    ///
+    /// Returns a [`CompactOverflow`] error if the slice is too long or its
+    /// serialized elements are too large to reference with a 16-bit pointer.
+    #[inline]
+    pub fn serialize_from_slice<U, S>(
+        slice: &[U],
+        serializer: &mut S,
+    ) -> Result<CompactResolver, S::Error>
+    where
+        U: Serialize<S, Archived = T>,
+        S: Fallible + Writer + ?Sized,
+        S::Error: Error,
+        [U]: SerializeUnsized<S>,
+    {
+        if slice.len() > MAX_COMPACT_LEN {
+            fail!(CompactOverflow::Length(slice.len()));
+        }
+        let pos = slice.serialize_unsized(serializer)?;
+        check_distance(pos, serializer)
+    }
+}
+
+impl<T> AsRef<[T]> for ArchivedCompactVec<T> {
+    #[inline]
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> Borrow<[T]> for ArchivedCompactVec<T> {
+    #[inline]
+    fn borrow(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedCompactVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T> Deref for ArchivedCompactVec<T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T: Eq> Eq for ArchivedCompactVec<T> {}
+
+impl<T: hash::Hash> hash::Hash for ArchivedCompactVec<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArchivedCompactVec<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl<T: Ord> Ord for ArchivedCompactVec<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedCompactVec<U>>
+    for ArchivedCompactVec<T>
+{
+    #[inline]
+    fn eq(&self, other: &ArchivedCompactVec<U>) -> bool {
+        self.as_slice().eq(other.as_slice())
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<[U]> for ArchivedCompactVec<T> {
+    #[inline]
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice().eq(other)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedCompactVec<U>> for [T] {
+    #[inline]
+    fn eq(&self, other: &ArchivedCompactVec<U>) -> bool {
+        self.eq(other.as_slice())
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for ArchivedCompactVec<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+/// An archived [`String`] with a 16-bit length and relative pointer.
+///
+/// Unlike [`ArchivedString`](crate::string::ArchivedString), it does not have
+/// an inline representation.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedCompactString {
+    ptr: RelPtr<u8, ArchivedI16>,
+    len: ArchivedU16,
+}
+
+impl ArchivedCompactString {
+    /// Returns the length of the archived string in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.to_native() as usize
+    }
+
+    /// Returns whether the archived string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Extracts a string slice containing the entire archived string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        unsafe {
+            let bytes = slice::from_raw_parts(self.ptr.as_ptr(), self.len());
+            str::from_utf8_unchecked(bytes)
+        }
+    }
+
+    /// Resolves an archived compact string from a given `str`.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing `value`
+    #[inline]
+    pub unsafe fn resolve_from_str(
+        value: &str,
+        pos: usize,
+        resolver: CompactResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.ptr);
+        RelPtr::emplace(pos + fp, resolver.pos, fo);
+        let (fp, fo) = out_field!(out.len);
+        u16::resolve(&(value.len() as u16), pos + fp, (), fo);
+    }
+
+    /// Serializes an archived compact string from a given `str`.
+    ///
+    /// Returns a [`CompactOverflow`] error if the string is longer than
+    /// [`MAX_COMPACT_LEN`] bytes.
+    #[inline]
+    pub fn serialize_from_str<S>(
+        value: &str,
+        serializer: &mut S,
+    ) -> Result<CompactResolver, S::Error>
+    where
+        S: Fallible + Writer + ?Sized,
+        S::Error: Error,
+        str: SerializeUnsized<S>,
+    {
+        if value.len() > MAX_COMPACT_LEN {
+            fail!(CompactOverflow::Length(value.len()));
+        }
+        let pos = value.serialize_unsized(serializer)?;
+        check_distance(pos, serializer)
+    }
+}
+
+impl AsRef<str> for ArchivedCompactString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for ArchivedCompactString {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for ArchivedCompactString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ArchivedCompactString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl Deref for ArchivedCompactString {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl Eq for ArchivedCompactString {}
+
+impl hash::Hash for ArchivedCompactString {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Ord for ArchivedCompactString {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialEq for ArchivedCompactString {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<str> for ArchivedCompactString {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ArchivedCompactString {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<ArchivedCompactString> for str {
+    #[inline]
+    fn eq(&self, other: &ArchivedCompactString) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialOrd for ArchivedCompactString {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// An archived [`Box`] with a 16-bit relative pointer.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(transparent)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedCompactBox<T: ArchivePointee + ?Sized> {
+    ptr: RelPtr<T, ArchivedI16>,
+}
+
+impl<T: ArchivePointee + ?Sized> ArchivedCompactBox<T> {
+    /// Returns a reference to the value of this archived box.
+    #[inline]
+    pub fn get(&self) -> &T {
+        unsafe { &*self.ptr.as_ptr() }
+    }
+
+    /// Returns a pinned mutable reference to the value of this archived box.
+    #[inline]
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut T> {
+        unsafe { self.map_unchecked_mut(|s| &mut *s.ptr.as_mut_ptr()) }
+    }
+
+    /// Resolves an archived compact box from the given value and parameters.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing `value`
+    #[inline]
+    pub unsafe fn resolve_from_ref<U: ArchiveUnsized<Archived = T> + ?Sized>(
+        value: &U,
+        pos: usize,
+        resolver: CompactResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.ptr);
+        RelPtr::emplace_unsized(
+            pos + fp,
+            resolver.pos,
+            value.archived_metadata(),
+            fo,
+        );
+    }
+
+    /// Serializes an archived compact box from the given value and serializer.
+    ///
+    /// Returns a [`CompactOverflow`] error if the serialized value is too large
+    /// to reference with a 16-bit pointer.
+    #[inline]
+    pub fn serialize_from_ref<U, S>(
+        value: &U,
+        serializer: &mut S,
+    ) -> Result<CompactResolver, S::Error>
+    where
+        U: SerializeUnsized<S, Archived = T> + ?Sized,
+        S: Fallible + Writer + ?Sized,
+        S::Error: Error,
+    {
+        let pos = value.serialize_unsized(serializer)?;
+        check_distance(pos, serializer)
+    }
+}
+
+impl<T: ArchivePointee + ?Sized> AsRef<T> for ArchivedCompactBox<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: ArchivePointee + ?Sized> Borrow<T> for ArchivedCompactBox<T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug
+    for ArchivedCompactBox<T>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl<T: ArchivePointee + ?Sized> Deref for ArchivedCompactBox<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<T: ArchivePointee + fmt::Display + ?Sized> fmt::Display
+    for ArchivedCompactBox<T>
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl<T: ArchivePointee + Eq + ?Sized> Eq for ArchivedCompactBox<T> {}
+
+impl<T: ArchivePointee + hash::Hash + ?Sized> hash::Hash
+    for ArchivedCompactBox<T>
+{
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+impl<T: ArchivePointee + Ord + ?Sized> Ord for ArchivedCompactBox<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.get().cmp(other.get())
+    }
+}
+
+impl<T, U> PartialEq<ArchivedCompactBox<U>> for ArchivedCompactBox<T>
+where
+    T: ArchivePointee + PartialEq<U> + ?Sized,
+    U: ArchivePointee + ?Sized,
+{
+    #[inline]
+    fn eq(&self, other: &ArchivedCompactBox<U>) -> bool {
+        self.get().eq(other.get())
+    }
+}
+
+impl<T: ArchivePointee + PartialOrd + ?Sized> PartialOrd
+    for ArchivedCompactBox<T>
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.get().partial_cmp(other.get())
+    }
+}
+
+/// The resolver for compact archived types.
+pub struct CompactResolver {
+    pos: usize,
+}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        CheckBytes, Verify,
+    };
+
+    use crate::{
+        compact::{
+            ArchivedCompactBox, ArchivedCompactString, ArchivedCompactVec,
+        },
+        validation::{ArchiveContext, ArchiveContextExt, LayoutRaw},
+        ArchivePointee,
+    };
+
+    unsafe impl<T, C> Verify<C> for ArchivedCompactVec<T>
+    where
+        T: CheckBytes<C>,
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            context.record_offset(self.ptr.offset_slot())?;
+            let ptr = unsafe {
+                context.bounds_check_subtree_base_offset::<[T]>(
+                    self.ptr.base(),
+                    self.ptr.offset(),
+                    self.len(),
+                )?
+            };
+
+            let range = unsafe { context.push_prefix_subtree(ptr)? };
+            let base = ptr.cast::<T>();
+            for index in 0..self.len() {
+                unsafe {
+                    T::check_bytes(base.add(index), context)?;
+                }
+            }
+            unsafe {
+                context.pop_subtree_range(range)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    unsafe impl<C> Verify<C> for ArchivedCompactString
+    where
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            context.record_offset(self.ptr.offset_slot())?;
+            let ptr = unsafe {
+                context.bounds_check_subtree_base_offset::<str>(
+                    self.ptr.base(),
+                    self.ptr.offset(),
+                    self.len(),
+                )?
+            };
+
+            let range = unsafe { context.push_prefix_subtree(ptr)? };
+            unsafe {
+                str::check_bytes(ptr, context)?;
+            }
+            unsafe {
+                context.pop_subtree_range(range)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    unsafe impl<T, C> Verify<C> for ArchivedCompactBox<T>
+    where
+        T: ArchivePointee + CheckBytes<C> + LayoutRaw + ?Sized,
+        T::ArchivedMetadata: CheckBytes<C>,
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            context.record_offset(self.ptr.offset_slot())?;
+            let ptr = unsafe {
+                context.bounds_check_subtree_base_offset::<T>(
+                    self.ptr.base(),
+                    self.ptr.offset(),
+                    T::pointer_metadata(self.ptr.metadata()),
+                )?
+            };
+
+            let range = unsafe { context.push_prefix_subtree(ptr)? };
+            unsafe {
+                T::check_bytes(ptr, context)?;
+            }
+            unsafe {
+                context.pop_subtree_range(range)?;
+            }
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Accessing archives as structurally identical types.
+//!
+//! Types with different names and paths can still have identical archived
+//! layouts, for example when two crates generate their types from the same
+//! schema. An archive written as one of them can be accessed as the other if
+//! their archived types are [`TransmuteCompatible`].
+//!
+//! Archived types which implement [`StructuralHash`] are compatible when their
+//! structural hashes are equal. If they aren't, the error names the first part
+//! of the layout of the two types which is different. Types which can't be
+//! hashed can be marked as compatible by implementing `TransmuteCompatible`
+//! manually.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{compat::access_as_compatible, rancor::Failure, to_bytes};
+//!
+//! mod v1 {
+//!     #[derive(rkyv::Archive, rkyv::Serialize)]
+//!     #[archive(structural_hash)]
+//!     pub struct Point {
+//!         pub x: f32,
+//!         pub y: f32,
+//!     }
+//! }
+//!
+//! mod v2 {
+//!     #[derive(rkyv::Archive)]
+//!     #[archive(structural_hash, check_bytes)]
+//!     pub struct Point {
+//!         pub x: f32,
+//!         pub y: f32,
+//!     }
+//! }
+//!
+//! let bytes = to_bytes::<_, 256, Failure>(&v1::Point { x: 1.0, y: 2.0 })
+//!     .unwrap();
+//! let point =
+//!     access_as_compatible::<v1::Point, v2::Point, Failure>(&bytes).unwrap();
+//! assert_eq!(point.x, 1.0);
+//! assert_eq!(point.y, 2.0);
+//! ```
+//!
+//! [`StructuralHash`]: crate::layout::StructuralHash
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+};
+use core::{convert::Infallible, fmt};
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+
+#[cfg(feature = "bytecheck")]
+use bytecheck::CheckBytes;
+#[cfg(feature = "bytecheck")]
+use rancor::Strategy;
+
+use crate::{
+    layout::{
+        DescribeLayout, FieldLayout, LayoutKind, LayoutRegistry,
+        StructuralHash, TypeLayout,
+    },
+    Archive,
+};
+#[cfg(feature = "bytecheck")]
+use crate::{validation::validators::DefaultValidator, Portable};
+
+/// An archived type which can be accessed as `B`.
+///
+/// This is implemented for all pairs of types which implement
+/// [`StructuralHash`], and checks that their structural hashes are equal. It
+/// can be implemented manually for other types, in which case
+/// `check_compatible` returns `Ok` by default.
+///
+/// # Safety
+///
+/// If `check_compatible` returns `Ok`, every valid `Self` must also be a valid
+/// `B` with the same meaning.
+pub unsafe trait TransmuteCompatible<B> {
+    /// Checks whether `Self` can be accessed as `B`.
+    fn check_compatible() -> Result<(), StructuralMismatch> {
+        Ok(())
+    }
+}
+
+// SAFETY: Types with the same structural hash have the same layout.
+unsafe impl<A: StructuralHash, B: StructuralHash> TransmuteCompatible<B> for A {
+    fn check_compatible() -> Result<(), StructuralMismatch> {
+        check_structure::<A, B>()
+    }
+}
+
+/// Checks whether the structural hashes of `A` and `B` are equal.
+///
+/// If they aren't, the layouts of `A` and `B` are compared to find the first
+/// part of them which is different.
+pub fn check_structure<A, B>() -> Result<(), StructuralMismatch>
+where
+    A: StructuralHash,
+    B: StructuralHash,
+{
+    if A::STRUCTURAL_HASH == B::STRUCTURAL_HASH {
+        Ok(())
+    } else {
+        Err(diff_structure::<A, B>())
+    }
+}
+
+/// A difference between the layouts of two archived types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuralMismatch {
+    /// The name of the layout of the type which was written.
+    pub from: String,
+    /// The name of the layout of the type which was accessed.
+    pub to: String,
+    /// The path to the first part of the layouts which is different, starting
+    /// from the name of the layout of `from`.
+    ///
+    /// This is `None` if the structural hashes of the types are different but
+    /// no difference was found between their layouts.
+    pub path: Option<String>,
+    /// A description of the part of the layout of `from` at `path`.
+    pub expected: String,
+    /// A description of the part of the layout of `to` at `path`.
+    pub found: String,
+}
+
+impl fmt::Display for StructuralMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not structurally compatible with `{}`",
+            self.from, self.to,
+        )?;
+        match self.path {
+            Some(ref path) => write!(
+                f,
+                "; first mismatch at `{}`:\n- {}\n+ {}",
+                path, self.expected, self.found,
+            ),
+            None => write!(f, "; their structural hashes are different"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StructuralMismatch {}
+
+/// An error which may occur while accessing an archive as a compatible type.
+#[derive(Debug)]
+pub enum CompatError<E = Infallible> {
+    /// The type which was written is not compatible with the type which was
+    /// accessed.
+    Mismatch(StructuralMismatch),
+    /// The archive failed validation.
+    Invalid(E),
+}
+
+impl<E: fmt::Display> fmt::Display for CompatError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatError::Mismatch(e) => write!(f, "{}", e),
+            CompatError::Invalid(e) => {
+                write!(f, "archive failed validation: {}", e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for CompatError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompatError::Mismatch(e) => Some(e),
+            CompatError::Invalid(e) => Some(e),
+        }
+    }
+}
+
+/// Checks that an archive of `A` can be accessed as `B`, then validates and
+/// accesses the archived root object as a `B`.
+///
+/// The archive is validated as `B::Archived`, so the archived value is valid
+/// even if the structural check is wrong.
+#[cfg(feature = "bytecheck")]
+pub fn access_as_compatible<A, B, E>(
+    bytes: &[u8],
+) -> Result<&B::Archived, CompatError<E>>
+where
+    A: Archive,
+    A::Archived: TransmuteCompatible<B::Archived>,
+    B: Archive,
+    B::Archived: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: rancor::Error,
+{
+    A::Archived::check_compatible().map_err(CompatError::Mismatch)?;
+    crate::access::<B::Archived, E>(bytes).map_err(CompatError::Invalid)
+}
+
+/// Checks that an archive of `A` can be accessed as `B`, then accesses the
+/// archived root object as a `B` without validating the archive.
+///
+/// # Safety
+///
+/// The byte slice must contain a valid `A::Archived` at the root position.
+pub unsafe fn access_as_compatible_unchecked<A, B>(
+    bytes: &[u8],
+) -> Result<&B::Archived, CompatError>
+where
+    A: Archive,
+    A::Archived: TransmuteCompatible<B::Archived>,
+    B: Archive,
+{
+    A::Archived::check_compatible().map_err(CompatError::Mismatch)?;
+    // SAFETY: The bytes contain a valid `A::Archived` at the root position,
+    // which is also a valid `B::Archived`.
+    Ok(unsafe { crate::util::access_unchecked::<B::Archived>(bytes) })
+}
+
+/// Compares the layouts of `A` and `B` to find the first part of them which
+/// is different.
+fn diff_structure<A, B>() -> StructuralMismatch
+where
+    A: DescribeLayout,
+    B: DescribeLayout,
+{
+    let mut from = LayoutRegistry::new();
+    let mut to = LayoutRegistry::new();
+    let from_root = from.register::<A>();
+    let to_root = to.register::<B>();
+
+    let mut diff = Diff {
+        from: &from,
+        to: &to,
+        visited: BTreeSet::new(),
+    };
+    let (path, expected, found) =
+        match diff.compare(&from_root, &from_root, &to_root) {
+            Some((path, expected, found)) => (Some(path), expected, found),
+            None => (None, String::new(), String::new()),
+        };
+
+    StructuralMismatch {
+        from: from_root,
+        to: to_root,
+        path,
+        expected,
+        found,
+    }
+}
+
+/// The path to a difference, and descriptions of both sides of it.
+type Difference = (String, String, String);
+
+struct Diff<'a> {
+    from: &'a LayoutRegistry,
+    to: &'a LayoutRegistry,
+    visited: BTreeSet<(String, String)>,
+}
+
+impl Diff<'_> {
+    /// Returns the path to the first difference between two layouts, along
+    /// with descriptions of both sides of the difference.
+    fn compare(
+        &mut self,
+        path: &str,
+        from: &str,
+        to: &str,
+    ) -> Option<Difference> {
+        if !self.visited.insert((from.to_string(), to.to_string())) {
+            return None;
+        }
+
+        let a = self.from.get(from).unwrap();
+        let b = self.to.get(to).unwrap();
+        let mismatch = || Some((path.to_string(), describe(a), describe(b)));
+
+        // Fields are compared before sizes so that the difference is reported
+        // at the most specific path.
+        let diff = match (&a.kind, &b.kind) {
+            (
+                LayoutKind::Primitive {
+                    kind: a_kind,
+                    endianness: a_endianness,
+                },
+                LayoutKind::Primitive {
+                    kind: b_kind,
+                    endianness: b_endianness,
+                },
+            ) => {
+                if a_kind != b_kind || a_endianness != b_endianness {
+                    return mismatch();
+                }
+                None
+            }
+            (
+                LayoutKind::Array {
+                    element: a_element,
+                    len: a_len,
+                },
+                LayoutKind::Array {
+                    element: b_element,
+                    len: b_len,
+                },
+            ) => {
+                if a_len != b_len {
+                    return mismatch();
+                }
+                self.compare(&format!("{}[]", path), a_element, b_element)
+            }
+            (
+                LayoutKind::RelPtr {
+                    offset: a_offset,
+                    target: a_target,
+                },
+                LayoutKind::RelPtr {
+                    offset: b_offset,
+                    target: b_target,
+                },
+            ) => {
+                if let Some(diff) = self.compare(
+                    &format!("{}.offset", path),
+                    a_offset,
+                    b_offset,
+                ) {
+                    return Some(diff);
+                }
+                match (a_target, b_target) {
+                    (None, None) => None,
+                    (Some(a_target), Some(b_target)) => self.compare(
+                        &format!("(*{})", path),
+                        a_target,
+                        b_target,
+                    ),
+                    _ => mismatch(),
+                }
+            }
+            (
+                LayoutKind::Struct { fields: a_fields },
+                LayoutKind::Struct { fields: b_fields },
+            )
+            | (
+                LayoutKind::Union { fields: a_fields },
+                LayoutKind::Union { fields: b_fields },
+            ) => self.compare_fields(path, a, b, a_fields, b_fields),
+            (
+                LayoutKind::Enum {
+                    tag: a_tag,
+                    variants: a_variants,
+                },
+                LayoutKind::Enum {
+                    tag: b_tag,
+                    variants: b_variants,
+                },
+            ) => {
+                if let Some(diff) =
+                    self.compare(&format!("{}.tag", path), a_tag, b_tag)
+                {
+                    return Some(diff);
+                }
+                if a_variants.len() != b_variants.len() {
+                    return mismatch();
+                }
+                for (a_variant, b_variant) in a_variants.iter().zip(b_variants)
+                {
+                    if a_variant.tag != b_variant.tag {
+                        return mismatch();
+                    }
+                    let path = format!("{}::{}", path, a_variant.name);
+                    if let Some(diff) = self.compare_fields(
+                        &path,
+                        a,
+                        b,
+                        &a_variant.fields,
+                        &b_variant.fields,
+                    ) {
+                        return Some(diff);
+                    }
+                }
+                None
+            }
+            _ => return mismatch(),
+        };
+
+        if diff.is_none() && (a.size != b.size || a.align != b.align) {
+            return mismatch();
+        }
+        diff
+    }
+
+    /// Compares the fields of two layouts in order.
+    fn compare_fields(
+        &mut self,
+        path: &str,
+        a: &TypeLayout,
+        b: &TypeLayout,
+        a_fields: &[FieldLayout],
+        b_fields: &[FieldLayout],
+    ) -> Option<Difference> {
+        if a_fields.len() != b_fields.len() {
+            return Some((path.to_string(), describe(a), describe(b)));
+        }
+        for (a_field, b_field) in a_fields.iter().zip(b_fields) {
+            let path = format!("{}.{}", path, a_field.name);
+            if a_field.offset != b_field.offset {
+                return Some((
+                    path,
+                    format!("{} at offset {}", a_field.ty, a_field.offset),
+                    format!("{} at offset {}", b_field.ty, b_field.offset),
+                ));
+            }
+            if let Some(diff) = self.compare(&path, &a_field.ty, &b_field.ty) {
+                return Some(diff);
+            }
+        }
+        None
+    }
+}
+
+fn describe(layout: &TypeLayout) -> String {
+    let kind = match layout.kind {
+        LayoutKind::Primitive { .. } => "primitive",
+        LayoutKind::Array { .. } => "array",
+        LayoutKind::RelPtr { .. } => "relative pointer",
+        LayoutKind::Struct { .. } => "struct",
+        LayoutKind::Union { .. } => "union",
+        LayoutKind::Enum { .. } => "enum",
+    };
+    format!(
+        "{} {} (size {}, align {})",
+        kind, layout.name, layout.size, layout.align,
+    )
+}
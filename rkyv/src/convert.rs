@@ -0,0 +1,48 @@
+//! Archived versions of `convert` types.
+
+use crate::Portable;
+
+/// An archived [`Infallible`](::core::convert::Infallible).
+///
+/// Like `Infallible`, this type has no values. Validating an
+/// `ArchivedInfallible` always fails.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ArchivedInfallible {}
+
+// SAFETY: `ArchivedInfallible` is uninhabited, so it has no bytes which could
+// differ between platforms.
+unsafe impl Portable for ArchivedInfallible {}
+
+#[cfg(feature = "bytecheck")]
+const _: () = {
+    use core::fmt;
+
+    use bytecheck::{rancor::Fallible, CheckBytes};
+    use rancor::{fail, Error};
+
+    #[derive(Debug)]
+    struct InfallibleError;
+
+    impl fmt::Display for InfallibleError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "attempted to check an uninhabited `Infallible`")
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for InfallibleError {}
+
+    unsafe impl<C> CheckBytes<C> for ArchivedInfallible
+    where
+        C: Fallible + ?Sized,
+        C::Error: Error,
+    {
+        #[inline]
+        unsafe fn check_bytes(
+            _: *const Self,
+            _: &mut C,
+        ) -> Result<(), C::Error> {
+            fail!(InfallibleError);
+        }
+    }
+};
@@ -1,173 +1,125 @@
 //! Optimization primitives for copyable types.
 
-#[cfg(target_has_atomic = "8")]
-use core::sync::atomic::{AtomicBool, AtomicI8, AtomicU8};
-#[cfg(target_has_atomic = "16")]
-use core::sync::atomic::{AtomicI16, AtomicU16};
-#[cfg(target_has_atomic = "32")]
-use core::sync::atomic::{AtomicI32, AtomicU32};
-#[cfg(target_has_atomic = "64")]
-use core::sync::atomic::{AtomicI64, AtomicU64};
-use core::{
-    marker::{PhantomData, PhantomPinned},
-    num::{
-        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8,
-        NonZeroIsize, NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64,
-        NonZeroU8, NonZeroUsize,
-    },
-};
+use core::{fmt, marker::PhantomData};
 
-/// A type that is `Copy` and can be archived without additional processing.
-///
-/// This trait is similar to `Copy` in that it's automatically implemented for
-/// all types composed entirely of other `ArchiveCopy` types. `Copy` is
-/// necessary, but not sufficient for `ArchiveCopy` as some `Copy` type
-/// representations may vary from platform to platform.
-#[rustc_unsafe_specialization_marker]
-pub auto trait ArchiveCopy {}
-
-// (), PhantomData, PhantomPinned, bool, i8, u8, NonZeroI8, and NonZeroU8 are
-// always ArchiveCopy
-impl<T: ?Sized> ArchiveCopy for PhantomData<T> {}
-
-// Multibyte integers are not ArchiveCopy if the target does not match the
-// archive endianness
-#[cfg(any(
-    all(target_endian = "little", feature = "big_endian"),
-    all(target_endian = "big", not(feature = "big_endian")),
-))]
-const _: () = {
-    impl !ArchiveCopy for i16 {}
-    impl !ArchiveCopy for i32 {}
-    impl !ArchiveCopy for i64 {}
-    impl !ArchiveCopy for i128 {}
-    impl !ArchiveCopy for u16 {}
-    impl !ArchiveCopy for u32 {}
-    impl !ArchiveCopy for u64 {}
-    impl !ArchiveCopy for u128 {}
-    impl !ArchiveCopy for f32 {}
-    impl !ArchiveCopy for f64 {}
-    impl !ArchiveCopy for char {}
-    impl !ArchiveCopy for NonZeroI16 {}
-    impl !ArchiveCopy for NonZeroI32 {}
-    impl !ArchiveCopy for NonZeroI64 {}
-    impl !ArchiveCopy for NonZeroI128 {}
-    impl !ArchiveCopy for NonZeroU16 {}
-    impl !ArchiveCopy for NonZeroU32 {}
-    impl !ArchiveCopy for NonZeroU64 {}
-    impl !ArchiveCopy for NonZeroU128 {}
-};
-
-// Atomics are not `ArchiveCopy`
-#[cfg(target_has_atomic = "8")]
-const _: () = {
-    impl !ArchiveCopy for AtomicBool {}
-    impl !ArchiveCopy for AtomicI8 {}
-    impl !ArchiveCopy for AtomicU8 {}
-};
-#[cfg(target_has_atomic = "16")]
-const _: () = {
-    impl !ArchiveCopy for AtomicI16 {}
-    impl !ArchiveCopy for AtomicU16 {}
-};
-#[cfg(target_has_atomic = "32")]
-const _: () = {
-    impl !ArchiveCopy for AtomicI32 {}
-    impl !ArchiveCopy for AtomicU32 {}
-};
-#[cfg(target_has_atomic = "64")]
-const _: () = {
-    impl !ArchiveCopy for AtomicI64 {}
-    impl !ArchiveCopy for AtomicU64 {}
-};
+use crate::Archive;
 
-// Pointers and references are never ArchiveCopy
-impl<T: ?Sized> !ArchiveCopy for *const T {}
-impl<T: ?Sized> !ArchiveCopy for *mut T {}
-impl<T: ?Sized> !ArchiveCopy for &T {}
-impl<T: ?Sized> !ArchiveCopy for &mut T {}
-
-impl !ArchiveCopy for crate::RawRelPtr {}
-
-/// Types that are `ArchiveCopy` and have no padding.
+/// A type whose archived representation is a byte-for-byte copy of itself.
 ///
-/// These types are always safe to `memcpy` around because they will never
-/// contain uninitialized padding.
-#[rustc_unsafe_specialization_marker]
-pub unsafe trait ArchiveCopySafe: ArchiveCopy + Sized {}
-
-// (), PhantomData, PhantomPinned, bool, i8, u8, NonZeroI8, and NonZeroU8 are
-// always ArchiveCopySafe
-unsafe impl ArchiveCopySafe for () {}
-unsafe impl<T: ?Sized> ArchiveCopySafe for PhantomData<T> {}
-unsafe impl ArchiveCopySafe for PhantomPinned {}
-unsafe impl ArchiveCopySafe for bool {}
-unsafe impl ArchiveCopySafe for i8 {}
-unsafe impl ArchiveCopySafe for u8 {}
-unsafe impl ArchiveCopySafe for NonZeroI8 {}
-unsafe impl ArchiveCopySafe for NonZeroU8 {}
-
-// Multibyte integers are ArchiveCopySafe if the target matches the archived
-// endianness
-#[cfg(any(
-    all(target_endian = "little", not(feature = "big_endian")),
-    all(target_endian = "big", feature = "big_endian"),
-))]
-const _: () = {
-    unsafe impl ArchiveCopySafe for i16 {}
-    unsafe impl ArchiveCopySafe for i32 {}
-    unsafe impl ArchiveCopySafe for i64 {}
-    unsafe impl ArchiveCopySafe for i128 {}
-    unsafe impl ArchiveCopySafe for u16 {}
-    unsafe impl ArchiveCopySafe for u32 {}
-    unsafe impl ArchiveCopySafe for u64 {}
-    unsafe impl ArchiveCopySafe for u128 {}
-    unsafe impl ArchiveCopySafe for f32 {}
-    unsafe impl ArchiveCopySafe for f64 {}
-    unsafe impl ArchiveCopySafe for char {}
-    unsafe impl ArchiveCopySafe for NonZeroI16 {}
-    unsafe impl ArchiveCopySafe for NonZeroI32 {}
-    unsafe impl ArchiveCopySafe for NonZeroI64 {}
-    unsafe impl ArchiveCopySafe for NonZeroI128 {}
-    unsafe impl ArchiveCopySafe for NonZeroU16 {}
-    unsafe impl ArchiveCopySafe for NonZeroU32 {}
-    unsafe impl ArchiveCopySafe for NonZeroU64 {}
-    unsafe impl ArchiveCopySafe for NonZeroU128 {}
-};
-
-macro_rules! impl_tuple {
-    () => {};
-    (T, $($ts:ident,)*) => {
-        unsafe impl<T: ArchiveCopySafe> ArchiveCopySafe for (T, $($ts,)*) {}
-
-        impl_tuple!($($ts,)*);
-    };
+/// Types which implement `ArchiveCopyOptimize` may enable
+/// [`Archive::COPY_OPTIMIZATION`] with [`CopyOptimization::enable`]. Slices,
+/// vecs, boxed slices, and arrays of copy-optimized types are serialized with a
+/// single write of their bytes instead of element by element.
+///
+/// The fixed-size primitives implement this trait when the archive endianness
+/// matches the target endianness. Structs can implement it with
+/// `#[archive(copy_safe)]`, which requires all of their fields to implement it
+/// as well.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{copy::ArchiveCopyOptimize, Archive};
+///
+/// #[derive(Archive)]
+/// #[archive(copy_safe)]
+/// #[repr(C)]
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// fn assert_copy_optimize<T: ArchiveCopyOptimize>() {}
+/// assert_copy_optimize::<Point>();
+/// assert!(Point::COPY_OPTIMIZATION.is_enabled());
+/// ```
+///
+/// Types which contain fields that are not copy optimized can't be
+/// `copy_safe`:
+///
+/// ```compile_fail
+/// use rkyv::{copy::ArchiveCopyOptimize, Archive};
+///
+/// #[derive(Archive)]
+/// #[archive(copy_safe)]
+/// #[repr(C)]
+/// struct Named {
+///     name: String,
+/// }
+///
+/// fn assert_copy_optimize<T: ArchiveCopyOptimize>() {}
+/// assert_copy_optimize::<Named>();
+/// ```
+///
+/// # Safety
+///
+/// - `Self` and `Self::Archived` must have the same size and layout, and the
+///   archived representation of each value must have the same bytes as the
+///   value itself.
+/// - `Self` must not contain any padding bytes.
+/// - Serializing `Self` must not write any data to the serializer, and
+///   resolving it must not depend on its position or resolver.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be copied directly into an archive",
+    label = "`{Self}` does not implement `ArchiveCopyOptimize`",
+    note = "all fields of a `copy_safe` type must implement \
+            `ArchiveCopyOptimize`"
+)]
+pub unsafe trait ArchiveCopyOptimize: Archive {}
+
+/// An optimization flag that allows the bytes of a type to be copied directly
+/// to a writer instead of serializing and resolving each value.
+///
+/// See [`Archive::COPY_OPTIMIZATION`] for more information.
+pub struct CopyOptimization<T: ?Sized>(bool, PhantomData<T>);
+
+impl<T: ?Sized> CopyOptimization<T> {
+    /// Returns a `CopyOptimization` indicating that the optimization is
+    /// disabled.
+    #[inline]
+    pub const fn disable() -> Self {
+        Self(false, PhantomData)
+    }
+
+    /// Returns a `CopyOptimization` indicating that the optimization is
+    /// enabled if `value` is `true`.
+    ///
+    /// # Safety
+    ///
+    /// If `value` is `true`, `T` must meet all of the requirements of
+    /// [`ArchiveCopyOptimize`].
+    #[inline]
+    pub const unsafe fn enable_if(value: bool) -> Self {
+        Self(value, PhantomData)
+    }
+
+    /// Returns whether the optimization is enabled.
+    #[inline]
+    pub const fn is_enabled(&self) -> bool {
+        self.0
+    }
 }
 
-impl_tuple!(T, T, T, T, T, T, T, T, T, T, T,);
-
-unsafe impl<T: ArchiveCopySafe, const N: usize> ArchiveCopySafe for [T; N] {}
-
-/// Types that may be copy optimized.
-///
-/// By default, only [`ArchiveCopySafe`] types may be copy optimized. By
-/// enabling the `copy_unsafe` feature, all types that are [`ArchiveCopy`] may
-/// be copy optimized.
-#[cfg(not(feature = "copy_unsafe"))]
-#[rustc_unsafe_specialization_marker]
-pub trait ArchiveCopyOptimize: ArchiveCopySafe {}
+impl<T: ArchiveCopyOptimize + ?Sized> CopyOptimization<T> {
+    /// Returns a `CopyOptimization` indicating that the optimization is
+    /// enabled.
+    #[inline]
+    pub const fn enable() -> Self {
+        Self(true, PhantomData)
+    }
+}
 
-#[cfg(not(feature = "copy_unsafe"))]
-impl<T: ArchiveCopySafe> ArchiveCopyOptimize for T {}
+impl<T: ?Sized> Clone for CopyOptimization<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
 
-/// Types that may be copy optimized.
-///
-/// By default, only [`ArchiveCopySafe`] types may be copy optimized. By
-/// enabling the `copy_unsafe` feature, all types that are [`ArchiveCopy`] may
-/// be copy optimized.
-#[cfg(feature = "copy_unsafe")]
-#[rustc_unsafe_specialization_marker]
-pub trait ArchiveCopyOptimize: ArchiveCopy {}
+impl<T: ?Sized> Copy for CopyOptimization<T> {}
 
-#[cfg(feature = "copy_unsafe")]
-impl<T: ArchiveCopy> ArchiveCopyOptimize for T {}
+impl<T: ?Sized> fmt::Debug for CopyOptimization<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CopyOptimization").field(&self.0).finish()
+    }
+}
@@ -0,0 +1,157 @@
+//! Allocator capabilities for deserializers.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use rancor::Strategy;
+
+#[cfg(feature = "alloc")]
+use crate::de::Interning;
+use crate::de::{AllocationLimiter, ErasedPtr, Pooling, SharedContent};
+
+/// A deserializer that can provide an allocator for deserialized values.
+///
+/// Allocator-aware collections like `Vec<T, A>` use this to get the allocator
+/// to deserialize into. Deserializers can be given an allocator with
+/// [`with_allocator`].
+pub trait AllocProvider<A> {
+    /// Returns the allocator that deserialized values should be allocated in.
+    fn allocator(&self) -> A;
+}
+
+impl<T, E, A> AllocProvider<A> for Strategy<T, E>
+where
+    T: AllocProvider<A> + ?Sized,
+{
+    #[inline]
+    fn allocator(&self) -> A {
+        T::allocator(self)
+    }
+}
+
+/// A deserializer adapter which provides an allocator to deserialize into.
+///
+/// All other deserializer capabilities are forwarded to the wrapped
+/// deserializer.
+#[derive(Debug, Default)]
+pub struct WithAllocator<D, A> {
+    /// The wrapped deserializer.
+    pub deserializer: D,
+    /// The allocator provided to deserialized values.
+    pub allocator: A,
+}
+
+impl<D, A> WithAllocator<D, A> {
+    /// Creates a new deserializer which provides the given allocator.
+    #[inline]
+    pub fn new(deserializer: D, allocator: A) -> Self {
+        Self {
+            deserializer,
+            allocator,
+        }
+    }
+
+    /// Consumes the adapter and returns the deserializer and allocator.
+    #[inline]
+    pub fn into_raw_parts(self) -> (D, A) {
+        (self.deserializer, self.allocator)
+    }
+}
+
+impl<D, A: Clone> AllocProvider<A> for WithAllocator<D, A> {
+    #[inline]
+    fn allocator(&self) -> A {
+        self.allocator.clone()
+    }
+}
+
+impl<D: Pooling<E>, A, E> Pooling<E> for WithAllocator<D, A> {
+    #[inline]
+    fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    unsafe fn add_shared_ptr(
+        &mut self,
+        address: usize,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_ptr(address, ptr, drop) }
+    }
+
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        self.deserializer.is_content_keyed()
+    }
+
+    #[inline]
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_content(content)
+    }
+
+    #[inline]
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_content(content, ptr, drop) }
+    }
+}
+
+impl<D: AllocationLimiter<E>, A, E> AllocationLimiter<E>
+    for WithAllocator<D, A>
+{
+    #[inline]
+    fn check_allocation(&mut self, len: usize, bytes: usize) -> Result<(), E> {
+        self.deserializer.check_allocation(len, bytes)
+    }
+
+    #[cfg(feature = "fallible_alloc")]
+    #[inline]
+    fn allocation_failed(&mut self, bytes: usize) -> Option<E> {
+        self.deserializer.allocation_failed(bytes)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<D: Interning, A> Interning for WithAllocator<D, A> {
+    #[inline]
+    fn intern_str(&mut self, value: &str) -> Arc<str> {
+        self.deserializer.intern_str(value)
+    }
+
+    #[inline]
+    fn intern_bytes(&mut self, value: &[u8]) -> Arc<[u8]> {
+        self.deserializer.intern_bytes(value)
+    }
+}
+
+/// Wraps a deserializer so that it provides the given allocator.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::de::{with_allocator, AllocProvider, Unify};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct Arena(u32);
+///
+/// let deserializer = with_allocator(Unify::new(), Arena(1));
+/// assert_eq!(deserializer.allocator(), Arena(1));
+/// ```
+#[inline]
+pub fn with_allocator<D, A>(
+    deserializer: D,
+    allocator: A,
+) -> WithAllocator<D, A> {
+    WithAllocator::new(deserializer, allocator)
+}
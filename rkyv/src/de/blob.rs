@@ -0,0 +1,165 @@
+//! Loading external blobs while deserializing.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::{collections::HashMap, hash::BuildHasher, sync::Arc};
+
+use rancor::Strategy;
+
+#[cfg(feature = "alloc")]
+use crate::de::Interning;
+use crate::{
+    de::{AllocProvider, AllocationLimiter, ErasedPtr, Pooling, SharedContent},
+    external::BlobHandle,
+};
+
+/// A deserializer that can load blobs stored outside of the archive.
+///
+/// This is used to deserialize values archived with the
+/// [`External`](crate::with::External) wrapper, and by
+/// [`ArchivedExternal::load`](crate::external::ArchivedExternal::load).
+pub trait BlobSource {
+    /// Returns the blob with the given handle, or `None` if there is no such
+    /// blob.
+    fn load_blob(&self, handle: &BlobHandle) -> Option<&[u8]>;
+}
+
+impl<T, E> BlobSource for Strategy<T, E>
+where
+    T: BlobSource + ?Sized,
+{
+    #[inline]
+    fn load_blob(&self, handle: &BlobHandle) -> Option<&[u8]> {
+        T::load_blob(self, handle)
+    }
+}
+
+impl<T: BlobSource + ?Sized> BlobSource for &T {
+    #[inline]
+    fn load_blob(&self, handle: &BlobHandle) -> Option<&[u8]> {
+        T::load_blob(self, handle)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: BuildHasher> BlobSource for HashMap<BlobHandle, Vec<u8>, S> {
+    #[inline]
+    fn load_blob(&self, handle: &BlobHandle) -> Option<&[u8]> {
+        self.get(handle).map(Vec::as_slice)
+    }
+}
+
+/// A deserializer adapter which loads external blobs from a [`BlobSource`].
+///
+/// All other deserializer capabilities are forwarded to the wrapped
+/// deserializer.
+///
+/// See [`External`](crate::with::External) for an example.
+#[derive(Debug)]
+pub struct BlobDeserializer<D, B> {
+    /// The wrapped deserializer.
+    pub deserializer: D,
+    /// The source which blobs are loaded from.
+    pub source: B,
+}
+
+impl<D, B> BlobDeserializer<D, B> {
+    /// Creates a new deserializer which loads external blobs from the given
+    /// source.
+    #[inline]
+    pub fn new(deserializer: D, source: B) -> Self {
+        Self {
+            deserializer,
+            source,
+        }
+    }
+
+    /// Consumes the adapter and returns the wrapped deserializer.
+    #[inline]
+    pub fn into_inner(self) -> D {
+        self.deserializer
+    }
+}
+
+impl<D, B: BlobSource> BlobSource for BlobDeserializer<D, B> {
+    #[inline]
+    fn load_blob(&self, handle: &BlobHandle) -> Option<&[u8]> {
+        self.source.load_blob(handle)
+    }
+}
+
+impl<D: Pooling<E>, B, E> Pooling<E> for BlobDeserializer<D, B> {
+    #[inline]
+    fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    unsafe fn add_shared_ptr(
+        &mut self,
+        address: usize,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_ptr(address, ptr, drop) }
+    }
+
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        self.deserializer.is_content_keyed()
+    }
+
+    #[inline]
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_content(content)
+    }
+
+    #[inline]
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_content(content, ptr, drop) }
+    }
+}
+
+impl<D: AllocationLimiter<E>, B, E> AllocationLimiter<E>
+    for BlobDeserializer<D, B>
+{
+    #[inline]
+    fn check_allocation(&mut self, len: usize, bytes: usize) -> Result<(), E> {
+        self.deserializer.check_allocation(len, bytes)
+    }
+
+    #[cfg(feature = "fallible_alloc")]
+    #[inline]
+    fn allocation_failed(&mut self, bytes: usize) -> Option<E> {
+        self.deserializer.allocation_failed(bytes)
+    }
+}
+
+impl<D: AllocProvider<A>, B, A> AllocProvider<A> for BlobDeserializer<D, B> {
+    #[inline]
+    fn allocator(&self) -> A {
+        self.deserializer.allocator()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<D: Interning, B> Interning for BlobDeserializer<D, B> {
+    #[inline]
+    fn intern_str(&mut self, value: &str) -> Arc<str> {
+        self.deserializer.intern_str(value)
+    }
+
+    #[inline]
+    fn intern_bytes(&mut self, value: &[u8]) -> Arc<[u8]> {
+        self.deserializer.intern_bytes(value)
+    }
+}
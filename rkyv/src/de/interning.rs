@@ -0,0 +1,211 @@
+//! Interning capabilities for deserializers.
+
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{collections::HashSet, sync::Arc};
+
+#[cfg(not(feature = "std"))]
+use hashbrown::HashSet;
+use rancor::Strategy;
+
+use crate::{
+    de::{
+        AllocProvider, AllocationLimiter, BlobSource, ErasedPtr, Pooling,
+        SharedContent,
+    },
+    external::BlobHandle,
+};
+
+/// A deserializer that can intern deserialized strings and byte slices.
+///
+/// Interning deserializers return the same shared pointer every time they are
+/// asked to intern equal contents. This is used by the
+/// [`InternedString`](crate::with::InternedString) and
+/// [`InternedBytes`](crate::with::InternedBytes) wrappers to deduplicate
+/// values at deserialization time.
+pub trait Interning {
+    /// Returns a shared string with the same contents as `value`.
+    fn intern_str(&mut self, value: &str) -> Arc<str>;
+
+    /// Returns a shared byte slice with the same contents as `value`.
+    fn intern_bytes(&mut self, value: &[u8]) -> Arc<[u8]>;
+}
+
+impl<T, E> Interning for Strategy<T, E>
+where
+    T: Interning + ?Sized,
+{
+    #[inline]
+    fn intern_str(&mut self, value: &str) -> Arc<str> {
+        T::intern_str(self, value)
+    }
+
+    #[inline]
+    fn intern_bytes(&mut self, value: &[u8]) -> Arc<[u8]> {
+        T::intern_bytes(self, value)
+    }
+}
+
+/// A deserializer adapter which interns strings and byte slices.
+///
+/// All other deserializer capabilities are forwarded to the wrapped
+/// deserializer.
+#[derive(Default)]
+pub struct Interner<D> {
+    /// The wrapped deserializer.
+    pub deserializer: D,
+    strings: HashSet<Arc<str>>,
+    bytes: HashSet<Arc<[u8]>>,
+}
+
+impl<D> Interner<D> {
+    /// Creates a new interning deserializer.
+    #[inline]
+    pub fn new(deserializer: D) -> Self {
+        Self {
+            deserializer,
+            strings: HashSet::new(),
+            bytes: HashSet::new(),
+        }
+    }
+
+    /// Returns the number of distinct strings that have been interned.
+    #[inline]
+    pub fn interned_strings(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns the number of distinct byte slices that have been interned.
+    #[inline]
+    pub fn interned_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Consumes the adapter and returns the wrapped deserializer.
+    ///
+    /// Previously interned values remain alive as long as they are referenced.
+    #[inline]
+    pub fn into_inner(self) -> D {
+        self.deserializer
+    }
+}
+
+impl<D: fmt::Debug> fmt::Debug for Interner<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interner")
+            .field("deserializer", &self.deserializer)
+            .field("interned_strings", &self.strings.len())
+            .field("interned_bytes", &self.bytes.len())
+            .finish()
+    }
+}
+
+impl<D> Interning for Interner<D> {
+    fn intern_str(&mut self, value: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(value) {
+            return interned.clone();
+        }
+
+        let interned = Arc::<str>::from(value);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    fn intern_bytes(&mut self, value: &[u8]) -> Arc<[u8]> {
+        if let Some(interned) = self.bytes.get(value) {
+            return interned.clone();
+        }
+
+        let interned = Arc::<[u8]>::from(value);
+        self.bytes.insert(interned.clone());
+        interned
+    }
+}
+
+impl<D: Pooling<E>, E> Pooling<E> for Interner<D> {
+    #[inline]
+    fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    unsafe fn add_shared_ptr(
+        &mut self,
+        address: usize,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_ptr(address, ptr, drop) }
+    }
+
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        self.deserializer.is_content_keyed()
+    }
+
+    #[inline]
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_content(content)
+    }
+
+    #[inline]
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_content(content, ptr, drop) }
+    }
+}
+
+impl<D: AllocationLimiter<E>, E> AllocationLimiter<E> for Interner<D> {
+    #[inline]
+    fn check_allocation(&mut self, len: usize, bytes: usize) -> Result<(), E> {
+        self.deserializer.check_allocation(len, bytes)
+    }
+
+    #[cfg(feature = "fallible_alloc")]
+    #[inline]
+    fn allocation_failed(&mut self, bytes: usize) -> Option<E> {
+        self.deserializer.allocation_failed(bytes)
+    }
+}
+
+impl<D: BlobSource> BlobSource for Interner<D> {
+    #[inline]
+    fn load_blob(&self, handle: &BlobHandle) -> Option<&[u8]> {
+        self.deserializer.load_blob(handle)
+    }
+}
+
+impl<D: AllocProvider<A>, A> AllocProvider<A> for Interner<D> {
+    #[inline]
+    fn allocator(&self) -> A {
+        self.deserializer.allocator()
+    }
+}
+
+/// Wraps a deserializer so that it interns strings and byte slices.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rkyv::de::{with_interning, Interning, Unify};
+///
+/// let mut deserializer = with_interning(Unify::new());
+/// let a = deserializer.intern_str("tag");
+/// let b = deserializer.intern_str("tag");
+/// assert!(Arc::ptr_eq(&a, &b));
+/// ```
+#[inline]
+pub fn with_interning<D>(deserializer: D) -> Interner<D> {
+    Interner::new(deserializer)
+}
@@ -0,0 +1,210 @@
+//! Iterators which deserialize the items of archived collections lazily.
+
+use core::{fmt, iter::FusedIterator, marker::PhantomData};
+
+use rancor::Fallible;
+
+use crate::Deserialize;
+
+/// An iterator which deserializes each element of an archived collection as
+/// it is reached.
+///
+/// Every element is deserialized with the same deserializer, so shared
+/// pointers which were archived once are deserialized once and shared between
+/// the deserialized elements. Elements which are never reached are never
+/// deserialized.
+///
+/// This is returned by
+/// [`ArchivedVec::iter_deserialized`](crate::vec::ArchivedVec::iter_deserialized).
+pub struct DeserializeIter<'d, I, T, D: ?Sized> {
+    inner: I,
+    deserializer: &'d mut D,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<'d, I, T, D: ?Sized> DeserializeIter<'d, I, T, D> {
+    /// Returns an iterator which deserializes the items of `inner` with the
+    /// given deserializer.
+    #[inline]
+    pub fn new(inner: I, deserializer: &'d mut D) -> Self {
+        Self {
+            inner,
+            deserializer,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, 'd, I, A, T, D> DeserializeIter<'d, I, T, D>
+where
+    I: Iterator<Item = &'a A>,
+    A: Deserialize<T, D> + 'a,
+    D: Fallible + ?Sized,
+{
+    /// Deserializes the remaining elements and collects them, returning the
+    /// first error if any element fails to deserialize.
+    ///
+    /// Elements after the one which failed are not deserialized.
+    #[inline]
+    pub fn collect_deserialized<C: FromIterator<T>>(
+        self,
+    ) -> Result<C, D::Error> {
+        self.collect()
+    }
+}
+
+impl<'a, I, A, T, D> Iterator for DeserializeIter<'_, I, T, D>
+where
+    I: Iterator<Item = &'a A>,
+    A: Deserialize<T, D> + 'a,
+    D: Fallible + ?Sized,
+{
+    type Item = Result<T, D::Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.inner.next()?;
+        Some(value.deserialize(self.deserializer))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, I, A, T, D> ExactSizeIterator for DeserializeIter<'_, I, T, D>
+where
+    I: ExactSizeIterator<Item = &'a A>,
+    A: Deserialize<T, D> + 'a,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, I, A, T, D> FusedIterator for DeserializeIter<'_, I, T, D>
+where
+    I: FusedIterator<Item = &'a A>,
+    A: Deserialize<T, D> + 'a,
+    D: Fallible + ?Sized,
+{
+}
+
+impl<I: fmt::Debug, T, D: ?Sized> fmt::Debug for DeserializeIter<'_, I, T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeserializeIter")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator which deserializes each entry of an archived map as it is
+/// reached.
+///
+/// Every key and value is deserialized with the same deserializer, so shared
+/// pointers which were archived once are deserialized once and shared between
+/// the deserialized entries. Entries which are never reached are never
+/// deserialized.
+///
+/// This is returned by
+/// [`ArchivedHashMap::entries_deserialized`](crate::collections::swiss_table::ArchivedHashMap::entries_deserialized)
+/// and
+/// [`ArchivedBTreeMap::entries_deserialized`](crate::collections::btree_map::ArchivedBTreeMap::entries_deserialized).
+pub struct DeserializeEntries<'d, I, K, V, D: ?Sized> {
+    inner: I,
+    deserializer: &'d mut D,
+    _phantom: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'d, I, K, V, D: ?Sized> DeserializeEntries<'d, I, K, V, D> {
+    /// Returns an iterator which deserializes the entries of `inner` with the
+    /// given deserializer.
+    #[inline]
+    pub fn new(inner: I, deserializer: &'d mut D) -> Self {
+        Self {
+            inner,
+            deserializer,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, 'd, I, AK, AV, K, V, D> DeserializeEntries<'d, I, K, V, D>
+where
+    I: Iterator<Item = (&'a AK, &'a AV)>,
+    AK: Deserialize<K, D> + 'a,
+    AV: Deserialize<V, D> + 'a,
+    D: Fallible + ?Sized,
+{
+    /// Deserializes the remaining entries and collects them, returning the
+    /// first error if any entry fails to deserialize.
+    ///
+    /// Entries after the one which failed are not deserialized.
+    #[inline]
+    pub fn collect_deserialized<C: FromIterator<(K, V)>>(
+        self,
+    ) -> Result<C, D::Error> {
+        self.collect()
+    }
+}
+
+impl<'a, I, AK, AV, K, V, D> Iterator for DeserializeEntries<'_, I, K, V, D>
+where
+    I: Iterator<Item = (&'a AK, &'a AV)>,
+    AK: Deserialize<K, D> + 'a,
+    AV: Deserialize<V, D> + 'a,
+    D: Fallible + ?Sized,
+{
+    type Item = Result<(K, V), D::Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.inner.next()?;
+        Some(
+            key.deserialize(self.deserializer)
+                .and_then(|k| Ok((k, value.deserialize(self.deserializer)?))),
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, I, AK, AV, K, V, D> ExactSizeIterator
+    for DeserializeEntries<'_, I, K, V, D>
+where
+    I: ExactSizeIterator<Item = (&'a AK, &'a AV)>,
+    AK: Deserialize<K, D> + 'a,
+    AV: Deserialize<V, D> + 'a,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, I, AK, AV, K, V, D> FusedIterator
+    for DeserializeEntries<'_, I, K, V, D>
+where
+    I: FusedIterator<Item = (&'a AK, &'a AV)>,
+    AK: Deserialize<K, D> + 'a,
+    AV: Deserialize<V, D> + 'a,
+    D: Fallible + ?Sized,
+{
+}
+
+impl<I: fmt::Debug, K, V, D: ?Sized> fmt::Debug
+    for DeserializeEntries<'_, I, K, V, D>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeserializeEntries")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
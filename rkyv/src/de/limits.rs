@@ -0,0 +1,441 @@
+//! Allocation limits for deserializers.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::sync::Arc;
+use core::{fmt, mem::size_of};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use rancor::{fail, Error, Strategy};
+
+#[cfg(feature = "alloc")]
+use crate::de::{Interning, Pool, SharedPool, Unify};
+#[cfg(feature = "alloc")]
+use crate::util::AllocationFailed;
+use crate::{
+    de::{
+        AllocProvider, BlobSource, Duplicate, ErasedPtr, Pooling, SharedContent,
+    },
+    external::BlobHandle,
+};
+
+/// A deserializer that can limit the memory allocated for deserialized values.
+///
+/// Deserializing validated archives is still dangerous when the archive comes
+/// from an untrusted source. A small archive may contain a collection with a
+/// very large length, for example a `Vec` of elements whose archived type is
+/// zero-sized. The collection types check every allocation with their
+/// deserializer before allocating, so that deserializers like [`Limited`] can
+/// refuse it.
+///
+/// Deserializers which don't limit allocations accept every allocation. The
+/// deserializers in this crate implement it without limits, and other
+/// deserializers can do the same with an empty implementation.
+///
+/// With the `fallible_alloc` feature, `Vec`, `String`, and `HashMap` also
+/// report to their deserializer when they fail to reserve memory for their
+/// elements. Deserializers which don't handle allocation failures let the
+/// collection abort like any other allocation, while deserializers like
+/// [`Limited`] return an error instead.
+pub trait AllocationLimiter<E = <Self as rancor::Fallible>::Error> {
+    /// Checks an allocation for a collection of `len` elements which occupies
+    /// `bytes` bytes, and records it if it is allowed.
+    ///
+    /// By default, every allocation is allowed.
+    #[inline]
+    fn check_allocation(&mut self, len: usize, bytes: usize) -> Result<(), E> {
+        let _ = (len, bytes);
+        Ok(())
+    }
+
+    /// Returns the error for a collection which failed to reserve `bytes`
+    /// bytes of memory, or `None` if the collection should reserve the memory
+    /// infallibly instead.
+    ///
+    /// By default, allocation failures are not handled.
+    #[cfg(feature = "fallible_alloc")]
+    #[inline]
+    fn allocation_failed(&mut self, bytes: usize) -> Option<E> {
+        let _ = bytes;
+        None
+    }
+
+    /// Checks an allocation for a collection of `len` elements of type `T`,
+    /// and records it if it is allowed.
+    #[inline]
+    fn check_array<T>(&mut self, len: usize) -> Result<(), E> {
+        self.check_allocation(len, size_of::<T>().saturating_mul(len))
+    }
+}
+
+impl<T, E> AllocationLimiter<E> for Strategy<T, E>
+where
+    T: AllocationLimiter<E> + ?Sized,
+{
+    #[inline]
+    fn check_allocation(&mut self, len: usize, bytes: usize) -> Result<(), E> {
+        T::check_allocation(self, len, bytes)
+    }
+
+    #[cfg(feature = "fallible_alloc")]
+    #[inline]
+    fn allocation_failed(&mut self, bytes: usize) -> Option<E> {
+        T::allocation_failed(self, bytes)
+    }
+}
+
+// The deserializers which don't limit allocations.
+
+impl<E> AllocationLimiter<E> for () {}
+
+impl<E> AllocationLimiter<E> for Duplicate {}
+
+#[cfg(feature = "alloc")]
+impl<E> AllocationLimiter<E> for Unify {}
+
+#[cfg(feature = "alloc")]
+impl<E> AllocationLimiter<E> for SharedPool {}
+
+#[cfg(feature = "alloc")]
+impl<E> AllocationLimiter<E> for Pool<'_> {}
+
+/// A collection which can reserve memory before its elements are
+/// deserialized.
+#[cfg(feature = "alloc")]
+pub(crate) trait Reserve {
+    fn reserve(&mut self, additional: usize);
+
+    #[cfg_attr(not(feature = "fallible_alloc"), allow(dead_code))]
+    fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), AllocationFailed>;
+}
+
+/// Reserves memory for `additional` elements in a collection.
+///
+/// With the `fallible_alloc` feature, the deserializer may return an error if
+/// the allocation fails. Otherwise, the memory is reserved infallibly.
+#[cfg(feature = "alloc")]
+#[inline]
+pub(crate) fn reserve<C, D, E>(
+    collection: &mut C,
+    additional: usize,
+    deserializer: &mut D,
+) -> Result<(), E>
+where
+    C: Reserve + ?Sized,
+    D: AllocationLimiter<E> + ?Sized,
+{
+    #[cfg(feature = "fallible_alloc")]
+    if let Err(failure) = collection.try_reserve(additional) {
+        if let Some(error) = deserializer.allocation_failed(failure.requested())
+        {
+            return Err(error);
+        }
+        collection.reserve(additional);
+    }
+    #[cfg(not(feature = "fallible_alloc"))]
+    {
+        let _ = deserializer;
+        collection.reserve(additional);
+    }
+    Ok(())
+}
+
+/// The limits enforced by a [`Limited`] deserializer.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::de::Limits;
+///
+/// let limits = Limits {
+///     max_len: 1024,
+///     ..Limits::default()
+/// };
+/// assert_eq!(limits.max_bytes, usize::MAX);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of bytes that may be allocated in total.
+    pub max_bytes: usize,
+    /// The maximum length of a single collection.
+    pub max_len: usize,
+    /// The maximum number of collection elements that may be allocated in
+    /// total.
+    pub max_elements: usize,
+}
+
+impl Limits {
+    /// Limits which allow every allocation.
+    pub const UNLIMITED: Self = Self {
+        max_bytes: usize::MAX,
+        max_len: usize::MAX,
+        max_elements: usize::MAX,
+    };
+}
+
+impl Default for Limits {
+    #[inline]
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// A limit enforced by a [`Limited`] deserializer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// The maximum number of bytes that may be allocated in total.
+    Bytes,
+    /// The maximum length of a single collection.
+    Len,
+    /// The maximum number of collection elements that may be allocated in
+    /// total.
+    Elements,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Bytes => write!(f, "total bytes"),
+            Limit::Len => write!(f, "collection length"),
+            Limit::Elements => write!(f, "total elements"),
+        }
+    }
+}
+
+/// An error resulting from an allocation which would exceed a limit of a
+/// [`Limited`] deserializer.
+#[derive(Debug)]
+pub struct LimitExceeded {
+    limit: Limit,
+    max: usize,
+    requested: usize,
+}
+
+impl LimitExceeded {
+    /// Returns the limit that would have been exceeded.
+    #[inline]
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    /// Returns the maximum value allowed by the limit.
+    #[inline]
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns the value that was requested.
+    ///
+    /// For the total limits, this is the total including the refused
+    /// allocation.
+    #[inline]
+    pub fn requested(&self) -> usize {
+        self.requested
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deserialization {} limit exceeded: requested {} but the limit is \
+             {}",
+            self.limit, self.requested, self.max,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LimitExceeded {}
+
+/// A deserializer adapter which limits the memory allocated for deserialized
+/// values.
+///
+/// Allocations which would exceed the [`Limits`] fail with a
+/// [`LimitExceeded`] error before any memory is allocated. With the
+/// `fallible_alloc` feature, collections which fail to reserve memory return
+/// an [`AllocationFailed`](crate::util::AllocationFailed) error instead of
+/// aborting. All other deserializer capabilities are forwarded to the wrapped
+/// deserializer.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     de::{Limited, Limits, Unify},
+///     deserialize,
+///     rancor::Failure,
+///     to_bytes, Archived,
+/// };
+///
+/// let value = vec![vec![1u32, 2, 3], vec![4, 5]];
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = rkyv::access::<Archived<Vec<Vec<u32>>>, Failure>(&bytes)
+///     .unwrap();
+///
+/// let limits = Limits {
+///     max_elements: 4,
+///     ..Limits::default()
+/// };
+/// let mut deserializer = Limited::new(Unify::new(), limits);
+/// let result =
+///     deserialize::<Vec<Vec<u32>>, _, Failure>(archived, &mut deserializer);
+/// assert!(result.is_err());
+///
+/// let mut deserializer = Limited::new(Unify::new(), Limits::default());
+/// let deserialized =
+///     deserialize::<Vec<Vec<u32>>, _, Failure>(archived, &mut deserializer)
+///         .unwrap();
+/// assert_eq!(deserialized, value);
+/// assert_eq!(deserializer.allocated_elements(), 7);
+/// ```
+#[derive(Debug, Default)]
+pub struct Limited<D> {
+    /// The wrapped deserializer.
+    pub deserializer: D,
+    limits: Limits,
+    bytes: usize,
+    elements: usize,
+}
+
+impl<D> Limited<D> {
+    /// Creates a new deserializer which enforces the given limits.
+    #[inline]
+    pub fn new(deserializer: D, limits: Limits) -> Self {
+        Self {
+            deserializer,
+            limits,
+            bytes: 0,
+            elements: 0,
+        }
+    }
+
+    /// Returns the limits enforced by the deserializer.
+    #[inline]
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Returns the number of bytes that have been allocated.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns the number of collection elements that have been allocated.
+    #[inline]
+    pub fn allocated_elements(&self) -> usize {
+        self.elements
+    }
+
+    /// Consumes the adapter and returns the wrapped deserializer.
+    #[inline]
+    pub fn into_inner(self) -> D {
+        self.deserializer
+    }
+}
+
+impl<D, E> AllocationLimiter<E> for Limited<D>
+where
+    D: AllocationLimiter<E>,
+    E: Error,
+{
+    fn check_allocation(&mut self, len: usize, bytes: usize) -> Result<(), E> {
+        let total_bytes = self.bytes.saturating_add(bytes);
+        let total_elements = self.elements.saturating_add(len);
+        let (limit, max, requested) = if len > self.limits.max_len {
+            (Limit::Len, self.limits.max_len, len)
+        } else if total_elements > self.limits.max_elements {
+            (Limit::Elements, self.limits.max_elements, total_elements)
+        } else if total_bytes > self.limits.max_bytes {
+            (Limit::Bytes, self.limits.max_bytes, total_bytes)
+        } else {
+            self.deserializer.check_allocation(len, bytes)?;
+            self.bytes = total_bytes;
+            self.elements = total_elements;
+            return Ok(());
+        };
+
+        fail!(LimitExceeded {
+            limit,
+            max,
+            requested,
+        });
+    }
+
+    #[cfg(feature = "fallible_alloc")]
+    #[inline]
+    fn allocation_failed(&mut self, bytes: usize) -> Option<E> {
+        Some(E::new(AllocationFailed::new(bytes)))
+    }
+}
+
+impl<D: Pooling<E>, E> Pooling<E> for Limited<D> {
+    #[inline]
+    fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    unsafe fn add_shared_ptr(
+        &mut self,
+        address: usize,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_ptr(address, ptr, drop) }
+    }
+
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        self.deserializer.is_content_keyed()
+    }
+
+    #[inline]
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        self.deserializer.get_shared_content(content)
+    }
+
+    #[inline]
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { self.deserializer.add_shared_content(content, ptr, drop) }
+    }
+}
+
+impl<D: BlobSource> BlobSource for Limited<D> {
+    #[inline]
+    fn load_blob(&self, handle: &BlobHandle) -> Option<&[u8]> {
+        self.deserializer.load_blob(handle)
+    }
+}
+
+impl<D: AllocProvider<A>, A> AllocProvider<A> for Limited<D> {
+    #[inline]
+    fn allocator(&self) -> A {
+        self.deserializer.allocator()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<D: Interning> Interning for Limited<D> {
+    #[inline]
+    fn intern_str(&mut self, value: &str) -> Arc<str> {
+        self.deserializer.intern_str(value)
+    }
+
+    #[inline]
+    fn intern_bytes(&mut self, value: &[u8]) -> Arc<[u8]> {
+        self.deserializer.intern_bytes(value)
+    }
+}
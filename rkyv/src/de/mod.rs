@@ -1,6 +1,18 @@
 //! Deserialization traits, deserializers, and adapters.
 
+pub mod allocator;
+pub mod blob;
+#[cfg(feature = "alloc")]
+pub mod interning;
+pub mod lazy;
+pub mod limits;
+pub mod path;
 pub mod pooling;
 
+#[cfg(feature = "alloc")]
 #[doc(inline)]
-pub use self::pooling::*;
+pub use self::interning::*;
+#[doc(inline)]
+pub use self::{
+    allocator::*, blob::*, lazy::*, limits::*, path::*, pooling::*,
+};
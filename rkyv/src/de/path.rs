@@ -0,0 +1,183 @@
+//! Locating deserialization errors within the deserialized value.
+//!
+//! Derived `Deserialize` impls trace each field they deserialize with a
+//! [`PathSegment`], and collections trace the index of each element they
+//! deserialize. The segments are only created when deserialization fails, so
+//! they cost nothing on success.
+//!
+//! Any error type that implements [`Trace`] receives the segments as regular
+//! traces. [`PathError`] collects them into a path instead, and displays
+//! errors as `failed to deserialize root.items[1042].timestamp: <cause>`.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(feature = "alloc")]
+use core::any::Any;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use rancor::StdError;
+#[cfg(feature = "alloc")]
+use rancor::{Error, Trace};
+
+/// One step of the path to a value being deserialized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A field of a struct or enum variant. Tuple fields are named by their
+    /// index.
+    Field(&'static str),
+    /// The element at an index of a sequence.
+    Index(usize),
+    /// The key of the entry at an index of a map, in iteration order.
+    Key(usize),
+    /// The value of the entry at an index of a map, in iteration order.
+    Value(usize),
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Field(name) => write!(f, ".{}", name),
+            PathSegment::Index(index) => write!(f, "[{}]", index),
+            PathSegment::Key(index) => write!(f, "[key #{}]", index),
+            PathSegment::Value(index) => write!(f, "[#{}]", index),
+        }
+    }
+}
+
+/// An error which records the path to the value that failed to deserialize.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     de::{Limited, Limits, PathError},
+///     deserialize,
+///     rancor::Failure,
+///     to_bytes, Archive, Archived, Deserialize, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize, Deserialize, Debug)]
+/// #[archive(check_bytes)]
+/// struct Event {
+///     tags: Vec<u32>,
+/// }
+///
+/// #[derive(Archive, Serialize, Deserialize, Debug)]
+/// #[archive(check_bytes)]
+/// struct Log {
+///     items: Vec<Event>,
+/// }
+///
+/// let log = Log {
+///     items: vec![
+///         Event { tags: vec![1, 2] },
+///         Event {
+///             tags: vec![3, 4, 5, 6, 7],
+///         },
+///     ],
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&log).unwrap();
+/// let archived = rkyv::access::<Archived<Log>, Failure>(&bytes).unwrap();
+///
+/// let limits = Limits {
+///     max_len: 4,
+///     ..Limits::default()
+/// };
+/// let error = deserialize::<Log, _, PathError>(
+///     archived,
+///     &mut Limited::new((), limits),
+/// )
+/// .unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "failed to deserialize root.items[1].tags: deserialization collection \
+///      length limit exceeded: requested 5 but the limit is 4",
+/// );
+/// ```
+#[cfg(feature = "alloc")]
+pub struct PathError {
+    source: Box<dyn StdError + Send + Sync + 'static>,
+    // Segments are added as the error propagates, so the innermost one is
+    // first.
+    path: Vec<PathSegment>,
+    traces: Vec<String>,
+}
+
+#[cfg(feature = "alloc")]
+impl PathError {
+    /// Returns the path to the value that failed to deserialize, starting
+    /// from the root.
+    pub fn path(&self) -> impl Iterator<Item = &PathSegment> + '_ {
+        self.path.iter().rev()
+    }
+
+    /// Returns the error that caused deserialization to fail.
+    pub fn cause(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        &*self.source
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Debug for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathError")
+            .field("source", &self.source)
+            .field("path", &self.path().collect::<Vec<_>>())
+            .field("traces", &self.traces)
+            .finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to deserialize root")?;
+        for segment in self.path() {
+            write!(f, "{}", segment)?;
+        }
+        write!(f, ": {}", self.source)?;
+        for trace in self.traces.iter() {
+            write!(f, "\ntrace: {}", trace)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Trace for PathError {
+    fn trace<R>(mut self, trace: R) -> Self
+    where
+        R: fmt::Debug + fmt::Display + Send + Sync + 'static,
+    {
+        match (&trace as &dyn Any).downcast_ref::<PathSegment>() {
+            Some(segment) => self.path.push(*segment),
+            None => self.traces.push(trace.to_string()),
+        }
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Error for PathError {
+    fn new<T: StdError + Send + Sync + 'static>(source: T) -> Self {
+        Self {
+            source: Box::new(source),
+            path: Vec::new(),
+            traces: Vec::new(),
+        }
+    }
+}
@@ -1,6 +1,13 @@
 //! Adapters wrap deserializers and add support for deserializer traits.
 
-use core::{fmt, mem::size_of};
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    any::TypeId,
+    fmt,
+    hash::{BuildHasher, BuildHasherDefault},
+    mem::size_of,
+};
 #[cfg(feature = "std")]
 use std::collections::hash_map;
 
@@ -8,7 +15,8 @@ use std::collections::hash_map;
 use hashbrown::hash_map;
 use rancor::{fail, Error};
 
-use super::{ErasedPtr, Pooling};
+use super::{ErasedPtr, Pooling, SharedContent};
+use crate::hash::FxHasher64;
 
 #[derive(Debug)]
 struct DuplicateSharedPointer {
@@ -43,11 +51,223 @@ impl Drop for SharedPointer {
     }
 }
 
+struct ContentEntry {
+    type_id: TypeId,
+    bytes: Box<[u8]>,
+    pointer: SharedPointer,
+}
+
+fn hash_content(bytes: &[u8]) -> u64 {
+    BuildHasherDefault::<FxHasher64>::default().hash_one(bytes)
+}
+
+/// A map of deserialized shared pointers.
+///
+/// Deserializing with a `SharedPool` unifies deserializations of the same
+/// shared pointer. The pool keeps every shared pointer it unifies alive until
+/// it is cleared or dropped, and it can be reused across deserialize calls with
+/// [`Pool::Pooled`].
+///
+/// By default, shared pointers are keyed by the address of their archived
+/// value. A pool must not be reused with another archive while it holds
+/// address-keyed shared pointers from an archive which has been moved or
+/// dropped. Use [`clear_addresses`](SharedPool::clear_addresses) between
+/// archives to forget them.
+///
+/// Content-keyed pools key shared pointers to copy-optimized values and
+/// strings by their archived bytes instead, so equal values in different
+/// archives deserialize to the same shared pointer. Values are only unified
+/// when their bytes are equal, so hash collisions never alias different
+/// values. Shared pointers to other values are still keyed by address.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rkyv::{
+///     access_unchecked,
+///     de::{Pool, SharedPool},
+///     deserialize,
+///     rancor::Failure,
+///     to_bytes, Archive, Deserialize, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Page {
+///     number: u32,
+///     footer: Arc<str>,
+/// }
+///
+/// let mut pool = SharedPool::content_keyed();
+/// let mut pages = Vec::new();
+/// for number in 0..2 {
+///     let page = Page {
+///         number,
+///         footer: Arc::from("Copyright 2024"),
+///     };
+///     let bytes = to_bytes::<_, 256, Failure>(&page).unwrap();
+///     let archived = unsafe { access_unchecked::<ArchivedPage>(&bytes) };
+///     let page = deserialize::<Page, _, Failure>(
+///         archived,
+///         &mut Pool::Pooled(&mut pool),
+///     )
+///     .unwrap();
+///     pool.clear_addresses();
+///     pages.push(page);
+/// }
+///
+/// assert!(Arc::ptr_eq(&pages[0].footer, &pages[1].footer));
+/// ```
+pub struct SharedPool {
+    shared_pointers: hash_map::HashMap<usize, SharedPointer>,
+    content_hash: Option<fn(&[u8]) -> u64>,
+    contents: hash_map::HashMap<u64, Vec<ContentEntry>>,
+}
+
+impl Default for SharedPool {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedPool {
+    /// Creates a new pool which keys shared pointers by address.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates a new pool which keys shared pointers by address with initial
+    /// capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            shared_pointers: hash_map::HashMap::with_capacity(capacity),
+            content_hash: None,
+            contents: hash_map::HashMap::new(),
+        }
+    }
+
+    /// Creates a new pool which keys shared pointers to copy-optimized values
+    /// and strings by their contents.
+    #[inline]
+    pub fn content_keyed() -> Self {
+        Self::content_keyed_with_hash(hash_content)
+    }
+
+    /// Creates a new pool which keys shared pointers to copy-optimized values
+    /// and strings by their contents, hashing them with the given function.
+    #[inline]
+    pub fn content_keyed_with_hash(hash: fn(&[u8]) -> u64) -> Self {
+        Self {
+            content_hash: Some(hash),
+            ..Self::new()
+        }
+    }
+
+    /// Returns the number of shared pointers in the pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shared_pointers.len()
+            + self.contents.values().map(Vec::len).sum::<usize>()
+    }
+
+    /// Returns whether the pool has no shared pointers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets the shared pointers keyed by address.
+    ///
+    /// Shared pointers keyed by their contents are kept.
+    #[inline]
+    pub fn clear_addresses(&mut self) {
+        self.shared_pointers.clear();
+    }
+
+    /// Forgets all of the shared pointers in the pool.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.shared_pointers.clear();
+        self.contents.clear();
+    }
+}
+
+impl fmt::Debug for SharedPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedPool")
+            .field("shared_pointers", &self.shared_pointers)
+            .field("content_keyed", &self.content_hash.is_some())
+            .field("contents", &(self.len() - self.shared_pointers.len()))
+            .finish()
+    }
+}
+
+impl<E: Error> Pooling<E> for SharedPool {
+    fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr> {
+        self.shared_pointers.get(&address).map(|p| p.ptr)
+    }
+
+    unsafe fn add_shared_ptr(
+        &mut self,
+        address: usize,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        match self.shared_pointers.entry(address) {
+            hash_map::Entry::Occupied(_) => {
+                fail!(DuplicateSharedPointer { address });
+            }
+            hash_map::Entry::Vacant(e) => {
+                e.insert(SharedPointer { ptr, drop });
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        self.content_hash.is_some()
+    }
+
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        let hash = (self.content_hash?)(content.bytes);
+        self.contents
+            .get(&hash)?
+            .iter()
+            .find(|e| {
+                e.type_id == content.type_id && *e.bytes == *content.bytes
+            })
+            .map(|e| e.pointer.ptr)
+    }
+
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        let hash = self.content_hash.unwrap_or(hash_content)(content.bytes);
+        self.contents.entry(hash).or_default().push(ContentEntry {
+            type_id: content.type_id,
+            bytes: content.bytes.into(),
+            pointer: SharedPointer { ptr, drop },
+        });
+        Ok(())
+    }
+}
+
 /// A shared pointer strategy that unifies deserializations of the same shared
 /// pointer.
 #[derive(Default)]
 pub struct Unify {
-    shared_pointers: hash_map::HashMap<usize, SharedPointer>,
+    pool: SharedPool,
 }
 
 impl Unify {
@@ -61,36 +281,118 @@ impl Unify {
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            shared_pointers: hash_map::HashMap::with_capacity(capacity),
+            pool: SharedPool::with_capacity(capacity),
         }
     }
 }
 
 impl fmt::Debug for Unify {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_map().entries(self.shared_pointers.iter()).finish()
+        f.debug_map()
+            .entries(self.pool.shared_pointers.iter())
+            .finish()
     }
 }
 
 impl<E: Error> Pooling<E> for Unify {
+    #[inline]
     fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr> {
-        self.shared_pointers.get(&address).map(|p| p.ptr)
+        Pooling::<E>::get_shared_ptr(&mut self.pool, address)
     }
 
+    #[inline]
     unsafe fn add_shared_ptr(
         &mut self,
         address: usize,
         ptr: ErasedPtr,
         drop: unsafe fn(ErasedPtr),
     ) -> Result<(), E> {
-        match self.shared_pointers.entry(address) {
-            hash_map::Entry::Occupied(_) => {
-                fail!(DuplicateSharedPointer { address });
+        unsafe { self.pool.add_shared_ptr(address, ptr, drop) }
+    }
+}
+
+/// A shared pointer strategy that unifies shared pointers either within one
+/// deserializer or with a caller-owned [`SharedPool`].
+///
+/// See [`SharedPool`] for an example.
+#[derive(Debug)]
+pub enum Pool<'a> {
+    /// Shared pointers are only unified with the others deserialized by this
+    /// deserializer, like [`Unify`].
+    Unpooled(Unify),
+    /// Shared pointers are unified with all of the others in the pool,
+    /// including those from previous deserialize calls.
+    Pooled(&'a mut SharedPool),
+}
+
+impl Default for Pool<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::Unpooled(Unify::new())
+    }
+}
+
+impl<E: Error> Pooling<E> for Pool<'_> {
+    #[inline]
+    fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr> {
+        match self {
+            Self::Unpooled(unify) => {
+                Pooling::<E>::get_shared_ptr(unify, address)
             }
-            hash_map::Entry::Vacant(e) => {
-                e.insert(SharedPointer { ptr, drop });
-                Ok(())
+            Self::Pooled(pool) => Pooling::<E>::get_shared_ptr(*pool, address),
+        }
+    }
+
+    #[inline]
+    unsafe fn add_shared_ptr(
+        &mut self,
+        address: usize,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        match self {
+            Self::Unpooled(unify) => unsafe {
+                unify.add_shared_ptr(address, ptr, drop)
+            },
+            Self::Pooled(pool) => unsafe {
+                pool.add_shared_ptr(address, ptr, drop)
+            },
+        }
+    }
+
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        match self {
+            Self::Unpooled(_) => false,
+            Self::Pooled(pool) => Pooling::<E>::is_content_keyed(*pool),
+        }
+    }
+
+    #[inline]
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        match self {
+            Self::Unpooled(_) => None,
+            Self::Pooled(pool) => {
+                Pooling::<E>::get_shared_content(*pool, content)
             }
         }
     }
+
+    #[inline]
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        match self {
+            Self::Unpooled(_) => Ok(()),
+            Self::Pooled(pool) => unsafe {
+                pool.add_shared_content(content, ptr, drop)
+            },
+        }
+    }
 }
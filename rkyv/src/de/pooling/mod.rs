@@ -4,7 +4,13 @@
 mod alloc;
 mod core;
 
-use ::core::{alloc::Layout, fmt, mem::transmute};
+use ::core::{
+    alloc::Layout,
+    any::TypeId,
+    fmt,
+    mem::{size_of_val, transmute},
+    slice,
+};
 use ptr_meta::{from_raw_parts_mut, metadata, DynMetadata, Pointee};
 use rancor::{Fallible, Strategy};
 
@@ -134,9 +140,28 @@ pub unsafe trait SharedPointer<T: ?Sized> {
     unsafe fn drop(ptr: *mut T);
 }
 
+/// The contents of an archived shared value.
+///
+/// Content-keyed pools use these to unify shared pointers to equal values
+/// across archives. They are only created for values whose archived
+/// representation is a byte-for-byte copy of the deserialized value, so values
+/// with equal contents always deserialize to equal values.
+#[derive(Clone, Copy, Debug)]
+pub struct SharedContent<'a> {
+    /// The type of the shared pointer being deserialized.
+    pub type_id: TypeId,
+    /// The bytes of the archived value.
+    pub bytes: &'a [u8],
+}
+
 /// A shared pointer deserialization strategy.
 ///
 /// This trait is required to deserialize `Rc` and `Arc`.
+///
+/// Shared pointers are keyed by the address of their archived value by
+/// default. Strategies which return `true` from `is_content_keyed` key shared
+/// pointers to self-contained values by their [`SharedContent`] instead, and
+/// key all other shared pointers by address.
 pub trait Pooling<E = <Self as Fallible>::Error> {
     /// Gets the data pointer of a previously-deserialized shared pointer.
     fn get_shared_ptr(&mut self, address: usize) -> Option<ErasedPtr>;
@@ -153,6 +178,43 @@ pub trait Pooling<E = <Self as Fallible>::Error> {
         ptr: ErasedPtr,
         drop: unsafe fn(ErasedPtr),
     ) -> Result<(), E>;
+
+    /// Returns whether shared pointers to self-contained values are keyed by
+    /// their contents instead of their addresses.
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        false
+    }
+
+    /// Gets the data pointer of a previously-deserialized shared pointer with
+    /// the given contents.
+    #[inline]
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        let _ = content;
+        None
+    }
+
+    /// Adds the contents of a deserialized shared pointer to the registry.
+    ///
+    /// This is only called when `is_content_keyed` returns `true`.
+    ///
+    /// # Safety
+    ///
+    /// The given `drop` function must be valid to call with the given
+    /// `pointer`.
+    #[inline]
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        let _ = (content, ptr, drop);
+        Ok(())
+    }
 }
 
 impl<T, E> Pooling<E> for Strategy<T, E>
@@ -173,6 +235,29 @@ where
     ) -> Result<(), E> {
         T::add_shared_ptr(self, address, ptr, drop)
     }
+
+    #[inline]
+    fn is_content_keyed(&self) -> bool {
+        T::is_content_keyed(self)
+    }
+
+    #[inline]
+    fn get_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+    ) -> Option<ErasedPtr> {
+        T::get_shared_content(self, content)
+    }
+
+    #[inline]
+    unsafe fn add_shared_content(
+        &mut self,
+        content: &SharedContent<'_>,
+        ptr: ErasedPtr,
+        drop: unsafe fn(ErasedPtr),
+    ) -> Result<(), E> {
+        unsafe { T::add_shared_content(self, content, ptr, drop) }
+    }
 }
 
 /// Helper methods for `SharedDeserializeRegistry`.
@@ -191,7 +276,7 @@ pub trait PoolingExt<E>: Pooling<E> {
         T::Metadata: Into<Metadata>,
         Metadata: Into<T::Metadata>,
         T::Archived: DeserializeUnsized<T, Self>,
-        P: SharedPointer<T>,
+        P: SharedPointer<T> + 'static,
         A: FnMut(Layout) -> *mut u8,
         Self: Fallible<Error = E>,
     {
@@ -204,26 +289,46 @@ pub trait PoolingExt<E>: Pooling<E> {
             unsafe { P::drop(ptr.downcast_unchecked::<T>()) }
         }
 
-        let address = value as *const T::Archived as *const () as usize;
+        let ptr = (value as *const T::Archived).cast::<u8>();
+        let address = ptr.addr();
         let metadata = T::Archived::deserialize_metadata(value, self)?;
 
-        if let Some(shared_pointer) = self.get_shared_ptr(address) {
-            Ok(from_raw_parts_mut(shared_pointer.data_address, metadata))
-        } else {
-            let ptr = unsafe { value.deserialize_unsized(self, alloc)? };
-            let ptr = from_raw_parts_mut::<T>(ptr, metadata);
-            let ptr = unsafe { P::from_value(ptr) };
-
-            unsafe {
-                self.add_shared_ptr(
-                    address,
-                    ErasedPtr::new(ptr),
-                    drop_shared::<T, P>,
-                )?;
-            }
+        // Copy-optimized values don't contain any padding or relative
+        // pointers, so all of their contents are in their archived bytes.
+        let content = (T::UNSIZED_COPY_OPTIMIZATION.is_enabled()
+            && self.is_content_keyed())
+        .then(|| SharedContent {
+            type_id: TypeId::of::<P>(),
+            bytes: unsafe { slice::from_raw_parts(ptr, size_of_val(value)) },
+        });
 
-            Ok(ptr)
+        let shared_pointer = match content {
+            Some(ref content) => self.get_shared_content(content),
+            None => self.get_shared_ptr(address),
+        };
+        if let Some(shared_pointer) = shared_pointer {
+            return Ok(from_raw_parts_mut(
+                shared_pointer.data_address,
+                metadata,
+            ));
         }
+
+        let ptr = unsafe { value.deserialize_unsized(self, alloc)? };
+        let ptr = from_raw_parts_mut::<T>(ptr, metadata);
+        let ptr = unsafe { P::from_value(ptr) };
+
+        let erased = ErasedPtr::new(ptr);
+        let drop = drop_shared::<T, P>;
+        unsafe {
+            match content {
+                Some(ref content) => {
+                    self.add_shared_content(content, erased, drop)?
+                }
+                None => self.add_shared_ptr(address, erased, drop)?,
+            }
+        }
+
+        Ok(ptr)
     }
 }
 
@@ -0,0 +1,440 @@
+//! Estimates of the memory used by deserialized values.
+//!
+//! [`ArchivedDeepSize`] estimates how much memory an archived value will use
+//! once it is deserialized, without deserializing it. This can be used to
+//! decide whether there is room for a value before deserializing it. See
+//! [`deserialized_size_estimate`](crate::util::deserialized_size_estimate).
+//!
+//! `ArchivedDeepSize` can be implemented for the archived type of a type which
+//! derives `Archive` by adding `#[archive(deep_size)]`.
+//!
+//! # Approximations
+//!
+//! Strings, vectors, and boxes are estimated exactly because deserializing them
+//! allocates exactly as much memory as their contents need. Maps and sets are
+//! estimated from the layouts of the standard library's collections at the
+//! time of writing:
+//!
+//! - Hash maps and sets allocate a power-of-two number of buckets for at least
+//!   8/7 of their length, plus one control byte per bucket and a trailing
+//!   group of 16 control bytes.
+//! - B-tree maps and sets store up to 11 entries per node. Deserializing
+//!   inserts entries in order, which splits each full node into one with 6
+//!   entries and one with 5 entries. Apart from the last node on each level,
+//!   this leaves about 7 entries per leaf node and 7 children per internal
+//!   node.
+//!
+//! Memory overhead from the allocator itself is not included. Estimates which
+//! would overflow a `usize` saturate at `usize::MAX`, since no allocation of
+//! that size could succeed.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     access_unchecked, rancor::Failure, to_bytes,
+//!     util::deserialized_size_estimate, Archive, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(deep_size)]
+//! struct Message {
+//!     id: u64,
+//!     body: String,
+//! }
+//!
+//! let value = Message {
+//!     id: 42,
+//!     body: "hello world".to_string(),
+//! };
+//! let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+//! let archived = unsafe { access_unchecked::<ArchivedMessage>(&bytes) };
+//! assert_eq!(
+//!     deserialized_size_estimate(archived),
+//!     core::mem::size_of::<Message>() + 11,
+//! );
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec::Vec,
+};
+use core::mem::size_of;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+#[cfg(feature = "std")]
+use crate::collections::swiss_table::{ArchivedHashMap, ArchivedHashSet};
+use crate::{
+    boxed::ArchivedBox,
+    collections::{btree_map::ArchivedBTreeMap, btree_set::ArchivedBTreeSet},
+    option::ArchivedOption,
+    string::ArchivedString,
+    tuple::*,
+    vec::ArchivedVec,
+};
+
+/// An archived type which can estimate the memory used by its deserialized
+/// value.
+pub trait ArchivedDeepSize {
+    /// The type that this archived type deserializes to.
+    type Deserialized;
+
+    /// Returns the number of bytes of heap memory that the deserialized value
+    /// would own, not including the size of the deserialized value itself.
+    fn deserialized_heap_size(&self) -> usize;
+}
+
+/// Returns the number of heap bytes used by the deserialized values of the
+/// given archived values, not including the values themselves.
+fn heap_size_of_all<'a, T: ArchivedDeepSize + 'a>(
+    values: impl Iterator<Item = &'a T>,
+) -> usize {
+    values
+        .map(T::deserialized_heap_size)
+        .fold(0, usize::saturating_add)
+}
+
+// Primitives
+
+macro_rules! impl_primitive {
+    ($($archived:ty => $native:ty),* $(,)?) => {
+        $(
+            impl ArchivedDeepSize for $archived {
+                type Deserialized = $native;
+
+                #[inline]
+                fn deserialized_heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive! {
+    () => (),
+    bool => bool,
+    i8 => i8,
+    u8 => u8,
+    i16 => i16,
+    i32 => i32,
+    i64 => i64,
+    i128 => i128,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+    u128 => u128,
+    f32 => f32,
+    f64 => f64,
+    char => char,
+}
+
+macro_rules! impl_endian_primitives {
+    ($module:path: $(
+        $i16:ident $i32:ident $i64:ident $i128:ident
+        $u16:ident $u32:ident $u64:ident $u128:ident
+        $f32:ident $f64:ident $char:ident
+    )*) => {
+        const _: () = {
+            use $module::{
+                $($i16, $i32, $i64, $i128, $u16, $u32, $u64, $u128, $f32,
+                $f64, $char,)*
+            };
+
+            impl_primitive! {
+                $(
+                    $i16 => i16, $i32 => i32, $i64 => i64, $i128 => i128,
+                    $u16 => u16, $u32 => u32, $u64 => u64, $u128 => u128,
+                    $f32 => f32, $f64 => f64, $char => char,
+                )*
+            }
+        };
+    };
+}
+
+impl_endian_primitives! {
+    crate::rend:
+    i16_le i32_le i64_le i128_le u16_le u32_le u64_le u128_le f32_le f64_le
+    char_le
+}
+
+impl_endian_primitives! {
+    crate::rend:
+    i16_be i32_be i64_be i128_be u16_be u32_be u64_be u128_be f32_be f64_be
+    char_be
+}
+
+impl_endian_primitives! {
+    crate::rend::unaligned:
+    i16_ule i32_ule i64_ule i128_ule u16_ule u32_ule u64_ule u128_ule f32_ule
+    f64_ule char_ule
+}
+
+impl_endian_primitives! {
+    crate::rend::unaligned:
+    i16_ube i32_ube i64_ube i128_ube u16_ube u32_ube u64_ube u128_ube f32_ube
+    f64_ube char_ube
+}
+
+// Arrays
+
+impl<T: ArchivedDeepSize, const N: usize> ArchivedDeepSize for [T; N] {
+    type Deserialized = [T::Deserialized; N];
+
+    #[inline]
+    fn deserialized_heap_size(&self) -> usize {
+        heap_size_of_all(self.iter())
+    }
+}
+
+// Tuples
+
+macro_rules! impl_tuple {
+    ($name:ident, $($type:ident $index:tt),*) => {
+        impl<$($type: ArchivedDeepSize),*> ArchivedDeepSize
+            for $name<$($type),*>
+        {
+            type Deserialized = ($($type::Deserialized,)*);
+
+            #[inline]
+            fn deserialized_heap_size(&self) -> usize {
+                0usize
+                    $(.saturating_add(self.$index.deserialized_heap_size()))*
+            }
+        }
+    };
+}
+
+impl_tuple!(ArchivedTuple1, T0 0);
+impl_tuple!(ArchivedTuple2, T0 0, T1 1);
+impl_tuple!(ArchivedTuple3, T0 0, T1 1, T2 2);
+impl_tuple!(ArchivedTuple4, T0 0, T1 1, T2 2, T3 3);
+impl_tuple!(ArchivedTuple5, T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_tuple!(ArchivedTuple6, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_tuple!(ArchivedTuple7, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_tuple!(ArchivedTuple8, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_tuple!(
+    ArchivedTuple9, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8
+);
+impl_tuple!(
+    ArchivedTuple10, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9
+);
+impl_tuple!(
+    ArchivedTuple11, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10
+);
+impl_tuple!(
+    ArchivedTuple12, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10, T11 11
+);
+impl_tuple!(
+    ArchivedTuple13, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10, T11 11, T12 12
+);
+
+// Option
+
+impl<T: ArchivedDeepSize> ArchivedDeepSize for ArchivedOption<T> {
+    type Deserialized = Option<T::Deserialized>;
+
+    #[inline]
+    fn deserialized_heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::deserialized_heap_size)
+    }
+}
+
+// String
+
+impl ArchivedDeepSize for ArchivedString {
+    type Deserialized = String;
+
+    #[inline]
+    fn deserialized_heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+// Box
+
+impl<T: ArchivedDeepSize> ArchivedDeepSize for ArchivedBox<T> {
+    type Deserialized = Box<T::Deserialized>;
+
+    #[inline]
+    fn deserialized_heap_size(&self) -> usize {
+        size_of::<T::Deserialized>()
+            .saturating_add(self.get().deserialized_heap_size())
+    }
+}
+
+impl<T: ArchivedDeepSize> ArchivedDeepSize for ArchivedBox<[T]> {
+    type Deserialized = Box<[T::Deserialized]>;
+
+    #[inline]
+    fn deserialized_heap_size(&self) -> usize {
+        self.len()
+            .saturating_mul(size_of::<T::Deserialized>())
+            .saturating_add(heap_size_of_all(self.iter()))
+    }
+}
+
+impl ArchivedDeepSize for ArchivedBox<str> {
+    type Deserialized = Box<str>;
+
+    #[inline]
+    fn deserialized_heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+// Vec
+
+impl<T: ArchivedDeepSize> ArchivedDeepSize for ArchivedVec<T> {
+    type Deserialized = Vec<T::Deserialized>;
+
+    #[inline]
+    fn deserialized_heap_size(&self) -> usize {
+        self.len()
+            .saturating_mul(size_of::<T::Deserialized>())
+            .saturating_add(heap_size_of_all(self.iter()))
+    }
+}
+
+// B-tree maps and sets
+
+/// The maximum number of entries in a B-tree node.
+const BTREE_CAPACITY: usize = 11;
+
+/// The number of entries which a full B-tree node keeps or passes to its parent
+/// when it is split by inserting an entry at its end.
+const BTREE_FILL: usize = 7;
+
+/// Estimates the number of nodes needed to hold `count` entries or children in
+/// one level of a B-tree built by inserting entries in order.
+fn btree_level_nodes(count: usize, capacity: usize) -> usize {
+    if count <= capacity {
+        1
+    } else {
+        1 + (count - capacity).div_ceil(BTREE_FILL)
+    }
+}
+
+/// Estimates the number of bytes allocated for the nodes of a B-tree map with
+/// the given length and entry sizes.
+fn btree_nodes_size(len: usize, key_size: usize, value_size: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+
+    // Each node has a parent pointer, a parent index, and a length
+    let header = size_of::<usize>() + 2 * size_of::<u16>();
+    let leaf = BTREE_CAPACITY
+        .saturating_mul(key_size.saturating_add(value_size))
+        .saturating_add(header)
+        .checked_next_multiple_of(size_of::<usize>())
+        .unwrap_or(usize::MAX);
+    let internal =
+        leaf.saturating_add((BTREE_CAPACITY + 1) * size_of::<usize>());
+
+    let mut nodes = btree_level_nodes(len, BTREE_CAPACITY);
+    let mut size = nodes.saturating_mul(leaf);
+    while nodes > 1 {
+        nodes = btree_level_nodes(nodes, BTREE_CAPACITY + 1);
+        size = size.saturating_add(nodes.saturating_mul(internal));
+    }
+    size
+}
+
+impl<K, V> ArchivedDeepSize for ArchivedBTreeMap<K, V>
+where
+    K: ArchivedDeepSize,
+    V: ArchivedDeepSize,
+{
+    type Deserialized = BTreeMap<K::Deserialized, V::Deserialized>;
+
+    fn deserialized_heap_size(&self) -> usize {
+        let nodes = btree_nodes_size(
+            self.len(),
+            size_of::<K::Deserialized>(),
+            size_of::<V::Deserialized>(),
+        );
+        self.iter().fold(nodes, |size, (k, v)| {
+            size.saturating_add(k.deserialized_heap_size())
+                .saturating_add(v.deserialized_heap_size())
+        })
+    }
+}
+
+impl<K: ArchivedDeepSize> ArchivedDeepSize for ArchivedBTreeSet<K> {
+    type Deserialized = BTreeSet<K::Deserialized>;
+
+    fn deserialized_heap_size(&self) -> usize {
+        btree_nodes_size(self.len(), size_of::<K::Deserialized>(), 0)
+            .saturating_add(heap_size_of_all(self.iter()))
+    }
+}
+
+// Hash maps and sets
+
+/// The number of trailing control bytes in a hash table.
+#[cfg(feature = "std")]
+const HASH_GROUP_WIDTH: usize = 16;
+
+/// Estimates the number of bytes allocated for a hash table with the given
+/// capacity and entry size.
+#[cfg(feature = "std")]
+fn hash_table_size(capacity: usize, entry_size: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+
+    let buckets = if capacity < 4 {
+        4
+    } else if capacity < 8 {
+        8
+    } else {
+        (capacity.saturating_mul(8) / 7)
+            .checked_next_power_of_two()
+            .unwrap_or(usize::MAX)
+    };
+    let entries = buckets
+        .saturating_mul(entry_size)
+        .checked_next_multiple_of(HASH_GROUP_WIDTH)
+        .unwrap_or(usize::MAX);
+    entries
+        .saturating_add(buckets)
+        .saturating_add(HASH_GROUP_WIDTH)
+}
+
+#[cfg(feature = "std")]
+impl<K, V, H> ArchivedDeepSize for ArchivedHashMap<K, V, H>
+where
+    K: ArchivedDeepSize,
+    V: ArchivedDeepSize,
+{
+    type Deserialized = HashMap<K::Deserialized, V::Deserialized>;
+
+    fn deserialized_heap_size(&self) -> usize {
+        let table = hash_table_size(
+            self.len(),
+            size_of::<(K::Deserialized, V::Deserialized)>(),
+        );
+        self.iter().fold(table, |size, (k, v)| {
+            size.saturating_add(k.deserialized_heap_size())
+                .saturating_add(v.deserialized_heap_size())
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: ArchivedDeepSize, H> ArchivedDeepSize for ArchivedHashSet<K, H> {
+    type Deserialized = HashSet<K::Deserialized>;
+
+    fn deserialized_heap_size(&self) -> usize {
+        hash_table_size(self.len(), size_of::<K::Deserialized>())
+            .saturating_add(heap_size_of_all(self.iter()))
+    }
+}
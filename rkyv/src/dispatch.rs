@@ -0,0 +1,217 @@
+//! Archiving trait objects through enum dispatch.
+//!
+//! Trait objects can't be archived directly because the set of types behind
+//! them is open. When the implementations of a trait are known ahead of time,
+//! [`rkyv_enum_dispatch`](macro@crate::rkyv_enum_dispatch) can archive
+//! `Box<dyn Trait>` as an enum with one variant per implementation instead.
+//!
+//! The macro is applied to an enum which lists the implementing types, and
+//! generates:
+//!
+//! - `Archive` and `Serialize` for `Box<dyn Trait>`. Serializing downcasts the
+//!   boxed value to each listed type in order, and fails with an
+//!   [`UnknownImplementation`] error if none of them match. The trait must
+//!   have `Any` as a supertrait so that trait objects can be downcast.
+//! - An archived enum with the same variants as the listed enum, holding the
+//!   archived types of the implementations.
+//! - `Deserialize` for the archived enum to both the listed enum and
+//!   `Box<dyn Trait>`, and `From` for converting the listed enum into a
+//!   `Box<dyn Trait>`.
+//!
+//! The archived enum is named "Archived" + the name of the listed enum by
+//! default. `#[archive(...)]` and `#[archive_attr(...)]` attributes on the
+//! listed enum are applied to the archived enum as if it had been derived.
+//!
+//! The archived enum can also implement a trait by delegating each method to
+//! the archived value in the matched variant. Mark the trait with
+//! `#[rkyv_enum_dispatch]` and name it with `archived = ...`. Every method of
+//! the trait which takes `&self` or `&mut self` and has no type parameters is
+//! delegated. The trait must be declared before the enum in the same crate,
+//! and the types in its method signatures must be nameable where the enum is
+//! declared.
+//!
+//! # Example
+//!
+//! ```
+//! use core::any::Any;
+//!
+//! use rkyv::{
+//!     rancor::Failure, rkyv_enum_dispatch, Archive, Archived, Deserialize,
+//!     Serialize,
+//! };
+//!
+//! trait Shape: Any {
+//!     fn area(&self) -> f32;
+//! }
+//!
+//! #[rkyv_enum_dispatch]
+//! trait ArchivedShape {
+//!     fn area(&self) -> f32;
+//! }
+//!
+//! #[derive(Archive, Serialize, Deserialize)]
+//! struct Square {
+//!     side: f32,
+//! }
+//!
+//! impl Shape for Square {
+//!     fn area(&self) -> f32 {
+//!         self.side * self.side
+//!     }
+//! }
+//!
+//! impl ArchivedShape for Archived<Square> {
+//!     fn area(&self) -> f32 {
+//!         self.side.to_native() * self.side.to_native()
+//!     }
+//! }
+//!
+//! #[derive(Archive, Serialize, Deserialize)]
+//! struct Circle {
+//!     radius: f32,
+//! }
+//!
+//! impl Shape for Circle {
+//!     fn area(&self) -> f32 {
+//!         3.0 * self.radius * self.radius
+//!     }
+//! }
+//!
+//! impl ArchivedShape for Archived<Circle> {
+//!     fn area(&self) -> f32 {
+//!         3.0 * self.radius.to_native() * self.radius.to_native()
+//!     }
+//! }
+//!
+//! #[rkyv_enum_dispatch(Shape, archived = ArchivedShape)]
+//! enum AnyShape {
+//!     Square(Square),
+//!     Circle(Circle),
+//! }
+//!
+//! let shape: Box<dyn Shape> = Box::new(Circle { radius: 2.0 });
+//! let bytes = rkyv::to_bytes::<_, 256, Failure>(&shape).unwrap();
+//! let archived =
+//!     unsafe { rkyv::access_unchecked::<ArchivedAnyShape>(&bytes) };
+//! assert!(matches!(archived, ArchivedAnyShape::Circle(_)));
+//! assert_eq!(ArchivedShape::area(archived), 12.0);
+//!
+//! let deserialized: Box<dyn Shape> =
+//!     rkyv::deserialize::<_, _, Failure>(archived, &mut ()).unwrap();
+//! assert_eq!(deserialized.area(), 12.0);
+//! ```
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+pub use alloc::boxed::Box;
+use core::{
+    any::{Any, TypeId},
+    fmt,
+};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use std::boxed::Box;
+
+use rancor::Fallible;
+
+use crate::{Archive, Serialize};
+
+/// An error resulting from serializing a trait object whose type is not one of
+/// the implementations listed for enum dispatch.
+#[derive(Debug)]
+pub struct UnknownImplementation {
+    trait_name: &'static str,
+}
+
+impl UnknownImplementation {
+    /// Returns a new `UnknownImplementation` error for the named trait.
+    #[inline]
+    pub fn new(trait_name: &'static str) -> Self {
+        Self { trait_name }
+    }
+
+    /// Returns the name of the trait that the trait object was created for.
+    #[inline]
+    pub fn trait_name(&self) -> &'static str {
+        self.trait_name
+    }
+}
+
+impl fmt::Display for UnknownImplementation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown implementation: the `dyn {}` is not any of the types \
+             listed for enum dispatch",
+            self.trait_name,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownImplementation {}
+
+/// Returns a reference to the given value as a `T` if that is its concrete
+/// type.
+///
+/// Unlike `<dyn Any>::downcast_ref`, this works on any trait object whose
+/// trait has `Any` as a supertrait.
+#[inline]
+pub fn downcast_ref<T: Any, U: Any + ?Sized>(value: &U) -> Option<&T> {
+    if Any::type_id(value) == TypeId::of::<T>() {
+        // SAFETY: The concrete type of `value` is `T`, so the data pointer of
+        // `value` points to a `T`.
+        Some(unsafe { &*(value as *const U as *const T) })
+    } else {
+        None
+    }
+}
+
+/// A borrowed value which archives as the value it points to.
+///
+/// The enums generated by `rkyv_enum_dispatch` hold these to serialize values
+/// that are only available by reference.
+#[doc(hidden)]
+pub struct DispatchRef<T> {
+    ptr: *const T,
+}
+
+impl<T> DispatchRef<T> {
+    /// Returns a new `DispatchRef` pointing to the given value.
+    ///
+    /// # Safety
+    ///
+    /// The returned `DispatchRef` must not be serialized or resolved after
+    /// `value` is dropped.
+    #[inline]
+    pub unsafe fn new(value: &T) -> Self {
+        Self { ptr: value }
+    }
+}
+
+impl<T: Archive> Archive for DispatchRef<T> {
+    type Archived = T::Archived;
+    type Resolver = T::Resolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        (*self.ptr).resolve(pos, resolver, out);
+    }
+}
+
+impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for DispatchRef<T> {
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        // SAFETY: The caller of `new` guaranteed that the value is still
+        // alive.
+        unsafe { (*self.ptr).serialize(serializer) }
+    }
+}
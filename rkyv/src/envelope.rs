@@ -0,0 +1,639 @@
+//! A self-describing container format for archives.
+//!
+//! An enveloped archive is a fixed-size header followed by the bytes of a
+//! regular archive. The header records everything a reader needs to check
+//! before touching the archive: the format that rkyv was configured with, the
+//! type of the root object, where the root object is, how long the archive is,
+//! and an optional checksum.
+//!
+//! The header is [`HEADER_SIZE`] bytes long, which is a multiple of the
+//! alignment of [`AlignedVec`](crate::util::AlignedVec). Reading an envelope
+//! into an aligned buffer keeps the archive after it aligned as well. All
+//! fields of the header are little-endian regardless of the format of the
+//! archive:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 4    | [`MAGIC`]                                              |
+//! | 4      | 1    | [`VERSION`]                                            |
+//! | 5      | 1    | Flags: bit 0 is big-endian, bit 1 is unaligned         |
+//! | 6      | 1    | Pointer width in bytes                                 |
+//! | 7      | 1    | Checksum: 0 for none, 1 for XXH64                      |
+//! | 8      | 8    | [`TYPE_HASH`](TypeHash::TYPE_HASH) of the root type    |
+//! | 16     | 8    | Position of the root object in the archive             |
+//! | 24     | 8    | Length of the archive                                  |
+//! | 32     | 8    | Checksum of the archive, or zero                       |
+//! | 40     | 8    | Reserved, must be zero                                 |
+//!
+//! Root types must implement [`TypeHash`], which can be derived with
+//! `#[archive(type_hash)]`.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     envelope::{peek_envelope, read_enveloped, write_enveloped, TypeHash},
+//!     rancor::Failure,
+//!     util::AlignedVec,
+//!     Archive, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(check_bytes, type_hash)]
+//! struct Config {
+//!     name: String,
+//!     retries: u32,
+//! }
+//!
+//! let value = Config {
+//!     name: "primary".to_string(),
+//!     retries: 3,
+//! };
+//! let mut bytes = AlignedVec::new();
+//! write_enveloped::<_, _, Failure>(&value, &mut bytes).unwrap();
+//!
+//! let info = peek_envelope(&bytes).unwrap();
+//! assert_eq!(info.type_hash, Config::TYPE_HASH);
+//!
+//! let archived = read_enveloped::<Config, Failure>(&bytes).unwrap();
+//! assert_eq!(archived.name, "primary");
+//! assert_eq!(archived.retries, 3);
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{
+    convert::Infallible,
+    fmt,
+    mem::{align_of, size_of},
+};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(feature = "bytecheck")]
+use bytecheck::CheckBytes;
+#[cfg(any(feature = "bytecheck", feature = "std"))]
+use rancor::Strategy;
+
+use crate::{primitive::FixedUsize, util::Checksum, Archive};
+#[cfg(feature = "bytecheck")]
+use crate::{validation::validators::DefaultValidator, Portable};
+
+/// The magic bytes which start every envelope.
+pub const MAGIC: [u8; 4] = *b"RKYV";
+
+/// The version of the envelope format written by this version of rkyv.
+pub const VERSION: u8 = 1;
+
+/// The size of an envelope header in bytes.
+pub const HEADER_SIZE: usize = 48;
+
+const BIG_ENDIAN_FLAG: u8 = 1 << 0;
+const UNALIGNED_FLAG: u8 = 1 << 1;
+
+/// A stable hash which identifies an archived type.
+///
+/// The hash of a type should change whenever the format of its archived type
+/// does. Types can derive a hash with `#[archive(type_hash)]`, which combines
+/// the name, fields, and field types of the type as written with the size and
+/// alignment of its archived type.
+pub trait TypeHash {
+    /// The hash of the archived type.
+    const TYPE_HASH: u64;
+}
+
+/// Hashes a type name and a list of parameters with 64-bit FNV-1a.
+///
+/// This can be used to implement [`TypeHash`] in const contexts.
+pub const fn hash_type(name: &str, params: &[u64]) -> u64 {
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    let bytes = name.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = (hash ^ bytes[i] as u64).wrapping_mul(PRIME);
+        i += 1;
+    }
+    let mut i = 0;
+    while i < params.len() {
+        let bytes = params[i].to_le_bytes();
+        let mut j = 0;
+        while j < bytes.len() {
+            hash = (hash ^ bytes[j] as u64).wrapping_mul(PRIME);
+            j += 1;
+        }
+        i += 1;
+    }
+    hash
+}
+
+macro_rules! impl_primitive_type_hash {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl TypeHash for $ty {
+                const TYPE_HASH: u64 = hash_type(stringify!($ty), &[]);
+            }
+        )*
+    };
+}
+
+impl_primitive_type_hash!(
+    (),
+    bool,
+    char,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    f32,
+    f64,
+    String,
+);
+
+impl<T: TypeHash> TypeHash for Option<T> {
+    const TYPE_HASH: u64 = hash_type("Option", &[T::TYPE_HASH]);
+}
+
+impl<T: TypeHash> TypeHash for Box<T> {
+    const TYPE_HASH: u64 = hash_type("Box", &[T::TYPE_HASH]);
+}
+
+impl<T: TypeHash> TypeHash for Vec<T> {
+    const TYPE_HASH: u64 = hash_type("Vec", &[T::TYPE_HASH]);
+}
+
+impl<T: TypeHash, const N: usize> TypeHash for [T; N] {
+    const TYPE_HASH: u64 = hash_type("Array", &[T::TYPE_HASH, N as u64]);
+}
+
+#[cfg(feature = "std")]
+impl<K: TypeHash, V: TypeHash, S> TypeHash for HashMap<K, V, S> {
+    const TYPE_HASH: u64 = hash_type("HashMap", &[K::TYPE_HASH, V::TYPE_HASH]);
+}
+
+/// The byte order of an archive.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Endianness {
+    /// Little-endian.
+    Little,
+    /// Big-endian.
+    Big,
+}
+
+impl Endianness {
+    /// The byte order that this crate reads and writes archives with.
+    pub const NATIVE: Self = if cfg!(feature = "big_endian") {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    };
+}
+
+impl fmt::Display for Endianness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endianness::Little => write!(f, "little-endian"),
+            Endianness::Big => write!(f, "big-endian"),
+        }
+    }
+}
+
+/// The contents of an envelope header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EnvelopeInfo {
+    /// The version of the envelope format.
+    pub version: u8,
+    /// The byte order of the archive.
+    pub endianness: Endianness,
+    /// Whether the archive was written with the `unaligned` feature.
+    pub unaligned: bool,
+    /// The width of archived pointers and lengths in bytes.
+    pub pointer_width: u8,
+    /// The checksum of the archive.
+    pub checksum: Checksum,
+    /// The [`TYPE_HASH`](TypeHash::TYPE_HASH) of the root type.
+    pub type_hash: u64,
+    /// The position of the root object within the archive.
+    pub root_pos: u64,
+    /// The length of the archive in bytes.
+    pub payload_len: u64,
+    /// The checksum value of the archive, or zero if there is no checksum.
+    pub checksum_value: u64,
+}
+
+impl EnvelopeInfo {
+    #[cfg(feature = "std")]
+    fn new<T: TypeHash + Archive>(checksum: Checksum, payload: &[u8]) -> Self {
+        Self {
+            version: VERSION,
+            endianness: Endianness::NATIVE,
+            unaligned: cfg!(feature = "unaligned"),
+            pointer_width: size_of::<FixedUsize>() as u8,
+            checksum,
+            type_hash: T::TYPE_HASH,
+            root_pos: (payload.len() - size_of::<T::Archived>()) as u64,
+            payload_len: payload.len() as u64,
+            checksum_value: checksum.compute(payload),
+        }
+    }
+
+    /// Returns the header bytes for this envelope.
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut flags = 0;
+        if self.endianness == Endianness::Big {
+            flags |= BIG_ENDIAN_FLAG;
+        }
+        if self.unaligned {
+            flags |= UNALIGNED_FLAG;
+        }
+
+        let mut result = [0; HEADER_SIZE];
+        result[0..4].copy_from_slice(&MAGIC);
+        result[4] = self.version;
+        result[5] = flags;
+        result[6] = self.pointer_width;
+        result[7] = match self.checksum {
+            Checksum::None => 0,
+            Checksum::Xxh64 => 1,
+        };
+        result[8..16].copy_from_slice(&self.type_hash.to_le_bytes());
+        result[16..24].copy_from_slice(&self.root_pos.to_le_bytes());
+        result[24..32].copy_from_slice(&self.payload_len.to_le_bytes());
+        result[32..40].copy_from_slice(&self.checksum_value.to_le_bytes());
+        result
+    }
+}
+
+/// An error which occurred while reading an envelope.
+///
+/// Errors from validating the archive in the envelope are returned as
+/// `Invalid`. Errors from reading only the header use `Infallible` for the
+/// validation error.
+#[derive(Debug)]
+pub enum EnvelopeError<E = Infallible> {
+    /// The buffer is too short to contain a header.
+    TooShort {
+        /// The length of the buffer.
+        len: usize,
+    },
+    /// The buffer does not start with [`MAGIC`].
+    BadMagic {
+        /// The first bytes of the buffer.
+        found: [u8; 4],
+    },
+    /// The envelope has a version which this version of rkyv can't read.
+    UnsupportedVersion {
+        /// The version of the envelope.
+        found: u8,
+    },
+    /// The reserved bytes of the header are not zero.
+    NonZeroReserved,
+    /// The archive was written with a different byte order.
+    EndiannessMismatch {
+        /// The byte order that this crate reads archives with.
+        expected: Endianness,
+        /// The byte order of the archive.
+        found: Endianness,
+    },
+    /// The archive was written with a different pointer width.
+    WidthMismatch {
+        /// The pointer width that this crate reads archives with, in bytes.
+        expected: u8,
+        /// The pointer width of the archive, in bytes.
+        found: u8,
+    },
+    /// The archive was written with a different setting of the `unaligned`
+    /// feature.
+    UnalignedMismatch {
+        /// Whether this crate reads unaligned archives.
+        expected: bool,
+        /// Whether the archive is unaligned.
+        found: bool,
+    },
+    /// The root of the archive is a different type.
+    WrongType {
+        /// The type hash of the type being read.
+        expected: u64,
+        /// The type hash in the envelope.
+        found: u64,
+    },
+    /// The envelope uses a checksum which this version of rkyv doesn't know.
+    UnknownChecksum {
+        /// The ID of the checksum.
+        found: u8,
+    },
+    /// The length of the archive doesn't match the length in the header.
+    LengthMismatch {
+        /// The length of the archive in the header.
+        expected: u64,
+        /// The number of bytes after the header.
+        found: usize,
+    },
+    /// The root position is not the position of a root object of the expected
+    /// type.
+    InvalidRootPosition {
+        /// The root position in the header.
+        pos: u64,
+        /// The length of the archive.
+        len: u64,
+    },
+    /// The checksum of the archive doesn't match the checksum in the header.
+    ChecksumMismatch {
+        /// The checksum in the header.
+        expected: u64,
+        /// The checksum of the archive.
+        actual: u64,
+    },
+    /// The archive failed validation.
+    Invalid(E),
+}
+
+impl<E: fmt::Display> fmt::Display for EnvelopeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::TooShort { len } => write!(
+                f,
+                "buffer of {} bytes is too short to contain an envelope \
+                 header of {} bytes",
+                len, HEADER_SIZE,
+            ),
+            EnvelopeError::BadMagic { found } => {
+                write!(f, "envelope has bad magic bytes {:?}", found)
+            }
+            EnvelopeError::UnsupportedVersion { found } => write!(
+                f,
+                "envelope version {} is not supported, expected {}",
+                found, VERSION,
+            ),
+            EnvelopeError::NonZeroReserved => {
+                write!(f, "reserved bytes of envelope header are not zero")
+            }
+            EnvelopeError::EndiannessMismatch { expected, found } => write!(
+                f,
+                "archive is {} but this crate reads {} archives",
+                found, expected,
+            ),
+            EnvelopeError::WidthMismatch { expected, found } => write!(
+                f,
+                "archive has {}-byte pointers but this crate reads {}-byte \
+                 pointers",
+                found, expected,
+            ),
+            EnvelopeError::UnalignedMismatch { expected, found } => write!(
+                f,
+                "archive is {} but this crate reads {} archives",
+                if *found { "unaligned" } else { "aligned" },
+                if *expected { "unaligned" } else { "aligned" },
+            ),
+            EnvelopeError::WrongType { expected, found } => write!(
+                f,
+                "envelope contains type hash {:#018x} but expected {:#018x}",
+                found, expected,
+            ),
+            EnvelopeError::UnknownChecksum { found } => {
+                write!(f, "envelope has unknown checksum ID {}", found)
+            }
+            EnvelopeError::LengthMismatch { expected, found } => write!(
+                f,
+                "envelope header has archive length {} but {} bytes follow \
+                 it",
+                expected, found,
+            ),
+            EnvelopeError::InvalidRootPosition { pos, len } => write!(
+                f,
+                "root position {} is invalid for an archive of {} bytes",
+                pos, len,
+            ),
+            EnvelopeError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {:#018x} but computed {:#018x}",
+                expected, actual,
+            ),
+            EnvelopeError::Invalid(e) => {
+                write!(f, "archive failed validation: {}", e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for EnvelopeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EnvelopeError::Invalid(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut result = [0; 8];
+    result.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(result)
+}
+
+/// Reads the header of an envelope.
+///
+/// Only the header is read, so this succeeds for envelopes in any format and
+/// of any type. The archive is not checked.
+pub fn peek_envelope(bytes: &[u8]) -> Result<EnvelopeInfo, EnvelopeError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(EnvelopeError::TooShort { len: bytes.len() });
+    }
+    let mut magic = [0; 4];
+    magic.copy_from_slice(&bytes[0..4]);
+    if magic != MAGIC {
+        return Err(EnvelopeError::BadMagic { found: magic });
+    }
+    if bytes[4] != VERSION {
+        return Err(EnvelopeError::UnsupportedVersion { found: bytes[4] });
+    }
+    let flags = bytes[5];
+    if flags & !(BIG_ENDIAN_FLAG | UNALIGNED_FLAG) != 0
+        || read_u64(bytes, 40) != 0
+    {
+        return Err(EnvelopeError::NonZeroReserved);
+    }
+    let checksum = match bytes[7] {
+        0 => Checksum::None,
+        1 => Checksum::Xxh64,
+        found => return Err(EnvelopeError::UnknownChecksum { found }),
+    };
+
+    Ok(EnvelopeInfo {
+        version: bytes[4],
+        endianness: if flags & BIG_ENDIAN_FLAG != 0 {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        },
+        unaligned: flags & UNALIGNED_FLAG != 0,
+        pointer_width: bytes[6],
+        checksum,
+        type_hash: read_u64(bytes, 8),
+        root_pos: read_u64(bytes, 16),
+        payload_len: read_u64(bytes, 24),
+        checksum_value: read_u64(bytes, 32),
+    })
+}
+
+/// Checks every field of the header against `T` and returns the root position
+/// and the archive.
+fn open<T, E>(bytes: &[u8]) -> Result<(usize, &[u8]), EnvelopeError<E>>
+where
+    T: TypeHash + Archive,
+{
+    let info = peek_envelope(bytes).map_err(|e| match e {
+        EnvelopeError::TooShort { len } => EnvelopeError::TooShort { len },
+        EnvelopeError::BadMagic { found } => EnvelopeError::BadMagic { found },
+        EnvelopeError::UnsupportedVersion { found } => {
+            EnvelopeError::UnsupportedVersion { found }
+        }
+        EnvelopeError::NonZeroReserved => EnvelopeError::NonZeroReserved,
+        EnvelopeError::UnknownChecksum { found } => {
+            EnvelopeError::UnknownChecksum { found }
+        }
+        _ => unreachable!(),
+    })?;
+
+    if info.endianness != Endianness::NATIVE {
+        return Err(EnvelopeError::EndiannessMismatch {
+            expected: Endianness::NATIVE,
+            found: info.endianness,
+        });
+    }
+    let width = size_of::<FixedUsize>() as u8;
+    if info.pointer_width != width {
+        return Err(EnvelopeError::WidthMismatch {
+            expected: width,
+            found: info.pointer_width,
+        });
+    }
+    if info.unaligned != cfg!(feature = "unaligned") {
+        return Err(EnvelopeError::UnalignedMismatch {
+            expected: cfg!(feature = "unaligned"),
+            found: info.unaligned,
+        });
+    }
+    if info.type_hash != T::TYPE_HASH {
+        return Err(EnvelopeError::WrongType {
+            expected: T::TYPE_HASH,
+            found: info.type_hash,
+        });
+    }
+
+    let payload = &bytes[HEADER_SIZE..];
+    if info.payload_len != payload.len() as u64 {
+        return Err(EnvelopeError::LengthMismatch {
+            expected: info.payload_len,
+            found: payload.len(),
+        });
+    }
+    let root_size = size_of::<T::Archived>() as u64;
+    let root_align = align_of::<T::Archived>() as u64;
+    if root_size > info.payload_len
+        || info.root_pos > info.payload_len - root_size
+        || info.root_pos % root_align != 0
+    {
+        return Err(EnvelopeError::InvalidRootPosition {
+            pos: info.root_pos,
+            len: info.payload_len,
+        });
+    }
+    let actual = info.checksum.compute(payload);
+    if actual != info.checksum_value {
+        return Err(EnvelopeError::ChecksumMismatch {
+            expected: info.checksum_value,
+            actual,
+        });
+    }
+
+    Ok((info.root_pos as usize, payload))
+}
+
+/// Checks the header of an envelope, then validates and accesses the archived
+/// root object.
+///
+/// Every field of the header is checked against `T` and the format of this
+/// crate before the archive is validated. The envelope must be aligned to at
+/// least the alignment of `T::Archived`, for example by reading it into an
+/// [`AlignedVec`](crate::util::AlignedVec).
+#[cfg(feature = "bytecheck")]
+pub fn read_enveloped<T, E>(
+    bytes: &[u8],
+) -> Result<&T::Archived, EnvelopeError<E>>
+where
+    T: TypeHash + Archive,
+    T::Archived: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: rancor::Error,
+{
+    let (pos, payload) = open::<T, E>(bytes)?;
+    crate::validation::util::access_pos::<T::Archived, E>(payload, pos)
+        .map_err(EnvelopeError::Invalid)
+}
+
+/// Checks the header of an envelope, then accesses the archived root object
+/// without validating the archive.
+///
+/// # Safety
+///
+/// The archive in the envelope must contain a valid `T::Archived` at the root
+/// position in the header.
+pub unsafe fn read_enveloped_unchecked<T>(
+    bytes: &[u8],
+) -> Result<&T::Archived, EnvelopeError>
+where
+    T: TypeHash + Archive,
+{
+    let (pos, payload) = open::<T, Infallible>(bytes)?;
+    Ok(unsafe { crate::util::access_pos_unchecked(payload, pos) })
+}
+
+/// Serializes a value into an envelope with no checksum.
+///
+/// The envelope is written with a single call to `write_all`.
+#[cfg(feature = "std")]
+pub fn write_enveloped<T, W, E>(value: &T, writer: &mut W) -> Result<(), E>
+where
+    T: TypeHash
+        + crate::Serialize<Strategy<crate::ser::AllocSerializer<1024>, E>>,
+    W: std::io::Write + ?Sized,
+    E: rancor::Error,
+{
+    write_enveloped_with_checksum(value, Checksum::None, writer)
+}
+
+/// Serializes a value into an envelope with the given checksum.
+///
+/// The envelope is written with a single call to `write_all`.
+#[cfg(feature = "std")]
+pub fn write_enveloped_with_checksum<T, W, E>(
+    value: &T,
+    checksum: Checksum,
+    writer: &mut W,
+) -> Result<(), E>
+where
+    T: TypeHash
+        + crate::Serialize<Strategy<crate::ser::AllocSerializer<1024>, E>>,
+    W: std::io::Write + ?Sized,
+    E: rancor::Error,
+{
+    use rancor::ResultExt as _;
+
+    let payload = crate::to_bytes::<T, 1024, E>(value)?;
+    let header = EnvelopeInfo::new::<T>(checksum, &payload).to_bytes();
+    let mut bytes =
+        crate::util::AlignedVec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&payload);
+    writer.write_all(&bytes).into_error()
+}
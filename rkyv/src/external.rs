@@ -0,0 +1,127 @@
+//! References to binary blobs stored outside of the archive.
+//!
+//! Large byte buffers can be archived with the
+//! [`External`](crate::with::External) wrapper, which hands them to a
+//! [`BlobSink`](crate::ser::BlobSink) while serializing and only writes the
+//! [`BlobHandle`] it returns. The blobs are usually kept in a
+//! content-addressed store, so equal blobs are only stored once no matter how
+//! many archives refer to them.
+//!
+//! The handle is archived as an [`ArchivedExternal`]. Its blob can be loaded
+//! from a [`BlobSource`](crate::de::BlobSource) with
+//! [`load`](ArchivedExternal::load), and deserializing it requires a
+//! deserializer which is a `BlobSource`, such as a
+//! [`BlobDeserializer`](crate::de::BlobDeserializer). Validating an archived
+//! handle only checks its bytes, it doesn't check that the blob exists.
+
+use core::fmt;
+
+use rancor::{fail, Error};
+
+use crate::{de::BlobSource, Portable};
+
+/// The size of a [`BlobHandle`] in bytes.
+pub const BLOB_HANDLE_SIZE: usize = 32;
+
+/// A handle to a blob stored outside of the archive, such as a 32-byte
+/// digest of its contents.
+pub type BlobHandle = [u8; BLOB_HANDLE_SIZE];
+
+/// Writes a blob handle as a hex string.
+fn fmt_handle(handle: &BlobHandle, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for byte in handle {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+/// An archived reference to a blob stored outside of the archive.
+///
+/// This is the archived form of values archived with
+/// [`External`](crate::with::External).
+#[derive(Clone, Copy, Portable, PartialEq, Eq, Hash)]
+#[archive(crate)]
+#[repr(transparent)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+pub struct ArchivedExternal {
+    handle: BlobHandle,
+}
+
+impl ArchivedExternal {
+    /// Returns the handle of the blob.
+    #[inline]
+    pub fn handle(&self) -> &BlobHandle {
+        &self.handle
+    }
+
+    /// Loads the blob from the given blob source.
+    ///
+    /// Fails with a [`MissingBlob`] error if the source doesn't have the blob.
+    #[inline]
+    pub fn load<'a, E: Error>(
+        &self,
+        source: &'a (impl BlobSource + ?Sized),
+    ) -> Result<&'a [u8], E> {
+        match source.load_blob(&self.handle) {
+            Some(blob) => Ok(blob),
+            None => fail!(MissingBlob {
+                handle: self.handle
+            }),
+        }
+    }
+
+    /// Resolves an archived external reference from a blob handle.
+    ///
+    /// # Safety
+    ///
+    /// `out` must point to memory suitable for holding an
+    /// `ArchivedExternal`.
+    #[inline]
+    pub unsafe fn resolve_from_handle(handle: BlobHandle, out: *mut Self) {
+        out.write(Self { handle });
+    }
+}
+
+impl fmt::Debug for ArchivedExternal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ArchivedExternal(")?;
+        fmt_handle(&self.handle, f)?;
+        write!(f, ")")
+    }
+}
+
+/// An error resulting from a blob which is missing from a blob source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MissingBlob {
+    /// The handle of the missing blob.
+    pub handle: BlobHandle,
+}
+
+impl fmt::Display for MissingBlob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "missing external blob ")?;
+        fmt_handle(&self.handle, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MissingBlob {}
+
+/// An error resulting from an external blob which was deserialized as a
+/// string but is not valid UTF-8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidBlobUtf8 {
+    /// The handle of the invalid blob.
+    pub handle: BlobHandle,
+}
+
+impl fmt::Display for InvalidBlobUtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "external blob ")?;
+        fmt_handle(&self.handle, f)?;
+        write!(f, " is not valid UTF-8")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidBlobUtf8 {}
@@ -55,7 +55,7 @@ impl ArchivedCString {
     /// Extracts a pinned mutable `CStr` slice containing the entire string.
     #[inline]
     pub fn pin_mut_c_str(self: Pin<&mut Self>) -> Pin<&mut CStr> {
-        unsafe { self.map_unchecked_mut(|s| &mut *s.ptr.as_ptr()) }
+        unsafe { self.map_unchecked_mut(|s| &mut *s.ptr.as_mut_ptr()) }
     }
 
     /// Resolves an archived C string from the given C string and parameters.
@@ -196,6 +196,7 @@ mod verify {
     {
         #[inline]
         fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            context.record_offset(self.ptr.offset_slot())?;
             let ptr =
                 unsafe { context.bounds_check_subtree_rel_ptr(&self.ptr)? };
 
@@ -0,0 +1,136 @@
+//! Packing `bool` and fieldless enum fields into bits.
+//!
+//! Structs which derive `Archive` with `#[archive(pack_flags)]` pack their
+//! `bool` fields and any fields marked with `#[archive(bits = N)]` into a
+//! single integer in their archived type. Each `bool` takes up one bit, and
+//! each field marked with `bits = N` takes up `N` bits. Packed fields are read
+//! from the archived type with accessor methods named after the fields, and
+//! deserializing unpacks them into the original fields.
+//!
+//! Packed fields with types other than `bool` must implement [`PackedFlag`].
+//! Fieldless enums can implement it by deriving `Archive` with
+//! `#[archive(pack_flags)]`.
+//!
+//! Validating a struct with packed fields checks that the bits of each packed
+//! field are valid and that all of the unused bits are zero.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     access, deserialize, rancor::Failure, to_bytes, Archive, Deserialize,
+//!     Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+//! #[archive(pack_flags)]
+//! enum Level {
+//!     Debug,
+//!     Info,
+//!     Warn,
+//!     Error,
+//! }
+//!
+//! #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+//! #[archive(pack_flags, check_bytes)]
+//! struct Record {
+//!     id: u32,
+//!     is_active: bool,
+//!     is_sampled: bool,
+//!     #[archive(bits = 2)]
+//!     level: Level,
+//! }
+//!
+//! assert_eq!(core::mem::size_of::<ArchivedRecord>(), 8);
+//!
+//! let value = Record {
+//!     id: 42,
+//!     is_active: true,
+//!     is_sampled: false,
+//!     level: Level::Warn,
+//! };
+//! let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+//! let archived = access::<ArchivedRecord, Failure>(&bytes).unwrap();
+//! assert_eq!(archived.id, 42);
+//! assert!(archived.is_active());
+//! assert!(!archived.is_sampled());
+//! assert_eq!(archived.level(), Level::Warn);
+//!
+//! let deserialized =
+//!     deserialize::<Record, _, Failure>(archived, &mut ()).unwrap();
+//! assert_eq!(deserialized, value);
+//! ```
+
+use core::fmt;
+
+/// A type which can be packed into the bits of a struct with
+/// `#[archive(pack_flags)]`.
+///
+/// This is implemented for `bool`, and can be implemented for fieldless enums
+/// by deriving `Archive` with `#[archive(pack_flags)]`.
+pub trait PackedFlag: Sized {
+    /// The number of bits needed to store every value of the type.
+    const BITS: u32;
+
+    /// Returns the bits which represent the value.
+    ///
+    /// Only the lowest [`BITS`](PackedFlag::BITS) bits may be set.
+    fn to_flag_bits(&self) -> u64;
+
+    /// Returns the value represented by the given bits, or `None` if they
+    /// don't represent a value.
+    fn from_flag_bits(bits: u64) -> Option<Self>;
+}
+
+impl PackedFlag for bool {
+    const BITS: u32 = 1;
+
+    #[inline]
+    fn to_flag_bits(&self) -> u64 {
+        *self as u64
+    }
+
+    #[inline]
+    fn from_flag_bits(bits: u64) -> Option<Self> {
+        match bits {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// An error resulting from invalid packed flags.
+#[derive(Debug)]
+pub enum InvalidFlags {
+    /// Bits which don't belong to any packed field were set.
+    UnusedBits {
+        /// The unused bits which were set.
+        bits: u64,
+    },
+    /// The bits of a packed field didn't represent a value.
+    InvalidField {
+        /// The name of the field.
+        field: &'static str,
+        /// The bits of the field.
+        bits: u64,
+    },
+}
+
+impl fmt::Display for InvalidFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidFlags::UnusedBits { bits } => {
+                write!(f, "unused flag bits {:#x} were set", bits)
+            }
+            InvalidFlags::InvalidField { field, bits } => write!(
+                f,
+                "invalid bits {:#b} for packed field `{}`",
+                bits, field,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidFlags {}
@@ -1,6 +1,7 @@
 //! Hashing support for archived hash maps and sets.
 
 use core::{
+    borrow::Borrow,
     hash::{Hash, Hasher},
     ops::BitXor as _,
 };
@@ -123,3 +124,204 @@ where
     value.hash(&mut state);
     state.finish()
 }
+
+/// A key which caches its hash with [`FxHasher64`].
+///
+/// Archived hash maps look up keys by their hash with `FxHasher64`, so
+/// serializing a hash map hashes every key. Hash maps with `Hashed` keys can
+/// be serialized with the [`Prehashed`](crate::with::Prehashed) wrapper,
+/// which uses the cached hashes instead.
+///
+/// `Hashed<K>` hashes and compares the same as `K`, and can be borrowed as
+/// `K` to look up keys in a hash map.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::hash::{hash_value, FxHasher64, Hashed};
+///
+/// let key = Hashed::new("hello".to_string());
+/// assert_eq!(key.cached_hash(), hash_value::<_, FxHasher64>("hello"));
+/// assert_eq!(key.get(), "hello");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Hashed<K> {
+    hash: u64,
+    key: K,
+}
+
+impl<K: Hash> Hashed<K> {
+    /// Hashes the given key and caches its hash.
+    #[inline]
+    pub fn new(key: K) -> Self {
+        Self {
+            hash: hash_value::<K, FxHasher64>(&key),
+            key,
+        }
+    }
+}
+
+impl<K> Hashed<K> {
+    /// Returns the cached hash of the key.
+    #[inline]
+    pub fn cached_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns a reference to the key.
+    #[inline]
+    pub fn get(&self) -> &K {
+        &self.key
+    }
+
+    /// Unwraps the key.
+    #[inline]
+    pub fn into_inner(self) -> K {
+        self.key
+    }
+}
+
+impl<K: Hash> Hash for Hashed<K> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hash the key instead of the cached hash so that `Hashed<K>` can be
+        // borrowed as `K`.
+        self.key.hash(state);
+    }
+}
+
+impl<K: PartialEq> PartialEq for Hashed<K> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.key == other.key
+    }
+}
+
+impl<K: Eq> Eq for Hashed<K> {}
+
+impl<K> Borrow<K> for Hashed<K> {
+    #[inline]
+    fn borrow(&self) -> &K {
+        &self.key
+    }
+}
+
+const XXH_PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+const XXH_PRIME64_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const XXH_PRIME64_3: u64 = 0x1656_67b1_9e37_79f9;
+const XXH_PRIME64_4: u64 = 0x85eb_ca77_c2b2_ca63;
+const XXH_PRIME64_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+#[inline]
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH_PRIME64_1)
+}
+
+#[inline]
+fn xxh64_merge_round(acc: u64, value: u64) -> u64 {
+    (acc ^ xxh64_round(0, value))
+        .wrapping_mul(XXH_PRIME64_1)
+        .wrapping_add(XXH_PRIME64_4)
+}
+
+#[inline]
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(word)
+}
+
+#[inline]
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(word)
+}
+
+/// Computes the 64-bit xxHash (XXH64) of the given bytes.
+///
+/// Unlike [`FxHasher64`], XXH64 is a well-specified and widely implemented
+/// algorithm. This makes it suitable for checksums which may be verified by
+/// other tools.
+pub fn xxh64(bytes: &[u8], seed: u64) -> u64 {
+    let mut rest = bytes;
+
+    let mut hash = if rest.len() >= 32 {
+        let mut v1 =
+            seed.wrapping_add(XXH_PRIME64_1).wrapping_add(XXH_PRIME64_2);
+        let mut v2 = seed.wrapping_add(XXH_PRIME64_2);
+        let mut v3 = seed;
+        let mut v4 = seed.wrapping_sub(XXH_PRIME64_1);
+
+        while rest.len() >= 32 {
+            v1 = xxh64_round(v1, read_u64(&rest[0..]));
+            v2 = xxh64_round(v2, read_u64(&rest[8..]));
+            v3 = xxh64_round(v3, read_u64(&rest[16..]));
+            v4 = xxh64_round(v4, read_u64(&rest[24..]));
+            rest = &rest[32..];
+        }
+
+        let mut hash = v1
+            .rotate_left(1)
+            .wrapping_add(v2.rotate_left(7))
+            .wrapping_add(v3.rotate_left(12))
+            .wrapping_add(v4.rotate_left(18));
+        hash = xxh64_merge_round(hash, v1);
+        hash = xxh64_merge_round(hash, v2);
+        hash = xxh64_merge_round(hash, v3);
+        xxh64_merge_round(hash, v4)
+    } else {
+        seed.wrapping_add(XXH_PRIME64_5)
+    };
+
+    hash = hash.wrapping_add(bytes.len() as u64);
+
+    while rest.len() >= 8 {
+        hash ^= xxh64_round(0, read_u64(rest));
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(XXH_PRIME64_1)
+            .wrapping_add(XXH_PRIME64_4);
+        rest = &rest[8..];
+    }
+
+    if rest.len() >= 4 {
+        hash ^= u64::from(read_u32(rest)).wrapping_mul(XXH_PRIME64_1);
+        hash = hash
+            .rotate_left(23)
+            .wrapping_mul(XXH_PRIME64_2)
+            .wrapping_add(XXH_PRIME64_3);
+        rest = &rest[4..];
+    }
+
+    for &byte in rest {
+        hash ^= u64::from(byte).wrapping_mul(XXH_PRIME64_5);
+        hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME64_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(XXH_PRIME64_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(XXH_PRIME64_3);
+    hash ^= hash >> 32;
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xxh64;
+
+    #[test]
+    fn xxh64_test_vectors() {
+        assert_eq!(xxh64(b"", 0), 0xef46_db37_51d8_e999);
+        assert_eq!(xxh64(b"a", 0), 0xd24e_c4f1_a98c_6e5b);
+        assert_eq!(xxh64(b"abc", 0), 0x44bc_2cf5_ad77_0999);
+        assert_eq!(xxh64(b"xxhash", 0), 0x32dd_3895_2c4b_c720);
+        assert_eq!(xxh64(b"xxhash", 20141025), 0xb559_b98d_844e_0635);
+
+        let bytes: [u8; 100] = core::array::from_fn(|i| i as u8);
+        assert_eq!(xxh64(&bytes, 0), 0xb616_76be_a2c4_daa5);
+    }
+}
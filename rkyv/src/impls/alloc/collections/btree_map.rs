@@ -3,10 +3,14 @@ use alloc::collections::BTreeMap;
 #[cfg(feature = "std")]
 use std::collections::BTreeMap;
 
-use rancor::Fallible;
+use rancor::{Fallible, ResultExt as _, Trace};
 
 use crate::{
-    collections::btree_map::{ArchivedBTreeMap, BTreeMapResolver},
+    collections::{
+        btree_map::{ArchivedBTreeMap, BTreeMapResolver},
+        swiss_table::ArchivedHashMap,
+    },
+    de::{AllocationLimiter, PathSegment},
     ser::Writer,
     Archive, Deserialize, Serialize,
 };
@@ -41,32 +45,81 @@ where
         &self,
         serializer: &mut S,
     ) -> Result<Self::Resolver, S::Error> {
-        unsafe {
+        #[cfg(feature = "hooks")]
+        serializer.enter_type(core::any::type_name::<Self>())?;
+        serializer.begin_collection(self.len())?;
+        let resolver = unsafe {
             ArchivedBTreeMap::serialize_from_reverse_iter(
                 self.iter().rev(),
                 serializer,
-            )
-        }
+            )?
+        };
+        serializer.end_collection(self.len())?;
+        #[cfg(feature = "hooks")]
+        serializer.exit_type();
+        Ok(resolver)
     }
 }
 
-impl<K: Archive + Ord, V: Archive, D: Fallible + ?Sized>
-    Deserialize<BTreeMap<K, V>, D>
+impl<K, V, D> Deserialize<BTreeMap<K, V>, D>
     for ArchivedBTreeMap<K::Archived, V::Archived>
 where
+    K: Archive + Ord,
     K::Archived: Deserialize<K, D> + Ord,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<BTreeMap<K, V>, D::Error> {
+        deserializer.check_array::<(K, V)>(self.len())?;
+        let mut result = BTreeMap::new();
+        for (i, (key, value)) in self.iter().enumerate() {
+            result.insert(
+                key.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Key(i))?,
+                value
+                    .deserialize(deserializer)
+                    .with_trace(|| PathSegment::Value(i))?,
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Deserializes the entries of the hash map and sorts them by key.
+///
+/// If the deserialized keys of several entries are equal, the value of the
+/// entry which is iterated last is kept. Hash maps are iterated in an
+/// unspecified order.
+impl<K, V, D> Deserialize<BTreeMap<K, V>, D>
+    for ArchivedHashMap<K::Archived, V::Archived>
+where
+    K: Archive + Ord,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
     V::Archived: Deserialize<V, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
 {
     #[inline]
     fn deserialize(
         &self,
         deserializer: &mut D,
     ) -> Result<BTreeMap<K, V>, D::Error> {
+        deserializer.check_array::<(K, V)>(self.len())?;
         let mut result = BTreeMap::new();
-        for (key, value) in self.iter() {
+        for (i, (key, value)) in self.iter().enumerate() {
             result.insert(
-                key.deserialize(deserializer)?,
-                value.deserialize(deserializer)?,
+                key.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Key(i))?,
+                value
+                    .deserialize(deserializer)
+                    .with_trace(|| PathSegment::Value(i))?,
             );
         }
         Ok(result)
@@ -7,6 +7,7 @@ use rancor::Fallible;
 
 use crate::{
     collections::btree_set::{ArchivedBTreeSet, BTreeSetResolver},
+    de::AllocationLimiter,
     ser::Writer,
     Archive, Deserialize, Serialize,
 };
@@ -58,13 +59,14 @@ impl<K, D> Deserialize<BTreeSet<K>, D> for ArchivedBTreeSet<K::Archived>
 where
     K: Archive + Ord,
     K::Archived: Deserialize<K, D> + Ord,
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
 {
     #[inline]
     fn deserialize(
         &self,
         deserializer: &mut D,
     ) -> Result<BTreeSet<K>, D::Error> {
+        deserializer.check_array::<K>(self.len())?;
         let mut result = BTreeSet::new();
         for k in self.iter() {
             result.insert(k.deserialize(deserializer)?);
@@ -0,0 +1,46 @@
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use crate::{
+    compact::{ArchivedCompactBox, ArchivedCompactString, ArchivedCompactVec},
+    ArchivePointee,
+};
+
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for ArchivedCompactVec<T> {
+    #[inline]
+    fn eq(&self, other: &Vec<U>) -> bool {
+        self.as_slice().eq(other.as_slice())
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedCompactVec<U>> for Vec<T> {
+    #[inline]
+    fn eq(&self, other: &ArchivedCompactVec<U>) -> bool {
+        self.as_slice().eq(other.as_slice())
+    }
+}
+
+impl PartialEq<String> for ArchivedCompactString {
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<ArchivedCompactString> for String {
+    #[inline]
+    fn eq(&self, other: &ArchivedCompactString) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<T, U> PartialEq<Box<U>> for ArchivedCompactBox<T>
+where
+    T: ArchivePointee + PartialEq<U> + ?Sized,
+    U: ?Sized,
+{
+    #[inline]
+    fn eq(&self, other: &Box<U>) -> bool {
+        self.get().eq(other.as_ref())
+    }
+}
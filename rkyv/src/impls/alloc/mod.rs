@@ -1,5 +1,6 @@
 mod boxed;
 mod collections;
+mod compact;
 mod niche;
 mod rc;
 mod string;
@@ -1,11 +1,13 @@
 #[cfg(not(feature = "std"))]
-use alloc::string::{String, ToString};
+use alloc::string::String;
 use core::cmp::Ordering;
 
 use rancor::Fallible;
 
 use crate::{
+    de::{reserve, AllocationLimiter, Reserve},
     string::{ArchivedString, StringResolver},
+    util::AllocationFailed,
     Archive, Deserialize, DeserializeUnsized, Serialize, SerializeUnsized,
 };
 
@@ -37,13 +39,34 @@ where
     }
 }
 
-impl<D: Fallible + ?Sized> Deserialize<String, D> for ArchivedString
+impl Reserve for String {
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.reserve_exact(additional);
+    }
+
+    #[inline]
+    fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), AllocationFailed> {
+        self.try_reserve_exact(additional)
+            .map_err(|_| AllocationFailed::new(additional))
+    }
+}
+
+impl<D> Deserialize<String, D> for ArchivedString
 where
     str: DeserializeUnsized<str, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
 {
     #[inline]
-    fn deserialize(&self, _: &mut D) -> Result<String, D::Error> {
-        Ok(self.as_str().to_string())
+    fn deserialize(&self, deserializer: &mut D) -> Result<String, D::Error> {
+        deserializer.check_array::<u8>(self.len())?;
+        let mut result = String::new();
+        reserve(&mut result, self.len(), deserializer)?;
+        result.push_str(self.as_str());
+        Ok(result)
     }
 }
 
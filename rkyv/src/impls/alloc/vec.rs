@@ -1,15 +1,16 @@
-use core::cmp;
-
 #[cfg(not(feature = "std"))]
-use ::alloc::{alloc, boxed::Box, vec::Vec};
-#[cfg(feature = "std")]
-use ::std::alloc;
-use rancor::Fallible;
+use ::alloc::vec::Vec;
+use core::{cmp, mem::size_of};
+
+use rancor::{Fallible, ResultExt as _, Trace};
 
 use crate::{
-    ser::{Allocator, Writer},
-    vec::{ArchivedVec, VecResolver},
-    Archive, Deserialize, DeserializeUnsized, Serialize,
+    collections::{btree_map::ArchivedBTreeMap, swiss_table::ArchivedHashMap},
+    de::{reserve, AllocationLimiter, PathSegment, Reserve},
+    ser::{Allocator, SerializeChunked, Writer},
+    util::AllocationFailed,
+    vec::{ArchivedVec, VecChunks, VecResolver},
+    Archive, Deserialize, Serialize,
 };
 
 impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for ArchivedVec<T> {
@@ -62,38 +63,235 @@ impl<T: Archive> Archive for Vec<T> {
     }
 }
 
-impl<T: Serialize<S>, S: Fallible + Allocator + Writer + ?Sized> Serialize<S>
-    for Vec<T>
+impl<T, S> Serialize<S> for Vec<T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
 {
     #[inline]
     fn serialize(
         &self,
         serializer: &mut S,
     ) -> Result<Self::Resolver, S::Error> {
-        ArchivedVec::<T::Archived>::serialize_from_slice(
+        #[cfg(feature = "hooks")]
+        serializer.enter_type(core::any::type_name::<Self>())?;
+        serializer.begin_collection(self.len())?;
+        let resolver = ArchivedVec::<T::Archived>::serialize_from_slice(
+            self.as_slice(),
+            serializer,
+        )?;
+        serializer.end_collection(self.len())?;
+        #[cfg(feature = "hooks")]
+        serializer.exit_type();
+        Ok(resolver)
+    }
+}
+
+impl<T, S> SerializeChunked<S> for Vec<T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    type State = VecChunks<T::Resolver>;
+
+    #[inline]
+    fn serialize_chunk(
+        &self,
+        state: &mut Self::State,
+        count: usize,
+        serializer: &mut S,
+    ) -> Result<Option<Self::Resolver>, S::Error> {
+        ArchivedVec::<T::Archived>::serialize_chunk_from_slice(
             self.as_slice(),
+            state,
+            count,
             serializer,
         )
     }
 }
 
-impl<T: Archive, D: Fallible + ?Sized> Deserialize<Vec<T>, D>
-    for ArchivedVec<T::Archived>
+impl<T> Reserve for Vec<T> {
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.reserve_exact(additional);
+    }
+
+    #[inline]
+    fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), AllocationFailed> {
+        self.try_reserve_exact(additional).map_err(|_| {
+            AllocationFailed::new(size_of::<T>().saturating_mul(additional))
+        })
+    }
+}
+
+impl<T, D> Deserialize<Vec<T>, D> for ArchivedVec<T::Archived>
 where
-    [T::Archived]: DeserializeUnsized<[T], D>,
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
 {
     #[inline]
     fn deserialize(&self, deserializer: &mut D) -> Result<Vec<T>, D::Error> {
-        unsafe {
-            let data_address = self
-                .as_slice()
-                .deserialize_unsized(deserializer, |layout| {
-                    alloc::alloc(layout)
-                })?;
-            let metadata =
-                self.as_slice().deserialize_metadata(deserializer)?;
-            let ptr = ptr_meta::from_raw_parts_mut(data_address, metadata);
-            Ok(Box::<[T]>::from_raw(ptr).into())
+        deserializer.check_array::<T>(self.len())?;
+        let mut result = Vec::new();
+        reserve(&mut result, self.len(), deserializer)?;
+        for (i, item) in self.iter().enumerate() {
+            result.push(
+                item.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Index(i))?,
+            );
+        }
+        Ok(result)
+    }
+}
+
+fn deserialize_entries<'a, K, V, AK, AV, D>(
+    len: usize,
+    iter: impl Iterator<Item = (&'a AK, &'a AV)>,
+    deserializer: &mut D,
+) -> Result<Vec<(K, V)>, D::Error>
+where
+    AK: Deserialize<K, D> + 'a,
+    AV: Deserialize<V, D> + 'a,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
+{
+    deserializer.check_array::<(K, V)>(len)?;
+    let mut result = Vec::new();
+    reserve(&mut result, len, deserializer)?;
+    for (i, (key, value)) in iter.enumerate() {
+        result.push((
+            key.deserialize(deserializer)
+                .with_trace(|| PathSegment::Key(i))?,
+            value
+                .deserialize(deserializer)
+                .with_trace(|| PathSegment::Value(i))?,
+        ));
+    }
+    Ok(result)
+}
+
+/// Deserializes the entries of the map in the order that they are iterated,
+/// which is unspecified for hash maps.
+impl<K, V, D> Deserialize<Vec<(K, V)>, D>
+    for ArchivedHashMap<K::Archived, V::Archived>
+where
+    K: Archive,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<Vec<(K, V)>, D::Error> {
+        deserialize_entries(self.len(), self.iter(), deserializer)
+    }
+}
+
+/// Deserializes the entries of the map in ascending order of their keys.
+impl<K, V, D> Deserialize<Vec<(K, V)>, D>
+    for ArchivedBTreeMap<K::Archived, V::Archived>
+where
+    K: Archive,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<Vec<(K, V)>, D::Error> {
+        deserialize_entries(self.len(), self.iter(), deserializer)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+mod allocator_api {
+    #[cfg(not(feature = "std"))]
+    use ::alloc::vec::Vec;
+    use core::alloc::Allocator;
+
+    use rancor::Fallible;
+
+    use crate::{
+        de::AllocProvider,
+        ser::{Allocator as SerAllocator, Writer},
+        vec::{ArchivedVec, VecResolver},
+        Archive, Deserialize, Serialize,
+    };
+
+    impl<'a, T: Archive, B: ?Sized> Archive for Vec<T, &'a B>
+    where
+        &'a B: Allocator,
+    {
+        type Archived = ArchivedVec<T::Archived>;
+        type Resolver = VecResolver;
+
+        #[inline]
+        unsafe fn resolve(
+            &self,
+            pos: usize,
+            resolver: Self::Resolver,
+            out: *mut Self::Archived,
+        ) {
+            ArchivedVec::resolve_from_slice(
+                self.as_slice(),
+                pos,
+                resolver,
+                out,
+            );
+        }
+    }
+
+    impl<'a, T, B, S> Serialize<S> for Vec<T, &'a B>
+    where
+        T: Serialize<S>,
+        B: ?Sized,
+        &'a B: Allocator,
+        S: Fallible + SerAllocator + Writer + ?Sized,
+    {
+        #[inline]
+        fn serialize(
+            &self,
+            serializer: &mut S,
+        ) -> Result<Self::Resolver, S::Error> {
+            ArchivedVec::<T::Archived>::serialize_from_slice(
+                self.as_slice(),
+                serializer,
+            )
+        }
+    }
+
+    impl<'a, T, B, D> Deserialize<Vec<T, &'a B>, D> for ArchivedVec<T::Archived>
+    where
+        T: Archive,
+        T::Archived: Deserialize<T, D>,
+        B: ?Sized,
+        &'a B: Allocator,
+        D: Fallible + AllocProvider<&'a B> + ?Sized,
+    {
+        #[inline]
+        fn deserialize(
+            &self,
+            deserializer: &mut D,
+        ) -> Result<Vec<T, &'a B>, D::Error> {
+            let mut result =
+                Vec::with_capacity_in(self.len(), deserializer.allocator());
+            for item in self.iter() {
+                result.push(item.deserialize(deserializer)?);
+            }
+            Ok(result)
         }
     }
 }
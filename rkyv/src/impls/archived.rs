@@ -0,0 +1,318 @@
+use core::{
+    hash::{Hash, Hasher},
+    hint::unreachable_unchecked,
+    ptr,
+};
+
+use rancor::{Error, Fallible};
+
+use crate::{
+    boxed::{ArchivedBox, BoxResolver},
+    collections::swiss_table::{
+        ArchivedHashMap, ArchivedHashSet, HashMapResolver, HashSetResolver,
+    },
+    option::ArchivedOption,
+    rc::{ArchivedRc, RcResolver},
+    ser::{Allocator, Sharing, Writer},
+    string::{ArchivedString, StringResolver},
+    vec::{ArchivedVec, VecResolver},
+    Archive, ArchivePointee, ArchiveUnsized, Serialize, SerializeUnsized,
+};
+
+// ArchivedString
+
+impl Archive for ArchivedString {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedString::resolve_from_str(self.as_str(), pos, resolver, out);
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for ArchivedString
+where
+    str: SerializeUnsized<S>,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedString::serialize_from_str(self.as_str(), serializer)
+    }
+}
+
+// ArchivedVec
+
+impl<T: Archive> Archive for ArchivedVec<T> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_slice(self.as_slice(), pos, resolver, out);
+    }
+}
+
+impl<T, S> Serialize<S> for ArchivedVec<T>
+where
+    T: Serialize<S>,
+    S: Fallible + Writer + ?Sized,
+    [T]: SerializeUnsized<S>,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::<T::Archived>::serialize_from_slice(
+            self.as_slice(),
+            serializer,
+        )
+    }
+}
+
+// ArchivedBox
+
+impl<T: ArchivePointee + ArchiveUnsized + ?Sized> Archive for ArchivedBox<T> {
+    type Archived = ArchivedBox<T::Archived>;
+    type Resolver = BoxResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedBox::resolve_from_ref(self.get(), pos, resolver, out);
+    }
+}
+
+impl<T, S> Serialize<S> for ArchivedBox<T>
+where
+    T: ArchivePointee + SerializeUnsized<S> + ?Sized,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedBox::serialize_from_ref(self.get(), serializer)
+    }
+}
+
+// ArchivedRc
+
+impl<T: ArchivePointee + ArchiveUnsized + ?Sized, F> Archive
+    for ArchivedRc<T, F>
+{
+    type Archived = ArchivedRc<T::Archived, F>;
+    type Resolver = RcResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedRc::resolve_from_ref(self.get(), pos, resolver, out);
+    }
+}
+
+impl<T, F, S> Serialize<S> for ArchivedRc<T, F>
+where
+    T: ArchivePointee + SerializeUnsized<S> + ?Sized,
+    S: Fallible + Writer + Sharing + ?Sized,
+{
+    /// Serializes the shared value once per address, so values which were
+    /// shared in the original archive are also shared in the new one.
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedRc::<T::Archived, F>::serialize_from_ref(self.get(), serializer)
+    }
+}
+
+// ArchivedOption
+
+#[allow(dead_code)]
+#[repr(u8)]
+enum ArchivedOptionTag {
+    None,
+    Some,
+}
+
+#[repr(C)]
+struct ArchivedOptionVariantNone(ArchivedOptionTag);
+
+#[repr(C)]
+struct ArchivedOptionVariantSome<T>(ArchivedOptionTag, T);
+
+impl<T: Archive> Archive for ArchivedOption<T> {
+    type Archived = ArchivedOption<T::Archived>;
+    type Resolver = Option<T::Resolver>;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        match resolver {
+            None => {
+                let out = out.cast::<ArchivedOptionVariantNone>();
+                ptr::addr_of_mut!((*out).0).write(ArchivedOptionTag::None);
+            }
+            Some(resolver) => {
+                let out = out.cast::<ArchivedOptionVariantSome<T::Archived>>();
+                ptr::addr_of_mut!((*out).0).write(ArchivedOptionTag::Some);
+
+                let value = if let Some(value) = self.as_ref() {
+                    value
+                } else {
+                    unreachable_unchecked();
+                };
+
+                let (fp, fo) = out_field!(out.1);
+                value.resolve(pos + fp, resolver, fo);
+            }
+        }
+    }
+}
+
+impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for ArchivedOption<T> {
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        self.as_ref()
+            .map(|value| value.serialize(serializer))
+            .transpose()
+    }
+}
+
+// ArchivedHashMap
+
+impl<K, V, H> Archive for ArchivedHashMap<K, V, H>
+where
+    K: Archive,
+    K::Archived: Hash + Eq,
+    V: Archive,
+    H: Hasher + Default,
+{
+    type Archived = ArchivedHashMap<K::Archived, V::Archived, H>;
+    type Resolver = HashMapResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashMap::resolve_from_len(
+            self.len(),
+            (7, 8),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, V, H, S> Serialize<S> for ArchivedHashMap<K, V, H>
+where
+    K: Serialize<S> + Hash + Eq,
+    K::Archived: Hash + Eq,
+    V: Serialize<S>,
+    H: Hasher + Default,
+    S: Fallible + Writer + Allocator + ?Sized,
+    S::Error: Error,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        serializer.begin_collection(self.len())?;
+        let resolver =
+            ArchivedHashMap::<K::Archived, V::Archived, H>::serialize_from_iter(
+                self.iter(),
+                (7, 8),
+                serializer,
+            )?;
+        serializer.end_collection(self.len())?;
+        Ok(resolver)
+    }
+}
+
+// ArchivedHashSet
+
+impl<K, H> Archive for ArchivedHashSet<K, H>
+where
+    K: Archive,
+    K::Archived: Hash + Eq,
+    H: Hasher + Default,
+{
+    type Archived = ArchivedHashSet<K::Archived, H>;
+    type Resolver = HashSetResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashSet::resolve_from_len(
+            self.len(),
+            (7, 8),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, H, S> Serialize<S> for ArchivedHashSet<K, H>
+where
+    K: Serialize<S> + Hash + Eq,
+    K::Archived: Hash + Eq,
+    H: Hasher + Default,
+    S: Fallible + Writer + Allocator + ?Sized,
+    S::Error: Error,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        serializer.begin_collection(self.len())?;
+        let resolver = ArchivedHashSet::<K::Archived, H>::serialize_from_iter(
+            self.iter(),
+            (7, 8),
+            serializer,
+        )?;
+        serializer.end_collection(self.len())?;
+        Ok(resolver)
+    }
+}
@@ -2,13 +2,14 @@ use core::convert::{TryFrom, TryInto};
 use core::ops::Deref;
 
 use bitvec::{prelude::*, view::BitViewSized};
-use rancor::Fallible;
+use rancor::{Fallible, Trace};
 
 use crate::bitvec::ArchivedBitArray;
 #[cfg(feature = "bitvec_alloc")]
 use crate::vec::{ArchivedVec, VecResolver};
 use crate::{
     bitvec::ArchivedBitVec,
+    de::AllocationLimiter,
     out_field,
     ser::{Allocator, Writer},
     vec::{ArchivedVec, VecResolver},
@@ -73,7 +74,8 @@ impl<T, O, D> Deserialize<BitVec<T, O>, D> for ArchivedBitVec<Archived<T>, O>
 where
     T: BitStore + Archive,
     O: BitOrder,
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
     Archived<T>: Deserialize<T, D> + BitStore,
 {
     fn deserialize(
@@ -0,0 +1,156 @@
+use bumpalo::{collections::Vec as BumpVec, Bump};
+use rancor::Fallible;
+
+use crate::{
+    de::AllocProvider,
+    ser::{Allocator, Writer},
+    vec::{ArchivedVec, VecResolver},
+    Archive, Deserialize, Serialize,
+};
+
+impl<'a, T: Archive> Archive for BumpVec<'a, T> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_slice(self.as_slice(), pos, resolver, out);
+    }
+}
+
+impl<'a, T, S> Serialize<S> for BumpVec<'a, T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::<T::Archived>::serialize_from_slice(
+            self.as_slice(),
+            serializer,
+        )
+    }
+}
+
+impl<'a, T, D> Deserialize<BumpVec<'a, T>, D> for ArchivedVec<T::Archived>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + AllocProvider<&'a Bump> + ?Sized,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<BumpVec<'a, T>, D::Error> {
+        let mut result =
+            BumpVec::with_capacity_in(self.len(), deserializer.allocator());
+        for item in self.iter() {
+            result.push(item.deserialize(deserializer)?);
+        }
+        Ok(result)
+    }
+}
+
+// With the `allocator_api` feature, these are covered by the impls for all
+// `HashMap<K, V, S, &'a B>` where `&'a B: Allocator`.
+#[cfg(not(feature = "allocator_api"))]
+mod hash_map {
+    use core::hash::{BuildHasher, Hash};
+
+    use bumpalo::Bump;
+    use hashbrown::HashMap;
+    use rancor::{Error, Fallible};
+
+    use crate::{
+        collections::swiss_table::map::{ArchivedHashMap, HashMapResolver},
+        de::AllocProvider,
+        ser::{Allocator, Writer},
+        Archive, Deserialize, Serialize,
+    };
+
+    impl<'a, K, V, S> Archive for HashMap<K, V, S, &'a Bump>
+    where
+        K: Archive + Hash + Eq,
+        K::Archived: Hash + Eq,
+        V: Archive,
+    {
+        type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+        type Resolver = HashMapResolver;
+
+        #[inline]
+        unsafe fn resolve(
+            &self,
+            pos: usize,
+            resolver: Self::Resolver,
+            out: *mut Self::Archived,
+        ) {
+            ArchivedHashMap::resolve_from_len(
+                self.len(),
+                (7, 8),
+                pos,
+                resolver,
+                out,
+            );
+        }
+    }
+
+    impl<'a, K, V, S, RS> Serialize<S> for HashMap<K, V, RS, &'a Bump>
+    where
+        K: Serialize<S> + Hash + Eq,
+        K::Archived: Hash + Eq,
+        V: Serialize<S>,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+    {
+        #[inline]
+        fn serialize(
+            &self,
+            serializer: &mut S,
+        ) -> Result<Self::Resolver, S::Error> {
+            ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
+                self.iter(),
+                (7, 8),
+                serializer,
+            )
+        }
+    }
+
+    impl<'a, K, V, D, S> Deserialize<HashMap<K, V, S, &'a Bump>, D>
+        for ArchivedHashMap<K::Archived, V::Archived>
+    where
+        K: Archive + Hash + Eq,
+        K::Archived: Deserialize<K, D> + Hash + Eq,
+        V: Archive,
+        V::Archived: Deserialize<V, D>,
+        D: Fallible + AllocProvider<&'a Bump> + ?Sized,
+        S: Default + BuildHasher,
+    {
+        #[inline]
+        fn deserialize(
+            &self,
+            deserializer: &mut D,
+        ) -> Result<HashMap<K, V, S, &'a Bump>, D::Error> {
+            let mut result = HashMap::with_capacity_and_hasher_in(
+                self.len(),
+                S::default(),
+                deserializer.allocator(),
+            );
+            for (k, v) in self.iter() {
+                result.insert(
+                    k.deserialize(deserializer)?,
+                    v.deserialize(deserializer)?,
+                );
+            }
+            Ok(result)
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use core::cmp::Ordering;
+
+use rancor::Fallible;
+
+use crate::{cmp::ArchivedOrdering, Archive, Deserialize, Serialize};
+
+impl Archive for Ordering {
+    type Archived = ArchivedOrdering;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        _: usize,
+        _: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        out.write(ArchivedOrdering::from(*self));
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for Ordering {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<Ordering, D> for ArchivedOrdering {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<Ordering, D::Error> {
+        Ok(Ordering::from(*self))
+    }
+}
+
+impl From<Ordering> for ArchivedOrdering {
+    #[inline]
+    fn from(ordering: Ordering) -> Self {
+        match ordering {
+            Ordering::Less => ArchivedOrdering::Less,
+            Ordering::Equal => ArchivedOrdering::Equal,
+            Ordering::Greater => ArchivedOrdering::Greater,
+        }
+    }
+}
+
+impl From<ArchivedOrdering> for Ordering {
+    #[inline]
+    fn from(ordering: ArchivedOrdering) -> Self {
+        match ordering {
+            ArchivedOrdering::Less => Ordering::Less,
+            ArchivedOrdering::Equal => Ordering::Equal,
+            ArchivedOrdering::Greater => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialEq<Ordering> for ArchivedOrdering {
+    #[inline]
+    fn eq(&self, other: &Ordering) -> bool {
+        Ordering::from(*self) == *other
+    }
+}
+
+impl PartialEq<ArchivedOrdering> for Ordering {
+    #[inline]
+    fn eq(&self, other: &ArchivedOrdering) -> bool {
+        other.eq(self)
+    }
+}
@@ -0,0 +1,55 @@
+use core::convert::Infallible;
+
+use rancor::Fallible;
+
+use crate::{convert::ArchivedInfallible, Archive, Deserialize, Serialize};
+
+impl Archive for Infallible {
+    type Archived = ArchivedInfallible;
+    type Resolver = Infallible;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        _: usize,
+        _: Self::Resolver,
+        _: *mut Self::Archived,
+    ) {
+        match *self {}
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for Infallible {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        match *self {}
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<Infallible, D> for ArchivedInfallible {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<Infallible, D::Error> {
+        match *self {}
+    }
+}
+
+impl From<ArchivedInfallible> for Infallible {
+    #[inline]
+    fn from(infallible: ArchivedInfallible) -> Self {
+        match infallible {}
+    }
+}
+
+impl PartialEq<Infallible> for ArchivedInfallible {
+    #[inline]
+    fn eq(&self, _: &Infallible) -> bool {
+        match *self {}
+    }
+}
+
+impl PartialEq<ArchivedInfallible> for Infallible {
+    #[inline]
+    fn eq(&self, _: &ArchivedInfallible) -> bool {
+        match *self {}
+    }
+}
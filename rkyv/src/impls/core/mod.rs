@@ -1,16 +1,16 @@
 use core::{
     alloc::Layout,
     cell::{Cell, UnsafeCell},
-    mem::ManuallyDrop,
-    ptr, str,
+    mem::{self, ManuallyDrop},
+    ptr, slice, str,
 };
 
 use ptr_meta::Pointee;
 use rancor::Fallible;
 
-#[cfg(feature = "copy")]
-use crate::copy::ArchiveCopyOptimize;
 use crate::{
+    copy::{ArchiveCopyOptimize, CopyOptimization},
+    de::AllocationLimiter,
     primitive::ArchivedUsize,
     ser::{Allocator, Writer, WriterExt as _},
     tuple::*,
@@ -18,10 +18,15 @@ use crate::{
     DeserializeUnsized, Portable, Serialize, SerializeUnsized,
 };
 
+mod cmp;
+mod convert;
+mod num;
 mod ops;
 mod option;
 mod primitive;
 mod result;
+mod sync;
+mod task;
 mod time;
 
 impl<T> ArchivePointee for T {
@@ -35,6 +40,9 @@ impl<T> ArchivePointee for T {
 }
 
 impl<T: Archive> ArchiveUnsized for T {
+    const UNSIZED_COPY_OPTIMIZATION: CopyOptimization<Self> =
+        T::COPY_OPTIMIZATION;
+
     type Archived = T::Archived;
 
     #[inline]
@@ -52,9 +60,11 @@ where
     }
 }
 
-impl<T: Archive, D: Fallible + ?Sized> DeserializeUnsized<T, D> for T::Archived
+impl<T, D> DeserializeUnsized<T, D> for T::Archived
 where
+    T: Archive,
     T::Archived: Deserialize<T, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
 {
     #[inline]
     unsafe fn deserialize_unsized(
@@ -62,6 +72,7 @@ where
         deserializer: &mut D,
         mut alloc: impl FnMut(Layout) -> *mut u8,
     ) -> Result<*mut (), D::Error> {
+        deserializer.check_array::<T>(1)?;
         let deserialized = self.deserialize(deserializer)?;
 
         let layout = Layout::new::<T>();
@@ -162,6 +173,10 @@ impl_tuple!(
 );
 
 impl<T: Archive, const N: usize> Archive for [T; N] {
+    const COPY_OPTIMIZATION: CopyOptimization<Self> = unsafe {
+        CopyOptimization::enable_if(T::COPY_OPTIMIZATION.is_enabled())
+    };
+
     type Archived = [T::Archived; N];
     type Resolver = [T::Resolver; N];
 
@@ -172,6 +187,15 @@ impl<T: Archive, const N: usize> Archive for [T; N] {
         resolver: Self::Resolver,
         out: *mut Self::Archived,
     ) {
+        if Self::COPY_OPTIMIZATION.is_enabled() {
+            ptr::copy_nonoverlapping(
+                (self as *const Self).cast::<u8>(),
+                out.cast::<u8>(),
+                core::mem::size_of::<Self>(),
+            );
+            return;
+        }
+
         let mut resolvers = core::mem::MaybeUninit::new(resolver);
         let resolvers_ptr = resolvers.as_mut_ptr().cast::<T::Resolver>();
         let out_ptr = out.cast::<T::Archived>();
@@ -185,6 +209,13 @@ impl<T: Archive, const N: usize> Archive for [T; N] {
     }
 }
 
+// SAFETY: Arrays of copy-optimized types have the same layout as their
+// archived counterparts and no padding between elements.
+unsafe impl<T: ArchiveCopyOptimize, const N: usize> ArchiveCopyOptimize
+    for [T; N]
+{
+}
+
 impl<T, S, const N: usize> Serialize<S> for [T; N]
 where
     T: Serialize<S>,
@@ -226,6 +257,12 @@ where
 }
 
 impl<T: Archive> ArchiveUnsized for [T] {
+    // Slices have no padding between their elements, so they are copies of
+    // their archived representation whenever their elements are.
+    const UNSIZED_COPY_OPTIMIZATION: CopyOptimization<Self> = unsafe {
+        CopyOptimization::enable_if(T::COPY_OPTIMIZATION.is_enabled())
+    };
+
     type Archived = [T::Archived];
 
     fn archived_metadata(&self) -> ArchivedMetadata<Self> {
@@ -249,105 +286,121 @@ where
     T: Serialize<S>,
     S: Fallible + Allocator + Writer + ?Sized,
 {
-    default! {
-        fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
-            use crate::util::ScratchVec;
+    fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
+        use crate::util::ScratchVec;
+
+        if T::COPY_OPTIMIZATION.is_enabled() {
+            let result = serializer.align_for::<T::Archived>()?;
+            // SAFETY: `T` is copy-optimized, so the bytes of the slice are the
+            // same as the bytes of its archived form.
+            let bytes = unsafe {
+                core::slice::from_raw_parts(
+                    self.as_ptr().cast::<u8>(),
+                    core::mem::size_of_val(self),
+                )
+            };
+            serializer.write(bytes)?;
+            return Ok(result);
+        }
 
+        if mem::size_of::<T::Resolver>() == 0 {
+            // Zero-sized resolvers don't carry any data from serializing to
+            // resolving, so they don't need to be kept in scratch space.
+            for value in self.iter() {
+                mem::forget(value.serialize(serializer)?);
+            }
+            let result = serializer.align_for::<T::Archived>()?;
             unsafe {
-                let mut resolvers = ScratchVec::new(serializer, self.len())?;
-
-                for value in self.iter() {
-                    resolvers.push(value.serialize(serializer)?);
-                }
-                let result = serializer.align_for::<T::Archived>()?;
-                for (value, resolver) in self.iter().zip(resolvers.drain(..)) {
-                    serializer.resolve_aligned(value, resolver)?;
-                }
-
-                resolvers.free(serializer)?;
-
-                Ok(result)
+                resolve_slice_zst_resolvers(self, result, serializer)?;
             }
+            return Ok(result);
         }
-    }
-}
 
-#[cfg(feature = "copy")]
-impl<T, S> SerializeUnsized<S> for [T]
-where
-    T: Serialize<S> + crate::copy::ArchiveCopyOptimize,
-    S: Allocator + Writer + ?Sized,
-{
-    fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, E> {
         unsafe {
-            let result = serializer.align_for::<T>()?;
-            if !self.is_empty() {
-                let bytes = core::slice::from_raw_parts(
-                    (self.as_ptr() as *const T).cast::<u8>(),
-                    self.len() * core::mem::size_of::<T>(),
-                );
-                serializer.write(bytes)?;
+            let mut resolvers = ScratchVec::new(serializer, self.len())?;
+
+            for value in self.iter() {
+                resolvers.push(value.serialize(serializer)?);
+            }
+            let result = serializer.align_for::<T::Archived>()?;
+            for (value, resolver) in self.iter().zip(resolvers.drain(..)) {
+                serializer.resolve_aligned(value, resolver)?;
             }
+
+            resolvers.free(serializer)?;
+
             Ok(result)
         }
     }
-
-    #[inline]
-    fn serialize_metadata(
-        &self,
-        _: &mut S,
-    ) -> Result<Self::MetadataResolver, E> {
-        Ok(())
-    }
 }
 
-impl<T, U, D> DeserializeUnsized<[U], D> for [T]
+/// Resolves the given values with zero-sized resolvers and writes them in
+/// batches.
+///
+/// # Safety
+///
+/// - `T::Resolver` must be a zero-sized type.
+/// - Each value must have been serialized, and its resolver forgotten.
+/// - The serializer must be at `pos`, which must be aligned for `T::Archived`.
+unsafe fn resolve_slice_zst_resolvers<T, S>(
+    values: &[T],
+    mut pos: usize,
+    serializer: &mut S,
+) -> Result<(), S::Error>
 where
-    T: Deserialize<U, D>,
-    D: Fallible + ?Sized,
+    T: Archive,
+    S: Fallible + Writer + ?Sized,
 {
-    default! {
-        unsafe fn deserialize_unsized(&self, deserializer: &mut D, mut alloc: impl FnMut(Layout) -> *mut u8) -> Result<*mut (), D::Error> {
-            if self.is_empty() || core::mem::size_of::<U>() == 0 {
-                Ok(ptr::NonNull::<U>::dangling().as_ptr().cast())
-            } else {
-                let result = alloc(Layout::array::<U>(self.len()).unwrap()).cast::<U>();
-                assert!(!result.is_null());
-                for (i, item) in self.iter().enumerate() {
-                    result.add(i).write(item.deserialize(deserializer)?);
-                }
-                Ok(result.cast())
-            }
+    const BATCH_LEN: usize = 16;
+    const MAX_BATCH_SIZE: usize = 4096;
+
+    let size = mem::size_of::<T::Archived>();
+    if size * BATCH_LEN > MAX_BATCH_SIZE {
+        for value in values.iter() {
+            let resolver = ptr::NonNull::<T::Resolver>::dangling().read();
+            serializer.resolve_aligned(value, resolver)?;
         }
+        return Ok(());
     }
 
-    default! {
-        #[inline]
-        fn deserialize_metadata(&self, _: &mut D) -> Result<<[U] as Pointee>::Metadata, D::Error> {
-            Ok(ptr_meta::metadata(self))
+    let mut batch = mem::MaybeUninit::<[T::Archived; BATCH_LEN]>::uninit();
+    let out = batch.as_mut_ptr().cast::<T::Archived>();
+    for chunk in values.chunks(BATCH_LEN) {
+        // Zero the batch so that padding bytes are written as zeroes
+        batch.as_mut_ptr().write_bytes(0, 1);
+        for (i, value) in chunk.iter().enumerate() {
+            // Reading a zero-sized type from a dangling pointer is sound, and
+            // the resolver that this replaces was forgotten.
+            let resolver = ptr::NonNull::<T::Resolver>::dangling().read();
+            value.resolve(pos + i * size, resolver, out.add(i));
         }
+        let len = chunk.len() * size;
+        serializer.write(slice::from_raw_parts(out.cast::<u8>(), len))?;
+        pos += len;
     }
+    Ok(())
 }
 
-#[cfg(feature = "copy")]
 impl<T, U, D> DeserializeUnsized<[U], D> for [T]
 where
     T: Deserialize<U, D>,
-    U: ArchiveCopyOptimize,
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
 {
     unsafe fn deserialize_unsized(
         &self,
-        _: &mut D,
+        deserializer: &mut D,
         mut alloc: impl FnMut(Layout) -> *mut u8,
-    ) -> Result<*mut (), E> {
-        if self.is_empty() || core::mem::size_of::<T>() == 0 {
+    ) -> Result<*mut (), D::Error> {
+        deserializer.check_array::<U>(self.len())?;
+        if self.is_empty() || core::mem::size_of::<U>() == 0 {
             Ok(ptr::NonNull::<U>::dangling().as_ptr().cast())
         } else {
             let result =
-                alloc(Layout::array::<T>(self.len()).unwrap()).cast::<T>();
+                alloc(Layout::array::<U>(self.len()).unwrap()).cast::<U>();
             assert!(!result.is_null());
-            ptr::copy_nonoverlapping(self.as_ptr(), result, self.len());
+            for (i, item) in self.iter().enumerate() {
+                result.add(i).write(item.deserialize(deserializer)?);
+            }
             Ok(result.cast())
         }
     }
@@ -356,7 +409,7 @@ where
     fn deserialize_metadata(
         &self,
         _: &mut D,
-    ) -> Result<<[T] as Pointee>::Metadata, E> {
+    ) -> Result<<[U] as Pointee>::Metadata, D::Error> {
         Ok(ptr_meta::metadata(self))
     }
 }
@@ -366,6 +419,9 @@ where
 unsafe impl Portable for str {}
 
 impl ArchiveUnsized for str {
+    const UNSIZED_COPY_OPTIMIZATION: CopyOptimization<Self> =
+        unsafe { CopyOptimization::enable_if(true) };
+
     type Archived = str;
 
     #[inline]
@@ -388,19 +444,27 @@ impl ArchivePointee for str {
 impl<S: Fallible + Writer + ?Sized> SerializeUnsized<S> for str {
     #[inline]
     fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
+        if let Some(pos) = serializer.find_str(self) {
+            return Ok(pos);
+        }
         let result = serializer.pos();
         serializer.write(self.as_bytes())?;
+        serializer.record_str(self, result)?;
         Ok(result)
     }
 }
 
-impl<D: Fallible + ?Sized> DeserializeUnsized<str, D> for str {
+impl<D> DeserializeUnsized<str, D> for str
+where
+    D: Fallible + AllocationLimiter + ?Sized,
+{
     #[inline]
     unsafe fn deserialize_unsized(
         &self,
-        _: &mut D,
+        deserializer: &mut D,
         mut alloc: impl FnMut(Layout) -> *mut u8,
     ) -> Result<*mut (), D::Error> {
+        deserializer.check_array::<u8>(self.len())?;
         if self.is_empty() {
             Ok(ptr::NonNull::dangling().as_ptr())
         } else {
@@ -424,8 +488,10 @@ impl<D: Fallible + ?Sized> DeserializeUnsized<str, D> for str {
 
 unsafe impl<T: Portable> Portable for ManuallyDrop<T> {}
 
+// Archived values are never dropped, so `ManuallyDrop` archives as the
+// archived type of the value it wraps.
 impl<T: Archive> Archive for ManuallyDrop<T> {
-    type Archived = ManuallyDrop<T::Archived>;
+    type Archived = T::Archived;
     type Resolver = T::Resolver;
 
     #[inline]
@@ -435,7 +501,7 @@ impl<T: Archive> Archive for ManuallyDrop<T> {
         resolver: Self::Resolver,
         out: *mut Self::Archived,
     ) {
-        T::resolve(self, pos, resolver, out.cast::<T::Archived>())
+        T::resolve(self, pos, resolver, out)
     }
 }
 
@@ -449,7 +515,7 @@ impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for ManuallyDrop<T> {
     }
 }
 
-impl<T, D> Deserialize<ManuallyDrop<T>, D> for ManuallyDrop<T::Archived>
+impl<T, D> Deserialize<ManuallyDrop<T>, D> for T::Archived
 where
     T: Archive,
     T::Archived: Deserialize<T, D>,
@@ -0,0 +1,74 @@
+use core::num::FpCategory;
+
+use rancor::Fallible;
+
+use crate::{num::ArchivedFpCategory, Archive, Deserialize, Serialize};
+
+impl Archive for FpCategory {
+    type Archived = ArchivedFpCategory;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        _: usize,
+        _: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        out.write(ArchivedFpCategory::from(*self));
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for FpCategory {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<FpCategory, D> for ArchivedFpCategory {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<FpCategory, D::Error> {
+        Ok(FpCategory::from(*self))
+    }
+}
+
+impl From<FpCategory> for ArchivedFpCategory {
+    #[inline]
+    fn from(category: FpCategory) -> Self {
+        match category {
+            FpCategory::Nan => ArchivedFpCategory::Nan,
+            FpCategory::Infinite => ArchivedFpCategory::Infinite,
+            FpCategory::Zero => ArchivedFpCategory::Zero,
+            FpCategory::Subnormal => ArchivedFpCategory::Subnormal,
+            FpCategory::Normal => ArchivedFpCategory::Normal,
+        }
+    }
+}
+
+impl From<ArchivedFpCategory> for FpCategory {
+    #[inline]
+    fn from(category: ArchivedFpCategory) -> Self {
+        match category {
+            ArchivedFpCategory::Nan => FpCategory::Nan,
+            ArchivedFpCategory::Infinite => FpCategory::Infinite,
+            ArchivedFpCategory::Zero => FpCategory::Zero,
+            ArchivedFpCategory::Subnormal => FpCategory::Subnormal,
+            ArchivedFpCategory::Normal => FpCategory::Normal,
+        }
+    }
+}
+
+impl PartialEq<FpCategory> for ArchivedFpCategory {
+    #[inline]
+    fn eq(&self, other: &FpCategory) -> bool {
+        FpCategory::from(*self) == *other
+    }
+}
+
+impl PartialEq<ArchivedFpCategory> for FpCategory {
+    #[inline]
+    fn eq(&self, other: &ArchivedFpCategory) -> bool {
+        other.eq(self)
+    }
+}
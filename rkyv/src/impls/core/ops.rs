@@ -1,13 +1,18 @@
-use core::ops::{
-    Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive,
+use core::{
+    hint::unreachable_unchecked,
+    ops::{
+        ControlFlow, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo,
+        RangeToInclusive,
+    },
+    ptr,
 };
 
 use rancor::Fallible;
 
 use crate::{
     ops::{
-        ArchivedRange, ArchivedRangeFrom, ArchivedRangeInclusive,
-        ArchivedRangeTo, ArchivedRangeToInclusive,
+        ArchivedControlFlow, ArchivedRange, ArchivedRangeFrom,
+        ArchivedRangeInclusive, ArchivedRangeTo, ArchivedRangeToInclusive,
     },
     Archive, Archived, Deserialize, Portable, Serialize,
 };
@@ -325,3 +330,109 @@ impl<T, U: PartialEq<T>> PartialEq<RangeToInclusive<T>>
         self.end.eq(&other.end)
     }
 }
+
+// ControlFlow
+
+#[allow(dead_code)]
+#[repr(u8)]
+enum ArchivedControlFlowTag {
+    Continue = 0,
+    Break = 1,
+}
+
+#[repr(C)]
+struct ArchivedControlFlowVariantContinue<C>(ArchivedControlFlowTag, C);
+
+#[repr(C)]
+struct ArchivedControlFlowVariantBreak<B>(ArchivedControlFlowTag, B);
+
+impl<B: Archive, C: Archive> Archive for ControlFlow<B, C> {
+    type Archived = ArchivedControlFlow<B::Archived, C::Archived>;
+    type Resolver = ControlFlow<B::Resolver, C::Resolver>;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        match resolver {
+            ControlFlow::Continue(resolver) => {
+                let out = out
+                    .cast::<ArchivedControlFlowVariantContinue<C::Archived>>();
+                ptr::addr_of_mut!((*out).0)
+                    .write(ArchivedControlFlowTag::Continue);
+
+                let (fp, fo) = out_field!(out.1);
+                match self {
+                    ControlFlow::Continue(value) => {
+                        value.resolve(pos + fp, resolver, fo)
+                    }
+                    ControlFlow::Break(_) => unreachable_unchecked(),
+                }
+            }
+            ControlFlow::Break(resolver) => {
+                let out =
+                    out.cast::<ArchivedControlFlowVariantBreak<B::Archived>>();
+                ptr::addr_of_mut!((*out).0)
+                    .write(ArchivedControlFlowTag::Break);
+
+                let (fp, fo) = out_field!(out.1);
+                match self {
+                    ControlFlow::Continue(_) => unreachable_unchecked(),
+                    ControlFlow::Break(value) => {
+                        value.resolve(pos + fp, resolver, fo)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<B, C, S> Serialize<S> for ControlFlow<B, C>
+where
+    B: Serialize<S>,
+    C: Serialize<S>,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        Ok(match self {
+            ControlFlow::Continue(value) => {
+                ControlFlow::Continue(value.serialize(serializer)?)
+            }
+            ControlFlow::Break(value) => {
+                ControlFlow::Break(value.serialize(serializer)?)
+            }
+        })
+    }
+}
+
+impl<B, C, D> Deserialize<ControlFlow<B, C>, D>
+    for ArchivedControlFlow<B::Archived, C::Archived>
+where
+    B: Archive,
+    C: Archive,
+    D: Fallible + ?Sized,
+    B::Archived: Deserialize<B, D>,
+    C::Archived: Deserialize<C, D>,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<ControlFlow<B, C>, D::Error> {
+        Ok(match self {
+            ArchivedControlFlow::Continue(value) => {
+                ControlFlow::Continue(value.deserialize(deserializer)?)
+            }
+            ArchivedControlFlow::Break(value) => {
+                ControlFlow::Break(value.deserialize(deserializer)?)
+            }
+        })
+    }
+}
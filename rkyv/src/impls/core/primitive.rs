@@ -10,6 +10,7 @@ use core::{
 use rancor::Fallible;
 
 use crate::{
+    copy::{ArchiveCopyOptimize, CopyOptimization},
     primitive::{
         ArchivedChar, ArchivedF32, ArchivedF64, ArchivedI128, ArchivedI16,
         ArchivedI32, ArchivedI64, ArchivedIsize, ArchivedNonZeroI128,
@@ -91,6 +92,9 @@ macro_rules! impl_serialize_noop {
 macro_rules! impl_portable_primitive {
     ($type:ty) => {
         impl Archive for $type {
+            const COPY_OPTIMIZATION: CopyOptimization<Self> =
+                CopyOptimization::enable();
+
             type Archived = Self;
             type Resolver = ();
 
@@ -105,6 +109,10 @@ macro_rules! impl_portable_primitive {
             }
         }
 
+        // SAFETY: Portable primitives archive as themselves and contain no
+        // padding.
+        unsafe impl ArchiveCopyOptimize for $type {}
+
         impl_serialize_noop!($type);
 
         impl<D: Fallible + ?Sized> Deserialize<$type, D> for Archived<$type> {
@@ -136,6 +144,13 @@ impl_portable_primitives! {
 macro_rules! impl_multibyte_primitive {
     ($archived:ident: $type:ty) => {
         impl Archive for $type {
+            #[cfg(any(
+                all(target_endian = "little", not(feature = "big_endian")),
+                all(target_endian = "big", feature = "big_endian"),
+            ))]
+            const COPY_OPTIMIZATION: CopyOptimization<Self> =
+                CopyOptimization::enable();
+
             type Archived = $archived;
             type Resolver = ();
 
@@ -150,6 +165,15 @@ macro_rules! impl_multibyte_primitive {
             }
         }
 
+        // SAFETY: Multibyte primitives have the same representation as their
+        // archived counterparts when the archive endianness matches the target
+        // endianness.
+        #[cfg(any(
+            all(target_endian = "little", not(feature = "big_endian")),
+            all(target_endian = "big", feature = "big_endian"),
+        ))]
+        unsafe impl ArchiveCopyOptimize for $type {}
+
         impl_serialize_noop!($type);
 
         impl<D: Fallible + ?Sized> Deserialize<$type, D> for $archived {
@@ -196,6 +220,9 @@ impl_multibyte_primitives! {
 unsafe impl<T: ?Sized> Portable for PhantomData<T> {}
 
 impl<T: ?Sized> Archive for PhantomData<T> {
+    const COPY_OPTIMIZATION: CopyOptimization<Self> =
+        CopyOptimization::enable();
+
     type Archived = PhantomData<T>;
     type Resolver = ();
 
@@ -209,6 +236,9 @@ impl<T: ?Sized> Archive for PhantomData<T> {
     }
 }
 
+// SAFETY: `PhantomData` is zero-sized and archives as itself.
+unsafe impl<T: ?Sized> ArchiveCopyOptimize for PhantomData<T> {}
+
 impl<T: ?Sized, S: Fallible + ?Sized> Serialize<S> for PhantomData<T> {
     #[inline]
     fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
@@ -230,6 +260,9 @@ impl<T: ?Sized, D: Fallible + ?Sized> Deserialize<PhantomData<T>, D>
 unsafe_impl_portable!(PhantomPinned);
 
 impl Archive for PhantomPinned {
+    const COPY_OPTIMIZATION: CopyOptimization<Self> =
+        CopyOptimization::enable();
+
     type Archived = PhantomPinned;
     type Resolver = ();
 
@@ -243,6 +276,9 @@ impl Archive for PhantomPinned {
     }
 }
 
+// SAFETY: `PhantomPinned` is zero-sized and archives as itself.
+unsafe impl ArchiveCopyOptimize for PhantomPinned {}
+
 impl<S: Fallible + ?Sized> Serialize<S> for PhantomPinned {
     #[inline]
     fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
@@ -0,0 +1,80 @@
+use core::sync::atomic::Ordering;
+
+use rancor::Fallible;
+
+use crate::{sync::ArchivedAtomicOrdering, Archive, Deserialize, Serialize};
+
+impl Archive for Ordering {
+    type Archived = ArchivedAtomicOrdering;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        _: usize,
+        _: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        out.write(ArchivedAtomicOrdering::from(*self));
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for Ordering {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<D> Deserialize<Ordering, D> for ArchivedAtomicOrdering
+where
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<Ordering, D::Error> {
+        Ok(Ordering::from(*self))
+    }
+}
+
+impl From<Ordering> for ArchivedAtomicOrdering {
+    #[inline]
+    fn from(ordering: Ordering) -> Self {
+        match ordering {
+            Ordering::Relaxed => ArchivedAtomicOrdering::Relaxed,
+            Ordering::Release => ArchivedAtomicOrdering::Release,
+            Ordering::Acquire => ArchivedAtomicOrdering::Acquire,
+            Ordering::AcqRel => ArchivedAtomicOrdering::AcqRel,
+            Ordering::SeqCst => ArchivedAtomicOrdering::SeqCst,
+            // `Ordering` is non-exhaustive. Falling back to the strongest
+            // ordering is always correct, if potentially slower.
+            _ => ArchivedAtomicOrdering::SeqCst,
+        }
+    }
+}
+
+impl From<ArchivedAtomicOrdering> for Ordering {
+    #[inline]
+    fn from(ordering: ArchivedAtomicOrdering) -> Self {
+        match ordering {
+            ArchivedAtomicOrdering::Relaxed => Ordering::Relaxed,
+            ArchivedAtomicOrdering::Release => Ordering::Release,
+            ArchivedAtomicOrdering::Acquire => Ordering::Acquire,
+            ArchivedAtomicOrdering::AcqRel => Ordering::AcqRel,
+            ArchivedAtomicOrdering::SeqCst => Ordering::SeqCst,
+        }
+    }
+}
+
+impl PartialEq<Ordering> for ArchivedAtomicOrdering {
+    #[inline]
+    fn eq(&self, other: &Ordering) -> bool {
+        Ordering::from(*self) == *other
+    }
+}
+
+impl PartialEq<ArchivedAtomicOrdering> for Ordering {
+    #[inline]
+    fn eq(&self, other: &ArchivedAtomicOrdering) -> bool {
+        other.eq(self)
+    }
+}
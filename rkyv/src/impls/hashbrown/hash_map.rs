@@ -1,14 +1,22 @@
 use core::{
     borrow::Borrow,
     hash::{BuildHasher, Hash},
+    mem::size_of,
 };
 
 use hashbrown::HashMap;
-use rancor::{Error, Fallible};
+use rancor::{Error, Fallible, ResultExt as _, Trace};
 
 use crate::{
-    collections::swiss_table::map::{ArchivedHashMap, HashMapResolver},
+    collections::{
+        btree_map::ArchivedBTreeMap,
+        swiss_table::map::{ArchivedHashMap, HashMapResolver},
+    },
+    de::{reserve, AllocationLimiter, PathSegment, Reserve},
+    hash::Hashed,
     ser::{Allocator, Writer},
+    util::AllocationFailed,
+    with::{ArchiveWith, DeserializeWith, Prehashed, SerializeWith},
     Archive, Deserialize, Serialize,
 };
 
@@ -49,11 +57,42 @@ where
         &self,
         serializer: &mut S,
     ) -> Result<Self::Resolver, S::Error> {
-        ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
-            self.iter(),
-            (7, 8),
-            serializer,
-        )
+        #[cfg(feature = "hooks")]
+        serializer.enter_type(core::any::type_name::<Self>())?;
+        serializer.begin_collection(self.len())?;
+        let resolver =
+            ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
+                self.iter(),
+                (7, 8),
+                serializer,
+            )?;
+        serializer.end_collection(self.len())?;
+        #[cfg(feature = "hooks")]
+        serializer.exit_type();
+        Ok(resolver)
+    }
+}
+
+impl<K, V, S> Reserve for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        HashMap::reserve(self, additional);
+    }
+
+    #[inline]
+    fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), AllocationFailed> {
+        HashMap::try_reserve(self, additional).map_err(|_| {
+            AllocationFailed::new(
+                size_of::<(K, V)>().saturating_mul(additional),
+            )
+        })
     }
 }
 
@@ -64,7 +103,8 @@ where
     K::Archived: Deserialize<K, D> + Hash + Eq,
     V: Archive,
     V::Archived: Deserialize<V, D>,
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
     S: Default + BuildHasher,
 {
     #[inline]
@@ -72,12 +112,47 @@ where
         &self,
         deserializer: &mut D,
     ) -> Result<HashMap<K, V, S>, D::Error> {
-        let mut result =
-            HashMap::with_capacity_and_hasher(self.len(), S::default());
-        for (k, v) in self.iter() {
+        deserializer.check_array::<(K, V)>(self.len())?;
+        let mut result = HashMap::with_hasher(S::default());
+        reserve(&mut result, self.len(), deserializer)?;
+        for (i, (k, v)) in self.iter().enumerate() {
             result.insert(
-                k.deserialize(deserializer)?,
-                v.deserialize(deserializer)?,
+                k.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Key(i))?,
+                v.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Value(i))?,
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Deserializes the entries of the B-tree map into a hash map.
+impl<K, V, D, S> Deserialize<HashMap<K, V, S>, D>
+    for ArchivedBTreeMap<K::Archived, V::Archived>
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
+    S: Default + BuildHasher,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<HashMap<K, V, S>, D::Error> {
+        deserializer.check_array::<(K, V)>(self.len())?;
+        let mut result = HashMap::with_hasher(S::default());
+        reserve(&mut result, self.len(), deserializer)?;
+        for (i, (k, v)) in self.iter().enumerate() {
+            result.insert(
+                k.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Key(i))?,
+                v.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Value(i))?,
             );
         }
         Ok(result)
@@ -115,6 +190,193 @@ where
     }
 }
 
+impl<K, V, H> ArchiveWith<HashMap<Hashed<K>, V, H>> for Prehashed
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Hash + Eq,
+    V: Archive,
+{
+    type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+    type Resolver = HashMapResolver;
+
+    unsafe fn resolve_with(
+        field: &HashMap<Hashed<K>, V, H>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashMap::resolve_from_len(
+            field.len(),
+            (7, 8),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, V, H, S> SerializeWith<HashMap<Hashed<K>, V, H>, S> for Prehashed
+where
+    K: Serialize<S> + Hash + Eq,
+    K::Archived: Hash + Eq,
+    V: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+{
+    fn serialize_with(
+        field: &HashMap<Hashed<K>, V, H>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_hashed_iter(
+            field
+                .iter()
+                .map(|(key, value)| (key.cached_hash(), key.get(), value)),
+            (7, 8),
+            serializer,
+        )
+    }
+}
+
+impl<K, V, H, D>
+    DeserializeWith<
+        ArchivedHashMap<K::Archived, V::Archived>,
+        HashMap<Hashed<K>, V, H>,
+        D,
+    > for Prehashed
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    H: Default + BuildHasher,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedHashMap<K::Archived, V::Archived>,
+        deserializer: &mut D,
+    ) -> Result<HashMap<Hashed<K>, V, H>, D::Error> {
+        let mut result =
+            HashMap::with_capacity_and_hasher(field.len(), H::default());
+        for (key, value) in field.iter() {
+            result.insert(
+                Hashed::new(key.deserialize(deserializer)?),
+                value.deserialize(deserializer)?,
+            );
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+mod allocator_api {
+    use core::{
+        alloc::Allocator,
+        hash::{BuildHasher, Hash},
+    };
+
+    use hashbrown::HashMap;
+    use rancor::{Error, Fallible};
+
+    use crate::{
+        collections::swiss_table::map::{ArchivedHashMap, HashMapResolver},
+        de::AllocProvider,
+        ser::{Allocator as SerAllocator, Writer},
+        Archive, Deserialize, Serialize,
+    };
+
+    impl<'a, K, V, S, B> Archive for HashMap<K, V, S, &'a B>
+    where
+        K: Archive + Hash + Eq,
+        K::Archived: Hash + Eq,
+        V: Archive,
+        B: ?Sized,
+        &'a B: Allocator,
+    {
+        type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+        type Resolver = HashMapResolver;
+
+        #[inline]
+        unsafe fn resolve(
+            &self,
+            pos: usize,
+            resolver: Self::Resolver,
+            out: *mut Self::Archived,
+        ) {
+            ArchivedHashMap::resolve_from_len(
+                self.len(),
+                (7, 8),
+                pos,
+                resolver,
+                out,
+            );
+        }
+    }
+
+    impl<'a, K, V, S, RS, B> Serialize<S> for HashMap<K, V, RS, &'a B>
+    where
+        K: Serialize<S> + Hash + Eq,
+        K::Archived: Hash + Eq,
+        V: Serialize<S>,
+        S: Fallible + Writer + SerAllocator + ?Sized,
+        S::Error: Error,
+        B: ?Sized,
+        &'a B: Allocator,
+    {
+        #[inline]
+        fn serialize(
+            &self,
+            serializer: &mut S,
+        ) -> Result<Self::Resolver, S::Error> {
+            #[cfg(feature = "hooks")]
+            serializer.enter_type(core::any::type_name::<Self>())?;
+            serializer.begin_collection(self.len())?;
+            let resolver =
+                ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
+                    self.iter(),
+                    (7, 8),
+                    serializer,
+                )?;
+            serializer.end_collection(self.len())?;
+            #[cfg(feature = "hooks")]
+            serializer.exit_type();
+            Ok(resolver)
+        }
+    }
+
+    impl<'a, K, V, D, S, B> Deserialize<HashMap<K, V, S, &'a B>, D>
+        for ArchivedHashMap<K::Archived, V::Archived>
+    where
+        K: Archive + Hash + Eq,
+        K::Archived: Deserialize<K, D> + Hash + Eq,
+        V: Archive,
+        V::Archived: Deserialize<V, D>,
+        D: Fallible + AllocProvider<&'a B> + AllocationLimiter + ?Sized,
+        S: Default + BuildHasher,
+        B: ?Sized,
+        &'a B: Allocator,
+    {
+        #[inline]
+        fn deserialize(
+            &self,
+            deserializer: &mut D,
+        ) -> Result<HashMap<K, V, S, &'a B>, D::Error> {
+            deserializer.check_array::<(K, V)>(self.len())?;
+            let mut result = HashMap::with_capacity_and_hasher_in(
+                self.len(),
+                S::default(),
+                deserializer.allocator(),
+            );
+            for (k, v) in self.iter() {
+                result.insert(
+                    k.deserialize(deserializer)?,
+                    v.deserialize(deserializer)?,
+                );
+            }
+            Ok(result)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(all(feature = "alloc", not(feature = "std")))]
@@ -156,6 +418,59 @@ mod tests {
         assert_eq!(value, deserialized);
     }
 
+    #[test]
+    fn prehashed_hash_map() {
+        use crate::{hash::Hashed, with::Prehashed, Archive, Serialize};
+
+        #[derive(Archive, Serialize)]
+        #[archive(crate)]
+        struct Example {
+            #[with(Prehashed)]
+            map: HashMap<Hashed<String>, u32>,
+        }
+
+        let value = Example {
+            map: (0..1000)
+                .map(|i| (Hashed::new(i.to_string()), i))
+                .collect::<HashMap<_, _>>(),
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            unsafe { access_unchecked::<ArchivedExample>(bytes.as_ref()) };
+
+        assert_eq!(archived.map.len(), 1000);
+        for (key, value) in value.map.iter() {
+            assert_eq!(
+                archived.map.get(key.get().as_str()).map(|v| v.to_native()),
+                Some(*value),
+            );
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic = "is different from the hash used for lookups"]
+    fn serialize_from_hashed_iter_wrong_hash() {
+        use rancor::Strategy;
+
+        use crate::{
+            hash::{hash_value, FxHasher64},
+            ser::AllocSerializer,
+        };
+
+        type ArchivedMap = ArchivedHashMap<ArchivedString, ArchivedString>;
+
+        let keys = (0..100).map(|i| i.to_string()).collect::<Vec<String>>();
+        let mut serializer = AllocSerializer::<256>::default();
+        let _ = ArchivedMap::serialize_from_hashed_iter(
+            keys.iter().map(|key| {
+                (hash_value::<String, FxHasher64>(key) ^ 1, key, key)
+            }),
+            (7, 8),
+            Strategy::<_, Failure>::wrap(&mut serializer),
+        );
+    }
+
     #[cfg(feature = "bytecheck")]
     #[test]
     fn validate_index_map() {
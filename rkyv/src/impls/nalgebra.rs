@@ -0,0 +1,138 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use nalgebra::{DMatrix, DMatrixView, Dyn, VecStorage};
+use rancor::{Error, Fallible};
+
+use crate::{
+    collections::matrix::{ArchivedMatrix, MatrixResolver},
+    de::AllocationLimiter,
+    ser::{Allocator, Writer},
+    Archive, Deserialize, Serialize,
+};
+
+impl<T: nalgebra::Scalar> ArchivedMatrix<T> {
+    /// Returns a `nalgebra` view of the archived elements.
+    ///
+    /// The view borrows the archived elements directly without copying. Its
+    /// row stride is the number of columns and its column stride is one.
+    #[inline]
+    pub fn dmatrix_view(&self) -> DMatrixView<'_, T, Dyn, Dyn> {
+        let (rows, cols) = self.shape();
+        DMatrixView::from_slice_with_strides(
+            self.as_slice(),
+            rows,
+            cols,
+            cols,
+            1,
+        )
+    }
+}
+
+impl<T: Archive> Archive for DMatrix<T> {
+    type Archived = ArchivedMatrix<T::Archived>;
+    type Resolver = MatrixResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedMatrix::resolve_from_resolver(pos, resolver, out);
+    }
+}
+
+impl<T, S> Serialize<S> for DMatrix<T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+{
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        // `DMatrix` stores its elements in column-major order
+        let (rows, cols) = self.shape();
+        ArchivedMatrix::<T::Archived>::serialize_from_iter::<T, _, _>(
+            rows,
+            cols,
+            (0..rows * cols).map(|i| &self[(i / cols, i % cols)]),
+            serializer,
+        )
+    }
+}
+
+impl<T, D> Deserialize<DMatrix<T>, D> for ArchivedMatrix<T::Archived>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+{
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<DMatrix<T>, D::Error> {
+        let (rows, cols) = self.shape();
+        deserializer.check_array::<T>(self.len())?;
+        let mut data = Vec::with_capacity(self.len());
+        for col in 0..cols {
+            for row in 0..rows {
+                data.push(self[(row, col)].deserialize(deserializer)?);
+            }
+        }
+        Ok(DMatrix::from_data(VecStorage::new(
+            Dyn(rows),
+            Dyn(cols),
+            data,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DMatrix;
+    use rancor::Failure;
+
+    use crate::{access, collections::matrix::ArchivedMatrix, from_bytes};
+
+    #[test]
+    fn dmatrix_round_trip() {
+        let value =
+            DMatrix::from_row_slice(2, 3, &[1.5f32, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            access::<ArchivedMatrix<crate::Archived<f32>>, Failure>(&bytes)
+                .unwrap();
+        assert_eq!(archived.shape(), (2, 3));
+        assert_eq!(archived.row(0), &[1.5, 2.0, 3.0]);
+
+        let view = archived.dmatrix_view();
+        assert_eq!(view.shape(), (2, 3));
+        assert_eq!(view[(1, 0)], 4.0);
+        assert_eq!(
+            view.column(2).iter().copied().collect::<Vec<_>>(),
+            [3.0, 6.0]
+        );
+
+        let deserialized = from_bytes::<DMatrix<f32>, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    fn empty_dmatrix() {
+        let value = DMatrix::<u32>::zeros(0, 5);
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            access::<ArchivedMatrix<crate::Archived<u32>>, Failure>(&bytes)
+                .unwrap();
+        assert_eq!(archived.shape(), (0, 5));
+        assert!(archived.is_empty());
+        assert_eq!(archived.dmatrix_view().shape(), (0, 5));
+
+        let deserialized = from_bytes::<DMatrix<u32>, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized.shape(), (0, 5));
+    }
+}
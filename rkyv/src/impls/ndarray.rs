@@ -0,0 +1,158 @@
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+
+use ndarray::{Array2, ArrayView2};
+#[cfg(feature = "alloc")]
+use rancor::fail;
+use rancor::{Error, Fallible};
+
+#[cfg(feature = "alloc")]
+use crate::{
+    collections::matrix::MatrixShapeError, de::AllocationLimiter, Deserialize,
+};
+use crate::{
+    collections::matrix::{ArchivedMatrix, MatrixResolver},
+    ser::{Allocator, Writer},
+    Archive, Serialize, SerializeUnsized,
+};
+
+impl<T> ArchivedMatrix<T> {
+    /// Returns an `ndarray` view of the archived elements.
+    ///
+    /// The archived elements are stored contiguously and are always aligned
+    /// for `T`, so the view borrows them directly without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements is not `rows * cols`. This can only
+    /// happen if the matrix was accessed without validation.
+    #[inline]
+    pub fn view(&self) -> ArrayView2<'_, T> {
+        ArrayView2::from_shape(self.shape(), self.as_slice())
+            .expect("archived matrix has an invalid shape")
+    }
+}
+
+impl<T: Archive> Archive for Array2<T> {
+    type Archived = ArchivedMatrix<T::Archived>;
+    type Resolver = MatrixResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedMatrix::resolve_from_resolver(pos, resolver, out);
+    }
+}
+
+impl<T, S> Serialize<S> for Array2<T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+    [T]: SerializeUnsized<S>,
+{
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        let (rows, cols) = self.dim();
+        // Arrays in standard layout are already stored in row-major order
+        if let Some(slice) = self.as_slice() {
+            ArchivedMatrix::serialize_from_slice(rows, cols, slice, serializer)
+        } else {
+            ArchivedMatrix::<T::Archived>::serialize_from_iter::<T, _, _>(
+                rows,
+                cols,
+                self.iter(),
+                serializer,
+            )
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, D> Deserialize<Array2<T>, D> for ArchivedMatrix<T::Archived>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Error,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<Array2<T>, D::Error> {
+        deserializer.check_array::<T>(self.len())?;
+        let mut data = Vec::with_capacity(self.len());
+        for value in self.as_slice() {
+            data.push(value.deserialize(deserializer)?);
+        }
+        match Array2::from_shape_vec(self.shape(), data) {
+            Ok(array) => Ok(array),
+            Err(_) => fail!(MatrixShapeError::ShapeMismatch {
+                rows: self.rows(),
+                cols: self.cols(),
+                len: self.len(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, Array2, ShapeBuilder as _};
+    use rancor::Failure;
+
+    use crate::{access, collections::matrix::ArchivedMatrix, from_bytes};
+
+    #[test]
+    fn array2_round_trip() {
+        let value = array![[1u32, 2, 3], [4, 5, 6]];
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            access::<ArchivedMatrix<crate::Archived<u32>>, Failure>(&bytes)
+                .unwrap();
+        assert_eq!(archived.shape(), (2, 3));
+        assert_eq!(archived.row(1), &[4, 5, 6]);
+        assert_eq!(archived.view().dim(), (2, 3));
+        assert_eq!(archived.view()[(1, 0)], 4);
+        assert_eq!(archived.view().column(2).to_vec(), &[3, 6]);
+
+        let deserialized = from_bytes::<Array2<u32>, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    fn array2_non_standard_layout() {
+        // Column-major arrays are archived in row-major order
+        let value =
+            Array2::from_shape_vec((2, 3).f(), vec![1u8, 4, 2, 5, 3, 6])
+                .unwrap();
+        assert!(value.as_slice().is_none());
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<ArchivedMatrix<u8>, Failure>(&bytes).unwrap();
+        assert_eq!(archived.as_slice(), &[1, 2, 3, 4, 5, 6]);
+
+        let transposed = value.t().to_owned();
+        let bytes = crate::to_bytes::<_, 256, Failure>(&transposed).unwrap();
+        let deserialized = from_bytes::<Array2<u8>, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized, transposed);
+        assert_eq!(deserialized.dim(), (3, 2));
+    }
+
+    #[test]
+    fn empty_array2() {
+        let value = Array2::<u16>::zeros((4, 0));
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            access::<ArchivedMatrix<crate::Archived<u16>>, Failure>(&bytes)
+                .unwrap();
+        assert_eq!(archived.shape(), (4, 0));
+        assert_eq!(archived.iter_rows().count(), 4);
+        assert_eq!(archived.view().dim(), (4, 0));
+
+        let deserialized = from_bytes::<Array2<u16>, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized.dim(), (4, 0));
+    }
+}
@@ -0,0 +1,143 @@
+use ordered_float::OrderedFloat;
+use rancor::Fallible;
+
+use crate::{
+    ordered_float::ArchivedOrderedFloat, Archive, Deserialize, Serialize,
+};
+
+impl<F: Archive> Archive for OrderedFloat<F> {
+    type Archived = ArchivedOrderedFloat<F::Archived>;
+    type Resolver = F::Resolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        // Safety: `ArchivedOrderedFloat` is a transparent wrapper around
+        // `F::Archived`
+        self.0.resolve(pos, resolver, out.cast());
+    }
+}
+
+impl<F, S> Serialize<S> for OrderedFloat<F>
+where
+    F: Serialize<S>,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<F, D> Deserialize<OrderedFloat<F>, D> for ArchivedOrderedFloat<F::Archived>
+where
+    F: Archive,
+    F::Archived: Deserialize<F, D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<OrderedFloat<F>, D::Error> {
+        Ok(OrderedFloat(self.0.deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ordered_float::OrderedFloat;
+    use rancor::{Failure, Infallible};
+
+    use crate::{
+        access_unchecked, deserialize, ordered_float::ArchivedOrderedFloat,
+        primitive::ArchivedF64, Archived,
+    };
+
+    #[test]
+    fn ordered_float() {
+        let value = (OrderedFloat(1.5f32), OrderedFloat(f64::NAN));
+
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<(OrderedFloat<f32>, OrderedFloat<f64>)>>(
+                bytes.as_ref(),
+            )
+        };
+        assert_eq!(archived.0.into_inner(), 1.5);
+        assert_eq!(archived.0, value.0);
+        assert_eq!(archived.1, value.1);
+        assert_eq!(
+            archived.1,
+            ArchivedOrderedFloat::<ArchivedF64>::from_native(f64::NAN)
+        );
+        assert!(archived.1 > OrderedFloat(f64::INFINITY));
+
+        let deserialized = deserialize::<
+            (OrderedFloat<f32>, OrderedFloat<f64>),
+            _,
+            Infallible,
+        >(archived, &mut ())
+        .unwrap();
+        assert_eq!(value, deserialized);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn ordered_float_map_keys() {
+        use std::collections::{BTreeMap, HashMap};
+
+        use crate::{
+            collections::swiss_table::ArchivedHashMap, primitive::ArchivedU32,
+        };
+
+        let keys = [-0.0, 0.0, 1.5, -2.25, f64::NAN, f64::INFINITY];
+        let value = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (OrderedFloat(*k), i as u32))
+            .collect::<HashMap<_, _>>();
+
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe {
+            access_unchecked::<
+                ArchivedHashMap<ArchivedOrderedFloat<ArchivedF64>, ArchivedU32>,
+            >(bytes.as_ref())
+        };
+        assert_eq!(archived.len(), value.len());
+        for (key, v) in value.iter() {
+            let key = ArchivedOrderedFloat::<ArchivedF64>::from_native(key.0);
+            assert_eq!(archived.get(&key), Some(&ArchivedU32::from_native(*v)));
+        }
+        let missing = ArchivedOrderedFloat::<ArchivedF64>::from_native(3.0);
+        assert!(archived.get(&missing).is_none());
+
+        let value = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (OrderedFloat(*k), i as u32))
+            .collect::<BTreeMap<_, _>>();
+
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<OrderedFloat<f64>, u32>>>(
+                bytes.as_ref(),
+            )
+        };
+        for (key, v) in value.iter() {
+            let key = ArchivedOrderedFloat::<ArchivedF64>::from_native(key.0);
+            assert_eq!(archived.get(&key), Some(&ArchivedU32::from_native(*v)));
+        }
+        let archived_keys = archived.iter().map(|(k, _)| k.into_inner());
+        assert!(archived_keys
+            .zip(value.keys())
+            .all(|(a, b)| OrderedFloat(a) == *b));
+    }
+}
@@ -1,10 +1,17 @@
 use rancor::Fallible;
 
-use crate::{rend::*, Archive, Archived, Deserialize, Serialize};
+use crate::{
+    copy::{ArchiveCopyOptimize, CopyOptimization},
+    rend::*,
+    Archive, Archived, Deserialize, Serialize,
+};
 
 macro_rules! impl_rend_primitive {
     ($type:ty) => {
         impl Archive for $type {
+            const COPY_OPTIMIZATION: CopyOptimization<Self> =
+                CopyOptimization::enable();
+
             type Archived = Self;
             type Resolver = ();
 
@@ -21,8 +28,7 @@ macro_rules! impl_rend_primitive {
 
         // Safety: rend primitives always have the same representation archived
         // and unarchived and contain no padding
-        #[cfg(feature = "copy")]
-        unsafe impl crate::copy::ArchiveCopySafe for $type {}
+        unsafe impl ArchiveCopyOptimize for $type {}
 
         impl<S: Fallible + ?Sized> Serialize<S> for $type {
             #[inline]
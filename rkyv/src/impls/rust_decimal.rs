@@ -0,0 +1,153 @@
+use rancor::Fallible;
+use rust_decimal::Decimal;
+
+use crate::{rust_decimal::ArchivedDecimal, Archive, Deserialize, Serialize};
+
+impl Archive for Decimal {
+    type Archived = ArchivedDecimal;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        _: usize,
+        _: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        out.write(ArchivedDecimal::from_decimal(self));
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for Decimal {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<Decimal, D> for ArchivedDecimal {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<Decimal, D::Error> {
+        Ok(self.to_decimal())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use rancor::{Failure, Infallible};
+    use rust_decimal::Decimal;
+
+    use crate::{
+        access_unchecked, deserialize, rust_decimal::ArchivedDecimal, Archived,
+    };
+
+    fn decimal(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn decimal_round_trip() {
+        for s in [
+            "0",
+            "-0.000",
+            "1.50",
+            "-123456.789",
+            "79228162514264337593543950335",
+            "0.0000000000000000000000000001",
+        ] {
+            let value = decimal(s);
+
+            let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+            let archived =
+                unsafe { access_unchecked::<ArchivedDecimal>(bytes.as_ref()) };
+            assert_eq!(archived, &value);
+            assert_eq!(archived.scale(), value.scale());
+            assert_eq!(archived.is_sign_negative(), value.is_sign_negative());
+
+            let deserialized =
+                deserialize::<Decimal, _, Infallible>(archived, &mut ())
+                    .unwrap();
+            assert_eq!(deserialized.serialize(), value.serialize());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn decimal_map_keys() {
+        use std::collections::{BTreeMap, HashMap};
+
+        use crate::{
+            collections::swiss_table::ArchivedHashMap, string::ArchivedString,
+        };
+
+        let value = [("1.50", "one and a half"), ("-2", "minus two")]
+            .into_iter()
+            .map(|(k, v)| (decimal(k), v.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe {
+            access_unchecked::<ArchivedHashMap<ArchivedDecimal, ArchivedString>>(
+                bytes.as_ref(),
+            )
+        };
+        // Decimals with different scales but the same value are equal
+        let key = ArchivedDecimal::from_decimal(&decimal("1.5"));
+        assert_eq!(archived.get(&key).unwrap(), "one and a half");
+        let key = ArchivedDecimal::from_decimal(&decimal("-2.000"));
+        assert_eq!(archived.get(&key).unwrap(), "minus two");
+        let key = ArchivedDecimal::from_decimal(&decimal("2"));
+        assert!(archived.get(&key).is_none());
+
+        let value = ["3.25", "-1", "0.001"]
+            .into_iter()
+            .map(|k| (decimal(k), ()))
+            .collect::<BTreeMap<_, _>>();
+
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<Decimal, ()>>>(bytes.as_ref())
+        };
+        let key = ArchivedDecimal::from_decimal(&decimal("3.250"));
+        assert!(archived.contains_key(&key));
+        assert!(archived
+            .iter()
+            .map(|(k, _)| k)
+            .zip(value.keys())
+            .all(|(a, b)| a == b));
+    }
+
+    #[cfg(feature = "bytecheck")]
+    #[test]
+    fn validate_decimal() {
+        use crate::{access, primitive::ArchivedU32, util::AlignedVec};
+
+        let value = decimal("-12.345");
+        let bytes = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        assert_eq!(
+            access::<ArchivedDecimal, Failure>(bytes.as_ref()).unwrap(),
+            &value,
+        );
+
+        // The flags are the first field of the archived decimal, which is at
+        // the start of the buffer
+        let with_flags = |flags: u32| {
+            let mut bytes = bytes.clone();
+            unsafe {
+                bytes
+                    .as_mut_ptr()
+                    .cast::<ArchivedU32>()
+                    .write(ArchivedU32::from_native(flags));
+            }
+            bytes
+        };
+        let invalid_scale: AlignedVec = with_flags(29 << 16);
+        assert!(access::<ArchivedDecimal, Failure>(&invalid_scale).is_err());
+        let invalid_flags: AlignedVec = with_flags(3 << 16 | 1);
+        assert!(access::<ArchivedDecimal, Failure>(&invalid_flags).is_err());
+        let max_scale: AlignedVec = with_flags(0x8000_0000 | 28 << 16);
+        assert!(access::<ArchivedDecimal, Failure>(&max_scale).is_ok());
+    }
+}
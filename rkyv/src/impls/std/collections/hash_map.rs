@@ -1,14 +1,23 @@
 use core::{
     borrow::Borrow,
     hash::{BuildHasher, Hash},
+    mem::size_of,
 };
 use std::collections::HashMap;
 
-use rancor::{Error, Fallible};
+use rancor::{Error, Fallible, ResultExt as _, Strategy, Trace};
 
 use crate::{
-    collections::swiss_table::map::{ArchivedHashMap, HashMapResolver},
+    collections::{
+        btree_map::ArchivedBTreeMap,
+        swiss_table::{
+            map::{ArchivedHashMap, HashMapResolver},
+            ArchivedIndexMap,
+        },
+    },
+    de::{reserve, AllocationLimiter, PathSegment, Reserve},
     ser::{Allocator, Writer},
+    util::AllocationFailed,
     Archive, Deserialize, Serialize,
 };
 
@@ -49,11 +58,42 @@ where
         &self,
         serializer: &mut S,
     ) -> Result<Self::Resolver, S::Error> {
-        ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
-            self.iter(),
-            (7, 8),
-            serializer,
-        )
+        #[cfg(feature = "hooks")]
+        serializer.enter_type(core::any::type_name::<Self>())?;
+        serializer.begin_collection(self.len())?;
+        let resolver =
+            ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
+                self.iter(),
+                (7, 8),
+                serializer,
+            )?;
+        serializer.end_collection(self.len())?;
+        #[cfg(feature = "hooks")]
+        serializer.exit_type();
+        Ok(resolver)
+    }
+}
+
+impl<K, V, S> Reserve for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        HashMap::reserve(self, additional);
+    }
+
+    #[inline]
+    fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), AllocationFailed> {
+        HashMap::try_reserve(self, additional).map_err(|_| {
+            AllocationFailed::new(
+                size_of::<(K, V)>().saturating_mul(additional),
+            )
+        })
     }
 }
 
@@ -64,7 +104,8 @@ where
     K::Archived: Deserialize<K, D> + Hash + Eq,
     V: Archive,
     V::Archived: Deserialize<V, D>,
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
     S: Default + BuildHasher,
 {
     #[inline]
@@ -72,12 +113,47 @@ where
         &self,
         deserializer: &mut D,
     ) -> Result<HashMap<K, V, S>, D::Error> {
-        let mut result =
-            HashMap::with_capacity_and_hasher(self.len(), S::default());
-        for (k, v) in self.iter() {
+        deserializer.check_array::<(K, V)>(self.len())?;
+        let mut result = HashMap::with_hasher(S::default());
+        reserve(&mut result, self.len(), deserializer)?;
+        for (i, (k, v)) in self.iter().enumerate() {
             result.insert(
-                k.deserialize(deserializer)?,
-                v.deserialize(deserializer)?,
+                k.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Key(i))?,
+                v.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Value(i))?,
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Deserializes the entries of the B-tree map into a hash map.
+impl<K, V, D, S> Deserialize<HashMap<K, V, S>, D>
+    for ArchivedBTreeMap<K::Archived, V::Archived>
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
+    S: Default + BuildHasher,
+{
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<HashMap<K, V, S>, D::Error> {
+        deserializer.check_array::<(K, V)>(self.len())?;
+        let mut result = HashMap::with_hasher(S::default());
+        reserve(&mut result, self.len(), deserializer)?;
+        for (i, (k, v)) in self.iter().enumerate() {
+            result.insert(
+                k.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Key(i))?,
+                v.deserialize(deserializer)
+                    .with_trace(|| PathSegment::Value(i))?,
             );
         }
         Ok(result)
@@ -112,3 +188,70 @@ impl<K: Hash + Eq + Borrow<AK>, V, AK: Hash + Eq, AV: PartialEq<V>>
         other.eq(self)
     }
 }
+
+fn collect_into_hashmap<'a, I, K, V, KO, VO, S, D>(
+    iter: I,
+    map: &mut HashMap<KO, VO, S>,
+    deserializer: &mut D,
+) -> Result<(), D::Error>
+where
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+    K: Deserialize<KO, D> + 'a,
+    V: Deserialize<VO, D> + 'a,
+    KO: Hash + Eq,
+    S: BuildHasher,
+    D: Fallible + ?Sized,
+{
+    let iter = iter.into_iter();
+    map.reserve(iter.size_hint().0);
+    for (k, v) in iter {
+        map.insert(k.deserialize(deserializer)?, v.deserialize(deserializer)?);
+    }
+    Ok(())
+}
+
+macro_rules! impl_to_hashmap {
+    ($archived:ident<K, V $(, $h:ident)?>) => {
+        impl<K, V $(, $h)?> $archived<K, V $(, $h)?> {
+            /// Deserializes the entries of this map and inserts them into the
+            /// given `HashMap`.
+            ///
+            /// Entries with keys that are already present in `map` overwrite
+            /// the existing values.
+            pub fn collect_into_hashmap<KO, VO, S, D, E>(
+                &self,
+                map: &mut HashMap<KO, VO, S>,
+                deserializer: &mut D,
+            ) -> Result<(), E>
+            where
+                K: Deserialize<KO, Strategy<D, E>>,
+                V: Deserialize<VO, Strategy<D, E>>,
+                KO: Hash + Eq,
+                S: BuildHasher,
+            {
+                collect_into_hashmap(self, map, Strategy::wrap(deserializer))
+            }
+
+            /// Deserializes the entries of this map into a new `HashMap`.
+            pub fn to_hashmap<KO, VO, S, D, E>(
+                &self,
+                deserializer: &mut D,
+            ) -> Result<HashMap<KO, VO, S>, E>
+            where
+                K: Deserialize<KO, Strategy<D, E>>,
+                V: Deserialize<VO, Strategy<D, E>>,
+                KO: Hash + Eq,
+                S: Default + BuildHasher,
+            {
+                let mut result =
+                    HashMap::with_capacity_and_hasher(self.len(), S::default());
+                self.collect_into_hashmap(&mut result, deserializer)?;
+                Ok(result)
+            }
+        }
+    };
+}
+
+impl_to_hashmap!(ArchivedHashMap<K, V, H>);
+impl_to_hashmap!(ArchivedIndexMap<K, V, H>);
+impl_to_hashmap!(ArchivedBTreeMap<K, V>);
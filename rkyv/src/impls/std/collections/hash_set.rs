@@ -8,6 +8,7 @@ use rancor::{Error, Fallible};
 
 use crate::{
     collections::swiss_table::set::{ArchivedHashSet, HashSetResolver},
+    de::AllocationLimiter,
     ser::{Allocator, Writer},
     Archive, Deserialize, Serialize,
 };
@@ -60,7 +61,7 @@ impl<K, D, S> Deserialize<HashSet<K, S>, D> for ArchivedHashSet<K::Archived>
 where
     K: Archive + Hash + Eq,
     K::Archived: Deserialize<K, D> + Hash + Eq,
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
     S: Default + BuildHasher,
 {
     #[inline]
@@ -68,6 +69,7 @@ where
         &self,
         deserializer: &mut D,
     ) -> Result<HashSet<K, S>, D::Error> {
+        deserializer.check_array::<K>(self.len())?;
         let mut result = HashSet::with_hasher(S::default());
         for k in self.iter() {
             result.insert(k.deserialize(deserializer)?);
@@ -6,6 +6,8 @@ use ptr_meta::Pointee;
 use rancor::Fallible;
 
 use crate::{
+    copy::CopyOptimization,
+    de::AllocationLimiter,
     ffi::{ArchivedCString, CStringResolver},
     primitive::ArchivedUsize,
     ser::Writer,
@@ -18,6 +20,9 @@ use crate::{
 unsafe impl Portable for CStr {}
 
 impl ArchiveUnsized for CStr {
+    const UNSIZED_COPY_OPTIMIZATION: CopyOptimization<Self> =
+        unsafe { CopyOptimization::enable_if(true) };
+
     type Archived = CStr;
 
     #[inline]
@@ -46,16 +51,18 @@ impl<S: Fallible + Writer + ?Sized> SerializeUnsized<S> for CStr {
     }
 }
 
-impl<D: Fallible + ?Sized> DeserializeUnsized<CStr, D>
-    for <CStr as ArchiveUnsized>::Archived
+impl<D> DeserializeUnsized<CStr, D> for <CStr as ArchiveUnsized>::Archived
+where
+    D: Fallible + AllocationLimiter + ?Sized,
 {
     #[inline]
     unsafe fn deserialize_unsized(
         &self,
-        _: &mut D,
+        deserializer: &mut D,
         mut alloc: impl FnMut(Layout) -> *mut u8,
     ) -> Result<*mut (), D::Error> {
         let slice = self.to_bytes_with_nul();
+        deserializer.check_array::<u8>(slice.len())?;
         let bytes = alloc(Layout::array::<u8>(slice.len()).unwrap());
         assert!(!bytes.is_null());
         ptr::copy_nonoverlapping(slice.as_ptr(), bytes, slice.len());
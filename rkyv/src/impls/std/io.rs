@@ -0,0 +1,115 @@
+use std::io::ErrorKind;
+
+use rancor::Fallible;
+
+use crate::{io::ArchivedErrorKind, Archive, Deserialize, Serialize};
+
+impl Archive for ErrorKind {
+    type Archived = ArchivedErrorKind;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        _: usize,
+        _: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        out.write(ArchivedErrorKind::from(*self));
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for ErrorKind {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<ErrorKind, D> for ArchivedErrorKind {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<ErrorKind, D::Error> {
+        Ok(ErrorKind::from(*self))
+    }
+}
+
+impl From<ErrorKind> for ArchivedErrorKind {
+    #[inline]
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Other => ArchivedErrorKind::Other,
+            ErrorKind::NotFound => ArchivedErrorKind::NotFound,
+            ErrorKind::PermissionDenied => ArchivedErrorKind::PermissionDenied,
+            ErrorKind::ConnectionRefused => {
+                ArchivedErrorKind::ConnectionRefused
+            }
+            ErrorKind::ConnectionReset => ArchivedErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted => {
+                ArchivedErrorKind::ConnectionAborted
+            }
+            ErrorKind::NotConnected => ArchivedErrorKind::NotConnected,
+            ErrorKind::AddrInUse => ArchivedErrorKind::AddrInUse,
+            ErrorKind::AddrNotAvailable => ArchivedErrorKind::AddrNotAvailable,
+            ErrorKind::BrokenPipe => ArchivedErrorKind::BrokenPipe,
+            ErrorKind::AlreadyExists => ArchivedErrorKind::AlreadyExists,
+            ErrorKind::WouldBlock => ArchivedErrorKind::WouldBlock,
+            ErrorKind::InvalidInput => ArchivedErrorKind::InvalidInput,
+            ErrorKind::InvalidData => ArchivedErrorKind::InvalidData,
+            ErrorKind::TimedOut => ArchivedErrorKind::TimedOut,
+            ErrorKind::WriteZero => ArchivedErrorKind::WriteZero,
+            ErrorKind::Interrupted => ArchivedErrorKind::Interrupted,
+            ErrorKind::UnexpectedEof => ArchivedErrorKind::UnexpectedEof,
+            ErrorKind::Unsupported => ArchivedErrorKind::Unsupported,
+            ErrorKind::OutOfMemory => ArchivedErrorKind::OutOfMemory,
+            // `ErrorKind` is non-exhaustive, and unstable or newly added kinds
+            // do not have stable archived representations yet.
+            _ => ArchivedErrorKind::Other,
+        }
+    }
+}
+
+impl From<ArchivedErrorKind> for ErrorKind {
+    #[inline]
+    fn from(kind: ArchivedErrorKind) -> Self {
+        match kind {
+            ArchivedErrorKind::Other => ErrorKind::Other,
+            ArchivedErrorKind::NotFound => ErrorKind::NotFound,
+            ArchivedErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            ArchivedErrorKind::ConnectionRefused => {
+                ErrorKind::ConnectionRefused
+            }
+            ArchivedErrorKind::ConnectionReset => ErrorKind::ConnectionReset,
+            ArchivedErrorKind::ConnectionAborted => {
+                ErrorKind::ConnectionAborted
+            }
+            ArchivedErrorKind::NotConnected => ErrorKind::NotConnected,
+            ArchivedErrorKind::AddrInUse => ErrorKind::AddrInUse,
+            ArchivedErrorKind::AddrNotAvailable => ErrorKind::AddrNotAvailable,
+            ArchivedErrorKind::BrokenPipe => ErrorKind::BrokenPipe,
+            ArchivedErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+            ArchivedErrorKind::WouldBlock => ErrorKind::WouldBlock,
+            ArchivedErrorKind::InvalidInput => ErrorKind::InvalidInput,
+            ArchivedErrorKind::InvalidData => ErrorKind::InvalidData,
+            ArchivedErrorKind::TimedOut => ErrorKind::TimedOut,
+            ArchivedErrorKind::WriteZero => ErrorKind::WriteZero,
+            ArchivedErrorKind::Interrupted => ErrorKind::Interrupted,
+            ArchivedErrorKind::UnexpectedEof => ErrorKind::UnexpectedEof,
+            ArchivedErrorKind::Unsupported => ErrorKind::Unsupported,
+            ArchivedErrorKind::OutOfMemory => ErrorKind::OutOfMemory,
+        }
+    }
+}
+
+impl PartialEq<ErrorKind> for ArchivedErrorKind {
+    #[inline]
+    fn eq(&self, other: &ErrorKind) -> bool {
+        ErrorKind::from(*self) == *other
+    }
+}
+
+impl PartialEq<ArchivedErrorKind> for ErrorKind {
+    #[inline]
+    fn eq(&self, other: &ArchivedErrorKind) -> bool {
+        other.eq(self)
+    }
+}
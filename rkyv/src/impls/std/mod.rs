@@ -1,4 +1,5 @@
 mod collections;
 mod ffi;
+mod io;
 mod net;
 mod time;
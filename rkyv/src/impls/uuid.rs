@@ -1,11 +1,17 @@
 use rancor::Fallible;
 use uuid::Uuid;
 
-use crate::{Archive, Deserialize, Portable, Serialize};
+use crate::{
+    copy::{ArchiveCopyOptimize, CopyOptimization},
+    Archive, Deserialize, Portable, Serialize,
+};
 
 unsafe impl Portable for Uuid {}
 
 impl Archive for Uuid {
+    const COPY_OPTIMIZATION: CopyOptimization<Self> =
+        CopyOptimization::enable();
+
     type Archived = Uuid;
     type Resolver = ();
 
@@ -21,8 +27,7 @@ impl Archive for Uuid {
 }
 
 // Safety: Uuid is portable and has no padding
-#[cfg(feature = "copy")]
-unsafe impl crate::copy::ArchiveCopySafe for Uuid {}
+unsafe impl ArchiveCopyOptimize for Uuid {}
 
 impl<S: Fallible + ?Sized> Serialize<S> for Uuid {
     fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
@@ -63,4 +68,29 @@ mod rkyv_tests {
 
         assert_eq!(u, deserialized);
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_map_keys() {
+        use std::collections::HashMap;
+
+        use rancor::Failure;
+
+        use crate::{
+            collections::swiss_table::ArchivedHashMap, primitive::ArchivedU32,
+        };
+
+        let value = (1..=16u32)
+            .map(|i| (Uuid::from_u128(i as u128 * 0x1234_5678_9abc), i))
+            .collect::<HashMap<_, _>>();
+
+        let buf = crate::to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe {
+            access_unchecked::<ArchivedHashMap<Uuid, ArchivedU32>>(buf.as_ref())
+        };
+        for (k, v) in value.iter() {
+            assert_eq!(archived.get(k), Some(&ArchivedU32::from_native(*v)));
+        }
+        assert!(archived.get(&Uuid::nil()).is_none());
+    }
 }
@@ -0,0 +1,200 @@
+//! Bit-validity guarantees for checked archived values.
+//!
+//! Unsafe code that works directly with archived buffers often needs to know
+//! more than "this value passed validation". For example, it may need to know
+//! whether a checked archived `char` is always a valid `char`, or whether a
+//! slice of checked archived values can be read as a slice of native values.
+//!
+//! Types which implement [`ValidatedInvariant`] guarantee that once a value
+//! has been checked with `CheckBytes`, its bit pattern satisfies the validity
+//! invariant of its [`Native`](ValidatedInvariant::Native) type. Types which
+//! also implement [`NativeLayout`] have the same layout and byte order as
+//! their native type, so checked values can be borrowed as native
+//! values with [`as_native`] and [`as_native_slice`] instead of with
+//! transmutes.
+//!
+//! | Archived type | Native type | `NativeLayout` |
+//! | --- | --- | --- |
+//! | `bool` | `bool` | always |
+//! | `NonZeroI8`, `NonZeroU8` | themselves | always |
+//! | [`ArchivedChar`](crate::primitive::ArchivedChar) | `char` | see below |
+//! | `ArchivedNonZero*` | `NonZero*` | see below |
+//! | derived archived enums | themselves | never |
+//!
+//! Multibyte primitives have a native layout when their archived byte order
+//! matches the byte order of the target and the `unaligned` feature is
+//! disabled. Derived archived enums guarantee that their tag is one of their
+//! variants and that the fields of the variant are valid.
+
+use core::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8,
+};
+
+use crate::Portable;
+
+#[doc(hidden)]
+pub mod sealed {
+    /// Prevents [`ValidatedInvariant`](super::ValidatedInvariant) from being
+    /// implemented outside of rkyv and its derive macros.
+    pub trait Sealed {}
+}
+
+/// An archived type whose checked values satisfy the validity invariant of a
+/// native type.
+///
+/// This trait is sealed. It is implemented for archived `bool`, `char`, and
+/// `NonZero*` types, and for the archived types of derived enums.
+///
+/// # Safety
+///
+/// If `CheckBytes` succeeds for a value of this type, then converting the
+/// value to native byte order must produce a valid value of `Native`.
+pub unsafe trait ValidatedInvariant: Portable + sealed::Sealed {
+    /// The native type whose validity invariant checked values satisfy.
+    type Native;
+}
+
+/// A [`ValidatedInvariant`] type with the same layout and byte order as its
+/// native type.
+///
+/// # Safety
+///
+/// `Self` must have the same size and byte order as `Self::Native`, and at
+/// least its alignment, so that every checked value of `Self` is also a valid
+/// value of `Self::Native`.
+pub unsafe trait NativeLayout: ValidatedInvariant {}
+
+/// Borrows a checked archived value as its native type.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{access, invariant::as_native, rancor::Failure, to_bytes};
+///
+/// let bytes = to_bytes::<_, 256, Failure>(&true).unwrap();
+/// let archived = access::<bool, Failure>(&bytes).unwrap();
+/// assert!(*as_native(archived));
+/// ```
+#[inline]
+pub fn as_native<T: NativeLayout>(value: &T) -> &T::Native {
+    // SAFETY: `NativeLayout` guarantees that `T` and `T::Native` have the same
+    // layout and that `value` is a valid `T::Native`.
+    unsafe { &*(value as *const T).cast::<T::Native>() }
+}
+
+/// Borrows a slice of checked archived values as a slice of their native
+/// type.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access, invariant::as_native_slice, rancor::Failure, to_bytes,
+///     Archived,
+/// };
+///
+/// let value = vec![true, false, true];
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = access::<Archived<Vec<bool>>, Failure>(&bytes).unwrap();
+/// let native: &[bool] = as_native_slice(archived.as_slice());
+/// assert_eq!(native, value.as_slice());
+/// ```
+#[inline]
+pub fn as_native_slice<T: NativeLayout>(values: &[T]) -> &[T::Native] {
+    // SAFETY: `NativeLayout` guarantees that `T` and `T::Native` have the same
+    // layout and that every element of `values` is a valid `T::Native`.
+    unsafe {
+        core::slice::from_raw_parts(
+            values.as_ptr().cast::<T::Native>(),
+            values.len(),
+        )
+    }
+}
+
+macro_rules! impl_native {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            // SAFETY: `CheckBytes` for this type only succeeds for valid
+            // values, and it is its own native type.
+            unsafe impl ValidatedInvariant for $ty {
+                type Native = $ty;
+            }
+
+            // SAFETY: The type is its own native type.
+            unsafe impl NativeLayout for $ty {}
+        )*
+    };
+}
+
+impl_native!(bool, NonZeroI8, NonZeroU8);
+
+macro_rules! impl_validated {
+    ($($native:ty: $le:ident, $be:ident;)*) => {
+        $(
+            impl_validated!(@impl $native: $le);
+            impl_validated!(@impl $native: $be);
+        )*
+    };
+    (@impl $native:ty: $ty:ident) => {
+        impl sealed::Sealed for rend::$ty {}
+
+        // SAFETY: `CheckBytes` for the endian-aware type only succeeds if the
+        // value is valid when converted to native byte order.
+        unsafe impl ValidatedInvariant for rend::$ty {
+            type Native = $native;
+        }
+    };
+}
+
+impl_validated! {
+    char: char_le, char_be;
+    NonZeroI16: NonZeroI16_le, NonZeroI16_be;
+    NonZeroI32: NonZeroI32_le, NonZeroI32_be;
+    NonZeroI64: NonZeroI64_le, NonZeroI64_be;
+    NonZeroI128: NonZeroI128_le, NonZeroI128_be;
+    NonZeroU16: NonZeroU16_le, NonZeroU16_be;
+    NonZeroU32: NonZeroU32_le, NonZeroU32_be;
+    NonZeroU64: NonZeroU64_le, NonZeroU64_be;
+    NonZeroU128: NonZeroU128_le, NonZeroU128_be;
+}
+
+macro_rules! impl_native_layout {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            // SAFETY: Aligned endian-aware types have the same size as their
+            // native types and are aligned to their size, which is at least
+            // the alignment of the native type. This one has the same byte
+            // order as the target.
+            unsafe impl NativeLayout for rend::$ty {}
+        )*
+    };
+}
+
+#[cfg(target_endian = "little")]
+impl_native_layout!(
+    char_le,
+    NonZeroI16_le,
+    NonZeroI32_le,
+    NonZeroI64_le,
+    NonZeroI128_le,
+    NonZeroU16_le,
+    NonZeroU32_le,
+    NonZeroU64_le,
+    NonZeroU128_le,
+);
+
+#[cfg(target_endian = "big")]
+impl_native_layout!(
+    char_be,
+    NonZeroI16_be,
+    NonZeroI32_be,
+    NonZeroI64_be,
+    NonZeroI128_be,
+    NonZeroU16_be,
+    NonZeroU32_be,
+    NonZeroU64_be,
+    NonZeroU128_be,
+);
@@ -0,0 +1,66 @@
+//! Archived versions of `io` types.
+
+use crate::Portable;
+
+/// An archived [`ErrorKind`](std::io::ErrorKind).
+///
+/// The discriminants of the archived variants are fixed, so the archived
+/// representation does not change when the standard library adds new error
+/// kinds. Error kinds which do not have a corresponding variant here are
+/// archived as [`Other`](ArchivedErrorKind::Other). This includes unstable
+/// error kinds and any kinds added to the standard library in the future. New
+/// variants may be added with new discriminants, but existing discriminants
+/// will never be reused.
+#[derive(Portable)]
+#[archive(crate)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u8)]
+pub enum ArchivedErrorKind {
+    /// A custom error, or an error kind which is not otherwise represented.
+    Other = 0,
+    /// An entity was not found.
+    NotFound = 1,
+    /// The operation lacked the necessary privileges to complete.
+    PermissionDenied = 2,
+    /// The connection was refused by the remote server.
+    ConnectionRefused = 3,
+    /// The connection was reset by the remote server.
+    ConnectionReset = 4,
+    /// The connection was aborted by the remote server.
+    ConnectionAborted = 5,
+    /// The network operation failed because it was not connected yet.
+    NotConnected = 6,
+    /// A socket address could not be bound because the address is already in
+    /// use elsewhere.
+    AddrInUse = 7,
+    /// A nonexistent interface was requested or the requested address was not
+    /// local.
+    AddrNotAvailable = 8,
+    /// The operation failed because a pipe was closed.
+    BrokenPipe = 9,
+    /// An entity already exists.
+    AlreadyExists = 10,
+    /// The operation needs to block to complete, but the blocking operation
+    /// was requested to not occur.
+    WouldBlock = 11,
+    /// A parameter was incorrect.
+    InvalidInput = 12,
+    /// Data not valid for the operation were encountered.
+    InvalidData = 13,
+    /// The I/O operation's timeout expired.
+    TimedOut = 14,
+    /// An error returned when an operation could not be completed because a
+    /// call to `write` returned `Ok(0)`.
+    WriteZero = 15,
+    /// This operation was interrupted.
+    Interrupted = 16,
+    /// An error returned when an operation could not be completed because an
+    /// "end of file" was reached prematurely.
+    UnexpectedEof = 17,
+    /// This operation is unsupported on this platform.
+    Unsupported = 18,
+    /// An operation could not be completed because it failed to allocate
+    /// enough memory.
+    OutOfMemory = 19,
+}
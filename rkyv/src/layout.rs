@@ -0,0 +1,874 @@
+//! Descriptions of the layouts of archived types.
+//!
+//! Archived types have well-defined layouts, so archives can be read by
+//! programs written in other languages. [`DescribeLayout`] describes the layout
+//! of an archived type at runtime, including the offset and type of each of its
+//! fields, the endianness of its primitives, the targets of its relative
+//! pointers, and the tags of its enum variants. [`to_c_header`] renders those
+//! descriptions as C type definitions.
+//!
+//! `DescribeLayout` can be derived for archived types by adding
+//! `#[archive(export_layout)]` to a type which derives `Archive`.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{layout::to_c_header, Archive};
+//!
+//! #[derive(Archive)]
+//! #[archive(export_layout)]
+//! struct Point {
+//!     x: f32,
+//!     y: f32,
+//! }
+//!
+//! let header = to_c_header::<ArchivedPoint>();
+//! assert!(header.contains("typedef struct ArchivedPoint {"));
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+use core::{
+    fmt::Write as _,
+    marker::{PhantomData, PhantomPinned},
+    mem::{align_of, size_of},
+};
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+
+use crate::{
+    envelope::hash_type,
+    rel_ptr::{RawRelPtr, RelPtr},
+};
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::string::String;
+    #[cfg(feature = "std")]
+    pub use std::string::String;
+
+    // Enum tags are stored with the native endianness
+    #[cfg(target_endian = "big")]
+    pub use crate::rend::{u16_be as U16, u32_be as U32, u64_be as U64};
+    #[cfg(target_endian = "little")]
+    pub use crate::rend::{u16_le as U16, u32_le as U32, u64_le as U64};
+}
+
+/// The byte order of a primitive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// The least significant byte is stored first.
+    Little,
+    /// The most significant byte is stored first.
+    Big,
+}
+
+/// The kind of value stored in a primitive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrimitiveKind {
+    /// A `bool` stored as a single byte which is either 0 or 1.
+    Bool,
+    /// A `char` stored as a 32-bit Unicode scalar value.
+    Char,
+    /// A signed two's complement integer.
+    Signed,
+    /// An unsigned integer.
+    Unsigned,
+    /// A nonzero signed two's complement integer.
+    NonZeroSigned,
+    /// A nonzero unsigned integer.
+    NonZeroUnsigned,
+    /// An IEEE 754 floating-point number.
+    Float,
+}
+
+/// A field located at a fixed offset inside of a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// The name of the field.
+    ///
+    /// Fields of tuple structs and tuple enum variants are named `_0`, `_1`,
+    /// and so on.
+    pub name: String,
+    /// The offset of the field from the start of the containing type, in
+    /// bytes.
+    pub offset: usize,
+    /// The name of the layout of the field's type.
+    pub ty: String,
+}
+
+impl FieldLayout {
+    /// Returns a new field layout with the given name, offset, and type name.
+    pub fn new(name: &str, offset: usize, ty: String) -> Self {
+        Self {
+            name: name.into(),
+            offset,
+            ty,
+        }
+    }
+}
+
+/// A variant of an enum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariantLayout {
+    /// The name of the variant.
+    pub name: String,
+    /// The value of the tag for the variant.
+    pub tag: u64,
+    /// The fields of the variant.
+    ///
+    /// Field offsets are relative to the start of the enum. The tag is always
+    /// located at offset 0.
+    pub fields: Vec<FieldLayout>,
+}
+
+impl VariantLayout {
+    /// Returns a new variant layout with the given name, tag, and fields.
+    pub fn new(name: &str, tag: u64, fields: Vec<FieldLayout>) -> Self {
+        Self {
+            name: name.into(),
+            tag,
+            fields,
+        }
+    }
+}
+
+/// The structure of a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayoutKind {
+    /// A primitive value.
+    Primitive {
+        /// The kind of value stored in the primitive.
+        kind: PrimitiveKind,
+        /// The byte order of the primitive, or `None` if it is a single byte.
+        endianness: Option<Endianness>,
+    },
+    /// A fixed-size array of elements.
+    Array {
+        /// The name of the layout of the elements.
+        element: String,
+        /// The number of elements in the array.
+        len: usize,
+    },
+    /// A relative pointer.
+    ///
+    /// The pointer is a signed offset from the start of the pointer to its
+    /// target, in bytes. Because objects are always serialized before the
+    /// objects that point to them, the offset is usually negative.
+    RelPtr {
+        /// The name of the layout of the offset.
+        offset: String,
+        /// The name of the layout of the target, or `None` if the pointer is
+        /// untyped.
+        target: Option<String>,
+    },
+    /// A struct with fields located at fixed offsets.
+    Struct {
+        /// The fields of the struct.
+        fields: Vec<FieldLayout>,
+    },
+    /// A union whose fields are all located at offset 0.
+    Union {
+        /// The fields of the union.
+        fields: Vec<FieldLayout>,
+    },
+    /// An enum with a tag located at offset 0.
+    Enum {
+        /// The name of the layout of the tag.
+        tag: String,
+        /// The variants of the enum.
+        variants: Vec<VariantLayout>,
+    },
+}
+
+/// A description of the layout of a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeLayout {
+    /// The name of the layout.
+    pub name: String,
+    /// The size of the type, in bytes.
+    pub size: usize,
+    /// The alignment of the type, in bytes.
+    pub align: usize,
+    /// A description of how the fields of the type should be interpreted, if
+    /// any.
+    pub description: Option<String>,
+    /// The structure of the type.
+    pub kind: LayoutKind,
+}
+
+impl TypeLayout {
+    /// Returns a new layout with the given name and kind, and the size and
+    /// alignment of `T`.
+    pub fn of<T>(name: String, kind: LayoutKind) -> Self {
+        Self {
+            name,
+            size: size_of::<T>(),
+            align: align_of::<T>(),
+            description: None,
+            kind,
+        }
+    }
+
+    /// Sets the description of the layout.
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// A type which can describe its layout.
+///
+/// This is usually implemented for archived types with
+/// `#[archive(export_layout)]`.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     layout::{describe, LayoutKind},
+///     Archive,
+/// };
+///
+/// #[derive(Archive)]
+/// #[archive(export_layout)]
+/// struct Example {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// let layouts = describe::<ArchivedExample>();
+/// let root = layouts.last().unwrap();
+/// assert_eq!(root.name, "ArchivedExample");
+/// match &root.kind {
+///     LayoutKind::Struct { fields } => {
+///         assert!(fields.iter().any(|f| f.name == "id" && f.ty == "u32_le"));
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+pub trait DescribeLayout {
+    /// Returns the name of the layout.
+    ///
+    /// Names are valid C identifiers. Types with the same name must have the
+    /// same layout.
+    fn layout_name() -> String;
+
+    /// Returns the layout of the type.
+    ///
+    /// The layouts of any types referred to by the returned layout must be
+    /// added to `registry`.
+    fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout;
+}
+
+/// A collection of type layouts.
+///
+/// Layouts are stored in dependency order: each layout is added after the
+/// layouts of its fields. The targets of relative pointers may be added after
+/// the pointers to them, which allows recursive types to be described.
+#[derive(Debug, Default)]
+pub struct LayoutRegistry {
+    layouts: Vec<TypeLayout>,
+    pending: BTreeSet<String>,
+}
+
+impl LayoutRegistry {
+    /// Returns a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the layout of `T` and the layouts it refers to, then returns the
+    /// name of the layout of `T`.
+    pub fn register<T: DescribeLayout>(&mut self) -> String {
+        let name = T::layout_name();
+        if self.get(&name).is_none() && self.pending.insert(name.clone()) {
+            let layout = T::describe_layout(self);
+            self.pending.remove(&name);
+            self.layouts.push(layout);
+        }
+        name
+    }
+
+    /// Returns the layout with the given name, if it has been added.
+    pub fn get(&self, name: &str) -> Option<&TypeLayout> {
+        self.layouts.iter().find(|layout| layout.name == name)
+    }
+
+    /// Returns the layouts in the registry.
+    pub fn layouts(&self) -> &[TypeLayout] {
+        &self.layouts
+    }
+
+    /// Consumes the registry and returns its layouts.
+    pub fn into_layouts(self) -> Vec<TypeLayout> {
+        self.layouts
+    }
+}
+
+/// Returns the layout of `T` along with the layouts of all of the types it
+/// refers to.
+///
+/// The layout of `T` is always last.
+pub fn describe<T: DescribeLayout>() -> Vec<TypeLayout> {
+    let mut registry = LayoutRegistry::new();
+    registry.register::<T>();
+    registry.into_layouts()
+}
+
+/// A fingerprint of the structure of a type's layout.
+///
+/// The structural hash covers the same tree as [`DescribeLayout`]: the size
+/// and alignment of each type, the offsets and structural hashes of its fields,
+/// the kinds and byte orders of its primitives, and the targets of its relative
+/// pointers. Names of types and fields are not included, so types with
+/// different names and paths have the same structural hash if their layouts
+/// are the same.
+///
+/// This is usually implemented for archived types with
+/// `#[archive(structural_hash)]`. Because the hash of a type includes the
+/// hashes of the types it points to, recursive types can't be hashed.
+///
+/// # Safety
+///
+/// Types with the same structural hash must have the same layout, and the
+/// structural hash must describe the same layout as `describe_layout`.
+pub unsafe trait StructuralHash: DescribeLayout {
+    /// The structural hash of the type.
+    const STRUCTURAL_HASH: u64;
+}
+
+/// Hashes the structure of `T` from its kind of layout and a list of
+/// parameters.
+///
+/// The size and alignment of `T` are always included in the hash. This can be
+/// used to implement [`StructuralHash`] in const contexts.
+pub const fn hash_structure<T>(kind: &str, params: &[u64]) -> u64 {
+    let layout =
+        hash_type(kind, &[size_of::<T>() as u64, align_of::<T>() as u64]);
+    hash_type("", &[layout, hash_type("", params)])
+}
+
+const fn endianness_code(endianness: Option<Endianness>) -> u64 {
+    match endianness {
+        None => 0,
+        Some(Endianness::Little) => 1,
+        Some(Endianness::Big) => 2,
+    }
+}
+
+/// Renders the layout of `T` and the layouts of all of the types it refers to
+/// as a C header.
+///
+/// Each type is rendered as a `typedef`, with explicit padding so that each
+/// field is located at the same offset as in the archive. The generated header
+/// includes `static_assert`s for the size of each type and the offset of each
+/// field so that compilers with different layout rules are caught. Primitives
+/// are rendered as fixed-width integers named after their archived types, so
+/// multibyte values must be converted from the endianness they are stored in
+/// before being used.
+///
+/// The header can be compiled as C11 or C++11.
+pub fn to_c_header<T: DescribeLayout>() -> String {
+    let mut registry = LayoutRegistry::new();
+    let root = registry.register::<T>();
+
+    let mut out = String::new();
+    out.push_str("/* Archived type layouts for `");
+    out.push_str(&root);
+    out.push_str("`, generated by rkyv. */\n\n");
+    out.push_str("#include <assert.h>\n");
+    out.push_str("#include <stddef.h>\n");
+    out.push_str("#include <stdint.h>\n");
+
+    for layout in registry.layouts() {
+        if layout.size != 0 {
+            out.push('\n');
+            render_c(&registry, layout, &mut out);
+        }
+    }
+
+    out
+}
+
+fn c_name(registry: &LayoutRegistry, name: &str) -> String {
+    match registry.get(name) {
+        Some(TypeLayout {
+            kind: LayoutKind::Primitive { .. },
+            ..
+        }) => format!("rkyv_{}", name),
+        _ => name.into(),
+    }
+}
+
+fn c_primitive(kind: PrimitiveKind, size: usize) -> String {
+    match kind {
+        PrimitiveKind::Signed | PrimitiveKind::NonZeroSigned => {
+            format!("int{}_t", size * 8)
+        }
+        _ => format!("uint{}_t", size * 8),
+    }
+}
+
+fn render_c_fields(
+    registry: &LayoutRegistry,
+    fields: &[FieldLayout],
+    size: usize,
+    out: &mut String,
+) {
+    let mut fields = fields
+        .iter()
+        .filter(|f| registry.get(&f.ty).map_or(0, |l| l.size) != 0)
+        .collect::<Vec<_>>();
+    fields.sort_by_key(|f| f.offset);
+
+    let mut pos = 0;
+    let mut padding = 0;
+    for field in fields {
+        if field.offset > pos {
+            let _ = writeln!(
+                out,
+                "    uint8_t _padding{}[{}];",
+                padding,
+                field.offset - pos,
+            );
+            padding += 1;
+        }
+        let _ = writeln!(
+            out,
+            "    {} {};",
+            c_name(registry, &field.ty),
+            field.name,
+        );
+        pos = field.offset + registry.get(&field.ty).unwrap().size;
+    }
+    if size > pos {
+        let _ =
+            writeln!(out, "    uint8_t _padding{}[{}];", padding, size - pos);
+    }
+}
+
+fn render_c_asserts(
+    registry: &LayoutRegistry,
+    name: &str,
+    fields: &[FieldLayout],
+    size: usize,
+    out: &mut String,
+) {
+    let _ = writeln!(
+        out,
+        "static_assert(sizeof({0}) == {1}, \"size of {0}\");",
+        name, size,
+    );
+    let mut fields = fields.iter().collect::<Vec<_>>();
+    fields.sort_by_key(|f| f.offset);
+    for field in fields {
+        if registry.get(&field.ty).map_or(0, |l| l.size) != 0 {
+            let _ = writeln!(
+                out,
+                "static_assert(offsetof({0}, {1}) == {2}, \"offset of \
+                 {0}.{1}\");",
+                name, field.name, field.offset,
+            );
+        }
+    }
+}
+
+fn render_c_struct(
+    registry: &LayoutRegistry,
+    keyword: &str,
+    name: &str,
+    fields: &[FieldLayout],
+    size: usize,
+    out: &mut String,
+) {
+    let _ = writeln!(out, "typedef {} {} {{", keyword, name);
+    if keyword == "union" {
+        for field in fields {
+            let _ = writeln!(
+                out,
+                "    {} {};",
+                c_name(registry, &field.ty),
+                field.name,
+            );
+        }
+    } else {
+        render_c_fields(registry, fields, size, out);
+    }
+    let _ = writeln!(out, "}} {};", name);
+    render_c_asserts(registry, name, fields, size, out);
+}
+
+fn render_c(registry: &LayoutRegistry, layout: &TypeLayout, out: &mut String) {
+    if let Some(description) = &layout.description {
+        let _ = writeln!(out, "/* {} */", description);
+    }
+
+    let name = c_name(registry, &layout.name);
+    match &layout.kind {
+        LayoutKind::Primitive { kind, endianness } => {
+            let ty = if layout.size > 8 {
+                let _ =
+                    writeln!(out, "typedef uint8_t {}[{}];", name, layout.size);
+                None
+            } else {
+                Some(c_primitive(*kind, layout.size))
+            };
+            let endianness = match endianness {
+                Some(Endianness::Little) => "little-endian ",
+                Some(Endianness::Big) => "big-endian ",
+                None => "",
+            };
+            let kind = match kind {
+                PrimitiveKind::Bool => "bool",
+                PrimitiveKind::Char => "char",
+                PrimitiveKind::Signed => "signed integer",
+                PrimitiveKind::Unsigned => "unsigned integer",
+                PrimitiveKind::NonZeroSigned => "nonzero signed integer",
+                PrimitiveKind::NonZeroUnsigned => "nonzero unsigned integer",
+                PrimitiveKind::Float => "float",
+            };
+            if let Some(ty) = ty {
+                let _ = writeln!(
+                    out,
+                    "typedef {} {}; /* {}{} */",
+                    ty, name, endianness, kind,
+                );
+            }
+            let _ = writeln!(
+                out,
+                "static_assert(sizeof({0}) == {1}, \"size of {0}\");",
+                name, layout.size,
+            );
+        }
+        LayoutKind::Array { element, len } => {
+            let _ = writeln!(
+                out,
+                "typedef {} {}[{}];",
+                c_name(registry, element),
+                name,
+                len,
+            );
+            let _ = writeln!(
+                out,
+                "static_assert(sizeof({0}) == {1}, \"size of {0}\");",
+                name, layout.size,
+            );
+        }
+        LayoutKind::RelPtr { offset, target } => {
+            let target = target.as_deref().unwrap_or("untyped data");
+            let _ = writeln!(
+                out,
+                "/* Relative pointer to {}: the target is located at \
+                 `(const char *)ptr + ptr->offset`. */",
+                target,
+            );
+            let fields = [FieldLayout::new("offset", 0, offset.clone())];
+            render_c_struct(
+                registry,
+                "struct",
+                &name,
+                &fields,
+                layout.size,
+                out,
+            );
+        }
+        LayoutKind::Struct { fields } => {
+            render_c_struct(
+                registry,
+                "struct",
+                &name,
+                fields,
+                layout.size,
+                out,
+            );
+        }
+        LayoutKind::Union { fields } => {
+            render_c_struct(registry, "union", &name, fields, layout.size, out);
+        }
+        LayoutKind::Enum { tag, variants } => {
+            let _ = writeln!(out, "enum {{");
+            for variant in variants {
+                let _ = writeln!(
+                    out,
+                    "    {}_TAG_{} = {},",
+                    name, variant.name, variant.tag,
+                );
+            }
+            let _ = writeln!(out, "}};");
+
+            let mut members = Vec::new();
+            for variant in variants {
+                if variant.fields.is_empty() {
+                    continue;
+                }
+                let variant_name = format!("{}_{}", name, variant.name);
+                let mut fields = Vec::with_capacity(variant.fields.len() + 1);
+                fields.push(FieldLayout::new("tag", 0, tag.clone()));
+                fields.extend(variant.fields.iter().cloned());
+                let _ = writeln!(out);
+                render_c_struct(
+                    registry,
+                    "struct",
+                    &variant_name,
+                    &fields,
+                    layout.size,
+                    out,
+                );
+                members.push(FieldLayout {
+                    name: variant.name.clone(),
+                    offset: 0,
+                    ty: variant_name,
+                });
+            }
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "typedef union {} {{", name);
+            let _ = writeln!(out, "    {} tag;", c_name(registry, tag));
+            for member in members.iter() {
+                let _ = writeln!(out, "    {} {};", member.ty, member.name);
+            }
+            let _ = writeln!(out, "}} {};", name);
+            render_c_asserts(registry, &name, &[], layout.size, out);
+        }
+    }
+}
+
+// Primitives
+
+macro_rules! impl_primitive {
+    ($($ty:ty: $name:expr, $kind:ident $endianness:expr);* $(;)?) => {
+        $(
+            impl DescribeLayout for $ty {
+                fn layout_name() -> String {
+                    $name.into()
+                }
+
+                fn describe_layout(_: &mut LayoutRegistry) -> TypeLayout {
+                    TypeLayout::of::<Self>(
+                        $name.into(),
+                        LayoutKind::Primitive {
+                            kind: PrimitiveKind::$kind,
+                            endianness: $endianness,
+                        },
+                    )
+                }
+            }
+
+            // SAFETY: The hash covers the kind, byte order, size, and
+            // alignment of the primitive.
+            unsafe impl StructuralHash for $ty {
+                const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+                    "primitive",
+                    &[
+                        PrimitiveKind::$kind as u64,
+                        endianness_code($endianness),
+                    ],
+                );
+            }
+        )*
+    };
+}
+
+impl_primitive! {
+    bool: "bool", Bool None;
+    i8: "i8", Signed None;
+    u8: "u8", Unsigned None;
+    core::num::NonZeroI8: "NonZeroI8", NonZeroSigned None;
+    core::num::NonZeroU8: "NonZeroU8", NonZeroUnsigned None;
+}
+
+macro_rules! impl_multibyte_primitives {
+    ($endianness:ident: $($ty:ident $kind:ident),* $(,)?) => {
+        impl_primitive! {
+            $($ty: stringify!($ty), $kind Some(Endianness::$endianness);)*
+        }
+    };
+}
+
+macro_rules! impl_endian_primitives {
+    ($endianness:ident $module:path: $(
+        $i16:ident $i32:ident $i64:ident $i128:ident
+        $u16:ident $u32:ident $u64:ident $u128:ident
+        $f32:ident $f64:ident $char:ident
+        $nzi16:ident $nzi32:ident $nzi64:ident $nzi128:ident
+        $nzu16:ident $nzu32:ident $nzu64:ident $nzu128:ident
+    )*) => {
+        const _: () = {
+            use $module::{
+                $($i16, $i32, $i64, $i128, $u16, $u32, $u64, $u128, $f32,
+                $f64, $char, $nzi16, $nzi32, $nzi64, $nzi128, $nzu16, $nzu32,
+                $nzu64, $nzu128,)*
+            };
+
+            impl_multibyte_primitives! {
+                $endianness: $(
+                    $i16 Signed, $i32 Signed, $i64 Signed, $i128 Signed,
+                    $u16 Unsigned, $u32 Unsigned, $u64 Unsigned,
+                    $u128 Unsigned,
+                    $f32 Float, $f64 Float,
+                    $char Char,
+                    $nzi16 NonZeroSigned, $nzi32 NonZeroSigned,
+                    $nzi64 NonZeroSigned, $nzi128 NonZeroSigned,
+                    $nzu16 NonZeroUnsigned, $nzu32 NonZeroUnsigned,
+                    $nzu64 NonZeroUnsigned, $nzu128 NonZeroUnsigned,
+                )*
+            }
+        };
+    };
+}
+
+impl_endian_primitives! {
+    Little crate::rend:
+    i16_le i32_le i64_le i128_le u16_le u32_le u64_le u128_le f32_le f64_le
+    char_le NonZeroI16_le NonZeroI32_le NonZeroI64_le NonZeroI128_le
+    NonZeroU16_le NonZeroU32_le NonZeroU64_le NonZeroU128_le
+}
+
+impl_endian_primitives! {
+    Big crate::rend:
+    i16_be i32_be i64_be i128_be u16_be u32_be u64_be u128_be f32_be f64_be
+    char_be NonZeroI16_be NonZeroI32_be NonZeroI64_be NonZeroI128_be
+    NonZeroU16_be NonZeroU32_be NonZeroU64_be NonZeroU128_be
+}
+
+impl_endian_primitives! {
+    Little crate::rend::unaligned:
+    i16_ule i32_ule i64_ule i128_ule u16_ule u32_ule u64_ule u128_ule f32_ule
+    f64_ule char_ule NonZeroI16_ule NonZeroI32_ule NonZeroI64_ule
+    NonZeroI128_ule NonZeroU16_ule NonZeroU32_ule NonZeroU64_ule
+    NonZeroU128_ule
+}
+
+impl_endian_primitives! {
+    Big crate::rend::unaligned:
+    i16_ube i32_ube i64_ube i128_ube u16_ube u32_ube u64_ube u128_ube f32_ube
+    f64_ube char_ube NonZeroI16_ube NonZeroI32_ube NonZeroI64_ube
+    NonZeroI128_ube NonZeroU16_ube NonZeroU32_ube NonZeroU64_ube
+    NonZeroU128_ube
+}
+
+// Zero-sized types
+
+macro_rules! impl_zero_sized {
+    ($($ty:ty: $name:literal),* $(,)?) => {
+        $(
+            impl DescribeLayout for $ty {
+                fn layout_name() -> String {
+                    $name.into()
+                }
+
+                fn describe_layout(_: &mut LayoutRegistry) -> TypeLayout {
+                    TypeLayout::of::<Self>(
+                        $name.into(),
+                        LayoutKind::Struct { fields: Vec::new() },
+                    )
+                }
+            }
+
+            // SAFETY: The type has no fields.
+            unsafe impl StructuralHash for $ty {
+                const STRUCTURAL_HASH: u64 =
+                    hash_structure::<Self>("struct", &[]);
+            }
+        )*
+    };
+}
+
+impl_zero_sized! {
+    (): "unit",
+    PhantomPinned: "PhantomPinned",
+}
+
+impl<T: ?Sized> DescribeLayout for PhantomData<T> {
+    fn layout_name() -> String {
+        "PhantomData".into()
+    }
+
+    fn describe_layout(_: &mut LayoutRegistry) -> TypeLayout {
+        TypeLayout::of::<Self>(
+            Self::layout_name(),
+            LayoutKind::Struct { fields: Vec::new() },
+        )
+    }
+}
+
+// SAFETY: `PhantomData` has no fields.
+unsafe impl<T: ?Sized> StructuralHash for PhantomData<T> {
+    const STRUCTURAL_HASH: u64 = hash_structure::<Self>("struct", &[]);
+}
+
+// Arrays
+
+impl<T: DescribeLayout, const N: usize> DescribeLayout for [T; N] {
+    fn layout_name() -> String {
+        format!("{}_x{}", T::layout_name(), N)
+    }
+
+    fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+        TypeLayout::of::<Self>(
+            Self::layout_name(),
+            LayoutKind::Array {
+                element: registry.register::<T>(),
+                len: N,
+            },
+        )
+    }
+}
+
+// SAFETY: The hash covers the structure of the elements and the length.
+unsafe impl<T: StructuralHash, const N: usize> StructuralHash for [T; N] {
+    const STRUCTURAL_HASH: u64 =
+        hash_structure::<Self>("array", &[T::STRUCTURAL_HASH, N as u64]);
+}
+
+// Relative pointers
+
+impl<O: DescribeLayout> DescribeLayout for RawRelPtr<O> {
+    fn layout_name() -> String {
+        format!("RawRelPtr_{}", O::layout_name())
+    }
+
+    fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+        TypeLayout::of::<Self>(
+            Self::layout_name(),
+            LayoutKind::RelPtr {
+                offset: registry.register::<O>(),
+                target: None,
+            },
+        )
+    }
+}
+
+// SAFETY: The hash covers the structure of the offset.
+unsafe impl<O: StructuralHash> StructuralHash for RawRelPtr<O> {
+    const STRUCTURAL_HASH: u64 =
+        hash_structure::<Self>("rel_ptr", &[O::STRUCTURAL_HASH, 0]);
+}
+
+impl<T: DescribeLayout, O: DescribeLayout> DescribeLayout for RelPtr<T, O> {
+    fn layout_name() -> String {
+        format!("RelPtr_{}_{}", O::layout_name(), T::layout_name())
+    }
+
+    fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+        TypeLayout::of::<Self>(
+            Self::layout_name(),
+            LayoutKind::RelPtr {
+                offset: registry.register::<O>(),
+                target: Some(registry.register::<T>()),
+            },
+        )
+    }
+}
+
+// SAFETY: The hash covers the structures of the offset and the target.
+unsafe impl<T: StructuralHash, O: StructuralHash> StructuralHash
+    for RelPtr<T, O>
+{
+    const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+        "rel_ptr",
+        &[O::STRUCTURAL_HASH, 1, T::STRUCTURAL_HASH],
+    );
+}
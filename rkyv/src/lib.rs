@@ -65,11 +65,6 @@
 //!   architectures.
 //! - `big_endian`: Forces archives into a big-endian format. This guarantees
 //!   cross-endian compatibility optimized for big-endian architectures.
-//! - `copy`: Enables copy optimizations for packed copyable data types.
-//!   Requires nightly.
-//! - `copy_unsafe`: Automatically opts all potentially copyable types into copy
-//!   optimization. This broadly improves performance but may cause
-//!   uninitialized bytes to be copied to the output. Requires nightly.
 //! - `size_16`: Archives integral `*size` types as 16-bit integers. This is
 //!   intended to be used only for small archives and may not handle large, more
 //!   general data.
@@ -79,7 +74,51 @@
 //!   intended to be used only for very large archives and may cause unnecessary
 //!   data bloat.
 //! - `std`: Enables standard library support. Enabled by default.
-//! - `bytecheck`: Enables validation support through `bytecheck`.
+//! - `bytecheck`: Enables validation support through `bytecheck`. Without
+//!   `alloc`, archives can be validated with a
+//!   [`FixedValidator`](validation::validators::FixedValidator).
+//! - `allocator_api`: Enables deserializing into collections which use custom
+//!   allocators through the unstable `allocator_api`. Requires nightly.
+//! - `fallible_alloc`: Makes `Vec`, `String`, and `HashMap` reserve memory
+//!   with `try_reserve` when deserializing them, and lets deserializers like
+//!   [`Limited`](de::Limited) return an
+//!   [`AllocationFailed`](util::AllocationFailed) error instead of aborting
+//!   when it fails. Fallible serialization is always available through
+//!   [`FallibleSerializer`](ser::FallibleSerializer).
+//! - `runtime-simd`: Selects the instructions used to scan the control bytes
+//!   of archived hash tables at runtime instead of at compile time. See
+//!   [`Scanner`](collections::swiss_table::Scanner).
+//! - `hooks`: Calls [`enter_type`](ser::Writer::enter_type) and
+//!   [`exit_type`](ser::Writer::exit_type) around serializing each
+//!   derived type and collection. Implied by `instrument` and `limits`.
+//! - `instrument`: Enables [`Profiling`](ser::instrument::Profiling), which
+//!   attributes the bytes written and the time spent serializing to types.
+//!   Implies `std`.
+//! - `limits`: Enables the [`Limited`](ser::limits::Limited) serializer,
+//!   which stops serializing values that exceed limits on their size or
+//!   depth.
+//! - `c_api`: Enables the [`c_api`] module for accessing archives from other
+//!   languages through generated `extern "C"` functions.
+//! - `reflect`: Enables the [`reflect`](mod@reflect) module for printing
+//!   archives as trees using only static descriptions of their types.
+//! - `simdutf8`: Validates strings serialized with
+//!   [`DenseStrings`](with::DenseStrings) using `simdutf8`.
+//! - `xxh3`: Enables the XXH3 hash for
+//!   [validation proofs](validation::proof) through `xxhash-rust`.
+//! - `blake3`: Enables the BLAKE3 hash for
+//!   [validation proofs](validation::proof), which lets them be checked
+//!   against archives from untrusted sources.
+//! - `wasm`: Enables helpers for accessing archives from byte buffers which
+//!   may not be aligned, such as those provided by JavaScript.
+//! - `test-helpers`: Enables the [`test_util`] module for round-trip testing
+//!   archivable types.
+//! - `tokio`: Enables serializing without blocking the async runtime through
+//!   `util::to_writer_async`.
+//!
+//! Building with `RUSTFLAGS="--cfg rkyv_strict_provenance"` on nightly denies
+//! casts between pointers and integers in rkyv. Pointer arithmetic in rkyv
+//! preserves provenance, so archives can be accessed under Miri with
+//! `-Zmiri-strict-provenance`.
 //!
 //! ## Crate support
 //!
@@ -91,11 +130,27 @@
 //!
 //! Crates supported by rkyv:
 //!
+//! - [`arrow`](https://docs.rs/arrow) *Archived numeric vecs can be exported
+//!   as Arrow buffers and arrays without copying. See [`arrow`](mod@arrow).
+//!   Requires `std`.*
+//! - [`bumpalo`](https://docs.rs/bumpalo) *Deserializes into arena-backed
+//!   collections. See [`de::AllocProvider`].*
 //! - [`indexmap`](https://docs.rs/indexmap)
+//! - [`nalgebra`](https://docs.rs/nalgebra) *`DMatrix` is archived as an
+//!   [`ArchivedMatrix`](collections::matrix::ArchivedMatrix). Requires
+//!   `alloc`.*
+//! - [`ndarray`](https://docs.rs/ndarray) *`Array2` is archived as an
+//!   [`ArchivedMatrix`](collections::matrix::ArchivedMatrix), which can be
+//!   viewed as an `ArrayView2` without copying.*
+//! - [`ordered-float`](https://docs.rs/ordered-float) *Archived ordered floats
+//!   can be used as the keys of archived maps.*
 //! - [`rend`](https://docs.rs/rend) *Enabled automatically when using
 //!   endian-specific archive features.*
+//! - [`rust_decimal`](https://docs.rs/rust_decimal)
+//! - [`serde_json`](https://docs.rs/serde_json) *JSON values can be converted
+//!   to and from [`Value`](value::Value)s.*
 //! - [`tinyvec`](https://docs.rs/tinyvec)
-//! - [`uuid`](https://docs.rs/uuid)
+//! - [`uuid`](https://docs.rs/uuid) *`Uuid` is its own archived type.*
 //!
 //! Support for each of these crates can be enabled with a feature of the same
 //! name. Additionally, the following external crate features are available:
@@ -119,12 +174,11 @@
     rustdoc::missing_crate_level_docs
 )]
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![cfg_attr(
-    feature = "copy",
-    feature(auto_traits),
-    feature(min_specialization),
-    feature(negative_impls),
-    feature(rustc_attrs)
+    rkyv_strict_provenance,
+    feature(strict_provenance_lints),
+    deny(fuzzy_provenance_casts, lossy_provenance_casts)
 )]
 #![doc(html_favicon_url = r#"
     data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg'
@@ -150,6 +204,9 @@ pub use ::bytecheck;
 pub use ::ptr_meta;
 pub use ::rancor;
 pub use ::rend;
+#[cfg(feature = "alloc")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "alloc")))]
+pub use ::rkyv_derive::rkyv_enum_dispatch;
 pub use ::rkyv_derive::{Archive, Deserialize, Portable, Serialize};
 
 // Modules
@@ -157,38 +214,91 @@ pub use ::rkyv_derive::{Archive, Deserialize, Portable, Serialize};
 mod alias;
 #[macro_use]
 mod _macros;
+#[cfg(feature = "arrow")]
+pub mod arrow;
 #[cfg(feature = "bitvec")]
 pub mod bitvec;
 pub mod boxed;
+#[cfg(feature = "c_api")]
+pub mod c_api;
+pub mod callable;
+#[cfg(feature = "alloc")]
+pub mod chain;
+pub mod cmp;
 pub mod collections;
-#[cfg(feature = "copy")]
+#[cfg(feature = "alloc")]
+pub mod columnar;
+pub mod compact;
+#[cfg(feature = "alloc")]
+pub mod compat;
+pub mod convert;
 pub mod copy;
 pub mod de;
+#[cfg(feature = "alloc")]
+pub mod deep_size;
+#[cfg(feature = "alloc")]
+pub mod dispatch;
+#[cfg(feature = "alloc")]
+pub mod envelope;
+pub mod external;
 // This is pretty unfortunate. CStr doesn't rely on the rest of std, but it's
 // not in core. If CStr ever gets moved into `core` then this module will no
 // longer need cfg(feature = "std")
 #[cfg(feature = "std")]
 pub mod ffi;
+pub mod flags;
 pub mod hash;
 mod impls;
+pub mod invariant;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "alloc")]
+pub mod layout;
 pub mod net;
 pub mod niche;
+pub mod num;
 pub mod ops;
 pub mod option;
+#[cfg(feature = "ordered-float")]
+pub mod ordered_float;
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+pub mod patch;
+pub mod peek;
 pub mod primitive;
+pub mod project;
 pub mod rc;
+#[cfg(feature = "reflect")]
+pub mod reflect;
 pub mod rel_ptr;
+pub mod render;
 pub mod result;
+#[cfg(feature = "rust_decimal")]
+pub mod rust_decimal;
+#[cfg(all(feature = "alloc", target_has_atomic = "64"))]
+pub mod seqlock;
 pub mod ser;
 mod simd;
+pub mod stable;
+pub mod stable_hash;
 pub mod string;
+pub mod sync;
+pub mod task;
+#[cfg(feature = "test-helpers")]
+pub mod test_util;
 pub mod time;
+#[cfg(feature = "alloc")]
+pub mod trailer;
 pub mod traits;
 pub mod tuple;
+pub mod unpin;
 pub mod util;
 #[cfg(feature = "bytecheck")]
 pub mod validation;
+#[cfg(feature = "std")]
+pub mod value;
 pub mod vec;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod with;
 
 // Exports
@@ -196,22 +306,24 @@ pub mod with;
 #[cfg(feature = "alloc")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "alloc")))]
 #[doc(inline)]
-pub use util::{from_bytes_unchecked, to_bytes};
+pub use util::{
+    from_bytes_default_unchecked, from_bytes_unchecked, to_bytes,
+    to_bytes_default, try_to_bytes,
+};
 #[cfg(all(feature = "bytecheck", feature = "alloc"))]
 #[cfg_attr(
     doc_cfg,
     doc(cfg(all(feature = "bytecheck", feature = "alloc")))
 )]
 #[doc(inline)]
-pub use validation::util::from_bytes;
-#[cfg(feature = "bytecheck")]
-#[cfg_attr(doc_cfg, doc(cfg(feature = "bytecheck")))]
-#[doc(inline)]
-pub use validation::util::{access, access_mut};
+pub use validation::util::{
+    access, access_mut, from_bytes, from_bytes_default, from_bytes_limited,
+};
 
 #[doc(inline)]
 pub use crate::{
     alias::*,
+    peek::peek,
     traits::*,
     util::{access_unchecked, access_unchecked_mut, deserialize, serialize},
 };
@@ -1,4 +1,100 @@
 //! Manually niched type replacements.
 
+pub mod niche_option;
 pub mod option_box;
 pub mod option_nonzero;
+
+use core::num::{NonZeroI8, NonZeroU8};
+
+use crate::{
+    primitive::{
+        ArchivedI128, ArchivedI16, ArchivedI32, ArchivedI64,
+        ArchivedNonZeroI128, ArchivedNonZeroI16, ArchivedNonZeroI32,
+        ArchivedNonZeroI64, ArchivedNonZeroU128, ArchivedNonZeroU16,
+        ArchivedNonZeroU32, ArchivedNonZeroU64, ArchivedU128, ArchivedU16,
+        ArchivedU32, ArchivedU64,
+    },
+    Portable,
+};
+
+/// An archived type with a bit pattern that is never a valid value.
+///
+/// That bit pattern, the niche, can represent `None` when the type is archived
+/// inside of an [`ArchivedNicheOption`](niche_option::ArchivedNicheOption).
+/// This lets `Option<T>` archive in the same space as `T` with
+/// [`NicheOption`](crate::with::NicheOption).
+///
+/// This can be derived for the archived types of enums with only unit variants
+/// with `#[archive(niche)]`.
+///
+/// # Safety
+///
+/// - `NicheRepr` must have the same size as `Self` and an alignment no greater
+///   than that of `Self`.
+/// - Every bit pattern must be a valid `NicheRepr`.
+/// - `NICHE` must not be a valid bit pattern for `Self`.
+pub unsafe trait ArchivedNiche: Portable + Sized {
+    /// A type with the same layout as `Self` that can hold the niche.
+    type NicheRepr: Portable + Copy + PartialEq;
+
+    /// The niche value.
+    const NICHE: Self::NicheRepr;
+
+    /// Returns whether the value pointed to by `ptr` is the niche.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be aligned and point to `size_of::<Self>()` initialized
+    /// bytes.
+    #[inline]
+    unsafe fn is_niche(ptr: *const Self) -> bool {
+        unsafe { *ptr.cast::<Self::NicheRepr>() == Self::NICHE }
+    }
+
+    /// Writes the niche to `out`.
+    ///
+    /// # Safety
+    ///
+    /// `out` must be aligned and valid for writes of `size_of::<Self>()`
+    /// bytes.
+    #[inline]
+    unsafe fn write_niche(out: *mut Self) {
+        unsafe {
+            out.cast::<Self::NicheRepr>().write(Self::NICHE);
+        }
+    }
+}
+
+// SAFETY: `bool` is one byte and only 0 and 1 are valid.
+unsafe impl ArchivedNiche for bool {
+    type NicheRepr = u8;
+
+    const NICHE: u8 = 2;
+}
+
+macro_rules! impl_nonzero_niche {
+    ($($nz:ty: $repr:ty = $zero:expr),* $(,)?) => {
+        $(
+            // SAFETY: Archived nonzero integers have the same layout as their
+            // archived integers and zero is not a valid value.
+            unsafe impl ArchivedNiche for $nz {
+                type NicheRepr = $repr;
+
+                const NICHE: $repr = $zero;
+            }
+        )*
+    };
+}
+
+impl_nonzero_niche! {
+    NonZeroI8: i8 = 0,
+    NonZeroU8: u8 = 0,
+    ArchivedNonZeroI16: ArchivedI16 = ArchivedI16::from_native(0),
+    ArchivedNonZeroI32: ArchivedI32 = ArchivedI32::from_native(0),
+    ArchivedNonZeroI64: ArchivedI64 = ArchivedI64::from_native(0),
+    ArchivedNonZeroI128: ArchivedI128 = ArchivedI128::from_native(0),
+    ArchivedNonZeroU16: ArchivedU16 = ArchivedU16::from_native(0),
+    ArchivedNonZeroU32: ArchivedU32 = ArchivedU32::from_native(0),
+    ArchivedNonZeroU64: ArchivedU64 = ArchivedU64::from_native(0),
+    ArchivedNonZeroU128: ArchivedU128 = ArchivedU128::from_native(0),
+}
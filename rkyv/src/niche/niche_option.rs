@@ -0,0 +1,204 @@
+//! An archived `Option<T>` that stores `None` in a niche of `T`.
+
+use core::{cmp, fmt, hash, mem::MaybeUninit, pin::Pin};
+
+use crate::{niche::ArchivedNiche, Archive, Portable};
+
+/// An archived `Option<T>` that is the same size as `T`.
+///
+/// `None` is represented by the [niche](ArchivedNiche) of `T`, and `Some` by
+/// a valid `T`. This is the archived type of an `Option` archived with
+/// [`NicheOption`](crate::with::NicheOption).
+#[repr(transparent)]
+pub struct ArchivedNicheOption<T> {
+    repr: MaybeUninit<T>,
+}
+
+// SAFETY: `ArchivedNicheOption<T>` is a transparent wrapper around `T`, which
+// is `Portable`.
+unsafe impl<T: ArchivedNiche> Portable for ArchivedNicheOption<T> {}
+
+impl<T: ArchivedNiche> ArchivedNicheOption<T> {
+    /// Returns `true` if the option is a `None` value.
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        unsafe { T::is_niche(self.repr.as_ptr()) }
+    }
+
+    /// Returns `true` if the option is a `Some` value.
+    #[inline]
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    /// Converts to an `Option<&T>`.
+    #[inline]
+    pub fn as_ref(&self) -> Option<&T> {
+        if self.is_none() {
+            None
+        } else {
+            // SAFETY: Values which are not the niche are valid `T`s.
+            Some(unsafe { self.repr.assume_init_ref() })
+        }
+    }
+
+    /// Converts to an `Option<&mut T>`.
+    #[inline]
+    pub fn as_mut(&mut self) -> Option<&mut T> {
+        if self.is_none() {
+            None
+        } else {
+            // SAFETY: Values which are not the niche are valid `T`s.
+            Some(unsafe { self.repr.assume_init_mut() })
+        }
+    }
+
+    /// Converts from `Pin<&ArchivedNicheOption<T>>` to `Option<Pin<&T>>`.
+    #[inline]
+    pub fn as_pin_ref(self: Pin<&Self>) -> Option<Pin<&T>> {
+        unsafe { Pin::get_ref(self).as_ref().map(|x| Pin::new_unchecked(x)) }
+    }
+
+    /// Converts from `Pin<&mut ArchivedNicheOption<T>>` to
+    /// `Option<Pin<&mut T>>`.
+    #[inline]
+    pub fn as_pin_mut(self: Pin<&mut Self>) -> Option<Pin<&mut T>> {
+        unsafe {
+            Pin::get_unchecked_mut(self)
+                .as_mut()
+                .map(|x| Pin::new_unchecked(x))
+        }
+    }
+
+    /// Returns an iterator over the possibly contained value.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.as_ref(),
+        }
+    }
+
+    /// Returns a mutable iterator over the possibly contained value.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            inner: self.as_mut(),
+        }
+    }
+
+    /// Resolves an `ArchivedNicheOption<U::Archived>` from an `Option<&U>`.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing `field`
+    #[inline]
+    pub unsafe fn resolve_from_option<U: Archive<Archived = T>>(
+        field: Option<&U>,
+        pos: usize,
+        resolver: Option<U::Resolver>,
+        out: *mut Self,
+    ) {
+        let out = out.cast::<T>();
+        match (field, resolver) {
+            (Some(value), Some(resolver)) => {
+                value.resolve(pos, resolver, out);
+            }
+            _ => T::write_niche(out),
+        }
+    }
+}
+
+impl<T: ArchivedNiche + fmt::Debug> fmt::Debug for ArchivedNicheOption<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_ref() {
+            Some(inner) => inner.fmt(f),
+            None => f.debug_tuple("None").finish(),
+        }
+    }
+}
+
+impl<T: ArchivedNiche + Eq> Eq for ArchivedNicheOption<T> {}
+
+impl<T: ArchivedNiche + hash::Hash> hash::Hash for ArchivedNicheOption<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
+impl<T: ArchivedNiche + Ord> Ord for ArchivedNicheOption<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.as_ref().cmp(&other.as_ref())
+    }
+}
+
+impl<T: ArchivedNiche + PartialEq> PartialEq for ArchivedNicheOption<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref().eq(&other.as_ref())
+    }
+}
+
+impl<T: ArchivedNiche + PartialOrd> PartialOrd for ArchivedNicheOption<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.as_ref().partial_cmp(&other.as_ref())
+    }
+}
+
+impl<T, U> PartialEq<Option<U>> for ArchivedNicheOption<T>
+where
+    T: ArchivedNiche + PartialEq<U>,
+{
+    #[inline]
+    fn eq(&self, other: &Option<U>) -> bool {
+        match (self.as_ref(), other) {
+            (Some(a), Some(b)) => a.eq(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// An iterator over a reference to the `Some` variant of an
+/// `ArchivedNicheOption`.
+///
+/// This iterator yields one value if the `ArchivedNicheOption` is a `Some`,
+/// otherwise none.
+pub type Iter<'a, T> = crate::option::Iter<'a, T>;
+
+/// An iterator over a mutable reference to the `Some` variant of an
+/// `ArchivedNicheOption`.
+///
+/// This iterator yields one value if the `ArchivedNicheOption` is a `Some`,
+/// otherwise none.
+pub type IterMut<'a, T> = crate::option::IterMut<'a, T>;
+
+#[cfg(feature = "bytecheck")]
+const _: () = {
+    use bytecheck::{rancor::Fallible, CheckBytes};
+
+    // SAFETY: The niche is a valid `None`, and all other values are checked as
+    // `T`s.
+    unsafe impl<T, C> CheckBytes<C> for ArchivedNicheOption<T>
+    where
+        T: ArchivedNiche + CheckBytes<C>,
+        C: Fallible + ?Sized,
+    {
+        unsafe fn check_bytes(
+            value: *const Self,
+            context: &mut C,
+        ) -> Result<(), C::Error> {
+            let value = value.cast::<T>();
+            if unsafe { !T::is_niche(value) } {
+                unsafe {
+                    T::check_bytes(value, context)?;
+                }
+            }
+            Ok(())
+        }
+    }
+};
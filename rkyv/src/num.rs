@@ -0,0 +1,25 @@
+//! Archived versions of `num` types.
+
+use crate::Portable;
+
+/// An archived [`FpCategory`](::core::num::FpCategory).
+///
+/// The discriminants of the archived variants are fixed and do not depend on
+/// the declaration order of `FpCategory`.
+#[derive(Portable)]
+#[archive(crate)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u8)]
+pub enum ArchivedFpCategory {
+    /// NaN (not a number).
+    Nan = 0,
+    /// Positive or negative infinity.
+    Infinite = 1,
+    /// Positive or negative zero.
+    Zero = 2,
+    /// A subnormal floating point number.
+    Subnormal = 3,
+    /// A normal floating point number.
+    Normal = 4,
+}
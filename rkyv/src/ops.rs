@@ -1,8 +1,8 @@
 //! Archived versions of `ops` types.
 
 use core::{
-    cmp, fmt,
-    ops::{Bound, RangeBounds},
+    cmp, fmt, hash,
+    ops::{Bound, ControlFlow, RangeBounds},
 };
 
 use crate::Portable;
@@ -245,3 +245,126 @@ impl<T> RangeBounds<T> for ArchivedRangeToInclusive<T> {
         Bound::Included(&self.end)
     }
 }
+
+/// An archived [`ControlFlow`] that tells an operation whether to exit early
+/// ([`Break`](ArchivedControlFlow::Break)) or go on as usual
+/// ([`Continue`](ArchivedControlFlow::Continue)).
+///
+/// The tag of `Continue` is always 0 and the tag of `Break` is always 1.
+#[derive(Debug, Portable)]
+#[archive(crate)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[repr(u8)]
+pub enum ArchivedControlFlow<B, C> {
+    /// Move on to the next phase of the operation as normal.
+    Continue(C) = 0,
+    /// Exit the operation without running subsequent phases.
+    Break(B) = 1,
+}
+
+impl<B, C> ArchivedControlFlow<B, C> {
+    /// Returns `true` if this is a [`Break`](ArchivedControlFlow::Break)
+    /// variant.
+    #[inline]
+    pub const fn is_break(&self) -> bool {
+        matches!(self, ArchivedControlFlow::Break(_))
+    }
+
+    /// Returns `true` if this is a [`Continue`](ArchivedControlFlow::Continue)
+    /// variant.
+    #[inline]
+    pub const fn is_continue(&self) -> bool {
+        matches!(self, ArchivedControlFlow::Continue(_))
+    }
+
+    /// Returns the value in the [`Break`](ArchivedControlFlow::Break) variant,
+    /// if any.
+    #[inline]
+    pub fn break_value(&self) -> Option<&B> {
+        match self {
+            ArchivedControlFlow::Continue(_) => None,
+            ArchivedControlFlow::Break(value) => Some(value),
+        }
+    }
+
+    /// Returns the value in the [`Continue`](ArchivedControlFlow::Continue)
+    /// variant, if any.
+    #[inline]
+    pub fn continue_value(&self) -> Option<&C> {
+        match self {
+            ArchivedControlFlow::Continue(value) => Some(value),
+            ArchivedControlFlow::Break(_) => None,
+        }
+    }
+
+    /// Converts from `&ArchivedControlFlow<B, C>` to `ControlFlow<&B, &C>`.
+    #[inline]
+    pub fn as_ref(&self) -> ControlFlow<&B, &C> {
+        match self {
+            ArchivedControlFlow::Continue(value) => {
+                ControlFlow::Continue(value)
+            }
+            ArchivedControlFlow::Break(value) => ControlFlow::Break(value),
+        }
+    }
+
+    /// Converts from `&mut ArchivedControlFlow<B, C>` to
+    /// `ControlFlow<&mut B, &mut C>`.
+    #[inline]
+    pub fn as_mut(&mut self) -> ControlFlow<&mut B, &mut C> {
+        match self {
+            ArchivedControlFlow::Continue(value) => {
+                ControlFlow::Continue(value)
+            }
+            ArchivedControlFlow::Break(value) => ControlFlow::Break(value),
+        }
+    }
+}
+
+impl<B: Eq, C: Eq> Eq for ArchivedControlFlow<B, C> {}
+
+impl<B: hash::Hash, C: hash::Hash> hash::Hash for ArchivedControlFlow<B, C> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
+impl<B: PartialEq, C: PartialEq> PartialEq for ArchivedControlFlow<B, C> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref().eq(&other.as_ref())
+    }
+}
+
+impl<B, C, AB, AC> PartialEq<ControlFlow<B, C>> for ArchivedControlFlow<AB, AC>
+where
+    AB: PartialEq<B>,
+    AC: PartialEq<C>,
+{
+    #[inline]
+    fn eq(&self, other: &ControlFlow<B, C>) -> bool {
+        match (self, other) {
+            (
+                ArchivedControlFlow::Continue(self_value),
+                ControlFlow::Continue(other_value),
+            ) => self_value.eq(other_value),
+            (
+                ArchivedControlFlow::Break(self_value),
+                ControlFlow::Break(other_value),
+            ) => self_value.eq(other_value),
+            _ => false,
+        }
+    }
+}
+
+impl<B, C, AB, AC> PartialEq<ArchivedControlFlow<AB, AC>> for ControlFlow<B, C>
+where
+    AB: PartialEq<B>,
+    AC: PartialEq<C>,
+{
+    #[inline]
+    fn eq(&self, other: &ArchivedControlFlow<AB, AC>) -> bool {
+        other.eq(self)
+    }
+}
@@ -2,13 +2,13 @@
 
 use core::{
     cmp, hash,
-    iter::DoubleEndedIterator,
+    iter::{DoubleEndedIterator, FusedIterator},
     mem,
     ops::{Deref, DerefMut},
     pin::Pin,
 };
 
-use crate::Portable;
+use crate::{unpin::ArchivedNoRelPtrs, Portable};
 
 /// An archived [`Option`].
 ///
@@ -25,10 +25,25 @@ pub enum ArchivedOption<T> {
     Some(T),
 }
 
+// SAFETY: `ArchivedOption` only contains its tag and its value.
+unsafe impl<T: ArchivedNoRelPtrs> ArchivedNoRelPtrs for ArchivedOption<T> {}
+
 impl<T> ArchivedOption<T> {
-    /// Transforms the `ArchivedOption<T>` into a `Result<T, E>`, mapping
-    /// `Some(v)` to `Ok(v)` and `None` to `Err(err)`.
-    pub fn ok_or<E>(self, err: E) -> Result<T, E> {
+    /// Transforms the `&ArchivedOption<T>` into a `Result<&T, E>`, mapping
+    /// `Some(v)` to `Ok(&v)` and `None` to `Err(err)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// let x = ArchivedOption::Some("foo");
+    /// assert_eq!(x.ok_or(0), Ok(&"foo"));
+    ///
+    /// let x: ArchivedOption<&str> = ArchivedOption::None;
+    /// assert_eq!(x.ok_or(0), Err(0));
+    /// ```
+    #[inline]
+    pub fn ok_or<E>(&self, err: E) -> Result<&T, E> {
         match self {
             ArchivedOption::None => Err(err),
             ArchivedOption::Some(x) => Ok(x),
@@ -43,8 +58,18 @@ impl<T> ArchivedOption<T> {
             ArchivedOption::Some(value) => value,
         }
     }
-    /// Returns the contained [`Some`] value or a provided default.
-    pub fn unwrap_or(self, default: T) -> T {
+    /// Returns a reference to the contained [`Some`] value or a provided
+    /// default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// assert_eq!(ArchivedOption::Some("car").unwrap_or(&"bike"), &"car");
+    /// assert_eq!(ArchivedOption::None.unwrap_or(&"bike"), &"bike");
+    /// ```
+    #[inline]
+    pub fn unwrap_or<'a>(&'a self, default: &'a T) -> &'a T {
         match self {
             ArchivedOption::None => default,
             ArchivedOption::Some(value) => value,
@@ -57,6 +82,100 @@ impl<T> ArchivedOption<T> {
             ArchivedOption::Some(value) => value,
         }
     }
+    /// Maps an `&ArchivedOption<T>` to an `Option<U>` by applying a function to
+    /// a reference to the contained value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// let maybe_some_string = ArchivedOption::Some("Hello, World!");
+    /// let maybe_some_len = maybe_some_string.map(|s| s.len());
+    /// assert_eq!(maybe_some_len, Some(13));
+    ///
+    /// let x: ArchivedOption<&str> = ArchivedOption::None;
+    /// assert_eq!(x.map(|s| s.len()), None);
+    /// ```
+    #[inline]
+    pub fn map<U, F: FnOnce(&T) -> U>(&self, f: F) -> Option<U> {
+        self.as_ref().map(f)
+    }
+
+    /// Returns `None` if the option is `None`, otherwise calls `f` with a
+    /// reference to the contained value and returns the result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// fn sq_then_to_string(x: &u32) -> Option<String> {
+    ///     x.checked_mul(*x).map(|sq| sq.to_string())
+    /// }
+    ///
+    /// assert_eq!(
+    ///     ArchivedOption::Some(2).and_then(sq_then_to_string),
+    ///     Some(4.to_string()),
+    /// );
+    /// // overflowed!
+    /// assert_eq!(
+    ///     ArchivedOption::Some(1_000_000).and_then(sq_then_to_string),
+    ///     None,
+    /// );
+    /// assert_eq!(ArchivedOption::None.and_then(sq_then_to_string), None);
+    /// ```
+    #[inline]
+    pub fn and_then<U, F: FnOnce(&T) -> Option<U>>(&self, f: F) -> Option<U> {
+        self.as_ref().and_then(f)
+    }
+
+    /// Returns `None` if the option is `None`, otherwise calls `predicate` with
+    /// a reference to the contained value and returns:
+    ///
+    /// - `Some(&t)` if `predicate` returns `true` (where `t` is the contained
+    ///   value), and
+    /// - `None` if `predicate` returns `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// fn is_even(n: &i32) -> bool {
+    ///     n % 2 == 0
+    /// }
+    ///
+    /// assert_eq!(ArchivedOption::None.filter(is_even), None);
+    /// assert_eq!(ArchivedOption::Some(3).filter(is_even), None);
+    /// assert_eq!(ArchivedOption::Some(4).filter(is_even), Some(&4));
+    /// ```
+    #[inline]
+    pub fn filter<P: FnOnce(&T) -> bool>(&self, predicate: P) -> Option<&T> {
+        self.as_ref().filter(|value| predicate(value))
+    }
+
+    /// Zips `self` with another `ArchivedOption`.
+    ///
+    /// If `self` is `Some(s)` and `other` is `Some(o)`, this method returns
+    /// `Some((&s, &o))`. Otherwise, `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// let x = ArchivedOption::Some(1);
+    /// let y = ArchivedOption::Some("hi");
+    /// let z = ArchivedOption::<u8>::None;
+    ///
+    /// assert_eq!(x.zip(&y), Some((&1, &"hi")));
+    /// assert_eq!(x.zip(&z), None);
+    /// ```
+    #[inline]
+    pub fn zip<'a, U>(
+        &'a self,
+        other: &'a ArchivedOption<U>,
+    ) -> Option<(&'a T, &'a U)> {
+        self.as_ref().zip(other.as_ref())
+    }
+
     /// Returns `true` if the option is a `None` value.
     #[inline]
     pub fn is_none(&self) -> bool {
@@ -109,6 +228,18 @@ impl<T> ArchivedOption<T> {
         }
     }
 
+    /// Converts from `Pin<&mut ArchivedOption<T>>` to `Option<&mut T>` for
+    /// values which can be mutated without pinning.
+    #[inline]
+    pub fn as_mut_unpinned(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        T: ArchivedNoRelPtrs,
+    {
+        // SAFETY: `T` does not contain any relative pointers, so it can be
+        // mutated without pinning.
+        unsafe { Pin::get_unchecked_mut(self).as_mut() }
+    }
+
     /// Returns an iterator over the possibly contained value.
     #[inline]
     pub const fn iter(&self) -> Iter<'_, T> {
@@ -145,6 +276,28 @@ impl<T> ArchivedOption<T> {
     }
 }
 
+impl<T> ArchivedOption<ArchivedOption<T>> {
+    /// Converts from `&ArchivedOption<ArchivedOption<T>>` to `Option<&T>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// let x = ArchivedOption::Some(ArchivedOption::Some(6));
+    /// assert_eq!(x.flatten(), Some(&6));
+    ///
+    /// let x = ArchivedOption::Some(ArchivedOption::<u32>::None);
+    /// assert_eq!(x.flatten(), None);
+    ///
+    /// let x = ArchivedOption::<ArchivedOption<u32>>::None;
+    /// assert_eq!(x.flatten(), None);
+    /// ```
+    #[inline]
+    pub fn flatten(&self) -> Option<&T> {
+        self.as_ref().and_then(ArchivedOption::as_ref)
+    }
+}
+
 impl<T: Deref> ArchivedOption<T> {
     /// Converts from `&ArchivedOption<T>` to `Option<&T::Target>`.
     ///
@@ -266,6 +419,44 @@ impl<T> From<T> for ArchivedOption<T> {
     }
 }
 
+impl<'a, T> From<&'a ArchivedOption<T>> for Option<&'a T> {
+    /// Converts from `&ArchivedOption<T>` to `Option<&T>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rkyv::option::ArchivedOption;
+    /// let s = ArchivedOption::Some(String::from("Hello, Rustaceans!"));
+    /// let o: Option<usize> = Option::from(&s).map(|ss: &String| ss.len());
+    ///
+    /// assert_eq!(o, Some(18));
+    /// ```
+    #[inline]
+    fn from(value: &'a ArchivedOption<T>) -> Self {
+        value.as_ref()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArchivedOption<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ArchivedOption<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 /// An iterator over a reference to the `Some` variant of an `ArchivedOption`.
 ///
 /// This iterator yields one value if the `ArchivedOption` is a `Some`,
@@ -284,6 +475,11 @@ impl<'a, T> Iterator for Iter<'a, T> {
         mem::swap(&mut self.inner, &mut result);
         result
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.inner.is_some() as usize;
+        (len, Some(len))
+    }
 }
 
 impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
@@ -292,6 +488,10 @@ impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
     }
 }
 
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
 /// An iterator over a mutable reference to the `Some` variant of an
 /// `ArchivedOption`.
 ///
@@ -311,6 +511,11 @@ impl<'a, T> Iterator for IterMut<'a, T> {
         mem::swap(&mut self.inner, &mut result);
         result
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.inner.is_some() as usize;
+        (len, Some(len))
+    }
 }
 
 impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
@@ -319,15 +524,154 @@ impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
     }
 }
 
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use crate::{
+        option::ArchivedOption,
+        reflect::{descriptor, DescriptorKind, Reflect, TypeDescriptor},
+    };
+
+    impl<T: Reflect> Reflect for ArchivedOption<T> {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "Option",
+            DescriptorKind::Option {
+                inner: descriptor::<T>,
+            },
+        );
+    }
+}
+
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+mod patch {
+    use crate::{
+        option::ArchivedOption,
+        patch::{Patchable, Relocation},
+    };
+
+    unsafe impl<T: Patchable> Patchable for ArchivedOption<T> {
+        fn relocations(&self, f: &mut dyn FnMut(Relocation)) {
+            if let ArchivedOption::Some(value) = self {
+                let offset =
+                    (value as *const T).addr() - (self as *const Self).addr();
+                value.relocations(&mut |relocation| {
+                    f(match relocation {
+                        Relocation::Offset(pos) => {
+                            Relocation::Offset(offset + pos)
+                        }
+                        Relocation::LittleEndianOffset(pos) => {
+                            Relocation::LittleEndianOffset(offset + pos)
+                        }
+                    })
+                });
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use core::{cmp::Ordering, pin::Pin};
+
+    use super::ArchivedOption;
+
+    fn some<T>(value: T) -> ArchivedOption<T> {
+        ArchivedOption::Some(value)
+    }
+
+    fn none<T>() -> ArchivedOption<T> {
+        ArchivedOption::None
+    }
+
     #[test]
-    #[cfg(feature = "extra_traits")]
-    fn partial_ord_option() {
-        use core::cmp::Ordering;
+    fn combinators_match_option() {
+        let options = [some(2u32), some(3), some(u32::MAX), none()];
+        for archived in options.iter() {
+            let option = archived.as_ref().copied();
+            assert_eq!(archived.map(|x| x / 2), option.map(|x| x / 2));
+            assert_eq!(
+                archived.and_then(|x| x.checked_mul(*x)),
+                option.and_then(|x| x.checked_mul(x)),
+            );
+            assert_eq!(
+                archived.filter(|x| *x % 2 == 0),
+                option.as_ref().filter(|x| **x % 2 == 0),
+            );
+            assert_eq!(
+                archived.zip(&some('a')),
+                option.as_ref().zip(Some(&'a')),
+            );
+            assert_eq!(archived.zip(&none::<char>()), None);
+            assert_eq!(archived.unwrap_or(&7), option.as_ref().unwrap_or(&7));
+            assert_eq!(archived.ok_or("none"), option.as_ref().ok_or("none"));
+            assert_eq!(Option::from(archived), option.as_ref());
+            assert!(archived.iter().eq(option.iter()));
+            assert!(archived.into_iter().eq(option.as_ref()));
+            assert_eq!(archived.iter().len(), option.iter().len());
+        }
+    }
 
-        use super::ArchivedOption;
+    #[test]
+    fn flatten() {
+        assert_eq!(some(some(6)).flatten(), Some(&6));
+        assert_eq!(some(none::<u32>()).flatten(), None);
+        assert_eq!(none::<ArchivedOption<u32>>().flatten(), None);
+        // Flattening only removes one level of nesting at a time
+        let x = some(some(some(6)));
+        assert_eq!(x.flatten(), Some(&some(6)));
+        assert_eq!(x.flatten().map(|x| x.as_ref()), Some(Some(&6)));
+    }
 
+    #[test]
+    fn as_pin_mut() {
+        let mut x = some(5);
+        if let Some(mut value) = Pin::new(&mut x).as_pin_mut() {
+            *value += 1;
+        }
+        assert_eq!(x, Some(6));
+
+        let mut x = none::<u32>();
+        assert!(Pin::new(&mut x).as_pin_mut().is_none());
+        assert!(Pin::new(&x).as_pin_ref().is_none());
+    }
+
+    #[test]
+    fn eq_option() {
+        assert_eq!(some(1), Some(1));
+        assert_eq!(Some(1), some(1));
+        assert_ne!(some(1), Some(2));
+        assert_ne!(Some(2), some(1));
+        assert_ne!(some(1), None::<i32>);
+        assert_ne!(None::<i32>, some(1));
+        assert_ne!(none::<i32>(), Some(1));
+        assert_ne!(Some(1), none::<i32>());
+        assert_eq!(none::<u32>(), None::<u32>);
+        assert_eq!(None::<u32>, none::<u32>());
+    }
+
+    #[test]
+    fn ord_matches_option() {
+        let options = [none(), some(1u32), some(2)];
+        for a in options.iter() {
+            for b in options.iter() {
+                let (x, y) = (a.as_ref(), b.as_ref());
+                assert_eq!(a.cmp(b), x.cmp(&y));
+                assert_eq!(a.partial_cmp(b), x.partial_cmp(&y));
+            }
+        }
+        assert_eq!(none::<u32>().cmp(&some(0)), Ordering::Less);
+
+        let nan = some(f32::NAN);
+        assert_eq!(nan.partial_cmp(&nan), None);
+        assert_eq!(nan.partial_cmp(&none()), Some(Ordering::Greater));
+    }
+
+    #[test]
+    #[cfg(feature = "extra_traits")]
+    fn partial_ord_option() {
         let a: ArchivedOption<u8> = ArchivedOption::Some(42);
         let b = Some(42);
         assert_eq!(Some(Ordering::Equal), a.partial_cmp(&b));
@@ -0,0 +1,124 @@
+//! Archived versions of `ordered-float` types.
+
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    primitive::{ArchivedF32, ArchivedF64},
+    Portable,
+};
+
+/// An archived [`OrderedFloat`].
+///
+/// Archived ordered floats compare, order, and hash the same way as the
+/// `OrderedFloat`s they were serialized from, so they can be used as the keys
+/// of archived hash maps and B-trees.
+#[derive(Clone, Copy, Portable)]
+#[archive(crate)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[repr(transparent)]
+pub struct ArchivedOrderedFloat<F>(pub(crate) F);
+
+macro_rules! impl_archived_ordered_float {
+    ($archived:ty, $native:ty) => {
+        impl ArchivedOrderedFloat<$archived> {
+            /// Creates a new archived ordered float from a native float.
+            #[inline]
+            pub fn from_native(value: $native) -> Self {
+                Self(<$archived>::from_native(value))
+            }
+
+            /// Returns the native float value.
+            #[inline]
+            pub fn into_inner(self) -> $native {
+                self.0.to_native()
+            }
+
+            /// Returns the value as a native `OrderedFloat`.
+            #[inline]
+            pub fn to_native(&self) -> OrderedFloat<$native> {
+                OrderedFloat(self.0.to_native())
+            }
+        }
+
+        impl fmt::Debug for ArchivedOrderedFloat<$archived> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.to_native().fmt(f)
+            }
+        }
+
+        impl fmt::Display for ArchivedOrderedFloat<$archived> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.to_native().fmt(f)
+            }
+        }
+
+        impl Hash for ArchivedOrderedFloat<$archived> {
+            #[inline]
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.to_native().hash(state);
+            }
+        }
+
+        impl PartialEq for ArchivedOrderedFloat<$archived> {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                self.to_native() == other.to_native()
+            }
+        }
+
+        impl Eq for ArchivedOrderedFloat<$archived> {}
+
+        impl PartialOrd for ArchivedOrderedFloat<$archived> {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for ArchivedOrderedFloat<$archived> {
+            #[inline]
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.to_native().cmp(&other.to_native())
+            }
+        }
+
+        impl PartialEq<OrderedFloat<$native>>
+            for ArchivedOrderedFloat<$archived>
+        {
+            #[inline]
+            fn eq(&self, other: &OrderedFloat<$native>) -> bool {
+                self.to_native() == *other
+            }
+        }
+
+        impl PartialEq<ArchivedOrderedFloat<$archived>>
+            for OrderedFloat<$native>
+        {
+            #[inline]
+            fn eq(&self, other: &ArchivedOrderedFloat<$archived>) -> bool {
+                other.eq(self)
+            }
+        }
+
+        impl PartialOrd<OrderedFloat<$native>>
+            for ArchivedOrderedFloat<$archived>
+        {
+            #[inline]
+            fn partial_cmp(
+                &self,
+                other: &OrderedFloat<$native>,
+            ) -> Option<Ordering> {
+                Some(self.to_native().cmp(other))
+            }
+        }
+    };
+}
+
+impl_archived_ordered_float!(ArchivedF32, f32);
+impl_archived_ordered_float!(ArchivedF64, f64);
@@ -0,0 +1,607 @@
+//! Copy-on-write patches over immutable archives.
+//!
+//! An [`ArchivePatch`] records replacement values for parts of a read-only
+//! base archive without modifying it. Replaced values are located with a
+//! closure that projects from the archived root to the archived value, and
+//! are serialized on their own. A patch serializes with rkyv, so only the
+//! edits need to be persisted.
+//!
+//! A [`PatchedArchive`] reads a base archive through a serialized patch.
+//! Values which were replaced are read from the patch, and all other values
+//! are read from the base archive. A patched archive can also be materialized
+//! into a new, complete archive with all of the replacements applied.
+//!
+//! Replaced values must implement [`Patchable`]. This includes the archived
+//! primitives, `ArchivedOption`, and out-of-line values like `ArchivedString`,
+//! `ArchivedVec`, and `ArchivedBox`, which are replaced as whole subtrees.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     patch::{ArchivePatch, PatchedArchive},
+//!     rancor::Failure,
+//!     to_bytes,
+//!     util::AlignedVec,
+//!     Archive, Deserialize, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize, Deserialize)]
+//! #[archive(check_bytes)]
+//! struct Config {
+//!     retries: u32,
+//!     name: String,
+//! }
+//!
+//! let base = to_bytes::<_, 256, Failure>(&Config {
+//!     retries: 3,
+//!     name: "base".to_string(),
+//! })
+//! .unwrap();
+//!
+//! let mut patch = ArchivePatch::<ArchivedConfig>::new::<Failure>(&base)
+//!     .unwrap();
+//! patch.set::<u32, Failure>(|c| &c.retries, &5).unwrap();
+//! let patch_bytes = patch.to_bytes::<Failure>().unwrap();
+//!
+//! let mut reloaded = AlignedVec::new();
+//! reloaded.extend_from_slice(&patch_bytes);
+//! let patched =
+//!     PatchedArchive::<ArchivedConfig>::new::<Failure>(&base, &reloaded)
+//!         .unwrap();
+//! assert_eq!(*patched.get::<_, Failure>(|c| &c.retries).unwrap(), 5);
+//! assert_eq!(patched.get::<_, Failure>(|c| &c.name).unwrap(), "base");
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    fmt,
+    mem::{size_of, transmute},
+};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use bytecheck::CheckBytes;
+use rancor::{fail, Error, Strategy};
+
+use crate::{
+    de::pooling::Unify,
+    deserialize,
+    primitive::{
+        ArchivedChar, ArchivedF32, ArchivedF64, ArchivedI128, ArchivedI16,
+        ArchivedI32, ArchivedI64, ArchivedIsize, ArchivedU128, ArchivedU16,
+        ArchivedU32, ArchivedU64, FixedIsize,
+    },
+    ser::AllocSerializer,
+    util::{access_unchecked, serialize_into, AlignedVec},
+    validation::{
+        util::{access, access_with_context},
+        validators::DefaultValidator,
+    },
+    with::AlignTo,
+    Archive, Deserialize, Portable, Serialize,
+};
+
+/// The serializer used to write patches and materialized archives.
+pub type PatchSerializer = AllocSerializer<1024>;
+
+/// A relative pointer offset located directly inside of an archived value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relocation {
+    /// An [`ArchivedIsize`] offset at the given position, like the offset of a
+    /// [`RelPtr`](crate::RelPtr).
+    Offset(usize),
+    /// A little-endian [`FixedIsize`] offset at the given position, like the
+    /// offset of an out-of-line
+    /// [`ArchivedString`](crate::string::ArchivedString).
+    LittleEndianOffset(usize),
+}
+
+/// An archived type which can be replaced by a patch.
+///
+/// Replacement values are serialized on their own, so any data they point to
+/// has to be moved when a patch is materialized. Relative pointers inside of
+/// that data move with it, but relative pointers located directly inside of
+/// the value must be adjusted.
+///
+/// # Safety
+///
+/// `relocations` must call `f` with every relative pointer offset located
+/// directly inside of `self`, and no others. Positions are relative to the
+/// start of `self`.
+pub unsafe trait Patchable: Portable {
+    /// Calls `f` with each relative pointer offset located directly inside of
+    /// this value.
+    fn relocations(&self, f: &mut dyn FnMut(Relocation));
+}
+
+macro_rules! impl_patchable {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            unsafe impl Patchable for $ty {
+                #[inline]
+                fn relocations(&self, _: &mut dyn FnMut(Relocation)) {}
+            }
+        )*
+    };
+}
+
+impl_patchable!(
+    (),
+    bool,
+    i8,
+    u8,
+    ArchivedI16,
+    ArchivedI32,
+    ArchivedI64,
+    ArchivedI128,
+    ArchivedU16,
+    ArchivedU32,
+    ArchivedU64,
+    ArchivedU128,
+    ArchivedF32,
+    ArchivedF64,
+    ArchivedChar,
+);
+
+/// An error resulting from an invalid patch.
+#[derive(Debug)]
+pub enum PatchError {
+    /// The patched value was not located inside of the base archive.
+    OutsideArchive,
+    /// The patched value overlapped a value which was already patched.
+    Overlapping {
+        /// The position of the patched value
+        pos: usize,
+    },
+    /// The patch was created for a base archive with a different length.
+    BaseMismatch {
+        /// The length of the base archive the patch was created for
+        expected: usize,
+        /// The length of the base archive
+        actual: usize,
+    },
+    /// A patched value was out of bounds or overlapped another patched value.
+    InvalidEntry {
+        /// The position of the patched value
+        pos: usize,
+    },
+    /// A patched value was read as a type with a different size.
+    SizeMismatch {
+        /// The position of the patched value
+        pos: usize,
+        /// The size of the patched value
+        expected: usize,
+        /// The size of the type it was read as
+        actual: usize,
+    },
+    /// A relocated offset did not fit in an archived offset.
+    OffsetOverflow {
+        /// The position of the patched value
+        pos: usize,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::OutsideArchive => write!(
+                f,
+                "the patched value is not located inside of the base archive",
+            ),
+            PatchError::Overlapping { pos } => write!(
+                f,
+                "the patched value at {:#x} overlaps another patched value",
+                pos,
+            ),
+            PatchError::BaseMismatch { expected, actual } => write!(
+                f,
+                "the patch was created for a base archive of {} bytes, but \
+                 the base archive is {} bytes",
+                expected, actual,
+            ),
+            PatchError::InvalidEntry { pos } => {
+                write!(f, "the patched value at {:#x} is invalid", pos)
+            }
+            PatchError::SizeMismatch {
+                pos,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "the patched value at {:#x} is {} bytes, but was read as a \
+                 type of {} bytes",
+                pos, expected, actual,
+            ),
+            PatchError::OffsetOverflow { pos } => write!(
+                f,
+                "a relocated offset of the patched value at {:#x} overflowed",
+                pos,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatchError {}
+
+#[derive(Archive, Serialize)]
+#[archive(crate)]
+#[archive(check_bytes)]
+enum RawRelocation {
+    Offset(u64),
+    LittleEndianOffset(u64),
+}
+
+#[derive(Archive, Serialize)]
+#[archive(crate)]
+#[archive(check_bytes)]
+struct RawEntry {
+    pos: u64,
+    size: u64,
+    relocations: Vec<RawRelocation>,
+    #[with(AlignTo<16>)]
+    data: Vec<u8>,
+}
+
+#[derive(Archive, Serialize)]
+#[archive(crate)]
+#[archive(check_bytes)]
+struct RawPatch {
+    base_len: u64,
+    entries: Vec<RawEntry>,
+}
+
+struct Entry {
+    size: usize,
+    relocations: Vec<Relocation>,
+    data: AlignedVec,
+}
+
+/// Returns the position of `value` in `base`, or `None` if it is not located
+/// entirely inside of `base`.
+fn position_in<U>(base: &[u8], value: &U) -> Option<usize> {
+    let start = base.as_ptr().addr();
+    let pos = (value as *const U).addr().checked_sub(start)?;
+    (pos.checked_add(size_of::<U>())? <= base.len()).then_some(pos)
+}
+
+/// A set of replacement values for a base archive.
+///
+/// See the [module docs](crate::patch) for an example.
+pub struct ArchivePatch<'a, T> {
+    base: &'a [u8],
+    root: &'a T,
+    entries: BTreeMap<usize, Entry>,
+}
+
+impl<'a, T: Portable> ArchivePatch<'a, T> {
+    /// Creates a new, empty patch for the given base archive.
+    ///
+    /// The base archive is validated before it is accessed.
+    pub fn new<E>(base: &'a [u8]) -> Result<Self, E>
+    where
+        T: CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        Ok(Self {
+            base,
+            root: access::<T, E>(base)?,
+            entries: BTreeMap::new(),
+        })
+    }
+
+    /// Returns the root of the base archive.
+    #[inline]
+    pub fn root(&self) -> &'a T {
+        self.root
+    }
+
+    /// Returns the number of patched values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no values have been patched.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replaces the archived value that `path` projects to with `value`.
+    ///
+    /// `path` is called with the root of the base archive and must return a
+    /// reference to a value inside of the base archive. Replacing a value which
+    /// was already replaced overwrites the previous replacement. Replacing a
+    /// value which overlaps a different replaced value fails.
+    pub fn set<U, E>(
+        &mut self,
+        path: impl FnOnce(&T) -> &U::Archived,
+        value: &U,
+    ) -> Result<(), E>
+    where
+        U: Archive + Serialize<Strategy<PatchSerializer, E>>,
+        U::Archived: Patchable,
+        E: Error,
+    {
+        let size = size_of::<U::Archived>();
+        let pos = match position_in(self.base, path(self.root)) {
+            Some(pos) => pos,
+            None => fail!(PatchError::OutsideArchive),
+        };
+
+        let overlaps_prev = self
+            .entries
+            .range(..pos)
+            .next_back()
+            .is_some_and(|(p, e)| p + e.size > pos);
+        let overlaps_next =
+            self.entries.range(pos..).next().is_some_and(|(p, e)| {
+                *p < pos + size && (*p, e.size) != (pos, size)
+            });
+        if overlaps_prev || overlaps_next {
+            fail!(PatchError::Overlapping { pos });
+        }
+
+        let data =
+            serialize_into(value, PatchSerializer::default())?.into_writer();
+        // SAFETY: `data` was just serialized from a `U`.
+        let archived = unsafe { access_unchecked::<U::Archived>(&data) };
+        let mut relocations = Vec::new();
+        archived.relocations(&mut |r| relocations.push(r));
+
+        self.entries.insert(
+            pos,
+            Entry {
+                size,
+                relocations,
+                data,
+            },
+        );
+        Ok(())
+    }
+
+    /// Serializes the patch and returns the resulting bytes.
+    pub fn to_bytes<E: Error>(&self) -> Result<AlignedVec, E> {
+        let patch = RawPatch {
+            base_len: self.base.len() as u64,
+            entries: self
+                .entries
+                .iter()
+                .map(|(pos, entry)| RawEntry {
+                    pos: *pos as u64,
+                    size: entry.size as u64,
+                    relocations: entry
+                        .relocations
+                        .iter()
+                        .map(|r| match *r {
+                            Relocation::Offset(p) => {
+                                RawRelocation::Offset(p as u64)
+                            }
+                            Relocation::LittleEndianOffset(p) => {
+                                RawRelocation::LittleEndianOffset(p as u64)
+                            }
+                        })
+                        .collect(),
+                    data: entry.data.to_vec(),
+                })
+                .collect(),
+        };
+        Ok(serialize_into(&patch, PatchSerializer::default())?.into_writer())
+    }
+}
+
+/// A base archive read through a serialized patch.
+///
+/// See the [module docs](crate::patch) for an example.
+pub struct PatchedArchive<'a, T> {
+    base: &'a [u8],
+    root: &'a T,
+    patch: &'a ArchivedRawPatch,
+}
+
+impl<'a, T: Portable> PatchedArchive<'a, T> {
+    /// Creates a new patched archive from a base archive and the bytes of a
+    /// serialized [`ArchivePatch`].
+    ///
+    /// The base archive and the patch are both validated. The patch must have
+    /// been created for a base archive of the same length.
+    pub fn new<E>(base: &'a [u8], patch: &'a [u8]) -> Result<Self, E>
+    where
+        T: CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        let root = access::<T, E>(base)?;
+        let patch = access::<ArchivedRawPatch, E>(patch)?;
+
+        let base_len = patch.base_len.to_native() as usize;
+        if base_len != base.len() {
+            fail!(PatchError::BaseMismatch {
+                expected: base_len,
+                actual: base.len(),
+            });
+        }
+
+        let mut end = 0;
+        for entry in patch.entries.iter() {
+            let pos = entry.pos.to_native() as usize;
+            let size = entry.size.to_native() as usize;
+            let is_valid = pos >= end
+                && pos.checked_add(size).is_some_and(|e| e <= base.len())
+                && size <= entry.data.len()
+                && entry.relocations.iter().all(|r| {
+                    let p = match r {
+                        ArchivedRawRelocation::Offset(p)
+                        | ArchivedRawRelocation::LittleEndianOffset(p) => {
+                            p.to_native() as usize
+                        }
+                    };
+                    p.checked_add(size_of::<FixedIsize>())
+                        .is_some_and(|e| e <= size)
+                });
+            if !is_valid {
+                fail!(PatchError::InvalidEntry { pos });
+            }
+            end = pos + size;
+        }
+
+        Ok(Self { base, root, patch })
+    }
+
+    /// Returns the root of the base archive.
+    #[inline]
+    pub fn root(&self) -> &'a T {
+        self.root
+    }
+
+    /// Returns the number of patched values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.patch.entries.len()
+    }
+
+    /// Returns whether no values are patched.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.patch.entries.is_empty()
+    }
+
+    /// Returns the value that `path` projects to, reading it from the patch if
+    /// it was replaced.
+    ///
+    /// `path` is called with the root of the base archive. Only the value that
+    /// `path` returns is looked up in the patch, so values inside of a replaced
+    /// value must be read through the replacement. Replacement values are
+    /// validated every time they are read.
+    pub fn get<U, E>(&self, path: impl FnOnce(&T) -> &U) -> Result<&'a U, E>
+    where
+        U: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        let value = path(self.root);
+        let pos = match position_in(self.base, value) {
+            Some(pos) => pos,
+            None => return Ok(value),
+        };
+
+        let entries = self.patch.entries.as_slice();
+        let index =
+            entries.partition_point(|e| (e.pos.to_native() as usize) < pos);
+        match entries.get(index) {
+            Some(entry) if entry.pos.to_native() as usize == pos => {
+                let size = entry.size.to_native() as usize;
+                if size != size_of::<U>() {
+                    fail!(PatchError::SizeMismatch {
+                        pos,
+                        expected: size,
+                        actual: size_of::<U>(),
+                    });
+                }
+                access::<U, E>(entry.data.as_slice())
+            }
+            _ => Ok(value),
+        }
+    }
+
+    /// Applies the patch to the base archive and returns a new, complete
+    /// archive.
+    ///
+    /// The patched archive is validated, deserialized as an `N`, and serialized
+    /// again. The result is the same as serializing the original value with
+    /// all of the replacements applied.
+    ///
+    /// The data that replacement values point to is validated in the order
+    /// that the replaced values are located in the base archive. Types which
+    /// derive `Archive` are always validated in this order.
+    pub fn materialize<N, E>(&self) -> Result<AlignedVec, E>
+    where
+        N: Archive<Archived = T> + Serialize<Strategy<PatchSerializer, E>>,
+        T: CheckBytes<Strategy<DefaultValidator, E>>
+            + Deserialize<N, Strategy<Unify, E>>,
+        E: Error,
+    {
+        // The data that replacements point to is written before the base
+        // archive so that out-of-line strings still point backwards, and it is
+        // validated as a cold region. Replacements are then written over the
+        // base archive and their offsets are adjusted to point to their data.
+        let mut merged = AlignedVec::new();
+        let mut blocks = Vec::with_capacity(self.patch.entries.len());
+        for entry in self.patch.entries.iter() {
+            let data = entry.data.as_slice();
+            let root = data.len() - entry.size.to_native() as usize;
+            blocks.push(merged.len() + root);
+            merged.extend_from_slice(&data[..root]);
+            merged.resize(merged.len().next_multiple_of(16), 0);
+        }
+        let shift = merged.len();
+        merged.extend_from_slice(self.base);
+
+        for (entry, block) in self.patch.entries.iter().zip(blocks) {
+            let pos = shift + entry.pos.to_native() as usize;
+            let size = entry.size.to_native() as usize;
+            let data = entry.data.as_slice();
+            merged[pos..pos + size].copy_from_slice(&data[data.len() - size..]);
+
+            let delta = block as isize - pos as isize;
+            for relocation in entry.relocations.iter() {
+                let relocated = match relocation {
+                    ArchivedRawRelocation::Offset(p) => relocate(
+                        &mut merged[pos + p.to_native() as usize..],
+                        delta,
+                        // SAFETY: `ArchivedIsize` has the same size as
+                        // `FixedIsize` and every bit pattern is valid.
+                        |bytes| unsafe {
+                            transmute::<
+                                [u8; size_of::<FixedIsize>()],
+                                ArchivedIsize,
+                            >(bytes)
+                            .to_native()
+                        },
+                        |offset| unsafe {
+                            transmute::<
+                                ArchivedIsize,
+                                [u8; size_of::<FixedIsize>()],
+                            >(
+                                ArchivedIsize::from_native(offset)
+                            )
+                        },
+                    ),
+                    ArchivedRawRelocation::LittleEndianOffset(p) => relocate(
+                        &mut merged[pos + p.to_native() as usize..],
+                        delta,
+                        FixedIsize::from_le_bytes,
+                        FixedIsize::to_le_bytes,
+                    ),
+                };
+                if relocated.is_none() {
+                    fail!(PatchError::OffsetOverflow { pos: pos - shift });
+                }
+            }
+        }
+
+        let mut validator =
+            DefaultValidator::with_cold_region(&merged, 0..shift);
+        let root = access_with_context::<T, DefaultValidator, E>(
+            &merged,
+            &mut validator,
+        )?;
+        let value = deserialize::<N, _, E>(root, &mut Unify::default())?;
+        Ok(serialize_into(&value, PatchSerializer::default())?.into_writer())
+    }
+}
+
+/// Adds `delta` to the offset at the start of `bytes`, returning `None` if the
+/// relocated offset overflows.
+fn relocate(
+    bytes: &mut [u8],
+    delta: isize,
+    read: impl FnOnce([u8; size_of::<FixedIsize>()]) -> FixedIsize,
+    write: impl FnOnce(FixedIsize) -> [u8; size_of::<FixedIsize>()],
+) -> Option<()> {
+    let bytes = bytes.get_mut(..size_of::<FixedIsize>())?;
+    let offset = (read(bytes.try_into().ok()?) as isize).checked_add(delta)?;
+    bytes.copy_from_slice(&write(FixedIsize::try_from(offset).ok()?));
+    Some(())
+}
@@ -0,0 +1,412 @@
+//! Reading single scalar fields out of archives without accessing them.
+//!
+//! Accessing an archive checks or trusts the whole root object, which is
+//! wasteful when only one small field of each of many archives is needed. The
+//! [`peek_field!`](crate::peek_field!) macro reads one scalar field of the root
+//! object instead. The offset of the field is computed at compile time with
+//! `offset_of!`, so every step of the path must be a field stored inline in
+//! its parent. Peeking checks that the root object fits in the buffer, reads
+//! the field with the byte order of the archive, and checks that the bytes are
+//! a valid value of its native type. It never creates a reference to the root
+//! object.
+//!
+//! `peek_field!(bytes, Root.a.b -> Target.c)` follows one relative pointer: it
+//! reads the `Box` or [`RelPtr`] at `Root.a.b`, checks that the `Target` it
+//! points to is inside of the buffer, and reads `Target.c`.
+//!
+//! Peeking does not validate the archive. A buffer which is not an archive of
+//! the root type may produce arbitrary values, but never undefined behavior.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{peek::PeekError, peek_field, rancor::Failure, to_bytes};
+//!
+//! #[derive(rkyv::Archive, rkyv::Serialize)]
+//! struct Message {
+//!     header: Header,
+//!     body: Vec<u8>,
+//!     trailer: Box<Trailer>,
+//! }
+//!
+//! #[derive(rkyv::Archive, rkyv::Serialize)]
+//! struct Header {
+//!     kind: u32,
+//!     urgent: bool,
+//! }
+//!
+//! #[derive(rkyv::Archive, rkyv::Serialize)]
+//! struct Trailer {
+//!     checksum: u64,
+//! }
+//!
+//! let message = Message {
+//!     header: Header {
+//!         kind: 7,
+//!         urgent: true,
+//!     },
+//!     body: vec![1, 2, 3],
+//!     trailer: Box::new(Trailer { checksum: 0xfeed }),
+//! };
+//! let bytes = to_bytes::<_, 256, Failure>(&message).unwrap();
+//!
+//! assert_eq!(peek_field!(&bytes, Message.header.kind), Ok(7));
+//! assert_eq!(peek_field!(&bytes, Message.header.urgent), Ok(true));
+//! assert_eq!(
+//!     peek_field!(&bytes, Message.trailer -> Trailer.checksum),
+//!     Ok(0xfeed),
+//! );
+//!
+//! let truncated = &bytes[bytes.len() - 4..];
+//! assert!(matches!(
+//!     peek_field!(truncated, Message.header.kind),
+//!     Err(PeekError::BufferTooSmall { .. }),
+//! ));
+//! ```
+//!
+//! Fields behind a relative pointer can't be peeked without naming the pointer
+//! as the hop:
+//!
+//! ```compile_fail
+//! use rkyv::{peek_field, rancor::Failure, to_bytes};
+//!
+//! #[derive(rkyv::Archive, rkyv::Serialize)]
+//! struct Message {
+//!     body: Vec<u8>,
+//! }
+//!
+//! let message = Message { body: vec![1, 2, 3] };
+//! let bytes = to_bytes::<_, 256, Failure>(&message).unwrap();
+//! let len = peek_field!(&bytes, Message.body.len);
+//! ```
+
+use core::{
+    fmt,
+    mem::size_of,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8,
+        NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8,
+    },
+};
+
+use crate::{
+    boxed::ArchivedBox,
+    primitive::ArchivedIsize,
+    project::Field,
+    rel_ptr::{Offset, RelPtr},
+    rend, Portable,
+};
+
+/// An archived scalar which can be peeked.
+///
+/// # Safety
+///
+/// `peek` must only read `size_of::<Self>()` bytes from `ptr`, and must return
+/// `None` if they are not a valid value of `Native`.
+///
+/// This is implemented for the archived versions of the primitive types in
+/// every byte order.
+pub unsafe trait Peek: Portable {
+    /// The native type of the scalar.
+    type Native;
+
+    /// Reads a scalar from the given pointer, which may be unaligned.
+    ///
+    /// Returns `None` if the bytes are not a valid value of the native type.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads of `size_of::<Self>()` bytes.
+    unsafe fn peek(ptr: *const Self) -> Option<Self::Native>;
+}
+
+macro_rules! impl_peek {
+    ($native:ty, $raw:ty, $from_bytes:ident, $from_raw:expr => $($ty:ty),*) => {
+        $(
+            // SAFETY: The bytes are read as an array, which is always valid,
+            // and only converted to the native type if they are a valid value
+            // of it.
+            unsafe impl Peek for $ty {
+                type Native = $native;
+
+                #[inline]
+                unsafe fn peek(ptr: *const Self) -> Option<Self::Native> {
+                    let bytes = ptr.cast::<[u8; size_of::<$raw>()]>().read();
+                    $from_raw(<$raw>::$from_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_peek!(u8, u8, from_le_bytes, Some => u8);
+impl_peek!(i8, i8, from_le_bytes, Some => i8);
+impl_peek!(NonZeroU8, u8, from_le_bytes, NonZeroU8::new => NonZeroU8);
+impl_peek!(NonZeroI8, i8, from_le_bytes, NonZeroI8::new => NonZeroI8);
+impl_peek!(
+    bool,
+    u8,
+    from_le_bytes,
+    |raw| match raw {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    } => bool
+);
+
+macro_rules! impl_peek_multibyte {
+    ($($native:ty, $raw:ty, $from_raw:expr => $le:ident, $be:ident;)*) => {
+        $(
+            impl_peek!($native, $raw, from_le_bytes, $from_raw => rend::$le);
+            impl_peek!($native, $raw, from_be_bytes, $from_raw => rend::$be);
+        )*
+    };
+}
+
+impl_peek_multibyte! {
+    i16, i16, Some => i16_le, i16_be;
+    i32, i32, Some => i32_le, i32_be;
+    i64, i64, Some => i64_le, i64_be;
+    i128, i128, Some => i128_le, i128_be;
+    u16, u16, Some => u16_le, u16_be;
+    u32, u32, Some => u32_le, u32_be;
+    u64, u64, Some => u64_le, u64_be;
+    u128, u128, Some => u128_le, u128_be;
+    f32, f32, Some => f32_le, f32_be;
+    f64, f64, Some => f64_le, f64_be;
+    char, u32, char::from_u32 => char_le, char_be;
+    NonZeroI16, i16, NonZeroI16::new => NonZeroI16_le, NonZeroI16_be;
+    NonZeroI32, i32, NonZeroI32::new => NonZeroI32_le, NonZeroI32_be;
+    NonZeroI64, i64, NonZeroI64::new => NonZeroI64_le, NonZeroI64_be;
+    NonZeroI128, i128, NonZeroI128::new => NonZeroI128_le, NonZeroI128_be;
+    NonZeroU16, u16, NonZeroU16::new => NonZeroU16_le, NonZeroU16_be;
+    NonZeroU32, u32, NonZeroU32::new => NonZeroU32_le, NonZeroU32_be;
+    NonZeroU64, u64, NonZeroU64::new => NonZeroU64_le, NonZeroU64_be;
+    NonZeroU128, u128, NonZeroU128::new => NonZeroU128_le, NonZeroU128_be;
+}
+
+/// An archived pointer to a sized value which can be followed while peeking.
+///
+/// # Safety
+///
+/// `Self` must start with a relative pointer with an offset of type `Offset`
+/// which points to a `Target`.
+pub unsafe trait PeekPointer: Portable {
+    /// The offset type of the relative pointer.
+    type Offset: Offset;
+    /// The type of the value the pointer points to.
+    type Target: Portable;
+}
+
+// SAFETY: `RelPtr` starts with its raw relative pointer, which is a
+// transparent wrapper around its offset.
+unsafe impl<T: Portable, O: Offset> PeekPointer for RelPtr<T, O>
+where
+    Self: Portable,
+{
+    type Offset = O;
+    type Target = T;
+}
+
+// SAFETY: `ArchivedBox` is a transparent wrapper around a `RelPtr`.
+unsafe impl<T: Portable> PeekPointer for ArchivedBox<T> {
+    type Offset = ArchivedIsize;
+    type Target = T;
+}
+
+/// An error resulting from peeking a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeekError {
+    /// The buffer is too small to contain the root object.
+    BufferTooSmall {
+        /// The size of the root object
+        size: usize,
+        /// The length of the buffer
+        len: usize,
+    },
+    /// The target of a relative pointer is not located completely inside of
+    /// the buffer.
+    OutOfBounds {
+        /// The position of the target relative to the start of the buffer
+        target: isize,
+        /// The size of the target
+        size: usize,
+        /// The length of the buffer
+        len: usize,
+    },
+    /// The bytes of the field are not a valid value of its native type.
+    InvalidValue {
+        /// The position of the field
+        pos: usize,
+    },
+}
+
+impl fmt::Display for PeekError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeekError::BufferTooSmall { size, len } => write!(
+                f,
+                "the root object with size {} does not fit in the buffer with \
+                 length {}",
+                size, len,
+            ),
+            PeekError::OutOfBounds { target, size, len } => write!(
+                f,
+                "the target at {} with size {} is outside of the buffer with \
+                 length {}",
+                target, size, len,
+            ),
+            PeekError::InvalidValue { pos } => write!(
+                f,
+                "the field at {:#x} is not a valid value of its native type",
+                pos,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PeekError {}
+
+/// Returns the position of the root object of type `T` in the buffer.
+#[inline]
+fn root_pos<T>(bytes: &[u8]) -> Result<usize, PeekError> {
+    bytes
+        .len()
+        .checked_sub(size_of::<T>())
+        .ok_or(PeekError::BufferTooSmall {
+            size: size_of::<T>(),
+            len: bytes.len(),
+        })
+}
+
+/// Reads the field of the `T` at `pos`.
+///
+/// # Safety
+///
+/// `pos + size_of::<T>()` must be at most the length of the buffer.
+#[inline]
+unsafe fn read_field<T, F: Peek>(
+    bytes: &[u8],
+    pos: usize,
+    field: Field<T, F>,
+) -> Result<F::Native, PeekError> {
+    let pos = pos + field.offset();
+    F::peek(bytes.as_ptr().add(pos).cast::<F>())
+        .ok_or(PeekError::InvalidValue { pos })
+}
+
+/// Reads a scalar field of the root object of type `T` from the given bytes.
+///
+/// The [`peek_field!`](crate::peek_field!) macro creates the field from a path
+/// of field names. See the [module docs](crate::peek) for more information.
+#[inline]
+pub fn peek<T: Portable, F: Peek>(
+    bytes: &[u8],
+    field: Field<T, F>,
+) -> Result<F::Native, PeekError> {
+    let pos = root_pos::<T>(bytes)?;
+    // SAFETY: The root object ends at the end of the buffer.
+    unsafe { read_field(bytes, pos, field) }
+}
+
+/// Follows the relative pointer in `pointer` of the root object of type `T`
+/// and reads a scalar field of the value it points to.
+///
+/// The [`peek_field!`](crate::peek_field!) macro creates both fields from paths
+/// of field names. See the [module docs](crate::peek) for more information.
+#[inline]
+pub fn peek_through<T, P, U, F>(
+    bytes: &[u8],
+    pointer: Field<T, P>,
+    field: Field<U, F>,
+) -> Result<F::Native, PeekError>
+where
+    T: Portable,
+    P: PeekPointer<Target = U>,
+    U: Portable,
+    F: Peek,
+{
+    let pos = root_pos::<T>(bytes)? + pointer.offset();
+    // SAFETY: The pointer is inside of the root object, which ends at the end
+    // of the buffer. Every bit pattern is a valid offset, and `PeekPointer`
+    // guarantees that the pointer starts with one.
+    let offset = unsafe {
+        bytes
+            .as_ptr()
+            .add(pos)
+            .cast::<P::Offset>()
+            .read_unaligned()
+            .to_isize()
+    };
+    let out_of_bounds = PeekError::OutOfBounds {
+        target: (pos as isize).wrapping_add(offset),
+        size: size_of::<U>(),
+        len: bytes.len(),
+    };
+    let target = pos
+        .checked_add_signed(offset)
+        .filter(|target| {
+            target
+                .checked_add(size_of::<U>())
+                .is_some_and(|end| end <= bytes.len())
+        })
+        .ok_or(out_of_bounds)?;
+    // SAFETY: We just checked that the target is inside of the buffer.
+    unsafe { read_field(bytes, target, field) }
+}
+
+#[doc(hidden)]
+#[inline]
+pub const unsafe fn field_of<T, F>(
+    offset: usize,
+    _: fn(&T) -> &F,
+) -> Field<T, F> {
+    Field::new(offset)
+}
+
+/// Reads a scalar field of the root object of an archive without accessing
+/// it.
+///
+/// `peek_field!(bytes, Type.a.b)` reads the field `a.b` of the archived
+/// `Type` at the end of `bytes`. Every step of the path must be a field stored
+/// inline, so paths through `Vec`s, `Box`es, and other pointers fail to
+/// compile. `peek_field!(bytes, Type.a -> Target.b)` follows the `Box` or
+/// relative pointer at `a` to an archived `Target` and reads its field `b`.
+///
+/// Both forms return a `Result` with the native value of the field or a
+/// [`PeekError`](crate::peek::PeekError). See the [`peek`](mod@crate::peek)
+/// module for more information and an example.
+#[macro_export]
+macro_rules! peek_field {
+    (@field $($ty:ident)::+ . $($field:tt).+) => {
+        // SAFETY: `offset_of!` only accepts fields which are stored inline,
+        // and the offset and type of the field come from the same path.
+        unsafe {
+            $crate::peek::field_of::<$crate::Archived<$($ty)::+>, _>(
+                ::core::mem::offset_of!(
+                    $crate::Archived<$($ty)::+>,
+                    $($field).+
+                ),
+                |value| &value.$($field).+,
+            )
+        }
+    };
+    (
+        $bytes:expr,
+        $($ty:ident)::+ . $($field:tt).+
+        -> $($target:ident)::+ . $($target_field:tt).+ $(,)?
+    ) => {
+        $crate::peek::peek_through(
+            $bytes,
+            $crate::peek_field!(@field $($ty)::+ . $($field).+),
+            $crate::peek_field!(@field $($target)::+ . $($target_field).+),
+        )
+    };
+    ($bytes:expr, $($ty:ident)::+ . $($field:tt).+ $(,)?) => {
+        $crate::peek::peek(
+            $bytes,
+            $crate::peek_field!(@field $($ty)::+ . $($field).+),
+        )
+    };
+}
@@ -0,0 +1,290 @@
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use crate::{
+    primitive::{ArchivedF32, ArchivedF64},
+    rend::{
+        f32_be, f32_le, f64_be, f64_le,
+        unaligned::{f32_ube, f32_ule, f64_ube, f64_ule},
+    },
+    Portable,
+};
+
+/// Additional methods for archived floating-point numbers.
+///
+/// This is implemented for all of the archived float types, including
+/// [`ArchivedF32`] and [`ArchivedF64`].
+pub trait ArchivedFloat: Copy {
+    /// The native float type.
+    type Native;
+    /// The native integer type with the same size as the float.
+    type Bits;
+
+    /// Returns the native float value.
+    fn to_native_float(&self) -> Self::Native;
+
+    /// Returns the raw bits of the float.
+    fn to_bits(&self) -> Self::Bits;
+
+    /// Returns whether the float is NaN.
+    fn is_nan(&self) -> bool;
+
+    /// Returns whether the float is neither infinite nor NaN.
+    fn is_finite(&self) -> bool;
+
+    /// Returns the ordering between `self` and `other` according to the
+    /// `totalOrder` predicate, like `f32::total_cmp`.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+
+    /// Returns whether the float is in canonical form.
+    ///
+    /// Canonical floats are not negative zero, and the only canonical NaN is
+    /// the positive quiet NaN with an empty payload.
+    fn is_canonical(&self) -> bool;
+}
+
+macro_rules! canonicalize {
+    ($native:ty, $value:expr) => {{
+        let value: $native = $value;
+        if value.is_nan() {
+            <$native>::NAN
+        } else if value == 0.0 {
+            0.0
+        } else {
+            value
+        }
+    }};
+}
+
+macro_rules! impl_archived_float {
+    ($native:ty, $bits:ty, $($archived:ty),* $(,)?) => {
+        $(
+            impl ArchivedFloat for $archived {
+                type Native = $native;
+                type Bits = $bits;
+
+                #[inline]
+                fn to_native_float(&self) -> $native {
+                    self.to_native()
+                }
+
+                #[inline]
+                fn to_bits(&self) -> $bits {
+                    self.to_native().to_bits()
+                }
+
+                #[inline]
+                fn is_nan(&self) -> bool {
+                    self.to_native().is_nan()
+                }
+
+                #[inline]
+                fn is_finite(&self) -> bool {
+                    self.to_native().is_finite()
+                }
+
+                #[inline]
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    self.to_native().total_cmp(&other.to_native())
+                }
+
+                #[inline]
+                fn is_canonical(&self) -> bool {
+                    let value = self.to_native();
+                    value.to_bits() == canonicalize!($native, value).to_bits()
+                }
+            }
+        )*
+    };
+}
+
+impl_archived_float!(f32, u32, f32_le, f32_be, f32_ule, f32_ube);
+impl_archived_float!(f64, u64, f64_le, f64_be, f64_ule, f64_ube);
+
+/// Returns the canonical form of an `f32`.
+///
+/// NaNs are replaced with [`f32::NAN`] and negative zero is replaced with
+/// positive zero. All other values are returned unchanged.
+#[inline]
+pub fn canonicalize_f32(value: f32) -> f32 {
+    canonicalize!(f32, value)
+}
+
+/// Returns the canonical form of an `f64`.
+///
+/// NaNs are replaced with [`f64::NAN`] and negative zero is replaced with
+/// positive zero. All other values are returned unchanged.
+#[inline]
+pub fn canonicalize_f64(value: f64) -> f64 {
+    canonicalize!(f64, value)
+}
+
+/// An archived float in canonical form.
+///
+/// This is the archived form of floats serialized with
+/// [`CanonicalFloat`](crate::with::CanonicalFloat). It has the same layout as
+/// the archived float `F`, and validation checks that the float is canonical.
+/// Because equal canonical floats have the same bits, canonical floats
+/// compare and hash by their bits and can be used as keys.
+#[derive(Clone, Copy, Portable)]
+#[archive(crate)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+#[repr(transparent)]
+pub struct ArchivedCanonicalFloat<F> {
+    inner: F,
+}
+
+impl<F: ArchivedFloat> ArchivedCanonicalFloat<F> {
+    /// Returns the archived float.
+    #[inline]
+    pub fn get(&self) -> &F {
+        &self.inner
+    }
+
+    /// Returns the native float value.
+    #[inline]
+    pub fn to_native(&self) -> F::Native {
+        self.inner.to_native_float()
+    }
+}
+
+impl ArchivedCanonicalFloat<ArchivedF32> {
+    /// Creates a new archived canonical float from a native float.
+    #[inline]
+    pub fn from_native(value: f32) -> Self {
+        Self {
+            inner: ArchivedF32::from_native(canonicalize_f32(value)),
+        }
+    }
+}
+
+impl ArchivedCanonicalFloat<ArchivedF64> {
+    /// Creates a new archived canonical float from a native float.
+    #[inline]
+    pub fn from_native(value: f64) -> Self {
+        Self {
+            inner: ArchivedF64::from_native(canonicalize_f64(value)),
+        }
+    }
+}
+
+impl<F> Deref for ArchivedCanonicalFloat<F> {
+    type Target = F;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<F: ArchivedFloat> fmt::Debug for ArchivedCanonicalFloat<F>
+where
+    F::Native: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_native().fmt(f)
+    }
+}
+
+impl<F: ArchivedFloat> fmt::Display for ArchivedCanonicalFloat<F>
+where
+    F::Native: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_native().fmt(f)
+    }
+}
+
+impl<F: ArchivedFloat> Hash for ArchivedCanonicalFloat<F>
+where
+    F::Bits: Hash,
+{
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.to_bits().hash(state);
+    }
+}
+
+impl<F: ArchivedFloat> PartialEq for ArchivedCanonicalFloat<F>
+where
+    F::Bits: PartialEq,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.to_bits() == other.inner.to_bits()
+    }
+}
+
+impl<F: ArchivedFloat> Eq for ArchivedCanonicalFloat<F> where F::Bits: Eq {}
+
+impl<F: ArchivedFloat> PartialOrd for ArchivedCanonicalFloat<F>
+where
+    F::Bits: Eq,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: ArchivedFloat> Ord for ArchivedCanonicalFloat<F>
+where
+    F::Bits: Eq,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.total_cmp(&other.inner)
+    }
+}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use core::fmt;
+
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        Verify,
+    };
+    use rancor::fail;
+
+    use super::{ArchivedCanonicalFloat, ArchivedFloat};
+
+    /// An error resulting from a canonical float which is not in canonical
+    /// form.
+    #[derive(Debug)]
+    struct NonCanonicalFloat;
+
+    impl fmt::Display for NonCanonicalFloat {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "canonical float was negative zero or a non-canonical NaN"
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for NonCanonicalFloat {}
+
+    unsafe impl<C, F> Verify<C> for ArchivedCanonicalFloat<F>
+    where
+        C: Fallible + ?Sized,
+        C::Error: Error,
+        F: ArchivedFloat,
+    {
+        fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+            if !self.inner.is_canonical() {
+                fail!(NonCanonicalFloat);
+            }
+            Ok(())
+        }
+    }
+}
@@ -5,10 +5,12 @@
 mod _macros;
 #[cfg(not(feature = "unaligned"))]
 mod atomic;
+mod float;
 
 // Aligned little-endian
 #[cfg(not(feature = "unaligned"))]
 pub use self::atomic::*;
+pub use self::float::*;
 // Unaligned big-endian
 #[cfg(all(feature = "unaligned", feature = "big_endian"))]
 use crate::rend::unaligned::{
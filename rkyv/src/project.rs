@@ -0,0 +1,296 @@
+//! Projecting fields out of slices of archived structs.
+//!
+//! Structs which derive `Archive` with `#[archive(project)]` implement
+//! [`Project`] for their archived type. The archived type gets a table of
+//! [`Field`]s with the offset of each of its fields, and slices of it can be
+//! projected onto some of their fields with [`project`] or the [`project!`]
+//! macro.
+//!
+//! A projection computes the address of each field from the start of the
+//! slice, the size of the archived struct, and the offset of the field. It
+//! never creates a reference to a whole element, so only the projected fields
+//! are read.
+//!
+//! For a struct named `Record`, the derive generates `ArchivedRecordFields`,
+//! which has a public [`Field`] for each field of `ArchivedRecord`.
+//!
+//! [`project!`]: crate::project!
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     access_unchecked, project, rancor::Failure, to_bytes, Archive,
+//!     Archived, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(project)]
+//! struct Record {
+//!     id: u32,
+//!     name: String,
+//!     score: f32,
+//! }
+//!
+//! let records = vec![
+//!     Record {
+//!         id: 1,
+//!         name: "first".to_string(),
+//!         score: 0.5,
+//!     },
+//!     Record {
+//!         id: 2,
+//!         name: "second".to_string(),
+//!         score: 0.75,
+//!     },
+//! ];
+//! let bytes = to_bytes::<_, 256, Failure>(&records).unwrap();
+//! let archived =
+//!     unsafe { access_unchecked::<Archived<Vec<Record>>>(&bytes) };
+//!
+//! let projected = project!(archived => (id, score))
+//!     .map(|(id, score)| (id.to_native(), score.to_native()))
+//!     .collect::<Vec<_>>();
+//! assert_eq!(projected, [(1, 0.5), (2, 0.75)]);
+//! ```
+
+use core::{fmt, iter::FusedIterator, marker::PhantomData};
+
+/// An archived struct with a table of the offsets of its fields.
+///
+/// This is usually implemented by deriving `Archive` with
+/// `#[archive(project)]`.
+///
+/// # Safety
+///
+/// Each [`Field`] in `FIELDS` must have the offset of a field of `Self` with
+/// the type of the `Field`.
+pub unsafe trait Project: Sized {
+    /// The table of the fields of this type.
+    type Fields: Copy;
+
+    /// The fields of this type.
+    const FIELDS: Self::Fields;
+}
+
+/// A field of type `F` at a fixed offset in a `T`.
+pub struct Field<T, F> {
+    offset: usize,
+    _phantom: PhantomData<fn(&T) -> &F>,
+}
+
+impl<T, F> Clone for Field<T, F> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, F> Copy for Field<T, F> {}
+
+impl<T, F> fmt::Debug for Field<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+impl<T, F> Field<T, F> {
+    /// Returns a field at the given offset.
+    ///
+    /// # Safety
+    ///
+    /// Every `T` must have a properly aligned `F` at `offset` bytes from its
+    /// start.
+    #[inline]
+    pub const unsafe fn new(offset: usize) -> Self {
+        Self {
+            offset,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the offset of the field from the start of a `T`.
+    #[inline]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns a reference to the field of the given value.
+    #[inline]
+    pub fn get<'a>(&self, value: &'a T) -> &'a F {
+        // SAFETY: `value` is a `T`, so it has an `F` at the field offset.
+        unsafe { self.get_unchecked((value as *const T).cast()) }
+    }
+
+    /// Returns a reference to the field of the `T` at `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must point to a `T` which is valid for reads for `'a`.
+    #[inline]
+    unsafe fn get_unchecked<'a>(&self, base: *const u8) -> &'a F {
+        &*base.add(self.offset).cast::<F>()
+    }
+}
+
+/// A selection of fields which can be projected out of a `T`.
+///
+/// This is implemented for [`Field`]s and tuples of up to eight `Field`s.
+pub trait Projection<'a, T> {
+    /// The references to the projected fields.
+    type Output;
+
+    /// Projects the fields of the `T` at `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must point to a `T` which is valid for reads for `'a`.
+    unsafe fn project(&self, base: *const u8) -> Self::Output;
+}
+
+impl<'a, T, F: 'a> Projection<'a, T> for Field<T, F> {
+    type Output = &'a F;
+
+    #[inline]
+    unsafe fn project(&self, base: *const u8) -> Self::Output {
+        self.get_unchecked(base)
+    }
+}
+
+macro_rules! impl_projection {
+    ($($field:ident: $ty:ident),*) => {
+        impl<'a, T, $($ty: 'a),*> Projection<'a, T>
+            for ($(Field<T, $ty>,)*)
+        {
+            type Output = ($(&'a $ty,)*);
+
+            #[inline]
+            unsafe fn project(&self, base: *const u8) -> Self::Output {
+                let ($($field,)*) = self;
+                ($($field.get_unchecked(base),)*)
+            }
+        }
+    };
+}
+
+impl_projection!(a: A);
+impl_projection!(a: A, b: B);
+impl_projection!(a: A, b: B, c: C);
+impl_projection!(a: A, b: B, c: C, d: D);
+impl_projection!(a: A, b: B, c: C, d: D, e: E);
+impl_projection!(a: A, b: B, c: C, d: D, e: E, f: F);
+impl_projection!(a: A, b: B, c: C, d: D, e: E, f: F, g: G);
+impl_projection!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H);
+
+/// An iterator over the projected fields of each element of a slice.
+///
+/// This is created by [`project`].
+pub struct ProjectedIter<'a, T, P> {
+    ptr: *const T,
+    remaining: usize,
+    projection: P,
+    _phantom: PhantomData<&'a [T]>,
+}
+
+impl<'a, T, P: Projection<'a, T>> Iterator for ProjectedIter<'a, T, P> {
+    type Item = P::Output;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        // SAFETY: `ptr` points to one of the remaining elements of the slice,
+        // which lives for `'a`. Advancing it by one element stays in bounds
+        // or points one past the end of the slice.
+        unsafe {
+            let item = self.projection.project(self.ptr.cast());
+            self.ptr = self.ptr.add(1);
+            Some(item)
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, P: Projection<'a, T>> ExactSizeIterator
+    for ProjectedIter<'a, T, P>
+{
+}
+
+impl<'a, T, P: Projection<'a, T>> FusedIterator for ProjectedIter<'a, T, P> {}
+
+/// Projects each element of a slice onto the fields chosen by `select`.
+///
+/// `select` receives the table of fields of `T` and returns the [`Field`] or
+/// tuple of `Field`s to project. The [`project!`](crate::project!) macro
+/// selects fields by name.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access_unchecked, project::project, rancor::Failure, to_bytes,
+///     Archive, Archived, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(project)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let points = vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+/// let bytes = to_bytes::<_, 256, Failure>(&points).unwrap();
+/// let archived =
+///     unsafe { access_unchecked::<Archived<Vec<Point>>>(&bytes) };
+///
+/// let ys = project(archived, |fields| fields.y)
+///     .map(|y| y.to_native())
+///     .collect::<Vec<_>>();
+/// assert_eq!(ys, [2, 4]);
+/// ```
+#[inline]
+pub fn project<'a, T, P, S>(
+    slice: &'a [T],
+    select: S,
+) -> ProjectedIter<'a, T, P>
+where
+    T: Project,
+    P: Projection<'a, T>,
+    S: FnOnce(T::Fields) -> P,
+{
+    ProjectedIter {
+        ptr: slice.as_ptr(),
+        remaining: slice.len(),
+        projection: select(T::FIELDS),
+        _phantom: PhantomData,
+    }
+}
+
+/// Projects each element of a slice of archived structs onto some of their
+/// fields.
+///
+/// `project!(slice => field)` yields a reference to `field` of each element,
+/// and `project!(slice => (a, b, ...))` yields tuples of references to the
+/// named fields. The elements must implement
+/// [`Project`](crate::project::Project), usually by deriving `Archive` with
+/// `#[archive(project)]`.
+///
+/// See the [`project`](mod@crate::project) module for an example.
+#[macro_export]
+macro_rules! project {
+    ($slice:expr => ($($field:ident),+ $(,)?)) => {
+        $crate::project::project(&$slice, |fields| ($(fields.$field,)+))
+    };
+    ($slice:expr => $field:ident) => {
+        $crate::project::project(&$slice, |fields| fields.$field)
+    };
+}
@@ -52,7 +52,7 @@ impl<T: ArchivePointee + ?Sized, F> ArchivedRc<T, F> {
     /// dereferenced for the duration of the returned borrow.
     #[inline]
     pub unsafe fn get_pin_mut_unchecked(self: Pin<&mut Self>) -> Pin<&mut T> {
-        self.map_unchecked_mut(|s| &mut *s.ptr.as_ptr())
+        self.map_unchecked_mut(|s| &mut *s.ptr.as_mut_ptr())
     }
 
     /// Resolves an archived `Rc` from a given reference.
@@ -310,6 +310,168 @@ struct ArchivedRcWeakVariantSome<T: ArchivePointee + ?Sized, F>(
     ArchivedRc<T, F>,
 );
 
+/// An archived `Option<rc::Weak>` which distinguishes dangling weak pointers
+/// from `None`.
+///
+/// This is the archived type of [`WeakTombstone`](crate::with::WeakTombstone).
+/// Weak pointers which could not be upgraded when they were serialized are
+/// archived as `Dangling` instead of `None`. The tags of `None` and `Some` are
+/// the same as those of [`ArchivedRcWeak`], so archived weak pointers can also
+/// be read as `ArchivedOptionRcWeak`.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(u8)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+pub enum ArchivedOptionRcWeak<T: ArchivePointee + ?Sized, F> {
+    /// No weak pointer
+    None,
+    /// A weak pointer to some shared pointer
+    Some(ArchivedRc<T, F>),
+    /// A weak pointer which could not be upgraded
+    Dangling,
+}
+
+impl<T: ArchivePointee + ?Sized, F> ArchivedOptionRcWeak<T, F> {
+    /// Returns `true` if there is no weak pointer.
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        matches!(self, ArchivedOptionRcWeak::None)
+    }
+
+    /// Returns `true` if the weak pointer could not be upgraded when it was
+    /// serialized.
+    #[inline]
+    pub fn is_dangling(&self) -> bool {
+        matches!(self, ArchivedOptionRcWeak::Dangling)
+    }
+
+    /// Attempts to upgrade the weak pointer to an `ArchivedRc`.
+    ///
+    /// Returns `None` if there is no weak pointer or if it is dangling.
+    #[inline]
+    pub fn upgrade(&self) -> Option<&ArchivedRc<T, F>> {
+        match self {
+            ArchivedOptionRcWeak::Some(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    /// Attempts to upgrade a pinned mutable weak pointer.
+    #[inline]
+    pub fn upgrade_pin_mut(
+        self: Pin<&mut Self>,
+    ) -> Option<Pin<&mut ArchivedRc<T, F>>> {
+        unsafe {
+            match self.get_unchecked_mut() {
+                ArchivedOptionRcWeak::Some(r) => Some(Pin::new_unchecked(r)),
+                _ => None,
+            }
+        }
+    }
+
+    /// Resolves an archived optional `Weak` from a given optional reference.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing `value`
+    #[inline]
+    pub unsafe fn resolve_from_ref<U: ArchiveUnsized<Archived = T> + ?Sized>(
+        value: Option<&U>,
+        pos: usize,
+        resolver: OptionRcWeakResolver,
+        out: *mut Self,
+    ) {
+        match resolver {
+            OptionRcWeakResolver::None => {
+                let out = out.cast::<ArchivedOptionRcWeakVariantUnit>();
+                ptr::addr_of_mut!((*out).0)
+                    .write(ArchivedOptionRcWeakTag::None);
+            }
+            OptionRcWeakResolver::Dangling => {
+                let out = out.cast::<ArchivedOptionRcWeakVariantUnit>();
+                ptr::addr_of_mut!((*out).0)
+                    .write(ArchivedOptionRcWeakTag::Dangling);
+            }
+            OptionRcWeakResolver::Some(resolver) => {
+                let out = out.cast::<ArchivedOptionRcWeakVariantSome<T, F>>();
+                ptr::addr_of_mut!((*out).0)
+                    .write(ArchivedOptionRcWeakTag::Some);
+
+                let (fp, fo) = out_field!(out.1);
+                ArchivedRc::resolve_from_ref(
+                    value.unwrap(),
+                    pos + fp,
+                    resolver,
+                    fo,
+                );
+            }
+        }
+    }
+
+    /// Serializes an archived optional `Weak` from whether a weak pointer is
+    /// present and the value it upgrades to.
+    ///
+    /// A weak pointer which is present but does not upgrade to a value is
+    /// serialized as dangling.
+    #[inline]
+    pub fn serialize_from_ref<U, S>(
+        is_some: bool,
+        value: Option<&U>,
+        serializer: &mut S,
+    ) -> Result<OptionRcWeakResolver, S::Error>
+    where
+        U: SerializeUnsized<S, Archived = T> + ?Sized,
+        S: Fallible + Writer + Sharing + ?Sized,
+    {
+        Ok(match value {
+            Some(r) => OptionRcWeakResolver::Some(
+                ArchivedRc::<T, F>::serialize_from_ref(r, serializer)?,
+            ),
+            None if is_some => OptionRcWeakResolver::Dangling,
+            None => OptionRcWeakResolver::None,
+        })
+    }
+}
+
+impl<T: ArchivePointee + ?Sized, F> fmt::Debug for ArchivedOptionRcWeak<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchivedOptionRcWeak::None => write!(f, "None"),
+            ArchivedOptionRcWeak::Some(_) => write!(f, "Some((Weak))"),
+            ArchivedOptionRcWeak::Dangling => write!(f, "Some((Dangling))"),
+        }
+    }
+}
+
+/// The resolver for `Option<rc::Weak>` with
+/// [`WeakTombstone`](crate::with::WeakTombstone).
+pub enum OptionRcWeakResolver {
+    /// There was no weak pointer
+    None,
+    /// The weak pointer could not be upgraded
+    Dangling,
+    /// The weak pointer was to some shared pointer
+    Some(RcResolver),
+}
+
+#[allow(dead_code)]
+#[repr(u8)]
+enum ArchivedOptionRcWeakTag {
+    None,
+    Some,
+    Dangling,
+}
+
+#[repr(C)]
+struct ArchivedOptionRcWeakVariantUnit(ArchivedOptionRcWeakTag);
+
+#[repr(C)]
+struct ArchivedOptionRcWeakVariantSome<T: ArchivePointee + ?Sized, F>(
+    ArchivedOptionRcWeakTag,
+    ArchivedRc<T, F>,
+);
+
 #[cfg(feature = "bytecheck")]
 mod verify {
     use core::any::TypeId;
@@ -337,12 +499,13 @@ mod verify {
     {
         #[inline]
         fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            // Shared pointers are recorded even when their target has already
+            // been checked
+            context.record_offset(self.ptr.offset_slot())?;
             let ptr = self.ptr.as_ptr_wrapping();
             let type_id = TypeId::of::<ArchivedRc<T, F>>();
 
-            if context
-                .register_shared_ptr(ptr as *const u8 as usize, type_id)?
-            {
+            if context.register_shared_ptr(ptr.cast::<u8>().addr(), type_id)? {
                 unsafe {
                     context.bounds_check_subtree_rel_ptr(&self.ptr)?;
                 }
@@ -359,3 +522,32 @@ mod verify {
         }
     }
 }
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use crate::{
+        rc::{ArcFlavor, ArchivedRc, RcFlavor},
+        reflect::{descriptor, DescriptorKind, Reflect, TypeDescriptor},
+        ArchivePointee,
+    };
+
+    impl<T: ArchivePointee + Reflect + ?Sized> Reflect for ArchivedRc<T, RcFlavor> {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "Rc",
+            DescriptorKind::Pointer {
+                target: descriptor::<T>,
+            },
+        );
+    }
+
+    impl<T: ArchivePointee + Reflect + ?Sized> Reflect
+        for ArchivedRc<T, ArcFlavor>
+    {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "Arc",
+            DescriptorKind::Pointer {
+                target: descriptor::<T>,
+            },
+        );
+    }
+}
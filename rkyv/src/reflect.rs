@@ -0,0 +1,1025 @@
+//! Inspecting archives without their types.
+//!
+//! [`Reflect`] describes an archived type with a static [`TypeDescriptor`],
+//! which records the names, offsets, and types of its fields, the variants of
+//! its enums, and the kinds of containers it holds. [`dump`] walks an archive
+//! using only a descriptor and prints it as a tree, so a single inspection
+//! program can print archives of any type it has a descriptor for.
+//!
+//! `Reflect` can be implemented for the archived type of a type which derives
+//! `Archive` by adding `#[archive(reflect)]`. It is implemented for archived
+//! primitives, arrays, `String`, `Vec`, `Option`, `Box`, `Rc`, `Arc`,
+//! `HashMap`, `HashSet`, and `BTreeMap`.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{rancor::Failure, reflect::Reflect, to_bytes, Archive, Serialize};
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(reflect)]
+//! struct Point {
+//!     x: f32,
+//!     y: f32,
+//! }
+//!
+//! let bytes = to_bytes::<_, 256, Failure>(&Point { x: 1.5, y: 2.0 }).unwrap();
+//!
+//! let mut output = String::new();
+//! rkyv::reflect::dump::<Failure>(
+//!     &bytes,
+//!     &ArchivedPoint::DESCRIPTOR,
+//!     &mut output,
+//! )
+//! .unwrap();
+//! assert_eq!(
+//!     output,
+//!     "ArchivedPoint @ 0x0\n    x: f32 @ 0x0 = 1.5\n    y: f32 @ 0x4 = 2.0\n",
+//! );
+//! ```
+
+use core::{
+    fmt,
+    marker::{PhantomData, PhantomPinned},
+    mem::{align_of, size_of},
+    str,
+};
+
+use rancor::{fail, Error, ResultExt as _};
+
+use crate::{
+    primitive::{
+        ArchivedF32, ArchivedF64, ArchivedI128, ArchivedI16, ArchivedI32,
+        ArchivedI64, ArchivedIsize, ArchivedU128, ArchivedU16, ArchivedU32,
+        ArchivedU64, ArchivedUsize, FixedUsize,
+    },
+    string::repr::ArchivedStringRepr,
+};
+
+/// A static description of an archived type.
+#[derive(Debug)]
+pub struct TypeDescriptor {
+    /// The name of the type.
+    ///
+    /// Containers are named without their type parameters, like `Vec`.
+    pub name: &'static str,
+    /// The size of the type, in bytes.
+    ///
+    /// The size of unsized types is zero.
+    pub size: usize,
+    /// The alignment of the type, in bytes.
+    pub align: usize,
+    /// What kind of type this is.
+    pub kind: DescriptorKind,
+}
+
+impl TypeDescriptor {
+    /// Returns a descriptor with the given name and kind, and the size and
+    /// alignment of `T`.
+    pub const fn of<T>(name: &'static str, kind: DescriptorKind) -> Self {
+        Self {
+            name,
+            size: core::mem::size_of::<T>(),
+            align: core::mem::align_of::<T>(),
+            kind,
+        }
+    }
+}
+
+/// The kind of an archived type.
+///
+/// Types referred to by a kind are given by functions which return their
+/// descriptors, so that recursive types can be described.
+#[derive(Debug)]
+pub enum DescriptorKind {
+    /// A primitive value.
+    Scalar(ScalarKind),
+    /// A struct.
+    Struct {
+        /// The fields of the struct.
+        fields: &'static [FieldDescriptor],
+    },
+    /// An enum with a tag in the native endianness at offset 0.
+    Enum {
+        /// The size of the tag, in bytes.
+        tag_size: usize,
+        /// The variants of the enum, in tag order.
+        variants: &'static [VariantDescriptor],
+    },
+    /// A fixed-size array.
+    Array {
+        /// The type of the elements.
+        element: fn() -> &'static TypeDescriptor,
+        /// The number of elements.
+        len: usize,
+    },
+    /// An unsized slice, with a length stored in the pointer to it.
+    Slice {
+        /// The type of the elements.
+        element: fn() -> &'static TypeDescriptor,
+    },
+    /// An unsized string slice, with a length stored in the pointer to it.
+    Str,
+    /// An [`ArchivedString`](crate::string::ArchivedString).
+    String,
+    /// An [`ArchivedVec`](crate::vec::ArchivedVec).
+    Vec {
+        /// The type of the elements.
+        element: fn() -> &'static TypeDescriptor,
+    },
+    /// An [`ArchivedOption`](crate::option::ArchivedOption).
+    Option {
+        /// The type of the value.
+        inner: fn() -> &'static TypeDescriptor,
+    },
+    /// An owned or shared pointer, like an
+    /// [`ArchivedBox`](crate::boxed::ArchivedBox).
+    Pointer {
+        /// The type pointed to.
+        target: fn() -> &'static TypeDescriptor,
+    },
+    /// An archived hash map, like an
+    /// [`ArchivedHashMap`](crate::collections::swiss_table::ArchivedHashMap).
+    HashMap {
+        /// The type of the keys.
+        key: fn() -> &'static TypeDescriptor,
+        /// The type of the values.
+        value: fn() -> &'static TypeDescriptor,
+        /// The size of each entry, in bytes.
+        entry_size: usize,
+        /// The offset of the value from the start of each entry.
+        value_offset: usize,
+    },
+    /// An archived hash set, like an
+    /// [`ArchivedHashSet`](crate::collections::swiss_table::ArchivedHashSet).
+    HashSet {
+        /// The type of the elements.
+        element: fn() -> &'static TypeDescriptor,
+    },
+    /// An archived B-tree map, like an
+    /// [`ArchivedBTreeMap`](crate::collections::btree_map::ArchivedBTreeMap).
+    ///
+    /// The layouts of its nodes follow from the layouts of the keys and
+    /// values.
+    BTreeMap {
+        /// The type of the keys.
+        key: fn() -> &'static TypeDescriptor,
+        /// The type of the values.
+        value: fn() -> &'static TypeDescriptor,
+    },
+}
+
+/// The kind of a primitive value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarKind {
+    /// A `bool`.
+    Bool,
+    /// An `i8`.
+    I8,
+    /// An `i16`.
+    I16,
+    /// An `i32`.
+    I32,
+    /// An `i64`.
+    I64,
+    /// An `i128`.
+    I128,
+    /// A `u8`.
+    U8,
+    /// A `u16`.
+    U16,
+    /// A `u32`.
+    U32,
+    /// A `u64`.
+    U64,
+    /// A `u128`.
+    U128,
+    /// An `f32`.
+    F32,
+    /// An `f64`.
+    F64,
+    /// A `char`.
+    Char,
+}
+
+/// A field of a struct or enum variant.
+#[derive(Debug)]
+pub struct FieldDescriptor {
+    /// The name of the field.
+    ///
+    /// Fields of tuple structs are named `_0`, `_1`, and so on.
+    pub name: &'static str,
+    /// The offset of the field from the start of the struct or enum, in
+    /// bytes.
+    pub offset: usize,
+    /// Returns the descriptor of the type of the field.
+    pub ty: fn() -> &'static TypeDescriptor,
+}
+
+/// A variant of an enum.
+#[derive(Debug)]
+pub struct VariantDescriptor {
+    /// The name of the variant.
+    pub name: &'static str,
+    /// The fields of the variant.
+    pub fields: &'static [FieldDescriptor],
+}
+
+/// An archived type which can be described by a static [`TypeDescriptor`].
+///
+/// This is usually implemented for archived types with
+/// `#[archive(reflect)]`.
+pub trait Reflect {
+    /// The descriptor of the type.
+    const DESCRIPTOR: TypeDescriptor;
+}
+
+/// Returns the descriptor of `T`.
+///
+/// This can be used as a function pointer in the kinds of descriptors.
+pub fn descriptor<T: Reflect + ?Sized>() -> &'static TypeDescriptor {
+    &T::DESCRIPTOR
+}
+
+/// Options for [`dump_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DumpOptions {
+    /// The number of elements printed at each end of a collection. The
+    /// elements in between are summarized if the collection has more than
+    /// twice this many elements.
+    ///
+    /// Defaults to 4.
+    pub edge_elements: usize,
+    /// The maximum depth of the printed tree. Archives which are nested more
+    /// deeply, including archives with pointer cycles, fail to print.
+    ///
+    /// Defaults to 128.
+    pub max_depth: usize,
+}
+
+impl Default for DumpOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            edge_elements: 4,
+            max_depth: 128,
+        }
+    }
+}
+
+/// An error resulting from an archive which doesn't match its descriptor.
+#[derive(Debug)]
+pub enum DumpError {
+    /// A value extended past the end of the archive.
+    OutOfBounds {
+        /// The position of the value.
+        pos: usize,
+        /// The size of the value.
+        size: usize,
+    },
+    /// A value was not aligned for its type.
+    Misaligned {
+        /// The position of the value.
+        pos: usize,
+        /// The alignment of its type.
+        align: usize,
+    },
+    /// A relative pointer pointed outside of the archive.
+    InvalidPointer {
+        /// The position of the pointer.
+        pos: usize,
+        /// The offset of the pointer.
+        offset: isize,
+    },
+    /// A `bool` was neither 0 nor 1.
+    InvalidBool {
+        /// The position of the `bool`.
+        pos: usize,
+        /// The byte of the `bool`.
+        value: u8,
+    },
+    /// A `char` was not a valid Unicode scalar value.
+    InvalidChar {
+        /// The position of the `char`.
+        pos: usize,
+        /// The value of the `char`.
+        value: u32,
+    },
+    /// An enum tag did not match any of its variants.
+    InvalidTag {
+        /// The position of the enum.
+        pos: usize,
+        /// The value of the tag.
+        tag: u64,
+    },
+    /// A string was not valid UTF-8.
+    InvalidUtf8 {
+        /// The position of the bytes of the string.
+        pos: usize,
+    },
+    /// A B-tree node was not a leaf node with entries where one was expected.
+    InvalidNode {
+        /// The position of the node.
+        pos: usize,
+    },
+    /// The archive was nested more deeply than the maximum depth.
+    DepthExceeded {
+        /// The maximum depth.
+        max_depth: usize,
+    },
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpError::OutOfBounds { pos, size } => write!(
+                f,
+                "{} bytes at {:#x} are out of bounds of the archive",
+                size, pos,
+            ),
+            DumpError::Misaligned { pos, align } => {
+                write!(f, "{:#x} is not aligned to {} bytes", pos, align)
+            }
+            DumpError::InvalidPointer { pos, offset } => write!(
+                f,
+                "relative pointer at {:#x} with offset {} points outside of \
+                 the archive",
+                pos, offset,
+            ),
+            DumpError::InvalidBool { pos, value } => {
+                write!(f, "invalid bool {} at {:#x}", value, pos)
+            }
+            DumpError::InvalidChar { pos, value } => {
+                write!(f, "invalid char {:#x} at {:#x}", value, pos)
+            }
+            DumpError::InvalidTag { pos, tag } => {
+                write!(f, "invalid enum tag {} at {:#x}", tag, pos)
+            }
+            DumpError::InvalidUtf8 { pos } => {
+                write!(f, "invalid UTF-8 in string at {:#x}", pos)
+            }
+            DumpError::InvalidNode { pos } => {
+                write!(f, "invalid B-tree node at {:#x}", pos)
+            }
+            DumpError::DepthExceeded { max_depth } => {
+                write!(f, "archive is nested more than {} levels", max_depth)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DumpError {}
+
+/// Prints the archive in `bytes` as a tree, using `descriptor` as the
+/// descriptor of its root type.
+///
+/// Each line of the tree shows the name, type, and position of a value.
+/// Primitives and strings are followed by their values, relative pointers by
+/// the positions they point to, and collections by their lengths. The elements
+/// in the middle of large collections are summarized.
+///
+/// Every value is checked against its descriptor before it is printed, so
+/// archives which are invalid or don't match the descriptor return an error
+/// instead of printing garbage. The tree printed before the error is left in
+/// `out`, which can help to locate the problem.
+///
+/// See the [module docs](self) for an example.
+pub fn dump<E: Error>(
+    bytes: &[u8],
+    descriptor: &TypeDescriptor,
+    out: &mut (impl fmt::Write + ?Sized),
+) -> Result<(), E> {
+    dump_with(bytes, descriptor, &DumpOptions::default(), out)
+}
+
+/// Prints the archive in `bytes` as a tree with the given options.
+///
+/// See [`dump`] for details.
+pub fn dump_with<E: Error>(
+    bytes: &[u8],
+    descriptor: &TypeDescriptor,
+    options: &DumpOptions,
+    out: &mut (impl fmt::Write + ?Sized),
+) -> Result<(), E> {
+    let pos = match bytes.len().checked_sub(descriptor.size) {
+        Some(pos) => pos,
+        None => fail!(DumpError::OutOfBounds {
+            pos: 0,
+            size: descriptor.size,
+        }),
+    };
+    let mut dumper = Dumper {
+        bytes,
+        options,
+        out,
+    };
+    dumper.node(0, None, descriptor, pos, 0)
+}
+
+struct TypeName<'a>(&'a TypeDescriptor);
+
+impl fmt::Display for TypeName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.0.name;
+        match self.0.kind {
+            DescriptorKind::Array { element, len } => {
+                write!(f, "[{}; {}]", TypeName(element()), len)
+            }
+            DescriptorKind::Slice { element } => {
+                write!(f, "[{}]", TypeName(element()))
+            }
+            DescriptorKind::Vec { element: inner }
+            | DescriptorKind::Option { inner }
+            | DescriptorKind::Pointer { target: inner }
+            | DescriptorKind::HashSet { element: inner } => {
+                write!(f, "{}<{}>", name, TypeName(inner()))
+            }
+            DescriptorKind::HashMap { key, value, .. }
+            | DescriptorKind::BTreeMap { key, value } => write!(
+                f,
+                "{}<{}, {}>",
+                name,
+                TypeName(key()),
+                TypeName(value()),
+            ),
+            _ => f.write_str(name),
+        }
+    }
+}
+
+struct Dumper<'a, W: ?Sized> {
+    bytes: &'a [u8],
+    options: &'a DumpOptions,
+    out: &'a mut W,
+}
+
+impl<W: fmt::Write + ?Sized> Dumper<'_, W> {
+    fn check<E: Error>(&self, pos: usize, size: usize) -> Result<(), E> {
+        match pos.checked_add(size) {
+            Some(end) if end <= self.bytes.len() => Ok(()),
+            _ => fail!(DumpError::OutOfBounds { pos, size }),
+        }
+    }
+
+    fn read<T, E: Error>(&self, pos: usize) -> Result<T, E> {
+        self.check(pos, size_of::<T>())?;
+        // SAFETY: The bytes of the value are in bounds, and `T` is only ever a
+        // type which is valid for any bit pattern.
+        Ok(
+            unsafe {
+                self.bytes.as_ptr().add(pos).cast::<T>().read_unaligned()
+            },
+        )
+    }
+
+    fn read_tag<E: Error>(&self, pos: usize, size: usize) -> Result<u64, E> {
+        Ok(match size {
+            1 => self.read::<u8, E>(pos)? as u64,
+            2 => self.read::<u16, E>(pos)? as u64,
+            4 => self.read::<u32, E>(pos)? as u64,
+            _ => self.read::<u64, E>(pos)?,
+        })
+    }
+
+    fn follow<E: Error>(&self, pos: usize) -> Result<usize, E> {
+        let offset = self.read::<ArchivedIsize, E>(pos)?.to_native() as isize;
+        match pos.checked_add_signed(offset) {
+            Some(target) if target <= self.bytes.len() => Ok(target),
+            _ => fail!(DumpError::InvalidPointer { pos, offset }),
+        }
+    }
+
+    fn line<E: Error>(
+        &mut self,
+        depth: usize,
+        label: Option<&dyn fmt::Display>,
+    ) -> Result<(), E> {
+        write!(self.out, "{:1$}", "", depth * 4).into_error()?;
+        if let Some(label) = label {
+            write!(self.out, "{}: ", label).into_error()?;
+        }
+        Ok(())
+    }
+
+    fn node<E: Error>(
+        &mut self,
+        depth: usize,
+        label: Option<&dyn fmt::Display>,
+        descriptor: &TypeDescriptor,
+        pos: usize,
+        len: usize,
+    ) -> Result<(), E> {
+        if depth > self.options.max_depth {
+            fail!(DumpError::DepthExceeded {
+                max_depth: self.options.max_depth,
+            });
+        }
+        self.check(pos, descriptor.size)?;
+        if pos & (descriptor.align - 1) != 0 {
+            fail!(DumpError::Misaligned {
+                pos,
+                align: descriptor.align,
+            });
+        }
+
+        self.line(depth, label)?;
+        write!(self.out, "{} @ {:#x}", TypeName(descriptor), pos)
+            .into_error()?;
+
+        match descriptor.kind {
+            DescriptorKind::Scalar(kind) => {
+                self.out.write_str(" = ").into_error()?;
+                self.scalar(kind, pos)?;
+                self.out.write_char('\n').into_error()?;
+            }
+            DescriptorKind::Struct { fields } => {
+                self.out.write_char('\n').into_error()?;
+                self.fields(depth + 1, fields, pos)?;
+            }
+            DescriptorKind::Enum { tag_size, variants } => {
+                let tag = self.read_tag(pos, tag_size)?;
+                let variant = match variants.get(tag as usize) {
+                    Some(variant) => variant,
+                    None => fail!(DumpError::InvalidTag { pos, tag }),
+                };
+                writeln!(self.out, " = {}", variant.name).into_error()?;
+                self.fields(depth + 1, variant.fields, pos)?;
+            }
+            DescriptorKind::Array { element, len } => {
+                self.out.write_char('\n').into_error()?;
+                self.elements(depth + 1, element(), pos, len)?;
+            }
+            DescriptorKind::Slice { element } => {
+                writeln!(self.out, " (len {})", len).into_error()?;
+                self.elements(depth + 1, element(), pos, len)?;
+            }
+            DescriptorKind::Str => {
+                self.string(pos, len)?;
+            }
+            DescriptorKind::String => {
+                let repr = self.read::<ArchivedStringRepr, E>(pos)?;
+                if repr.is_inline() {
+                    self.string(pos, repr.len())?;
+                } else {
+                    // SAFETY: The representation is out-of-line.
+                    let offset = unsafe { repr.out_of_line_offset() };
+                    let target = match pos.checked_add_signed(offset) {
+                        Some(target) if target <= self.bytes.len() => target,
+                        _ => fail!(DumpError::InvalidPointer { pos, offset }),
+                    };
+                    write!(self.out, " -> {:#x}", target).into_error()?;
+                    self.string(target, repr.len())?;
+                }
+            }
+            DescriptorKind::Vec { element } => {
+                let element = element();
+                let target = self.follow(pos)?;
+                let len = self
+                    .read::<ArchivedUsize, E>(pos + size_of::<ArchivedIsize>())?
+                    .to_native() as usize;
+                writeln!(self.out, " -> {:#x} (len {})", target, len)
+                    .into_error()?;
+                self.elements(depth + 1, element, target, len)?;
+            }
+            DescriptorKind::Option { inner } => {
+                let inner = inner();
+                match self.read::<u8, E>(pos)? {
+                    0 => self.out.write_str(" = None\n").into_error()?,
+                    1 => {
+                        self.out.write_char('\n').into_error()?;
+                        self.node(
+                            depth + 1,
+                            Some(&"Some"),
+                            inner,
+                            pos + descriptor.size - inner.size,
+                            0,
+                        )?;
+                    }
+                    tag => fail!(DumpError::InvalidTag {
+                        pos,
+                        tag: tag as u64,
+                    }),
+                }
+            }
+            DescriptorKind::Pointer { target } => {
+                let target = target();
+                let target_pos = self.follow(pos)?;
+                let len = match target.kind {
+                    DescriptorKind::Slice { .. } | DescriptorKind::Str => {
+                        self.read::<ArchivedUsize, E>(
+                            pos + size_of::<ArchivedIsize>(),
+                        )?
+                        .to_native() as usize
+                    }
+                    _ => 0,
+                };
+                writeln!(self.out, " -> {:#x}", target_pos).into_error()?;
+                self.node(depth + 1, Some(&"*"), target, target_pos, len)?;
+            }
+            DescriptorKind::HashMap {
+                key,
+                value,
+                entry_size,
+                value_offset,
+            } => {
+                let entries = (key(), value(), value_offset);
+                self.hash_table(depth, pos, entry_size, |this, index, pos| {
+                    this.line(depth + 1, None)?;
+                    writeln!(this.out, "[{}] @ {:#x}", index, pos)
+                        .into_error()?;
+                    let (key, value, value_offset) = entries;
+                    this.node(depth + 2, Some(&"key"), key, pos, 0)?;
+                    this.node(
+                        depth + 2,
+                        Some(&"value"),
+                        value,
+                        pos + value_offset,
+                        0,
+                    )
+                })?;
+            }
+            DescriptorKind::HashSet { element } => {
+                let element = element();
+                self.hash_table(
+                    depth,
+                    pos,
+                    element.size,
+                    |this, index, pos| {
+                        this.node(
+                            depth + 1,
+                            Some(&Index(index)),
+                            element,
+                            pos,
+                            0,
+                        )
+                    },
+                )?;
+            }
+            DescriptorKind::BTreeMap { key, value } => {
+                self.btree(depth, pos, key(), value())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn scalar<E: Error>(
+        &mut self,
+        kind: ScalarKind,
+        pos: usize,
+    ) -> Result<(), E> {
+        macro_rules! write_value {
+            ($ty:ty) => {
+                write!(self.out, "{:?}", self.read::<$ty, E>(pos)?.to_native())
+            };
+        }
+
+        match kind {
+            ScalarKind::Bool => match self.read::<u8, E>(pos)? {
+                0 => self.out.write_str("false"),
+                1 => self.out.write_str("true"),
+                value => fail!(DumpError::InvalidBool { pos, value }),
+            },
+            ScalarKind::I8 => write!(self.out, "{}", self.read::<i8, E>(pos)?),
+            ScalarKind::I16 => write_value!(ArchivedI16),
+            ScalarKind::I32 => write_value!(ArchivedI32),
+            ScalarKind::I64 => write_value!(ArchivedI64),
+            ScalarKind::I128 => write_value!(ArchivedI128),
+            ScalarKind::U8 => write!(self.out, "{}", self.read::<u8, E>(pos)?),
+            ScalarKind::U16 => write_value!(ArchivedU16),
+            ScalarKind::U32 => write_value!(ArchivedU32),
+            ScalarKind::U64 => write_value!(ArchivedU64),
+            ScalarKind::U128 => write_value!(ArchivedU128),
+            ScalarKind::F32 => write_value!(ArchivedF32),
+            ScalarKind::F64 => write_value!(ArchivedF64),
+            ScalarKind::Char => {
+                let value = self.read::<ArchivedU32, E>(pos)?.to_native();
+                match char::from_u32(value) {
+                    Some(c) => write!(self.out, "{:?}", c),
+                    None => fail!(DumpError::InvalidChar { pos, value }),
+                }
+            }
+        }
+        .into_error()
+    }
+
+    fn string<E: Error>(&mut self, pos: usize, len: usize) -> Result<(), E> {
+        self.check(pos, len)?;
+        let bytes = &self.bytes[pos..pos + len];
+        let s = match str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(_) => fail!(DumpError::InvalidUtf8 { pos }),
+        };
+        writeln!(self.out, " = {:?} (len {})", s, len).into_error()
+    }
+
+    fn fields<E: Error>(
+        &mut self,
+        depth: usize,
+        fields: &[FieldDescriptor],
+        pos: usize,
+    ) -> Result<(), E> {
+        for field in fields {
+            let label: &dyn fmt::Display = &field.name;
+            self.node(depth, Some(label), (field.ty)(), pos + field.offset, 0)?;
+        }
+        Ok(())
+    }
+
+    // Returns whether the element at `index` of a collection of `len`
+    // elements should be printed, and prints a summary of the skipped elements
+    // before the first one which isn't.
+    fn visible<E: Error>(
+        &mut self,
+        depth: usize,
+        index: usize,
+        len: usize,
+    ) -> Result<bool, E> {
+        let edge = self.options.edge_elements;
+        if len <= edge.saturating_mul(2) || index < edge || index >= len - edge
+        {
+            return Ok(true);
+        }
+        if index == edge {
+            self.line(depth, None)?;
+            writeln!(self.out, "... {} more", len - 2 * edge).into_error()?;
+        }
+        Ok(false)
+    }
+
+    fn elements<E: Error>(
+        &mut self,
+        depth: usize,
+        element: &TypeDescriptor,
+        pos: usize,
+        len: usize,
+    ) -> Result<(), E> {
+        match element.size.checked_mul(len) {
+            Some(size) => self.check(pos, size)?,
+            None => fail!(DumpError::OutOfBounds {
+                pos,
+                size: usize::MAX,
+            }),
+        }
+        let mut index = 0;
+        while index < len {
+            if self.visible(depth, index, len)? {
+                let pos = pos + index * element.size;
+                self.node(depth, Some(&Index(index)), element, pos, 0)?;
+                index += 1;
+            } else {
+                index = len - self.options.edge_elements;
+            }
+        }
+        Ok(())
+    }
+
+    fn hash_table<E: Error>(
+        &mut self,
+        depth: usize,
+        pos: usize,
+        bucket_size: usize,
+        mut entry: impl FnMut(&mut Self, usize, usize) -> Result<(), E>,
+    ) -> Result<(), E> {
+        const ORDERED_FLAG: FixedUsize = 1 << (FixedUsize::BITS - 1);
+
+        let control = self.follow(pos)?;
+        let len_pos = pos + size_of::<ArchivedIsize>();
+        let len = self.read::<ArchivedUsize, E>(len_pos)?.to_native() as usize;
+        let cap = self
+            .read::<ArchivedUsize, E>(len_pos + size_of::<ArchivedUsize>())?
+            .to_native();
+        let cap = (cap & !ORDERED_FLAG) as usize;
+        writeln!(self.out, " -> {:#x} (len {})", control, len).into_error()?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        self.check(control, cap)?;
+        let buckets = match bucket_size
+            .checked_mul(cap)
+            .and_then(|size| control.checked_sub(size))
+        {
+            Some(buckets) => buckets,
+            None => fail!(DumpError::OutOfBounds {
+                pos: control,
+                size: usize::MAX,
+            }),
+        };
+
+        let mut index = 0;
+        for bucket in 0..cap {
+            if self.bytes[control + bucket] & 0x80 != 0 {
+                continue;
+            }
+            if self.visible(depth + 1, index, len)? {
+                entry(self, index, buckets + (cap - bucket - 1) * bucket_size)?;
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn btree<E: Error>(
+        &mut self,
+        depth: usize,
+        pos: usize,
+        key: &TypeDescriptor,
+        value: &TypeDescriptor,
+    ) -> Result<(), E> {
+        const INNER_FLAG: u16 = 0x80_00;
+
+        let len = self.read::<ArchivedUsize, E>(pos)?.to_native() as usize;
+        let root = self.follow(pos + size_of::<ArchivedUsize>())?;
+        writeln!(self.out, " -> {:#x} (len {})", root, len).into_error()?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        let layout = LeafLayout::new(key, value);
+        let mut node = root;
+        let mut levels = 0;
+        while self.read::<ArchivedU16, E>(node)?.to_native() & INNER_FLAG != 0 {
+            levels += 1;
+            if levels > self.options.max_depth {
+                fail!(DumpError::DepthExceeded {
+                    max_depth: self.options.max_depth,
+                });
+            }
+            node = self.follow(node + layout.ptr_offset)?;
+        }
+
+        // Every leaf takes up at least the size of its header, so visiting
+        // more leaves than could fit in the archive means that they form a
+        // cycle.
+        let max_leaves = self.bytes.len() / layout.entries_offset.max(1);
+        let mut leaves = 0;
+        let mut index = 0;
+        while index < len {
+            let meta = self.read::<ArchivedU16, E>(node)?.to_native();
+            let count = (meta & !INNER_FLAG) as usize;
+            leaves += 1;
+            if meta & INNER_FLAG != 0 || count == 0 || leaves > max_leaves {
+                fail!(DumpError::InvalidNode { pos: node });
+            }
+            for i in 0..count.min(len - index) {
+                if self.visible(depth + 1, index, len)? {
+                    let pos =
+                        node + layout.entries_offset + i * layout.entry_size;
+                    self.line(depth + 1, None)?;
+                    writeln!(self.out, "[{}] @ {:#x}", index, pos)
+                        .into_error()?;
+                    self.node(depth + 2, Some(&"key"), key, pos, 0)?;
+                    self.node(
+                        depth + 2,
+                        Some(&"value"),
+                        value,
+                        pos + layout.value_offset,
+                        0,
+                    )?;
+                }
+                index += 1;
+            }
+            node = self.follow(node + layout.ptr_offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// The layout of the leaf nodes of an archived B-tree map.
+///
+/// Nodes start with a header of a `u16` with the inner node flag in its high
+/// bit and the number of entries in its low bits, the size of the node, and a
+/// relative pointer. Inner nodes point to their first child and leaf nodes
+/// point to the next leaf. The entries of leaf nodes follow the header, and
+/// are laid out like a `#[repr(C)]` struct of a key and a value.
+struct LeafLayout {
+    ptr_offset: usize,
+    entries_offset: usize,
+    entry_size: usize,
+    value_offset: usize,
+}
+
+impl LeafLayout {
+    fn new(key: &TypeDescriptor, value: &TypeDescriptor) -> Self {
+        let usize_align = align_of::<ArchivedUsize>();
+        let isize_align = align_of::<ArchivedIsize>();
+
+        let size_offset = size_of::<u16>().next_multiple_of(usize_align);
+        let ptr_offset = (size_offset + size_of::<ArchivedUsize>())
+            .next_multiple_of(isize_align);
+        let header_align = usize_align.max(isize_align).max(2);
+        let header_size = (ptr_offset + size_of::<ArchivedIsize>())
+            .next_multiple_of(header_align);
+
+        let value_offset = key.size.next_multiple_of(value.align);
+        let entry_align = key.align.max(value.align);
+        Self {
+            ptr_offset,
+            entries_offset: header_size.next_multiple_of(entry_align),
+            entry_size: (value_offset + value.size)
+                .next_multiple_of(entry_align),
+            value_offset,
+        }
+    }
+}
+
+struct Index(usize);
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.0)
+    }
+}
+
+// Primitives
+
+macro_rules! impl_scalar {
+    ($($ty:ty: $name:literal $kind:ident),* $(,)?) => {
+        $(
+            impl Reflect for $ty {
+                const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+                    $name,
+                    DescriptorKind::Scalar(ScalarKind::$kind),
+                );
+            }
+        )*
+    };
+}
+
+impl_scalar! {
+    bool: "bool" Bool,
+    i8: "i8" I8,
+    ArchivedI16: "i16" I16,
+    ArchivedI32: "i32" I32,
+    ArchivedI64: "i64" I64,
+    ArchivedI128: "i128" I128,
+    u8: "u8" U8,
+    ArchivedU16: "u16" U16,
+    ArchivedU32: "u32" U32,
+    ArchivedU64: "u64" U64,
+    ArchivedU128: "u128" U128,
+    ArchivedF32: "f32" F32,
+    ArchivedF64: "f64" F64,
+    crate::primitive::ArchivedChar: "char" Char,
+}
+
+// Zero-sized types
+
+macro_rules! impl_zero_sized {
+    ($($ty:ty: $name:literal),* $(,)?) => {
+        $(
+            impl Reflect for $ty {
+                const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+                    $name,
+                    DescriptorKind::Struct { fields: &[] },
+                );
+            }
+        )*
+    };
+}
+
+impl_zero_sized! {
+    (): "()",
+    PhantomPinned: "PhantomPinned",
+}
+
+impl<T: ?Sized> Reflect for PhantomData<T> {
+    const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+        "PhantomData",
+        DescriptorKind::Struct { fields: &[] },
+    );
+}
+
+// Arrays and slices
+
+impl<T: Reflect, const N: usize> Reflect for [T; N] {
+    const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+        "array",
+        DescriptorKind::Array {
+            element: descriptor::<T>,
+            len: N,
+        },
+    );
+}
+
+impl<T: Reflect> Reflect for [T] {
+    const DESCRIPTOR: TypeDescriptor = TypeDescriptor {
+        name: "slice",
+        size: 0,
+        align: core::mem::align_of::<T>(),
+        kind: DescriptorKind::Slice {
+            element: descriptor::<T>,
+        },
+    };
+}
+
+impl Reflect for str {
+    const DESCRIPTOR: TypeDescriptor = TypeDescriptor {
+        name: "str",
+        size: 0,
+        align: 1,
+        kind: DescriptorKind::Str,
+    };
+}
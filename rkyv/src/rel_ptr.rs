@@ -4,11 +4,14 @@ use core::{
     convert::TryFrom,
     fmt,
     marker::{PhantomData, PhantomPinned},
+    mem::size_of,
     ptr::{self, addr_of_mut},
 };
 
-use rancor::{fail, Error, Panic, ResultExt as _};
+use rancor::{fail, Error, Failure, Panic, ResultExt as _};
 
+#[cfg(feature = "bytecheck")]
+use crate::validation::LayoutRaw;
 use crate::{
     primitive::{
         ArchivedI16, ArchivedI32, ArchivedI64, ArchivedU16, ArchivedU32,
@@ -62,7 +65,10 @@ macro_rules! impl_offset_single_byte {
                 // computed offsets cannot overflow an isize, which is why we're
                 // using signed_offset instead of `checked_sub` for unsized
                 // types.
-                Self::try_from(value).into_error()
+                match Self::try_from(value) {
+                    Ok(offset) => Ok(offset),
+                    Err(_) => fail!(ExceedsStorageRange),
+                }
             }
 
             #[inline]
@@ -87,9 +93,10 @@ macro_rules! impl_offset_multi_byte {
                 // computed offsets cannot overflow an isize, which is why we're
                 // using signed_offset instead of `checked_sub` for unsized
                 // types.
-                Ok(<$archived>::from_native(
-                    <$ty>::try_from(value).into_error()?,
-                ))
+                match <$ty>::try_from(value) {
+                    Ok(offset) => Ok(<$archived>::from_native(offset)),
+                    Err(_) => fail!(ExceedsStorageRange),
+                }
             }
 
             #[inline]
@@ -114,8 +121,8 @@ impl_offset_multi_byte!(u32, ArchivedU32);
 #[cfg(target_pointer_width = "64")]
 impl_offset_multi_byte!(u64, ArchivedU64);
 
-/// Errors that can occur while creating raw relative pointers.
-#[derive(Debug)]
+/// Errors that can occur while creating or resolving relative pointers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RelPtrError {
     /// The given `from` and `to` positions for the relative pointer failed to
     /// form a valid offset.
@@ -123,6 +130,161 @@ pub enum RelPtrError {
     /// This is probably because the distance between them could not be
     /// represented by the offset type.
     OffsetError,
+    /// The relative pointer is not located inside of the buffer it was
+    /// resolved within.
+    NotInBuffer,
+    /// The metadata of the relative pointer does not describe a valid layout.
+    InvalidMetadata,
+    /// The target of the relative pointer is not located completely inside of
+    /// the buffer it was resolved within.
+    OutOfBounds {
+        /// The position of the target relative to the start of the buffer
+        target: isize,
+        /// The size of the target
+        size: usize,
+        /// The length of the buffer
+        len: usize,
+    },
+    /// The target of the relative pointer is not properly aligned.
+    Unaligned {
+        /// The position of the target relative to the start of the buffer
+        target: usize,
+        /// The required alignment of the target
+        align: usize,
+    },
+}
+
+impl fmt::Display for RelPtrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelPtrError::OffsetError => write!(
+                f,
+                "the offset between the positions could not be represented",
+            ),
+            RelPtrError::NotInBuffer => {
+                write!(f, "the relative pointer is not inside the buffer")
+            }
+            RelPtrError::InvalidMetadata => {
+                write!(f, "the relative pointer has invalid metadata")
+            }
+            RelPtrError::OutOfBounds { target, size, len } => write!(
+                f,
+                "the target at {} with size {} is outside of the buffer with \
+                 length {}",
+                target, size, len,
+            ),
+            RelPtrError::Unaligned { target, align } => write!(
+                f,
+                "the target at {} is not aligned to {}",
+                target, align,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RelPtrError {}
+
+/// The location, value, and encoding of a relative pointer offset.
+///
+/// Offset slots are recorded with
+/// [`ArchiveContext::record_offset`](crate::validation::ArchiveContext::record_offset)
+/// while an archive is validated. They describe how to rewrite the offset when
+/// its base or target is moved.
+#[derive(Clone, Copy, Debug)]
+pub struct OffsetSlot {
+    base: *const u8,
+    ptr: *const u8,
+    size: usize,
+    offset: isize,
+    write: fn(isize, &mut [u8]) -> bool,
+}
+
+impl OffsetSlot {
+    /// Creates a new offset slot.
+    ///
+    /// `base` is the address the offset is relative to, and `ptr` is the
+    /// address of the `size` bytes which store `offset`. `write` encodes an
+    /// offset into a slice of `size` bytes and returns whether the offset could
+    /// be represented.
+    #[inline]
+    pub fn new(
+        base: *const u8,
+        ptr: *const u8,
+        size: usize,
+        offset: isize,
+        write: fn(isize, &mut [u8]) -> bool,
+    ) -> Self {
+        Self {
+            base,
+            ptr,
+            size,
+            offset,
+            write,
+        }
+    }
+
+    /// Returns the address the offset is relative to.
+    #[inline]
+    pub fn base(&self) -> *const u8 {
+        self.base
+    }
+
+    /// Returns the address of the stored offset.
+    #[inline]
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    /// Returns the size of the stored offset in bytes.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the stored offset.
+    #[inline]
+    pub fn offset(&self) -> isize {
+        self.offset
+    }
+
+    /// Returns the address the offset points to.
+    ///
+    /// The returned pointer has not been bounds checked.
+    #[inline]
+    pub fn target(&self) -> *const u8 {
+        self.base.wrapping_offset(self.offset)
+    }
+
+    /// Returns the function which encodes offsets for the slot.
+    #[inline]
+    pub fn writer(&self) -> fn(isize, &mut [u8]) -> bool {
+        self.write
+    }
+
+    /// Encodes `offset` into `out`, which must be [`size`](Self::size) bytes
+    /// long.
+    ///
+    /// Returns `false` if the offset can't be represented by the slot.
+    #[inline]
+    pub fn write(&self, offset: isize, out: &mut [u8]) -> bool {
+        out.len() == self.size && (self.write)(offset, out)
+    }
+}
+
+fn write_offset<O: Offset>(offset: isize, out: &mut [u8]) -> bool {
+    if out.len() != size_of::<O>() {
+        return false;
+    }
+    match O::from_isize::<Failure>(offset) {
+        Ok(offset) => {
+            // SAFETY: `out` is valid for writes of `size_of::<O>()` bytes, and
+            // offsets are plain old data.
+            unsafe { out.as_mut_ptr().cast::<O>().write_unaligned(offset) };
+            true
+        }
+        Err(_) => false,
+    }
 }
 
 /// An untyped pointer which resolves relative to its position in memory.
@@ -222,6 +384,15 @@ impl<O: Offset> RawRelPtr<O> {
         (self as *const Self).cast_mut().cast::<u8>()
     }
 
+    /// Gets the base pointer for the relative pointer.
+    ///
+    /// Unlike [`base`](Self::base), the returned pointer is derived from a
+    /// mutable borrow and may be used to write to the target.
+    #[inline]
+    pub fn base_mut(&mut self) -> *mut u8 {
+        (self as *mut Self).cast::<u8>()
+    }
+
     /// Gets the offset of the relative pointer from its base.
     #[inline]
     pub fn offset(&self) -> isize {
@@ -234,6 +405,19 @@ impl<O: Offset> RawRelPtr<O> {
         self.offset() == 0
     }
 
+    /// Returns the slot which stores the offset of the relative pointer.
+    #[inline]
+    pub fn offset_slot(&self) -> OffsetSlot {
+        let base = self.base().cast_const();
+        OffsetSlot::new(
+            base,
+            base,
+            size_of::<O>(),
+            self.offset(),
+            write_offset::<O>,
+        )
+    }
+
     /// Calculates the memory address being pointed to by this relative pointer.
     ///
     /// # Safety
@@ -253,6 +437,22 @@ impl<O: Offset> RawRelPtr<O> {
     pub fn as_ptr_wrapping(&self) -> *mut () {
         self.base().wrapping_offset(self.offset()).cast()
     }
+
+    /// Calculates the memory address being pointed to by this relative pointer
+    /// from a mutable borrow.
+    ///
+    /// Pointers returned by [`as_ptr`](Self::as_ptr) are derived from a shared
+    /// borrow, so writing through them is undefined behavior.
+    ///
+    /// # Safety
+    ///
+    /// The offset of this relative pointer, when added to its base, must be
+    /// located in the same allocated object as it.
+    #[inline]
+    pub unsafe fn as_mut_ptr(&mut self) -> *mut () {
+        let offset = self.offset();
+        unsafe { self.base_mut().offset(offset).cast() }
+    }
 }
 
 impl<O: fmt::Debug> fmt::Debug for RawRelPtr<O> {
@@ -430,6 +630,12 @@ impl<T: ArchivePointee + ?Sized, O: Offset> RelPtr<T, O> {
         self.raw_ptr.base()
     }
 
+    /// Gets the base pointer for the relative pointer from a mutable borrow.
+    #[inline]
+    pub fn base_mut(&mut self) -> *mut u8 {
+        self.raw_ptr.base_mut()
+    }
+
     /// Gets the offset of the relative pointer from its base.
     #[inline]
     pub fn offset(&self) -> isize {
@@ -442,6 +648,12 @@ impl<T: ArchivePointee + ?Sized, O: Offset> RelPtr<T, O> {
         self.raw_ptr.is_null()
     }
 
+    /// Returns the slot which stores the offset of the relative pointer.
+    #[inline]
+    pub fn offset_slot(&self) -> OffsetSlot {
+        self.raw_ptr.offset_slot()
+    }
+
     /// Gets the metadata of the relative pointer.
     #[inline]
     pub fn metadata(&self) -> &T::ArchivedMetadata {
@@ -473,6 +685,118 @@ impl<T: ArchivePointee + ?Sized, O: Offset> RelPtr<T, O> {
             T::pointer_metadata(&self.metadata),
         )
     }
+
+    /// Calculates the memory address being pointed to by this relative pointer
+    /// from a mutable borrow.
+    ///
+    /// # Safety
+    ///
+    /// The offset of this relative pointer, when added to its base, must be
+    /// located in the same allocated object as it.
+    #[inline]
+    pub unsafe fn as_mut_ptr(&mut self) -> *mut T {
+        let metadata = T::pointer_metadata(&self.metadata);
+        ptr_meta::from_raw_parts_mut(
+            unsafe { self.raw_ptr.as_mut_ptr() },
+            metadata,
+        )
+    }
+
+    /// Calculates the memory address being pointed to by this relative
+    /// pointer, checking that both this relative pointer and its target are
+    /// located inside of `buffer` and that the target is properly aligned.
+    ///
+    /// This is useful for debugging archives which were sliced or
+    /// concatenated incorrectly. The target is not checked to be valid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rkyv::{
+    ///     primitive::ArchivedI32,
+    ///     rel_ptr::{RelPtr, RelPtrError},
+    ///     util::AlignedBytes,
+    /// };
+    ///
+    /// let mut bytes = AlignedBytes([0u8; 16]);
+    /// let out = bytes[8..].as_mut_ptr().cast::<RelPtr<u32, ArchivedI32>>();
+    ///
+    /// // A relative pointer at position 8 which points to position 4
+    /// unsafe { RelPtr::emplace(8, 4, out) };
+    /// let rel_ptr = unsafe { &*out };
+    /// assert_eq!(
+    ///     rel_ptr.try_resolve_within(&*bytes),
+    ///     Ok(bytes[4..].as_ptr().cast::<u32>()),
+    /// );
+    /// assert_eq!(
+    ///     rel_ptr.try_resolve_within(&bytes[..8]),
+    ///     Err(RelPtrError::NotInBuffer),
+    /// );
+    /// assert_eq!(
+    ///     rel_ptr.try_resolve_within(&bytes[6..]),
+    ///     Err(RelPtrError::OutOfBounds {
+    ///         target: -2,
+    ///         size: 4,
+    ///         len: 10,
+    ///     }),
+    /// );
+    ///
+    /// // A relative pointer at position 8 which points to position 6
+    /// unsafe { RelPtr::emplace(8, 6, out) };
+    /// let rel_ptr = unsafe { &*out };
+    /// assert_eq!(
+    ///     rel_ptr.try_resolve_within(&*bytes),
+    ///     Err(RelPtrError::Unaligned { target: 6, align: 4 }),
+    /// );
+    /// ```
+    #[cfg(feature = "bytecheck")]
+    pub fn try_resolve_within(
+        &self,
+        buffer: &[u8],
+    ) -> Result<*const T, RelPtrError>
+    where
+        T: LayoutRaw,
+    {
+        let start = buffer.as_ptr().addr();
+        let base = self.base().addr();
+        if base < start
+            || base - start > buffer.len()
+            || buffer.len() - (base - start) < core::mem::size_of::<Self>()
+        {
+            return Err(RelPtrError::NotInBuffer);
+        }
+
+        let metadata = T::pointer_metadata(&self.metadata);
+        let layout = T::layout_raw(metadata)
+            .map_err(|_| RelPtrError::InvalidMetadata)?;
+        let out_of_bounds = |target| RelPtrError::OutOfBounds {
+            target,
+            size: layout.size(),
+            len: buffer.len(),
+        };
+
+        let target = ((base - start) as isize)
+            .checked_add(self.offset())
+            .ok_or(out_of_bounds(isize::MAX))?;
+        if target < 0
+            || target as usize > buffer.len()
+            || buffer.len() - (target as usize) < layout.size()
+        {
+            return Err(out_of_bounds(target));
+        }
+        let target = target as usize;
+        if (start + target) & (layout.align() - 1) != 0 {
+            return Err(RelPtrError::Unaligned {
+                target,
+                align: layout.align(),
+            });
+        }
+
+        Ok(ptr_meta::from_raw_parts(
+            buffer.as_ptr().wrapping_add(target).cast(),
+            metadata,
+        ))
+    }
 }
 
 impl<T: ArchivePointee + ?Sized, O: fmt::Debug> fmt::Debug for RelPtr<T, O>
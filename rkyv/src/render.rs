@@ -0,0 +1,822 @@
+//! Rendering archived values as JSON text without allocating.
+//!
+//! [`RenderJson`] renders an archived value as JSON into any
+//! [`fmt::Write`](core::fmt::Write), so archived values can be logged into
+//! fixed-size buffers in `no_std` environments. Unlike `Debug`, the output is
+//! stable and machine-readable:
+//!
+//! - Integers and floats are rendered as numbers. Floats use the shortest
+//!   representation which round-trips. NaN and infinities are rendered
+//!   according to [`NonFiniteFloats`].
+//! - `bool`s are rendered as `true` or `false`, and `()` and `None` as `null`.
+//! - Strings and `char`s are rendered as escaped JSON strings.
+//! - `Some`, boxes, and newtype structs are rendered as their contents.
+//! - Vecs, slices, arrays, sets, tuples, and tuple structs are rendered as
+//!   arrays.
+//! - Maps and structs with named fields are rendered as objects. Map keys
+//!   which are not strings are rendered as JSON and then stringified, so the
+//!   key `1` is rendered as `"1"`.
+//! - Enums are rendered like `serde_json` renders them: unit variants as their
+//!   name, and other variants as an object with a single entry from their
+//!   name to their fields.
+//!
+//! [`JsonOptions`] can limit the depth of the rendered value and the number of
+//! entries rendered for each collection to keep the output bounded.
+//!
+//! `RenderJson` can be implemented for the archived type of a type which
+//! derives `Archive` by adding `#[archive(render_json)]`.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     access_unchecked, rancor::Failure, render::render_json, to_bytes,
+//!     Archive, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(render_json)]
+//! struct Event {
+//!     id: u32,
+//!     message: String,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let value = Event {
+//!     id: 7,
+//!     message: "disk \"sda\" full".to_string(),
+//!     tags: vec!["storage".to_string()],
+//! };
+//! let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+//! let archived = unsafe { access_unchecked::<ArchivedEvent>(&bytes) };
+//!
+//! let mut out = String::new();
+//! render_json(archived, &mut out).unwrap();
+//! assert_eq!(
+//!     out,
+//!     r#"{"id":7,"message":"disk \"sda\" full","tags":["storage"]}"#,
+//! );
+//! ```
+
+use core::{
+    fmt::{self, Write as _},
+    marker::{PhantomData, PhantomPinned},
+};
+
+use crate::{
+    boxed::ArchivedBox,
+    collections::{
+        btree_map::ArchivedBTreeMap,
+        btree_set::ArchivedBTreeSet,
+        swiss_table::{
+            ArchivedHashMap, ArchivedHashSet, ArchivedIndexMap,
+            ArchivedIndexSet,
+        },
+    },
+    option::ArchivedOption,
+    string::ArchivedString,
+    tuple::*,
+    vec::ArchivedVec,
+    ArchivePointee,
+};
+
+/// How NaN and infinite floats are rendered.
+///
+/// JSON has no representation for these values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// Render them as `null`.
+    #[default]
+    Null,
+    /// Render them as the strings `"NaN"`, `"Infinity"`, and `"-Infinity"`.
+    String,
+}
+
+/// Options for rendering archived values as JSON.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::render::JsonOptions;
+///
+/// let values = [[1, 2], [3, 4], [5, 6]];
+///
+/// let mut out = String::new();
+/// JsonOptions::new()
+///     .ellipsis_after(2)
+///     .render(&values, &mut out)
+///     .unwrap();
+/// assert_eq!(out, r#"[[1,2],[3,4],"..."]"#);
+///
+/// let out = JsonOptions::new().max_depth(1).display(&values).to_string();
+/// assert_eq!(out, r#"["...","...","..."]"#);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    max_depth: Option<usize>,
+    ellipsis_after: Option<usize>,
+    non_finite_floats: NonFiniteFloats,
+}
+
+impl JsonOptions {
+    /// Returns the default options, which render values completely.
+    pub const fn new() -> Self {
+        Self {
+            max_depth: None,
+            ellipsis_after: None,
+            non_finite_floats: NonFiniteFloats::Null,
+        }
+    }
+
+    /// Renders arrays and objects nested more than `depth` levels deep as the
+    /// string `"..."`.
+    ///
+    /// With a depth of 0, the root value is rendered as `"..."` if it is an
+    /// array or object.
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Renders only the first `n` entries of each array and object.
+    ///
+    /// Arrays with more entries end with the string `"..."`, and objects with
+    /// more entries end with the entry `"...":"..."`.
+    pub const fn ellipsis_after(mut self, n: usize) -> Self {
+        self.ellipsis_after = Some(n);
+        self
+    }
+
+    /// Sets how NaN and infinite floats are rendered.
+    pub const fn non_finite_floats(mut self, policy: NonFiniteFloats) -> Self {
+        self.non_finite_floats = policy;
+        self
+    }
+
+    /// Renders `value` as JSON into `out` with these options.
+    pub fn render<T, W>(&self, value: &T, out: &mut W) -> fmt::Result
+    where
+        T: RenderJson + ?Sized,
+        W: fmt::Write,
+    {
+        value.render_json(&mut JsonRenderer {
+            out,
+            options: self,
+            depth: 0,
+            bare_strings: false,
+        })
+    }
+
+    /// Returns a value which renders `value` as JSON with these options when
+    /// it is formatted with `Display`.
+    pub fn display<'a, T: RenderJson + ?Sized>(
+        &'a self,
+        value: &'a T,
+    ) -> DisplayJson<'a, T> {
+        DisplayJson {
+            value,
+            options: self,
+        }
+    }
+}
+
+/// Renders `value` as JSON into `out` with the default options.
+pub fn render_json<T, W>(value: &T, out: &mut W) -> fmt::Result
+where
+    T: RenderJson + ?Sized,
+    W: fmt::Write,
+{
+    JsonOptions::new().render(value, out)
+}
+
+/// A value which is rendered as JSON when it is formatted with `Display`.
+///
+/// This is returned by [`JsonOptions::display`].
+pub struct DisplayJson<'a, T: ?Sized> {
+    value: &'a T,
+    options: &'a JsonOptions,
+}
+
+impl<T: RenderJson + ?Sized> fmt::Display for DisplayJson<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.options.render(self.value, f)
+    }
+}
+
+/// An archived type which can be rendered as JSON.
+pub trait RenderJson {
+    /// Renders this value with the given renderer.
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result;
+}
+
+/// Writes JSON values to an output.
+///
+/// This is passed to [`RenderJson::render_json`].
+pub struct JsonRenderer<'a> {
+    out: &'a mut dyn fmt::Write,
+    options: &'a JsonOptions,
+    depth: usize,
+    // Whether strings are written without quotes, for rendering map keys
+    bare_strings: bool,
+}
+
+impl<'a> JsonRenderer<'a> {
+    /// Writes `null`.
+    pub fn write_null(&mut self) -> fmt::Result {
+        self.out.write_str("null")
+    }
+
+    /// Writes a `bool`.
+    pub fn write_bool(&mut self, value: bool) -> fmt::Result {
+        self.out.write_str(if value { "true" } else { "false" })
+    }
+
+    /// Writes an integer.
+    pub fn write_int(&mut self, value: impl fmt::Display) -> fmt::Result {
+        write!(self.out, "{}", value)
+    }
+
+    /// Writes a float.
+    pub fn write_f32(&mut self, value: f32) -> fmt::Result {
+        if value.is_finite() {
+            write!(self.out, "{:?}", value)
+        } else {
+            self.write_non_finite(value.is_nan(), value > 0.0)
+        }
+    }
+
+    /// Writes a float.
+    pub fn write_f64(&mut self, value: f64) -> fmt::Result {
+        if value.is_finite() {
+            write!(self.out, "{:?}", value)
+        } else {
+            self.write_non_finite(value.is_nan(), value > 0.0)
+        }
+    }
+
+    fn write_non_finite(&mut self, nan: bool, positive: bool) -> fmt::Result {
+        match self.options.non_finite_floats {
+            NonFiniteFloats::Null => self.write_null(),
+            NonFiniteFloats::String if nan => self.write_str("NaN"),
+            NonFiniteFloats::String if positive => self.write_str("Infinity"),
+            NonFiniteFloats::String => self.write_str("-Infinity"),
+        }
+    }
+
+    /// Writes an escaped string.
+    pub fn write_str(&mut self, value: &str) -> fmt::Result {
+        if self.bare_strings {
+            // The output escapes the string when rendering keys
+            return self.out.write_str(value);
+        }
+        self.out.write_char('"')?;
+        Escape(self.out).write_str(value)?;
+        self.out.write_char('"')
+    }
+
+    /// Writes a `char` as an escaped string.
+    pub fn write_char(&mut self, value: char) -> fmt::Result {
+        self.write_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Returns whether nested arrays and objects would exceed the maximum
+    /// depth.
+    fn too_deep(&self) -> bool {
+        self.options.max_depth.is_some_and(|max| self.depth >= max)
+    }
+
+    fn nested(&mut self) -> JsonRenderer<'_> {
+        JsonRenderer {
+            out: self.out,
+            options: self.options,
+            depth: self.depth + 1,
+            bare_strings: false,
+        }
+    }
+
+    /// Begins writing an array.
+    pub fn array(&mut self) -> JsonArray<'_, 'a> {
+        JsonArray::new(self)
+    }
+
+    /// Begins writing an object.
+    pub fn object(&mut self) -> JsonObject<'_, 'a> {
+        JsonObject::new(self)
+    }
+
+    /// Writes the values of an iterator as an array.
+    pub fn write_seq<'i, T: RenderJson + ?Sized + 'i>(
+        &mut self,
+        values: impl IntoIterator<Item = &'i T>,
+    ) -> fmt::Result {
+        let mut array = self.array();
+        for value in values {
+            array.entry(value)?;
+        }
+        array.finish()
+    }
+
+    /// Writes the entries of an iterator as an object.
+    pub fn write_map<'i, K, V>(
+        &mut self,
+        entries: impl IntoIterator<Item = (&'i K, &'i V)>,
+    ) -> fmt::Result
+    where
+        K: RenderJson + ?Sized + 'i,
+        V: RenderJson + ?Sized + 'i,
+    {
+        let mut object = self.object();
+        for (key, value) in entries {
+            object.entry(key, value)?;
+        }
+        object.finish()
+    }
+}
+
+// Escapes the strings written to the inner writer.
+struct Escape<'a>(&'a mut dyn fmt::Write);
+
+impl fmt::Write for Escape<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (i, b) in s.bytes().enumerate() {
+            let escape = match b {
+                b'"' => "\\\"",
+                b'\\' => "\\\\",
+                b'\n' => "\\n",
+                b'\r' => "\\r",
+                b'\t' => "\\t",
+                0x08 => "\\b",
+                0x0c => "\\f",
+                0x00..=0x1f => "",
+                _ => continue,
+            };
+            self.0.write_str(&s[start..i])?;
+            if escape.is_empty() {
+                write!(self.0, "\\u{:04x}", b)?;
+            } else {
+                self.0.write_str(escape)?;
+            }
+            start = i + 1;
+        }
+        self.0.write_str(&s[start..])
+    }
+}
+
+const ELLIPSIS: &str = "\"...\"";
+
+/// A helper for writing arrays.
+///
+/// This is returned by [`JsonRenderer::array`].
+pub struct JsonArray<'a, 'b> {
+    renderer: &'a mut JsonRenderer<'b>,
+    len: usize,
+    result: fmt::Result,
+}
+
+impl<'a, 'b> JsonArray<'a, 'b> {
+    fn new(renderer: &'a mut JsonRenderer<'b>) -> Self {
+        let result = if renderer.too_deep() {
+            renderer.out.write_str(ELLIPSIS)
+        } else {
+            renderer.out.write_char('[')
+        };
+        Self {
+            renderer,
+            len: 0,
+            result,
+        }
+    }
+
+    /// Writes an entry of the array.
+    pub fn entry<T: RenderJson + ?Sized>(&mut self, value: &T) -> fmt::Result {
+        self.result?;
+        let len = self.len;
+        self.len += 1;
+        if self.renderer.too_deep()
+            || self
+                .renderer
+                .options
+                .ellipsis_after
+                .is_some_and(|n| len > n)
+        {
+            return Ok(());
+        }
+        if len > 0 {
+            self.renderer.out.write_char(',')?;
+        }
+        if self.renderer.options.ellipsis_after == Some(len) {
+            return self.renderer.out.write_str(ELLIPSIS);
+        }
+        self.result = value.render_json(&mut self.renderer.nested());
+        self.result
+    }
+
+    /// Finishes writing the array.
+    pub fn finish(self) -> fmt::Result {
+        self.result?;
+        if self.renderer.too_deep() {
+            Ok(())
+        } else {
+            self.renderer.out.write_char(']')
+        }
+    }
+}
+
+/// A helper for writing objects.
+///
+/// This is returned by [`JsonRenderer::object`].
+pub struct JsonObject<'a, 'b> {
+    renderer: &'a mut JsonRenderer<'b>,
+    len: usize,
+    result: fmt::Result,
+}
+
+impl<'a, 'b> JsonObject<'a, 'b> {
+    fn new(renderer: &'a mut JsonRenderer<'b>) -> Self {
+        let result = if renderer.too_deep() {
+            renderer.out.write_str(ELLIPSIS)
+        } else {
+            renderer.out.write_char('{')
+        };
+        Self {
+            renderer,
+            len: 0,
+            result,
+        }
+    }
+
+    // Writes the separator before an entry and returns whether the entry
+    // should be written.
+    fn begin_entry(&mut self) -> Result<bool, fmt::Error> {
+        self.result?;
+        let len = self.len;
+        self.len += 1;
+        if self.renderer.too_deep()
+            || self
+                .renderer
+                .options
+                .ellipsis_after
+                .is_some_and(|n| len > n)
+        {
+            return Ok(false);
+        }
+        if len > 0 {
+            self.renderer.out.write_char(',')?;
+        }
+        if self.renderer.options.ellipsis_after == Some(len) {
+            write!(self.renderer.out, "{}:{}", ELLIPSIS, ELLIPSIS)?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Writes a field with the given name.
+    pub fn field<T: RenderJson + ?Sized>(
+        &mut self,
+        name: &str,
+        value: &T,
+    ) -> fmt::Result {
+        self.field_with(name, |renderer| value.render_json(renderer))
+    }
+
+    /// Writes a field with the given name, whose value is written by `f`.
+    pub fn field_with(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut JsonRenderer<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        if self.begin_entry()? {
+            self.result = (|| {
+                let mut nested = self.renderer.nested();
+                nested.write_str(name)?;
+                nested.out.write_char(':')?;
+                f(&mut nested)
+            })();
+        }
+        self.result
+    }
+
+    /// Writes an entry with the given key.
+    ///
+    /// The key is rendered as a string. Keys which are not strings are
+    /// rendered as JSON, and the result is escaped and quoted.
+    pub fn entry<K, V>(&mut self, key: &K, value: &V) -> fmt::Result
+    where
+        K: RenderJson + ?Sized,
+        V: RenderJson + ?Sized,
+    {
+        if self.begin_entry()? {
+            self.result = (|| {
+                let out = &mut *self.renderer.out;
+                out.write_char('"')?;
+                key.render_json(&mut JsonRenderer {
+                    out: &mut Escape(out),
+                    options: self.renderer.options,
+                    depth: self.renderer.depth + 1,
+                    bare_strings: true,
+                })?;
+                out.write_str("\":")?;
+                value.render_json(&mut self.renderer.nested())
+            })();
+        }
+        self.result
+    }
+
+    /// Finishes writing the object.
+    pub fn finish(self) -> fmt::Result {
+        self.result?;
+        if self.renderer.too_deep() {
+            Ok(())
+        } else {
+            self.renderer.out.write_char('}')
+        }
+    }
+}
+
+// Primitives
+
+macro_rules! impl_int {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl RenderJson for $ty {
+                #[inline]
+                fn render_json(
+                    &self,
+                    renderer: &mut JsonRenderer<'_>,
+                ) -> fmt::Result {
+                    renderer.write_int(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(i8, u8, i16, i32, i64, i128, u16, u32, u64, u128);
+
+impl RenderJson for bool {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_bool(*self)
+    }
+}
+
+impl RenderJson for f32 {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_f32(*self)
+    }
+}
+
+impl RenderJson for f64 {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_f64(*self)
+    }
+}
+
+impl RenderJson for char {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_char(*self)
+    }
+}
+
+impl RenderJson for () {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_null()
+    }
+}
+
+impl RenderJson for PhantomPinned {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_null()
+    }
+}
+
+impl<T: ?Sized> RenderJson for PhantomData<T> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_null()
+    }
+}
+
+macro_rules! impl_endian_primitives {
+    ($module:path: $(
+        $i16:ident $i32:ident $i64:ident $i128:ident
+        $u16:ident $u32:ident $u64:ident $u128:ident
+        $f32:ident $f64:ident $char:ident
+    )*) => {
+        const _: () = {
+            use $module::{
+                $($i16, $i32, $i64, $i128, $u16, $u32, $u64, $u128, $f32,
+                $f64, $char,)*
+            };
+
+            $(
+                impl_endian_primitives!(
+                    @native $i16 $i32 $i64 $i128 $u16 $u32 $u64 $u128 $f32
+                    $f64 $char
+                );
+            )*
+        };
+    };
+    (@native $($ty:ident)*) => {
+        $(
+            impl RenderJson for $ty {
+                #[inline]
+                fn render_json(
+                    &self,
+                    renderer: &mut JsonRenderer<'_>,
+                ) -> fmt::Result {
+                    self.to_native().render_json(renderer)
+                }
+            }
+        )*
+    };
+}
+
+impl_endian_primitives! {
+    crate::rend:
+    i16_le i32_le i64_le i128_le u16_le u32_le u64_le u128_le f32_le f64_le
+    char_le
+}
+
+impl_endian_primitives! {
+    crate::rend:
+    i16_be i32_be i64_be i128_be u16_be u32_be u64_be u128_be f32_be f64_be
+    char_be
+}
+
+impl_endian_primitives! {
+    crate::rend::unaligned:
+    i16_ule i32_ule i64_ule i128_ule u16_ule u32_ule u64_ule u128_ule f32_ule
+    f64_ule char_ule
+}
+
+impl_endian_primitives! {
+    crate::rend::unaligned:
+    i16_ube i32_ube i64_ube i128_ube u16_ube u32_ube u64_ube u128_ube f32_ube
+    f64_ube char_ube
+}
+
+// Strings
+
+impl RenderJson for str {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_str(self)
+    }
+}
+
+impl RenderJson for ArchivedString {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_str(self.as_str())
+    }
+}
+
+// Arrays, slices, and vecs
+
+impl<T: RenderJson, const N: usize> RenderJson for [T; N] {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_seq(self.iter())
+    }
+}
+
+impl<T: RenderJson> RenderJson for [T] {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_seq(self.iter())
+    }
+}
+
+impl<T: RenderJson> RenderJson for ArchivedVec<T> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_seq(self.iter())
+    }
+}
+
+// Tuples
+
+macro_rules! impl_tuple {
+    ($name:ident, $($type:ident $index:tt),*) => {
+        impl<$($type: RenderJson),*> RenderJson for $name<$($type),*> {
+            #[inline]
+            fn render_json(
+                &self,
+                renderer: &mut JsonRenderer<'_>,
+            ) -> fmt::Result {
+                let mut array = renderer.array();
+                $(array.entry(&self.$index)?;)*
+                array.finish()
+            }
+        }
+    };
+}
+
+impl_tuple!(ArchivedTuple1, T0 0);
+impl_tuple!(ArchivedTuple2, T0 0, T1 1);
+impl_tuple!(ArchivedTuple3, T0 0, T1 1, T2 2);
+impl_tuple!(ArchivedTuple4, T0 0, T1 1, T2 2, T3 3);
+impl_tuple!(ArchivedTuple5, T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_tuple!(ArchivedTuple6, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_tuple!(ArchivedTuple7, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_tuple!(ArchivedTuple8, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_tuple!(
+    ArchivedTuple9, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8
+);
+impl_tuple!(
+    ArchivedTuple10, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9
+);
+impl_tuple!(
+    ArchivedTuple11, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10
+);
+impl_tuple!(
+    ArchivedTuple12, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10, T11 11
+);
+impl_tuple!(
+    ArchivedTuple13, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10, T11 11, T12 12
+);
+
+// Option and Box
+
+impl<T: RenderJson> RenderJson for ArchivedOption<T> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        match self.as_ref() {
+            Some(value) => value.render_json(renderer),
+            None => renderer.write_null(),
+        }
+    }
+}
+
+impl<T> RenderJson for ArchivedBox<T>
+where
+    T: ArchivePointee + RenderJson + ?Sized,
+{
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        self.get().render_json(renderer)
+    }
+}
+
+// Maps and sets
+
+impl<K: RenderJson, V: RenderJson, H> RenderJson for ArchivedHashMap<K, V, H> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_map(self.iter())
+    }
+}
+
+impl<K: RenderJson, H> RenderJson for ArchivedHashSet<K, H> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_seq(self.iter())
+    }
+}
+
+impl<K: RenderJson, V: RenderJson> RenderJson for ArchivedIndexMap<K, V> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_map(self.iter())
+    }
+}
+
+impl<K: RenderJson> RenderJson for ArchivedIndexSet<K> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_seq(self.iter())
+    }
+}
+
+impl<K: RenderJson, V: RenderJson> RenderJson for ArchivedBTreeMap<K, V> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_map(self.iter())
+    }
+}
+
+impl<K: RenderJson> RenderJson for ArchivedBTreeSet<K> {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        renderer.write_seq(self.iter())
+    }
+}
+
+// References
+
+impl<T: RenderJson + ?Sized> RenderJson for &T {
+    #[inline]
+    fn render_json(&self, renderer: &mut JsonRenderer<'_>) -> fmt::Result {
+        (**self).render_json(renderer)
+    }
+}
@@ -0,0 +1,210 @@
+//! Archived versions of `rust_decimal` types.
+
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use rust_decimal::Decimal;
+
+use crate::{primitive::ArchivedU32, Portable};
+
+/// The mask of the bits of the flags which hold the scale.
+const SCALE_MASK: u32 = 0x00ff_0000;
+/// The number of bits the scale is shifted left in the flags.
+const SCALE_SHIFT: u32 = 16;
+/// The mask of the bit of the flags which holds the sign.
+const SIGN_MASK: u32 = 0x8000_0000;
+/// The largest scale that a `Decimal` supports.
+const MAX_SCALE: u32 = 28;
+
+/// An archived [`Decimal`].
+///
+/// Archived decimals store the same 96-bit mantissa, scale, and sign as a
+/// `Decimal` in a portable format. They compare and hash by numeric value like
+/// `Decimal`, so `1.0` and `1.00` are equal.
+#[derive(Clone, Copy, Portable)]
+#[archive(crate)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+#[repr(C)]
+pub struct ArchivedDecimal {
+    flags: ArchivedU32,
+    lo: ArchivedU32,
+    mid: ArchivedU32,
+    hi: ArchivedU32,
+}
+
+impl ArchivedDecimal {
+    /// Creates a new archived decimal from a native `Decimal`.
+    #[inline]
+    pub fn from_decimal(value: &Decimal) -> Self {
+        let bytes = value.serialize();
+        let part = |i: usize| {
+            ArchivedU32::from_native(u32::from_le_bytes([
+                bytes[i],
+                bytes[i + 1],
+                bytes[i + 2],
+                bytes[i + 3],
+            ]))
+        };
+        Self {
+            flags: part(0),
+            lo: part(4),
+            mid: part(8),
+            hi: part(12),
+        }
+    }
+
+    /// Returns the scale of the archived decimal, which is the power of ten
+    /// that its mantissa is divided by.
+    #[inline]
+    pub fn scale(&self) -> u32 {
+        (self.flags.to_native() & SCALE_MASK) >> SCALE_SHIFT
+    }
+
+    /// Returns whether the archived decimal has a negative sign.
+    #[inline]
+    pub fn is_sign_negative(&self) -> bool {
+        self.flags.to_native() & SIGN_MASK != 0
+    }
+
+    /// Returns the archived decimal as a native `Decimal`.
+    #[inline]
+    pub fn to_decimal(&self) -> Decimal {
+        let mut bytes = [0; 16];
+        let parts = [self.flags, self.lo, self.mid, self.hi];
+        for (chunk, part) in bytes.chunks_exact_mut(4).zip(parts) {
+            chunk.copy_from_slice(&part.to_native().to_le_bytes());
+        }
+        Decimal::deserialize(bytes)
+    }
+}
+
+impl fmt::Debug for ArchivedDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_decimal().fmt(f)
+    }
+}
+
+impl fmt::Display for ArchivedDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_decimal().fmt(f)
+    }
+}
+
+impl Hash for ArchivedDecimal {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_decimal().hash(state);
+    }
+}
+
+impl PartialEq for ArchivedDecimal {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.to_decimal() == other.to_decimal()
+    }
+}
+
+impl Eq for ArchivedDecimal {}
+
+impl PartialOrd for ArchivedDecimal {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArchivedDecimal {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_decimal().cmp(&other.to_decimal())
+    }
+}
+
+impl PartialEq<Decimal> for ArchivedDecimal {
+    #[inline]
+    fn eq(&self, other: &Decimal) -> bool {
+        self.to_decimal() == *other
+    }
+}
+
+impl PartialEq<ArchivedDecimal> for Decimal {
+    #[inline]
+    fn eq(&self, other: &ArchivedDecimal) -> bool {
+        other.eq(self)
+    }
+}
+
+impl PartialOrd<Decimal> for ArchivedDecimal {
+    #[inline]
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.to_decimal().cmp(other))
+    }
+}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use core::fmt;
+
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        Verify,
+    };
+    use rancor::fail;
+
+    use super::{ArchivedDecimal, MAX_SCALE, SCALE_MASK, SIGN_MASK};
+
+    /// An error resulting from an invalid archived decimal.
+    #[derive(Debug)]
+    enum DecimalError {
+        /// The scale was larger than the maximum scale.
+        InvalidScale(u32),
+        /// Bits of the flags which must be zero were set.
+        InvalidFlags(u32),
+    }
+
+    impl fmt::Display for DecimalError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::InvalidScale(scale) => write!(
+                    f,
+                    "invalid decimal scale {}, expected at most {}",
+                    scale, MAX_SCALE,
+                ),
+                Self::InvalidFlags(flags) => write!(
+                    f,
+                    "invalid decimal flags {:#010x}, expected only the sign \
+                     and scale bits to be set",
+                    flags,
+                ),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for DecimalError {}
+
+    unsafe impl<C> Verify<C> for ArchivedDecimal
+    where
+        C: Fallible + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+            let flags = self.flags.to_native();
+            if flags & !(SIGN_MASK | SCALE_MASK) != 0 {
+                fail!(DecimalError::InvalidFlags(flags));
+            }
+            let scale = self.scale();
+            if scale > MAX_SCALE {
+                fail!(DecimalError::InvalidScale(scale));
+            }
+            Ok(())
+        }
+    }
+}
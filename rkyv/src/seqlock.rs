@@ -0,0 +1,576 @@
+//! Publishing archives to concurrent readers through shared memory.
+//!
+//! A [`SeqLockArchive`] manages a region of memory which holds a small header
+//! followed by the bytes of an archive. A publisher replaces the archive with
+//! [`publish`](SeqLockArchive::publish), and readers in other threads or
+//! processes read the latest archive with [`read`](SeqLockArchive::read)
+//! without ever blocking the publisher.
+//!
+//! The region is protected by a sequence lock. The header holds a sequence
+//! number which is odd while an archive is being published and even
+//! otherwise. Publishing:
+//!
+//! 1. Bumps the sequence number to the next odd number, then issues a release
+//!    fence.
+//! 2. Serializes the value directly into the region and records the length and
+//!    root position of the archive in the header.
+//! 3. Bumps the sequence number to the next even number with a release store.
+//!
+//! Reading:
+//!
+//! 1. Waits for an even sequence number with an acquire load.
+//! 2. Copies the length, root position, and bytes of the archive out of the
+//!    region.
+//! 3. Issues an acquire fence and loads the sequence number again. If it
+//!    changed, a publish overlapped the copy and the read is retried.
+//!
+//! All accesses to the region are made with relaxed atomic loads and stores,
+//! so a reader which overlaps a publish copies torn bytes instead of racing
+//! with the publisher. If a reader copies any byte written by a publish, the
+//! fences guarantee that it also sees the odd sequence number written before
+//! it, and so it never returns a torn copy.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     rancor::Failure, seqlock::SeqLockArchive, util::AlignedBytes, Archive,
+//!     Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(check_bytes)]
+//! struct Config {
+//!     name: String,
+//!     limit: u32,
+//! }
+//!
+//! let mut region = AlignedBytes([0u8; 256]);
+//! let archive = SeqLockArchive::<Config>::new::<Failure>(&mut region.0)
+//!     .unwrap();
+//! assert!(archive.read::<Failure>().unwrap().is_none());
+//!
+//! archive
+//!     .publish::<Failure>(&Config {
+//!         name: "primary".to_string(),
+//!         limit: 10,
+//!     })
+//!     .unwrap();
+//!
+//! let snapshot = archive.read::<Failure>().unwrap().unwrap();
+//! assert_eq!(snapshot.name, "primary");
+//! assert_eq!(snapshot.limit, 10);
+//! ```
+
+#[cfg(feature = "bytecheck")]
+use core::hint::spin_loop;
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    sync::atomic::{fence, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "bytecheck")]
+use bytecheck::CheckBytes;
+use rancor::{fail, Error, Strategy};
+
+use crate::{
+    ser::{
+        allocator::{BackupAllocator, BumpAllocator, GlobalAllocator},
+        sharing::Unify,
+        Composite, Positional, Writer,
+    },
+    Serialize,
+};
+#[cfg(feature = "bytecheck")]
+use crate::{
+    util::{AlignedVec, OwnedArchive},
+    validation::{util::access_pos, validators::DefaultValidator},
+    Archive,
+};
+
+/// The header at the start of a region.
+#[repr(C, align(16))]
+struct Header {
+    sequence: AtomicU64,
+    len: AtomicU64,
+    root: AtomicU64,
+}
+
+/// The root position recorded when there is no archive in the region.
+const EMPTY: u64 = u64::MAX;
+
+/// The number of bytes at the start of a region which are used for the
+/// header.
+///
+/// The archive is written immediately after the header.
+pub const HEADER_SIZE: usize = size_of::<Header>();
+
+/// The alignment required for a region.
+pub const REGION_ALIGN: usize = align_of::<Header>();
+
+#[derive(Debug)]
+enum SeqLockError {
+    Misaligned {
+        address: usize,
+    },
+    TooSmall {
+        len: usize,
+    },
+    PublishInProgress,
+    #[cfg(feature = "bytecheck")]
+    Corrupt {
+        len: u64,
+        root: u64,
+        capacity: usize,
+    },
+}
+
+impl fmt::Display for SeqLockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Misaligned { address } => write!(
+                f,
+                "seqlock region at {:#x} is not aligned to {} bytes",
+                address, REGION_ALIGN,
+            ),
+            Self::TooSmall { len } => write!(
+                f,
+                "seqlock region of {} bytes is too small for its {} byte \
+                 header",
+                len, HEADER_SIZE,
+            ),
+            Self::PublishInProgress => {
+                write!(f, "another archive is already being published")
+            }
+            #[cfg(feature = "bytecheck")]
+            Self::Corrupt {
+                len,
+                root,
+                capacity,
+            } => write!(
+                f,
+                "seqlock header is corrupt: archive length {} and root \
+                 position {} do not fit in {} bytes",
+                len, root, capacity,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+const _: () = {
+    use std::error::Error;
+
+    impl Error for SeqLockError {}
+};
+
+#[derive(Debug)]
+struct RegionOverflow {
+    bytes: usize,
+    pos: usize,
+    capacity: usize,
+}
+
+impl fmt::Display for RegionOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "overflowed seqlock region while writing {} bytes at pos {} \
+             (capacity is {})",
+            self.bytes, self.pos, self.capacity,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+const _: () = {
+    use std::error::Error;
+
+    impl Error for RegionOverflow {}
+};
+
+/// Copies bytes from `src` to `dst` with relaxed atomic loads.
+///
+/// # Safety
+///
+/// `src` must be valid for atomic reads of `dst.len()` bytes.
+#[cfg(feature = "bytecheck")]
+unsafe fn load_bytes(src: *const u8, dst: &mut [u8]) {
+    const WORD: usize = size_of::<usize>();
+
+    let mut i = 0;
+    while i < dst.len() && (src.addr() + i) & (WORD - 1) != 0 {
+        dst[i] = (*src.add(i).cast::<AtomicU8>()).load(Ordering::Relaxed);
+        i += 1;
+    }
+    while i + WORD <= dst.len() {
+        let word = (*src.add(i).cast::<AtomicUsize>()).load(Ordering::Relaxed);
+        dst[i..i + WORD].copy_from_slice(&word.to_ne_bytes());
+        i += WORD;
+    }
+    while i < dst.len() {
+        dst[i] = (*src.add(i).cast::<AtomicU8>()).load(Ordering::Relaxed);
+        i += 1;
+    }
+}
+
+/// Copies bytes from `src` to `dst` with relaxed atomic stores.
+///
+/// # Safety
+///
+/// `dst` must be valid for atomic writes of `src.len()` bytes.
+unsafe fn store_bytes(src: &[u8], dst: *mut u8) {
+    const WORD: usize = size_of::<usize>();
+
+    let mut i = 0;
+    while i < src.len() && (dst.addr() + i) & (WORD - 1) != 0 {
+        (*dst.add(i).cast::<AtomicU8>()).store(src[i], Ordering::Relaxed);
+        i += 1;
+    }
+    while i + WORD <= src.len() {
+        let mut word = [0; WORD];
+        word.copy_from_slice(&src[i..i + WORD]);
+        (*dst.add(i).cast::<AtomicUsize>())
+            .store(usize::from_ne_bytes(word), Ordering::Relaxed);
+        i += WORD;
+    }
+    while i < src.len() {
+        (*dst.add(i).cast::<AtomicU8>()).store(src[i], Ordering::Relaxed);
+        i += 1;
+    }
+}
+
+/// A writer which writes to the archive bytes of a [`SeqLockArchive`].
+///
+/// Bytes are written with relaxed atomic stores so that concurrent readers
+/// never race with the writer. Writing past the end of the region returns an
+/// error.
+pub struct SeqLockWriter<'a> {
+    ptr: *mut u8,
+    capacity: usize,
+    pos: usize,
+    _phantom: PhantomData<&'a [AtomicU8]>,
+}
+
+impl fmt::Debug for SeqLockWriter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeqLockWriter")
+            .field("capacity", &self.capacity)
+            .field("pos", &self.pos)
+            .finish()
+    }
+}
+
+impl Positional for SeqLockWriter<'_> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<E: Error> Writer<E> for SeqLockWriter<'_> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        if bytes.len() > self.capacity - self.pos {
+            fail!(RegionOverflow {
+                bytes: bytes.len(),
+                pos: self.pos,
+                capacity: self.capacity,
+            });
+        }
+        // SAFETY: The bytes from `pos` to `pos + bytes.len()` are inside of
+        // the region, which is valid for atomic writes for `'a`.
+        unsafe {
+            store_bytes(bytes, self.ptr.add(self.pos));
+        }
+        self.pos += bytes.len();
+        Ok(())
+    }
+}
+
+/// The serializer used to publish values to a [`SeqLockArchive`].
+pub type SeqLockSerializer<'a> = Composite<
+    SeqLockWriter<'a>,
+    BackupAllocator<BumpAllocator<1024>, GlobalAllocator>,
+    Unify,
+>;
+
+/// An archive of a `T` in a region of shared memory which can be replaced
+/// while it is being read.
+///
+/// Only one archive may be published at a time. Publishing while another
+/// publish is in progress returns an error, so publishers in different
+/// threads or processes do not need to coordinate with each other. If a
+/// publisher stops while publishing, readers will wait forever for it to
+/// finish.
+///
+/// See the [module docs](crate::seqlock) for the protocol and an example.
+pub struct SeqLockArchive<'a, T> {
+    header: &'a Header,
+    payload: *mut u8,
+    capacity: usize,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+// SAFETY: The region is only accessed through atomic operations, except for
+// reads made by `read_with` whose safety requirements forbid racing writes.
+unsafe impl<T> Send for SeqLockArchive<'_, T> {}
+unsafe impl<T> Sync for SeqLockArchive<'_, T> {}
+
+impl<'a, T> SeqLockArchive<'a, T> {
+    /// Creates a new, empty archive in the given region.
+    ///
+    /// The region must be aligned to [`REGION_ALIGN`] bytes and at least
+    /// [`HEADER_SIZE`] bytes long. The remaining bytes hold the archive.
+    pub fn new<E: Error>(region: &'a mut [u8]) -> Result<Self, E> {
+        let len = region.len();
+        // SAFETY: The region is valid for reads and writes for `'a` because
+        // it is borrowed mutably for `'a`.
+        let archive =
+            unsafe { Self::from_raw_parts(region.as_mut_ptr(), len)? };
+        archive.header.sequence.store(0, Ordering::Relaxed);
+        archive.header.len.store(0, Ordering::Relaxed);
+        archive.header.root.store(EMPTY, Ordering::Relaxed);
+        Ok(archive)
+    }
+
+    /// Opens the archive in the region of `len` bytes starting at `ptr`.
+    ///
+    /// This is used to read and publish to a region which is shared with
+    /// another process, or with another `SeqLockArchive` in this process.
+    ///
+    /// # Safety
+    ///
+    /// - The region must be valid for reads and writes for `'a`.
+    /// - The header of the region must have been initialized by
+    ///   [`new`](SeqLockArchive::new).
+    /// - The region must only be accessed through `SeqLockArchive`s for `T`
+    ///   while it is open.
+    pub unsafe fn from_raw_parts<E: Error>(
+        ptr: *mut u8,
+        len: usize,
+    ) -> Result<Self, E> {
+        if ptr.addr() & (REGION_ALIGN - 1) != 0 {
+            fail!(SeqLockError::Misaligned {
+                address: ptr.addr(),
+            });
+        }
+        if len < HEADER_SIZE {
+            fail!(SeqLockError::TooSmall { len });
+        }
+        Ok(Self {
+            header: &*ptr.cast::<Header>(),
+            payload: ptr.add(HEADER_SIZE),
+            capacity: len - HEADER_SIZE,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the maximum number of bytes an archive may have.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the current sequence number.
+    ///
+    /// The sequence number is even when no archive is being published. It
+    /// increases by two every time an archive is published, so readers can
+    /// poll it to find out whether the archive has changed.
+    #[inline]
+    pub fn sequence(&self) -> u64 {
+        self.header.sequence.load(Ordering::Acquire)
+    }
+
+    /// Serializes the given value into the region, replacing the current
+    /// archive, and returns the new sequence number.
+    ///
+    /// If serialization fails, for example because the archive does not fit
+    /// in the region, the region is left empty and readers will not find an
+    /// archive until the next successful publish.
+    pub fn publish<E>(&self, value: &T) -> Result<u64, E>
+    where
+        T: Serialize<Strategy<SeqLockSerializer<'a>, E>>,
+        E: Error,
+    {
+        let start = self.header.sequence.load(Ordering::Relaxed);
+        if start & 1 != 0
+            || self
+                .header
+                .sequence
+                .compare_exchange(
+                    start,
+                    start + 1,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            fail!(SeqLockError::PublishInProgress);
+        }
+        // Readers which copy any of the bytes written after this fence will
+        // also see the odd sequence number when they check it again.
+        fence(Ordering::Release);
+
+        let mut serializer = Composite::new(
+            SeqLockWriter {
+                ptr: self.payload,
+                capacity: self.capacity,
+                pos: 0,
+                _phantom: PhantomData,
+            },
+            BackupAllocator::new(BumpAllocator::new(), GlobalAllocator::new()),
+            Unify::new(),
+        );
+        let result =
+            value.serialize_and_resolve(Strategy::wrap(&mut serializer));
+        let (len, root) = match result {
+            Ok(root) => (serializer.writer.pos as u64, root as u64),
+            Err(_) => (0, EMPTY),
+        };
+        self.header.len.store(len, Ordering::Relaxed);
+        self.header.root.store(root, Ordering::Relaxed);
+
+        let end = start + 2;
+        self.header.sequence.store(end, Ordering::Release);
+        result.map(|_| end)
+    }
+}
+
+#[cfg(feature = "bytecheck")]
+impl<'a, T: Archive> SeqLockArchive<'a, T> {
+    /// Copies the current archive out of the region, validates it, and
+    /// returns it.
+    ///
+    /// Returns `None` if there is no archive in the region. If an archive is
+    /// published while it is being copied, the copy is retried.
+    pub fn read<E>(&self) -> Result<Option<OwnedArchive<T>>, E>
+    where
+        T::Archived: CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        loop {
+            let start = self.begin_read();
+            let len = self.header.len.load(Ordering::Relaxed);
+            let root = self.header.root.load(Ordering::Relaxed);
+            let mut bytes = AlignedVec::new();
+            if root != EMPTY && self.fits(len, root) {
+                bytes.resize(len as usize, 0);
+                // SAFETY: The region holds `capacity` bytes after the header,
+                // and `len` is at most `capacity`.
+                unsafe {
+                    load_bytes(self.payload, bytes.as_mut_slice());
+                }
+            }
+            if !self.end_read(start) {
+                continue;
+            }
+
+            if root == EMPTY {
+                return Ok(None);
+            }
+            if !self.fits(len, root) {
+                fail!(SeqLockError::Corrupt {
+                    len,
+                    root,
+                    capacity: self.capacity,
+                });
+            }
+            return OwnedArchive::new::<E>(bytes).map(Some);
+        }
+    }
+
+    /// Validates the current archive in place and calls `f` with it.
+    ///
+    /// Returns `None` if there is no archive in the region. This avoids
+    /// copying the archive, but the archive is only valid until the next
+    /// publish begins. After `f` returns, the sequence number is checked
+    /// again. If an archive was published in the meantime, the result is
+    /// discarded and `f` is called again with the new archive.
+    ///
+    /// # Safety
+    ///
+    /// No archive may be published from the time `read_with` is called until
+    /// `f` returns. If one is, `f` may read an archive which is being
+    /// overwritten, including relative pointers which point outside of the
+    /// region. The sequence check keeps such a result from being returned,
+    /// but cannot undo the reads which `f` made.
+    pub unsafe fn read_with<E, R>(
+        &self,
+        mut f: impl FnMut(&T::Archived) -> R,
+    ) -> Result<Option<R>, E>
+    where
+        T::Archived: CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        loop {
+            let start = self.begin_read();
+            let len = self.header.len.load(Ordering::Relaxed);
+            let root = self.header.root.load(Ordering::Relaxed);
+            if root == EMPTY || !self.fits(len, root) {
+                if !self.end_read(start) {
+                    continue;
+                }
+                if root == EMPTY {
+                    return Ok(None);
+                }
+                fail!(SeqLockError::Corrupt {
+                    len,
+                    root,
+                    capacity: self.capacity,
+                });
+            }
+
+            let bytes = core::slice::from_raw_parts(self.payload, len as usize);
+            let archived =
+                match access_pos::<T::Archived, E>(bytes, root as usize) {
+                    Ok(archived) => archived,
+                    Err(error) => {
+                        if self.end_read(start) {
+                            return Err(error);
+                        }
+                        continue;
+                    }
+                };
+            let result = f(archived);
+            if self.end_read(start) {
+                return Ok(Some(result));
+            }
+        }
+    }
+
+    /// Waits until no archive is being published and returns the sequence
+    /// number.
+    fn begin_read(&self) -> u64 {
+        loop {
+            let sequence = self.header.sequence.load(Ordering::Acquire);
+            if sequence & 1 == 0 {
+                return sequence;
+            }
+            spin_loop();
+        }
+    }
+
+    /// Returns whether no archive was published since `begin_read` returned
+    /// `start`.
+    fn end_read(&self, start: u64) -> bool {
+        fence(Ordering::Acquire);
+        self.header.sequence.load(Ordering::Relaxed) == start
+    }
+
+    /// Returns whether an archive with the given length and root position
+    /// fits in the region.
+    fn fits(&self, len: u64, root: u64) -> bool {
+        len <= self.capacity as u64 && root <= len
+    }
+}
+
+impl<T> fmt::Debug for SeqLockArchive<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeqLockArchive")
+            .field("sequence", &self.sequence())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
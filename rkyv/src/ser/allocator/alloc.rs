@@ -4,7 +4,7 @@ use alloc::{
     boxed::Box,
     vec::Vec,
 };
-use core::{alloc::Layout, fmt, ptr::NonNull};
+use core::{alloc::Layout, fmt, mem::size_of, ptr::NonNull};
 #[cfg(feature = "std")]
 use std::alloc::{alloc, alloc_zeroed, dealloc};
 
@@ -12,7 +12,7 @@ use rancor::{fail, Error};
 
 use crate::{
     ser::{allocator::BufferAllocator, Allocator},
-    util::AlignedBytes,
+    util::{AlignedBytes, AllocationFailed},
 };
 
 /// Fixed-size scratch space allocated on the heap.
@@ -24,20 +24,28 @@ pub struct BumpAllocator<const N: usize> {
 impl<const N: usize> BumpAllocator<N> {
     /// Creates a new heap scratch space.
     pub fn new() -> Self {
+        Self::try_new().expect("failed to allocate scratch space")
+    }
+
+    /// Creates a new heap scratch space, returning an error if the memory
+    /// could not be allocated.
+    pub fn try_new() -> Result<Self, AllocationFailed> {
         if N != 0 {
             unsafe {
                 let layout = Layout::new::<AlignedBytes<N>>();
                 let ptr = alloc_zeroed(layout).cast::<AlignedBytes<N>>();
-                assert!(!ptr.is_null());
+                if ptr.is_null() {
+                    return Err(AllocationFailed::new(layout.size()));
+                }
                 let buf = Box::from_raw(ptr);
-                Self {
+                Ok(Self {
                     inner: BufferAllocator::new(buf),
-                }
+                })
             }
         } else {
-            Self {
+            Ok(Self {
                 inner: BufferAllocator::new(Box::default()),
-            }
+            })
         }
     }
 
@@ -117,6 +125,9 @@ const _: () = {
 ///
 /// This allocator will panic if scratch is popped that it did not allocate. For
 /// this reason, it should only ever be used as a fallback allocator.
+///
+/// If the global allocator fails, allocating scratch space returns an
+/// [`AllocationFailed`] error.
 #[derive(Debug, Default)]
 pub struct GlobalAllocator {
     remaining: Option<usize>,
@@ -172,8 +183,13 @@ impl<E: Error> Allocator<E> for GlobalAllocator {
                 });
             }
         }
+        if self.allocations.try_reserve(1).is_err() {
+            fail!(AllocationFailed::new(size_of::<(*mut u8, Layout)>()));
+        }
         let result_ptr = alloc(layout);
-        assert!(!result_ptr.is_null());
+        if result_ptr.is_null() {
+            fail!(AllocationFailed::new(layout.size()));
+        }
         self.allocations.push((result_ptr, layout));
         let result_slice =
             ptr_meta::from_raw_parts_mut(result_ptr.cast(), layout.size());
@@ -194,9 +210,9 @@ impl<E: Error> Allocator<E> for GlobalAllocator {
                 Ok(())
             } else {
                 fail!(GlobalAllocatorError::NotPoppedInReverseOrder {
-                    expected: last_ptr as usize,
+                    expected: last_ptr.addr(),
                     expected_layout: last_layout,
-                    actual: ptr.as_ptr() as usize,
+                    actual: ptr.as_ptr().addr(),
                     actual_layout: layout,
                 });
             }
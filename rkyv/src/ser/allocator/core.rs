@@ -84,7 +84,7 @@ where
     ) -> Result<NonNull<[u8]>, E> {
         let bytes = self.buffer.as_mut();
 
-        let pos = bytes.as_ptr() as usize + self.pos;
+        let pos = bytes.as_ptr().addr() + self.pos;
         let pad = 0usize.wrapping_sub(pos) % layout.align();
         if pad + layout.size() <= bytes.len() - self.pos {
             self.pos += pad;
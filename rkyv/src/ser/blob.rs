@@ -0,0 +1,183 @@
+//! Storing external blobs while serializing.
+
+use core::{alloc::Layout, hash::Hash, ptr::NonNull};
+
+use rancor::Strategy;
+
+use crate::{
+    external::BlobHandle,
+    ser::{Allocator, InternTable, Positional, Regions, Sharing, Writer},
+};
+
+/// A serializer that can store blobs outside of the archive.
+///
+/// This is used by the [`External`](crate::with::External) wrapper. Blob
+/// sinks are usually implemented by users for their own blob stores, and are
+/// added to a serializer with a [`BlobSerializer`].
+pub trait BlobSink<E = <Self as rancor::Fallible>::Error> {
+    /// Stores a blob and returns a handle which can be used to load it later.
+    fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E>;
+}
+
+impl<T, E> BlobSink<E> for Strategy<T, E>
+where
+    T: BlobSink<E> + ?Sized,
+{
+    #[inline]
+    fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+        T::store_blob(self, bytes)
+    }
+}
+
+impl<T: BlobSink<E> + ?Sized, E> BlobSink<E> for &mut T {
+    #[inline]
+    fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+        T::store_blob(self, bytes)
+    }
+}
+
+/// A serializer adapter which stores external blobs in a [`BlobSink`].
+///
+/// All other serializer capabilities are forwarded to the wrapped serializer.
+///
+/// See [`External`](crate::with::External) for an example.
+#[derive(Debug)]
+pub struct BlobSerializer<S, B> {
+    /// The wrapped serializer.
+    pub serializer: S,
+    /// The sink which blobs are stored in.
+    pub sink: B,
+}
+
+impl<S, B> BlobSerializer<S, B> {
+    /// Creates a new serializer which stores external blobs in the given sink.
+    #[inline]
+    pub fn new(serializer: S, sink: B) -> Self {
+        Self { serializer, sink }
+    }
+
+    /// Consumes the adapter and returns the wrapped serializer.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.serializer
+    }
+}
+
+impl<S, B: BlobSink<E>, E> BlobSink<E> for BlobSerializer<S, B> {
+    #[inline]
+    fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+        self.sink.store_blob(bytes)
+    }
+}
+
+impl<S: Positional, B> Positional for BlobSerializer<S, B> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.serializer.pos()
+    }
+}
+
+impl<S: Regions, B> Regions for BlobSerializer<S, B> {
+    #[inline]
+    fn is_cold(&self) -> bool {
+        self.serializer.is_cold()
+    }
+
+    #[inline]
+    fn set_cold(&mut self, is_cold: bool) {
+        self.serializer.set_cold(is_cold)
+    }
+}
+
+impl<S: Writer<E>, B, E> Writer<E> for BlobSerializer<S, B> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.serializer.write(bytes)
+    }
+
+    #[inline]
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        self.serializer.find_str(value)
+    }
+
+    #[inline]
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        self.serializer.record_str(value, pos)
+    }
+
+    #[inline]
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        self.serializer.begin_collection(len)
+    }
+
+    #[inline]
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        self.serializer.end_collection(len)
+    }
+
+    #[inline]
+    fn enter_type(&mut self, type_name: &'static str) -> Result<(), E> {
+        self.serializer.enter_type(type_name)
+    }
+
+    #[inline]
+    fn exit_type(&mut self) {
+        self.serializer.exit_type()
+    }
+}
+
+impl<S: Allocator<E>, B, E> Allocator<E> for BlobSerializer<S, B> {
+    #[inline]
+    unsafe fn push_alloc(
+        &mut self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, E> {
+        self.serializer.push_alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn pop_alloc(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), E> {
+        self.serializer.pop_alloc(ptr, layout)
+    }
+}
+
+impl<S: Sharing<E>, B, E> Sharing<E> for BlobSerializer<S, B> {
+    #[inline]
+    fn get_shared_ptr(&self, address: usize) -> Option<usize> {
+        self.serializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
+        self.serializer.add_shared_ptr(address, pos)
+    }
+
+    #[inline]
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        self.serializer.start_shared_ptr(address, type_name)
+    }
+}
+
+impl<S: InternTable<E>, B, E> InternTable<E> for BlobSerializer<S, B> {
+    #[inline]
+    fn get_interned<T: Hash + Eq + 'static>(&self, value: &T) -> Option<usize> {
+        self.serializer.get_interned(value)
+    }
+
+    #[inline]
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &T,
+        pos: usize,
+    ) -> Result<(), E> {
+        self.serializer.add_interned(value, pos)
+    }
+}
@@ -0,0 +1,423 @@
+//! Attributing serialized bytes and time to types.
+//!
+//! With the `instrument` feature, derived `Serialize` implementations and the
+//! implementations for `Vec`, `HashMap`, and `BTreeMap` call
+//! [`enter_type`](Writer::enter_type) and
+//! [`exit_type`](Writer::exit_type) around serializing each value.
+//! The [`Profiling`] serializer adapter counts the bytes written and the time
+//! spent between those calls, and [`Profiling::report`] collects them into a
+//! table with one row per type.
+//!
+//! Bytes and time are attributed to the innermost type being serialized, so
+//! each byte is counted once. Because archived values are written by the
+//! value which contains them, the bytes of an archived struct are attributed to
+//! the type which contains it, and the root object is not attributed to any
+//! type.
+//!
+//! Without the `hooks` feature, which `instrument` enables, the hooks are
+//! compiled out entirely.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     rancor::Failure,
+//!     ser::{instrument::Profiling, AllocSerializer},
+//!     util::serialize_into,
+//!     Archive, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! struct Document {
+//!     title: String,
+//!     pages: Vec<Page>,
+//! }
+//!
+//! #[derive(Archive, Serialize)]
+//! struct Page {
+//!     text: String,
+//! }
+//!
+//! let document = Document {
+//!     title: "Instrumentation".to_string(),
+//!     pages: vec![Page {
+//!         text: "Where did all of the bytes go?".to_string(),
+//!     }],
+//! };
+//!
+//! let serializer = serialize_into::<_, _, Failure>(
+//!     &document,
+//!     Profiling::new(AllocSerializer::<256>::default()),
+//! )
+//! .unwrap();
+//! let report = serializer.report();
+//! let page = report.get(core::any::type_name::<Page>()).unwrap();
+//! assert_eq!(page.count, 1);
+//! assert_eq!(page.bytes, 30);
+//! println!("{}", report);
+//! ```
+
+use core::{
+    alloc::Layout,
+    fmt,
+    hash::{BuildHasherDefault, Hash},
+    ptr::NonNull,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use rancor::Error;
+
+use crate::{
+    external::BlobHandle,
+    hash::FxHasher64,
+    ser::{
+        Allocator, BlobSink, InternTable, Positional, Regions, Sharing, Writer,
+    },
+};
+
+/// The bytes and time attributed to a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeProfile {
+    /// The name of the type, as returned by
+    /// [`type_name`](core::any::type_name).
+    pub type_name: &'static str,
+    /// The number of values of the type which were serialized.
+    pub count: u64,
+    /// The number of bytes written while serializing values of the type,
+    /// excluding bytes written by other types inside of them.
+    pub bytes: u64,
+    /// The time spent serializing values of the type, excluding time spent
+    /// serializing other types inside of them, in nanoseconds.
+    pub nanos: u64,
+}
+
+/// The bytes and time attributed to each type by a [`Profiling`] serializer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// The profiles of each type, sorted by the number of bytes attributed to
+    /// them from most to least.
+    pub types: Vec<TypeProfile>,
+    /// The number of bytes written outside of any type.
+    pub unattributed_bytes: u64,
+    /// The total number of bytes written.
+    pub total_bytes: u64,
+}
+
+impl Report {
+    /// Returns the profile of the type with the given name, if any.
+    pub fn get(&self, type_name: &str) -> Option<&TypeProfile> {
+        self.types.iter().find(|t| t.type_name == type_name)
+    }
+
+    /// Returns the total number of bytes attributed to types.
+    pub fn attributed_bytes(&self) -> u64 {
+        self.types.iter().map(|t| t.bytes).sum()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>12} {:>7} {:>10} {:>12}  type",
+            "bytes", "%", "count", "time (us)",
+        )?;
+        let percent = |bytes: u64| {
+            if self.total_bytes == 0 {
+                0.0
+            } else {
+                bytes as f64 * 100.0 / self.total_bytes as f64
+            }
+        };
+        for t in self.types.iter() {
+            writeln!(
+                f,
+                "{:>12} {:>6.2}% {:>10} {:>12.1}  {}",
+                t.bytes,
+                percent(t.bytes),
+                t.count,
+                t.nanos as f64 / 1000.0,
+                t.type_name,
+            )?;
+        }
+        writeln!(
+            f,
+            "{:>12} {:>6.2}% {:>10} {:>12}  (unattributed)",
+            self.unattributed_bytes,
+            percent(self.unattributed_bytes),
+            "",
+            "",
+        )?;
+        write!(
+            f,
+            "{:>12} {:>7} {:>10} {:>12}  total",
+            self.total_bytes, "", "", ""
+        )
+    }
+}
+
+struct Frame {
+    index: usize,
+    start: Option<Instant>,
+    children: Duration,
+}
+
+/// A serializer adapter which attributes the bytes written and the time spent
+/// serializing to types.
+///
+/// The attribution relies on the `enter_type` and `exit_type` hooks, which are
+/// only called with the `instrument` feature. If serialization fails, the
+/// report only covers the values serialized before the failure.
+///
+/// Reading the clock twice for every value is most of the cost of profiling.
+/// Use [`without_timing`](Profiling::without_timing) if only the bytes
+/// attributed to each type are needed.
+///
+/// All other serializer capabilities are forwarded to the wrapped serializer.
+pub struct Profiling<S> {
+    /// The wrapped serializer.
+    pub serializer: S,
+    types: Vec<TypeProfile>,
+    // Indexed by the address of the type name, which is much faster to hash
+    // than the name itself.
+    indices: HashMap<usize, usize, BuildHasherDefault<FxHasher64>>,
+    stack: Vec<Frame>,
+    unattributed_bytes: u64,
+    total_bytes: u64,
+    timing: bool,
+}
+
+impl<S> Profiling<S> {
+    /// Creates a new serializer which profiles the given serializer.
+    pub fn new(serializer: S) -> Self {
+        Self {
+            serializer,
+            types: Vec::new(),
+            indices: HashMap::default(),
+            stack: Vec::new(),
+            unattributed_bytes: 0,
+            total_bytes: 0,
+            timing: true,
+        }
+    }
+
+    /// Creates a new serializer which profiles the given serializer without
+    /// measuring time.
+    ///
+    /// The time attributed to each type is always zero.
+    pub fn without_timing(serializer: S) -> Self {
+        Self {
+            timing: false,
+            ..Self::new(serializer)
+        }
+    }
+
+    /// Returns the bytes and time attributed to each type so far.
+    ///
+    /// Types with the same name are combined.
+    pub fn report(&self) -> Report {
+        let mut types = Vec::<TypeProfile>::with_capacity(self.types.len());
+        let mut by_name = HashMap::<&str, usize>::new();
+        for profile in self.types.iter() {
+            match by_name.get(profile.type_name) {
+                Some(&index) => {
+                    let existing = &mut types[index];
+                    existing.count += profile.count;
+                    existing.bytes += profile.bytes;
+                    existing.nanos += profile.nanos;
+                }
+                None => {
+                    by_name.insert(profile.type_name, types.len());
+                    types.push(profile.clone());
+                }
+            }
+        }
+        types.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then(b.nanos.cmp(&a.nanos))
+                .then(a.type_name.cmp(b.type_name))
+        });
+
+        Report {
+            types,
+            unattributed_bytes: self.unattributed_bytes,
+            total_bytes: self.total_bytes,
+        }
+    }
+
+    /// Consumes the adapter and returns the wrapped serializer.
+    pub fn into_inner(self) -> S {
+        self.serializer
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for Profiling<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profiling")
+            .field("serializer", &self.serializer)
+            .field("types", &self.types)
+            .field("depth", &self.stack.len())
+            .field("unattributed_bytes", &self.unattributed_bytes)
+            .field("total_bytes", &self.total_bytes)
+            .finish()
+    }
+}
+
+impl<S: Positional> Positional for Profiling<S> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.serializer.pos()
+    }
+}
+
+impl<S: Regions> Regions for Profiling<S> {
+    #[inline]
+    fn is_cold(&self) -> bool {
+        self.serializer.is_cold()
+    }
+
+    #[inline]
+    fn set_cold(&mut self, is_cold: bool) {
+        self.serializer.set_cold(is_cold)
+    }
+}
+
+impl<S: Writer<E>, E: Error> Writer<E> for Profiling<S> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.serializer.write(bytes)?;
+        let len = bytes.len() as u64;
+        self.total_bytes += len;
+        match self.stack.last() {
+            Some(frame) => self.types[frame.index].bytes += len,
+            None => self.unattributed_bytes += len,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        self.serializer.find_str(value)
+    }
+
+    #[inline]
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        self.serializer.record_str(value, pos)
+    }
+
+    #[inline]
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        self.serializer.begin_collection(len)
+    }
+
+    #[inline]
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        self.serializer.end_collection(len)
+    }
+
+    fn enter_type(&mut self, type_name: &'static str) -> Result<(), E> {
+        self.serializer.enter_type(type_name)?;
+        let index = *self
+            .indices
+            .entry(type_name.as_ptr().addr())
+            .or_insert_with(|| {
+                self.types.push(TypeProfile {
+                    type_name,
+                    count: 0,
+                    bytes: 0,
+                    nanos: 0,
+                });
+                self.types.len() - 1
+            });
+        self.types[index].count += 1;
+        self.stack.push(Frame {
+            index,
+            start: self.timing.then(Instant::now),
+            children: Duration::ZERO,
+        });
+        Ok(())
+    }
+
+    fn exit_type(&mut self) {
+        self.serializer.exit_type();
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        if let Some(start) = frame.start {
+            let elapsed = start.elapsed();
+            let exclusive = elapsed.saturating_sub(frame.children);
+            self.types[frame.index].nanos += exclusive.as_nanos() as u64;
+            if let Some(parent) = self.stack.last_mut() {
+                parent.children += elapsed;
+            }
+        }
+    }
+}
+
+impl<S: Allocator<E>, E> Allocator<E> for Profiling<S> {
+    #[inline]
+    unsafe fn push_alloc(
+        &mut self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, E> {
+        self.serializer.push_alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn pop_alloc(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), E> {
+        self.serializer.pop_alloc(ptr, layout)
+    }
+}
+
+impl<S: Sharing<E>, E> Sharing<E> for Profiling<S> {
+    #[inline]
+    fn get_shared_ptr(&self, address: usize) -> Option<usize> {
+        self.serializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
+        self.serializer.add_shared_ptr(address, pos)
+    }
+
+    #[inline]
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        self.serializer.start_shared_ptr(address, type_name)
+    }
+}
+
+impl<S: InternTable<E>, E> InternTable<E> for Profiling<S> {
+    #[inline]
+    fn get_interned<T: Hash + Eq + 'static>(&self, value: &T) -> Option<usize> {
+        self.serializer.get_interned(value)
+    }
+
+    #[inline]
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &T,
+        pos: usize,
+    ) -> Result<(), E> {
+        self.serializer.add_interned(value, pos)
+    }
+}
+
+impl<S: BlobSink<E>, E> BlobSink<E> for Profiling<S> {
+    #[inline]
+    fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+        self.serializer.store_blob(bytes)
+    }
+}
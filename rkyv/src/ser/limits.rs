@@ -0,0 +1,391 @@
+//! Size and depth limits for serializers.
+//!
+//! Values built from untrusted input, like deeply-nested JSON, can produce
+//! pathological archives. The [`Limited`] serializer adapter stops serializing
+//! as soon as a value exceeds its [`Limits`] instead of finishing an enormous
+//! archive, and returns a [`SerializationLimitExceeded`] error describing the
+//! limit which was exceeded.
+//!
+//! The depth of values is tracked with the
+//! [`enter_type`](crate::ser::Writer::enter_type) and
+//! [`exit_type`](crate::ser::Writer::exit_type) hooks, which derived
+//! `Serialize` implementations and the implementations for `Vec`, `HashMap`,
+//! and `BTreeMap` call with the `limits` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     rancor::BoxedError, ser::limits::Limits, util::to_bytes_limited,
+//! };
+//!
+//! let value = vec![vec![vec![1u32, 2, 3]]];
+//! let limits = Limits {
+//!     max_depth: 2,
+//!     ..Limits::default()
+//! };
+//! let error =
+//!     to_bytes_limited::<_, 256, BoxedError>(&value, limits).unwrap_err();
+//! assert_eq!(
+//!     error.to_string(),
+//!     "serialization depth limit exceeded: requested 3 but the limit is 2",
+//! );
+//!
+//! let limits = Limits {
+//!     max_depth: 3,
+//!     ..Limits::default()
+//! };
+//! assert!(to_bytes_limited::<_, 256, BoxedError>(&value, limits).is_ok());
+//! ```
+
+use core::{alloc::Layout, fmt, hash::Hash, ptr::NonNull};
+
+use rancor::{fail, Error};
+
+use crate::{
+    external::BlobHandle,
+    ser::{
+        Allocator, BlobSink, InternTable, Positional, Regions, Sharing, Writer,
+    },
+};
+
+/// The limits enforced by a [`Limited`] serializer.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::ser::limits::Limits;
+///
+/// let limits = Limits {
+///     max_depth: 64,
+///     ..Limits::default()
+/// };
+/// assert_eq!(limits.max_bytes, usize::MAX);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of bytes that may be written in total.
+    pub max_bytes: usize,
+    /// The maximum number of scratch space allocations that may be made in
+    /// total. Collections allocate scratch space for the resolvers of their
+    /// out-of-line elements.
+    pub max_allocations: usize,
+    /// The maximum number of derived types and collections which may be nested
+    /// inside of each other.
+    pub max_depth: usize,
+}
+
+impl Limits {
+    /// Limits which allow every value.
+    pub const UNLIMITED: Self = Self {
+        max_bytes: usize::MAX,
+        max_allocations: usize::MAX,
+        max_depth: usize::MAX,
+    };
+}
+
+impl Default for Limits {
+    #[inline]
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// A limit enforced by a [`Limited`] serializer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// The maximum number of bytes that may be written in total.
+    Bytes,
+    /// The maximum number of scratch space allocations that may be made in
+    /// total.
+    Allocations,
+    /// The maximum depth of nested values.
+    Depth,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Bytes => write!(f, "total bytes"),
+            Limit::Allocations => write!(f, "total allocations"),
+            Limit::Depth => write!(f, "depth"),
+        }
+    }
+}
+
+/// An error resulting from a value which would exceed a limit of a
+/// [`Limited`] serializer.
+#[derive(Debug)]
+pub struct SerializationLimitExceeded {
+    limit: Limit,
+    max: usize,
+    requested: usize,
+}
+
+impl SerializationLimitExceeded {
+    /// Returns the limit that would have been exceeded.
+    #[inline]
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    /// Returns the maximum value allowed by the limit.
+    #[inline]
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns the value that was requested.
+    ///
+    /// For the total limits, this is the total including the refused write
+    /// or allocation.
+    #[inline]
+    pub fn requested(&self) -> usize {
+        self.requested
+    }
+}
+
+impl fmt::Display for SerializationLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "serialization {} limit exceeded: requested {} but the limit is {}",
+            self.limit, self.requested, self.max,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SerializationLimitExceeded {}
+
+/// A serializer adapter which limits the size and depth of serialized values.
+///
+/// Writes and allocations which would exceed the [`Limits`] fail with a
+/// [`SerializationLimitExceeded`] error before they are forwarded, and so do
+/// values which would be nested too deeply. Only the bytes written and the
+/// allocations made through the adapter are counted. All other serializer
+/// capabilities are forwarded to the wrapped serializer.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     ser::{
+///         limits::{Limited, Limits},
+///         AllocSerializer,
+///     },
+///     util::serialize_into,
+/// };
+///
+/// let value = vec!["a".repeat(100), "b".repeat(100)];
+/// let limits = Limits {
+///     max_bytes: 128,
+///     ..Limits::default()
+/// };
+/// let serializer = Limited::new(AllocSerializer::<256>::default(), limits);
+/// assert!(serialize_into::<_, _, Failure>(&value, serializer).is_err());
+///
+/// let serializer = Limited::new(AllocSerializer::<256>::default(), limits);
+/// let serializer = serialize_into::<_, _, Failure>(&value[0], serializer);
+/// assert!(serializer.unwrap().written_bytes() <= 128);
+/// ```
+#[derive(Debug, Default)]
+pub struct Limited<S> {
+    /// The wrapped serializer.
+    pub serializer: S,
+    limits: Limits,
+    bytes: usize,
+    allocations: usize,
+    depth: usize,
+}
+
+impl<S> Limited<S> {
+    /// Creates a new serializer which enforces the given limits.
+    #[inline]
+    pub fn new(serializer: S, limits: Limits) -> Self {
+        Self {
+            serializer,
+            limits,
+            bytes: 0,
+            allocations: 0,
+            depth: 0,
+        }
+    }
+
+    /// Returns the limits enforced by the serializer.
+    #[inline]
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Returns the number of bytes that have been written.
+    #[inline]
+    pub fn written_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns the number of scratch space allocations that have been made.
+    #[inline]
+    pub fn allocations(&self) -> usize {
+        self.allocations
+    }
+
+    /// Returns the number of values currently being serialized inside of each
+    /// other.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Consumes the adapter and returns the wrapped serializer.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.serializer
+    }
+}
+
+impl<S: Positional> Positional for Limited<S> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.serializer.pos()
+    }
+}
+
+impl<S: Regions> Regions for Limited<S> {
+    #[inline]
+    fn is_cold(&self) -> bool {
+        self.serializer.is_cold()
+    }
+
+    #[inline]
+    fn set_cold(&mut self, is_cold: bool) {
+        self.serializer.set_cold(is_cold)
+    }
+}
+
+impl<S: Writer<E>, E: Error> Writer<E> for Limited<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        let total = self.bytes.saturating_add(bytes.len());
+        if total > self.limits.max_bytes {
+            fail!(SerializationLimitExceeded {
+                limit: Limit::Bytes,
+                max: self.limits.max_bytes,
+                requested: total,
+            });
+        }
+        self.serializer.write(bytes)?;
+        self.bytes = total;
+        Ok(())
+    }
+
+    #[inline]
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        self.serializer.find_str(value)
+    }
+
+    #[inline]
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        self.serializer.record_str(value, pos)
+    }
+
+    #[inline]
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        self.serializer.begin_collection(len)
+    }
+
+    #[inline]
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        self.serializer.end_collection(len)
+    }
+
+    fn enter_type(&mut self, type_name: &'static str) -> Result<(), E> {
+        if self.depth >= self.limits.max_depth {
+            fail!(SerializationLimitExceeded {
+                limit: Limit::Depth,
+                max: self.limits.max_depth,
+                requested: self.depth + 1,
+            });
+        }
+        self.serializer.enter_type(type_name)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn exit_type(&mut self) {
+        self.serializer.exit_type();
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+impl<S: Allocator<E>, E: Error> Allocator<E> for Limited<S> {
+    unsafe fn push_alloc(
+        &mut self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, E> {
+        let total = self.allocations.saturating_add(1);
+        if total > self.limits.max_allocations {
+            fail!(SerializationLimitExceeded {
+                limit: Limit::Allocations,
+                max: self.limits.max_allocations,
+                requested: total,
+            });
+        }
+        let result = self.serializer.push_alloc(layout)?;
+        self.allocations = total;
+        Ok(result)
+    }
+
+    #[inline]
+    unsafe fn pop_alloc(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), E> {
+        self.serializer.pop_alloc(ptr, layout)
+    }
+}
+
+impl<S: Sharing<E>, E> Sharing<E> for Limited<S> {
+    #[inline]
+    fn get_shared_ptr(&self, address: usize) -> Option<usize> {
+        self.serializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
+        self.serializer.add_shared_ptr(address, pos)
+    }
+
+    #[inline]
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        self.serializer.start_shared_ptr(address, type_name)
+    }
+}
+
+impl<S: InternTable<E>, E> InternTable<E> for Limited<S> {
+    #[inline]
+    fn get_interned<T: Hash + Eq + 'static>(&self, value: &T) -> Option<usize> {
+        self.serializer.get_interned(value)
+    }
+
+    #[inline]
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &T,
+        pos: usize,
+    ) -> Result<(), E> {
+        self.serializer.add_interned(value, pos)
+    }
+}
+
+impl<S: BlobSink<E>, E> BlobSink<E> for Limited<S> {
+    #[inline]
+    fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+        self.serializer.store_blob(bytes)
+    }
+}
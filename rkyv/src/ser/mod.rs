@@ -1,28 +1,39 @@
 //! Serialization traits and adapters.
 
 pub mod allocator;
+pub mod blob;
+#[cfg(feature = "instrument")]
+pub mod instrument;
+#[cfg(feature = "limits")]
+pub mod limits;
+pub mod progress;
 pub mod sharing;
 pub mod writer;
 
-use ::core::{alloc::Layout, ptr::NonNull};
+use ::core::{alloc::Layout, hash::Hash, ptr::NonNull};
 
 #[doc(inline)]
 pub use self::{
     allocator::Allocator,
-    sharing::{Sharing, SharingExt},
-    writer::{Positional, Writer, WriterExt},
+    blob::{BlobSerializer, BlobSink},
+    sharing::{InternTable, Sharing, SharingExt},
+    writer::{Positional, Regions, Writer, WriterExt},
 };
+use rancor::Fallible;
+
 use crate::{
     ser::{
         allocator::BufferAllocator, sharing::Duplicate, writer::BufferWriter,
     },
     util::AlignedBytes,
+    Serialize,
 };
 #[cfg(feature = "alloc")]
 use crate::{
     ser::{
         allocator::{BackupAllocator, BumpAllocator, GlobalAllocator},
         sharing::Unify,
+        writer::{DedupWriter, FallibleWriter, RegionWriter},
     },
     util::AlignedVec,
 };
@@ -72,11 +83,53 @@ impl<W: Positional, A, S> Positional for Composite<W, A, S> {
     }
 }
 
+impl<W: Regions, A, S> Regions for Composite<W, A, S> {
+    #[inline]
+    fn is_cold(&self) -> bool {
+        self.writer.is_cold()
+    }
+
+    #[inline]
+    fn set_cold(&mut self, is_cold: bool) {
+        self.writer.set_cold(is_cold)
+    }
+}
+
 impl<W: Writer<E>, A, S, E> Writer<E> for Composite<W, A, S> {
     #[inline]
     fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
         self.writer.write(bytes)
     }
+
+    #[inline]
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        self.writer.find_str(value)
+    }
+
+    #[inline]
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        self.writer.record_str(value, pos)
+    }
+
+    #[inline]
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        self.writer.begin_collection(len)
+    }
+
+    #[inline]
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        self.writer.end_collection(len)
+    }
+
+    #[inline]
+    fn enter_type(&mut self, type_name: &'static str) -> Result<(), E> {
+        self.writer.enter_type(type_name)
+    }
+
+    #[inline]
+    fn exit_type(&mut self) {
+        self.writer.exit_type()
+    }
 }
 
 impl<W, A: Allocator<E>, S, E> Allocator<E> for Composite<W, A, S> {
@@ -108,6 +161,31 @@ impl<W, A, S: Sharing<E>, E> Sharing<E> for Composite<W, A, S> {
     fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
         self.share.add_shared_ptr(address, pos)
     }
+
+    #[inline]
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        self.share.start_shared_ptr(address, type_name)
+    }
+}
+
+impl<W, A, S: InternTable<E>, E> InternTable<E> for Composite<W, A, S> {
+    #[inline]
+    fn get_interned<T: Hash + Eq + 'static>(&self, value: &T) -> Option<usize> {
+        self.share.get_interned(value)
+    }
+
+    #[inline]
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &T,
+        pos: usize,
+    ) -> Result<(), E> {
+        self.share.add_interned(value, pos)
+    }
 }
 
 /// A serializer suitable for environments where allocations cannot be made.
@@ -136,3 +214,81 @@ pub type AllocSerializer<const A: usize> = Composite<
     BackupAllocator<BumpAllocator<A>, GlobalAllocator>,
     Unify,
 >;
+
+/// A general-purpose serializer which returns an error instead of aborting
+/// when it fails to allocate memory.
+///
+/// This is the same as [`AllocSerializer`], but writes to a
+/// [`FallibleWriter`]. Allocation failures while writing or allocating scratch
+/// space are returned as [`AllocationFailed`] errors. Use
+/// [`BumpAllocator::try_new`] to construct its scratch space fallibly, or
+/// serialize with [`try_to_bytes`](crate::util::try_to_bytes).
+///
+/// [`AllocationFailed`]: crate::util::AllocationFailed
+#[cfg(feature = "alloc")]
+pub type FallibleSerializer<const A: usize> = Composite<
+    FallibleWriter,
+    BackupAllocator<BumpAllocator<A>, GlobalAllocator>,
+    Unify,
+>;
+
+/// A general-purpose serializer which writes the out-of-line data of
+/// [`Cold`](crate::with::Cold) fields after all other out-of-line data.
+///
+/// See [`RegionWriter`] for more information.
+#[cfg(feature = "alloc")]
+pub type RegionSerializer<const A: usize> = Composite<
+    RegionWriter<AlignedVec>,
+    BackupAllocator<BumpAllocator<A>, GlobalAllocator>,
+    Unify,
+>;
+
+/// A general-purpose serializer which writes strings with the same contents
+/// only once.
+///
+/// See [`DedupWriter`] for more information.
+#[cfg(feature = "alloc")]
+pub type DedupSerializer<const A: usize> = Composite<
+    DedupWriter<AlignedVec>,
+    BackupAllocator<BumpAllocator<A>, GlobalAllocator>,
+    Unify,
+>;
+
+/// A general-purpose serializer which writes to an
+/// [`AsyncWrite`](tokio::io::AsyncWrite).
+///
+/// See [`AsyncIoWriter`](writer::AsyncIoWriter) for more information.
+#[cfg(feature = "tokio")]
+pub type AsyncIoSerializer<W, const A: usize> = Composite<
+    writer::AsyncIoWriter<W>,
+    BackupAllocator<BumpAllocator<A>, GlobalAllocator>,
+    Unify,
+>;
+
+/// A value which can be serialized a few elements at a time.
+///
+/// Serializing large collections can take a long time. Async serialization
+/// drivers like [`to_writer_async`](crate::util::to_writer_async) serialize
+/// values in chunks of elements so that they can flush their output and yield
+/// between chunks instead of blocking.
+///
+/// Chunks only split the top-level elements of a value. Each element is still
+/// serialized all at once.
+pub trait SerializeChunked<S: Fallible + ?Sized>: Serialize<S> {
+    /// The state kept between chunks.
+    type State: Default;
+
+    /// Serializes the next chunk of up to `count` elements.
+    ///
+    /// `state` must be the default state for the first chunk, and must be
+    /// passed unmodified between chunks. Returns the resolver for the value
+    /// once all of its elements have been serialized, and `None` if more chunks
+    /// remain. The output must be the same as serializing the value all at
+    /// once.
+    fn serialize_chunk(
+        &self,
+        state: &mut Self::State,
+        count: usize,
+        serializer: &mut S,
+    ) -> Result<Option<Self::Resolver>, S::Error>;
+}
@@ -0,0 +1,369 @@
+//! Progress reporting and cancellation for long-running serialization.
+
+use core::{alloc::Layout, fmt, hash::Hash, ops::ControlFlow, ptr::NonNull};
+
+use rancor::{fail, Error};
+
+use crate::{
+    external::BlobHandle,
+    ser::{
+        Allocator, BlobSink, InternTable, Positional, Regions, Sharing, Writer,
+    },
+};
+
+/// An event reported by a [`Progress`] serializer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// Another milestone of bytes was written. `total` is the total number of
+    /// bytes written so far, which is always a multiple of the milestone
+    /// interval.
+    BytesWritten {
+        /// The total number of bytes written.
+        total: usize,
+    },
+    /// Started serializing the elements of a collection.
+    BeginCollection {
+        /// The number of elements in the collection.
+        len: usize,
+    },
+    /// Finished serializing the elements of a collection.
+    EndCollection {
+        /// The number of elements in the collection.
+        len: usize,
+    },
+}
+
+/// The options for a [`Progress`] serializer.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::ser::progress::ProgressConfig;
+///
+/// let config = ProgressConfig {
+///     milestone_bytes: 64 * 1024,
+///     ..ProgressConfig::default()
+/// };
+/// assert_eq!(config.min_collection_len, 1024);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgressConfig {
+    /// The number of bytes between [`BytesWritten`] events. Zero disables
+    /// them.
+    ///
+    /// Defaults to 1 MiB.
+    ///
+    /// [`BytesWritten`]: ProgressEvent::BytesWritten
+    pub milestone_bytes: usize,
+    /// The minimum length of collections that report [`BeginCollection`] and
+    /// [`EndCollection`] events.
+    ///
+    /// Defaults to 1024.
+    ///
+    /// [`BeginCollection`]: ProgressEvent::BeginCollection
+    /// [`EndCollection`]: ProgressEvent::EndCollection
+    pub min_collection_len: usize,
+}
+
+impl Default for ProgressConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            milestone_bytes: 1 << 20,
+            min_collection_len: 1024,
+        }
+    }
+}
+
+/// An error resulting from a [`Progress`] callback cancelling serialization.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "serialization was cancelled")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Cancelled {}
+
+/// A serializer adapter which reports progress to a callback.
+///
+/// The callback is called with a [`ProgressEvent`] whenever another milestone
+/// of bytes is written and whenever a large collection begins or ends. If the
+/// callback returns [`ControlFlow::Break`], the operation that triggered the
+/// event fails with a [`Cancelled`] error, and so does every operation after
+/// it. The partially-written output should be discarded.
+///
+/// All other serializer capabilities are forwarded to the wrapped serializer.
+///
+/// # Example
+///
+/// ```
+/// use core::ops::ControlFlow;
+///
+/// use rkyv::{
+///     rancor::Failure,
+///     ser::{
+///         progress::{Progress, ProgressConfig, ProgressEvent},
+///         AllocSerializer,
+///     },
+///     util::serialize_into,
+/// };
+///
+/// let value = vec![vec![0u32; 1000]; 10];
+///
+/// let mut events = Vec::new();
+/// let config = ProgressConfig {
+///     milestone_bytes: 4096,
+///     min_collection_len: 10,
+/// };
+/// let serializer = Progress::new(
+///     AllocSerializer::<256>::default(),
+///     config,
+///     |event| {
+///         events.push(event);
+///         ControlFlow::Continue(())
+///     },
+/// );
+/// let serializer =
+///     serialize_into::<_, _, Failure>(&value, serializer).unwrap();
+/// assert_eq!(serializer.bytes_written(), 40_088);
+/// drop(serializer);
+///
+/// assert_eq!(events[0], ProgressEvent::BeginCollection { len: 10 });
+/// let last = events[events.len() - 1];
+/// assert_eq!(last, ProgressEvent::EndCollection { len: 10 });
+/// let milestones = events
+///     .iter()
+///     .filter(|e| matches!(e, ProgressEvent::BytesWritten { .. }))
+///     .count();
+/// assert_eq!(milestones, 9);
+/// ```
+#[derive(Debug)]
+pub struct Progress<S, F> {
+    /// The wrapped serializer.
+    pub serializer: S,
+    callback: F,
+    config: ProgressConfig,
+    written: usize,
+    next_milestone: usize,
+    cancelled: bool,
+}
+
+impl<S, F> Progress<S, F> {
+    /// Creates a new serializer which reports progress to the given callback.
+    #[inline]
+    pub fn new(serializer: S, config: ProgressConfig, callback: F) -> Self {
+        Self {
+            serializer,
+            callback,
+            config,
+            written: 0,
+            next_milestone: config.milestone_bytes,
+            cancelled: false,
+        }
+    }
+
+    /// Returns the options of the serializer.
+    #[inline]
+    pub fn config(&self) -> &ProgressConfig {
+        &self.config
+    }
+
+    /// Returns the number of bytes that have been written.
+    #[inline]
+    pub fn bytes_written(&self) -> usize {
+        self.written
+    }
+
+    /// Returns whether the callback has cancelled serialization.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Consumes the adapter and returns the wrapped serializer.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.serializer
+    }
+}
+
+impl<S, F> Progress<S, F>
+where
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+{
+    fn check_cancelled<E: Error>(&self) -> Result<(), E> {
+        if self.cancelled {
+            fail!(Cancelled);
+        }
+        Ok(())
+    }
+
+    fn report<E: Error>(&mut self, event: ProgressEvent) -> Result<(), E> {
+        self.check_cancelled()?;
+        if (self.callback)(event).is_break() {
+            self.cancelled = true;
+            fail!(Cancelled);
+        }
+        Ok(())
+    }
+}
+
+impl<S: Positional, F> Positional for Progress<S, F> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.serializer.pos()
+    }
+}
+
+impl<S: Regions, F> Regions for Progress<S, F> {
+    #[inline]
+    fn is_cold(&self) -> bool {
+        self.serializer.is_cold()
+    }
+
+    #[inline]
+    fn set_cold(&mut self, is_cold: bool) {
+        self.serializer.set_cold(is_cold)
+    }
+}
+
+impl<S, F, E> Writer<E> for Progress<S, F>
+where
+    S: Writer<E>,
+    F: FnMut(ProgressEvent) -> ControlFlow<()>,
+    E: Error,
+{
+    fn write(&mut self, mut bytes: &[u8]) -> Result<(), E> {
+        self.check_cancelled()?;
+
+        let interval = self.config.milestone_bytes;
+        if interval == 0 {
+            self.serializer.write(bytes)?;
+            self.written += bytes.len();
+            return Ok(());
+        }
+
+        // Split writes at milestones so that each milestone is reported
+        // exactly when it is reached
+        while !bytes.is_empty() {
+            let len =
+                usize::min(bytes.len(), self.next_milestone - self.written);
+            let (head, tail) = bytes.split_at(len);
+            self.serializer.write(head)?;
+            self.written += len;
+            bytes = tail;
+
+            if self.written == self.next_milestone {
+                self.next_milestone =
+                    self.next_milestone.saturating_add(interval);
+                self.report(ProgressEvent::BytesWritten {
+                    total: self.written,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        self.serializer.find_str(value)
+    }
+
+    #[inline]
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        self.serializer.record_str(value, pos)
+    }
+
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        self.check_cancelled()?;
+        if len >= self.config.min_collection_len {
+            self.report(ProgressEvent::BeginCollection { len })?;
+        }
+        Ok(())
+    }
+
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        self.check_cancelled()?;
+        if len >= self.config.min_collection_len {
+            self.report(ProgressEvent::EndCollection { len })?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn enter_type(&mut self, type_name: &'static str) -> Result<(), E> {
+        self.serializer.enter_type(type_name)
+    }
+
+    #[inline]
+    fn exit_type(&mut self) {
+        self.serializer.exit_type()
+    }
+}
+
+impl<S: Allocator<E>, F, E> Allocator<E> for Progress<S, F> {
+    #[inline]
+    unsafe fn push_alloc(
+        &mut self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, E> {
+        self.serializer.push_alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn pop_alloc(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), E> {
+        self.serializer.pop_alloc(ptr, layout)
+    }
+}
+
+impl<S: Sharing<E>, F, E> Sharing<E> for Progress<S, F> {
+    #[inline]
+    fn get_shared_ptr(&self, address: usize) -> Option<usize> {
+        self.serializer.get_shared_ptr(address)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
+        self.serializer.add_shared_ptr(address, pos)
+    }
+
+    #[inline]
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        self.serializer.start_shared_ptr(address, type_name)
+    }
+}
+
+impl<S: InternTable<E>, F, E> InternTable<E> for Progress<S, F> {
+    #[inline]
+    fn get_interned<T: Hash + Eq + 'static>(&self, value: &T) -> Option<usize> {
+        self.serializer.get_interned(value)
+    }
+
+    #[inline]
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &T,
+        pos: usize,
+    ) -> Result<(), E> {
+        self.serializer.add_interned(value, pos)
+    }
+}
+
+impl<S: BlobSink<E>, F, E> BlobSink<E> for Progress<S, F> {
+    #[inline]
+    fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+        self.serializer.store_blob(bytes)
+    }
+}
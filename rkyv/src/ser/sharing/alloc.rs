@@ -1,4 +1,11 @@
-use core::{fmt, mem::size_of};
+#[cfg(not(feature = "std"))]
+use ::alloc::boxed::Box;
+use core::{
+    any::{Any, TypeId},
+    fmt,
+    hash::{Hash, Hasher},
+    mem::size_of,
+};
 #[cfg(feature = "std")]
 use std::collections::hash_map;
 
@@ -6,7 +13,7 @@ use std::collections::hash_map;
 use hashbrown::hash_map;
 use rancor::{fail, Error};
 
-use crate::ser::Sharing;
+use crate::ser::{sharing::CyclicSharedPointer, InternTable, Sharing};
 
 #[derive(Debug)]
 struct DuplicateSharedPointer {
@@ -27,11 +34,63 @@ impl fmt::Display for DuplicateSharedPointer {
 #[cfg(feature = "std")]
 impl std::error::Error for DuplicateSharedPointer {}
 
+/// A type-erased interned value.
+trait InternKey: Any {
+    fn hash_key(&self, state: &mut dyn Hasher);
+
+    fn eq_key(&self, other: &dyn InternKey) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Hash + Eq + Any> InternKey for T {
+    fn hash_key(&self, mut state: &mut dyn Hasher) {
+        TypeId::of::<T>().hash(&mut state);
+        self.hash(&mut state);
+    }
+
+    fn eq_key(&self, other: &dyn InternKey) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Hash for dyn InternKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash_key(state);
+    }
+}
+
+impl PartialEq for dyn InternKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_key(other)
+    }
+}
+
+impl Eq for dyn InternKey {}
+
+impl fmt::Debug for dyn InternKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InternKey")
+    }
+}
+
 /// A shared pointer strategy that unifies serializations of the same shared
 /// pointer.
+///
+/// It also interns values serialized with [`Intern`](crate::with::Intern) by
+/// keeping a copy of each distinct value.
+///
+/// Shared pointers which are reached again while they are being serialized
+/// fail with a [`CyclicSharedPointer`] error instead of recursing forever.
 #[derive(Debug, Default)]
 pub struct Unify {
-    shared_address_to_pos: hash_map::HashMap<usize, usize>,
+    // Shared pointers which are still being serialized map to `None`.
+    shared_address_to_pos: hash_map::HashMap<usize, Option<usize>>,
+    interned_to_pos: hash_map::HashMap<Box<dyn InternKey>, usize>,
 }
 
 impl Unify {
@@ -40,6 +99,7 @@ impl Unify {
     pub fn new() -> Self {
         Self {
             shared_address_to_pos: hash_map::HashMap::new(),
+            interned_to_pos: hash_map::HashMap::new(),
         }
     }
 
@@ -48,24 +108,63 @@ impl Unify {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             shared_address_to_pos: hash_map::HashMap::with_capacity(capacity),
+            interned_to_pos: hash_map::HashMap::new(),
         }
     }
 }
 
 impl<E: Error> Sharing<E> for Unify {
     fn get_shared_ptr(&self, address: usize) -> Option<usize> {
-        self.shared_address_to_pos.get(&address).copied()
+        self.shared_address_to_pos.get(&address).copied().flatten()
     }
 
     fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
         match self.shared_address_to_pos.entry(address) {
-            hash_map::Entry::Occupied(_) => {
-                fail!(DuplicateSharedPointer { address });
+            hash_map::Entry::Occupied(mut e) => {
+                if e.get().is_some() {
+                    fail!(DuplicateSharedPointer { address });
+                }
+                e.insert(Some(pos));
+                Ok(())
             }
             hash_map::Entry::Vacant(e) => {
-                e.insert(pos);
+                e.insert(Some(pos));
                 Ok(())
             }
         }
     }
+
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        match self.shared_address_to_pos.entry(address) {
+            hash_map::Entry::Occupied(e) => match *e.get() {
+                Some(pos) => Ok(Some(pos)),
+                None => fail!(CyclicSharedPointer::new(type_name, address)),
+            },
+            hash_map::Entry::Vacant(e) => {
+                e.insert(None);
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<E> InternTable<E> for Unify {
+    fn get_interned<T: Hash + Eq + 'static>(&self, value: &T) -> Option<usize> {
+        self.interned_to_pos.get(value as &dyn InternKey).copied()
+    }
+
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &T,
+        pos: usize,
+    ) -> Result<(), E> {
+        self.interned_to_pos
+            .entry(Box::new(value.clone()))
+            .or_insert(pos);
+        Ok(())
+    }
 }
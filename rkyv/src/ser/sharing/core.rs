@@ -1,16 +1,81 @@
-use crate::ser::Sharing;
-
-/// A shared pointer strategy that duplicates serializations of the same shared
-/// pointer.
-#[derive(Debug, Default)]
-pub struct Duplicate;
-
-impl<E> Sharing<E> for Duplicate {
-    fn get_shared_ptr(&self, _: usize) -> Option<usize> {
-        None
-    }
-
-    fn add_shared_ptr(&mut self, _: usize, _: usize) -> Result<(), E> {
-        Ok(())
-    }
-}
+use core::hash::Hash;
+
+use crate::ser::{InternTable, Sharing};
+
+/// A shared pointer strategy that duplicates serializations of the same shared
+/// pointer.
+///
+/// Because it doesn't keep track of which shared pointers are being serialized,
+/// it can't detect cycles of shared pointers. Serializing a cycle with it will
+/// recurse until the stack overflows.
+#[derive(Debug, Default)]
+pub struct Duplicate;
+
+impl<E> Sharing<E> for Duplicate {
+    fn get_shared_ptr(&self, _: usize) -> Option<usize> {
+        None
+    }
+
+    fn add_shared_ptr(&mut self, _: usize, _: usize) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+impl<E> InternTable<E> for Duplicate {
+    fn get_interned<T: Hash + Eq + 'static>(&self, _: &T) -> Option<usize> {
+        None
+    }
+
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        _: &T,
+        _: usize,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Shares pointers with the inner strategy if it is `Some`, and otherwise
+/// duplicates them like [`Duplicate`].
+impl<T: Sharing<E>, E> Sharing<E> for Option<T> {
+    fn get_shared_ptr(&self, address: usize) -> Option<usize> {
+        self.as_ref()?.get_shared_ptr(address)
+    }
+
+    fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
+        match self {
+            Some(inner) => inner.add_shared_ptr(address, pos),
+            None => Ok(()),
+        }
+    }
+
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        match self {
+            Some(inner) => inner.start_shared_ptr(address, type_name),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Interns values with the inner strategy if it is `Some`, and otherwise
+/// duplicates them like [`Duplicate`].
+impl<T: InternTable<E>, E> InternTable<E> for Option<T> {
+    fn get_interned<U: Hash + Eq + 'static>(&self, value: &U) -> Option<usize> {
+        self.as_ref()?.get_interned(value)
+    }
+
+    fn add_interned<U: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &U,
+        pos: usize,
+    ) -> Result<(), E> {
+        match self {
+            Some(inner) => inner.add_interned(value, pos),
+            None => Ok(()),
+        }
+    }
+}
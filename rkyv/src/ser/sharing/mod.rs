@@ -1,9 +1,11 @@
-//! Shared pointer serialization.
+//! Shared pointer and interned value serialization.
 
 #[cfg(feature = "alloc")]
 mod alloc;
 mod core;
 
+use ::core::{fmt, hash::Hash, mem::size_of};
+
 use rancor::{Fallible, Strategy};
 
 #[cfg(feature = "alloc")]
@@ -22,6 +24,26 @@ pub trait Sharing<E = <Self as Fallible>::Error> {
 
     /// Adds the serialized position of a shared pointer.
     fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E>;
+
+    /// Starts serializing a shared pointer.
+    ///
+    /// Returns the position of the shared pointer if it has already been added.
+    /// Otherwise, the pointer is in progress until it is added with
+    /// [`add_shared_ptr`](Sharing::add_shared_ptr). Strategies which track
+    /// pointers that are in progress should return a [`CyclicSharedPointer`]
+    /// error when one of them is started again, since the pointer must then be
+    /// part of a cycle.
+    ///
+    /// The default implementation returns the result of
+    /// [`get_shared_ptr`](Sharing::get_shared_ptr) and does not detect cycles.
+    #[inline]
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        _type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        Ok(self.get_shared_ptr(address))
+    }
 }
 
 impl<T, E> Sharing<E> for Strategy<T, E>
@@ -35,8 +57,65 @@ where
     fn add_shared_ptr(&mut self, address: usize, pos: usize) -> Result<(), E> {
         T::add_shared_ptr(self, address, pos)
     }
+
+    fn start_shared_ptr(
+        &mut self,
+        address: usize,
+        type_name: &'static str,
+    ) -> Result<Option<usize>, E> {
+        T::start_shared_ptr(self, address, type_name)
+    }
+}
+
+/// An error indicating that a shared pointer was reached again while it was
+/// being serialized.
+///
+/// Shared pointers which form a cycle can't be serialized, because each pointer
+/// in the cycle would have to be serialized before all of the others. Cycles
+/// can be broken by replacing one of the pointers with a weak pointer which
+/// won't be upgraded while serializing.
+#[derive(Debug)]
+pub struct CyclicSharedPointer {
+    type_name: &'static str,
+    address: usize,
+}
+
+impl CyclicSharedPointer {
+    /// Returns a new error for the shared pointer of the given type at the
+    /// given address.
+    #[inline]
+    pub fn new(type_name: &'static str, address: usize) -> Self {
+        Self { type_name, address }
+    }
+
+    /// Returns the name of the type pointed to by the shared pointer.
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the address of the shared pointer.
+    #[inline]
+    pub fn address(&self) -> usize {
+        self.address
+    }
 }
 
+impl fmt::Display for CyclicSharedPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cyclic shared pointer to `{}`: {:#.*x}",
+            self.type_name,
+            size_of::<usize>() * 2,
+            self.address
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CyclicSharedPointer {}
+
 /// TODO: Document this
 pub trait SharingExt<E>: Sharing<E> {
     /// Gets the position of a previously-added shared value.
@@ -44,7 +123,7 @@ pub trait SharingExt<E>: Sharing<E> {
     /// Returns `None` if the value has not yet been added.
     #[inline]
     fn get_shared<T: ?Sized>(&self, value: &T) -> Option<usize> {
-        self.get_shared_ptr(value as *const T as *const () as usize)
+        self.get_shared_ptr((value as *const T).cast::<()>().addr())
     }
 
     /// Adds the position of a shared value to the registry.
@@ -54,12 +133,16 @@ pub trait SharingExt<E>: Sharing<E> {
         value: &T,
         pos: usize,
     ) -> Result<(), E> {
-        self.add_shared_ptr(value as *const T as *const () as usize, pos)
+        self.add_shared_ptr((value as *const T).cast::<()>().addr(), pos)
     }
 
     /// Archives the given shared value and returns its position. If the value
     /// has already been added then it returns the position of the
     /// previously added value.
+    ///
+    /// If the value is reached again while it is being archived, then this
+    /// returns an error from [`start_shared_ptr`](Sharing::start_shared_ptr)
+    /// when the sharing strategy detects cycles.
     #[inline]
     fn serialize_shared<T: SerializeUnsized<Self> + ?Sized>(
         &mut self,
@@ -68,7 +151,9 @@ pub trait SharingExt<E>: Sharing<E> {
     where
         Self: Fallible<Error = E>,
     {
-        if let Some(pos) = self.get_shared(value) {
+        let address = (value as *const T).cast::<()>().addr();
+        let type_name = ::core::any::type_name::<T>();
+        if let Some(pos) = self.start_shared_ptr(address, type_name)? {
             Ok(pos)
         } else {
             let pos = value.serialize_unsized(self)?;
@@ -79,3 +164,40 @@ pub trait SharingExt<E>: Sharing<E> {
 }
 
 impl<S, E> SharingExt<E> for S where S: Sharing<E> + ?Sized {}
+
+/// A serializer that can intern identical values.
+///
+/// This trait is required to serialize values with
+/// [`Intern`](crate::with::Intern). Interning serializers keep a copy of each
+/// interned value and the position it was serialized at, so that later values
+/// which are equal to it can point to the same position.
+pub trait InternTable<E = <Self as Fallible>::Error> {
+    /// Gets the position of a serialized value which is equal to `value`.
+    ///
+    /// Returns `None` if no equal value has been added yet.
+    fn get_interned<T: Hash + Eq + 'static>(&self, value: &T) -> Option<usize>;
+
+    /// Adds the serialized position of an interned value.
+    fn add_interned<T: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &T,
+        pos: usize,
+    ) -> Result<(), E>;
+}
+
+impl<T, E> InternTable<E> for Strategy<T, E>
+where
+    T: InternTable<E> + ?Sized,
+{
+    fn get_interned<U: Hash + Eq + 'static>(&self, value: &U) -> Option<usize> {
+        T::get_interned(self, value)
+    }
+
+    fn add_interned<U: Clone + Hash + Eq + 'static>(
+        &mut self,
+        value: &U,
+        pos: usize,
+    ) -> Result<(), E> {
+        T::add_interned(self, value, pos)
+    }
+}
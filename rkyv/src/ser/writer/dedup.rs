@@ -0,0 +1,164 @@
+#[cfg(not(feature = "std"))]
+use ::alloc::boxed::Box;
+#[cfg(feature = "std")]
+use ::std::collections::hash_map;
+
+#[cfg(not(feature = "std"))]
+use hashbrown::hash_map;
+
+use crate::ser::{Positional, Writer};
+
+/// Which values a [`DedupWriter`] deduplicates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dedup {
+    /// Every value is written out, even if an identical value was already
+    /// written.
+    #[default]
+    None,
+    /// Strings with the same contents are written once and shared.
+    Strings,
+}
+
+/// Statistics about the strings deduplicated by a [`DedupWriter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DedupStats {
+    /// The number of distinct strings which were written.
+    pub unique: usize,
+    /// The number of strings which pointed to a previously written string
+    /// instead of being written again.
+    pub hits: usize,
+    /// The total number of bytes which were not written because of hits.
+    pub bytes_saved: usize,
+}
+
+/// A writer which writes strings with the same contents only once.
+///
+/// With [`Dedup::Strings`], every string which is serialized out of line is
+/// looked up by its contents before it is written. If an identical string was
+/// already written, the archived string points to the earlier bytes instead.
+/// This applies to every string in the archive regardless of whether its field
+/// uses a wrapper like [`Intern`](crate::with::Intern). Strings which are
+/// short enough to be stored inline in an
+/// [`ArchivedString`](crate::string::ArchivedString) are never written out of
+/// line, and so are never deduplicated.
+///
+/// Archives written with deduplication contain aliased strings. They must be
+/// validated with
+/// [`access_interned`](crate::validation::util::access_interned) or a
+/// validator with aliasing enabled, and must not be accessed mutably. Strings
+/// written while a [`RegionWriter`](crate::ser::writer::RegionWriter) has its
+/// cold region active are not deduplicated.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     ser::{
+///         writer::{Dedup, DedupWriter},
+///         DedupSerializer,
+///     },
+///     util::{serialize_into, AlignedVec},
+/// };
+///
+/// let value = vec!["a string long enough to be out of line".to_string(); 4];
+/// let serializer = DedupSerializer::<1024>::new(
+///     DedupWriter::new(AlignedVec::new(), Dedup::Strings),
+///     Default::default(),
+///     Default::default(),
+/// );
+/// let writer = serialize_into::<_, _, Failure>(&value, serializer)
+///     .unwrap()
+///     .into_writer();
+///
+/// assert_eq!(writer.stats().unique, 1);
+/// assert_eq!(writer.stats().hits, 3);
+/// ```
+#[derive(Debug)]
+pub struct DedupWriter<W> {
+    inner: W,
+    mode: Dedup,
+    strings: hash_map::HashMap<Box<str>, usize>,
+    stats: DedupStats,
+}
+
+impl<W> DedupWriter<W> {
+    /// Returns a new writer which writes to `inner` and deduplicates the
+    /// values selected by `mode`.
+    #[inline]
+    pub fn new(inner: W, mode: Dedup) -> Self {
+        Self {
+            inner,
+            mode,
+            strings: hash_map::HashMap::new(),
+            stats: DedupStats::default(),
+        }
+    }
+
+    /// Returns which values are deduplicated.
+    #[inline]
+    pub fn mode(&self) -> Dedup {
+        self.mode
+    }
+
+    /// Returns the statistics for the values written so far.
+    #[inline]
+    pub fn stats(&self) -> DedupStats {
+        self.stats
+    }
+
+    /// Returns a reference to the underlying writer.
+    #[inline]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    /// Consumes the dedup writer and returns the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Positional> Positional for DedupWriter<W> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+}
+
+impl<W: Writer<E>, E> Writer<E> for DedupWriter<W> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.inner.write(bytes)
+    }
+
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        if self.mode != Dedup::Strings || value.is_empty() {
+            return None;
+        }
+        // Keys are compared by their bytes, so a hash collision never aliases
+        // two different strings.
+        let pos = *self.strings.get(value)?;
+        self.stats.hits += 1;
+        self.stats.bytes_saved += value.len();
+        Some(pos)
+    }
+
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        if self.mode == Dedup::Strings && !value.is_empty() {
+            self.strings.insert(value.into(), pos);
+            self.stats.unique += 1;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        self.inner.begin_collection(len)
+    }
+
+    #[inline]
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        self.inner.end_collection(len)
+    }
+}
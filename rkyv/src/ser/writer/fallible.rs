@@ -0,0 +1,90 @@
+use core::ops::Deref;
+
+use rancor::{Error, ResultExt as _};
+
+use crate::{
+    ser::{Positional, Writer},
+    util::{AlignedVec, AllocationFailed},
+};
+
+/// A writer which grows an [`AlignedVec`] with fallible allocation.
+///
+/// Writing to an `AlignedVec` aborts if its memory can't be grown. This writer
+/// reserves memory with [`AlignedVec::try_reserve`] instead, and returns an
+/// [`AllocationFailed`] error if the allocator fails. The bytes which were
+/// written before the error remain valid, but the output is incomplete and
+/// should be discarded.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     ser::{writer::FallibleWriter, Writer},
+/// };
+///
+/// let mut writer = FallibleWriter::new();
+/// Writer::<Failure>::write(&mut writer, &[1, 2, 3, 4]).unwrap();
+/// assert_eq!(writer.into_inner().as_slice(), &[1, 2, 3, 4]);
+/// ```
+#[derive(Debug, Default)]
+pub struct FallibleWriter {
+    inner: AlignedVec,
+}
+
+impl FallibleWriter {
+    /// Creates a new, empty writer.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty writer with the given capacity, returning an
+    /// error if the memory could not be allocated.
+    #[inline]
+    pub fn try_with_capacity(
+        capacity: usize,
+    ) -> Result<Self, AllocationFailed> {
+        Ok(Self {
+            inner: AlignedVec::try_with_capacity(capacity)?,
+        })
+    }
+
+    /// Consumes the writer and returns the written bytes.
+    #[inline]
+    pub fn into_inner(self) -> AlignedVec {
+        self.inner
+    }
+}
+
+impl From<AlignedVec> for FallibleWriter {
+    #[inline]
+    fn from(inner: AlignedVec) -> Self {
+        Self { inner }
+    }
+}
+
+impl Deref for FallibleWriter {
+    type Target = AlignedVec;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Positional for FallibleWriter {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<E: Error> Writer<E> for FallibleWriter {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.inner.try_reserve(bytes.len()).into_error()?;
+        self.inner.extend_from_slice(bytes);
+        Ok(())
+    }
+}
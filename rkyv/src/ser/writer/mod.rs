@@ -3,8 +3,16 @@
 #[cfg(feature = "alloc")]
 mod alloc;
 mod core;
+#[cfg(feature = "alloc")]
+mod dedup;
+#[cfg(feature = "alloc")]
+mod fallible;
+#[cfg(feature = "alloc")]
+mod regions;
 #[cfg(feature = "std")]
 mod std;
+#[cfg(feature = "tokio")]
+mod tokio;
 
 use ::core::{mem, slice};
 use rancor::{Fallible, Strategy};
@@ -12,6 +20,10 @@ use rancor::{Fallible, Strategy};
 pub use self::core::*;
 #[cfg(feature = "std")]
 pub use self::std::*;
+#[cfg(feature = "tokio")]
+pub use self::tokio::*;
+#[cfg(feature = "alloc")]
+pub use self::{dedup::*, fallible::*, regions::*};
 use crate::{Archive, ArchiveUnsized, RelPtr};
 
 /// A writer that knows its current position.
@@ -41,6 +53,76 @@ where
 pub trait Writer<E = <Self as Fallible>::Error>: Positional {
     /// Attempts to write the given bytes to the serializer.
     fn write(&mut self, bytes: &[u8]) -> Result<(), E>;
+
+    /// Returns the position of a previously written string with the same
+    /// contents as `value`, if the writer deduplicates strings.
+    ///
+    /// Strings serialized out of line call this before writing their bytes,
+    /// and use the returned position instead of writing them again. The
+    /// default implementation never finds a string.
+    #[inline]
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        let _ = value;
+        None
+    }
+
+    /// Records that the bytes of `value` were written at `pos`, so that later
+    /// strings with the same contents may be found with
+    /// [`find_str`](Writer::find_str).
+    ///
+    /// The default implementation does nothing.
+    #[inline]
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        let _ = (value, pos);
+        Ok(())
+    }
+
+    /// Called before the elements of a collection of `len` elements are
+    /// serialized.
+    ///
+    /// Collections like `Vec` and the map types call this before serializing
+    /// their elements and [`end_collection`](Writer::end_collection)
+    /// afterward. Serializers like [`Progress`](crate::ser::progress::Progress)
+    /// use these hooks to report progress, and may fail them to cancel
+    /// serialization. The default implementation does nothing.
+    #[inline]
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        let _ = len;
+        Ok(())
+    }
+
+    /// Called after the elements of a collection of `len` elements have been
+    /// serialized.
+    ///
+    /// The default implementation does nothing.
+    #[inline]
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        let _ = len;
+        Ok(())
+    }
+
+    /// Called before a value is serialized with the
+    /// [`type_name`](core::any::type_name) of its type.
+    ///
+    /// With the `hooks` feature, derived `Serialize` implementations and the
+    /// collection implementations call this and
+    /// [`exit_type`](Writer::exit_type) around serializing each value.
+    /// Serializers like `Profiling` and `Limited` use these hooks to attribute
+    /// bytes and time to types and to limit the depth of values, and may fail
+    /// `enter_type` to stop serializing. If this fails, `exit_type` is not
+    /// called for the value. The default implementation does nothing.
+    #[inline]
+    fn enter_type(&mut self, type_name: &'static str) -> Result<(), E> {
+        let _ = type_name;
+        Ok(())
+    }
+
+    /// Called after the value of the most recently entered type has been
+    /// serialized.
+    ///
+    /// The default implementation does nothing.
+    #[inline]
+    fn exit_type(&mut self) {}
 }
 
 impl<T, E> Writer<E> for Strategy<T, E>
@@ -50,21 +132,83 @@ where
     fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
         T::write(self, bytes)
     }
+
+    fn find_str(&mut self, value: &str) -> Option<usize> {
+        T::find_str(self, value)
+    }
+
+    fn record_str(&mut self, value: &str, pos: usize) -> Result<(), E> {
+        T::record_str(self, value, pos)
+    }
+
+    fn begin_collection(&mut self, len: usize) -> Result<(), E> {
+        T::begin_collection(self, len)
+    }
+
+    fn end_collection(&mut self, len: usize) -> Result<(), E> {
+        T::end_collection(self, len)
+    }
+
+    fn enter_type(&mut self, type_name: &'static str) -> Result<(), E> {
+        T::enter_type(self, type_name)
+    }
+
+    fn exit_type(&mut self) {
+        T::exit_type(self)
+    }
 }
 
-/// TODO: Document
+/// A writer which can place out-of-line data into a separate cold region.
+///
+/// Data written while the cold region is active is placed after all other
+/// out-of-line data in the archive. This is used by the
+/// [`Cold`](crate::with::Cold) wrapper.
+pub trait Regions {
+    /// Returns whether bytes are currently being written to the cold region.
+    fn is_cold(&self) -> bool;
+
+    /// Sets whether bytes should be written to the cold region.
+    fn set_cold(&mut self, is_cold: bool);
+}
+
+impl<T, E> Regions for Strategy<T, E>
+where
+    T: Regions + ?Sized,
+{
+    fn is_cold(&self) -> bool {
+        T::is_cold(self)
+    }
+
+    fn set_cold(&mut self, is_cold: bool) {
+        T::set_cold(self, is_cold)
+    }
+}
+
+/// Helper methods for [`Writer`].
+///
+/// Padding is always written as zero bytes, so serializing the same value
+/// always produces the same bytes regardless of the prior contents of the
+/// output buffer.
 pub trait WriterExt<E>: Writer<E> {
-    /// Advances the given number of bytes as padding.
+    /// Writes the given number of zero bytes as padding.
     #[inline]
-    fn pad(&mut self, padding: usize) -> Result<(), E> {
+    fn pad(&mut self, mut padding: usize) -> Result<(), E> {
         const MAX_ZEROES: usize = 32;
         const ZEROES: [u8; MAX_ZEROES] = [0; MAX_ZEROES];
-        debug_assert!(padding < MAX_ZEROES);
 
-        self.write(&ZEROES[0..padding])
+        while padding > 0 {
+            let len = usize::min(padding, MAX_ZEROES);
+            self.write(&ZEROES[0..len])?;
+            padding -= len;
+        }
+        Ok(())
     }
 
-    /// Aligns the position of the serializer to the given alignment.
+    /// Aligns the position of the serializer to the given alignment by writing
+    /// zero bytes as padding.
+    ///
+    /// Returns the aligned position. `align` must be a power of two.
+    #[doc(alias = "align_to")]
     #[inline]
     fn align(&mut self, align: usize) -> Result<usize, E> {
         let mask = align - 1;
@@ -0,0 +1,163 @@
+use core::{fmt, ops::Range};
+
+use rancor::{fail, Error};
+
+use crate::{
+    ser::{Positional, Regions, Writer, WriterExt as _},
+    util::AlignedVec,
+};
+
+#[derive(Debug)]
+struct ColdRegionOverlap {
+    hot_len: usize,
+    cold_base: usize,
+}
+
+impl fmt::Display for ColdRegionOverlap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hot region overran cold region: hot region is {} bytes but cold \
+             region starts at pos {}",
+            self.hot_len, self.cold_base,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+const _: () = {
+    use std::error::Error;
+
+    impl Error for ColdRegionOverlap {}
+};
+
+/// Wraps a writer and equips it with a cold region.
+///
+/// Bytes written while the cold region is active are buffered separately and
+/// are reported as starting at `cold_base`. Calling [`splice`] writes them to
+/// the underlying writer, which must be positioned at or before `cold_base` at
+/// that time. Because the positions of cold data must be known while it is
+/// written, `cold_base` is usually found by serializing once to measure the hot
+/// region and then serializing again. [`to_bytes_with_regions`] does this
+/// automatically.
+///
+/// `cold_base` must be a multiple of [`AlignedVec::ALIGNMENT`] so that cold
+/// data is aligned the same way after splicing. It must also be strictly
+/// greater than the length of the hot region so that the end of the hot region
+/// can be distinguished from the start of the cold region.
+///
+/// [`splice`]: RegionWriter::splice
+/// [`to_bytes_with_regions`]: crate::util::to_bytes_with_regions
+#[derive(Debug)]
+pub struct RegionWriter<W> {
+    hot: W,
+    cold: AlignedVec,
+    cold_base: usize,
+    is_cold: bool,
+}
+
+impl<W> RegionWriter<W> {
+    /// Creates a new region writer from a writer and the position that the
+    /// cold region will start at.
+    #[inline]
+    pub fn new(inner: W, cold_base: usize) -> Self {
+        debug_assert_eq!(cold_base % AlignedVec::ALIGNMENT, 0);
+
+        Self {
+            hot: inner,
+            cold: AlignedVec::new(),
+            cold_base,
+            is_cold: false,
+        }
+    }
+
+    /// Returns the position that the cold region starts at.
+    #[inline]
+    pub fn cold_base(&self) -> usize {
+        self.cold_base
+    }
+
+    /// Returns the range of positions occupied by the cold region.
+    #[inline]
+    pub fn cold_range(&self) -> Range<usize> {
+        self.cold_base..self.cold_base + self.cold.len()
+    }
+
+    /// Returns a reference to the underlying writer.
+    #[inline]
+    pub fn inner(&self) -> &W {
+        &self.hot
+    }
+
+    /// Consumes the region writer and returns the underlying writer.
+    ///
+    /// Any cold data which has not been spliced is discarded.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.hot
+    }
+}
+
+impl<W: Positional> RegionWriter<W> {
+    /// Writes the cold region to the underlying writer and deactivates it.
+    ///
+    /// Writing the cold region pads the underlying writer to `cold_base`. If
+    /// the underlying writer is already at or past `cold_base`, an error is
+    /// returned. Subsequent writes go directly to the underlying writer.
+    pub fn splice<E: Error>(&mut self) -> Result<(), E>
+    where
+        W: Writer<E>,
+    {
+        self.is_cold = false;
+        if self.cold.is_empty() {
+            return Ok(());
+        }
+
+        let hot_len = self.hot.pos();
+        if hot_len >= self.cold_base {
+            fail!(ColdRegionOverlap {
+                hot_len,
+                cold_base: self.cold_base,
+            });
+        }
+        self.hot.pad(self.cold_base - hot_len)?;
+        self.hot.write(&self.cold)?;
+        self.cold.clear();
+        Ok(())
+    }
+}
+
+impl<W: Positional> Positional for RegionWriter<W> {
+    #[inline]
+    fn pos(&self) -> usize {
+        if self.is_cold {
+            self.cold_base + self.cold.len()
+        } else {
+            self.hot.pos()
+        }
+    }
+}
+
+impl<W: Writer<E>, E> Writer<E> for RegionWriter<W> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        if self.is_cold {
+            self.cold.extend_from_slice(bytes);
+            Ok(())
+        } else {
+            self.hot.write(bytes)
+        }
+    }
+}
+
+impl<W> Regions for RegionWriter<W> {
+    #[inline]
+    fn is_cold(&self) -> bool {
+        self.is_cold
+    }
+
+    #[inline]
+    fn set_cold(&mut self, is_cold: bool) {
+        self.is_cold = is_cold;
+    }
+}
@@ -0,0 +1,127 @@
+use std::{io, vec::Vec};
+
+use tokio::io::{AsyncWrite, AsyncWriteExt as _};
+
+use crate::ser::{Positional, Writer};
+
+/// Wraps a type that implements [`AsyncWrite`] and equips it with [`Writer`].
+///
+/// Because serialization is synchronous, bytes written to an `AsyncIoWriter`
+/// are buffered in memory until they are flushed with
+/// [`flush_chunks`](AsyncIoWriter::flush_chunks) or
+/// [`finish`](AsyncIoWriter::finish). Buffered bytes are written to the inner
+/// writer in chunks of a fixed size. Async drivers like
+/// [`to_writer_async`](crate::util::to_writer_async) flush between chunks of
+/// elements to keep the buffer bounded.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     ser::{writer::AsyncIoWriter, Positional, Writer},
+/// };
+///
+/// let mut writer = AsyncIoWriter::with_chunk_size(Vec::new(), 4);
+/// Writer::<Failure>::write(&mut writer, &[0, 1, 2, 3, 4, 5]).unwrap();
+/// assert_eq!(writer.pos(), 6);
+/// assert_eq!(writer.buffered_len(), 6);
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread()
+///     .build()
+///     .unwrap();
+/// runtime.block_on(writer.flush_chunks()).unwrap();
+/// assert_eq!(writer.buffered_len(), 2);
+///
+/// let bytes = runtime.block_on(writer.finish()).unwrap();
+/// assert_eq!(bytes, [0, 1, 2, 3, 4, 5]);
+/// ```
+#[derive(Debug)]
+pub struct AsyncIoWriter<W> {
+    inner: W,
+    buffer: Vec<u8>,
+    chunk_size: usize,
+    pos: usize,
+}
+
+impl<W> AsyncIoWriter<W> {
+    /// The default number of bytes written to the inner writer at a time.
+    pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Creates a new writer from an async writer.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_chunk_size(inner, Self::DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new writer from an async writer which writes the given number
+    /// of bytes to it at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    #[inline]
+    pub fn with_chunk_size(inner: W, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be greater than 0");
+        Self {
+            inner,
+            buffer: Vec::new(),
+            chunk_size,
+            pos: 0,
+        }
+    }
+
+    /// Returns the number of bytes written to the inner writer at a time.
+    #[inline]
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the number of bytes which have been written but not yet flushed
+    /// to the inner writer.
+    #[inline]
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncIoWriter<W> {
+    /// Writes all of the complete chunks of buffered bytes to the inner
+    /// writer.
+    ///
+    /// Fewer than one chunk of bytes remain buffered afterward.
+    pub async fn flush_chunks(&mut self) -> io::Result<()> {
+        let len = self.buffer.len() - self.buffer.len() % self.chunk_size;
+        for chunk in self.buffer[..len].chunks(self.chunk_size) {
+            self.inner.write_all(chunk).await?;
+        }
+        self.buffer.drain(..len);
+        Ok(())
+    }
+
+    /// Writes all of the buffered bytes to the inner writer, flushes it, and
+    /// returns it.
+    pub async fn finish(mut self) -> io::Result<W> {
+        for chunk in self.buffer.chunks(self.chunk_size) {
+            self.inner.write_all(chunk).await?;
+        }
+        self.inner.flush().await?;
+        Ok(self.inner)
+    }
+}
+
+impl<W> Positional for AsyncIoWriter<W> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<W, E> Writer<E> for AsyncIoWriter<W> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.buffer.extend_from_slice(bytes);
+        self.pos += bytes.len();
+        Ok(())
+    }
+}
@@ -1,8 +1,59 @@
 #[path = "generic.rs"]
 mod group;
+#[cfg(all(
+    feature = "runtime-simd",
+    any(target_arch = "x86", target_arch = "x86_64"),
+))]
+pub mod sse2;
 
-// TODO: add optimized SIMD implementations for sse2 and neon
+// TODO: add optimized SIMD implementations for neon
 
 pub use group::*;
 
 pub const MAX_GROUP_WIDTH: usize = 16;
+
+/// A group of control bytes which can be scanned for a control byte.
+///
+/// `WIDTH` must evenly divide `MAX_GROUP_WIDTH`, so that every scanner reads
+/// the same control bytes for each probe.
+pub trait ControlGroup: Copy {
+    const WIDTH: usize;
+
+    type Bitmask: Iterator<Item = usize>;
+
+    /// Reads a group of control bytes from `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads of `WIDTH` bytes.
+    unsafe fn read(ptr: *const u8) -> Self;
+
+    /// Returns a bitmask of the control bytes which may be equal to `byte`.
+    ///
+    /// The bitmask may include false positives, but never false negatives.
+    fn match_byte(self, byte: u8) -> Self::Bitmask;
+
+    /// Returns whether any of the control bytes are empty.
+    fn any_empty(self) -> bool;
+}
+
+impl ControlGroup for Group {
+    const WIDTH: usize = Group::WIDTH;
+
+    type Bitmask = Bitmask;
+
+    #[inline]
+    unsafe fn read(ptr: *const u8) -> Self {
+        Group::read(ptr)
+    }
+
+    #[inline]
+    fn match_byte(self, byte: u8) -> Self::Bitmask {
+        Group::match_byte(self, byte)
+    }
+
+    #[inline]
+    fn any_empty(self) -> bool {
+        self.match_empty().any_bit_set()
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::{
+    __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+};
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{
+    __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+};
+
+use super::ControlGroup;
+
+#[derive(Clone, Copy)]
+pub struct Bitmask(u16);
+
+impl Iterator for Bitmask {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            None
+        } else {
+            let bit = self.0.trailing_zeros() as usize;
+            self.0 &= self.0 - 1;
+            Some(bit)
+        }
+    }
+}
+
+/// A group of control bytes scanned with SSE2 instructions.
+///
+/// Groups must only be created after checking that SSE2 is available.
+#[derive(Clone, Copy)]
+pub struct Group(__m128i);
+
+impl ControlGroup for Group {
+    const WIDTH: usize = 16;
+
+    type Bitmask = Bitmask;
+
+    #[inline]
+    unsafe fn read(ptr: *const u8) -> Self {
+        Self(unsafe { _mm_loadu_si128(ptr.cast()) })
+    }
+
+    #[inline]
+    fn match_byte(self, byte: u8) -> Self::Bitmask {
+        // SAFETY: Groups are only created when SSE2 is available.
+        unsafe {
+            let cmp = _mm_cmpeq_epi8(self.0, _mm_set1_epi8(byte as i8));
+            Bitmask(_mm_movemask_epi8(cmp) as u16)
+        }
+    }
+
+    #[inline]
+    fn any_empty(self) -> bool {
+        // Empty control bytes have their high bit set.
+        // SAFETY: Groups are only created when SSE2 is available.
+        unsafe { _mm_movemask_epi8(self.0) != 0 }
+    }
+}
@@ -0,0 +1,266 @@
+//! Checks that the wire format of archived types does not change.
+//!
+//! Archives which are stored for a long time must be read by later versions of
+//! a program. [`FormatStable`] records the field offsets of an archived type so
+//! that changes to its layout can be caught before they reach production.
+//!
+//! `FormatStable` can be implemented for the archived type of a type which
+//! derives `Archive` by adding `#[archive(format_stable)]`. The size and
+//! alignment of the archived type can also be pinned at compile time with
+//! `#[archive(format_stable(size = ..., align = ...))]`, which fails to compile
+//! if they change. The field offsets can be checked against a golden table in
+//! a test with [`format_table`].
+//!
+//! The layouts of archived types depend on the enabled endianness and pointer
+//! width features, so pinned layouts are only valid for one set of features.
+//!
+//! Layouts can also be checked at compile time with [`assert_archived_size!`],
+//! [`assert_archived_align!`], and [`archived_offset_of!`]. Adding
+//! `#[archive(stable_layout)]` to a struct generates a `LAYOUT` associated
+//! constant for its archived type, along with the field offsets used by
+//! `archived_offset_of!`. These work for private fields and for generic types
+//! instantiated with concrete parameters.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{stable::format_table, Archive};
+//!
+//! #[derive(Archive)]
+//! #[archive(format_stable(size = 8, align = 4))]
+//! struct Point {
+//!     x: f32,
+//!     y: f32,
+//! }
+//!
+//! assert_eq!(
+//!     format_table::<ArchivedPoint>(),
+//!     "size 8\nalign 4\nx 0\ny 4\n",
+//! );
+//! ```
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use core::fmt::Write as _;
+
+use crate::Portable;
+
+#[doc(hidden)]
+pub mod __private {
+    use super::FieldOffset;
+
+    /// Returns the offset of the field with the given name.
+    pub const fn field_offset(offsets: &[FieldOffset], name: &str) -> usize {
+        let mut i = 0;
+        while i < offsets.len() {
+            if str_eq(offsets[i].name, name) {
+                return offsets[i].offset;
+            }
+            i += 1;
+        }
+        panic!("the archived type has no field with that name");
+    }
+
+    const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+/// The name and offset of a field of an archived type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldOffset {
+    /// The name of the field.
+    ///
+    /// Fields of tuple structs are named `_0`, `_1`, and so on. Fields of enum
+    /// variants are prefixed with the name of the variant, like `Variant.field`.
+    pub name: &'static str,
+    /// The offset of the field from the start of the archived type, in bytes.
+    pub offset: usize,
+}
+
+/// An archived type whose field offsets are recorded so that changes to its
+/// layout can be detected.
+pub trait FormatStable: Portable {
+    /// The offsets of the fields of the archived type, in declaration order.
+    ///
+    /// Fields of enum variants are offset from the start of the enum, and
+    /// the tag of the enum is always located at offset 0.
+    const FIELD_OFFSETS: &'static [FieldOffset];
+}
+
+/// Returns a table of the size, alignment, and field offsets of an archived
+/// type.
+///
+/// The table has one line for each of `size`, `align`, and the fields of the
+/// type, and can be compared against a golden table in a test.
+#[cfg(feature = "alloc")]
+pub fn format_table<T: FormatStable>() -> String {
+    let mut result = String::new();
+    writeln!(result, "size {}", core::mem::size_of::<T>()).unwrap();
+    writeln!(result, "align {}", core::mem::align_of::<T>()).unwrap();
+    for field in T::FIELD_OFFSETS {
+        writeln!(result, "{} {}", field.name, field.offset).unwrap();
+    }
+    result
+}
+
+/// Asserts at compile time that the archived type of a type has the given
+/// size.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{assert_archived_size, Archive};
+///
+/// #[derive(Archive)]
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// assert_archived_size!(Point, 8);
+/// assert_archived_size!([u16; 3], 6);
+/// ```
+///
+/// Compilation fails if the size is different:
+///
+/// ```compile_fail
+/// use rkyv::assert_archived_size;
+///
+/// assert_archived_size!(u32, 8);
+/// ```
+#[macro_export]
+macro_rules! assert_archived_size {
+    ($ty:ty, $size:expr $(,)?) => {
+        const _: () = ::core::assert!(
+            ::core::mem::size_of::<$crate::Archived<$ty>>() == $size,
+            ::core::concat!(
+                "the size of the archived type of `",
+                ::core::stringify!($ty),
+                "` is not ",
+                ::core::stringify!($size),
+            ),
+        );
+    };
+}
+
+/// Asserts at compile time that the archived type of a type has the given
+/// alignment.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{assert_archived_align, Archive};
+///
+/// #[derive(Archive)]
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// assert_archived_align!(Point, 4);
+/// ```
+#[macro_export]
+macro_rules! assert_archived_align {
+    ($ty:ty, $align:expr $(,)?) => {
+        const _: () = ::core::assert!(
+            ::core::mem::align_of::<$crate::Archived<$ty>>() == $align,
+            ::core::concat!(
+                "the alignment of the archived type of `",
+                ::core::stringify!($ty),
+                "` is not ",
+                ::core::stringify!($align),
+            ),
+        );
+    };
+}
+
+/// Returns the offset of a field of the archived type of a type, in bytes.
+///
+/// The type must derive `Archive` with `#[archive(stable_layout)]`. Fields of
+/// tuple structs are named by their index. The offset can be used in constant
+/// expressions, and naming a field which does not exist fails to compile when
+/// the offset is used in one.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{archived_offset_of, Archive};
+///
+/// #[derive(Archive)]
+/// #[archive(stable_layout)]
+/// struct Header<T> {
+///     magic: u32,
+///     len: u32,
+///     extra: T,
+/// }
+///
+/// #[derive(Archive)]
+/// #[archive(stable_layout)]
+/// struct Pair(u32, u8);
+///
+/// // Fields with generic types are laid out first
+/// const LEN: usize = archived_offset_of!(Header<u32>, len);
+/// assert_eq!(LEN, 8);
+/// assert_eq!(archived_offset_of!(Header<u32>, extra), 0);
+/// assert_eq!(archived_offset_of!(Pair, 1), 4);
+/// assert_eq!(ArchivedHeader::<u32>::LAYOUT.size(), 12);
+/// ```
+#[macro_export]
+macro_rules! archived_offset_of {
+    ($ty:ty, $field:ident $(,)?) => {
+        $crate::stable::__private::field_offset(
+            <$crate::Archived<$ty>>::__RKYV_FIELD_OFFSETS,
+            ::core::stringify!($field),
+        )
+    };
+    ($ty:ty, $index:literal $(,)?) => {
+        $crate::stable::__private::field_offset(
+            <$crate::Archived<$ty>>::__RKYV_FIELD_OFFSETS,
+            ::core::concat!("_", ::core::stringify!($index)),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(all(feature = "alloc", not(feature = "std")))]
+    use alloc::{string::String, vec::Vec};
+    use core::mem::size_of;
+
+    use crate::primitive::FixedUsize;
+
+    // Relative pointers and lengths have the size of the pointer width
+    const WIDTH: usize = size_of::<FixedUsize>();
+    const ALIGN: usize = if cfg!(feature = "unaligned") {
+        1
+    } else {
+        WIDTH
+    };
+    const U32_ALIGN: usize = if cfg!(feature = "unaligned") { 1 } else { 4 };
+
+    #[cfg(feature = "alloc")]
+    assert_archived_size!(String, 2 * WIDTH);
+    #[cfg(feature = "alloc")]
+    assert_archived_align!(String, ALIGN);
+    #[cfg(feature = "alloc")]
+    assert_archived_size!(Vec<u32>, 2 * WIDTH);
+    #[cfg(feature = "alloc")]
+    assert_archived_align!(Vec<u32>, ALIGN);
+    // The tag is padded to the alignment of the value
+    assert_archived_size!(Option<u32>, U32_ALIGN + 4);
+    assert_archived_align!(Option<u32>, U32_ALIGN);
+    assert_archived_size!([Option<u32>; 3], 3 * (U32_ALIGN + 4));
+}
@@ -0,0 +1,492 @@
+//! Deterministic hashes of archived values for content addressing.
+//!
+//! [`StableHash`] hashes the logical value of an archived value, so two
+//! archives of equal values hash the same even if their bytes differ. The
+//! bytes of archives can differ because of padding, where subobjects are
+//! placed, whether they are shared, the load factor and insertion order of
+//! hash tables, and the endianness and pointer width that rkyv was configured
+//! with. None of these affect the stable hash.
+//!
+//! Unlike `Hash`, stable hashes are defined by a fixed encoding and hash
+//! function which don't change between releases or platforms. If they ever
+//! need to change, [`STABLE_HASH_VERSION`] is incremented. Digests should be
+//! stored alongside the version they were computed with, and digests from
+//! different versions must not be compared.
+//!
+//! `StableHash` can be implemented for the archived type of a type which
+//! derives `Archive` by adding `#[archive(stable_hash)]`.
+//!
+//! # Scheme
+//!
+//! Values are written to a [`StableHasher`] as a canonical sequence of bytes,
+//! which is hashed with 128-bit FNV-1a. This is version 1 of the scheme:
+//!
+//! - Integers are written as little-endian bytes of their native size. `bool`
+//!   is written as one byte, 0 or 1, and `char` as a `u32`.
+//! - Floats are written as the little-endian bytes of their bits, except that
+//!   negative zero is written as positive zero and all NaNs are written as
+//!   the NaN returned by `f32::NAN` or `f64::NAN`.
+//! - Lengths are written as `u64`s.
+//! - Strings and slices, including those in vectors and boxes, are written as
+//!   their length followed by their bytes or elements. Arrays are written as
+//!   their elements alone.
+//! - Boxes and reference-counted pointers are written as the value they point
+//!   to.
+//! - Options are written as a 0 byte for `None`, or a 1 byte followed by the
+//!   value for `Some`.
+//! - Tuples and structs are written as their fields in declaration order.
+//!   Enums are written as the index of their variant in declaration order as a
+//!   `u32`, followed by the fields of the variant.
+//! - B-tree maps and sets, and index maps and sets, are written as their
+//!   length followed by their entries in order. Each map entry is written as
+//!   its key followed by its value.
+//! - Hash maps and sets are written as their length followed by the wrapping
+//!   sum of the digests of each of their entries as a `u128`. Each entry is
+//!   hashed with a new hasher, so the sum doesn't depend on the order of the
+//!   entries.
+//!
+//! FNV-1a is not a cryptographic hash function. It's suitable for finding
+//! duplicates among trusted values, but collisions can be found deliberately.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     access_unchecked, rancor::Failure, stable_hash::stable_hash, to_bytes,
+//!     Archive, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(stable_hash)]
+//! struct Document {
+//!     id: u8,
+//!     title: String,
+//! }
+//!
+//! let value = Document {
+//!     id: 1,
+//!     title: "stable".to_string(),
+//! };
+//! let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+//! let archived = unsafe { access_unchecked::<ArchivedDocument>(&bytes) };
+//!
+//! // Padding the archive moves the value but doesn't change its hash
+//! let mut padded = rkyv::util::AlignedVec::new();
+//! padded.extend_from_slice(&[0; 16]);
+//! let padded = rkyv::util::serialize_into::<_, _, Failure>(&value, padded)
+//!     .unwrap();
+//! let moved = unsafe { access_unchecked::<ArchivedDocument>(&padded) };
+//! assert_eq!(stable_hash(archived), stable_hash(moved));
+//! ```
+
+use crate::{
+    boxed::ArchivedBox,
+    collections::{
+        btree_map::ArchivedBTreeMap,
+        btree_set::ArchivedBTreeSet,
+        swiss_table::{
+            ArchivedHashMap, ArchivedHashSet, ArchivedIndexMap,
+            ArchivedIndexSet,
+        },
+    },
+    option::ArchivedOption,
+    rc::ArchivedRc,
+    string::ArchivedString,
+    tuple::*,
+    vec::ArchivedVec,
+    ArchivePointee,
+};
+
+/// The version of the stable hashing scheme.
+///
+/// This is incremented whenever the digest of any value changes.
+pub const STABLE_HASH_VERSION: u32 = 1;
+
+const FNV_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// An incremental hasher which computes 128-bit stable hashes.
+///
+/// See the [module documentation](self) for the encodings of values.
+#[derive(Clone, Debug)]
+pub struct StableHasher {
+    state: u128,
+}
+
+impl Default for StableHasher {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StableHasher {
+    /// Returns a new hasher which has not been written to.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// Writes some bytes to the hasher.
+    #[inline]
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= byte as u128;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Writes a length to the hasher as a `u64`.
+    #[inline]
+    pub fn write_len(&mut self, len: usize) {
+        self.write_u64(len as u64);
+    }
+
+    /// Returns the digest of the bytes written so far.
+    #[inline]
+    pub fn finish(&self) -> u128 {
+        self.state
+    }
+}
+
+macro_rules! impl_write {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        impl StableHasher {
+            $(
+                #[doc = concat!(
+                    "Writes a `", stringify!($ty), "` to the hasher as ",
+                    "little-endian bytes.",
+                )]
+                #[inline]
+                pub fn $name(&mut self, value: $ty) {
+                    self.write(&value.to_le_bytes());
+                }
+            )*
+        }
+    };
+}
+
+impl_write! {
+    write_u8: u8,
+    write_u16: u16,
+    write_u32: u32,
+    write_u64: u64,
+    write_u128: u128,
+    write_i8: i8,
+    write_i16: i16,
+    write_i32: i32,
+    write_i64: i64,
+    write_i128: i128,
+}
+
+/// An archived type which can be hashed deterministically by its logical
+/// value.
+///
+/// Implementations must write values with the encodings described in the
+/// [module documentation](self), so that equal values hash the same regardless
+/// of how they were archived.
+pub trait StableHash {
+    /// Writes this value to the given hasher.
+    fn stable_hash(&self, hasher: &mut StableHasher);
+}
+
+/// Returns the 128-bit stable hash of the given value.
+#[inline]
+pub fn stable_hash<T: StableHash + ?Sized>(value: &T) -> u128 {
+    let mut hasher = StableHasher::new();
+    value.stable_hash(&mut hasher);
+    hasher.finish()
+}
+
+// Primitives
+
+impl StableHash for () {
+    #[inline]
+    fn stable_hash(&self, _: &mut StableHasher) {}
+}
+
+impl StableHash for bool {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u8(*self as u8);
+    }
+}
+
+impl StableHash for char {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(*self as u32);
+    }
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl StableHash for $ty {
+                #[inline]
+                fn stable_hash(&self, hasher: &mut StableHasher) {
+                    hasher.write(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+macro_rules! impl_float {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl StableHash for $ty {
+                #[inline]
+                fn stable_hash(&self, hasher: &mut StableHasher) {
+                    let value = if self.is_nan() {
+                        <$ty>::NAN
+                    } else if *self == 0.0 {
+                        0.0
+                    } else {
+                        *self
+                    };
+                    hasher.write(&value.to_bits().to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(f32, f64);
+
+macro_rules! impl_endian_primitives {
+    ($module:path: $($ty:ident)*) => {
+        const _: () = {
+            use $module::{$($ty,)*};
+
+            $(
+                impl StableHash for $ty {
+                    #[inline]
+                    fn stable_hash(&self, hasher: &mut StableHasher) {
+                        self.to_native().stable_hash(hasher);
+                    }
+                }
+            )*
+        };
+    };
+}
+
+impl_endian_primitives! {
+    crate::rend:
+    i16_le i32_le i64_le i128_le u16_le u32_le u64_le u128_le f32_le f64_le
+    char_le
+}
+
+impl_endian_primitives! {
+    crate::rend:
+    i16_be i32_be i64_be i128_be u16_be u32_be u64_be u128_be f32_be f64_be
+    char_be
+}
+
+impl_endian_primitives! {
+    crate::rend::unaligned:
+    i16_ule i32_ule i64_ule i128_ule u16_ule u32_ule u64_ule u128_ule f32_ule
+    f64_ule char_ule
+}
+
+impl_endian_primitives! {
+    crate::rend::unaligned:
+    i16_ube i32_ube i64_ube i128_ube u16_ube u32_ube u64_ube u128_ube f32_ube
+    f64_ube char_ube
+}
+
+// Strings, slices, and arrays
+
+impl StableHash for str {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl<T: StableHash> StableHash for [T] {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        for value in self.iter() {
+            value.stable_hash(hasher);
+        }
+    }
+}
+
+impl<T: StableHash, const N: usize> StableHash for [T; N] {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        for value in self.iter() {
+            value.stable_hash(hasher);
+        }
+    }
+}
+
+impl StableHash for ArchivedString {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.as_str().stable_hash(hasher);
+    }
+}
+
+impl<T: StableHash> StableHash for ArchivedVec<T> {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.as_slice().stable_hash(hasher);
+    }
+}
+
+// Pointers
+
+impl<T: ArchivePointee + StableHash + ?Sized> StableHash for ArchivedBox<T> {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        self.get().stable_hash(hasher);
+    }
+}
+
+impl<T: ArchivePointee + StableHash + ?Sized, F> StableHash
+    for ArchivedRc<T, F>
+{
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        (**self).stable_hash(hasher);
+    }
+}
+
+// Option
+
+impl<T: StableHash> StableHash for ArchivedOption<T> {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        match self.as_ref() {
+            None => hasher.write_u8(0),
+            Some(value) => {
+                hasher.write_u8(1);
+                value.stable_hash(hasher);
+            }
+        }
+    }
+}
+
+// Tuples
+
+macro_rules! impl_tuple {
+    ($name:ident, $($type:ident $index:tt),*) => {
+        impl<$($type: StableHash),*> StableHash for $name<$($type),*> {
+            #[inline]
+            fn stable_hash(&self, hasher: &mut StableHasher) {
+                $(self.$index.stable_hash(hasher);)*
+            }
+        }
+    };
+}
+
+impl_tuple!(ArchivedTuple1, T0 0);
+impl_tuple!(ArchivedTuple2, T0 0, T1 1);
+impl_tuple!(ArchivedTuple3, T0 0, T1 1, T2 2);
+impl_tuple!(ArchivedTuple4, T0 0, T1 1, T2 2, T3 3);
+impl_tuple!(ArchivedTuple5, T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_tuple!(ArchivedTuple6, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_tuple!(ArchivedTuple7, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_tuple!(ArchivedTuple8, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_tuple!(
+    ArchivedTuple9, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8
+);
+impl_tuple!(
+    ArchivedTuple10, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9
+);
+impl_tuple!(
+    ArchivedTuple11, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10
+);
+impl_tuple!(
+    ArchivedTuple12, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10, T11 11
+);
+impl_tuple!(
+    ArchivedTuple13, T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9,
+    T10 10, T11 11, T12 12
+);
+
+// Ordered maps and sets
+
+impl<K: StableHash, V: StableHash> StableHash for ArchivedBTreeMap<K, V> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        for (key, value) in self.iter() {
+            key.stable_hash(hasher);
+            value.stable_hash(hasher);
+        }
+    }
+}
+
+impl<K: StableHash> StableHash for ArchivedBTreeSet<K> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        for key in self.iter() {
+            key.stable_hash(hasher);
+        }
+    }
+}
+
+impl<K: StableHash, V: StableHash, H> StableHash for ArchivedIndexMap<K, V, H> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        for (key, value) in self.iter() {
+            key.stable_hash(hasher);
+            value.stable_hash(hasher);
+        }
+    }
+}
+
+impl<K: StableHash, H> StableHash for ArchivedIndexSet<K, H> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        for key in self.iter() {
+            key.stable_hash(hasher);
+        }
+    }
+}
+
+// Hash maps and sets
+
+/// Writes the order-insensitive combination of the digests of the given
+/// entries.
+fn write_unordered<I: Iterator>(
+    hasher: &mut StableHasher,
+    len: usize,
+    entries: I,
+    hash_entry: impl Fn(I::Item, &mut StableHasher),
+) {
+    let sum = entries.fold(0u128, |sum, entry| {
+        let mut entry_hasher = StableHasher::new();
+        hash_entry(entry, &mut entry_hasher);
+        sum.wrapping_add(entry_hasher.finish())
+    });
+    hasher.write_len(len);
+    hasher.write_u128(sum);
+}
+
+impl<K: StableHash, V: StableHash, H> StableHash for ArchivedHashMap<K, V, H> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        write_unordered(hasher, self.len(), self.iter(), |(k, v), hasher| {
+            k.stable_hash(hasher);
+            v.stable_hash(hasher);
+        });
+    }
+}
+
+impl<K: StableHash, H> StableHash for ArchivedHashSet<K, H> {
+    fn stable_hash(&self, hasher: &mut StableHasher) {
+        write_unordered(hasher, self.len(), self.iter(), |k, hasher| {
+            k.stable_hash(hasher);
+        });
+    }
+}
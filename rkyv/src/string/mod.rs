@@ -14,7 +14,7 @@ use core::{
 };
 
 use rancor::Fallible;
-use repr::{ArchivedStringRepr, INLINE_CAPACITY};
+use repr::ArchivedStringRepr;
 
 use crate::{Portable, SerializeUnsized};
 
@@ -23,6 +23,13 @@ use crate::{Portable, SerializeUnsized};
 /// This has inline and out-of-line representations. Short strings will use the
 /// available space inside the structure to store the string, and long strings
 /// will store a [`RelPtr`](crate::RelPtr) to a `str` instead.
+///
+/// Strings of at most [`INLINE_CAPACITY`](ArchivedString::INLINE_CAPACITY)
+/// bytes are stored inline by default. Use
+/// [`serialize_from_str_with_policy`](ArchivedString::serialize_from_str_with_policy)
+/// to choose a different [`InlinePolicy`]. Both representations behave the
+/// same way for comparisons, hashing, and lookups, and are both checked to
+/// contain valid UTF-8 during validation.
 #[repr(transparent)]
 #[cfg_attr(
     feature = "bytecheck",
@@ -36,6 +43,35 @@ pub struct ArchivedString {
 }
 
 impl ArchivedString {
+    /// The maximum number of bytes that can be stored inline.
+    pub const INLINE_CAPACITY: usize = repr::INLINE_CAPACITY;
+
+    /// Returns whether the bytes of the string are stored inline.
+    ///
+    /// Inline strings store their bytes inside the `ArchivedString` itself.
+    /// Out-of-line strings store them elsewhere in the archive, before the
+    /// `ArchivedString`.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::{
+    ///     access_unchecked, rancor::Failure, string::ArchivedString, to_bytes,
+    /// };
+    ///
+    /// let bytes = to_bytes::<_, 256, Failure>(&"hi".to_string()).unwrap();
+    /// let string = unsafe { access_unchecked::<ArchivedString>(&bytes) };
+    /// assert!(string.is_inline());
+    ///
+    /// let long = "a".repeat(ArchivedString::INLINE_CAPACITY + 1);
+    /// let bytes = to_bytes::<_, 256, Failure>(&long).unwrap();
+    /// let string = unsafe { access_unchecked::<ArchivedString>(&bytes) };
+    /// assert!(!string.is_inline());
+    /// ```
+    #[inline]
+    pub fn is_inline(&self) -> bool {
+        self.repr.is_inline()
+    }
+
     /// Extracts a string slice containing the entire `ArchivedString`.
     #[inline]
     pub fn as_str(&self) -> &str {
@@ -49,6 +85,77 @@ impl ArchivedString {
         unsafe { self.map_unchecked_mut(|s| s.repr.as_mut_str()) }
     }
 
+    /// Calls `f` with a mutable string slice containing the entire
+    /// `ArchivedString`.
+    ///
+    /// A `&mut str` can only be edited in ways that keep its length the same,
+    /// so the length and representation of the archived string never change.
+    /// This works for both inline and out-of-line strings.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::{access_mut, rancor::Failure, string::ArchivedString, to_bytes};
+    ///
+    /// let mut bytes =
+    ///     to_bytes::<_, 256, Failure>(&"password: hunter2".to_string())
+    ///         .unwrap();
+    /// let mut string = access_mut::<ArchivedString, Failure>(&mut bytes)
+    ///     .unwrap();
+    /// string.as_mut().edit_in_place(|s| {
+    ///     let start = s.find(": ").unwrap() + 2;
+    ///     // SAFETY: Asterisks are ASCII, so the string stays valid UTF-8.
+    ///     unsafe { s[start..].as_bytes_mut().fill(b'*') };
+    /// });
+    /// assert_eq!(*string, "password: *******");
+    /// ```
+    #[inline]
+    pub fn edit_in_place<R>(
+        self: Pin<&mut Self>,
+        f: impl FnOnce(&mut str) -> R,
+    ) -> R {
+        f(Pin::into_inner(self.pin_mut_str()))
+    }
+
+    /// Converts the string to its ASCII upper case equivalent in place.
+    #[inline]
+    pub fn make_ascii_uppercase(self: Pin<&mut Self>) {
+        self.edit_in_place(str::make_ascii_uppercase)
+    }
+
+    /// Converts the string to its ASCII lower case equivalent in place.
+    #[inline]
+    pub fn make_ascii_lowercase(self: Pin<&mut Self>) {
+        self.edit_in_place(str::make_ascii_lowercase)
+    }
+
+    /// Overwrites every byte of the string with repeated copies of `c`.
+    ///
+    /// Returns an error and leaves the string unchanged if the length of the
+    /// string is not a multiple of the UTF-8 length of `c`.
+    #[inline]
+    pub fn fill_char(
+        self: Pin<&mut Self>,
+        c: char,
+    ) -> Result<(), FillCharError> {
+        let mut buf = [0; 4];
+        let encoded = c.encode_utf8(&mut buf).as_bytes();
+        self.edit_in_place(|s| {
+            if s.len() % encoded.len() != 0 {
+                return Err(FillCharError {
+                    len: s.len(),
+                    char_len: encoded.len(),
+                });
+            }
+            // SAFETY: The string is overwritten with complete copies of a
+            // valid UTF-8 encoded char, so it remains valid UTF-8.
+            let bytes = unsafe { s.as_bytes_mut() };
+            for chunk in bytes.chunks_exact_mut(encoded.len()) {
+                chunk.copy_from_slice(encoded);
+            }
+            Ok(())
+        })
+    }
+
     /// Resolves an archived string from a given `str`.
     ///
     /// # Safety
@@ -62,7 +169,7 @@ impl ArchivedString {
         resolver: StringResolver,
         out: *mut Self,
     ) {
-        if value.len() <= repr::INLINE_CAPACITY {
+        if !resolver.out_of_line && value.len() <= repr::INLINE_CAPACITY {
             ArchivedStringRepr::emplace_inline(value, out.cast());
         } else {
             ArchivedStringRepr::emplace_out_of_line(
@@ -75,6 +182,8 @@ impl ArchivedString {
     }
 
     /// Serializes an archived string from a given `str`.
+    ///
+    /// This uses the default [`InlinePolicy`].
     #[inline]
     pub fn serialize_from_str<S: Fallible + ?Sized>(
         value: &str,
@@ -83,11 +192,82 @@ impl ArchivedString {
     where
         str: SerializeUnsized<S>,
     {
-        if value.len() <= INLINE_CAPACITY {
-            Ok(StringResolver { pos: 0 })
+        Self::serialize_from_str_with_policy(
+            value,
+            InlinePolicy::default(),
+            serializer,
+        )
+    }
+
+    /// Serializes an archived string from a given `str`, storing it inline or
+    /// out-of-line as chosen by `policy`.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::{
+    ///     access_unchecked,
+    ///     rancor::{Failure, Fallible},
+    ///     string::{ArchivedString, InlinePolicy, StringResolver},
+    ///     to_bytes, Archive, Serialize, SerializeUnsized,
+    /// };
+    ///
+    /// // A string which is never stored inline
+    /// struct Exported(String);
+    ///
+    /// impl Archive for Exported {
+    ///     type Archived = ArchivedString;
+    ///     type Resolver = StringResolver;
+    ///
+    ///     unsafe fn resolve(
+    ///         &self,
+    ///         pos: usize,
+    ///         resolver: Self::Resolver,
+    ///         out: *mut Self::Archived,
+    ///     ) {
+    ///         ArchivedString::resolve_from_str(&self.0, pos, resolver, out);
+    ///     }
+    /// }
+    ///
+    /// impl<S: Fallible + ?Sized> Serialize<S> for Exported
+    /// where
+    ///     str: SerializeUnsized<S>,
+    /// {
+    ///     fn serialize(
+    ///         &self,
+    ///         serializer: &mut S,
+    ///     ) -> Result<Self::Resolver, S::Error> {
+    ///         ArchivedString::serialize_from_str_with_policy(
+    ///             &self.0,
+    ///             InlinePolicy::Never,
+    ///             serializer,
+    ///         )
+    ///     }
+    /// }
+    ///
+    /// let value = Exported("short".to_string());
+    /// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+    /// let archived = unsafe { access_unchecked::<ArchivedString>(&bytes) };
+    /// assert!(!archived.is_inline());
+    /// assert_eq!(archived, "short");
+    /// ```
+    #[inline]
+    pub fn serialize_from_str_with_policy<S: Fallible + ?Sized>(
+        value: &str,
+        policy: InlinePolicy,
+        serializer: &mut S,
+    ) -> Result<StringResolver, S::Error>
+    where
+        str: SerializeUnsized<S>,
+    {
+        if policy.is_inline(value.len()) {
+            Ok(StringResolver {
+                pos: 0,
+                out_of_line: false,
+            })
         } else {
             Ok(StringResolver {
                 pos: value.serialize_unsized(serializer)?,
+                out_of_line: true,
             })
         }
     }
@@ -236,21 +416,118 @@ impl PartialOrd<ArchivedString> for str {
     }
 }
 
+/// An error resulting from filling an [`ArchivedString`] with a char whose
+/// UTF-8 length does not evenly divide the length of the string.
+#[derive(Debug)]
+pub struct FillCharError {
+    len: usize,
+    char_len: usize,
+}
+
+impl fmt::Display for FillCharError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot fill a string of length {} with a char of UTF-8 length {}",
+            self.len, self.char_len,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FillCharError {}
+
+/// Whether to store an archived string inline or out-of-line.
+///
+/// Strings which are longer than
+/// [`ArchivedString::INLINE_CAPACITY`] are always stored out-of-line, and empty
+/// strings are always stored inline since there are no bytes to point to.
+///
+/// Out-of-line strings have their bytes at a stable position in the archive,
+/// which is useful when their offsets are exported. Inline strings keep their
+/// bytes next to the rest of the archived value, which is better for locality.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InlinePolicy {
+    /// Strings with a length of at most the given number of bytes are stored
+    /// inline.
+    UpTo(usize),
+    /// Non-empty strings are never stored inline.
+    Never,
+}
+
+impl InlinePolicy {
+    /// Returns whether a string of the given length is stored inline under
+    /// this policy.
+    #[inline]
+    pub fn is_inline(&self, len: usize) -> bool {
+        match *self {
+            InlinePolicy::UpTo(max) => {
+                len <= max && len <= repr::INLINE_CAPACITY
+            }
+            InlinePolicy::Never => len == 0,
+        }
+    }
+}
+
+impl Default for InlinePolicy {
+    /// Stores strings inline whenever they fit.
+    #[inline]
+    fn default() -> Self {
+        InlinePolicy::UpTo(repr::INLINE_CAPACITY)
+    }
+}
+
 /// The resolver for `String`.
 pub struct StringResolver {
     pos: usize,
+    out_of_line: bool,
 }
 
+impl StringResolver {
+    /// Creates a new string resolver from the position of a serialized string.
+    ///
+    /// In most cases, you won't need to create a [`StringResolver`] yourself
+    /// and can instead obtain it through
+    /// [`ArchivedString::serialize_from_str`].
+    #[inline]
+    pub fn from_pos(pos: usize) -> Self {
+        Self {
+            pos,
+            out_of_line: false,
+        }
+    }
+
+    /// Creates a new string resolver from the position of a serialized string
+    /// which is stored out-of-line even if it is short enough to be inlined.
+    #[inline]
+    pub fn from_out_of_line_pos(pos: usize) -> Self {
+        Self {
+            pos,
+            out_of_line: true,
+        }
+    }
+}
+
+#[cfg(feature = "bytecheck")]
+pub use self::verify::check_strings;
+
 #[cfg(feature = "bytecheck")]
 mod verify {
+    #[cfg(not(feature = "simdutf8"))]
+    use core::str::from_utf8;
+    use core::{alloc::Layout, ptr::slice_from_raw_parts};
+
     use bytecheck::{
         rancor::{Error, Fallible},
         CheckBytes, Verify,
     };
+    use rancor::ResultExt as _;
+    #[cfg(feature = "simdutf8")]
+    use simdutf8::basic::from_utf8;
 
     use crate::{
         string::{repr::ArchivedStringRepr, ArchivedString},
-        validation::{ArchiveContext, ArchiveContextExt},
+        validation::{overflow::range_end, ArchiveContext, ArchiveContextExt},
     };
 
     unsafe impl<C> Verify<C> for ArchivedString
@@ -260,6 +537,9 @@ mod verify {
     {
         #[inline]
         fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            if let Some(slot) = self.repr.offset_slot() {
+                context.record_offset(slot)?;
+            }
             if self.repr.is_inline() {
                 unsafe {
                     str::check_bytes(self.repr.as_str_ptr(), context)?;
@@ -269,13 +549,11 @@ mod verify {
                 let offset = unsafe { self.repr.out_of_line_offset() };
                 let metadata = self.repr.len();
 
-                let ptr = unsafe {
-                    context.bounds_check_subtree_base_offset::<str>(
+                let (ptr, range) = unsafe {
+                    context.push_aliasable_subtree_base_offset::<str>(
                         base, offset, metadata,
                     )?
                 };
-
-                let range = unsafe { context.push_prefix_subtree(ptr)? };
                 unsafe {
                     str::check_bytes(ptr, context)?;
                 }
@@ -287,4 +565,166 @@ mod verify {
             Ok(())
         }
     }
+
+    /// Returns a pointer to the bytes of an out-of-line string.
+    ///
+    /// The returned pointer has not been bounds checked.
+    fn out_of_line_ptr(string: &ArchivedString) -> *const u8 {
+        let base = (&string.repr as *const ArchivedStringRepr).cast::<u8>();
+        base.wrapping_offset(unsafe { string.repr.out_of_line_offset() })
+    }
+
+    /// Returns the out-of-line bytes of `strings` if they are laid out
+    /// back-to-back in order.
+    ///
+    /// The returned pointer has not been bounds checked.
+    fn dense_bytes(strings: &[ArchivedString]) -> Option<*const [u8]> {
+        let mut start = None;
+        let mut end = core::ptr::null::<u8>();
+        for string in strings.iter().filter(|s| !s.repr.is_inline()) {
+            let ptr = out_of_line_ptr(string);
+            if start.is_some() && ptr != end {
+                return None;
+            }
+            start.get_or_insert(ptr);
+            range_end(ptr.addr(), string.repr.len()).ok()?;
+            end = ptr.wrapping_add(string.repr.len());
+        }
+        let start = start?;
+        let len = end.addr().checked_sub(start.addr())?;
+        Some(slice_from_raw_parts(start, len))
+    }
+
+    /// Checks a slice of archived strings.
+    ///
+    /// This checks the same things as `<[ArchivedString]>::check_bytes`. If the
+    /// bytes of the out-of-line strings are laid out back-to-back in order, as
+    /// they are when serialized with
+    /// [`DenseStrings`](crate::with::DenseStrings), they are claimed and
+    /// validated as UTF-8 all at once. Otherwise, each string is checked on its
+    /// own.
+    ///
+    /// # Safety
+    ///
+    /// `value` must be aligned and point to enough bytes to represent the
+    /// slice, and must be located inside the archive `context` is validating.
+    pub unsafe fn check_strings<C>(
+        value: *const [ArchivedString],
+        context: &mut C,
+    ) -> Result<(), C::Error>
+    where
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        let base = value.cast::<ArchivedString>();
+        let len = ptr_meta::metadata(value);
+        for i in 0..len {
+            unsafe {
+                ArchivedStringRepr::check_bytes(base.add(i).cast(), context)?;
+            }
+        }
+        // SAFETY: The representations of the strings are valid, so they can
+        // be read. Their bytes are only read after they have been checked.
+        let strings = unsafe { &*value };
+
+        if let Some(bytes) = dense_bytes(strings) {
+            let start = bytes.cast::<u8>();
+            let len = ptr_meta::metadata(bytes);
+            let layout = Layout::array::<u8>(len).into_error()?;
+            for string in strings.iter().filter(|s| !s.repr.is_inline()) {
+                if let Some(slot) = string.repr.offset_slot() {
+                    context.record_offset(slot)?;
+                }
+                context.record_subtree_ptr(
+                    (&string.repr as *const ArchivedStringRepr).cast(),
+                    out_of_line_ptr(string),
+                    &Layout::array::<u8>(string.repr.len()).into_error()?,
+                    "str",
+                )?;
+            }
+            context.check_subtree_ptr(start, &layout)?;
+
+            // SAFETY: The bytes were just bounds checked.
+            let bytes = unsafe { &*bytes };
+            if is_dense_utf8(strings, bytes) {
+                let range = unsafe {
+                    context.push_prefix_subtree_range(start, start.add(len))?
+                };
+                for string in strings.iter().filter(|s| s.repr.is_inline()) {
+                    unsafe {
+                        str::check_bytes(string.repr.as_str_ptr(), context)?;
+                    }
+                }
+                unsafe {
+                    context.pop_subtree_range(range)?;
+                }
+                return Ok(());
+            }
+        }
+
+        // Check each string on its own so that invalid strings are reported
+        // the same way as they are for any other slice.
+        for (index, string) in strings.iter().enumerate() {
+            unsafe {
+                context.start_element()?;
+                let result = ArchivedString::check_bytes(string, context);
+                context.finish_element(
+                    index,
+                    (string as *const ArchivedString).cast(),
+                    result,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether `bytes` is valid UTF-8 and each of the out-of-line
+    /// strings laid out in it starts on a character boundary.
+    fn is_dense_utf8(strings: &[ArchivedString], bytes: &[u8]) -> bool {
+        let dense = match from_utf8(bytes) {
+            Ok(dense) => dense,
+            Err(_) => return false,
+        };
+        let mut pos = 0;
+        for string in strings.iter().filter(|s| !s.repr.is_inline()) {
+            if !dense.is_char_boundary(pos) {
+                return false;
+            }
+            pos += string.repr.len();
+        }
+        true
+    }
+}
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use crate::{
+        reflect::{DescriptorKind, Reflect, TypeDescriptor},
+        string::ArchivedString,
+    };
+
+    impl Reflect for ArchivedString {
+        const DESCRIPTOR: TypeDescriptor =
+            TypeDescriptor::of::<Self>("String", DescriptorKind::String);
+    }
+}
+
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+mod patch {
+    use core::mem::offset_of;
+
+    use crate::{
+        patch::{Patchable, Relocation},
+        string::{repr::OUT_OF_LINE_OFFSET, ArchivedString},
+    };
+
+    unsafe impl Patchable for ArchivedString {
+        fn relocations(&self, f: &mut dyn FnMut(Relocation)) {
+            if !self.repr.is_inline() {
+                f(Relocation::LittleEndianOffset(
+                    offset_of!(ArchivedString, repr) + OUT_OF_LINE_OFFSET,
+                ));
+            }
+        }
+    }
 }
@@ -6,6 +6,7 @@ use rancor::{Error, Panic, ResultExt as _};
 
 use crate::{
     primitive::{ArchivedUsize, FixedIsize},
+    rel_ptr::OffsetSlot,
     Portable,
 };
 
@@ -23,6 +24,23 @@ struct OutOfLineRepr {
     _phantom: PhantomPinned,
 }
 
+/// The position of the little-endian offset of an out-of-line string.
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+pub(crate) const OUT_OF_LINE_OFFSET: usize =
+    mem::offset_of!(OutOfLineRepr, offset);
+
+// The sign bit of the offset marks the representation as out-of-line, so only
+// negative offsets can be written.
+fn write_out_of_line_offset(offset: isize, out: &mut [u8]) -> bool {
+    match FixedIsize::try_from(offset) {
+        Ok(offset) if offset < 0 && out.len() == OFFSET_BYTES => {
+            out.copy_from_slice(&offset.to_le_bytes());
+            true
+        }
+        _ => false,
+    }
+}
+
 /// The maximum number of bytes that can be inlined.
 pub const INLINE_CAPACITY: usize = mem::size_of::<OutOfLineRepr>() - 1;
 
@@ -60,6 +78,23 @@ impl ArchivedStringRepr {
         FixedIsize::from_le_bytes(self.out_of_line.offset) as isize
     }
 
+    /// Returns the slot which stores the offset of the representation, or
+    /// `None` if it is inline.
+    #[inline]
+    pub fn offset_slot(&self) -> Option<OffsetSlot> {
+        if self.is_inline() {
+            return None;
+        }
+        let base = (self as *const Self).cast::<u8>();
+        Some(OffsetSlot::new(
+            base,
+            base.wrapping_add(mem::offset_of!(OutOfLineRepr, offset)),
+            OFFSET_BYTES,
+            unsafe { self.out_of_line_offset() },
+            write_out_of_line_offset,
+        ))
+    }
+
     /// Returns a pointer to the bytes of the string.
     #[inline]
     pub fn as_ptr(&self) -> *const u8 {
@@ -252,3 +287,143 @@ const _: () = {
         }
     }
 };
+
+#[cfg(feature = "alloc")]
+mod layout {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec};
+    use core::mem::offset_of;
+
+    use super::{InlineRepr, OutOfLineRepr, OFFSET_BYTES};
+    use crate::{
+        layout::{
+            hash_structure, DescribeLayout, FieldLayout, LayoutKind,
+            LayoutRegistry, StructuralHash, TypeLayout,
+        },
+        primitive::ArchivedUsize,
+        string::ArchivedString,
+    };
+
+    impl DescribeLayout for OutOfLineRepr {
+        fn layout_name() -> String {
+            "ArchivedStringOutOfLine".into()
+        }
+
+        fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+            let fields = vec![
+                FieldLayout::new(
+                    "len",
+                    offset_of!(Self, len),
+                    registry.register::<ArchivedUsize>(),
+                ),
+                FieldLayout::new(
+                    "offset",
+                    offset_of!(Self, offset),
+                    registry.register::<[u8; OFFSET_BYTES]>(),
+                ),
+            ];
+            TypeLayout::of::<Self>(
+                Self::layout_name(),
+                LayoutKind::Struct { fields },
+            )
+            .with_description(
+                "`offset` is a little-endian signed offset from the start of \
+                 the string to its `len` bytes, regardless of the endianness \
+                 of the archive.",
+            )
+        }
+    }
+
+    // SAFETY: The hash covers the offsets and structures of the fields.
+    unsafe impl StructuralHash for OutOfLineRepr {
+        const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+            "struct",
+            &[
+                offset_of!(Self, len) as u64,
+                ArchivedUsize::STRUCTURAL_HASH,
+                offset_of!(Self, offset) as u64,
+                <[u8; OFFSET_BYTES]>::STRUCTURAL_HASH,
+            ],
+        );
+    }
+
+    impl DescribeLayout for InlineRepr {
+        fn layout_name() -> String {
+            "ArchivedStringInline".into()
+        }
+
+        fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+            let fields = vec![
+                FieldLayout::new(
+                    "bytes",
+                    offset_of!(Self, bytes),
+                    registry.register::<[u8; super::INLINE_CAPACITY]>(),
+                ),
+                FieldLayout::new(
+                    "len",
+                    offset_of!(Self, len),
+                    registry.register::<u8>(),
+                ),
+            ];
+            TypeLayout::of::<Self>(
+                Self::layout_name(),
+                LayoutKind::Struct { fields },
+            )
+        }
+    }
+
+    // SAFETY: The hash covers the offsets and structures of the fields.
+    unsafe impl StructuralHash for InlineRepr {
+        const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+            "struct",
+            &[
+                offset_of!(Self, bytes) as u64,
+                <[u8; super::INLINE_CAPACITY]>::STRUCTURAL_HASH,
+                offset_of!(Self, len) as u64,
+                u8::STRUCTURAL_HASH,
+            ],
+        );
+    }
+
+    impl DescribeLayout for ArchivedString {
+        fn layout_name() -> String {
+            "ArchivedString".into()
+        }
+
+        fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+            let fields = vec![
+                FieldLayout::new(
+                    "out_of_line_repr",
+                    0,
+                    registry.register::<OutOfLineRepr>(),
+                ),
+                FieldLayout::new(
+                    "inline_repr",
+                    0,
+                    registry.register::<InlineRepr>(),
+                ),
+            ];
+            TypeLayout::of::<Self>(
+                Self::layout_name(),
+                LayoutKind::Union { fields },
+            )
+            .with_description(
+                "The string is stored inline if the high bit of \
+                 `inline_repr.len` is clear, and out-of-line otherwise.",
+            )
+        }
+    }
+
+    // SAFETY: The hash covers the structures of both representations.
+    unsafe impl StructuralHash for ArchivedString {
+        const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+            "union",
+            &[
+                0,
+                OutOfLineRepr::STRUCTURAL_HASH,
+                0,
+                InlineRepr::STRUCTURAL_HASH,
+            ],
+        );
+    }
+}
@@ -0,0 +1,27 @@
+//! Archived versions of `sync` types.
+
+use crate::Portable;
+
+/// An archived [`Ordering`](::core::sync::atomic::Ordering).
+///
+/// The discriminants of the archived variants are fixed and do not depend on
+/// the declaration order of `Ordering`. Because `Ordering` is non-exhaustive,
+/// any memory ordering which is added to it in the future is archived as
+/// `SeqCst`, the strongest ordering.
+#[derive(Portable)]
+#[archive(crate)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u8)]
+pub enum ArchivedAtomicOrdering {
+    /// No ordering constraints, only atomic operations.
+    Relaxed = 0,
+    /// Release ordering for stores.
+    Release = 1,
+    /// Acquire ordering for loads.
+    Acquire = 2,
+    /// Both acquire and release ordering.
+    AcqRel = 3,
+    /// Sequentially consistent ordering.
+    SeqCst = 4,
+}
@@ -0,0 +1,104 @@
+//! Archived versions of `task` types.
+
+use core::{cmp::Ordering, hash, task::Poll};
+
+use crate::Portable;
+
+/// An archived [`Poll`] that indicates whether a value is available
+/// ([`Ready`](ArchivedPoll::Ready)) or not
+/// ([`Pending`](ArchivedPoll::Pending)).
+///
+/// The tag of `Ready` is always 0 and the tag of `Pending` is always 1.
+#[derive(Debug, Portable)]
+#[archive(crate)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+#[repr(u8)]
+pub enum ArchivedPoll<T> {
+    /// Represents that a value is immediately ready.
+    Ready(T) = 0,
+    /// Represents that a value is not ready yet.
+    Pending = 1,
+}
+
+impl<T> ArchivedPoll<T> {
+    /// Returns `true` if the poll is a [`Ready`](ArchivedPoll::Ready) value.
+    #[inline]
+    pub const fn is_ready(&self) -> bool {
+        matches!(self, ArchivedPoll::Ready(_))
+    }
+
+    /// Returns `true` if the poll is a [`Pending`](ArchivedPoll::Pending)
+    /// value.
+    #[inline]
+    pub const fn is_pending(&self) -> bool {
+        matches!(self, ArchivedPoll::Pending)
+    }
+
+    /// Converts from `&ArchivedPoll<T>` to `Poll<&T>`.
+    #[inline]
+    pub fn as_ref(&self) -> Poll<&T> {
+        match self {
+            ArchivedPoll::Ready(value) => Poll::Ready(value),
+            ArchivedPoll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Converts from `&mut ArchivedPoll<T>` to `Poll<&mut T>`.
+    #[inline]
+    pub fn as_mut(&mut self) -> Poll<&mut T> {
+        match self {
+            ArchivedPoll::Ready(value) => Poll::Ready(value),
+            ArchivedPoll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T: Eq> Eq for ArchivedPoll<T> {}
+
+impl<T: hash::Hash> hash::Hash for ArchivedPoll<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
+impl<T: Ord> Ord for ArchivedPoll<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_ref().cmp(&other.as_ref())
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArchivedPoll<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref().eq(&other.as_ref())
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for ArchivedPoll<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_ref().partial_cmp(&other.as_ref())
+    }
+}
+
+impl<T, U: PartialEq<T>> PartialEq<Poll<T>> for ArchivedPoll<U> {
+    #[inline]
+    fn eq(&self, other: &Poll<T>) -> bool {
+        match (self, other) {
+            (ArchivedPoll::Ready(self_value), Poll::Ready(other_value)) => {
+                self_value.eq(other_value)
+            }
+            (ArchivedPoll::Pending, Poll::Pending) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedPoll<T>> for Poll<U> {
+    #[inline]
+    fn eq(&self, other: &ArchivedPoll<T>) -> bool {
+        other.eq(self)
+    }
+}
@@ -0,0 +1,396 @@
+//! Round-trip testing helpers for archivable types.
+//!
+//! These perform every step of a round trip and check the result at each one:
+//!
+//! 1. The value is serialized.
+//! 2. The archive is validated with [`access`].
+//! 3. The archived value is compared against the original value (only for the
+//!    functions that require `T::Archived: PartialEq<T>`).
+//! 4. The archived value is deserialized and the result is compared against
+//!    the original value.
+//!
+//! When a comparison fails, the panic message includes a hex dump of the
+//! archive around the first byte which differs from an archive of the
+//! deserialized value.
+//!
+//! [`arbitrary_roundtrip`] runs round trips on values generated by `proptest`:
+//!
+//! ```
+//! use rkyv::test_util::arbitrary_roundtrip;
+//!
+//! arbitrary_roundtrip::<Vec<Option<u32>>>();
+//! ```
+
+use core::fmt::{self, Debug, Write as _};
+
+use bytecheck::CheckBytes;
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    test_runner::{Config, TestCaseError, TestRunner},
+};
+use rancor::{BoxedError, Strategy};
+
+use crate::{
+    access,
+    de::Unify,
+    deserialize,
+    ser::{AllocSerializer, Writer},
+    util::serialize_into,
+    validation::validators::DefaultValidator,
+    Archive, Deserialize, Portable, Serialize,
+};
+
+const SCRATCH_SIZE: usize = 256;
+const DUMP_RADIUS: usize = 32;
+
+/// The serializer used by [`roundtrip`].
+pub type TestSerializer = AllocSerializer<SCRATCH_SIZE>;
+
+/// The deserializer used by all round-trip functions.
+pub type TestDeserializer = Unify;
+
+/// Serializes, validates, accesses, and deserializes the given value, checking
+/// the archived and deserialized values against it.
+///
+/// # Panics
+///
+/// Panics with a descriptive message if any step fails.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::test_util::roundtrip;
+///
+/// roundtrip(&vec![Some(1u8), None, Some(3)]);
+/// ```
+pub fn roundtrip<T>(value: &T)
+where
+    T: Debug + PartialEq + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Debug
+        + PartialEq<T>
+        + Portable
+        + CheckBytes<Strategy<DefaultValidator, BoxedError>>
+        + Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+{
+    if let Err(e) = try_roundtrip(value) {
+        panic!("{}", e);
+    }
+}
+
+/// Performs a round trip like [`roundtrip`], but without comparing the
+/// archived value to the original value.
+///
+/// This is useful for types which don't implement `PartialEq` between their
+/// archived and unarchived forms.
+pub fn roundtrip_without_archived_eq<T>(value: &T)
+where
+    T: Debug + PartialEq + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Portable
+        + CheckBytes<Strategy<DefaultValidator, BoxedError>>
+        + Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+{
+    let result = serialize_into(value, TestSerializer::default())
+        .map_err(|e| RoundtripError::serialize(value, e))
+        .and_then(|serializer| {
+            let bytes = serializer.into_writer();
+            check_bytes::<T, _>(value, &bytes, |_| Ok(()))
+        });
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
+
+/// Performs a round trip like [`roundtrip`] with a custom serializer.
+///
+/// `bytes` is called with the serializer after serialization to get the
+/// serialized bytes. When a comparison fails, the archive of the deserialized
+/// value is produced with a [`TestSerializer`].
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     ser::{CoreSerializer, Positional as _},
+///     test_util::roundtrip_with_serializer,
+/// };
+///
+/// roundtrip_with_serializer(
+///     &(1u32, 2u64),
+///     CoreSerializer::<256, 256>::default(),
+///     |s| &s.writer.inner()[..s.pos()],
+/// );
+/// ```
+pub fn roundtrip_with_serializer<T, S, F>(value: &T, serializer: S, bytes: F)
+where
+    T: Debug
+        + PartialEq
+        + Serialize<Strategy<S, BoxedError>>
+        + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Debug
+        + PartialEq<T>
+        + Portable
+        + CheckBytes<Strategy<DefaultValidator, BoxedError>>
+        + Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+    S: Writer<BoxedError>,
+    F: FnOnce(&S) -> &[u8],
+{
+    let result = serialize_into(value, serializer)
+        .map_err(|e| RoundtripError::serialize(value, e))
+        .and_then(|serializer| {
+            check_bytes::<T, _>(value, bytes(&serializer), |archived| {
+                archived_eq(value, archived)
+            })
+        });
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
+
+/// Runs [`roundtrip`] on values generated by `proptest`.
+///
+/// Failing values are shrunk before the test panics.
+pub fn arbitrary_roundtrip<T>()
+where
+    T: Arbitrary
+        + Debug
+        + PartialEq
+        + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Debug
+        + PartialEq<T>
+        + Portable
+        + CheckBytes<Strategy<DefaultValidator, BoxedError>>
+        + Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+{
+    arbitrary_roundtrip_with_config::<T>(Config::default());
+}
+
+/// Runs [`roundtrip`] on values generated by `proptest` with the given
+/// configuration.
+pub fn arbitrary_roundtrip_with_config<T>(config: Config)
+where
+    T: Arbitrary
+        + Debug
+        + PartialEq
+        + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Debug
+        + PartialEq<T>
+        + Portable
+        + CheckBytes<Strategy<DefaultValidator, BoxedError>>
+        + Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+{
+    let mut runner = TestRunner::new(config);
+    let result = runner.run(&any::<T>(), |value| {
+        try_roundtrip(&value).map_err(|e| TestCaseError::fail(e.to_string()))
+    });
+    if let Err(e) = result {
+        panic!("{}\n{}", e, runner);
+    }
+}
+
+fn try_roundtrip<T>(value: &T) -> Result<(), RoundtripError>
+where
+    T: Debug + PartialEq + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Debug
+        + PartialEq<T>
+        + Portable
+        + CheckBytes<Strategy<DefaultValidator, BoxedError>>
+        + Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+{
+    let bytes = serialize_into(value, TestSerializer::default())
+        .map_err(|e| RoundtripError::serialize(value, e))?
+        .into_writer();
+    check_bytes::<T, _>(value, &bytes, |archived| archived_eq(value, archived))
+}
+
+fn archived_eq<T>(value: &T, archived: &T::Archived) -> Result<(), String>
+where
+    T: Archive + Debug,
+    T::Archived: Debug + PartialEq<T>,
+{
+    if archived == value {
+        Ok(())
+    } else {
+        Err(format!(
+            "archived value does not equal original value\n  original: \
+             {:?}\n  archived: {:?}",
+            value, archived,
+        ))
+    }
+}
+
+fn check_bytes<T, C>(
+    value: &T,
+    bytes: &[u8],
+    compare_archived: C,
+) -> Result<(), RoundtripError>
+where
+    T: Debug + PartialEq + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Portable
+        + CheckBytes<Strategy<DefaultValidator, BoxedError>>
+        + Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+    C: FnOnce(&T::Archived) -> Result<(), String>,
+{
+    let archived = access::<T::Archived, BoxedError>(bytes).map_err(|e| {
+        RoundtripError::new(
+            format!("failed to validate archive of {:?}: {}", value, e),
+            bytes,
+            None::<&T>,
+        )
+    })?;
+    check_archived(value, bytes, archived, compare_archived)
+}
+
+fn check_archived<T, C>(
+    value: &T,
+    bytes: &[u8],
+    archived: &T::Archived,
+    compare_archived: C,
+) -> Result<(), RoundtripError>
+where
+    T: Debug + PartialEq + Serialize<Strategy<TestSerializer, BoxedError>>,
+    T::Archived: Deserialize<T, Strategy<TestDeserializer, BoxedError>>,
+    C: FnOnce(&T::Archived) -> Result<(), String>,
+{
+    let deserialized = deserialize::<T, _, BoxedError>(
+        archived,
+        &mut TestDeserializer::default(),
+    )
+    .map_err(|e| {
+        RoundtripError::new(
+            format!("failed to deserialize archive of {:?}: {}", value, e),
+            bytes,
+            None::<&T>,
+        )
+    })?;
+
+    if let Err(message) = compare_archived(archived) {
+        return Err(RoundtripError::new(message, bytes, Some(&deserialized)));
+    }
+
+    if &deserialized != value {
+        return Err(RoundtripError::new(
+            format!(
+                "deserialized value does not equal original value\n  \
+                 original: {:?}\n  deserialized: {:?}",
+                value, deserialized,
+            ),
+            bytes,
+            Some(&deserialized),
+        ));
+    }
+
+    Ok(())
+}
+
+struct RoundtripError {
+    message: String,
+    dump: String,
+}
+
+impl RoundtripError {
+    fn serialize<T: Debug>(value: &T, error: BoxedError) -> Self {
+        Self {
+            message: format!("failed to serialize {:?}: {}", value, error),
+            dump: String::new(),
+        }
+    }
+
+    fn new<T>(message: String, bytes: &[u8], reserialize: Option<&T>) -> Self
+    where
+        T: Serialize<Strategy<TestSerializer, BoxedError>>,
+    {
+        let other = reserialize.and_then(|value| {
+            serialize_into(value, TestSerializer::default())
+                .ok()
+                .map(|s| s.into_writer())
+        });
+        let dump = match other {
+            Some(other) => match first_difference(bytes, &other) {
+                Some(index) => format!(
+                    "archive differs from archive of deserialized value at \
+                     byte {:#x}:\n{}\narchive of deserialized value:\n{}",
+                    index,
+                    hex_dump(bytes, index),
+                    hex_dump(&other, index),
+                ),
+                None => format!(
+                    "archive is identical to archive of deserialized \
+                     value:\n{}",
+                    hex_dump(bytes, 0),
+                ),
+            },
+            None => format!("archive:\n{}", hex_dump(bytes, 0)),
+        };
+        Self { message, dump }
+    }
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "round trip failed: {}", self.message)?;
+        if !self.dump.is_empty() {
+            write!(f, "\n{}", self.dump)?;
+        }
+        Ok(())
+    }
+}
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b.iter())
+        .position(|(a, b)| a != b)
+        .or_else(|| (a.len() != b.len()).then(|| a.len().min(b.len())))
+}
+
+/// Formats the bytes within `DUMP_RADIUS` of `index` as rows of 16 bytes. The
+/// byte at `index` is surrounded by brackets.
+fn hex_dump(bytes: &[u8], index: usize) -> String {
+    let start = index.saturating_sub(DUMP_RADIUS) & !0xf;
+    let end = usize::min(index + DUMP_RADIUS, bytes.len());
+
+    let mut result = String::new();
+    for row in (start..end).step_by(16) {
+        let _ = write!(result, "  {:08x}:", row);
+        let row_end = usize::min(row + 16, end);
+        for (i, byte) in bytes[row..row_end].iter().enumerate() {
+            let i = row + i;
+            if i == index {
+                let _ = write!(result, "[{:02x}]", byte);
+            } else if i == index + 1 {
+                let _ = write!(result, "{:02x}", byte);
+            } else {
+                let _ = write!(result, " {:02x}", byte);
+            }
+        }
+        result.push('\n');
+    }
+    if end < bytes.len() {
+        let _ = writeln!(result, "  ... {} more bytes", bytes.len() - end);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_difference, hex_dump};
+
+    #[test]
+    fn hex_dump_marks_index() {
+        let bytes = (0..64u8).collect::<Vec<_>>();
+        let dump = hex_dump(&bytes, 40);
+        assert!(dump.contains("00000020: 20 21 22 23 24 25 26 27[28]29 2a"));
+        assert!(dump.contains("00000030:"));
+        assert!(!dump.contains("more bytes"));
+
+        let dump = hex_dump(&bytes, 0);
+        assert!(dump.starts_with("  00000000:[00]01"));
+        assert!(dump.contains("32 more bytes"));
+    }
+
+    #[test]
+    fn finds_first_difference() {
+        assert_eq!(first_difference(&[1, 2, 3], &[1, 2, 3]), None);
+        assert_eq!(first_difference(&[1, 2, 3], &[1, 4, 3]), Some(1));
+        assert_eq!(first_difference(&[1, 2], &[1, 2, 3]), Some(2));
+    }
+}
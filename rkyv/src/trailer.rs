@@ -0,0 +1,346 @@
+//! Archives which record the position of their root object.
+//!
+//! Regular archives are accessed by assuming that the root object is located at
+//! the end of the buffer. That convention breaks when an archive is embedded in
+//! a larger file or padded out to a block size. Archives written with
+//! [`to_bytes_with_root_pointer`] end with a fixed-size trailer instead, which
+//! records where the root object is:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 8    | [`MAGIC`]                                              |
+//! | 8      | 8    | Position of the root object in the archive             |
+//! | 16     | 8    | Length of the archive, not including the trailer       |
+//! | 24     | 8    | Alignment required for the start of the archive        |
+//! | 32     | 8    | [XXH64](crate::hash::xxh64) checksum of bytes 0..32    |
+//!
+//! All fields are little-endian. Readers scan backward from the end of the
+//! buffer for the trailer, so any amount of padding up to a maximum may follow
+//! it. Any bytes may precede the archive as well, since the start of the
+//! archive is found from its length. Padding which happens to contain the magic
+//! bytes is skipped because its checksum doesn't match.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     rancor::Failure,
+//!     trailer::{access_self_describing, to_bytes_with_root_pointer},
+//!     util::AlignedVec,
+//!     Archive, Archived, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(check_bytes)]
+//! struct Config {
+//!     name: String,
+//!     retries: u32,
+//! }
+//!
+//! let value = Config {
+//!     name: "primary".to_string(),
+//!     retries: 3,
+//! };
+//! let mut bytes =
+//!     to_bytes_with_root_pointer::<_, 256, Failure>(&value).unwrap();
+//! // Pad the archive out to a block size
+//! bytes.resize(1024, 0);
+//!
+//! let archived =
+//!     access_self_describing::<Archived<Config>, Failure>(&bytes).unwrap();
+//! assert_eq!(archived.name, "primary");
+//! assert_eq!(archived.retries, 3);
+//! ```
+
+use core::{fmt, mem::size_of, ops::Range};
+
+#[cfg(feature = "bytecheck")]
+use bytecheck::CheckBytes;
+use rancor::{fail, Error, Strategy};
+
+#[cfg(feature = "bytecheck")]
+use crate::validation::{util::access_pos, validators::DefaultValidator};
+use crate::{
+    hash::xxh64,
+    ser::AllocSerializer,
+    util::{access_pos_unchecked, AlignedVec},
+    Portable, Serialize,
+};
+
+/// The magic bytes which start every root trailer.
+pub const MAGIC: [u8; 8] = *b"RKYVROOT";
+
+/// The size of a root trailer in bytes.
+pub const TRAILER_SIZE: usize = 40;
+
+/// The default maximum number of padding bytes which may follow a root
+/// trailer.
+pub const DEFAULT_MAX_PADDING: usize = 4096;
+
+/// The contents of a root trailer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RootTrailer {
+    /// The position of the root object within the archive.
+    pub root_pos: u64,
+    /// The length of the archive in bytes, not including the trailer.
+    pub len: u64,
+    /// The alignment required for the start of the archive.
+    pub align: u64,
+}
+
+impl RootTrailer {
+    /// Returns the trailer bytes for this root trailer.
+    pub fn to_bytes(&self) -> [u8; TRAILER_SIZE] {
+        let mut result = [0; TRAILER_SIZE];
+        result[0..8].copy_from_slice(&MAGIC);
+        result[8..16].copy_from_slice(&self.root_pos.to_le_bytes());
+        result[16..24].copy_from_slice(&self.len.to_le_bytes());
+        result[24..32].copy_from_slice(&self.align.to_le_bytes());
+        let checksum = xxh64(&result[0..32], 0);
+        result[32..40].copy_from_slice(&checksum.to_le_bytes());
+        result
+    }
+
+    /// Reads a root trailer from the given bytes.
+    ///
+    /// Returns `None` if the bytes don't start with [`MAGIC`], their checksum
+    /// doesn't match, or their fields are inconsistent.
+    pub fn from_bytes(bytes: &[u8; TRAILER_SIZE]) -> Option<Self> {
+        if bytes[0..8] != MAGIC
+            || read_u64(bytes, 32) != xxh64(&bytes[0..32], 0)
+        {
+            return None;
+        }
+
+        let result = Self {
+            root_pos: read_u64(bytes, 8),
+            len: read_u64(bytes, 16),
+            align: read_u64(bytes, 24),
+        };
+        if result.root_pos > result.len || !result.align.is_power_of_two() {
+            return None;
+        }
+        Some(result)
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut result = [0; 8];
+    result.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(result)
+}
+
+/// A root trailer found in a buffer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoundTrailer {
+    /// The contents of the trailer.
+    pub trailer: RootTrailer,
+    /// The range of the buffer occupied by the archive.
+    pub archive: Range<usize>,
+}
+
+impl FoundTrailer {
+    /// Returns the position of the root object within the archive.
+    #[inline]
+    pub fn root_pos(&self) -> usize {
+        self.trailer.root_pos as usize
+    }
+}
+
+/// An error which occurred while reading a root trailer.
+#[derive(Debug)]
+pub enum TrailerError {
+    /// No valid trailer was found within the last bytes of the buffer.
+    NotFound {
+        /// The length of the buffer.
+        len: usize,
+        /// The maximum number of padding bytes after the trailer.
+        max_padding: usize,
+    },
+    /// The start of the archive is not aligned as required by the trailer.
+    Unaligned {
+        /// The address of the start of the archive.
+        address: usize,
+        /// The alignment required by the trailer.
+        align: u64,
+    },
+    /// The root position is not the position of a root object of the expected
+    /// type.
+    InvalidRootPosition {
+        /// The root position in the trailer.
+        pos: u64,
+        /// The length of the archive.
+        len: u64,
+    },
+}
+
+impl fmt::Display for TrailerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrailerError::NotFound { len, max_padding } => write!(
+                f,
+                "no root trailer found in buffer of {} bytes with up to {} \
+                 bytes of padding",
+                len, max_padding,
+            ),
+            TrailerError::Unaligned { address, align } => write!(
+                f,
+                "archive starting at {:#x} is not aligned to {} bytes",
+                address, align,
+            ),
+            TrailerError::InvalidRootPosition { pos, len } => write!(
+                f,
+                "root position {} is invalid for an archive of {} bytes",
+                pos, len,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TrailerError {}
+
+/// Finds the root trailer at the end of the given bytes.
+///
+/// The trailer may be followed by up to `max_padding` bytes of padding. If more
+/// than one valid trailer is found, the last one is used.
+pub fn find_trailer(
+    bytes: &[u8],
+    max_padding: usize,
+) -> Result<FoundTrailer, TrailerError> {
+    let not_found = TrailerError::NotFound {
+        len: bytes.len(),
+        max_padding,
+    };
+    let last = bytes.len().checked_sub(TRAILER_SIZE).ok_or(not_found)?;
+    let first = last.saturating_sub(max_padding);
+
+    for pos in (first..=last).rev() {
+        if bytes[pos..pos + MAGIC.len()] != MAGIC {
+            continue;
+        }
+        let mut trailer = [0; TRAILER_SIZE];
+        trailer.copy_from_slice(&bytes[pos..pos + TRAILER_SIZE]);
+        let trailer = match RootTrailer::from_bytes(&trailer) {
+            Some(trailer) if trailer.len <= pos as u64 => trailer,
+            _ => continue,
+        };
+
+        let start = pos - trailer.len as usize;
+        let address = bytes.as_ptr().addr() + start;
+        if address & (trailer.align as usize - 1) != 0 {
+            return Err(TrailerError::Unaligned {
+                address,
+                align: trailer.align,
+            });
+        }
+        return Ok(FoundTrailer {
+            trailer,
+            archive: start..pos,
+        });
+    }
+
+    Err(TrailerError::NotFound {
+        len: bytes.len(),
+        max_padding,
+    })
+}
+
+/// Returns whether the given bytes end with a root trailer followed by at most
+/// [`DEFAULT_MAX_PADDING`] bytes of padding.
+pub fn is_self_describing(bytes: &[u8]) -> bool {
+    find_trailer(bytes, DEFAULT_MAX_PADDING).is_ok()
+}
+
+/// Finds the trailer and checks that its root position can hold a `T`.
+fn open<T, E: Error>(
+    bytes: &[u8],
+    max_padding: usize,
+) -> Result<(usize, &[u8]), E> {
+    let found = match find_trailer(bytes, max_padding) {
+        Ok(found) => found,
+        Err(e) => fail!(e),
+    };
+    let RootTrailer { root_pos, len, .. } = found.trailer;
+    let root_size = size_of::<T>() as u64;
+    let root_align = core::mem::align_of::<T>() as u64;
+    if root_size > len
+        || root_pos > len - root_size
+        || root_pos & (root_align - 1) != 0
+    {
+        fail!(TrailerError::InvalidRootPosition { pos: root_pos, len });
+    }
+    Ok((found.root_pos(), &bytes[found.archive]))
+}
+
+/// Serializes the given value and returns the resulting bytes followed by a
+/// root trailer.
+///
+/// The const generic parameter `N` specifies the number of bytes to
+/// pre-allocate as scratch space.
+pub fn to_bytes_with_root_pointer<T, const N: usize, E>(
+    value: &T,
+) -> Result<AlignedVec, E>
+where
+    T: Serialize<Strategy<AllocSerializer<N>, E>>,
+{
+    let mut bytes = crate::to_bytes::<T, N, E>(value)?;
+    let trailer = RootTrailer {
+        root_pos: (bytes.len() - size_of::<T::Archived>()) as u64,
+        len: bytes.len() as u64,
+        align: AlignedVec::ALIGNMENT as u64,
+    };
+    bytes.extend_from_slice(&trailer.to_bytes());
+    Ok(bytes)
+}
+
+/// Finds the root trailer at the end of the given bytes, then validates and
+/// accesses the archived root object.
+///
+/// The trailer may be followed by up to [`DEFAULT_MAX_PADDING`] bytes of
+/// padding. Use [`access_self_describing_with_padding`] to allow more padding.
+#[cfg(feature = "bytecheck")]
+pub fn access_self_describing<T, E>(bytes: &[u8]) -> Result<&T, E>
+where
+    T: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: Error,
+{
+    access_self_describing_with_padding::<T, E>(bytes, DEFAULT_MAX_PADDING)
+}
+
+/// Finds the root trailer at the end of the given bytes, allowing up to
+/// `max_padding` bytes of padding after it, then validates and accesses the
+/// archived root object.
+#[cfg(feature = "bytecheck")]
+pub fn access_self_describing_with_padding<T, E>(
+    bytes: &[u8],
+    max_padding: usize,
+) -> Result<&T, E>
+where
+    T: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: Error,
+{
+    let (pos, archive) = open::<T, E>(bytes, max_padding)?;
+    access_pos::<T, E>(archive, pos)
+}
+
+/// Finds the root trailer at the end of the given bytes, then accesses the
+/// archived root object without validating the archive.
+///
+/// The trailer may be followed by up to [`DEFAULT_MAX_PADDING`] bytes of
+/// padding. The trailer itself is always checked.
+///
+/// # Safety
+///
+/// The archive before the trailer must contain a valid `T` at the root
+/// position in the trailer.
+pub unsafe fn access_self_describing_unchecked<T, E>(
+    bytes: &[u8],
+) -> Result<&T, E>
+where
+    T: Portable,
+    E: Error,
+{
+    let (pos, archive) = open::<T, E>(bytes, DEFAULT_MAX_PADDING)?;
+    Ok(unsafe { access_pos_unchecked::<T>(archive, pos) })
+}
@@ -3,6 +3,7 @@
 use core::{alloc::Layout, hash::Hash};
 
 use crate::{
+    copy::CopyOptimization,
     ptr_meta::Pointee,
     rancor::Fallible,
     ser::{Writer, WriterExt as _},
@@ -206,6 +207,17 @@ pub unsafe trait Portable {}
 /// assert_eq!(archived.as_str(), STR_VAL);
 /// ```
 pub trait Archive {
+    /// An optimization flag that allows the bytes of this type to be copied
+    /// directly to a writer instead of calling `serialize` and `resolve` for
+    /// each value.
+    ///
+    /// Slices, vecs, boxed slices, and arrays of copy-optimized types are
+    /// serialized with a single write. The optimization is disabled by default
+    /// and can only be enabled safely for types which implement
+    /// [`ArchiveCopyOptimize`](crate::copy::ArchiveCopyOptimize).
+    const COPY_OPTIMIZATION: CopyOptimization<Self> =
+        CopyOptimization::disable();
+
     /// The archived representation of this type.
     ///
     /// In this form, the data can be used with zero-copy deserialization.
@@ -454,6 +466,16 @@ pub trait Deserialize<T, D: Fallible + ?Sized> {
 /// assert_eq!(archived_ref.tail, [1, 2, 3, 4]);
 /// ```
 pub trait ArchiveUnsized: Pointee {
+    /// An optimization flag which indicates that the archived representation
+    /// of this type is a byte-for-byte copy of it.
+    ///
+    /// This is enabled for sized types which enable
+    /// [`Archive::COPY_OPTIMIZATION`], slices of them, `str`, and `CStr`.
+    /// Content-keyed [`SharedPool`](crate::de::SharedPool)s only unify shared
+    /// pointers to types with this optimization enabled.
+    const UNSIZED_COPY_OPTIMIZATION: CopyOptimization<Self> =
+        CopyOptimization::disable();
+
     /// The archived counterpart of this type. Unlike `Archive`, it may be
     /// unsized.
     ///
@@ -0,0 +1,180 @@
+//! Mutable access to archived types without pinning.
+//!
+//! Archived types are mutated through `Pin<&mut T>` because moving a value
+//! which contains a [`RelPtr`](crate::RelPtr) would change what it points to.
+//! Archived types which don't contain any relative pointers can be moved
+//! freely, so they can be mutated through plain `&mut T` instead.
+
+use core::{marker::PhantomData, pin::Pin};
+
+use crate::{
+    primitive::{
+        ArchivedChar, ArchivedF32, ArchivedF64, ArchivedI128, ArchivedI16,
+        ArchivedI32, ArchivedI64, ArchivedNonZeroI128, ArchivedNonZeroI16,
+        ArchivedNonZeroI32, ArchivedNonZeroI64, ArchivedNonZeroU128,
+        ArchivedNonZeroU16, ArchivedNonZeroU32, ArchivedNonZeroU64,
+        ArchivedU128, ArchivedU16, ArchivedU32, ArchivedU64,
+    },
+    tuple::*,
+    Portable,
+};
+
+/// An archived type which does not contain any relative pointers.
+///
+/// Values of these types can be moved without invalidating the archive, so
+/// they may be mutated without pinning. Use [`unpin_mut`] to get a mutable
+/// reference from a pinned one, or one of the unpinned accessors like
+/// [`ArchivedVec::as_mut_slice`](crate::vec::ArchivedVec::as_mut_slice).
+///
+/// This trait is implemented for the archived primitives, arrays and tuples of
+/// types which implement it, and [`ArchivedOption`](crate::option::ArchivedOption)s
+/// of types which implement it. The archived types generated by
+/// `#[derive(Archive)]` implement it when all of their fields do.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{unpin::ArchivedNoRelPtrs, Archive, Archived};
+///
+/// #[derive(Archive)]
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// fn assert_no_rel_ptrs<T: ArchivedNoRelPtrs>() {}
+/// assert_no_rel_ptrs::<Archived<Point>>();
+/// ```
+///
+/// Archived types which contain relative pointers, even indirectly, don't
+/// implement it:
+///
+/// ```compile_fail
+/// use rkyv::{unpin::ArchivedNoRelPtrs, Archive, Archived};
+///
+/// #[derive(Archive)]
+/// struct Named {
+///     id: u32,
+///     name: Option<String>,
+/// }
+///
+/// fn assert_no_rel_ptrs<T: ArchivedNoRelPtrs>() {}
+/// assert_no_rel_ptrs::<Archived<Named>>();
+/// ```
+///
+/// # Safety
+///
+/// `Self` must not contain any [`RelPtr`](crate::RelPtr)s, and must not rely
+/// on its address in any other way.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` may contain relative pointers",
+    label = "`{Self}` does not implement `ArchivedNoRelPtrs`",
+    note = "only archived types without relative pointers can be mutated \
+            without pinning"
+)]
+pub unsafe trait ArchivedNoRelPtrs: Portable {}
+
+/// Returns a mutable reference to a pinned archived value which does not
+/// contain any relative pointers.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access_unchecked_mut, rancor::Failure, to_bytes, unpin::unpin_mut,
+///     Archive, Archived, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Counter {
+///     hits: u32,
+/// }
+///
+/// let mut bytes =
+///     to_bytes::<_, 256, Failure>(&Counter { hits: 1 }).unwrap();
+/// let counter = unpin_mut(unsafe {
+///     access_unchecked_mut::<Archived<Counter>>(&mut bytes)
+/// });
+/// counter.hits += 1;
+/// assert_eq!(counter.hits, 2);
+/// ```
+#[inline]
+pub fn unpin_mut<T: ArchivedNoRelPtrs + ?Sized>(value: Pin<&mut T>) -> &mut T {
+    // SAFETY: `T` does not contain any relative pointers, so moving it out of
+    // the returned reference can't invalidate the archive.
+    unsafe { Pin::get_unchecked_mut(value) }
+}
+
+macro_rules! impl_no_rel_ptrs {
+    ($($type:ty),* $(,)?) => {
+        $(
+            // SAFETY: Primitives do not contain relative pointers.
+            unsafe impl ArchivedNoRelPtrs for $type {}
+        )*
+    };
+}
+
+impl_no_rel_ptrs!(
+    (),
+    bool,
+    i8,
+    u8,
+    core::num::NonZeroI8,
+    core::num::NonZeroU8,
+    ArchivedI16,
+    ArchivedI32,
+    ArchivedI64,
+    ArchivedI128,
+    ArchivedU16,
+    ArchivedU32,
+    ArchivedU64,
+    ArchivedU128,
+    ArchivedF32,
+    ArchivedF64,
+    ArchivedChar,
+    ArchivedNonZeroI16,
+    ArchivedNonZeroI32,
+    ArchivedNonZeroI64,
+    ArchivedNonZeroI128,
+    ArchivedNonZeroU16,
+    ArchivedNonZeroU32,
+    ArchivedNonZeroU64,
+    ArchivedNonZeroU128,
+);
+
+// SAFETY: `PhantomData` is zero-sized.
+unsafe impl<T: ?Sized> ArchivedNoRelPtrs for PhantomData<T> {}
+
+// SAFETY: Arrays only contain their elements.
+unsafe impl<T: ArchivedNoRelPtrs, const N: usize> ArchivedNoRelPtrs for [T; N] {}
+
+// SAFETY: Slices only contain their elements.
+unsafe impl<T: ArchivedNoRelPtrs> ArchivedNoRelPtrs for [T] {}
+
+macro_rules! impl_tuples {
+    ($($name:ident: $($type:ident)*;)*) => {
+        $(
+            // SAFETY: Archived tuples only contain their elements.
+            unsafe impl<$($type: ArchivedNoRelPtrs),*> ArchivedNoRelPtrs
+                for $name<$($type),*>
+            {
+            }
+        )*
+    };
+}
+
+impl_tuples! {
+    ArchivedTuple1: T0;
+    ArchivedTuple2: T0 T1;
+    ArchivedTuple3: T0 T1 T2;
+    ArchivedTuple4: T0 T1 T2 T3;
+    ArchivedTuple5: T0 T1 T2 T3 T4;
+    ArchivedTuple6: T0 T1 T2 T3 T4 T5;
+    ArchivedTuple7: T0 T1 T2 T3 T4 T5 T6;
+    ArchivedTuple8: T0 T1 T2 T3 T4 T5 T6 T7;
+    ArchivedTuple9: T0 T1 T2 T3 T4 T5 T6 T7 T8;
+    ArchivedTuple10: T0 T1 T2 T3 T4 T5 T6 T7 T8 T9;
+    ArchivedTuple11: T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10;
+    ArchivedTuple12: T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11;
+    ArchivedTuple13: T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12;
+}
@@ -1,6 +1,7 @@
 use core::borrow::{Borrow, BorrowMut};
 use core::{
     fmt,
+    mem::ManuallyDrop,
     ops::{Deref, DerefMut, Index, IndexMut},
     ptr::NonNull,
     slice,
@@ -21,6 +22,10 @@ use crate::{
 
 /// A vector of bytes that aligns its memory to 16 bytes.
 ///
+/// A larger alignment can be requested with
+/// [`with_alignment`](AlignedVec::with_alignment). This is useful for
+/// archives whose data is accessed with wide SIMD loads or direct I/O.
+///
 /// The alignment also applies to `ArchivedAlignedVec`, which is useful for
 /// aligning opaque bytes inside of an archived data type.
 ///
@@ -54,6 +59,7 @@ pub struct AlignedVec {
     ptr: NonNull<u8>,
     cap: usize,
     len: usize,
+    align: usize,
 }
 
 impl Drop for AlignedVec {
@@ -68,10 +74,10 @@ impl Drop for AlignedVec {
 }
 
 impl AlignedVec {
-    /// The alignment of the vector
+    /// The default alignment of the vector
     pub const ALIGNMENT: usize = 16;
 
-    /// Maximum capacity of the vector.
+    /// Maximum capacity of a vector with the default alignment.
     /// Dictated by the requirements of
     /// [`alloc::Layout`](https://doc.rust-lang.org/alloc/alloc/struct.Layout.html).
     /// "`size`, when rounded up to the nearest multiple of `align`, must not
@@ -95,9 +101,67 @@ impl AlignedVec {
             ptr: NonNull::dangling(),
             cap: 0,
             len: 0,
+            align: Self::ALIGNMENT,
+        }
+    }
+
+    /// Constructs a new, empty `AlignedVec` which aligns its memory to
+    /// `align` bytes.
+    ///
+    /// The vector will not allocate until elements are pushed into it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two or is less than
+    /// [`ALIGNMENT`](AlignedVec::ALIGNMENT).
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let mut vec = AlignedVec::with_alignment(64);
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// assert_eq!(vec.alignment(), 64);
+    /// assert_eq!(vec.as_ptr().align_offset(64), 0);
+    /// ```
+    #[inline]
+    pub fn with_alignment(align: usize) -> Self {
+        assert!(
+            align.is_power_of_two() && align >= Self::ALIGNMENT,
+            "`align` must be a power of two of at least 16"
+        );
+        assert!(
+            align <= (isize::MAX as usize + 1) >> 1,
+            "`align` is too large"
+        );
+        AlignedVec {
+            ptr: NonNull::dangling(),
+            cap: 0,
+            len: 0,
+            align,
         }
     }
 
+    /// Returns the alignment of the vector's memory.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// assert_eq!(AlignedVec::new().alignment(), AlignedVec::ALIGNMENT);
+    /// ```
+    #[inline]
+    pub fn alignment(&self) -> usize {
+        self.align
+    }
+
+    /// Returns the maximum capacity of the vector, which depends on its
+    /// alignment.
+    #[inline]
+    fn max_capacity(&self) -> usize {
+        isize::MAX as usize - (self.align - 1)
+    }
+
     /// Constructs a new, empty `AlignedVec` with the specified capacity.
     ///
     /// The vector will be able to hold exactly `capacity` bytes without
@@ -127,36 +191,98 @@ impl AlignedVec {
     /// ```
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
-        if capacity == 0 {
-            Self::new()
-        } else {
+        let mut result = Self::new();
+        if capacity != 0 {
             assert!(
                 capacity <= Self::MAX_CAPACITY,
                 "`capacity` cannot exceed isize::MAX - 15"
             );
-            let ptr = unsafe {
-                let layout = alloc::Layout::from_size_align_unchecked(
-                    capacity,
-                    Self::ALIGNMENT,
-                );
-                let ptr = alloc::alloc(layout);
-                if ptr.is_null() {
-                    alloc::handle_alloc_error(layout);
-                }
-                NonNull::new_unchecked(ptr)
-            };
-            Self {
-                ptr,
-                cap: capacity,
-                len: 0,
-            }
+            unsafe { result.change_capacity(capacity) };
         }
+        result
+    }
+
+    /// Constructs a new, empty `AlignedVec` with the specified capacity,
+    /// returning an error if the memory could not be allocated.
+    ///
+    /// This is like [`with_capacity`](AlignedVec::with_capacity), but returns
+    /// an error instead of panicking or aborting when `capacity` is too large
+    /// or the allocator fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let vec = AlignedVec::try_with_capacity(10).unwrap();
+    /// assert_eq!(vec.capacity(), 10);
+    ///
+    /// assert!(AlignedVec::try_with_capacity(usize::MAX).is_err());
+    /// ```
+    #[inline]
+    pub fn try_with_capacity(
+        capacity: usize,
+    ) -> Result<Self, AllocationFailed> {
+        let mut result = Self::new();
+        result.try_reserve_exact(capacity)?;
+        Ok(result)
+    }
+
+    /// Constructs a new, empty `AlignedVec` with the specified capacity which
+    /// aligns its memory to `align` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two or is less than
+    /// [`ALIGNMENT`](AlignedVec::ALIGNMENT), or if `capacity` is too large for
+    /// the alignment.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let vec = AlignedVec::with_capacity_and_alignment(10, 64);
+    /// assert_eq!(vec.capacity(), 10);
+    /// assert_eq!(vec.as_ptr().align_offset(64), 0);
+    /// ```
+    #[inline]
+    pub fn with_capacity_and_alignment(capacity: usize, align: usize) -> Self {
+        let mut result = Self::with_alignment(align);
+        if capacity != 0 {
+            assert!(
+                capacity <= result.max_capacity(),
+                "`capacity` cannot exceed isize::MAX - (align - 1)"
+            );
+            unsafe { result.change_capacity(capacity) };
+        }
+        result
+    }
+
+    /// Constructs a new `AlignedVec` containing a copy of the given bytes.
+    ///
+    /// The bytes are copied exactly once into an allocation with exactly
+    /// enough capacity to hold them. If `bytes` is empty, the vector will not
+    /// allocate.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::AlignedVec;
+    ///
+    /// let vec = AlignedVec::from_slice_min_copy(&[1, 2, 3, 4]);
+    /// assert_eq!(vec.as_slice(), &[1, 2, 3, 4]);
+    /// assert_eq!(vec.capacity(), 4);
+    /// assert_eq!(vec.as_ptr().align_offset(AlignedVec::ALIGNMENT), 0);
+    /// ```
+    #[inline]
+    pub fn from_slice_min_copy(bytes: &[u8]) -> Self {
+        let mut result = Self::with_capacity(bytes.len());
+        result.extend_from_slice(bytes);
+        result
     }
 
     #[inline]
     fn layout(&self) -> alloc::Layout {
         unsafe {
-            alloc::Layout::from_size_align_unchecked(self.cap, Self::ALIGNMENT)
+            alloc::Layout::from_size_align_unchecked(self.cap, self.align)
         }
     }
 
@@ -196,37 +322,44 @@ impl AlignedVec {
     /// # Safety
     ///
     /// - `new_cap` must be less than or equal to
-    ///   [`MAX_CAPACITY`](AlignedVec::MAX_CAPACITY)
+    ///   [`MAX_CAPACITY`](AlignedVec::MAX_CAPACITY), or `isize::MAX - (align -
+    ///   1)` for vectors with a larger alignment
     /// - `new_cap` must be greater than or equal to [`len()`](AlignedVec::len)
     #[inline]
     pub unsafe fn change_capacity(&mut self, new_cap: usize) {
-        debug_assert!(new_cap <= Self::MAX_CAPACITY);
+        if self.try_change_capacity(new_cap).is_err() {
+            alloc::handle_alloc_error(
+                alloc::Layout::from_size_align_unchecked(new_cap, self.align),
+            );
+        }
+    }
+
+    /// Changes the capacity of the vector to exactly `new_cap`, returning an
+    /// error and leaving the vector unchanged if the allocator fails.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`change_capacity`] apply.
+    ///
+    /// [`change_capacity`]: AlignedVec::change_capacity
+    unsafe fn try_change_capacity(
+        &mut self,
+        new_cap: usize,
+    ) -> Result<(), AllocationFailed> {
+        debug_assert!(new_cap <= self.max_capacity());
         debug_assert!(new_cap >= self.len);
 
         if new_cap > 0 {
             let new_ptr = if self.cap > 0 {
-                let new_ptr =
-                    alloc::realloc(self.ptr.as_ptr(), self.layout(), new_cap);
-                if new_ptr.is_null() {
-                    alloc::handle_alloc_error(
-                        alloc::Layout::from_size_align_unchecked(
-                            new_cap,
-                            Self::ALIGNMENT,
-                        ),
-                    );
-                }
-                new_ptr
+                alloc::realloc(self.ptr.as_ptr(), self.layout(), new_cap)
             } else {
-                let layout = alloc::Layout::from_size_align_unchecked(
-                    new_cap,
-                    Self::ALIGNMENT,
-                );
-                let new_ptr = alloc::alloc(layout);
-                if new_ptr.is_null() {
-                    alloc::handle_alloc_error(layout);
-                }
-                new_ptr
+                alloc::alloc(alloc::Layout::from_size_align_unchecked(
+                    new_cap, self.align,
+                ))
             };
+            if new_ptr.is_null() {
+                return Err(AllocationFailed { requested: new_cap });
+            }
             self.ptr = NonNull::new_unchecked(new_ptr);
             self.cap = new_cap;
         } else if self.cap > 0 {
@@ -234,6 +367,7 @@ impl AlignedVec {
             self.ptr = NonNull::dangling();
             self.cap = 0;
         }
+        Ok(())
     }
 
     /// Shrinks the capacity of the vector as much as possible.
@@ -427,6 +561,92 @@ impl AlignedVec {
         unsafe { self.grow_capacity_to(new_cap) };
     }
 
+    /// Tries to reserve capacity for at least `additional` more bytes to be
+    /// inserted into the given `AlignedVec`.
+    ///
+    /// This is like [`reserve`](AlignedVec::reserve), but returns an error
+    /// instead of panicking or aborting when the new capacity is too large or
+    /// the allocator fails. If an error is returned, the vector is unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let mut vec = AlignedVec::new();
+    /// vec.push(1);
+    /// vec.try_reserve(10).unwrap();
+    /// assert!(vec.capacity() >= 11);
+    ///
+    /// assert!(vec.try_reserve(usize::MAX).is_err());
+    /// assert_eq!(vec.as_slice(), &[1]);
+    /// ```
+    #[inline]
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), AllocationFailed> {
+        let remaining = self.cap.wrapping_sub(self.len);
+        if additional > remaining {
+            let requested = self.len.saturating_add(additional);
+            let new_cap = self
+                .grown_capacity(requested)
+                .ok_or(AllocationFailed { requested })?;
+            unsafe { self.try_change_capacity(new_cap) }?;
+        }
+        Ok(())
+    }
+
+    /// Tries to reserve the minimum capacity for exactly `additional` more
+    /// bytes to be inserted into the given `AlignedVec`.
+    ///
+    /// This is like [`reserve_exact`](AlignedVec::reserve_exact), but returns
+    /// an error instead of panicking or aborting when the new capacity is too
+    /// large or the allocator fails. If an error is returned, the vector is
+    /// unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let mut vec = AlignedVec::new();
+    /// vec.try_reserve_exact(10).unwrap();
+    /// assert_eq!(vec.capacity(), 10);
+    /// ```
+    #[inline]
+    pub fn try_reserve_exact(
+        &mut self,
+        additional: usize,
+    ) -> Result<(), AllocationFailed> {
+        let requested = self.len.saturating_add(additional);
+        if requested > self.cap {
+            if requested > self.max_capacity() {
+                return Err(AllocationFailed { requested });
+            }
+            unsafe { self.try_change_capacity(requested) }?;
+        }
+        Ok(())
+    }
+
+    /// Returns the capacity that the vector grows to in order to hold at
+    /// least `new_cap` bytes, or `None` if it would exceed the maximum
+    /// capacity.
+    #[inline]
+    fn grown_capacity(&self, new_cap: usize) -> Option<usize> {
+        if new_cap > (isize::MAX as usize + 1) >> 1 {
+            // Rounding up to next power of 2 would result in `isize::MAX + 1`
+            // or higher, which exceeds max capacity. So cap at max
+            // instead.
+            if new_cap <= self.max_capacity() {
+                Some(self.max_capacity())
+            } else {
+                None
+            }
+        } else {
+            // Cannot overflow due to check above
+            Some(new_cap.next_power_of_two())
+        }
+    }
+
     /// Grows total capacity of vector to `new_cap` or more.
     ///
     /// Capacity after this call will be `new_cap` rounded up to next power of
@@ -467,19 +687,9 @@ impl AlignedVec {
     pub unsafe fn grow_capacity_to(&mut self, new_cap: usize) {
         debug_assert!(new_cap > self.cap);
 
-        let new_cap = if new_cap > (isize::MAX as usize + 1) >> 1 {
-            // Rounding up to next power of 2 would result in `isize::MAX + 1`
-            // or higher, which exceeds max capacity. So cap at max
-            // instead.
-            assert!(
-                new_cap <= Self::MAX_CAPACITY,
-                "cannot reserve a larger AlignedVec"
-            );
-            Self::MAX_CAPACITY
-        } else {
-            // Cannot overflow due to check above
-            new_cap.next_power_of_two()
-        };
+        let new_cap = self
+            .grown_capacity(new_cap)
+            .expect("cannot reserve a larger AlignedVec");
         self.change_capacity(new_cap);
     }
 
@@ -687,7 +897,7 @@ impl AlignedVec {
             .expect("cannot reserve a larger AlignedVec");
         if new_cap > self.cap {
             assert!(
-                new_cap <= Self::MAX_CAPACITY,
+                new_cap <= self.max_capacity(),
                 "cannot reserve a larger AlignedVec"
             );
             unsafe { self.change_capacity(new_cap) };
@@ -763,6 +973,10 @@ impl AlignedVec {
     /// This method reallocates and copies the underlying bytes. Any excess
     /// capacity is dropped.
     ///
+    /// The allocation can't be handed to the `Vec` as-is because it must be
+    /// deallocated with the same alignment it was allocated with, and `Vec`
+    /// always deallocates with an alignment of 1.
+    ///
     /// # Examples
     /// ```
     /// use rkyv::AlignedVec;
@@ -778,6 +992,152 @@ impl AlignedVec {
     pub fn into_vec(self) -> Vec<u8> {
         Vec::from(self.as_ref())
     }
+
+    /// Decomposes the vector into its raw parts: a pointer to its memory, its
+    /// length, and its capacity.
+    ///
+    /// After calling this function, the caller is responsible for the memory
+    /// previously managed by the vector. If the capacity is not zero, the
+    /// memory was allocated with the global allocator using a layout with a
+    /// size of the capacity and an alignment of [`alignment()`]. The only way
+    /// to free it is to convert it back into an `AlignedVec` with
+    /// [`from_raw_parts`] or [`from_raw_parts_with_alignment`], or to
+    /// deallocate it with that same layout.
+    ///
+    /// The alignment is not part of the returned parts. Vectors with a larger
+    /// alignment than [`ALIGNMENT`] must record it separately.
+    ///
+    /// [`alignment()`]: AlignedVec::alignment
+    /// [`from_raw_parts`]: AlignedVec::from_raw_parts
+    /// [`from_raw_parts_with_alignment`]: AlignedVec::from_raw_parts_with_alignment
+    /// [`ALIGNMENT`]: AlignedVec::ALIGNMENT
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let mut vec = AlignedVec::with_capacity(8);
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    ///
+    /// let (ptr, len, cap) = vec.into_raw_parts();
+    /// assert_eq!((len, cap), (3, 8));
+    ///
+    /// // SAFETY: The parts were returned from `into_raw_parts` on a vector
+    /// // with the default alignment.
+    /// let vec = unsafe { AlignedVec::from_raw_parts(ptr, len, cap) };
+    /// assert_eq!(vec.as_slice(), &[1, 2, 3]);
+    /// ```
+    #[inline]
+    pub fn into_raw_parts(self) -> (NonNull<u8>, usize, usize) {
+        let vec = ManuallyDrop::new(self);
+        (vec.ptr, vec.len, vec.cap)
+    }
+
+    /// Creates an `AlignedVec` with the default alignment directly from a
+    /// pointer, a length, and a capacity.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`from_raw_parts_with_alignment`] apply, with
+    /// an alignment of [`ALIGNMENT`].
+    ///
+    /// [`from_raw_parts_with_alignment`]: AlignedVec::from_raw_parts_with_alignment
+    /// [`ALIGNMENT`]: AlignedVec::ALIGNMENT
+    #[inline]
+    pub unsafe fn from_raw_parts(
+        ptr: NonNull<u8>,
+        len: usize,
+        capacity: usize,
+    ) -> Self {
+        Self::from_raw_parts_with_alignment(ptr, len, capacity, Self::ALIGNMENT)
+    }
+
+    /// Creates an `AlignedVec` which aligns its memory to `align` bytes
+    /// directly from a pointer, a length, and a capacity.
+    ///
+    /// # Safety
+    ///
+    /// - `align` must be a power of two of at least [`ALIGNMENT`], and
+    ///   `capacity` must be at most `isize::MAX - (align - 1)`.
+    /// - If `capacity` is not zero, `ptr` must have been allocated with the
+    ///   global allocator using a layout with a size of `capacity` and an
+    ///   alignment of `align`. Otherwise, `ptr` is ignored.
+    /// - `len` must be less than or equal to `capacity`, and the first `len`
+    ///   bytes at `ptr` must be initialized.
+    /// - The memory must not be used or freed through any other pointer
+    ///   afterward.
+    ///
+    /// These are all upheld by parts returned from
+    /// [`into_raw_parts`](AlignedVec::into_raw_parts) along with the
+    /// [`alignment()`](AlignedVec::alignment) of the vector.
+    ///
+    /// [`ALIGNMENT`]: AlignedVec::ALIGNMENT
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let mut vec = AlignedVec::with_alignment(64);
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    ///
+    /// let align = vec.alignment();
+    /// let (ptr, len, cap) = vec.into_raw_parts();
+    /// // SAFETY: The parts and alignment came from the same vector.
+    /// let mut vec = unsafe {
+    ///     AlignedVec::from_raw_parts_with_alignment(ptr, len, cap, align)
+    /// };
+    /// vec.push(4);
+    /// assert_eq!(vec.as_slice(), &[1, 2, 3, 4]);
+    /// assert_eq!(vec.as_ptr().align_offset(64), 0);
+    /// ```
+    #[inline]
+    pub unsafe fn from_raw_parts_with_alignment(
+        ptr: NonNull<u8>,
+        len: usize,
+        capacity: usize,
+        align: usize,
+    ) -> Self {
+        debug_assert!(align.is_power_of_two() && align >= Self::ALIGNMENT);
+        debug_assert!(len <= capacity);
+
+        Self {
+            ptr: if capacity == 0 {
+                NonNull::dangling()
+            } else {
+                ptr
+            },
+            cap: capacity,
+            len,
+            align,
+        }
+    }
+
+    /// Consumes and leaks the vector, returning a mutable reference to its
+    /// contents.
+    ///
+    /// The memory of the vector, including any excess capacity, is never
+    /// freed. The returned slice is aligned to the
+    /// [`alignment()`](AlignedVec::alignment) of the vector unless it is
+    /// empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::util::AlignedVec;
+    ///
+    /// let mut vec = AlignedVec::new();
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    ///
+    /// let bytes: &'static mut [u8] = vec.leak();
+    /// bytes[0] = 4;
+    /// assert_eq!(bytes, &[4, 2, 3]);
+    /// ```
+    #[inline]
+    pub fn leak(self) -> &'static mut [u8] {
+        let (ptr, len, _) = self.into_raw_parts();
+        // SAFETY: The vector was not dropped, so its first `len` bytes are
+        // initialized and are never freed.
+        unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), len) }
+    }
 }
 
 #[cfg(feature = "std")]
@@ -890,6 +1250,42 @@ const _: () = {
     }
 };
 
+/// An error resulting from failing to allocate memory.
+///
+/// This is returned by the fallible allocation methods of [`AlignedVec`], and
+/// by serializers and deserializers which report allocation failures instead
+/// of aborting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocationFailed {
+    requested: usize,
+}
+
+impl AllocationFailed {
+    /// Creates a new error for a failed allocation of `requested` bytes.
+    #[inline]
+    pub fn new(requested: usize) -> Self {
+        Self { requested }
+    }
+
+    /// Returns the number of bytes that were requested.
+    ///
+    /// For collections, this is the total capacity that was requested in
+    /// bytes, and may be an estimate.
+    #[inline]
+    pub fn requested(&self) -> usize {
+        self.requested
+    }
+}
+
+impl fmt::Display for AllocationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to allocate {} bytes", self.requested)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocationFailed {}
+
 impl From<AlignedVec> for Vec<u8> {
     #[inline]
     fn from(aligned: AlignedVec) -> Self {
@@ -897,6 +1293,23 @@ impl From<AlignedVec> for Vec<u8> {
     }
 }
 
+/// Copies the bytes of the `Vec` into a new `AlignedVec`.
+///
+/// The allocation of the `Vec` can't be reused even if it happens to be
+/// aligned, because it was allocated with an alignment of 1 and must be
+/// deallocated with the same alignment. The conversion fails if the new
+/// allocation fails.
+impl TryFrom<Vec<u8>> for AlignedVec {
+    type Error = AllocationFailed;
+
+    #[inline]
+    fn try_from(vec: Vec<u8>) -> Result<Self, Self::Error> {
+        let mut result = Self::try_with_capacity(vec.len())?;
+        result.extend_from_slice(&vec);
+        Ok(result)
+    }
+}
+
 impl Archive for AlignedVec {
     type Archived = ArchivedVec<u8>;
     type Resolver = VecResolver;
@@ -944,7 +1357,8 @@ impl Clone for AlignedVec {
     #[inline]
     fn clone(&self) -> Self {
         unsafe {
-            let mut result = AlignedVec::with_capacity(self.len);
+            let mut result =
+                AlignedVec::with_capacity_and_alignment(self.len, self.align);
             result.len = self.len;
             core::ptr::copy_nonoverlapping(
                 self.as_ptr(),
@@ -1043,7 +1457,7 @@ impl<S: Fallible + Allocator + Writer + ?Sized> Serialize<S> for AlignedVec {
         &self,
         serializer: &mut S,
     ) -> Result<Self::Resolver, S::Error> {
-        serializer.align(Self::ALIGNMENT)?;
+        serializer.align(self.align)?;
         ArchivedVec::<Archived<u8>>::serialize_from_slice(
             self.as_slice(),
             serializer,
@@ -0,0 +1,371 @@
+use core::fmt;
+
+#[cfg(feature = "bytecheck")]
+use bytecheck::CheckBytes;
+use rancor::{fail, Error, Strategy};
+
+#[cfg(feature = "bytecheck")]
+use crate::{
+    de::pooling::Unify as UnifyDeserializer,
+    validation::validators::DefaultValidator, Deserialize,
+};
+use crate::{
+    hash::xxh64,
+    ser::{
+        allocator::{BackupAllocator, BufferAllocator, GlobalAllocator},
+        sharing::Unify,
+        Composite,
+    },
+    util::{access_unchecked, serialize, AlignedVec},
+    Portable, Serialize,
+};
+
+/// The serializer used by an [`Archiver`].
+///
+/// Shared pointers are deduplicated if the share field is `Some`, and
+/// duplicated otherwise.
+pub type ArchiverSerializer = Composite<
+    AlignedVec,
+    BackupAllocator<BufferAllocator<AlignedVec>, GlobalAllocator>,
+    Option<Unify>,
+>;
+
+/// A checksum which is appended to archives by an [`Archiver`] and verified by
+/// a [`Reader`].
+///
+/// Checksums are written as little-endian integers immediately after the root
+/// object. Buffers with checksums must be accessed through a `Reader`
+/// configured with the same checksum.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Checksum {
+    /// No checksum.
+    #[default]
+    None,
+    /// A 64-bit [XXH64](crate::hash::xxh64) checksum with a seed of zero.
+    Xxh64,
+}
+
+impl Checksum {
+    /// Returns the number of bytes that the checksum occupies at the end of a
+    /// buffer.
+    #[inline]
+    pub const fn size(&self) -> usize {
+        match self {
+            Checksum::None => 0,
+            Checksum::Xxh64 => 8,
+        }
+    }
+
+    pub(crate) fn compute(&self, bytes: &[u8]) -> u64 {
+        match self {
+            Checksum::None => 0,
+            Checksum::Xxh64 => xxh64(bytes, 0),
+        }
+    }
+
+    fn append(&self, bytes: &mut AlignedVec) {
+        match self {
+            Checksum::None => (),
+            Checksum::Xxh64 => {
+                let checksum = self.compute(bytes);
+                bytes.extend_from_slice(&checksum.to_le_bytes());
+            }
+        }
+    }
+
+    fn verify<'a, E: Error>(&self, bytes: &'a [u8]) -> Result<&'a [u8], E> {
+        let size = self.size();
+        if size == 0 {
+            return Ok(bytes);
+        }
+
+        if bytes.len() < size {
+            fail!(ChecksumError::Missing { len: bytes.len() });
+        }
+        let (archive, trailer) = bytes.split_at(bytes.len() - size);
+        let mut expected = [0; 8];
+        expected.copy_from_slice(trailer);
+        let expected = u64::from_le_bytes(expected);
+        let actual = self.compute(archive);
+        if expected != actual {
+            fail!(ChecksumError::Mismatch { expected, actual });
+        }
+
+        Ok(archive)
+    }
+}
+
+#[derive(Debug)]
+enum ChecksumError {
+    Missing { len: usize },
+    Mismatch { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumError::Missing { len } => write!(
+                f,
+                "buffer of {} bytes is too small to contain a checksum",
+                len,
+            ),
+            ChecksumError::Mismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {:#018x} but computed {:#018x}",
+                expected, actual,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChecksumError {}
+
+#[derive(Debug)]
+struct NondeterministicOutput;
+
+impl fmt::Display for NondeterministicOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "serializing the same value twice produced different bytes"
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NondeterministicOutput {}
+
+/// A configurable, high-level interface for serializing values.
+///
+/// `Archiver` collects serialization options in one place and composes the
+/// serializer for them. Archives it produces can be read with a [`Reader`]
+/// configured with the same [`Checksum`].
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     util::{Archiver, Checksum, Reader},
+///     Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     name: String,
+///     values: Vec<u32>,
+/// }
+///
+/// let value = Example {
+///     name: "pi".to_string(),
+///     values: vec![3, 1, 4, 1, 5],
+/// };
+///
+/// let bytes = Archiver::new()
+///     .scratch_capacity(64 << 10)
+///     .deterministic(true)
+///     .checksum(Checksum::Xxh64)
+///     .to_bytes::<_, Failure>(&value)
+///     .unwrap();
+///
+/// let archived = Reader::new()
+///     .checksum(Checksum::Xxh64)
+///     .access::<ArchivedExample, Failure>(&bytes)
+///     .unwrap();
+/// assert_eq!(archived.name, "pi");
+/// assert_eq!(archived.values, [3, 1, 4, 1, 5]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Archiver {
+    scratch_capacity: usize,
+    share_pointers: bool,
+    deterministic: bool,
+    checksum: Checksum,
+}
+
+impl Default for Archiver {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Archiver {
+    /// The default amount of scratch space, in bytes.
+    pub const DEFAULT_SCRATCH_CAPACITY: usize = 1024;
+
+    /// Creates a new archiver with the default options.
+    ///
+    /// By default, archivers share pointers, do not check for determinism, and
+    /// do not append a checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            scratch_capacity: Self::DEFAULT_SCRATCH_CAPACITY,
+            share_pointers: true,
+            deterministic: false,
+            checksum: Checksum::None,
+        }
+    }
+
+    /// Sets the amount of scratch space to allocate up front.
+    ///
+    /// Scratch allocations which do not fit in this space are made on the heap
+    /// instead.
+    #[inline]
+    pub const fn scratch_capacity(mut self, capacity: usize) -> Self {
+        self.scratch_capacity = capacity;
+        self
+    }
+
+    /// Sets whether shared pointers like `Rc` and `Arc` are deduplicated.
+    ///
+    /// If disabled, each shared pointer serializes a separate copy of its
+    /// value.
+    #[inline]
+    pub const fn share_pointers(mut self, share_pointers: bool) -> Self {
+        self.share_pointers = share_pointers;
+        self
+    }
+
+    /// Sets whether the archiver checks that serialization is deterministic.
+    ///
+    /// Archived hash tables are always laid out independently of iteration
+    /// order, but custom `Serialize` implementations may still produce
+    /// different bytes for equal values. If enabled, the value is serialized
+    /// twice and an error is returned if the results differ.
+    #[inline]
+    pub const fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Sets the checksum to append to archives.
+    #[inline]
+    pub const fn checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Returns a new serializer configured with the options of this archiver.
+    pub fn serializer(&self) -> ArchiverSerializer {
+        let mut scratch = AlignedVec::with_capacity(self.scratch_capacity);
+        scratch.resize(self.scratch_capacity, 0);
+
+        Composite::new(
+            AlignedVec::new(),
+            BackupAllocator::new(
+                BufferAllocator::new(scratch),
+                GlobalAllocator::new(),
+            ),
+            self.share_pointers.then(Unify::new),
+        )
+    }
+
+    fn serialize<T, E>(&self, value: &T) -> Result<AlignedVec, E>
+    where
+        T: Serialize<Strategy<ArchiverSerializer, E>>,
+        E: Error,
+    {
+        let mut serializer = self.serializer();
+        serialize(value, &mut serializer)?;
+        Ok(serializer.into_writer())
+    }
+
+    /// Serializes the given value and returns the resulting bytes.
+    pub fn to_bytes<T, E>(&self, value: &T) -> Result<AlignedVec, E>
+    where
+        T: Serialize<Strategy<ArchiverSerializer, E>>,
+        E: Error,
+    {
+        let mut bytes = self.serialize(value)?;
+
+        if self.deterministic {
+            let again = self.serialize(value)?;
+            if bytes.as_slice() != again.as_slice() {
+                fail!(NondeterministicOutput);
+            }
+        }
+
+        self.checksum.append(&mut bytes);
+
+        Ok(bytes)
+    }
+}
+
+/// A configurable, high-level interface for accessing archives.
+///
+/// Readers verify the checksum of archives produced by an [`Archiver`] before
+/// accessing them. See [`Archiver`] for an example.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Reader {
+    checksum: Checksum,
+}
+
+impl Reader {
+    /// Creates a new reader which does not require a checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            checksum: Checksum::None,
+        }
+    }
+
+    /// Sets the checksum which archives are required to end with.
+    #[inline]
+    pub const fn checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Verifies the checksum of the given bytes and returns the bytes of the
+    /// archive without the checksum.
+    pub fn verify<'a, E: Error>(&self, bytes: &'a [u8]) -> Result<&'a [u8], E> {
+        self.checksum.verify(bytes)
+    }
+
+    /// Verifies the checksum of the given bytes, then validates and accesses
+    /// the archived value.
+    #[cfg(feature = "bytecheck")]
+    pub fn access<'a, T, E>(&self, bytes: &'a [u8]) -> Result<&'a T, E>
+    where
+        T: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        crate::access::<T, E>(self.verify(bytes)?)
+    }
+
+    /// Verifies the checksum of the given bytes, then accesses the archived
+    /// value without validating it.
+    ///
+    /// # Safety
+    ///
+    /// The bytes without the checksum must represent a valid archived `T` with
+    /// its root at the end of the archive.
+    pub unsafe fn access_unchecked<'a, T, E>(
+        &self,
+        bytes: &'a [u8],
+    ) -> Result<&'a T, E>
+    where
+        T: Portable,
+        E: Error,
+    {
+        let bytes = self.verify(bytes)?;
+        unsafe { Ok(access_unchecked::<T>(bytes)) }
+    }
+
+    /// Verifies the checksum of the given bytes, then validates and
+    /// deserializes the archived value.
+    #[cfg(feature = "bytecheck")]
+    pub fn from_bytes<T, E>(&self, bytes: &[u8]) -> Result<T, E>
+    where
+        T: crate::Archive,
+        T::Archived: CheckBytes<Strategy<DefaultValidator, E>>
+            + Deserialize<T, Strategy<UnifyDeserializer, E>>,
+        E: Error,
+    {
+        crate::from_bytes::<T, E>(self.verify(bytes)?)
+    }
+}
@@ -0,0 +1,322 @@
+use core::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use rancor::{fail, Error, Strategy};
+
+use crate::{
+    util::{
+        access_pos_unchecked, access_pos_unchecked_mut, AlignedVec, Archiver,
+        ArchiverSerializer,
+    },
+    Archive, Serialize,
+};
+
+// The next generation to give to a builder buffer. Every buffer and every
+// truncation gets a new generation, so handles never match another buffer.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[inline]
+fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// An error resulting from resolving a [`Handle`] which does not refer to a
+/// value in the current contents of a [`BuilderBuffer`].
+#[derive(Debug)]
+pub struct StaleHandle {
+    handle_generation: u64,
+    buffer_generation: u64,
+}
+
+impl StaleHandle {
+    /// Returns the generation of the buffer when the handle was created.
+    #[inline]
+    pub fn handle_generation(&self) -> u64 {
+        self.handle_generation
+    }
+
+    /// Returns the generation of the buffer that the handle was resolved in.
+    #[inline]
+    pub fn buffer_generation(&self) -> u64 {
+        self.buffer_generation
+    }
+}
+
+impl fmt::Display for StaleHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stale handle: the handle was created at generation {} but the \
+             buffer is at generation {}",
+            self.handle_generation, self.buffer_generation,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StaleHandle {}
+
+/// A handle to a value serialized into a [`BuilderBuffer`].
+///
+/// Handles store the position of the archived value and the generation of the
+/// buffer it was serialized into, and are resolved to references with
+/// [`BuilderBuffer::resolve`] and [`BuilderBuffer::resolve_mut`].
+pub struct Handle<T: ?Sized> {
+    pos: usize,
+    generation: u64,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: ?Sized> Handle<T> {
+    /// Returns the position of the archived value in the buffer.
+    ///
+    /// The position remains valid in the bytes returned by
+    /// [`BuilderBuffer::into_inner`].
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the generation of the buffer when the handle was created.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl<T: ?Sized> Clone for Handle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for Handle<T> {}
+
+impl<T: ?Sized> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("pos", &self.pos)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl<T: ?Sized> PartialEq for Handle<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.pos == other.pos && self.generation == other.generation
+    }
+}
+
+impl<T: ?Sized> Eq for Handle<T> {}
+
+/// A buffer which values can be serialized into one after another, and which
+/// hands out [`Handle`]s to them instead of references.
+///
+/// References into an `AlignedVec` are invalidated when it grows, so they
+/// can't be held while more values are serialized into it. Handles store
+/// positions instead, and are resolved to references only when they're
+/// needed. Growing the buffer moves its bytes but doesn't change their
+/// positions, so handles remain valid as values are appended.
+///
+/// Truncating or clearing the buffer removes bytes that handles may point to,
+/// and values serialized afterward may reuse their positions. Each of these
+/// moves the buffer to a new generation, and resolving a handle from an
+/// earlier generation returns a [`StaleHandle`] error. Generations are unique
+/// across all buffers, so resolving a handle in a different buffer also
+/// returns an error.
+///
+/// Values are serialized with an [`ArchiverSerializer`] configured with the
+/// default [`Archiver`] options.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{rancor::Failure, util::BuilderBuffer, Archive, Serialize};
+///
+/// #[derive(Archive, Serialize)]
+/// struct Counter {
+///     hits: u32,
+/// }
+///
+/// let mut buffer = BuilderBuffer::new();
+/// let first = buffer
+///     .serialize_and_handle::<_, Failure>(&Counter { hits: 0 })
+///     .unwrap();
+/// for _ in 0..100 {
+///     buffer
+///         .serialize_and_handle::<_, Failure>(&Counter { hits: 0 })
+///         .unwrap();
+/// }
+///
+/// let mut counter = buffer.resolve_mut::<_, Failure>(first).unwrap();
+/// counter.hits = (counter.hits.to_native() + 1).into();
+/// assert_eq!(buffer.resolve::<_, Failure>(first).unwrap().hits, 1);
+///
+/// buffer.truncate(first.pos());
+/// assert!(buffer.resolve::<_, Failure>(first).is_err());
+/// ```
+#[derive(Debug)]
+pub struct BuilderBuffer {
+    bytes: AlignedVec,
+    generation: u64,
+}
+
+impl Default for BuilderBuffer {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuilderBuffer {
+    /// Creates a new empty builder buffer.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates a new empty builder buffer with at least the given capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bytes: AlignedVec::with_capacity(capacity),
+            generation: next_generation(),
+        }
+    }
+
+    /// Returns the current generation of the buffer.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the number of bytes in the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns whether the buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the bytes in the buffer.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Consumes the buffer and returns its bytes.
+    #[inline]
+    pub fn into_inner(self) -> AlignedVec {
+        self.bytes
+    }
+
+    /// Serializes the given value at the end of the buffer and returns a
+    /// handle to it.
+    ///
+    /// If serialization fails, the buffer is returned to its previous length
+    /// and existing handles remain valid.
+    pub fn serialize_and_handle<T, E>(
+        &mut self,
+        value: &T,
+    ) -> Result<Handle<T>, E>
+    where
+        T: Serialize<Strategy<ArchiverSerializer, E>>,
+        E: Error,
+    {
+        let start = self.bytes.len();
+        let mut serializer = Archiver::new().serializer();
+        serializer.writer = core::mem::take(&mut self.bytes);
+        let result =
+            value.serialize_and_resolve(Strategy::wrap(&mut serializer));
+        self.bytes = serializer.into_writer();
+
+        match result {
+            Ok(pos) => Ok(Handle {
+                pos,
+                generation: self.generation,
+                _phantom: PhantomData,
+            }),
+            Err(error) => {
+                self.bytes.resize(start, 0);
+                Err(error)
+            }
+        }
+    }
+
+    fn check<T: ?Sized, E: Error>(&self, handle: Handle<T>) -> Result<(), E> {
+        if handle.generation != self.generation {
+            fail!(StaleHandle {
+                handle_generation: handle.generation,
+                buffer_generation: self.generation,
+            });
+        }
+        Ok(())
+    }
+
+    /// Resolves the given handle to a reference to its archived value.
+    ///
+    /// Returns a [`StaleHandle`] error if the handle was created for another
+    /// buffer or before the buffer was truncated.
+    #[inline]
+    pub fn resolve<T: Archive, E: Error>(
+        &self,
+        handle: Handle<T>,
+    ) -> Result<&T::Archived, E> {
+        self.check(handle)?;
+        // SAFETY: The generation of the handle matches, so the bytes at its
+        // position are the archived value it was created for and have only
+        // been changed through pinned references.
+        Ok(unsafe {
+            access_pos_unchecked::<T::Archived>(&self.bytes, handle.pos)
+        })
+    }
+
+    /// Resolves the given handle to a mutable reference to its archived value.
+    ///
+    /// Returns a [`StaleHandle`] error if the handle was created for another
+    /// buffer or before the buffer was truncated.
+    #[inline]
+    pub fn resolve_mut<T: Archive, E: Error>(
+        &mut self,
+        handle: Handle<T>,
+    ) -> Result<Pin<&mut T::Archived>, E> {
+        self.check(handle)?;
+        // SAFETY: The generation of the handle matches, so the bytes at its
+        // position are the archived value it was created for and have only
+        // been changed through pinned references.
+        Ok(unsafe {
+            access_pos_unchecked_mut::<T::Archived>(&mut self.bytes, handle.pos)
+        })
+    }
+
+    /// Shortens the buffer to the given length and moves it to a new
+    /// generation.
+    ///
+    /// All existing handles become stale, even those to values which are
+    /// still in the buffer. This does nothing if `len` is not less than the
+    /// length of the buffer.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.bytes.len() {
+            self.bytes.resize(len, 0);
+            self.generation = next_generation();
+        }
+    }
+
+    /// Removes all bytes from the buffer and moves it to a new generation.
+    ///
+    /// All existing handles become stale.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.generation = next_generation();
+    }
+}
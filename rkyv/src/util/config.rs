@@ -0,0 +1,242 @@
+use core::marker::PhantomData;
+
+use rancor::{Error, Strategy};
+
+use crate::{
+    de::pooling::Unify,
+    ser::{AllocSerializer, Writer},
+    util::{access_unchecked, deserialize, serialize_into, AlignedVec},
+    Archive, Deserialize, Serialize,
+};
+
+/// The serializer and error type used to serialize values with a
+/// configuration.
+///
+/// Implement this and [`DefaultDeserializer`] once on a configuration type,
+/// then serialize with [`to_bytes_with_config`] or with the `to_archive_bytes`
+/// methods generated by `#[archive(convenience(config = ...))]`.
+/// [`DefaultConfig`] is used when no configuration is given.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     ser::AllocSerializer,
+///     util::{to_bytes_with_config, AlignedVec, DefaultSerializer},
+/// };
+///
+/// struct Small;
+///
+/// impl DefaultSerializer for Small {
+///     type Error = Failure;
+///     type Serializer = AllocSerializer<64>;
+///
+///     fn serializer() -> Self::Serializer {
+///         AllocSerializer::default()
+///     }
+///
+///     fn into_bytes(serializer: Self::Serializer) -> AlignedVec {
+///         serializer.into_writer()
+///     }
+/// }
+///
+/// let bytes = to_bytes_with_config::<Small, _>(&vec![1, 2, 3]).unwrap();
+/// assert_eq!(bytes.len(), 20);
+/// ```
+pub trait DefaultSerializer {
+    /// The error type returned when serialization fails.
+    type Error: Error;
+    /// The serializer to serialize values with.
+    type Serializer: Writer<Self::Error>;
+
+    /// Returns a new serializer.
+    fn serializer() -> Self::Serializer;
+
+    /// Returns the bytes written to the given serializer.
+    fn into_bytes(serializer: Self::Serializer) -> AlignedVec;
+}
+
+/// The deserializer and error type used to deserialize values with a
+/// configuration.
+///
+/// See [`DefaultSerializer`] for more information.
+pub trait DefaultDeserializer {
+    /// The error type returned when validation or deserialization fails.
+    type Error: Error;
+    /// The deserializer to deserialize values with.
+    type Deserializer;
+
+    /// Returns a new deserializer.
+    fn deserializer() -> Self::Deserializer;
+}
+
+/// The error type of [`DefaultConfig`].
+///
+/// This is [`BoxedError`](rancor::BoxedError) with the `std` feature, and
+/// [`Failure`](rancor::Failure) without it.
+#[cfg(feature = "std")]
+pub type DefaultError = rancor::BoxedError;
+
+/// The error type of [`DefaultConfig`].
+///
+/// This is [`BoxedError`](rancor::BoxedError) with the `std` feature, and
+/// [`Failure`](rancor::Failure) without it.
+#[cfg(not(feature = "std"))]
+pub type DefaultError = rancor::Failure;
+
+/// A configuration which returns errors of type `E`, serializes with an
+/// [`AllocSerializer`] with `N` bytes of scratch space, and deserializes with
+/// a [`Unify`] deserializer.
+///
+/// These are the same serializer and deserializer as [`to_bytes`] and
+/// [`from_bytes`](crate::from_bytes) use.
+///
+/// [`to_bytes`]: crate::to_bytes
+pub struct StandardConfig<E, const N: usize> {
+    _phantom: PhantomData<fn() -> E>,
+}
+
+impl<E: Error, const N: usize> DefaultSerializer for StandardConfig<E, N> {
+    type Error = E;
+    type Serializer = AllocSerializer<N>;
+
+    #[inline]
+    fn serializer() -> Self::Serializer {
+        AllocSerializer::default()
+    }
+
+    #[inline]
+    fn into_bytes(serializer: Self::Serializer) -> AlignedVec {
+        serializer.into_writer()
+    }
+}
+
+impl<E: Error, const N: usize> DefaultDeserializer for StandardConfig<E, N> {
+    type Error = E;
+    type Deserializer = Unify;
+
+    #[inline]
+    fn deserializer() -> Self::Deserializer {
+        Unify::default()
+    }
+}
+
+/// The configuration used by [`to_bytes_default`], [`from_bytes_default`],
+/// and the methods generated by `#[archive(convenience)]`.
+///
+/// [`from_bytes_default`]: crate::from_bytes_default
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{Archive, Deserialize, Serialize};
+///
+/// #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+/// #[archive(convenience)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let point = Point { x: 1, y: 2 };
+/// let bytes = point.to_archive_bytes().unwrap();
+/// // SAFETY: The bytes are an archived `Point` with its root at the end.
+/// let deserialized = unsafe { Point::from_archive_bytes_unchecked(&bytes) };
+/// assert_eq!(deserialized.unwrap(), point);
+/// ```
+pub type DefaultConfig = StandardConfig<DefaultError, 1024>;
+
+/// Serializes the given value with the given configuration and returns the
+/// resulting bytes.
+#[inline]
+pub fn to_bytes_with_config<C, T>(value: &T) -> Result<AlignedVec, C::Error>
+where
+    C: DefaultSerializer + ?Sized,
+    T: Serialize<Strategy<C::Serializer, C::Error>>,
+{
+    Ok(C::into_bytes(serialize_into(value, C::serializer())?))
+}
+
+/// Serializes the given value with the [`DefaultConfig`] and returns the
+/// resulting bytes.
+///
+/// # Example
+///
+/// ```
+/// let value = vec![1, 2, 3, 4];
+/// let bytes = rkyv::to_bytes_default(&value).unwrap();
+/// let deserialized = rkyv::from_bytes_default::<Vec<i32>>(&bytes).unwrap();
+/// assert_eq!(deserialized, value);
+/// ```
+#[inline]
+pub fn to_bytes_default<T>(
+    value: &T,
+) -> Result<AlignedVec, <DefaultConfig as DefaultSerializer>::Error>
+where
+    T: Serialize<
+        Strategy<
+            <DefaultConfig as DefaultSerializer>::Serializer,
+            <DefaultConfig as DefaultSerializer>::Error,
+        >,
+    >,
+{
+    to_bytes_with_config::<DefaultConfig, T>(value)
+}
+
+/// Deserializes a value from the given bytes with the given configuration.
+///
+/// # Safety
+///
+/// - The byte slice must represent an archived object.
+/// - The root of the object must be stored at the end of the slice (this is the
+///   default behavior).
+#[inline]
+pub unsafe fn from_bytes_with_config_unchecked<C, T>(
+    bytes: &[u8],
+) -> Result<T, C::Error>
+where
+    C: DefaultDeserializer + ?Sized,
+    T: Archive,
+    T::Archived: Deserialize<T, Strategy<C::Deserializer, C::Error>>,
+{
+    deserialize(
+        access_unchecked::<T::Archived>(bytes),
+        &mut C::deserializer(),
+    )
+}
+
+/// Deserializes a value from the given bytes with the [`DefaultConfig`].
+///
+/// # Safety
+///
+/// - The byte slice must represent an archived object.
+/// - The root of the object must be stored at the end of the slice (this is the
+///   default behavior).
+///
+/// # Example
+///
+/// ```
+/// let value = vec![1, 2, 3, 4];
+/// let bytes = rkyv::to_bytes_default(&value).unwrap();
+/// // SAFETY: The bytes are an archived `Vec<i32>` with its root at the end.
+/// let deserialized =
+///     unsafe { rkyv::from_bytes_default_unchecked::<Vec<i32>>(&bytes) };
+/// assert_eq!(deserialized.unwrap(), value);
+/// ```
+#[inline]
+pub unsafe fn from_bytes_default_unchecked<T>(
+    bytes: &[u8],
+) -> Result<T, <DefaultConfig as DefaultDeserializer>::Error>
+where
+    T: Archive,
+    T::Archived: Deserialize<
+        T,
+        Strategy<
+            <DefaultConfig as DefaultDeserializer>::Deserializer,
+            <DefaultConfig as DefaultDeserializer>::Error,
+        >,
+    >,
+{
+    from_bytes_with_config_unchecked::<DefaultConfig, T>(bytes)
+}
@@ -0,0 +1,266 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::{fmt, mem::size_of, ops::Range};
+
+use bytecheck::CheckBytes;
+use rancor::{fail, Error, Strategy};
+
+use crate::{
+    util::AlignedVec,
+    validation::{util::check_pos_with_context, validators::ExtractValidator},
+    Archive,
+};
+
+/// An error which occurred while extracting a value from an archive.
+#[derive(Debug)]
+pub enum ExtractError {
+    /// The value is not located inside of the archive.
+    NotInArchive {
+        /// The address of the value.
+        address: usize,
+    },
+    /// A relative pointer points outside of the memory reachable from the
+    /// value.
+    UnreachableTarget {
+        /// The position of the relative pointer in the archive.
+        pos: usize,
+        /// The position of its target in the archive.
+        target: usize,
+    },
+    /// The offset of a relative pointer could not be represented after it was
+    /// moved.
+    OffsetOverflow {
+        /// The position of the relative pointer in the archive.
+        pos: usize,
+        /// The offset which could not be represented.
+        offset: isize,
+    },
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::NotInArchive { address } => write!(
+                f,
+                "value at {:#x} is not located inside of the archive",
+                address,
+            ),
+            ExtractError::UnreachableTarget { pos, target } => write!(
+                f,
+                "relative pointer at {:#x} points to {:#x}, which is not \
+                 reachable from the value",
+                pos, target,
+            ),
+            ExtractError::OffsetOverflow { pos, offset } => write!(
+                f,
+                "relative pointer at {:#x} could not store offset {}",
+                pos, offset,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExtractError {}
+
+/// A contiguous range of the archive and where it is moved to.
+struct Region {
+    old: Range<usize>,
+    new: usize,
+}
+
+/// Returns the new position of `pos` if it is the start of one of the given
+/// regions.
+fn relocate_start(regions: &[Region], pos: usize) -> Option<usize> {
+    let region = regions.get(regions.partition_point(|r| r.old.start < pos))?;
+    (region.old.start == pos).then_some(region.new)
+}
+
+/// Returns the new position of `pos` in the given regions, or `None` if it is
+/// not located inside of any of them. Positions at the end of a region belong
+/// to that region unless another region starts there.
+fn relocate(regions: &[Region], pos: usize) -> Option<usize> {
+    relocate_start(regions, pos).or_else(|| {
+        let index = regions.partition_point(|r| r.old.end < pos);
+        let region = regions.get(index)?;
+        (region.old.start <= pos).then(|| region.new + (pos - region.old.start))
+    })
+}
+
+/// Returns the new position of a relative pointer target.
+///
+/// Targets are usually located in the same group as their relative pointer.
+/// Shared pointers may point into another group instead.
+fn relocate_target(
+    groups: &[Vec<Region>],
+    group: usize,
+    pos: usize,
+) -> Option<usize> {
+    relocate_start(&groups[group], pos)
+        .or_else(|| groups.iter().find_map(|r| relocate_start(r, pos)))
+        .or_else(|| relocate(&groups[group], pos))
+        .or_else(|| groups.iter().find_map(|r| relocate(r, pos)))
+}
+
+/// Copies an archived value and everything reachable from it into a new
+/// archive with the value as its root.
+///
+/// `bytes` must be the archive which contains `value`. The value is validated,
+/// then every region of the archive it can reach is copied into the new archive
+/// with the gaps between them removed. Relative pointers are rewritten to point
+/// to the new locations of their targets, so values which were shared in the
+/// original archive are still shared.
+///
+/// Regions keep their original order, except for shared values which were
+/// first serialized before `value`. Those are moved to where `value` first
+/// points to them so that the new archive passes validation.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     access, access_unchecked, rancor::Failure, to_bytes, util::extract,
+///     Archive, Archived, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Library {
+///     name: String,
+///     shelves: Vec<Vec<String>>,
+/// }
+///
+/// let library = Library {
+///     name: "central library".to_string(),
+///     shelves: vec![
+///         vec!["a tale of two cities".to_string()],
+///         vec!["the count of monte cristo".to_string()],
+///     ],
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&library).unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedLibrary>(&bytes) };
+///
+/// let shelf = &archived.shelves[1];
+/// let extracted =
+///     extract::<Vec<String>, Failure>(&bytes, shelf).unwrap();
+/// assert!(extracted.len() < bytes.len());
+///
+/// let shelf =
+///     access::<Archived<Vec<String>>, Failure>(&extracted).unwrap();
+/// assert_eq!(shelf, &archived.shelves[1]);
+/// ```
+pub fn extract<T, E>(bytes: &[u8], value: &T::Archived) -> Result<AlignedVec, E>
+where
+    T: Archive,
+    T::Archived: CheckBytes<Strategy<ExtractValidator, E>>,
+    E: Error,
+{
+    let size = size_of::<T::Archived>();
+    let address = (value as *const T::Archived).addr();
+    let root = match address.checked_sub(bytes.as_ptr().addr()) {
+        Some(pos) if pos <= bytes.len() && bytes.len() - pos >= size => pos,
+        _ => fail!(ExtractError::NotInArchive { address }),
+    };
+
+    let mut validator = ExtractValidator::new(bytes);
+    check_pos_with_context::<T::Archived, ExtractValidator, E>(
+        bytes,
+        root,
+        &mut validator,
+    )?;
+
+    // Each group is placed inside of its parent group at the position where
+    // the parent would have claimed it. Groups are ordered by their position
+    // in their parent, then by when they were checked.
+    let mut paths = Vec::<Vec<usize>>::with_capacity(validator.groups().len());
+    paths.push(Vec::new());
+    for (i, group) in validator.groups().iter().enumerate().skip(1) {
+        let mut path = paths[group.parent].clone();
+        path.extend_from_slice(&[group.at, i]);
+        paths.push(path);
+    }
+
+    // Merge overlapping and adjacent regions in each group. Empty regions are
+    // kept so that relative pointers to them can be relocated. Regions are not
+    // merged across the positions of other groups.
+    let mut ranges = validator.regions().to_vec();
+    ranges.sort_by_key(|(group, r)| (*group, r.start, r.end));
+    let mut merged = Vec::<(usize, Range<usize>)>::with_capacity(ranges.len());
+    for (group, range) in ranges {
+        let is_split = |pos| {
+            validator.groups()[1..]
+                .iter()
+                .any(|g| g.parent == group && g.at == pos)
+        };
+        match merged.last_mut() {
+            Some((last_group, last))
+                if *last_group == group
+                    && (range.start < last.end
+                        || range.start == last.end
+                            && (last.start == last.end
+                                || !is_split(range.start))) =>
+            {
+                last.end = usize::max(last.end, range.end);
+            }
+            _ => merged.push((group, range)),
+        }
+    }
+
+    // Regions are sorted so that each one comes after everything claimed
+    // before it. Regions which start at the position of a group are claimed
+    // after that group.
+    let mut order = merged
+        .into_iter()
+        .map(|(group, old)| {
+            let mut key = paths[group].clone();
+            key.extend_from_slice(&[old.start, usize::MAX]);
+            (key, group, old)
+        })
+        .collect::<Vec<_>>();
+    order.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Each region is moved to the first position after the previous region
+    // which keeps its alignment.
+    let align = usize::max(AlignedVec::ALIGNMENT, validator.max_alignment());
+    let mut groups = validator
+        .groups()
+        .iter()
+        .map(|_| Vec::new())
+        .collect::<Vec<Vec<Region>>>();
+    let mut len = 0;
+    for (_, group, old) in order {
+        let new = len + (old.start.wrapping_sub(len) & (align - 1));
+        len = new + old.len();
+        groups[group].push(Region { old, new });
+    }
+
+    let mut result = AlignedVec::with_capacity_and_alignment(len, align);
+    result.resize(len, 0);
+    for region in groups.iter().flatten() {
+        result[region.new..region.new + region.old.len()]
+            .copy_from_slice(&bytes[region.old.clone()]);
+    }
+
+    for offset in validator.offsets() {
+        let relocated = (
+            relocate(&groups[offset.group], offset.base),
+            relocate(&groups[offset.group], offset.pos),
+            relocate_target(&groups, offset.group, offset.target),
+        );
+        let (base, pos, target) = match relocated {
+            (Some(base), Some(pos), Some(target)) => (base, pos, target),
+            _ => fail!(ExtractError::UnreachableTarget {
+                pos: offset.pos,
+                target: offset.target,
+            }),
+        };
+        let new_offset = target as isize - base as isize;
+        if !(offset.write)(new_offset, &mut result[pos..pos + offset.size]) {
+            fail!(ExtractError::OffsetOverflow {
+                pos: offset.pos,
+                offset: new_offset,
+            });
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,205 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use bytecheck::CheckBytes;
+use rancor::{Error, Strategy};
+
+use crate::{
+    ser::{AllocSerializer, WriterExt as _},
+    util::{serialize_into, AlignedVec},
+    validation::{util::access, validators::DefaultValidator},
+    vec::{ArchivedVec, VecResolver},
+    Archive, Serialize,
+};
+
+/// The serializer used to write migrated archives.
+pub type MigrateSerializer = AllocSerializer<1024>;
+
+/// Migrates an archive from one type to another.
+///
+/// The old archive is validated and accessed as an `Old`, then `f` maps the
+/// archived value to a new `New` which is serialized. `f` may deserialize only
+/// the parts of the old value that it needs.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     access, rancor::Failure, to_bytes, util::migrate, Archive,
+///     Deserialize, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// #[archive(check_bytes)]
+/// struct UserV1 {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct UserV2 {
+///     id: u64,
+///     name: String,
+///     admin: bool,
+/// }
+///
+/// let old = UserV1 {
+///     id: 7,
+///     name: "alice".to_string(),
+/// };
+/// let old_bytes = to_bytes::<_, 256, Failure>(&old).unwrap();
+///
+/// let new_bytes =
+///     migrate::<UserV1, UserV2, Failure>(&old_bytes, |old| {
+///         Ok(UserV2 {
+///             id: old.id.to_native().into(),
+///             name: old.name.to_string(),
+///             admin: false,
+///         })
+///     })
+///     .unwrap();
+///
+/// let new = access::<ArchivedUserV2, Failure>(&new_bytes).unwrap();
+/// assert_eq!(new.id, 7);
+/// assert_eq!(new.name, "alice");
+/// ```
+pub fn migrate<Old, New, E>(
+    old_bytes: &[u8],
+    f: impl FnOnce(&Old::Archived) -> Result<New, E>,
+) -> Result<AlignedVec, E>
+where
+    Old: Archive,
+    Old::Archived: CheckBytes<Strategy<DefaultValidator, E>>,
+    New: Serialize<Strategy<MigrateSerializer, E>>,
+    E: Error,
+{
+    let old = access::<Old::Archived, E>(old_bytes)?;
+    let new = f(old)?;
+    Ok(serialize_into(&new, MigrateSerializer::default())?.into_writer())
+}
+
+/// The root of a migrated `Vec`, whose elements have already been written.
+struct VecRoot<T> {
+    len: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Archive> Archive for VecRoot<T> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_len(self.len, pos, resolver, out);
+    }
+}
+
+/// Migrates an archive of a `Vec` from one element type to another, one
+/// element at a time.
+///
+/// The old archive is validated and accessed as a `Vec<Old>`, then `f` maps
+/// each archived element to a new `New`. The result is an archive of a
+/// `Vec<New>` which is the same as migrating the whole `Vec` with [`migrate`].
+///
+/// Unlike `migrate`, the new elements are never all held in memory at once.
+/// Each new element is serialized and dropped before the next is created, and
+/// only its resolver is kept. Because the elements of the new `Vec` can only
+/// be written after all of their dependencies, `f` is called twice for each
+/// element: once to serialize its dependencies and once more to write it.
+///
+/// # Safety
+///
+/// `f` must return the same value each time it is called with the same
+/// element.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     access, rancor::Failure, to_bytes, util::migrate_vec, vec::ArchivedVec,
+///     Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct PointV1 {
+///     x: i16,
+///     y: i16,
+/// }
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct PointV2 {
+///     x: i32,
+///     y: i32,
+///     z: i32,
+/// }
+///
+/// let old = vec![PointV1 { x: 1, y: 2 }, PointV1 { x: 3, y: 4 }];
+/// let old_bytes = to_bytes::<_, 256, Failure>(&old).unwrap();
+///
+/// // SAFETY: The closure always returns the same point for each element.
+/// let new_bytes = unsafe {
+///     migrate_vec::<PointV1, PointV2, Failure>(&old_bytes, |old| {
+///         Ok(PointV2 {
+///             x: old.x.to_native().into(),
+///             y: old.y.to_native().into(),
+///             z: 0,
+///         })
+///     })
+/// }
+/// .unwrap();
+///
+/// let new =
+///     access::<ArchivedVec<ArchivedPointV2>, Failure>(&new_bytes).unwrap();
+/// assert_eq!(new.len(), 2);
+/// assert_eq!(new[1].x, 3);
+/// ```
+pub unsafe fn migrate_vec<Old, New, E>(
+    old_bytes: &[u8],
+    f: impl Fn(&Old::Archived) -> Result<New, E>,
+) -> Result<AlignedVec, E>
+where
+    Old: Archive,
+    ArchivedVec<Old::Archived>: CheckBytes<Strategy<DefaultValidator, E>>,
+    New: Serialize<Strategy<MigrateSerializer, E>>,
+    E: Error,
+{
+    let old = access::<ArchivedVec<Old::Archived>, E>(old_bytes)?;
+
+    let mut serializer = MigrateSerializer::default();
+    let strategy = Strategy::<_, E>::wrap(&mut serializer);
+
+    let mut resolvers = Vec::with_capacity(old.len());
+    for element in old.iter() {
+        resolvers.push(f(element)?.serialize(strategy)?);
+    }
+
+    let pos = strategy.align_for::<New::Archived>()?;
+    for (element, resolver) in old.iter().zip(resolvers.drain(..)) {
+        // SAFETY: The caller has guaranteed that `f` returns the same value
+        // that `resolver` was produced by serializing.
+        unsafe {
+            strategy.resolve_aligned(&f(element)?, resolver)?;
+        }
+    }
+    drop(resolvers);
+
+    let root = VecRoot::<New> {
+        len: old.len(),
+        _phantom: PhantomData,
+    };
+    strategy.align_for::<ArchivedVec<New::Archived>>()?;
+    // SAFETY: The elements of the vec were just written at `pos`, and the
+    // serializer was just aligned for the root.
+    unsafe {
+        strategy.resolve_aligned(&root, VecResolver::from_pos(pos))?;
+    }
+
+    Ok(serializer.into_writer())
+}
@@ -12,24 +12,88 @@
 
 #[cfg(feature = "alloc")]
 mod aligned_vec;
+#[cfg(feature = "alloc")]
+mod archiver;
+#[cfg(all(feature = "alloc", target_has_atomic = "64"))]
+mod builder;
+#[cfg(feature = "alloc")]
+mod config;
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+mod extract;
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+mod migrate;
+#[cfg(feature = "alloc")]
+mod owned;
 mod scratch_vec;
+#[cfg(feature = "tokio")]
+mod tokio;
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use ::alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::{alloc::Layout, ops::Range};
 use core::{
     mem,
     ops::{Deref, DerefMut},
     pin::Pin,
 };
 
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+use bytecheck::CheckBytes;
 use rancor::Strategy;
+#[cfg(feature = "alloc")]
+use rancor::{Error, Fallible, ResultExt as _};
 
 #[doc(inline)]
 #[cfg(feature = "alloc")]
 pub use self::aligned_vec::*;
 #[doc(inline)]
+#[cfg(feature = "alloc")]
+pub use self::archiver::*;
+#[doc(inline)]
+#[cfg(all(feature = "alloc", target_has_atomic = "64"))]
+pub use self::builder::*;
+#[doc(inline)]
+#[cfg(feature = "alloc")]
+pub use self::config::*;
+#[doc(inline)]
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+pub use self::extract::*;
+#[doc(inline)]
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+pub use self::migrate::*;
+#[doc(inline)]
+#[cfg(feature = "alloc")]
+pub use self::owned::*;
+#[doc(inline)]
 pub use self::scratch_vec::*;
+#[doc(inline)]
+#[cfg(feature = "tokio")]
+pub use self::tokio::*;
+#[cfg(all(feature = "alloc", feature = "limits"))]
+use crate::ser::limits::{Limited, Limits};
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+use crate::validation::{
+    util::check_pos_with_context,
+    validators::{AuditReport, AuditValidator},
+};
 use crate::Portable;
 #[cfg(feature = "alloc")]
-use crate::{de::pooling::Unify, ser::AllocSerializer};
+use crate::{
+    boxed::{ArchivedBox, BoxResolver},
+    de::pooling::Unify,
+    deep_size::ArchivedDeepSize,
+    primitive::{ArchivedUsize, FixedUsize},
+    ser::{
+        allocator::{BackupAllocator, BumpAllocator, GlobalAllocator},
+        sharing::Unify as SharingUnify,
+        writer::{FallibleWriter, RegionWriter},
+        AllocSerializer, Allocator, FallibleSerializer, RegionSerializer,
+        WriterExt as _,
+    },
+    string::{ArchivedString, StringResolver},
+    vec::{ArchivedVec, VecResolver},
+};
 use crate::{
     ser::Writer, Archive, ArchivePointee, Deserialize, RelPtr, Serialize,
     SerializeUnsized,
@@ -39,7 +103,7 @@ use crate::{
 #[inline]
 fn check_alignment<T: Portable>(ptr: *const u8) {
     let expect_align = core::mem::align_of::<T>();
-    let actual_align = (ptr as usize) & (expect_align - 1);
+    let actual_align = ptr.addr() & (expect_align - 1);
     debug_assert_eq!(
         actual_align,
         0,
@@ -143,7 +207,7 @@ where
     check_alignment::<RelPtr<T>>(bytes.as_ptr());
 
     let rel_ptr = &mut *bytes.as_mut_ptr().add(pos).cast::<RelPtr<T>>();
-    Pin::new_unchecked(&mut *rel_ptr.as_ptr())
+    Pin::new_unchecked(&mut *rel_ptr.as_mut_ptr())
 }
 
 /// Accesses an archived value from the given byte slice by calculating the root
@@ -326,6 +390,465 @@ where
     Ok(serialize_into(value, Default::default())?.into_writer())
 }
 
+/// The size of the header at the start of archives serialized with
+/// [`to_bytes_forward_only`].
+#[cfg(feature = "alloc")]
+pub(crate) const FORWARD_ONLY_HEADER_SIZE: usize =
+    2 * mem::size_of::<ArchivedUsize>();
+
+/// Serializes the given values as a forward-only archive and returns the
+/// resulting bytes.
+///
+/// The root of the archive is an archived `Vec<Box<T>>`. Each value is written
+/// in its own frame together with its out-of-line data, and only the root
+/// points back to earlier frames. This lets forward-only archives be validated
+/// incrementally with a
+/// [`StreamValidator`](crate::validation::stream::StreamValidator) as their
+/// bytes arrive. Shared pointers are not shared between different values. See
+/// [`validation::stream`](crate::validation::stream) for the layout of
+/// forward-only archives.
+///
+/// Forward-only archives are also regular archives, and can be accessed with
+/// [`access`](crate::access) and deserialized as a `Vec<Box<T>>`.
+///
+/// The const generic parameter `N` specifies the number of bytes to
+/// pre-allocate as scratch space.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     rancor::Failure, util::to_bytes_forward_only,
+///     validation::util::access_forward_only, Archived,
+/// };
+///
+/// let value = vec!["a string which is stored out of line".to_string(); 4];
+/// let bytes = to_bytes_forward_only::<_, 256, Failure>(&value).unwrap();
+/// let archived =
+///     access_forward_only::<Archived<String>, Failure>(&bytes).unwrap();
+/// assert_eq!(archived.len(), 4);
+/// assert_eq!(*archived[3], value[3]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn to_bytes_forward_only<T, const N: usize, E>(
+    values: &[T],
+) -> Result<AlignedVec, E>
+where
+    T: Serialize<Strategy<AllocSerializer<N>, E>>,
+    E: Error,
+{
+    // SAFETY: `pos` must be aligned for `ArchivedUsize` and the writer must
+    // contain at least `pos + size_of::<ArchivedUsize>()` bytes.
+    unsafe fn write_usize<E: Error>(
+        bytes: &mut AlignedVec,
+        pos: usize,
+        value: usize,
+    ) -> Result<(), E> {
+        let value = FixedUsize::try_from(value).into_error()?;
+        unsafe {
+            let out = bytes.as_mut_ptr().add(pos).cast::<ArchivedUsize>();
+            out.write(ArchivedUsize::from_native(value));
+        }
+        Ok(())
+    }
+
+    let mut serializer = AllocSerializer::<N>::default();
+    Strategy::<_, E>::wrap(&mut serializer).pad(FORWARD_ONLY_HEADER_SIZE)?;
+
+    let mut elements = Vec::with_capacity(values.len());
+    for value in values {
+        // Shared pointers must not point into the frames of other values.
+        serializer.share = SharingUnify::default();
+        let strategy = Strategy::<_, E>::wrap(&mut serializer);
+        let frame = strategy.align_for::<ArchivedUsize>()?;
+        strategy.pad(mem::size_of::<ArchivedUsize>())?;
+        let resolver = value.serialize(strategy)?;
+        strategy.align_for::<T::Archived>()?;
+        // SAFETY: `resolver` is the result of serializing `value` and the
+        // serializer is aligned for `T::Archived`.
+        let pos = unsafe { strategy.resolve_aligned(value, resolver)? };
+        // SAFETY: The frame was written as zeroes and is aligned.
+        unsafe {
+            write_usize::<E>(&mut serializer.writer, frame, pos)?;
+        }
+        elements.push(pos);
+    }
+
+    let strategy = Strategy::<_, E>::wrap(&mut serializer);
+    let boxes_pos = strategy.align_for::<ArchivedBox<T::Archived>>()?;
+    let boxes_layout =
+        Layout::array::<ArchivedBox<T::Archived>>(values.len()).into_error()?;
+    strategy.pad(boxes_layout.size())?;
+    let root_pos =
+        strategy.align_for::<ArchivedVec<ArchivedBox<T::Archived>>>()?;
+    strategy.pad(mem::size_of::<ArchivedVec<ArchivedBox<T::Archived>>>())?;
+
+    let mut bytes = serializer.into_writer();
+    // SAFETY: The space for the boxes, root, and header was written as zeroes
+    // and is aligned.
+    unsafe {
+        for (i, &pos) in elements.iter().enumerate() {
+            let box_pos =
+                boxes_pos + i * mem::size_of::<ArchivedBox<T::Archived>>();
+            ArchivedBox::<T::Archived>::resolve_from_raw_parts(
+                box_pos,
+                BoxResolver::from_pos(pos),
+                (),
+                bytes.as_mut_ptr().add(box_pos).cast(),
+            );
+        }
+        ArchivedVec::<ArchivedBox<T::Archived>>::resolve_from_len(
+            values.len(),
+            root_pos,
+            VecResolver::from_pos(boxes_pos),
+            bytes.as_mut_ptr().add(root_pos).cast(),
+        );
+        write_usize::<E>(&mut bytes, 0, values.len())?;
+        write_usize::<E>(
+            &mut bytes,
+            mem::size_of::<ArchivedUsize>(),
+            root_pos,
+        )?;
+    }
+
+    Ok(bytes)
+}
+
+/// Serializes the given value and returns the resulting bytes, returning an
+/// error instead of aborting if memory could not be allocated.
+///
+/// This is like [`to_bytes`], but serializes with a
+/// [`FallibleSerializer`](crate::ser::FallibleSerializer). Allocation failures
+/// while creating the serializer, writing, or allocating scratch space are
+/// returned as [`AllocationFailed`] errors.
+///
+/// # Examples
+/// ```
+/// use rkyv::{rancor::Failure, util::try_to_bytes};
+///
+/// let value = vec![1, 2, 3, 4];
+/// let bytes = try_to_bytes::<_, 1024, Failure>(&value).unwrap();
+/// let deserialized =
+///     rkyv::from_bytes::<Vec<i32>, Failure>(&bytes).unwrap();
+/// assert_eq!(deserialized, value);
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn try_to_bytes<T, const N: usize, E>(value: &T) -> Result<AlignedVec, E>
+where
+    T: Serialize<Strategy<FallibleSerializer<N>, E>>,
+    E: Error,
+{
+    let serializer = FallibleSerializer::new(
+        FallibleWriter::new(),
+        BackupAllocator::new(
+            BumpAllocator::try_new().into_error()?,
+            GlobalAllocator::new(),
+        ),
+        SharingUnify::default(),
+    );
+    Ok(serialize_into(value, serializer)?
+        .into_writer()
+        .into_inner())
+}
+
+/// Serializes the given value and returns the resulting bytes, stopping as
+/// soon as the archive would exceed the given limits.
+///
+/// This is like [`to_bytes`], but serializes with a [`Limited`] serializer.
+/// Values which exceed the limits fail with a
+/// [`SerializationLimitExceeded`](crate::ser::limits::SerializationLimitExceeded)
+/// error before they are completely serialized.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     rancor::Failure, ser::limits::Limits, util::to_bytes_limited,
+/// };
+///
+/// let limits = Limits {
+///     max_bytes: 1024,
+///     ..Limits::default()
+/// };
+/// let bytes = to_bytes_limited::<_, 256, Failure>(&vec![1u8; 1000], limits);
+/// assert!(bytes.is_ok());
+/// let bytes = to_bytes_limited::<_, 256, Failure>(&vec![1u8; 2000], limits);
+/// assert!(bytes.is_err());
+/// ```
+#[cfg(all(feature = "alloc", feature = "limits"))]
+#[inline]
+pub fn to_bytes_limited<T, const N: usize, E>(
+    value: &T,
+    limits: Limits,
+) -> Result<AlignedVec, E>
+where
+    T: Serialize<Strategy<Limited<AllocSerializer<N>>, E>>,
+    E: Error,
+{
+    let serializer = Limited::new(AllocSerializer::default(), limits);
+    Ok(serialize_into(value, serializer)?
+        .into_inner()
+        .into_writer())
+}
+
+/// A borrowed slice which serializes as a `Vec`.
+#[cfg(feature = "alloc")]
+struct SliceRoot<'a, T>(&'a [T]);
+
+#[cfg(feature = "alloc")]
+impl<T: Archive> Archive for SliceRoot<'_, T> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_slice(self.0, pos, resolver, out);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, S> Serialize<S> for SliceRoot<'_, T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::<T::Archived>::serialize_from_slice(self.0, serializer)
+    }
+}
+
+/// A borrowed string slice which serializes as a `String`.
+#[cfg(feature = "alloc")]
+struct StrRoot<'a>(&'a str);
+
+#[cfg(feature = "alloc")]
+impl Archive for StrRoot<'_> {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedString::resolve_from_str(self.0, pos, resolver, out);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<S: Fallible + ?Sized> Serialize<S> for StrRoot<'_>
+where
+    str: SerializeUnsized<S>,
+{
+    #[inline]
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedString::serialize_from_str(self.0, serializer)
+    }
+}
+
+/// Serializes the given slice and returns the resulting bytes.
+///
+/// The resulting bytes are the same as those produced by serializing a `Vec`
+/// with the same elements using [`to_bytes`], and the root is an
+/// [`ArchivedVec`]. This avoids copying borrowed data into a `Vec` just to
+/// serialize it.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     access_unchecked, rancor::Failure, util::to_bytes_from_slice,
+///     vec::ArchivedVec, Archived,
+/// };
+///
+/// let values: &[u32] = &[1, 2, 3, 4];
+/// let bytes = to_bytes_from_slice::<_, 256, Failure>(values).unwrap();
+/// let archived =
+///     unsafe { access_unchecked::<ArchivedVec<Archived<u32>>>(&bytes) };
+/// assert_eq!(archived, values);
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn to_bytes_from_slice<T, const N: usize, E>(
+    value: &[T],
+) -> Result<AlignedVec, E>
+where
+    T: Serialize<Strategy<AllocSerializer<N>, E>>,
+    E: Error,
+{
+    to_bytes::<_, N, E>(&SliceRoot(value))
+}
+
+/// Serializes the given string slice and returns the resulting bytes.
+///
+/// The resulting bytes are the same as those produced by serializing a
+/// `String` with the same contents using [`to_bytes`], and the root is an
+/// [`ArchivedString`].
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     access_unchecked, rancor::Failure, string::ArchivedString,
+///     util::to_bytes_from_str,
+/// };
+///
+/// let bytes =
+///     to_bytes_from_str::<256, Failure>("hello from a borrowed str").unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedString>(&bytes) };
+/// assert_eq!(archived, "hello from a borrowed str");
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn to_bytes_from_str<const N: usize, E>(
+    value: &str,
+) -> Result<AlignedVec, E>
+where
+    E: Error,
+{
+    to_bytes::<_, N, E>(&StrRoot(value))
+}
+
+/// Serializes the given unsized value and returns the resulting bytes.
+///
+/// The root of an unsized value can't be found from the length of the bytes
+/// alone, so the value is followed by a [`RelPtr`] to it which holds its
+/// pointer metadata (e.g. the length of a slice or string). The resulting bytes
+/// must be accessed with [`access_unsized_unchecked`] or
+/// [`access_unsized`](crate::validation::util::access_unsized).
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     util::{access_unsized_unchecked, to_bytes_unsized},
+///     Archived,
+/// };
+///
+/// let values: &[u64] = &[1, 2, 3, 4];
+/// let bytes = to_bytes_unsized::<_, 256, Failure>(values).unwrap();
+/// let archived =
+///     unsafe { access_unsized_unchecked::<[Archived<u64>]>(&bytes) };
+/// assert_eq!(archived, values);
+///
+/// let bytes = to_bytes_unsized::<_, 256, Failure>("hello").unwrap();
+/// let archived = unsafe { access_unsized_unchecked::<str>(&bytes) };
+/// assert_eq!(archived, "hello");
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn to_bytes_unsized<T, const N: usize, E>(
+    value: &T,
+) -> Result<AlignedVec, E>
+where
+    T: SerializeUnsized<Strategy<AllocSerializer<N>, E>> + ?Sized,
+{
+    Ok(serialize_rel_ptr_into(value, Default::default())?.into_writer())
+}
+
+/// Serializes the given value and returns the resulting bytes along with the
+/// range of positions occupied by the cold region.
+///
+/// The out-of-line data of fields serialized with [`Cold`](crate::with::Cold)
+/// is written after all other out-of-line data and immediately before the root.
+/// Because the positions of the cold region depend on the size of the rest of
+/// the archive, values with cold data are serialized twice. The resulting bytes
+/// must be validated with
+/// [`access_with_cold_region`](crate::validation::util::access_with_cold_region).
+///
+/// The const generic parameter `N` specifies the number of bytes to
+/// pre-allocate as scratch space.
+///
+/// # Examples
+/// ```
+/// use rkyv::{rancor::Failure, util::to_bytes_with_regions, with::Cold};
+///
+/// #[derive(rkyv::Archive, rkyv::Serialize)]
+/// struct Example {
+///     name: String,
+///     #[with(Cold)]
+///     blob: Vec<u8>,
+/// }
+///
+/// let value = Example {
+///     name: "hot".to_string(),
+///     blob: vec![0xff; 64],
+/// };
+///
+/// let (bytes, cold) =
+///     to_bytes_with_regions::<_, 256, Failure>(&value).unwrap();
+/// assert_eq!(&bytes[cold], &[0xff; 64]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn to_bytes_with_regions<T, const N: usize, E>(
+    value: &T,
+) -> Result<(AlignedVec, Range<usize>), E>
+where
+    T: Serialize<Strategy<RegionSerializer<N>, E>>,
+    E: Error,
+{
+    // Returns `None` instead of the bytes if the hot region overran
+    // `cold_base`.
+    fn serialize_regions<T, const N: usize, E>(
+        value: &T,
+        cold_base: usize,
+    ) -> Result<(Option<AlignedVec>, usize, Range<usize>), E>
+    where
+        T: Serialize<Strategy<RegionSerializer<N>, E>>,
+        E: Error,
+    {
+        let mut serializer = RegionSerializer::<N>::new(
+            RegionWriter::new(AlignedVec::new(), cold_base),
+            Default::default(),
+            Default::default(),
+        );
+        let strategy = Strategy::<_, E>::wrap(&mut serializer);
+        let resolver = value.serialize(strategy)?;
+        let hot_len = strategy.writer.inner().len();
+        let mut cold = strategy.writer.cold_range();
+        if cold.is_empty() {
+            cold = hot_len..hot_len;
+        } else if hot_len >= cold_base {
+            return Ok((None, hot_len, cold));
+        }
+        strategy.writer.splice()?;
+        strategy.align_for::<T::Archived>()?;
+        unsafe {
+            strategy.resolve_aligned(value, resolver)?;
+        }
+        let bytes = serializer.into_writer().into_inner();
+        Ok((Some(bytes), hot_len, cold))
+    }
+
+    // The first pass measures the hot region. The length of the hot region
+    // does not depend on where the cold region starts, so the second pass
+    // always succeeds.
+    let mut cold_base = 0;
+    loop {
+        let (bytes, hot_len, cold) =
+            serialize_regions::<T, N, E>(value, cold_base)?;
+        if let Some(bytes) = bytes {
+            return Ok((bytes, cold));
+        }
+
+        // Leave at least one byte between the hot and cold regions so that
+        // validators can tell where zero-sized subtrees belong.
+        cold_base =
+            (hot_len + AlignedVec::ALIGNMENT) & !(AlignedVec::ALIGNMENT - 1);
+    }
+}
+
 /// Serializes the given value into the given serializer and then returns the
 /// serializer.
 #[inline]
@@ -431,3 +954,69 @@ where
 {
     value.deserialize(Strategy::wrap(deserializer))
 }
+
+/// Estimates the number of bytes of memory that the given archived value would
+/// use once deserialized, including any heap memory it would own.
+///
+/// See [`deep_size`](crate::deep_size) for how the estimate is calculated and
+/// an example.
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn deserialized_size_estimate<T: ArchivedDeepSize>(value: &T) -> usize {
+    mem::size_of::<T::Deserialized>()
+        .saturating_add(value.deserialized_heap_size())
+}
+
+/// Walks an archive of the given type by calculating the root position and
+/// checking its validity, and reports every relative pointer in it.
+///
+/// Each relative pointer is recorded with its position, the position of its
+/// target, and the type of its target. Pointers which cross the root, point
+/// outside of the archive, point into padding, or overlap other objects are
+/// flagged. If validation fails, the report contains the pointers recorded up
+/// to the error along with the error. See [`AuditValidator`] for more
+/// information.
+///
+/// This is meant for debugging archives which were sliced or concatenated
+/// incorrectly.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{rancor::Failure, to_bytes, util::audit, Archived};
+///
+/// let value = vec![1u8, 2, 3, 4];
+/// let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+///
+/// let report = audit::<Archived<Vec<u8>>, Failure>(&bytes);
+/// assert!(report.is_clean());
+/// assert_eq!(report.entries().len(), 1);
+/// assert_eq!(report.entries()[0].source(), report.root());
+/// assert_eq!(report.entries()[0].target(), 0);
+/// assert_eq!(report.entries()[0].type_name(), "[u8]");
+///
+/// // Point the vec past the end of the archive
+/// let root = report.root();
+/// bytes[root] = 0x40;
+///
+/// let report = audit::<Archived<Vec<u8>>, Failure>(&bytes);
+/// assert!(report.error().is_some());
+/// assert!(report.entries()[0].is_out_of_bounds());
+/// ```
+///
+/// [`AuditValidator`]: crate::validation::validators::AuditValidator
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+pub fn audit<T, E>(bytes: &[u8]) -> AuditReport<E>
+where
+    T: Portable + CheckBytes<Strategy<AuditValidator, E>>,
+    E: Error,
+{
+    let root = bytes.len().saturating_sub(mem::size_of::<T>());
+    let mut validator = AuditValidator::new(bytes, root, mem::size_of::<T>());
+    let result = check_pos_with_context::<T, AuditValidator, E>(
+        bytes,
+        root,
+        &mut validator,
+    );
+    validator.into_report(result.err())
+}
@@ -0,0 +1,288 @@
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, rc::Rc, sync::Arc, vec::Vec};
+use core::{fmt, marker::PhantomData, ops::Deref, ptr::NonNull};
+#[cfg(feature = "std")]
+use std::{rc::Rc, sync::Arc};
+
+#[cfg(feature = "bytecheck")]
+use bytecheck::CheckBytes;
+#[cfg(feature = "bytecheck")]
+use rancor::{Error, Strategy};
+
+#[cfg(feature = "bytecheck")]
+use crate::validation::{util::access, validators::DefaultValidator};
+use crate::{
+    util::{access_unchecked, AlignedVec},
+    Archive,
+};
+
+/// A buffer whose bytes do not move while it is alive, even if the buffer
+/// itself is moved.
+///
+/// This is implemented for the common owned and shared byte buffers. Other
+/// buffers, like memory maps, can implement it through a wrapper type.
+///
+/// # Safety
+///
+/// The slice returned by `as_ref` must always have the same address and
+/// length, and its contents must not change while the buffer is alive.
+pub unsafe trait StableBuffer: AsRef<[u8]> {}
+
+// SAFETY: The contents of each of these buffers are stored in a separate heap
+// allocation which is not modified through a shared reference.
+unsafe impl StableBuffer for AlignedVec {}
+unsafe impl StableBuffer for Vec<u8> {}
+unsafe impl StableBuffer for Box<[u8]> {}
+unsafe impl StableBuffer for Arc<[u8]> {}
+unsafe impl StableBuffer for Rc<[u8]> {}
+unsafe impl StableBuffer for &[u8] {}
+#[cfg(feature = "bytes")]
+unsafe impl StableBuffer for bytes::Bytes {}
+
+/// A [`StableBuffer`] whose clones share the same bytes.
+///
+/// # Safety
+///
+/// Cloning the buffer must return a buffer whose `as_ref` returns the same
+/// slice as the original.
+pub unsafe trait SharedBuffer: StableBuffer + Clone {}
+
+// SAFETY: Cloning each of these buffers only clones a reference to the same
+// bytes.
+unsafe impl SharedBuffer for Arc<[u8]> {}
+unsafe impl SharedBuffer for Rc<[u8]> {}
+unsafe impl SharedBuffer for &[u8] {}
+#[cfg(feature = "bytes")]
+unsafe impl SharedBuffer for bytes::Bytes {}
+
+/// A reference to a value inside of a buffer which is owned along with it.
+///
+/// Views are made by projecting an [`OwnedArchive`] or another view with
+/// `map` or `try_map`, and keep the whole buffer alive.
+pub struct OwnedView<U: ?Sized, B = AlignedVec> {
+    ptr: NonNull<U>,
+    buffer: B,
+}
+
+// SAFETY: The view only ever hands out shared references to the value, so it
+// may be sent and shared like a `&U` as long as the buffer can be.
+unsafe impl<U: Sync + ?Sized, B: Send> Send for OwnedView<U, B> {}
+unsafe impl<U: Sync + ?Sized, B: Sync> Sync for OwnedView<U, B> {}
+
+impl<U: ?Sized, B: StableBuffer> OwnedView<U, B> {
+    /// Returns a reference to the value.
+    #[inline]
+    pub fn get(&self) -> &U {
+        // SAFETY: `ptr` points to a value which lives at least as long as the
+        // buffer, which is owned by `self`.
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns a reference to the buffer that the value is in.
+    #[inline]
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Returns the buffer that the value is in.
+    #[inline]
+    pub fn into_buffer(self) -> B {
+        self.buffer
+    }
+
+    /// Projects the view to a value reachable from the current one.
+    #[inline]
+    pub fn map<V: ?Sized>(self, f: impl FnOnce(&U) -> &V) -> OwnedView<V, B> {
+        let ptr = NonNull::from(f(self.get()));
+        OwnedView {
+            ptr,
+            buffer: self.buffer,
+        }
+    }
+
+    /// Projects the view to a value reachable from the current one, or returns
+    /// the error returned by `f`.
+    #[inline]
+    pub fn try_map<V: ?Sized, E>(
+        self,
+        f: impl FnOnce(&U) -> Result<&V, E>,
+    ) -> Result<OwnedView<V, B>, E> {
+        let ptr = NonNull::from(f(self.get())?);
+        Ok(OwnedView {
+            ptr,
+            buffer: self.buffer,
+        })
+    }
+}
+
+impl<U: ?Sized, B: SharedBuffer> Clone for OwnedView<U, B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            ptr: self.ptr,
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
+impl<U: ?Sized, B: StableBuffer> Deref for OwnedView<U, B> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<U: fmt::Debug + ?Sized, B: StableBuffer> fmt::Debug for OwnedView<U, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// An archive which owns its buffer.
+///
+/// The root of the archive is accessed once when the `OwnedArchive` is
+/// created, and can be borrowed with `get` or through `Deref` for as long as
+/// the `OwnedArchive` lives. It can be stored and passed around without
+/// borrowing from the buffer.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure, to_bytes, util::OwnedArchive, Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     name: String,
+///     values: Vec<u32>,
+/// }
+///
+/// let value = Example {
+///     name: "pi".to_string(),
+///     values: vec![3, 1, 4, 1, 5],
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+///
+/// let archive = OwnedArchive::<Example>::new::<Failure>(bytes).unwrap();
+/// assert_eq!(archive.name, "pi");
+///
+/// let values = archive.map(|archived| archived.values.as_slice());
+/// assert_eq!(values.len(), 5);
+/// assert_eq!(values[2], 4);
+/// ```
+pub struct OwnedArchive<T: Archive, B = AlignedVec> {
+    view: OwnedView<T::Archived, B>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: Archive, B: StableBuffer> OwnedArchive<T, B> {
+    /// Validates the archive in the given buffer and takes ownership of it.
+    #[cfg(feature = "bytecheck")]
+    #[inline]
+    pub fn new<E>(buffer: B) -> Result<Self, E>
+    where
+        T::Archived: CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        let ptr = NonNull::from(access::<T::Archived, E>(buffer.as_ref())?);
+        Ok(Self {
+            view: OwnedView { ptr, buffer },
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Takes ownership of the archive in the given buffer without validating
+    /// it.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must contain a valid archive of a `T`, and must be aligned
+    /// for `T::Archived`.
+    #[inline]
+    pub unsafe fn new_unchecked(buffer: B) -> Self {
+        let ptr = NonNull::from(unsafe {
+            access_unchecked::<T::Archived>(buffer.as_ref())
+        });
+        Self {
+            view: OwnedView { ptr, buffer },
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the archived root.
+    #[inline]
+    pub fn get(&self) -> &T::Archived {
+        self.view.get()
+    }
+
+    /// Returns a reference to the buffer of the archive.
+    #[inline]
+    pub fn buffer(&self) -> &B {
+        self.view.buffer()
+    }
+
+    /// Returns the buffer of the archive.
+    #[inline]
+    pub fn into_buffer(self) -> B {
+        self.view.into_buffer()
+    }
+
+    /// Returns a view of the archived root.
+    #[inline]
+    pub fn into_view(self) -> OwnedView<T::Archived, B> {
+        self.view
+    }
+
+    /// Projects the archive to a value reachable from the archived root.
+    #[inline]
+    pub fn map<U: ?Sized>(
+        self,
+        f: impl FnOnce(&T::Archived) -> &U,
+    ) -> OwnedView<U, B> {
+        self.view.map(f)
+    }
+
+    /// Projects the archive to a value reachable from the archived root, or
+    /// returns the error returned by `f`.
+    #[inline]
+    pub fn try_map<U: ?Sized, E>(
+        self,
+        f: impl FnOnce(&T::Archived) -> Result<&U, E>,
+    ) -> Result<OwnedView<U, B>, E> {
+        self.view.try_map(f)
+    }
+}
+
+impl<T: Archive, B: SharedBuffer> Clone for OwnedArchive<T, B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            view: self.view.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Archive, B: StableBuffer> Deref for OwnedArchive<T, B> {
+    type Target = T::Archived;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<T, B> fmt::Debug for OwnedArchive<T, B>
+where
+    T: Archive,
+    T::Archived: fmt::Debug,
+    B: StableBuffer,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
@@ -0,0 +1,174 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io;
+
+use rancor::{Error, ResultExt as _, Strategy};
+use tokio::io::AsyncWrite;
+
+use crate::{
+    ser::{
+        writer::AsyncIoWriter, AllocSerializer, AsyncIoSerializer,
+        SerializeChunked, Writer, WriterExt as _,
+    },
+    util::AlignedVec,
+};
+
+/// The maximum number of elements serialized between checks for a checkpoint.
+const CHUNK_LEN: usize = 64;
+
+/// The number of bytes written after which a checkpoint is reached.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// A future which yields to the executor once before completing.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// A serializer which can flush its output between chunks of elements.
+trait Checkpoint {
+    async fn checkpoint(&mut self) -> io::Result<()>;
+}
+
+impl<const N: usize> Checkpoint for AllocSerializer<N> {
+    #[inline]
+    async fn checkpoint(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: AsyncWrite + Unpin, const N: usize> Checkpoint
+    for AsyncIoSerializer<W, N>
+{
+    #[inline]
+    async fn checkpoint(&mut self) -> io::Result<()> {
+        self.writer.flush_chunks().await
+    }
+}
+
+async fn serialize_chunked<T, S, E>(
+    value: &T,
+    serializer: &mut S,
+) -> Result<(), E>
+where
+    T: SerializeChunked<Strategy<S, E>>,
+    S: Checkpoint + Writer<E>,
+    E: Error,
+{
+    let mut state = T::State::default();
+    let mut last_checkpoint = serializer.pos();
+    let resolver = loop {
+        let strategy = Strategy::wrap(serializer);
+        if let Some(resolver) =
+            value.serialize_chunk(&mut state, CHUNK_LEN, strategy)?
+        {
+            break resolver;
+        }
+
+        if serializer.pos() - last_checkpoint >= CHUNK_BYTES {
+            serializer.checkpoint().await.into_error()?;
+            YieldNow { yielded: false }.await;
+            last_checkpoint = serializer.pos();
+        }
+    };
+
+    let serializer = Strategy::<S, E>::wrap(serializer);
+    serializer.align_for::<T::Archived>()?;
+    unsafe {
+        serializer.resolve_aligned(value, resolver)?;
+    }
+    Ok(())
+}
+
+/// Serializes the given value to an [`AlignedVec`] without blocking the async
+/// runtime.
+///
+/// The top-level elements of the value are serialized in chunks, and the
+/// current task yields to the runtime after every 64 KiB of output. The bytes
+/// are the same as [`to_bytes`](crate::to_bytes).
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{rancor::Failure, util::to_bytes_async};
+///
+/// let value = vec![vec![1u8; 1024]; 256];
+/// let bytes = tokio::runtime::Builder::new_current_thread()
+///     .build()
+///     .unwrap()
+///     .block_on(to_bytes_async::<_, 256, Failure>(&value))
+///     .unwrap();
+/// assert_eq!(
+///     bytes.as_slice(),
+///     rkyv::to_bytes::<_, 256, Failure>(&value).unwrap().as_slice(),
+/// );
+/// ```
+pub async fn to_bytes_async<T, const N: usize, E>(
+    value: &T,
+) -> Result<AlignedVec, E>
+where
+    T: SerializeChunked<Strategy<AllocSerializer<N>, E>>,
+    E: Error,
+{
+    let mut serializer = AllocSerializer::<N>::default();
+    serialize_chunked(value, &mut serializer).await?;
+    Ok(serializer.into_writer())
+}
+
+/// Serializes the given value to an [`AsyncWrite`] without blocking the async
+/// runtime and returns the writer.
+///
+/// The top-level elements of the value are serialized in chunks. After every
+/// 64 KiB of output, the buffered bytes are written to `writer` and the current
+/// task yields to the runtime. The bytes written are the same as
+/// [`to_bytes`](crate::to_bytes). See [`AsyncIoWriter`] for more information.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{rancor::Failure, util::to_writer_async};
+///
+/// let value = vec!["a string".to_string(); 256];
+/// let bytes = tokio::runtime::Builder::new_current_thread()
+///     .build()
+///     .unwrap()
+///     .block_on(to_writer_async::<_, _, 256, Failure>(&value, Vec::new()))
+///     .unwrap();
+/// assert_eq!(
+///     bytes,
+///     rkyv::to_bytes::<_, 256, Failure>(&value).unwrap().as_slice(),
+/// );
+/// ```
+pub async fn to_writer_async<T, W, const N: usize, E>(
+    value: &T,
+    writer: W,
+) -> Result<W, E>
+where
+    T: SerializeChunked<Strategy<AsyncIoSerializer<W, N>, E>>,
+    W: AsyncWrite + Unpin,
+    E: Error,
+{
+    let mut serializer = AsyncIoSerializer::<W, N>::new(
+        AsyncIoWriter::new(writer),
+        Default::default(),
+        Default::default(),
+    );
+    serialize_chunked(value, &mut serializer).await?;
+    serializer.into_writer().finish().await.into_error()
+}
@@ -0,0 +1,329 @@
+//! Lazy validation for archives which are only partially accessed.
+//!
+//! Validating an archive up front checks every object in it, even if only a
+//! small part of the archive will ever be read. A [`LazyArchive`] instead
+//! checks only that its root object is located inside of the archive bytes.
+//! Objects are then validated the first time they are accessed through a
+//! [`Lazy`] handle.
+//!
+//! Regions of the archive which are never accessed are never validated. They
+//! may contain invalid data without causing an error.
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeSet;
+use core::{
+    alloc::Layout,
+    any::TypeId,
+    cell::RefCell,
+    fmt,
+    marker::PhantomData,
+    mem::{align_of, size_of, take},
+};
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+
+use bytecheck::CheckBytes;
+use rancor::{Error, Strategy};
+
+use crate::{
+    validation::{
+        validators::{ArchiveValidator, DefaultValidator, SharedValidator},
+        ArchiveContext as _,
+    },
+    Portable,
+};
+
+/// A marker type for validating only the inline part of a container.
+///
+/// Containers use this as the type of the object they validate when checking
+/// their own layout, so that the result is cached separately from the result
+/// of validating the whole container.
+pub(crate) struct Shell<T: ?Sized>(PhantomData<T>);
+
+#[derive(Default)]
+struct LazyState {
+    // The start and end addresses of each validated object along with the
+    // type it was validated as
+    validated: BTreeSet<(usize, usize, TypeId)>,
+    // Shared pointers are registered across all validations so that shared
+    // objects are only validated once
+    shared: SharedValidator,
+}
+
+/// An archive which validates its objects as they are accessed.
+///
+/// Creating a `LazyArchive` only checks that the root object is located inside
+/// of the archive bytes. Each object is validated the first time it is
+/// accessed through [`Lazy::get`], and the result is cached so that accessing
+/// it again does not validate it again. Container types like
+/// [`ArchivedVec`](crate::vec::ArchivedVec),
+/// [`ArchivedHashMap`](crate::collections::swiss_table::ArchivedHashMap), and
+/// [`ArchivedBTreeMap`](crate::collections::btree_map::ArchivedBTreeMap)
+/// provide methods on `Lazy` to validate just the entries that are accessed.
+///
+/// Regions of the archive which are never accessed are never validated.
+/// Archives which are checked lazily may therefore contain invalid data without
+/// returning an error. Objects are also validated independently of each other,
+/// so unlike [`access`](crate::access), lazy validation does not check that
+/// objects do not overlap.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure, to_bytes, validation::lazy::LazyArchive, Archived,
+/// };
+///
+/// let value = vec!["hello".to_string(), "world".to_string()];
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+///
+/// let archive =
+///     LazyArchive::<Archived<Vec<String>>>::new::<Failure>(&bytes).unwrap();
+/// let root = archive.root();
+/// assert_eq!(root.len(), 2);
+///
+/// // Only the second string is validated
+/// let second = root.element::<Failure>(1).unwrap().unwrap();
+/// assert_eq!(second.get::<Failure>().unwrap(), "world");
+/// assert_eq!(archive.validated_count(), 2);
+/// ```
+pub struct LazyArchive<'a, T> {
+    bytes: &'a [u8],
+    state: RefCell<LazyState>,
+    _phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T: Portable> LazyArchive<'a, T> {
+    /// Creates a new lazy archive from the given bytes.
+    ///
+    /// The root object is located at the end of the bytes, the same as with
+    /// [`access`](crate::access). This only checks that the root object is
+    /// located inside of the bytes and properly aligned.
+    pub fn new<E: Error>(bytes: &'a [u8]) -> Result<Self, E> {
+        let pos = bytes.len().saturating_sub(size_of::<T>());
+        let root = bytes.as_ptr().wrapping_add(pos);
+        ArchiveValidator::new(bytes)
+            .check_subtree_ptr(root, &Layout::new::<T>())?;
+
+        Ok(Self {
+            bytes,
+            state: RefCell::new(LazyState::default()),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns a lazy handle to the root object of the archive.
+    pub fn root(&self) -> Lazy<'_, T> {
+        let pos = self.bytes.len() - size_of::<T>();
+        Lazy {
+            bytes: self.bytes,
+            state: &self.state,
+            ptr: self.bytes[pos..].as_ptr().cast(),
+        }
+    }
+
+    /// Returns the number of objects that have been validated so far.
+    ///
+    /// Objects which are accessed again after being validated are not
+    /// validated again, and so are only counted once.
+    pub fn validated_count(&self) -> usize {
+        self.state.borrow().validated.len()
+    }
+}
+
+impl<T> fmt::Debug for LazyArchive<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyArchive")
+            .field("len", &self.bytes.len())
+            .field("validated_count", &self.state.borrow().validated.len())
+            .finish()
+    }
+}
+
+/// A handle to an object in a [`LazyArchive`] which has not necessarily been
+/// validated.
+///
+/// The object is located inside of the archive and properly aligned, but its
+/// bytes may be invalid. Call [`get`](Lazy::get) to validate the object and
+/// access it.
+pub struct Lazy<'a, T> {
+    bytes: &'a [u8],
+    state: &'a RefCell<LazyState>,
+    ptr: *const T,
+}
+
+impl<T> Clone for Lazy<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Lazy<'_, T> {}
+
+impl<T> fmt::Debug for Lazy<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lazy").field("ptr", &self.ptr).finish()
+    }
+}
+
+impl<'a, T> Lazy<'a, T> {
+    /// Returns a pointer to the object.
+    ///
+    /// The object may not be valid, so the pointer must not be dereferenced
+    /// until the object has been validated.
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    /// Validates the object if it has not been validated yet, then returns a
+    /// reference to it.
+    ///
+    /// All of the out-of-line data owned by the object is validated along with
+    /// it.
+    pub fn get<E>(&self) -> Result<&'a T, E>
+    where
+        T: Portable + CheckBytes<Strategy<DefaultValidator, E>> + 'static,
+        E: Error,
+    {
+        unsafe {
+            self.validate::<T, E>(|context| T::check_bytes(self.ptr, context))?;
+            Ok(&*self.ptr)
+        }
+    }
+
+    /// Returns a lazy handle to a field of the object without validating the
+    /// object.
+    ///
+    /// `project` is called with a pointer to the object and must return a
+    /// pointer to one of its fields, typically with
+    /// [`addr_of!`](core::ptr::addr_of).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the returned pointer is not located inside of the object or is
+    /// not properly aligned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::ptr::addr_of;
+    ///
+    /// use rkyv::{
+    ///     rancor::Failure, to_bytes, validation::lazy::LazyArchive, Archive,
+    ///     Serialize,
+    /// };
+    ///
+    /// #[derive(Archive, Serialize)]
+    /// #[archive(check_bytes)]
+    /// struct Example {
+    ///     name: String,
+    ///     values: Vec<u32>,
+    /// }
+    ///
+    /// let value = Example {
+    ///     name: "example".to_string(),
+    ///     values: vec![1, 2, 3],
+    /// };
+    /// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+    ///
+    /// let archive =
+    ///     LazyArchive::<ArchivedExample>::new::<Failure>(&bytes).unwrap();
+    /// let values = archive
+    ///     .root()
+    ///     .project(|example| unsafe { addr_of!((*example).values) });
+    /// assert_eq!(values.get::<Failure>().unwrap(), &[1, 2, 3]);
+    /// ```
+    pub fn project<U>(
+        &self,
+        project: impl FnOnce(*const T) -> *const U,
+    ) -> Lazy<'a, U> {
+        let ptr = project(self.ptr);
+        let start = ptr.addr();
+        let base = self.ptr.addr();
+        let in_bounds = start >= base
+            && start - base <= size_of::<T>()
+            && size_of::<T>() - (start - base) >= size_of::<U>();
+        assert!(
+            in_bounds && start & (align_of::<U>() - 1) == 0,
+            "projected pointer must point to a field of the lazy object",
+        );
+        Lazy {
+            bytes: self.bytes,
+            state: self.state,
+            ptr,
+        }
+    }
+
+    /// Returns a lazy handle to another object in the same archive.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be located inside of the archive and properly aligned. If it
+    /// has not been checked yet, then the returned handle may only be used to
+    /// check it with [`validate_before`](Lazy::validate_before).
+    pub(crate) unsafe fn child<U>(&self, ptr: *const U) -> Lazy<'a, U> {
+        Lazy {
+            bytes: self.bytes,
+            state: self.state,
+            ptr,
+        }
+    }
+
+    /// Validates the object as an `M` with `check` if it has not been
+    /// validated as an `M` yet.
+    ///
+    /// `check` is called with a validator for the bytes located before the
+    /// object.
+    ///
+    /// # Safety
+    ///
+    /// `check` must validate that the object is a valid `M`.
+    pub(crate) unsafe fn validate<M: ?Sized + 'static, E: Error>(
+        &self,
+        check: impl FnOnce(&mut Strategy<DefaultValidator, E>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.validate_before::<M, E>(self.ptr.cast(), check)
+    }
+
+    /// Validates the object as an `M` with `check` if it has not been
+    /// validated as an `M` yet.
+    ///
+    /// `check` is called with a validator for the bytes located before `end`.
+    ///
+    /// # Safety
+    ///
+    /// - `end` must be located inside of the archive.
+    /// - `check` must validate that the object is a valid `M`.
+    pub(crate) unsafe fn validate_before<M: ?Sized + 'static, E: Error>(
+        &self,
+        end: *const u8,
+        check: impl FnOnce(&mut Strategy<DefaultValidator, E>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let start = self.ptr.addr();
+        let key = (start, start + size_of::<T>(), TypeId::of::<M>());
+        if self.state.borrow().validated.contains(&key) {
+            return Ok(());
+        }
+
+        let len = end.addr() - self.bytes.as_ptr().addr();
+        let shared = take(&mut self.state.borrow_mut().shared);
+        let mut validator =
+            DefaultValidator::with_shared(&self.bytes[..len], shared);
+        let result = check(Strategy::wrap(&mut validator));
+
+        let mut state = self.state.borrow_mut();
+        match result {
+            Ok(()) => {
+                state.shared = validator.into_shared();
+                state.validated.insert(key);
+                Ok(())
+            }
+            Err(error) => {
+                // Shared pointers registered during a failed validation may
+                // not be valid, so they must be checked again
+                state.shared = SharedValidator::new();
+                Err(error)
+            }
+        }
+    }
+}
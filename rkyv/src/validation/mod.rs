@@ -1,15 +1,29 @@
 //! Validation implementations and helper types.
 
+#[cfg(feature = "alloc")]
+pub mod lazy;
+pub(crate) mod overflow;
+#[cfg(feature = "alloc")]
+pub mod proof;
+#[cfg(feature = "alloc")]
+pub mod stream;
 pub mod util;
 pub mod validators;
 
-use core::{alloc::Layout, alloc::LayoutError, any::TypeId, ops::Range};
+use core::{
+    alloc::Layout,
+    alloc::LayoutError,
+    any::{type_name, TypeId},
+    ops::Range,
+};
 
 use bytecheck::rancor::{Error, Fallible, Strategy};
 use ptr_meta::Pointee;
 use rancor::ResultExt as _;
 
-use crate::{ArchivePointee, RelPtr};
+use self::overflow::offset_address;
+pub use self::overflow::SizeOverflow;
+use crate::{rel_ptr::OffsetSlot, ArchivePointee, RelPtr};
 
 // Replace this trait with core::mem::{align_of_val_raw, size_of_val_raw} when
 // they get stabilized.
@@ -125,11 +139,131 @@ pub unsafe trait ArchiveContext<E = <Self as Fallible>::Error> {
         &mut self,
         range: Range<usize>,
     ) -> Result<(), E>;
+
+    /// Pushes the range of a subtree which has already been claimed and starts
+    /// validating it again.
+    ///
+    /// Values serialized with [`Intern`](crate::with::Intern) are pointed to by
+    /// every relative pointer to an equal value, so most of those pointers
+    /// point to a subtree which has already been claimed. If the validator
+    /// permits aliasing and `ptr` and `layout` describe a subtree which has
+    /// already been claimed, this returns the range to pop after checking the
+    /// subtree again. Otherwise, this returns `None` and the subtree must be
+    /// claimed as usual. By default, this returns `None`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be the target of a relative pointer located inside the
+    /// archive.
+    #[inline]
+    unsafe fn push_aliased_subtree_range(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<Option<Range<usize>>, E> {
+        let _ = (ptr, layout);
+        Ok(None)
+    }
+
+    /// Starts checking an element of a collection.
+    ///
+    /// Collections call this before checking each of their elements and call
+    /// [`finish_element`](ArchiveContext::finish_element) with the result
+    /// afterward. Validators which continue past invalid elements use this to
+    /// save their state. By default, this does nothing.
+    ///
+    /// # Safety
+    ///
+    /// Each call must be followed by exactly one call to `finish_element`.
+    /// Calls may be nested, but must be finished in reverse order.
+    #[inline]
+    unsafe fn start_element(&mut self) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Finishes checking an element of a collection.
+    ///
+    /// `index` is the index of the element in its collection and `ptr` is the
+    /// address of the element. `result` is the result of checking the element.
+    ///
+    /// If this returns `Ok`, the collection should continue checking its
+    /// remaining elements. By default, this returns `result` so that the first
+    /// invalid element stops validation.
+    ///
+    /// # Safety
+    ///
+    /// This must be called once for each call to `start_element`, in reverse
+    /// order.
+    #[inline]
+    unsafe fn finish_element(
+        &mut self,
+        index: usize,
+        ptr: *const u8,
+        result: Result<(), E>,
+    ) -> Result<(), E> {
+        let _ = (index, ptr);
+        result
+    }
+
+    /// Records a checked relative pointer to a subtree.
+    ///
+    /// `base` is the address of the relative pointer, and `ptr` and `layout`
+    /// are the address and layout of the subtree it points to. `type_name` is
+    /// the name of the type of the subtree. This is called before the subtree
+    /// pointer is checked so that pointers which fail the check are recorded
+    /// too. Validators which report on the structure of archives use this to
+    /// collect their relative pointers. By default, this does nothing.
+    #[inline]
+    fn record_subtree_ptr(
+        &mut self,
+        base: *const u8,
+        ptr: *const u8,
+        layout: &Layout,
+        type_name: &'static str,
+    ) -> Result<(), E> {
+        let _ = (base, ptr, layout, type_name);
+        Ok(())
+    }
+
+    /// Records a relative pointer offset which is followed to a subtree.
+    ///
+    /// This is called for every relative pointer which is followed, including
+    /// shared pointers to subtrees which have already been checked. Validators
+    /// which move archived data use this to find the offsets they need to
+    /// rewrite. By default, this does nothing.
+    #[inline]
+    fn record_offset(&mut self, slot: OffsetSlot) -> Result<(), E> {
+        let _ = slot;
+        Ok(())
+    }
+
+    /// Returns the address of the start of the archive, if it is known.
+    ///
+    /// Archived types which are aligned relative to the start of the archive
+    /// use this to check their alignment. By default, this returns `None`.
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns whether archived hash maps and sets should check the hashes of
+    /// their keys.
+    ///
+    /// When this returns `true`, each key is hashed to check that the control
+    /// byte of its bucket matches its hash, and looked up to check that it can
+    /// be found. These checks are not needed for the archive to be safe to
+    /// access, but without them a crafted archive may contain keys which can't
+    /// be found. Validators which only need the archive to be safe to access
+    /// can skip them. By default, this returns `true`.
+    #[inline]
+    fn check_hashes(&self) -> bool {
+        true
+    }
 }
 
 unsafe impl<T, E> ArchiveContext<E> for Strategy<T, E>
 where
-    T: ArchiveContext<E>,
+    T: ArchiveContext<E> + ?Sized,
 {
     fn check_subtree_ptr(
         &mut self,
@@ -161,6 +295,49 @@ where
     ) -> Result<(), E> {
         T::pop_subtree_range(self, range)
     }
+
+    unsafe fn push_aliased_subtree_range(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<Option<Range<usize>>, E> {
+        T::push_aliased_subtree_range(self, ptr, layout)
+    }
+
+    unsafe fn start_element(&mut self) -> Result<(), E> {
+        T::start_element(self)
+    }
+
+    unsafe fn finish_element(
+        &mut self,
+        index: usize,
+        ptr: *const u8,
+        result: Result<(), E>,
+    ) -> Result<(), E> {
+        T::finish_element(self, index, ptr, result)
+    }
+
+    fn record_subtree_ptr(
+        &mut self,
+        base: *const u8,
+        ptr: *const u8,
+        layout: &Layout,
+        type_name: &'static str,
+    ) -> Result<(), E> {
+        T::record_subtree_ptr(self, base, ptr, layout, type_name)
+    }
+
+    fn record_offset(&mut self, slot: OffsetSlot) -> Result<(), E> {
+        T::record_offset(self, slot)
+    }
+
+    fn archive_start(&self) -> Option<usize> {
+        T::archive_start(self)
+    }
+
+    fn check_hashes(&self) -> bool {
+        T::check_hashes(self)
+    }
 }
 
 /// Helper methods for `ArchiveContext`s.
@@ -205,6 +382,28 @@ pub trait ArchiveContextExt<E>: ArchiveContext<E> {
         &mut self,
         root: *const T,
     ) -> Result<Range<usize>, E>;
+
+    /// Checks that the given relative pointer to a subtree which may be
+    /// aliased can be dereferenced, then pushes its subtree range.
+    ///
+    /// Subtrees which have already been claimed are pushed with
+    /// [`push_aliased_subtree_range`](ArchiveContext::push_aliased_subtree_range)
+    /// so that they can be checked again. All other subtrees are claimed as
+    /// usual.
+    ///
+    /// # Safety
+    ///
+    /// - `base` must be inside the archive this validator was created for.
+    /// - `metadata` must be the metadata for the pointer defined by `base` and
+    ///   `offset`.
+    unsafe fn push_aliasable_subtree_base_offset<
+        T: LayoutRaw + Pointee + ?Sized,
+    >(
+        &mut self,
+        base: *const u8,
+        offset: isize,
+        metadata: T::Metadata,
+    ) -> Result<(*const T, Range<usize>), E>;
 }
 
 impl<C: ArchiveContext<E> + ?Sized, E: Error> ArchiveContextExt<E> for C {
@@ -224,8 +423,10 @@ impl<C: ArchiveContext<E> + ?Sized, E: Error> ArchiveContextExt<E> for C {
         offset: isize,
         metadata: T::Metadata,
     ) -> Result<*const T, E> {
+        offset_address(base.addr(), offset).into_error()?;
         let ptr = base.wrapping_offset(offset);
         let layout = T::layout_raw(metadata).into_error()?;
+        self.record_subtree_ptr(base, ptr, &layout, type_name::<T>())?;
         self.check_subtree_ptr(ptr, &layout)?;
         Ok(ptr_meta::from_raw_parts(ptr.cast(), metadata))
     }
@@ -268,6 +469,37 @@ impl<C: ArchiveContext<E> + ?Sized, E: Error> ArchiveContextExt<E> for C {
             (root as *const u8).add(layout.size()),
         )
     }
+
+    /// Checks that the given relative pointer to a subtree which may be
+    /// aliased can be dereferenced, then pushes its subtree range.
+    ///
+    /// # Safety
+    ///
+    /// - `base` must be inside the archive this validator was created for.
+    /// - `metadata` must be the metadata for the pointer defined by `base` and
+    ///   `offset`.
+    #[inline]
+    unsafe fn push_aliasable_subtree_base_offset<
+        T: LayoutRaw + Pointee + ?Sized,
+    >(
+        &mut self,
+        base: *const u8,
+        offset: isize,
+        metadata: T::Metadata,
+    ) -> Result<(*const T, Range<usize>), E> {
+        offset_address(base.addr(), offset).into_error()?;
+        let ptr = base.wrapping_offset(offset);
+        let layout = T::layout_raw(metadata).into_error()?;
+        self.record_subtree_ptr(base, ptr, &layout, type_name::<T>())?;
+        let range = match self.push_aliased_subtree_range(ptr, &layout)? {
+            Some(range) => range,
+            None => {
+                self.check_subtree_ptr(ptr, &layout)?;
+                self.push_prefix_subtree_range(ptr, ptr.add(layout.size()))?
+            }
+        };
+        Ok((ptr_meta::from_raw_parts(ptr.cast(), metadata), range))
+    }
 }
 
 /// A context that can validate shared archive memory.
@@ -287,7 +519,7 @@ pub trait SharedContext<E = <Self as Fallible>::Error> {
 
 impl<T, E> SharedContext<E> for Strategy<T, E>
 where
-    T: SharedContext<E>,
+    T: SharedContext<E> + ?Sized,
 {
     fn register_shared_ptr(
         &mut self,
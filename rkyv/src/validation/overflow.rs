@@ -0,0 +1,291 @@
+//! Checked address arithmetic for validation.
+//!
+//! Validators compute the addresses of subtrees from relative pointers and
+//! archived sizes, all of which come from untrusted bytes. On targets with
+//! narrow pointers, these computations can wrap around the address space and
+//! produce ranges which pass bounds checks that they shouldn't. The helpers in
+//! this module are generic over the width of addresses so that their behavior
+//! at 16- and 32-bit boundaries can be tested on any target.
+
+use core::fmt;
+
+/// An error resulting from an address or size computation which would
+/// overflow the address space.
+#[derive(Debug)]
+pub enum SizeOverflow {
+    /// Offsetting an address by a relative offset overflowed.
+    Offset {
+        /// The address that was offset.
+        base: u64,
+        /// The offset from the address.
+        offset: i64,
+    },
+    /// The end of a range extends past the end of the address space.
+    End {
+        /// The start of the range.
+        start: u64,
+        /// The size of the range.
+        size: u64,
+    },
+    /// The start of a range extends before the start of the address space.
+    Start {
+        /// The end of the range.
+        end: u64,
+        /// The size of the range.
+        size: u64,
+    },
+}
+
+impl fmt::Display for SizeOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeOverflow::Offset { base, offset } => write!(
+                f,
+                "size overflow: offsetting address {:#x} by {} overflows the \
+                 address space",
+                base, offset,
+            ),
+            SizeOverflow::End { start, size } => write!(
+                f,
+                "size overflow: range of {} bytes starting at {:#x} extends \
+                 past the end of the address space",
+                size, start,
+            ),
+            SizeOverflow::Start { end, size } => write!(
+                f,
+                "size overflow: range of {} bytes ending at {:#x} extends \
+                 before the start of the address space",
+                size, end,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SizeOverflow {}
+
+/// An unsigned integer which holds an address.
+pub(crate) trait Address: Copy {
+    /// The signed integer of the same width.
+    type Signed: Copy;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    fn checked_add_signed(self, rhs: Self::Signed) -> Option<Self>;
+
+    fn to_u64(self) -> u64;
+
+    fn signed_to_i64(value: Self::Signed) -> i64;
+}
+
+macro_rules! impl_address {
+    ($($unsigned:ty: $signed:ty),* $(,)?) => {
+        $(
+            impl Address for $unsigned {
+                type Signed = $signed;
+
+                #[inline]
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$unsigned>::checked_add(self, rhs)
+                }
+
+                #[inline]
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$unsigned>::checked_sub(self, rhs)
+                }
+
+                #[inline]
+                fn checked_add_signed(self, rhs: $signed) -> Option<Self> {
+                    <$unsigned>::checked_add_signed(self, rhs)
+                }
+
+                #[inline]
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+
+                #[inline]
+                fn signed_to_i64(value: $signed) -> i64 {
+                    value as i64
+                }
+            }
+        )*
+    };
+}
+
+impl_address!(u16: i16, u32: i32, usize: isize);
+
+/// Returns `base` offset by `offset`, or an error if it would overflow.
+#[inline]
+pub(crate) fn offset_address<A: Address>(
+    base: A,
+    offset: A::Signed,
+) -> Result<A, SizeOverflow> {
+    base.checked_add_signed(offset)
+        .ok_or_else(|| SizeOverflow::Offset {
+            base: base.to_u64(),
+            offset: A::signed_to_i64(offset),
+        })
+}
+
+/// Returns the end of a range of `size` bytes starting at `start`, or an
+/// error if it would overflow.
+#[inline]
+pub(crate) fn range_end<A: Address>(
+    start: A,
+    size: A,
+) -> Result<A, SizeOverflow> {
+    start.checked_add(size).ok_or_else(|| SizeOverflow::End {
+        start: start.to_u64(),
+        size: size.to_u64(),
+    })
+}
+
+/// Returns the start of a range of `size` bytes ending at `end`, or an error
+/// if it would overflow.
+#[inline]
+pub(crate) fn range_start<A: Address>(
+    end: A,
+    size: A,
+) -> Result<A, SizeOverflow> {
+    end.checked_sub(size).ok_or_else(|| SizeOverflow::Start {
+        end: end.to_u64(),
+        size: size.to_u64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{offset_address, range_end, range_start, SizeOverflow};
+
+    #[test]
+    fn offsets_at_32_bit_boundaries() {
+        assert_eq!(offset_address(0x1000u32, 0x10).unwrap(), 0x1010);
+        assert_eq!(offset_address(0x1000u32, -0x1000).unwrap(), 0);
+        assert_eq!(offset_address(u32::MAX - 4, 4).unwrap(), u32::MAX);
+        assert!(matches!(
+            offset_address(0x1000u32, -0x1001),
+            Err(SizeOverflow::Offset {
+                base: 0x1000,
+                offset: -0x1001,
+            }),
+        ));
+        assert!(offset_address(u32::MAX - 4, 5).is_err());
+        assert_eq!(offset_address(0x8000_0000u32, i32::MAX).unwrap(), u32::MAX);
+        assert!(offset_address(0x8000_0001u32, i32::MAX).is_err());
+        assert!(offset_address(0x7fff_ffffu32, i32::MIN).is_err());
+    }
+
+    #[test]
+    fn ranges_at_32_bit_boundaries() {
+        assert_eq!(range_end(0xffff_0000u32, 0xffff).unwrap(), u32::MAX);
+        assert!(range_end(0xffff_0000u32, 0x1_0000).is_err());
+        // A vec claiming `u32::MAX` bytes starting just past the bottom of
+        // the address space
+        assert!(matches!(
+            range_end(0x10u32, u32::MAX),
+            Err(SizeOverflow::End {
+                start: 0x10,
+                size: 0xffff_ffff,
+            }),
+        ));
+
+        assert_eq!(range_start(0x100u32, 0x100).unwrap(), 0);
+        assert!(matches!(
+            range_start(0x100u32, 0x101),
+            Err(SizeOverflow::Start {
+                end: 0x100,
+                size: 0x101,
+            }),
+        ));
+        assert!(range_start(0x8000_0000u32, 0x8000_0001).is_err());
+    }
+
+    #[test]
+    fn ranges_at_16_bit_boundaries() {
+        assert_eq!(offset_address(0xff00u16, 0xff).unwrap(), u16::MAX);
+        assert!(offset_address(0xff00u16, 0x100).is_err());
+        assert!(offset_address(0x10u16, -0x11).is_err());
+        assert_eq!(range_end(0xfff0u16, 0xf).unwrap(), u16::MAX);
+        assert!(range_end(0xfff0u16, 0x10).is_err());
+        assert!(range_start(0x10u16, 0x11).is_err());
+    }
+
+    #[cfg(all(feature = "alloc", feature = "bytecheck"))]
+    #[test]
+    fn crafted_archives_fail_validation() {
+        #[cfg(not(feature = "std"))]
+        use alloc::{vec, vec::Vec};
+        use core::mem::size_of;
+
+        use rancor::Failure;
+
+        use crate::{
+            access, from_bytes,
+            primitive::{ArchivedIsize, ArchivedUsize, FixedIsize, FixedUsize},
+            to_bytes,
+            util::AlignedVec,
+            vec::ArchivedVec,
+            Archived,
+        };
+
+        // The root vec is at the end of the archive, with its relative offset
+        // followed by its length
+        fn craft(
+            offset: Option<FixedIsize>,
+            len: Option<FixedUsize>,
+        ) -> AlignedVec {
+            let mut bytes =
+                to_bytes::<_, 256, Failure>(&vec![1u32, 2, 3, 4]).unwrap();
+            let root = bytes.len() - size_of::<ArchivedVec<Archived<u32>>>();
+            if let Some(offset) = offset {
+                let offset = ArchivedIsize::from_native(offset);
+                unsafe {
+                    bytes
+                        .as_mut_ptr()
+                        .add(root)
+                        .cast::<ArchivedIsize>()
+                        .write_unaligned(offset);
+                }
+            }
+            if let Some(len) = len {
+                let len = ArchivedUsize::from_native(len);
+                unsafe {
+                    bytes
+                        .as_mut_ptr()
+                        .add(root + size_of::<ArchivedIsize>())
+                        .cast::<ArchivedUsize>()
+                        .write_unaligned(len);
+                }
+            }
+            bytes
+        }
+
+        let crafted = [
+            // A length whose elements would span the whole address space
+            craft(None, Some(FixedUsize::MAX)),
+            // A length which reaches just past the end of the archive
+            craft(None, Some(5)),
+            // An offset pointing as far before the archive as possible
+            craft(Some(FixedIsize::MIN), None),
+            // An offset pointing as far after the archive as possible, with a
+            // length which extends that range even further
+            craft(Some(FixedIsize::MAX), Some(FixedUsize::MAX)),
+        ];
+        for bytes in crafted.iter() {
+            assert!(
+                access::<ArchivedVec<Archived<u32>>, Failure>(bytes).is_err()
+            );
+            assert!(from_bytes::<Vec<u32>, Failure>(bytes).is_err());
+        }
+
+        // The archive is valid before it is modified
+        let bytes = craft(None, None);
+        assert_eq!(
+            from_bytes::<Vec<u32>, Failure>(&bytes).unwrap(),
+            [1, 2, 3, 4],
+        );
+    }
+}
@@ -0,0 +1,604 @@
+//! Proofs that an archive has already been validated.
+//!
+//! Validating an archive walks its entire structure, which can take much
+//! longer than reading the bytes it's made of. Archives which are opened many
+//! times without changing can be validated once with
+//! [`ValidationProof::generate`], and then opened with [`access_with_proof`].
+//! Checking a proof only hashes the bytes of the archive and compares a few
+//! fields, and falls back to validating the archive if the proof does not
+//! match.
+//!
+//! A proof records:
+//!
+//! - The [`FORMAT_FINGERPRINT`] of the rkyv which generated it, which covers
+//!   the version of rkyv and the format features it was built with.
+//! - The [`TYPE_HASH`](TypeHash::TYPE_HASH) of the root type.
+//! - The length and hash of the archive.
+//! - The largest alignment checked while validating the archive, and the
+//!   offset of the archive from that alignment.
+//!
+//! The archive is hashed with one of the [`ProofHash`] functions. XXH64 is
+//! always available, and the `xxh3` and `blake3` features enable XXH3 and
+//! BLAKE3. [`ValidationProof::generate`] uses the strongest hash that is
+//! enabled.
+//!
+//! Proofs are converted to and from [`PROOF_SIZE`] bytes with
+//! [`ValidationProof::to_bytes`] and [`ValidationProof::from_bytes`], so they
+//! can be stored next to the archive in a sidecar file or an extended
+//! attribute. All fields are little-endian:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 4    | [`PROOF_MAGIC`]                                        |
+//! | 4      | 1    | [`PROOF_VERSION`]                                      |
+//! | 5      | 1    | Hash: 1 for XXH64, 2 for XXH3-128, 3 for BLAKE3        |
+//! | 6      | 2    | Reserved, must be zero                                 |
+//! | 8      | 8    | Format fingerprint                                     |
+//! | 16     | 8    | Type hash of the root type                             |
+//! | 24     | 8    | Length of the archive                                  |
+//! | 32     | 4    | Largest alignment checked during validation            |
+//! | 36     | 4    | Offset of the archive from that alignment              |
+//! | 40     | 32   | Hash of the archive, padded with zeroes                |
+//!
+//! A proof is only as trustworthy as the place it's stored, so accessing an
+//! archive with a proof is unsafe. When the proof uses BLAKE3, the archive
+//! itself may come from an untrusted source because no other archive can be
+//! found with the same hash. XXH64 and XXH3 are not cryptographic hashes, and
+//! archives can be crafted to match proofs which use them.
+//!
+//! # Example
+//!
+//! ```
+//! use rkyv::{
+//!     envelope::TypeHash,
+//!     rancor::Failure,
+//!     to_bytes,
+//!     validation::proof::{access_with_proof, ValidationProof},
+//!     Archive, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(check_bytes, type_hash)]
+//! struct Index {
+//!     names: Vec<String>,
+//! }
+//!
+//! let value = Index {
+//!     names: vec!["a".to_string(), "b".to_string()],
+//! };
+//! let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+//!
+//! let proof = ValidationProof::generate::<Index, Failure>(&bytes).unwrap();
+//! let stored = proof.to_bytes();
+//!
+//! // Later, after reading the archive and the proof back in
+//! let proof = ValidationProof::from_bytes::<Failure>(&stored).unwrap();
+//! // SAFETY: The proof was generated by `ValidationProof::generate` and the
+//! // archive was not crafted to match it.
+//! let archived =
+//!     unsafe { access_with_proof::<Index, Failure>(&bytes, &proof) }
+//!         .unwrap();
+//! assert_eq!(archived.names[1], "b");
+//! ```
+
+use core::{fmt, mem::size_of};
+
+use bytecheck::CheckBytes;
+use rancor::{fail, Error, Strategy};
+
+use crate::{
+    envelope::{hash_type, TypeHash},
+    hash::xxh64,
+    primitive::FixedUsize,
+    util::access_pos_unchecked,
+    validation::{
+        util::{access, check_pos_with_context},
+        validators::DefaultValidator,
+    },
+    Archive, Portable,
+};
+
+/// The magic bytes which start every serialized proof.
+pub const PROOF_MAGIC: [u8; 4] = *b"RKVP";
+
+/// The version of the proof format written by this version of rkyv.
+pub const PROOF_VERSION: u8 = 2;
+
+/// The size of a serialized proof in bytes.
+pub const PROOF_SIZE: usize = 72;
+
+/// The size of the hash of the archive in a proof in bytes.
+pub const PROOF_HASH_SIZE: usize = 32;
+
+/// A hash function which a proof can use to hash its archive.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ProofHash {
+    /// XXH64 with a seed of zero.
+    Xxh64,
+    /// The 128-bit variant of XXH3. Requires the `xxh3` feature.
+    #[cfg(feature = "xxh3")]
+    Xxh3,
+    /// BLAKE3. Requires the `blake3` feature.
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl ProofHash {
+    /// The strongest hash function which is enabled.
+    pub const STRONGEST: Self = {
+        #[cfg(feature = "blake3")]
+        let result = Self::Blake3;
+        #[cfg(all(feature = "xxh3", not(feature = "blake3")))]
+        let result = Self::Xxh3;
+        #[cfg(not(any(feature = "xxh3", feature = "blake3")))]
+        let result = Self::Xxh64;
+        result
+    };
+
+    /// Returns the ID of the hash function in serialized proofs.
+    #[inline]
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Xxh64 => 1,
+            #[cfg(feature = "xxh3")]
+            Self::Xxh3 => 2,
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => 3,
+        }
+    }
+
+    /// Returns the hash function with the given ID, if it is enabled.
+    #[inline]
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Xxh64),
+            #[cfg(feature = "xxh3")]
+            2 => Some(Self::Xxh3),
+            #[cfg(feature = "blake3")]
+            3 => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the hash function is cryptographic.
+    ///
+    /// Archives can't be crafted to match proofs which use a cryptographic
+    /// hash.
+    #[inline]
+    pub fn is_cryptographic(self) -> bool {
+        match self {
+            Self::Xxh64 => false,
+            #[cfg(feature = "xxh3")]
+            Self::Xxh3 => false,
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => true,
+        }
+    }
+
+    /// Hashes the given bytes.
+    ///
+    /// Hashes shorter than [`PROOF_HASH_SIZE`] bytes are written in
+    /// little-endian order and padded with zeroes.
+    pub fn hash(self, bytes: &[u8]) -> [u8; PROOF_HASH_SIZE] {
+        let mut result = [0; PROOF_HASH_SIZE];
+        match self {
+            Self::Xxh64 => {
+                result[..8].copy_from_slice(&xxh64(bytes, 0).to_le_bytes());
+            }
+            #[cfg(feature = "xxh3")]
+            Self::Xxh3 => {
+                let hash = xxhash_rust::xxh3::xxh3_128(bytes);
+                result[..16].copy_from_slice(&hash.to_le_bytes());
+            }
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => {
+                result.copy_from_slice(blake3::hash(bytes).as_bytes());
+            }
+        }
+        result
+    }
+}
+
+impl Default for ProofHash {
+    #[inline]
+    fn default() -> Self {
+        Self::STRONGEST
+    }
+}
+
+impl fmt::Display for ProofHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Xxh64 => write!(f, "XXH64"),
+            #[cfg(feature = "xxh3")]
+            Self::Xxh3 => write!(f, "XXH3-128"),
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => write!(f, "BLAKE3"),
+        }
+    }
+}
+
+/// A hash of the version of rkyv and the features which change the format of
+/// archives.
+///
+/// Proofs generated by an rkyv with a different fingerprint are never
+/// accepted, since it may validate archives differently.
+pub const FORMAT_FINGERPRINT: u64 = hash_type(
+    concat!("rkyv ", env!("CARGO_PKG_VERSION")),
+    &[
+        cfg!(feature = "big_endian") as u64,
+        cfg!(feature = "unaligned") as u64,
+        size_of::<FixedUsize>() as u64,
+    ],
+);
+
+/// The reason that a proof was not accepted for an archive.
+#[derive(Debug)]
+pub enum ProofError {
+    /// The serialized proof is not [`PROOF_SIZE`] bytes long.
+    WrongSize {
+        /// The length of the serialized proof.
+        len: usize,
+    },
+    /// The serialized proof does not start with [`PROOF_MAGIC`].
+    BadMagic {
+        /// The first bytes of the serialized proof.
+        found: [u8; 4],
+    },
+    /// The proof has a version which this version of rkyv can't read.
+    UnsupportedVersion {
+        /// The version of the proof.
+        found: u8,
+    },
+    /// The proof uses a hash which this version of rkyv doesn't know, or
+    /// which is not enabled.
+    UnknownHash {
+        /// The ID of the hash.
+        found: u8,
+    },
+    /// The reserved bytes of the proof are not zero.
+    NonZeroReserved,
+    /// The proof was generated by an rkyv with a different format
+    /// fingerprint.
+    FormatMismatch {
+        /// The fingerprint of this rkyv.
+        expected: u64,
+        /// The fingerprint in the proof.
+        found: u64,
+    },
+    /// The proof was generated for a different root type.
+    WrongType {
+        /// The type hash of the type being accessed.
+        expected: u64,
+        /// The type hash in the proof.
+        found: u64,
+    },
+    /// The archive has a different length than the proof.
+    LengthMismatch {
+        /// The length in the proof.
+        expected: u64,
+        /// The length of the archive.
+        found: usize,
+    },
+    /// The archive has a different hash than the proof.
+    HashMismatch {
+        /// The hash function used by the proof.
+        hash_fn: ProofHash,
+    },
+    /// The archive is at an address which is aligned differently than when
+    /// the proof was generated.
+    AlignmentMismatch {
+        /// The largest alignment checked during validation.
+        align: u32,
+        /// The offset of the archive from that alignment in the proof.
+        expected: u32,
+        /// The offset of the archive from that alignment.
+        found: u32,
+    },
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::WrongSize { len } => write!(
+                f,
+                "serialized proof is {} bytes long, expected {}",
+                len, PROOF_SIZE,
+            ),
+            ProofError::BadMagic { found } => {
+                write!(f, "proof has bad magic bytes {:?}", found)
+            }
+            ProofError::UnsupportedVersion { found } => write!(
+                f,
+                "proof version {} is not supported, expected {}",
+                found, PROOF_VERSION,
+            ),
+            ProofError::UnknownHash { found } => {
+                write!(f, "proof has unknown or disabled hash ID {}", found)
+            }
+            ProofError::NonZeroReserved => {
+                write!(f, "reserved bytes of proof are not zero")
+            }
+            ProofError::FormatMismatch { expected, found } => write!(
+                f,
+                "proof has format fingerprint {:#018x} but expected {:#018x}",
+                found, expected,
+            ),
+            ProofError::WrongType { expected, found } => write!(
+                f,
+                "proof contains type hash {:#018x} but expected {:#018x}",
+                found, expected,
+            ),
+            ProofError::LengthMismatch { expected, found } => write!(
+                f,
+                "proof is for an archive of {} bytes but the archive is {} \
+                 bytes long",
+                expected, found,
+            ),
+            ProofError::HashMismatch { hash_fn } => write!(
+                f,
+                "archive does not match the {} hash in the proof",
+                hash_fn,
+            ),
+            ProofError::AlignmentMismatch {
+                align,
+                expected,
+                found,
+            } => write!(
+                f,
+                "archive is {} bytes past a multiple of {} but the proof \
+                 requires {}",
+                found, align, expected,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProofError {}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut result = [0; 8];
+    result.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(result)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut result = [0; 4];
+    result.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(result)
+}
+
+#[inline]
+fn align_offset(bytes: &[u8], align: u32) -> u32 {
+    (bytes.as_ptr().addr() % align as usize) as u32
+}
+
+/// A proof that an archive was validated.
+///
+/// See the [module docs](self) for more information.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ValidationProof {
+    /// The [`FORMAT_FINGERPRINT`] of the rkyv which generated the proof.
+    pub format: u64,
+    /// The [`TYPE_HASH`](TypeHash::TYPE_HASH) of the root type.
+    pub type_hash: u64,
+    /// The length of the archive in bytes.
+    pub len: u64,
+    /// The largest alignment checked while validating the archive.
+    pub align: u32,
+    /// The offset of the archive from `align` when it was validated.
+    pub align_offset: u32,
+    /// The hash function used to hash the archive.
+    pub hash_fn: ProofHash,
+    /// The hash of the archive, as returned by [`ProofHash::hash`].
+    pub hash: [u8; PROOF_HASH_SIZE],
+}
+
+impl ValidationProof {
+    /// Validates the archived root object at the end of the given bytes, and
+    /// returns a proof that it is valid.
+    ///
+    /// The proof uses the [strongest](ProofHash::STRONGEST) hash function
+    /// which is enabled.
+    #[inline]
+    pub fn generate<T, E>(bytes: &[u8]) -> Result<Self, E>
+    where
+        T: TypeHash + Archive,
+        T::Archived: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        Self::generate_with::<T, E>(bytes, ProofHash::STRONGEST)
+    }
+
+    /// Validates the archived root object at the end of the given bytes, and
+    /// returns a proof that it is valid which uses the given hash function.
+    pub fn generate_with<T, E>(
+        bytes: &[u8],
+        hash_fn: ProofHash,
+    ) -> Result<Self, E>
+    where
+        T: TypeHash + Archive,
+        T::Archived: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+        E: Error,
+    {
+        let mut validator = DefaultValidator::new(bytes);
+        check_pos_with_context::<T::Archived, _, E>(
+            bytes,
+            bytes.len().saturating_sub(size_of::<T::Archived>()),
+            &mut validator,
+        )?;
+
+        // Alignments of Rust types are at most 2^29, so this can't truncate.
+        let align = validator.max_alignment() as u32;
+        Ok(Self {
+            format: FORMAT_FINGERPRINT,
+            type_hash: T::TYPE_HASH,
+            len: bytes.len() as u64,
+            align,
+            align_offset: align_offset(bytes, align),
+            hash_fn,
+            hash: hash_fn.hash(bytes),
+        })
+    }
+
+    /// Checks that this proof was generated for the given bytes and root type
+    /// by an rkyv with the same format as this one.
+    ///
+    /// This hashes the bytes but does not validate them.
+    pub fn verify<T, E>(&self, bytes: &[u8]) -> Result<(), E>
+    where
+        T: TypeHash,
+        E: Error,
+    {
+        if self.format != FORMAT_FINGERPRINT {
+            fail!(ProofError::FormatMismatch {
+                expected: FORMAT_FINGERPRINT,
+                found: self.format,
+            });
+        }
+        if self.type_hash != T::TYPE_HASH {
+            fail!(ProofError::WrongType {
+                expected: T::TYPE_HASH,
+                found: self.type_hash,
+            });
+        }
+        if self.len != bytes.len() as u64 {
+            fail!(ProofError::LengthMismatch {
+                expected: self.len,
+                found: bytes.len(),
+            });
+        }
+        // A proof with a zero alignment can't have come from `generate`.
+        let offset = align_offset(bytes, self.align.max(1));
+        if self.align == 0 || offset != self.align_offset {
+            fail!(ProofError::AlignmentMismatch {
+                align: self.align,
+                expected: self.align_offset,
+                found: offset,
+            });
+        }
+        if self.hash_fn.hash(bytes) != self.hash {
+            fail!(ProofError::HashMismatch {
+                hash_fn: self.hash_fn,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the serialized bytes of this proof.
+    pub fn to_bytes(&self) -> [u8; PROOF_SIZE] {
+        let mut result = [0; PROOF_SIZE];
+        result[0..4].copy_from_slice(&PROOF_MAGIC);
+        result[4] = PROOF_VERSION;
+        result[5] = self.hash_fn.id();
+        result[8..16].copy_from_slice(&self.format.to_le_bytes());
+        result[16..24].copy_from_slice(&self.type_hash.to_le_bytes());
+        result[24..32].copy_from_slice(&self.len.to_le_bytes());
+        result[32..36].copy_from_slice(&self.align.to_le_bytes());
+        result[36..40].copy_from_slice(&self.align_offset.to_le_bytes());
+        result[40..72].copy_from_slice(&self.hash);
+        result
+    }
+
+    /// Reads a proof from the bytes returned by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes<E: Error>(bytes: &[u8]) -> Result<Self, E> {
+        if bytes.len() != PROOF_SIZE {
+            fail!(ProofError::WrongSize { len: bytes.len() });
+        }
+        let mut magic = [0; 4];
+        magic.copy_from_slice(&bytes[0..4]);
+        if magic != PROOF_MAGIC {
+            fail!(ProofError::BadMagic { found: magic });
+        }
+        if bytes[4] != PROOF_VERSION {
+            fail!(ProofError::UnsupportedVersion { found: bytes[4] });
+        }
+        let Some(hash_fn) = ProofHash::from_id(bytes[5]) else {
+            fail!(ProofError::UnknownHash { found: bytes[5] });
+        };
+        if bytes[6] != 0 || bytes[7] != 0 {
+            fail!(ProofError::NonZeroReserved);
+        }
+
+        let mut hash = [0; PROOF_HASH_SIZE];
+        hash.copy_from_slice(&bytes[40..72]);
+        Ok(Self {
+            format: read_u64(bytes, 8),
+            type_hash: read_u64(bytes, 16),
+            len: read_u64(bytes, 24),
+            align: read_u32(bytes, 32),
+            align_offset: read_u32(bytes, 36),
+            hash_fn,
+            hash,
+        })
+    }
+}
+
+/// Accesses the archived root object at the end of the given bytes with a
+/// proof that it's valid.
+///
+/// If the proof doesn't match the bytes, the archive is validated instead. Use
+/// [`access_with_proof_strict`] to return an error instead.
+///
+/// # Safety
+///
+/// The proof must have been returned from [`ValidationProof::generate`], or
+/// read from the bytes of such a proof. Proofs from untrusted sources must not
+/// be used. Unless the proof uses a
+/// [cryptographic](ProofHash::is_cryptographic) hash, the bytes must also not
+/// have been crafted to match the proof.
+pub unsafe fn access_with_proof<'a, T, E>(
+    bytes: &'a [u8],
+    proof: &ValidationProof,
+) -> Result<&'a T::Archived, E>
+where
+    T: TypeHash + Archive,
+    T::Archived: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: Error,
+{
+    match proof.verify::<T, E>(bytes) {
+        // SAFETY: The caller has guaranteed that the proof was generated by
+        // validating an archive, and the bytes match that archive.
+        Ok(()) => unsafe { Ok(access_root_unchecked::<T>(bytes)) },
+        Err(_) => access::<T::Archived, E>(bytes),
+    }
+}
+
+/// Accesses the archived root object at the end of the given bytes with a
+/// proof that it's valid, returning an error if the proof doesn't match.
+///
+/// # Safety
+///
+/// The proof must have been returned from [`ValidationProof::generate`], or
+/// read from the bytes of such a proof. Proofs from untrusted sources must not
+/// be used. Unless the proof uses a
+/// [cryptographic](ProofHash::is_cryptographic) hash, the bytes must also not
+/// have been crafted to match the proof.
+pub unsafe fn access_with_proof_strict<'a, T, E>(
+    bytes: &'a [u8],
+    proof: &ValidationProof,
+) -> Result<&'a T::Archived, E>
+where
+    T: TypeHash + Archive,
+    E: Error,
+{
+    proof.verify::<T, E>(bytes)?;
+    // SAFETY: The caller has guaranteed that the proof was generated by
+    // validating an archive, and the bytes match that archive.
+    unsafe { Ok(access_root_unchecked::<T>(bytes)) }
+}
+
+/// # Safety
+///
+/// A proof for `T` must have been verified for `bytes`.
+#[inline]
+unsafe fn access_root_unchecked<T: Archive>(bytes: &[u8]) -> &T::Archived {
+    unsafe {
+        access_pos_unchecked::<T::Archived>(
+            bytes,
+            bytes.len() - size_of::<T::Archived>(),
+        )
+    }
+}
@@ -0,0 +1,626 @@
+//! Validation of forward-only archives as their bytes arrive.
+//!
+//! Regular archives place the values in a collection after all of their
+//! out-of-line data, so no value can be checked until the end of the archive
+//! has been received. Forward-only archives, serialized with
+//! [`to_bytes_forward_only`](crate::util::to_bytes_forward_only), are laid out
+//! so that they can be validated in order instead:
+//!
+//! 1. A header containing the number of elements and the position of the root
+//!    as two [`ArchivedUsize`]s.
+//! 2. For each element, a frame containing the position of the archived
+//!    element as an [`ArchivedUsize`], followed by the out-of-line data of the
+//!    element and then the archived element itself.
+//! 3. The root, an archived `Vec<Box<T>>` which points to each element.
+//!
+//! Only the root points to data before the frame it is located in, so each
+//! element can be validated as soon as its frame has arrived. The frames and
+//! header are not pointed to by anything, so forward-only archives are also
+//! regular archives of `Vec<Box<T>>`.
+//!
+//! [`StreamValidator`] validates forward-only archives chunk by chunk, and
+//! [`access_forward_only`](crate::validation::util::access_forward_only)
+//! validates a forward-only archive which has been received completely.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::{
+    alloc::Layout,
+    any::TypeId,
+    fmt,
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    ops::Range,
+};
+
+use bytecheck::CheckBytes;
+use rancor::{fail, Error, Strategy};
+
+use crate::{
+    boxed::ArchivedBox,
+    primitive::ArchivedUsize,
+    util::{AlignedVec, FORWARD_ONLY_HEADER_SIZE},
+    validation::{
+        util::check_pos_with_context,
+        validators::{ArchiveError, ArchiveValidator, SharedValidator},
+        ArchiveContext, SharedContext,
+    },
+    vec::ArchivedVec,
+    Portable,
+};
+
+/// The progress of a [`StreamValidator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamStatus {
+    /// All of the bytes received so far are valid, but more bytes are needed
+    /// to finish validating the archive.
+    Pending,
+    /// The archive is complete and valid. Contains the position of the root.
+    Complete(usize),
+}
+
+/// Errors that can occur while validating a forward-only archive.
+#[derive(Debug)]
+pub enum StreamError {
+    /// The input ended before the archive was complete.
+    Incomplete {
+        /// The number of bytes received
+        received: usize,
+    },
+    /// The header of the archive was invalid.
+    InvalidHeader {
+        /// The number of elements in the header
+        len: usize,
+        /// The position of the root in the header
+        root_pos: usize,
+    },
+    /// The frame of an element did not contain a valid element position.
+    InvalidFrame {
+        /// The index of the element
+        index: usize,
+        /// The position of the element in the frame
+        pos: usize,
+    },
+    /// The root of the archive did not point to its elements.
+    InvalidRoot,
+    /// More bytes were received after the end of the archive.
+    TrailingBytes {
+        /// The length of the archive
+        expected: usize,
+        /// The number of bytes received
+        received: usize,
+    },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Incomplete { received } => write!(
+                f,
+                "input ended after {} bytes before the archive was complete",
+                received,
+            ),
+            StreamError::InvalidHeader { len, root_pos } => write!(
+                f,
+                "invalid forward-only header: {} elements with root at {}",
+                len, root_pos,
+            ),
+            StreamError::InvalidFrame { index, pos } => write!(
+                f,
+                "invalid frame for element {}: element position {}",
+                index, pos,
+            ),
+            StreamError::InvalidRoot => write!(
+                f,
+                "the root of the archive does not point to its elements",
+            ),
+            StreamError::TrailingBytes { expected, received } => write!(
+                f,
+                "received {} bytes for an archive of {} bytes",
+                received, expected,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StreamError {}
+
+/// The validation context used to check the elements of forward-only archives.
+///
+/// This checks subtrees like an [`ArchiveValidator`] restricted to the frame of
+/// an element, and shared pointers like a [`SharedValidator`].
+#[derive(Debug)]
+pub struct StreamContext {
+    archive: ArchiveValidator,
+    shared: SharedValidator,
+}
+
+unsafe impl<E: Error> ArchiveContext<E> for StreamContext {
+    #[inline]
+    fn check_subtree_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), E> {
+        self.archive.check_subtree_ptr(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn push_prefix_subtree_range(
+        &mut self,
+        root: *const u8,
+        end: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_prefix_subtree_range(root, end)
+    }
+
+    #[inline]
+    unsafe fn push_suffix_subtree_range(
+        &mut self,
+        start: *const u8,
+        root: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_suffix_subtree_range(start, root)
+    }
+
+    #[inline]
+    unsafe fn pop_subtree_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<(), E> {
+        unsafe { self.archive.pop_subtree_range(range) }
+    }
+
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        ArchiveContext::<E>::archive_start(&self.archive)
+    }
+}
+
+impl<E: Error> SharedContext<E> for StreamContext {
+    #[inline]
+    fn register_shared_ptr(
+        &mut self,
+        address: usize,
+        type_id: TypeId,
+    ) -> Result<bool, E> {
+        self.shared.register_shared_ptr(address, type_id)
+    }
+}
+
+#[inline]
+fn align_up(pos: usize, align: usize) -> usize {
+    (pos + align - 1) & !(align - 1)
+}
+
+// SAFETY: `pos` must be aligned for `ArchivedUsize` and `bytes` must contain
+// at least `pos + size_of::<ArchivedUsize>()` bytes.
+#[inline]
+unsafe fn read_usize(bytes: &[u8], pos: usize) -> usize {
+    unsafe {
+        (*bytes.as_ptr().add(pos).cast::<ArchivedUsize>()).to_native() as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Header {
+    len: usize,
+    root_pos: usize,
+}
+
+impl Header {
+    #[inline]
+    fn total_len<T>(&self) -> usize {
+        self.root_pos + size_of::<ArchivedVec<ArchivedBox<T>>>()
+    }
+}
+
+/// The validation progress through a forward-only archive.
+#[derive(Debug)]
+pub(crate) struct StreamState {
+    header: Option<Header>,
+    // The position of the next frame
+    frame: usize,
+    // The positions of the elements which have been validated
+    elements: Vec<usize>,
+}
+
+impl StreamState {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            header: None,
+            frame: FORWARD_ONLY_HEADER_SIZE,
+            elements: Vec::new(),
+        }
+    }
+
+    fn read_header<T, E: Error>(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<Option<Header>, E> {
+        if let Some(header) = self.header {
+            return Ok(Some(header));
+        }
+        if bytes.len() < FORWARD_ONLY_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        if bytes.as_ptr().addr() & (AlignedVec::ALIGNMENT - 1) != 0 {
+            fail!(ArchiveError::Unaligned {
+                address: bytes.as_ptr().addr(),
+                align: AlignedVec::ALIGNMENT,
+            });
+        }
+
+        // SAFETY: The bytes are aligned and contain the header.
+        let (len, root_pos) = unsafe {
+            (
+                read_usize(bytes, 0),
+                read_usize(bytes, size_of::<ArchivedUsize>()),
+            )
+        };
+        let is_valid = root_pos >= FORWARD_ONLY_HEADER_SIZE
+            && root_pos & (align_of::<ArchivedVec<ArchivedBox<T>>>() - 1) == 0
+            && root_pos
+                .checked_add(size_of::<ArchivedVec<ArchivedBox<T>>>())
+                .is_some_and(|end| end <= isize::MAX as usize);
+        if !is_valid {
+            fail!(StreamError::InvalidHeader { len, root_pos });
+        }
+
+        let header = Header { len, root_pos };
+        self.header = Some(header);
+        Ok(Some(header))
+    }
+
+    /// Validates as much of `bytes` as possible.
+    ///
+    /// `bytes` must start with the bytes passed to previous calls. If
+    /// `is_final` is `true`, no more bytes will be received.
+    pub(crate) fn advance<T, E>(
+        &mut self,
+        bytes: &[u8],
+        is_final: bool,
+    ) -> Result<StreamStatus, E>
+    where
+        T: Portable + CheckBytes<Strategy<StreamContext, E>>,
+        E: Error,
+    {
+        let status = self.advance_elements::<T, E>(bytes)?;
+        if status == StreamStatus::Pending && is_final {
+            fail!(StreamError::Incomplete {
+                received: bytes.len(),
+            });
+        }
+        Ok(status)
+    }
+
+    fn advance_elements<T, E>(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<StreamStatus, E>
+    where
+        T: Portable + CheckBytes<Strategy<StreamContext, E>>,
+        E: Error,
+    {
+        let header = match self.read_header::<T, E>(bytes)? {
+            Some(header) => header,
+            None => return Ok(StreamStatus::Pending),
+        };
+
+        while self.elements.len() < header.len {
+            let data_start = self.frame + size_of::<ArchivedUsize>();
+            if bytes.len() < data_start {
+                return Ok(StreamStatus::Pending);
+            }
+
+            // SAFETY: Frames are aligned for `ArchivedUsize` and the frame has
+            // been received.
+            let pos = unsafe { read_usize(bytes, self.frame) };
+            let is_valid = pos >= data_start
+                && pos & (align_of::<T>() - 1) == 0
+                && pos
+                    .checked_add(size_of::<T>())
+                    .is_some_and(|end| end <= header.root_pos);
+            if !is_valid {
+                fail!(StreamError::InvalidFrame {
+                    index: self.elements.len(),
+                    pos,
+                });
+            }
+            let end = pos + size_of::<T>();
+            if bytes.len() < end {
+                return Ok(StreamStatus::Pending);
+            }
+
+            let mut context = StreamContext {
+                archive: ArchiveValidator::with_subtree_range(
+                    bytes,
+                    data_start..end,
+                ),
+                shared: SharedValidator::new(),
+            };
+            check_pos_with_context::<T, StreamContext, E>(
+                bytes,
+                pos,
+                &mut context,
+            )?;
+
+            self.elements.push(pos);
+            self.frame = align_up(end, align_of::<ArchivedUsize>());
+        }
+
+        let total_len = header.total_len::<T>();
+        if bytes.len() < total_len {
+            return Ok(StreamStatus::Pending);
+        } else if bytes.len() > total_len {
+            fail!(StreamError::TrailingBytes {
+                expected: total_len,
+                received: bytes.len(),
+            });
+        }
+
+        self.check_root::<T, E>(bytes, header)?;
+        Ok(StreamStatus::Complete(header.root_pos))
+    }
+
+    fn check_root<T: Portable, E: Error>(
+        &self,
+        bytes: &[u8],
+        header: Header,
+    ) -> Result<(), E> {
+        // SAFETY: The root is aligned and has been received. Every bit pattern
+        // is a valid `ArchivedVec`.
+        let root = unsafe {
+            &*bytes
+                .as_ptr()
+                .add(header.root_pos)
+                .cast::<ArchivedVec<ArchivedBox<T>>>()
+        };
+        if root.len() != header.len {
+            fail!(StreamError::InvalidRoot);
+        }
+
+        // The boxes must be located between the last frame and the root.
+        let boxes = root
+            .as_ptr_wrapping()
+            .addr()
+            .wrapping_sub(bytes.as_ptr().addr());
+        let boxes_end = header
+            .len
+            .checked_mul(size_of::<ArchivedBox<T>>())
+            .and_then(|size| size.checked_add(boxes));
+        let is_valid = boxes >= self.frame
+            && boxes & (align_of::<ArchivedBox<T>>() - 1) == 0
+            && boxes_end.is_some_and(|end| end <= header.root_pos);
+        if !is_valid {
+            fail!(StreamError::InvalidRoot);
+        }
+
+        for (i, &pos) in self.elements.iter().enumerate() {
+            // SAFETY: The boxes are aligned and located inside `bytes`. Every
+            // bit pattern is a valid `ArchivedBox` of a sized type.
+            let boxed = unsafe {
+                &*bytes
+                    .as_ptr()
+                    .add(boxes + i * size_of::<ArchivedBox<T>>())
+                    .cast::<ArchivedBox<T>>()
+            };
+            if boxed.as_ptr_wrapping().cast::<u8>()
+                != bytes.as_ptr().wrapping_add(pos)
+            {
+                fail!(StreamError::InvalidRoot);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A validator for forward-only archives which checks each element as soon as
+/// its bytes have arrived.
+///
+/// `T` is the archived type of the elements, and the root of the archive is an
+/// archived `Vec<Box<T>>`. Bytes are passed to the validator in chunks with
+/// [`push`](Self::push), and an invalid element is reported as soon as the
+/// chunk which completes it has been pushed. The received bytes are kept so
+/// that the archive can be accessed without validating it again once it is
+/// complete.
+///
+/// See the [module documentation](self) for the layout of forward-only
+/// archives.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     util::to_bytes_forward_only,
+///     validation::stream::{StreamStatus, StreamValidator},
+///     Archive, Archived, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Record {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// let records = (0..100)
+///     .map(|id| Record {
+///         id,
+///         name: format!("a record with a long name, number {id}"),
+///     })
+///     .collect::<Vec<_>>();
+/// let bytes = to_bytes_forward_only::<_, 256, Failure>(&records).unwrap();
+///
+/// let mut validator = StreamValidator::<Archived<Record>>::new();
+/// let mut status = StreamStatus::Pending;
+/// for chunk in bytes.chunks(64) {
+///     status = validator.push::<Failure>(chunk).unwrap();
+/// }
+/// assert!(matches!(status, StreamStatus::Complete(_)));
+///
+/// let archived = validator.archive().unwrap();
+/// assert_eq!(archived[42].name, records[42].name);
+/// ```
+#[derive(Debug)]
+pub struct StreamValidator<T> {
+    bytes: AlignedVec,
+    state: StreamState,
+    status: StreamStatus,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for StreamValidator<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> StreamValidator<T> {
+    /// Creates a new stream validator which has not received any bytes.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            bytes: AlignedVec::new(),
+            state: StreamState::new(),
+            status: StreamStatus::Pending,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the bytes received so far.
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the number of bytes received so far.
+    #[inline]
+    pub fn received(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the number of elements which have been validated so far.
+    #[inline]
+    pub fn validated(&self) -> usize {
+        self.state.elements.len()
+    }
+
+    /// Returns the total length of the archive in bytes, if its header has
+    /// been received.
+    #[inline]
+    pub fn expected_len(&self) -> Option<usize> {
+        self.state.header.map(|header| header.total_len::<T>())
+    }
+
+    /// Returns the progress of the validator.
+    #[inline]
+    pub fn status(&self) -> StreamStatus {
+        self.status
+    }
+
+    /// Consumes the stream validator and returns the bytes received.
+    #[inline]
+    pub fn into_bytes(self) -> AlignedVec {
+        self.bytes
+    }
+}
+
+impl<T: Portable> StreamValidator<T> {
+    /// Receives the next chunk of the archive and validates as much of it as
+    /// possible.
+    ///
+    /// Returns [`StreamStatus::Complete`] once the whole archive has been
+    /// received and validated. Once an error has been returned, the stream
+    /// validator should be discarded.
+    pub fn push<E>(&mut self, chunk: &[u8]) -> Result<StreamStatus, E>
+    where
+        T: CheckBytes<Strategy<StreamContext, E>>,
+        E: Error,
+    {
+        self.bytes.extend_from_slice(chunk);
+        self.status = self.state.advance::<T, E>(&self.bytes, false)?;
+        Ok(self.status)
+    }
+
+    /// Signals that no more bytes will be received.
+    ///
+    /// Returns the position of the root if the archive is complete, and an
+    /// error otherwise.
+    pub fn finish<E>(&mut self) -> Result<usize, E>
+    where
+        T: CheckBytes<Strategy<StreamContext, E>>,
+        E: Error,
+    {
+        match self.state.advance::<T, E>(&self.bytes, true)? {
+            StreamStatus::Complete(pos) => Ok(pos),
+            StreamStatus::Pending => unreachable!(),
+        }
+    }
+
+    /// Reads the archive from the given reader, validating it as its bytes
+    /// arrive.
+    ///
+    /// Bytes past the end of the archive are not read. Returns the position of
+    /// the root once the archive is complete.
+    #[cfg(feature = "std")]
+    pub fn read_from<R, E>(&mut self, reader: &mut R) -> Result<usize, E>
+    where
+        R: std::io::Read + ?Sized,
+        T: CheckBytes<Strategy<StreamContext, E>>,
+        E: Error,
+    {
+        use rancor::ResultExt as _;
+
+        const CHUNK_SIZE: usize = 4096;
+
+        let mut buffer = [0u8; CHUNK_SIZE];
+        loop {
+            if let StreamStatus::Complete(pos) = self.status {
+                return Ok(pos);
+            }
+
+            let remaining = match self.expected_len() {
+                Some(len) => len.saturating_sub(self.received()),
+                None => {
+                    FORWARD_ONLY_HEADER_SIZE.saturating_sub(self.received())
+                }
+            };
+            let chunk = &mut buffer[..usize::clamp(remaining, 1, CHUNK_SIZE)];
+            let read = match reader.read(chunk) {
+                Ok(0) => return self.finish(),
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                    continue
+                }
+                Err(e) => return Err(e).into_error(),
+            };
+            self.push::<E>(&chunk[..read])?;
+        }
+    }
+
+    /// Returns the archived elements once the archive is complete.
+    #[inline]
+    pub fn archive(&self) -> Option<&ArchivedVec<ArchivedBox<T>>> {
+        match self.status {
+            // SAFETY: The archive has been validated and the root is located
+            // at `pos`.
+            StreamStatus::Complete(pos) => unsafe {
+                Some(
+                    &*self
+                        .bytes
+                        .as_ptr()
+                        .add(pos)
+                        .cast::<ArchivedVec<ArchivedBox<T>>>(),
+                )
+            },
+            StreamStatus::Pending => None,
+        }
+    }
+}
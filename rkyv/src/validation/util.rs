@@ -1,19 +1,30 @@
 //! Utility methods for accessing and deserializing safely.
 
-use core::{mem::size_of, pin::Pin};
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+use core::{alloc::Layout, mem::size_of, pin::Pin};
 
 use bytecheck::CheckBytes;
 use ptr_meta::Pointee;
 use rancor::{Error, ResultExt as _, Strategy};
 
 use crate::{
-    de::pooling::Unify,
-    deserialize,
+    boxed::ArchivedBox,
     util::{access_pos_unchecked, access_pos_unchecked_mut},
+    validation::{ArchiveContext, ArchiveContextExt as _},
+    ArchivePointee, Portable,
+};
+#[cfg(feature = "alloc")]
+use crate::{
+    de::{pooling::Unify, Limited, Limits},
+    deserialize,
+    util::{DefaultConfig, DefaultDeserializer},
     validation::{
-        validators::DefaultValidator, ArchiveContext, ArchiveContextExt as _,
+        stream::{StreamContext, StreamState, StreamStatus},
+        validators::{DefaultValidator, ExhaustiveValidator, ValidationReport},
     },
-    Archive, Deserialize, Portable,
+    vec::ArchivedVec,
+    Archive, Deserialize,
 };
 
 /// Checks a byte slice for a valid instance of the given archived type at the
@@ -31,11 +42,10 @@ where
     unsafe {
         let offset = pos.try_into().into_error()?;
 
-        let ptr = context.bounds_check_subtree_base_offset::<T>(
-            bytes.as_ptr(),
-            offset,
-            (),
-        )?;
+        // The root is not pointed to by a relative pointer, so check it
+        // without recording a subtree pointer.
+        let ptr = bytes.as_ptr().wrapping_offset(offset).cast::<T>();
+        context.check_subtree_ptr(ptr.cast(), &Layout::new::<T>())?;
 
         let range = context.push_prefix_subtree(ptr)?;
         CheckBytes::check_bytes(ptr, Strategy::wrap(context))?;
@@ -125,6 +135,7 @@ where
 /// let bytes = serializer.into_inner();
 /// let archived = check_archived_value::<Example>(bytes.as_ref(), pos).unwrap();
 /// ```
+#[cfg(feature = "alloc")]
 #[inline]
 pub fn access_pos<T, E>(bytes: &[u8], pos: usize) -> Result<&T, E>
 where
@@ -145,6 +156,7 @@ where
 /// This is a safe alternative to [`access_unchecked`][unsafe_version].
 ///
 /// [unsafe_version]: crate::access_unchecked
+#[cfg(feature = "alloc")]
 #[inline]
 pub fn access<T, E>(bytes: &[u8]) -> Result<&T, E>
 where
@@ -155,6 +167,261 @@ where
     access_with_context::<T, DefaultValidator, E>(bytes, &mut validator)
 }
 
+/// Accesses an unsized archived value from the given byte slice by calculating
+/// the root position after checking its validity with the given context.
+///
+/// This is a safe alternative to
+/// [`access_unsized_unchecked`](crate::util::access_unsized_unchecked).
+#[inline]
+pub fn access_unsized_with_context<'a, T, C, E>(
+    bytes: &'a [u8],
+    context: &mut C,
+) -> Result<&'a T, E>
+where
+    T: ArchivePointee + Portable + ?Sized,
+    ArchivedBox<T>: CheckBytes<Strategy<C, E>>,
+    C: ArchiveContext<E> + ?Sized,
+    E: Error,
+{
+    // The root of an unsized archive is a relative pointer to the value, which
+    // has the same layout as an archived box.
+    let root = access_with_context::<ArchivedBox<T>, C, E>(bytes, context)?;
+    Ok(root.get())
+}
+
+/// Accesses an unsized archived value from the given byte slice by calculating
+/// the root position after checking its validity.
+///
+/// This is a safe alternative to
+/// [`access_unsized_unchecked`](crate::util::access_unsized_unchecked) for
+/// bytes serialized with [`to_bytes_unsized`](crate::util::to_bytes_unsized).
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     rancor::Failure, util::to_bytes_unsized,
+///     validation::util::access_unsized, Archived,
+/// };
+///
+/// let values: &[u64] = &[1, 2, 3, 4];
+/// let bytes = to_bytes_unsized::<_, 256, Failure>(values).unwrap();
+/// let archived =
+///     access_unsized::<[Archived<u64>], Failure>(&bytes).unwrap();
+/// assert_eq!(archived, values);
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn access_unsized<T, E>(bytes: &[u8]) -> Result<&T, E>
+where
+    T: ArchivePointee + Portable + ?Sized,
+    ArchivedBox<T>: CheckBytes<Strategy<DefaultValidator, E>>,
+    E: Error,
+{
+    let mut validator = DefaultValidator::new(bytes);
+    access_unsized_with_context::<T, DefaultValidator, E>(bytes, &mut validator)
+}
+
+/// Accesses an archived value from the given byte slice by calculating the root
+/// position after checking its validity. The archive may contain a cold region
+/// at the given range of positions.
+///
+/// Archives serialized with [`Cold`](crate::with::Cold) fields must be accessed
+/// with this function instead of [`access`].
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure, util::to_bytes_with_regions,
+///     validation::util::access_with_cold_region, with::Cold, Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     #[with(Cold)]
+///     blob: Vec<u8>,
+///     name: String,
+/// }
+///
+/// let value = Example {
+///     blob: vec![0xff; 64],
+///     name: "hot".to_string(),
+/// };
+///
+/// let (bytes, cold) =
+///     to_bytes_with_regions::<_, 256, Failure>(&value).unwrap();
+/// let archived =
+///     access_with_cold_region::<ArchivedExample, Failure>(&bytes, cold)
+///         .unwrap();
+/// assert_eq!(archived.blob.as_slice(), &[0xff; 64]);
+/// assert_eq!(archived.name, "hot");
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn access_with_cold_region<T, E>(
+    bytes: &[u8],
+    cold: Range<usize>,
+) -> Result<&T, E>
+where
+    T: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: Error,
+{
+    let mut validator = DefaultValidator::with_cold_region(bytes, cold);
+    access_with_context::<T, DefaultValidator, E>(bytes, &mut validator)
+}
+
+/// Accesses an archived value from the given byte slice by calculating the root
+/// position after checking its validity. Relative pointers may point to
+/// subtrees which have already been claimed.
+///
+/// Archives serialized with [`Intern`](crate::with::Intern) fields must be
+/// accessed with this function instead of [`access`]. See
+/// [`DefaultValidator::set_aliasing`] for more information.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure, to_bytes, validation::util::access_interned,
+///     with::Intern, Archive, Archived, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     #[with(Intern)]
+///     name: String,
+/// }
+///
+/// let value = (0..4)
+///     .map(|_| Example {
+///         name: "a name which is too long to be inline".to_string(),
+///     })
+///     .collect::<Vec<_>>();
+///
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived =
+///     access_interned::<Archived<Vec<Example>>, Failure>(&bytes).unwrap();
+/// assert_eq!(archived[0].name.as_ptr(), archived[3].name.as_ptr());
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn access_interned<T, E>(bytes: &[u8]) -> Result<&T, E>
+where
+    T: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: Error,
+{
+    let mut validator = DefaultValidator::new(bytes);
+    validator.set_aliasing(true);
+    access_with_context::<T, DefaultValidator, E>(bytes, &mut validator)
+}
+
+/// Accesses an archived value from the given byte slice by calculating the root
+/// position after checking its validity, recording invalid elements of
+/// collections instead of failing.
+///
+/// Errors in the elements of archived vecs, hash tables, and B-tree maps are
+/// collected into the returned [`ValidationReport`], up to `max_errors`
+/// errors. Structural errors in collections and other values, and any element
+/// errors beyond `max_errors`, are returned instead. See
+/// [`ExhaustiveValidator`] for more information.
+///
+/// # Safety
+///
+/// The elements listed in the returned report are invalid and must not be
+/// accessed. Use [`ArchivedVec::iter_valid`] and [`ValidationReport::is_valid`]
+/// to skip them. Looking up keys in hash tables and B-tree maps with invalid
+/// elements may access those elements.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure, to_bytes, validation::util::access_exhaustive,
+///     Archived,
+/// };
+///
+/// let value = vec!["a string which is stored out of line".to_string(); 4];
+/// let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+///
+/// // Corrupt the contents of the first string
+/// bytes[0] = 0xff;
+///
+/// let (archived, report) = unsafe {
+///     access_exhaustive::<Archived<Vec<String>>, Failure>(&bytes, 10)
+/// }
+/// .unwrap();
+/// assert_eq!(report.len(), 1);
+/// assert_eq!(report.errors()[0].index(), 0);
+/// assert_eq!(archived.iter_valid(&report).count(), 3);
+/// ```
+///
+/// [`ArchivedVec::iter_valid`]: crate::vec::ArchivedVec::iter_valid
+#[cfg(feature = "alloc")]
+#[inline]
+pub unsafe fn access_exhaustive<T, E>(
+    bytes: &[u8],
+    max_errors: usize,
+) -> Result<(&T, ValidationReport<E>), E>
+where
+    T: Portable + CheckBytes<Strategy<ExhaustiveValidator<E>, E>>,
+    E: Error,
+{
+    let mut validator = ExhaustiveValidator::new(bytes, max_errors);
+    let archived = access_with_context::<T, ExhaustiveValidator<E>, E>(
+        bytes,
+        &mut validator,
+    )?;
+    Ok((archived, validator.into_report()))
+}
+
+/// Accesses the archived values in a forward-only archive after checking its
+/// validity.
+///
+/// Forward-only archives are serialized with
+/// [`to_bytes_forward_only`](crate::util::to_bytes_forward_only). This checks
+/// the same layout that a [`StreamValidator`] does. To validate a forward-only
+/// archive as its bytes arrive, use a [`StreamValidator`] instead.
+///
+/// [`StreamValidator`]: crate::validation::stream::StreamValidator
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure, util::to_bytes_forward_only,
+///     validation::util::access_forward_only, Archived,
+/// };
+///
+/// let value = vec![vec![1u32, 2, 3], vec![4, 5], vec![]];
+/// let bytes = to_bytes_forward_only::<_, 256, Failure>(&value).unwrap();
+/// let archived =
+///     access_forward_only::<Archived<Vec<u32>>, Failure>(&bytes).unwrap();
+/// assert_eq!(*archived[1], [4, 5]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn access_forward_only<T, E>(
+    bytes: &[u8],
+) -> Result<&ArchivedVec<ArchivedBox<T>>, E>
+where
+    T: Portable + CheckBytes<Strategy<StreamContext, E>>,
+    E: Error,
+{
+    let mut state = StreamState::new();
+    let pos = match state.advance::<T, E>(bytes, true)? {
+        StreamStatus::Complete(pos) => pos,
+        StreamStatus::Pending => unreachable!(),
+    };
+    // SAFETY: The archive has been validated and the root is located at `pos`.
+    unsafe {
+        Ok(access_pos_unchecked::<ArchivedVec<ArchivedBox<T>>>(
+            bytes, pos,
+        ))
+    }
+}
+
 // TODO: `Pin` is not technically correct for the return type. `Pin` requires
 // the pinned value to be dropped before its memory can be reused, but archived
 // types explicitly do not require that. It just wants immovable types.
@@ -209,6 +476,7 @@ where
 /// position after checking its validity.
 ///
 /// This is a safe alternative to [`access_pos_unchecked`].
+#[cfg(feature = "alloc")]
 #[inline]
 pub fn access_pos_mut<T, E>(
     bytes: &mut [u8],
@@ -232,6 +500,7 @@ where
 /// This is a safe alternative to [`access_unchecked`][unsafe_version].
 ///
 /// [unsafe_version]: crate::access_unchecked
+#[cfg(feature = "alloc")]
 #[inline]
 pub fn access_mut<T, E>(bytes: &mut [u8]) -> Result<Pin<&mut T>, E>
 where
@@ -265,6 +534,7 @@ where
 ///
 /// assert_eq!(deserialized, value);
 /// ```
+#[cfg(feature = "alloc")]
 #[inline]
 pub fn from_bytes<T, E>(bytes: &[u8]) -> Result<T, E>
 where
@@ -276,3 +546,105 @@ where
     let mut deserializer = Unify::default();
     deserialize(access::<T::Archived, E>(bytes)?, &mut deserializer)
 }
+
+/// Checks and deserializes a value from the given bytes, refusing to allocate
+/// more memory than the given limits allow.
+///
+/// Validation guarantees that an archive is well-formed, but a valid archive
+/// may still describe values which are far larger than the archive itself.
+/// This deserializes with a [`Limited`] deserializer, which fails with a
+/// [`LimitExceeded`](crate::de::LimitExceeded) error before making any
+/// allocation that would exceed the limits. Use this instead of
+/// [`from_bytes`] when deserializing archives from untrusted sources.
+///
+/// # Examples
+/// ```
+/// use rkyv::{de::Limits, from_bytes_limited, rancor::Failure, to_bytes};
+///
+/// let value = vec![1, 2, 3, 4];
+/// let bytes = to_bytes::<_, 1024, Failure>(&value).unwrap();
+///
+/// let limits = Limits {
+///     max_len: 4,
+///     ..Limits::default()
+/// };
+/// let deserialized =
+///     from_bytes_limited::<Vec<i32>, Failure>(&bytes, limits).unwrap();
+/// assert_eq!(deserialized, value);
+///
+/// let limits = Limits {
+///     max_len: 3,
+///     ..Limits::default()
+/// };
+/// assert!(from_bytes_limited::<Vec<i32>, Failure>(&bytes, limits).is_err());
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn from_bytes_limited<T, E>(bytes: &[u8], limits: Limits) -> Result<T, E>
+where
+    T: Archive,
+    T::Archived: CheckBytes<Strategy<DefaultValidator, E>>
+        + Deserialize<T, Strategy<Limited<Unify>, E>>,
+    E: Error,
+{
+    let mut deserializer = Limited::new(Unify::default(), limits);
+    deserialize(access::<T::Archived, E>(bytes)?, &mut deserializer)
+}
+
+/// Checks and deserializes a value from the given bytes with the given
+/// configuration.
+///
+/// This is a safe alternative to
+/// [`from_bytes_with_config_unchecked`][unsafe_version].
+///
+/// [unsafe_version]: crate::util::from_bytes_with_config_unchecked
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn from_bytes_with_config<C, T>(bytes: &[u8]) -> Result<T, C::Error>
+where
+    C: DefaultDeserializer + ?Sized,
+    T: Archive,
+    T::Archived: CheckBytes<Strategy<DefaultValidator, C::Error>>
+        + Deserialize<T, Strategy<C::Deserializer, C::Error>>,
+{
+    let mut deserializer = C::deserializer();
+    deserialize(access::<T::Archived, C::Error>(bytes)?, &mut deserializer)
+}
+
+/// Checks and deserializes a value from the given bytes with the
+/// [`DefaultConfig`].
+///
+/// This is a safe alternative to
+/// [`from_bytes_default_unchecked`][unsafe_version].
+///
+/// [unsafe_version]: crate::from_bytes_default_unchecked
+///
+/// # Examples
+/// ```
+/// let value = vec![1, 2, 3, 4];
+/// let bytes = rkyv::to_bytes_default(&value).unwrap();
+/// let deserialized = rkyv::from_bytes_default::<Vec<i32>>(&bytes).unwrap();
+/// assert_eq!(deserialized, value);
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn from_bytes_default<T>(
+    bytes: &[u8],
+) -> Result<T, <DefaultConfig as DefaultDeserializer>::Error>
+where
+    T: Archive,
+    T::Archived: CheckBytes<
+            Strategy<
+                DefaultValidator,
+                <DefaultConfig as DefaultDeserializer>::Error,
+            >,
+        > + Deserialize<
+            T,
+            Strategy<
+                <DefaultConfig as DefaultDeserializer>::Deserializer,
+                <DefaultConfig as DefaultDeserializer>::Error,
+            >,
+        >,
+{
+    from_bytes_with_config::<DefaultConfig, T>(bytes)
+}
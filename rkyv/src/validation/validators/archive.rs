@@ -8,9 +8,9 @@ use core::{
 };
 
 use bytecheck::rancor::Error;
-use rancor::{fail, OptionExt};
+use rancor::{fail, OptionExt, ResultExt as _};
 
-use crate::validation::ArchiveContext;
+use crate::validation::{overflow::range_end, ArchiveContext};
 
 /// Errors that can occur when checking archive memory.
 #[derive(Debug)]
@@ -77,11 +77,27 @@ impl fmt::Display for ArchiveError {
 #[cfg(feature = "std")]
 impl std::error::Error for ArchiveError {}
 
+/// The cold region of an archive serialized with
+/// [`Cold`](crate::with::Cold) fields.
+///
+/// Subtrees in the cold region are claimed in order separately from the rest of
+/// the archive. Once a subtree in the cold region has been pushed, all nested
+/// subtrees must also be located in the cold region.
+#[derive(Clone, Debug)]
+struct ColdRegion {
+    bounds: Range<usize>,
+    subtree_range: Range<usize>,
+    depth: usize,
+}
+
 /// A validator that can verify archives with nonlocal memory.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ArchiveValidator {
+    start: usize,
     subtree_range: Range<usize>,
     max_subtree_depth: Option<NonZeroUsize>,
+    cold: Option<ColdRegion>,
+    max_align: usize,
 }
 
 // SAFETY: `ArchiveValidator` is safe to send between threads because the
@@ -110,12 +126,133 @@ impl ArchiveValidator {
     ) -> Self {
         let Range { start, end } = bytes.as_ptr_range();
         Self {
+            start: start.addr(),
             subtree_range: Range {
-                start: start as usize,
-                end: end as usize,
+                start: start.addr(),
+                end: end.addr(),
             },
             max_subtree_depth,
+            cold: None,
+            max_align: 1,
+        }
+    }
+
+    /// Creates a new bounds validator for the given bytes with a cold region.
+    ///
+    /// `cold` is the range of positions in `bytes` which were written as the
+    /// cold region during serialization. Positions outside of `bytes` are
+    /// ignored.
+    #[inline]
+    pub fn with_cold_region(bytes: &[u8], cold: Range<usize>) -> Self {
+        let mut result = Self::new(bytes);
+        let base = result.subtree_range.start;
+        let end = usize::min(cold.end, bytes.len());
+        let start = usize::min(cold.start, end);
+        let bounds = Range {
+            start: base + start,
+            end: base + end,
+        };
+        result.cold = Some(ColdRegion {
+            subtree_range: bounds.clone(),
+            bounds,
+            depth: 0,
+        });
+        result
+    }
+
+    /// Creates a new bounds validator for the given bytes which may only claim
+    /// subtrees in the given range of positions.
+    #[inline]
+    pub(crate) fn with_subtree_range(
+        bytes: &[u8],
+        range: Range<usize>,
+    ) -> Self {
+        let mut result = Self::new(bytes);
+        result.subtree_range = Range {
+            start: result.start + range.start,
+            end: result.start + range.end,
+        };
+        result
+    }
+
+    /// Returns the largest alignment of any subtree pointer which has been
+    /// checked so far.
+    ///
+    /// An archive which was valid at one address remains valid at another
+    /// address if the two addresses are congruent modulo this alignment.
+    #[inline]
+    pub fn max_alignment(&self) -> usize {
+        self.max_align
+    }
+
+    /// Returns the subtree range that a subtree starting at `start` and ending
+    /// at `end` must be located in, and whether that range is in the cold
+    /// region.
+    #[inline]
+    fn region_range<E: Error>(
+        &mut self,
+        start: usize,
+        end: usize,
+    ) -> Result<(&mut Range<usize>, bool), E> {
+        if let Some(cold) = &mut self.cold {
+            // Zero-sized subtrees may be located at the end of the cold
+            // region.
+            if cold.depth > 0
+                || (start >= cold.bounds.start && start < cold.bounds.end)
+                || (start == cold.bounds.end && end == start)
+            {
+                return Ok((&mut cold.subtree_range, true));
+            } else if end > cold.bounds.start && start < cold.bounds.end {
+                fail!(ArchiveError::InvalidSubtreePointer {
+                    address: start,
+                    size: end.wrapping_sub(start),
+                    subtree_range: self.subtree_range.clone(),
+                });
+            }
+        }
+        Ok((&mut self.subtree_range, false))
+    }
+
+    /// Pushes the range of a subtree located entirely within the claimed part
+    /// of the archive and starts validating it again.
+    ///
+    /// Returns `None` if the subtree is not located entirely within the
+    /// claimed part of the archive, or if the archive has a cold region.
+    #[inline]
+    pub(crate) fn push_claimed_subtree_range<E: Error>(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<Option<Range<usize>>, E> {
+        let start = ptr.addr();
+        let end = start.wrapping_add(layout.size());
+        if self.cold.is_some()
+            || start < self.start
+            || end < start
+            || end > self.subtree_range.start
+        {
+            return Ok(None);
+        }
+        if start & (layout.align() - 1) != 0 {
+            fail!(ArchiveError::Unaligned {
+                address: start,
+                align: layout.align(),
+            });
+        }
+        self.max_align = usize::max(self.max_align, layout.align());
+
+        if let Some(max_subtree_depth) = &mut self.max_subtree_depth {
+            *max_subtree_depth = NonZeroUsize::new(max_subtree_depth.get() - 1)
+                .into_trace(ArchiveError::ExceededMaximumSubtreeDepth)?;
         }
+
+        // The subtrees of an aliased subtree are all located before it.
+        let result = self.subtree_range.clone();
+        self.subtree_range = Range {
+            start: self.start,
+            end: start,
+        };
+        Ok(Some(result))
     }
 }
 
@@ -126,20 +263,22 @@ unsafe impl<E: Error> ArchiveContext<E> for ArchiveValidator {
         ptr: *const u8,
         layout: &Layout,
     ) -> Result<(), E> {
-        let start = ptr as usize;
-        let end = ptr.wrapping_add(layout.size()) as usize;
-        if start < self.subtree_range.start || end > self.subtree_range.end {
+        let start = ptr.addr();
+        let end = range_end(start, layout.size()).into_error()?;
+        let (subtree_range, _) = self.region_range::<E>(start, end)?;
+        if start < subtree_range.start || end > subtree_range.end {
             fail!(ArchiveError::InvalidSubtreePointer {
                 address: start,
                 size: layout.size(),
-                subtree_range: self.subtree_range.clone(),
+                subtree_range: subtree_range.clone(),
             });
         } else if start & (layout.align() - 1) != 0 {
             fail!(ArchiveError::Unaligned {
-                address: ptr as usize,
+                address: ptr.addr(),
                 align: layout.align(),
             });
         } else {
+            self.max_align = usize::max(self.max_align, layout.align());
             Ok(())
         }
     }
@@ -155,11 +294,18 @@ unsafe impl<E: Error> ArchiveContext<E> for ArchiveValidator {
                 .into_trace(ArchiveError::ExceededMaximumSubtreeDepth)?;
         }
 
+        let (subtree_range, is_cold) =
+            self.region_range::<E>(root.addr(), end.addr())?;
         let result = Range {
-            start: end as usize,
-            end: self.subtree_range.end,
+            start: end.addr(),
+            end: subtree_range.end,
         };
-        self.subtree_range.end = root as usize;
+        subtree_range.end = root.addr();
+        if is_cold {
+            if let Some(cold) = &mut self.cold {
+                cold.depth += 1;
+            }
+        }
         Ok(result)
     }
 
@@ -174,12 +320,19 @@ unsafe impl<E: Error> ArchiveContext<E> for ArchiveValidator {
                 .into_trace(ArchiveError::ExceededMaximumSubtreeDepth)?;
         }
 
+        let (subtree_range, is_cold) =
+            self.region_range::<E>(start.addr(), root.addr())?;
         let result = Range {
-            start: self.subtree_range.start,
-            end: start as usize,
+            start: subtree_range.start,
+            end: start.addr(),
         };
-        self.subtree_range.start = start as usize;
-        self.subtree_range.end = root as usize;
+        subtree_range.start = start.addr();
+        subtree_range.end = root.addr();
+        if is_cold {
+            if let Some(cold) = &mut self.cold {
+                cold.depth += 1;
+            }
+        }
         Ok(result)
     }
 
@@ -188,7 +341,13 @@ unsafe impl<E: Error> ArchiveContext<E> for ArchiveValidator {
         &mut self,
         range: Range<usize>,
     ) -> Result<(), E> {
-        self.subtree_range = range;
+        match &mut self.cold {
+            Some(cold) if cold.depth > 0 => {
+                cold.subtree_range = range;
+                cold.depth -= 1;
+            }
+            _ => self.subtree_range = range,
+        }
         if let Some(max_subtree_depth) = &mut self.max_subtree_depth {
             *max_subtree_depth = max_subtree_depth
                 .checked_add(1)
@@ -196,4 +355,9 @@ unsafe impl<E: Error> ArchiveContext<E> for ArchiveValidator {
         }
         Ok(())
     }
+
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        Some(self.start)
+    }
 }
@@ -0,0 +1,372 @@
+//! A validator which records the relative pointers in an archive.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::{alloc::Layout, any::TypeId, fmt, ops::Range};
+
+use bytecheck::rancor::Error;
+
+use crate::validation::{
+    validators::{ArchiveValidator, SharedValidator},
+    ArchiveContext, SharedContext,
+};
+
+/// A relative pointer recorded by an [`AuditValidator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    source: usize,
+    target: isize,
+    size: usize,
+    align: usize,
+    type_name: &'static str,
+    crosses_root: bool,
+    out_of_bounds: bool,
+    unaligned: bool,
+    overlapping: bool,
+}
+
+impl AuditEntry {
+    /// Returns the position of the relative pointer in the archive.
+    #[inline]
+    pub fn source(&self) -> usize {
+        self.source
+    }
+
+    /// Returns the position of the target of the relative pointer in the
+    /// archive.
+    ///
+    /// This may be negative or past the end of the archive if the relative
+    /// pointer is out of bounds.
+    #[inline]
+    pub fn target(&self) -> isize {
+        self.target
+    }
+
+    /// Returns the size of the target of the relative pointer.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the alignment of the target of the relative pointer.
+    #[inline]
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Returns the name of the type of the target of the relative pointer.
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns whether the target of the relative pointer is not located
+    /// entirely before the root object.
+    ///
+    /// Relative pointers in an archive always point backwards to objects
+    /// serialized before the root, so this usually means that the archive was
+    /// concatenated or sliced incorrectly.
+    #[inline]
+    pub fn crosses_root(&self) -> bool {
+        self.crosses_root
+    }
+
+    /// Returns whether the target of the relative pointer is not located
+    /// completely inside of the archive.
+    #[inline]
+    pub fn is_out_of_bounds(&self) -> bool {
+        self.out_of_bounds
+    }
+
+    /// Returns whether the target of the relative pointer is not properly
+    /// aligned.
+    ///
+    /// This usually means that the relative pointer points into the padding
+    /// before an object.
+    #[inline]
+    pub fn is_unaligned(&self) -> bool {
+        self.unaligned
+    }
+
+    /// Returns whether the target of the relative pointer starts inside of or
+    /// partially overlaps another recorded object.
+    ///
+    /// Targets which start at the same position as another recorded object
+    /// (like shared pointers) are not overlapping.
+    #[inline]
+    pub fn is_overlapping(&self) -> bool {
+        self.overlapping
+    }
+
+    /// Returns whether any problem was found with the relative pointer.
+    #[inline]
+    pub fn is_flagged(&self) -> bool {
+        self.crosses_root
+            || self.out_of_bounds
+            || self.unaligned
+            || self.overlapping
+    }
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x} -> ", self.source)?;
+        if self.target < 0 {
+            write!(f, "-{:#06x}", self.target.unsigned_abs())?;
+        } else {
+            write!(f, "{:#06x}", self.target)?;
+        }
+        write!(
+            f,
+            " {} (size {}, align {})",
+            self.type_name, self.size, self.align,
+        )?;
+
+        let flags = [
+            (self.crosses_root, "crosses root"),
+            (self.out_of_bounds, "out of bounds"),
+            (self.unaligned, "unaligned"),
+            (self.overlapping, "overlapping"),
+        ];
+        let mut first = true;
+        for (_, name) in flags.iter().filter(|(set, _)| *set) {
+            f.write_str(if first { " [" } else { ", " })?;
+            f.write_str(name)?;
+            first = false;
+        }
+        if !first {
+            f.write_str("]")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A report of the relative pointers found by an [`AuditValidator`].
+///
+/// See [`audit`](crate::util::audit) for an example.
+#[derive(Debug)]
+pub struct AuditReport<E> {
+    root: usize,
+    entries: Vec<AuditEntry>,
+    error: Option<E>,
+}
+
+impl<E> AuditReport<E> {
+    /// Returns the position of the root object in the archive.
+    #[inline]
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    /// Returns the relative pointers in the archive in the order they were
+    /// checked.
+    #[inline]
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Returns an iterator over the relative pointers which were flagged.
+    #[inline]
+    pub fn flagged(&self) -> impl Iterator<Item = &AuditEntry> {
+        self.entries.iter().filter(|e| e.is_flagged())
+    }
+
+    /// Returns the error which stopped validation, if any.
+    ///
+    /// The last entry of the report is usually the relative pointer which
+    /// caused the error.
+    #[inline]
+    pub fn error(&self) -> Option<&E> {
+        self.error.as_ref()
+    }
+
+    /// Returns whether the archive was valid and no relative pointers were
+    /// flagged.
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.error.is_none() && self.flagged().next().is_none()
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for AuditReport<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "root at {:#06x}", self.root)?;
+        for entry in self.entries.iter() {
+            writeln!(f, "{}", entry)?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// A validator which records every relative pointer in an archive.
+///
+/// Each relative pointer is recorded in an [`AuditEntry`] before it is checked
+/// and flagged if its target is out of bounds, unaligned, overlaps another
+/// object, or is not located before the root object. Validation is otherwise
+/// the same as the default validator.
+#[derive(Debug)]
+pub struct AuditValidator {
+    archive: ArchiveValidator,
+    shared: SharedValidator,
+    bytes: Range<usize>,
+    root: usize,
+    // The ranges of the root and recorded targets, sorted by start
+    targets: Vec<Range<usize>>,
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditValidator {
+    /// Creates a new validator from a byte range with a root object of the
+    /// given size at the given position.
+    #[inline]
+    pub fn new(bytes: &[u8], root: usize, root_size: usize) -> Self {
+        let Range { start, end } = bytes.as_ptr_range();
+        let mut targets = Vec::new();
+        if root_size > 0 {
+            targets.push(root..root.saturating_add(root_size));
+        }
+        Self {
+            archive: ArchiveValidator::new(bytes),
+            shared: SharedValidator::new(),
+            bytes: start.addr()..end.addr(),
+            root,
+            targets,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the relative pointers recorded so far.
+    #[inline]
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Consumes the validator and returns a report of its relative pointers
+    /// and the error which stopped validation, if any.
+    #[inline]
+    pub fn into_report<E>(self, error: Option<E>) -> AuditReport<E> {
+        AuditReport {
+            root: self.root,
+            entries: self.entries,
+            error,
+        }
+    }
+
+    fn is_overlapping(&mut self, target: Range<usize>) -> bool {
+        let index = self.targets.partition_point(|t| t.start < target.start);
+        if self
+            .targets
+            .get(index)
+            .is_some_and(|t| t.start == target.start)
+        {
+            return false;
+        }
+
+        let overlaps_prev =
+            index > 0 && self.targets[index - 1].end > target.start;
+        let overlaps_next = self
+            .targets
+            .get(index)
+            .is_some_and(|t| t.start < target.end);
+        if target.start != target.end {
+            self.targets.insert(index, target);
+        }
+        overlaps_prev || overlaps_next
+    }
+}
+
+unsafe impl<E: Error> ArchiveContext<E> for AuditValidator {
+    #[inline]
+    fn check_subtree_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), E> {
+        self.archive.check_subtree_ptr(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn push_prefix_subtree_range(
+        &mut self,
+        root: *const u8,
+        end: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_prefix_subtree_range(root, end)
+    }
+
+    #[inline]
+    unsafe fn push_suffix_subtree_range(
+        &mut self,
+        start: *const u8,
+        root: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_suffix_subtree_range(start, root)
+    }
+
+    #[inline]
+    unsafe fn pop_subtree_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<(), E> {
+        unsafe { self.archive.pop_subtree_range(range) }
+    }
+
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        ArchiveContext::<E>::archive_start(&self.archive)
+    }
+
+    fn record_subtree_ptr(
+        &mut self,
+        base: *const u8,
+        ptr: *const u8,
+        layout: &Layout,
+        type_name: &'static str,
+    ) -> Result<(), E> {
+        let len = self.bytes.end - self.bytes.start;
+        let source = base.addr().wrapping_sub(self.bytes.start);
+        let target = ptr.addr().wrapping_sub(self.bytes.start) as isize;
+
+        let out_of_bounds = target < 0
+            || target as usize > len
+            || len - (target as usize) < layout.size();
+        let (crosses_root, overlapping) = if out_of_bounds {
+            (false, false)
+        } else {
+            let start = target as usize;
+            let end = start + layout.size();
+            (end > self.root, self.is_overlapping(start..end))
+        };
+
+        self.entries.push(AuditEntry {
+            source,
+            target,
+            size: layout.size(),
+            align: layout.align(),
+            type_name,
+            crosses_root,
+            out_of_bounds,
+            unaligned: ptr.addr() & (layout.align() - 1) != 0,
+            overlapping,
+        });
+        Ok(())
+    }
+}
+
+impl<E> SharedContext<E> for AuditValidator
+where
+    SharedValidator: SharedContext<E>,
+{
+    #[inline]
+    fn register_shared_ptr(
+        &mut self,
+        address: usize,
+        type_id: TypeId,
+    ) -> Result<bool, E> {
+        self.shared.register_shared_ptr(address, type_id)
+    }
+}
@@ -0,0 +1,300 @@
+//! A validator which continues past invalid elements of collections.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::{alloc::Layout, any::TypeId, ops::Range};
+
+use bytecheck::rancor::Error;
+
+use crate::validation::{
+    validators::{ArchiveValidator, SharedValidator},
+    ArchiveContext, SharedContext,
+};
+
+/// An error which occurred while checking an element of a collection.
+#[derive(Debug)]
+pub struct ElementError<E> {
+    index: usize,
+    address: usize,
+    error: E,
+}
+
+impl<E> ElementError<E> {
+    /// Returns the index of the invalid element in its collection.
+    ///
+    /// For archived hash tables, this is the index of the bucket containing
+    /// the element. For archived B-tree maps, this is the index of the entry
+    /// in key order.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the address of the invalid element.
+    #[inline]
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the error that occurred while checking the element.
+    #[inline]
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Consumes the element error and returns the underlying error.
+    #[inline]
+    pub fn into_error(self) -> E {
+        self.error
+    }
+}
+
+/// A report of the invalid elements found by an [`ExhaustiveValidator`].
+#[derive(Debug)]
+pub struct ValidationReport<E> {
+    errors: Vec<ElementError<E>>,
+    // The addresses of invalid elements, sorted
+    addresses: Vec<usize>,
+    max_errors: usize,
+}
+
+impl<E> ValidationReport<E> {
+    /// Creates a new empty report which can hold up to `max_errors` errors.
+    #[inline]
+    pub fn new(max_errors: usize) -> Self {
+        Self {
+            errors: Vec::new(),
+            addresses: Vec::new(),
+            max_errors,
+        }
+    }
+
+    /// Returns the maximum number of errors that the report can hold.
+    #[inline]
+    pub fn max_errors(&self) -> usize {
+        self.max_errors
+    }
+
+    /// Returns the number of invalid elements in the report.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns whether the report contains no invalid elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the errors in the report in the order they were found.
+    #[inline]
+    pub fn errors(&self) -> &[ElementError<E>] {
+        &self.errors
+    }
+
+    /// Consumes the report and returns its errors in the order they were
+    /// found.
+    #[inline]
+    pub fn into_errors(self) -> Vec<ElementError<E>> {
+        self.errors
+    }
+
+    /// Returns whether the given element was not reported as invalid.
+    ///
+    /// `element` must be a reference to an element of a checked collection.
+    #[inline]
+    pub fn is_valid<T>(&self, element: &T) -> bool {
+        self.is_valid_address((element as *const T).addr())
+    }
+
+    #[inline]
+    pub(crate) fn is_valid_address(&self, address: usize) -> bool {
+        self.addresses.binary_search(&address).is_err()
+    }
+
+    fn push(
+        &mut self,
+        index: usize,
+        address: usize,
+        error: E,
+    ) -> Result<(), E> {
+        if self.errors.len() >= self.max_errors {
+            return Err(error);
+        }
+
+        if let Err(i) = self.addresses.binary_search(&address) {
+            self.addresses.insert(i, address);
+        }
+        self.errors.push(ElementError {
+            index,
+            address,
+            error,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Checkpoint {
+    archive: ArchiveValidator,
+    registered: usize,
+}
+
+/// A validator which records invalid elements of collections instead of
+/// stopping at the first one.
+///
+/// Errors in elements of archived vecs, hash tables, and B-tree maps are
+/// recorded in a [`ValidationReport`] and the rest of the collection is
+/// checked. Other errors, and element errors beyond the report's maximum, are
+/// returned as usual. Invalid elements are not checked to be disjoint from the
+/// rest of the archive. The hashes of keys in hash maps and sets are not
+/// checked after an invalid element has been recorded, and neither is the order
+/// of keys in B-tree maps with an invalid entry.
+///
+/// The entries of B-tree maps are recorded at the address of their keys, so
+/// they can be checked by passing their key to
+/// [`is_valid`](ValidationReport::is_valid).
+///
+/// See [`access_exhaustive`](crate::validation::util::access_exhaustive) for
+/// an example.
+#[derive(Debug)]
+pub struct ExhaustiveValidator<E> {
+    archive: ArchiveValidator,
+    shared: SharedValidator,
+    checkpoints: Vec<Checkpoint>,
+    // Shared pointers registered while checking elements, in order
+    registered: Vec<usize>,
+    report: ValidationReport<E>,
+}
+
+impl<E> ExhaustiveValidator<E> {
+    /// Creates a new validator from a byte range which records up to
+    /// `max_errors` invalid elements.
+    #[inline]
+    pub fn new(bytes: &[u8], max_errors: usize) -> Self {
+        Self {
+            archive: ArchiveValidator::new(bytes),
+            shared: SharedValidator::new(),
+            checkpoints: Vec::new(),
+            registered: Vec::new(),
+            report: ValidationReport::new(max_errors),
+        }
+    }
+
+    /// Returns the report of invalid elements found so far.
+    #[inline]
+    pub fn report(&self) -> &ValidationReport<E> {
+        &self.report
+    }
+
+    /// Consumes the validator and returns its report of invalid elements.
+    #[inline]
+    pub fn into_report(self) -> ValidationReport<E> {
+        self.report
+    }
+}
+
+unsafe impl<E: Error> ArchiveContext<E> for ExhaustiveValidator<E> {
+    #[inline]
+    fn check_subtree_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), E> {
+        self.archive.check_subtree_ptr(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn push_prefix_subtree_range(
+        &mut self,
+        root: *const u8,
+        end: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_prefix_subtree_range(root, end)
+    }
+
+    #[inline]
+    unsafe fn push_suffix_subtree_range(
+        &mut self,
+        start: *const u8,
+        root: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_suffix_subtree_range(start, root)
+    }
+
+    #[inline]
+    unsafe fn pop_subtree_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<(), E> {
+        unsafe { self.archive.pop_subtree_range(range) }
+    }
+
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        ArchiveContext::<E>::archive_start(&self.archive)
+    }
+
+    #[inline]
+    fn check_hashes(&self) -> bool {
+        // Keys may be invalid once an invalid element has been recorded, so
+        // they must not be hashed.
+        self.report.is_empty()
+    }
+
+    #[inline]
+    unsafe fn start_element(&mut self) -> Result<(), E> {
+        self.checkpoints.push(Checkpoint {
+            archive: self.archive.clone(),
+            registered: self.registered.len(),
+        });
+        Ok(())
+    }
+
+    unsafe fn finish_element(
+        &mut self,
+        index: usize,
+        ptr: *const u8,
+        result: Result<(), E>,
+    ) -> Result<(), E> {
+        let checkpoint = match self.checkpoints.pop() {
+            Some(checkpoint) => checkpoint,
+            None => return result,
+        };
+
+        match result {
+            Ok(()) => {
+                if self.checkpoints.is_empty() {
+                    self.registered.clear();
+                }
+                Ok(())
+            }
+            Err(error) => {
+                // Roll back any subtree claims and shared pointers from the
+                // invalid element so that the remaining elements are checked
+                // as if it were not there.
+                self.archive = checkpoint.archive;
+                for address in self.registered.drain(checkpoint.registered..) {
+                    self.shared.unregister_shared_ptr(address);
+                }
+                self.report.push(index, ptr.addr(), error)
+            }
+        }
+    }
+}
+
+impl<E: Error> SharedContext<E> for ExhaustiveValidator<E> {
+    #[inline]
+    fn register_shared_ptr(
+        &mut self,
+        address: usize,
+        type_id: TypeId,
+    ) -> Result<bool, E> {
+        let is_new = self.shared.register_shared_ptr(address, type_id)?;
+        if is_new && !self.checkpoints.is_empty() {
+            self.registered.push(address);
+        }
+        Ok(is_new)
+    }
+}
@@ -0,0 +1,258 @@
+//! A validator which records the memory reachable from a value.
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::{alloc::Layout, any::TypeId, ops::Range};
+
+use bytecheck::rancor::Error;
+
+use crate::{
+    rel_ptr::OffsetSlot,
+    validation::{
+        validators::{ArchiveValidator, SharedValidator},
+        ArchiveContext, SharedContext,
+    },
+};
+
+/// A group of regions recorded by an [`ExtractValidator`].
+///
+/// A shared subtree which was claimed before the value being checked is checked
+/// out of order, and its regions form a new group. The group belongs at `at` in
+/// the regions of its parent group, which is where the parent would have
+/// claimed it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ExtractGroup {
+    pub parent: usize,
+    pub at: usize,
+}
+
+/// A relative pointer offset recorded by an [`ExtractValidator`].
+///
+/// Positions are relative to the start of the archive.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ExtractOffset {
+    pub group: usize,
+    pub base: usize,
+    pub pos: usize,
+    pub size: usize,
+    pub target: usize,
+    pub write: fn(isize, &mut [u8]) -> bool,
+}
+
+/// A shared pointer which is being checked for the first time.
+#[derive(Debug)]
+struct PendingShared {
+    address: usize,
+    checked: Option<(Layout, Range<usize>)>,
+}
+
+#[derive(Debug)]
+enum PushedRange {
+    Prefix,
+    Suffix,
+    Claimed { parent: usize },
+}
+
+/// A validator which records the memory and relative pointers reachable from
+/// the value it checks.
+///
+/// Every subtree pointer checked by the validator is recorded as a region of
+/// the archive, and every relative pointer followed is recorded along with the
+/// encoding of its offset. See [`extract`](crate::util::extract) for how this
+/// is used.
+///
+/// Validation is the same as the default validator, except that shared
+/// pointers may point to memory which was claimed before the value being
+/// checked. That memory is checked when it is first pointed to. Shared pointers
+/// inside of suffix subtree ranges, like the entries of B-tree nodes, must
+/// still point into their subtree range because it has a fixed size.
+#[derive(Debug)]
+pub struct ExtractValidator {
+    archive: ArchiveValidator,
+    shared: SharedValidator,
+    start: usize,
+    pending_shared: Option<PendingShared>,
+    pushed: Vec<PushedRange>,
+    group: usize,
+    groups: Vec<ExtractGroup>,
+    regions: Vec<(usize, Range<usize>)>,
+    offsets: Vec<ExtractOffset>,
+}
+
+impl ExtractValidator {
+    /// Creates a new validator from a byte range.
+    #[inline]
+    pub fn new(bytes: &[u8]) -> Self {
+        Self {
+            archive: ArchiveValidator::new(bytes),
+            shared: SharedValidator::new(),
+            start: bytes.as_ptr().addr(),
+            pending_shared: None,
+            pushed: Vec::new(),
+            group: 0,
+            groups: vec![ExtractGroup { parent: 0, at: 0 }],
+            regions: Vec::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Returns the largest alignment of any subtree pointer which has been
+    /// checked so far.
+    #[inline]
+    pub fn max_alignment(&self) -> usize {
+        self.archive.max_alignment()
+    }
+
+    /// Returns the groups of regions. The first group contains the value which
+    /// was checked.
+    #[inline]
+    pub(crate) fn groups(&self) -> &[ExtractGroup] {
+        &self.groups
+    }
+
+    /// Returns the checked regions of the archive and their groups.
+    ///
+    /// Regions are relative to the start of the archive, and may be empty or
+    /// overlap other regions in the same group.
+    #[inline]
+    pub(crate) fn regions(&self) -> &[(usize, Range<usize>)] {
+        &self.regions
+    }
+
+    #[inline]
+    pub(crate) fn offsets(&self) -> &[ExtractOffset] {
+        &self.offsets
+    }
+
+    fn pos(&self, ptr: *const u8) -> usize {
+        ptr.addr().wrapping_sub(self.start)
+    }
+
+    fn in_suffix_range(&self) -> bool {
+        self.pushed
+            .iter()
+            .rev()
+            .take_while(|r| !matches!(r, PushedRange::Claimed { .. }))
+            .any(|r| matches!(r, PushedRange::Suffix))
+    }
+}
+
+unsafe impl<E: Error> ArchiveContext<E> for ExtractValidator {
+    #[inline]
+    fn check_subtree_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), E> {
+        let start = self.pos(ptr);
+        let region = start..start.wrapping_add(layout.size());
+        match &mut self.pending_shared {
+            // Shared subtrees are checked when they are pushed, which
+            // immediately follows this check.
+            Some(pending) if pending.address == ptr.addr() => {
+                pending.checked = Some((*layout, region));
+            }
+            _ => {
+                self.archive.check_subtree_ptr(ptr, layout)?;
+                self.regions.push((self.group, region));
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    unsafe fn push_prefix_subtree_range(
+        &mut self,
+        root: *const u8,
+        end: *const u8,
+    ) -> Result<Range<usize>, E> {
+        if let Some(PendingShared {
+            address,
+            checked: Some((layout, region)),
+        }) = self.pending_shared.take()
+        {
+            let claimed = if address == root.addr() && !self.in_suffix_range() {
+                self.archive.push_claimed_subtree_range(root, &layout)?
+            } else {
+                None
+            };
+            if let Some(range) = claimed {
+                self.groups.push(ExtractGroup {
+                    parent: self.group,
+                    at: range.start.wrapping_sub(self.start),
+                });
+                self.pushed
+                    .push(PushedRange::Claimed { parent: self.group });
+                self.group = self.groups.len() - 1;
+                self.regions.push((self.group, region));
+                return Ok(range);
+            }
+            self.archive
+                .check_subtree_ptr(root.with_addr(address), &layout)?;
+            self.regions.push((self.group, region));
+        }
+        let range = self.archive.push_prefix_subtree_range(root, end)?;
+        self.pushed.push(PushedRange::Prefix);
+        Ok(range)
+    }
+
+    #[inline]
+    unsafe fn push_suffix_subtree_range(
+        &mut self,
+        start: *const u8,
+        root: *const u8,
+    ) -> Result<Range<usize>, E> {
+        let range = self.archive.push_suffix_subtree_range(start, root)?;
+        self.pushed.push(PushedRange::Suffix);
+        Ok(range)
+    }
+
+    #[inline]
+    unsafe fn pop_subtree_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<(), E> {
+        if let Some(PushedRange::Claimed { parent }) = self.pushed.pop() {
+            self.group = parent;
+        }
+        unsafe { self.archive.pop_subtree_range(range) }
+    }
+
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        ArchiveContext::<E>::archive_start(&self.archive)
+    }
+
+    fn record_offset(&mut self, slot: OffsetSlot) -> Result<(), E> {
+        self.offsets.push(ExtractOffset {
+            group: self.group,
+            base: self.pos(slot.base()),
+            pos: self.pos(slot.ptr()),
+            size: slot.size(),
+            target: self.pos(slot.target()),
+            write: slot.writer(),
+        });
+        Ok(())
+    }
+}
+
+impl<E> SharedContext<E> for ExtractValidator
+where
+    SharedValidator: SharedContext<E>,
+{
+    #[inline]
+    fn register_shared_ptr(
+        &mut self,
+        address: usize,
+        type_id: TypeId,
+    ) -> Result<bool, E> {
+        let is_new = self.shared.register_shared_ptr(address, type_id)?;
+        if is_new {
+            self.pending_shared = Some(PendingShared {
+                address,
+                checked: None,
+            });
+        }
+        Ok(is_new)
+    }
+}
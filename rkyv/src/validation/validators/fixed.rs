@@ -0,0 +1,291 @@
+//! Validators which track shared pointers in a fixed-capacity buffer instead of
+//! allocating.
+
+use core::{
+    alloc::Layout, any::TypeId, fmt, mem::MaybeUninit, ops::Range,
+    slice::from_raw_parts,
+};
+
+use bytecheck::rancor::Error;
+use rancor::fail;
+
+use super::{ArchiveValidator, SharedError};
+use crate::validation::{ArchiveContext, SharedContext};
+
+/// A shared pointer registered with a [`FixedSharedValidator`].
+#[derive(Clone, Copy, Debug)]
+pub struct SharedClaim {
+    address: usize,
+    type_id: TypeId,
+}
+
+impl SharedClaim {
+    /// An uninitialized claim.
+    ///
+    /// This can be used to create arrays of claims to back a fixed validator:
+    ///
+    /// ```
+    /// # use core::mem::MaybeUninit;
+    /// # use rkyv::validation::validators::SharedClaim;
+    /// let mut claims = [SharedClaim::UNINIT; 16];
+    /// ```
+    pub const UNINIT: MaybeUninit<Self> = MaybeUninit::uninit();
+
+    /// Returns the address of the shared pointer.
+    #[inline]
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the type that the shared pointer was checked as.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+}
+
+/// An error resulting from an archive containing more shared pointers than a
+/// fixed validator has space to track.
+#[derive(Debug)]
+pub struct ValidatorCapacityExceeded {
+    capacity: usize,
+}
+
+impl ValidatorCapacityExceeded {
+    /// Returns the number of shared pointers the validator could track.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl fmt::Display for ValidatorCapacityExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the archive contains more than {} shared pointers, which is the \
+             capacity of the validator",
+            self.capacity,
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidatorCapacityExceeded {}
+
+/// A validator that can verify shared memory without allocating.
+///
+/// Registered shared pointers are stored in a caller-provided buffer of
+/// claims. Validating an archive with more distinct shared pointers than there
+/// are claims fails with a [`ValidatorCapacityExceeded`] error. Archives without
+/// shared pointers never use any claims.
+#[derive(Debug)]
+pub struct FixedSharedValidator<'a> {
+    claims: &'a mut [MaybeUninit<SharedClaim>],
+    len: usize,
+}
+
+impl<'a> FixedSharedValidator<'a> {
+    /// Creates a new shared validator which tracks shared pointers in the
+    /// given claims.
+    #[inline]
+    pub fn new(claims: &'a mut [MaybeUninit<SharedClaim>]) -> Self {
+        Self { claims, len: 0 }
+    }
+
+    /// Returns the maximum number of shared pointers that can be registered.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.claims.len()
+    }
+
+    /// Returns the shared pointers that have been registered.
+    #[inline]
+    pub fn claims(&self) -> &[SharedClaim] {
+        // SAFETY: The first `len` claims are always initialized.
+        unsafe { from_raw_parts(self.claims.as_ptr().cast(), self.len) }
+    }
+}
+
+impl<E: Error> SharedContext<E> for FixedSharedValidator<'_> {
+    fn register_shared_ptr(
+        &mut self,
+        address: usize,
+        type_id: TypeId,
+    ) -> Result<bool, E> {
+        if let Some(claim) =
+            self.claims().iter().find(|claim| claim.address == address)
+        {
+            if claim.type_id != type_id {
+                fail!(SharedError::TypeMismatch {
+                    previous: claim.type_id,
+                    current: type_id,
+                });
+            }
+            return Ok(false);
+        }
+
+        if self.len == self.claims.len() {
+            fail!(ValidatorCapacityExceeded {
+                capacity: self.claims.len(),
+            });
+        }
+        self.claims[self.len].write(SharedClaim { address, type_id });
+        self.len += 1;
+        Ok(true)
+    }
+}
+
+/// A validator which does not allocate.
+///
+/// This validates the same archives as the
+/// [`DefaultValidator`](super::DefaultValidator), but tracks shared pointers
+/// with a [`FixedSharedValidator`]. It is available without the `alloc`
+/// feature and can be used with
+/// [`access_with_context`](crate::validation::util::access_with_context).
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure,
+///     to_bytes,
+///     validation::{
+///         util::access_with_context,
+///         validators::{FixedValidator, SharedClaim},
+///     },
+///     Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     name: String,
+///     values: Vec<u32>,
+/// }
+///
+/// let value = Example {
+///     name: "pi".to_string(),
+///     values: vec![3, 1, 4, 1, 5],
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+///
+/// // Archives without shared pointers don't need any claims.
+/// let mut validator = FixedValidator::new(&bytes, &mut []);
+/// let archived = access_with_context::<ArchivedExample, _, Failure>(
+///     &bytes,
+///     &mut validator,
+/// )
+/// .unwrap();
+/// assert_eq!(archived.name, "pi");
+///
+/// let mut claims = [SharedClaim::UNINIT; 16];
+/// let mut validator = FixedValidator::new(&bytes, &mut claims);
+/// let archived = access_with_context::<ArchivedExample, _, Failure>(
+///     &bytes,
+///     &mut validator,
+/// )
+/// .unwrap();
+/// assert_eq!(archived.values.len(), 5);
+/// ```
+#[derive(Debug)]
+pub struct FixedValidator<'a> {
+    archive: ArchiveValidator,
+    shared: FixedSharedValidator<'a>,
+}
+
+impl<'a> FixedValidator<'a> {
+    /// Creates a new validator from a byte range which tracks shared pointers
+    /// in the given claims.
+    #[inline]
+    pub fn new(
+        bytes: &[u8],
+        claims: &'a mut [MaybeUninit<SharedClaim>],
+    ) -> Self {
+        Self {
+            archive: ArchiveValidator::new(bytes),
+            shared: FixedSharedValidator::new(claims),
+        }
+    }
+
+    /// Creates a new validator from a byte range with a cold region which
+    /// tracks shared pointers in the given claims.
+    ///
+    /// See [`ArchiveValidator::with_cold_region`] for more information.
+    #[inline]
+    pub fn with_cold_region(
+        bytes: &[u8],
+        cold: Range<usize>,
+        claims: &'a mut [MaybeUninit<SharedClaim>],
+    ) -> Self {
+        Self {
+            archive: ArchiveValidator::with_cold_region(bytes, cold),
+            shared: FixedSharedValidator::new(claims),
+        }
+    }
+
+    /// Returns the shared pointers that have been registered.
+    #[inline]
+    pub fn claims(&self) -> &[SharedClaim] {
+        self.shared.claims()
+    }
+}
+
+unsafe impl<E> ArchiveContext<E> for FixedValidator<'_>
+where
+    ArchiveValidator: ArchiveContext<E>,
+{
+    #[inline]
+    fn check_subtree_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), E> {
+        self.archive.check_subtree_ptr(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn push_prefix_subtree_range(
+        &mut self,
+        root: *const u8,
+        end: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_prefix_subtree_range(root, end)
+    }
+
+    #[inline]
+    unsafe fn push_suffix_subtree_range(
+        &mut self,
+        start: *const u8,
+        root: *const u8,
+    ) -> Result<Range<usize>, E> {
+        self.archive.push_suffix_subtree_range(start, root)
+    }
+
+    #[inline]
+    unsafe fn pop_subtree_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<(), E> {
+        unsafe { self.archive.pop_subtree_range(range) }
+    }
+
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        ArchiveContext::<E>::archive_start(&self.archive)
+    }
+}
+
+impl<'a, E> SharedContext<E> for FixedValidator<'a>
+where
+    FixedSharedValidator<'a>: SharedContext<E>,
+{
+    #[inline]
+    fn register_shared_ptr(
+        &mut self,
+        address: usize,
+        type_id: TypeId,
+    ) -> Result<bool, E> {
+        self.shared.register_shared_ptr(address, type_id)
+    }
+}
@@ -1,22 +1,50 @@
 //! Validators that can check archived types.
 
 mod archive;
+#[cfg(feature = "alloc")]
+mod audit;
+#[cfg(feature = "alloc")]
+mod exhaustive;
+#[cfg(feature = "alloc")]
+mod extract;
+mod fixed;
 mod shared;
 
-use core::{any::TypeId, ops::Range};
+#[cfg(feature = "alloc")]
+use core::{alloc::Layout, any::TypeId, num::NonZeroUsize, ops::Range};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 pub use archive::*;
+#[cfg(feature = "alloc")]
+pub use audit::*;
+#[cfg(feature = "alloc")]
+pub use exhaustive::*;
+#[cfg(feature = "alloc")]
+pub use extract::*;
+pub use fixed::*;
 pub use shared::*;
 
+#[cfg(feature = "alloc")]
+use bytecheck::rancor::Error;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use hashbrown::HashMap;
+
+#[cfg(feature = "alloc")]
 use crate::validation::{ArchiveContext, SharedContext};
 
 /// The default validator.
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub struct DefaultValidator {
     archive: ArchiveValidator,
     shared: SharedValidator,
+    // The ends of claimed subtrees by their starts, if aliasing is permitted
+    claimed: Option<HashMap<usize, usize>>,
+    check_hashes: bool,
 }
 
+#[cfg(feature = "alloc")]
 impl DefaultValidator {
     /// Creates a new validator from a byte range.
     #[inline]
@@ -24,28 +52,145 @@ impl DefaultValidator {
         Self {
             archive: ArchiveValidator::new(bytes),
             shared: SharedValidator::new(),
+            claimed: None,
+            check_hashes: true,
+        }
+    }
+
+    /// Creates a new validator from a byte range with a maximum subtree depth.
+    ///
+    /// See [`ArchiveValidator::with_max_depth`] for more information.
+    #[inline]
+    pub fn with_max_depth(
+        bytes: &[u8],
+        max_subtree_depth: Option<NonZeroUsize>,
+    ) -> Self {
+        Self {
+            archive: ArchiveValidator::with_max_depth(bytes, max_subtree_depth),
+            shared: SharedValidator::new(),
+            claimed: None,
+            check_hashes: true,
         }
     }
 
+    /// Creates a new validator from a byte range with a cold region.
+    ///
+    /// See [`ArchiveValidator::with_cold_region`] for more information.
+    #[inline]
+    pub fn with_cold_region(bytes: &[u8], cold: Range<usize>) -> Self {
+        Self {
+            archive: ArchiveValidator::with_cold_region(bytes, cold),
+            shared: SharedValidator::new(),
+            claimed: None,
+            check_hashes: true,
+        }
+    }
+
+    /// Creates a new validator from a byte range which continues validating
+    /// shared pointers with the given shared validator.
+    #[inline]
+    pub(crate) fn with_shared(bytes: &[u8], shared: SharedValidator) -> Self {
+        Self {
+            archive: ArchiveValidator::new(bytes),
+            shared,
+            claimed: None,
+            check_hashes: true,
+        }
+    }
+
+    /// Consumes the validator and returns its shared validator.
+    #[inline]
+    pub(crate) fn into_shared(self) -> SharedValidator {
+        self.shared
+    }
+
+    /// Sets whether relative pointers may point to subtrees which have already
+    /// been claimed.
+    ///
+    /// Aliasing is forbidden by default. Permitting it allows archives with
+    /// [`Intern`](crate::with::Intern) values to be validated. Every relative
+    /// pointer to an aliased subtree must point to exactly the same range of
+    /// bytes as the pointer which claimed it, and the subtree is checked again
+    /// each time it is pointed to. Aliasing is never permitted in archives with
+    /// a cold region.
+    ///
+    /// Aliasing must not be permitted when validating an archive for mutable
+    /// access.
+    #[inline]
+    pub fn set_aliasing(&mut self, aliasing: bool) {
+        if aliasing != self.claimed.is_some() {
+            self.claimed = aliasing.then(HashMap::new);
+        }
+    }
+
+    /// Sets whether archived hash maps and sets check the hashes of their
+    /// keys.
+    ///
+    /// Hashes are checked by default. Skipping them makes validating large
+    /// hash maps faster, but crafted archives may then contain keys which
+    /// can't be found by looking them up. See
+    /// [`ArchiveContext::check_hashes`] for more information.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use rkyv::{
+    ///     rancor::Failure, to_bytes, validation::util::access_with_context,
+    ///     validation::validators::DefaultValidator, Archived,
+    /// };
+    ///
+    /// let value = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+    /// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+    ///
+    /// let mut validator = DefaultValidator::new(&bytes);
+    /// validator.set_check_hashes(false);
+    /// let archived = access_with_context::<
+    ///     Archived<HashMap<String, i32>>,
+    ///     _,
+    ///     Failure,
+    /// >(&bytes, &mut validator)
+    /// .unwrap();
+    /// assert_eq!(archived.get("b").map(|v| v.to_native()), Some(2));
+    /// ```
+    #[inline]
+    pub fn set_check_hashes(&mut self, check_hashes: bool) {
+        self.check_hashes = check_hashes;
+    }
+
+    /// Returns the largest alignment of any subtree pointer which has been
+    /// checked so far.
+    ///
+    /// See [`ArchiveValidator::max_alignment`] for more information.
+    #[inline]
+    pub fn max_alignment(&self) -> usize {
+        self.archive.max_alignment()
+    }
+
     /// Create a new validator from a byte range with specific capacity.
     #[inline]
     pub fn with_capacity(bytes: &[u8], capacity: usize) -> Self {
         Self {
             archive: ArchiveValidator::new(bytes),
             shared: SharedValidator::with_capacity(capacity),
+            claimed: None,
+            check_hashes: true,
         }
     }
 }
 
+#[cfg(feature = "alloc")]
 unsafe impl<E> ArchiveContext<E> for DefaultValidator
 where
     ArchiveValidator: ArchiveContext<E>,
+    E: Error,
 {
     #[inline]
     fn check_subtree_ptr(
         &mut self,
         ptr: *const u8,
-        layout: &core::alloc::Layout,
+        layout: &Layout,
     ) -> Result<(), E> {
         self.archive.check_subtree_ptr(ptr, layout)
     }
@@ -56,7 +201,11 @@ where
         root: *const u8,
         end: *const u8,
     ) -> Result<Range<usize>, E> {
-        self.archive.push_prefix_subtree_range(root, end)
+        let range = self.archive.push_prefix_subtree_range(root, end)?;
+        if let Some(claimed) = &mut self.claimed {
+            claimed.insert(root.addr(), end.addr());
+        }
+        Ok(range)
     }
 
     #[inline]
@@ -68,6 +217,24 @@ where
         self.archive.push_suffix_subtree_range(start, root)
     }
 
+    #[inline]
+    unsafe fn push_aliased_subtree_range(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<Option<Range<usize>>, E> {
+        let start = ptr.addr();
+        match &self.claimed {
+            Some(claimed)
+                if claimed.get(&start).copied()
+                    == start.checked_add(layout.size()) =>
+            {
+                self.archive.push_claimed_subtree_range(ptr, layout)
+            }
+            _ => Ok(None),
+        }
+    }
+
     #[inline]
     unsafe fn pop_subtree_range(
         &mut self,
@@ -75,8 +242,19 @@ where
     ) -> Result<(), E> {
         unsafe { self.archive.pop_subtree_range(range) }
     }
+
+    #[inline]
+    fn archive_start(&self) -> Option<usize> {
+        ArchiveContext::<E>::archive_start(&self.archive)
+    }
+
+    #[inline]
+    fn check_hashes(&self) -> bool {
+        self.check_hashes
+    }
 }
 
+#[cfg(feature = "alloc")]
 impl<E> SharedContext<E> for DefaultValidator
 where
     SharedValidator: SharedContext<E>,
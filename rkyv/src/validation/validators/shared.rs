@@ -5,11 +5,14 @@ use core::{any::TypeId, fmt};
 #[cfg(feature = "std")]
 use std::collections::HashMap;
 
+#[cfg(feature = "alloc")]
 use bytecheck::rancor::Error;
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 use hashbrown::HashMap;
+#[cfg(feature = "alloc")]
 use rancor::fail;
 
+#[cfg(feature = "alloc")]
 use crate::validation::SharedContext;
 
 /// Errors that can occur when checking shared memory.
@@ -47,11 +50,13 @@ impl std::error::Error for SharedError {
 }
 
 /// A validator that can verify shared memory.
+#[cfg(feature = "alloc")]
 #[derive(Debug, Default)]
 pub struct SharedValidator {
     shared: HashMap<usize, TypeId>,
 }
 
+#[cfg(feature = "alloc")]
 impl SharedValidator {
     /// Wraps the given context and adds shared memory validation.
     #[inline]
@@ -70,8 +75,16 @@ impl SharedValidator {
             shared: HashMap::with_capacity(capacity),
         }
     }
+
+    /// Removes the registration of a shared pointer so that it will be checked
+    /// again the next time it is registered.
+    #[inline]
+    pub(crate) fn unregister_shared_ptr(&mut self, address: usize) {
+        self.shared.remove(&address);
+    }
 }
 
+#[cfg(feature = "alloc")]
 impl<E: Error> SharedContext<E> for SharedValidator {
     #[inline]
     fn register_shared_ptr(
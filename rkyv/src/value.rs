@@ -0,0 +1,323 @@
+//! A dynamically-typed value for archiving property bags.
+//!
+//! [`Value`] is a JSON-like recursive enum of nulls, booleans, integers,
+//! floats, strings, bytes, lists, and maps. It archives as an
+//! [`ArchivedValue`], which can be read without deserializing through accessor
+//! methods like [`as_i64`](ArchivedValue::as_i64),
+//! [`get`](ArchivedValue::get), and [`get_path`](ArchivedValue::get_path).
+//! Maps are archived as SwissTable [`ArchivedHashMap`]s.
+//!
+//! Values can nest arbitrarily deeply, so validating them with
+//! [`access`](crate::access) can recurse as deeply as the archive allows. Use
+//! [`access_value`] to validate values with a limited depth.
+//!
+//! With the `serde_json` feature, values can be converted to and from
+//! `serde_json::Value`.
+//!
+//! # Example
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use rkyv::{
+//!     rancor::Failure,
+//!     to_bytes,
+//!     value::{access_value, Value},
+//! };
+//!
+//! let value = Value::Map(HashMap::from([
+//!     ("name".to_string(), Value::String("widget".to_string())),
+//!     (
+//!         "sizes".to_string(),
+//!         Value::List(vec![Value::Int(1), Value::Int(2), Value::Float(2.5)]),
+//!     ),
+//! ]));
+//!
+//! let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+//! let archived = access_value::<Failure>(&bytes).unwrap();
+//! assert_eq!(archived.get("name").unwrap().as_str(), Some("widget"));
+//! assert_eq!(archived.get_path("sizes[1]").unwrap().as_i64(), Some(2));
+//! assert_eq!(*archived, value);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    collections::swiss_table::ArchivedHashMap, string::ArchivedString, Archive,
+    Deserialize, Serialize,
+};
+
+/// The maximum subtree depth that [`access_value`] validates.
+///
+/// Each nested list or map takes up at least one level.
+pub const MAX_DEPTH: usize = 256;
+
+/// A dynamically-typed value.
+#[derive(Archive, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[archive(crate)]
+#[archive(no_accessors)]
+#[archive(serialize_bounds(
+    __S: crate::ser::Writer + crate::ser::Allocator,
+    __S::Error: rancor::Error,
+))]
+#[archive(deserialize_bounds(
+    __D: crate::de::AllocationLimiter,
+))]
+#[cfg_attr(feature = "bytecheck", archive(check_bytes))]
+#[cfg_attr(
+    feature = "bytecheck",
+    archive_attr(check_bytes(bounds(__C: crate::validation::ArchiveContext)))
+)]
+#[archive_attr(derive(Debug))]
+pub enum Value {
+    /// No value.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A signed integer.
+    Int(i64),
+    /// A floating-point number.
+    Float(f64),
+    /// A UTF-8 string.
+    String(String),
+    /// A byte string.
+    Bytes(Vec<u8>),
+    /// A list of values.
+    List(
+        #[omit_bounds]
+        #[cfg_attr(feature = "bytecheck", archive_attr(omit_bounds))]
+        Vec<Value>,
+    ),
+    /// A map of values by their keys.
+    Map(
+        #[omit_bounds]
+        #[cfg_attr(feature = "bytecheck", archive_attr(omit_bounds))]
+        HashMap<String, Value>,
+    ),
+}
+
+impl ArchivedValue {
+    /// Returns whether the value is null.
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Returns the value as a boolean, if it is one.
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an integer, if it is one.
+    #[inline]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(value.to_native()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a floating-point number, if it is one.
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Float(value) => Some(value.to_native()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a string, if it is one.
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a byte string, if it is one.
+    #[inline]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(value) => Some(value.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a list, if it is one.
+    #[inline]
+    pub fn as_list(&self) -> Option<&[ArchivedValue]> {
+        match self {
+            Self::List(value) => Some(value.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a map, if it is one.
+    #[inline]
+    pub fn as_map(&self) -> Option<&ArchivedHashMap<ArchivedString, Self>> {
+        match self {
+            Self::Map(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value for the given key if this value is a map.
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&ArchivedValue> {
+        self.as_map()?.get(key)
+    }
+
+    /// Returns the value at the given index if this value is a list.
+    #[inline]
+    pub fn get_index(&self, index: usize) -> Option<&ArchivedValue> {
+        self.as_list()?.get(index)
+    }
+
+    /// Returns the value at the given path.
+    ///
+    /// Paths are made of map keys separated by `.`, each of which may be
+    /// followed by any number of list indices in brackets. For example,
+    /// `a.b[2].c` gets the value for key `c` in the third element of the list
+    /// for key `b` in the map for key `a`. A path may also start with a list
+    /// index, like `[0].a`. The empty path returns this value.
+    ///
+    /// Keys which contain `.` or `[` can't be reached with a path. Returns
+    /// `None` if the path is malformed or doesn't lead to a value.
+    pub fn get_path(&self, path: &str) -> Option<&ArchivedValue> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        let mut value = self;
+        for segment in path.split('.') {
+            let (key, mut indices) = match segment.find('[') {
+                Some(i) => segment.split_at(i),
+                None => (segment, ""),
+            };
+            if !key.is_empty() {
+                value = value.get(key)?;
+            } else if indices.is_empty() {
+                return None;
+            }
+            while !indices.is_empty() {
+                let rest = indices.strip_prefix('[')?;
+                let end = rest.find(']')?;
+                value = value.get_index(rest[..end].parse().ok()?)?;
+                indices = &rest[end + 1..];
+            }
+        }
+        Some(value)
+    }
+}
+
+impl PartialEq<Value> for ArchivedValue {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Self::Null, Value::Null) => true,
+            (Self::Bool(a), Value::Bool(b)) => a == b,
+            (Self::Int(a), Value::Int(b)) => a.to_native() == *b,
+            (Self::Float(a), Value::Float(b)) => a.to_native() == *b,
+            (Self::String(a), Value::String(b)) => a == b,
+            (Self::Bytes(a), Value::Bytes(b)) => a.as_slice() == b.as_slice(),
+            (Self::List(a), Value::List(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b.iter()).all(|(a, b)| a == b)
+            }
+            (Self::Map(a), Value::Map(b)) => {
+                a.len() == b.len()
+                    && b.iter().all(|(key, b)| {
+                        a.get(key.as_str()).is_some_and(|a| a == b)
+                    })
+            }
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<ArchivedValue> for Value {
+    #[inline]
+    fn eq(&self, other: &ArchivedValue) -> bool {
+        other.eq(self)
+    }
+}
+
+/// Accesses an archived value from the given byte slice, validating it with a
+/// maximum subtree depth of [`MAX_DEPTH`].
+#[cfg(feature = "bytecheck")]
+pub fn access_value<E: rancor::Error>(
+    bytes: &[u8],
+) -> Result<&ArchivedValue, E> {
+    use core::num::NonZeroUsize;
+
+    use crate::validation::{
+        util::access_with_context, validators::DefaultValidator,
+    };
+
+    let mut validator =
+        DefaultValidator::with_max_depth(bytes, NonZeroUsize::new(MAX_DEPTH));
+    access_with_context::<ArchivedValue, _, E>(bytes, &mut validator)
+}
+
+#[cfg(feature = "serde_json")]
+mod serde_json_impls {
+    use serde_json::{Map, Number};
+
+    use super::Value;
+
+    impl From<serde_json::Value> for Value {
+        /// Converts a JSON value into a value.
+        ///
+        /// Numbers which fit in an `i64` become integers, and all other
+        /// numbers become floats.
+        fn from(value: serde_json::Value) -> Self {
+            match value {
+                serde_json::Value::Null => Value::Null,
+                serde_json::Value::Bool(b) => Value::Bool(b),
+                serde_json::Value::Number(n) => match n.as_i64() {
+                    Some(i) => Value::Int(i),
+                    None => Value::Float(n.as_f64().unwrap_or(f64::NAN)),
+                },
+                serde_json::Value::String(s) => Value::String(s),
+                serde_json::Value::Array(a) => {
+                    Value::List(a.into_iter().map(Value::from).collect())
+                }
+                serde_json::Value::Object(o) => Value::Map(
+                    o.into_iter().map(|(k, v)| (k, Value::from(v))).collect(),
+                ),
+            }
+        }
+    }
+
+    impl From<Value> for serde_json::Value {
+        /// Converts a value into a JSON value.
+        ///
+        /// Floats which are not finite become `null`, and byte strings become
+        /// arrays of numbers.
+        fn from(value: Value) -> Self {
+            match value {
+                Value::Null => serde_json::Value::Null,
+                Value::Bool(b) => serde_json::Value::Bool(b),
+                Value::Int(i) => serde_json::Value::Number(i.into()),
+                Value::Float(f) => Number::from_f64(f)
+                    .map_or(serde_json::Value::Null, serde_json::Value::Number),
+                Value::String(s) => serde_json::Value::String(s),
+                Value::Bytes(b) => serde_json::Value::Array(
+                    b.into_iter().map(serde_json::Value::from).collect(),
+                ),
+                Value::List(l) => serde_json::Value::Array(
+                    l.into_iter().map(serde_json::Value::from).collect(),
+                ),
+                Value::Map(m) => serde_json::Value::Object(
+                    m.into_iter()
+                        .map(|(k, v)| (k, serde_json::Value::from(v)))
+                        .collect::<Map<_, _>>(),
+                ),
+            }
+        }
+    }
+}
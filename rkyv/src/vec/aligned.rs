@@ -0,0 +1,174 @@
+use core::{borrow::Borrow, fmt, ops::Deref};
+
+use crate::{
+    vec::{ArchivedVec, VecResolver},
+    Portable,
+};
+
+/// An archived byte vector whose bytes are aligned to `ALIGN` bytes from the
+/// start of the archive.
+///
+/// This is the archived form of a `Vec<u8>` or `Box<[u8]>` serialized with
+/// [`AlignTo`](crate::with::AlignTo). It has the same layout as an
+/// [`ArchivedVec<u8>`]. If the buffer containing the archive is aligned to
+/// `ALIGN` bytes, then so are the bytes of this vector.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(transparent)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedAlignedBytes<const ALIGN: usize> {
+    inner: ArchivedVec<u8>,
+}
+
+impl<const ALIGN: usize> ArchivedAlignedBytes<ALIGN> {
+    /// Returns a pointer to the first byte of the archived bytes.
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.inner.as_ptr()
+    }
+
+    /// Returns the number of archived bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether there are no archived bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Gets the archived bytes as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.inner.as_slice()
+    }
+
+    /// Gets the archived bytes as an archived vec.
+    #[inline]
+    pub fn as_archived_vec(&self) -> &ArchivedVec<u8> {
+        &self.inner
+    }
+
+    /// Resolves archived aligned bytes from a given length.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of writing `len` bytes at a position
+    ///   which is a multiple of `ALIGN`
+    #[inline]
+    pub unsafe fn resolve_from_len(
+        len: usize,
+        pos: usize,
+        resolver: VecResolver,
+        out: *mut Self,
+    ) {
+        ArchivedVec::<u8>::resolve_from_len(len, pos, resolver, out.cast());
+    }
+}
+
+impl<const ALIGN: usize> AsRef<[u8]> for ArchivedAlignedBytes<ALIGN> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<const ALIGN: usize> Borrow<[u8]> for ArchivedAlignedBytes<ALIGN> {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<const ALIGN: usize> fmt::Debug for ArchivedAlignedBytes<ALIGN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<const ALIGN: usize> Deref for ArchivedAlignedBytes<ALIGN> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<const ALIGN: usize> PartialEq<[u8]> for ArchivedAlignedBytes<ALIGN> {
+    #[inline]
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<const ALIGN: usize> PartialEq<ArchivedAlignedBytes<ALIGN>> for [u8] {
+    #[inline]
+    fn eq(&self, other: &ArchivedAlignedBytes<ALIGN>) -> bool {
+        self == other.as_slice()
+    }
+}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use core::fmt;
+
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        Verify,
+    };
+    use rancor::fail;
+
+    use super::ArchivedAlignedBytes;
+    use crate::validation::ArchiveContext;
+
+    /// An error resulting from archived bytes which are not aligned relative
+    /// to the start of the archive.
+    #[derive(Debug)]
+    struct UnalignedBytes {
+        offset: usize,
+        align: usize,
+    }
+
+    impl fmt::Display for UnalignedBytes {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "aligned bytes start at offset {} from the start of the \
+                 archive, which is not a multiple of {}",
+                self.offset, self.align,
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for UnalignedBytes {}
+
+    unsafe impl<C, const ALIGN: usize> Verify<C> for ArchivedAlignedBytes<ALIGN>
+    where
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, context: &mut C) -> Result<(), C::Error> {
+            // Validators which don't know where the archive starts can only
+            // check the absolute address, which is the same as long as the
+            // buffer is aligned to `ALIGN` bytes.
+            let start = context.archive_start().unwrap_or(0);
+            let offset = self.as_ptr().addr().wrapping_sub(start);
+            if ALIGN != 0 && offset % ALIGN != 0 {
+                fail!(UnalignedBytes {
+                    offset,
+                    align: ALIGN,
+                });
+            }
+            Ok(())
+        }
+    }
+}
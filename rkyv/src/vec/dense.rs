@@ -0,0 +1,157 @@
+use core::{fmt, ops::Deref};
+
+use crate::{
+    string::ArchivedString,
+    vec::{ArchivedVec, VecResolver},
+    Portable,
+};
+
+/// An archived vector of strings whose bytes are laid out back-to-back.
+///
+/// This is the archived form of a `Vec<String>` serialized with
+/// [`DenseStrings`](crate::with::DenseStrings). It has the same layout as an
+/// [`ArchivedVec<ArchivedString>`], and the bytes of its out-of-line strings
+/// are written back-to-back in order. This lets validation claim and check
+/// the bytes of all of the strings at once with
+/// [`check_strings`](crate::string::check_strings).
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(transparent)]
+pub struct ArchivedDenseStrings {
+    inner: ArchivedVec<ArchivedString>,
+}
+
+impl ArchivedDenseStrings {
+    /// Returns the number of strings.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether there are no strings.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Gets the strings as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[ArchivedString] {
+        self.inner.as_slice()
+    }
+
+    /// Gets the strings as an archived vec.
+    #[inline]
+    pub fn as_archived_vec(&self) -> &ArchivedVec<ArchivedString> {
+        &self.inner
+    }
+
+    /// Resolves archived dense strings from a given length.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of writing `len` archived strings
+    #[inline]
+    pub unsafe fn resolve_from_len(
+        len: usize,
+        pos: usize,
+        resolver: VecResolver,
+        out: *mut Self,
+    ) {
+        ArchivedVec::<ArchivedString>::resolve_from_len(
+            len,
+            pos,
+            resolver,
+            out.cast(),
+        );
+    }
+}
+
+impl AsRef<[ArchivedString]> for ArchivedDenseStrings {
+    #[inline]
+    fn as_ref(&self) -> &[ArchivedString] {
+        self.as_slice()
+    }
+}
+
+impl fmt::Debug for ArchivedDenseStrings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl Deref for ArchivedDenseStrings {
+    type Target = [ArchivedString];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<U: AsRef<str>> PartialEq<[U]> for ArchivedDenseStrings {
+    #[inline]
+    fn eq(&self, other: &[U]) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(a, b)| a.as_str() == b.as_ref())
+    }
+}
+
+#[cfg(feature = "bytecheck")]
+const _: () = {
+    use core::ptr::addr_of;
+
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        CheckBytes,
+    };
+
+    use crate::{
+        primitive::ArchivedUsize,
+        string::check_strings,
+        validation::{ArchiveContext, ArchiveContextExt},
+        RelPtr,
+    };
+
+    unsafe impl<C> CheckBytes<C> for ArchivedDenseStrings
+    where
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        unsafe fn check_bytes(
+            value: *const Self,
+            context: &mut C,
+        ) -> Result<(), C::Error> {
+            unsafe {
+                RelPtr::<ArchivedString>::check_bytes(
+                    addr_of!((*value).inner.ptr),
+                    context,
+                )?;
+                ArchivedUsize::check_bytes(
+                    addr_of!((*value).inner.len),
+                    context,
+                )?;
+            }
+
+            let inner = unsafe { &(*value).inner };
+            context.record_offset(inner.ptr.offset_slot())?;
+            let (ptr, range) = unsafe {
+                context.push_aliasable_subtree_base_offset::<[ArchivedString]>(
+                    inner.ptr.base(),
+                    inner.ptr.offset(),
+                    inner.len(),
+                )?
+            };
+            unsafe {
+                check_strings(ptr, context)?;
+                context.pop_subtree_range(range)?;
+            }
+
+            Ok(())
+        }
+    }
+};
@@ -1,23 +1,36 @@
 //! An archived version of `Vec`.
 
+mod aligned;
+mod dense;
+mod packed;
 // mod raw;
+mod run_length;
 
 use core::{
     borrow::Borrow,
     cmp, fmt, hash,
     ops::{Deref, Index, IndexMut},
     pin::Pin,
-    slice::SliceIndex,
+    slice::{self, SliceIndex},
 };
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+use core::{iter::FusedIterator, marker::PhantomData};
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use ::alloc::vec::Vec;
 use rancor::Fallible;
 
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+use crate::validation::validators::ValidationReport;
 use crate::{
+    de::DeserializeIter,
     primitive::ArchivedUsize,
     ser::{Allocator, Writer, WriterExt as _},
-    Archive, Portable, RelPtr, Serialize, SerializeUnsized,
+    unpin::{unpin_mut, ArchivedNoRelPtrs},
+    Archive, Deserialize, Portable, RelPtr, Serialize, SerializeUnsized,
 };
 
+pub use self::{aligned::*, dense::*, packed::*, run_length::*};
 // pub use self::raw::*;
 
 /// An archived [`Vec`].
@@ -45,6 +58,13 @@ impl<T> ArchivedVec<T> {
         unsafe { self.ptr.as_ptr().cast_const() }
     }
 
+    /// Returns a pointer to the first element of the archived vec, calculated
+    /// with wrapping arithmetic.
+    #[inline]
+    pub(crate) fn as_ptr_wrapping(&self) -> *const T {
+        self.ptr.as_ptr_wrapping().cast_const()
+    }
+
     /// Returns the number of elements in the archived vec.
     #[inline]
     pub fn len(&self) -> usize {
@@ -63,16 +83,66 @@ impl<T> ArchivedVec<T> {
         unsafe { core::slice::from_raw_parts(self.as_ptr(), self.len()) }
     }
 
+    /// Returns an iterator which deserializes the elements of the archived vec
+    /// one at a time.
+    ///
+    /// All of the elements are deserialized with the same deserializer, and
+    /// elements are only deserialized when they are reached. Use
+    /// [`collect_deserialized`](DeserializeIter::collect_deserialized) to
+    /// deserialize all of the remaining elements into a collection.
+    #[inline]
+    pub fn iter_deserialized<'d, U, D>(
+        &self,
+        deserializer: &'d mut D,
+    ) -> DeserializeIter<'d, slice::Iter<'_, T>, U, D>
+    where
+        T: Deserialize<U, D>,
+        D: Fallible + ?Sized,
+    {
+        DeserializeIter::new(self.iter(), deserializer)
+    }
+
+    /// Returns an iterator over the elements of the archived vec which were
+    /// not reported as invalid.
+    ///
+    /// This can be used to read vecs which were accessed with
+    /// [`access_exhaustive`](crate::validation::util::access_exhaustive).
+    #[cfg(all(feature = "bytecheck", feature = "alloc"))]
+    #[inline]
+    pub fn iter_valid<'a, E>(
+        &'a self,
+        report: &'a ValidationReport<E>,
+    ) -> IterValid<'a, T, E> {
+        IterValid {
+            ptr: self.as_ptr(),
+            remaining: self.len(),
+            report,
+            _phantom: PhantomData,
+        }
+    }
+
     /// Gets the elements of the archived vec as a pinned mutable slice.
     #[inline]
     pub fn pin_mut_slice(self: Pin<&mut Self>) -> Pin<&mut [T]> {
         unsafe {
             self.map_unchecked_mut(|s| {
-                core::slice::from_raw_parts_mut(s.ptr.as_ptr(), s.len())
+                core::slice::from_raw_parts_mut(s.ptr.as_mut_ptr(), s.len())
             })
         }
     }
 
+    /// Gets the elements of the archived vec as a mutable slice.
+    ///
+    /// This is only available for elements which can be mutated without
+    /// pinning.
+    #[inline]
+    pub fn as_mut_slice(self: Pin<&mut Self>) -> &mut [T]
+    where
+        T: ArchivedNoRelPtrs,
+    {
+        unpin_mut(self.pin_mut_slice())
+    }
+
     // This method can go away once pinned slices have indexing support
     // https://github.com/rust-lang/rust/pull/78370
 
@@ -212,6 +282,99 @@ impl<T> ArchivedVec<T> {
             Ok(VecResolver { pos })
         }
     }
+
+    /// Serializes the next chunk of up to `count` elements of an archived
+    /// `Vec` from a given slice.
+    ///
+    /// `state` must be the default state for the first chunk and must be passed
+    /// unmodified between chunks of the same slice. Returns the resolver once
+    /// all of the elements have been serialized. The output is the same as
+    /// [`serialize_from_slice`](ArchivedVec::serialize_from_slice).
+    #[cfg(feature = "alloc")]
+    pub fn serialize_chunk_from_slice<U, S>(
+        slice: &[U],
+        state: &mut VecChunks<U::Resolver>,
+        count: usize,
+        serializer: &mut S,
+    ) -> Result<Option<VecResolver>, S::Error>
+    where
+        U: Serialize<S, Archived = T>,
+        S: Fallible + Writer + ?Sized,
+    {
+        use core::{mem::size_of, slice::from_raw_parts};
+
+        if U::COPY_OPTIMIZATION.is_enabled() {
+            let pos = match state.pos {
+                Some(pos) => pos,
+                None => *state.pos.insert(serializer.align_for::<T>()?),
+            };
+            let end =
+                usize::min(state.index.saturating_add(count), slice.len());
+            // SAFETY: `U` is copy-optimized, so the bytes of the slice are the
+            // same as the bytes of its archived form.
+            let bytes = unsafe {
+                from_raw_parts(
+                    slice[state.index..end].as_ptr().cast::<u8>(),
+                    size_of::<U>() * (end - state.index),
+                )
+            };
+            serializer.write(bytes)?;
+            state.index = end;
+            return Ok((end == slice.len()).then_some(VecResolver { pos }));
+        }
+
+        match state.pos {
+            None => {
+                let end = usize::min(
+                    state.resolvers.len().saturating_add(count),
+                    slice.len(),
+                );
+                for value in &slice[state.resolvers.len()..end] {
+                    state.resolvers.push(value.serialize(serializer)?);
+                }
+                if end == slice.len() {
+                    state.pos = Some(serializer.align_for::<T>()?);
+                    state.resolvers.reverse();
+                }
+                Ok(None)
+            }
+            Some(pos) => {
+                let end =
+                    usize::min(state.index.saturating_add(count), slice.len());
+                for value in &slice[state.index..end] {
+                    // Resolvers are reversed so they can be popped in order
+                    let resolver = state.resolvers.pop().unwrap();
+                    unsafe {
+                        serializer.resolve_aligned(value, resolver)?;
+                    }
+                }
+                state.index = end;
+                Ok((end == slice.len()).then_some(VecResolver { pos }))
+            }
+        }
+    }
+}
+
+/// The state kept between chunks by
+/// [`ArchivedVec::serialize_chunk_from_slice`].
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct VecChunks<R> {
+    resolvers: Vec<R>,
+    index: usize,
+    pos: Option<usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl<R> Default for VecChunks<R> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            resolvers: Vec::new(),
+            index: 0,
+            pos: None,
+        }
+    }
 }
 
 impl<T> AsRef<[T]> for ArchivedVec<T> {
@@ -261,6 +424,16 @@ impl<T, I: SliceIndex<[T]>> Index<I> for ArchivedVec<T> {
     }
 }
 
+impl<'a, T> IntoIterator for &'a ArchivedVec<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
 impl<T: Ord> Ord for ArchivedVec<T> {
     #[inline]
     fn cmp(&self, other: &Self) -> cmp::Ordering {
@@ -324,23 +497,88 @@ impl<T: PartialOrd> PartialOrd<ArchivedVec<T>> for [T] {
     }
 }
 
+/// An iterator over the elements of an [`ArchivedVec`] which were not reported
+/// as invalid.
+///
+/// This `struct` is created by the [`ArchivedVec::iter_valid`] function.
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+pub struct IterValid<'a, T, E> {
+    ptr: *const T,
+    remaining: usize,
+    report: &'a ValidationReport<E>,
+    _phantom: PhantomData<&'a T>,
+}
+
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+impl<'a, T, E> Iterator for IterValid<'a, T, E> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining > 0 {
+            let ptr = self.ptr;
+            self.ptr = unsafe { ptr.add(1) };
+            self.remaining -= 1;
+            if self.report.is_valid_address(ptr.addr()) {
+                return Some(unsafe { &*ptr });
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+impl<T, E> FusedIterator for IterValid<'_, T, E> {}
+
 /// The resolver for [`ArchivedVec`].
 pub struct VecResolver {
     pos: usize,
 }
 
+impl VecResolver {
+    /// Creates a new [`VecResolver`] from the position of the serialized
+    /// elements.
+    ///
+    /// In most cases, you won't need to create a [`VecResolver`] yourself and
+    /// can instead obtain it through [`ArchivedVec::serialize_from_slice`] or
+    /// [`ArchivedVec::serialize_from_iter`].
+    #[inline]
+    pub fn from_pos(pos: usize) -> Self {
+        Self { pos }
+    }
+}
+
 #[cfg(feature = "bytecheck")]
 mod verify {
+    use core::fmt;
+
     use bytecheck::{
         rancor::{Error, Fallible},
         CheckBytes, Verify,
     };
+    use rancor::ResultExt as _;
 
     use crate::{
         validation::{ArchiveContext, ArchiveContextExt},
         vec::ArchivedVec,
     };
 
+    #[derive(Debug)]
+    struct ElementCheckContext {
+        index: usize,
+    }
+
+    impl fmt::Display for ElementCheckContext {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "while checking index '{}' of vec", self.index)
+        }
+    }
+
     unsafe impl<T, C> Verify<C> for ArchivedVec<T>
     where
         T: CheckBytes<C>,
@@ -348,17 +586,23 @@ mod verify {
         C::Error: Error,
     {
         fn verify(&self, context: &mut C) -> Result<(), C::Error> {
-            let ptr = unsafe {
-                context.bounds_check_subtree_base_offset::<[T]>(
+            context.record_offset(self.ptr.offset_slot())?;
+            let (ptr, range) = unsafe {
+                context.push_aliasable_subtree_base_offset::<[T]>(
                     self.ptr.base(),
                     self.ptr.offset(),
                     self.len.to_native() as usize,
                 )?
             };
-
-            let range = unsafe { context.push_prefix_subtree(ptr)? };
-            unsafe {
-                <[T]>::check_bytes(ptr, context)?;
+            let base = ptr.cast::<T>();
+            for index in 0..self.len() {
+                unsafe {
+                    let element = base.add(index);
+                    context.start_element()?;
+                    let result = T::check_bytes(element, context)
+                        .with_trace(|| ElementCheckContext { index });
+                    context.finish_element(index, element.cast(), result)?;
+                }
             }
             unsafe {
                 context.pop_subtree_range(range)?;
@@ -368,3 +612,153 @@ mod verify {
         }
     }
 }
+
+#[cfg(all(feature = "bytecheck", feature = "alloc"))]
+mod lazy {
+    use rancor::Error;
+
+    use crate::{
+        validation::{
+            lazy::{Lazy, Shell},
+            ArchiveContextExt as _,
+        },
+        vec::ArchivedVec,
+    };
+
+    impl<'a, T: 'static> Lazy<'a, ArchivedVec<T>> {
+        /// Returns the number of elements in the archived vec.
+        #[inline]
+        pub fn len(&self) -> usize {
+            // The length is valid for any bit pattern
+            unsafe { (*self.as_ptr()).len() }
+        }
+
+        /// Returns whether the archived vec is empty.
+        #[inline]
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// Returns a lazy handle to the element at the given index, or `None`
+        /// if the index is out of bounds.
+        ///
+        /// This checks that the elements of the vec are located inside of the
+        /// archive, but does not validate any of them.
+        pub fn element<E: Error>(
+            &self,
+            index: usize,
+        ) -> Result<Option<Lazy<'a, T>>, E> {
+            unsafe {
+                self.validate::<Shell<ArchivedVec<T>>, E>(|context| {
+                    let vec = &*self.as_ptr();
+                    context.bounds_check_subtree_base_offset::<[T]>(
+                        vec.ptr.base(),
+                        vec.ptr.offset(),
+                        vec.len(),
+                    )?;
+                    Ok(())
+                })?;
+
+                if index < self.len() {
+                    let vec = &*self.as_ptr();
+                    Ok(Some(self.child(vec.ptr.as_ptr_wrapping().add(index))))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod layout {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::String, vec};
+    use core::mem::offset_of;
+
+    use crate::{
+        layout::{
+            hash_structure, DescribeLayout, FieldLayout, LayoutKind,
+            LayoutRegistry, StructuralHash, TypeLayout,
+        },
+        primitive::ArchivedUsize,
+        vec::ArchivedVec,
+        RelPtr,
+    };
+
+    impl<T: DescribeLayout> DescribeLayout for ArchivedVec<T> {
+        fn layout_name() -> String {
+            format!("ArchivedVec_{}", T::layout_name())
+        }
+
+        fn describe_layout(registry: &mut LayoutRegistry) -> TypeLayout {
+            let fields = vec![
+                FieldLayout::new(
+                    "ptr",
+                    offset_of!(Self, ptr),
+                    registry.register::<RelPtr<T>>(),
+                ),
+                FieldLayout::new(
+                    "len",
+                    offset_of!(Self, len),
+                    registry.register::<ArchivedUsize>(),
+                ),
+            ];
+            TypeLayout::of::<Self>(
+                Self::layout_name(),
+                LayoutKind::Struct { fields },
+            )
+            .with_description(
+                "`ptr` points to the first of `len` consecutive elements.",
+            )
+        }
+    }
+
+    // SAFETY: The hash covers the offsets and structures of the fields.
+    unsafe impl<T: StructuralHash> StructuralHash for ArchivedVec<T> {
+        const STRUCTURAL_HASH: u64 = hash_structure::<Self>(
+            "struct",
+            &[
+                offset_of!(Self, ptr) as u64,
+                RelPtr::<T>::STRUCTURAL_HASH,
+                offset_of!(Self, len) as u64,
+                ArchivedUsize::STRUCTURAL_HASH,
+            ],
+        );
+    }
+}
+
+#[cfg(feature = "reflect")]
+mod reflect {
+    use crate::{
+        reflect::{descriptor, DescriptorKind, Reflect, TypeDescriptor},
+        vec::ArchivedVec,
+    };
+
+    impl<T: Reflect> Reflect for ArchivedVec<T> {
+        const DESCRIPTOR: TypeDescriptor = TypeDescriptor::of::<Self>(
+            "Vec",
+            DescriptorKind::Vec {
+                element: descriptor::<T>,
+            },
+        );
+    }
+}
+
+#[cfg(all(feature = "alloc", feature = "bytecheck"))]
+mod patch {
+    use core::mem::offset_of;
+
+    use crate::{
+        patch::{Patchable, Relocation},
+        vec::ArchivedVec,
+        Portable,
+    };
+
+    unsafe impl<T: Portable> Patchable for ArchivedVec<T> {
+        #[inline]
+        fn relocations(&self, f: &mut dyn FnMut(Relocation)) {
+            f(Relocation::Offset(offset_of!(ArchivedVec<T>, ptr)));
+        }
+    }
+}
@@ -0,0 +1,570 @@
+use core::{fmt, iter::FusedIterator, marker::PhantomData};
+
+use rancor::Fallible;
+
+use crate::{
+    primitive::{ArchivedU64, ArchivedUsize},
+    ser::{Writer, WriterExt as _},
+    vec::{ArchivedVec, VecResolver},
+    Archive, Portable,
+};
+
+/// The number of values between the entries of the skip index of an
+/// [`ArchivedPackedInts`].
+pub const PACKED_BLOCK_LEN: usize = 64;
+
+/// The maximum number of bytes in a varint.
+const MAX_VARINT_LEN: usize = 10;
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u64 {}
+    impl Sealed for i64 {}
+}
+
+/// An integer type that can be packed into an [`ArchivedPackedInts`].
+///
+/// This trait is sealed and implemented for `u64` and `i64`.
+pub trait PackedInt: Copy + private::Sealed {
+    /// Returns the bits of the integer.
+    fn to_bits(self) -> u64;
+
+    /// Returns the integer with the given bits.
+    fn from_bits(bits: u64) -> Self;
+
+    /// Maps the integer to an unsigned integer that is small when the
+    /// magnitude of the integer is small.
+    fn encode(self) -> u64;
+
+    /// Inverts [`encode`](PackedInt::encode).
+    fn decode(code: u64) -> Self;
+}
+
+impl PackedInt for u64 {
+    #[inline]
+    fn to_bits(self) -> u64 {
+        self
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits
+    }
+
+    #[inline]
+    fn encode(self) -> u64 {
+        self
+    }
+
+    #[inline]
+    fn decode(code: u64) -> Self {
+        code
+    }
+}
+
+impl PackedInt for i64 {
+    #[inline]
+    fn to_bits(self) -> u64 {
+        self as u64
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits as i64
+    }
+
+    #[inline]
+    fn encode(self) -> u64 {
+        zigzag(self)
+    }
+
+    #[inline]
+    fn decode(code: u64) -> Self {
+        unzigzag(code)
+    }
+}
+
+#[inline]
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[inline]
+fn unzigzag(code: u64) -> i64 {
+    ((code >> 1) as i64) ^ -((code & 1) as i64)
+}
+
+/// Encodes `value` as a LEB128 varint and returns the encoded bytes and their
+/// length.
+#[inline]
+fn encode_varint(mut value: u64) -> ([u8; MAX_VARINT_LEN], usize) {
+    let mut bytes = [0; MAX_VARINT_LEN];
+    let mut len = 0;
+    while value >= 0x80 {
+        bytes[len] = value as u8 | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    bytes[len] = value as u8;
+    (bytes, len + 1)
+}
+
+/// Decodes a LEB128 varint from `bytes` at `pos`, advancing `pos` past it.
+///
+/// Returns `None` if the varint does not end within `bytes` or does not fit in
+/// a `u64`.
+#[inline]
+fn decode_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..MAX_VARINT_LEN {
+        let byte = *bytes.get(*pos + i)?;
+        if i == MAX_VARINT_LEN - 1 && byte > 1 {
+            return None;
+        }
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *pos += i + 1;
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Tracks the state needed to encode or decode the next value of a packed
+/// stream.
+struct Cursor<T, const DELTA: bool> {
+    pos: usize,
+    prev: u64,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: PackedInt, const DELTA: bool> Cursor<T, DELTA> {
+    #[inline]
+    fn new(pos: usize, prev: u64) -> Self {
+        Self {
+            pos,
+            prev,
+            _phantom: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn encode(&mut self, value: T) -> ([u8; MAX_VARINT_LEN], usize) {
+        let code = if DELTA {
+            let delta = value.to_bits().wrapping_sub(self.prev) as i64;
+            self.prev = value.to_bits();
+            zigzag(delta)
+        } else {
+            value.encode()
+        };
+        let (bytes, len) = encode_varint(code);
+        self.pos += len;
+        (bytes, len)
+    }
+
+    #[inline]
+    fn decode(&mut self, bytes: &[u8]) -> Option<T> {
+        let code = decode_varint(bytes, &mut self.pos)?;
+        if DELTA {
+            self.prev = self.prev.wrapping_add(unzigzag(code) as u64);
+            Some(T::from_bits(self.prev))
+        } else {
+            Some(T::decode(code))
+        }
+    }
+}
+
+/// An entry of the skip index of an [`ArchivedPackedInts`].
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+pub struct ArchivedPackedBlock {
+    offset: ArchivedUsize,
+    prev: ArchivedU64,
+}
+
+impl ArchivedPackedBlock {
+    /// Returns the offset of the first value of the block in the encoded
+    /// bytes.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset.to_native() as usize
+    }
+
+    /// Returns the bits of the value before the first value of the block, or
+    /// zero if the values are not delta-encoded.
+    #[inline]
+    pub fn prev(&self) -> u64 {
+        self.prev.to_native()
+    }
+
+    #[inline]
+    fn cursor<T: PackedInt, const DELTA: bool>(&self) -> Cursor<T, DELTA> {
+        Cursor::new(self.offset(), self.prev())
+    }
+}
+
+/// A skip index entry of a slice that is being serialized.
+struct PackedBlock {
+    offset: usize,
+    prev: u64,
+}
+
+impl Archive for PackedBlock {
+    type Archived = ArchivedPackedBlock;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        _: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        let (fp, fo) = out_field!(out.offset);
+        usize::resolve(&self.offset, pos + fp, (), fo);
+        let (fp, fo) = out_field!(out.prev);
+        u64::resolve(&self.prev, pos + fp, (), fo);
+    }
+}
+
+/// An archived vector of integers packed into varints.
+///
+/// This is the archived form of a `Vec<u64>` or `Vec<i64>` serialized with
+/// [`Varint`](crate::with::Varint) or [`DeltaVarint`](crate::with::DeltaVarint).
+/// Each value is encoded as a LEB128 varint. Signed values are zigzag-encoded
+/// so that values with small magnitudes take up few bytes. If `DELTA` is
+/// `true`, the differences between consecutive values are encoded instead.
+///
+/// A skip index records where every [`PACKED_BLOCK_LEN`]th value starts, so
+/// [`get`](ArchivedPackedInts::get) decodes at most `PACKED_BLOCK_LEN` values.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedPackedInts<T, const DELTA: bool> {
+    bytes: ArchivedVec<u8>,
+    blocks: ArchivedVec<ArchivedPackedBlock>,
+    len: ArchivedUsize,
+    _phantom: PhantomData<T>,
+}
+
+/// Integers archived with [`Varint`](crate::with::Varint).
+pub type ArchivedVarints<T> = ArchivedPackedInts<T, false>;
+
+/// Integers archived with [`DeltaVarint`](crate::with::DeltaVarint).
+pub type ArchivedDeltaVarints<T> = ArchivedPackedInts<T, true>;
+
+impl<T: PackedInt, const DELTA: bool> ArchivedPackedInts<T, DELTA> {
+    /// Returns the number of values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.to_native() as usize
+    }
+
+    /// Returns whether there are no values.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the encoded bytes of the values.
+    #[inline]
+    pub fn encoded_bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Returns the skip index.
+    #[inline]
+    pub fn blocks(&self) -> &[ArchivedPackedBlock] {
+        self.blocks.as_slice()
+    }
+
+    /// Returns the value at the given index, or `None` if the index is out of
+    /// bounds.
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        let block = self.blocks().get(index / PACKED_BLOCK_LEN)?;
+        let mut cursor = block.cursor::<T, DELTA>();
+        for _ in 0..index % PACKED_BLOCK_LEN {
+            cursor.decode(self.encoded_bytes())?;
+        }
+        cursor.decode(self.encoded_bytes())
+    }
+
+    /// Returns an iterator over the values.
+    #[inline]
+    pub fn iter(&self) -> PackedIntsIter<'_, T, DELTA> {
+        PackedIntsIter {
+            bytes: self.encoded_bytes(),
+            cursor: Cursor::new(0, 0),
+            remaining: self.len(),
+        }
+    }
+
+    /// Resolves archived packed integers from the length of the original
+    /// slice.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing a slice of `len` values
+    ///   with [`serialize_from_slice`](Self::serialize_from_slice)
+    #[inline]
+    pub unsafe fn resolve_from_len(
+        len: usize,
+        pos: usize,
+        resolver: PackedIntsResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.bytes);
+        ArchivedVec::resolve_from_len(
+            resolver.bytes_len,
+            pos + fp,
+            resolver.bytes,
+            fo,
+        );
+        let (fp, fo) = out_field!(out.blocks);
+        ArchivedVec::resolve_from_len(
+            len.div_ceil(PACKED_BLOCK_LEN),
+            pos + fp,
+            resolver.blocks,
+            fo,
+        );
+        let (fp, fo) = out_field!(out.len);
+        usize::resolve(&len, pos + fp, (), fo);
+    }
+
+    /// Serializes the values of a slice as packed integers.
+    pub fn serialize_from_slice<S>(
+        slice: &[T],
+        serializer: &mut S,
+    ) -> Result<PackedIntsResolver, S::Error>
+    where
+        S: Fallible + Writer + ?Sized,
+    {
+        let bytes_pos = serializer.pos();
+        let mut cursor = Cursor::<T, DELTA>::new(0, 0);
+        for &value in slice {
+            let (bytes, len) = cursor.encode(value);
+            serializer.write(&bytes[..len])?;
+        }
+        let bytes_len = cursor.pos;
+
+        // Encode the values again to find where each block starts.
+        let blocks_pos = serializer.align_for::<ArchivedPackedBlock>()?;
+        let mut cursor = Cursor::<T, DELTA>::new(0, 0);
+        for chunk in slice.chunks(PACKED_BLOCK_LEN) {
+            let block = PackedBlock {
+                offset: cursor.pos,
+                prev: cursor.prev,
+            };
+            unsafe {
+                serializer.resolve_aligned(&block, ())?;
+            }
+            for &value in chunk {
+                cursor.encode(value);
+            }
+        }
+
+        Ok(PackedIntsResolver {
+            bytes: VecResolver::from_pos(bytes_pos),
+            bytes_len,
+            blocks: VecResolver::from_pos(blocks_pos),
+        })
+    }
+}
+
+impl<T, const DELTA: bool> fmt::Debug for ArchivedPackedInts<T, DELTA>
+where
+    T: PackedInt + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T: PackedInt, const DELTA: bool> IntoIterator
+    for &'a ArchivedPackedInts<T, DELTA>
+{
+    type Item = T;
+    type IntoIter = PackedIntsIter<'a, T, DELTA>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const DELTA: bool> PartialEq<[T]> for ArchivedPackedInts<T, DELTA>
+where
+    T: PackedInt + PartialEq,
+{
+    fn eq(&self, other: &[T]) -> bool {
+        self.len() == other.len()
+            && self.iter().zip(other.iter()).all(|(a, b)| a == *b)
+    }
+}
+
+impl<T, const DELTA: bool> PartialEq<ArchivedPackedInts<T, DELTA>> for [T]
+where
+    T: PackedInt + PartialEq,
+{
+    #[inline]
+    fn eq(&self, other: &ArchivedPackedInts<T, DELTA>) -> bool {
+        other.eq(self)
+    }
+}
+
+/// An iterator over the values of an [`ArchivedPackedInts`].
+pub struct PackedIntsIter<'a, T, const DELTA: bool> {
+    bytes: &'a [u8],
+    cursor: Cursor<T, DELTA>,
+    remaining: usize,
+}
+
+impl<T: PackedInt, const DELTA: bool> Iterator
+    for PackedIntsIter<'_, T, DELTA>
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = self.cursor.decode(self.bytes);
+        // Only unvalidated archives can end early.
+        self.remaining = if value.is_some() {
+            self.remaining - 1
+        } else {
+            0
+        };
+        value
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: PackedInt, const DELTA: bool> ExactSizeIterator
+    for PackedIntsIter<'_, T, DELTA>
+{
+}
+
+impl<T: PackedInt, const DELTA: bool> FusedIterator
+    for PackedIntsIter<'_, T, DELTA>
+{
+}
+
+/// The resolver for an [`ArchivedPackedInts`].
+pub struct PackedIntsResolver {
+    bytes: VecResolver,
+    bytes_len: usize,
+    blocks: VecResolver,
+}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use core::fmt;
+
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        Verify,
+    };
+    use rancor::fail;
+
+    use super::{ArchivedPackedInts, Cursor, PackedInt, PACKED_BLOCK_LEN};
+    use crate::validation::ArchiveContext;
+
+    /// An error resulting from invalid packed integers.
+    #[derive(Debug)]
+    enum PackedIntsError {
+        BlockCount { expected: usize, found: usize },
+        InvalidVarint { index: usize },
+        BlockMismatch { block: usize },
+        TrailingBytes { count: usize },
+    }
+
+    impl fmt::Display for PackedIntsError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                PackedIntsError::BlockCount { expected, found } => write!(
+                    f,
+                    "packed integers have {} skip index entries but expected \
+                     {}",
+                    found, expected,
+                ),
+                PackedIntsError::InvalidVarint { index } => write!(
+                    f,
+                    "varint for value {} is out of bounds or too long",
+                    index,
+                ),
+                PackedIntsError::BlockMismatch { block } => write!(
+                    f,
+                    "skip index entry {} does not match the encoded values",
+                    block,
+                ),
+                PackedIntsError::TrailingBytes { count } => write!(
+                    f,
+                    "{} bytes remain after the last packed integer",
+                    count,
+                ),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for PackedIntsError {}
+
+    unsafe impl<T, C, const DELTA: bool> Verify<C> for ArchivedPackedInts<T, DELTA>
+    where
+        T: PackedInt,
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+            let expected = self.len().div_ceil(PACKED_BLOCK_LEN);
+            if self.blocks().len() != expected {
+                fail!(PackedIntsError::BlockCount {
+                    expected,
+                    found: self.blocks().len(),
+                });
+            }
+
+            let bytes = self.encoded_bytes();
+            let mut cursor = Cursor::<T, DELTA>::new(0, 0);
+            for index in 0..self.len() {
+                if index % PACKED_BLOCK_LEN == 0 {
+                    let block = index / PACKED_BLOCK_LEN;
+                    let entry = &self.blocks()[block];
+                    if entry.offset() != cursor.pos
+                        || entry.prev() != cursor.prev
+                    {
+                        fail!(PackedIntsError::BlockMismatch { block });
+                    }
+                }
+                if cursor.decode(bytes).is_none() {
+                    fail!(PackedIntsError::InvalidVarint { index });
+                }
+            }
+            if cursor.pos != bytes.len() {
+                fail!(PackedIntsError::TrailingBytes {
+                    count: bytes.len() - cursor.pos,
+                });
+            }
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use core::{fmt, iter::FusedIterator, slice};
+
+use rancor::Fallible;
+
+use crate::{
+    primitive::ArchivedUsize,
+    ser::{Allocator, Writer},
+    vec::{ArchivedVec, VecResolver},
+    Archive, Portable, Serialize,
+};
+
+/// A run of equal values in an [`ArchivedRunLength`].
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(feature = "bytecheck", derive(bytecheck::CheckBytes))]
+pub struct ArchivedRun<T> {
+    value: T,
+    count: ArchivedUsize,
+}
+
+impl<T> ArchivedRun<T> {
+    /// Returns the value which is repeated in the run.
+    #[inline]
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the number of times that the value is repeated.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count.to_native() as usize
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedRun<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchivedRun")
+            .field("value", &self.value)
+            .field("count", &self.count())
+            .finish()
+    }
+}
+
+/// An archived vector which stores runs of equal values once.
+///
+/// This is the archived form of a `Vec<T>` serialized with
+/// [`RunLength`](crate::with::RunLength). Each run of consecutive equal
+/// elements is archived as a single value and the length of the run. Elements
+/// are expanded lazily by [`iter`](ArchivedRunLength::iter), and
+/// [`get`](ArchivedRunLength::get) walks the runs, so random access takes time
+/// proportional to the number of runs.
+#[derive(Portable)]
+#[archive(crate)]
+#[repr(C)]
+#[cfg_attr(
+    feature = "bytecheck",
+    derive(bytecheck::CheckBytes),
+    check_bytes(verify)
+)]
+pub struct ArchivedRunLength<T> {
+    runs: ArchivedVec<ArchivedRun<T>>,
+    len: ArchivedUsize,
+}
+
+impl<T> ArchivedRunLength<T> {
+    /// Returns the number of elements, counting every element of every run.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.to_native() as usize
+    }
+
+    /// Returns whether there are no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the runs of elements.
+    #[inline]
+    pub fn runs(&self) -> &[ArchivedRun<T>] {
+        self.runs.as_slice()
+    }
+
+    /// Returns the element at the given index, or `None` if the index is out
+    /// of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let mut start = 0;
+        for run in self.runs() {
+            start += run.count();
+            if index < start {
+                return Some(run.value());
+            }
+        }
+        None
+    }
+
+    /// Returns an iterator over the elements.
+    #[inline]
+    pub fn iter(&self) -> RunLengthIter<'_, T> {
+        RunLengthIter {
+            runs: self.runs().iter(),
+            current: None,
+            remaining: self.len(),
+        }
+    }
+
+    /// Resolves an archived run-length vector from the length of the original
+    /// slice.
+    ///
+    /// # Safety
+    ///
+    /// - `pos` must be the position of `out` within the archive
+    /// - `resolver` must be the result of serializing a slice of `len`
+    ///   elements with [`serialize_from_slice`](Self::serialize_from_slice)
+    #[inline]
+    pub unsafe fn resolve_from_len(
+        len: usize,
+        pos: usize,
+        resolver: RunLengthResolver,
+        out: *mut Self,
+    ) {
+        let (fp, fo) = out_field!(out.runs);
+        ArchivedVec::resolve_from_len(
+            resolver.runs_len,
+            pos + fp,
+            resolver.runs,
+            fo,
+        );
+        let (fp, fo) = out_field!(out.len);
+        usize::resolve(&len, pos + fp, (), fo);
+    }
+
+    /// Serializes the runs of equal elements in a slice.
+    pub fn serialize_from_slice<U, S>(
+        slice: &[U],
+        serializer: &mut S,
+    ) -> Result<RunLengthResolver, S::Error>
+    where
+        U: Serialize<S, Archived = T> + Eq,
+        S: Fallible + Allocator + Writer + ?Sized,
+    {
+        let runs = Runs::new(slice);
+        let runs_len = runs.len();
+        Ok(RunLengthResolver {
+            runs: ArchivedVec::serialize_from_iter::<RunRef<'_, U>, _, _>(
+                runs, serializer,
+            )?,
+            runs_len,
+        })
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedRunLength<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArchivedRunLength<T> {
+    type Item = &'a T;
+    type IntoIter = RunLengthIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<[U]> for ArchivedRunLength<T> {
+    fn eq(&self, other: &[U]) -> bool {
+        self.len() == other.len()
+            && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedRunLength<T>> for [U] {
+    #[inline]
+    fn eq(&self, other: &ArchivedRunLength<T>) -> bool {
+        other.eq(self)
+    }
+}
+
+/// An iterator over the elements of an [`ArchivedRunLength`].
+pub struct RunLengthIter<'a, T> {
+    runs: slice::Iter<'a, ArchivedRun<T>>,
+    current: Option<(&'a T, usize)>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for RunLengthIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.current {
+                Some((value, ref mut count)) if *count > 0 => {
+                    *count -= 1;
+                    self.remaining -= 1;
+                    return Some(value);
+                }
+                _ => {
+                    let run = self.runs.next()?;
+                    self.current = Some((run.value(), run.count()));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for RunLengthIter<'_, T> {}
+
+impl<T> FusedIterator for RunLengthIter<'_, T> {}
+
+/// The resolver for an [`ArchivedRunLength`].
+pub struct RunLengthResolver {
+    runs: VecResolver,
+    runs_len: usize,
+}
+
+/// A run of equal elements in a slice that is being serialized.
+struct RunRef<'a, U> {
+    value: &'a U,
+    count: usize,
+}
+
+impl<U: Archive> Archive for RunRef<'_, U> {
+    type Archived = ArchivedRun<U::Archived>;
+    type Resolver = U::Resolver;
+
+    #[inline]
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        let (fp, fo) = out_field!(out.value);
+        self.value.resolve(pos + fp, resolver, fo);
+        let (fp, fo) = out_field!(out.count);
+        usize::resolve(&self.count, pos + fp, (), fo);
+    }
+}
+
+impl<U: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for RunRef<'_, U> {
+    #[inline]
+    fn serialize(&self, serializer: &mut S) -> Result<U::Resolver, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+/// An iterator over the runs of equal elements in a slice.
+struct Runs<'a, U> {
+    slice: &'a [U],
+    len: usize,
+}
+
+impl<'a, U: Eq> Runs<'a, U> {
+    fn new(slice: &'a [U]) -> Self {
+        let len = match slice.split_first() {
+            Some((_, rest)) => {
+                1 + slice.iter().zip(rest).filter(|(a, b)| a != b).count()
+            }
+            None => 0,
+        };
+        Self { slice, len }
+    }
+}
+
+impl<'a, U: Eq> Iterator for Runs<'a, U> {
+    type Item = RunRef<'a, U>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.slice.first()?;
+        let count = self
+            .slice
+            .iter()
+            .position(|x| x != value)
+            .unwrap_or(self.slice.len());
+        self.slice = &self.slice[count..];
+        self.len -= 1;
+        Some(RunRef { value, count })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<U: Eq> ExactSizeIterator for Runs<'_, U> {}
+
+#[cfg(feature = "bytecheck")]
+mod verify {
+    use core::fmt;
+
+    use bytecheck::{
+        rancor::{Error, Fallible},
+        CheckBytes, Verify,
+    };
+    use rancor::fail;
+
+    use super::ArchivedRunLength;
+    use crate::validation::ArchiveContext;
+
+    /// An error resulting from run lengths which do not add up to the length
+    /// of the vector.
+    #[derive(Debug)]
+    struct RunLengthMismatch {
+        len: usize,
+        total: Option<usize>,
+    }
+
+    impl fmt::Display for RunLengthMismatch {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.total {
+                Some(total) => write!(
+                    f,
+                    "runs contain {} elements but the length is {}",
+                    total, self.len,
+                ),
+                None => write!(
+                    f,
+                    "run lengths overflow but the length is {}",
+                    self.len,
+                ),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for RunLengthMismatch {}
+
+    unsafe impl<T, C> Verify<C> for ArchivedRunLength<T>
+    where
+        T: CheckBytes<C>,
+        C: Fallible + ArchiveContext + ?Sized,
+        C::Error: Error,
+    {
+        fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+            let total = self
+                .runs()
+                .iter()
+                .try_fold(0usize, |total, run| total.checked_add(run.count()));
+            if total != Some(self.len()) {
+                fail!(RunLengthMismatch {
+                    len: self.len(),
+                    total,
+                });
+            }
+            Ok(())
+        }
+    }
+}
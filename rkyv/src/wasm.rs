@@ -0,0 +1,204 @@
+//! Helpers for accessing archives in WebAssembly environments.
+//!
+//! Byte buffers handed to WebAssembly from JavaScript (for example, the
+//! contents of a `Uint8Array`) may be located at any alignment. These helpers
+//! avoid copying those buffers when they happen to be sufficiently aligned.
+
+use core::{fmt, marker::PhantomData, ops::Deref};
+
+use bytecheck::CheckBytes;
+use rancor::{Error, Strategy};
+
+use crate::{
+    access_unchecked,
+    util::AlignedVec,
+    validation::{util::access, validators::DefaultValidator},
+    Archive, Portable,
+};
+
+enum Bytes<'a> {
+    Borrowed(&'a [u8]),
+    Owned(AlignedVec),
+}
+
+/// A reference to a validated archived value that either borrows or owns its
+/// bytes.
+///
+/// This is returned by [`access_aligned_or_copy`] and dereferences to the
+/// archived root value.
+pub struct ArchiveRef<'a, T: Archive> {
+    bytes: Bytes<'a>,
+    _phantom: PhantomData<&'a T::Archived>,
+}
+
+impl<'a, T: Archive> ArchiveRef<'a, T> {
+    /// Returns whether the archived value borrows the original bytes.
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.bytes, Bytes::Borrowed(_))
+    }
+
+    /// Returns the bytes that the archived value is located in.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self.bytes {
+            Bytes::Borrowed(bytes) => bytes,
+            Bytes::Owned(ref vec) => vec.as_slice(),
+        }
+    }
+
+    /// Returns the owned bytes of the archived value if they were copied, or
+    /// `None` if they were borrowed.
+    #[inline]
+    pub fn into_owned_bytes(self) -> Option<AlignedVec> {
+        match self.bytes {
+            Bytes::Borrowed(_) => None,
+            Bytes::Owned(vec) => Some(vec),
+        }
+    }
+}
+
+impl<T: Archive> Deref for ArchiveRef<'_, T> {
+    type Target = T::Archived;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The bytes were validated to contain a `T::Archived` at the
+        // end when this `ArchiveRef` was created, and they have not changed
+        // since then.
+        unsafe { access_unchecked::<T::Archived>(self.as_bytes()) }
+    }
+}
+
+impl<T: Archive> AsRef<T::Archived> for ArchiveRef<'_, T> {
+    #[inline]
+    fn as_ref(&self) -> &T::Archived {
+        self
+    }
+}
+
+impl<T: Archive> fmt::Debug for ArchiveRef<'_, T>
+where
+    T::Archived: fmt::Debug,
+{
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// Accesses an archived value from the given bytes, copying them into an
+/// [`AlignedVec`] only if they are not sufficiently aligned.
+///
+/// The bytes are borrowed if they are aligned to
+/// [`AlignedVec::ALIGNMENT`]. This is the same alignment that serializers
+/// assume their output has, so every object in the archive is located exactly
+/// where it would be if the bytes were copied. Validation is performed after
+/// the copy (if any) and accepts or rejects the same archives in both cases.
+///
+/// # Examples
+/// ```
+/// use rkyv::{rancor::Failure, to_bytes, wasm::access_aligned_or_copy};
+///
+/// let bytes = to_bytes::<_, 256, Failure>(&vec![1, 2, 3, 4]).unwrap();
+///
+/// // `AlignedVec` is always sufficiently aligned, so this borrows.
+/// let archived =
+///     access_aligned_or_copy::<Vec<i32>, Failure>(bytes.as_slice()).unwrap();
+/// assert!(archived.is_borrowed());
+/// assert_eq!(archived.len(), 4);
+/// assert_eq!(archived[0], 1);
+/// ```
+pub fn access_aligned_or_copy<T, E>(
+    bytes: &[u8],
+) -> Result<ArchiveRef<'_, T>, E>
+where
+    T: Archive,
+    T::Archived: Portable + CheckBytes<Strategy<DefaultValidator, E>>,
+    E: Error,
+{
+    let bytes = if bytes.as_ptr().align_offset(AlignedVec::ALIGNMENT) == 0 {
+        Bytes::Borrowed(bytes)
+    } else {
+        Bytes::Owned(AlignedVec::from_slice_min_copy(bytes))
+    };
+
+    let result = ArchiveRef {
+        bytes,
+        _phantom: PhantomData,
+    };
+    access::<T::Archived, E>(result.as_bytes())?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec, vec::Vec};
+    use core::mem::size_of;
+
+    use rancor::Failure;
+
+    use super::access_aligned_or_copy;
+    use crate::{to_bytes, util::AlignedVec, vec::ArchivedVec, Archived};
+
+    fn offset_copy(bytes: &[u8]) -> AlignedVec {
+        let mut result = AlignedVec::with_capacity(bytes.len() + 1);
+        result.push(0);
+        result.extend_from_slice(bytes);
+        result
+    }
+
+    #[test]
+    fn borrows_aligned_bytes() {
+        let value = vec![String::from("hello"), String::from("world")];
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let archived =
+            access_aligned_or_copy::<Vec<String>, Failure>(bytes.as_slice())
+                .unwrap();
+        assert!(archived.is_borrowed());
+        assert_eq!(archived.as_bytes().as_ptr(), bytes.as_ptr());
+        assert_eq!(archived.len(), 2);
+        assert_eq!(archived[0], "hello");
+        assert_eq!(archived[1], "world");
+    }
+
+    #[test]
+    fn copies_unaligned_bytes() {
+        let value = vec![String::from("hello"), String::from("world")];
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let offset = offset_copy(bytes.as_slice());
+
+        let archived =
+            access_aligned_or_copy::<Vec<String>, Failure>(&offset[1..])
+                .unwrap();
+        assert!(!archived.is_borrowed());
+        assert_eq!(archived.as_bytes(), bytes.as_slice());
+        assert_eq!(archived.len(), 2);
+        assert_eq!(archived[0], "hello");
+        assert_eq!(archived[1], "world");
+    }
+
+    #[test]
+    fn validates_both_branches() {
+        let value = vec![1u32, 2, 3, 4];
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        // Corrupt the pointer and length of the archived vec
+        let len = bytes.len();
+        let root_size = size_of::<ArchivedVec<Archived<u32>>>();
+        bytes[len - root_size..].fill(0xff);
+
+        assert!(
+            access_aligned_or_copy::<Vec<u32>, Failure>(bytes.as_slice())
+                .is_err()
+        );
+
+        let offset = offset_copy(bytes.as_slice());
+        assert!(
+            access_aligned_or_copy::<Vec<u32>, Failure>(&offset[1..]).is_err()
+        );
+    }
+}
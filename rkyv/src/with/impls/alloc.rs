@@ -3,28 +3,52 @@ use alloc::{
     borrow::Cow,
     boxed::Box,
     collections::{BTreeMap, BTreeSet},
+    rc,
+    string::{String, ToString},
+    sync::{self, Arc},
     vec::Vec,
 };
-use core::marker::PhantomData;
+use core::{hash::Hash, marker::PhantomData};
 #[cfg(feature = "std")]
 use std::{
     borrow::Cow,
     boxed::Box,
     collections::{BTreeMap, BTreeSet},
+    rc,
+    sync::{self, Arc},
 };
 
-use rancor::Fallible;
+use rancor::{fail, Error, Fallible, Trace};
 
 use crate::{
     boxed::{ArchivedBox, BoxResolver},
-    collections::util::Entry,
+    chain::{drop_chain, iter_chain, ArchivedChain, ChainLink, ChainResolver},
+    collections::{
+        btree_map::{ArchivedBTreeMap, BTreeMapResolver},
+        matrix::{ArchivedMatrix, MatrixResolver, MatrixShapeError},
+        util::Entry,
+    },
+    columnar::{ArchiveColumns, DeserializeColumns, SerializeColumns},
+    compact::{
+        ArchivedCompactBox, ArchivedCompactString, ArchivedCompactVec,
+        CompactResolver,
+    },
+    de::{AllocationLimiter, BlobSource, Interning, Pooling},
+    external::{ArchivedExternal, BlobHandle, InvalidBlobUtf8},
     niche::option_box::{ArchivedOptionBox, OptionBoxResolver},
-    ser::{Allocator, Writer},
-    string::{ArchivedString, StringResolver},
-    vec::{ArchivedVec, VecResolver},
+    rc::{ArcFlavor, ArchivedOptionRcWeak, OptionRcWeakResolver, RcFlavor},
+    ser::{Allocator, BlobSink, InternTable, Sharing, Writer, WriterExt as _},
+    string::{repr::INLINE_CAPACITY, ArchivedString, StringResolver},
+    vec::{
+        ArchivedAlignedBytes, ArchivedDenseStrings, ArchivedPackedInts,
+        ArchivedRunLength, ArchivedVec, PackedInt, PackedIntsResolver,
+        RunLengthResolver, VecResolver,
+    },
     with::{
-        ArchiveWith, AsOwned, AsVec, BoxedInline, CopyOptimize,
-        DeserializeWith, Map, Niche, SerializeWith, With,
+        AlignTo, ArchiveWith, AsColumns, AsMatrix, AsOwned, AsVec, BoxedInline,
+        Compact, CopyOptimize, DeltaVarint, DenseStrings, DeserializeWith,
+        External, Intern, InternedBytes, InternedString, IterateChain, Map,
+        MapKV, Niche, RunLength, SerializeWith, Varint, WeakTombstone, With,
     },
     Archive, ArchiveUnsized, ArchivedMetadata, Deserialize, DeserializeUnsized,
     Serialize, SerializeUnsized,
@@ -113,6 +137,148 @@ where
     }
 }
 
+// MapKV for Vecs of pairs
+
+impl<K, V, KW, VW> ArchiveWith<Vec<(K, V)>> for MapKV<KW, VW>
+where
+    KW: ArchiveWith<K>,
+    VW: ArchiveWith<V>,
+{
+    type Archived = ArchivedVec<<Self as ArchiveWith<(K, V)>>::Archived>;
+    type Resolver = VecResolver;
+
+    unsafe fn resolve_with(
+        field: &Vec<(K, V)>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_len(field.len(), pos, resolver, out)
+    }
+}
+
+impl<K, V, KW, VW, S> SerializeWith<Vec<(K, V)>, S> for MapKV<KW, VW>
+where
+    KW: SerializeWith<K, S>,
+    VW: SerializeWith<V, S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &Vec<(K, V)>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::<<Self as ArchiveWith<(K, V)>>::Archived>::serialize_from_iter::<
+            With<(K, V), Self>,
+            _,
+            _,
+        >(field.iter().map(With::cast), serializer)
+    }
+}
+
+impl<K, V, KW, VW, D>
+    DeserializeWith<
+        ArchivedVec<<Self as ArchiveWith<(K, V)>>::Archived>,
+        Vec<(K, V)>,
+        D,
+    > for MapKV<KW, VW>
+where
+    KW: ArchiveWith<K>
+        + DeserializeWith<<KW as ArchiveWith<K>>::Archived, K, D>,
+    VW: ArchiveWith<V>
+        + DeserializeWith<<VW as ArchiveWith<V>>::Archived, V, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedVec<<Self as ArchiveWith<(K, V)>>::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Vec<(K, V)>, D::Error> {
+        field
+            .iter()
+            .map(|pair| Self::deserialize_with(pair, deserializer))
+            .collect()
+    }
+}
+
+// MapKV for BTreeMaps
+
+impl<K, V, KW, VW> ArchiveWith<BTreeMap<K, V>> for MapKV<KW, VW>
+where
+    KW: ArchiveWith<K>,
+    KW::Archived: Ord,
+    VW: ArchiveWith<V>,
+{
+    type Archived = ArchivedBTreeMap<KW::Archived, VW::Archived>;
+    type Resolver = BTreeMapResolver;
+
+    unsafe fn resolve_with(
+        field: &BTreeMap<K, V>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedBTreeMap::resolve_from_len(field.len(), pos, resolver, out);
+    }
+}
+
+impl<K, V, KW, VW, S> SerializeWith<BTreeMap<K, V>, S> for MapKV<KW, VW>
+where
+    KW: SerializeWith<K, S>,
+    KW::Archived: Ord,
+    VW: SerializeWith<V, S>,
+    S: Fallible + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &BTreeMap<K, V>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        // Key wrappers are required to preserve the order of keys, so the
+        // archived keys are still unique and sorted.
+        unsafe {
+            ArchivedBTreeMap::serialize_from_reverse_iter(
+                field.iter().rev().map(|(key, value)| {
+                    (With::<K, KW>::cast(key), With::<V, VW>::cast(value))
+                }),
+                serializer,
+            )
+        }
+    }
+}
+
+impl<K, V, KW, VW, D>
+    DeserializeWith<
+        ArchivedBTreeMap<
+            <KW as ArchiveWith<K>>::Archived,
+            <VW as ArchiveWith<V>>::Archived,
+        >,
+        BTreeMap<K, V>,
+        D,
+    > for MapKV<KW, VW>
+where
+    K: Ord,
+    KW: ArchiveWith<K>
+        + DeserializeWith<<KW as ArchiveWith<K>>::Archived, K, D>,
+    VW: ArchiveWith<V>
+        + DeserializeWith<<VW as ArchiveWith<V>>::Archived, V, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedBTreeMap<
+            <KW as ArchiveWith<K>>::Archived,
+            <VW as ArchiveWith<V>>::Archived,
+        >,
+        deserializer: &mut D,
+    ) -> Result<BTreeMap<K, V>, D::Error> {
+        let mut result = BTreeMap::new();
+        for (key, value) in field.iter() {
+            result.insert(
+                KW::deserialize_with(key, deserializer)?,
+                VW::deserialize_with(value, deserializer)?,
+            );
+        }
+        Ok(result)
+    }
+}
+
 // AsOwned
 
 impl<'a, F: Archive + Clone> ArchiveWith<Cow<'a, F>> for AsOwned {
@@ -193,7 +359,8 @@ impl<'a, T, D> DeserializeWith<ArchivedVec<T::Archived>, Cow<'a, [T]>, D>
 where
     T: Archive + Clone,
     T::Archived: Deserialize<T, D>,
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
+    D::Error: Trace,
 {
     #[inline]
     fn deserialize_with(
@@ -234,7 +401,7 @@ where
 
 impl<'a, D> DeserializeWith<ArchivedString, Cow<'a, str>, D> for AsOwned
 where
-    D: Fallible + ?Sized,
+    D: Fallible + AllocationLimiter + ?Sized,
 {
     #[inline]
     fn deserialize_with(
@@ -278,8 +445,9 @@ const _: () = {
         }
     }
 
-    impl<'a, D: Fallible + ?Sized>
-        DeserializeWith<ArchivedCString, Cow<'a, CStr>, D> for AsOwned
+    impl<'a, D> DeserializeWith<ArchivedCString, Cow<'a, CStr>, D> for AsOwned
+    where
+        D: Fallible + AllocationLimiter + ?Sized,
     {
         #[inline]
         fn deserialize_with(
@@ -457,6 +625,274 @@ where
     }
 }
 
+// AlignTo
+
+impl<const ALIGN: usize> AlignTo<ALIGN> {
+    const ASSERT_POWER_OF_TWO: () = assert!(
+        ALIGN.is_power_of_two(),
+        "the alignment of AlignTo must be a power of two",
+    );
+
+    fn serialize_bytes<S>(
+        bytes: &[u8],
+        serializer: &mut S,
+    ) -> Result<VecResolver, S::Error>
+    where
+        S: Fallible + Writer + ?Sized,
+    {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ASSERT_POWER_OF_TWO;
+
+        serializer.align(ALIGN)?;
+        let pos = serializer.pos();
+        serializer.write(bytes)?;
+        Ok(VecResolver::from_pos(pos))
+    }
+}
+
+impl<const ALIGN: usize> ArchiveWith<Vec<u8>> for AlignTo<ALIGN> {
+    type Archived = ArchivedAlignedBytes<ALIGN>;
+    type Resolver = VecResolver;
+
+    unsafe fn resolve_with(
+        field: &Vec<u8>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedAlignedBytes::resolve_from_len(field.len(), pos, resolver, out);
+    }
+}
+
+impl<S, const ALIGN: usize> SerializeWith<Vec<u8>, S> for AlignTo<ALIGN>
+where
+    S: Fallible + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &Vec<u8>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        Self::serialize_bytes(field, serializer)
+    }
+}
+
+impl<D, const ALIGN: usize>
+    DeserializeWith<ArchivedAlignedBytes<ALIGN>, Vec<u8>, D> for AlignTo<ALIGN>
+where
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedAlignedBytes<ALIGN>,
+        _: &mut D,
+    ) -> Result<Vec<u8>, D::Error> {
+        Ok(field.as_slice().to_vec())
+    }
+}
+
+impl<const ALIGN: usize> ArchiveWith<Box<[u8]>> for AlignTo<ALIGN> {
+    type Archived = ArchivedAlignedBytes<ALIGN>;
+    type Resolver = VecResolver;
+
+    unsafe fn resolve_with(
+        field: &Box<[u8]>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedAlignedBytes::resolve_from_len(field.len(), pos, resolver, out);
+    }
+}
+
+impl<S, const ALIGN: usize> SerializeWith<Box<[u8]>, S> for AlignTo<ALIGN>
+where
+    S: Fallible + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &Box<[u8]>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        Self::serialize_bytes(field, serializer)
+    }
+}
+
+impl<D, const ALIGN: usize>
+    DeserializeWith<ArchivedAlignedBytes<ALIGN>, Box<[u8]>, D>
+    for AlignTo<ALIGN>
+where
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedAlignedBytes<ALIGN>,
+        _: &mut D,
+    ) -> Result<Box<[u8]>, D::Error> {
+        Ok(field.as_slice().into())
+    }
+}
+
+// DenseStrings
+
+impl ArchiveWith<Vec<String>> for DenseStrings {
+    type Archived = ArchivedDenseStrings;
+    type Resolver = VecResolver;
+
+    unsafe fn resolve_with(
+        field: &Vec<String>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedDenseStrings::resolve_from_len(field.len(), pos, resolver, out);
+    }
+}
+
+impl<S> SerializeWith<Vec<String>, S> for DenseStrings
+where
+    S: Fallible + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &Vec<String>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        // The out-of-line strings are written back-to-back, so their positions
+        // can be recovered from their lengths without storing resolvers.
+        let mut data_pos = serializer.pos();
+        for string in field.iter().filter(|s| s.len() > INLINE_CAPACITY) {
+            serializer.write(string.as_bytes())?;
+        }
+
+        let pos = serializer.align_for::<ArchivedString>()?;
+        for string in field.iter() {
+            let resolver = if string.len() > INLINE_CAPACITY {
+                data_pos += string.len();
+                StringResolver::from_pos(data_pos - string.len())
+            } else {
+                StringResolver::from_pos(0)
+            };
+            unsafe {
+                serializer.resolve_aligned(string, resolver)?;
+            }
+        }
+
+        Ok(VecResolver::from_pos(pos))
+    }
+}
+
+impl<D> DeserializeWith<ArchivedDenseStrings, Vec<String>, D> for DenseStrings
+where
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedDenseStrings,
+        _: &mut D,
+    ) -> Result<Vec<String>, D::Error> {
+        Ok(field.iter().map(|s| s.as_str().to_string()).collect())
+    }
+}
+
+// RunLength
+
+impl<T: Archive> ArchiveWith<Vec<T>> for RunLength {
+    type Archived = ArchivedRunLength<T::Archived>;
+    type Resolver = RunLengthResolver;
+
+    unsafe fn resolve_with(
+        field: &Vec<T>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedRunLength::resolve_from_len(field.len(), pos, resolver, out);
+    }
+}
+
+impl<T, S> SerializeWith<Vec<T>, S> for RunLength
+where
+    T: Serialize<S> + Eq,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &Vec<T>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedRunLength::serialize_from_slice(field, serializer)
+    }
+}
+
+impl<T, D> DeserializeWith<ArchivedRunLength<T::Archived>, Vec<T>, D>
+    for RunLength
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedRunLength<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Vec<T>, D::Error> {
+        field
+            .iter()
+            .map(|value| value.deserialize(deserializer))
+            .collect()
+    }
+}
+
+// Varint and DeltaVarint
+
+macro_rules! impl_packed_ints {
+    ($wrapper:ident, $delta:literal) => {
+        impl<T: PackedInt> ArchiveWith<Vec<T>> for $wrapper {
+            type Archived = ArchivedPackedInts<T, $delta>;
+            type Resolver = PackedIntsResolver;
+
+            unsafe fn resolve_with(
+                field: &Vec<T>,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedPackedInts::resolve_from_len(
+                    field.len(),
+                    pos,
+                    resolver,
+                    out,
+                );
+            }
+        }
+
+        impl<T, S> SerializeWith<Vec<T>, S> for $wrapper
+        where
+            T: PackedInt,
+            S: Fallible + Writer + ?Sized,
+        {
+            fn serialize_with(
+                field: &Vec<T>,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedPackedInts::<T, $delta>::serialize_from_slice(
+                    field, serializer,
+                )
+            }
+        }
+
+        impl<T, D> DeserializeWith<ArchivedPackedInts<T, $delta>, Vec<T>, D>
+            for $wrapper
+        where
+            T: PackedInt,
+            D: Fallible + ?Sized,
+        {
+            fn deserialize_with(
+                field: &ArchivedPackedInts<T, $delta>,
+                _: &mut D,
+            ) -> Result<Vec<T>, D::Error> {
+                Ok(field.iter().collect())
+            }
+        }
+    };
+}
+
+impl_packed_ints!(Varint, false);
+impl_packed_ints!(DeltaVarint, true);
+
 // CopyOptimize
 
 impl<T: Archive> ArchiveWith<Vec<T>> for CopyOptimize {
@@ -625,25 +1061,419 @@ where
     }
 }
 
-// // Raw
+// InternedString
 
-// impl<T: Archive> ArchiveWith<Vec<T>> for Raw {
-//     type Archived = RawArchivedVec<T::Archived>;
-//     type Resolver = VecResolver;
+impl ArchiveWith<Arc<str>> for InternedString {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
 
-//     unsafe fn resolve_with(
-//         field: &Vec<T>,
-//         pos: usize,
-//         resolver: Self::Resolver,
-//         out: *mut Self::Archived,
-//     ) {
-//         RawArchivedVec::resolve_from_slice(
-//             field.as_slice(),
-//             pos,
-//             resolver,
-//             out,
-//         );
-//     }
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Arc<str>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedString::resolve_from_str(field, pos, resolver, out);
+    }
+}
+
+impl<S> SerializeWith<Arc<str>, S> for InternedString
+where
+    str: SerializeUnsized<S>,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize_with(
+        field: &Arc<str>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedString::serialize_from_str(field, serializer)
+    }
+}
+
+impl<D> DeserializeWith<ArchivedString, Arc<str>, D> for InternedString
+where
+    D: Fallible + Interning + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedString,
+        deserializer: &mut D,
+    ) -> Result<Arc<str>, D::Error> {
+        Ok(deserializer.intern_str(field.as_str()))
+    }
+}
+
+// InternedBytes
+
+impl ArchiveWith<Arc<[u8]>> for InternedBytes {
+    type Archived = ArchivedVec<u8>;
+    type Resolver = VecResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Arc<[u8]>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_slice(field, pos, resolver, out);
+    }
+}
+
+impl<S> SerializeWith<Arc<[u8]>, S> for InternedBytes
+where
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    #[inline]
+    fn serialize_with(
+        field: &Arc<[u8]>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::serialize_from_slice(field, serializer)
+    }
+}
+
+impl<D> DeserializeWith<ArchivedVec<u8>, Arc<[u8]>, D> for InternedBytes
+where
+    D: Fallible + Interning + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedVec<u8>,
+        deserializer: &mut D,
+    ) -> Result<Arc<[u8]>, D::Error> {
+        Ok(deserializer.intern_bytes(field.as_slice()))
+    }
+}
+
+// Intern
+
+impl ArchiveWith<String> for Intern {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &String,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedString::resolve_from_str(field, pos, resolver, out);
+    }
+}
+
+impl<S> SerializeWith<String, S> for Intern
+where
+    str: SerializeUnsized<S>,
+    S: Fallible + InternTable + ?Sized,
+{
+    fn serialize_with(
+        field: &String,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        if field.len() <= INLINE_CAPACITY {
+            ArchivedString::serialize_from_str(field, serializer)
+        } else if let Some(pos) = serializer.get_interned(field) {
+            Ok(StringResolver::from_pos(pos))
+        } else {
+            let pos = field.as_str().serialize_unsized(serializer)?;
+            serializer.add_interned(field, pos)?;
+            Ok(StringResolver::from_pos(pos))
+        }
+    }
+}
+
+impl<D> DeserializeWith<ArchivedString, String, D> for Intern
+where
+    D: Fallible + ?Sized,
+    ArchivedString: Deserialize<String, D>,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedString,
+        deserializer: &mut D,
+    ) -> Result<String, D::Error> {
+        field.deserialize(deserializer)
+    }
+}
+
+impl ArchiveWith<Vec<u8>> for Intern {
+    type Archived = ArchivedVec<u8>;
+    type Resolver = VecResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Vec<u8>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_slice(field, pos, resolver, out);
+    }
+}
+
+impl<S> SerializeWith<Vec<u8>, S> for Intern
+where
+    [u8]: SerializeUnsized<S>,
+    S: Fallible + InternTable + ?Sized,
+{
+    fn serialize_with(
+        field: &Vec<u8>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        if let Some(pos) = serializer.get_interned(field) {
+            Ok(VecResolver::from_pos(pos))
+        } else {
+            let pos = field.as_slice().serialize_unsized(serializer)?;
+            serializer.add_interned(field, pos)?;
+            Ok(VecResolver::from_pos(pos))
+        }
+    }
+}
+
+impl<D> DeserializeWith<ArchivedVec<u8>, Vec<u8>, D> for Intern
+where
+    D: Fallible + ?Sized,
+    ArchivedVec<u8>: Deserialize<Vec<u8>, D>,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedVec<u8>,
+        deserializer: &mut D,
+    ) -> Result<Vec<u8>, D::Error> {
+        field.deserialize(deserializer)
+    }
+}
+
+impl<T: Archive> ArchiveWith<Box<T>> for Intern {
+    type Archived = ArchivedBox<T::Archived>;
+    type Resolver = BoxResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Box<T>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedBox::resolve_from_ref(field.as_ref(), pos, resolver, out);
+    }
+}
+
+impl<T, S> SerializeWith<Box<T>, S> for Intern
+where
+    T: Serialize<S> + Clone + Hash + Eq + 'static,
+    S: Fallible + InternTable + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &Box<T>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        let value = field.as_ref();
+        if let Some(pos) = serializer.get_interned(value) {
+            Ok(BoxResolver::from_pos(pos))
+        } else {
+            let pos = value.serialize_unsized(serializer)?;
+            serializer.add_interned(value, pos)?;
+            Ok(BoxResolver::from_pos(pos))
+        }
+    }
+}
+
+impl<T, D> DeserializeWith<ArchivedBox<T::Archived>, Box<T>, D> for Intern
+where
+    T: Archive,
+    ArchivedBox<T::Archived>: Deserialize<Box<T>, D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedBox<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Box<T>, D::Error> {
+        field.deserialize(deserializer)
+    }
+}
+
+// Compact
+
+impl<T: Archive> ArchiveWith<Vec<T>> for Compact {
+    type Archived = ArchivedCompactVec<T::Archived>;
+    type Resolver = CompactResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Vec<T>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedCompactVec::resolve_from_len(field.len(), pos, resolver, out);
+    }
+}
+
+impl<T, S> SerializeWith<Vec<T>, S> for Compact
+where
+    T: Serialize<S>,
+    S: Fallible + Writer + ?Sized,
+    S::Error: Error,
+    [T]: SerializeUnsized<S>,
+{
+    #[inline]
+    fn serialize_with(
+        field: &Vec<T>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedCompactVec::serialize_from_slice(field.as_slice(), serializer)
+    }
+}
+
+impl<T, D> DeserializeWith<ArchivedCompactVec<T::Archived>, Vec<T>, D>
+    for Compact
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedCompactVec<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Vec<T>, D::Error> {
+        let mut result = Vec::with_capacity(field.len());
+        for value in field.iter() {
+            result.push(value.deserialize(deserializer)?);
+        }
+        Ok(result)
+    }
+}
+
+impl ArchiveWith<String> for Compact {
+    type Archived = ArchivedCompactString;
+    type Resolver = CompactResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &String,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedCompactString::resolve_from_str(field, pos, resolver, out);
+    }
+}
+
+impl<S> SerializeWith<String, S> for Compact
+where
+    S: Fallible + Writer + ?Sized,
+    S::Error: Error,
+    str: SerializeUnsized<S>,
+{
+    #[inline]
+    fn serialize_with(
+        field: &String,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedCompactString::serialize_from_str(field, serializer)
+    }
+}
+
+impl<D> DeserializeWith<ArchivedCompactString, String, D> for Compact
+where
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedCompactString,
+        _: &mut D,
+    ) -> Result<String, D::Error> {
+        Ok(field.as_str().to_string())
+    }
+}
+
+impl<T: ArchiveUnsized + ?Sized> ArchiveWith<Box<T>> for Compact {
+    type Archived = ArchivedCompactBox<T::Archived>;
+    type Resolver = CompactResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Box<T>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedCompactBox::resolve_from_ref(
+            field.as_ref(),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<T, S> SerializeWith<Box<T>, S> for Compact
+where
+    T: SerializeUnsized<S> + ?Sized,
+    S: Fallible + Writer + ?Sized,
+    S::Error: Error,
+{
+    #[inline]
+    fn serialize_with(
+        field: &Box<T>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedCompactBox::serialize_from_ref(field.as_ref(), serializer)
+    }
+}
+
+impl<T, D> DeserializeWith<ArchivedCompactBox<T::Archived>, Box<T>, D>
+    for Compact
+where
+    T: ArchiveUnsized + ?Sized,
+    T::Archived: DeserializeUnsized<T, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedCompactBox<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Box<T>, D::Error> {
+        #[cfg(not(feature = "std"))]
+        use ::alloc::alloc::alloc;
+        #[cfg(feature = "std")]
+        use ::std::alloc::alloc;
+
+        unsafe {
+            let data_address = field
+                .get()
+                .deserialize_unsized(deserializer, |layout| alloc(layout))?;
+            let metadata = field.get().deserialize_metadata(deserializer)?;
+            let ptr = ptr_meta::from_raw_parts_mut(data_address, metadata);
+            Ok(Box::from_raw(ptr))
+        }
+    }
+}
+
+// // Raw
+
+// impl<T: Archive> ArchiveWith<Vec<T>> for Raw {
+//     type Archived = RawArchivedVec<T::Archived>;
+//     type Resolver = VecResolver;
+
+//     unsafe fn resolve_with(
+//         field: &Vec<T>,
+//         pos: usize,
+//         resolver: Self::Resolver,
+//         out: *mut Self::Archived,
+//     ) {
+//         RawArchivedVec::resolve_from_slice(
+//             field.as_slice(),
+//             pos,
+//             resolver,
+//             out,
+//         );
+//     }
 // }
 
 // impl<T, S> SerializeWith<Vec<T>, S> for Raw
@@ -694,3 +1524,347 @@ where
 //         Ok(result)
 //     }
 // }
+
+// WeakTombstone
+
+macro_rules! impl_weak_tombstone {
+    ($rc:ident::$rc_ty:ident, $flavor:ty) => {
+        impl<T: ArchiveUnsized + ?Sized> ArchiveWith<Option<$rc::Weak<T>>>
+            for WeakTombstone
+        {
+            type Archived = ArchivedOptionRcWeak<T::Archived, $flavor>;
+            type Resolver = OptionRcWeakResolver;
+
+            #[inline]
+            unsafe fn resolve_with(
+                field: &Option<$rc::Weak<T>>,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                let upgraded = field.as_ref().and_then(|weak| weak.upgrade());
+                ArchivedOptionRcWeak::resolve_from_ref(
+                    upgraded.as_deref(),
+                    pos,
+                    resolver,
+                    out,
+                );
+            }
+        }
+
+        impl<T, S> SerializeWith<Option<$rc::Weak<T>>, S> for WeakTombstone
+        where
+            T: SerializeUnsized<S> + ?Sized + 'static,
+            S: Fallible + Writer + Sharing + ?Sized,
+        {
+            #[inline]
+            fn serialize_with(
+                field: &Option<$rc::Weak<T>>,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                let upgraded = field.as_ref().and_then(|weak| weak.upgrade());
+                ArchivedOptionRcWeak::<T::Archived, $flavor>::serialize_from_ref(
+                    field.is_some(),
+                    upgraded.as_deref(),
+                    serializer,
+                )
+            }
+        }
+
+        // Deserialize can only be implemented for sized types because weak
+        // pointers don't have from/into raw functions.
+        impl<T, D>
+            DeserializeWith<
+                ArchivedOptionRcWeak<T::Archived, $flavor>,
+                Option<$rc::Weak<T>>,
+                D,
+            > for WeakTombstone
+        where
+            T: Archive + 'static,
+            T::Archived: DeserializeUnsized<T, D>,
+            D: Fallible + Pooling + ?Sized,
+        {
+            #[inline]
+            fn deserialize_with(
+                field: &ArchivedOptionRcWeak<T::Archived, $flavor>,
+                deserializer: &mut D,
+            ) -> Result<Option<$rc::Weak<T>>, D::Error> {
+                Ok(match field {
+                    ArchivedOptionRcWeak::None => None,
+                    ArchivedOptionRcWeak::Some(r) => {
+                        let rc: $rc::$rc_ty<T> = r.deserialize(deserializer)?;
+                        Some($rc::$rc_ty::downgrade(&rc))
+                    }
+                    ArchivedOptionRcWeak::Dangling => Some($rc::Weak::new()),
+                })
+            }
+        }
+    };
+}
+
+impl_weak_tombstone!(rc::Rc, RcFlavor);
+impl_weak_tombstone!(sync::Arc, ArcFlavor);
+
+// AsColumns
+
+impl<T: ArchiveColumns> ArchiveWith<Vec<T>> for AsColumns {
+    type Archived = T::ArchivedColumns;
+    type Resolver = T::ColumnsResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Vec<T>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        T::resolve_columns(field, pos, resolver, out);
+    }
+}
+
+impl<T, S> SerializeWith<Vec<T>, S> for AsColumns
+where
+    T: SerializeColumns<S>,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize_with(
+        field: &Vec<T>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        T::serialize_columns(field, serializer)
+    }
+}
+
+impl<T, D> DeserializeWith<T::ArchivedColumns, Vec<T>, D> for AsColumns
+where
+    T: DeserializeColumns<D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &T::ArchivedColumns,
+        deserializer: &mut D,
+    ) -> Result<Vec<T>, D::Error> {
+        T::deserialize_columns(field, deserializer)
+    }
+}
+
+// AsMatrix
+
+impl<T: Archive> ArchiveWith<Vec<Vec<T>>> for AsMatrix {
+    type Archived = ArchivedMatrix<T::Archived>;
+    type Resolver = MatrixResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        _: &Vec<Vec<T>>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedMatrix::resolve_from_resolver(pos, resolver, out);
+    }
+}
+
+impl<T, S> SerializeWith<Vec<Vec<T>>, S> for AsMatrix
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+{
+    fn serialize_with(
+        field: &Vec<Vec<T>>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        let rows = field.len();
+        let cols = field.first().map_or(0, Vec::len);
+        for (row, values) in field.iter().enumerate() {
+            if values.len() != cols {
+                fail!(MatrixShapeError::Ragged {
+                    row,
+                    expected: cols,
+                    found: values.len(),
+                });
+            }
+        }
+
+        ArchivedMatrix::<T::Archived>::serialize_from_iter::<T, _, _>(
+            rows,
+            cols,
+            (0..rows * cols).map(|i| &field[i / cols][i % cols]),
+            serializer,
+        )
+    }
+}
+
+impl<T, D> DeserializeWith<ArchivedMatrix<T::Archived>, Vec<Vec<T>>, D>
+    for AsMatrix
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedMatrix<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Vec<Vec<T>>, D::Error> {
+        deserializer.check_array::<Vec<T>>(field.rows())?;
+        let mut result = Vec::with_capacity(field.rows());
+        for values in field.iter_rows() {
+            deserializer.check_array::<T>(values.len())?;
+            let mut row = Vec::with_capacity(values.len());
+            for value in values {
+                row.push(value.deserialize(deserializer)?);
+            }
+            result.push(row);
+        }
+        Ok(result)
+    }
+}
+
+// IterateChain
+
+impl<T: ChainLink + Archive> ArchiveWith<Option<Box<T>>> for IterateChain {
+    type Archived = ArchivedChain<T::Archived>;
+    type Resolver = ChainResolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        _: &Option<Box<T>>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedChain::resolve_from_resolver(pos, resolver, out);
+    }
+}
+
+impl<T, S> SerializeWith<Option<Box<T>>, S> for IterateChain
+where
+    T: ChainLink + Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize_with(
+        field: &Option<Box<T>>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        let links = iter_chain(field);
+        let len = links.len();
+        let nodes = ArchivedVec::<T::Archived>::serialize_from_iter::<T, _, _>(
+            links, serializer,
+        )?;
+        Ok(ChainResolver::new(len, nodes))
+    }
+}
+
+impl<T, D> DeserializeWith<ArchivedChain<T::Archived>, Option<Box<T>>, D>
+    for IterateChain
+where
+    T: ChainLink + Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + AllocationLimiter + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedChain<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Option<Box<T>>, D::Error> {
+        deserializer.check_array::<T>(field.len())?;
+        // Build the chain from its tail so each node can be linked to the
+        // nodes after it as soon as it's deserialized.
+        let mut head = None;
+        for node in field.iter().rev() {
+            match node.deserialize(deserializer) {
+                Ok(mut node) => {
+                    *node.link_mut() = head;
+                    head = Some(Box::new(node));
+                }
+                Err(e) => {
+                    drop_chain(&mut head);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(head)
+    }
+}
+
+// External
+
+macro_rules! impl_external {
+    (
+        $ty:ty,
+        |$field:ident| $bytes:expr,
+        |$archived:ident, $blob:ident| $owned:expr $(,)?
+    ) => {
+        impl ArchiveWith<$ty> for External {
+            type Archived = ArchivedExternal;
+            type Resolver = BlobHandle;
+
+            #[inline]
+            unsafe fn resolve_with(
+                _: &$ty,
+                _: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedExternal::resolve_from_handle(resolver, out);
+            }
+        }
+
+        impl<S: Fallible + BlobSink + ?Sized> SerializeWith<$ty, S>
+            for External
+        {
+            #[inline]
+            fn serialize_with(
+                $field: &$ty,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                serializer.store_blob($bytes)
+            }
+        }
+
+        impl<D> DeserializeWith<ArchivedExternal, $ty, D> for External
+        where
+            D: Fallible + BlobSource + AllocationLimiter + ?Sized,
+            D::Error: Error,
+        {
+            #[inline]
+            fn deserialize_with(
+                $archived: &ArchivedExternal,
+                deserializer: &mut D,
+            ) -> Result<$ty, D::Error> {
+                let $blob = load_external($archived, deserializer)?;
+                $owned
+            }
+        }
+    };
+}
+
+fn load_external<D>(
+    field: &ArchivedExternal,
+    deserializer: &mut D,
+) -> Result<Vec<u8>, D::Error>
+where
+    D: Fallible + BlobSource + AllocationLimiter + ?Sized,
+    D::Error: Error,
+{
+    // The blob is looked up twice so that the deserializer isn't borrowed while
+    // checking the allocation.
+    let len = field.load::<D::Error>(deserializer)?.len();
+    deserializer.check_array::<u8>(len)?;
+    Ok(field.load::<D::Error>(deserializer)?.to_vec())
+}
+
+impl_external!(Vec<u8>, |field| field, |_field, blob| Ok(blob));
+impl_external!(Box<[u8]>, |field| field, |_field, blob| {
+    Ok(blob.into_boxed_slice())
+});
+impl_external!(String, |field| field.as_bytes(), |field, blob| {
+    match String::from_utf8(blob) {
+        Ok(string) => Ok(string),
+        Err(_) => fail!(InvalidBlobUtf8 {
+            handle: *field.handle(),
+        }),
+    }
+});
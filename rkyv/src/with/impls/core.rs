@@ -1,23 +1,38 @@
 use core::{
-    cell::{Cell, UnsafeCell},
+    cell::{Cell, RefCell, UnsafeCell},
     convert::TryInto,
+    hash::{Hash, Hasher},
     hint::unreachable_unchecked,
-    num::{NonZeroIsize, NonZeroUsize},
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroIsize,
+        NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize,
+    },
     ptr,
 };
 
-use rancor::Fallible;
+use rancor::{Error, Fallible};
 
 use crate::{
     boxed::{ArchivedBox, BoxResolver},
-    niche::option_nonzero::{
-        ArchivedOptionNonZeroIsize, ArchivedOptionNonZeroUsize,
+    niche::{
+        niche_option::ArchivedNicheOption,
+        option_nonzero::{
+            ArchivedOptionNonZeroIsize, ArchivedOptionNonZeroUsize,
+        },
+        ArchivedNiche,
     },
     option::ArchivedOption,
-    primitive::{FixedNonZeroIsize, FixedNonZeroUsize},
+    primitive::{
+        canonicalize_f32, canonicalize_f64, ArchivedCanonicalFloat,
+        ArchivedF32, ArchivedF64, FixedNonZeroIsize, FixedNonZeroUsize,
+    },
+    ser::Regions,
+    tuple::ArchivedTuple2,
     with::{
-        ArchiveWith, Boxed, BoxedInline, DeserializeWith, Inline, Map, Niche,
-        SerializeWith, Skip, Unsafe,
+        ArchiveWith, AsBigEndian, AsLittleEndian, Boxed, BoxedInline,
+        CanonicalFloat, Cold, DeserializeWith, HashWith, Identity, Immutable,
+        Inline, Lock, LockError, Map, MapKV, Niche, NicheOption, SerializeWith,
+        Skip, Unsafe,
     },
     Archive, ArchiveUnsized, Deserialize, Serialize, SerializeUnsized,
 };
@@ -114,6 +129,128 @@ struct ArchivedOptionVariantNone(ArchivedOptionTag);
 #[repr(C)]
 struct ArchivedOptionVariantSome<T>(ArchivedOptionTag, T);
 
+// Identity
+
+impl<F: Archive> ArchiveWith<F> for Identity {
+    type Archived = F::Archived;
+    type Resolver = F::Resolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &F,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        field.resolve(pos, resolver, out);
+    }
+}
+
+impl<F: Serialize<S>, S: Fallible + ?Sized> SerializeWith<F, S> for Identity {
+    #[inline]
+    fn serialize_with(
+        field: &F,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        field.serialize(serializer)
+    }
+}
+
+impl<F, D> DeserializeWith<F::Archived, F, D> for Identity
+where
+    F: Archive,
+    F::Archived: Deserialize<F, D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &F::Archived,
+        deserializer: &mut D,
+    ) -> Result<F, D::Error> {
+        field.deserialize(deserializer)
+    }
+}
+
+impl<F: Archive + Hash> HashWith<F> for Identity {
+    #[inline]
+    fn hash_with<H: Hasher>(field: &F, state: &mut H) {
+        field.hash(state);
+    }
+}
+
+// MapKV for pairs
+
+impl<K, V, KW, VW> ArchiveWith<(K, V)> for MapKV<KW, VW>
+where
+    KW: ArchiveWith<K>,
+    VW: ArchiveWith<V>,
+{
+    type Archived = ArchivedTuple2<KW::Archived, VW::Archived>;
+    type Resolver = (KW::Resolver, VW::Resolver);
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &(K, V),
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        let (fp, fo) = out_field!(out.0);
+        KW::resolve_with(&field.0, pos + fp, resolver.0, fo);
+        let (fp, fo) = out_field!(out.1);
+        VW::resolve_with(&field.1, pos + fp, resolver.1, fo);
+    }
+}
+
+impl<K, V, KW, VW, S> SerializeWith<(K, V), S> for MapKV<KW, VW>
+where
+    KW: SerializeWith<K, S>,
+    VW: SerializeWith<V, S>,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize_with(
+        field: &(K, V),
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        Ok((
+            KW::serialize_with(&field.0, serializer)?,
+            VW::serialize_with(&field.1, serializer)?,
+        ))
+    }
+}
+
+impl<K, V, KW, VW, D>
+    DeserializeWith<
+        ArchivedTuple2<
+            <KW as ArchiveWith<K>>::Archived,
+            <VW as ArchiveWith<V>>::Archived,
+        >,
+        (K, V),
+        D,
+    > for MapKV<KW, VW>
+where
+    KW: ArchiveWith<K>
+        + DeserializeWith<<KW as ArchiveWith<K>>::Archived, K, D>,
+    VW: ArchiveWith<V>
+        + DeserializeWith<<VW as ArchiveWith<V>>::Archived, V, D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedTuple2<
+            <KW as ArchiveWith<K>>::Archived,
+            <VW as ArchiveWith<V>>::Archived,
+        >,
+        deserializer: &mut D,
+    ) -> Result<(K, V), D::Error> {
+        Ok((
+            KW::deserialize_with(&field.0, deserializer)?,
+            VW::deserialize_with(&field.1, deserializer)?,
+        ))
+    }
+}
+
 // Inline
 
 impl<F: Archive> ArchiveWith<&F> for Inline {
@@ -301,6 +438,69 @@ impl<D: Fallible + ?Sized>
     }
 }
 
+// NicheOption
+
+impl<T> ArchiveWith<Option<T>> for NicheOption
+where
+    T: Archive,
+    T::Archived: ArchivedNiche,
+{
+    type Archived = ArchivedNicheOption<T::Archived>;
+    type Resolver = Option<T::Resolver>;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &Option<T>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedNicheOption::resolve_from_option(
+            field.as_ref(),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<T, S> SerializeWith<Option<T>, S> for NicheOption
+where
+    T: Serialize<S>,
+    T::Archived: ArchivedNiche,
+    S: Fallible + ?Sized,
+{
+    #[inline]
+    fn serialize_with(
+        field: &Option<T>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        field
+            .as_ref()
+            .map(|value| value.serialize(serializer))
+            .transpose()
+    }
+}
+
+impl<T, D> DeserializeWith<ArchivedNicheOption<T::Archived>, Option<T>, D>
+    for NicheOption
+where
+    T: Archive,
+    T::Archived: ArchivedNiche + Deserialize<T, D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedNicheOption<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Option<T>, D::Error> {
+        field
+            .as_ref()
+            .map(|value| value.deserialize(deserializer))
+            .transpose()
+    }
+}
+
 // Unsafe
 
 impl<F: Archive> ArchiveWith<UnsafeCell<F>> for Unsafe {
@@ -393,6 +593,57 @@ where
     }
 }
 
+// Lock
+
+impl<F: Archive> ArchiveWith<RefCell<F>> for Lock {
+    type Archived = Immutable<F::Archived>;
+    type Resolver = F::Resolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &RefCell<F>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        // Resolve must be infallible, so this panics if the cell was mutably
+        // borrowed after it was serialized. See the `Lock` docs for details.
+        field.borrow().resolve(pos, resolver, out.cast());
+    }
+}
+
+impl<F, S> SerializeWith<RefCell<F>, S> for Lock
+where
+    F: Serialize<S>,
+    S: Fallible + ?Sized,
+    S::Error: Error,
+{
+    #[inline]
+    fn serialize_with(
+        field: &RefCell<F>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        field
+            .try_borrow()
+            .map_err(|_| S::Error::new(LockError::Borrowed))?
+            .serialize(serializer)
+    }
+}
+
+impl<F, T, D> DeserializeWith<Immutable<F>, RefCell<T>, D> for Lock
+where
+    F: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &Immutable<F>,
+        deserializer: &mut D,
+    ) -> Result<RefCell<T>, D::Error> {
+        Ok(RefCell::new(field.value().deserialize(deserializer)?))
+    }
+}
+
 // Skip
 
 impl<F> ArchiveWith<F> for Skip {
@@ -419,3 +670,203 @@ impl<F: Default, D: Fallible + ?Sized> DeserializeWith<(), F, D> for Skip {
         Ok(Default::default())
     }
 }
+
+// Cold
+
+impl<F: Archive> ArchiveWith<F> for Cold {
+    type Archived = F::Archived;
+    type Resolver = F::Resolver;
+
+    #[inline]
+    unsafe fn resolve_with(
+        field: &F,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        field.resolve(pos, resolver, out);
+    }
+}
+
+impl<F, S> SerializeWith<F, S> for Cold
+where
+    F: Serialize<S>,
+    S: Fallible + Regions + ?Sized,
+{
+    #[inline]
+    fn serialize_with(
+        field: &F,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        let was_cold = serializer.is_cold();
+        serializer.set_cold(true);
+        let result = field.serialize(serializer);
+        serializer.set_cold(was_cold);
+        result
+    }
+}
+
+impl<F, D> DeserializeWith<F::Archived, F, D> for Cold
+where
+    F: Archive,
+    F::Archived: Deserialize<F, D>,
+    D: Fallible + ?Sized,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &F::Archived,
+        deserializer: &mut D,
+    ) -> Result<F, D::Error> {
+        field.deserialize(deserializer)
+    }
+}
+
+// AsBigEndian and AsLittleEndian
+
+macro_rules! impl_endian_wrapper {
+    ($wrapper:ident, $native:ty, $archived:ty) => {
+        impl ArchiveWith<$native> for $wrapper {
+            type Archived = $archived;
+            type Resolver = ();
+
+            #[inline]
+            unsafe fn resolve_with(
+                field: &$native,
+                _: usize,
+                _: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                out.write(<$archived>::from_native(*field));
+            }
+        }
+
+        impl<S: Fallible + ?Sized> SerializeWith<$native, S> for $wrapper {
+            #[inline]
+            fn serialize_with(
+                _: &$native,
+                _: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                Ok(())
+            }
+        }
+
+        impl<D: Fallible + ?Sized> DeserializeWith<$archived, $native, D>
+            for $wrapper
+        {
+            #[inline]
+            fn deserialize_with(
+                field: &$archived,
+                _: &mut D,
+            ) -> Result<$native, D::Error> {
+                Ok(field.to_native())
+            }
+        }
+    };
+}
+
+macro_rules! impl_endian_wrappers {
+    ($($native:ty: $be:ident, $le:ident);* $(;)?) => {
+        $(
+            impl_endian_wrapper!(AsBigEndian, $native, endian::$be);
+            impl_endian_wrapper!(AsLittleEndian, $native, endian::$le);
+        )*
+    };
+}
+
+// The endian wrappers use unaligned primitives when the `unaligned` feature is
+// enabled, just like the default archived primitives
+mod endian {
+    #[cfg(feature = "unaligned")]
+    pub use crate::rend::unaligned::{
+        char_ube as char_be, char_ule as char_le, f32_ube as f32_be,
+        f32_ule as f32_le, f64_ube as f64_be, f64_ule as f64_le,
+        i128_ube as i128_be, i128_ule as i128_le, i16_ube as i16_be,
+        i16_ule as i16_le, i32_ube as i32_be, i32_ule as i32_le,
+        i64_ube as i64_be, i64_ule as i64_le, u128_ube as u128_be,
+        u128_ule as u128_le, u16_ube as u16_be, u16_ule as u16_le,
+        u32_ube as u32_be, u32_ule as u32_le, u64_ube as u64_be,
+        u64_ule as u64_le, NonZeroI128_ube as NonZeroI128_be,
+        NonZeroI128_ule as NonZeroI128_le, NonZeroI16_ube as NonZeroI16_be,
+        NonZeroI16_ule as NonZeroI16_le, NonZeroI32_ube as NonZeroI32_be,
+        NonZeroI32_ule as NonZeroI32_le, NonZeroI64_ube as NonZeroI64_be,
+        NonZeroI64_ule as NonZeroI64_le, NonZeroU128_ube as NonZeroU128_be,
+        NonZeroU128_ule as NonZeroU128_le, NonZeroU16_ube as NonZeroU16_be,
+        NonZeroU16_ule as NonZeroU16_le, NonZeroU32_ube as NonZeroU32_be,
+        NonZeroU32_ule as NonZeroU32_le, NonZeroU64_ube as NonZeroU64_be,
+        NonZeroU64_ule as NonZeroU64_le,
+    };
+    #[cfg(not(feature = "unaligned"))]
+    pub use crate::rend::*;
+}
+
+impl_endian_wrappers! {
+    i16: i16_be, i16_le;
+    i32: i32_be, i32_le;
+    i64: i64_be, i64_le;
+    i128: i128_be, i128_le;
+    u16: u16_be, u16_le;
+    u32: u32_be, u32_le;
+    u64: u64_be, u64_le;
+    u128: u128_be, u128_le;
+    f32: f32_be, f32_le;
+    f64: f64_be, f64_le;
+    char: char_be, char_le;
+    NonZeroI16: NonZeroI16_be, NonZeroI16_le;
+    NonZeroI32: NonZeroI32_be, NonZeroI32_le;
+    NonZeroI64: NonZeroI64_be, NonZeroI64_le;
+    NonZeroI128: NonZeroI128_be, NonZeroI128_le;
+    NonZeroU16: NonZeroU16_be, NonZeroU16_le;
+    NonZeroU32: NonZeroU32_be, NonZeroU32_le;
+    NonZeroU64: NonZeroU64_be, NonZeroU64_le;
+    NonZeroU128: NonZeroU128_be, NonZeroU128_le;
+}
+
+// CanonicalFloat
+
+macro_rules! impl_canonical_float {
+    ($native:ty, $archived:ty, $canonicalize:ident) => {
+        impl ArchiveWith<$native> for CanonicalFloat {
+            type Archived = ArchivedCanonicalFloat<$archived>;
+            type Resolver = ();
+
+            #[inline]
+            unsafe fn resolve_with(
+                field: &$native,
+                _: usize,
+                _: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                out.cast::<$archived>()
+                    .write(<$archived>::from_native($canonicalize(*field)));
+            }
+        }
+
+        impl<S: Fallible + ?Sized> SerializeWith<$native, S>
+            for CanonicalFloat
+        {
+            #[inline]
+            fn serialize_with(
+                _: &$native,
+                _: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                Ok(())
+            }
+        }
+
+        impl<D: Fallible + ?Sized>
+            DeserializeWith<ArchivedCanonicalFloat<$archived>, $native, D>
+            for CanonicalFloat
+        {
+            #[inline]
+            fn deserialize_with(
+                field: &ArchivedCanonicalFloat<$archived>,
+                _: &mut D,
+            ) -> Result<$native, D::Error> {
+                Ok(field.to_native())
+            }
+        }
+    };
+}
+
+impl_canonical_float!(f32, ArchivedF32, canonicalize_f32);
+impl_canonical_float!(f64, ArchivedF64, canonicalize_f64);
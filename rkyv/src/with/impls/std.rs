@@ -1,4 +1,7 @@
-use core::{hash::Hash, str::FromStr};
+use core::{
+    hash::{BuildHasher, Hash},
+    str::FromStr,
+};
 use std::{
     collections::{HashMap, HashSet},
     ffi::OsString,
@@ -10,14 +13,22 @@ use std::{
 use rancor::{Error, Fallible, OptionExt, ResultExt};
 
 use crate::{
-    collections::util::Entry,
+    collections::{
+        swiss_table::{
+            ArchivedHashMap, ArchivedHashSet, Density, HashMapResolver,
+            HashSetResolver,
+        },
+        util::Entry,
+    },
+    hash::Hashed,
     ser::{Allocator, Writer},
     string::{ArchivedString, StringResolver},
     time::ArchivedDuration,
     vec::{ArchivedVec, VecResolver},
     with::{
-        ArchiveWith, AsString, AsVec, DeserializeWith, Immutable, InvalidStr,
-        Lock, Poisoned, SerializeWith, UnixTimestamp,
+        ArchiveWith, AsString, AsVec, Dense, DeserializeWith, HashWith,
+        Immutable, InvalidStr, Lock, LockError, MapKV, Prehashed,
+        SerializeWith, UnixTimestamp, With,
     },
     Archive, Deserialize, Serialize, SerializeUnsized,
 };
@@ -166,8 +177,7 @@ where
     ) -> Result<Self::Resolver, S::Error> {
         field
             .lock()
-            .ok()
-            .into_trace(Poisoned)?
+            .map_err(|_| S::Error::new(LockError::Poisoned))?
             .serialize(serializer)
     }
 }
@@ -224,8 +234,7 @@ where
     ) -> Result<Self::Resolver, S::Error> {
         field
             .read()
-            .ok()
-            .into_trace(Poisoned)?
+            .map_err(|_| S::Error::new(LockError::Poisoned))?
             .serialize(serializer)
     }
 }
@@ -244,6 +253,95 @@ where
     }
 }
 
+// MapKV for HashMaps
+
+impl<K, V, KW, VW, H> ArchiveWith<HashMap<K, V, H>> for MapKV<KW, VW>
+where
+    KW: ArchiveWith<K>,
+    KW::Archived: Hash + Eq,
+    VW: ArchiveWith<V>,
+{
+    type Archived = ArchivedHashMap<KW::Archived, VW::Archived>;
+    type Resolver = HashMapResolver;
+
+    unsafe fn resolve_with(
+        field: &HashMap<K, V, H>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashMap::resolve_from_len(
+            field.len(),
+            (7, 8),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, V, KW, VW, H, S> SerializeWith<HashMap<K, V, H>, S> for MapKV<KW, VW>
+where
+    K: Eq,
+    KW: SerializeWith<K, S> + HashWith<K>,
+    KW::Archived: Hash + Eq,
+    VW: SerializeWith<V, S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+{
+    fn serialize_with(
+        field: &HashMap<K, V, H>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        // Keys are hashed with `HashWith` so that the hashes match the hashes
+        // of the archived keys.
+        ArchivedHashMap::<KW::Archived, VW::Archived>::serialize_from_iter(
+            field.iter().map(|(key, value)| {
+                (With::<K, KW>::cast(key), With::<V, VW>::cast(value))
+            }),
+            (7, 8),
+            serializer,
+        )
+    }
+}
+
+impl<K, V, KW, VW, H, D>
+    DeserializeWith<
+        ArchivedHashMap<
+            <KW as ArchiveWith<K>>::Archived,
+            <VW as ArchiveWith<V>>::Archived,
+        >,
+        HashMap<K, V, H>,
+        D,
+    > for MapKV<KW, VW>
+where
+    K: Hash + Eq,
+    KW: ArchiveWith<K>
+        + DeserializeWith<<KW as ArchiveWith<K>>::Archived, K, D>,
+    VW: ArchiveWith<V>
+        + DeserializeWith<<VW as ArchiveWith<V>>::Archived, V, D>,
+    H: Default + BuildHasher,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedHashMap<
+            <KW as ArchiveWith<K>>::Archived,
+            <VW as ArchiveWith<V>>::Archived,
+        >,
+        deserializer: &mut D,
+    ) -> Result<HashMap<K, V, H>, D::Error> {
+        let mut result =
+            HashMap::with_capacity_and_hasher(field.len(), H::default());
+        for (key, value) in field.iter() {
+            result.insert(
+                KW::deserialize_with(key, deserializer)?,
+                VW::deserialize_with(value, deserializer)?,
+            );
+        }
+        Ok(result)
+    }
+}
+
 // AsVec
 
 impl<K: Archive, V: Archive> ArchiveWith<HashMap<K, V>> for AsVec {
@@ -396,3 +494,207 @@ impl<D: Fallible + ?Sized> DeserializeWith<ArchivedDuration, SystemTime, D>
         Ok(UNIX_EPOCH + (*field).into())
     }
 }
+
+// Dense
+
+impl<K, V, H> ArchiveWith<HashMap<K, V, H>> for Dense
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Hash + Eq,
+    V: Archive,
+{
+    type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+    type Resolver = HashMapResolver;
+
+    unsafe fn resolve_with(
+        field: &HashMap<K, V, H>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashMap::resolve_from_len(
+            field.len(),
+            Density::Max.load_factor(),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, V, H, S> SerializeWith<HashMap<K, V, H>, S> for Dense
+where
+    K: Serialize<S> + Hash + Eq,
+    K::Archived: Hash + Eq,
+    V: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+{
+    fn serialize_with(
+        field: &HashMap<K, V, H>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
+            field.iter(),
+            Density::Max.load_factor(),
+            serializer,
+        )
+    }
+}
+
+impl<K, V, H, D>
+    DeserializeWith<
+        ArchivedHashMap<K::Archived, V::Archived>,
+        HashMap<K, V, H>,
+        D,
+    > for Dense
+where
+    K: Archive,
+    V: Archive,
+    ArchivedHashMap<K::Archived, V::Archived>: Deserialize<HashMap<K, V, H>, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedHashMap<K::Archived, V::Archived>,
+        deserializer: &mut D,
+    ) -> Result<HashMap<K, V, H>, D::Error> {
+        field.deserialize(deserializer)
+    }
+}
+
+impl<K, H> ArchiveWith<HashSet<K, H>> for Dense
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Hash + Eq,
+{
+    type Archived = ArchivedHashSet<K::Archived>;
+    type Resolver = HashSetResolver;
+
+    unsafe fn resolve_with(
+        field: &HashSet<K, H>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashSet::resolve_from_len(
+            field.len(),
+            Density::Max.load_factor(),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, H, S> SerializeWith<HashSet<K, H>, S> for Dense
+where
+    K: Serialize<S> + Hash + Eq,
+    K::Archived: Hash + Eq,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+{
+    fn serialize_with(
+        field: &HashSet<K, H>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedHashSet::<K::Archived>::serialize_from_iter(
+            field.iter(),
+            Density::Max.load_factor(),
+            serializer,
+        )
+    }
+}
+
+impl<K, H, D> DeserializeWith<ArchivedHashSet<K::Archived>, HashSet<K, H>, D>
+    for Dense
+where
+    K: Archive,
+    ArchivedHashSet<K::Archived>: Deserialize<HashSet<K, H>, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedHashSet<K::Archived>,
+        deserializer: &mut D,
+    ) -> Result<HashSet<K, H>, D::Error> {
+        field.deserialize(deserializer)
+    }
+}
+
+// Prehashed
+
+impl<K, V, H> ArchiveWith<HashMap<Hashed<K>, V, H>> for Prehashed
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Hash + Eq,
+    V: Archive,
+{
+    type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+    type Resolver = HashMapResolver;
+
+    unsafe fn resolve_with(
+        field: &HashMap<Hashed<K>, V, H>,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashMap::resolve_from_len(
+            field.len(),
+            (7, 8),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, V, H, S> SerializeWith<HashMap<Hashed<K>, V, H>, S> for Prehashed
+where
+    K: Serialize<S> + Hash + Eq,
+    K::Archived: Hash + Eq,
+    V: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+    S::Error: Error,
+{
+    fn serialize_with(
+        field: &HashMap<Hashed<K>, V, H>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_hashed_iter(
+            field
+                .iter()
+                .map(|(key, value)| (key.cached_hash(), key.get(), value)),
+            (7, 8),
+            serializer,
+        )
+    }
+}
+
+impl<K, V, H, D>
+    DeserializeWith<
+        ArchivedHashMap<K::Archived, V::Archived>,
+        HashMap<Hashed<K>, V, H>,
+        D,
+    > for Prehashed
+where
+    K: Archive + Hash + Eq,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    H: Default + BuildHasher,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedHashMap<K::Archived, V::Archived>,
+        deserializer: &mut D,
+    ) -> Result<HashMap<Hashed<K>, V, H>, D::Error> {
+        let mut result =
+            HashMap::with_capacity_and_hasher(field.len(), H::default());
+        for (key, value) in field.iter() {
+            result.insert(
+                Hashed::new(key.deserialize(deserializer)?),
+                value.deserialize(deserializer)?,
+            );
+        }
+        Ok(result)
+    }
+}
@@ -5,7 +5,13 @@
 
 mod impls;
 
-use core::{fmt, marker::PhantomData, mem::transmute, ops::Deref};
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem::transmute,
+    ops::Deref,
+};
 
 use rancor::Fallible;
 
@@ -169,6 +175,12 @@ impl<F: ?Sized, W> AsRef<F> for With<F, W> {
 /// // ... and the unwrapped field is unchanged
 /// assert_eq!(deserialized.b, 9);
 /// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be used as a wrapper for fields of type `{F}`",
+    label = "`{Self}` does not implement `ArchiveWith<{F}>`",
+    note = "wrappers in `#[with(...)]` are applied to the whole field type; \
+            to wrap the contents of an `Option` or `Vec`, use `Map<{Self}>`"
+)]
 pub trait ArchiveWith<F: ?Sized> {
     /// The archived type of a `With<F, Self>`.
     type Archived: Portable;
@@ -205,6 +217,10 @@ impl<F: ?Sized, W: ArchiveWith<F>> Archive for With<F, W> {
 }
 
 /// A variant of `Serialize` that works with `With` wrappers.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot serialize fields of type `{F}` with `{S}`",
+    label = "`{Self}` does not implement `SerializeWith<{F}, _>`"
+)]
 pub trait SerializeWith<F: ?Sized, S: Fallible + ?Sized>:
     ArchiveWith<F>
 {
@@ -231,6 +247,10 @@ where
 }
 
 /// A variant of `Deserialize` that works with `With` wrappers.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot deserialize `{F}` into `{T}`",
+    label = "`{Self}` does not implement `DeserializeWith<{F}, {T}, _>`"
+)]
 pub trait DeserializeWith<F: ?Sized, T, D: Fallible + ?Sized> {
     /// Deserializes the field type `F` using the given deserializer.
     fn deserialize_with(field: &F, deserializer: &mut D)
@@ -255,6 +275,34 @@ where
     }
 }
 
+/// A variant of `Hash` that works with `With` wrappers.
+///
+/// Archived hash maps look up entries by the hashes of their archived keys, so
+/// wrappers used on hash map keys must hash fields exactly the same way that
+/// their archived type hashes. For example, a wrapper that archives a `String`
+/// as an [`ArchivedString`](crate::string::ArchivedString) should hash the
+/// `str` that it archives.
+pub trait HashWith<F: ?Sized>: ArchiveWith<F> {
+    /// Feeds the field type `F` into the given hasher as if it were archived.
+    fn hash_with<H: Hasher>(field: &F, state: &mut H);
+}
+
+impl<F: ?Sized, W: HashWith<F>> Hash for With<F, W> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        W::hash_with(&self.field, state)
+    }
+}
+
+impl<F: ?Sized + PartialEq, W> PartialEq for With<F, W> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field
+    }
+}
+
+impl<F: ?Sized + Eq, W> Eq for With<F, W> {}
+
 /// A wrapper to make a type immutable.
 #[derive(Debug, Portable)]
 #[archive(crate)]
@@ -299,6 +347,68 @@ pub struct Map<Archivable> {
     _type: PhantomData<Archivable>,
 }
 
+/// A generic wrapper that allows wrapping the keys and values of a map.
+///
+/// `MapKV<KW, VW>` archives each key with `KW` and each value with `VW`. It
+/// can be used with `HashMap`, `BTreeMap`, and `Vec<(K, V)>`. [`MapK`] and
+/// [`MapV`] only wrap the keys or the values respectively.
+///
+/// Key wrappers used with `HashMap` must implement [`HashWith`]. Key wrappers
+/// used with `BTreeMap` must preserve the order of keys, and key wrappers used
+/// with either map must not archive two different keys as equal keys.
+/// Otherwise, lookups in the archived map may fail.
+///
+/// # Example
+///
+/// ```
+/// use std::{collections::HashMap, time::SystemTime};
+///
+/// use rkyv::{
+///     with::{BoxedInline, MapKV, MapV, UnixTimestamp},
+///     Archive,
+/// };
+///
+/// #[derive(Archive)]
+/// struct Example<'a> {
+///     #[with(MapV<UnixTimestamp>)]
+///     modified: HashMap<String, SystemTime>,
+///     #[with(MapKV<BoxedInline, UnixTimestamp>)]
+///     created: Vec<(&'a str, SystemTime)>,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MapKV<KW, VW> {
+    _type: PhantomData<(KW, VW)>,
+}
+
+/// A generic wrapper that allows wrapping the keys of a map.
+///
+/// See [`MapKV`] for more details.
+pub type MapK<KW> = MapKV<KW, Identity>;
+
+/// A generic wrapper that allows wrapping the values of a map.
+///
+/// See [`MapKV`] for more details.
+pub type MapV<VW> = MapKV<Identity, VW>;
+
+/// A wrapper that archives a field the same way as if it were not wrapped.
+///
+/// This is mostly useful in combination with other wrappers like [`MapKV`].
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{with::Identity, Archive};
+///
+/// #[derive(Archive)]
+/// struct Example {
+///     #[with(Identity)]
+///     value: u32,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Identity;
+
 /// A type indicating relaxed atomic loads.
 pub struct Relaxed;
 
@@ -468,14 +578,23 @@ impl fmt::Display for InvalidStr {
 #[cfg(feature = "std")]
 impl ::std::error::Error for InvalidStr {}
 
-/// A wrapper that locks a lock and serializes the value immutably.
+/// A wrapper that locks a lock or borrows a cell and serializes the value
+/// immutably.
+///
+/// This wrapper supports `Mutex` and `RwLock` with the `std` feature, and
+/// `RefCell` in all builds. The value is archived as an [`Immutable`] of its
+/// archived type, and deserialized into a new lock or cell. Locks which are
+/// poisoned and `RefCell`s which are mutably borrowed fail to serialize with a
+/// [`LockError`]. For `Cell` and `UnsafeCell`, use [`Unsafe`].
 ///
 /// This wrapper can panic under very specific circumstances when:
 ///
 /// 1. `serialize_with` is called and succeeds in locking the value to serialize
 ///    it.
-/// 2. Another thread locks the value and panics, poisoning the lock
-/// 3. `resolve_with` is called and gets a poisoned value.
+/// 2. Another thread locks the value and panics, poisoning the lock, or the
+///    `RefCell` is mutably borrowed.
+/// 3. `resolve_with` is called and gets a poisoned value or fails to borrow
+///    the `RefCell`.
 ///
 /// Unfortunately, it's not possible to work around this issue. If your code
 /// absolutely must not panic under any circumstances, it's recommended that you
@@ -486,36 +605,44 @@ impl ::std::error::Error for InvalidStr {}
 /// of this wrapper should be considered unsafe** with the requirement that the
 /// data not be mutated between these two steps.
 ///
-/// Regular serializers don't support the custom error handling needed for this
-/// type by default. To use this wrapper, a custom serializer with an error type
-/// satisfying `<S as Fallible>::Error: From<LockError>` must be provided.
-///
 /// # Example
 ///
 /// ```
-/// use std::sync::Mutex;
+/// use std::{cell::RefCell, sync::Mutex};
 /// use rkyv::{Archive, with::Lock};
 ///
 /// #[derive(Archive)]
 /// struct Example {
 ///     #[with(Lock)]
 ///     a: Mutex<i32>,
+///     #[with(Lock)]
+///     b: RefCell<String>,
 /// }
 /// ```
 #[derive(Debug)]
 pub struct Lock;
 
-#[derive(Debug)]
-struct Poisoned;
+/// An error which occurs when the value of a lock or cell can't be accessed to
+/// serialize it with [`Lock`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockError {
+    /// The lock was poisoned by a thread which panicked while holding it.
+    Poisoned,
+    /// The `RefCell` was mutably borrowed.
+    Borrowed,
+}
 
-impl fmt::Display for Poisoned {
+impl fmt::Display for LockError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "lock poisoned")
+        match self {
+            LockError::Poisoned => write!(f, "lock poisoned"),
+            LockError::Borrowed => write!(f, "cell already mutably borrowed"),
+        }
     }
 }
 
 #[cfg(feature = "std")]
-impl ::std::error::Error for Poisoned {}
+impl ::std::error::Error for LockError {}
 
 /// A wrapper that serializes a `Cow` as if it were owned.
 ///
@@ -583,6 +710,161 @@ pub struct AsVec;
 #[derive(Debug)]
 pub struct Niche;
 
+/// A wrapper that archives an `Option<T>` in the same space as `T` by storing
+/// `None` in a niche of the archived `T`.
+///
+/// The archived type of `T` must implement
+/// [`ArchivedNiche`](crate::niche::ArchivedNiche). Archived bools and nonzero
+/// integers do, and enums with only unit variants can derive it with
+/// `#[archive(niche)]`.
+///
+/// # Example
+///
+/// ```
+/// use core::{mem::size_of, num::NonZeroU32};
+/// use rkyv::{Archive, Archived, with::NicheOption};
+///
+/// #[derive(Archive)]
+/// struct BasicExample {
+///     value: Option<NonZeroU32>,
+///     flag: Option<bool>,
+/// }
+///
+/// #[derive(Archive)]
+/// struct NichedExample {
+///     #[with(NicheOption)]
+///     value: Option<NonZeroU32>,
+///     #[with(NicheOption)]
+///     flag: Option<bool>,
+/// }
+///
+/// assert_eq!(size_of::<Archived<BasicExample>>(), 12);
+/// assert_eq!(size_of::<Archived<NichedExample>>(), 8);
+/// ```
+#[derive(Debug)]
+pub struct NicheOption;
+
+/// A wrapper that archives a `Vec`, `String`, or `Box` with 16-bit lengths and
+/// relative pointers.
+///
+/// This saves space in small archives without changing the pointer width of the
+/// whole crate. `Vec<T>` archives as an
+/// [`ArchivedCompactVec`](crate::compact::ArchivedCompactVec), `String` as an
+/// [`ArchivedCompactString`](crate::compact::ArchivedCompactString), and
+/// `Box<T>` as an [`ArchivedCompactBox`](crate::compact::ArchivedCompactBox).
+///
+/// Serialization fails with a
+/// [`CompactOverflow`](crate::compact::CompactOverflow) error if a length does
+/// not fit in 16 bits or if the serialized data is too large to be reached by a
+/// 16-bit relative pointer.
+///
+/// # Example
+///
+/// ```
+/// use core::mem::size_of;
+/// use rkyv::{Archive, Archived, with::Compact};
+///
+/// #[derive(Archive)]
+/// struct Example {
+///     #[with(Compact)]
+///     values: Vec<u32>,
+///     #[with(Compact)]
+///     name: String,
+///     #[with(Compact)]
+///     boxed: Box<u64>,
+/// }
+///
+/// assert_eq!(size_of::<Archived<Example>>(), 10);
+/// ```
+#[derive(Debug)]
+pub struct Compact;
+
+/// A wrapper that archives primitives as big-endian regardless of the enabled
+/// endianness features.
+///
+/// Archived big-endian and little-endian primitives are always available from
+/// [`rend`](crate::rend), so a program built with the default little-endian
+/// features can still read structures written by big-endian programs (and vice
+/// versa). Use [`Map`] to archive containers of primitives as big-endian.
+///
+/// The layout of relative pointers and lengths still follows the enabled
+/// endianness features, so only structures made up of primitives with fixed
+/// endianness are portable between programs with different features.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{rend::u32_be, with::AsBigEndian, Archive};
+///
+/// #[derive(Archive)]
+/// struct Example {
+///     #[with(AsBigEndian)]
+///     value: u32,
+/// }
+///
+/// let archived = ArchivedExample {
+///     value: u32_be::from_native(42),
+/// };
+/// assert_eq!(archived.value, 42);
+/// ```
+#[derive(Debug)]
+pub struct AsBigEndian;
+
+/// A wrapper that archives primitives as little-endian regardless of the
+/// enabled endianness features.
+///
+/// See [`AsBigEndian`] for more details.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{rend::u32_le, with::AsLittleEndian, Archive};
+///
+/// #[derive(Archive)]
+/// struct Example {
+///     #[with(AsLittleEndian)]
+///     value: u32,
+/// }
+///
+/// let archived = ArchivedExample {
+///     value: u32_le::from_native(42),
+/// };
+/// assert_eq!(archived.value, 42);
+/// ```
+#[derive(Debug)]
+pub struct AsLittleEndian;
+
+/// A wrapper that archives `f32`s and `f64`s in canonical form.
+///
+/// NaNs are archived as a single quiet NaN bit pattern and negative zero is
+/// archived as positive zero, so archives of equal logical values are
+/// byte-identical. All other values are archived unchanged.
+///
+/// Floats are archived as an
+/// [`ArchivedCanonicalFloat`](crate::primitive::ArchivedCanonicalFloat), which
+/// has the same layout as a regular archived float. Validating an
+/// `ArchivedCanonicalFloat` checks that it is in canonical form, so foreign
+/// archives can be trusted to be canonical. To read archived floats that may
+/// not be canonical, access them as regular archived floats instead.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{rancor::Failure, to_bytes, with::CanonicalFloat, Archive, Serialize};
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     #[with(CanonicalFloat)]
+///     value: f32,
+/// }
+///
+/// let a = to_bytes::<_, 256, Failure>(&Example { value: 0.0 }).unwrap();
+/// let b = to_bytes::<_, 256, Failure>(&Example { value: -0.0 }).unwrap();
+/// assert_eq!(a.as_slice(), b.as_slice());
+/// ```
+#[derive(Debug)]
+pub struct CanonicalFloat;
+
 /// A wrapper that provides specialized, performant implementations of
 /// serialization and deserialization.
 ///
@@ -656,6 +938,189 @@ impl fmt::Display for UnixTimestampError {
 #[cfg(feature = "std")]
 impl ::std::error::Error for UnixTimestampError {}
 
+/// A wrapper that aligns the bytes of a `Vec<u8>` or `Box<[u8]>` to `ALIGN`
+/// bytes from the start of the archive.
+///
+/// The serializer is padded to the alignment before the bytes are copied into
+/// the archive, so the archived bytes begin at a multiple of `ALIGN` bytes. If
+/// the buffer holding the archive is also aligned to `ALIGN` bytes (e.g. with
+/// [`AlignedVec::with_alignment`](crate::util::AlignedVec::with_alignment)),
+/// then the archived bytes can be used directly with wide SIMD loads or direct
+/// I/O. Validation checks that the bytes are aligned relative to the start of
+/// the archive.
+///
+/// The bytes are archived as an
+/// [`ArchivedAlignedBytes`](crate::vec::ArchivedAlignedBytes), which has the
+/// same layout as an [`ArchivedVec<u8>`](crate::vec::ArchivedVec). `ALIGN`
+/// must be a power of two.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access,
+///     rancor::Failure,
+///     util::{serialize_into, AlignedVec},
+///     with::AlignTo,
+///     Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     id: u8,
+///     #[with(AlignTo<64>)]
+///     payload: Vec<u8>,
+/// }
+///
+/// let value = Example {
+///     id: 1,
+///     payload: vec![0xff; 100],
+/// };
+/// let bytes =
+///     serialize_into::<_, _, Failure>(&value, AlignedVec::with_alignment(64))
+///         .unwrap();
+/// let archived = access::<ArchivedExample, Failure>(&bytes).unwrap();
+/// assert_eq!(archived.payload.as_ptr().align_offset(64), 0);
+/// ```
+#[derive(Debug)]
+pub struct AlignTo<const ALIGN: usize>;
+
+/// A wrapper that writes the bytes of the strings in a `Vec<String>`
+/// back-to-back.
+///
+/// The strings are archived as an
+/// [`ArchivedDenseStrings`](crate::vec::ArchivedDenseStrings), which has the
+/// same layout as an [`ArchivedVec<ArchivedString>`](crate::vec::ArchivedVec).
+/// The bytes of all of the out-of-line strings are written in order with
+/// nothing between them, so validation can claim them and check that they are
+/// valid UTF-8 all at once instead of one string at a time. This is much
+/// faster for vectors of many short strings.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access, rancor::Failure, to_bytes, with::DenseStrings, Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     #[with(DenseStrings)]
+///     names: Vec<String>,
+/// }
+///
+/// let value = Example {
+///     names: vec!["a short name".to_string(), "another name".to_string()],
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = access::<ArchivedExample, Failure>(&bytes).unwrap();
+/// assert_eq!(archived.names[1], "another name");
+/// ```
+#[derive(Debug)]
+pub struct DenseStrings;
+
+/// A wrapper that archives a `Vec<T>` as runs of equal elements.
+///
+/// Each run of consecutive equal elements is archived once along with the
+/// length of the run, as an
+/// [`ArchivedRunLength`](crate::vec::ArchivedRunLength). This saves space for
+/// vectors which repeat the same value many times in a row.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access, rancor::Failure, to_bytes, with::RunLength, Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     #[with(RunLength)]
+///     status: Vec<u32>,
+/// }
+///
+/// let value = Example {
+///     status: [vec![200; 1000], vec![500; 10], vec![200; 1000]].concat(),
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = access::<ArchivedExample, Failure>(&bytes).unwrap();
+/// assert_eq!(archived.status.len(), 2010);
+/// assert_eq!(archived.status.runs().len(), 3);
+/// assert_eq!(*archived.status.get(1005).unwrap(), 500);
+/// ```
+#[derive(Debug)]
+pub struct RunLength;
+
+/// A wrapper that archives a `Vec<u64>` or `Vec<i64>` as varints.
+///
+/// Values are archived as an [`ArchivedVarints`](crate::vec::ArchivedVarints),
+/// which takes one byte for each value less than 128. Signed values are
+/// zigzag-encoded first so that negative values with small magnitudes are also
+/// small.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access, rancor::Failure, to_bytes, with::Varint, Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     #[with(Varint)]
+///     retries: Vec<u64>,
+/// }
+///
+/// let value = Example {
+///     retries: (0..1000).map(|i| i % 4).collect(),
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = access::<ArchivedExample, Failure>(&bytes).unwrap();
+/// assert_eq!(archived.retries.encoded_bytes().len(), 1000);
+/// assert_eq!(archived.retries.get(999), Some(3));
+/// ```
+#[derive(Debug)]
+pub struct Varint;
+
+/// A wrapper that archives a `Vec<u64>` or `Vec<i64>` as varints of the
+/// differences between consecutive values.
+///
+/// Values are archived as an
+/// [`ArchivedDeltaVarints`](crate::vec::ArchivedDeltaVarints). This saves space
+/// for values like timestamps and sorted IDs, which are large but close to
+/// each other.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access, rancor::Failure, to_bytes, with::DeltaVarint, Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(check_bytes)]
+/// struct Example {
+///     #[with(DeltaVarint)]
+///     timestamps: Vec<u64>,
+/// }
+///
+/// let value = Example {
+///     timestamps: (0..1000).map(|i| 1_700_000_000_000 + i * 15).collect(),
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = access::<ArchivedExample, Failure>(&bytes).unwrap();
+/// assert!(archived.timestamps.encoded_bytes().len() < 1100);
+/// assert_eq!(archived.timestamps.get(10), Some(1_700_000_000_150));
+/// ```
+#[derive(Debug)]
+pub struct DeltaVarint;
+
 /// A wrapper that provides an optimized bulk data array. This is primarily
 /// intended for large amounts of raw data, like bytes, floats, or integers.
 ///
@@ -700,6 +1165,8 @@ pub struct Raw;
 ///
 /// This wrapper enables serializing these types, and places the burden of
 /// verifying that their access semantics are used safely on the user.
+/// It is opt-in and works without `std` or `alloc`. `RefCell` tracks its
+/// borrows, so it can be serialized safely with [`Lock`] instead.
 ///
 /// # Safety
 ///
@@ -742,3 +1209,462 @@ pub struct Unsafe;
 /// ```
 #[derive(Debug)]
 pub struct Skip;
+
+/// A wrapper that writes a field's out-of-line data into the cold region of the
+/// archive.
+///
+/// Cold data is placed after all other out-of-line data, which keeps small
+/// structural data clustered near the root. This requires a serializer which
+/// implements [`Regions`](crate::ser::Regions), such as a
+/// [`RegionSerializer`](crate::ser::RegionSerializer). Archives with cold data
+/// must be validated with
+/// [`access_with_cold_region`](crate::validation::util::access_with_cold_region).
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{Archive, with::Cold};
+///
+/// #[derive(Archive)]
+/// struct Example {
+///     name: String,
+///     #[with(Cold)]
+///     blob: Vec<u8>,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Cold;
+
+/// A wrapper that deserializes a shared string by interning it.
+///
+/// The string is archived as an
+/// [`ArchivedString`](crate::string::ArchivedString) regardless of whether it
+/// was shared before serialization. During deserialization, equal strings
+/// resolve to the same `Arc<str>`. This requires a deserializer which
+/// implements [`Interning`](crate::de::Interning), such as an
+/// [`Interner`](crate::de::Interner).
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use rkyv::{Archive, with::InternedString};
+///
+/// #[derive(Archive)]
+/// struct Example {
+///     #[with(InternedString)]
+///     tag: Arc<str>,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct InternedString;
+
+/// A wrapper that deserializes a shared byte slice by interning it.
+///
+/// The bytes are archived as an [`ArchivedVec`](crate::vec::ArchivedVec).
+/// During deserialization, equal byte slices resolve to the same `Arc<[u8]>`.
+/// This requires a deserializer which implements
+/// [`Interning`](crate::de::Interning), such as an
+/// [`Interner`](crate::de::Interner).
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use rkyv::{Archive, with::InternedBytes};
+///
+/// #[derive(Archive)]
+/// struct Example {
+///     #[with(InternedBytes)]
+///     key: Arc<[u8]>,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct InternedBytes;
+
+/// A wrapper that serializes identical values only once.
+///
+/// `Intern` archives a `String`, `Vec<u8>`, or `Box<T>` as its usual archived
+/// type, but points all of the equal values serialized with `Intern` at the
+/// same serialized data. This requires a serializer which implements
+/// [`InternTable`](crate::ser::InternTable), such as one using a
+/// [`Unify`](crate::ser::sharing::Unify) sharing strategy. Serializers which
+/// duplicate shared pointers also duplicate interned values.
+///
+/// Interning keeps a copy of each distinct value, so `Box<T>` requires `T` to
+/// implement `Clone`, `Hash`, and `Eq`. Strings which are short enough to be
+/// stored inline are never interned.
+///
+/// Reading an archive with interned values doesn't require anything special,
+/// and deserializing creates independent values. However, archives with
+/// interned values must be validated with
+/// [`access_interned`](crate::validation::util::access_interned), which checks
+/// an interned value again every time it is pointed to. They can't be
+/// validated for mutable access.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     rancor::Failure, to_bytes, validation::util::access_interned,
+///     with::Intern, Archive, Archived,
+/// };
+///
+/// #[derive(Archive, rkyv::Serialize)]
+/// #[archive(check_bytes)]
+/// struct Row {
+///     #[with(Intern)]
+///     label: String,
+/// }
+///
+/// let rows = (0..100)
+///     .map(|_| Row {
+///         label: "a label which is too long to be inline".to_string(),
+///     })
+///     .collect::<Vec<_>>();
+/// let bytes = to_bytes::<_, 256, Failure>(&rows).unwrap();
+/// assert!(bytes.len() < 100 * 40);
+///
+/// let archived =
+///     access_interned::<Archived<Vec<Row>>, Failure>(&bytes).unwrap();
+/// assert_eq!(archived[0].label, archived[99].label);
+/// ```
+#[derive(Debug)]
+pub struct Intern;
+
+/// A wrapper that archives an optional weak pointer and preserves whether it
+/// was dangling.
+///
+/// Weak pointers which can't be upgraded are normally archived the same as
+/// weak pointers that were never set. `WeakTombstone` archives an
+/// `Option<rc::Weak<T>>` or `Option<sync::Weak<T>>` as an
+/// [`ArchivedOptionRcWeak`](crate::rc::ArchivedOptionRcWeak) with three
+/// states: `None`, `Some` for weak pointers which can be upgraded, and
+/// `Dangling` for weak pointers which can't. Dangling weak pointers don't
+/// serialize any data, and deserialize as `Some(Weak::new())`.
+///
+/// # Example
+///
+/// ```
+/// use std::rc::{Rc, Weak};
+///
+/// use rkyv::{
+///     access_unchecked, rancor::Failure, to_bytes, with::WeakTombstone,
+///     Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     #[with(WeakTombstone)]
+///     tombstone: Option<Weak<u32>>,
+/// }
+///
+/// let value = Example {
+///     tombstone: Some(Rc::downgrade(&Rc::new(42))),
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedExample>(&bytes) };
+/// assert!(archived.tombstone.is_dangling());
+/// ```
+#[derive(Debug)]
+pub struct WeakTombstone;
+
+/// A wrapper that archives a `Vec` of structs as one column per field.
+///
+/// The struct must derive `Archive`, `Serialize`, and `Deserialize` with
+/// `#[archive(columnar)]`. The vec is archived as the generated columns type,
+/// which stores each field in its own [`ArchivedVec`](crate::vec::ArchivedVec).
+/// See the [`columnar`](crate::columnar) module for more details.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access_unchecked, rancor::Failure, to_bytes, with::AsColumns, Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(columnar)]
+/// struct Trade {
+///     price: f64,
+///     quantity: u32,
+/// }
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     #[with(AsColumns)]
+///     trades: Vec<Trade>,
+/// }
+///
+/// let value = Example {
+///     trades: vec![
+///         Trade { price: 1.5, quantity: 10 },
+///         Trade { price: 2.5, quantity: 20 },
+///     ],
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedExample>(&bytes) };
+/// assert_eq!(archived.trades.len(), 2);
+/// assert_eq!(archived.trades.price().as_slice(), &[1.5, 2.5]);
+/// assert_eq!(archived.trades.row(1).quantity(), &20);
+/// ```
+#[derive(Debug)]
+pub struct AsColumns;
+
+/// A wrapper that archives a `Vec<Vec<T>>` as an
+/// [`ArchivedMatrix`](crate::collections::matrix::ArchivedMatrix).
+///
+/// The rows are stored contiguously in row-major order. Serialization fails
+/// with a [`MatrixShapeError`](crate::collections::matrix::MatrixShapeError)
+/// if the rows don't all have the same length.
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access_unchecked, rancor::Failure, to_bytes, with::AsMatrix, Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     #[with(AsMatrix)]
+///     grid: Vec<Vec<u8>>,
+/// }
+///
+/// let value = Example {
+///     grid: vec![vec![1, 2, 3], vec![4, 5, 6]],
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedExample>(&bytes) };
+/// assert_eq!(archived.grid.shape(), (2, 3));
+/// assert_eq!(archived.grid.row(1), &[4, 5, 6]);
+/// assert_eq!(archived.grid[(0, 2)], 3);
+///
+/// let ragged = Example {
+///     grid: vec![vec![1, 2, 3], vec![4, 5]],
+/// };
+/// assert!(to_bytes::<_, 256, Failure>(&ragged).is_err());
+/// ```
+#[derive(Debug)]
+pub struct AsMatrix;
+
+/// A wrapper that archives a linked chain of boxed nodes as an
+/// [`ArchivedChain`](crate::chain::ArchivedChain).
+///
+/// The nodes of the chain are serialized, deserialized, and validated in a
+/// loop instead of recursively, so chains of any length can be archived
+/// without overflowing the stack. The node type must implement
+/// [`ChainLink`](crate::chain::ChainLink) and skip its next-pointer with
+/// [`Skip`].
+///
+/// # Example
+///
+/// ```
+/// use rkyv::{
+///     access_unchecked,
+///     chain::ChainLink,
+///     rancor::Failure,
+///     to_bytes,
+///     with::{IterateChain, Skip},
+///     Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Node {
+///     value: u32,
+///     #[with(Skip)]
+///     next: Option<Box<Node>>,
+/// }
+///
+/// impl ChainLink for Node {
+///     fn link(&self) -> &Option<Box<Self>> {
+///         &self.next
+///     }
+///
+///     fn link_mut(&mut self) -> &mut Option<Box<Self>> {
+///         &mut self.next
+///     }
+/// }
+///
+/// #[derive(Archive, Serialize)]
+/// struct List {
+///     #[with(IterateChain)]
+///     head: Option<Box<Node>>,
+/// }
+///
+/// let list = List {
+///     head: Some(Box::new(Node {
+///         value: 1,
+///         next: Some(Box::new(Node {
+///             value: 2,
+///             next: None,
+///         })),
+///     })),
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&list).unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedList>(&bytes) };
+/// assert_eq!(archived.head.len(), 2);
+/// let head = archived.head.head().unwrap();
+/// assert_eq!(head.value, 1);
+/// assert_eq!(head.next().unwrap().value, 2);
+/// assert!(head.next().unwrap().next().is_none());
+/// ```
+#[derive(Debug)]
+pub struct IterateChain;
+
+/// A wrapper that stores a byte buffer or string outside of the archive and
+/// archives a handle to it as an
+/// [`ArchivedExternal`](crate::external::ArchivedExternal).
+///
+/// This works with `Vec<u8>`, `Box<[u8]>`, and `String`. Serializing requires
+/// a serializer which is a [`BlobSink`](crate::ser::BlobSink), and
+/// deserializing requires a deserializer which is a
+/// [`BlobSource`](crate::de::BlobSource). Deserializing fails with a
+/// [`MissingBlob`](crate::external::MissingBlob) error if the blob can't be
+/// found.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use rkyv::{
+///     access_unchecked,
+///     de::BlobDeserializer,
+///     external::BlobHandle,
+///     rancor::Failure,
+///     ser::{AllocSerializer, BlobSerializer, BlobSink},
+///     util::{deserialize, serialize_into},
+///     with::External,
+///     Archive, Deserialize, Serialize,
+/// };
+///
+/// #[derive(Default)]
+/// struct Store {
+///     blobs: HashMap<BlobHandle, Vec<u8>>,
+/// }
+///
+/// impl<E> BlobSink<E> for Store {
+///     fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+///         // A real store would use a digest of the bytes as the handle
+///         let handle = [self.blobs.len() as u8; 32];
+///         self.blobs.insert(handle, bytes.to_vec());
+///         Ok(handle)
+///     }
+/// }
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Asset {
+///     name: String,
+///     #[with(External)]
+///     data: Vec<u8>,
+/// }
+///
+/// let asset = Asset {
+///     name: "texture".to_string(),
+///     data: vec![0xab; 4096],
+/// };
+/// let mut store = Store::default();
+/// let serializer = serialize_into::<_, _, Failure>(
+///     &asset,
+///     BlobSerializer::new(AllocSerializer::<256>::default(), &mut store),
+/// )
+/// .unwrap();
+/// let bytes = serializer.into_inner().into_writer();
+/// assert!(bytes.len() < 128);
+///
+/// let archived = unsafe { access_unchecked::<ArchivedAsset>(&bytes) };
+/// let data = archived.data.load::<Failure>(&store.blobs).unwrap();
+/// assert_eq!(data, asset.data.as_slice());
+///
+/// let deserialized = deserialize::<Asset, _, Failure>(
+///     archived,
+///     &mut BlobDeserializer::new((), &store.blobs),
+/// )
+/// .unwrap();
+/// assert_eq!(deserialized.data, asset.data);
+/// ```
+#[derive(Debug)]
+pub struct External;
+
+/// A wrapper that archives a `HashMap` or `HashSet` with as few buckets as
+/// possible.
+///
+/// The archived table is filled up to [`Density::Max`], which makes it smaller
+/// at the cost of probing more buckets during lookups. Tables archived
+/// without this wrapper use [`Density::Balanced`].
+///
+/// [`Density::Max`]: crate::collections::swiss_table::Density::Max
+/// [`Density::Balanced`]: crate::collections::swiss_table::Density::Balanced
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use rkyv::{
+///     access_unchecked, rancor::Failure, to_bytes, with::Dense, Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     sparse: HashMap<u8, u8>,
+///     #[with(Dense)]
+///     dense: HashMap<u8, u8>,
+/// }
+///
+/// let map = (0..100).map(|i| (i, i * 2)).collect::<HashMap<_, _>>();
+/// let value = Example {
+///     sparse: map.clone(),
+///     dense: map,
+/// };
+/// let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedExample>(&bytes) };
+/// assert_eq!(archived.sparse.buckets(), 115);
+/// assert_eq!(archived.dense.buckets(), 107);
+/// assert_eq!(archived.dense.get(&7), Some(&14));
+/// ```
+#[derive(Debug)]
+pub struct Dense;
+
+/// A wrapper that archives a `HashMap` with [`Hashed`] keys using their cached
+/// hashes.
+///
+/// Serializing a hash map normally hashes every key to place it in the
+/// archived table. Keys wrapped in `Hashed` already know their hash, so this
+/// wrapper skips hashing them again. The archived hash map is the same as the
+/// archived hash map of the unwrapped keys. It can be used with `HashMap` and
+/// `hashbrown::HashMap`.
+///
+/// [`Hashed`]: crate::hash::Hashed
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use rkyv::{
+///     access_unchecked, hash::Hashed, rancor::Failure, to_bytes,
+///     with::Prehashed, Archive, Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     #[with(Prehashed)]
+///     map: HashMap<Hashed<String>, u8>,
+/// }
+///
+/// let value = Example {
+///     map: (0..100u8)
+///         .map(|i| (Hashed::new(i.to_string()), i))
+///         .collect::<HashMap<_, _>>(),
+/// };
+/// let bytes = to_bytes::<_, 1024, Failure>(&value).unwrap();
+/// let archived = unsafe { access_unchecked::<ArchivedExample>(&bytes) };
+/// assert_eq!(archived.map.get("42"), Some(&42));
+/// ```
+#[derive(Debug)]
+pub struct Prehashed;
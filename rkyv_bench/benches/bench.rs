@@ -3,12 +3,8 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rand::Rng;
 use rand_pcg::Lcg64Xsh32;
 use rkyv::{
-    archived_root, check_archived_root,
-    ser::{
-        serializers::{AlignedSerializer, BufferScratch, CompositeSerializer},
-        Serializer,
-    },
-    AlignedVec, Archive, Deserialize, Infallible, Serialize,
+    access, access_unchecked, from_bytes, from_bytes_unchecked,
+    rancor::Failure, to_bytes, Archive, Archived, Deserialize, Serialize,
 };
 use std::collections::HashMap;
 
@@ -17,9 +13,7 @@ trait Generate {
 }
 
 impl Generate for () {
-    fn generate<R: Rng>(_: &mut R) -> Self {
-        ()
-    }
+    fn generate<R: Rng>(_: &mut R) -> Self {}
 }
 
 impl Generate for bool {
@@ -126,7 +120,7 @@ pub struct Item {
 
 impl Generate for Item {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const IDS: [&'static str; 8] = [
+        const IDS: [&str; 8] = [
             "dirt",
             "stone",
             "pickaxe",
@@ -203,11 +197,11 @@ pub struct Entity {
 
 impl Generate for Entity {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const IDS: [&'static str; 8] = [
+        const IDS: [&str; 8] = [
             "cow", "sheep", "zombie", "skeleton", "spider", "creeper",
             "parrot", "bee",
         ];
-        const CUSTOM_NAMES: [&'static str; 8] = [
+        const CUSTOM_NAMES: [&str; 8] = [
             "rainbow", "princess", "steve", "johnny", "missy", "coward",
             "fairy", "howard",
         ];
@@ -254,7 +248,7 @@ pub struct RecipeBook {
 
 impl Generate for RecipeBook {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const RECIPES: [&'static str; 8] = [
+        const RECIPES: [&str; 8] = [
             "pickaxe",
             "torch",
             "bow",
@@ -345,7 +339,7 @@ pub struct Player {
 
 impl Generate for Player {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const DIMENSIONS: [&'static str; 3] = ["overworld", "nether", "end"];
+        const DIMENSIONS: [&str; 3] = ["overworld", "nether", "end"];
         const MAX_ITEMS: usize = 40;
         const MAX_ENDER_ITEMS: usize = 27;
         Self {
@@ -387,7 +381,7 @@ impl Generate for Player {
 }
 
 fn generate_player_name<R: Rng>(rng: &mut R) -> String {
-    const LEGAL_CHARS: &'static [u8] =
+    const LEGAL_CHARS: &[u8] =
         b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_";
 
     let len = rng.gen_range(10..40);
@@ -419,6 +413,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     const BUFFER_LEN: usize = 10_000_000;
     const SCRATCH_LEN: usize = 512_000;
 
+    type ArchivedPlayers = Archived<Players>;
+
     let mut group = c.benchmark_group("bincode");
     {
         let mut serialize_buffer = vec![0; BUFFER_LEN];
@@ -446,66 +442,43 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     let mut group = c.benchmark_group("rkyv");
     {
-        let mut serialize_buffer = AlignedVec::with_capacity(BUFFER_LEN);
-        let mut serialize_scratch = AlignedVec::with_capacity(SCRATCH_LEN);
-        unsafe {
-            serialize_scratch.set_len(SCRATCH_LEN);
-        }
-
         group.bench_function("serialize", |b| {
             b.iter(|| {
-                serialize_buffer.clear();
-
-                let mut serializer = CompositeSerializer::new(
-                    AlignedSerializer::new(black_box(&mut serialize_buffer)),
-                    BufferScratch::new(black_box(&mut serialize_scratch)),
-                    Infallible,
-                );
                 black_box(
-                    serializer.serialize_value(black_box(&players)).unwrap(),
+                    to_bytes::<_, SCRATCH_LEN, Failure>(black_box(&players))
+                        .unwrap(),
                 );
             });
         });
 
-        let mut buffer = AlignedVec::new();
-        let mut serializer = CompositeSerializer::new(
-            AlignedSerializer::new(black_box(&mut buffer)),
-            BufferScratch::new(black_box(&mut serialize_scratch)),
-            Infallible,
-        );
-        serializer.serialize_value(&players).unwrap();
+        let buffer = to_bytes::<_, SCRATCH_LEN, Failure>(&players).unwrap();
 
         group.bench_function("access", |b| {
             b.iter(|| {
                 black_box(unsafe {
-                    archived_root::<Players>(black_box(buffer.as_ref()))
+                    access_unchecked::<ArchivedPlayers>(black_box(&buffer))
                 });
             })
         });
         group.bench_function("validate", |b| {
             b.iter(|| {
-                check_archived_root::<Players>(black_box(buffer.as_ref()))
-                    .unwrap();
+                access::<ArchivedPlayers, Failure>(black_box(&buffer)).unwrap();
             })
         });
         group.bench_function("deserialize", |b| {
             b.iter(|| {
-                let value = unsafe {
-                    archived_root::<Players>(black_box(buffer.as_ref()))
-                };
-                let deserialized: Players =
-                    value.deserialize(&mut Infallible).unwrap();
+                let deserialized = unsafe {
+                    from_bytes_unchecked::<Players, Failure>(black_box(&buffer))
+                }
+                .unwrap();
                 black_box(deserialized);
             })
         });
         group.bench_function("deserialize with validate", |b| {
             b.iter(|| {
-                let value =
-                    check_archived_root::<Players>(black_box(buffer.as_ref()))
-                        .unwrap();
-                let deserialize: Players =
-                    value.deserialize(&mut Infallible).unwrap();
-                black_box(deserialize);
+                let deserialized =
+                    from_bytes::<Players, Failure>(black_box(&buffer)).unwrap();
+                black_box(deserialized);
             })
         });
     }
@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rkyv::{access_unchecked, rancor::Failure, to_bytes, Archived};
+
+const LEN: u32 = 1_000_000;
+const BATCH_LEN: u32 = 10_000;
+
+pub fn btree_benchmark(c: &mut Criterion) {
+    let map = (0..LEN).map(|i| (i, i)).collect::<BTreeMap<_, _>>();
+    let bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+    let archived =
+        unsafe { access_unchecked::<Archived<BTreeMap<u32, u32>>>(&bytes) };
+
+    // A sorted batch of nearby keys from the middle of the map
+    let keys = (0..BATCH_LEN)
+        .map(|i| Archived::<u32>::from_native(LEN / 2 + i * 2))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("btree");
+    group.bench_function("get", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(archived.get_key_value(key));
+            }
+        })
+    });
+    group.bench_function("get_many_sorted", |b| {
+        b.iter(|| {
+            for entry in archived.get_many_sorted(black_box(&keys)) {
+                black_box(entry);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, btree_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rkyv::{rancor::Failure, to_bytes, util::AlignedVec};
+
+// 1 GiB of `u64`s
+const LEN: usize = (1 << 30) / core::mem::size_of::<u64>();
+
+pub fn copy_benchmark(c: &mut Criterion) {
+    let values = (0..LEN as u64).collect::<Vec<_>>();
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            values.as_ptr().cast::<u8>(),
+            core::mem::size_of_val(values.as_slice()),
+        )
+    };
+
+    let mut group = c.benchmark_group("copy");
+    group.sample_size(10);
+    group.bench_function("memcpy", |b| {
+        b.iter(|| {
+            let mut buffer = AlignedVec::with_capacity(bytes.len());
+            buffer.extend_from_slice(black_box(bytes));
+            black_box(buffer);
+        })
+    });
+    group.bench_function("serialize vec", |b| {
+        b.iter(|| {
+            black_box(to_bytes::<_, 256, Failure>(black_box(&values)).unwrap());
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, copy_benchmark);
+criterion_main!(benches);
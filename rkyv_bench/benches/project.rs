@@ -0,0 +1,54 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rkyv::{
+    access_unchecked, project, rancor::Failure, to_bytes, Archive, Archived,
+    Serialize,
+};
+
+const LEN: u32 = 1_000_000;
+
+// 264 bytes archived, so each element spans several cache lines
+#[derive(Archive, Serialize)]
+#[archive(project)]
+struct Wide {
+    id: u32,
+    payload: [u64; 32],
+    score: f32,
+}
+
+pub fn project_benchmark(c: &mut Criterion) {
+    let items = (0..LEN)
+        .map(|i| Wide {
+            id: i,
+            payload: [i as u64; 32],
+            score: i as f32,
+        })
+        .collect::<Vec<_>>();
+    let bytes = to_bytes::<_, 256, Failure>(&items).unwrap();
+    let archived = unsafe { access_unchecked::<Archived<Vec<Wide>>>(&bytes) };
+
+    let mut group = c.benchmark_group("project");
+    group.bench_function("naive", |b| {
+        b.iter(|| {
+            black_box(archived).iter().map(|x| (&x.id, &x.score)).fold(
+                0f32,
+                |sum, (id, score)| {
+                    sum + id.to_native() as f32 * score.to_native()
+                },
+            )
+        })
+    });
+    group.bench_function("projected", |b| {
+        b.iter(|| {
+            project!(black_box(archived) => (id, score)).fold(
+                0f32,
+                |sum, (id, score)| {
+                    sum + id.to_native() as f32 * score.to_native()
+                },
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, project_benchmark);
+criterion_main!(benches);
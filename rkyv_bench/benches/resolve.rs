@@ -0,0 +1,71 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rkyv::{
+    rancor::{Failure, Fallible},
+    to_bytes, Archive, Serialize,
+};
+
+const LEN: u32 = 10_000_000;
+
+// Not copy-optimized because of the padding after `flag`
+#[derive(Archive, Clone, Serialize)]
+pub struct Small {
+    id: u32,
+    flag: bool,
+    count: u16,
+}
+
+// Archives the same as `Small`, but always takes the slow path because its
+// resolver isn't zero-sized.
+#[derive(Clone)]
+pub struct SlowPath(Small);
+
+impl Archive for SlowPath {
+    type Archived = ArchivedSmall;
+    type Resolver = (SmallResolver, u8);
+
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        self.0.resolve(pos, resolver.0, out);
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for SlowPath {
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        Ok((self.0.serialize(serializer)?, 0))
+    }
+}
+
+pub fn resolve_benchmark(c: &mut Criterion) {
+    let values = (0..LEN)
+        .map(|i| Small {
+            id: i,
+            flag: i % 2 == 0,
+            count: i as u16,
+        })
+        .collect::<Vec<_>>();
+    let slow = values.iter().cloned().map(SlowPath).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("resolve");
+    group.sample_size(10);
+    group.bench_function("zst resolvers", |b| {
+        b.iter(|| {
+            black_box(to_bytes::<_, 256, Failure>(black_box(&values)).unwrap());
+        })
+    });
+    group.bench_function("stored resolvers", |b| {
+        b.iter(|| {
+            black_box(to_bytes::<_, 256, Failure>(black_box(&slow)).unwrap());
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, resolve_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rkyv::{
+    access,
+    rancor::Failure,
+    string::ArchivedString,
+    to_bytes,
+    vec::{ArchivedDenseStrings, ArchivedVec},
+};
+
+const LEN: usize = 1_000_000;
+
+pub fn strings_benchmark(c: &mut Criterion) {
+    // Short, mostly out-of-line strings with some non-ASCII characters
+    let strings = (0..LEN)
+        .map(|i| format!("string #{} (\u{e9}\u{20ac})", i))
+        .collect::<Vec<_>>();
+    // Dense strings have the same format as a plain vec of strings
+    let bytes = to_bytes::<_, 256, Failure>(&strings).unwrap();
+
+    let mut group = c.benchmark_group("strings");
+    group.sample_size(20);
+    group.bench_function("validate each", |b| {
+        b.iter(|| {
+            black_box(
+                access::<ArchivedVec<ArchivedString>, Failure>(black_box(
+                    &bytes,
+                ))
+                .unwrap(),
+            );
+        })
+    });
+    group.bench_function("validate dense", |b| {
+        b.iter(|| {
+            black_box(
+                access::<ArchivedDenseStrings, Failure>(black_box(&bytes))
+                    .unwrap(),
+            );
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, strings_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rkyv::{
+    access_unchecked, hash::Hashed, rancor::Failure, to_bytes, with::Prehashed,
+    Archive, Archived, Serialize,
+};
+
+const LEN: u32 = 1_000_000;
+const BATCH_LEN: u32 = 10_000;
+const SERIALIZE_LEN: u32 = 10_000_000;
+
+pub fn swiss_table_benchmark(c: &mut Criterion) {
+    let map = (0..LEN).map(|i| (i * 2, i)).collect::<HashMap<_, _>>();
+    let bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+    let archived =
+        unsafe { access_unchecked::<Archived<HashMap<u32, u32>>>(&bytes) };
+
+    // Half of the keys are in the map and half are missing
+    let keys = (0..BATCH_LEN)
+        .map(|i| Archived::<u32>::from_native(i * 97))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("swiss_table");
+    let mut bench_get = |name: &str| {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for key in black_box(&keys) {
+                    black_box(archived.get(key));
+                }
+            })
+        });
+    };
+
+    #[cfg(not(feature = "runtime-simd"))]
+    bench_get("get");
+    #[cfg(feature = "runtime-simd")]
+    for scanner in rkyv::collections::swiss_table::Scanner::ALL {
+        if scanner.is_supported() {
+            scanner.select();
+            bench_get(&format!("get {:?}", scanner));
+        }
+    }
+
+    group.finish();
+}
+
+#[derive(Archive, Serialize)]
+struct PrehashedMap {
+    #[with(Prehashed)]
+    map: HashMap<Hashed<String>, u32>,
+}
+
+pub fn swiss_table_serialize_benchmark(c: &mut Criterion) {
+    let map = (0..SERIALIZE_LEN)
+        .map(|i| (format!("key {}", i), i))
+        .collect::<HashMap<_, _>>();
+    let prehashed = PrehashedMap {
+        map: map
+            .iter()
+            .map(|(key, value)| (Hashed::new(key.clone()), *value))
+            .collect(),
+    };
+
+    let mut group = c.benchmark_group("swiss_table_serialize");
+    group.sample_size(10);
+    group.bench_function("rehash", |b| {
+        b.iter(|| to_bytes::<_, 256, Failure>(black_box(&map)).unwrap())
+    });
+    group.bench_function("prehashed", |b| {
+        b.iter(|| to_bytes::<_, 256, Failure>(black_box(&prehashed)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    swiss_table_benchmark,
+    swiss_table_serialize_benchmark
+);
+criterion_main!(benches);
@@ -2,12 +2,8 @@ use core::mem::MaybeUninit;
 use rand::Rng;
 use rand_pcg::Lcg64Xsh32;
 use rkyv::{
-    archived_root, check_archived_root,
-    ser::{
-        serializers::{AlignedSerializer, BufferScratch, CompositeSerializer},
-        Serializer,
-    },
-    AlignedVec, Archive, Deserialize, Infallible, Serialize,
+    access, access_unchecked, from_bytes, from_bytes_unchecked,
+    rancor::Failure, to_bytes, Archive, Archived, Deserialize, Serialize,
 };
 use std::collections::HashMap;
 
@@ -16,9 +12,7 @@ trait Generate {
 }
 
 impl Generate for () {
-    fn generate<R: Rng>(_: &mut R) -> Self {
-        ()
-    }
+    fn generate<R: Rng>(_: &mut R) -> Self {}
 }
 
 impl Generate for bool {
@@ -126,7 +120,7 @@ pub struct Item {
 
 impl Generate for Item {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const IDS: [&'static str; 8] = [
+        const IDS: [&str; 8] = [
             "dirt",
             "stone",
             "pickaxe",
@@ -204,11 +198,11 @@ pub struct Entity {
 
 impl Generate for Entity {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const IDS: [&'static str; 8] = [
+        const IDS: [&str; 8] = [
             "cow", "sheep", "zombie", "skeleton", "spider", "creeper",
             "parrot", "bee",
         ];
-        const CUSTOM_NAMES: [&'static str; 8] = [
+        const CUSTOM_NAMES: [&str; 8] = [
             "rainbow", "princess", "steve", "johnny", "missy", "coward",
             "fairy", "howard",
         ];
@@ -255,7 +249,7 @@ pub struct RecipeBook {
 
 impl Generate for RecipeBook {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const RECIPES: [&'static str; 8] = [
+        const RECIPES: [&str; 8] = [
             "pickaxe",
             "torch",
             "bow",
@@ -346,7 +340,7 @@ pub struct Player {
 
 impl Generate for Player {
     fn generate<R: Rng>(rng: &mut R) -> Self {
-        const DIMENSIONS: [&'static str; 3] = ["overworld", "nether", "end"];
+        const DIMENSIONS: [&str; 3] = ["overworld", "nether", "end"];
         const MAX_ITEMS: usize = 40;
         const MAX_ENDER_ITEMS: usize = 27;
         Self {
@@ -388,7 +382,7 @@ impl Generate for Player {
 }
 
 fn generate_player_name<R: Rng>(rng: &mut R) -> String {
-    const LEGAL_CHARS: &'static [u8] =
+    const LEGAL_CHARS: &[u8] =
         b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_";
 
     let len = rng.gen_range(10..40);
@@ -417,34 +411,21 @@ fn main() {
         players.insert(name, Player::generate(&mut rng));
     }
 
-    const BUFFER_LEN: usize = 10_000_000;
     const SCRATCH_LEN: usize = 512_000;
 
-    let mut serialize_buffer = AlignedVec::with_capacity(BUFFER_LEN);
-    let mut serialize_scratch = AlignedVec::with_capacity(SCRATCH_LEN);
-    unsafe {
-        serialize_scratch.set_len(SCRATCH_LEN);
-    }
-    serialize_buffer.clear();
-    let mut serializer = CompositeSerializer::new(
-        AlignedSerializer::new(&mut serialize_buffer),
-        BufferScratch::new(&mut serialize_scratch),
-        Infallible,
-    );
-    serializer.serialize_value(&players).unwrap();
+    type ArchivedPlayers = Archived<Players>;
 
-    let buf = serializer.into_serializer().into_inner();
+    let buf = to_bytes::<_, SCRATCH_LEN, Failure>(&players).unwrap();
 
-    unsafe { archived_root::<Players>(buf.as_ref()) };
-    check_archived_root::<Players>(buf.as_ref()).unwrap();
+    unsafe { access_unchecked::<ArchivedPlayers>(&buf) };
+    access::<ArchivedPlayers, Failure>(&buf).unwrap();
 
-    let value = unsafe { archived_root::<Players>(buf.as_ref()) };
-    let deserialized: Players = value.deserialize(&mut Infallible).unwrap();
+    let deserialized =
+        unsafe { from_bytes_unchecked::<Players, Failure>(&buf) }.unwrap();
 
     println!("{:?}", deserialized);
 
-    let value = check_archived_root::<Players>(buf.as_ref()).unwrap();
-    let deserialized: Players = value.deserialize(&mut Infallible).unwrap();
+    let deserialized = from_bytes::<Players, Failure>(&buf).unwrap();
 
     println!("{:?}", deserialized);
 }
@@ -0,0 +1,495 @@
+//! A reference decoder which reads archives using only their descriptions.
+
+use core::str;
+
+use rkyv::layout::Endianness;
+use serde_json::{Map, Value};
+
+use crate::{value, Format, Mismatch};
+
+/// The maximum depth of nested values. Archives nested more deeply, including
+/// archives with pointer cycles, fail to decode.
+const MAX_DEPTH: usize = 128;
+
+/// The flag in the metadata of a B-tree node which marks inner nodes.
+const INNER_NODE_FLAG: u128 = 0x80_00;
+
+/// Returns the field of a description with the given name.
+pub fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, Mismatch> {
+    value
+        .get(name)
+        .ok_or_else(|| Mismatch::Description(format!("missing `{}`", name)))
+}
+
+/// Returns the field of a description with the given name as a `usize`.
+pub fn usize_field(value: &Value, name: &str) -> Result<usize, Mismatch> {
+    field(value, name)?
+        .as_u64()
+        .and_then(|value| usize::try_from(value).ok())
+        .ok_or_else(|| {
+            Mismatch::Description(format!("`{}` is not a size", name))
+        })
+}
+
+/// Returns the field of a description with the given name as a string.
+pub fn str_field<'a>(
+    value: &'a Value,
+    name: &str,
+) -> Result<&'a str, Mismatch> {
+    field(value, name)?.as_str().ok_or_else(|| {
+        Mismatch::Description(format!("`{}` is not a string", name))
+    })
+}
+
+/// Returns the field of a description with the given name as an array.
+pub fn array_field<'a>(
+    value: &'a Value,
+    name: &str,
+) -> Result<&'a [Value], Mismatch> {
+    field(value, name)?
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| {
+            Mismatch::Description(format!("`{}` is not an array", name))
+        })
+}
+
+/// Decodes values from an archive by following their layouts.
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    format: Format,
+}
+
+impl<'a> Decoder<'a> {
+    /// Returns a decoder for an archive in the given format.
+    pub fn new(bytes: &'a [u8], format: Format) -> Self {
+        Self { bytes, format }
+    }
+
+    /// Decodes the value with the given layout at `pos`.
+    pub fn decode(
+        &self,
+        layout: &Value,
+        pos: usize,
+    ) -> Result<Value, Mismatch> {
+        self.node(layout, pos, 0, 0)
+    }
+
+    fn word_size(&self) -> usize {
+        self.format.pointer_width as usize / 8
+    }
+
+    fn align_of(&self, size: usize) -> usize {
+        if self.format.aligned {
+            size
+        } else {
+            1
+        }
+    }
+
+    fn read(&self, pos: usize, size: usize) -> Result<&'a [u8], Mismatch> {
+        pos.checked_add(size)
+            .and_then(|end| self.bytes.get(pos..end))
+            .ok_or(Mismatch::OutOfBounds { pos, size })
+    }
+
+    fn read_uint(
+        &self,
+        pos: usize,
+        size: usize,
+        endianness: Endianness,
+    ) -> Result<u128, Mismatch> {
+        let bytes = self.read(pos, size)?;
+        let fold = |value: u128, byte: &u8| value << 8 | *byte as u128;
+        Ok(match endianness {
+            Endianness::Little => bytes.iter().rev().fold(0, fold),
+            Endianness::Big => bytes.iter().fold(0, fold),
+        })
+    }
+
+    fn read_int(
+        &self,
+        pos: usize,
+        size: usize,
+        endianness: Endianness,
+    ) -> Result<i128, Mismatch> {
+        let shift = 128 - 8 * size as u32;
+        let value = self.read_uint(pos, size, endianness)?;
+        Ok(((value << shift) as i128) >> shift)
+    }
+
+    fn read_usize(&self, pos: usize) -> Result<usize, Mismatch> {
+        let value =
+            self.read_uint(pos, self.word_size(), self.format.endianness)?;
+        usize::try_from(value)
+            .map_err(|_| Mismatch::InvalidValue { pos, kind: "usize" })
+    }
+
+    fn offset(&self, pos: usize, offset: i128) -> Result<usize, Mismatch> {
+        match usize::try_from(pos as i128 + offset) {
+            Ok(target) if target <= self.bytes.len() => Ok(target),
+            _ => Err(Mismatch::InvalidPointer { pos }),
+        }
+    }
+
+    fn follow(&self, pos: usize) -> Result<usize, Mismatch> {
+        let offset =
+            self.read_int(pos, self.word_size(), self.format.endianness)?;
+        self.offset(pos, offset)
+    }
+
+    fn node(
+        &self,
+        layout: &Value,
+        pos: usize,
+        len: usize,
+        depth: usize,
+    ) -> Result<Value, Mismatch> {
+        if depth > MAX_DEPTH {
+            return Err(Mismatch::DepthExceeded);
+        }
+        let depth = depth + 1;
+
+        let size = usize_field(layout, "size")?;
+        let align = usize_field(layout, "align")?;
+        if align == 0 {
+            return Err(Mismatch::Description("`align` is zero".to_string()));
+        }
+        self.read(pos, size)?;
+        if !pos.is_multiple_of(align) {
+            return Err(Mismatch::Misaligned { pos, align });
+        }
+
+        match str_field(layout, "kind")? {
+            "scalar" => self.scalar(str_field(layout, "scalar")?, pos),
+            "struct" => {
+                let fields = array_field(layout, "fields")?;
+                Ok(Value::Object(self.fields(fields, pos, depth)?))
+            }
+            "enum" => {
+                let tag_size = usize_field(layout, "tag_size")?;
+                let tag =
+                    self.read_uint(pos, tag_size, self.format.tag_endianness)?;
+                let variant = usize::try_from(tag)
+                    .ok()
+                    .and_then(|tag| {
+                        array_field(layout, "variants").ok()?.get(tag)
+                    })
+                    .ok_or(Mismatch::InvalidValue {
+                        pos,
+                        kind: "enum tag",
+                    })?;
+                let fields = array_field(variant, "fields")?;
+                let fields = self.fields(fields, pos, depth)?;
+                Ok(value::variant(str_field(variant, "name")?, fields))
+            }
+            "array" => {
+                let element = field(layout, "element")?;
+                let len = usize_field(layout, "len")?;
+                self.elements(element, pos, len, depth)
+            }
+            "slice" => {
+                self.elements(field(layout, "element")?, pos, len, depth)
+            }
+            "str" => self.string(pos, len),
+            "string" => self.archived_string(pos),
+            "vec" => {
+                let target = self.follow(pos)?;
+                let len = self.read_usize(pos + self.word_size())?;
+                self.elements(field(layout, "element")?, target, len, depth)
+            }
+            "option" => {
+                let inner = field(layout, "inner")?;
+                match self.read(pos, 1)?[0] {
+                    0 => Ok(Value::Null),
+                    1 => {
+                        let offset = size
+                            .checked_sub(usize_field(inner, "size")?)
+                            .ok_or_else(|| {
+                                Mismatch::Description(
+                                    "option is smaller than its value"
+                                        .to_string(),
+                                )
+                            })?;
+                        let value = self.node(inner, pos + offset, 0, depth)?;
+                        Ok(value::some(value))
+                    }
+                    _ => Err(Mismatch::InvalidValue {
+                        pos,
+                        kind: "option tag",
+                    }),
+                }
+            }
+            "pointer" => {
+                let target = field(layout, "target")?;
+                let target_pos = self.follow(pos)?;
+                let len = match str_field(target, "kind")? {
+                    "slice" | "str" => {
+                        self.read_usize(pos + self.word_size())?
+                    }
+                    _ => 0,
+                };
+                self.node(target, target_pos, len, depth)
+            }
+            "hash_map" => {
+                let key = field(layout, "key")?;
+                let value = field(layout, "value")?;
+                let entry_size = usize_field(layout, "entry_size")?;
+                let value_offset = usize_field(layout, "value_offset")?;
+                self.hash_table(pos, entry_size, |pos| {
+                    Ok(value::entry(
+                        self.node(key, pos, 0, depth)?,
+                        self.node(value, pos + value_offset, 0, depth)?,
+                    ))
+                })
+            }
+            "hash_set" => {
+                let element = field(layout, "element")?;
+                let element_size = usize_field(element, "size")?;
+                self.hash_table(pos, element_size, |pos| {
+                    self.node(element, pos, 0, depth)
+                })
+            }
+            "btree_map" => self.btree(layout, pos, depth),
+            kind => {
+                Err(Mismatch::Description(format!("unknown kind `{}`", kind)))
+            }
+        }
+    }
+
+    fn scalar(&self, name: &str, pos: usize) -> Result<Value, Mismatch> {
+        let endianness = self.format.endianness;
+        let int = |size| self.read_int(pos, size, endianness);
+        let uint = |size| self.read_uint(pos, size, endianness);
+        Ok(match name {
+            "bool" => match uint(1)? {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                _ => return Err(Mismatch::InvalidValue { pos, kind: "bool" }),
+            },
+            "i8" => Value::from(int(1)? as i64),
+            "i16" => Value::from(int(2)? as i64),
+            "i32" => Value::from(int(4)? as i64),
+            "i64" => Value::from(int(8)? as i64),
+            "i128" => value::wide(int(16)?),
+            "u8" => Value::from(uint(1)? as u64),
+            "u16" => Value::from(uint(2)? as u64),
+            "u32" => Value::from(uint(4)? as u64),
+            "u64" => Value::from(uint(8)? as u64),
+            "u128" => value::wide(uint(16)?),
+            "f32" => value::float(f32::from_bits(uint(4)? as u32) as f64),
+            "f64" => value::float(f64::from_bits(uint(8)? as u64)),
+            "char" => match char::from_u32(uint(4)? as u32) {
+                Some(c) => Value::from(c.to_string()),
+                None => {
+                    return Err(Mismatch::InvalidValue { pos, kind: "char" })
+                }
+            },
+            name => {
+                return Err(Mismatch::Description(format!(
+                    "unknown scalar `{}`",
+                    name
+                )))
+            }
+        })
+    }
+
+    fn fields(
+        &self,
+        fields: &[Value],
+        pos: usize,
+        depth: usize,
+    ) -> Result<Map<String, Value>, Mismatch> {
+        let mut map = Map::new();
+        for field in fields {
+            let name = str_field(field, "name")?;
+            let offset = usize_field(field, "offset")?;
+            let value =
+                self.node(self::field(field, "type")?, pos + offset, 0, depth)?;
+            map.insert(name.to_string(), value);
+        }
+        Ok(map)
+    }
+
+    fn elements(
+        &self,
+        element: &Value,
+        pos: usize,
+        len: usize,
+        depth: usize,
+    ) -> Result<Value, Mismatch> {
+        let size = usize_field(element, "size")?;
+        let total = size.checked_mul(len).ok_or(Mismatch::OutOfBounds {
+            pos,
+            size: usize::MAX,
+        })?;
+        self.read(pos, total)?;
+        // Zero-sized elements don't take up any space, so their number is
+        // only limited by the length of the archive.
+        if size == 0 && len > self.bytes.len() {
+            return Err(Mismatch::InvalidValue {
+                pos,
+                kind: "length",
+            });
+        }
+        (0..len)
+            .map(|i| self.node(element, pos + i * size, 0, depth))
+            .collect()
+    }
+
+    fn string(&self, pos: usize, len: usize) -> Result<Value, Mismatch> {
+        match str::from_utf8(self.read(pos, len)?) {
+            Ok(s) => Ok(Value::from(s)),
+            Err(_) => Err(Mismatch::InvalidValue { pos, kind: "UTF-8" }),
+        }
+    }
+
+    // Strings of up to two words minus one byte are stored inline, with their
+    // length in the last byte. Longer strings store their length in the first
+    // word and a negative little-endian offset to their bytes in the second.
+    // The sign bit of the offset is in the last byte, which distinguishes them
+    // from inline strings.
+    fn archived_string(&self, pos: usize) -> Result<Value, Mismatch> {
+        let word_size = self.word_size();
+        let last = self.read(pos + 2 * word_size - 1, 1)?[0];
+        if last & 0x80 == 0 {
+            if last as usize > 2 * word_size - 1 {
+                return Err(Mismatch::InvalidValue {
+                    pos,
+                    kind: "string length",
+                });
+            }
+            self.string(pos, last as usize)
+        } else {
+            let len = self.read_usize(pos)?;
+            let offset =
+                self.read_int(pos + word_size, word_size, Endianness::Little)?;
+            self.string(self.offset(pos, offset)?, len)
+        }
+    }
+
+    // Hash tables are a relative pointer to their control bytes, their length,
+    // and their capacity. The high bit of the capacity is set if the table
+    // also stores the order of its entries. The control byte of each full
+    // bucket has its high bit cleared, and the buckets are stored in reverse
+    // order before the control bytes.
+    fn hash_table(
+        &self,
+        pos: usize,
+        bucket_size: usize,
+        mut entry: impl FnMut(usize) -> Result<Value, Mismatch>,
+    ) -> Result<Value, Mismatch> {
+        let word_size = self.word_size();
+        let control = self.follow(pos)?;
+        let len = self.read_usize(pos + word_size)?;
+        let cap = self.read_uint(
+            pos + 2 * word_size,
+            word_size,
+            self.format.endianness,
+        )?;
+        let ordered_flag = 1 << (self.format.pointer_width - 1);
+        let cap = (cap & !ordered_flag) as usize;
+
+        let mut entries = Vec::new();
+        if len == 0 {
+            return Ok(Value::Array(entries));
+        }
+        let controls = self.read(control, cap)?;
+        let buckets = bucket_size
+            .checked_mul(cap)
+            .and_then(|size| control.checked_sub(size))
+            .ok_or(Mismatch::OutOfBounds {
+                pos: control,
+                size: usize::MAX,
+            })?;
+        for (bucket, control) in controls.iter().enumerate() {
+            if control & 0x80 == 0 {
+                entries
+                    .push(entry(buckets + (cap - bucket - 1) * bucket_size)?);
+            }
+        }
+        if entries.len() != len {
+            return Err(Mismatch::InvalidValue {
+                pos,
+                kind: "hash table length",
+            });
+        }
+        Ok(Value::Array(entries))
+    }
+
+    // B-trees are their length and a relative pointer to their root node.
+    // Nodes start with a header of a `u16` with the inner node flag in its
+    // high bit and the number of entries in its low bits, the size of the
+    // node, and a relative pointer. Inner nodes point to their first child and
+    // leaf nodes point to the next leaf. The entries of leaf nodes follow the
+    // header, and are laid out like a `#[repr(C)]` struct of a key and a
+    // value.
+    fn btree(
+        &self,
+        layout: &Value,
+        pos: usize,
+        depth: usize,
+    ) -> Result<Value, Mismatch> {
+        let key = field(layout, "key")?;
+        let value = field(layout, "value")?;
+        let word_size = self.word_size();
+        let len = self.read_usize(pos)?;
+        let root = self.follow(pos + word_size)?;
+
+        let mut entries = Vec::new();
+        if len == 0 {
+            return Ok(Value::Array(entries));
+        }
+
+        let word_align = self.align_of(word_size);
+        let ptr_offset = 2usize.next_multiple_of(word_align) + word_size;
+        let header_size = (ptr_offset + word_size)
+            .next_multiple_of(word_align.max(self.align_of(2)));
+        let key_align = usize_field(key, "align")?.max(1);
+        let value_align = usize_field(value, "align")?.max(1);
+        let value_offset =
+            usize_field(key, "size")?.next_multiple_of(value_align);
+        let entry_align = key_align.max(value_align);
+        let entry_size = (value_offset + usize_field(value, "size")?)
+            .next_multiple_of(entry_align);
+        let entries_offset = header_size.next_multiple_of(entry_align);
+
+        let meta = |node| self.read_uint(node, 2, self.format.endianness);
+        let mut node = root;
+        let mut levels = 0;
+        while meta(node)? & INNER_NODE_FLAG != 0 {
+            levels += 1;
+            if levels > MAX_DEPTH {
+                return Err(Mismatch::DepthExceeded);
+            }
+            node = self.follow(node + ptr_offset)?;
+        }
+
+        // Every leaf takes up at least the size of its header, so visiting
+        // more leaves than could fit in the archive means that they form a
+        // cycle.
+        let max_leaves = self.bytes.len() / entries_offset;
+        let mut leaves = 0;
+        while entries.len() < len {
+            let meta = meta(node)?;
+            let count = (meta & !INNER_NODE_FLAG) as usize;
+            leaves += 1;
+            if meta & INNER_NODE_FLAG != 0 || count == 0 || leaves > max_leaves
+            {
+                return Err(Mismatch::InvalidValue {
+                    pos: node,
+                    kind: "B-tree node",
+                });
+            }
+            for i in 0..count.min(len - entries.len()) {
+                let pos = node + entries_offset + i * entry_size;
+                entries.push(value::entry(
+                    self.node(key, pos, 0, depth)?,
+                    self.node(value, pos + value_offset, 0, depth)?,
+                ));
+            }
+            node = self.follow(node + ptr_offset)?;
+        }
+        Ok(Value::Array(entries))
+    }
+}
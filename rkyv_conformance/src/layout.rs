@@ -0,0 +1,144 @@
+//! JSON descriptions of the layouts of archived types.
+//!
+//! See the [crate docs](crate#layouts) for a description of each layout.
+
+use rkyv::reflect::{
+    DescriptorKind, FieldDescriptor, ScalarKind, TypeDescriptor,
+    VariantDescriptor,
+};
+use serde_json::{json, Map, Value};
+
+/// The maximum depth of a layout. Recursive types can't be described, because
+/// their layouts are infinitely deep.
+const MAX_DEPTH: usize = 64;
+
+/// Describes the layout of the type with the given descriptor.
+///
+/// # Panics
+///
+/// Panics if the type is recursive.
+pub fn describe(descriptor: &TypeDescriptor) -> Value {
+    layout(descriptor, 0)
+}
+
+fn layout(descriptor: &TypeDescriptor, depth: usize) -> Value {
+    assert!(
+        depth <= MAX_DEPTH,
+        "the layout of {} is too deep to describe",
+        descriptor.name,
+    );
+    let depth = depth + 1;
+
+    let mut map = Map::new();
+    map.insert("name".to_string(), Value::from(descriptor.name));
+    map.insert("size".to_string(), Value::from(descriptor.size));
+    map.insert("align".to_string(), Value::from(descriptor.align));
+
+    let mut insert = |key: &str, value: Value| {
+        map.insert(key.to_string(), value);
+    };
+    match descriptor.kind {
+        DescriptorKind::Scalar(kind) => {
+            insert("kind", Value::from("scalar"));
+            insert("scalar", Value::from(scalar_name(kind)));
+        }
+        DescriptorKind::Struct { fields } => {
+            insert("kind", Value::from("struct"));
+            insert("fields", describe_fields(fields, depth));
+        }
+        DescriptorKind::Enum { tag_size, variants } => {
+            insert("kind", Value::from("enum"));
+            insert("tag_size", Value::from(tag_size));
+            insert("variants", describe_variants(variants, depth));
+        }
+        DescriptorKind::Array { element, len } => {
+            insert("kind", Value::from("array"));
+            insert("element", layout(element(), depth));
+            insert("len", Value::from(len));
+        }
+        DescriptorKind::Slice { element } => {
+            insert("kind", Value::from("slice"));
+            insert("element", layout(element(), depth));
+        }
+        DescriptorKind::Str => insert("kind", Value::from("str")),
+        DescriptorKind::String => insert("kind", Value::from("string")),
+        DescriptorKind::Vec { element } => {
+            insert("kind", Value::from("vec"));
+            insert("element", layout(element(), depth));
+        }
+        DescriptorKind::Option { inner } => {
+            insert("kind", Value::from("option"));
+            insert("inner", layout(inner(), depth));
+        }
+        DescriptorKind::Pointer { target } => {
+            insert("kind", Value::from("pointer"));
+            insert("target", layout(target(), depth));
+        }
+        DescriptorKind::HashMap {
+            key,
+            value,
+            entry_size,
+            value_offset,
+        } => {
+            insert("kind", Value::from("hash_map"));
+            insert("key", layout(key(), depth));
+            insert("value", layout(value(), depth));
+            insert("entry_size", Value::from(entry_size));
+            insert("value_offset", Value::from(value_offset));
+        }
+        DescriptorKind::HashSet { element } => {
+            insert("kind", Value::from("hash_set"));
+            insert("element", layout(element(), depth));
+        }
+        DescriptorKind::BTreeMap { key, value } => {
+            insert("kind", Value::from("btree_map"));
+            insert("key", layout(key(), depth));
+            insert("value", layout(value(), depth));
+        }
+    }
+    Value::Object(map)
+}
+
+fn describe_fields(fields: &[FieldDescriptor], depth: usize) -> Value {
+    fields
+        .iter()
+        .map(|field| {
+            json!({
+                "name": field.name,
+                "offset": field.offset,
+                "type": layout((field.ty)(), depth),
+            })
+        })
+        .collect()
+}
+
+fn describe_variants(variants: &[VariantDescriptor], depth: usize) -> Value {
+    variants
+        .iter()
+        .map(|variant| {
+            json!({
+                "name": variant.name,
+                "fields": describe_fields(variant.fields, depth),
+            })
+        })
+        .collect()
+}
+
+fn scalar_name(kind: ScalarKind) -> &'static str {
+    match kind {
+        ScalarKind::Bool => "bool",
+        ScalarKind::I8 => "i8",
+        ScalarKind::I16 => "i16",
+        ScalarKind::I32 => "i32",
+        ScalarKind::I64 => "i64",
+        ScalarKind::I128 => "i128",
+        ScalarKind::U8 => "u8",
+        ScalarKind::U16 => "u16",
+        ScalarKind::U32 => "u32",
+        ScalarKind::U64 => "u64",
+        ScalarKind::U128 => "u128",
+        ScalarKind::F32 => "f32",
+        ScalarKind::F64 => "f64",
+        ScalarKind::Char => "char",
+    }
+}
@@ -0,0 +1,578 @@
+//! Test vectors and a reference verifier for the rkyv archive format.
+//!
+//! Implementations of rkyv readers in other languages can check themselves
+//! against the test vectors in the `vectors` directory of this crate. Each
+//! test vector is an archive, `<name>.bin`, and a JSON description of it,
+//! `<name>.json`. Descriptions look like this:
+//!
+//! ```json
+//! {
+//!   "name": "option_some",
+//!   "format": {
+//!     "endianness": "little",
+//!     "tag_endianness": "little",
+//!     "pointer_width": 32,
+//!     "aligned": true
+//!   },
+//!   "archive_size": 16,
+//!   "root": {
+//!     "position": 0,
+//!     "layout": { "kind": "option", "name": "Option", ... }
+//!   },
+//!   "value": { "Some": 1234 }
+//! }
+//! ```
+//!
+//! The format is the byte order of primitives and enum tags, the size of
+//! relative pointers and archived `usize`s in bits, and whether primitives are
+//! aligned to their size. Enum tags are stored in the byte order of the target
+//! that wrote the archive, so they may differ from the byte order of the other
+//! primitives. The vectors for each format are in a separate directory, like
+//! `le_32` for little-endian archives with 32-bit pointers.
+//!
+//! The root object always ends at the end of the archive. Its layout describes
+//! the size and alignment of every type in it, and the value is what the
+//! archive decodes to.
+//!
+//! [`verify_vector`] decodes an archive using only its description, and checks
+//! that it decodes to the expected value. It doesn't depend on the format that
+//! this crate is compiled for, so it verifies the test vectors of every format
+//! and can serve as a reference for other readers. [`generate`] creates the
+//! test vectors for the format that this crate is compiled for, and the tests
+//! of this crate fail if they ever differ from the ones in the `vectors`
+//! directory. Run the tests with `RKYV_BLESS=1` to regenerate them after
+//! changing the format on purpose.
+//!
+//! # Layouts
+//!
+//! Every layout has a `kind`, a `name`, and a `size` and `align` in bytes.
+//! Offsets are in bytes from the start of the value which contains them.
+//!
+//! - `scalar`: a primitive, with a `scalar` of `bool`, `i8` through `i128`,
+//!   `u8` through `u128`, `f32`, `f64`, or `char`.
+//! - `struct`: `fields`, each of which has a `name`, an `offset`, and the
+//!   layout of its `type`.
+//! - `enum`: a tag of `tag_size` bytes at offset 0, and `variants` in tag
+//!   order, each of which has a `name` and `fields` like a struct.
+//! - `array`: `len` elements with the layout `element`.
+//! - `slice` and `str`: unsized types, which are only pointed to. Their
+//!   lengths are stored next to the relative pointers to them.
+//! - `string`: an archived string, which is inline if it fits in two words
+//!   minus one byte.
+//! - `vec`: a relative pointer to elements with the layout `element` and a
+//!   length.
+//! - `option`: a tag byte at offset 0, and a value with the layout `inner` at
+//!   the end of the option.
+//! - `pointer`: a relative pointer to a value with the layout `target`,
+//!   followed by the length of the target if it's unsized.
+//! - `hash_map` and `hash_set`: a Swiss table of entries of `entry_size`
+//!   bytes, with a `key` at offset 0 and a `value` at `value_offset`, or of
+//!   `element`s.
+//! - `btree_map`: a B-tree of `key`s and `value`s.
+//!
+//! See the decoder in this crate for the details of strings, hash tables, and
+//! B-trees.
+//!
+//! # Values
+//!
+//! - Booleans, strings, and integers up to 64 bits are JSON booleans, strings,
+//!   and numbers.
+//! - 128-bit integers are decimal strings.
+//! - Floating-point numbers are JSON numbers, except for `"inf"`, `"-inf"`,
+//!   and `"NaN"`.
+//! - `char`s are strings.
+//! - Structs are objects of their fields, and enums are objects of their
+//!   `variant` name and `fields`.
+//! - Arrays, slices, vecs, and sets are arrays.
+//! - Options are `null` for `None` and `{ "Some": value }` for `Some`.
+//! - Pointers are the value they point to.
+//! - Maps are arrays of `[key, value]` pairs, in the order they're stored.
+
+mod decode;
+mod layout;
+mod value;
+mod vectors;
+
+use core::{fmt, mem::size_of};
+use std::{fs, io, path::Path};
+
+use rkyv::{
+    layout::Endianness,
+    primitive::{ArchivedU16, ArchivedUsize},
+};
+use serde_json::{json, Value};
+
+use crate::decode::{field, str_field, usize_field, Decoder};
+pub use crate::vectors::generate;
+
+/// The format of an archive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Format {
+    /// The byte order of multibyte primitives.
+    pub endianness: Endianness,
+    /// The byte order of multibyte enum tags.
+    pub tag_endianness: Endianness,
+    /// The size of relative pointers and archived `usize`s, in bits.
+    pub pointer_width: u32,
+    /// Whether primitives are aligned to their size.
+    pub aligned: bool,
+}
+
+impl Format {
+    /// Returns the format that this crate writes archives in.
+    pub fn current() -> Self {
+        let one = ArchivedU16::from_native(1);
+        // SAFETY: `ArchivedU16` is two bytes, and any two bytes are a valid
+        // `[u8; 2]`.
+        let bytes =
+            unsafe { core::mem::transmute::<ArchivedU16, [u8; 2]>(one) };
+        Self {
+            endianness: endianness(bytes[0] == 1),
+            tag_endianness: endianness(cfg!(target_endian = "little")),
+            pointer_width: 8 * size_of::<ArchivedUsize>() as u32,
+            aligned: core::mem::align_of::<ArchivedU16>() == 2,
+        }
+    }
+
+    /// Returns the name of the directory of the test vectors in this format.
+    pub fn name(&self) -> String {
+        let endianness = match self.endianness {
+            Endianness::Little => "le",
+            Endianness::Big => "be",
+        };
+        let unaligned = if self.aligned { "" } else { "_unaligned" };
+        format!("{}_{}{}", endianness, self.pointer_width, unaligned)
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "endianness": endianness_name(self.endianness),
+            "tag_endianness": endianness_name(self.tag_endianness),
+            "pointer_width": self.pointer_width,
+            "aligned": self.aligned,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Mismatch> {
+        let endianness = |name| match str_field(value, name)? {
+            "little" => Ok(Endianness::Little),
+            "big" => Ok(Endianness::Big),
+            _ => Err(Mismatch::Description(format!(
+                "`{}` is not an endianness",
+                name
+            ))),
+        };
+        let pointer_width = match usize_field(value, "pointer_width")? {
+            width @ (16 | 32 | 64) => width as u32,
+            _ => {
+                return Err(Mismatch::Description(
+                    "`pointer_width` is not 16, 32, or 64".to_string(),
+                ))
+            }
+        };
+        let aligned = field(value, "aligned")?.as_bool().ok_or_else(|| {
+            Mismatch::Description("`aligned` is not a boolean".to_string())
+        })?;
+        Ok(Self {
+            endianness: endianness("endianness")?,
+            tag_endianness: endianness("tag_endianness")?,
+            pointer_width,
+            aligned,
+        })
+    }
+}
+
+fn endianness(little: bool) -> Endianness {
+    if little {
+        Endianness::Little
+    } else {
+        Endianness::Big
+    }
+}
+
+fn endianness_name(endianness: Endianness) -> &'static str {
+    match endianness {
+        Endianness::Little => "little",
+        Endianness::Big => "big",
+    }
+}
+
+/// An archive and its description.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestVector {
+    /// The name of the test vector.
+    pub name: String,
+    /// The bytes of the archive.
+    pub bytes: Vec<u8>,
+    /// The description of the archive.
+    pub description: Value,
+}
+
+impl TestVector {
+    /// Checks that the archive matches its description.
+    ///
+    /// See [`verify_vector`] for details.
+    pub fn verify(&self) -> Result<(), Mismatch> {
+        verify_vector(&self.bytes, &self.description)
+    }
+}
+
+/// Writes test vectors to `<name>.bin` and `<name>.json` files in a
+/// directory, creating it if it doesn't exist.
+pub fn write_vectors(dir: &Path, vectors: &[TestVector]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for vector in vectors {
+        fs::write(dir.join(format!("{}.bin", vector.name)), &vector.bytes)?;
+        let mut json = serde_json::to_string_pretty(&vector.description)?;
+        json.push('\n');
+        fs::write(dir.join(format!("{}.json", vector.name)), json)?;
+    }
+    Ok(())
+}
+
+/// Reads the test vectors in a directory, sorted by name.
+pub fn read_vectors(dir: &Path) -> io::Result<Vec<TestVector>> {
+    let mut vectors = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        vectors.push(TestVector {
+            bytes: fs::read(path.with_extension("bin"))?,
+            description: serde_json::from_slice(&fs::read(&path)?)?,
+            name,
+        });
+    }
+    vectors.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(vectors)
+}
+
+/// An error resulting from an archive which doesn't match its description.
+#[derive(Debug)]
+pub enum Mismatch {
+    /// The description was missing a field or had a field of the wrong type.
+    Description(String),
+    /// The archive was not the size given by its description.
+    ArchiveSize {
+        /// The size given by the description.
+        expected: usize,
+        /// The size of the archive.
+        actual: usize,
+    },
+    /// The root object did not end at the end of the archive.
+    RootPosition {
+        /// The position of the root object.
+        position: usize,
+        /// The size of the root object.
+        size: usize,
+    },
+    /// A value extended past the end of the archive.
+    OutOfBounds {
+        /// The position of the value.
+        pos: usize,
+        /// The size of the value.
+        size: usize,
+    },
+    /// A value was not aligned for its type.
+    Misaligned {
+        /// The position of the value.
+        pos: usize,
+        /// The alignment of its type.
+        align: usize,
+    },
+    /// A relative pointer pointed outside of the archive.
+    InvalidPointer {
+        /// The position of the pointer.
+        pos: usize,
+    },
+    /// A value was not valid for its type.
+    InvalidValue {
+        /// The position of the value.
+        pos: usize,
+        /// What was invalid about the value.
+        kind: &'static str,
+    },
+    /// The archive was nested too deeply to decode.
+    DepthExceeded,
+    /// The archive decoded to a value other than the expected value.
+    Value {
+        /// The path to the value which differs, like `$.items[3]`.
+        path: String,
+        /// The expected value.
+        expected: Value,
+        /// The decoded value.
+        actual: Value,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Description(reason) => {
+                write!(f, "invalid description: {}", reason)
+            }
+            Mismatch::ArchiveSize { expected, actual } => write!(
+                f,
+                "archive is {} bytes but should be {} bytes",
+                actual, expected,
+            ),
+            Mismatch::RootPosition { position, size } => write!(
+                f,
+                "root object of {} bytes at {:#x} doesn't end at the end of \
+                 the archive",
+                size, position,
+            ),
+            Mismatch::OutOfBounds { pos, size } => write!(
+                f,
+                "{} bytes at {:#x} are out of bounds of the archive",
+                size, pos,
+            ),
+            Mismatch::Misaligned { pos, align } => {
+                write!(f, "{:#x} is not aligned to {} bytes", pos, align)
+            }
+            Mismatch::InvalidPointer { pos } => write!(
+                f,
+                "relative pointer at {:#x} points outside of the archive",
+                pos,
+            ),
+            Mismatch::InvalidValue { pos, kind } => {
+                write!(f, "invalid {} at {:#x}", kind, pos)
+            }
+            Mismatch::DepthExceeded => {
+                write!(f, "archive is nested too deeply to decode")
+            }
+            Mismatch::Value {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} decoded to {} but should be {}",
+                path, actual, expected,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// Checks that an archive matches its description.
+///
+/// The archive is decoded using only the format and layouts in the
+/// description, and must be the size that the description gives, have its root
+/// object at the end, and decode to the expected value.
+pub fn verify_vector(
+    bytes: &[u8],
+    description: &Value,
+) -> Result<(), Mismatch> {
+    let format = Format::from_json(field(description, "format")?)?;
+    let archive_size = usize_field(description, "archive_size")?;
+    if bytes.len() != archive_size {
+        return Err(Mismatch::ArchiveSize {
+            expected: archive_size,
+            actual: bytes.len(),
+        });
+    }
+
+    let root = field(description, "root")?;
+    let position = usize_field(root, "position")?;
+    let layout = field(root, "layout")?;
+    let size = usize_field(layout, "size")?;
+    if position.checked_add(size) != Some(bytes.len()) {
+        return Err(Mismatch::RootPosition { position, size });
+    }
+
+    let actual = Decoder::new(bytes, format).decode(layout, position)?;
+    compare(field(description, "value")?, &actual, &mut "$".to_string())
+}
+
+// Returns the first difference between the expected and actual values.
+fn compare(
+    expected: &Value,
+    actual: &Value,
+    path: &mut String,
+) -> Result<(), Mismatch> {
+    let len = path.len();
+    match (expected, actual) {
+        (Value::Array(expected), Value::Array(actual))
+            if expected.len() == actual.len() =>
+        {
+            for (i, (expected, actual)) in
+                expected.iter().zip(actual).enumerate()
+            {
+                path.push_str(&format!("[{}]", i));
+                compare(expected, actual, path)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        (Value::Object(expected), Value::Object(actual))
+            if expected.keys().eq(actual.keys()) =>
+        {
+            for (key, expected) in expected {
+                path.push_str(&format!(".{}", key));
+                compare(expected, &actual[key], path)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        _ if expected == actual => Ok(()),
+        _ => Err(Mismatch::Value {
+            path: path.clone(),
+            expected: expected.clone(),
+            actual: actual.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use serde_json::{json, Value};
+
+    use crate::{
+        generate, read_vectors, verify_vector, write_vectors, Format, Mismatch,
+        TestVector,
+    };
+
+    const VECTORS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/vectors");
+
+    fn get<'a>(vectors: &'a [TestVector], name: &str) -> &'a TestVector {
+        vectors.iter().find(|vector| vector.name == name).unwrap()
+    }
+
+    #[test]
+    fn generated_vectors_verify() {
+        for vector in generate() {
+            if let Err(e) = vector.verify() {
+                panic!("test vector `{}` failed to verify: {}", vector.name, e);
+            }
+        }
+    }
+
+    // Fails if the generated test vectors differ from the golden test vectors
+    // for the current format. Run with `RKYV_BLESS=1` to regenerate them.
+    #[test]
+    fn golden_vectors() {
+        let dir = Path::new(VECTORS).join(Format::current().name());
+        let actual = generate();
+
+        if std::env::var_os("RKYV_BLESS").is_some() {
+            let _ = fs::remove_dir_all(&dir);
+            write_vectors(&dir, &actual).unwrap();
+        }
+
+        let expected = read_vectors(&dir).unwrap_or_else(|_| {
+            panic!(
+                "missing golden test vectors at {}, run with RKYV_BLESS=1 to \
+                 generate them",
+                dir.display(),
+            )
+        });
+        let mut expected = expected.iter();
+        for actual in actual.iter() {
+            let expected = expected.next().unwrap_or_else(|| {
+                panic!("test vector `{}` is missing", actual.name)
+            });
+            assert_eq!(actual.name, expected.name, "missing test vectors");
+            assert!(
+                actual.bytes == expected.bytes,
+                "the archive of test vector `{}` changed",
+                actual.name,
+            );
+            assert_eq!(
+                actual.description, expected.description,
+                "the description of test vector `{}` changed",
+                actual.name,
+            );
+        }
+        assert_eq!(expected.next(), None, "extra golden test vectors");
+    }
+
+    // The verifier doesn't depend on the format that it's compiled for, so it
+    // checks the golden test vectors of every format.
+    #[test]
+    fn all_golden_vectors_verify() {
+        for dir in fs::read_dir(VECTORS).unwrap() {
+            let dir = dir.unwrap().path();
+            for vector in read_vectors(&dir).unwrap() {
+                if let Err(e) = vector.verify() {
+                    panic!(
+                        "test vector `{}` in {} failed to verify: {}",
+                        vector.name,
+                        dir.display(),
+                        e,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mismatches() {
+        let vectors = generate();
+
+        // Wrong expected values report the path to the first difference.
+        let vector = get(&vectors, "vec_strings");
+        let mut description = vector.description.clone();
+        description["value"][1] = json!("wrong");
+        match verify_vector(&vector.bytes, &description) {
+            Err(Mismatch::Value {
+                path,
+                expected,
+                actual,
+            }) => {
+                assert_eq!(path, "$[1]");
+                assert_eq!(expected, "wrong");
+                assert_eq!(actual, vector.description["value"][1]);
+            }
+            result => panic!("expected a value mismatch, got {:?}", result),
+        }
+
+        // Truncated archives and moved roots don't match their descriptions.
+        let truncated = &vector.bytes[1..];
+        assert!(matches!(
+            verify_vector(truncated, &vector.description),
+            Err(Mismatch::ArchiveSize { .. }),
+        ));
+        let mut description = vector.description.clone();
+        description["root"]["position"] = json!(0);
+        assert!(matches!(
+            verify_vector(&vector.bytes, &description),
+            Err(Mismatch::RootPosition { .. }),
+        ));
+
+        // Invalid values fail to decode.
+        let vector = get(&vectors, "bool");
+        assert!(matches!(
+            verify_vector(&[2], &vector.description),
+            Err(Mismatch::InvalidValue { kind: "bool", .. }),
+        ));
+        let vector = get(&vectors, "box_u32");
+        let mut bytes = vector.bytes.clone();
+        let position = bytes.len() - 4;
+        bytes[position..].copy_from_slice(&[0x7f; 4]);
+        assert!(matches!(
+            verify_vector(&bytes, &vector.description),
+            Err(Mismatch::InvalidPointer { .. }),
+        ));
+
+        // Descriptions must be complete.
+        let mut description = vector.description.clone();
+        description["format"]["pointer_width"] = json!(24);
+        assert!(matches!(
+            verify_vector(&vector.bytes, &description),
+            Err(Mismatch::Description(_)),
+        ));
+        assert!(matches!(
+            verify_vector(&vector.bytes, &Value::Null),
+            Err(Mismatch::Description(_)),
+        ));
+    }
+}
@@ -0,0 +1,46 @@
+//! The JSON encodings of decoded values.
+//!
+//! See the [crate docs](crate#values) for a description of each encoding.
+
+use serde_json::{Map, Value};
+
+/// Encodes a floating-point number.
+///
+/// Infinities and NaN don't have JSON numbers, so they are encoded as strings.
+pub fn float(value: f64) -> Value {
+    if value.is_nan() {
+        Value::from("NaN")
+    } else if value == f64::INFINITY {
+        Value::from("inf")
+    } else if value == f64::NEG_INFINITY {
+        Value::from("-inf")
+    } else {
+        Value::from(value)
+    }
+}
+
+/// Encodes a 128-bit integer as a decimal string, since JSON numbers can't
+/// hold all of them exactly.
+pub fn wide(value: impl ToString) -> Value {
+    Value::from(value.to_string())
+}
+
+/// Encodes a `Some`.
+pub fn some(value: Value) -> Value {
+    let mut map = Map::new();
+    map.insert("Some".to_string(), value);
+    Value::Object(map)
+}
+
+/// Encodes an enum variant and its fields.
+pub fn variant(name: &str, fields: Map<String, Value>) -> Value {
+    let mut map = Map::new();
+    map.insert("variant".to_string(), Value::from(name));
+    map.insert("fields".to_string(), Value::Object(fields));
+    Value::Object(map)
+}
+
+/// Encodes an entry of a map.
+pub fn entry(key: Value, value: Value) -> Value {
+    Value::Array(vec![key, value])
+}
@@ -0,0 +1,675 @@
+//! The test vectors.
+
+use core::{hash::Hash, mem::size_of};
+use std::{collections::BTreeMap, rc::Rc, sync::Arc};
+
+use rkyv::{
+    access_unchecked,
+    boxed::ArchivedBox,
+    collections::{
+        btree_map::ArchivedBTreeMap,
+        swiss_table::{
+            ArchivedHashMap, ArchivedHashSet, HashMapResolver, HashSetResolver,
+        },
+    },
+    option::ArchivedOption,
+    primitive::{
+        ArchivedChar, ArchivedF32, ArchivedF64, ArchivedI128, ArchivedI16,
+        ArchivedI32, ArchivedI64, ArchivedU128, ArchivedU16, ArchivedU32,
+        ArchivedU64,
+    },
+    rancor::{Error, Failure, Fallible, Strategy},
+    rc::ArchivedRc,
+    reflect::Reflect,
+    ser::{AllocSerializer, Allocator, Writer},
+    string::{repr::INLINE_CAPACITY, ArchivedString},
+    to_bytes,
+    vec::ArchivedVec,
+    Archive, ArchivePointee, Portable, Serialize,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{layout, value, Format, TestVector};
+
+/// Converts an archived value to the value that it should decode to.
+///
+/// This reads archives with the archived types instead of their layouts, so
+/// that the decoder is checked against rkyv itself.
+trait Expected {
+    fn expected(&self) -> Value;
+}
+
+impl Expected for () {
+    fn expected(&self) -> Value {
+        json!({})
+    }
+}
+
+impl Expected for bool {
+    fn expected(&self) -> Value {
+        Value::from(*self)
+    }
+}
+
+macro_rules! impl_expected_int {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Expected for $ty {
+                fn expected(&self) -> Value {
+                    Value::from(self.to_native())
+                }
+            }
+        )*
+    };
+}
+
+impl_expected_int!(
+    ArchivedI16,
+    ArchivedI32,
+    ArchivedI64,
+    ArchivedU16,
+    ArchivedU32,
+    ArchivedU64,
+);
+
+impl Expected for i8 {
+    fn expected(&self) -> Value {
+        Value::from(*self)
+    }
+}
+
+impl Expected for u8 {
+    fn expected(&self) -> Value {
+        Value::from(*self)
+    }
+}
+
+impl Expected for ArchivedI128 {
+    fn expected(&self) -> Value {
+        value::wide(self.to_native())
+    }
+}
+
+impl Expected for ArchivedU128 {
+    fn expected(&self) -> Value {
+        value::wide(self.to_native())
+    }
+}
+
+impl Expected for ArchivedF32 {
+    fn expected(&self) -> Value {
+        value::float(self.to_native() as f64)
+    }
+}
+
+impl Expected for ArchivedF64 {
+    fn expected(&self) -> Value {
+        value::float(self.to_native())
+    }
+}
+
+impl Expected for ArchivedChar {
+    fn expected(&self) -> Value {
+        Value::from(self.to_native().to_string())
+    }
+}
+
+impl<T: Expected, const N: usize> Expected for [T; N] {
+    fn expected(&self) -> Value {
+        self.as_slice().expected()
+    }
+}
+
+impl<T: Expected> Expected for [T] {
+    fn expected(&self) -> Value {
+        self.iter().map(T::expected).collect()
+    }
+}
+
+impl Expected for str {
+    fn expected(&self) -> Value {
+        Value::from(self)
+    }
+}
+
+impl Expected for ArchivedString {
+    fn expected(&self) -> Value {
+        self.as_str().expected()
+    }
+}
+
+impl<T: Expected> Expected for ArchivedVec<T> {
+    fn expected(&self) -> Value {
+        self.as_slice().expected()
+    }
+}
+
+impl<T: Expected> Expected for ArchivedOption<T> {
+    fn expected(&self) -> Value {
+        match self.as_ref() {
+            Some(value) => value::some(value.expected()),
+            None => Value::Null,
+        }
+    }
+}
+
+impl<T: ArchivePointee + Expected + ?Sized> Expected for ArchivedBox<T> {
+    fn expected(&self) -> Value {
+        self.get().expected()
+    }
+}
+
+impl<T: ArchivePointee + Expected + ?Sized, F> Expected for ArchivedRc<T, F> {
+    fn expected(&self) -> Value {
+        (**self).expected()
+    }
+}
+
+impl<K: Expected, V: Expected, H> Expected for ArchivedHashMap<K, V, H> {
+    fn expected(&self) -> Value {
+        self.iter()
+            .map(|(key, value)| value::entry(key.expected(), value.expected()))
+            .collect()
+    }
+}
+
+impl<K: Expected, H> Expected for ArchivedHashSet<K, H> {
+    fn expected(&self) -> Value {
+        self.iter().map(K::expected).collect()
+    }
+}
+
+impl<K: Expected, V: Expected> Expected for ArchivedBTreeMap<K, V> {
+    fn expected(&self) -> Value {
+        self.iter()
+            .map(|(key, value)| value::entry(key.expected(), value.expected()))
+            .collect()
+    }
+}
+
+fn fields(fields: &[(&str, Value)]) -> Map<String, Value> {
+    fields
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect()
+}
+
+macro_rules! impl_expected_struct {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl Expected for $ty {
+            fn expected(&self) -> Value {
+                Value::Object(fields(&[
+                    $((stringify!($field), self.$field.expected()),)*
+                ]))
+            }
+        }
+    };
+}
+
+#[derive(Archive, Serialize)]
+#[archive(reflect)]
+struct Integers {
+    int8: i8,
+    int16: i16,
+    int32: i32,
+    int64: i64,
+    int128: i128,
+    uint8: u8,
+    uint16: u16,
+    uint32: u32,
+    uint64: u64,
+    uint128: u128,
+}
+
+impl_expected_struct!(ArchivedIntegers {
+    int8,
+    int16,
+    int32,
+    int64,
+    int128,
+    uint8,
+    uint16,
+    uint32,
+    uint64,
+    uint128,
+});
+
+#[derive(Archive, Serialize)]
+#[archive(reflect)]
+struct Floats {
+    half: f32,
+    tiny: f32,
+    negative: f64,
+    max: f64,
+    infinity: f32,
+    negative_infinity: f64,
+    nan: f64,
+}
+
+impl_expected_struct!(ArchivedFloats {
+    half,
+    tiny,
+    negative,
+    max,
+    infinity,
+    negative_infinity,
+    nan,
+});
+
+// The fields are out of order by alignment, so the archived struct has
+// padding.
+#[derive(Archive, Serialize)]
+#[archive(reflect)]
+struct Record {
+    flag: bool,
+    id: u64,
+    name: String,
+    code: u16,
+    scores: Vec<f32>,
+    pair: Option<Box<Pair>>,
+}
+
+impl_expected_struct!(ArchivedRecord {
+    flag,
+    id,
+    name,
+    code,
+    scores,
+    pair,
+});
+
+#[derive(Archive, Serialize)]
+#[archive(reflect)]
+struct Pair(u8, u32);
+
+impl Expected for ArchivedPair {
+    fn expected(&self) -> Value {
+        Value::Object(fields(&[
+            ("_0", self.0.expected()),
+            ("_1", self.1.expected()),
+        ]))
+    }
+}
+
+#[derive(Archive, Serialize)]
+#[archive(reflect)]
+enum Shape {
+    Point,
+    Circle(f32),
+    Rect { width: u16, height: u64 },
+}
+
+impl Expected for ArchivedShape {
+    fn expected(&self) -> Value {
+        match self {
+            ArchivedShape::Point => value::variant("Point", Map::new()),
+            ArchivedShape::Circle(radius) => {
+                value::variant("Circle", fields(&[("_0", radius.expected())]))
+            }
+            ArchivedShape::Rect { width, height } => value::variant(
+                "Rect",
+                fields(&[
+                    ("width", width.expected()),
+                    ("height", height.expected()),
+                ]),
+            ),
+        }
+    }
+}
+
+// Enums with more than 256 variants have two-byte tags.
+macro_rules! wide_enum {
+    ($($variant:ident)*) => {
+        #[derive(Archive, Serialize)]
+        #[archive(reflect)]
+        #[allow(dead_code)]
+        enum Wide {
+            $($variant,)*
+            Last(u32),
+        }
+
+        impl Expected for ArchivedWide {
+            fn expected(&self) -> Value {
+                match self {
+                    $(
+                        ArchivedWide::$variant => {
+                            value::variant(stringify!($variant), Map::new())
+                        }
+                    )*
+                    ArchivedWide::Last(value) => value::variant(
+                        "Last",
+                        fields(&[("_0", value.expected())]),
+                    ),
+                }
+            }
+        }
+    };
+}
+
+wide_enum! {
+    V000 V001 V002 V003 V004 V005 V006 V007 V008 V009 V010 V011 V012 V013 V014
+    V015 V016 V017 V018 V019 V020 V021 V022 V023 V024 V025 V026 V027 V028 V029
+    V030 V031 V032 V033 V034 V035 V036 V037 V038 V039 V040 V041 V042 V043 V044
+    V045 V046 V047 V048 V049 V050 V051 V052 V053 V054 V055 V056 V057 V058 V059
+    V060 V061 V062 V063 V064 V065 V066 V067 V068 V069 V070 V071 V072 V073 V074
+    V075 V076 V077 V078 V079 V080 V081 V082 V083 V084 V085 V086 V087 V088 V089
+    V090 V091 V092 V093 V094 V095 V096 V097 V098 V099 V100 V101 V102 V103 V104
+    V105 V106 V107 V108 V109 V110 V111 V112 V113 V114 V115 V116 V117 V118 V119
+    V120 V121 V122 V123 V124 V125 V126 V127 V128 V129 V130 V131 V132 V133 V134
+    V135 V136 V137 V138 V139 V140 V141 V142 V143 V144 V145 V146 V147 V148 V149
+    V150 V151 V152 V153 V154 V155 V156 V157 V158 V159 V160 V161 V162 V163 V164
+    V165 V166 V167 V168 V169 V170 V171 V172 V173 V174 V175 V176 V177 V178 V179
+    V180 V181 V182 V183 V184 V185 V186 V187 V188 V189 V190 V191 V192 V193 V194
+    V195 V196 V197 V198 V199 V200 V201 V202 V203 V204 V205 V206 V207 V208 V209
+    V210 V211 V212 V213 V214 V215 V216 V217 V218 V219 V220 V221 V222 V223 V224
+    V225 V226 V227 V228 V229 V230 V231 V232 V233 V234 V235 V236 V237 V238 V239
+    V240 V241 V242 V243 V244 V245 V246 V247 V248 V249 V250 V251 V252 V253 V254
+    V255 V256 V257 V258 V259
+}
+
+// Hash maps serialized from a fixed order of entries and load factor. The
+// layout of an archived hash table depends on the order its entries are
+// inserted when their probe sequences collide, and the iteration order of a
+// `HashMap` changes between runs.
+struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    load_factor: (usize, usize),
+}
+
+impl<K, V> Archive for OrderedMap<K, V>
+where
+    K: Archive + Hash + Eq,
+    V: Archive,
+{
+    type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+    type Resolver = HashMapResolver;
+
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashMap::resolve_from_len(
+            self.entries.len(),
+            self.load_factor,
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, V, S> Serialize<S> for OrderedMap<K, V>
+where
+    K: Serialize<S> + Hash + Eq,
+    V: Serialize<S>,
+    S: Fallible + Writer + Allocator + ?Sized,
+    S::Error: Error,
+{
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
+            self.entries.iter().map(|(k, v)| (k, v)),
+            self.load_factor,
+            serializer,
+        )
+    }
+}
+
+struct OrderedSet<K>(Vec<K>);
+
+impl<K: Archive + Hash + Eq> Archive for OrderedSet<K> {
+    type Archived = ArchivedHashSet<K::Archived>;
+    type Resolver = HashSetResolver;
+
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedHashSet::resolve_from_len(
+            self.0.len(),
+            (7, 8),
+            pos,
+            resolver,
+            out,
+        );
+    }
+}
+
+impl<K, S> Serialize<S> for OrderedSet<K>
+where
+    K: Serialize<S> + Hash + Eq,
+    S: Fallible + Writer + Allocator + ?Sized,
+    S::Error: Error,
+{
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedHashSet::<K::Archived>::serialize_from_iter(
+            self.0.iter(),
+            (7, 8),
+            serializer,
+        )
+    }
+}
+
+#[derive(Default)]
+struct Vectors(Vec<TestVector>);
+
+impl Vectors {
+    fn add<T>(&mut self, name: &str, value: &T)
+    where
+        T: Serialize<Strategy<AllocSerializer<256>, Failure>>,
+        T::Archived: Portable + Reflect + Expected,
+    {
+        let bytes = to_bytes::<_, 256, Failure>(value).unwrap();
+        // SAFETY: The bytes were just serialized from a `T`.
+        let root = unsafe { access_unchecked::<T::Archived>(&bytes) };
+        let description = json!({
+            "name": name,
+            "format": Format::current().to_json(),
+            "archive_size": bytes.len(),
+            "root": {
+                "position": bytes.len() - size_of::<T::Archived>(),
+                "layout": layout::describe(&T::Archived::DESCRIPTOR),
+            },
+            "value": root.expected(),
+        });
+        self.0.push(TestVector {
+            name: name.to_string(),
+            bytes: bytes.to_vec(),
+            description,
+        });
+    }
+}
+
+fn ordered_map(load_factor: (usize, usize)) -> OrderedMap<u32, String> {
+    OrderedMap {
+        entries: (0..20u32).map(|i| (i * 7, i.to_string())).collect(),
+        load_factor,
+    }
+}
+
+/// Generates the test vectors for the format that this crate is compiled for,
+/// sorted by name.
+pub fn generate() -> Vec<TestVector> {
+    let mut v = Vectors::default();
+
+    // Primitives
+    v.add("unit", &());
+    v.add("bool", &true);
+    v.add(
+        "integers_min",
+        &Integers {
+            int8: i8::MIN,
+            int16: i16::MIN,
+            int32: i32::MIN,
+            int64: i64::MIN,
+            int128: i128::MIN,
+            uint8: 0,
+            uint16: 0,
+            uint32: 0,
+            uint64: 0,
+            uint128: 0,
+        },
+    );
+    v.add(
+        "integers_max",
+        &Integers {
+            int8: i8::MAX,
+            int16: i16::MAX,
+            int32: i32::MAX,
+            int64: i64::MAX,
+            int128: i128::MAX,
+            uint8: u8::MAX,
+            uint16: u16::MAX,
+            uint32: u32::MAX,
+            uint64: u64::MAX,
+            uint128: u128::MAX,
+        },
+    );
+    v.add("usize", &0x1234usize);
+    v.add("isize", &-0x1234isize);
+    v.add(
+        "floats",
+        &Floats {
+            half: 0.5,
+            tiny: f32::MIN_POSITIVE,
+            negative: -2.25,
+            max: f64::MAX,
+            infinity: f32::INFINITY,
+            negative_infinity: f64::NEG_INFINITY,
+            nan: f64::NAN,
+        },
+    );
+    v.add("chars", &['a', '\u{e9}', '\u{1f980}', '\u{10ffff}']);
+
+    // Strings
+    v.add("string_empty", &String::new());
+    v.add("string_inline", &"i".repeat(INLINE_CAPACITY));
+    v.add("string_out_of_line", &"o".repeat(INLINE_CAPACITY + 1));
+    v.add(
+        "string_unicode",
+        &"\u{bf}D\u{f3}nde est\u{e1} el \u{1f980}?".to_string(),
+    );
+
+    // Arrays, vecs, and boxes
+    v.add("array_nested", &[[1u16, 2], [3, 4], [5, 6]]);
+    v.add("vec_empty", &Vec::<u32>::new());
+    v.add("vec_u16", &vec![1u16, 2, 3, u16::MAX]);
+    v.add(
+        "vec_strings",
+        &vec![
+            "short".to_string(),
+            "a string too long to inline".to_string(),
+        ],
+    );
+    v.add("vec_nested", &vec![vec![], vec![1u8], vec![2, 3]]);
+    v.add("box_u32", &Box::new(0x01020304u32));
+    v.add("box_slice", &vec![1u32, 2, 3].into_boxed_slice());
+    v.add("box_slice_empty", &Vec::<u64>::new().into_boxed_slice());
+    v.add("box_str", &"boxed".to_string().into_boxed_str());
+
+    // Options
+    v.add("option_none", &None::<u32>);
+    v.add("option_some", &Some(1234u64));
+    v.add("option_nested", &vec![None, Some(None), Some(Some(7u8))]);
+    v.add("option_box", &Some(Box::new("boxed".to_string())));
+
+    // Shared pointers are only serialized once
+    let shared = Rc::new("a shared string that is out of line".to_string());
+    v.add(
+        "rc_shared",
+        &vec![
+            shared.clone(),
+            Rc::new("another string".to_string()),
+            shared,
+        ],
+    );
+    let shared = Arc::new(0x0102030405060708u64);
+    v.add("arc_shared", &vec![shared.clone(), shared]);
+    v.add("rc_str", &Rc::<str>::from("shared str"));
+
+    // Derived structs and enums
+    v.add(
+        "struct_record",
+        &Record {
+            flag: true,
+            id: 0x0102030405060708,
+            name: "a record with a long name".to_string(),
+            code: 0xabcd,
+            scores: vec![0.5, -1.25],
+            pair: Some(Box::new(Pair(5, 6))),
+        },
+    );
+    v.add("struct_tuple", &Pair(1, 0x01020304));
+    v.add(
+        "enum_variants",
+        &vec![
+            Shape::Point,
+            Shape::Circle(1.5),
+            Shape::Rect {
+                width: 3,
+                height: 4,
+            },
+        ],
+    );
+    v.add("enum_wide_first", &Wide::V000);
+    v.add("enum_wide_unit", &Wide::V259);
+    v.add("enum_wide_fields", &Wide::Last(0x01020304));
+
+    // Swiss tables with different load factors
+    v.add(
+        "hash_map_empty",
+        &OrderedMap::<u32, u32> {
+            entries: Vec::new(),
+            load_factor: (7, 8),
+        },
+    );
+    v.add("hash_map_load_7_8", &ordered_map((7, 8)));
+    v.add("hash_map_load_1_2", &ordered_map((1, 2)));
+    v.add("hash_map_load_1_1", &ordered_map((1, 1)));
+    v.add(
+        "hash_map_strings",
+        &OrderedMap {
+            entries: vec![
+                ("apple".to_string(), Some(1u8)),
+                ("banana".to_string(), None),
+                ("a much longer key".to_string(), Some(3)),
+            ],
+            load_factor: (7, 8),
+        },
+    );
+    v.add("hash_set", &OrderedSet((0..20u16).map(|i| i * 3).collect()));
+
+    // B-trees with different numbers of entries per node. Nodes hold about
+    // 4KB of entries, so small entries make wide nodes and large keys make
+    // narrow nodes and deep trees.
+    v.add("btree_map_empty", &BTreeMap::<u32, u32>::new());
+    v.add(
+        "btree_map_small",
+        &(0..3u32).map(|i| (i, i * 10)).collect::<BTreeMap<_, _>>(),
+    );
+    v.add(
+        "btree_map_wide",
+        &(0..3000u16)
+            .map(|i| (i, i as u8))
+            .collect::<BTreeMap<_, _>>(),
+    );
+    v.add(
+        "btree_map_deep",
+        &(0..30u32)
+            .map(|i| (format!("{:04}", i).repeat(250), i))
+            .collect::<BTreeMap<_, _>>(),
+    );
+
+    let mut vectors = v.0;
+    vectors.sort_by(|a, b| a.name.cmp(&b.name));
+    vectors
+}
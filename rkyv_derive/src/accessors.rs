@@ -0,0 +1,289 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Ident};
+
+use crate::{
+    attributes::Attributes,
+    derive_std::debug,
+    util::{field_predicate, is_not_omitted, strip_raw, Variant},
+    with::make_with_ty,
+};
+
+/// Converts a variant name from `UpperCamelCase` to `snake_case`.
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut result = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_lower)
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+/// Returns the name of the kind enum generated for `name`.
+pub fn kind_name(name: &Ident) -> Ident {
+    Ident::new(&format!("{}Kind", strip_raw(name)), name.span())
+}
+
+/// Generates the kind enum and the accessor methods for an enum, and the
+/// `Debug` implementation for its archived type.
+///
+/// Returns the kind enum, which is placed next to the archived type, and the
+/// implementations.
+pub fn accessors(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<(Option<TokenStream>, Option<TokenStream>), Error> {
+    let data = match input.data {
+        Data::Enum(ref data) => data,
+        _ => return Ok((None, None)),
+    };
+    if attributes.no_accessors.is_some()
+        || attributes.archive_as.is_some()
+        || data.variants.is_empty()
+    {
+        return Ok((None, None));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let kind_name = kind_name(name);
+    let variants = Variant::all(input, archived_name).unwrap();
+
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let where_clause = where_clause.unwrap();
+    let fields = variants
+        .iter()
+        .flat_map(|v| v.fields.iter())
+        .filter(is_not_omitted)
+        .collect::<Vec<_>>();
+    let mut archive_where = where_clause.clone();
+    for field in fields.iter() {
+        let ty = with_ty(field)?;
+        archive_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+    }
+
+    let mut method_names = Vec::<(String, &Ident)>::new();
+    for variant in data.variants.iter() {
+        let method_name = snake_case(&strip_raw(&variant.ident));
+        if let Some((_, other)) =
+            method_names.iter().find(|(n, _)| *n == method_name)
+        {
+            return Err(Error::new_spanned(
+                &variant.ident,
+                format!(
+                    "variants `{}` and `{}` have the same accessor names\nuse \
+                     `#[archive(no_accessors)]` to disable accessors",
+                    other, variant.ident,
+                ),
+            ));
+        }
+        method_names.push((method_name, &variant.ident));
+    }
+
+    let kind_doc = format!(
+        "The variants of [`{}`] and [`{}`] without their fields",
+        name, archived_name,
+    );
+    let kind_variants = data.variants.iter().map(|v| {
+        let ident = &v.ident;
+        let doc = format!("The [`{}::{}`] variant", name, ident);
+        quote! {
+            #[doc = #doc]
+            #ident
+        }
+    });
+    let kind_def = quote! {
+        #[automatically_derived]
+        #[doc = #kind_doc]
+        #[derive(
+            ::core::clone::Clone,
+            ::core::marker::Copy,
+            ::core::fmt::Debug,
+            ::core::cmp::PartialEq,
+            ::core::cmp::Eq,
+            ::core::cmp::PartialOrd,
+            ::core::cmp::Ord,
+            ::core::hash::Hash,
+        )]
+        #vis enum #kind_name {
+            #(#kind_variants,)*
+        }
+    };
+
+    let kind_arms = variants
+        .iter()
+        .zip(data.variants.iter())
+        .map(|(v, variant)| {
+            let path = &v.path;
+            let ident = &variant.ident;
+            quote! { #path { .. } => #kind_name::#ident }
+        })
+        .collect::<Vec<_>>();
+    let kind_method = quote! {
+        /// Returns which variant this is.
+        #[inline]
+        #vis fn kind(&self) -> #kind_name {
+            match self {
+                #(#kind_arms,)*
+            }
+        }
+    };
+
+    let variant_methods =
+        variants.iter().zip(method_names.iter()).map(|(v, (method, ident))| {
+            let path = &v.path;
+            let is_name = Ident::new(&format!("is_{}", method), ident.span());
+            let is_doc = format!("Returns whether this is a `{}`.", v.name);
+            let is_method = quote! {
+                #[doc = #is_doc]
+                #[inline]
+                #vis fn #is_name(&self) -> bool {
+                    ::core::matches!(self, #path { .. })
+                }
+            };
+
+            if matches!(v.fields, Fields::Unit) {
+                return is_method;
+            }
+
+            let as_name = Ident::new(&format!("as_{}", method), ident.span());
+            let as_pin_name =
+                Ident::new(&format!("as_{}_pin", method), ident.span());
+            let as_doc = format!(
+                "Returns the fields of this `{}`, or `None` if it is another \
+                 variant.",
+                v.name,
+            );
+            let as_pin_doc = format!(
+                "Returns the pinned fields of this `{}`, or `None` if it is \
+                 another variant.",
+                v.name,
+            );
+            let pattern = v.pattern("__self");
+            let bindings = v.bindings("__self");
+            let types = v
+                .fields
+                .iter()
+                .map(|f| {
+                    let ty = with_ty(f).unwrap();
+                    quote! { #rkyv_path::Archived<#ty> }
+                })
+                .collect::<Vec<_>>();
+            let (ref_ty, mut_ty, ref_value, pin_value) = if types.len() == 1 {
+                let ty = &types[0];
+                let binding = &bindings[0];
+                (
+                    quote! { &#ty },
+                    quote! { ::core::pin::Pin<&mut #ty> },
+                    quote! { #binding },
+                    quote! { ::core::pin::Pin::new_unchecked(#binding) },
+                )
+            } else {
+                (
+                    quote! { (#(&#types,)*) },
+                    quote! { (#(::core::pin::Pin<&mut #types>,)*) },
+                    quote! { (#(#bindings,)*) },
+                    quote! { (#(::core::pin::Pin::new_unchecked(#bindings),)*) },
+                )
+            };
+
+            quote! {
+                #is_method
+
+                #[doc = #as_doc]
+                #[inline]
+                #vis fn #as_name(&self) -> ::core::option::Option<#ref_ty> {
+                    match self {
+                        #pattern => ::core::option::Option::Some(#ref_value),
+                        #[allow(unreachable_patterns)]
+                        _ => ::core::option::Option::None,
+                    }
+                }
+
+                #[doc = #as_pin_doc]
+                #[inline]
+                #vis fn #as_pin_name(
+                    self: ::core::pin::Pin<&mut Self>,
+                ) -> ::core::option::Option<#mut_ty> {
+                    // SAFETY: The fields of archived enums are structurally
+                    // pinned.
+                    unsafe {
+                        match ::core::pin::Pin::get_unchecked_mut(self) {
+                            #pattern => ::core::option::Option::Some(#pin_value),
+                            #[allow(unreachable_patterns)]
+                            _ => ::core::option::Option::None,
+                        }
+                    }
+                }
+            }
+        });
+
+    // The `Debug` implementation requires all of the archived fields to
+    // implement `Debug`. The bounds are higher-ranked so that they are checked
+    // where the impl is used instead of being rejected when a field with a
+    // concrete type doesn't implement it. Fields with omitted bounds can't be
+    // bounded at all, so enums with them don't get a `Debug` implementation.
+    let has_omitted_bounds = variants
+        .iter()
+        .flat_map(|v| v.fields.iter())
+        .any(|f| !is_not_omitted(&f));
+    let debug_impl = if attributes.derive_std.is_some()
+        || attributes.find_derive(&["Debug"]).is_some()
+        || has_omitted_bounds
+    {
+        None
+    } else {
+        let mut debug_where = archive_where.clone();
+        for field in fields.iter() {
+            let ty = with_ty(field)?;
+            debug_where.predicates.push(field_predicate(
+                &ty,
+                quote! { for<'__a> #rkyv_path::Archived<#ty>: ::core::fmt::Debug },
+            ));
+        }
+        let patterns = variants.iter().map(|v| v.pattern("__self"));
+        let arms = variants.iter().map(debug);
+        Some(quote! {
+            impl #impl_generics ::core::fmt::Debug for #archived_name #ty_generics #debug_where {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #(#patterns => #arms,)*
+                    }
+                }
+            }
+        })
+    };
+
+    let impls = quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #kind_method
+        }
+
+        impl #impl_generics #archived_name #ty_generics #archive_where {
+            #kind_method
+
+            #(#variant_methods)*
+        }
+
+        #debug_impl
+    };
+
+    Ok((Some(kind_def), Some(impls)))
+}
@@ -2,12 +2,33 @@ use proc_macro2::{Literal, TokenStream};
 use quote::quote;
 use syn::{
     parse_quote, spanned::Spanned, Attribute, Data, DeriveInput, Error, Field,
-    Fields, Ident, Index, LitStr, Meta, Type,
+    Fields, Ident, Index, LitStr, Member, Meta, Type,
 };
 
 use crate::{
+    accessors::accessors,
     attributes::Attributes,
-    util::{is_not_omitted, strip_raw},
+    c_api::c_api,
+    callable::callable,
+    columnar::archive_columns,
+    convenience::convenience,
+    deep_size::deep_size,
+    derive_std::derive_std,
+    format_stable::format_stable,
+    niche::niche,
+    pack_flags,
+    project::project,
+    rearchive::rearchive,
+    reflect::reflect,
+    render_json::render_json,
+    stable_hash::stable_hash,
+    stable_layout::stable_layout,
+    transparent,
+    type_hash::type_hash,
+    util::{
+        check_layout_derives, field_predicate, is_not_omitted, layout_order,
+        strip_raw,
+    },
     with::{make_with_cast, make_with_ty},
 };
 
@@ -16,7 +37,7 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream, Error> {
     derive_archive_impl(input, &attributes)
 }
 
-fn field_archive_attrs(
+pub fn field_archive_attrs(
     field: &Field,
 ) -> impl '_ + Iterator<Item = &TokenStream> {
     field.attrs.iter().filter_map(|attr| {
@@ -55,7 +76,7 @@ fn derive_archive_impl(
     let with_ty = make_with_ty(&rkyv_path);
     let with_cast = make_with_cast(&rkyv_path);
 
-    let derive_check_bytes = if attributes.check_bytes.is_some() {
+    let derive_check_bytes = if attributes.derives_check_bytes().is_some() {
         let path = quote!(#rkyv_path::bytecheck).to_string();
         let path_lit_str = LitStr::new(&path, rkyv_path.span());
         vec![
@@ -66,20 +87,7 @@ fn derive_archive_impl(
         Vec::new()
     };
 
-    let archive_attrs = derive_check_bytes.into_iter().chain(
-        attributes
-            .attrs
-            .iter()
-            .map::<Attribute, _>(|d| parse_quote! { #[#d] }),
-    );
-
     if let Some(ref archive_as) = attributes.archive_as {
-        if let Some(ref ident) = attributes.archived {
-            return Err(Error::new_spanned(
-                ident,
-                "archived = \"...\" may not be used with as = \"...\" because no type is generated",
-            ));
-        }
         if let Some(first) = attributes.attrs.first() {
             return Err(Error::new_spanned(
                 first,
@@ -94,12 +102,51 @@ fn derive_archive_impl(
         }
     }
 
+    if attributes.transparent.is_some() {
+        let archive_impl =
+            transparent::archive(attributes, &input, where_clause)?;
+        let convenience_impl = convenience(attributes, &input)?;
+        return Ok(quote! {
+            #[automatically_derived]
+            const _: () = {
+                #archive_impl
+                #convenience_impl
+            };
+        });
+    }
+
     let archived_name = attributes.archived.as_ref().map_or_else(
         || Ident::new(&format!("Archived{}", strip_raw(name)), name.span()),
         |value| value.clone(),
     );
     let archived_doc = format!("An archived [`{}`]", name);
 
+    pack_flags::check(attributes, &input)?;
+    let packed_flag_impl = pack_flags::packed_flag(attributes, &input)?;
+
+    let std_impls = derive_std(attributes, &input, &archived_name)?;
+    let deep_size_impl = deep_size(attributes, &input, &archived_name)?;
+    let stable_hash_impl = stable_hash(attributes, &input, &archived_name)?;
+    let render_json_impl = render_json(attributes, &input, &archived_name)?;
+    let format_stable_impl = format_stable(attributes, &input, &archived_name)?;
+    let stable_layout_impl = stable_layout(attributes, &input, &archived_name)?;
+    let reflect_impl = reflect(attributes, &input, &archived_name)?;
+    let callable_impl = callable(attributes, &input)?;
+    let convenience_impl = convenience(attributes, &input)?;
+    let c_api_impl = c_api(attributes, &input, &archived_name)?;
+    let (kind_type, accessors_impl) =
+        accessors(attributes, &input, &archived_name)?;
+
+    let archive_attrs = derive_check_bytes
+        .into_iter()
+        .chain(
+            attributes
+                .attrs
+                .iter()
+                .map::<Attribute, _>(|d| parse_quote! { #[#d] }),
+        )
+        .collect::<Vec<_>>();
+
     let archived_type = attributes.archive_as.as_ref().map_or_else(
         || Ok(parse_quote! { #archived_name #ty_generics }),
         |lit| lit.parse::<Type>(),
@@ -111,26 +158,61 @@ fn derive_archive_impl(
     );
     let resolver_doc = format!("The resolver for an archived [`{}`]", name);
 
+    let layout_impl = export_layout(attributes, &input, &archived_name)?;
+    let rearchive_impl =
+        rearchive(attributes, &input, &archived_name, &resolver)?;
+    let type_hash_impl = type_hash(attributes, &input)?;
+    let niche_impl = niche(attributes, &input, &archived_name)?;
+    let no_rel_ptrs_impl = no_rel_ptrs(attributes, &input, &archived_name)?;
+    let (project_type, project_impl) =
+        project(attributes, &input, &archived_name)?;
+    let (columns_types, columns_impls) = archive_columns(attributes, &input)?;
+
     let (archive_types, archive_impls) = match input.data {
         Data::Struct(ref data) => {
             match data.fields {
+                Fields::Named(ref fields)
+                    if attributes.pack_flags.is_some() =>
+                {
+                    pack_flags::archive_struct(
+                        attributes,
+                        &input,
+                        fields,
+                        &archived_name,
+                        &resolver,
+                        &archive_attrs,
+                        where_clause,
+                    )?
+                }
                 Fields::Named(ref fields) => {
                     let mut archive_where = where_clause.clone();
                     for field in fields.named.iter().filter(is_not_omitted) {
                         let ty = with_ty(field)?;
-                        archive_where
-                            .predicates
-                            .push(parse_quote! { #ty: #rkyv_path::Archive });
+                        archive_where.predicates.push(field_predicate(
+                            &ty,
+                            quote! { #ty: #rkyv_path::Archive },
+                        ));
                     }
 
-                    let resolver_fields = fields.named.iter().map(|f| {
+                    let layout_fields = layout_order(
+                        fields.named.iter(),
+                        attributes.preserve_order.is_some()
+                            || attributes.copy_safe.is_some(),
+                    );
+                    check_layout_derives(
+                        attributes,
+                        fields.named.iter(),
+                        &layout_fields,
+                    )?;
+
+                    let resolver_fields = layout_fields.iter().map(|f| {
                         let name = &f.ident;
                         let ty = with_ty(f).unwrap();
                         quote! { #name: #rkyv_path::Resolver<#ty> }
                     });
 
                     let archived_def = if attributes.archive_as.is_none() {
-                        let archived_fields = fields.named.iter().map(|f| {
+                        let archived_fields = layout_fields.iter().map(|f| {
                             let field_name = f.ident.as_ref();
                             let ty = with_ty(f).unwrap();
                             let vis = &f.vis;
@@ -163,11 +245,11 @@ fn derive_archive_impl(
                         None
                     };
 
-                    let resolve_fields = fields.named.iter().map(|f| {
+                    let resolve_fields = layout_fields.iter().map(|f| {
                         let name = &f.ident;
                         let field = with_cast(f, parse_quote! { (&self.#name) }).unwrap();
                         quote! {
-                            let (fp, fo) = out_field!(out.#name);
+                            let (fp, fo) = #rkyv_path::out_field!(out.#name);
                             #rkyv_path::Archive::resolve(#field, pos + fp, resolver.#name, fo);
                         }
                     });
@@ -185,7 +267,7 @@ fn derive_archive_impl(
                                     let ty = &field.ty;
                                     let wrapped_ty = with_ty(field).unwrap();
                                     partial_eq_where.predicates.push(
-                                        parse_quote! { Archived<#wrapped_ty>: PartialEq<#ty> },
+                                        parse_quote! { #rkyv_path::Archived<#wrapped_ty>: ::core::cmp::PartialEq<#ty> },
                                     );
                                 }
 
@@ -193,17 +275,17 @@ fn derive_archive_impl(
                                     fields.named.iter().map(|f| &f.ident);
 
                                 partial_eq_impl = Some(quote! {
-                                    impl #impl_generics PartialEq<#archived_type> for #name #ty_generics #partial_eq_where {
+                                    impl #impl_generics ::core::cmp::PartialEq<#archived_type> for #name #ty_generics #partial_eq_where {
                                         #[inline]
                                         fn eq(&self, other: &#archived_type) -> bool {
-                                            true #(&& other.#field_names.eq(&self.#field_names))*
+                                            true #(&& ::core::cmp::PartialEq::eq(&other.#field_names, &self.#field_names))*
                                         }
                                     }
 
-                                    impl #impl_generics PartialEq<#name #ty_generics> for #archived_type #partial_eq_where {
+                                    impl #impl_generics ::core::cmp::PartialEq<#name #ty_generics> for #archived_type #partial_eq_where {
                                         #[inline]
                                         fn eq(&self, other: &#name #ty_generics) -> bool {
-                                            other.eq(self)
+                                            ::core::cmp::PartialEq::eq(other, self)
                                         }
                                     }
                                 });
@@ -216,7 +298,7 @@ fn derive_archive_impl(
                                     let ty = &field.ty;
                                     let archived_ty = with_ty(field).unwrap();
                                     partial_ord_where.predicates.push(
-                                        parse_quote! { Archived<#archived_ty>: PartialOrd<#ty> },
+                                        parse_quote! { #rkyv_path::Archived<#archived_ty>: ::core::cmp::PartialOrd<#ty> },
                                     );
                                 }
 
@@ -224,23 +306,23 @@ fn derive_archive_impl(
                                     fields.named.iter().map(|f| &f.ident);
 
                                 partial_ord_impl = Some(quote! {
-                                    impl #impl_generics PartialOrd<#archived_type> for #name #ty_generics #partial_ord_where {
+                                    impl #impl_generics ::core::cmp::PartialOrd<#archived_type> for #name #ty_generics #partial_ord_where {
                                         #[inline]
-                                        fn partial_cmp(&self, other: &#archived_type) -> Option<::core::cmp::Ordering> {
+                                        fn partial_cmp(&self, other: &#archived_type) -> ::core::option::Option<::core::cmp::Ordering> {
                                             #(
-                                                match other.#field_names.partial_cmp(&self.#field_names) {
-                                                    Some(::core::cmp::Ordering::Equal) => (),
+                                                match ::core::cmp::PartialOrd::partial_cmp(&other.#field_names, &self.#field_names) {
+                                                    ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
                                                     x => return x,
                                                 }
                                             )*
-                                            Some(::core::cmp::Ordering::Equal)
+                                            ::core::option::Option::Some(::core::cmp::Ordering::Equal)
                                         }
                                     }
 
-                                    impl #impl_generics PartialOrd<#name #ty_generics> for #archived_type #partial_ord_where {
+                                    impl #impl_generics ::core::cmp::PartialOrd<#name #ty_generics> for #archived_type #partial_ord_where {
                                         #[inline]
-                                        fn partial_cmp(&self, other: &#name #ty_generics) -> Option<::core::cmp::Ordering> {
-                                            other.partial_cmp(self)
+                                        fn partial_cmp(&self, other: &#name #ty_generics) -> ::core::option::Option<::core::cmp::Ordering> {
+                                            ::core::cmp::PartialOrd::partial_cmp(other, self)
                                         }
                                     }
                                 });
@@ -253,24 +335,12 @@ fn derive_archive_impl(
                         }
                     }
 
-                    let copy_safe_impl = if cfg!(feature = "copy")
-                        && attributes.copy_safe.is_some()
-                    {
-                        let mut copy_safe_where = where_clause.clone();
-                        for field in fields.named.iter().filter(is_not_omitted)
-                        {
-                            let ty = with_ty(field).unwrap();
-                            copy_safe_where
-                                .predicates
-                                .push(parse_quote! { #ty: #rkyv_path::copy::ArchiveCopySafe });
-                        }
-
-                        Some(quote! {
-                            unsafe impl #impl_generics #rkyv_path::copy::ArchiveCopySafe for #name #ty_generics #copy_safe_where {}
-                        })
-                    } else {
-                        None
-                    };
+                    let (copy_optimization, copy_safe_impl) = copy_safe(
+                        attributes,
+                        &input,
+                        &archived_type,
+                        &data.fields,
+                    )?;
 
                     (
                         quote! {
@@ -284,6 +354,8 @@ fn derive_archive_impl(
                         },
                         quote! {
                             impl #impl_generics #rkyv_path::Archive for #name #ty_generics #archive_where {
+                                #copy_optimization
+
                                 type Archived = #archived_type;
                                 type Resolver = #resolver #ty_generics;
 
@@ -305,9 +377,10 @@ fn derive_archive_impl(
                     let mut archive_where = where_clause.clone();
                     for field in fields.unnamed.iter().filter(is_not_omitted) {
                         let ty = with_ty(field)?;
-                        archive_where
-                            .predicates
-                            .push(parse_quote! { #ty: #rkyv_path::Archive });
+                        archive_where.predicates.push(field_predicate(
+                            &ty,
+                            quote! { #ty: #rkyv_path::Archive },
+                        ));
                     }
 
                     let resolver_fields = fields.unnamed.iter().map(|f| {
@@ -350,7 +423,7 @@ fn derive_archive_impl(
                         let index = Index::from(i);
                         let field = with_cast(f, parse_quote! { (&self.#index) }).unwrap();
                         quote! {
-                            let (fp, fo) = out_field!(out.#index);
+                            let (fp, fo) = #rkyv_path::out_field!(out.#index);
                             #rkyv_path::Archive::resolve(#field, pos + fp, resolver.#index, fo);
                         }
                     });
@@ -368,7 +441,7 @@ fn derive_archive_impl(
                                     let ty = &field.ty;
                                     let wrapped_ty = with_ty(field).unwrap();
                                     partial_eq_where.predicates.push(
-                                        parse_quote! { Archived<#wrapped_ty>: PartialEq<#ty> },
+                                        parse_quote! { #rkyv_path::Archived<#wrapped_ty>: ::core::cmp::PartialEq<#ty> },
                                     );
                                 }
 
@@ -379,17 +452,17 @@ fn derive_archive_impl(
                                     .map(|(i, _)| Index::from(i));
 
                                 partial_eq_impl = Some(quote! {
-                                    impl #impl_generics PartialEq<#archived_type> for #name #ty_generics #partial_eq_where {
+                                    impl #impl_generics ::core::cmp::PartialEq<#archived_type> for #name #ty_generics #partial_eq_where {
                                         #[inline]
                                         fn eq(&self, other: &#archived_type) -> bool {
-                                            true #(&& other.#field_names.eq(&self.#field_names))*
+                                            true #(&& ::core::cmp::PartialEq::eq(&other.#field_names, &self.#field_names))*
                                         }
                                     }
 
-                                    impl #impl_generics PartialEq<#name #ty_generics> for #archived_type #partial_eq_where {
+                                    impl #impl_generics ::core::cmp::PartialEq<#name #ty_generics> for #archived_type #partial_eq_where {
                                         #[inline]
                                         fn eq(&self, other: &#name #ty_generics) -> bool {
-                                            other.eq(self)
+                                            ::core::cmp::PartialEq::eq(other, self)
                                         }
                                     }
                                 });
@@ -402,7 +475,7 @@ fn derive_archive_impl(
                                     let ty = &field.ty;
                                     let wrapped_ty = with_ty(field).unwrap();
                                     partial_ord_where.predicates.push(
-                                        parse_quote! { Archived<#wrapped_ty>: PartialOrd<#ty> },
+                                        parse_quote! { #rkyv_path::Archived<#wrapped_ty>: ::core::cmp::PartialOrd<#ty> },
                                     );
                                 }
 
@@ -413,23 +486,23 @@ fn derive_archive_impl(
                                     .map(|(i, _)| Index::from(i));
 
                                 partial_ord_impl = Some(quote! {
-                                    impl #impl_generics PartialOrd<#archived_type> for #name #ty_generics #partial_ord_where {
+                                    impl #impl_generics ::core::cmp::PartialOrd<#archived_type> for #name #ty_generics #partial_ord_where {
                                         #[inline]
-                                        fn partial_cmp(&self, other: &#archived_type) -> Option<::core::cmp::Ordering> {
+                                        fn partial_cmp(&self, other: &#archived_type) -> ::core::option::Option<::core::cmp::Ordering> {
                                             #(
-                                                match other.#field_names.partial_cmp(&self.#field_names) {
-                                                    Some(::core::cmp::Ordering::Equal) => (),
+                                                match ::core::cmp::PartialOrd::partial_cmp(&other.#field_names, &self.#field_names) {
+                                                    ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
                                                     x => return x,
                                                 }
                                             )*
-                                            Some(::core::cmp::Ordering::Equal)
+                                            ::core::option::Option::Some(::core::cmp::Ordering::Equal)
                                         }
                                     }
 
-                                    impl #impl_generics PartialOrd<#name #ty_generics> for #archived_type #partial_ord_where {
+                                    impl #impl_generics ::core::cmp::PartialOrd<#name #ty_generics> for #archived_type #partial_ord_where {
                                         #[inline]
-                                        fn partial_cmp(&self, other: &#name #ty_generics) -> Option<::core::cmp::Ordering> {
-                                            other.partial_cmp(self)
+                                        fn partial_cmp(&self, other: &#name #ty_generics) -> ::core::option::Option<::core::cmp::Ordering> {
+                                            ::core::cmp::PartialOrd::partial_cmp(other, self)
                                         }
                                     }
                                 });
@@ -439,25 +512,12 @@ fn derive_archive_impl(
                         }
                     }
 
-                    let copy_safe_impl = if cfg!(feature = "copy")
-                        && attributes.copy_safe.is_some()
-                    {
-                        let mut copy_safe_where = where_clause.clone();
-                        for field in
-                            fields.unnamed.iter().filter(is_not_omitted)
-                        {
-                            let ty = with_ty(field).unwrap();
-                            copy_safe_where
-                                .predicates
-                                .push(parse_quote! { #ty: #rkyv_path::copy::ArchiveCopySafe });
-                        }
-
-                        Some(quote! {
-                            unsafe impl #impl_generics #rkyv_path::copy::ArchiveCopySafe for #name #ty_generics #copy_safe_where {}
-                        })
-                    } else {
-                        None
-                    };
+                    let (copy_optimization, copy_safe_impl) = copy_safe(
+                        attributes,
+                        &input,
+                        &archived_type,
+                        &data.fields,
+                    )?;
 
                     (
                         quote! {
@@ -468,7 +528,9 @@ fn derive_archive_impl(
                             #vis struct #resolver #generics (#(#resolver_fields,)*) #archive_where;
                         },
                         quote! {
-                            impl #impl_generics Archive for #name #ty_generics #archive_where {
+                            impl #impl_generics #rkyv_path::Archive for #name #ty_generics #archive_where {
+                                #copy_optimization
+
                                 type Archived = #archived_type;
                                 type Resolver = #resolver #ty_generics;
 
@@ -509,14 +571,14 @@ fn derive_archive_impl(
                         for compare in compares {
                             if compare.is_ident("PartialEq") {
                                 partial_eq_impl = Some(quote! {
-                                    impl #impl_generics PartialEq<#archived_type> for #name #ty_generics #where_clause {
+                                    impl #impl_generics ::core::cmp::PartialEq<#archived_type> for #name #ty_generics #where_clause {
                                         #[inline]
                                         fn eq(&self, _: &#archived_type) -> bool {
                                             true
                                         }
                                     }
 
-                                    impl #impl_generics PartialEq<#name #ty_generics> for #archived_type #where_clause {
+                                    impl #impl_generics ::core::cmp::PartialEq<#name #ty_generics> for #archived_type #where_clause {
                                         #[inline]
                                         fn eq(&self, _: &#name #ty_generics) -> bool {
                                             true
@@ -525,17 +587,17 @@ fn derive_archive_impl(
                                 });
                             } else if compare.is_ident("PartialOrd") {
                                 partial_ord_impl = Some(quote! {
-                                    impl #impl_generics PartialOrd<#archived_type> for #name #ty_generics #where_clause {
+                                    impl #impl_generics ::core::cmp::PartialOrd<#archived_type> for #name #ty_generics #where_clause {
                                         #[inline]
-                                        fn partial_cmp(&self, _: &#archived_type) -> Option<::core::cmp::Ordering> {
-                                            Some(::core::cmp::Ordering::Equal)
+                                        fn partial_cmp(&self, _: &#archived_type) -> ::core::option::Option<::core::cmp::Ordering> {
+                                            ::core::option::Option::Some(::core::cmp::Ordering::Equal)
                                         }
                                     }
 
-                                    impl #impl_generics PartialOrd<#name #ty_generics> for #archived_type #where_clause {
+                                    impl #impl_generics ::core::cmp::PartialOrd<#name #ty_generics> for #archived_type #where_clause {
                                         #[inline]
-                                        fn partial_cmp(&self, _:&#name #ty_generics) -> Option<::core::cmp::Ordering> {
-                                            Some(::core::cmp::Ordering::Equal)
+                                        fn partial_cmp(&self, _:&#name #ty_generics) -> ::core::option::Option<::core::cmp::Ordering> {
+                                            ::core::option::Option::Some(::core::cmp::Ordering::Equal)
                                         }
                                     }
                                 });
@@ -548,15 +610,12 @@ fn derive_archive_impl(
                         }
                     }
 
-                    let copy_safe_impl = if cfg!(feature = "copy")
-                        && attributes.copy_safe.is_some()
-                    {
-                        Some(quote! {
-                            unsafe impl #impl_generics #rkyv_path::copy::ArchiveCopySafe for #name #ty_generics #where_clause {}
-                        })
-                    } else {
-                        None
-                    };
+                    let (copy_optimization, copy_safe_impl) = copy_safe(
+                        attributes,
+                        &input,
+                        &archived_type,
+                        &data.fields,
+                    )?;
 
                     (
                         quote! {
@@ -568,7 +627,9 @@ fn derive_archive_impl(
                             #where_clause;
                         },
                         quote! {
-                            impl #impl_generics Archive for #name #ty_generics #where_clause {
+                            impl #impl_generics #rkyv_path::Archive for #name #ty_generics #where_clause {
+                                #copy_optimization
+
                                 type Archived = #archived_type;
                                 type Resolver = #resolver #ty_generics;
 
@@ -592,9 +653,10 @@ fn derive_archive_impl(
                         for field in fields.named.iter().filter(is_not_omitted)
                         {
                             let ty = with_ty(field)?;
-                            archive_where.predicates.push(
-                                parse_quote! { #ty: #rkyv_path::Archive },
-                            );
+                            archive_where.predicates.push(field_predicate(
+                                &ty,
+                                quote! { #ty: #rkyv_path::Archive },
+                            ));
                         }
                     }
                     Fields::Unnamed(ref fields) => {
@@ -602,9 +664,10 @@ fn derive_archive_impl(
                             fields.unnamed.iter().filter(is_not_omitted)
                         {
                             let ty = with_ty(field)?;
-                            archive_where.predicates.push(
-                                parse_quote! { #ty: #rkyv_path::Archive },
-                            );
+                            archive_where.predicates.push(field_predicate(
+                                &ty,
+                                quote! { #ty: #rkyv_path::Archive },
+                            ));
                         }
                     }
                     Fields::Unit => (),
@@ -699,7 +762,7 @@ fn derive_archive_impl(
                             let resolver_binding = Ident::new(&format!("resolver_{}", strip_raw(name.as_ref().unwrap())), name.span());
                             let value = with_cast(f, parse_quote! { #self_binding }).unwrap();
                             quote! {
-                                let (fp, fo) = out_field!(out.#name);
+                                let (fp, fo) = #rkyv_path::out_field!(out.#name);
                                 #rkyv_path::Archive::resolve(#value, pos + fp, #resolver_binding, fo);
                             }
                         });
@@ -733,7 +796,7 @@ fn derive_archive_impl(
                             let resolver_binding = Ident::new(&format!("resolver_{}", i), f.span());
                             let value = with_cast(f, parse_quote! { #self_binding }).unwrap();
                             quote! {
-                                let (fp, fo) = out_field!(out.#index);
+                                let (fp, fo) = #rkyv_path::out_field!(out.#index);
                                 #rkyv_path::Archive::resolve(#value, pos + fp, #resolver_binding, fo);
                             }
                         });
@@ -841,6 +904,13 @@ fn derive_archive_impl(
                     // SAFETY: As long as the `Archive` impl holds, the archived type is guaranteed to be `Portable`.
                     unsafe impl #impl_generics #rkyv_path::Portable for #archived_name #ty_generics #archive_where {}
 
+                    impl #impl_generics #rkyv_path::invariant::sealed::Sealed for #archived_name #ty_generics #archive_where {}
+
+                    // SAFETY: Checking an archived enum fails unless its tag is one of its variants and the fields of that variant are valid.
+                    unsafe impl #impl_generics #rkyv_path::invariant::ValidatedInvariant for #archived_name #ty_generics #archive_where {
+                        type Native = Self;
+                    }
+
                     #[automatically_derived]
                     #[doc = #archived_doc]
                     #(#archive_attrs)*
@@ -868,25 +938,25 @@ fn derive_archive_impl(
                         let fields = fields.named.iter().map(|f| {
                             let name = &f.ident;
                             let ty = with_ty(f).unwrap();
-                            quote! { #name: Archived<#ty> }
+                            quote! { #name: #rkyv_path::Archived<#ty> }
                         });
                         quote! {
                             #[repr(C)]
                             struct #archived_variant_name #generics #archive_where {
                                 __tag: ArchivedTag,
                                 #(#fields,)*
-                                __phantom: PhantomData<#name #ty_generics>,
+                                __phantom: ::core::marker::PhantomData<#name #ty_generics>,
                             }
                         }
                     }
                     Fields::Unnamed(ref fields) => {
                         let fields = fields.unnamed.iter().map(|f| {
                             let ty = with_ty(f).unwrap();
-                            quote! { Archived<#ty> }
+                            quote! { #rkyv_path::Archived<#ty> }
                         });
                         quote! {
                             #[repr(C)]
-                            struct #archived_variant_name #generics (ArchivedTag, #(#fields,)* PhantomData<#name #ty_generics>) #archive_where;
+                            struct #archived_variant_name #generics (ArchivedTag, #(#fields,)* ::core::marker::PhantomData<#name #ty_generics>) #archive_where;
                         }
                     }
                     Fields::Unit => quote! {}
@@ -911,7 +981,7 @@ fn derive_archive_impl(
                                         let wrapped_ty =
                                             with_ty(field).unwrap();
                                         partial_eq_where.predicates.push(
-                                            parse_quote! { Archived<#wrapped_ty>: PartialEq<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#wrapped_ty>: ::core::cmp::PartialEq<#ty> },
                                         );
                                     }
                                 }
@@ -925,7 +995,7 @@ fn derive_archive_impl(
                                         let wrapped_ty =
                                             with_ty(field).unwrap();
                                         partial_eq_where.predicates.push(
-                                            parse_quote! { Archived<#wrapped_ty>: PartialEq<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#wrapped_ty>: ::core::cmp::PartialEq<#ty> },
                                         );
                                     }
                                 }
@@ -952,7 +1022,7 @@ fn derive_archive_impl(
                                     }).collect::<Vec<_>>();
                                     quote! {
                                         #name::#variant { #(#field_names: #self_bindings,)* } => match other {
-                                            #archived_name::#variant { #(#field_names: #other_bindings,)* } => true #(&& #other_bindings.eq(#self_bindings))*,
+                                            #archived_name::#variant { #(#field_names: #other_bindings,)* } => true #(&& ::core::cmp::PartialEq::eq(#other_bindings, #self_bindings))*,
                                             #[allow(unreachable_patterns)]
                                             _ => false,
                                         }
@@ -967,7 +1037,7 @@ fn derive_archive_impl(
                                     }).collect::<Vec<_>>();
                                     quote! {
                                         #name::#variant(#(#self_bindings,)*) => match other {
-                                            #archived_name::#variant(#(#other_bindings,)*) => true #(&& #other_bindings.eq(#self_bindings))*,
+                                            #archived_name::#variant(#(#other_bindings,)*) => true #(&& ::core::cmp::PartialEq::eq(#other_bindings, #self_bindings))*,
                                             #[allow(unreachable_patterns)]
                                             _ => false,
                                         }
@@ -984,7 +1054,7 @@ fn derive_archive_impl(
                         });
 
                         partial_eq_impl = Some(quote! {
-                            impl #impl_generics PartialEq<#archived_type> for #name #ty_generics #partial_eq_where {
+                            impl #impl_generics ::core::cmp::PartialEq<#archived_type> for #name #ty_generics #partial_eq_where {
                                 #[inline]
                                 fn eq(&self, other: &#archived_type) -> bool {
                                     match self {
@@ -993,10 +1063,10 @@ fn derive_archive_impl(
                                 }
                             }
 
-                            impl #impl_generics PartialEq<#name #ty_generics> for #archived_type #partial_eq_where {
+                            impl #impl_generics ::core::cmp::PartialEq<#name #ty_generics> for #archived_type #partial_eq_where {
                                 #[inline]
                                 fn eq(&self, other: &#name #ty_generics) -> bool {
-                                    other.eq(self)
+                                    ::core::cmp::PartialEq::eq(other, self)
                                 }
                             }
                         });
@@ -1014,7 +1084,7 @@ fn derive_archive_impl(
                                         let wrapped_ty =
                                             with_ty(field).unwrap();
                                         partial_ord_where.predicates.push(
-                                            parse_quote! { Archived<#wrapped_ty>: PartialOrd<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#wrapped_ty>: ::core::cmp::PartialOrd<#ty> },
                                         );
                                     }
                                 }
@@ -1028,7 +1098,7 @@ fn derive_archive_impl(
                                         let wrapped_ty =
                                             with_ty(field).unwrap();
                                         partial_ord_where.predicates.push(
-                                            parse_quote! { Archived<#wrapped_ty>: PartialOrd<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#wrapped_ty>: ::core::cmp::PartialOrd<#ty> },
                                         );
                                     }
                                 }
@@ -1088,12 +1158,12 @@ fn derive_archive_impl(
                                         #name::#variant { #(#field_names: #self_bindings,)* } => match other {
                                             #archived_name::#variant { #(#field_names: #other_bindings,)* } => {
                                                 #(
-                                                    match #other_bindings.partial_cmp(#self_bindings) {
-                                                        Some(::core::cmp::Ordering::Equal) => (),
+                                                    match ::core::cmp::PartialOrd::partial_cmp(#other_bindings, #self_bindings) {
+                                                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
                                                         cmp => return cmp,
                                                     }
                                                 )*
-                                                Some(::core::cmp::Ordering::Equal)
+                                                ::core::option::Option::Some(::core::cmp::Ordering::Equal)
                                             }
                                             #[allow(unreachable_patterns)]
                                             _ => unsafe { ::core::hint::unreachable_unchecked() },
@@ -1111,12 +1181,12 @@ fn derive_archive_impl(
                                         #name::#variant(#(#self_bindings,)*) => match other {
                                             #archived_name::#variant(#(#other_bindings,)*) => {
                                                 #(
-                                                    match #other_bindings.partial_cmp(#self_bindings) {
-                                                        Some(::core::cmp::Ordering::Equal) => (),
+                                                    match ::core::cmp::PartialOrd::partial_cmp(#other_bindings, #self_bindings) {
+                                                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
                                                         cmp => return cmp,
                                                     }
                                                 )*
-                                                Some(::core::cmp::Ordering::Equal)
+                                                ::core::option::Option::Some(::core::cmp::Ordering::Equal)
                                             }
                                             #[allow(unreachable_patterns)]
                                             _ => unsafe { ::core::hint::unreachable_unchecked() },
@@ -1125,7 +1195,7 @@ fn derive_archive_impl(
                                 }
                                 Fields::Unit => quote! {
                                     #name::#variant => match other {
-                                        #archived_name::#variant => Some(::core::cmp::Ordering::Equal),
+                                        #archived_name::#variant => ::core::option::Option::Some(::core::cmp::Ordering::Equal),
                                         #[allow(unreachable_patterns)]
                                         _ => unsafe { ::core::hint::unreachable_unchecked() },
                                     }
@@ -1134,9 +1204,9 @@ fn derive_archive_impl(
                         });
 
                         partial_ord_impl = Some(quote! {
-                            impl #impl_generics PartialOrd<#archived_type> for #name #ty_generics #partial_ord_where {
+                            impl #impl_generics ::core::cmp::PartialOrd<#archived_type> for #name #ty_generics #partial_ord_where {
                                 #[inline]
-                                fn partial_cmp(&self, other: &#archived_type) -> Option<::core::cmp::Ordering> {
+                                fn partial_cmp(&self, other: &#archived_type) -> ::core::option::Option<::core::cmp::Ordering> {
                                     let self_disc = match self { #(#self_disc,)* };
                                     let other_disc = match other { #(#other_disc,)* };
                                     if self_disc == other_disc {
@@ -1144,17 +1214,17 @@ fn derive_archive_impl(
                                             #(#variant_impls,)*
                                         }
                                     } else {
-                                        self_disc.partial_cmp(&other_disc)
+                                        ::core::cmp::PartialOrd::partial_cmp(&self_disc, &other_disc)
                                     }
                                 }
                             }
 
-                            impl #impl_generics PartialOrd<#name #ty_generics> for #archived_type #partial_ord_where {
+                            impl #impl_generics ::core::cmp::PartialOrd<#name #ty_generics> for #archived_type #partial_ord_where {
                                 #[inline]
-                                fn partial_cmp(&self, other: &#name #ty_generics) -> Option<::core::cmp::Ordering> {
-                                    match other.partial_cmp(self) {
-                                        Some(::core::cmp::Ordering::Less) => Some(::core::cmp::Ordering::Greater),
-                                        Some(::core::cmp::Ordering::Greater) => Some(::core::cmp::Ordering::Less),
+                                fn partial_cmp(&self, other: &#name #ty_generics) -> ::core::option::Option<::core::cmp::Ordering> {
+                                    match ::core::cmp::PartialOrd::partial_cmp(other, self) {
+                                        ::core::option::Option::Some(::core::cmp::Ordering::Less) => ::core::option::Option::Some(::core::cmp::Ordering::Greater),
+                                        ::core::option::Option::Some(::core::cmp::Ordering::Greater) => ::core::option::Option::Some(::core::cmp::Ordering::Less),
                                         cmp => cmp,
                                     }
                                 }
@@ -1166,42 +1236,12 @@ fn derive_archive_impl(
                 }
             }
 
-            let copy_safe_impl = if cfg!(feature = "copy")
-                && attributes.copy_safe.is_some()
-            {
-                let mut copy_safe_where = where_clause.clone();
-                for variant in data.variants.iter() {
-                    match variant.fields {
-                        Fields::Named(ref fields) => {
-                            for field in
-                                fields.named.iter().filter(is_not_omitted)
-                            {
-                                let ty = with_ty(field).unwrap();
-                                copy_safe_where
-                                    .predicates
-                                    .push(parse_quote! { #ty: #rkyv_path::copy::ArchiveCopySafe });
-                            }
-                        }
-                        Fields::Unnamed(ref fields) => {
-                            for field in
-                                fields.unnamed.iter().filter(is_not_omitted)
-                            {
-                                let ty = with_ty(field).unwrap();
-                                copy_safe_where
-                                    .predicates
-                                    .push(parse_quote! { #ty: #rkyv_path::copy::ArchiveCopySafe });
-                            }
-                        }
-                        Fields::Unit => (),
-                    }
-                }
-
-                Some(quote! {
-                    unsafe impl #impl_generics #rkyv_path::copy::ArchiveCopySafe for #name #ty_generics #copy_safe_where {}
-                })
-            } else {
-                None
-            };
+            if let Some(ref copy_safe) = attributes.copy_safe {
+                return Err(Error::new_spanned(
+                    copy_safe,
+                    "copy_safe is not supported for enums",
+                ));
+            }
 
             (
                 quote! {
@@ -1221,14 +1261,14 @@ fn derive_archive_impl(
 
                     #(#archived_variant_structs)*
 
-                    impl #impl_generics Archive for #name #ty_generics #archive_where {
+                    impl #impl_generics #rkyv_path::Archive for #name #ty_generics #archive_where {
                         type Archived = #archived_type;
                         type Resolver = #resolver #ty_generics;
 
                         // Some resolvers will be (), this allow is to prevent clippy from complaining
                         #[allow(clippy::unit_arg)]
                         #[inline]
-                        unsafe fn resolve(&self, pos: usize, resolver: <Self as Archive>::Resolver, out: *mut <Self as Archive>::Archived) {
+                        unsafe fn resolve(&self, pos: usize, resolver: <Self as #rkyv_path::Archive>::Resolver, out: *mut <Self as #rkyv_path::Archive>::Archived) {
                             match resolver {
                                 #(#resolve_arms,)*
                             }
@@ -1237,7 +1277,6 @@ fn derive_archive_impl(
 
                     #partial_eq_impl
                     #partial_ord_impl
-                    #copy_safe_impl
                 },
             )
         }
@@ -1251,13 +1290,327 @@ fn derive_archive_impl(
 
     Ok(quote! {
         #archive_types
+        #kind_type
+        #columns_types
+        #project_type
 
         #[automatically_derived]
         const _: () = {
-            use core::marker::PhantomData;
-            use #rkyv_path::{out_field, Archive, Archived};
-
             #archive_impls
+            #layout_impl
+            #std_impls
+            #deep_size_impl
+            #stable_hash_impl
+            #render_json_impl
+            #format_stable_impl
+            #stable_layout_impl
+            #reflect_impl
+            #rearchive_impl
+            #type_hash_impl
+            #niche_impl
+            #no_rel_ptrs_impl
+            #project_impl
+            #callable_impl
+            #convenience_impl
+            #c_api_impl
+            #columns_impls
+            #accessors_impl
+            #packed_flag_impl
         };
     })
 }
+
+fn copy_safe(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_type: &Type,
+    fields: &Fields,
+) -> Result<(Option<TokenStream>, Option<TokenStream>), Error> {
+    if attributes.copy_safe.is_none() {
+        return Ok((None, None));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    let mut copy_safe_where = where_clause.unwrap().clone();
+    let mut members = Vec::new();
+    let mut tys = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let ty = with_ty(field)?;
+        if is_not_omitted(&field) {
+            copy_safe_where.predicates.push(field_predicate(
+                &ty,
+                quote! { #ty: #rkyv_path::copy::ArchiveCopyOptimize },
+            ));
+        }
+        members.push(match field.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        });
+        tys.push(ty);
+    }
+
+    let message = format!(
+        "`{}` is copy_safe but contains padding or is laid out differently \
+         from its archived type",
+        name,
+    );
+    let copy_optimization = quote! {
+        const COPY_OPTIMIZATION: #rkyv_path::copy::CopyOptimization<Self> = {
+            ::core::assert!(
+                ::core::mem::size_of::<Self>() == ::core::mem::size_of::<#archived_type>()
+                    && ::core::mem::size_of::<Self>() == 0 #(+ ::core::mem::size_of::<#tys>())*
+                    #(&& ::core::mem::offset_of!(Self, #members) == ::core::mem::offset_of!(#archived_type, #members))*,
+                #message,
+            );
+            // SAFETY: `Self` has no padding and each of its fields is at the
+            // same offset as in the archived type. If all of the fields are
+            // copy-optimized, then so is `Self`.
+            unsafe {
+                #rkyv_path::copy::CopyOptimization::enable_if(
+                    true #(&& <#tys as #rkyv_path::Archive>::COPY_OPTIMIZATION.is_enabled())*
+                )
+            }
+        };
+    };
+
+    // Non-generic types are checked eagerly so that layout errors are reported
+    // even if the copy optimization is never used.
+    let eager_check = if input.generics.params.is_empty() {
+        Some(quote! {
+            const _: () = {
+                let _ = <#name as #rkyv_path::Archive>::COPY_OPTIMIZATION;
+            };
+        })
+    } else {
+        None
+    };
+
+    let copy_safe_impl = quote! {
+        // SAFETY: The layout of the type is checked when its copy
+        // optimization is evaluated.
+        unsafe impl #impl_generics #rkyv_path::copy::ArchiveCopyOptimize for #name #ty_generics #copy_safe_where {}
+
+        #eager_check
+    };
+
+    Ok((Some(copy_optimization), Some(copy_safe_impl)))
+}
+
+/// Implements `ArchivedNoRelPtrs` for the archived type when all of its fields
+/// implement it.
+///
+/// The field bounds are higher-ranked so that types with fields which may
+/// contain relative pointers compile, and just don't implement the trait.
+fn no_rel_ptrs(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    if attributes.archive_as.is_some() || attributes.pack_flags.is_some() {
+        return Ok(None);
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    let fields: Vec<&Field> = match input.data {
+        Data::Struct(ref data) => data.fields.iter().collect(),
+        Data::Enum(ref data) => {
+            data.variants.iter().flat_map(|v| v.fields.iter()).collect()
+        }
+        Data::Union(_) => return Ok(None),
+    };
+
+    let mut no_rel_ptrs_where = where_clause.unwrap().clone();
+    for field in fields {
+        let ty = with_ty(field)?;
+        if is_not_omitted(&field) {
+            no_rel_ptrs_where.predicates.push(field_predicate(
+                &ty,
+                quote! { #ty: #rkyv_path::Archive },
+            ));
+        }
+        no_rel_ptrs_where.predicates.push(field_predicate(
+            &ty,
+            quote! {
+                for<'__a> #rkyv_path::Archived<#ty>: #rkyv_path::unpin::ArchivedNoRelPtrs
+            },
+        ));
+    }
+
+    Ok(Some(quote! {
+        // SAFETY: The archived type only contains its fields and, for enums,
+        // a tag, and none of its fields contain relative pointers.
+        unsafe impl #impl_generics #rkyv_path::unpin::ArchivedNoRelPtrs for #archived_name #ty_generics #no_rel_ptrs_where {}
+    }))
+}
+
+fn export_layout(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    // `structural_hash` implies `export_layout`
+    let (export_layout, option) =
+        match (&attributes.export_layout, &attributes.structural_hash) {
+            (_, Some(structural_hash)) => (structural_hash, "structural_hash"),
+            (Some(export_layout), None) => (export_layout, "export_layout"),
+            (None, None) => return Ok(None),
+        };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            export_layout,
+            format!("{} is not supported for generic types", option),
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let layout = quote! { #rkyv_path::layout };
+
+    // Describes each of the fields of `ty`, which may be the archived type or
+    // one of its variant structs. Fields of variant structs are offset by one
+    // to skip over the tag. Also returns the offset and structural hash of
+    // each field.
+    let describe_fields = |ty: &Ident, fields: &Fields, skip: usize| {
+        let mut hashes = Vec::new();
+        let fields = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let (name, member) = match f.ident {
+                    Some(ref ident) => {
+                        (strip_raw(ident), Member::Named(ident.clone()))
+                    }
+                    None => (
+                        format!("_{}", i),
+                        Member::Unnamed(Index::from(i + skip)),
+                    ),
+                };
+                let ty_field = with_ty(f)?;
+                hashes.push(quote! {
+                    ::core::mem::offset_of!(#ty, #member) as u64,
+                    <
+                        #rkyv_path::Archived<#ty_field>
+                        as #layout::StructuralHash
+                    >::STRUCTURAL_HASH,
+                });
+                Ok(quote! {
+                    #layout::FieldLayout::new(
+                        #name,
+                        ::core::mem::offset_of!(#ty, #member),
+                        registry.register::<#rkyv_path::Archived<#ty_field>>(),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok::<_, Error>((
+            quote! { ::core::convert::From::from([#(#fields,)*]) },
+            hashes,
+        ))
+    };
+
+    let (kind, hash) = match input.data {
+        Data::Struct(ref data) => {
+            let (fields, hashes) =
+                describe_fields(archived_name, &data.fields, 0)?;
+            (
+                quote! { #layout::LayoutKind::Struct { fields: #fields } },
+                quote! {
+                    #layout::hash_structure::<Self>("struct", &[#(#hashes)*])
+                },
+            )
+        }
+        Data::Enum(ref data) => {
+            let tag = match data.variants.len() as u128 {
+                0..=255 => quote! { u8 },
+                256..=65_535 => quote! { #layout::__private::U16 },
+                65_536..=4_294_967_295 => {
+                    quote! { #layout::__private::U32 }
+                }
+                _ => quote! { #layout::__private::U64 },
+            };
+            let mut variant_hashes = Vec::new();
+            let variants = data
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let name = strip_raw(&v.ident);
+                    let variant_struct = Ident::new(
+                        &format!("ArchivedVariant{}", name),
+                        v.span(),
+                    );
+                    let (fields, hashes) =
+                        describe_fields(&variant_struct, &v.fields, 1)?;
+                    let tag = i as u64;
+                    let len = v.fields.len() as u64;
+                    variant_hashes.push(quote! { #tag, #len, #(#hashes)* });
+                    Ok(quote! {
+                        #layout::VariantLayout::new(#name, #tag, #fields)
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let len = data.variants.len() as u64;
+            (
+                quote! {
+                    #layout::LayoutKind::Enum {
+                        tag: registry.register::<#tag>(),
+                        variants: ::core::convert::From::from([
+                            #(#variants,)*
+                        ]),
+                    }
+                },
+                quote! {
+                    #layout::hash_structure::<Self>(
+                        "enum",
+                        &[
+                            <#tag as #layout::StructuralHash>::STRUCTURAL_HASH,
+                            #len,
+                            #(#variant_hashes)*
+                        ],
+                    )
+                },
+            )
+        }
+        Data::Union(_) => return Ok(None),
+    };
+
+    let structural_hash_impl = attributes.structural_hash.as_ref().map(|_| {
+        quote! {
+            // SAFETY: The hash covers the size and alignment of the archived
+            // type and the offset and structure of each of its fields, in the
+            // same order as its layout.
+            unsafe impl #layout::StructuralHash for #archived_name {
+                const STRUCTURAL_HASH: u64 = #hash;
+            }
+        }
+    });
+
+    let name = archived_name.to_string();
+    Ok(Some(quote! {
+        #structural_hash_impl
+
+        impl #layout::DescribeLayout for #archived_name {
+            fn layout_name() -> #layout::__private::String {
+                ::core::convert::From::from(#name)
+            }
+
+            fn describe_layout(
+                registry: &mut #layout::LayoutRegistry,
+            ) -> #layout::TypeLayout {
+                #layout::TypeLayout::of::<Self>(
+                    <Self as #layout::DescribeLayout>::layout_name(),
+                    #kind,
+                )
+            }
+        }
+    }))
+}
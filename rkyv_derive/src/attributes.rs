@@ -1,10 +1,261 @@
 use quote::ToTokens;
 use syn::{
     meta::ParseNestedMeta, parenthesized, parse::Parse, parse_quote,
-    punctuated::Punctuated, AttrStyle, DeriveInput, Error, Ident, LitStr, Meta,
-    Path, Token, WherePredicate,
+    punctuated::Punctuated, token, AttrStyle, Data, DeriveInput, Error, Field,
+    Ident, LitInt, LitStr, Meta, Path, Token, WherePredicate,
 };
 
+use crate::util::suggest;
+
+const ARGUMENTS: &[&str] = &[
+    "archived",
+    "resolver",
+    "as",
+    "transparent",
+    "compare",
+    "archive_bounds",
+    "serialize_bounds",
+    "deserialize_bounds",
+    "check_bytes",
+    "copy_safe",
+    "preserve_order",
+    "export_layout",
+    "structural_hash",
+    "columnar",
+    "derive_std",
+    "deep_size",
+    "stable_hash",
+    "render_json",
+    "format_stable",
+    "stable_layout",
+    "callable",
+    "c_api",
+    "no_accessors",
+    "pack_flags",
+    "reflect",
+    "rearchive",
+    "type_hash",
+    "niche",
+    "project",
+    "convenience",
+    "crate",
+];
+
+const COMPARES: &[&str] = &["PartialEq", "PartialOrd"];
+
+// Derives which are added to archived types by `derive_std`.
+pub const STD_DERIVES: &[&str] =
+    &["Debug", "PartialEq", "Eq", "Hash", "PartialOrd", "Ord"];
+
+// Derives which are commonly passed through to archived types. Derives which
+// aren't in this list are passed through as long as they don't look like a
+// misspelling of one that is.
+const KNOWN_DERIVES: &[&str] = &[
+    "Clone",
+    "Copy",
+    "Debug",
+    "Default",
+    "PartialEq",
+    "Eq",
+    "PartialOrd",
+    "Ord",
+    "Hash",
+    "CheckBytes",
+];
+
+fn path_to_string(path: &Path) -> String {
+    path.to_token_stream().to_string().replace(' ', "")
+}
+
+// Parses the value of an argument which may be either a path or a string
+// containing a path.
+fn parse_path_value(meta: &ParseNestedMeta<'_>) -> Result<Path, Error> {
+    let value = meta.value()?;
+    if value.peek(LitStr) {
+        value.parse::<LitStr>()?.parse()
+    } else {
+        value.parse()
+    }
+}
+
+fn is_c_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn did_you_mean(name: &str, candidates: &[&str]) -> String {
+    suggest(name, candidates)
+        .map(|s| format!(", did you mean `{}`?", s))
+        .unwrap_or_default()
+}
+
+fn unrecognized_argument(meta: &ParseNestedMeta<'_>) -> Error {
+    let name = path_to_string(&meta.path);
+    let hint = match name.as_str() {
+        "repr" => {
+            "\nuse `#[archive_attr(repr(...))]` to set the representation \
+            of the archived type"
+                .to_string()
+        }
+        "bound" => "\nuse `archive_bounds(...)`, `serialize_bounds(...)`, or \
+            `deserialize_bounds(...)` instead"
+            .to_string(),
+        "derive" => {
+            "\nuse `#[archive_attr(derive(...))]` to derive traits for \
+            the archived type"
+                .to_string()
+        }
+        "with" | "omit_bounds" => format!(
+            "\n`{}` must be placed on a field as `#[{}]`",
+            name,
+            if name == "with" {
+                "with(...)"
+            } else {
+                "omit_bounds"
+            },
+        ),
+        _ => did_you_mean(&name, ARGUMENTS),
+    };
+    meta.error(format!("unrecognized archive argument `{}`{}", name, hint))
+}
+
+fn validate_compares(
+    compares: &Punctuated<Path, Token![,]>,
+) -> Result<(), Error> {
+    for compare in compares {
+        let name = path_to_string(compare);
+        if !COMPARES.contains(&name.as_str()) {
+            return Err(Error::new_spanned(
+                compare,
+                format!(
+                    "unrecognized compare argument `{}`{}\nsupported \
+                     compares are PartialEq and PartialOrd",
+                    name,
+                    did_you_mean(&name, COMPARES),
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_archive_attr(meta: &Meta) -> Result<(), Error> {
+    if let Meta::List(list) = meta {
+        if list.path.is_ident("derive") {
+            let derives = list.parse_args_with(
+                Punctuated::<Path, Token![,]>::parse_terminated,
+            )?;
+            for derive in derives.iter() {
+                if let Some(ident) = derive.get_ident() {
+                    let name = ident.to_string();
+                    if let Some(known) = suggest(&name, KNOWN_DERIVES) {
+                        return Err(Error::new_spanned(
+                            derive,
+                            format!(
+                                "unknown derive `{}`, did you mean `{}`?",
+                                name, known,
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_field(field: &Field) -> Result<(), Error> {
+    for attr in field.attrs.iter() {
+        let path = attr.path();
+        if path.is_ident("with") {
+            if !matches!(attr.meta, Meta::List(_)) {
+                return Err(Error::new_spanned(
+                    attr,
+                    "expected `#[with(...)]` with at least one wrapper",
+                ));
+            }
+        } else if path.is_ident("omit_bounds") {
+            if !matches!(attr.meta, Meta::Path(_)) {
+                return Err(Error::new_spanned(
+                    attr,
+                    "`omit_bounds` does not take any arguments, use \
+                     `#[omit_bounds]`",
+                ));
+            }
+        } else if path.is_ident("archive") || path.is_ident("rkyv") {
+            field_bits(field)?;
+        } else if path.is_ident("archive_attr") {
+            let metas = attr.parse_args_with(
+                Punctuated::<Meta, Token![,]>::parse_terminated,
+            )?;
+            for meta in metas.iter() {
+                validate_archive_attr(meta)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the number of bits given by a `#[archive(bits = N)]` attribute on
+/// the field, if it has one.
+pub fn field_bits(field: &Field) -> Result<Option<LitInt>, Error> {
+    let mut result = None;
+    for attr in field.attrs.iter() {
+        if !attr.path().is_ident("archive") && !attr.path().is_ident("rkyv") {
+            continue;
+        }
+
+        let mut bits = None;
+        let mut unsupported = !matches!(attr.meta, Meta::List(_));
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("bits") {
+                let value = meta.value()?.parse::<LitInt>()?;
+                let n = value.base10_parse::<u32>()?;
+                if n == 0 || n > 64 {
+                    return Err(Error::new_spanned(
+                        &value,
+                        "bits must be between 1 and 64",
+                    ));
+                }
+                try_set_attribute(&mut bits, value, "bits")
+            } else {
+                unsupported = true;
+                Err(meta.error("unsupported field argument"))
+            }
+        });
+        if unsupported {
+            return Err(Error::new_spanned(
+                attr,
+                "archive arguments other than `bits = ...` are not supported \
+                 on fields\nuse `#[with(...)]`, `#[omit_bounds]`, or \
+                 `#[archive_attr(...)]` instead",
+            ));
+        }
+        parsed?;
+        if let Some(bits) = bits {
+            try_set_attribute(&mut result, bits, "bits")?;
+        }
+    }
+    Ok(result)
+}
+
+fn validate_fields(data: &Data) -> Result<(), Error> {
+    match data {
+        Data::Struct(data) => data.fields.iter().try_for_each(validate_field),
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .flat_map(|v| v.fields.iter())
+            .try_for_each(validate_field),
+        Data::Union(data) => {
+            data.fields.named.iter().try_for_each(validate_field)
+        }
+    }
+}
+
 fn try_set_attribute<T: ToTokens>(
     attribute: &mut Option<T>,
     value: T,
@@ -21,9 +272,33 @@ fn try_set_attribute<T: ToTokens>(
     }
 }
 
+fn conflict<T: ToTokens>(tokens: T, name: &str, archive_as: &LitStr) -> Error {
+    Error::new_spanned(
+        tokens,
+        format!(
+            "`{}` may not be used with `as = \"{}\"` because no archived type \
+             is generated",
+            name,
+            archive_as.value(),
+        ),
+    )
+}
+
+fn transparent_conflict<T: ToTokens>(tokens: T, name: &str) -> Error {
+    Error::new_spanned(
+        tokens,
+        format!(
+            "`{}` may not be used with `transparent` because no archived type \
+             is generated for it",
+            name,
+        ),
+    )
+}
+
 #[derive(Default)]
 pub struct Attributes {
     pub archive_as: Option<LitStr>,
+    pub transparent: Option<Path>,
     pub archived: Option<Ident>,
     pub resolver: Option<Ident>,
     pub attrs: Vec<Meta>,
@@ -33,6 +308,32 @@ pub struct Attributes {
     pub deserialize_bounds: Option<Punctuated<WherePredicate, Token![,]>>,
     pub check_bytes: Option<Path>,
     pub copy_safe: Option<Path>,
+    pub preserve_order: Option<Path>,
+    pub export_layout: Option<Path>,
+    pub structural_hash: Option<Path>,
+    pub columnar: Option<Path>,
+    pub derive_std: Option<Path>,
+    pub deep_size: Option<Path>,
+    pub stable_hash: Option<Path>,
+    pub render_json: Option<Path>,
+    pub format_stable: Option<Path>,
+    pub format_stable_size: Option<LitInt>,
+    pub format_stable_align: Option<LitInt>,
+    pub stable_layout: Option<Path>,
+    pub callable: Option<Path>,
+    pub callable_registry: Option<Path>,
+    pub callable_function: Option<Path>,
+    pub c_api: Option<Path>,
+    pub c_api_prefix: Option<LitStr>,
+    pub no_accessors: Option<Path>,
+    pub pack_flags: Option<Path>,
+    pub reflect: Option<Path>,
+    pub rearchive: Option<Path>,
+    pub type_hash: Option<Path>,
+    pub niche: Option<Path>,
+    pub project: Option<Path>,
+    pub convenience: Option<Path>,
+    pub convenience_config: Option<Path>,
     rkyv_path: Option<Path>,
 }
 
@@ -44,16 +345,274 @@ impl Attributes {
             }
 
             try_set_attribute(&mut self.check_bytes, meta.path, "check_bytes")
+        } else if meta.path.is_ident("transparent") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("transparent argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.transparent, meta.path, "transparent")
         } else if meta.path.is_ident("copy_safe") {
             if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
                 return Err(meta.error("copy_safe argument must be a path"));
             }
 
             try_set_attribute(&mut self.copy_safe, meta.path, "copy_safe")
+        } else if meta.path.is_ident("preserve_order") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(
+                    meta.error("preserve_order argument must be a path")
+                );
+            }
+
+            try_set_attribute(
+                &mut self.preserve_order,
+                meta.path,
+                "preserve_order",
+            )
+        } else if meta.path.is_ident("export_layout") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("export_layout argument must be a path"));
+            }
+
+            try_set_attribute(
+                &mut self.export_layout,
+                meta.path,
+                "export_layout",
+            )
+        } else if meta.path.is_ident("structural_hash") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(
+                    meta.error("structural_hash argument must be a path")
+                );
+            }
+
+            try_set_attribute(
+                &mut self.structural_hash,
+                meta.path,
+                "structural_hash",
+            )
+        } else if meta.path.is_ident("columnar") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("columnar argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.columnar, meta.path, "columnar")
+        } else if meta.path.is_ident("derive_std") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("derive_std argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.derive_std, meta.path, "derive_std")
+        } else if meta.path.is_ident("deep_size") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("deep_size argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.deep_size, meta.path, "deep_size")
+        } else if meta.path.is_ident("stable_hash") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("stable_hash argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.stable_hash, meta.path, "stable_hash")
+        } else if meta.path.is_ident("render_json") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("render_json argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.render_json, meta.path, "render_json")
+        } else if meta.path.is_ident("stable_layout") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("stable_layout argument must be a path"));
+            }
+
+            try_set_attribute(
+                &mut self.stable_layout,
+                meta.path,
+                "stable_layout",
+            )
+        } else if meta.path.is_ident("format_stable") {
+            if meta.input.peek(token::Paren) {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("size") {
+                        try_set_attribute(
+                            &mut self.format_stable_size,
+                            meta.value()?.parse()?,
+                            "size",
+                        )
+                    } else if meta.path.is_ident("align") {
+                        try_set_attribute(
+                            &mut self.format_stable_align,
+                            meta.value()?.parse()?,
+                            "align",
+                        )
+                    } else {
+                        let name = path_to_string(&meta.path);
+                        Err(meta.error(format!(
+                            "unrecognized format_stable argument `{}`{}",
+                            name,
+                            did_you_mean(&name, &["size", "align"]),
+                        )))
+                    }
+                })?;
+            } else if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error(
+                    "expected `format_stable` or `format_stable(...)`",
+                ));
+            }
+
+            try_set_attribute(
+                &mut self.format_stable,
+                meta.path,
+                "format_stable",
+            )
+        } else if meta.path.is_ident("no_accessors") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("no_accessors argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.no_accessors, meta.path, "no_accessors")
+        } else if meta.path.is_ident("pack_flags") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("pack_flags argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.pack_flags, meta.path, "pack_flags")
+        } else if meta.path.is_ident("reflect") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("reflect argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.reflect, meta.path, "reflect")
+        } else if meta.path.is_ident("rearchive") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("rearchive argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.rearchive, meta.path, "rearchive")
+        } else if meta.path.is_ident("type_hash") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("type_hash argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.type_hash, meta.path, "type_hash")
+        } else if meta.path.is_ident("niche") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("niche argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.niche, meta.path, "niche")
+        } else if meta.path.is_ident("project") {
+            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error("project argument must be a path"));
+            }
+
+            try_set_attribute(&mut self.project, meta.path, "project")
+        } else if meta.path.is_ident("convenience") {
+            if meta.input.peek(token::Paren) {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("config") {
+                        try_set_attribute(
+                            &mut self.convenience_config,
+                            parse_path_value(&meta)?,
+                            "config",
+                        )
+                    } else {
+                        let name = path_to_string(&meta.path);
+                        Err(meta.error(format!(
+                            "unrecognized convenience argument `{}`{}",
+                            name,
+                            did_you_mean(&name, &["config"]),
+                        )))
+                    }
+                })?;
+            } else if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                return Err(meta.error(
+                    "expected `convenience` or `convenience(config = ...)`",
+                ));
+            }
+
+            try_set_attribute(&mut self.convenience, meta.path, "convenience")
+        } else if meta.path.is_ident("callable") {
+            if !meta.input.peek(token::Paren) {
+                return Err(meta.error(
+                    "expected `callable(registry = ..., function = ...)`",
+                ));
+            }
+
+            meta.parse_nested_meta(|meta| {
+                if meta.path.is_ident("registry") {
+                    try_set_attribute(
+                        &mut self.callable_registry,
+                        parse_path_value(&meta)?,
+                        "registry",
+                    )
+                } else if meta.path.is_ident("function") {
+                    try_set_attribute(
+                        &mut self.callable_function,
+                        parse_path_value(&meta)?,
+                        "function",
+                    )
+                } else {
+                    let name = path_to_string(&meta.path);
+                    Err(meta.error(format!(
+                        "unrecognized callable argument `{}`{}",
+                        name,
+                        did_you_mean(&name, &["registry", "function"]),
+                    )))
+                }
+            })?;
+            if self.callable_registry.is_none() {
+                return Err(Error::new_spanned(
+                    meta.path,
+                    "callable requires a `registry`",
+                ));
+            }
+            if self.callable_function.is_none() {
+                return Err(Error::new_spanned(
+                    meta.path,
+                    "callable requires a `function`",
+                ));
+            }
+
+            try_set_attribute(&mut self.callable, meta.path, "callable")
+        } else if meta.path.is_ident("c_api") {
+            if !meta.input.peek(token::Paren) {
+                return Err(meta.error("expected `c_api(prefix = \"...\")`"));
+            }
+
+            meta.parse_nested_meta(|meta| {
+                if meta.path.is_ident("prefix") {
+                    let prefix = meta.value()?.parse::<LitStr>()?;
+                    if !is_c_identifier(&prefix.value()) {
+                        return Err(Error::new_spanned(
+                            prefix,
+                            "c_api prefix must be a valid C identifier",
+                        ));
+                    }
+                    try_set_attribute(&mut self.c_api_prefix, prefix, "prefix")
+                } else {
+                    let name = path_to_string(&meta.path);
+                    Err(meta.error(format!(
+                        "unrecognized c_api argument `{}`{}",
+                        name,
+                        did_you_mean(&name, &["prefix"]),
+                    )))
+                }
+            })?;
+            if self.c_api_prefix.is_none() {
+                return Err(Error::new_spanned(
+                    meta.path,
+                    "c_api requires a `prefix`",
+                ));
+            }
+
+            try_set_attribute(&mut self.c_api, meta.path, "c_api")
         } else if meta.path.is_ident("compare") {
             let traits;
             parenthesized!(traits in meta.input);
             let traits = traits.parse_terminated(Path::parse, Token![,])?;
+            validate_compares(&traits)?;
             try_set_attribute(&mut self.compares, traits, "compare")
         } else if meta.path.is_ident("archive_bounds") {
             let bounds;
@@ -105,7 +664,11 @@ impl Attributes {
             )
         } else if meta.path.is_ident("crate") {
             if meta.input.parse::<Token![=]>().is_ok() {
-                let path = meta.input.parse::<Path>()?;
+                let path = if meta.input.peek(LitStr) {
+                    meta.input.parse::<LitStr>()?.parse::<Path>()?
+                } else {
+                    meta.input.parse::<Path>()?
+                };
                 try_set_attribute(&mut self.rkyv_path, path, "crate")
             } else if meta.input.is_empty() {
                 try_set_attribute(
@@ -117,7 +680,7 @@ impl Attributes {
                 Err(meta.error("expected `crate` or `crate = ...`"))
             }
         } else {
-            Err(meta.error("unrecognized archive argument"))
+            Err(unrecognized_argument(&meta))
         }
     }
 
@@ -128,21 +691,210 @@ impl Attributes {
                 continue;
             }
 
-            if attr.path().is_ident("archive") {
+            if attr.path().is_ident("archive") || attr.path().is_ident("rkyv") {
                 attr.parse_nested_meta(|meta| result.parse_meta(meta))?;
             } else if attr.path().is_ident("archive_attr") {
-                result.attrs.extend(
-                    attr.parse_args_with(
-                        Punctuated::<Meta, Token![,]>::parse_terminated,
-                    )?
-                    .into_iter(),
-                );
+                let metas = attr.parse_args_with(
+                    Punctuated::<Meta, Token![,]>::parse_terminated,
+                )?;
+                for meta in metas.iter() {
+                    validate_archive_attr(meta)?;
+                }
+                result.attrs.extend(metas);
+            }
+        }
+
+        result.validate()?;
+        validate_fields(&input.data)?;
+        if result.pack_flags.is_none() {
+            let fields = match input.data {
+                Data::Struct(ref data) => data.fields.iter().collect(),
+                Data::Enum(ref data) => {
+                    data.variants.iter().flat_map(|v| v.fields.iter()).collect()
+                }
+                Data::Union(_) => Vec::new(),
+            };
+            for field in fields {
+                if let Some(bits) = field_bits(field)? {
+                    return Err(Error::new_spanned(
+                        bits,
+                        "`bits` requires `#[archive(pack_flags)]` on the struct",
+                    ));
+                }
             }
         }
 
         Ok(result)
     }
 
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(ref archive_as) = self.archive_as {
+            if let Some(ref ident) = self.archived {
+                return Err(conflict(ident, "archived", archive_as));
+            }
+            if let Some(ref path) = self.check_bytes {
+                let error = conflict(path, "check_bytes", archive_as);
+                return Err(Error::new_spanned(
+                    path,
+                    format!(
+                        "{}\nderive `CheckBytes` on the archived type instead",
+                        error,
+                    ),
+                ));
+            }
+            if let Some(ref path) = self.preserve_order {
+                return Err(conflict(path, "preserve_order", archive_as));
+            }
+            if let Some(ref path) = self.export_layout {
+                return Err(conflict(path, "export_layout", archive_as));
+            }
+            if let Some(ref path) = self.structural_hash {
+                return Err(conflict(path, "structural_hash", archive_as));
+            }
+            if let Some(ref path) = self.columnar {
+                return Err(conflict(path, "columnar", archive_as));
+            }
+            if let Some(ref path) = self.derive_std {
+                return Err(conflict(path, "derive_std", archive_as));
+            }
+            if let Some(ref path) = self.deep_size {
+                return Err(conflict(path, "deep_size", archive_as));
+            }
+            if let Some(ref path) = self.stable_hash {
+                return Err(conflict(path, "stable_hash", archive_as));
+            }
+            if let Some(ref path) = self.render_json {
+                return Err(conflict(path, "render_json", archive_as));
+            }
+            if let Some(ref path) = self.format_stable {
+                return Err(conflict(path, "format_stable", archive_as));
+            }
+            if let Some(ref path) = self.stable_layout {
+                return Err(conflict(path, "stable_layout", archive_as));
+            }
+            if let Some(ref path) = self.pack_flags {
+                return Err(conflict(path, "pack_flags", archive_as));
+            }
+            if let Some(ref path) = self.reflect {
+                return Err(conflict(path, "reflect", archive_as));
+            }
+            if let Some(ref path) = self.rearchive {
+                return Err(conflict(path, "rearchive", archive_as));
+            }
+            if let Some(ref path) = self.niche {
+                return Err(conflict(path, "niche", archive_as));
+            }
+            if let Some(ref path) = self.project {
+                return Err(conflict(path, "project", archive_as));
+            }
+            if let Some(ref path) = self.c_api {
+                return Err(conflict(path, "c_api", archive_as));
+            }
+        }
+
+        if self.transparent.is_some() {
+            if let Some(ref ident) = self.archived {
+                return Err(transparent_conflict(ident, "archived"));
+            }
+            if let Some(ref ident) = self.resolver {
+                return Err(transparent_conflict(ident, "resolver"));
+            }
+            if let Some(ref compares) = self.compares {
+                return Err(transparent_conflict(compares, "compare"));
+            }
+            if let Some(first) = self.attrs.first() {
+                return Err(transparent_conflict(first, "archive_attr"));
+            }
+            let flags = [
+                (&self.check_bytes, "check_bytes"),
+                (&self.copy_safe, "copy_safe"),
+                (&self.preserve_order, "preserve_order"),
+                (&self.export_layout, "export_layout"),
+                (&self.structural_hash, "structural_hash"),
+                (&self.columnar, "columnar"),
+                (&self.derive_std, "derive_std"),
+                (&self.deep_size, "deep_size"),
+                (&self.stable_hash, "stable_hash"),
+                (&self.render_json, "render_json"),
+                (&self.format_stable, "format_stable"),
+                (&self.stable_layout, "stable_layout"),
+                (&self.callable, "callable"),
+                (&self.c_api, "c_api"),
+                (&self.pack_flags, "pack_flags"),
+                (&self.reflect, "reflect"),
+                (&self.rearchive, "rearchive"),
+                (&self.type_hash, "type_hash"),
+                (&self.niche, "niche"),
+                (&self.project, "project"),
+            ];
+            for (flag, name) in flags {
+                if let Some(path) = flag {
+                    return Err(transparent_conflict(path, name));
+                }
+            }
+        }
+
+        if let Some(implied_by) = self.derives_check_bytes() {
+            if let Some(path) = self.find_derive(&["CheckBytes"]) {
+                return Err(Error::new_spanned(
+                    path,
+                    format!(
+                        "`CheckBytes` is already derived by `{}`",
+                        path_to_string(implied_by),
+                    ),
+                ));
+            }
+        }
+
+        if self.derive_std.is_some() {
+            if let Some(path) = self.find_derive(STD_DERIVES) {
+                return Err(Error::new_spanned(
+                    &path,
+                    format!(
+                        "`{}` is already derived by `derive_std`",
+                        path_to_string(&path),
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the first derive passed through to the archived type whose name
+    // is one of `names`.
+    pub fn find_derive(&self, names: &[&str]) -> Option<Path> {
+        self.attrs.iter().find_map(|meta| {
+            let list = match meta {
+                Meta::List(list) if list.path.is_ident("derive") => list,
+                _ => return None,
+            };
+            list.parse_args_with(
+                Punctuated::<Path, Token![,]>::parse_terminated,
+            )
+            .ok()?
+            .into_iter()
+            .find(|path| {
+                path.segments
+                    .last()
+                    .is_some_and(|s| names.iter().any(|n| s.ident == n))
+            })
+        })
+    }
+
+    // Returns the argument which causes `CheckBytes` to be derived for the
+    // archived type, if any. `convenience` needs it for the checked
+    // `from_archive_bytes` method.
+    pub fn derives_check_bytes(&self) -> Option<&Path> {
+        self.check_bytes
+            .as_ref()
+            .or(if cfg!(feature = "bytecheck") {
+                self.convenience.as_ref()
+            } else {
+                None
+            })
+    }
+
     pub fn rkyv_path(&self) -> Path {
         self.rkyv_path
             .clone()
@@ -0,0 +1,162 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Field, Fields, Ident, Type};
+
+use crate::{attributes::Attributes, util::strip_raw, with::make_with_ty};
+
+/// Generates the `extern "C"` functions and the `CApi` and `CValue`
+/// implementations for the archived type of a `c_api` type.
+pub fn c_api(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let c_api = match attributes.c_api {
+        Some(ref c_api) => c_api,
+        None => return Ok(None),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            c_api,
+            "c_api is not supported for generic types",
+        ));
+    }
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => Some(&fields.named),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| {
+        Error::new_spanned(
+            c_api,
+            "c_api is only supported for structs with named fields",
+        )
+    })?;
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let c_api = quote! { #rkyv_path::c_api };
+    let prefix = attributes.c_api_prefix.as_ref().unwrap().value();
+    let archived_name_str = strip_raw(archived_name);
+
+    let check = format_ident!("{}_check", prefix);
+    let root = format_ident!("{}_root", prefix);
+
+    let mut descriptors = Vec::new();
+    let mut functions = Vec::new();
+    for field in fields.iter() {
+        let ident = field.ident.as_ref().unwrap();
+        let name = strip_raw(ident);
+        let ty = with_ty(field)?;
+        let archived = quote! { #rkyv_path::Archived<#ty> };
+
+        let getter = format_ident!("{}_get_{}", prefix, name);
+        functions.push(quote! {
+            #[unsafe(no_mangle)]
+            pub unsafe extern "C" fn #getter(
+                handle: *const ::core::ffi::c_void,
+                out: *mut <#archived as #c_api::CValue>::Out,
+            ) -> ::core::ffi::c_int {
+                unsafe {
+                    #c_api::get_field::<#archived_name, #archived>(
+                        handle,
+                        out,
+                        |archived| &archived.#ident,
+                    )
+                }
+            }
+        });
+
+        let value_c_type = if is_map(field) {
+            let lookup = format_ident!("{}_{}_get", prefix, name);
+            let value = quote! { <#archived as #c_api::CMap>::Value };
+            functions.push(quote! {
+                #[unsafe(no_mangle)]
+                pub unsafe extern "C" fn #lookup(
+                    table: *const ::core::ffi::c_void,
+                    key: *const u8,
+                    key_len: usize,
+                    out: *mut <#value as #c_api::CValue>::Out,
+                ) -> ::core::ffi::c_int {
+                    unsafe {
+                        #c_api::map_get::<#archived>(table, key, key_len, out)
+                    }
+                }
+            });
+            quote! {
+                ::core::option::Option::Some(
+                    <#value as #c_api::CValue>::C_TYPE,
+                )
+            }
+        } else {
+            quote! { ::core::option::Option::None }
+        };
+
+        descriptors.push(quote! {
+            #c_api::CField {
+                name: #name,
+                c_type: <#archived as #c_api::CValue>::C_TYPE,
+                value_c_type: #value_c_type,
+            }
+        });
+    }
+
+    Ok(Some(quote! {
+        impl #c_api::CApi for #archived_name {
+            const PREFIX: &'static str = #prefix;
+            const NAME: &'static str = #archived_name_str;
+            const FIELDS: &'static [#c_api::CField] = &[#(#descriptors,)*];
+        }
+
+        unsafe impl #c_api::CValue for #archived_name {
+            type Out = #c_api::CSlice;
+
+            const C_TYPE: &'static str = "rkyv_slice";
+
+            #[inline]
+            fn to_c(&self) -> Self::Out {
+                #c_api::CSlice {
+                    ptr: (self as *const Self).cast(),
+                    len: ::core::mem::size_of::<Self>(),
+                }
+            }
+        }
+
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn #check(
+            ptr: *const u8,
+            len: usize,
+        ) -> ::core::ffi::c_int {
+            unsafe { #c_api::check::<#archived_name>(ptr, len) }
+        }
+
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn #root(
+            ptr: *const u8,
+            len: usize,
+            out: *mut *const ::core::ffi::c_void,
+        ) -> ::core::ffi::c_int {
+            unsafe { #c_api::root::<#archived_name>(ptr, len, out) }
+        }
+
+        #(#functions)*
+    }))
+}
+
+// Returns whether the field is declared as a `HashMap` without wrappers, and
+// so gets a lookup function.
+fn is_map(field: &Field) -> bool {
+    if field.attrs.iter().any(|a| a.path().is_ident("with")) {
+        return false;
+    }
+    match field.ty {
+        Type::Path(ref path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "HashMap"),
+        _ => false,
+    }
+}
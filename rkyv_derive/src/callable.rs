@@ -0,0 +1,50 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error};
+
+use crate::{attributes::Attributes, util::strip_raw};
+
+/// Generates the `Callable` implementation for a `callable` type.
+///
+/// The ID of the type is the hash of its module path and name, and calling it
+/// forwards the archived value and arguments to the named function.
+pub fn callable(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<Option<TokenStream>, Error> {
+    let callable = match attributes.callable {
+        Some(ref callable) => callable,
+        None => return Ok(None),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            callable,
+            "callable is not supported for generic types",
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let name = &input.ident;
+    let name_str = strip_raw(name);
+    let registry = attributes.callable_registry.as_ref().unwrap();
+    let function = attributes.callable_function.as_ref().unwrap();
+
+    Ok(Some(quote! {
+        impl #rkyv_path::callable::Callable<#registry> for #name {
+            const ID: #rkyv_path::callable::CallableId =
+                #rkyv_path::callable::callable_id(::core::concat!(
+                    ::core::module_path!(),
+                    "::",
+                    #name_str,
+                ));
+
+            #[inline]
+            fn call(
+                archived: &<Self as #rkyv_path::Archive>::Archived,
+                args: <#registry as #rkyv_path::callable::Registry>::Args,
+            ) -> <#registry as #rkyv_path::callable::Registry>::Output {
+                #function(archived, args)
+            }
+        }
+    }))
+}
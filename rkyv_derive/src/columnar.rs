@@ -0,0 +1,451 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, Attribute, Data, DeriveInput, Error, Fields, FieldsNamed,
+    Ident, LitStr,
+};
+
+use crate::{
+    attributes::Attributes,
+    util::{field_predicate, is_not_omitted, strip_raw},
+    with::{make_with_cast, make_with_ty, with_inner},
+};
+
+struct Names {
+    columns: Ident,
+    resolver: Ident,
+    row_ref: Ident,
+}
+
+fn columnar_fields<'a>(
+    attributes: &Attributes,
+    input: &'a DeriveInput,
+) -> Result<Option<(&'a FieldsNamed, Names)>, Error> {
+    let columnar = match attributes.columnar {
+        Some(ref columnar) => columnar,
+        None => return Ok(None),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            columnar,
+            "columnar is not supported for generic types",
+        ));
+    }
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => Some(fields),
+            _ => None,
+        },
+        _ => None,
+    };
+    let fields = match fields {
+        Some(fields) => fields,
+        None => {
+            return Err(Error::new_spanned(
+                columnar,
+                "columnar is only supported for structs with named fields",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let archived_name = attributes
+        .archived
+        .as_ref()
+        .map_or_else(|| format!("Archived{}", strip_raw(name)), strip_raw);
+    let names = Names {
+        columns: Ident::new(&format!("{}Columns", archived_name), name.span()),
+        resolver: Ident::new(
+            &format!("{}ColumnsResolver", strip_raw(name)),
+            name.span(),
+        ),
+        row_ref: Ident::new(&format!("{}RowRef", strip_raw(name)), name.span()),
+    };
+
+    Ok(Some((fields, names)))
+}
+
+/// Generates the archived columns, resolver, and row view types of a columnar
+/// struct, and the implementations which go along with them.
+pub fn archive_columns(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<(Option<TokenStream>, Option<TokenStream>), Error> {
+    let (fields, names) = match columnar_fields(attributes, input)? {
+        Some(result) => result,
+        None => return Ok((None, None)),
+    };
+    let Names {
+        columns,
+        resolver,
+        row_ref,
+    } = &names;
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let name = &input.ident;
+    let vis = &input.vis;
+
+    let mut column_fields = Vec::new();
+    let mut column_accessors = Vec::new();
+    let mut row_accessors = Vec::new();
+    let mut resolve_fields = Vec::new();
+    let mut check_lens = Vec::new();
+    for field in fields.named.iter() {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_vis = &field.vis;
+        let ty = with_ty(field)?;
+        let column_ty = quote! {
+            #rkyv_path::vec::ArchivedVec<#rkyv_path::Archived<#ty>>
+        };
+
+        let column_doc = format!(
+            "The archived column of [`{}::{}`]",
+            name,
+            strip_raw(field_name),
+        );
+        column_fields.push(quote! {
+            #[doc = #column_doc]
+            #field_name: #column_ty
+        });
+        column_accessors.push(quote! {
+            #[doc = #column_doc]
+            #[inline]
+            #field_vis fn #field_name(&self) -> &#column_ty {
+                &self.#field_name
+            }
+        });
+
+        let row_doc = format!(
+            "The archived value of [`{}::{}`] in this row",
+            name,
+            strip_raw(field_name),
+        );
+        row_accessors.push(quote! {
+            #[doc = #row_doc]
+            #[inline]
+            #field_vis fn #field_name(&self) -> &'a #rkyv_path::Archived<#ty> {
+                &self.columns.#field_name.as_slice()[self.index]
+            }
+        });
+
+        resolve_fields.push(quote! {
+            let (fp, fo) = #rkyv_path::out_field!(out.#field_name);
+            #rkyv_path::vec::ArchivedVec::resolve_from_len(
+                len,
+                pos + fp,
+                resolver.#field_name,
+                fo,
+            );
+        });
+
+        let column_name = strip_raw(field_name);
+        check_lens.push(quote! {
+            #rkyv_path::columnar::check_column_len(
+                #column_name,
+                self.len(),
+                self.#field_name.len(),
+            )?;
+        });
+    }
+
+    let (check_bytes_attrs, verify_impl): (Vec<Attribute>, _) = if attributes
+        .check_bytes
+        .is_some()
+    {
+        let path = quote!(#rkyv_path::bytecheck).to_string();
+        let path_lit_str = LitStr::new(&path, name.span());
+        (
+            vec![
+                parse_quote! {
+                    #[derive(#rkyv_path::bytecheck::CheckBytes)]
+                },
+                parse_quote! {
+                    #[check_bytes(crate = #path_lit_str, verify)]
+                },
+            ],
+            Some(quote! {
+                // SAFETY: `verify` only checks that every column has one
+                // element per row.
+                unsafe impl<__C> #rkyv_path::bytecheck::Verify<__C> for #columns
+                where
+                    __C: #rkyv_path::rancor::Fallible + ?::core::marker::Sized,
+                    <__C as #rkyv_path::rancor::Fallible>::Error: #rkyv_path::rancor::Error,
+                {
+                    fn verify(&self, _: &mut __C) -> ::core::result::Result<(), <__C as #rkyv_path::rancor::Fallible>::Error> {
+                        #(#check_lens)*
+                        ::core::result::Result::Ok(())
+                    }
+                }
+            }),
+        )
+    } else {
+        (Vec::new(), None)
+    };
+
+    let columns_doc = format!("The archived columns of a `Vec<{}>`", name);
+    let resolver_doc =
+        format!("The resolver for the archived columns of a `Vec<{}>`", name);
+    let row_ref_doc =
+        format!("A view of a row of archived [`{}`] columns", name);
+    let field_names = fields.named.iter().map(|f| &f.ident);
+
+    let types = quote! {
+        #[automatically_derived]
+        #[doc = #columns_doc]
+        #(#check_bytes_attrs)*
+        #[repr(C)]
+        #vis struct #columns {
+            len: #rkyv_path::primitive::ArchivedUsize,
+            #(#column_fields,)*
+        }
+
+        #[automatically_derived]
+        #[doc = #resolver_doc]
+        #vis struct #resolver {
+            #(#field_names: #rkyv_path::vec::VecResolver,)*
+        }
+
+        #[automatically_derived]
+        #[doc = #row_ref_doc]
+        #vis struct #row_ref<'a> {
+            columns: &'a #columns,
+            index: usize,
+        }
+    };
+
+    let impls = quote! {
+        // SAFETY: The columns are `#[repr(C)]` and all of their fields are
+        // `Portable`.
+        unsafe impl #rkyv_path::Portable for #columns {}
+
+        #verify_impl
+
+        impl #rkyv_path::columnar::ArchiveColumns for #name {
+            type ArchivedColumns = #columns;
+            type ColumnsResolver = #resolver;
+
+            #[inline]
+            unsafe fn resolve_columns(
+                rows: &[Self],
+                pos: usize,
+                resolver: Self::ColumnsResolver,
+                out: *mut Self::ArchivedColumns,
+            ) {
+                let len = rows.len();
+                let (fp, fo) = #rkyv_path::out_field!(out.len);
+                #rkyv_path::Archive::resolve(&len, pos + fp, (), fo);
+                #(#resolve_fields)*
+            }
+        }
+
+        #[allow(dead_code)]
+        impl #columns {
+            /// Returns the number of rows.
+            #[inline]
+            pub fn len(&self) -> usize {
+                self.len.to_native() as usize
+            }
+
+            /// Returns whether there are no rows.
+            #[inline]
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// Returns a view of the row at `index`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `index` is out of bounds.
+            #[inline]
+            pub fn row(&self, index: usize) -> #row_ref<'_> {
+                ::core::assert!(
+                    index < self.len(),
+                    "row index {} out of bounds for {} rows",
+                    index,
+                    self.len(),
+                );
+                #row_ref { columns: self, index }
+            }
+
+            /// Returns a view of the row at `index`, or `None` if `index` is
+            /// out of bounds.
+            #[inline]
+            pub fn get_row(&self, index: usize) -> ::core::option::Option<#row_ref<'_>> {
+                if index < self.len() {
+                    ::core::option::Option::Some(#row_ref { columns: self, index })
+                } else {
+                    ::core::option::Option::None
+                }
+            }
+
+            /// Returns an iterator over views of each row.
+            #[inline]
+            pub fn rows(&self) -> impl '_ + ::core::iter::Iterator<Item = #row_ref<'_>> {
+                ::core::iter::Iterator::map(0..self.len(), move |index| #row_ref { columns: self, index })
+            }
+
+            #(#column_accessors)*
+        }
+
+        impl<'a> ::core::clone::Clone for #row_ref<'a> {
+            #[inline]
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<'a> ::core::marker::Copy for #row_ref<'a> {}
+
+        #[allow(dead_code)]
+        impl<'a> #row_ref<'a> {
+            /// Returns the index of this row.
+            #[inline]
+            pub fn index(&self) -> usize {
+                self.index
+            }
+
+            /// Returns the columns that this row is in.
+            #[inline]
+            pub fn columns(&self) -> &'a #columns {
+                self.columns
+            }
+
+            #(#row_accessors)*
+        }
+    };
+
+    Ok((Some(types), Some(impls)))
+}
+
+/// Generates the `SerializeColumns` implementation of a columnar struct.
+pub fn serialize_columns(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<Option<TokenStream>, Error> {
+    let (fields, names) = match columnar_fields(attributes, input)? {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let resolver = &names.resolver;
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let with_cast = make_with_cast(&rkyv_path);
+    let name = &input.ident;
+
+    let mut predicates = Vec::new();
+    for field in fields.named.iter().filter(is_not_omitted) {
+        let ty = with_ty(field)?;
+        predicates.push(field_predicate(
+            &ty,
+            quote! { #ty: #rkyv_path::Serialize<__S> },
+        ));
+    }
+
+    let resolver_values = fields
+        .named
+        .iter()
+        .map(|f| {
+            let field_name = &f.ident;
+            let ty = with_ty(f)?;
+            let field = with_cast(f, parse_quote! { &row.#field_name })?;
+            Ok(quote! {
+                #field_name: #rkyv_path::vec::ArchivedVec::<#rkyv_path::Archived<#ty>>::serialize_from_iter::<#ty, _, _>(
+                    ::core::iter::Iterator::map(rows.iter(), |row| #field),
+                    serializer,
+                )?
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Some(quote! {
+        #[automatically_derived]
+        impl<__S> #rkyv_path::columnar::SerializeColumns<__S> for #name
+        where
+            __S: #rkyv_path::rancor::Fallible + #rkyv_path::ser::Allocator + #rkyv_path::ser::Writer + ?::core::marker::Sized,
+            #(#predicates,)*
+        {
+            #[inline]
+            fn serialize_columns(
+                rows: &[Self],
+                serializer: &mut __S,
+            ) -> ::core::result::Result<Self::ColumnsResolver, <__S as #rkyv_path::rancor::Fallible>::Error> {
+                ::core::result::Result::Ok(#resolver {
+                    #(#resolver_values,)*
+                })
+            }
+        }
+    }))
+}
+
+/// Generates the `DeserializeColumns` implementation of a columnar struct.
+pub fn deserialize_columns(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<Option<TokenStream>, Error> {
+    let (fields, _) = match columnar_fields(attributes, input)? {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let name = &input.ident;
+
+    let mut predicates = Vec::new();
+    for field in fields.named.iter().filter(is_not_omitted) {
+        let ty = with_ty(field)?;
+        predicates.push(field_predicate(
+            &ty,
+            quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> },
+        ));
+    }
+
+    let deserialize_fields = fields
+        .named
+        .iter()
+        .map(|f| {
+            let field_name = &f.ident;
+            let ty = with_ty(f)?;
+            let value = with_inner(
+                f,
+                parse_quote! {
+                    #rkyv_path::Deserialize::<#ty, __D>::deserialize(
+                        &columns.#field_name.as_slice()[index],
+                        deserializer,
+                    )?
+                },
+            )?;
+            Ok(quote! { #field_name: #value })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Some(quote! {
+        #[automatically_derived]
+        impl<__D> #rkyv_path::columnar::DeserializeColumns<__D> for #name
+        where
+            __D: #rkyv_path::rancor::Fallible + ?::core::marker::Sized,
+            #(#predicates,)*
+        {
+            #[inline]
+            fn deserialize_columns(
+                columns: &Self::ArchivedColumns,
+                deserializer: &mut __D,
+            ) -> ::core::result::Result<
+                #rkyv_path::columnar::__private::Vec<Self>,
+                <__D as #rkyv_path::rancor::Fallible>::Error,
+            > {
+                let mut result =
+                    #rkyv_path::columnar::__private::Vec::with_capacity(columns.len());
+                for index in 0..columns.len() {
+                    result.push(#name {
+                        #(#deserialize_fields,)*
+                    });
+                }
+                ::core::result::Result::Ok(result)
+            }
+        }
+    }))
+}
@@ -0,0 +1,123 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, DeriveInput, Error};
+
+use crate::attributes::Attributes;
+
+/// Generates the `to_archive_bytes` and `from_archive_bytes` methods for a
+/// `convenience` type.
+///
+/// The methods serialize and deserialize with the configuration passed as
+/// `convenience(config = ...)`, or with `DefaultConfig` if none was given. The
+/// checked `from_archive_bytes` is only generated with the `bytecheck` feature,
+/// and `from_archive_bytes_unchecked` is always generated.
+pub fn convenience(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<Option<TokenStream>, Error> {
+    if attributes.convenience.is_none() {
+        return Ok(None);
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let name = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    let config = attributes
+        .convenience_config
+        .clone()
+        .unwrap_or_else(|| parse_quote! { #rkyv_path::util::DefaultConfig });
+    let ser_config = quote! {
+        <#config as #rkyv_path::util::DefaultSerializer>
+    };
+    let de_config = quote! {
+        <#config as #rkyv_path::util::DefaultDeserializer>
+    };
+    let ser_strategy = quote! {
+        #rkyv_path::rancor::Strategy<
+            #ser_config::Serializer,
+            #ser_config::Error,
+        >
+    };
+    let de_strategy = quote! {
+        #rkyv_path::rancor::Strategy<
+            #de_config::Deserializer,
+            #de_config::Error,
+        >
+    };
+
+    let checked = cfg!(feature = "bytecheck").then(|| {
+        let validator = quote! {
+            #rkyv_path::validation::validators::DefaultValidator
+        };
+        quote! {
+            /// Checks and deserializes a value from the given bytes.
+            ///
+            /// The root of the archive must be stored at the end of the
+            /// bytes.
+            #[inline]
+            #vis fn from_archive_bytes(
+                bytes: &[u8],
+            ) -> ::core::result::Result<Self, #de_config::Error>
+            where
+                Self: #rkyv_path::Archive,
+                #rkyv_path::Archived<Self>: #rkyv_path::bytecheck::CheckBytes<
+                        #rkyv_path::rancor::Strategy<
+                            #validator,
+                            #de_config::Error,
+                        >,
+                    >
+                    + #rkyv_path::Deserialize<Self, #de_strategy>,
+            {
+                #rkyv_path::validation::util::from_bytes_with_config::<
+                    #config,
+                    Self,
+                >(bytes)
+            }
+        }
+    });
+
+    Ok(Some(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Serializes this value and returns the resulting bytes.
+            #[inline]
+            #vis fn to_archive_bytes(
+                &self,
+            ) -> ::core::result::Result<
+                #rkyv_path::util::AlignedVec,
+                #ser_config::Error,
+            >
+            where
+                Self: #rkyv_path::Serialize<#ser_strategy>,
+            {
+                #rkyv_path::util::to_bytes_with_config::<#config, Self>(self)
+            }
+
+            #checked
+
+            /// Deserializes a value from the given bytes without checking
+            /// them.
+            ///
+            /// # Safety
+            ///
+            /// The bytes must be an archived value of this type with its root
+            /// stored at the end of the bytes.
+            #[inline]
+            #vis unsafe fn from_archive_bytes_unchecked(
+                bytes: &[u8],
+            ) -> ::core::result::Result<Self, #de_config::Error>
+            where
+                Self: #rkyv_path::Archive,
+                #rkyv_path::Archived<Self>:
+                    #rkyv_path::Deserialize<Self, #de_strategy>,
+            {
+                #rkyv_path::util::from_bytes_with_config_unchecked::<
+                    #config,
+                    Self,
+                >(bytes)
+            }
+        }
+    }))
+}
@@ -0,0 +1,84 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Ident};
+
+use crate::{
+    attributes::Attributes,
+    util::{field_predicate, is_not_omitted, Variant},
+    with::make_with_ty,
+};
+
+/// Generates the `ArchivedDeepSize` implementation for the archived type of a
+/// `deep_size` type.
+///
+/// The deserialized value owns the heap memory owned by each of its fields, so
+/// the heap size of the archived type is the sum of the heap sizes of its
+/// fields.
+pub fn deep_size(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let deep_size = match attributes.deep_size {
+        Some(ref deep_size) => deep_size,
+        None => return Ok(None),
+    };
+
+    let variants = match Variant::all(input, archived_name) {
+        Some(variants) => variants,
+        None => return Ok(None),
+    };
+    if matches!(input.data, Data::Enum(_)) && variants.is_empty() {
+        return Err(Error::new_spanned(
+            deep_size,
+            "deep_size is not supported for enums without variants",
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let deep_size = quote! { #rkyv_path::deep_size::ArchivedDeepSize };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let mut deep_size_where = where_clause.unwrap().clone();
+    for field in variants
+        .iter()
+        .flat_map(|v| v.fields.iter())
+        .filter(is_not_omitted)
+    {
+        let ty = with_ty(field)?;
+        deep_size_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+        // Higher-ranked so that fields which don't implement
+        // `ArchivedDeepSize` are reported where the impl is used
+        deep_size_where.predicates.push(field_predicate(
+            &ty,
+            quote! { for<'__a> #rkyv_path::Archived<#ty>: #deep_size },
+        ));
+    }
+
+    let patterns = variants.iter().map(|v| v.pattern("__self"));
+    let bindings = variants.iter().map(|v| v.bindings("__self"));
+
+    Ok(Some(quote! {
+        impl #impl_generics #deep_size for #archived_name #ty_generics #deep_size_where {
+            type Deserialized = #name #ty_generics;
+
+            #[inline]
+            fn deserialized_heap_size(&self) -> usize {
+                match self {
+                    #(
+                        #patterns => {
+                            0usize #(.saturating_add(
+                                #deep_size::deserialized_heap_size(#bindings),
+                            ))*
+                        }
+                    )*
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,257 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, Data, DeriveInput, Error, Fields, Ident, Path, WhereClause,
+};
+
+use crate::{
+    attributes::Attributes,
+    util::{field_predicate, is_not_omitted, strip_raw, Variant},
+    with::make_with_ty,
+};
+
+// Returns the body of the `Debug` implementation for a variant.
+pub fn debug(variant: &Variant<'_>) -> TokenStream {
+    let name = &variant.name;
+    let bindings = variant.bindings("__self");
+    match variant.fields {
+        Fields::Named(ref fields) => {
+            let names = fields
+                .named
+                .iter()
+                .map(|f| strip_raw(f.ident.as_ref().unwrap()));
+            quote! {
+                f.debug_struct(#name)
+                    #(.field(#names, #bindings))*
+                    .finish()
+            }
+        }
+        Fields::Unnamed(_) => quote! {
+            f.debug_tuple(#name)
+                #(.field(#bindings))*
+                .finish()
+        },
+        Fields::Unit => quote! { f.write_str(#name) },
+    }
+}
+
+/// Generates the `Debug`, `PartialEq`, `Eq`, `Hash`, `PartialOrd`, and `Ord`
+/// implementations for the archived type of a `derive_std` type.
+///
+/// These can't be derived on the archived type because the fields of archived
+/// structs may be reordered, and because a derive would require every field of
+/// a non-generic type to implement the trait. Fields are visited in
+/// declaration order and enum variants are ordered and hashed by their index,
+/// so the implementations are consistent with the same derives on the original
+/// type.
+pub fn derive_std(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let derive_std = match attributes.derive_std {
+        Some(ref derive_std) => derive_std,
+        None => return Ok(None),
+    };
+
+    let variants = match Variant::all(input, archived_name) {
+        Some(variants) => variants,
+        None => return Ok(None),
+    };
+    let is_enum = matches!(input.data, Data::Enum(_));
+    if is_enum && variants.is_empty() {
+        return Err(Error::new_spanned(
+            derive_std,
+            "derive_std is not supported for enums without variants",
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let fields = variants
+        .iter()
+        .flat_map(|v| v.fields.iter())
+        .filter(is_not_omitted)
+        .collect::<Vec<_>>();
+    let mut archive_where = where_clause.unwrap().clone();
+    for field in fields.iter() {
+        let ty = with_ty(field)?;
+        archive_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+    }
+
+    // Requires all of the archived fields to implement `bound`. The bounds are
+    // higher-ranked so that they are checked where the impl is used instead of
+    // being rejected when a field with a concrete type doesn't implement it.
+    let bounded_where = |bound: Path| -> Result<WhereClause, Error> {
+        let mut result = archive_where.clone();
+        for field in fields.iter() {
+            let ty = with_ty(field)?;
+            result.predicates.push(field_predicate(
+                &ty,
+                quote! { for<'__a> #rkyv_path::Archived<#ty>: #bound },
+            ));
+        }
+        Ok(result)
+    };
+    let debug_where = bounded_where(parse_quote! { ::core::fmt::Debug })?;
+    let partial_eq_where =
+        bounded_where(parse_quote! { ::core::cmp::PartialEq })?;
+    let eq_where = bounded_where(parse_quote! { ::core::cmp::Eq })?;
+    let hash_where = bounded_where(parse_quote! { ::core::hash::Hash })?;
+    let partial_ord_where =
+        bounded_where(parse_quote! { ::core::cmp::PartialOrd })?;
+    let ord_where = bounded_where(parse_quote! { ::core::cmp::Ord })?;
+
+    let self_patterns = variants
+        .iter()
+        .map(|v| v.pattern("__self"))
+        .collect::<Vec<_>>();
+    let other_patterns = variants
+        .iter()
+        .map(|v| v.pattern("__other"))
+        .collect::<Vec<_>>();
+    let self_bindings = variants
+        .iter()
+        .map(|v| v.bindings("__self"))
+        .collect::<Vec<_>>();
+    let other_bindings = variants
+        .iter()
+        .map(|v| v.bindings("__other"))
+        .collect::<Vec<_>>();
+    let debug_arms = variants.iter().map(debug);
+
+    // Enum variants are compared by their index when they differ
+    let (indices, self_index, mismatch, partial_mismatch) = if is_enum {
+        let arms = variants
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let path = &v.path;
+                quote! { #path { .. } => #i }
+            })
+            .collect::<Vec<_>>();
+        let self_index = quote! {
+            let __self_index: usize = match self { #(#arms,)* };
+        };
+        (
+            Some(quote! {
+                #self_index
+                let __other_index: usize = match other { #(#arms,)* };
+            }),
+            Some(self_index),
+            Some(quote! {
+                #[allow(unreachable_patterns)]
+                _ => ::core::cmp::Ord::cmp(&__self_index, &__other_index),
+            }),
+            Some(quote! {
+                #[allow(unreachable_patterns)]
+                _ => ::core::cmp::PartialOrd::partial_cmp(
+                    &__self_index,
+                    &__other_index,
+                ),
+            }),
+        )
+    } else {
+        (None, None, None, None)
+    };
+
+    // Like the `Hash` derive, enums with more than one variant hash their
+    // discriminant first. The discriminant is hashed as an `isize` to match
+    // enums with the default representation.
+    let hash_index = if is_enum && variants.len() > 1 {
+        Some(quote! {
+            #self_index
+            ::core::hash::Hash::hash(&(__self_index as isize), state);
+        })
+    } else {
+        None
+    };
+
+    Ok(Some(quote! {
+        impl #impl_generics ::core::fmt::Debug for #archived_name #ty_generics #debug_where {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #(#self_patterns => #debug_arms,)*
+                }
+            }
+        }
+
+        impl #impl_generics ::core::cmp::PartialEq for #archived_name #ty_generics #partial_eq_where {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    #(
+                        (#self_patterns, #other_patterns) => {
+                            true #(&& ::core::cmp::PartialEq::eq(#self_bindings, #other_bindings))*
+                        }
+                    )*
+                    #[allow(unreachable_patterns)]
+                    _ => false,
+                }
+            }
+        }
+
+        impl #impl_generics ::core::cmp::Eq for #archived_name #ty_generics #eq_where {}
+
+        impl #impl_generics ::core::hash::Hash for #archived_name #ty_generics #hash_where {
+            #[inline]
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                #hash_index
+                match self {
+                    #(
+                        #self_patterns => {
+                            #(::core::hash::Hash::hash(#self_bindings, state);)*
+                        }
+                    )*
+                }
+            }
+        }
+
+        impl #impl_generics ::core::cmp::PartialOrd for #archived_name #ty_generics #partial_ord_where {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                #indices
+                match (self, other) {
+                    #(
+                        (#self_patterns, #other_patterns) => {
+                            #(
+                                match ::core::cmp::PartialOrd::partial_cmp(#self_bindings, #other_bindings) {
+                                    ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                                    x => return x,
+                                }
+                            )*
+                            ::core::option::Option::Some(::core::cmp::Ordering::Equal)
+                        }
+                    )*
+                    #partial_mismatch
+                }
+            }
+        }
+
+        impl #impl_generics ::core::cmp::Ord for #archived_name #ty_generics #ord_where {
+            #[inline]
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                #indices
+                match (self, other) {
+                    #(
+                        (#self_patterns, #other_patterns) => {
+                            #(
+                                match ::core::cmp::Ord::cmp(#self_bindings, #other_bindings) {
+                                    ::core::cmp::Ordering::Equal => (),
+                                    x => return x,
+                                }
+                            )*
+                            ::core::cmp::Ordering::Equal
+                        }
+                    )*
+                    #mismatch
+                }
+            }
+        }
+    }))
+}
@@ -2,12 +2,16 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{
     parse_quote, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput,
-    Error, Fields, Generics, Ident, Index,
+    Error, Expr, Field, Fields, Generics, Ident, Index, Path, Type,
+    WhereClause,
 };
 
 use crate::{
     attributes::Attributes,
-    util::is_not_omitted,
+    columnar::deserialize_columns,
+    pack_flags::packed_bits,
+    transparent,
+    util::{field_predicate, is_not_omitted, strip_raw},
     with::{make_with_ty, with_inner},
 };
 
@@ -37,7 +41,7 @@ fn derive_deserialize_impl(
 
     let mut impl_input_params = Punctuated::default();
     impl_input_params
-        .push(parse_quote! { __D: #rkyv_path::rancor::Fallible + ?Sized });
+        .push(parse_quote! { __D: #rkyv_path::rancor::Fallible + ?::core::marker::Sized });
     for param in input.generics.params.iter() {
         impl_input_params.push(param.clone());
     }
@@ -54,30 +58,60 @@ fn derive_deserialize_impl(
     let where_clause = where_clause.unwrap();
 
     let deserialize_impl = match input.data {
+        _ if attributes.transparent.is_some() => transparent::deserialize(
+            attributes,
+            &input,
+            impl_generics,
+            where_clause,
+        )?,
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
+                let mut packed_fields = Vec::new();
+                for field in fields.named.iter() {
+                    if packed_bits(attributes, field)?.is_some() {
+                        packed_fields.push(field.ident.as_ref());
+                    }
+                }
+                let is_packed =
+                    |f: &&Field| packed_fields.contains(&f.ident.as_ref());
+
                 let mut deserialize_where = where_clause.clone();
-                for field in fields.named.iter().filter(is_not_omitted) {
+                for field in fields
+                    .named
+                    .iter()
+                    .filter(is_not_omitted)
+                    .filter(|f| !is_packed(f))
+                {
                     let ty = with_ty(field)?;
-                    deserialize_where
-                        .predicates
-                        .push(parse_quote! { #ty: #rkyv_path::Archive });
+                    deserialize_where.predicates.push(field_predicate(
+                        &ty,
+                        quote! { #ty: #rkyv_path::Archive },
+                    ));
                     deserialize_where.predicates.push(
-                        parse_quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> },
+                        field_predicate(&ty, quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> }),
                     );
                 }
+                push_trace_bound(
+                    &mut deserialize_where,
+                    &rkyv_path,
+                    fields.named.iter().any(|f| !is_packed(&f)),
+                );
 
                 let deserialize_fields = fields.named.iter().map(|f| {
                     let name = &f.ident;
+                    // Packed fields are unpacked by their accessors
+                    if is_packed(&f) {
+                        return quote! { #name: self.#name() };
+                    }
                     let ty = with_ty(f).unwrap();
                     let value = with_inner(
                         f,
-                        parse_quote! {
-                            #rkyv_path::Deserialize::<#ty, __D>::deserialize(
-                                &self.#name,
-                                deserializer,
-                            )?
-                        },
+                        deserialize_field(
+                            &rkyv_path,
+                            &ty,
+                            quote! { &self.#name },
+                            strip_raw(name.as_ref().unwrap()),
+                        ),
                     )
                     .unwrap();
                     quote! { #name: #value }
@@ -87,7 +121,7 @@ fn derive_deserialize_impl(
                     impl #impl_generics #rkyv_path::Deserialize<#name #ty_generics, __D> for #rkyv_path::Archived<#name #ty_generics> #deserialize_where {
                         #[inline]
                         fn deserialize(&self, deserializer: &mut __D) -> ::core::result::Result<#name #ty_generics, <__D as #rkyv_path::rancor::Fallible>::Error> {
-                            Ok(#name {
+                            ::core::result::Result::Ok(#name {
                                 #(#deserialize_fields,)*
                             })
                         }
@@ -98,13 +132,19 @@ fn derive_deserialize_impl(
                 let mut deserialize_where = where_clause.clone();
                 for field in fields.unnamed.iter().filter(is_not_omitted) {
                     let ty = with_ty(field)?;
-                    deserialize_where
-                        .predicates
-                        .push(parse_quote! { #ty: #rkyv_path::Archive });
+                    deserialize_where.predicates.push(field_predicate(
+                        &ty,
+                        quote! { #ty: #rkyv_path::Archive },
+                    ));
                     deserialize_where.predicates.push(
-                        parse_quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> },
+                        field_predicate(&ty, quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> }),
                     );
                 }
+                push_trace_bound(
+                    &mut deserialize_where,
+                    &rkyv_path,
+                    !fields.unnamed.is_empty(),
+                );
 
                 let deserialize_fields =
                     fields.unnamed.iter().enumerate().map(|(i, f)| {
@@ -112,12 +152,12 @@ fn derive_deserialize_impl(
                         let ty = with_ty(f).unwrap();
                         let value = with_inner(
                             f,
-                            parse_quote! {
-                                #rkyv_path::Deserialize::<#ty, __D>::deserialize(
-                                    &self.#index,
-                                    deserializer,
-                                )?
-                            },
+                            deserialize_field(
+                                &rkyv_path,
+                                &ty,
+                                quote! { &self.#index },
+                                i.to_string(),
+                            ),
                         )
                         .unwrap();
                         quote! { #value }
@@ -127,7 +167,7 @@ fn derive_deserialize_impl(
                     impl #impl_generics #rkyv_path::Deserialize<#name #ty_generics, __D> for #rkyv_path::Archived<#name #ty_generics> #deserialize_where {
                         #[inline]
                         fn deserialize(&self, deserializer: &mut __D) -> ::core::result::Result<#name #ty_generics, <__D as #rkyv_path::rancor::Fallible>::Error> {
-                            Ok(#name(
+                            ::core::result::Result::Ok(#name(
                                 #(#deserialize_fields,)*
                             ))
                         }
@@ -138,7 +178,7 @@ fn derive_deserialize_impl(
                 impl #impl_generics #rkyv_path::Deserialize<#name #ty_generics, __D> for #rkyv_path::Archived<#name #ty_generics> #where_clause {
                     #[inline]
                     fn deserialize(&self, _: &mut __D) -> ::core::result::Result<#name #ty_generics, <__D as #rkyv_path::rancor::Fallible>::Error> {
-                        Ok(#name)
+                        ::core::result::Result::Ok(#name)
                     }
                 }
             },
@@ -151,12 +191,13 @@ fn derive_deserialize_impl(
                         for field in fields.named.iter().filter(is_not_omitted)
                         {
                             let ty = with_ty(field)?;
-                            deserialize_where.predicates.push(
-                                parse_quote! { #ty: #rkyv_path::Archive },
-                            );
+                            deserialize_where.predicates.push(field_predicate(
+                                &ty,
+                                quote! { #ty: #rkyv_path::Archive },
+                            ));
                             deserialize_where
                                 .predicates
-                                .push(parse_quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> });
+                                .push(field_predicate(&ty, quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> }));
                         }
                     }
                     Fields::Unnamed(ref fields) => {
@@ -164,17 +205,23 @@ fn derive_deserialize_impl(
                             fields.unnamed.iter().filter(is_not_omitted)
                         {
                             let ty = with_ty(field)?;
-                            deserialize_where.predicates.push(
-                                parse_quote! { #ty: #rkyv_path::Archive },
-                            );
+                            deserialize_where.predicates.push(field_predicate(
+                                &ty,
+                                quote! { #ty: #rkyv_path::Archive },
+                            ));
                             deserialize_where
                                 .predicates
-                                .push(parse_quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> });
+                                .push(field_predicate(&ty, quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> }));
                         }
                     }
                     Fields::Unit => (),
                 }
             }
+            push_trace_bound(
+                &mut deserialize_where,
+                &rkyv_path,
+                data.variants.iter().any(|v| !v.fields.is_empty()),
+            );
 
             let deserialize_variants = data.variants.iter().map(|v| {
                 let variant = &v.ident;
@@ -189,12 +236,12 @@ fn derive_deserialize_impl(
                             let ty = with_ty(f).unwrap();
                             let value = with_inner(
                                 f,
-                                parse_quote! {
-                                    #rkyv_path::Deserialize::<#ty, __D>::deserialize(
-                                        #name,
-                                        deserializer,
-                                    )?
-                                },
+                                deserialize_field(
+                                    &rkyv_path,
+                                    &ty,
+                                    quote! { #name },
+                                    strip_raw(name.as_ref().unwrap()),
+                                ),
                             )
                             .unwrap();
                             quote! { #name: #value }
@@ -213,12 +260,12 @@ fn derive_deserialize_impl(
                             let ty = with_ty(f).unwrap();
                             let value = with_inner(
                                 f,
-                                parse_quote! {
-                                    #rkyv_path::Deserialize::<#ty, __D>::deserialize(
-                                        #binding,
-                                        deserializer,
-                                    )?
-                                },
+                                deserialize_field(
+                                    &rkyv_path,
+                                    &ty,
+                                    quote! { #binding },
+                                    i.to_string(),
+                                ),
                             )
                             .unwrap();
                             quote! { #value }
@@ -237,7 +284,7 @@ fn derive_deserialize_impl(
                 impl #impl_generics #rkyv_path::Deserialize<#name #ty_generics, __D> for #rkyv_path::Archived<#name #ty_generics> #deserialize_where {
                     #[inline]
                     fn deserialize(&self, deserializer: &mut __D) -> ::core::result::Result<#name #ty_generics, <__D as #rkyv_path::rancor::Fallible>::Error> {
-                        Ok(match self {
+                        ::core::result::Result::Ok(match self {
                             #(#deserialize_variants,)*
                         })
                     }
@@ -252,8 +299,45 @@ fn derive_deserialize_impl(
         }
     };
 
+    let columns_impl = deserialize_columns(attributes, &input)?;
+
     Ok(quote! {
         #[automatically_derived]
         #deserialize_impl
+        #columns_impl
     })
 }
+
+/// Returns an expression which deserializes a field and traces errors with the
+/// name of the field.
+fn deserialize_field(
+    rkyv_path: &Path,
+    ty: &Type,
+    field: TokenStream,
+    name: String,
+) -> Expr {
+    parse_quote! {
+        #rkyv_path::rancor::ResultExt::with_trace(
+            #rkyv_path::Deserialize::<#ty, __D>::deserialize(
+                #field,
+                deserializer,
+            ),
+            || #rkyv_path::de::PathSegment::Field(#name),
+        )?
+    }
+}
+
+/// Adds the bound which lets field errors be traced, if any fields are
+/// deserialized.
+fn push_trace_bound(
+    where_clause: &mut WhereClause,
+    rkyv_path: &Path,
+    has_fields: bool,
+) {
+    if has_fields {
+        where_clause.predicates.push(parse_quote! {
+            <__D as #rkyv_path::rancor::Fallible>::Error:
+                #rkyv_path::rancor::Trace
+        });
+    }
+}
@@ -0,0 +1,487 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse2, parse_quote,
+    spanned::Spanned,
+    Data, DeriveInput, Error, FnArg, Ident, ItemTrait, Path, Signature, Token,
+    TraitItem, Type, WherePredicate,
+};
+
+use crate::{attributes::Attributes, util::strip_raw};
+
+struct Args {
+    dispatch_trait: Path,
+    archived_trait: Option<Path>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream<'_>) -> Result<Self, Error> {
+        let dispatch_trait = input.parse::<Path>()?;
+        let mut archived_trait = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name = input.parse::<Ident>()?;
+            if name != "archived" {
+                return Err(Error::new_spanned(
+                    name,
+                    "unrecognized rkyv_enum_dispatch argument, expected \
+                     `archived = ...`",
+                ));
+            }
+            if archived_trait.is_some() {
+                return Err(Error::new_spanned(
+                    name,
+                    "archived already specified",
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            archived_trait = Some(input.parse::<Path>()?);
+        }
+
+        Ok(Self {
+            dispatch_trait,
+            archived_trait,
+        })
+    }
+}
+
+// The name of the hidden macro which implements a dispatch trait for an
+// archived enum.
+fn delegate_macro(trait_ident: &Ident) -> Ident {
+    format_ident!("__rkyv_enum_dispatch_{}", strip_raw(trait_ident))
+}
+
+/// Expands `#[rkyv_enum_dispatch]` on a trait or an enum.
+pub fn expand(
+    args: TokenStream,
+    item: TokenStream,
+) -> Result<TokenStream, Error> {
+    if let Ok(input) = parse2::<ItemTrait>(item.clone()) {
+        if !args.is_empty() {
+            return Err(Error::new_spanned(
+                args,
+                "#[rkyv_enum_dispatch] on a trait takes no arguments",
+            ));
+        }
+        expand_trait(input)
+    } else {
+        expand_enum(parse2(args)?, parse2(item)?)
+    }
+}
+
+// Returns whether the method can be called on a trait object.
+fn is_dispatchable(sig: &Signature) -> bool {
+    let by_ref = matches!(
+        sig.inputs.first(),
+        Some(FnArg::Receiver(receiver)) if receiver.reference.is_some(),
+    );
+    let sized: WherePredicate = parse_quote! { Self: Sized };
+    let sized = sized.to_token_stream().to_string();
+    let requires_sized = sig.generics.where_clause.as_ref().is_some_and(|w| {
+        w.predicates
+            .iter()
+            .any(|p| p.to_token_stream().to_string() == sized)
+    });
+
+    by_ref
+        && sig.generics.type_params().next().is_none()
+        && sig.generics.const_params().next().is_none()
+        && sig.asyncness.is_none()
+        && !requires_sized
+}
+
+fn expand_trait(input: ItemTrait) -> Result<TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "#[rkyv_enum_dispatch] traits may not be generic",
+        ));
+    }
+
+    let mut methods = Vec::new();
+    for item in input.items.iter() {
+        match item {
+            TraitItem::Fn(method) if is_dispatchable(&method.sig) => {
+                let mut sig = method.sig.clone();
+                let mut args = Vec::new();
+                for (i, input) in sig.inputs.iter_mut().enumerate() {
+                    if let FnArg::Typed(pat_type) = input {
+                        let arg = format_ident!("__arg{}", i);
+                        pat_type.pat = parse_quote! { #arg };
+                        args.push(arg);
+                    }
+                }
+
+                let name = &sig.ident;
+                let call = quote! {
+                    <_ as $trait>::#name(__inner, #(#args,)*)
+                };
+                let call = if sig.unsafety.is_some() {
+                    quote! { unsafe { #call } }
+                } else {
+                    call
+                };
+                methods.push(quote! {
+                    #[inline]
+                    #sig {
+                        match self {
+                            $(Self::$variant(__inner, ..) => #call,)*
+                        }
+                    }
+                });
+            }
+            TraitItem::Fn(method) if method.default.is_none() => {
+                return Err(Error::new_spanned(
+                    &method.sig,
+                    "methods of #[rkyv_enum_dispatch] traits must take \
+                     `&self` or `&mut self` and have no type parameters \
+                     unless they have a default implementation",
+                ));
+            }
+            TraitItem::Type(ty) => {
+                return Err(Error::new_spanned(
+                    ty,
+                    "#[rkyv_enum_dispatch] traits may not have associated \
+                     types",
+                ));
+            }
+            TraitItem::Const(c) if c.default.is_none() => {
+                return Err(Error::new_spanned(
+                    c,
+                    "associated constants of #[rkyv_enum_dispatch] traits \
+                     must have a default value",
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    let macro_name = delegate_macro(&input.ident);
+
+    Ok(quote! {
+        #input
+
+        #[doc(hidden)]
+        macro_rules! #macro_name {
+            ($trait:path, $archived:ty, [$($variant:ident),* $(,)?]) => {
+                impl $trait for $archived {
+                    #(#methods)*
+                }
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #macro_name;
+    })
+}
+
+fn expand_enum(
+    args: Args,
+    mut input: DeriveInput,
+) -> Result<TokenStream, Error> {
+    let attributes = Attributes::parse(&input)?;
+    let rkyv_path = attributes.rkyv_path();
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "#[rkyv_enum_dispatch] enums may not be generic",
+        ));
+    }
+
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "#[rkyv_enum_dispatch] may only be used on enums and traits",
+        ));
+    };
+    if data.variants.is_empty() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "#[rkyv_enum_dispatch] enums must list at least one \
+             implementation",
+        ));
+    }
+
+    let mut variants = Vec::new();
+    let mut types = Vec::<Type>::new();
+    for variant in data.variants.iter() {
+        if variant.fields.len() != 1
+            || variant.fields.iter().any(|f| f.ident.is_some())
+            || variant.discriminant.is_some()
+        {
+            return Err(Error::new(
+                variant.span(),
+                "each variant of a #[rkyv_enum_dispatch] enum must be a \
+                 tuple variant with a single field holding an \
+                 implementation",
+            ));
+        }
+        variants.push(variant.ident.clone());
+        types.push(variant.fields.iter().next().unwrap().ty.clone());
+    }
+
+    // Archive attributes apply to the generated archived enum, so they're
+    // moved to the dispatch enum which derives it.
+    let (archive_attrs, attrs) = input.attrs.drain(..).partition(|attr| {
+        attr.path().is_ident("archive")
+            || attr.path().is_ident("archive_attr")
+            || attr.path().is_ident("rkyv")
+    });
+    let archive_attrs: Vec<_> = archive_attrs;
+    input.attrs = attrs;
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let dispatch_trait = &args.dispatch_trait;
+    let trait_name = dispatch_trait.segments.last().unwrap().ident.to_string();
+    let dispatch = format_ident!("__{}Dispatch", strip_raw(name));
+    let archived = attributes
+        .archived
+        .clone()
+        .unwrap_or_else(|| format_ident!("Archived{}", strip_raw(name)));
+    let archived_attr = attributes
+        .archived
+        .is_none()
+        .then(|| quote! { #[archive(archived = #archived)] });
+    let resolver_attr = attributes.resolver.is_none().then(|| {
+        let resolver = format_ident!("{}Resolver", strip_raw(name));
+        quote! { #[archive(resolver = #resolver)] }
+    });
+
+    let boxed = quote! {
+        #rkyv_path::dispatch::Box<dyn #dispatch_trait>
+    };
+    let unknown = quote! {
+        #rkyv_path::dispatch::UnknownImplementation::new(#trait_name)
+    };
+    // The derived `Serialize` impl of the dispatch enum requires serializers
+    // to be writers with the `hooks` feature, so ours must as well.
+    let writer_bound = if cfg!(feature = "hooks") {
+        quote! { __S: #rkyv_path::ser::Writer, }
+    } else {
+        TokenStream::new()
+    };
+    let deserialize_bounds = quote! {
+        #(
+            #rkyv_path::Archived<#types>:
+                #rkyv_path::Deserialize<#types, __D>,
+        )*
+    };
+
+    let delegate = args.archived_trait.map(|archived_trait| {
+        let mut macro_path = archived_trait.clone();
+        let last = macro_path.segments.last_mut().unwrap();
+        last.ident = delegate_macro(&last.ident);
+        last.arguments = syn::PathArguments::None;
+        quote! {
+            #macro_path!(#archived_trait, #archived, [#(#variants),*]);
+        }
+    });
+
+    Ok(quote! {
+        #input
+
+        #[derive(#rkyv_path::Archive, #rkyv_path::Serialize)]
+        #(#archive_attrs)*
+        #archived_attr
+        #resolver_attr
+        #[doc(hidden)]
+        #vis enum #dispatch {
+            #(#variants(#rkyv_path::dispatch::DispatchRef<#types>),)*
+        }
+
+        impl #dispatch {
+            #[inline]
+            fn __from_dyn(
+                value: &dyn #dispatch_trait,
+            ) -> ::core::option::Option<Self> {
+                #(
+                    if let ::core::option::Option::Some(value) =
+                        #rkyv_path::dispatch::downcast_ref::<#types, _>(value)
+                    {
+                        // SAFETY: The dispatch enum is only serialized and
+                        // resolved while the trait object is borrowed.
+                        return ::core::option::Option::Some(Self::#variants(
+                            unsafe {
+                                #rkyv_path::dispatch::DispatchRef::new(value)
+                            },
+                        ));
+                    }
+                )*
+                ::core::option::Option::None
+            }
+
+            #[inline]
+            fn __from_enum(value: &#name) -> Self {
+                match value {
+                    #(
+                        // SAFETY: The dispatch enum is only serialized and
+                        // resolved while the enum is borrowed.
+                        #name::#variants(value) => Self::#variants(unsafe {
+                            #rkyv_path::dispatch::DispatchRef::new(value)
+                        }),
+                    )*
+                }
+            }
+        }
+
+        impl #rkyv_path::Archive for #name {
+            type Archived = #archived;
+            type Resolver = <#dispatch as #rkyv_path::Archive>::Resolver;
+
+            #[inline]
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                #rkyv_path::Archive::resolve(
+                    &#dispatch::__from_enum(self),
+                    pos,
+                    resolver,
+                    out,
+                );
+            }
+        }
+
+        impl<__S> #rkyv_path::Serialize<__S> for #name
+        where
+            __S: #rkyv_path::rancor::Fallible + ?Sized,
+            #writer_bound
+            #(#types: #rkyv_path::Serialize<__S>,)*
+        {
+            #[inline]
+            fn serialize(
+                &self,
+                serializer: &mut __S,
+            ) -> ::core::result::Result<Self::Resolver, __S::Error> {
+                #rkyv_path::Serialize::serialize(
+                    &#dispatch::__from_enum(self),
+                    serializer,
+                )
+            }
+        }
+
+        impl #rkyv_path::Archive for #boxed {
+            type Archived = #archived;
+            type Resolver = <#dispatch as #rkyv_path::Archive>::Resolver;
+
+            #[inline]
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                match #dispatch::__from_dyn(&**self) {
+                    ::core::option::Option::Some(dispatch) => {
+                        #rkyv_path::Archive::resolve(
+                            &dispatch,
+                            pos,
+                            resolver,
+                            out,
+                        );
+                    }
+                    // SAFETY: The resolver was returned from serializing this
+                    // value, which fails when the value isn't one of the
+                    // listed implementations.
+                    ::core::option::Option::None => {
+                        ::core::hint::unreachable_unchecked()
+                    }
+                }
+            }
+        }
+
+        impl<__S> #rkyv_path::Serialize<__S> for #boxed
+        where
+            __S: #rkyv_path::rancor::Fallible + ?Sized,
+            __S::Error: #rkyv_path::rancor::Error,
+            #writer_bound
+            #(#types: #rkyv_path::Serialize<__S>,)*
+        {
+            #[inline]
+            fn serialize(
+                &self,
+                serializer: &mut __S,
+            ) -> ::core::result::Result<Self::Resolver, __S::Error> {
+                match #dispatch::__from_dyn(&**self) {
+                    ::core::option::Option::Some(dispatch) => {
+                        #rkyv_path::Serialize::serialize(&dispatch, serializer)
+                    }
+                    ::core::option::Option::None => {
+                        ::core::result::Result::Err(
+                            <__S::Error as #rkyv_path::rancor::Error>::new(
+                                #unknown,
+                            ),
+                        )
+                    }
+                }
+            }
+        }
+
+        impl<__D> #rkyv_path::Deserialize<#name, __D> for #archived
+        where
+            __D: #rkyv_path::rancor::Fallible + ?Sized,
+            #deserialize_bounds
+        {
+            #[inline]
+            fn deserialize(
+                &self,
+                deserializer: &mut __D,
+            ) -> ::core::result::Result<#name, __D::Error> {
+                ::core::result::Result::Ok(match self {
+                    #(
+                        #archived::#variants(value, ..) => #name::#variants(
+                            #rkyv_path::Deserialize::<#types, __D>::deserialize(
+                                value,
+                                deserializer,
+                            )?,
+                        ),
+                    )*
+                })
+            }
+        }
+
+        impl<__D> #rkyv_path::Deserialize<#boxed, __D> for #archived
+        where
+            __D: #rkyv_path::rancor::Fallible + ?Sized,
+            #deserialize_bounds
+        {
+            #[inline]
+            fn deserialize(
+                &self,
+                deserializer: &mut __D,
+            ) -> ::core::result::Result<#boxed, __D::Error> {
+                ::core::result::Result::Ok(::core::convert::From::from(
+                    #rkyv_path::Deserialize::<#name, __D>::deserialize(
+                        self,
+                        deserializer,
+                    )?,
+                ))
+            }
+        }
+
+        impl ::core::convert::From<#name> for #boxed {
+            #[inline]
+            fn from(value: #name) -> Self {
+                match value {
+                    #(
+                        #name::#variants(value) => {
+                            #rkyv_path::dispatch::Box::new(value)
+                        }
+                    )*
+                }
+            }
+        }
+
+        #delegate
+    }
+    .into_token_stream())
+}
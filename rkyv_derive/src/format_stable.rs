@@ -0,0 +1,116 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Error, Fields, Ident, Index, LitInt,
+    Member,
+};
+
+use crate::{attributes::Attributes, util::strip_raw};
+
+/// Generates the `FormatStable` implementation for the archived type of a
+/// `format_stable` type, along with static assertions for its pinned size and
+/// alignment.
+pub fn format_stable(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let format_stable = match attributes.format_stable {
+        Some(ref format_stable) => format_stable,
+        None => return Ok(None),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            format_stable,
+            "format_stable is not supported for generic types",
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+
+    // Returns the offsets of each of the fields of `ty`, which may be the
+    // archived type or one of its variant structs. Fields of variant structs
+    // are offset by one to skip over the tag.
+    let field_offsets =
+        |ty: &Ident, prefix: &str, fields: &Fields, skip: usize| {
+            fields
+                .iter()
+                .enumerate()
+                .map(|(i, f)| {
+                    let (name, member) = match f.ident {
+                        Some(ref ident) => {
+                            (strip_raw(ident), Member::Named(ident.clone()))
+                        }
+                        None => (
+                            format!("_{}", i),
+                            Member::Unnamed(Index::from(i + skip)),
+                        ),
+                    };
+                    let name = format!("{}{}", prefix, name);
+                    quote! {
+                        #rkyv_path::stable::FieldOffset {
+                            name: #name,
+                            offset: ::core::mem::offset_of!(#ty, #member),
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+    let offsets = match input.data {
+        Data::Struct(ref data) => {
+            field_offsets(archived_name, "", &data.fields, 0)
+        }
+        Data::Enum(ref data) => data
+            .variants
+            .iter()
+            .flat_map(|v| {
+                let name = strip_raw(&v.ident);
+                let variant_struct =
+                    Ident::new(&format!("ArchivedVariant{}", name), v.span());
+                let prefix = format!("{}.", name);
+                field_offsets(&variant_struct, &prefix, &v.fields, 1)
+            })
+            .collect(),
+        Data::Union(_) => return Ok(None),
+    };
+
+    let assert = |lit: &Option<LitInt>, what: &str, function: TokenStream| {
+        lit.as_ref().map(|lit| {
+            let message = format!(
+                "the {} of `{}` is no longer {}",
+                what,
+                archived_name,
+                lit.base10_digits(),
+            );
+            quote! {
+                ::core::assert!(
+                    #function::<#archived_name>() == #lit,
+                    #message,
+                );
+            }
+        })
+    };
+    let size_assert = assert(
+        &attributes.format_stable_size,
+        "size",
+        quote! { ::core::mem::size_of },
+    );
+    let align_assert = assert(
+        &attributes.format_stable_align,
+        "alignment",
+        quote! { ::core::mem::align_of },
+    );
+
+    Ok(Some(quote! {
+        impl #rkyv_path::stable::FormatStable for #archived_name {
+            const FIELD_OFFSETS: &'static [#rkyv_path::stable::FieldOffset] =
+                &[#(#offsets,)*];
+        }
+
+        const _: () = {
+            #size_assert
+            #align_assert
+        };
+    }))
+}
@@ -6,13 +6,34 @@
     rustdoc::missing_crate_level_docs
 )]
 
+mod accessors;
 mod archive;
 mod attributes;
+mod c_api;
+mod callable;
+mod columnar;
+mod convenience;
+mod deep_size;
+mod derive_std;
 mod deserialize;
+mod enum_dispatch;
+mod format_stable;
+mod niche;
+mod pack_flags;
 mod portable;
+mod project;
+mod rearchive;
+mod reflect;
+mod render_json;
 mod repr;
 mod serde;
 mod serialize;
+mod stable_hash;
+mod stable_layout;
+#[cfg(test)]
+mod tests;
+mod transparent;
+mod type_hash;
 mod util;
 mod with;
 
@@ -24,7 +45,7 @@ use syn::{parse_macro_input, DeriveInput};
 ///
 /// This macro also supports the `#[omit_bounds]` attribute. See [`Archive`] for
 /// more information.
-#[proc_macro_derive(Portable, attributes(archive, omit_bounds))]
+#[proc_macro_derive(Portable, attributes(archive, omit_bounds, rkyv))]
 pub fn derive_portable(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
@@ -52,34 +73,251 @@ pub fn derive_portable(
 /// - `resolver = "..."`: Changes the name of the generated resolver type to the
 ///   given value. By default, resolver types are named `the name of the type` +
 ///   "Resolver".
-/// - `repr(...)`: *Deprecated, use `#[archive_attr(repr(...))]` instead.* Sets
-///   the representation for the archived type to the given representation.
-///   Available representation options may vary depending on features and type
-///   layout.
 /// - `compare(...)`: Implements common comparison operators between the
 ///   original and archived types. Supported comparisons are `PartialEq` and
 ///   `PartialOrd` (i.e. `#[archive(compare(PartialEq, PartialOrd))]`).
-/// - `bound(...)`: Adds additional bounds to trait implementations. This can be
-///   especially useful when dealing with recursive structures, where bounds may
-///   need to be omitted to prevent recursive type definitions. Use `archive =
-///   "..."` to specify `Archive` bounds, `serialize = "..."` to specify
-///   `Serialize` bounds, and `deserialize = "..."` to specify `Deserialize`
-///   bounds.
+/// - `archive_bounds(...)`, `serialize_bounds(...)`, and
+///   `deserialize_bounds(...)`: Add additional where clauses to the `Archive`,
+///   `Serialize`, and `Deserialize` implementations. This can be especially
+///   useful when dealing with recursive structures, where bounds may need to
+///   be omitted to prevent recursive type definitions.
 /// - `check_bytes`: Derive `CheckBytes` on the archived type, in order to
 ///   enable safe deserialization. Requires `validation` feature. Not compatible
 ///   with `as = "..."`. In that case, use `#[derive(CheckBytes)]` on the
 ///   archived type, and include a `use rkyv::bytecheck` statement.
-/// - `copy_safe`: States that the archived type is tightly packed with no
-///   padding bytes. This qualifies it for copy optimizations. (requires
-///   nightly)
+/// - `copy_safe`: Implements `ArchiveCopyOptimize` for the type and enables
+///   its copy optimization, so that slices and arrays of it are serialized
+///   with a single write. All fields must implement `ArchiveCopyOptimize`, and
+///   the type must have no padding and the same layout as its archived type.
+///   This usually requires `#[repr(C)]`. Fields are laid out in declaration
+///   order. Not supported for enums.
 /// - `as = "..."`: Instead of generating a separate archived type, this type
 ///   will archive as the named type. This is useful for types which are generic
 ///   over their parameters.
+/// - `transparent`: Archives a struct which wraps a single field as the
+///   archived type of that field, without generating an archived type or a
+///   resolver. See [Transparent types](#transparent-types) for details.
 /// - `crate = "..."`: Chooses an alternative crate path to import rkyv from.
+///   The path may also be given without quotes. All generated code refers to
+///   rkyv through this path and to the standard library through `::core`, so
+///   the derives work inside `#[no_implicit_prelude]` modules and in crates
+///   which only depend on rkyv through a re-export.
+///
+/// - `preserve_order`: Lays out the fields of the archived struct in
+///   declaration order instead of reordering them to minimize padding. See
+///   [Field order](#field-order) for details. Required to derive `PartialOrd`,
+///   `Ord`, or `Hash` for an archived struct whose fields would be reordered.
+/// - `export_layout`: Implements `DescribeLayout` for the archived type so
+///   that its layout can be rendered as a C header with
+///   `rkyv::layout::to_c_header`. All fields must have archived types which
+///   implement `DescribeLayout`. Not supported for generic types or with
+///   `as = "..."`.
+/// - `structural_hash`: Implies `export_layout`, and also implements
+///   `StructuralHash` for the archived type so that archives of it can be
+///   accessed as structurally identical types with
+///   `rkyv::compat::access_as_compatible`. All fields must have archived types
+///   which implement `StructuralHash`. Not supported for generic types or with
+///   `as = "..."`.
+/// - `columnar`: Generates a columns type, a row view type, and the
+///   `ArchiveColumns`, `SerializeColumns`, and `DeserializeColumns` traits so
+///   that a `Vec` of the type can be archived as one column per field with the
+///   `AsColumns` wrapper. Only supported for non-generic structs with named
+///   fields. Not compatible with `as = "..."`.
+/// - `derive_std`: Implements `Debug`, `PartialEq`, `Eq`, `Hash`,
+///   `PartialOrd`, and `Ord` for the archived type. Each is implemented
+///   whenever all of the archived fields implement it, so types with
+///   floating-point fields only implement `PartialEq` and `PartialOrd`. Fields
+///   are compared and hashed in declaration order, so comparisons are
+///   consistent with the same derives on the original type and structs hash
+///   the same as the original type when their fields do. Archived enums use a
+///   smaller discriminant than most enums, so they do not hash the same as the
+///   original type. Not compatible with `as = "..."`.
+/// - `deep_size`: Implements `ArchivedDeepSize` for the archived type so that
+///   the memory used by its deserialized value can be estimated with
+///   `rkyv::util::deserialized_size_estimate`. All fields must have archived
+///   types which implement `ArchivedDeepSize`. Requires the `alloc` feature.
+///   Not compatible with `as = "..."`.
+/// - `stable_hash`: Implements `StableHash` for the archived type so that its
+///   values can be hashed deterministically with
+///   `rkyv::stable_hash::stable_hash`. All fields must have archived types
+///   which implement `StableHash`. Not compatible with `as = "..."`.
+/// - `render_json`: Implements `RenderJson` for the archived type so that its
+///   values can be rendered as JSON with `rkyv::render::render_json`. All
+///   fields must have archived types which implement `RenderJson`. Not
+///   compatible with `as = "..."`.
+/// - `format_stable` or `format_stable(size = ..., align = ...)`: Implements
+///   `FormatStable` for the archived type so that its field offsets can be
+///   checked against a golden table with `rkyv::stable::format_table`. If
+///   `size` or `align` are given, compilation fails when the size or alignment
+///   of the archived type no longer matches. Not supported for generic types
+///   or with `as = "..."`.
+/// - `stable_layout`: Adds a `LAYOUT` associated constant to the archived type
+///   and records its field offsets so that they can be read in constant
+///   expressions with `rkyv::archived_offset_of!`, even if the fields are
+///   private. Only supported for structs. Not compatible with `as = "..."`.
+/// - `callable(registry = ..., function = ...)`: Implements `Callable` for the
+///   type so that its archived values can be called through the given
+///   registry. `function` is called with the archived value and the arguments
+///   of the registry. The ID of the type is derived from its module path and
+///   name. Not supported for generic types.
+/// - `c_api(prefix = "...")`: Generates `extern "C"` functions named with the
+///   given prefix which validate archives of the type and read its fields, and
+///   implements `CApi` for the archived type so that a C header declaring them
+///   can be generated with `rkyv::c_api::emit_c_header`. Fields declared as a
+///   `HashMap` with `String` keys also get a lookup function. See the
+///   `rkyv::c_api` module for details. Requires the `c_api` feature and an
+///   archived type which implements `CheckBytes`. Only supported for
+///   non-generic structs with named fields. Not compatible with `as = "..."`
+///   or `pack_flags`.
+/// - `no_accessors`: Disables the kind enum, the accessor methods, and the
+///   `Debug` implementation generated for enums. See
+///   [Enum accessors](#enum-accessors) for details.
+/// - `pack_flags`: On structs with named fields, packs `bool` fields and fields
+///   marked with `#[archive(bits = N)]` into a single integer in the archived
+///   type. See [Packed flags](#packed-flags) for details. On fieldless enums,
+///   implements `PackedFlag` so that the enum can be packed.
+/// - `reflect`: Implements `Reflect` for the archived type so that archives of
+///   it can be printed as a tree with `rkyv::reflect::dump`. All fields must
+///   have archived types which implement `Reflect`. Requires the `reflect`
+///   feature. Not supported for generic types or with `as = "..."`.
+/// - `rearchive`: Implements `Archive` and `Serialize` for the archived type so
+///   that archived values can be serialized again without the original type.
+///   The archived type archives as itself with the resolver of the original
+///   type, so `to_bytes(archived)` produces the same format as `to_bytes` on
+///   the original value. All fields must have archived types which archive as
+///   themselves with the same resolvers as the original fields. rkyv's
+///   archived strings, boxes, vecs, options, hash maps, hash sets, and
+///   shared pointers all do. Not compatible with `as = "..."` or
+///   `pack_flags`.
+/// - `type_hash`: Implements `TypeHash` for the type so that it can be the root
+///   type of an `rkyv::envelope`. The hash is computed from the name, field
+///   names, and field types of the type as written and the size and alignment
+///   of its archived type. Not supported for generic types.
+/// - `niche`: On enums with only unit variants, implements `ArchivedNiche` for
+///   the archived type so that `Option`s of it can be archived in the same
+///   space with `rkyv::with::NicheOption`. The first unused tag value is the
+///   niche. Not supported for generic types or with `as = "..."`.
+/// - `project`: On structs with named fields, generates a table of the offsets
+///   of the fields of the archived type and implements `Project` for it, so
+///   that slices of it can be projected onto some of their fields with
+///   `rkyv::project!`. Not supported for generic types. Not compatible with
+///   `as = "..."` or `pack_flags`.
+/// - `convenience`: Generates `to_archive_bytes`, `from_archive_bytes`, and
+///   `from_archive_bytes_unchecked` methods for the type which serialize and
+///   deserialize it with `rkyv::util::DefaultConfig`. Use
+///   `convenience(config = ...)` to use another type implementing
+///   `DefaultSerializer` and `DefaultDeserializer` instead. With the
+///   `bytecheck` feature, this also derives `CheckBytes` for the archived type
+///   and `from_archive_bytes` checks the bytes before deserializing them.
+///   Without it, only `from_archive_bytes_unchecked` is generated. Requires the
+///   `alloc` feature.
+///
+/// `#[rkyv(...)]` is an alias for `#[archive(...)]`. Unrecognized arguments,
+/// conflicting arguments (such as `archived` with `as`), and misspelled
+/// comparisons are reported as errors.
 ///
 /// `#[archive_attr(...)]` adds the attributes passed as arguments as attributes
 /// to the generated type. This is commonly used with attributes like
 /// `derive(...)` to derive trait implementations for the archived type.
+/// Derives which look like misspellings of common derives (e.g. `Debg`) are
+/// reported as errors.
+///
+/// # Field order
+///
+/// The fields of archived structs with named fields are reordered to reduce
+/// the amount of padding between them. The order is determined only by the
+/// field types as written, so it is the same across all compilations and
+/// targets:
+///
+/// 1. Fields are sorted by the alignment of their archived type, largest
+///    first.
+/// 2. The alignments of primitive integers, floats, `bool`, `char`, and arrays
+///    of those are known. All other fields, including fields with wrappers,
+///    are treated as having the largest alignment.
+/// 3. Fields with the same alignment keep their declaration order.
+///
+/// For example, `struct S { a: u8, b: u64, c: u16 }` archives as if it were
+/// declared `struct S { b: u64, c: u16, a: u8 }`, which is 16 bytes instead
+/// of 24.
+///
+/// Tuple structs, enum variants, and `copy_safe` structs always use
+/// declaration order. Use `#[archive(preserve_order)]` (or
+/// `#[rkyv(preserve_order)]`) to opt out of reordering, for example when the
+/// fields of an older version of a type must remain a prefix of the fields of
+/// a newer version.
+///
+/// Derived `PartialOrd`, `Ord`, and `Hash` implementations visit fields in the
+/// order they are declared in, so deriving them for the archived type with
+/// `#[archive_attr(derive(...))]` would compare and hash reordered fields in a
+/// different order than the original type. Archived hash maps and B-tree maps
+/// keyed by the type would then fail validation and miss lookups. Deriving
+/// any of these for a struct whose fields are reordered is an error. Either
+/// use `derive_std`, which implements them in declaration order, or use
+/// `preserve_order`.
+///
+/// # Enum accessors
+///
+/// For an enum `Foo`, the derive also generates a fieldless `FooKind` enum
+/// with the same variants as `Foo`, and the following methods:
+///
+/// - `kind()` on both `Foo` and `ArchivedFoo`, which returns the `FooKind` of
+///   the value.
+/// - `is_variant()` on `ArchivedFoo` for each variant, which returns whether
+///   the value is that variant.
+/// - `as_variant()` and `as_variant_pin()` on `ArchivedFoo` for each variant
+///   with fields, which return references to the archived fields of the
+///   variant, or `None` if the value is another variant. Variants with one
+///   field return a reference to it, and variants with more fields return a
+///   tuple of references in declaration order.
+///
+/// The method names are the variant names converted to `snake_case`, so
+/// `Foo::HttpRequest` gets `is_http_request()`. Variants whose names convert
+/// to the same method name are reported as errors.
+///
+/// `ArchivedFoo` also implements `Debug` whenever all of its archived fields
+/// do. It is formatted like a derived `Debug` on `Foo`. The implementation is
+/// not generated if the archived type already derives `Debug` or uses
+/// `derive_std`, or if any field has `#[omit_bounds]`.
+///
+/// None of these are generated with `as = "..."` or for enums without
+/// variants. Use `#[archive(no_accessors)]` to disable them.
+///
+/// # Transparent types
+///
+/// Structs with `#[archive(transparent)]` archive as their only field, so
+/// `struct UserId(u64)` archives as `Archived<u64>`. Archives of transparent
+/// types can be read as archives of the types they wrap, and vice versa. The
+/// struct must have exactly one field, or be `#[repr(transparent)]` with one
+/// field and any number of `PhantomData` fields. Wrappers on the field apply
+/// as usual, and `ManuallyDrop` fields archive as the value they wrap.
+///
+/// `as = "..."` may be used to name the archived type. It must be the archived
+/// type of the field, and compilation fails if it isn't. When it names the
+/// type directly (not through `Archived<...>`) and the struct is not generic,
+/// `From` is also implemented in both directions between the struct and the
+/// archived type whenever the archived type and the field type convert to
+/// each other.
+///
+/// `transparent` may only be combined with `as`, the bounds arguments,
+/// `convenience`, and `crate`.
+///
+/// # Packed flags
+///
+/// Structs with `#[archive(pack_flags)]` pack each `bool` field into one bit
+/// and each field marked with `#[archive(bits = N)]` into `N` bits of a single
+/// private integer field. The integer is the smallest of `u8`, `u16`, `u32`,
+/// and `u64` that fits all of the packed fields. Packed fields are read from
+/// the archived type with methods named after the fields, which return the
+/// unpacked values. Fields with wrappers are not packed.
+///
+/// Fields marked with `bits = N` must have types which implement
+/// `rkyv::flags::PackedFlag` in at most `N` bits. Validation checks that the
+/// bits of each packed field are valid and that all unused bits are zero.
+///
+/// `pack_flags` is not supported for generic types, and is not compatible
+/// with `copy_safe`, `export_layout`, `structural_hash`, `columnar`,
+/// `derive_std`, `deep_size`, `stable_hash`, `render_json`, `format_stable`,
+/// `stable_layout`, `reflect`, `c_api`, or `compare` on structs.
 ///
 /// # Recursive types
 ///
@@ -90,7 +328,8 @@ pub fn derive_portable(
 /// as well. Adding the attribute `#[omit_bounds]` to a field will suppress this
 /// trait bound and allow recursive structures. This may be too coarse for some
 /// types, in which case additional type bounds may be required with
-/// `bound(...)`.
+/// `archive_bounds(...)`, `serialize_bounds(...)`, and
+/// `deserialize_bounds(...)`.
 ///
 /// # Wrappers
 ///
@@ -103,7 +342,7 @@ pub fn derive_portable(
 /// `With<With<With<MyType, C>, B, A>`).
 #[proc_macro_derive(
     Archive,
-    attributes(archive, archive_attr, omit_bounds, with)
+    attributes(archive, archive_attr, omit_bounds, rkyv, with)
 )]
 pub fn derive_archive(
     input: proc_macro::TokenStream,
@@ -121,7 +360,7 @@ pub fn derive_archive(
 ///
 /// This macro also supports the `#[archive]`, `#[omit_bounds]`, and `#[with]`
 /// attributes. See [`Archive`] for more information.
-#[proc_macro_derive(Serialize, attributes(archive, omit_bounds, with))]
+#[proc_macro_derive(Serialize, attributes(archive, omit_bounds, rkyv, with))]
 pub fn derive_serialize(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
@@ -136,9 +375,14 @@ pub fn derive_serialize(
 
 /// Derives `Deserialize` for the labeled type.
 ///
+/// Errors from deserializing fields are traced with the name of the field as a
+/// `PathSegment`, so the error type of the deserializer must implement
+/// `Trace`. `PathError` collects these traces into the path of the value that
+/// failed to deserialize.
+///
 /// This macro also supports the `#[archive]`, `#[omit_bounds]`, and `#[with]`
 /// attributes. See [`Archive`] for more information.
-#[proc_macro_derive(Deserialize, attributes(archive, omit_bounds, with))]
+#[proc_macro_derive(Deserialize, attributes(archive, omit_bounds, rkyv, with))]
 pub fn derive_deserialize(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
@@ -150,3 +394,23 @@ pub fn derive_deserialize(
         Err(e) => e.to_compile_error().into(),
     }
 }
+
+/// Archives `Box<dyn Trait>` as an enum of the listed implementations.
+///
+/// Apply to an enum whose variants each hold one implementation of the trait,
+/// naming the trait as the first argument. The trait must have `Any` as a
+/// supertrait. Adding `archived = ...` implements the named trait for the
+/// archived enum by delegating to the archived value in each variant. That
+/// trait must also be marked with `#[rkyv_enum_dispatch]`.
+///
+/// See the `dispatch` module of rkyv for more information and an example.
+#[proc_macro_attribute]
+pub fn rkyv_enum_dispatch(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    match enum_dispatch::expand(attr.into(), item.into()) {
+        Ok(result) => result.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
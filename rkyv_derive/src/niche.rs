@@ -0,0 +1,64 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Ident};
+
+use crate::attributes::Attributes;
+
+/// Generates the `ArchivedNiche` implementation for the archived type of a
+/// `niche` enum.
+///
+/// Archived variants are numbered from zero, so the number of variants is the
+/// first tag that isn't used by any variant.
+pub fn niche(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let niche = match attributes.niche {
+        Some(ref niche) => niche,
+        None => return Ok(None),
+    };
+
+    let len = match input.data {
+        Data::Enum(ref data)
+            if !data.variants.is_empty()
+                && data
+                    .variants
+                    .iter()
+                    .all(|v| matches!(v.fields, Fields::Unit)) =>
+        {
+            data.variants.len() as u128
+        }
+        _ => {
+            return Err(Error::new_spanned(
+                niche,
+                "niche is only supported for enums with only unit variants",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            niche,
+            "niche is not supported for generic types",
+        ));
+    }
+
+    // Matches the repr of the archived enum
+    let repr = match len {
+        0..=255 => quote! { u8 },
+        256..=65_535 => quote! { u16 },
+        65_536..=4_294_967_295 => quote! { u32 },
+        4_294_967_296..=18_446_744_073_709_551_615 => quote! { u64 },
+        _ => quote! { u128 },
+    };
+    let value = Literal::u128_unsuffixed(len);
+    let rkyv_path = attributes.rkyv_path();
+
+    Ok(Some(quote! {
+        unsafe impl #rkyv_path::niche::ArchivedNiche for #archived_name {
+            type NicheRepr = #repr;
+
+            const NICHE: #repr = #value;
+        }
+    }))
+}
@@ -0,0 +1,466 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{
+    parse::Parser, Attribute, Data, DeriveInput, Error, Field, Fields,
+    FieldsNamed, Ident, Path, Type, WhereClause,
+};
+
+use crate::{
+    archive::field_archive_attrs,
+    attributes::{field_bits, Attributes},
+    util::{
+        check_layout_derives, field_predicate, has_with, is_not_omitted,
+        layout_order, strip_raw,
+    },
+    with::{make_with_cast, make_with_ty},
+};
+
+/// The name of the field which holds the packed bits in the archived type.
+const FLAGS_FIELD: &str = "__flags";
+
+fn is_bool(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.is_ident("bool"),
+        Type::Paren(paren) => is_bool(&paren.elem),
+        Type::Group(group) => is_bool(&group.elem),
+        _ => false,
+    }
+}
+
+/// Returns the number of bits that a field is packed into, or `None` if it
+/// isn't packed.
+///
+/// `bool` fields are packed into one bit unless they have a wrapper, and other
+/// fields are packed when they have a `bits` argument.
+pub fn packed_bits(
+    attributes: &Attributes,
+    field: &Field,
+) -> Result<Option<u32>, Error> {
+    if attributes.pack_flags.is_none() {
+        return Ok(None);
+    }
+
+    match field_bits(field)? {
+        Some(bits) => {
+            if has_with(field) {
+                return Err(Error::new_spanned(
+                    bits,
+                    "packed fields may not have wrappers",
+                ));
+            }
+            Ok(Some(bits.base10_parse()?))
+        }
+        None if is_bool(&field.ty) && !has_with(field) => Ok(Some(1)),
+        None => Ok(None),
+    }
+}
+
+/// Checks that `pack_flags` can be used with the type and its other
+/// arguments.
+pub fn check(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<(), Error> {
+    let pack_flags = match attributes.pack_flags {
+        Some(ref pack_flags) => pack_flags,
+        None => return Ok(()),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            pack_flags,
+            "pack_flags is not supported for generic types",
+        ));
+    }
+
+    match input.data {
+        Data::Struct(ref data) if matches!(data.fields, Fields::Named(_)) => {
+            let conflicts: [(&Option<Path>, &str); 14] = [
+                (&attributes.copy_safe, "copy_safe"),
+                (&attributes.export_layout, "export_layout"),
+                (&attributes.structural_hash, "structural_hash"),
+                (&attributes.columnar, "columnar"),
+                (&attributes.derive_std, "derive_std"),
+                (&attributes.deep_size, "deep_size"),
+                (&attributes.stable_hash, "stable_hash"),
+                (&attributes.render_json, "render_json"),
+                (&attributes.format_stable, "format_stable"),
+                (&attributes.stable_layout, "stable_layout"),
+                (&attributes.reflect, "reflect"),
+                (&attributes.rearchive, "rearchive"),
+                (&attributes.c_api, "c_api"),
+                (&attributes.project, "project"),
+            ];
+            for (path, name) in conflicts {
+                if let Some(path) = path {
+                    return Err(Error::new_spanned(
+                        path,
+                        format!(
+                            "`{}` may not be used with `pack_flags` on structs",
+                            name,
+                        ),
+                    ));
+                }
+            }
+            if let Some(ref compares) = attributes.compares {
+                return Err(Error::new_spanned(
+                    compares,
+                    "`compare` may not be used with `pack_flags` on structs",
+                ));
+            }
+            Ok(())
+        }
+        Data::Enum(ref data)
+            if !data.variants.is_empty()
+                && data
+                    .variants
+                    .iter()
+                    .all(|v| matches!(v.fields, Fields::Unit)) =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::new_spanned(
+            pack_flags,
+            "pack_flags is only supported for structs with named fields and \
+             enums with only unit variants",
+        )),
+    }
+}
+
+/// Generates the `PackedFlag` implementation for a `pack_flags` enum.
+///
+/// Each variant is represented by its index.
+pub fn packed_flag(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<Option<TokenStream>, Error> {
+    let data = match (&attributes.pack_flags, &input.data) {
+        (Some(_), Data::Enum(ref data)) => data,
+        _ => return Ok(None),
+    };
+
+    let rkyv_path = attributes.rkyv_path();
+    let name = &input.ident;
+    let len = data.variants.len() as u64;
+    let bits = 64 - (len - 1).leading_zeros();
+    let variants = data.variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
+    let indices = (0..len).map(Literal::u64_suffixed).collect::<Vec<_>>();
+
+    Ok(Some(quote! {
+        impl #rkyv_path::flags::PackedFlag for #name {
+            const BITS: u32 = #bits;
+
+            #[inline]
+            fn to_flag_bits(&self) -> u64 {
+                match self {
+                    #(Self::#variants => #indices,)*
+                }
+            }
+
+            #[inline]
+            fn from_flag_bits(bits: u64) -> ::core::option::Option<Self> {
+                match bits {
+                    #(#indices => ::core::option::Option::Some(Self::#variants),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    }))
+}
+
+/// A packed field and the bits it occupies.
+struct PackedField<'a> {
+    field: &'a Field,
+    name: &'a Ident,
+    offset: u32,
+    bits: u32,
+}
+
+impl PackedField<'_> {
+    fn mask(&self) -> Literal {
+        Literal::u64_suffixed(u64::MAX >> (64 - self.bits))
+    }
+
+    // Returns an expression for the bits of the field in `flags`
+    fn extract(&self, flags: &TokenStream) -> TokenStream {
+        let mask = self.mask();
+        if self.offset == 0 {
+            quote! { #flags & #mask }
+        } else {
+            let offset = self.offset;
+            quote! { (#flags >> #offset) & #mask }
+        }
+    }
+}
+
+/// Generates the archived type, resolver type, `Archive` implementation, and
+/// accessor methods for a `pack_flags` struct.
+///
+/// The packed fields are replaced with a single integer in the archived type,
+/// which is laid out like an unsigned integer field of the same size.
+#[allow(clippy::too_many_arguments)]
+pub fn archive_struct(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    archived_name: &Ident,
+    resolver: &Ident,
+    archive_attrs: &[Attribute],
+    where_clause: &WhereClause,
+) -> Result<(TokenStream, TokenStream), Error> {
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let with_cast = make_with_cast(&rkyv_path);
+    let packed_flag = quote! { #rkyv_path::flags::PackedFlag };
+
+    let name = &input.ident;
+    let vis = &input.vis;
+
+    let mut packed = Vec::new();
+    let mut unpacked = Vec::new();
+    let mut total_bits = 0;
+    for field in fields.named.iter() {
+        match packed_bits(attributes, field)? {
+            Some(bits) => {
+                if total_bits + bits > 64 {
+                    return Err(Error::new_spanned(
+                        field,
+                        "packed fields may not take up more than 64 bits",
+                    ));
+                }
+                packed.push(PackedField {
+                    field,
+                    name: field.ident.as_ref().unwrap(),
+                    offset: total_bits,
+                    bits,
+                });
+                total_bits += bits;
+            }
+            None => unpacked.push(field.clone()),
+        }
+    }
+
+    let word = match total_bits {
+        0..=8 => quote! { u8 },
+        9..=16 => quote! { u16 },
+        17..=32 => quote! { u32 },
+        _ => quote! { u64 },
+    };
+    let flags_ident = Ident::new(FLAGS_FIELD, name.span());
+    unpacked.push(Field::parse_named.parse2(quote! { #flags_ident: #word })?);
+    let layout_fields =
+        layout_order(unpacked.iter(), attributes.preserve_order.is_some());
+    check_layout_derives(attributes, unpacked.iter(), &layout_fields)?;
+    let is_flags = |f: &Field| f.ident.as_ref() == Some(&flags_ident);
+
+    let mut archive_where = where_clause.clone();
+    for field in unpacked.iter().filter(is_not_omitted) {
+        if !is_flags(field) {
+            let ty = with_ty(field)?;
+            archive_where.predicates.push(field_predicate(
+                &ty,
+                quote! { #ty: #rkyv_path::Archive },
+            ));
+        }
+    }
+
+    let archived_fields = layout_fields.iter().map(|f| {
+        let field_name = f.ident.as_ref().unwrap();
+        if is_flags(f) {
+            let doc = format!("The packed fields of [`{}`]", name);
+            quote! {
+                #[doc = #doc]
+                #field_name: #rkyv_path::Archived<#word>
+            }
+        } else {
+            let ty = with_ty(f).unwrap();
+            let vis = &f.vis;
+            let field_doc = format!(
+                "The archived counterpart of [`{}::{}`]",
+                name, field_name
+            );
+            let archive_attrs = field_archive_attrs(f);
+            quote! {
+                #[doc = #field_doc]
+                #(#[#archive_attrs])*
+                #vis #field_name: #rkyv_path::Archived<#ty>
+            }
+        }
+    });
+
+    let resolver_fields =
+        layout_fields.iter().filter(|f| !is_flags(f)).map(|f| {
+            let name = &f.ident;
+            let ty = with_ty(f).unwrap();
+            quote! { #name: #rkyv_path::Resolver<#ty> }
+        });
+
+    let resolve_fields = layout_fields.iter().map(|f| {
+        let name = &f.ident;
+        if is_flags(f) {
+            let bits = packed.iter().map(|p| {
+                let name = p.name;
+                let ty = &p.field.ty;
+                let bits = quote! { <#ty as #packed_flag>::to_flag_bits(&self.#name) };
+                if p.offset == 0 {
+                    bits
+                } else {
+                    let offset = p.offset;
+                    quote! { (#bits << #offset) }
+                }
+            });
+            quote! {
+                let flags = 0u64 #(| #bits)*;
+                let (fp, fo) = #rkyv_path::out_field!(out.#name);
+                #rkyv_path::Archive::resolve(&(flags as #word), pos + fp, (), fo);
+            }
+        } else {
+            let field = with_cast(f, syn::parse_quote! { (&self.#name) }).unwrap();
+            quote! {
+                let (fp, fo) = #rkyv_path::out_field!(out.#name);
+                #rkyv_path::Archive::resolve(#field, pos + fp, resolver.#name, fo);
+            }
+        }
+    });
+
+    let flags = if total_bits <= 8 {
+        quote! { (self.#flags_ident as u64) }
+    } else {
+        quote! { (self.#flags_ident.to_native() as u64) }
+    };
+
+    let accessors = packed.iter().map(|p| {
+        let name = p.name;
+        let ty = &p.field.ty;
+        let vis = &p.field.vis;
+        let doc = format!(
+            "Returns the value of the packed [`{}::{}`]",
+            input.ident, name
+        );
+        let message =
+            format!("invalid bits for packed field `{}`", strip_raw(name));
+        let bits = p.extract(&flags);
+        quote! {
+            #[doc = #doc]
+            #[inline]
+            #vis fn #name(&self) -> #ty {
+                match <#ty as #packed_flag>::from_flag_bits(#bits) {
+                    ::core::option::Option::Some(value) => value,
+                    ::core::option::Option::None => ::core::panic!(#message),
+                }
+            }
+        }
+    });
+
+    // Packed fields must fit in the bits they are given
+    let bits_checks = packed.iter().map(|p| {
+        let ty = &p.field.ty;
+        let bits = p.bits;
+        let message = format!(
+            "the type of packed field `{}` does not fit in {} bit{}",
+            strip_raw(p.name),
+            bits,
+            if bits == 1 { "" } else { "s" },
+        );
+        quote! {
+            const _: () = ::core::assert!(<#ty as #packed_flag>::BITS <= #bits, #message);
+        }
+    });
+
+    let verify_impl = if attributes.derives_check_bytes().is_some() {
+        let used_mask = Literal::u64_suffixed(if total_bits == 0 {
+            0
+        } else {
+            u64::MAX >> (64 - total_bits)
+        });
+        let checks = packed.iter().map(|p| {
+            let ty = &p.field.ty;
+            let field_name = strip_raw(p.name);
+            let bits = p.extract(&quote! { flags });
+            quote! {
+                let bits = #bits;
+                if <#ty as #packed_flag>::from_flag_bits(bits).is_none() {
+                    #rkyv_path::rancor::fail!(#rkyv_path::flags::InvalidFlags::InvalidField {
+                        field: #field_name,
+                        bits,
+                    });
+                }
+            }
+        });
+        Some(quote! {
+            // SAFETY: `verify` only checks the packed bits, and does not
+            // change which bit patterns of the fields are valid.
+            unsafe impl<__C> #rkyv_path::bytecheck::Verify<__C> for #archived_name
+            where
+                __C: #rkyv_path::rancor::Fallible + ?::core::marker::Sized,
+                <__C as #rkyv_path::rancor::Fallible>::Error: #rkyv_path::rancor::Error,
+            {
+                fn verify(
+                    &self,
+                    _: &mut __C,
+                ) -> ::core::result::Result<(), <__C as #rkyv_path::rancor::Fallible>::Error> {
+                    let flags = #flags;
+                    let unused = flags & !#used_mask;
+                    if unused != 0 {
+                        #rkyv_path::rancor::fail!(#rkyv_path::flags::InvalidFlags::UnusedBits {
+                            bits: unused,
+                        });
+                    }
+                    #(#checks)*
+                    ::core::result::Result::Ok(())
+                }
+            }
+        })
+    } else {
+        None
+    };
+    let verify_attr = verify_impl
+        .as_ref()
+        .map(|_| quote! { #[check_bytes(verify)] });
+
+    let archived_doc = format!("An archived [`{}`]", name);
+    let resolver_doc = format!("The resolver for an archived [`{}`]", name);
+
+    let archive_types = quote! {
+        // SAFETY: As long as the `Archive` impl holds, the archived type is guaranteed to be `Portable`.
+        unsafe impl #rkyv_path::Portable for #archived_name #archive_where {}
+
+        #[automatically_derived]
+        #[doc = #archived_doc]
+        #(#archive_attrs)*
+        #verify_attr
+        #[repr(C)]
+        #vis struct #archived_name #archive_where {
+            #(#archived_fields,)*
+        }
+
+        #[automatically_derived]
+        #[doc = #resolver_doc]
+        #vis struct #resolver #archive_where {
+            #(#resolver_fields,)*
+        }
+    };
+
+    let archive_impls = quote! {
+        impl #rkyv_path::Archive for #name #archive_where {
+            type Archived = #archived_name;
+            type Resolver = #resolver;
+
+            // Some resolvers will be (), this allow is to prevent clippy from complaining
+            #[allow(clippy::unit_arg)]
+            #[inline]
+            unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+                #(#resolve_fields)*
+            }
+        }
+
+        impl #archived_name #archive_where {
+            #(#accessors)*
+        }
+
+        #(#bits_checks)*
+
+        #verify_impl
+    };
+
+    Ok((archive_types, archive_impls))
+}
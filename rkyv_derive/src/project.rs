@@ -0,0 +1,103 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DataStruct, DeriveInput, Error, Fields, Ident};
+
+use crate::{attributes::Attributes, util::strip_raw, with::make_with_ty};
+
+/// Generates the field table type of a `project` struct and the `Project`
+/// implementation for its archived type.
+///
+/// Returns the field table type, which is placed next to the archived type,
+/// and the implementation.
+pub fn project(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<(Option<TokenStream>, Option<TokenStream>), Error> {
+    let project = match attributes.project {
+        Some(ref project) => project,
+        None => return Ok((None, None)),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            project,
+            "project is not supported for generic types",
+        ));
+    }
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => fields,
+        _ => {
+            return Err(Error::new_spanned(
+                project,
+                "project is only supported for structs with named fields",
+            ))
+        }
+    };
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let name = &input.ident;
+    let vis = &input.vis;
+    let fields_name =
+        Ident::new(&format!("{}Fields", strip_raw(archived_name)), name.span());
+
+    let mut field_decls = Vec::new();
+    let mut field_values = Vec::new();
+    for field in fields.named.iter() {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_vis = &field.vis;
+        let ty = with_ty(field)?;
+        let field_ty = quote! {
+            #rkyv_path::project::Field<
+                #archived_name,
+                #rkyv_path::Archived<#ty>,
+            >
+        };
+
+        let doc = format!(
+            "The archived field of [`{}::{}`]",
+            name,
+            strip_raw(field_name),
+        );
+        field_decls.push(quote! {
+            #[doc = #doc]
+            #field_vis #field_name: #field_ty
+        });
+        field_values.push(quote! {
+            // SAFETY: The offset is the offset of the field in the archived
+            // type, and the field has the archived type of the field.
+            #field_name: unsafe {
+                #rkyv_path::project::Field::new(
+                    ::core::mem::offset_of!(#archived_name, #field_name),
+                )
+            }
+        });
+    }
+
+    let doc = format!("The fields of [`{}`]", archived_name);
+    let types = quote! {
+        #[automatically_derived]
+        #[doc = #doc]
+        #[derive(::core::clone::Clone, ::core::marker::Copy)]
+        #vis struct #fields_name {
+            #(#field_decls,)*
+        }
+    };
+
+    let impls = quote! {
+        // SAFETY: Each field in the table has the offset and type of the
+        // corresponding field of the archived type.
+        unsafe impl #rkyv_path::project::Project for #archived_name {
+            type Fields = #fields_name;
+
+            const FIELDS: Self::Fields = #fields_name {
+                #(#field_values,)*
+            };
+        }
+    };
+
+    Ok((Some(types), Some(impls)))
+}
@@ -0,0 +1,276 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput,
+    Error, Fields, Generics, Ident, Index,
+};
+
+use crate::{
+    attributes::Attributes,
+    util::{field_predicate, is_not_omitted, strip_raw},
+    with::make_with_ty,
+};
+
+/// Generates the `Archive` and `Serialize` implementations for the archived
+/// type of a `rearchive` type.
+///
+/// The archived type archives as itself and reuses the resolver of the
+/// original type, so each archived field must archive as itself with the
+/// resolver of the original field.
+pub fn rearchive(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+    resolver: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    if attributes.rearchive.is_none() {
+        return Ok(None);
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+
+    let fields = match input.data {
+        Data::Struct(ref data) => data.fields.iter().collect::<Vec<_>>(),
+        Data::Enum(ref data) => {
+            data.variants.iter().flat_map(|v| v.fields.iter()).collect()
+        }
+        Data::Union(_) => return Ok(None),
+    };
+
+    let mut archive_where = input.generics.where_clause.clone().unwrap();
+    for field in fields.iter().copied().filter(is_not_omitted) {
+        let ty = with_ty(field)?;
+        archive_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+        archive_where.predicates.push(field_predicate(
+            &ty,
+            quote! {
+                #rkyv_path::Archived<#ty>: #rkyv_path::Archive<
+                    Archived = #rkyv_path::Archived<#ty>,
+                    Resolver = #rkyv_path::Resolver<#ty>,
+                >
+            },
+        ));
+    }
+
+    let mut serialize_where = archive_where.clone();
+    if let Some(ref bounds) = attributes.serialize_bounds {
+        for bound in bounds {
+            serialize_where.predicates.push(bound.clone());
+        }
+    }
+    for field in fields.iter().copied().filter(is_not_omitted) {
+        let ty = with_ty(field)?;
+        serialize_where.predicates.push(field_predicate(
+            &ty,
+            quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Serialize<__S> },
+        ));
+    }
+
+    let mut serialize_params = Punctuated::default();
+    serialize_params.push(
+        parse_quote! { __S: #rkyv_path::rancor::Fallible + ?::core::marker::Sized },
+    );
+    for param in input.generics.params.iter() {
+        serialize_params.push(param.clone());
+    }
+    let serialize_generics = Generics {
+        lt_token: Some(Default::default()),
+        params: serialize_params,
+        gt_token: Some(Default::default()),
+        where_clause: None,
+    };
+    let (serialize_impl_generics, _, _) = serialize_generics.split_for_impl();
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+
+    let (resolve, serialize) = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let names =
+                    fields.named.iter().map(|f| &f.ident).collect::<Vec<_>>();
+                (
+                    quote! {
+                        #(
+                            let (fp, fo) = #rkyv_path::out_field!(out.#names);
+                            #rkyv_path::Archive::resolve(&self.#names, pos + fp, resolver.#names, fo);
+                        )*
+                    },
+                    quote! {
+                        #resolver {
+                            #(#names: #rkyv_path::Serialize::<__S>::serialize(&self.#names, serializer)?,)*
+                        }
+                    },
+                )
+            }
+            Fields::Unnamed(ref fields) => {
+                let indices = (0..fields.unnamed.len())
+                    .map(Index::from)
+                    .collect::<Vec<_>>();
+                (
+                    quote! {
+                        #(
+                            let (fp, fo) = #rkyv_path::out_field!(out.#indices);
+                            #rkyv_path::Archive::resolve(&self.#indices, pos + fp, resolver.#indices, fo);
+                        )*
+                    },
+                    quote! {
+                        #resolver(
+                            #(#rkyv_path::Serialize::<__S>::serialize(&self.#indices, serializer)?,)*
+                        )
+                    },
+                )
+            }
+            Fields::Unit => (quote! {}, quote! { #resolver }),
+        },
+        Data::Enum(ref data) => {
+            let mut resolve_arms = Vec::new();
+            let mut serialize_arms = Vec::new();
+            for v in data.variants.iter() {
+                let variant = &v.ident;
+                let archived_variant_name = Ident::new(
+                    &format!("ArchivedVariant{}", strip_raw(variant)),
+                    v.span(),
+                );
+                match v.fields {
+                    Fields::Named(ref fields) => {
+                        let names = fields
+                            .named
+                            .iter()
+                            .map(|f| &f.ident)
+                            .collect::<Vec<_>>();
+                        let self_bindings = names
+                            .iter()
+                            .map(|name| {
+                                let name = name.as_ref().unwrap();
+                                Ident::new(
+                                    &format!("self_{}", strip_raw(name)),
+                                    name.span(),
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        let resolver_bindings = names
+                            .iter()
+                            .map(|name| {
+                                let name = name.as_ref().unwrap();
+                                Ident::new(
+                                    &format!("resolver_{}", strip_raw(name)),
+                                    name.span(),
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        resolve_arms.push(quote! {
+                            #resolver::#variant { #(#names: #resolver_bindings,)* } => {
+                                match self {
+                                    #archived_name::#variant { #(#names: #self_bindings,)* } => {
+                                        let out = out.cast::<#archived_variant_name #ty_generics>();
+                                        ::core::ptr::addr_of_mut!((*out).__tag)
+                                            .write(ArchivedTag::#variant);
+                                        #(
+                                            let (fp, fo) = #rkyv_path::out_field!(out.#names);
+                                            #rkyv_path::Archive::resolve(#self_bindings, pos + fp, #resolver_bindings, fo);
+                                        )*
+                                    },
+                                    #[allow(unreachable_patterns)]
+                                    _ => ::core::hint::unreachable_unchecked(),
+                                }
+                            }
+                        });
+                        serialize_arms.push(quote! {
+                            #archived_name::#variant { #(#names: #self_bindings,)* } => #resolver::#variant {
+                                #(#names: #rkyv_path::Serialize::<__S>::serialize(#self_bindings, serializer)?,)*
+                            }
+                        });
+                    }
+                    Fields::Unnamed(ref fields) => {
+                        let self_bindings = fields
+                            .unnamed
+                            .iter()
+                            .enumerate()
+                            .map(|(i, f)| {
+                                Ident::new(&format!("self_{}", i), f.span())
+                            })
+                            .collect::<Vec<_>>();
+                        let resolver_bindings = fields
+                            .unnamed
+                            .iter()
+                            .enumerate()
+                            .map(|(i, f)| {
+                                Ident::new(&format!("resolver_{}", i), f.span())
+                            })
+                            .collect::<Vec<_>>();
+                        let indices = (1..=fields.unnamed.len())
+                            .map(Index::from)
+                            .collect::<Vec<_>>();
+                        resolve_arms.push(quote! {
+                            #resolver::#variant(#(#resolver_bindings,)*) => {
+                                match self {
+                                    #archived_name::#variant(#(#self_bindings,)*) => {
+                                        let out = out.cast::<#archived_variant_name #ty_generics>();
+                                        ::core::ptr::addr_of_mut!((*out).0).write(ArchivedTag::#variant);
+                                        #(
+                                            let (fp, fo) = #rkyv_path::out_field!(out.#indices);
+                                            #rkyv_path::Archive::resolve(#self_bindings, pos + fp, #resolver_bindings, fo);
+                                        )*
+                                    },
+                                    #[allow(unreachable_patterns)]
+                                    _ => ::core::hint::unreachable_unchecked(),
+                                }
+                            }
+                        });
+                        serialize_arms.push(quote! {
+                            #archived_name::#variant(#(#self_bindings,)*) => #resolver::#variant(
+                                #(#rkyv_path::Serialize::<__S>::serialize(#self_bindings, serializer)?,)*
+                            )
+                        });
+                    }
+                    Fields::Unit => {
+                        resolve_arms.push(quote! {
+                            #resolver::#variant => {
+                                out.cast::<ArchivedTag>().write(ArchivedTag::#variant);
+                            }
+                        });
+                        serialize_arms.push(quote! {
+                            #archived_name::#variant => #resolver::#variant
+                        });
+                    }
+                }
+            }
+            (
+                quote! {
+                    match resolver {
+                        #(#resolve_arms,)*
+                    }
+                },
+                quote! {
+                    match self {
+                        #(#serialize_arms,)*
+                    }
+                },
+            )
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    Ok(Some(quote! {
+        impl #impl_generics #rkyv_path::Archive for #archived_name #ty_generics #archive_where {
+            type Archived = Self;
+            type Resolver = #resolver #ty_generics;
+
+            // Some resolvers will be (), this allow is to prevent clippy from complaining
+            #[allow(clippy::unit_arg)]
+            #[inline]
+            unsafe fn resolve(&self, pos: usize, resolver: <Self as #rkyv_path::Archive>::Resolver, out: *mut <Self as #rkyv_path::Archive>::Archived) {
+                #resolve
+            }
+        }
+
+        impl #serialize_impl_generics #rkyv_path::Serialize<__S> for #archived_name #ty_generics #serialize_where {
+            #[inline]
+            fn serialize(&self, serializer: &mut __S) -> ::core::result::Result<<Self as #rkyv_path::Archive>::Resolver, <__S as #rkyv_path::rancor::Fallible>::Error> {
+                ::core::result::Result::Ok(#serialize)
+            }
+        }
+    }))
+}
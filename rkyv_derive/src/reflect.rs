@@ -0,0 +1,111 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Error, Fields, Ident, Index, Member,
+};
+
+use crate::{attributes::Attributes, util::strip_raw, with::make_with_ty};
+
+/// Generates the `Reflect` implementation for the archived type of a `reflect`
+/// type.
+pub fn reflect(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let reflect = match attributes.reflect {
+        Some(ref reflect) => reflect,
+        None => return Ok(None),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            reflect,
+            "reflect is not supported for generic types",
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let reflect = quote! { #rkyv_path::reflect };
+
+    // Describes each of the fields of `ty`, which may be the archived type or
+    // one of its variant structs. Fields of variant structs are offset by one
+    // to skip over the tag.
+    let describe_fields = |ty: &Ident, fields: &Fields, skip: usize| {
+        let fields = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let (name, member) = match f.ident {
+                    Some(ref ident) => {
+                        (strip_raw(ident), Member::Named(ident.clone()))
+                    }
+                    None => (
+                        format!("_{}", i),
+                        Member::Unnamed(Index::from(i + skip)),
+                    ),
+                };
+                let ty_field = with_ty(f)?;
+                Ok(quote! {
+                    #reflect::FieldDescriptor {
+                        name: #name,
+                        offset: ::core::mem::offset_of!(#ty, #member),
+                        ty: #reflect::descriptor::<
+                            #rkyv_path::Archived<#ty_field>
+                        >,
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok::<_, Error>(quote! { &[#(#fields,)*] })
+    };
+
+    let kind = match input.data {
+        Data::Struct(ref data) => {
+            let fields = describe_fields(archived_name, &data.fields, 0)?;
+            quote! { #reflect::DescriptorKind::Struct { fields: #fields } }
+        }
+        Data::Enum(ref data) => {
+            let tag_size: usize = match data.variants.len() as u128 {
+                0..=255 => 1,
+                256..=65_535 => 2,
+                65_536..=4_294_967_295 => 4,
+                _ => 8,
+            };
+            let variants = data
+                .variants
+                .iter()
+                .map(|v| {
+                    let name = strip_raw(&v.ident);
+                    let variant_struct = Ident::new(
+                        &format!("ArchivedVariant{}", name),
+                        v.span(),
+                    );
+                    let fields =
+                        describe_fields(&variant_struct, &v.fields, 1)?;
+                    Ok(quote! {
+                        #reflect::VariantDescriptor {
+                            name: #name,
+                            fields: #fields,
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            quote! {
+                #reflect::DescriptorKind::Enum {
+                    tag_size: #tag_size,
+                    variants: &[#(#variants,)*],
+                }
+            }
+        }
+        Data::Union(_) => return Ok(None),
+    };
+
+    let name = archived_name.to_string();
+    Ok(Some(quote! {
+        impl #reflect::Reflect for #archived_name {
+            const DESCRIPTOR: #reflect::TypeDescriptor =
+                #reflect::TypeDescriptor::of::<Self>(#name, #kind);
+        }
+    }))
+}
@@ -0,0 +1,124 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Ident};
+
+use crate::{
+    attributes::Attributes,
+    util::{field_predicate, is_not_omitted, strip_raw, Variant},
+    with::make_with_ty,
+};
+
+/// Generates the `RenderJson` implementation for the archived type of a
+/// `render_json` type.
+///
+/// Values are rendered in the same shape as `serde_json` renders them: structs
+/// with named fields as objects, newtype structs as their field, tuple structs
+/// as arrays, and unit structs as `null`. Unit variants are rendered as their
+/// name, and other variants as an object with a single entry from their name
+/// to their fields.
+pub fn render_json(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let render_json = match attributes.render_json {
+        Some(ref render_json) => render_json,
+        None => return Ok(None),
+    };
+
+    let variants = match Variant::all(input, archived_name) {
+        Some(variants) => variants,
+        None => return Ok(None),
+    };
+    let is_enum = matches!(input.data, Data::Enum(_));
+    if is_enum && variants.is_empty() {
+        return Err(Error::new_spanned(
+            render_json,
+            "render_json is not supported for enums without variants",
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let render = quote! { #rkyv_path::render };
+
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let mut render_where = where_clause.unwrap().clone();
+    for field in variants
+        .iter()
+        .flat_map(|v| v.fields.iter())
+        .filter(is_not_omitted)
+    {
+        let ty = with_ty(field)?;
+        render_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+        // Higher-ranked so that fields which don't implement `RenderJson` are
+        // reported where the impl is used
+        render_where.predicates.push(field_predicate(
+            &ty,
+            quote! { for<'__a> #rkyv_path::Archived<#ty>: #render::RenderJson },
+        ));
+    }
+
+    let arms = variants.iter().map(|v| {
+        let pattern = v.pattern("__self");
+        let bindings = v.bindings("__self");
+        let fields = match v.fields {
+            Fields::Named(ref fields) => {
+                let names = fields
+                    .named
+                    .iter()
+                    .map(|f| strip_raw(f.ident.as_ref().unwrap()));
+                quote! {
+                    let mut __object = __renderer.object();
+                    #(__object.field(#names, #bindings)?;)*
+                    __object.finish()
+                }
+            }
+            Fields::Unnamed(_) if bindings.len() == 1 => {
+                let binding = &bindings[0];
+                quote! {
+                    #render::RenderJson::render_json(#binding, __renderer)
+                }
+            }
+            Fields::Unnamed(_) => quote! {
+                let mut __array = __renderer.array();
+                #(__array.entry(#bindings)?;)*
+                __array.finish()
+            },
+            Fields::Unit if is_enum => {
+                let name = &v.name;
+                quote! { __renderer.write_str(#name) }
+            }
+            Fields::Unit => quote! { __renderer.write_null() },
+        };
+
+        let body = if is_enum && !matches!(v.fields, Fields::Unit) {
+            let name = &v.name;
+            quote! {
+                let mut __object = __renderer.object();
+                __object.field_with(#name, |__renderer| { #fields })?;
+                __object.finish()
+            }
+        } else {
+            fields
+        };
+
+        quote! { #pattern => { #body } }
+    });
+
+    Ok(Some(quote! {
+        impl #impl_generics #render::RenderJson for #archived_name #ty_generics #render_where {
+            fn render_json(
+                &self,
+                __renderer: &mut #render::JsonRenderer<'_>,
+            ) -> ::core::fmt::Result {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    }))
+}
@@ -7,7 +7,10 @@ use syn::{
 
 use crate::{
     attributes::Attributes,
-    util::{is_not_omitted, strip_raw},
+    columnar::serialize_columns,
+    pack_flags::packed_bits,
+    transparent,
+    util::{field_predicate, is_not_omitted, strip_raw},
     with::{make_with_cast, make_with_ty},
 };
 
@@ -35,10 +38,15 @@ fn derive_serialize_impl(
             where_clause.predicates.push(bound.clone());
         }
     }
+    if cfg!(feature = "hooks") {
+        where_clause
+            .predicates
+            .push(parse_quote! { __S: #rkyv_path::ser::Writer });
+    }
 
     let mut impl_input_params = Punctuated::default();
     impl_input_params
-        .push(parse_quote! { __S: #rkyv_path::rancor::Fallible + ?Sized });
+        .push(parse_quote! { __S: #rkyv_path::rancor::Fallible + ?::core::marker::Sized });
     for param in input.generics.params.iter() {
         impl_input_params.push(param.clone());
     }
@@ -59,18 +67,58 @@ fn derive_serialize_impl(
         |value| value.clone(),
     );
 
+    // With the `hooks` feature, serializers are told which type is being
+    // serialized so that they can profile it or limit the depth of values. If
+    // a field fails to serialize, the type is never exited.
+    let (enter_type, exit_type) = if cfg!(feature = "hooks") {
+        (
+            quote! {
+                <__S as #rkyv_path::ser::Writer>::enter_type(
+                    serializer,
+                    ::core::any::type_name::<Self>(),
+                )?;
+            },
+            quote! {
+                <__S as #rkyv_path::ser::Writer>::exit_type(
+                    serializer,
+                );
+            },
+        )
+    } else {
+        (TokenStream::new(), TokenStream::new())
+    };
+
     let serialize_impl = match input.data {
+        _ if attributes.transparent.is_some() => transparent::serialize(
+            attributes,
+            &input,
+            impl_generics,
+            where_clause,
+            enter_type,
+            exit_type,
+        )?,
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
+                // Packed fields are resolved from the original value
+                let mut serialized_fields = Vec::new();
+                for field in fields.named.iter() {
+                    if packed_bits(attributes, field)?.is_none() {
+                        serialized_fields.push(field);
+                    }
+                }
+
                 let mut serialize_where = where_clause.clone();
-                for field in fields.named.iter().filter(is_not_omitted) {
+                for field in
+                    serialized_fields.iter().copied().filter(is_not_omitted)
+                {
                     let ty = with_ty(field)?;
-                    serialize_where
-                        .predicates
-                        .push(parse_quote! { #ty: #rkyv_path::Serialize<__S> });
+                    serialize_where.predicates.push(field_predicate(
+                        &ty,
+                        quote! { #ty: #rkyv_path::Serialize<__S> },
+                    ));
                 }
 
-                let resolver_values = fields.named.iter().map(|f| {
+                let resolver_values = serialized_fields.iter().map(|f| {
                     let name = &f.ident;
                     let field = with_cast(f, parse_quote! { &self.#name }).unwrap();
                     quote! { #name: #rkyv_path::Serialize::<__S>::serialize(#field, serializer)? }
@@ -80,9 +128,12 @@ fn derive_serialize_impl(
                     impl #impl_generics #rkyv_path::Serialize<__S> for #name #ty_generics #serialize_where {
                         #[inline]
                         fn serialize(&self, serializer: &mut __S) -> ::core::result::Result<Self::Resolver, <__S as #rkyv_path::rancor::Fallible>::Error> {
-                            Ok(#resolver {
+                            #enter_type
+                            let result = ::core::result::Result::Ok(#resolver {
                                 #(#resolver_values,)*
-                            })
+                            });
+                            #exit_type
+                            result
                         }
                     }
                 }
@@ -91,9 +142,10 @@ fn derive_serialize_impl(
                 let mut serialize_where = where_clause.clone();
                 for field in fields.unnamed.iter().filter(is_not_omitted) {
                     let ty = with_ty(field)?;
-                    serialize_where
-                        .predicates
-                        .push(parse_quote! { #ty: #rkyv_path::Serialize<__S> });
+                    serialize_where.predicates.push(field_predicate(
+                        &ty,
+                        quote! { #ty: #rkyv_path::Serialize<__S> },
+                    ));
                 }
 
                 let resolver_values = fields.unnamed.iter().enumerate().map(|(i, f)| {
@@ -106,9 +158,12 @@ fn derive_serialize_impl(
                     impl #impl_generics #rkyv_path::Serialize<__S> for #name #ty_generics #serialize_where {
                         #[inline]
                         fn serialize(&self, serializer: &mut __S) -> ::core::result::Result<Self::Resolver, <__S as #rkyv_path::rancor::Fallible>::Error> {
-                            Ok(#resolver(
+                            #enter_type
+                            let result = ::core::result::Result::Ok(#resolver(
                                 #(#resolver_values,)*
-                            ))
+                            ));
+                            #exit_type
+                            result
                         }
                     }
                 }
@@ -118,7 +173,9 @@ fn derive_serialize_impl(
                     impl #impl_generics #rkyv_path::Serialize<__S> for #name #ty_generics #where_clause {
                         #[inline]
                         fn serialize(&self, serializer: &mut __S) -> ::core::result::Result<Self::Resolver, <__S as #rkyv_path::rancor::Fallible>::Error> {
-                            Ok(#resolver)
+                            #enter_type
+                            #exit_type
+                            ::core::result::Result::Ok(#resolver)
                         }
                     }
                 }
@@ -132,9 +189,10 @@ fn derive_serialize_impl(
                         for field in fields.named.iter().filter(is_not_omitted)
                         {
                             let ty = with_ty(field)?;
-                            serialize_where
-                                .predicates
-                                .push(parse_quote! { #ty: #rkyv_path::Serialize<__S> });
+                            serialize_where.predicates.push(field_predicate(
+                                &ty,
+                                quote! { #ty: #rkyv_path::Serialize<__S> },
+                            ));
                         }
                     }
                     Fields::Unnamed(ref fields) => {
@@ -142,9 +200,10 @@ fn derive_serialize_impl(
                             fields.unnamed.iter().filter(is_not_omitted)
                         {
                             let ty = with_ty(field)?;
-                            serialize_where
-                                .predicates
-                                .push(parse_quote! { #ty: #rkyv_path::Serialize<__S> });
+                            serialize_where.predicates.push(field_predicate(
+                                &ty,
+                                quote! { #ty: #rkyv_path::Serialize<__S> },
+                            ));
                         }
                     }
                     Fields::Unit => (),
@@ -198,9 +257,12 @@ fn derive_serialize_impl(
                 impl #impl_generics #rkyv_path::Serialize<__S> for #name #ty_generics #serialize_where {
                     #[inline]
                     fn serialize(&self, serializer: &mut __S) -> ::core::result::Result<<Self as #rkyv_path::Archive>::Resolver, <__S as #rkyv_path::rancor::Fallible>::Error> {
-                        Ok(match self {
+                        #enter_type
+                        let result = ::core::result::Result::Ok(match self {
                             #(#serialize_arms,)*
-                        })
+                        });
+                        #exit_type
+                        result
                     }
                 }
             }
@@ -213,8 +275,11 @@ fn derive_serialize_impl(
         }
     };
 
+    let columns_impl = serialize_columns(attributes, &input)?;
+
     Ok(quote! {
         #[automatically_derived]
         #serialize_impl
+        #columns_impl
     })
 }
@@ -0,0 +1,86 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Ident};
+
+use crate::{
+    attributes::Attributes,
+    util::{field_predicate, is_not_omitted, Variant},
+    with::make_with_ty,
+};
+
+/// Generates the `StableHash` implementation for the archived type of a
+/// `stable_hash` type.
+///
+/// Structs write each of their fields in declaration order. Enums first write
+/// the index of their variant as a `u32`, so that variants with the same fields
+/// hash differently.
+pub fn stable_hash(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let stable_hash = match attributes.stable_hash {
+        Some(ref stable_hash) => stable_hash,
+        None => return Ok(None),
+    };
+
+    let variants = match Variant::all(input, archived_name) {
+        Some(variants) => variants,
+        None => return Ok(None),
+    };
+    let is_enum = matches!(input.data, Data::Enum(_));
+    if is_enum && variants.is_empty() {
+        return Err(Error::new_spanned(
+            stable_hash,
+            "stable_hash is not supported for enums without variants",
+        ));
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+    let stable_hash = quote! { #rkyv_path::stable_hash::StableHash };
+
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let mut stable_hash_where = where_clause.unwrap().clone();
+    for field in variants
+        .iter()
+        .flat_map(|v| v.fields.iter())
+        .filter(is_not_omitted)
+    {
+        let ty = with_ty(field)?;
+        stable_hash_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+        // Higher-ranked so that fields which don't implement `StableHash`
+        // are reported where the impl is used
+        stable_hash_where.predicates.push(field_predicate(
+            &ty,
+            quote! { for<'__a> #rkyv_path::Archived<#ty>: #stable_hash },
+        ));
+    }
+
+    let patterns = variants.iter().map(|v| v.pattern("__self"));
+    let bindings = variants.iter().map(|v| v.bindings("__self"));
+    let tags = (0..variants.len() as u32)
+        .map(|index| is_enum.then(|| quote! { __hasher.write_u32(#index); }));
+
+    Ok(Some(quote! {
+        impl #impl_generics #stable_hash for #archived_name #ty_generics #stable_hash_where {
+            #[inline]
+            fn stable_hash(
+                &self,
+                __hasher: &mut #rkyv_path::stable_hash::StableHasher,
+            ) {
+                match self {
+                    #(
+                        #patterns => {
+                            #tags
+                            #(#stable_hash::stable_hash(#bindings, __hasher);)*
+                        }
+                    )*
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Ident, Index, Member};
+
+use crate::{
+    attributes::Attributes,
+    util::{field_predicate, is_not_omitted, strip_raw},
+    with::make_with_ty,
+};
+
+/// Generates the `LAYOUT` constant and field offsets for the archived type of
+/// a `stable_layout` type.
+///
+/// The offsets are computed in an inherent impl so that they can be read by
+/// `archived_offset_of!` even when the fields of the archived type are private.
+pub fn stable_layout(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    archived_name: &Ident,
+) -> Result<Option<TokenStream>, Error> {
+    let stable_layout = match attributes.stable_layout {
+        Some(ref stable_layout) => stable_layout,
+        None => return Ok(None),
+    };
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                stable_layout,
+                "stable_layout is only supported for structs",
+            ))
+        }
+    };
+
+    let rkyv_path = attributes.rkyv_path();
+    let with_ty = make_with_ty(&rkyv_path);
+
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let mut layout_where = where_clause.unwrap().clone();
+    for field in fields.iter().filter(is_not_omitted) {
+        let ty = with_ty(field)?;
+        layout_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+    }
+
+    let offsets = fields.iter().enumerate().map(|(i, f)| {
+        let (name, member) = match f.ident {
+            Some(ref ident) => (strip_raw(ident), Member::Named(ident.clone())),
+            None => (format!("_{}", i), Member::Unnamed(Index::from(i))),
+        };
+        quote! {
+            #rkyv_path::stable::FieldOffset {
+                name: #name,
+                offset: ::core::mem::offset_of!(Self, #member),
+            }
+        }
+    });
+
+    Ok(Some(quote! {
+        impl #impl_generics #archived_name #ty_generics #layout_where {
+            /// The layout of the archived type.
+            pub const LAYOUT: ::core::alloc::Layout =
+                ::core::alloc::Layout::new::<Self>();
+
+            #[doc(hidden)]
+            pub const __RKYV_FIELD_OFFSETS:
+                &'static [#rkyv_path::stable::FieldOffset] =
+                &[#(#offsets,)*];
+        }
+    }))
+}
@@ -0,0 +1,588 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::Parser, parse_quote, spanned::Spanned, DeriveInput, Error, Field,
+};
+
+use crate::{
+    archive, deserialize, serialize, util::field_predicate, with::make_with_ty,
+};
+
+// Runs a derive on `source` and returns the message of the error it produced
+// along with the source text that the error points at.
+fn derive_error(
+    derive: fn(DeriveInput) -> Result<TokenStream, Error>,
+    source: &str,
+) -> (String, String) {
+    let input = syn::parse_str::<DeriveInput>(source).unwrap();
+    let error = match derive(input) {
+        Ok(_) => panic!("expected derive to fail for:\n{}", source),
+        Err(error) => error,
+    };
+    let text = error.span().source_text().unwrap();
+    (error.to_string(), text)
+}
+
+fn assert_archive_error(source: &str, message: &str, text: &str) {
+    assert_eq!(
+        derive_error(archive::derive, source),
+        (message.into(), text.into())
+    );
+}
+
+#[test]
+fn unrecognized_argument() {
+    assert_archive_error(
+        "#[archive(preserve_ordr)] struct S { a: u32 }",
+        "unrecognized archive argument `preserve_ordr`, did you mean \
+         `preserve_order`?",
+        "preserve_ordr",
+    );
+    assert_archive_error(
+        "#[rkyv(Check_Bytes)] struct S { a: u32 }",
+        "unrecognized archive argument `Check_Bytes`, did you mean \
+         `check_bytes`?",
+        "Check_Bytes",
+    );
+    assert_archive_error(
+        "#[archive(frobnicate)] struct S { a: u32 }",
+        "unrecognized archive argument `frobnicate`",
+        "frobnicate",
+    );
+}
+
+#[test]
+fn unrecognized_argument_hints() {
+    assert_archive_error(
+        "#[archive(repr(C))] struct S { a: u32 }",
+        "unrecognized archive argument `repr`\nuse \
+         `#[archive_attr(repr(...))]` to set the representation of the \
+         archived type",
+        "repr",
+    );
+    assert_archive_error(
+        "#[archive(bound(archive = \"T: Clone\"))] struct S<T> { a: T }",
+        "unrecognized archive argument `bound`\nuse `archive_bounds(...)`, \
+         `serialize_bounds(...)`, or `deserialize_bounds(...)` instead",
+        "bound",
+    );
+    assert_archive_error(
+        "#[archive(derive(Debug))] struct S { a: u32 }",
+        "unrecognized archive argument `derive`\nuse \
+         `#[archive_attr(derive(...))]` to derive traits for the archived \
+         type",
+        "derive",
+    );
+    assert_archive_error(
+        "#[archive(with(Inline))] struct S<'a> { a: &'a u32 }",
+        "unrecognized archive argument `with`\n`with` must be placed on a \
+         field as `#[with(...)]`",
+        "with",
+    );
+}
+
+#[test]
+fn invalid_argument_values() {
+    assert_archive_error(
+        "#[archive(check_bytes = true)] struct S { a: u32 }",
+        "check_bytes argument must be a path",
+        "check_bytes",
+    );
+    assert_archive_error(
+        "#[archive(crate(rkyv))] struct S { a: u32 }",
+        "expected `crate` or `crate = ...`",
+        "crate",
+    );
+    assert_archive_error(
+        "#[archive(compare(PartialEq), compare(PartialOrd))] struct S(u32);",
+        "compare already specified",
+        "PartialOrd",
+    );
+    assert_archive_error(
+        "#[archive(format_stable(sise = 8))] struct S { a: u32 }",
+        "unrecognized format_stable argument `sise`, did you mean `size`?",
+        "sise",
+    );
+    assert_archive_error(
+        "#[archive(format_stable = 8)] struct S { a: u32 }",
+        "expected `format_stable` or `format_stable(...)`",
+        "format_stable",
+    );
+    assert_archive_error(
+        "#[archive(callable(registry = R, functon = f))] struct S { a: u32 }",
+        "unrecognized callable argument `functon`, did you mean `function`?",
+        "functon",
+    );
+    assert_archive_error(
+        "#[archive(callable(function = f))] struct S { a: u32 }",
+        "callable requires a `registry`",
+        "callable",
+    );
+    assert_archive_error(
+        "#[archive(c_api(prefx = \"s\"))] struct S { a: u32 }",
+        "unrecognized c_api argument `prefx`, did you mean `prefix`?",
+        "prefx",
+    );
+    assert_archive_error(
+        "#[archive(c_api(prefix = \"1s\"))] struct S { a: u32 }",
+        "c_api prefix must be a valid C identifier",
+        "\"1s\"",
+    );
+    assert_archive_error(
+        "#[archive(convenience(confg = C))] struct S { a: u32 }",
+        "unrecognized convenience argument `confg`, did you mean `config`?",
+        "confg",
+    );
+    assert_archive_error(
+        "#[archive(convenience = C)] struct S { a: u32 }",
+        "expected `convenience` or `convenience(config = ...)`",
+        "convenience",
+    );
+}
+
+#[test]
+fn unrecognized_compare() {
+    assert_archive_error(
+        "#[archive(compare(ParialEq))] struct S { a: u32 }",
+        "unrecognized compare argument `ParialEq`, did you mean \
+         `PartialEq`?\nsupported compares are PartialEq and PartialOrd",
+        "ParialEq",
+    );
+    assert_archive_error(
+        "#[archive(compare(PartialEq, Ord))] struct S { a: u32 }",
+        "unrecognized compare argument `Ord`\nsupported compares are \
+         PartialEq and PartialOrd",
+        "Ord",
+    );
+}
+
+#[test]
+fn unknown_derive() {
+    assert_archive_error(
+        "#[archive_attr(derive(Debg))] struct S { a: u32 }",
+        "unknown derive `Debg`, did you mean `Debug`?",
+        "Debg",
+    );
+    assert_archive_error(
+        "struct S { #[archive_attr(derive(PartialEQ))] a: u32 }",
+        "unknown derive `PartialEQ`, did you mean `PartialEq`?",
+        "PartialEQ",
+    );
+
+    // Known derives and derives which don't look like a misspelling are
+    // passed through
+    let input = syn::parse_str::<DeriveInput>(
+        "#[archive_attr(derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, \
+         Ord, Hash, serde::Serialize, Zeroable))] struct S { a: u32 }",
+    )
+    .unwrap();
+    assert!(archive::derive(input).is_ok());
+}
+
+#[test]
+fn conflicting_arguments() {
+    assert_archive_error(
+        "#[archive(as = \"Foo\", archived = ArchivedFoo)] struct S { a: u32 }",
+        "`archived` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "ArchivedFoo",
+    );
+    assert_archive_error(
+        "#[archive(as = \"Foo\", check_bytes)] struct S { a: u32 }",
+        "`check_bytes` may not be used with `as = \"Foo\"` because no \
+         archived type is generated\nderive `CheckBytes` on the archived type \
+         instead",
+        "check_bytes",
+    );
+    assert_archive_error(
+        "#[archive(preserve_order, as = \"Foo\")] struct S { a: u32 }",
+        "`preserve_order` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "preserve_order",
+    );
+    assert_archive_error(
+        "#[archive(check_bytes)] #[archive_attr(derive(Debug, \
+         bytecheck::CheckBytes))] struct S { a: u32 }",
+        "`CheckBytes` is already derived by `check_bytes`",
+        "bytecheck::CheckBytes",
+    );
+    assert_archive_error(
+        "#[archive(copy_safe)] enum E { A }",
+        "copy_safe is not supported for enums",
+        "copy_safe",
+    );
+    assert_archive_error(
+        "#[archive(deep_size, as = \"Foo\")] struct S { a: u32 }",
+        "`deep_size` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "deep_size",
+    );
+    assert_archive_error(
+        "#[archive(stable_hash, as = \"Foo\")] struct S { a: u32 }",
+        "`stable_hash` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "stable_hash",
+    );
+    assert_archive_error(
+        "#[archive(stable_hash)] enum E {}",
+        "stable_hash is not supported for enums without variants",
+        "stable_hash",
+    );
+    assert_archive_error(
+        "#[archive(render_json, as = \"Foo\")] struct S { a: u32 }",
+        "`render_json` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "render_json",
+    );
+    assert_archive_error(
+        "#[archive(render_json)] enum E {}",
+        "render_json is not supported for enums without variants",
+        "render_json",
+    );
+    assert_archive_error(
+        "#[archive(derive_std, as = \"Foo\")] struct S { a: u32 }",
+        "`derive_std` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "derive_std",
+    );
+    assert_archive_error(
+        "#[archive(derive_std)] #[archive_attr(derive(Clone, Hash))] struct S \
+         { a: u32 }",
+        "`Hash` is already derived by `derive_std`",
+        "Hash",
+    );
+    assert_archive_error(
+        "#[archive(export_layout, as = \"Foo\")] struct S { a: u32 }",
+        "`export_layout` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "export_layout",
+    );
+    assert_archive_error(
+        "#[archive(export_layout)] struct S<T> { a: T }",
+        "export_layout is not supported for generic types",
+        "export_layout",
+    );
+    assert_archive_error(
+        "#[archive(structural_hash, as = \"Foo\")] struct S { a: u32 }",
+        "`structural_hash` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "structural_hash",
+    );
+    assert_archive_error(
+        "#[archive(export_layout, structural_hash)] enum E<T> { A(T) }",
+        "structural_hash is not supported for generic types",
+        "structural_hash",
+    );
+    assert_archive_error(
+        "#[archive(format_stable, as = \"Foo\")] struct S { a: u32 }",
+        "`format_stable` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "format_stable",
+    );
+    assert_archive_error(
+        "#[archive(stable_layout, as = \"Foo\")] struct S { a: u32 }",
+        "`stable_layout` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "stable_layout",
+    );
+    assert_archive_error(
+        "#[archive(stable_layout)] enum E { A }",
+        "stable_layout is only supported for structs",
+        "stable_layout",
+    );
+    assert_archive_error(
+        "#[archive(format_stable)] struct S<T> { a: T }",
+        "format_stable is not supported for generic types",
+        "format_stable",
+    );
+    assert_archive_error(
+        "#[archive(reflect, as = \"Foo\")] struct S { a: u32 }",
+        "`reflect` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "reflect",
+    );
+    assert_archive_error(
+        "#[archive(reflect)] struct S<T> { a: T }",
+        "reflect is not supported for generic types",
+        "reflect",
+    );
+    assert_archive_error(
+        "#[archive(rearchive, as = \"Foo\")] struct S { a: u32 }",
+        "`rearchive` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "rearchive",
+    );
+    assert_archive_error(
+        "#[archive(type_hash)] struct S<T> { a: T }",
+        "type_hash is not supported for generic types",
+        "type_hash",
+    );
+    assert_archive_error(
+        "#[archive(niche)] enum E { A, B(u32) }",
+        "niche is only supported for enums with only unit variants",
+        "niche",
+    );
+    assert_archive_error(
+        "#[archive(niche, as = \"Foo\")] enum E { A, B }",
+        "`niche` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "niche",
+    );
+    assert_archive_error(
+        "#[archive(callable(registry = R, function = f))] struct S<T> { a: T }",
+        "callable is not supported for generic types",
+        "callable",
+    );
+    assert_archive_error(
+        "#[archive(columnar, as = \"Foo\")] struct S { a: u32 }",
+        "`columnar` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "columnar",
+    );
+    assert_archive_error(
+        "#[archive(columnar)] struct S<T> { a: T }",
+        "columnar is not supported for generic types",
+        "columnar",
+    );
+    assert_archive_error(
+        "#[archive(columnar)] struct S(u32);",
+        "columnar is only supported for structs with named fields",
+        "columnar",
+    );
+    assert_archive_error(
+        "#[archive(columnar)] enum E { A { a: u32 } }",
+        "columnar is only supported for structs with named fields",
+        "columnar",
+    );
+    assert_archive_error(
+        "#[archive(c_api(prefix = \"s\"), as = \"Foo\")] struct S { a: u32 }",
+        "`c_api` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "c_api",
+    );
+    assert_archive_error(
+        "#[archive(c_api(prefix = \"s\"))] struct S(u32);",
+        "c_api is only supported for structs with named fields",
+        "c_api",
+    );
+    assert_archive_error(
+        "#[archive(project, as = \"Foo\")] struct S { a: u32 }",
+        "`project` may not be used with `as = \"Foo\"` because no archived \
+         type is generated",
+        "project",
+    );
+    assert_archive_error(
+        "#[archive(project)] struct S<T> { a: T }",
+        "project is not supported for generic types",
+        "project",
+    );
+    assert_archive_error(
+        "#[archive(project)] struct S(u32);",
+        "project is only supported for structs with named fields",
+        "project",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags, project)] struct S { a: bool }",
+        "`project` may not be used with `pack_flags` on structs",
+        "project",
+    );
+}
+
+#[test]
+fn invalid_field_attributes() {
+    assert_archive_error(
+        "struct S { #[archive(omit_bounds)] a: u32 }",
+        "archive arguments other than `bits = ...` are not supported on \
+         fields\nuse `#[with(...)]`, `#[omit_bounds]`, or \
+         `#[archive_attr(...)]` instead",
+        "#[archive(omit_bounds)]",
+    );
+    assert_archive_error(
+        "enum E { A { #[omit_bounds(recursive)] a: Box<E> } }",
+        "`omit_bounds` does not take any arguments, use `#[omit_bounds]`",
+        "#[omit_bounds(recursive)]",
+    );
+    assert_archive_error(
+        "struct S(#[with] u32);",
+        "expected `#[with(...)]` with at least one wrapper",
+        "#[with]",
+    );
+}
+
+#[test]
+fn errors_from_all_derives() {
+    let source = "#[archive(compare(ParialEq))] struct S { a: u32 }";
+    let expected = derive_error(archive::derive, source);
+    assert_eq!(derive_error(serialize::derive, source), expected);
+    assert_eq!(derive_error(deserialize::derive, source), expected);
+}
+
+#[test]
+fn field_bounds_point_at_fields() {
+    let rkyv_path = parse_quote! { ::rkyv };
+    let with_ty = make_with_ty(&rkyv_path);
+
+    // Bounds on wrapped fields point at the outermost wrapper
+    let field = Field::parse_named
+        .parse_str("#[with(Inline, Boxed)] a: &'a u32")
+        .unwrap();
+    let ty = with_ty(&field).unwrap();
+    assert_eq!(ty.span().source_text().unwrap(), "Inline");
+    let predicate = field_predicate(&ty, quote! { #ty: ::rkyv::Archive });
+    assert_eq!(predicate.span().source_text().unwrap(), "Inline");
+
+    // Bounds on other fields point at the field type
+    let field = Field::parse_named.parse_str("b: Cell<u8>").unwrap();
+    let ty = with_ty(&field).unwrap();
+    let predicate = field_predicate(&ty, quote! { #ty: ::rkyv::Archive });
+    assert_eq!(predicate.span().source_text().unwrap(), "Cell<u8>");
+}
+
+#[test]
+fn duplicate_accessor_names() {
+    assert_archive_error(
+        "enum E { HttpRequest, HTTPRequest(u32) }",
+        "variants `HttpRequest` and `HTTPRequest` have the same accessor \
+         names\nuse `#[archive(no_accessors)]` to disable accessors",
+        "HTTPRequest",
+    );
+}
+
+#[test]
+fn invalid_pack_flags() {
+    assert_archive_error(
+        "struct S { #[archive(bits = 2)] a: E }",
+        "`bits` requires `#[archive(pack_flags)]` on the struct",
+        "2",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags)] struct S { #[archive(bits = 65)] a: E }",
+        "bits must be between 1 and 64",
+        "65",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags)] struct S { #[archive(bits = 2)] #[with(Skip)] \
+         a: E }",
+        "packed fields may not have wrappers",
+        "2",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags, as = \"Foo\")] struct S { a: bool }",
+        "`pack_flags` may not be used with `as = \"Foo\"` because no \
+         archived type is generated",
+        "pack_flags",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags)] struct S<T> { a: bool, b: T }",
+        "pack_flags is not supported for generic types",
+        "pack_flags",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags, derive_std)] struct S { a: bool }",
+        "`derive_std` may not be used with `pack_flags` on structs",
+        "derive_std",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags)] struct S(bool);",
+        "pack_flags is only supported for structs with named fields and \
+         enums with only unit variants",
+        "pack_flags",
+    );
+    assert_archive_error(
+        "#[archive(pack_flags)] enum E { A, B(u32) }",
+        "pack_flags is only supported for structs with named fields and \
+         enums with only unit variants",
+        "pack_flags",
+    );
+}
+
+#[test]
+fn invalid_transparent() {
+    let message = "transparent requires a struct with exactly one field, or a \
+                   `#[repr(transparent)]` struct whose other fields are \
+                   `PhantomData`";
+    assert_archive_error(
+        "#[archive(transparent)] struct S { a: u32, b: u32 }",
+        message,
+        "transparent",
+    );
+    assert_archive_error(
+        "#[archive(transparent)] #[repr(transparent)] struct S(u32, u64);",
+        message,
+        "transparent",
+    );
+    assert_archive_error(
+        "#[archive(transparent)] struct S;",
+        message,
+        "transparent",
+    );
+    assert_archive_error(
+        "#[archive(transparent)] enum E { A(u32) }",
+        "transparent is only supported for structs",
+        "transparent",
+    );
+    assert_archive_error(
+        "#[archive(transparent, archived = ArchivedS)] struct S(u32);",
+        "`archived` may not be used with `transparent` because no archived \
+         type is generated for it",
+        "ArchivedS",
+    );
+    assert_archive_error(
+        "#[archive(transparent, stable_hash)] struct S(u32);",
+        "`stable_hash` may not be used with `transparent` because no archived \
+         type is generated for it",
+        "stable_hash",
+    );
+
+    // Serialize and Deserialize report the same errors
+    let source = "#[archive(transparent)] struct S { a: u32, b: u32 }";
+    let expected = derive_error(archive::derive, source);
+    assert_eq!(derive_error(serialize::derive, source), expected);
+    assert_eq!(derive_error(deserialize::derive, source), expected);
+
+    // Zero-sized fields are allowed next to the wrapped field
+    let input = parse_quote! {
+        #[archive(transparent)]
+        #[repr(transparent)]
+        struct S<T> {
+            a: u32,
+            b: ::core::marker::PhantomData<T>,
+        }
+    };
+    assert!(archive::derive(input).is_ok());
+}
+
+#[test]
+fn reordered_ordering_derives() {
+    assert_archive_error(
+        "#[archive_attr(derive(Eq, Hash, PartialEq))] struct Key { a: u8, b: \
+         u64 }",
+        "`Hash` can't be derived for the archived type because its fields are \
+         reordered\nuse `derive_std` to implement it in declaration order, or \
+         `preserve_order` to lay out the fields in declaration order",
+        "Hash",
+    );
+    assert_archive_error(
+        "#[archive_attr(derive(core::cmp::PartialOrd, PartialEq))] struct Key \
+         { a: u8, b: u64 }",
+        "`PartialOrd` can't be derived for the archived type because its \
+         fields are reordered\nuse `derive_std` to implement it in \
+         declaration order, or `preserve_order` to lay out the fields in \
+         declaration order",
+        "core::cmp::PartialOrd",
+    );
+
+    // Fields which are not reordered, fields in declaration order, and
+    // `derive_std` are all consistent with the original type
+    for source in [
+        "#[archive_attr(derive(Hash, Ord))] struct Key { b: u64, a: u8 }",
+        "#[archive(preserve_order)] #[archive_attr(derive(Hash, Ord))] struct \
+         Key { a: u8, b: u64 }",
+        "#[rkyv(preserve_order)] #[archive_attr(derive(Hash, Ord))] struct \
+         Key { a: u8, b: u64 }",
+        "#[archive(derive_std)] struct Key { a: u8, b: u64 }",
+    ] {
+        let input = syn::parse_str::<DeriveInput>(source).unwrap();
+        assert!(archive::derive(input).is_ok(), "{}", source);
+    }
+}
@@ -0,0 +1,294 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    parse_quote, spanned::Spanned, Data, DeriveInput, Error, Field, Index,
+    Member, Path, Type, WhereClause,
+};
+
+use crate::{
+    attributes::Attributes,
+    repr::Repr,
+    util::{field_predicate, is_not_omitted},
+    with::{make_with_cast, make_with_ty, with_inner},
+};
+
+/// The field that a `transparent` type archives as, and the zero-sized fields
+/// which are recreated when it is deserialized.
+struct Transparent<'a> {
+    field: &'a Field,
+    member: Member,
+    phantoms: Vec<Member>,
+}
+
+fn member(index: usize, field: &Field) -> Member {
+    match field.ident {
+        Some(ref ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index {
+            index: index as u32,
+            span: field.span(),
+        }),
+    }
+}
+
+fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData"),
+        _ => false,
+    }
+}
+
+// Returns whether `ty` is spelled as an associated type, either directly or
+// through the `Archived` alias.
+fn is_projection(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => {
+            path.qself.is_some()
+                || path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "Archived")
+        }
+        _ => true,
+    }
+}
+
+fn transparent_field<'a>(
+    transparent: &Path,
+    input: &'a DeriveInput,
+) -> Result<Transparent<'a>, Error> {
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                transparent,
+                "transparent is only supported for structs",
+            ))
+        }
+    };
+
+    if fields.len() == 1 {
+        let field = fields.iter().next().unwrap();
+        return Ok(Transparent {
+            field,
+            member: member(0, field),
+            phantoms: Vec::new(),
+        });
+    }
+
+    // `#[repr(transparent)]` structs may have any number of zero-sized fields
+    // next to the field they wrap. Only `PhantomData` can be recreated when
+    // deserializing.
+    if matches!(Repr::from_attrs(&input.attrs)?, Repr::Transparent) {
+        let (wrapped, phantoms) = fields
+            .iter()
+            .enumerate()
+            .partition::<Vec<_>, _>(|(_, f)| !is_phantom_data(&f.ty));
+        if let [(i, field)] = wrapped.as_slice() {
+            return Ok(Transparent {
+                field,
+                member: member(*i, field),
+                phantoms: phantoms
+                    .into_iter()
+                    .map(|(i, f)| member(i, f))
+                    .collect(),
+            });
+        }
+    }
+
+    Err(Error::new_spanned(
+        transparent,
+        "transparent requires a struct with exactly one field, or a \
+         `#[repr(transparent)]` struct whose other fields are `PhantomData`",
+    ))
+}
+
+/// Generates the `Archive` implementation for a `transparent` type, along with
+/// `From` conversions between it and the type given with `as = "..."`.
+///
+/// The archived type is the archived type of the wrapped field, or the type
+/// given with `as`. The field is resolved directly into it, so a type given
+/// with `as` which doesn't match the archived field fails to compile.
+pub fn archive(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    where_clause: &WhereClause,
+) -> Result<TokenStream, Error> {
+    let transparent = attributes.transparent.as_ref().unwrap();
+    let Transparent {
+        field,
+        member,
+        phantoms,
+    } = transparent_field(transparent, input)?;
+
+    let rkyv_path = attributes.rkyv_path();
+    let name = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+
+    let ty = make_with_ty(&rkyv_path)(field)?;
+    let cast =
+        make_with_cast(&rkyv_path)(field, parse_quote! { &self.#member })?;
+
+    let mut archive_where = where_clause.clone();
+    if is_not_omitted(&field) {
+        archive_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+    }
+
+    let (archived_type, span) = match attributes.archive_as {
+        Some(ref lit) => (lit.parse::<Type>()?, lit.span()),
+        None => (parse_quote! { #rkyv_path::Archived<#ty> }, field.span()),
+    };
+    let resolve = quote_spanned! { span=>
+        #rkyv_path::Archive::resolve(#cast, pos, resolver, out)
+    };
+
+    // Conversions are only generated when the archived type is named
+    // directly. Coherence can't tell that a projection like `Archived<u64>`
+    // isn't the type itself, so it would overlap with `From<T> for T`. The
+    // bounds are higher-ranked so that they are checked when the conversions
+    // are used instead of when they are declared.
+    let from_impls = (input.generics.params.is_empty()
+        && attributes.archive_as.is_some()
+        && !is_projection(&archived_type))
+    .then(|| {
+        let ty = &field.ty;
+        quote! {
+            impl ::core::convert::From<#name> for #archived_type
+            where
+                for<'__a> #archived_type: ::core::convert::From<#ty>,
+            {
+                #[inline]
+                fn from(value: #name) -> Self {
+                    ::core::convert::From::from(value.#member)
+                }
+            }
+
+            impl ::core::convert::From<#archived_type> for #name
+            where
+                for<'__a> #ty: ::core::convert::From<#archived_type>,
+            {
+                #[inline]
+                fn from(value: #archived_type) -> Self {
+                    #name {
+                        #member: ::core::convert::From::from(value),
+                        #(#phantoms: ::core::marker::PhantomData,)*
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #rkyv_path::Archive for #name #ty_generics #archive_where {
+            type Archived = #archived_type;
+            type Resolver = #rkyv_path::Resolver<#ty>;
+
+            #[inline]
+            unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+                #resolve
+            }
+        }
+
+        #from_impls
+    })
+}
+
+/// Generates the `Serialize` implementation for a `transparent` type, which
+/// serializes the wrapped field.
+pub fn serialize(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    impl_generics: impl ToTokens,
+    where_clause: &WhereClause,
+    enter_type: TokenStream,
+    exit_type: TokenStream,
+) -> Result<TokenStream, Error> {
+    let transparent = attributes.transparent.as_ref().unwrap();
+    let Transparent { field, member, .. } =
+        transparent_field(transparent, input)?;
+
+    let rkyv_path = attributes.rkyv_path();
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let ty = make_with_ty(&rkyv_path)(field)?;
+    let cast =
+        make_with_cast(&rkyv_path)(field, parse_quote! { &self.#member })?;
+
+    let mut serialize_where = where_clause.clone();
+    if is_not_omitted(&field) {
+        serialize_where.predicates.push(field_predicate(
+            &ty,
+            quote! { #ty: #rkyv_path::Serialize<__S> },
+        ));
+    }
+
+    Ok(quote! {
+        impl #impl_generics #rkyv_path::Serialize<__S> for #name #ty_generics #serialize_where {
+            #[inline]
+            fn serialize(&self, serializer: &mut __S) -> ::core::result::Result<Self::Resolver, <__S as #rkyv_path::rancor::Fallible>::Error> {
+                #enter_type
+                let resolver = #rkyv_path::Serialize::<__S>::serialize(#cast, serializer)?;
+                #exit_type
+                ::core::result::Result::Ok(resolver)
+            }
+        }
+    })
+}
+
+/// Generates the `Deserialize` implementation for a `transparent` type, which
+/// deserializes the wrapped field and recreates any `PhantomData` fields.
+pub fn deserialize(
+    attributes: &Attributes,
+    input: &DeriveInput,
+    impl_generics: impl ToTokens,
+    where_clause: &WhereClause,
+) -> Result<TokenStream, Error> {
+    let transparent = attributes.transparent.as_ref().unwrap();
+    let Transparent {
+        field,
+        member,
+        phantoms,
+    } = transparent_field(transparent, input)?;
+
+    let rkyv_path = attributes.rkyv_path();
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let ty = make_with_ty(&rkyv_path)(field)?;
+    let value = with_inner(
+        field,
+        parse_quote! {
+            #rkyv_path::Deserialize::<#ty, __D>::deserialize(self, deserializer)?
+        },
+    )?;
+
+    let mut deserialize_where = where_clause.clone();
+    if is_not_omitted(&field) {
+        deserialize_where
+            .predicates
+            .push(field_predicate(&ty, quote! { #ty: #rkyv_path::Archive }));
+        deserialize_where.predicates.push(field_predicate(
+            &ty,
+            quote! { #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D> },
+        ));
+    }
+
+    Ok(quote! {
+        impl #impl_generics #rkyv_path::Deserialize<#name #ty_generics, __D> for #rkyv_path::Archived<#name #ty_generics> #deserialize_where {
+            #[inline]
+            fn deserialize(&self, deserializer: &mut __D) -> ::core::result::Result<#name #ty_generics, <__D as #rkyv_path::rancor::Fallible>::Error> {
+                ::core::result::Result::Ok(#name {
+                    #member: #value,
+                    #(#phantoms: ::core::marker::PhantomData,)*
+                })
+            }
+        }
+    })
+}
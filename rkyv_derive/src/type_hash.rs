@@ -0,0 +1,75 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Error, Fields};
+
+use crate::{attributes::Attributes, util::strip_raw};
+
+/// Generates the `TypeHash` implementation for a `type_hash` type.
+///
+/// The hash combines a description of the type as written with the size and
+/// alignment of its archived type.
+pub fn type_hash(
+    attributes: &Attributes,
+    input: &DeriveInput,
+) -> Result<Option<TokenStream>, Error> {
+    let type_hash = match attributes.type_hash {
+        Some(ref type_hash) => type_hash,
+        None => return Ok(None),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            type_hash,
+            "type_hash is not supported for generic types",
+        ));
+    }
+
+    let mut description = strip_raw(&input.ident);
+    match input.data {
+        Data::Struct(ref data) => {
+            describe_fields(&mut description, &data.fields)
+        }
+        Data::Enum(ref data) => {
+            description.push('{');
+            for v in data.variants.iter() {
+                description.push_str(&strip_raw(&v.ident));
+                describe_fields(&mut description, &v.fields);
+                description.push(',');
+            }
+            description.push('}');
+        }
+        Data::Union(_) => return Ok(None),
+    }
+
+    let rkyv_path = attributes.rkyv_path();
+    let name = &input.ident;
+
+    Ok(Some(quote! {
+        impl #rkyv_path::envelope::TypeHash for #name {
+            const TYPE_HASH: u64 = #rkyv_path::envelope::hash_type(
+                #description,
+                &[
+                    ::core::mem::size_of::<#rkyv_path::Archived<Self>>() as u64,
+                    ::core::mem::align_of::<#rkyv_path::Archived<Self>>() as u64,
+                ],
+            );
+        }
+    }))
+}
+
+fn describe_fields(description: &mut String, fields: &Fields) {
+    let (open, close) = match fields {
+        Fields::Named(_) => ('{', '}'),
+        Fields::Unnamed(_) => ('(', ')'),
+        Fields::Unit => return,
+    };
+    description.push(open);
+    for f in fields.iter() {
+        if let Some(ref ident) = f.ident {
+            description.push_str(&strip_raw(ident));
+            description.push(':');
+        }
+        description.push_str(&f.ty.to_token_stream().to_string());
+        description.push(',');
+    }
+    description.push(close);
+}
@@ -1,5 +1,11 @@
-use proc_macro2::Ident;
-use syn::{Field, Meta};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, spanned::Spanned, Data, DeriveInput, Error, Expr, ExprLit,
+    Field, Fields, Lit, Meta, Type, WherePredicate,
+};
+
+use crate::attributes::Attributes;
 
 pub fn strip_raw(ident: &Ident) -> String {
     let as_string = ident.to_string();
@@ -18,3 +24,230 @@ pub fn is_not_omitted(f: &&Field) -> bool {
         }
     })
 }
+
+/// Returns the tokens of `tokens` with all of their spans set to `span`.
+pub fn respan<T: ToTokens>(tokens: &T, span: Span) -> TokenStream {
+    tokens
+        .to_token_stream()
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+/// Returns a where predicate for a field of type `ty`.
+///
+/// Errors for unsatisfied predicates are reported at the span of the whole
+/// predicate, so it is given the span of the field type (or its outermost
+/// wrapper) instead of the derive.
+pub fn field_predicate(ty: &Type, predicate: TokenStream) -> WherePredicate {
+    let predicate = respan(&predicate, ty.span());
+    parse_quote! { #predicate }
+}
+
+pub fn has_with(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| {
+        if let Meta::List(list) = &attr.meta {
+            list.path.is_ident("with")
+        } else {
+            false
+        }
+    })
+}
+
+// Returns the alignment of the archived version of `ty` if it can be
+// determined from its name alone. Primitives are assumed to archive with their
+// native alignment, which is the largest alignment they can have under any
+// combination of endianness and alignment features.
+fn known_align(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let ident = path.path.get_ident()?;
+            let align = match ident.to_string().as_str() {
+                "u128" | "i128" => 16,
+                "u64" | "i64" | "f64" => 8,
+                "u32" | "i32" | "f32" | "char" => 4,
+                "u16" | "i16" => 2,
+                "u8" | "i8" | "bool" => 1,
+                _ => return None,
+            };
+            Some(align)
+        }
+        Type::Array(array) => match array.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(ref len),
+                ..
+            }) if len.base10_digits() == "0" => Some(1),
+            _ => known_align(&array.elem),
+        },
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Some(1),
+        Type::Paren(paren) => known_align(&paren.elem),
+        Type::Group(group) => known_align(&group.elem),
+        _ => None,
+    }
+}
+
+/// Returns the fields of a struct in the order they are laid out in its
+/// archived type.
+///
+/// Unless `preserve_order` is set, fields are sorted by decreasing alignment.
+/// Fields with an alignment that can't be determined from their type name
+/// (including any field with a wrapper) sort before all others. Ties are
+/// broken by declaration order, so the resulting order depends only on the
+/// field types as written and is the same across all compilations.
+pub fn layout_order<'a>(
+    fields: impl Iterator<Item = &'a Field>,
+    preserve_order: bool,
+) -> Vec<&'a Field> {
+    let mut fields = fields.collect::<Vec<_>>();
+    if !preserve_order {
+        // `sort_by_key` is stable, which gives the declaration order tiebreak
+        fields.sort_by_key(|f| {
+            let align = if has_with(f) {
+                None
+            } else {
+                known_align(&f.ty)
+            };
+            core::cmp::Reverse(align.unwrap_or(usize::MAX))
+        });
+    }
+    fields
+}
+
+/// Returns an error if the fields of a struct were reordered by
+/// [`layout_order`] and the archived type derives a trait which visits its
+/// fields in order.
+///
+/// Derived `PartialOrd`, `Ord`, and `Hash` implementations on the archived type
+/// would compare and hash its fields in layout order, so they would disagree
+/// with the same derives on the original type. Archived hash maps and B-tree
+/// maps with such keys would then fail validation and miss lookups.
+pub fn check_layout_derives<'a>(
+    attributes: &Attributes,
+    fields: impl Iterator<Item = &'a Field>,
+    layout_fields: &[&Field],
+) -> Result<(), Error> {
+    let reordered = fields
+        .zip(layout_fields.iter())
+        .any(|(field, layout_field)| !core::ptr::eq(field, *layout_field));
+    if !reordered {
+        return Ok(());
+    }
+
+    match attributes.find_derive(&["PartialOrd", "Ord", "Hash"]) {
+        Some(path) => Err(Error::new_spanned(
+            &path,
+            format!(
+                "`{}` can't be derived for the archived type because its \
+                 fields are reordered\nuse `derive_std` to implement it in \
+                 declaration order, or `preserve_order` to lay out the fields \
+                 in declaration order",
+                path.segments.last().unwrap().ident,
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+// Returns the number of single-character edits needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Returns the candidate which `name` is most likely a misspelling of.
+///
+/// Returns `None` if `name` is one of the candidates. Otherwise, candidates are
+/// compared ignoring case and are only considered if they are within one edit
+/// per three characters of `name`.
+pub fn suggest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    if candidates.contains(&name) {
+        return None;
+    }
+
+    let threshold = (name.chars().count() / 3).max(1);
+    let name_lower = name.to_lowercase();
+    candidates
+        .iter()
+        .map(|c| (edit_distance(&name_lower, &c.to_lowercase()), *c))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+/// The archived struct or one of the variants of the archived enum, for
+/// generating trait implementations which match on archived values.
+pub struct Variant<'a> {
+    /// The path to match the variant with (`Self` or `Self::Variant`).
+    pub path: TokenStream,
+    /// The name of the archived struct or variant.
+    pub name: String,
+    pub fields: &'a Fields,
+}
+
+impl Variant<'_> {
+    /// Returns the variants of the archived type of `input`, or `None` if it
+    /// is a union.
+    pub fn all<'a>(
+        input: &'a DeriveInput,
+        archived_name: &Ident,
+    ) -> Option<Vec<Variant<'a>>> {
+        match input.data {
+            Data::Struct(ref data) => Some(vec![Variant {
+                path: quote! { Self },
+                name: strip_raw(archived_name),
+                fields: &data.fields,
+            }]),
+            Data::Enum(ref data) => Some(
+                data.variants
+                    .iter()
+                    .map(|v| {
+                        let ident = &v.ident;
+                        Variant {
+                            path: quote! { Self::#ident },
+                            name: strip_raw(ident),
+                            fields: &v.fields,
+                        }
+                    })
+                    .collect(),
+            ),
+            Data::Union(_) => None,
+        }
+    }
+
+    /// Returns the names of the bindings for the fields of the variant.
+    pub fn bindings(&self, prefix: &str) -> Vec<Ident> {
+        (0..self.fields.len())
+            .map(|i| {
+                Ident::new(&format!("{}_{}", prefix, i), Span::call_site())
+            })
+            .collect()
+    }
+
+    /// Returns a pattern which binds the fields of the variant to the
+    /// bindings with the given prefix.
+    pub fn pattern(&self, prefix: &str) -> TokenStream {
+        let path = &self.path;
+        let bindings = self.bindings(prefix);
+        match self.fields {
+            Fields::Named(ref fields) => {
+                let names = fields.named.iter().map(|f| &f.ident);
+                quote! { #path { #(#names: #bindings,)* } }
+            }
+            Fields::Unnamed(_) => quote! { #path(#(#bindings,)*) },
+            Fields::Unit => quote! { #path },
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use syn::{
-    parse_quote, punctuated::Punctuated, token::Comma, Error, Expr, Field,
-    Meta, Path, Type,
+    parse_quote, parse_quote_spanned, punctuated::Punctuated, spanned::Spanned,
+    token::Comma, Error, Expr, Field, Meta, Path, Type,
 };
 
+use crate::util::respan;
+
 #[inline]
 pub fn with<B, F: FnMut(B, &Type) -> B>(
     field: &Field,
@@ -37,7 +39,15 @@ pub fn make_with_ty(
         with(
             field,
             field.ty.clone(),
-            |ty, wrapper| parse_quote! { #rkyv_path::with::With<#ty, #wrapper> },
+            // Errors from wrappers which can't be used with the field type
+            // are reported at the wrapper
+            |ty, wrapper| {
+                let span = wrapper.span();
+                let rkyv_path = respan(rkyv_path, span);
+                parse_quote_spanned! { span=>
+                    #rkyv_path::with::With<#ty, #wrapper>
+                }
+            },
         )
     }
 }
@@ -47,11 +57,13 @@ pub fn make_with_cast(
     rkyv_path: &Path,
 ) -> impl '_ + Fn(&Field, Expr) -> Result<Expr, Error> {
     move |field, expr| {
-        with(
-            field,
-            expr,
-            |expr, wrapper| parse_quote! { #rkyv_path::with::With::<_, #wrapper>::cast(#expr) },
-        )
+        with(field, expr, |expr, wrapper| {
+            let span = wrapper.span();
+            let rkyv_path = respan(rkyv_path, span);
+            parse_quote_spanned! { span=>
+                #rkyv_path::with::With::<_, #wrapper>::cast(#expr)
+            }
+        })
     }
 }
 
@@ -7,16 +7,16 @@
 //!
 //! ## Features
 //!
-//! - `bytecheck`: Enables validation support through `bytecheck`.
+//! - `bytecheck`: Enables validation support through `bytecheck`. See the
+//!   [`validation`] module for details.
 
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(missing_docs)]
 #![deny(rustdoc::missing_crate_level_docs)]
 
 mod lazy_static;
-// TODO: re-enable
-// #[cfg(feature = "bytecheck")]
-// mod bytecheck;
+#[cfg(feature = "bytecheck")]
+pub mod validation;
 
 use core::{alloc::Layout, hash, marker::PhantomData};
 
@@ -39,6 +39,11 @@ pub type ImplId = FixedUsize;
 /// Instead of an associated error type, `DynSerializer` returns the `E` type.
 /// If you have a serializer that already implements `Serializer`, then it will
 /// automatically implement `DynSerializer`.
+///
+/// Shared pointers serialized through a `DynSerializer` are tracked by the
+/// sharing strategy of the underlying serializer, so cycles of shared pointers
+/// fail with a [`CyclicSharedPointer`](rkyv::ser::sharing::CyclicSharedPointer)
+/// error when that strategy detects them.
 pub trait DynSerializer<E>: Writer<E> + Allocator<E> + Sharing<E> {}
 
 impl<E> Fallible for dyn DynSerializer<E> + '_ {
@@ -234,15 +239,20 @@ pub trait DeserializeDyn<T: Pointee + ?Sized, E> {
 }
 
 /// The archived version of `DynMetadata`.
+///
+/// In addition to the impl ID, this stores the [type hash](type_hash) of the
+/// implementing type so that validation can detect impl IDs which refer to a
+/// different type than the one that was serialized.
 #[derive(Portable)]
 #[cfg_attr(
     feature = "bytecheck",
     derive(::bytecheck::CheckBytes),
     check_bytes(verify)
 )]
-#[repr(transparent)]
+#[repr(C)]
 pub struct ArchivedDynMetadata<T: ?Sized> {
     impl_id: Archived<ImplId>,
+    type_hash: Archived<u64>,
     phantom: PhantomData<T>,
 }
 
@@ -252,23 +262,31 @@ unsafe impl<T: ?Sized> Sync for ArchivedDynMetadata<T> {}
 impl<T: ?Sized> Unpin for ArchivedDynMetadata<T> {}
 
 impl<T: ?Sized> ArchivedDynMetadata<T> {
-    /// Creates a new `ArchivedDynMetadata` for the given type.
-    ///
-    /// # Safety
-    ///
-    /// `out` must point to a valid location for an `ArchivedDynMetadata<T>`.
-    pub fn new(impl_id: ImplId) -> Self {
+    /// Creates a new `ArchivedDynMetadata` from an impl ID and the type hash of
+    /// the implementing type.
+    pub fn new(impl_id: ImplId, type_hash: u64) -> Self {
         Self {
             impl_id: Archived::<ImplId>::from_native(impl_id),
+            type_hash: Archived::<u64>::from_native(type_hash),
             phantom: PhantomData,
         }
     }
 
+    /// Creates a new `ArchivedDynMetadata` for the given registered impl.
+    pub fn for_impl<U: RegisteredImpl<T> + ?Sized>() -> Self {
+        Self::new(U::IMPL_ID, U::TYPE_HASH)
+    }
+
     /// Returns the impl ID of the associated with this `ArchivedDynMetadata`.
     pub fn impl_id(&self) -> ImplId {
         self.impl_id.to_native()
     }
 
+    /// Returns the type hash of the implementing type.
+    pub fn type_hash(&self) -> u64 {
+        self.type_hash.to_native()
+    }
+
     /// Returns the pointer metadata for the trait object this metadata refers
     /// to.
     pub fn lookup_metadata(&self) -> DynMetadata<T> {
@@ -293,13 +311,14 @@ impl<T: ?Sized> hash::Hash for ArchivedDynMetadata<T> {
     #[inline]
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         self.impl_id.hash(state);
+        self.type_hash.hash(state);
     }
 }
 
 impl<T: ?Sized> PartialEq for ArchivedDynMetadata<T> {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.impl_id.eq(&other.impl_id)
+        self.impl_id.eq(&other.impl_id) && self.type_hash.eq(&other.type_hash)
     }
 }
 
@@ -315,20 +334,71 @@ impl<T: ?Sized> PartialOrd for ArchivedDynMetadata<T> {
 impl<T: ?Sized> Ord for ArchivedDynMetadata<T> {
     #[inline]
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.impl_id.cmp(&other.impl_id)
+        self.impl_id
+            .cmp(&other.impl_id)
+            .then_with(|| self.type_hash.cmp(&other.type_hash))
     }
 }
 
+/// The salt used to compute type hashes when none is specified.
+pub const DEFAULT_SALT: u64 = 0;
+
+// Increment this whenever the type hash algorithm changes.
+const TYPE_HASH_VERSION: u8 = 1;
+
+/// Computes the type hash of an implementing type.
+///
+/// The type hash is stored alongside the impl ID in archived trait object
+/// metadata, and is checked against the registered trait impl during
+/// validation. It combines the salt, the name of the type, and the size and
+/// alignment of the type.
+///
+/// Because type names are often easy to guess, choosing a unique salt when
+/// [registering trait impls](register_trait_impls) makes it more difficult to
+/// forge the metadata for a different registered trait impl.
+pub const fn type_hash(
+    salt: u64,
+    type_name: &str,
+    size: usize,
+    align: usize,
+) -> u64 {
+    // 64-bit FNV-1a
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    const fn write(mut hash: u64, bytes: &[u8]) -> u64 {
+        let mut i = 0;
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(PRIME);
+            i += 1;
+        }
+        hash
+    }
+
+    let mut hash = OFFSET_BASIS;
+    hash = write(hash, &[TYPE_HASH_VERSION]);
+    hash = write(hash, &salt.to_le_bytes());
+    hash = write(hash, type_name.as_bytes());
+    hash = write(hash, &(size as u64).to_le_bytes());
+    hash = write(hash, &(align as u64).to_le_bytes());
+    hash
+}
+
 /// The trait object metadata for a trait implementation.
 #[derive(Clone, Copy, Debug)]
 pub struct TraitImpl {
     // The type of this `DynMetadata` is erased. Whatever uses it will
     // transmute it to the correct `DynMetadata<T>`.
     metadata: DynMetadata<()>,
+    type_hash: u64,
+    #[cfg(feature = "bytecheck")]
+    check_bytes: Option<validation::CheckBytesFn>,
 }
 
 impl TraitImpl {
-    /// Creates a new trait impl from a trait object pointer.
+    /// Creates a new trait impl from a trait object pointer and the type hash
+    /// of the implementing type.
     ///
     /// # Safety
     ///
@@ -337,17 +407,25 @@ impl TraitImpl {
         T: Pointee<Metadata = DynMetadata<T>> + ?Sized,
     >(
         pointer: *const T,
+        type_hash: u64,
     ) -> Self {
-        Self::from_metadata(ptr_meta::metadata(pointer))
+        Self::from_metadata(ptr_meta::metadata(pointer), type_hash)
     }
 
-    /// Creates a new trait impl from its trait object metadata.
-    pub fn from_metadata<T: ?Sized>(metadata: DynMetadata<T>) -> Self {
+    /// Creates a new trait impl from its trait object metadata and the type
+    /// hash of the implementing type.
+    pub fn from_metadata<T: ?Sized>(
+        metadata: DynMetadata<T>,
+        type_hash: u64,
+    ) -> Self {
         Self {
             // SAFETY: All `DynMetadata<T>` have the same layout and validity.
             // They all contain a single erased `&'static VTable` reference and
             // a `PhantomData<T>`.
             metadata: unsafe { core::mem::transmute(metadata) },
+            type_hash,
+            #[cfg(feature = "bytecheck")]
+            check_bytes: None,
         }
     }
 
@@ -360,15 +438,33 @@ impl TraitImpl {
     pub unsafe fn downcast_metadata<T: ?Sized>(&self) -> DynMetadata<T> {
         unsafe { core::mem::transmute(self.metadata) }
     }
+
+    /// Returns the layout of the implementing type.
+    pub fn layout(&self) -> Layout {
+        self.metadata.layout()
+    }
+
+    /// Returns the type hash of the implementing type.
+    pub fn type_hash(&self) -> u64 {
+        self.type_hash
+    }
 }
 
 /// Creates a new [`TraitImpl`] from the given type and dyn trait.
 ///
+/// The type hash of the trait impl is computed with [`DEFAULT_SALT`] unless a
+/// salt is provided with `salt = ...`, or it can be given directly with
+/// `type_hash = ...`. If the `bytecheck` feature is enabled and the type
+/// implements `CheckBytes`, the trait impl will be able to validate archived
+/// values.
+///
 /// See [`register_trait_impls`] for a macro that registers these trait impls
 /// globally.
 ///
 /// # Example
 /// ```
+/// use rkyv_dyn::trait_impl;
+///
 /// struct MyType;
 ///
 /// trait MyTrait {}
@@ -376,17 +472,63 @@ impl TraitImpl {
 /// impl MyTrait for MyType {}
 ///
 /// let trait_impl = trait_impl!(MyType as dyn MyTrait);
+/// let salted = trait_impl!(MyType as dyn MyTrait, salt = 0x1234);
+/// assert_ne!(trait_impl.type_hash(), salted.type_hash());
 /// ```
 #[macro_export]
 macro_rules! trait_impl {
     ($type:ty as $trait:ty) => {
-        // SAFETY: The given pointer is guaranteed to have valid metadata
-        // because we just made them.
-        unsafe {
-            $crate::TraitImpl::from_pointer(
-                ::core::ptr::null::<$type>() as *const $trait
+        $crate::trait_impl!($type as $trait, salt = $crate::DEFAULT_SALT)
+    };
+    ($type:ty as $trait:ty, salt = $salt:expr) => {
+        $crate::trait_impl!(
+            $type as $trait,
+            type_hash = $crate::type_hash(
+                $salt,
+                ::core::stringify!($type),
+                ::core::mem::size_of::<$type>(),
+                ::core::mem::align_of::<$type>(),
             )
-        }
+        )
+    };
+    ($type:ty as $trait:ty, type_hash = $type_hash:expr) => {
+        $crate::__with_check_bytes!(
+            $type,
+            // SAFETY: The given pointer is guaranteed to have valid metadata
+            // because we just made them.
+            unsafe {
+                $crate::TraitImpl::from_pointer(
+                    ::core::ptr::null::<$type>() as *const $trait,
+                    $type_hash,
+                )
+            }
+        )
+    };
+}
+
+#[cfg(feature = "bytecheck")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __with_check_bytes {
+    ($type:ty, $trait_impl:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::validation::{
+            CheckBytesFallback as _, CheckBytesSupported as _,
+        };
+
+        $trait_impl.with_check_bytes(
+            (&&$crate::validation::CheckBytesFor::<$type>::new())
+                .check_bytes_fn(),
+        )
+    }};
+}
+
+#[cfg(not(feature = "bytecheck"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __with_check_bytes {
+    ($type:ty, $trait_impl:expr) => {
+        $trait_impl
     };
 }
 
@@ -399,15 +541,31 @@ pub static TRAIT_IMPLS: LazyStatic<&'static [TraitImpl]> = LazyStatic::new();
 /// functions:
 ///
 /// 1. Generating `impl RegisteredImpl<$trait> for $type` definitions with valid
-///    impl IDs.
+///    impl IDs and type hashes.
 /// 2. Creating and initializing a static array of [`TraitImpl`]s, one for each
 ///    trait impl argument.
 /// 3. Initializing [`TRAIT_IMPLS`] with a reference to the array of
 ///    [`TraitImpl`]s.
+///
+/// A salt for the [type hashes](type_hash) of the trait impls may be provided
+/// with `salt = ...;` before the list of trait impls. Archives must be
+/// serialized and validated with the same salt.
+///
+/// # Example
+///
+/// ```ignore
+/// register_trait_impls! {
+///     salt = 0x5eed_5a17_c0ff_ee00;
+///     Archived<Foo> as dyn DeserializeMyTrait,
+///     Archived<Bar> as dyn DeserializeMyTrait = 10,
+/// }
+/// ```
 #[macro_export]
 macro_rules! register_trait_impls {
-    ($($type:ty as $trait:ty $(= $id:expr)?),* $(,)?) => {
+    (salt = $salt:expr; $($type:ty as $trait:ty $(= $id:expr)?),* $(,)?) => {
         let _: () = {
+            const REGISTERED_IMPL_SALT: u64 = $salt;
+
             $crate::register_trait_impls!(@register $($type as $trait $(= $id)?,)*);
             const TRAIT_IMPL_COUNT: usize = 0
                 $(+ { let _ = ::core::marker::PhantomData::<$type>; 1 })*;
@@ -415,12 +573,21 @@ macro_rules! register_trait_impls {
                 $crate::LazyStatic::new();
             let trait_impls = TRAIT_IMPLS.init([
                 $(
-                    $crate::trait_impl!($type as $trait),
+                    $crate::trait_impl!(
+                        $type as $trait,
+                        type_hash = <$type as $crate::RegisteredImpl<$trait>>::TYPE_HASH
+                    ),
                 )*
             ]).unwrap();
             $crate::TRAIT_IMPLS.init(trait_impls).unwrap();
         };
     };
+    ($($type:ty as $trait:ty $(= $id:expr)?),* $(,)?) => {
+        $crate::register_trait_impls!(
+            salt = $crate::DEFAULT_SALT;
+            $($type as $trait $(= $id)?),*
+        );
+    };
     (@register $first_type:ty as $first_trait:ty $(= $first_id:expr)?, $($rest_type:ty as $rest_trait:ty $(= $rest_id:expr)?,)*) => {
         struct ImplIds;
 
@@ -428,6 +595,7 @@ macro_rules! register_trait_impls {
 
         unsafe impl $crate::RegisteredImpl<$first_trait> for $first_type {
             const IMPL_ID: $crate::ImplId = $crate::register_trait_impls!(@choose_id 0, $($first_id)?);
+            const TYPE_HASH: u64 = $crate::register_trait_impls!(@type_hash $first_type);
         }
         impl Registered<{ <$first_type as $crate::RegisteredImpl<$first_trait>>::IMPL_ID }> for ImplIds {}
 
@@ -437,6 +605,7 @@ macro_rules! register_trait_impls {
     (@register_rest $prev_type:ty as $prev_trait:ty, $type:ty as $trait:ty $(= $id:expr)?, $($rest_type:ty as $rest_trait:ty $(= $rest_id:expr)?,)*) => {
         unsafe impl $crate::RegisteredImpl<$trait> for $type {
             const IMPL_ID: $crate::ImplId = $crate::register_trait_impls!(@choose_id <$prev_type as $crate::RegisteredImpl<$prev_trait>>::IMPL_ID + 1, $($id)?);
+            const TYPE_HASH: u64 = $crate::register_trait_impls!(@type_hash $type);
         }
         impl Registered<{ <$type as $crate::RegisteredImpl<$trait>>::IMPL_ID }> for ImplIds {}
 
@@ -444,14 +613,26 @@ macro_rules! register_trait_impls {
     };
     (@choose_id $default:expr, $explicit:expr) => { $explicit };
     (@choose_id $default:expr,) => { $default };
+    (@type_hash $type:ty) => {
+        $crate::type_hash(
+            REGISTERED_IMPL_SALT,
+            ::core::stringify!($type),
+            ::core::mem::size_of::<$type>(),
+            ::core::mem::align_of::<$type>(),
+        )
+    };
 }
 
 /// A trait impl that has a globally-unique ID.
 ///
 /// # Safety
 ///
-/// `IMPL_ID` must be globally unique.
+/// - `IMPL_ID` must be globally unique.
+/// - `TYPE_HASH` must be the type hash of the trait impl registered with
+///   `IMPL_ID`.
 pub unsafe trait RegisteredImpl<T: ?Sized> {
     /// The ID of this trait impl.
     const IMPL_ID: ImplId;
+    /// The [type hash](type_hash) of the implementing type.
+    const TYPE_HASH: u64;
 }
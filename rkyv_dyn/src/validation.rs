@@ -0,0 +1,367 @@
+//! Validation implementations and helper types.
+//!
+//! Archived trait objects are validated in two steps:
+//!
+//! 1. When the [`ArchivedDynMetadata`] of a trait object is checked, its impl
+//!    ID is looked up in the registry and the archived [type hash](type_hash)
+//!    is compared against the type hash of the registered trait impl.
+//! 2. When the trait object itself is checked with [`check_dyn_value`], the
+//!    registered layout of the implementing type is compared against the
+//!    layout of the trait object, the value is bounds checked, and then the
+//!    `CheckBytes` implementation of the implementing type is run on it.
+//!
+//! This prevents an archive from pairing a payload with the impl ID of a
+//! different registered type and having it validated as that type.
+
+use core::{alloc::Layout, any::TypeId, fmt, marker::PhantomData, ops::Range};
+
+use bytecheck::{CheckBytes, Verify};
+use ptr_meta::{DynMetadata, Pointee};
+use rancor::{fail, BoxedError, Error, Fallible, ResultExt as _, Strategy};
+use rkyv::validation::{ArchiveContext, SharedContext};
+
+use crate::{ArchivedDynMetadata, ImplId, TraitImpl, TRAIT_IMPLS};
+
+/// Errors that can occur when checking archived trait objects.
+#[derive(Debug)]
+pub enum DynCheckError {
+    /// No trait impls have been registered.
+    Unregistered,
+    /// The impl ID of an archived trait object was not registered.
+    UnknownImplId {
+        /// The impl ID of the trait object
+        impl_id: ImplId,
+    },
+    /// The type hash of an archived trait object did not match the type hash
+    /// of the registered trait impl.
+    TypeHashMismatch {
+        /// The impl ID of the trait object
+        impl_id: ImplId,
+        /// The type hash of the registered trait impl
+        expected: u64,
+        /// The type hash of the trait object
+        found: u64,
+    },
+    /// The pointer metadata of a trait object was not registered.
+    UnknownMetadata,
+    /// The layout of a trait object did not match the layout of the type that
+    /// was registered to check it.
+    LayoutMismatch {
+        /// The layout of the type registered to check the trait object
+        expected: Layout,
+        /// The layout of the trait object
+        found: Layout,
+    },
+    /// The registered trait impl for a trait object does not support
+    /// validation.
+    Unchecked,
+}
+
+impl fmt::Display for DynCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynCheckError::Unregistered => {
+                write!(f, "no trait impls have been registered")
+            }
+            DynCheckError::UnknownImplId { impl_id } => {
+                write!(f, "unknown impl id: {} not registered", impl_id)
+            }
+            DynCheckError::TypeHashMismatch {
+                impl_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "type hash mismatch for impl id {}: expected {:#018x}, found \
+                 {:#018x}",
+                impl_id, expected, found,
+            ),
+            DynCheckError::UnknownMetadata => {
+                write!(f, "trait object metadata not registered")
+            }
+            DynCheckError::LayoutMismatch { expected, found } => write!(
+                f,
+                "layout mismatch: expected size {} and alignment {}, found \
+                 size {} and alignment {}",
+                expected.size(),
+                expected.align(),
+                found.size(),
+                found.align(),
+            ),
+            DynCheckError::Unchecked => {
+                write!(f, "registered trait impl does not support validation")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DynCheckError {}
+
+impl<T: ?Sized> ArchivedDynMetadata<T> {
+    /// Checks that the impl ID of this metadata is registered and that its
+    /// type hash matches the registered trait impl, returning the registered
+    /// trait impl.
+    pub fn check(&self) -> Result<&'static TraitImpl, DynCheckError> {
+        let impl_id = self.impl_id();
+        let trait_impl = TRAIT_IMPLS
+            .get()
+            .ok_or(DynCheckError::Unregistered)?
+            .get(impl_id as usize)
+            .ok_or(DynCheckError::UnknownImplId { impl_id })?;
+
+        if trait_impl.type_hash() != self.type_hash() {
+            return Err(DynCheckError::TypeHashMismatch {
+                impl_id,
+                expected: trait_impl.type_hash(),
+                found: self.type_hash(),
+            });
+        }
+
+        Ok(trait_impl)
+    }
+}
+
+unsafe impl<T, C> Verify<C> for ArchivedDynMetadata<T>
+where
+    T: ?Sized,
+    C: Fallible + ?Sized,
+    C::Error: Error,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        match self.check() {
+            Ok(_) => Ok(()),
+            Err(e) => fail!(e),
+        }
+    }
+}
+
+/// A validation context which can be used to check trait objects.
+///
+/// This is implemented for all types which implement both [`ArchiveContext`]
+/// and [`SharedContext`] with an error type of [`BoxedError`].
+pub trait DynContext:
+    ArchiveContext<BoxedError> + SharedContext<BoxedError>
+{
+}
+
+impl<C> DynContext for C where
+    C: ArchiveContext<BoxedError> + SharedContext<BoxedError> + ?Sized
+{
+}
+
+/// The validator that registered `CheckBytes` implementations are called with.
+pub type DynValidator<'a> = Strategy<dyn DynContext + 'a, BoxedError>;
+
+/// The layout and type-erased `CheckBytes` function of a registered type.
+#[derive(Clone, Copy)]
+pub struct CheckBytesFn {
+    layout: Layout,
+    check_bytes:
+        unsafe fn(*const u8, &mut DynValidator<'_>) -> Result<(), BoxedError>,
+}
+
+impl CheckBytesFn {
+    /// Returns the layout of the type this function checks.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+}
+
+impl fmt::Debug for CheckBytesFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckBytesFn")
+            .field("layout", &self.layout)
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe fn check_bytes_erased<T>(
+    ptr: *const u8,
+    context: &mut DynValidator<'_>,
+) -> Result<(), BoxedError>
+where
+    T: for<'a> CheckBytes<DynValidator<'a>>,
+{
+    // SAFETY: The caller has guaranteed that `ptr` points to a `T` which is
+    // valid for reads.
+    unsafe { T::check_bytes(ptr.cast::<T>(), context) }
+}
+
+#[doc(hidden)]
+pub struct CheckBytesFor<T>(PhantomData<T>);
+
+impl<T> CheckBytesFor<T> {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait CheckBytesSupported {
+    fn check_bytes_fn(&self) -> Option<CheckBytesFn>;
+}
+
+impl<T> CheckBytesSupported for &CheckBytesFor<T>
+where
+    T: for<'a> CheckBytes<DynValidator<'a>>,
+{
+    fn check_bytes_fn(&self) -> Option<CheckBytesFn> {
+        Some(CheckBytesFn {
+            layout: Layout::new::<T>(),
+            check_bytes: check_bytes_erased::<T>,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub trait CheckBytesFallback {
+    fn check_bytes_fn(&self) -> Option<CheckBytesFn>;
+}
+
+impl<T> CheckBytesFallback for CheckBytesFor<T> {
+    fn check_bytes_fn(&self) -> Option<CheckBytesFn> {
+        None
+    }
+}
+
+impl TraitImpl {
+    /// Returns this trait impl with the given `CheckBytes` function.
+    ///
+    /// This is called by [`trait_impl`](crate::trait_impl) when the
+    /// implementing type supports validation.
+    pub fn with_check_bytes(
+        mut self,
+        check_bytes: Option<CheckBytesFn>,
+    ) -> Self {
+        self.check_bytes = check_bytes;
+        self
+    }
+
+    /// Returns the `CheckBytes` function of the implementing type, if any.
+    pub fn check_bytes_fn(&self) -> Option<CheckBytesFn> {
+        self.check_bytes
+    }
+}
+
+struct ErasedContext<'a, C: ?Sized, E> {
+    inner: &'a mut C,
+    _phantom: PhantomData<E>,
+}
+
+unsafe impl<C, E> ArchiveContext<BoxedError> for ErasedContext<'_, C, E>
+where
+    C: ArchiveContext<E> + ?Sized,
+    E: Error,
+{
+    fn check_subtree_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), BoxedError> {
+        self.inner.check_subtree_ptr(ptr, layout).into_error()
+    }
+
+    unsafe fn push_prefix_subtree_range(
+        &mut self,
+        root: *const u8,
+        end: *const u8,
+    ) -> Result<Range<usize>, BoxedError> {
+        unsafe { self.inner.push_prefix_subtree_range(root, end) }.into_error()
+    }
+
+    unsafe fn push_suffix_subtree_range(
+        &mut self,
+        start: *const u8,
+        root: *const u8,
+    ) -> Result<Range<usize>, BoxedError> {
+        unsafe { self.inner.push_suffix_subtree_range(start, root) }
+            .into_error()
+    }
+
+    unsafe fn pop_subtree_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<(), BoxedError> {
+        unsafe { self.inner.pop_subtree_range(range) }.into_error()
+    }
+}
+
+impl<C, E> SharedContext<BoxedError> for ErasedContext<'_, C, E>
+where
+    C: SharedContext<E> + ?Sized,
+    E: Error,
+{
+    fn register_shared_ptr(
+        &mut self,
+        address: usize,
+        type_id: TypeId,
+    ) -> Result<bool, BoxedError> {
+        self.inner
+            .register_shared_ptr(address, type_id)
+            .into_error()
+    }
+}
+
+fn find_check_bytes_fn<T: ?Sized>(
+    metadata: DynMetadata<T>,
+) -> Result<CheckBytesFn, DynCheckError> {
+    let trait_impl = TRAIT_IMPLS
+        .get()
+        .ok_or(DynCheckError::Unregistered)?
+        .iter()
+        // SAFETY: The registered metadata is only compared against `metadata`
+        // and never used as a `DynMetadata<T>`.
+        .find(|i| unsafe { i.downcast_metadata::<T>() } == metadata)
+        .ok_or(DynCheckError::UnknownMetadata)?;
+    trait_impl.check_bytes_fn().ok_or(DynCheckError::Unchecked)
+}
+
+/// Checks the bytes of a trait object using the `CheckBytes` implementation
+/// of the registered implementing type.
+///
+/// This should be called from the `CheckBytes` implementation of archived
+/// `dyn Trait` types.
+///
+/// # Safety
+///
+/// `value` must be valid for reads, and its pointer metadata must have come
+/// from a checked [`ArchivedDynMetadata`].
+pub unsafe fn check_dyn_value<T, C>(
+    value: *const T,
+    context: &mut C,
+) -> Result<(), C::Error>
+where
+    T: Pointee<Metadata = DynMetadata<T>> + ?Sized,
+    C: Fallible + ArchiveContext + SharedContext + ?Sized,
+    C::Error: Error,
+{
+    let (ptr, metadata) = ptr_meta::to_raw_parts(value);
+    let ptr = ptr.cast::<u8>();
+
+    let check_bytes = find_check_bytes_fn(metadata).into_error()?;
+    let found = metadata.layout();
+    if check_bytes.layout != found {
+        fail!(DynCheckError::LayoutMismatch {
+            expected: check_bytes.layout,
+            found,
+        });
+    }
+
+    context.check_subtree_ptr(ptr, &found)?;
+
+    let mut erased = ErasedContext::<C, C::Error> {
+        inner: context,
+        _phantom: PhantomData,
+    };
+    // SAFETY: `ptr` is valid for reads and was checked to be in bounds and
+    // aligned for the registered type.
+    unsafe {
+        (check_bytes.check_bytes)(
+            ptr,
+            Strategy::wrap(&mut erased as &mut dyn DynContext),
+        )
+    }
+    .into_error()
+}
@@ -1,7 +1,3 @@
-// TODO: uncomment and fix
-// #[cfg(feature = "bytecheck")]
-// mod validation;
-
 #[cfg(test)]
 mod tests {
     // #[cfg_attr(feature = "wasm", allow(unused_imports))]
@@ -44,6 +40,7 @@ mod tests {
                 TestTrait + SerializeDyn<SE>
             {
                 fn archived_impl_id(&self) -> ImplId;
+                fn archived_type_hash(&self) -> u64;
             }
 
             impl<T, SE, DE> SerializeTestTrait<SE, DE> for T
@@ -54,13 +51,20 @@ mod tests {
                 fn archived_impl_id(&self) -> ImplId {
                     T::Archived::IMPL_ID
                 }
+
+                fn archived_type_hash(&self) -> u64 {
+                    T::Archived::TYPE_HASH
+                }
             }
 
             impl<SE, DE> ArchiveUnsized for dyn SerializeTestTrait<SE, DE> {
                 type Archived = dyn DeserializeTestTrait<SE, DE>;
 
                 fn archived_metadata(&self) -> ArchivedMetadata<Self> {
-                    ArchivedDynMetadata::new(self.archived_impl_id())
+                    ArchivedDynMetadata::new(
+                        self.archived_impl_id(),
+                        self.archived_type_hash(),
+                    )
                 }
             }
 
@@ -0,0 +1,366 @@
+#![cfg(all(feature = "bytecheck", not(feature = "wasm")))]
+
+// Trait impls can only be registered once per process, so these tests live in
+// their own test binary.
+
+use core::{
+    alloc::{Layout, LayoutError},
+    mem::size_of,
+};
+use std::sync::Once;
+
+use bytecheck::CheckBytes;
+use ptr_meta::Pointee;
+use rkyv::{
+    access,
+    rancor::{BoxedError, Error, Failure, Fallible},
+    to_bytes,
+    util::AlignedVec,
+    validation::{ArchiveContext, LayoutRaw, SharedContext},
+    Archive, ArchivePointee, ArchiveUnsized, Archived, ArchivedMetadata,
+    Portable, Serialize, SerializeUnsized,
+};
+use rkyv_dyn::{
+    register_trait_impls, trait_impl,
+    validation::{check_dyn_value, DynCheckError},
+    ArchivedDynMetadata, AsDynSerializer, DynSerializer, ImplId,
+    RegisteredImpl, SerializeDyn,
+};
+
+const TEST_SALT: u64 = 0x7e57_5a17_0000_0001;
+
+pub trait TestTrait {
+    fn get_id(&self) -> u64;
+}
+
+#[ptr_meta::pointee]
+pub trait SerializeTestTrait<E>: TestTrait + SerializeDyn<E> {
+    fn archived_impl_id(&self) -> ImplId;
+    fn archived_type_hash(&self) -> u64;
+}
+
+impl<T, E> SerializeTestTrait<E> for T
+where
+    T: TestTrait + for<'a> Serialize<dyn DynSerializer<E> + 'a>,
+    T::Archived: RegisteredImpl<dyn DeserializeTestTrait>,
+{
+    fn archived_impl_id(&self) -> ImplId {
+        T::Archived::IMPL_ID
+    }
+
+    fn archived_type_hash(&self) -> u64 {
+        T::Archived::TYPE_HASH
+    }
+}
+
+impl<E> ArchiveUnsized for dyn SerializeTestTrait<E> {
+    type Archived = dyn DeserializeTestTrait;
+
+    fn archived_metadata(&self) -> ArchivedMetadata<Self> {
+        ArchivedDynMetadata::new(
+            self.archived_impl_id(),
+            self.archived_type_hash(),
+        )
+    }
+}
+
+impl<S> SerializeUnsized<S> for dyn SerializeTestTrait<S::Error>
+where
+    S: Fallible + AsDynSerializer<S::Error> + ?Sized,
+{
+    fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
+        self.serialize_and_resolve_dyn(serializer.as_dyn_serializer())
+    }
+}
+
+#[ptr_meta::pointee]
+pub trait DeserializeTestTrait: TestTrait + Portable {}
+
+impl<T: TestTrait + Portable> DeserializeTestTrait for T {}
+
+impl ArchivePointee for dyn DeserializeTestTrait {
+    type ArchivedMetadata = ArchivedDynMetadata<Self>;
+
+    fn pointer_metadata(
+        archived: &Self::ArchivedMetadata,
+    ) -> <Self as Pointee>::Metadata {
+        archived.lookup_metadata()
+    }
+}
+
+impl LayoutRaw for dyn DeserializeTestTrait {
+    fn layout_raw(
+        metadata: <Self as Pointee>::Metadata,
+    ) -> Result<Layout, LayoutError> {
+        Ok(metadata.layout())
+    }
+}
+
+unsafe impl<C> CheckBytes<C> for dyn DeserializeTestTrait
+where
+    C: Fallible + ArchiveContext + SharedContext + ?Sized,
+    C::Error: Error,
+{
+    unsafe fn check_bytes(
+        value: *const Self,
+        context: &mut C,
+    ) -> Result<(), C::Error> {
+        unsafe { check_dyn_value(value, context) }
+    }
+}
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes)]
+pub struct Small {
+    id: u32,
+}
+
+impl TestTrait for Small {
+    fn get_id(&self) -> u64 {
+        self.id as u64
+    }
+}
+
+impl TestTrait for Archived<Small> {
+    fn get_id(&self) -> u64 {
+        self.id.to_native() as u64
+    }
+}
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes)]
+pub struct Large {
+    ids: [u64; 4],
+}
+
+impl TestTrait for Large {
+    fn get_id(&self) -> u64 {
+        self.ids.iter().sum()
+    }
+}
+
+impl TestTrait for Archived<Large> {
+    fn get_id(&self) -> u64 {
+        self.ids.iter().map(|id| id.to_native()).sum()
+    }
+}
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes)]
+pub struct Flag {
+    value: bool,
+}
+
+impl TestTrait for Flag {
+    fn get_id(&self) -> u64 {
+        self.value as u64
+    }
+}
+
+impl TestTrait for Archived<Flag> {
+    fn get_id(&self) -> u64 {
+        self.value as u64
+    }
+}
+
+fn register() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| {
+        register_trait_impls! {
+            salt = TEST_SALT;
+            Archived<Small> as dyn DeserializeTestTrait,
+            Archived<Large> as dyn DeserializeTestTrait,
+            Archived<Flag> as dyn DeserializeTestTrait,
+        }
+    });
+}
+
+type TestBox = Box<dyn SerializeTestTrait<Failure>>;
+type ArchivedTestBox = Archived<TestBox>;
+type TestMetadata = ArchivedDynMetadata<dyn DeserializeTestTrait>;
+
+fn serialize(value: TestBox) -> AlignedVec {
+    register();
+    to_bytes::<_, 256, Failure>(&value).unwrap()
+}
+
+// The archived metadata is the last field of the root `ArchivedBox`, which is
+// located at the end of the buffer. The impl ID is its first field and the type
+// hash is its last field.
+fn write_metadata(bytes: &mut [u8], impl_id: ImplId, type_hash: u64) {
+    let metadata_pos = bytes.len() - size_of::<TestMetadata>();
+    let type_hash_pos = bytes.len() - size_of::<Archived<u64>>();
+    unsafe {
+        bytes
+            .as_mut_ptr()
+            .add(metadata_pos)
+            .cast::<Archived<ImplId>>()
+            .write_unaligned(Archived::<ImplId>::from_native(impl_id));
+        bytes
+            .as_mut_ptr()
+            .add(type_hash_pos)
+            .cast::<Archived<u64>>()
+            .write_unaligned(Archived::<u64>::from_native(type_hash));
+    }
+}
+
+fn impl_id<T: RegisteredImpl<dyn DeserializeTestTrait>>() -> ImplId {
+    T::IMPL_ID
+}
+
+fn type_hash<T: RegisteredImpl<dyn DeserializeTestTrait>>() -> u64 {
+    T::TYPE_HASH
+}
+
+fn check(bytes: &[u8]) -> Result<&ArchivedTestBox, BoxedError> {
+    access::<ArchivedTestBox, BoxedError>(bytes)
+}
+
+fn expect_error(bytes: &[u8], message: &str) {
+    match check(bytes) {
+        Ok(_) => panic!("expected validation to fail with \"{}\"", message),
+        Err(e) => assert!(
+            e.to_string().contains(message),
+            "expected error containing \"{}\", found \"{}\"",
+            message,
+            e,
+        ),
+    }
+}
+
+#[test]
+fn check_valid() {
+    let small = serialize(Box::new(Small { id: 42 }));
+    assert_eq!(check(&small).unwrap().get_id(), 42);
+
+    let large = serialize(Box::new(Large { ids: [1, 2, 3, 4] }));
+    assert_eq!(check(&large).unwrap().get_id(), 10);
+
+    let flag = serialize(Box::new(Flag { value: true }));
+    assert_eq!(check(&flag).unwrap().get_id(), 1);
+}
+
+#[test]
+fn salted_type_hashes() {
+    register();
+
+    let unsalted = trait_impl!(Archived<Small> as dyn DeserializeTestTrait);
+    let salted = trait_impl!(
+        Archived<Small> as dyn DeserializeTestTrait,
+        salt = TEST_SALT
+    );
+    assert_ne!(unsalted.type_hash(), salted.type_hash());
+    assert_eq!(salted.type_hash(), type_hash::<Archived<Small>>());
+
+    assert_ne!(
+        type_hash::<Archived<Small>>(),
+        type_hash::<Archived<Large>>()
+    );
+    assert_ne!(
+        type_hash::<Archived<Small>>(),
+        type_hash::<Archived<Flag>>()
+    );
+}
+
+#[test]
+fn check_metadata() {
+    register();
+
+    let metadata = TestMetadata::for_impl::<Archived<Small>>();
+    let trait_impl = metadata.check().unwrap();
+    assert_eq!(trait_impl.type_hash(), type_hash::<Archived<Small>>());
+    assert_eq!(trait_impl.layout(), Layout::new::<Archived<Small>>());
+
+    let unknown = TestMetadata::new(1000, type_hash::<Archived<Small>>());
+    assert!(matches!(
+        unknown.check(),
+        Err(DynCheckError::UnknownImplId { impl_id: 1000 }),
+    ));
+
+    let mismatched = TestMetadata::new(
+        impl_id::<Archived<Large>>(),
+        type_hash::<Archived<Small>>(),
+    );
+    assert!(matches!(
+        mismatched.check(),
+        Err(DynCheckError::TypeHashMismatch { .. }),
+    ));
+}
+
+#[test]
+fn forged_unknown_impl_id() {
+    let mut bytes = serialize(Box::new(Small { id: 42 }));
+    write_metadata(&mut bytes, 1000, type_hash::<Archived<Small>>());
+    expect_error(&bytes, "unknown impl id");
+
+    write_metadata(&mut bytes, ImplId::MAX, type_hash::<Archived<Small>>());
+    expect_error(&bytes, "unknown impl id");
+}
+
+#[test]
+fn forged_impl_id() {
+    // Point a `Small` payload at the `Flag` impl without updating the type
+    // hash.
+    let mut bytes = serialize(Box::new(Small { id: 2 }));
+    write_metadata(
+        &mut bytes,
+        impl_id::<Archived<Flag>>(),
+        type_hash::<Archived<Small>>(),
+    );
+    expect_error(&bytes, "type hash mismatch");
+
+    // Point a `Large` payload at the `Small` impl without updating the type
+    // hash.
+    let mut bytes = serialize(Box::new(Large { ids: [1, 2, 3, 4] }));
+    write_metadata(
+        &mut bytes,
+        impl_id::<Archived<Small>>(),
+        type_hash::<Archived<Large>>(),
+    );
+    expect_error(&bytes, "type hash mismatch");
+}
+
+#[test]
+fn forged_type_hash() {
+    let mut bytes = serialize(Box::new(Small { id: 42 }));
+    write_metadata(
+        &mut bytes,
+        impl_id::<Archived<Small>>(),
+        type_hash::<Archived<Small>>() ^ 1,
+    );
+    expect_error(&bytes, "type hash mismatch");
+}
+
+#[test]
+fn forged_impl_id_and_type_hash() {
+    // Even with a matching type hash, the payload is still checked as the
+    // forged type.
+    let mut bytes = serialize(Box::new(Small { id: 2 }));
+    write_metadata(
+        &mut bytes,
+        impl_id::<Archived<Flag>>(),
+        type_hash::<Archived<Flag>>(),
+    );
+    assert!(check(&bytes).is_err());
+}
+
+#[test]
+fn truncated_payload() {
+    // Claim a `Large` payload where only a `Small` payload was written. The
+    // claimed payload overruns the bytes before the root.
+    let mut bytes = serialize(Box::new(Small { id: 42 }));
+    write_metadata(
+        &mut bytes,
+        impl_id::<Archived<Large>>(),
+        type_hash::<Archived<Large>>(),
+    );
+    expect_error(&bytes, "subtree pointer overran range");
+
+    // Drop the payload and keep only the root.
+    let bytes = serialize(Box::new(Large { ids: [1, 2, 3, 4] }));
+    let root_size = size_of::<ArchivedTestBox>();
+    let mut truncated = AlignedVec::new();
+    truncated.extend_from_slice(&bytes[bytes.len() - root_size..]);
+    expect_error(&truncated, "subtree pointer overran range");
+}
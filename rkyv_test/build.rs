@@ -0,0 +1,16 @@
+fn main() {
+    // The C host for the `c_api` test is linked into the test binary, which
+    // defines the functions that it calls.
+    if std::env::var_os("CARGO_FEATURE_C_API").is_some() {
+        println!("cargo:rerun-if-changed=tests/c_api");
+        cc::Build::new()
+            .file("tests/c_api/host.c")
+            .include("tests/c_api")
+            .cargo_metadata(false)
+            .compile("c_api_host");
+        println!(
+            "cargo:rustc-link-search=native={}",
+            std::env::var("OUT_DIR").unwrap(),
+        );
+    }
+}
@@ -0,0 +1,182 @@
+/* Archived type layouts for `ArchivedDrawing`, generated by rkyv. */
+
+#include <assert.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef uint64_t rkyv_u64_le; /* little-endian unsigned integer */
+static_assert(sizeof(rkyv_u64_le) == 8, "size of rkyv_u64_le");
+
+typedef uint8_t rkyv_bool; /* bool */
+static_assert(sizeof(rkyv_bool) == 1, "size of rkyv_bool");
+
+typedef uint32_t rkyv_u32_le; /* little-endian unsigned integer */
+static_assert(sizeof(rkyv_u32_le) == 4, "size of rkyv_u32_le");
+
+typedef uint8_t rkyv_u8; /* unsigned integer */
+static_assert(sizeof(rkyv_u8) == 1, "size of rkyv_u8");
+
+typedef rkyv_u8 u8_x4[4];
+static_assert(sizeof(u8_x4) == 4, "size of u8_x4");
+
+/* `offset` is a little-endian signed offset from the start of the string to its `len` bytes, regardless of the endianness of the archive. */
+typedef struct ArchivedStringOutOfLine {
+    rkyv_u32_le len;
+    u8_x4 offset;
+} ArchivedStringOutOfLine;
+static_assert(sizeof(ArchivedStringOutOfLine) == 8, "size of ArchivedStringOutOfLine");
+static_assert(offsetof(ArchivedStringOutOfLine, len) == 0, "offset of ArchivedStringOutOfLine.len");
+static_assert(offsetof(ArchivedStringOutOfLine, offset) == 4, "offset of ArchivedStringOutOfLine.offset");
+
+typedef rkyv_u8 u8_x7[7];
+static_assert(sizeof(u8_x7) == 7, "size of u8_x7");
+
+typedef struct ArchivedStringInline {
+    u8_x7 bytes;
+    rkyv_u8 len;
+} ArchivedStringInline;
+static_assert(sizeof(ArchivedStringInline) == 8, "size of ArchivedStringInline");
+static_assert(offsetof(ArchivedStringInline, bytes) == 0, "offset of ArchivedStringInline.bytes");
+static_assert(offsetof(ArchivedStringInline, len) == 7, "offset of ArchivedStringInline.len");
+
+/* The string is stored inline if the high bit of `inline_repr.len` is clear, and out-of-line otherwise. */
+typedef union ArchivedString {
+    ArchivedStringOutOfLine out_of_line_repr;
+    ArchivedStringInline inline_repr;
+} ArchivedString;
+static_assert(sizeof(ArchivedString) == 8, "size of ArchivedString");
+static_assert(offsetof(ArchivedString, out_of_line_repr) == 0, "offset of ArchivedString.out_of_line_repr");
+static_assert(offsetof(ArchivedString, inline_repr) == 0, "offset of ArchivedString.inline_repr");
+
+typedef rkyv_u8 u8_x3[3];
+static_assert(sizeof(u8_x3) == 3, "size of u8_x3");
+
+typedef int32_t rkyv_i32_le; /* little-endian signed integer */
+static_assert(sizeof(rkyv_i32_le) == 4, "size of rkyv_i32_le");
+
+typedef uint32_t rkyv_f32_le; /* little-endian float */
+static_assert(sizeof(rkyv_f32_le) == 4, "size of rkyv_f32_le");
+
+typedef struct ArchivedPoint {
+    rkyv_f32_le x;
+    rkyv_f32_le y;
+} ArchivedPoint;
+static_assert(sizeof(ArchivedPoint) == 8, "size of ArchivedPoint");
+static_assert(offsetof(ArchivedPoint, x) == 0, "offset of ArchivedPoint.x");
+static_assert(offsetof(ArchivedPoint, y) == 4, "offset of ArchivedPoint.y");
+
+/* Relative pointer to ArchivedPoint: the target is located at `(const char *)ptr + ptr->offset`. */
+typedef struct RelPtr_i32_le_ArchivedPoint {
+    rkyv_i32_le offset;
+} RelPtr_i32_le_ArchivedPoint;
+static_assert(sizeof(RelPtr_i32_le_ArchivedPoint) == 4, "size of RelPtr_i32_le_ArchivedPoint");
+static_assert(offsetof(RelPtr_i32_le_ArchivedPoint, offset) == 0, "offset of RelPtr_i32_le_ArchivedPoint.offset");
+
+/* `ptr` points to the first of `len` consecutive elements. */
+typedef struct ArchivedVec_ArchivedPoint {
+    RelPtr_i32_le_ArchivedPoint ptr;
+    rkyv_u32_le len;
+} ArchivedVec_ArchivedPoint;
+static_assert(sizeof(ArchivedVec_ArchivedPoint) == 8, "size of ArchivedVec_ArchivedPoint");
+static_assert(offsetof(ArchivedVec_ArchivedPoint, ptr) == 0, "offset of ArchivedVec_ArchivedPoint.ptr");
+static_assert(offsetof(ArchivedVec_ArchivedPoint, len) == 4, "offset of ArchivedVec_ArchivedPoint.len");
+
+enum {
+    ArchivedShape_TAG_Circle = 0,
+    ArchivedShape_TAG_Polygon = 1,
+    ArchivedShape_TAG_Empty = 2,
+};
+
+typedef struct ArchivedShape_Circle {
+    rkyv_u8 tag;
+    uint8_t _padding0[3];
+    ArchivedPoint center;
+    rkyv_f32_le radius;
+} ArchivedShape_Circle;
+static_assert(sizeof(ArchivedShape_Circle) == 16, "size of ArchivedShape_Circle");
+static_assert(offsetof(ArchivedShape_Circle, tag) == 0, "offset of ArchivedShape_Circle.tag");
+static_assert(offsetof(ArchivedShape_Circle, center) == 4, "offset of ArchivedShape_Circle.center");
+static_assert(offsetof(ArchivedShape_Circle, radius) == 12, "offset of ArchivedShape_Circle.radius");
+
+typedef struct ArchivedShape_Polygon {
+    rkyv_u8 tag;
+    uint8_t _padding0[3];
+    ArchivedVec_ArchivedPoint _0;
+    uint8_t _padding1[4];
+} ArchivedShape_Polygon;
+static_assert(sizeof(ArchivedShape_Polygon) == 16, "size of ArchivedShape_Polygon");
+static_assert(offsetof(ArchivedShape_Polygon, tag) == 0, "offset of ArchivedShape_Polygon.tag");
+static_assert(offsetof(ArchivedShape_Polygon, _0) == 4, "offset of ArchivedShape_Polygon._0");
+
+typedef union ArchivedShape {
+    rkyv_u8 tag;
+    ArchivedShape_Circle Circle;
+    ArchivedShape_Polygon Polygon;
+} ArchivedShape;
+static_assert(sizeof(ArchivedShape) == 16, "size of ArchivedShape");
+
+/* Relative pointer to ArchivedShape: the target is located at `(const char *)ptr + ptr->offset`. */
+typedef struct RelPtr_i32_le_ArchivedShape {
+    rkyv_i32_le offset;
+} RelPtr_i32_le_ArchivedShape;
+static_assert(sizeof(RelPtr_i32_le_ArchivedShape) == 4, "size of RelPtr_i32_le_ArchivedShape");
+static_assert(offsetof(RelPtr_i32_le_ArchivedShape, offset) == 0, "offset of RelPtr_i32_le_ArchivedShape.offset");
+
+/* `ptr` points to the first of `len` consecutive elements. */
+typedef struct ArchivedVec_ArchivedShape {
+    RelPtr_i32_le_ArchivedShape ptr;
+    rkyv_u32_le len;
+} ArchivedVec_ArchivedShape;
+static_assert(sizeof(ArchivedVec_ArchivedShape) == 8, "size of ArchivedVec_ArchivedShape");
+static_assert(offsetof(ArchivedVec_ArchivedShape, ptr) == 0, "offset of ArchivedVec_ArchivedShape.ptr");
+static_assert(offsetof(ArchivedVec_ArchivedShape, len) == 4, "offset of ArchivedVec_ArchivedShape.len");
+
+/* Relative pointer to untyped data: the target is located at `(const char *)ptr + ptr->offset`. */
+typedef struct RawRelPtr_i32_le {
+    rkyv_i32_le offset;
+} RawRelPtr_i32_le;
+static_assert(sizeof(RawRelPtr_i32_le) == 4, "size of RawRelPtr_i32_le");
+static_assert(offsetof(RawRelPtr_i32_le, offset) == 0, "offset of RawRelPtr_i32_le.offset");
+
+typedef struct ArchivedHashMapEntry_ArchivedString_u32_le {
+    ArchivedString key;
+    rkyv_u32_le value;
+} ArchivedHashMapEntry_ArchivedString_u32_le;
+static_assert(sizeof(ArchivedHashMapEntry_ArchivedString_u32_le) == 12, "size of ArchivedHashMapEntry_ArchivedString_u32_le");
+static_assert(offsetof(ArchivedHashMapEntry_ArchivedString_u32_le, key) == 0, "offset of ArchivedHashMapEntry_ArchivedString_u32_le.key");
+static_assert(offsetof(ArchivedHashMapEntry_ArchivedString_u32_le, value) == 8, "offset of ArchivedHashMapEntry_ArchivedString_u32_le.value");
+
+/* `ptr` points to `cap + 15` control bytes, and the `cap` buckets of `ArchivedHashMapEntry_ArchivedString_u32_le` are located immediately before them in reverse order. The bucket for control byte `i < cap` ends at `ptr - i * sizeof(bucket)`, and is full if the high bit of the control byte is clear. If the high bit of `cap` is set, the table is ordered: the capacity is `cap` without that bit, and `len` 32-bit bucket indices follow the control bytes, aligned to 4 bytes from the start of the buckets, in the order the entries were serialized. */
+typedef struct ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le {
+    RawRelPtr_i32_le ptr;
+    rkyv_u32_le len;
+    rkyv_u32_le cap;
+} ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le;
+static_assert(sizeof(ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le) == 12, "size of ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le");
+static_assert(offsetof(ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le, ptr) == 0, "offset of ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le.ptr");
+static_assert(offsetof(ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le, len) == 4, "offset of ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le.len");
+static_assert(offsetof(ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le, cap) == 8, "offset of ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le.cap");
+
+typedef struct ArchivedHashMap_ArchivedString_u32_le {
+    ArchivedHashTable_ArchivedHashMapEntry_ArchivedString_u32_le table;
+} ArchivedHashMap_ArchivedString_u32_le;
+static_assert(sizeof(ArchivedHashMap_ArchivedString_u32_le) == 12, "size of ArchivedHashMap_ArchivedString_u32_le");
+static_assert(offsetof(ArchivedHashMap_ArchivedString_u32_le, table) == 0, "offset of ArchivedHashMap_ArchivedString_u32_le.table");
+
+typedef struct ArchivedDrawing {
+    ArchivedString name;
+    ArchivedVec_ArchivedShape shapes;
+    ArchivedHashMap_ArchivedString_u32_le tags;
+    uint8_t _padding0[4];
+    rkyv_u64_le id;
+    rkyv_bool visible;
+    u8_x3 color;
+    uint8_t _padding1[4];
+} ArchivedDrawing;
+static_assert(sizeof(ArchivedDrawing) == 48, "size of ArchivedDrawing");
+static_assert(offsetof(ArchivedDrawing, name) == 0, "offset of ArchivedDrawing.name");
+static_assert(offsetof(ArchivedDrawing, shapes) == 8, "offset of ArchivedDrawing.shapes");
+static_assert(offsetof(ArchivedDrawing, tags) == 16, "offset of ArchivedDrawing.tags");
+static_assert(offsetof(ArchivedDrawing, id) == 32, "offset of ArchivedDrawing.id");
+static_assert(offsetof(ArchivedDrawing, visible) == 40, "offset of ArchivedDrawing.visible");
+static_assert(offsetof(ArchivedDrawing, color) == 41, "offset of ArchivedDrawing.color");
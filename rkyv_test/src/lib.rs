@@ -10,7 +10,22 @@ extern crate alloc;
 #[cfg(feature = "alloc")]
 mod test_alloc;
 #[cfg(feature = "std")]
+mod test_archiver;
+#[cfg(feature = "arrow")]
+mod test_arrow;
+#[cfg(feature = "bumpalo")]
+mod test_bumpalo;
+#[cfg(feature = "std")]
+mod test_format;
+mod test_paths;
+#[cfg(all(feature = "std", feature = "bytecheck"))]
+mod test_provenance;
+#[cfg(feature = "test-helpers")]
+mod test_roundtrip;
+#[cfg(feature = "std")]
 mod test_std;
+#[cfg(feature = "tokio")]
+mod test_tokio;
 pub mod util;
 #[cfg(feature = "bytecheck")]
 pub mod validation;
@@ -142,4 +157,32 @@ mod tests {
         test_archive::<r#virtual>(&r#virtual { r#virtual: 42 });
         test_archive::<r#try>(&r#try::r#try { r#try: 42 });
     }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_core_enums() {
+        use core::{
+            cmp::Ordering, convert::Infallible, marker::PhantomPinned,
+            num::FpCategory, sync::atomic,
+        };
+
+        test_archive(&Ordering::Less);
+        test_archive(&Ordering::Equal);
+        test_archive(&Ordering::Greater);
+
+        test_archive(&atomic::Ordering::Relaxed);
+        test_archive(&atomic::Ordering::Release);
+        test_archive(&atomic::Ordering::Acquire);
+        test_archive(&atomic::Ordering::AcqRel);
+        test_archive(&atomic::Ordering::SeqCst);
+
+        test_archive(&FpCategory::Nan);
+        test_archive(&FpCategory::Infinite);
+        test_archive(&FpCategory::Zero);
+        test_archive(&FpCategory::Subnormal);
+        test_archive(&FpCategory::Normal);
+
+        test_archive(&PhantomPinned);
+        test_archive(&Result::<u32, Infallible>::Ok(42));
+    }
 }
@@ -5,6 +5,7 @@ mod tests {
         borrow::Cow,
         boxed::Box,
         collections::{BTreeMap, BTreeSet},
+        format,
         rc::{Rc, Weak},
         string::{String, ToString},
         vec,
@@ -20,6 +21,7 @@ mod tests {
 
     use rkyv::{
         access_unchecked, access_unchecked_mut,
+        de::AllocationLimiter,
         rancor::{Error, Failure, Fallible, Strategy},
         ser::{writer::BufferWriter, Writer},
         to_bytes,
@@ -114,6 +116,299 @@ mod tests {
         test_archive::<Result<(), _>>(&Err(Box::new(vec![1, 2, 3, 4])));
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_control_flow_and_poll() {
+        use core::{ops::ControlFlow, task::Poll};
+
+        test_archive::<ControlFlow<(), _>>(&ControlFlow::Continue(
+            "hello world".to_string(),
+        ));
+        test_archive::<ControlFlow<(), _>>(&ControlFlow::Continue(vec![1, 2]));
+        test_archive::<ControlFlow<_, ()>>(&ControlFlow::Break(
+            "hello world".to_string(),
+        ));
+        test_archive::<ControlFlow<_, ()>>(&ControlFlow::Break(vec![1, 2, 3]));
+        test_archive(&Poll::Ready("hello world".to_string()));
+        test_archive(&Poll::Ready(vec![1, 2, 3, 4]));
+        test_archive::<Poll<String>>(&Poll::Pending);
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(compare(PartialEq))]
+        #[archive_attr(derive(Debug))]
+        struct StateMachine {
+            step: ControlFlow<String, Vec<u32>>,
+            output: Poll<Vec<String>>,
+        }
+
+        let value = StateMachine {
+            step: ControlFlow::Continue(vec![1, 2, 3]),
+            output: Poll::Pending,
+        };
+        test_archive(&value);
+
+        let value = StateMachine {
+            step: ControlFlow::Break("done".to_string()),
+            output: Poll::Ready(vec!["a".to_string(), "b".to_string()]),
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            unsafe { access_unchecked::<ArchivedStateMachine>(&bytes) };
+        assert!(archived.step.is_break());
+        assert!(!archived.step.is_continue());
+        assert_eq!(archived.step.break_value().unwrap(), "done");
+        assert!(archived.step.continue_value().is_none());
+        assert!(matches!(
+            archived.step.as_ref(),
+            ControlFlow::Break(s) if s == "done",
+        ));
+        assert_eq!(archived.step, value.step);
+        assert!(archived.output.is_ready());
+        assert!(!archived.output.is_pending());
+        assert!(matches!(
+            archived.output.as_ref(),
+            Poll::Ready(v) if v.len() == 2,
+        ));
+        assert_eq!(value.output, archived.output);
+        assert_ne!(archived.output, Poll::<Vec<String>>::Pending);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_enum_dispatch() {
+        use core::any::Any;
+
+        use rkyv::rkyv_enum_dispatch;
+
+        trait Shape: Any {
+            fn area(&self) -> u32;
+            fn name(&self) -> &str;
+        }
+
+        #[rkyv_enum_dispatch]
+        trait ArchivedShape {
+            fn area(&self) -> u32;
+            fn name(&self) -> &str;
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Square {
+            side: u32,
+        }
+
+        impl Shape for Square {
+            fn area(&self) -> u32 {
+                self.side * self.side
+            }
+
+            fn name(&self) -> &str {
+                "square"
+            }
+        }
+
+        impl ArchivedShape for ArchivedSquare {
+            fn area(&self) -> u32 {
+                self.side * self.side
+            }
+
+            fn name(&self) -> &str {
+                "square"
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Rect {
+            width: u32,
+            height: u32,
+        }
+
+        impl Shape for Rect {
+            fn area(&self) -> u32 {
+                self.width * self.height
+            }
+
+            fn name(&self) -> &str {
+                "rect"
+            }
+        }
+
+        impl ArchivedShape for ArchivedRect {
+            fn area(&self) -> u32 {
+                self.width * self.height
+            }
+
+            fn name(&self) -> &str {
+                "rect"
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Custom {
+            name: String,
+            area: u32,
+        }
+
+        impl Shape for Custom {
+            fn area(&self) -> u32 {
+                self.area
+            }
+
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+
+        impl ArchivedShape for ArchivedCustom {
+            fn area(&self) -> u32 {
+                self.area.to_native()
+            }
+
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+
+        #[rkyv_enum_dispatch(Shape, archived = ArchivedShape)]
+        enum AnyShape {
+            Square(Square),
+            Rect(Rect),
+            Custom(Custom),
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Scene {
+            main: Box<dyn Shape>,
+            shapes: Vec<Box<dyn Shape>>,
+        }
+
+        let value = Scene {
+            main: Box::new(Rect {
+                width: 3,
+                height: 4,
+            }),
+            shapes: vec![
+                Box::new(Square { side: 5 }),
+                Box::new(Custom {
+                    name: "triangle".to_string(),
+                    area: 6,
+                }),
+                Box::new(Rect {
+                    width: 1,
+                    height: 2,
+                }),
+            ],
+        };
+        let expected =
+            [(12, "rect"), (25, "square"), (6, "triangle"), (2, "rect")];
+
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedScene>(&bytes) };
+        assert!(matches!(archived.main, ArchivedAnyShape::Rect(_)));
+        assert!(matches!(archived.shapes[1], ArchivedAnyShape::Custom(_)));
+        let archived_shapes =
+            core::iter::once(&archived.main).chain(archived.shapes.iter());
+        for (shape, &(area, name)) in archived_shapes.zip(expected.iter()) {
+            assert_eq!(ArchivedShape::area(shape), area);
+            assert_eq!(ArchivedShape::name(shape), name);
+        }
+
+        let deserialized =
+            deserialize::<Scene, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized.shapes.len(), 3);
+        let shapes = core::iter::once(&deserialized.main)
+            .chain(deserialized.shapes.iter());
+        for (shape, &(area, name)) in shapes.zip(expected.iter()) {
+            assert_eq!(shape.area(), area);
+            assert_eq!(shape.name(), name);
+        }
+
+        let any_shape =
+            deserialize::<AnyShape, _, Failure>(&archived.shapes[0], &mut ())
+                .unwrap();
+        assert!(matches!(any_shape, AnyShape::Square(Square { side: 5 })));
+        let boxed: Box<dyn Shape> = any_shape.into();
+        assert_eq!(boxed.area(), 25);
+
+        struct Unlisted;
+
+        impl Shape for Unlisted {
+            fn area(&self) -> u32 {
+                0
+            }
+
+            fn name(&self) -> &str {
+                "unlisted"
+            }
+        }
+
+        let value: Vec<Box<dyn Shape>> =
+            vec![Box::new(Square { side: 1 }), Box::new(Unlisted)];
+        assert!(to_bytes::<_, 256, Failure>(&value).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn aligned_vec_raw_parts() {
+        use core::ptr::NonNull;
+
+        let mut vec = AlignedVec::with_capacity(32);
+        vec.extend_from_slice(&[7; 20]);
+        let (ptr, len, cap) = vec.into_raw_parts();
+        assert_eq!((len, cap), (20, 32));
+        assert_eq!(ptr.as_ptr().align_offset(AlignedVec::ALIGNMENT), 0);
+        let mut vec = unsafe { AlignedVec::from_raw_parts(ptr, len, cap) };
+        assert_eq!(vec.as_slice(), &[7; 20]);
+        // Growing reallocates with the layout the parts were created with
+        vec.extend_from_slice(&[8; 100]);
+        assert_eq!(vec.len(), 120);
+        assert_eq!(vec.as_ptr().align_offset(AlignedVec::ALIGNMENT), 0);
+        drop(vec);
+
+        let mut vec = AlignedVec::with_alignment(128);
+        vec.extend_from_slice(&[1, 2, 3]);
+        let align = vec.alignment();
+        let (ptr, len, cap) = vec.into_raw_parts();
+        let mut vec = unsafe {
+            AlignedVec::from_raw_parts_with_alignment(ptr, len, cap, align)
+        };
+        assert_eq!(vec.alignment(), 128);
+        vec.reserve(1000);
+        assert_eq!(vec.as_ptr().align_offset(128), 0);
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        vec.shrink_to_fit();
+        drop(vec);
+
+        let (ptr, len, cap) = AlignedVec::new().into_raw_parts();
+        assert_eq!((len, cap), (0, 0));
+        let mut vec = unsafe { AlignedVec::from_raw_parts(ptr, len, cap) };
+        vec.push(1);
+        assert_eq!(vec.as_slice(), &[1]);
+        drop(vec);
+
+        let mut vec = AlignedVec::with_capacity(4);
+        vec.extend_from_slice(&[1, 2, 3, 4]);
+        let leaked = vec.leak();
+        leaked[3] = 5;
+        assert_eq!(leaked, &[1, 2, 3, 5]);
+        let ptr = NonNull::new(leaked.as_mut_ptr()).unwrap();
+        // Reclaim the leaked memory so that it's freed with the right layout
+        let vec = unsafe { AlignedVec::from_raw_parts(ptr, 4, 4) };
+        assert_eq!(vec.as_slice(), &[1, 2, 3, 5]);
+        drop(vec);
+
+        let bytes = (0..100).collect::<Vec<u8>>();
+        let aligned = AlignedVec::try_from(bytes.clone()).unwrap();
+        assert_eq!(aligned.as_slice(), bytes.as_slice());
+        assert_eq!(aligned.as_ptr().align_offset(AlignedVec::ALIGNMENT), 0);
+        assert_eq!(aligned.into_vec(), bytes);
+        let aligned = AlignedVec::try_from(bytes.clone()).unwrap();
+        assert_eq!(Vec::from(aligned), bytes);
+
+        let empty = AlignedVec::try_from(Vec::new()).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.capacity(), 0);
+    }
+
     #[cfg(all(feature = "std", feature = "bytecheck"))]
     mod isolate {
         #[cfg(feature = "wasm")]
@@ -385,28 +680,6 @@ mod tests {
             }
         }
 
-        impl<T: TestTrait> fmt::Debug for ArchivedTest<T>
-        where
-            T::Associated: Archive,
-            <T::Associated as Archive>::Archived: fmt::Debug,
-        {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                match self {
-                    ArchivedTest::A => {
-                        f.debug_tuple("ArchivedTest::A").finish()
-                    }
-                    ArchivedTest::B(value) => {
-                        f.debug_tuple("ArchivedTest::B").field(value).finish()
-                    }
-                    ArchivedTest::C { a, b } => f
-                        .debug_struct("ArchivedTest::C")
-                        .field("a", a)
-                        .field("b", b)
-                        .finish(),
-                }
-            }
-        }
-
         test_archive(&Test::<()>::A);
         test_archive(&Test::<()>::B("hello_world".to_string()));
         test_archive(&Test::<()>::C {
@@ -425,7 +698,6 @@ mod tests {
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    #[cfg(feature = "copy")]
     fn archive_copy() {
         use core::fmt;
 
@@ -437,13 +709,13 @@ mod tests {
         test_archive(&TestUnit);
 
         #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
-        // This is not technically copy safe but we're here to test
         #[archive(copy_safe, compare(PartialEq))]
         #[archive_attr(derive(Debug))]
+        #[repr(C)]
         struct TestStruct {
             a: (),
             b: i32,
-            c: bool,
+            c: [bool; 4],
             d: f32,
             e: TestUnit,
         }
@@ -451,33 +723,28 @@ mod tests {
         test_archive(&TestStruct {
             a: (),
             b: 42,
-            c: true,
-            d: 3.14f32,
+            c: [true, false, true, true],
+            d: 2.5f32,
             e: TestUnit,
         });
 
         #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
-        // This is not technically copy safe but we're here to test
-        #[archive(copy_safe, compare(PartialEq))]
-        #[archive_attr(derive(Debug))]
-        struct TestTuple((), i32, bool, f32, TestUnit);
-
-        test_archive(&TestTuple((), 42, true, 3.14f32, TestUnit));
-
-        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
-        // This is not technically copy safe but we're here to test
         #[archive(copy_safe, compare(PartialEq))]
         #[archive_attr(derive(Debug))]
-        #[repr(u8)]
-        enum TestEnum {
-            A((), i32, bool, f32, TestUnit),
-        }
+        #[repr(C)]
+        struct TestTuple((), i32, [bool; 4], f32, TestUnit);
 
-        test_archive(&TestEnum::A((), 42, true, 3.14f32, TestUnit));
+        test_archive(&TestTuple(
+            (),
+            42,
+            [true, false, true, true],
+            2.5f32,
+            TestUnit,
+        ));
 
         #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
-        // This is not technically copy safe but we're here to test
         #[archive(copy_safe, compare(PartialEq))]
+        #[repr(C)]
         struct TestGeneric<T>(T);
 
         impl<T: Archive> fmt::Debug for ArchivedTestGeneric<T>
@@ -490,6 +757,102 @@ mod tests {
         }
 
         test_archive(&TestGeneric(42));
+
+        assert!(TestUnit::COPY_OPTIMIZATION.is_enabled());
+        assert!(TestStruct::COPY_OPTIMIZATION.is_enabled());
+        assert!(TestTuple::COPY_OPTIMIZATION.is_enabled());
+        assert!(TestGeneric::<u32>::COPY_OPTIMIZATION.is_enabled());
+        assert!(<[TestStruct; 2]>::COPY_OPTIMIZATION.is_enabled());
+
+        test_archive(&vec![
+            TestStruct {
+                a: (),
+                b: 1,
+                c: [false; 4],
+                d: 1.5,
+                e: TestUnit,
+            },
+            TestStruct {
+                a: (),
+                b: -1,
+                c: [true; 4],
+                d: -1.5,
+                e: TestUnit,
+            },
+        ]);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn copy_optimization_matches_element_wise() {
+        use rkyv::{
+            ser::Allocator,
+            vec::{ArchivedVec, VecResolver},
+        };
+
+        // Serializes a slice one element at a time, which is never copy
+        // optimized.
+        struct ElementWise<'a, T>(&'a [T]);
+
+        impl<T: Archive> Archive for ElementWise<'_, T> {
+            type Archived = ArchivedVec<T::Archived>;
+            type Resolver = VecResolver;
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedVec::resolve_from_len(self.0.len(), pos, resolver, out);
+            }
+        }
+
+        impl<T, S> Serialize<S> for ElementWise<'_, T>
+        where
+            T: Serialize<S>,
+            S: Fallible + Allocator + Writer + ?Sized,
+        {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedVec::<T::Archived>::serialize_from_iter::<T, _, _>(
+                    self.0.iter(),
+                    serializer,
+                )
+            }
+        }
+
+        fn check<T>(values: Vec<T>)
+        where
+            T: Serialize<Strategy<DefaultSerializer, Failure>>,
+        {
+            assert!(T::COPY_OPTIMIZATION.is_enabled());
+
+            let copied = to_bytes::<_, 256, Failure>(&values).unwrap();
+            let element_wise =
+                to_bytes::<_, 256, Failure>(&ElementWise(&values)).unwrap();
+            assert_eq!(copied.as_slice(), element_wise.as_slice());
+
+            let boxed = values.into_boxed_slice();
+            let copied = to_bytes::<_, 256, Failure>(&boxed).unwrap();
+            assert_eq!(copied.as_slice(), element_wise.as_slice());
+        }
+
+        check::<u8>((0..=255).collect());
+        check::<i16>((-100..100).collect());
+        check::<u32>((0..1000).map(|i| i * 7919).collect());
+        check::<u64>((0..1000).map(|i| i << 40 | i).collect());
+        check::<f64>((0..100).map(|i| i as f64 / 3.0).collect());
+        check::<char>("hello, world".chars().collect());
+        check::<[u16; 3]>((0..50).map(|i| [i, i + 1, i + 2]).collect());
+        check::<()>(vec![(); 10]);
+
+        assert!(!<String as Archive>::COPY_OPTIMIZATION.is_enabled());
+        assert!(!<Vec<u8> as Archive>::COPY_OPTIMIZATION.is_enabled());
+        assert!(!<(u32, u32) as Archive>::COPY_OPTIMIZATION.is_enabled());
+        assert!(!<[String; 2] as Archive>::COPY_OPTIMIZATION.is_enabled());
     }
 
     #[test]
@@ -646,7 +1009,10 @@ mod tests {
         #[archive_attr(derive(Debug))]
         // The derive macros don't apply the right bounds from Box so we have to
         // manually specify what bounds to apply
-        #[archive(serialize_bounds(__S: Writer))]
+        #[archive(
+            serialize_bounds(__S: Writer),
+            deserialize_bounds(__D: AllocationLimiter),
+        )]
         enum Node {
             Nil,
             Cons(#[omit_bounds] Box<Node>),
@@ -663,7 +1029,10 @@ mod tests {
         #[archive_attr(derive(Debug))]
         // The derive macros don't apply the right bounds from Box so we have to
         // manually specify what bounds to apply
-        #[archive(serialize_bounds(__S: Writer))]
+        #[archive(
+            serialize_bounds(__S: Writer),
+            deserialize_bounds(__D: AllocationLimiter),
+        )]
         pub enum LinkedList<T: Archive>
         where
             T::Archived: core::fmt::Debug,
@@ -1022,98 +1391,505 @@ mod tests {
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn derive_attributes() {
-        #[derive(Archive, Debug, PartialEq)]
-        #[archive(archived = ATest, resolver = RTest, compare(PartialEq))]
-        #[archive_attr(derive(Debug))]
-        struct Test {
-            a: i32,
-            b: Option<u32>,
-            c: String,
-            d: Vec<i32>,
-        }
-
-        impl<S> Serialize<S> for Test
-        where
-            S: Fallible + ?Sized,
-            S::Error: Error,
-            i32: Serialize<S>,
-            Option<u32>: Serialize<S>,
-            String: Serialize<S>,
-            Vec<i32>: Serialize<S>,
-        {
-            fn serialize(&self, serializer: &mut S) -> Result<RTest, S::Error> {
-                Ok(RTest {
-                    a: self.a.serialize(serializer)?,
-                    b: self.b.serialize(serializer)?,
-                    c: self.c.serialize(serializer)?,
-                    d: self.d.serialize(serializer)?,
-                })
-            }
-        }
+    fn archive_weak_tombstone() {
+        use rkyv::with::WeakTombstone;
 
-        impl<D> Deserialize<Test, D> for ATest
-        where
-            D: Fallible + ?Sized,
-            D::Error: Error,
-            Archived<i32>: Deserialize<i32, D>,
-            Archived<Option<u32>>: Deserialize<Option<u32>, D>,
-            Archived<String>: Deserialize<String, D>,
-            Archived<Vec<i32>>: Deserialize<Vec<i32>, D>,
-        {
-            fn deserialize(
-                &self,
-                deserializer: &mut D,
-            ) -> Result<Test, D::Error> {
-                Ok(Test {
-                    a: self.a.deserialize(deserializer)?,
-                    b: self.b.deserialize(deserializer)?,
-                    c: self.c.deserialize(deserializer)?,
-                    d: self.d.deserialize(deserializer)?,
-                })
-            }
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            first: Rc<u32>,
+            #[with(WeakTombstone)]
+            live: Option<Weak<u32>>,
+            #[with(WeakTombstone)]
+            dangling: Option<Weak<u32>>,
+            #[with(WeakTombstone)]
+            none: Option<Weak<u32>>,
+            second: Rc<u32>,
+            #[with(WeakTombstone)]
+            never_set: Option<Weak<u32>>,
+            #[with(WeakTombstone)]
+            live_second: Option<Weak<u32>>,
         }
 
+        let first = Rc::new(10);
+        let second = Rc::new(20);
         let value = Test {
-            a: 42,
-            b: Some(12),
-            c: "hello world".to_string(),
-            d: vec![1, 2, 3, 4],
+            first: first.clone(),
+            live: Some(Rc::downgrade(&first)),
+            dangling: Some(Rc::downgrade(&Rc::new(30))),
+            none: None,
+            second: second.clone(),
+            never_set: Some(Weak::new()),
+            live_second: Some(Rc::downgrade(&second)),
         };
 
-        test_archive(&value);
+        let serializer = serialize_into::<_, _, Failure>(
+            &value,
+            DefaultSerializer::default(),
+        )
+        .unwrap();
+        let buf = serializer.into_writer();
+
+        let archived =
+            unsafe { access_unchecked::<ArchivedTest>(buf.as_ref()) };
+        assert_eq!(*archived.first, 10);
+        assert_eq!(**archived.live.upgrade().unwrap(), 10);
+        assert!(core::ptr::eq(
+            archived.first.get(),
+            archived.live.upgrade().unwrap().get(),
+        ));
+        assert!(archived.dangling.is_dangling());
+        assert!(archived.dangling.upgrade().is_none());
+        assert!(archived.none.is_none());
+        assert!(!archived.none.is_dangling());
+        assert_eq!(*archived.second, 20);
+        assert!(archived.never_set.is_dangling());
+        assert_eq!(**archived.live_second.upgrade().unwrap(), 20);
+
+        let mut deserializer = DefaultDeserializer::default();
+        let deserialized =
+            deserialize::<Test, _, Failure>(archived, &mut deserializer)
+                .unwrap();
+        core::mem::drop(deserializer);
+
+        let live = deserialized.live.as_ref().unwrap().upgrade().unwrap();
+        assert!(Rc::ptr_eq(&deserialized.first, &live));
+        let dangling = deserialized.dangling.as_ref().unwrap();
+        assert!(dangling.upgrade().is_none());
+        assert!(dangling.ptr_eq(&Weak::new()));
+        assert!(deserialized.none.is_none());
+        assert!(deserialized.never_set.is_some());
+        let live_second = deserialized
+            .live_second
+            .as_ref()
+            .unwrap()
+            .upgrade()
+            .unwrap();
+        assert!(Rc::ptr_eq(&deserialized.second, &live_second));
+        assert_eq!(*live_second, 20);
     }
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn compare() {
-        #[derive(Archive, Serialize, Deserialize)]
-        #[archive(compare(PartialEq, PartialOrd))]
-        pub struct UnitFoo;
-
-        #[derive(Archive, Serialize, Deserialize)]
-        #[archive(compare(PartialEq, PartialOrd))]
-        pub struct TupleFoo(i32);
+    fn pooled_shared_ptr() {
+        use rkyv::de::{Pool, SharedPool};
 
         #[derive(Archive, Serialize, Deserialize)]
-        #[archive(compare(PartialEq, PartialOrd))]
-        pub struct StructFoo {
-            t: i32,
+        struct First {
+            title: Rc<str>,
+            data: Rc<[u32]>,
+            name: Rc<String>,
         }
 
         #[derive(Archive, Serialize, Deserialize)]
-        #[archive(compare(PartialEq, PartialOrd))]
-        pub enum EnumFoo {
-            #[allow(dead_code)]
-            Foo(i32),
+        struct Second {
+            id: u64,
+            data: Rc<[u32]>,
+            name: Rc<String>,
+            title: Rc<str>,
         }
-    }
 
-    #[test]
-    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn default_type_parameters() {
-        #[derive(Archive, Serialize, Deserialize)]
-        pub struct TupleFoo<T = i32>(T);
+        let title = Rc::<str>::from("a shared title");
+        let data = Rc::<[u32]>::from([1, 2, 3].as_slice());
+        let name = Rc::new("a shared name".to_string());
+        let first = to_bytes::<_, 256, Failure>(&First {
+            title: title.clone(),
+            data: data.clone(),
+            name: name.clone(),
+        })
+        .unwrap();
+        let second = to_bytes::<_, 256, Failure>(&Second {
+            id: 42,
+            data,
+            name,
+            title,
+        })
+        .unwrap();
+        let first = unsafe { access_unchecked::<ArchivedFirst>(&first) };
+        let second = unsafe { access_unchecked::<ArchivedSecond>(&second) };
+
+        let mut pool = SharedPool::content_keyed();
+        let a = deserialize::<First, _, Failure>(
+            first,
+            &mut Pool::Pooled(&mut pool),
+        )
+        .unwrap();
+        let b = deserialize::<Second, _, Failure>(
+            second,
+            &mut Pool::Pooled(&mut pool),
+        )
+        .unwrap();
+        assert!(Rc::ptr_eq(&a.title, &b.title));
+        assert!(Rc::ptr_eq(&a.data, &b.data));
+        assert_eq!(*a.data, [1, 2, 3]);
+        // Strings contain relative pointers, so they are keyed by address
+        assert!(!Rc::ptr_eq(&a.name, &b.name));
+        assert_eq!(a.name, b.name);
+        assert_eq!(pool.len(), 4);
+
+        // The pool holds a reference to each shared pointer
+        assert_eq!(Rc::strong_count(&a.title), 3);
+        pool.clear();
+        assert!(pool.is_empty());
+        assert_eq!(Rc::strong_count(&a.title), 2);
+
+        // Address-keyed and unpooled deserializers don't unify shared
+        // pointers across archives
+        let mut pool = SharedPool::new();
+        let a = deserialize::<First, _, Failure>(
+            first,
+            &mut Pool::Pooled(&mut pool),
+        )
+        .unwrap();
+        let b = deserialize::<Second, _, Failure>(
+            second,
+            &mut Pool::Pooled(&mut pool),
+        )
+        .unwrap();
+        assert!(!Rc::ptr_eq(&a.title, &b.title));
+        assert!(!Rc::ptr_eq(&a.data, &b.data));
+
+        let a = deserialize::<First, _, Failure>(first, &mut Pool::default())
+            .unwrap();
+        let b = deserialize::<Second, _, Failure>(second, &mut Pool::default())
+            .unwrap();
+        assert!(!Rc::ptr_eq(&a.title, &b.title));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn pooled_shared_ptr_hash_collision() {
+        use rkyv::de::{Pool, SharedPool};
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            bytes: Rc<[u8]>,
+            string: Rc<str>,
+            other_bytes: Rc<[u8]>,
+            unsigned: Rc<u32>,
+            signed: Rc<i32>,
+        }
+
+        let value = Test {
+            bytes: Rc::from(b"abcd".as_slice()),
+            string: Rc::from("abcd"),
+            other_bytes: Rc::from(b"abce".as_slice()),
+            unsigned: Rc::new(1),
+            signed: Rc::new(1),
+        };
+        let first = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let second = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        // Every value hashes the same, so only byte equality and the type of
+        // the shared pointer distinguish them
+        let mut pool = SharedPool::content_keyed_with_hash(|_| 0);
+        let mut deserialize_from = |bytes: &[u8]| {
+            let archived = unsafe { access_unchecked::<ArchivedTest>(bytes) };
+            deserialize::<Test, _, Failure>(
+                archived,
+                &mut Pool::Pooled(&mut pool),
+            )
+            .unwrap()
+        };
+        let a = deserialize_from(&first);
+        let b = deserialize_from(&second);
+
+        assert_eq!(*a.bytes, *b"abcd");
+        assert_eq!(&*a.string, "abcd");
+        assert_eq!(*a.other_bytes, *b"abce");
+        assert_eq!(*a.unsigned, 1);
+        assert_eq!(*a.signed, 1);
+        assert!(!Rc::ptr_eq(&a.bytes, &a.other_bytes));
+        assert_ne!(a.bytes.as_ptr(), a.string.as_ptr());
+        assert_ne!(
+            Rc::as_ptr(&a.unsigned).cast::<()>(),
+            Rc::as_ptr(&a.signed).cast::<()>(),
+        );
+
+        assert!(Rc::ptr_eq(&a.bytes, &b.bytes));
+        assert!(Rc::ptr_eq(&a.string, &b.string));
+        assert!(Rc::ptr_eq(&a.other_bytes, &b.other_bytes));
+        assert!(Rc::ptr_eq(&a.unsigned, &b.unsigned));
+        assert!(Rc::ptr_eq(&a.signed, &b.signed));
+        assert_eq!(pool.len(), 5);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_columnar() {
+        use rkyv::with::{AsColumns, Niche};
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(compare(PartialEq))]
+        #[archive_attr(derive(Debug))]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(columnar)]
+        struct Record {
+            id: u32,
+            price: f64,
+            name: String,
+            location: Point,
+            #[with(Niche)]
+            extra: Option<Box<u16>>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Table {
+            #[with(AsColumns)]
+            records: Vec<Record>,
+        }
+
+        let value = Table {
+            records: (0..10)
+                .map(|i| Record {
+                    id: i,
+                    price: i as f64 * 1.5,
+                    name: format!("record {}", i),
+                    location: Point {
+                        x: i as i32,
+                        y: -(i as i32),
+                    },
+                    extra: if i % 3 == 0 {
+                        Some(Box::new(i as u16))
+                    } else {
+                        None
+                    },
+                })
+                .collect(),
+        };
+
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedTable>(&buf) };
+        let columns = &archived.records;
+
+        assert_eq!(columns.len(), value.records.len());
+        assert!(!columns.is_empty());
+        assert_eq!(columns.id().len(), columns.len());
+        assert_eq!(columns.price().len(), columns.len());
+        assert_eq!(columns.name().len(), columns.len());
+        assert_eq!(columns.location().len(), columns.len());
+        assert_eq!(columns.extra().len(), columns.len());
+
+        for (i, record) in value.records.iter().enumerate() {
+            let row = columns.row(i);
+            assert_eq!(row.index(), i);
+            assert_eq!(*row.id(), record.id);
+            assert_eq!(*row.price(), record.price);
+            assert_eq!(row.name(), &record.name);
+            assert_eq!(row.location(), &record.location);
+            assert_eq!(
+                row.extra().as_ref().map(|x| x.to_native()),
+                record.extra.as_deref().copied(),
+            );
+
+            assert!(core::ptr::eq(row.id(), &columns.id()[i]));
+            assert!(core::ptr::eq(row.price(), &columns.price()[i]));
+            assert!(core::ptr::eq(row.name(), &columns.name()[i]));
+            assert!(core::ptr::eq(row.location(), &columns.location()[i]));
+        }
+        assert!(columns.get_row(columns.len()).is_none());
+        assert_eq!(columns.rows().count(), columns.len());
+        assert!(columns
+            .rows()
+            .zip(value.records.iter())
+            .all(|(row, record)| *row.id() == record.id));
+
+        let deserialized =
+            deserialize::<Table, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized, value);
+
+        let empty = Table {
+            records: Vec::new(),
+        };
+        let buf = to_bytes::<_, 256, Failure>(&empty).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedTable>(&buf) };
+        assert!(archived.records.is_empty());
+        assert!(archived.records.get_row(0).is_none());
+        let deserialized =
+            deserialize::<Table, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized, empty);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn columnar_scan_size() {
+        use core::mem::size_of_val;
+
+        use rkyv::with::AsColumns;
+
+        #[derive(Archive, Serialize)]
+        #[archive(columnar)]
+        struct Record {
+            id: u64,
+            price: f64,
+            quantity: u32,
+            flags: [u8; 12],
+            volume: f64,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Rows {
+            records: Vec<Record>,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Columns {
+            #[with(AsColumns)]
+            records: Vec<Record>,
+        }
+
+        const LEN: usize = 1000;
+        let records = || {
+            (0..LEN)
+                .map(|i| Record {
+                    id: i as u64,
+                    price: i as f64 * 0.25,
+                    quantity: i as u32,
+                    flags: [i as u8; 12],
+                    volume: i as f64 * 2.0,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let rows_buf =
+            to_bytes::<_, 256, Failure>(&Rows { records: records() }).unwrap();
+        let rows = unsafe { access_unchecked::<ArchivedRows>(&rows_buf) };
+        let columns_buf =
+            to_bytes::<_, 256, Failure>(&Columns { records: records() })
+                .unwrap();
+        let columns =
+            unsafe { access_unchecked::<ArchivedColumns>(&columns_buf) };
+
+        // Summing the prices of every row reads every byte of every row when
+        // the rows are stored together, but only the prices when stored as
+        // columns.
+        let row_sum = rows
+            .records
+            .iter()
+            .map(|r| r.price.to_native())
+            .sum::<f64>();
+        let column_sum = columns
+            .records
+            .price()
+            .iter()
+            .map(|p| p.to_native())
+            .sum::<f64>();
+        assert_eq!(row_sum, column_sum);
+
+        let row_bytes = size_of_val(rows.records.as_slice());
+        let column_bytes = size_of_val(columns.records.price().as_slice());
+        assert_eq!(column_bytes, LEN * 8);
+        assert_eq!(
+            row_bytes / column_bytes,
+            core::mem::size_of::<ArchivedRecord>() / 8,
+        );
+        assert!(column_bytes * 5 <= row_bytes);
+
+        // Every column is contiguous and inside the archive
+        let start = columns_buf.as_ptr() as usize;
+        let end = start + columns_buf.len();
+        let price_start = columns.records.price().as_ptr() as usize;
+        assert!(start <= price_start && price_start + column_bytes <= end);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn derive_attributes() {
+        #[derive(Archive, Debug, PartialEq)]
+        #[archive(archived = ATest, resolver = RTest, compare(PartialEq))]
+        #[archive_attr(derive(Debug))]
+        struct Test {
+            a: i32,
+            b: Option<u32>,
+            c: String,
+            d: Vec<i32>,
+        }
+
+        impl<S> Serialize<S> for Test
+        where
+            S: Fallible + ?Sized,
+            S::Error: Error,
+            i32: Serialize<S>,
+            Option<u32>: Serialize<S>,
+            String: Serialize<S>,
+            Vec<i32>: Serialize<S>,
+        {
+            fn serialize(&self, serializer: &mut S) -> Result<RTest, S::Error> {
+                Ok(RTest {
+                    a: self.a.serialize(serializer)?,
+                    b: self.b.serialize(serializer)?,
+                    c: self.c.serialize(serializer)?,
+                    d: self.d.serialize(serializer)?,
+                })
+            }
+        }
+
+        impl<D> Deserialize<Test, D> for ATest
+        where
+            D: Fallible + ?Sized,
+            D::Error: Error,
+            Archived<i32>: Deserialize<i32, D>,
+            Archived<Option<u32>>: Deserialize<Option<u32>, D>,
+            Archived<String>: Deserialize<String, D>,
+            Archived<Vec<i32>>: Deserialize<Vec<i32>, D>,
+        {
+            fn deserialize(
+                &self,
+                deserializer: &mut D,
+            ) -> Result<Test, D::Error> {
+                Ok(Test {
+                    a: self.a.deserialize(deserializer)?,
+                    b: self.b.deserialize(deserializer)?,
+                    c: self.c.deserialize(deserializer)?,
+                    d: self.d.deserialize(deserializer)?,
+                })
+            }
+        }
+
+        let value = Test {
+            a: 42,
+            b: Some(12),
+            c: "hello world".to_string(),
+            d: vec![1, 2, 3, 4],
+        };
+
+        test_archive(&value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn compare() {
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(compare(PartialEq, PartialOrd))]
+        pub struct UnitFoo;
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(compare(PartialEq, PartialOrd))]
+        pub struct TupleFoo(i32);
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(compare(PartialEq, PartialOrd))]
+        pub struct StructFoo {
+            t: i32,
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(compare(PartialEq, PartialOrd))]
+        pub enum EnumFoo {
+            #[allow(dead_code)]
+            Foo(i32),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn default_type_parameters() {
+        #[derive(Archive, Serialize, Deserialize)]
+        pub struct TupleFoo<T = i32>(T);
 
         #[derive(Archive, Serialize, Deserialize)]
         pub struct StructFoo<T = i32> {
@@ -1285,6 +2061,169 @@ mod tests {
         assert_eq!(core::mem::align_of::<ArchivedCAlignRepr>(), 8);
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn reorder_fields() {
+        use core::{
+            mem::{align_of, size_of},
+            ptr::addr_of,
+        };
+
+        #[derive(Archive, Deserialize, Serialize, Debug, PartialEq)]
+        #[archive(compare(PartialEq))]
+        #[archive_attr(derive(Debug))]
+        struct Reordered {
+            a: u8,
+            b: u64,
+            c: u16,
+        }
+
+        #[derive(Archive, Deserialize, Serialize, Debug, PartialEq)]
+        #[archive(compare(PartialEq), preserve_order)]
+        #[archive_attr(derive(Debug))]
+        struct Preserved {
+            a: u8,
+            b: u64,
+            c: u16,
+        }
+
+        #[derive(Archive, Deserialize, Serialize, Debug, PartialEq)]
+        #[archive(compare(PartialEq))]
+        #[archive_attr(derive(Debug))]
+        struct Mixed {
+            a: bool,
+            b: String,
+            c: [u16; 3],
+            d: u32,
+            e: Option<u8>,
+        }
+
+        assert_eq!(size_of::<ArchivedReordered>(), 16);
+        assert_eq!(size_of::<ArchivedPreserved>(), 24);
+        assert_eq!(align_of::<ArchivedReordered>(), 8);
+
+        let value = Reordered { a: 1, b: 2, c: 3 };
+        test_archive(&value);
+        test_archive(&Preserved { a: 1, b: 2, c: 3 });
+        test_archive(&Mixed {
+            a: true,
+            b: "hello world".to_string(),
+            c: [1, 2, 3],
+            d: 4,
+            e: Some(5),
+        });
+
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            unsafe { access_unchecked::<ArchivedReordered>(bytes.as_ref()) };
+        let base = archived as *const ArchivedReordered as usize;
+        assert_eq!(addr_of!(archived.b) as usize - base, 0);
+        assert_eq!(addr_of!(archived.c) as usize - base, 8);
+        assert_eq!(addr_of!(archived.a) as usize - base, 10);
+        assert_eq!(archived.a, 1);
+        assert_eq!(archived.b, 2);
+        assert_eq!(archived.c, 3);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn pack_flags() {
+        use core::mem::size_of;
+
+        #[derive(
+            Archive, Deserialize, Serialize, Clone, Copy, Debug, PartialEq,
+        )]
+        #[archive(pack_flags)]
+        enum Color {
+            Red,
+            Green,
+            Blue,
+            Cyan,
+            Magenta,
+        }
+
+        #[derive(Archive, Deserialize, Serialize, Debug, PartialEq)]
+        #[archive(pack_flags)]
+        #[archive_attr(derive(Debug))]
+        struct Packed {
+            name: String,
+            a: bool,
+            b: bool,
+            c: bool,
+            #[archive(bits = 3)]
+            color: Color,
+            d: bool,
+            #[with(rkyv::with::Skip)]
+            skipped: bool,
+        }
+
+        #[derive(Archive, Deserialize, Serialize, Debug, PartialEq)]
+        #[archive(pack_flags)]
+        #[archive_attr(derive(Debug))]
+        struct ManyFlags {
+            id: u32,
+            f0: bool,
+            f1: bool,
+            f2: bool,
+            f3: bool,
+            f4: bool,
+            f5: bool,
+            f6: bool,
+            f7: bool,
+            f8: bool,
+            f9: bool,
+        }
+
+        assert_eq!(size_of::<ArchivedManyFlags>(), 8);
+        assert_eq!(size_of::<Archived<(u32, [bool; 10])>>(), 16);
+
+        for (i, color) in [
+            Color::Red,
+            Color::Green,
+            Color::Blue,
+            Color::Cyan,
+            Color::Magenta,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let value = Packed {
+                name: "hello world".to_string(),
+                a: i % 2 == 0,
+                b: i % 3 == 0,
+                c: true,
+                color,
+                d: false,
+                skipped: false,
+            };
+            test_archive_with(&value, |v, a| {
+                a.name == v.name
+                    && a.a() == v.a
+                    && a.b() == v.b
+                    && a.c() == v.c
+                    && a.color() == v.color
+                    && a.d() == v.d
+            });
+        }
+
+        let value = ManyFlags {
+            id: 42,
+            f0: true,
+            f1: false,
+            f2: true,
+            f3: true,
+            f4: false,
+            f5: false,
+            f6: true,
+            f7: false,
+            f8: false,
+            f9: true,
+        };
+        test_archive_with(&value, |v, a| {
+            a.id == v.id && a.f0() && !a.f1() && a.f6() && a.f9()
+        });
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn archive_as() {
@@ -1967,18 +2906,45 @@ mod tests {
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn with_boxed_inline_copy_optimize() {
-        use rkyv::with::{BoxedInline, CopyOptimize};
+    fn with_compact() {
+        use core::mem::size_of;
+
+        use rkyv::{
+            compact::{
+                ArchivedCompactBox, ArchivedCompactString, ArchivedCompactVec,
+            },
+            with::Compact,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Test {
+            #[with(Compact)]
+            values: Vec<u32>,
+            #[with(Compact)]
+            names: Vec<String>,
+            #[with(Compact)]
+            name: String,
+            #[with(Compact)]
+            boxed: Box<u64>,
+            #[with(Compact)]
+            boxed_str: Box<str>,
+        }
 
         #[derive(Archive, Serialize, Deserialize)]
-        struct Test<'a> {
-            #[with(CopyOptimize, BoxedInline)]
-            bytes: &'a [u8],
+        struct TestNotCompact {
+            values: Vec<u32>,
+            names: Vec<String>,
+            name: String,
+            boxed: Box<u64>,
+            boxed_str: Box<str>,
         }
 
-        let bytes = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
         let value = Test {
-            bytes: bytes.as_ref(),
+            values: vec![1, 2, 3, 4],
+            names: vec!["a".to_string(), "hello world".to_string()],
+            name: "a string that doesn't fit inline".to_string(),
+            boxed: Box::new(42),
+            boxed_str: "hello world".into(),
         };
         let result = serialize_into::<_, _, Failure>(
             &value,
@@ -1989,7 +2955,177 @@ mod tests {
         let archived =
             unsafe { access_unchecked::<ArchivedTest>(result.as_slice()) };
 
-        assert_eq!(&*archived.bytes, value.bytes);
+        assert_eq!(archived.values, value.values);
+        assert_eq!(archived.values.len(), 4);
+        assert_eq!(archived.values[2], 3);
+        assert_eq!(archived.names.len(), 2);
+        assert_eq!(archived.names[1], "hello world");
+        assert_eq!(archived.name, value.name);
+        assert_eq!(archived.name.as_str(), value.name.as_str());
+        assert_eq!(archived.boxed, value.boxed);
+        assert_eq!(*archived.boxed, 42);
+        assert_eq!(&*archived.boxed_str, "hello world");
+
+        let deserialized =
+            deserialize::<Test, _, Infallible>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized, value);
+
+        // Compact strings and vecs take a 16-bit offset and length, and compact
+        // boxes of sized types take only a 16-bit offset
+        assert_eq!(size_of::<ArchivedCompactVec<Archived<u32>>>(), 4);
+        assert_eq!(size_of::<ArchivedCompactString>(), 4);
+        assert_eq!(size_of::<ArchivedCompactBox<Archived<u64>>>(), 2);
+        assert!(
+            size_of::<Archived<Test>>() < size_of::<Archived<TestNotCompact>>()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn with_compact_empty() {
+        use rkyv::with::Compact;
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Test {
+            #[with(Compact)]
+            values: Vec<u32>,
+            #[with(Compact)]
+            name: String,
+        }
+
+        let value = Test {
+            values: Vec::new(),
+            name: String::new(),
+        };
+        let result = serialize_into::<_, _, Failure>(
+            &value,
+            DefaultSerializer::default(),
+        )
+        .unwrap()
+        .into_writer();
+        let archived =
+            unsafe { access_unchecked::<ArchivedTest>(result.as_slice()) };
+
+        assert!(archived.values.is_empty());
+        assert!(archived.name.is_empty());
+        assert_eq!(
+            deserialize::<Test, _, Infallible>(archived, &mut ()).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn with_compact_overflow() {
+        use rkyv::{
+            compact::CompactOverflow, rancor::BoxedError, with::Compact,
+        };
+
+        #[derive(Archive, Serialize)]
+        struct CompactVec<T> {
+            #[with(Compact)]
+            value: Vec<T>,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct CompactString {
+            #[with(Compact)]
+            value: String,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct CompactBox {
+            #[with(Compact)]
+            value: Box<[u8]>,
+        }
+
+        fn error<T>(value: &T) -> String
+        where
+            T: Serialize<Strategy<DefaultSerializer, BoxedError>>,
+        {
+            serialize_into::<_, _, BoxedError>(
+                value,
+                DefaultSerializer::default(),
+            )
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+        }
+
+        // Lengths must fit in 16 bits
+        let value = CompactVec {
+            value: vec![(); 0x1_0000],
+        };
+        assert_eq!(
+            error(&value),
+            CompactOverflow::Length(0x1_0000).to_string()
+        );
+
+        // The largest length is allowed
+        let value = CompactVec {
+            value: vec![(); 0xFFFF],
+        };
+        serialize_into::<_, _, Failure>(&value, DefaultSerializer::default())
+            .unwrap();
+
+        // Data must be close enough for a 16-bit pointer
+        let value = CompactVec {
+            value: vec![0u32; 10_000],
+        };
+        assert_eq!(
+            error(&value),
+            CompactOverflow::Distance(40_000).to_string()
+        );
+
+        let value = CompactString {
+            value: "a".repeat(40_000),
+        };
+        assert_eq!(
+            error(&value),
+            CompactOverflow::Distance(40_000).to_string()
+        );
+
+        let value = CompactBox {
+            value: vec![0; 40_000].into_boxed_slice(),
+        };
+        assert_eq!(
+            error(&value),
+            CompactOverflow::Distance(40_000).to_string()
+        );
+
+        // The farthest distance is allowed
+        let value = CompactBox {
+            value: vec![0; 0x8000].into_boxed_slice(),
+        };
+        serialize_into::<_, _, Failure>(&value, DefaultSerializer::default())
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn with_boxed_inline_copy_optimize() {
+        use rkyv::with::{BoxedInline, CopyOptimize};
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test<'a> {
+            #[with(CopyOptimize, BoxedInline)]
+            bytes: &'a [u8],
+        }
+
+        let bytes = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let value = Test {
+            bytes: bytes.as_ref(),
+        };
+        let result = serialize_into::<_, _, Failure>(
+            &value,
+            DefaultSerializer::default(),
+        )
+        .unwrap()
+        .into_writer();
+        let archived =
+            unsafe { access_unchecked::<ArchivedTest>(result.as_slice()) };
+
+        assert_eq!(&*archived.bytes, value.bytes);
     }
 
     // #[test]
@@ -2058,6 +3194,66 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn with_unsafe_cell() {
+        use core::cell::Cell;
+
+        use rkyv::with::Unsafe;
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[with(Unsafe)]
+            inner: Cell<u32>,
+        }
+
+        let value = Test {
+            inner: Cell::new(100),
+        };
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedTest>(&buf) };
+        assert_eq!(archived.inner.get(), 100);
+
+        let deserialized =
+            deserialize::<Test, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized.inner.get(), 100);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn with_lock_ref_cell() {
+        use core::cell::RefCell;
+
+        use rkyv::with::Lock;
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[with(Lock)]
+            inner: RefCell<String>,
+        }
+
+        let value = Test {
+            inner: RefCell::new("hello world".to_string()),
+        };
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedTest>(&buf) };
+        assert_eq!(*archived.inner.value(), "hello world");
+
+        let deserialized =
+            deserialize::<Test, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(*deserialized.inner.borrow(), "hello world");
+
+        // Shared borrows may be held while serializing, but mutable borrows
+        // cause serialization to fail
+        let borrow = value.inner.borrow();
+        to_bytes::<_, 256, Failure>(&value).unwrap();
+        drop(borrow);
+
+        let borrow = value.inner.borrow_mut();
+        assert!(to_bytes::<_, 256, Failure>(&value).is_err());
+        drop(borrow);
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn archive_crate_path() {
@@ -2202,6 +3398,132 @@ mod tests {
         assert_eq!(value, deserialized);
     }
 
+    // Large keys keep the fanout of the B-tree low so that it has several
+    // levels of inner nodes
+    #[cfg(not(feature = "pointer_width_16"))]
+    type BigKey = [u8; 256];
+
+    #[cfg(not(feature = "pointer_width_16"))]
+    fn big_key(i: u32) -> BigKey {
+        let mut key = [0; 256];
+        key[..4].copy_from_slice(&i.to_be_bytes());
+        key
+    }
+
+    #[cfg(not(feature = "pointer_width_16"))]
+    fn big_btree_map() -> (BTreeMap<BigKey, u32>, AlignedVec) {
+        // Only even keys are present so that odd keys are missing
+        let value = (0..20_000u32)
+            .map(|i| (big_key(i * 2), i))
+            .collect::<BTreeMap<_, _>>();
+        let bytes =
+            serialize_into::<_, _, Failure>(&value, AlignedVec::new()).unwrap();
+        (value, bytes)
+    }
+
+    // A small deterministic PRNG so that failures are reproducible
+    #[cfg(not(feature = "pointer_width_16"))]
+    struct XorShift(u64);
+
+    #[cfg(not(feature = "pointer_width_16"))]
+    impl XorShift {
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 32) as u32
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    #[cfg(not(feature = "pointer_width_16"))]
+    fn btree_map_get_many_sorted() {
+        let (_, bytes) = big_btree_map();
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<BigKey, u32>>>(
+                bytes.as_slice(),
+            )
+        };
+
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100 {
+            // Batches range from dense runs of adjacent keys to sparse keys
+            // spread over the whole map, and may go past the end of the map
+            let len = rng.next() % 200;
+            let max_gap = 1 << (rng.next() % 10);
+            let mut i = rng.next() % 40_000;
+            let mut keys = Vec::new();
+            for _ in 0..len {
+                keys.push(big_key(i));
+                i += rng.next() % max_gap;
+            }
+
+            let results = archived.get_many_sorted(&keys).collect::<Vec<_>>();
+            assert_eq!(results.len(), keys.len());
+            for (key, result) in keys.iter().zip(results) {
+                assert_eq!(result, archived.get_key_value(key));
+            }
+        }
+
+        assert!(archived.get_many_sorted::<BigKey>(&[]).next().is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    #[cfg(not(feature = "pointer_width_16"))]
+    fn btree_map_cursor() {
+        let (value, bytes) = big_btree_map();
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<BigKey, u32>>>(
+                bytes.as_slice(),
+            )
+        };
+
+        // Iterating a cursor visits every entry in order
+        assert!(archived.cursor().eq(archived.iter()));
+
+        let mut cursor = archived.cursor();
+        assert_eq!(cursor.peek(), archived.iter().next());
+
+        // Seeking moves to the first key greater than or equal to the key
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        let mut i = 0;
+        while i < 40_000 {
+            let key = big_key(i);
+            let expected = value.range(key..).next().map(|(k, _)| k);
+            let found = cursor.seek(&key);
+            assert_eq!(found.map(|(k, _)| k), expected);
+            assert_eq!(cursor.peek(), found);
+
+            // Seeking backward doesn't move the cursor
+            assert_eq!(cursor.seek(&big_key(0)), found);
+
+            i += rng.next() % 500;
+        }
+
+        assert_eq!(cursor.seek(&big_key(40_000)), None);
+        assert_eq!(cursor.peek(), None);
+        assert_eq!(cursor.next(), None);
+
+        // Iterating continues from the position of the cursor
+        let mut cursor = archived.cursor();
+        cursor.seek(&big_key(1001));
+        assert!(cursor.eq(archived.iter().skip(501)));
+
+        let empty =
+            to_bytes::<_, 256, Failure>(&BTreeMap::<u32, u32>::new()).unwrap();
+        let empty = unsafe {
+            access_unchecked::<Archived<BTreeMap<u32, u32>>>(empty.as_slice())
+        };
+        let mut cursor = empty.cursor();
+        assert_eq!(cursor.peek(), None);
+        assert_eq!(cursor.seek(&Archived::<u32>::from_native(1)), None);
+        assert_eq!(cursor.next(), None);
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn archive_zst_containers() {
@@ -2323,4 +3645,1455 @@ mod tests {
 
         drop(ManuallyDrop::into_inner(vec));
     }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_transparent_newtypes() {
+        use core::mem::ManuallyDrop;
+
+        use rkyv::{
+            rend::{u16_be, u64_le},
+            string::ArchivedString,
+        };
+
+        // Two crates which agree that IDs are archived as plain `u64`s
+        mod users {
+            use rkyv::{Archive, Deserialize, Serialize};
+
+            #[derive(
+                Archive, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+            )]
+            #[archive(transparent)]
+            pub struct UserId(pub u64);
+        }
+
+        mod accounts {
+            use core::marker::PhantomData;
+
+            use rkyv::{rend::u64_le, Archive, Deserialize, Serialize};
+
+            #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+            #[archive(transparent, as = "u64_le")]
+            #[repr(transparent)]
+            pub struct AccountId {
+                pub id: u64,
+                pub _marker: PhantomData<fn() -> Self>,
+            }
+
+            impl AccountId {
+                pub fn new(id: u64) -> Self {
+                    Self {
+                        id,
+                        _marker: PhantomData,
+                    }
+                }
+            }
+        }
+
+        use accounts::AccountId;
+        use users::UserId;
+
+        let ids = vec![UserId(1), UserId(0xdead_beef), UserId(u64::MAX)];
+        let bytes = to_bytes::<_, 256, Failure>(&ids).unwrap();
+
+        // All three views are the same archived type
+        let archived: &Archived<Vec<u64>> =
+            unsafe { access_unchecked::<Archived<Vec<UserId>>>(&bytes) };
+        let as_accounts: &Archived<Vec<AccountId>> = archived;
+        assert_eq!(archived.as_slice(), [1, 0xdead_beef, u64::MAX]);
+
+        let accounts =
+            deserialize::<Vec<AccountId>, _, Failure>(as_accounts, &mut ())
+                .unwrap();
+        assert_eq!(
+            accounts,
+            [
+                AccountId::new(1),
+                AccountId::new(0xdead_beef),
+                AccountId::new(u64::MAX)
+            ],
+        );
+        let bytes = to_bytes::<_, 256, Failure>(&accounts).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<UserId>>>(&bytes) };
+        assert_eq!(
+            deserialize::<Vec<UserId>, _, Failure>(archived, &mut ()).unwrap(),
+            ids,
+        );
+
+        // Newtypes with a named archived type convert to and from it
+        let archived_id: u64_le = AccountId::new(42).into();
+        assert_eq!(archived_id, 42);
+        assert_eq!(AccountId::from(archived_id), AccountId::new(42));
+
+        // Wrappers apply to the wrapped field
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(transparent, as = "u16_be")]
+        struct Port(#[with(rkyv::with::AsBigEndian)] u16);
+
+        let bytes = to_bytes::<_, 256, Failure>(&Port(8080)).unwrap();
+        let archived = unsafe { access_unchecked::<u16_be>(&bytes) };
+        assert_eq!(*archived, u16_be::from_native(8080));
+        assert_eq!(
+            deserialize::<Port, _, Failure>(archived, &mut ()).unwrap(),
+            Port(8080),
+        );
+        assert_eq!(Port::from(*archived), Port(8080));
+
+        // Conversions are only available when both directions exist
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(transparent, as = "ArchivedString")]
+        struct Name(String);
+
+        let bytes = to_bytes::<_, 256, Failure>(&Name("name".into())).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedString>(&bytes) };
+        assert_eq!(
+            deserialize::<Name, _, Failure>(archived, &mut ()).unwrap(),
+            Name("name".into()),
+        );
+
+        // `ManuallyDrop` archives as the value it wraps
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(transparent)]
+        struct Handle(ManuallyDrop<String>);
+
+        let handle = Handle(ManuallyDrop::new("handle".to_string()));
+        let bytes = to_bytes::<_, 256, Failure>(&handle).unwrap();
+        let archived: &ArchivedString =
+            unsafe { access_unchecked::<Archived<Handle>>(&bytes) };
+        assert_eq!(archived, "handle");
+        let mut deserialized =
+            deserialize::<Handle, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(*deserialized.0, "handle");
+        unsafe {
+            ManuallyDrop::drop(&mut deserialized.0);
+        }
+        drop(ManuallyDrop::into_inner(handle.0));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_interned() {
+        #[cfg(not(feature = "std"))]
+        use alloc::{format, sync::Arc};
+        #[cfg(feature = "std")]
+        use std::sync::Arc;
+
+        use rkyv::{
+            de::{with_interning, Unify},
+            with::{InternedBytes, InternedString},
+        };
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Record {
+            #[with(InternedString)]
+            tag: Arc<str>,
+            #[with(InternedBytes)]
+            key: Arc<[u8]>,
+        }
+
+        // Every record gets its own allocation so that nothing is shared
+        // before serialization.
+        let value = (0..100_000)
+            .map(|i| {
+                let tag = format!("tag-{}", i % 10);
+                Record {
+                    key: Arc::from(tag.as_bytes()),
+                    tag: Arc::from(tag),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<Record>>>(&bytes) };
+
+        let mut deserializer = with_interning(Unify::new());
+        let deserialized =
+            deserialize::<Vec<Record>, _, Failure>(archived, &mut deserializer)
+                .unwrap();
+        assert_eq!(deserializer.interned_strings(), 10);
+        assert_eq!(deserializer.interned_bytes(), 10);
+
+        assert_eq!(deserialized.len(), value.len());
+        for (a, b) in deserialized.iter().zip(value.iter()) {
+            assert_eq!(a.tag, b.tag);
+            assert_eq!(a.key, b.key);
+        }
+
+        let tags = deserialized
+            .iter()
+            .map(|r| r.tag.as_ptr() as usize)
+            .collect::<BTreeSet<_>>();
+        let keys = deserialized
+            .iter()
+            .map(|r| r.key.as_ptr() as usize)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(tags.len(), 10);
+        assert_eq!(keys.len(), 10);
+    }
+
+    #[test]
+    #[cfg(feature = "bytecheck")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_intern() {
+        #[cfg(not(feature = "std"))]
+        use alloc::format;
+        use core::mem::size_of;
+
+        use rkyv::{
+            access, access_mut, validation::util::access_interned, with::Intern,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Row {
+            id: u32,
+            #[with(Intern)]
+            label: String,
+            #[with(Intern)]
+            payload: Vec<u8>,
+            #[with(Intern)]
+            boxed: Box<u64>,
+        }
+
+        const ROWS: u32 = 1_000_000;
+        const DISTINCT: u32 = 100;
+
+        let value = (0..ROWS)
+            .map(|id| {
+                let n = id % DISTINCT;
+                let label = format!("a label which is not inline #{:03}", n);
+                Row {
+                    id,
+                    payload: label.as_bytes().to_vec(),
+                    label,
+                    boxed: Box::new(n as u64),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        // Each distinct label, payload, and box is stored once.
+        let label_len = value[0].label.len();
+        let payloads = DISTINCT as usize * (2 * label_len + size_of::<u64>());
+        let rows = ROWS as usize * size_of::<ArchivedRow>();
+        assert!(bytes.len() <= rows + payloads + 4096);
+
+        // Interned values are only accepted when aliasing is permitted.
+        assert!(access::<Archived<Vec<Row>>, Failure>(&bytes).is_err());
+        assert!(access_mut::<Archived<Vec<Row>>, Failure>(&mut bytes).is_err());
+
+        let archived =
+            access_interned::<Archived<Vec<Row>>, Failure>(&bytes).unwrap();
+        let labels = archived
+            .iter()
+            .map(|r| r.label.as_ptr() as usize)
+            .collect::<BTreeSet<_>>();
+        let payloads = archived
+            .iter()
+            .map(|r| r.payload.as_ptr() as usize)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(labels.len(), DISTINCT as usize);
+        assert_eq!(payloads.len(), DISTINCT as usize);
+
+        let deserialized =
+            deserialize::<Vec<Row>, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    #[cfg(feature = "bytecheck")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_dedup_strings() {
+        #[cfg(not(feature = "std"))]
+        use alloc::{format, string::ToString};
+        use core::mem::size_of;
+
+        use rkyv::{
+            access,
+            ser::{
+                writer::{Dedup, DedupStats, DedupWriter},
+                DedupSerializer,
+            },
+            string::ArchivedString,
+            util::{serialize_into, AlignedVec},
+            validation::util::access_interned,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Node {
+            name: String,
+            kind: String,
+            tags: Vec<String>,
+        }
+
+        const NODES: usize = 10_000;
+        const TAGS: usize = 4;
+        const DISTINCT: usize = 100;
+
+        // Every string is its own allocation, and none of the fields use a
+        // sharing wrapper.
+        let value = (0..NODES)
+            .map(|i| Node {
+                name: format!(
+                    "a node name which is not inline #{:03}",
+                    i % 100
+                ),
+                kind: "inline".to_string(),
+                tags: (0..TAGS)
+                    .map(|t| {
+                        format!(
+                            "a tag which is not inline #{:03}",
+                            (i + t) % 100
+                        )
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        let serialize = |mode| {
+            let serializer = DedupSerializer::<256>::new(
+                DedupWriter::new(AlignedVec::new(), mode),
+                Default::default(),
+                Default::default(),
+            );
+            serialize_into::<_, _, Failure>(&value, serializer)
+                .unwrap()
+                .into_writer()
+        };
+        let plain = serialize(Dedup::None);
+        let dedup = serialize(Dedup::Strings);
+
+        assert_eq!(plain.stats(), DedupStats::default());
+
+        // The short `kind` strings are stored inline and never looked up.
+        let total = NODES * (1 + TAGS);
+        let name_len = value[0].name.len();
+        let tag_len = value[0].tags[0].len();
+        let unique_bytes = DISTINCT * (name_len + tag_len);
+        let saved = NODES * (name_len + TAGS * tag_len) - unique_bytes;
+        assert_eq!(
+            dedup.stats(),
+            DedupStats {
+                unique: 2 * DISTINCT,
+                hits: total - 2 * DISTINCT,
+                bytes_saved: saved,
+            }
+        );
+
+        // Only the distinct strings are written out of line.
+        let bytes = dedup.into_inner();
+        let nodes = NODES * (size_of::<ArchivedNode>())
+            + NODES * TAGS * size_of::<ArchivedString>();
+        assert!(bytes.len() <= nodes + unique_bytes + 4096);
+        // The padding before each list of tags may differ between the two.
+        let plain = plain.into_inner();
+        assert!((bytes.len() + saved).abs_diff(plain.len()) <= NODES * 8);
+
+        // Deduplicated strings alias, so they are only accepted when aliasing
+        // is permitted.
+        assert!(access::<Archived<Vec<Node>>, Failure>(&bytes).is_err());
+        let archived =
+            access_interned::<Archived<Vec<Node>>, Failure>(&bytes).unwrap();
+        let names = archived
+            .iter()
+            .map(|n| n.name.as_ptr() as usize)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(names.len(), DISTINCT);
+
+        let deserialized =
+            deserialize::<Vec<Node>, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    #[cfg(feature = "bytecheck")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_patch() {
+        #[cfg(not(feature = "std"))]
+        use alloc::{format, string::ToString};
+
+        use rkyv::{
+            access,
+            patch::{ArchivePatch, PatchedArchive},
+            util::AlignedVec,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Clone, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Entry {
+            id: u32,
+            name: String,
+            parent: Option<u32>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Clone, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Catalog {
+            version: u64,
+            title: String,
+            entries: Vec<Entry>,
+            tags: Vec<String>,
+        }
+
+        let value = Catalog {
+            version: 1,
+            title: "catalog".to_string(),
+            entries: (0..100_000)
+                .map(|id| Entry {
+                    id,
+                    name: format!("an entry which is not inline #{}", id),
+                    parent: id.checked_sub(1),
+                })
+                .collect(),
+            tags: vec!["a".to_string(), "b".to_string()],
+        };
+        let base = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let mut patch =
+            ArchivePatch::<ArchivedCatalog>::new::<Failure>(&base).unwrap();
+        patch.set::<u64, Failure>(|c| &c.version, &2).unwrap();
+        patch
+            .set::<u32, Failure>(|c| &c.entries[12_345].id, &999_999)
+            .unwrap();
+        let name = "a replacement name which is not inline".to_string();
+        patch
+            .set::<String, Failure>(|c| &c.entries[54_321].name, &name)
+            .unwrap();
+        patch
+            .set::<Option<u32>, Failure>(|c| &c.entries[1].parent, &Some(7))
+            .unwrap();
+        let tags = vec!["c".to_string(), "d".to_string(), "e".to_string()];
+        patch
+            .set::<Vec<String>, Failure>(|c| &c.tags, &tags)
+            .unwrap();
+        assert_eq!(patch.len(), 5);
+
+        // Overlapping a patched value fails, but replacing it again succeeds.
+        assert!(patch
+            .set::<u32, Failure>(|c| c.entries[1].parent.as_ref().unwrap(), &0)
+            .is_err());
+        patch.set::<u64, Failure>(|c| &c.version, &3).unwrap();
+        assert_eq!(patch.len(), 5);
+
+        // The patch is persisted and reloaded separately from the base.
+        let persisted = patch.to_bytes::<Failure>().unwrap().to_vec();
+        assert!(persisted.len() < base.len() / 100);
+        let mut patch_bytes = AlignedVec::new();
+        patch_bytes.extend_from_slice(&persisted);
+
+        let patched = PatchedArchive::<ArchivedCatalog>::new::<Failure>(
+            &base,
+            &patch_bytes,
+        )
+        .unwrap();
+        assert_eq!(*patched.get::<_, Failure>(|c| &c.version).unwrap(), 3);
+        assert_eq!(
+            *patched
+                .get::<_, Failure>(|c| &c.entries[12_345].id)
+                .unwrap(),
+            999_999,
+        );
+        assert_eq!(
+            patched
+                .get::<_, Failure>(|c| &c.entries[54_321].name)
+                .unwrap(),
+            name.as_str(),
+        );
+        assert_eq!(
+            patched
+                .get::<_, Failure>(|c| &c.entries[1].parent)
+                .unwrap()
+                .as_ref()
+                .map(|p| p.to_native()),
+            Some(7),
+        );
+        assert_eq!(patched.get::<_, Failure>(|c| &c.tags).unwrap().len(), 3);
+        assert_eq!(patched.get::<_, Failure>(|c| &c.title).unwrap(), "catalog");
+        assert_eq!(
+            patched.get::<_, Failure>(|c| &c.entries[1].name).unwrap(),
+            "an entry which is not inline #1",
+        );
+
+        // The base archive is unchanged.
+        let archived = access::<ArchivedCatalog, Failure>(&base).unwrap();
+        assert_eq!(archived.version, 1);
+        assert_eq!(archived.entries[12_345].id, 12_345);
+        assert_eq!(archived.tags.len(), 2);
+
+        // A patch only applies to the base archive it was created for.
+        let other = to_bytes::<_, 256, Failure>(&Catalog {
+            entries: Vec::new(),
+            ..value.clone()
+        })
+        .unwrap();
+        assert!(PatchedArchive::<ArchivedCatalog>::new::<Failure>(
+            &other,
+            &patch_bytes,
+        )
+        .is_err());
+
+        let mut expected = value;
+        expected.version = 3;
+        expected.entries[12_345].id = 999_999;
+        expected.entries[54_321].name = name;
+        expected.entries[1].parent = Some(7);
+        expected.tags = tags;
+        let expected = to_bytes::<_, 256, Failure>(&expected).unwrap();
+
+        let materialized = patched.materialize::<Catalog, Failure>().unwrap();
+        assert_eq!(materialized.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn to_bytes_from_borrowed() {
+        use rkyv::{
+            ser::AllocSerializer,
+            util::{to_bytes_from_slice, to_bytes_from_str},
+        };
+
+        fn check_slice<T>(value: &[T])
+        where
+            T: Clone + Serialize<Strategy<AllocSerializer<256>, Failure>>,
+        {
+            let owned = to_bytes::<_, 256, Failure>(&value.to_vec()).unwrap();
+            let borrowed =
+                to_bytes_from_slice::<_, 256, Failure>(value).unwrap();
+            assert_eq!(owned.as_slice(), borrowed.as_slice());
+        }
+
+        fn check_str(value: &str) {
+            let owned =
+                to_bytes::<_, 256, Failure>(&value.to_string()).unwrap();
+            let borrowed = to_bytes_from_str::<256, Failure>(value).unwrap();
+            assert_eq!(owned.as_slice(), borrowed.as_slice());
+        }
+
+        #[derive(Archive, Serialize, Clone)]
+        struct Record {
+            id: u64,
+            name: String,
+            tags: Vec<u16>,
+        }
+
+        check_slice::<u8>(&[]);
+        check_slice(&[1u32, 2, 3, 4]);
+        check_slice(&[Some(1u8), None, Some(3)]);
+        check_slice(&["a".to_string(), "out-of-line string".to_string()]);
+        check_slice(&[vec![1u8, 2], vec![], vec![3]]);
+        check_slice(&[
+            Record {
+                id: 1,
+                name: "first record".to_string(),
+                tags: vec![1, 2, 3],
+            },
+            Record {
+                id: 2,
+                name: "second".to_string(),
+                tags: vec![],
+            },
+        ]);
+
+        check_str("");
+        check_str("short");
+        check_str("a string which is too long to be stored inline");
+
+        let bytes = to_bytes_from_str::<256, Failure>("borrowed").unwrap();
+        let archived = unsafe { access_unchecked::<Archived<String>>(&bytes) };
+        assert_eq!(archived, "borrowed");
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_enum_accessors() {
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        enum Message {
+            Ping,
+            Move(i32, i32),
+            Write(String),
+            Batch { id: u32, lines: Vec<String> },
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(no_accessors)]
+        enum Quiet {
+            Quit,
+        }
+
+        impl ArchivedQuiet {
+            // Doesn't conflict because accessors are disabled
+            fn kind(&self) -> u8 {
+                0
+            }
+        }
+
+        let values = vec![
+            Message::Ping,
+            Message::Move(-3, 4),
+            Message::Write("hello world".to_string()),
+            Message::Batch {
+                id: 7,
+                lines: vec!["first".to_string(), "second".to_string()],
+            },
+        ];
+        let kinds = [
+            MessageKind::Ping,
+            MessageKind::Move,
+            MessageKind::Write,
+            MessageKind::Batch,
+        ];
+
+        let mut buf = to_bytes::<_, 256, Failure>(&values).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<Message>>>(buf.as_ref()) };
+        for ((value, archived), kind) in
+            values.iter().zip(archived.iter()).zip(kinds)
+        {
+            assert_eq!(value.kind(), kind);
+            assert_eq!(archived.kind(), kind);
+            assert_eq!(format!("{:?}", archived), format!("{:?}", value));
+        }
+
+        let [ping, moved, write, batch] = archived.as_slice() else {
+            panic!("expected four messages");
+        };
+        assert!(ping.is_ping());
+        assert!(!ping.is_move());
+        assert!(moved.is_move());
+        assert!(write.is_write());
+        assert!(batch.is_batch());
+
+        assert!(ping.as_move().is_none());
+        let (x, y) = moved.as_move().unwrap();
+        assert_eq!((x.to_native(), y.to_native()), (-3, 4));
+        assert!(moved.as_write().is_none());
+        assert_eq!(write.as_write().unwrap(), "hello world");
+        let (id, lines) = batch.as_batch().unwrap();
+        assert_eq!(*id, 7);
+        assert_eq!(lines.as_slice(), ["first", "second"]);
+        assert!(batch.as_write().is_none());
+
+        let mut archived = unsafe {
+            access_unchecked_mut::<Archived<Vec<Message>>>(buf.as_mut())
+        };
+        let mut moved = archived.as_mut().index_pin(1);
+        assert!(moved.as_mut().as_write_pin().is_none());
+        let (mut x, _) = moved.as_move_pin().unwrap();
+        *x = 10.into();
+        let mut batch = archived.as_mut().index_pin(3);
+        let (mut id, _) = batch.as_mut().as_batch_pin().unwrap();
+        *id = 8.into();
+
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<Message>>>(buf.as_ref()) };
+        let (x, _) = archived[1].as_move().unwrap();
+        assert_eq!(*x, 10);
+        assert_eq!(*archived[3].as_batch().unwrap().0, 8);
+        assert_eq!(archived[3].kind(), MessageKind::Batch);
+
+        let bytes = to_bytes::<_, 256, Failure>(&Quiet::Quit).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedQuiet>(&bytes) };
+        assert_eq!(archived.kind(), 0);
+    }
+
+    #[derive(Archive, Serialize)]
+    struct Table {
+        name: String,
+        rows: Vec<u64>,
+        index: BTreeMap<u32, String>,
+    }
+
+    #[derive(Archive, Serialize)]
+    struct Dataset {
+        tables: Vec<Table>,
+    }
+
+    fn dataset() -> Dataset {
+        Dataset {
+            tables: (0..4)
+                .map(|i| Table {
+                    name: format!("table {}", i),
+                    rows: (0..2000).map(|r| r * i).collect(),
+                    index: (0..3).map(|k| (k, k.to_string())).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn serialize_progress() {
+        use core::ops::ControlFlow;
+
+        use rkyv::ser::{
+            progress::{Progress, ProgressConfig, ProgressEvent},
+            AllocSerializer,
+        };
+
+        let value = dataset();
+        let expected = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let config = ProgressConfig {
+            milestone_bytes: 8192,
+            min_collection_len: 4,
+        };
+        let mut events = Vec::new();
+        let serializer =
+            Progress::new(AllocSerializer::<256>::default(), config, |event| {
+                events.push(event);
+                ControlFlow::Continue(())
+            });
+        let serializer =
+            serialize_into::<_, _, Failure>(&value, serializer).unwrap();
+        assert_eq!(serializer.bytes_written(), expected.len());
+        let bytes = serializer.into_inner().into_writer();
+        assert_eq!(bytes.as_slice(), expected.as_slice());
+
+        // The outer vec surrounds the vec of rows of each table. The small
+        // index maps don't report any events.
+        let collections = events
+            .iter()
+            .filter(|e| !matches!(e, ProgressEvent::BytesWritten { .. }))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut expected_collections =
+            vec![ProgressEvent::BeginCollection { len: 4 }];
+        for _ in 0..4 {
+            expected_collections
+                .push(ProgressEvent::BeginCollection { len: 2000 });
+            expected_collections
+                .push(ProgressEvent::EndCollection { len: 2000 });
+        }
+        expected_collections.push(ProgressEvent::EndCollection { len: 4 });
+        assert_eq!(collections, expected_collections);
+
+        // Each milestone is reported exactly once, even when a single write
+        // passes several of them
+        let totals = events
+            .iter()
+            .filter_map(|e| match e {
+                ProgressEvent::BytesWritten { total } => Some(*total),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let expected_totals = (1..=expected.len() / 8192)
+            .map(|i| i * 8192)
+            .collect::<Vec<_>>();
+        assert_eq!(totals, expected_totals);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn cancel_serialization() {
+        use core::ops::ControlFlow;
+
+        use rkyv::{
+            rancor::BoxedError,
+            ser::{
+                progress::{Progress, ProgressConfig, ProgressEvent},
+                AllocSerializer,
+            },
+            util::serialize,
+        };
+
+        let value = dataset();
+        let config = ProgressConfig {
+            milestone_bytes: 8192,
+            min_collection_len: 4,
+        };
+
+        let mut events = Vec::new();
+        let mut serializer =
+            Progress::new(AllocSerializer::<256>::default(), config, |event| {
+                events.push(event);
+                match event {
+                    ProgressEvent::BytesWritten { total } if total >= 16384 => {
+                        ControlFlow::Break(())
+                    }
+                    _ => ControlFlow::Continue(()),
+                }
+            });
+        let error =
+            serialize::<_, _, BoxedError>(&value, &mut serializer).unwrap_err();
+        assert_eq!(error.to_string(), "serialization was cancelled");
+
+        // Serialization stopped at the milestone and the serializer keeps
+        // failing
+        assert!(serializer.is_cancelled());
+        let written = serializer.bytes_written();
+        assert_eq!(written, 16384);
+        assert!(Writer::<BoxedError>::write(&mut serializer, &[0]).is_err());
+        assert_eq!(serializer.bytes_written(), written);
+
+        // The partially-filled buffer is discarded
+        let partial = serializer.into_inner().into_writer();
+        assert_eq!(partial.len(), written);
+        drop(partial);
+
+        assert_eq!(
+            events.last(),
+            Some(&ProgressEvent::BytesWritten { total: written }),
+        );
+        let milestones = events
+            .iter()
+            .filter(|e| matches!(e, ProgressEvent::BytesWritten { .. }))
+            .count();
+        assert_eq!(milestones, 2);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn serialize_without_progress_hooks() {
+        use rkyv::ser::{
+            allocator::{BackupAllocator, BumpAllocator, GlobalAllocator},
+            sharing::Unify,
+            Composite, Positional,
+        };
+
+        // A writer which only implements the required methods, and so ignores
+        // the progress hooks
+        struct PlainWriter(Vec<u8>);
+
+        impl Positional for PlainWriter {
+            fn pos(&self) -> usize {
+                self.0.len()
+            }
+        }
+
+        impl<E> Writer<E> for PlainWriter {
+            fn write(&mut self, bytes: &[u8]) -> Result<(), E> {
+                self.0.extend_from_slice(bytes);
+                Ok(())
+            }
+        }
+
+        let value = dataset();
+        let expected = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let serializer = Composite::<
+            _,
+            BackupAllocator<BumpAllocator<256>, GlobalAllocator>,
+            Unify,
+        >::new(
+            PlainWriter(Vec::new()),
+            Default::default(),
+            Unify::default(),
+        );
+        let serializer =
+            serialize_into::<_, _, Failure>(&value, serializer).unwrap();
+        assert_eq!(serializer.into_writer().0, expected.as_slice());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn zst_resolver_fast_path() {
+        use core::mem::size_of;
+
+        use rkyv::ser::AllocSerializer;
+
+        type Serializer = Strategy<AllocSerializer<256>, Failure>;
+
+        // Archives the same as `T`, but always takes the slow path because
+        // its resolver isn't zero-sized.
+        #[derive(Clone)]
+        struct SlowPath<T>(T);
+
+        impl<T: Archive> Archive for SlowPath<T> {
+            type Archived = T::Archived;
+            type Resolver = (T::Resolver, u8);
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                self.0.resolve(pos, resolver.0, out);
+            }
+        }
+
+        impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for SlowPath<T> {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                Ok((self.0.serialize(serializer)?, 0))
+            }
+        }
+
+        fn check<T>(make: impl Fn(usize) -> T)
+        where
+            T: Clone + Serialize<Serializer>,
+            SlowPath<T>: Serialize<Serializer>,
+        {
+            for len in [0, 1, 15, 16, 17, 100] {
+                let values = (0..len).map(&make).collect::<Vec<_>>();
+                let slow = values.iter().cloned().map(SlowPath).collect();
+                let expected =
+                    to_bytes::<Vec<SlowPath<T>>, 256, Failure>(&slow).unwrap();
+                let actual = to_bytes::<_, 256, Failure>(&values).unwrap();
+                assert_eq!(actual.as_slice(), expected.as_slice());
+
+                let boxed = values.into_boxed_slice();
+                let actual = to_bytes::<_, 256, Failure>(&boxed).unwrap();
+                assert_eq!(actual.as_slice(), expected.as_slice());
+            }
+        }
+
+        // Has padding bytes, which must be zeroed on both paths
+        #[derive(Archive, Clone, Serialize)]
+        struct Small {
+            a: u8,
+            b: u32,
+            c: u16,
+            d: bool,
+        }
+
+        #[derive(Archive, Clone, Serialize)]
+        struct Unit;
+
+        #[derive(Archive, Clone, Serialize)]
+        struct Named {
+            id: u16,
+            name: String,
+        }
+
+        #[derive(Archive, Clone, Serialize)]
+        enum Shape {
+            Point,
+            Circle(u32),
+            Rect { w: u8, h: u64 },
+        }
+
+        assert_eq!(size_of::<SmallResolver>(), 0);
+        assert_eq!(size_of::<UnitResolver>(), 0);
+        assert_ne!(size_of::<NamedResolver>(), 0);
+
+        check(|i| Small {
+            a: i as u8,
+            b: i as u32 * 3,
+            c: !(i as u16),
+            d: i % 2 == 0,
+        });
+        check(|_| Unit);
+        check(|i| (i as u8, i as u64, i % 3 == 0));
+        check(|i| [i as u8, 2, 3]);
+        check(|i| [i as u64; 64]);
+        check(|i| Named {
+            id: i as u16,
+            name: "a name which is too long to be inline".repeat(i % 3),
+        });
+        check(|i| match i % 3 {
+            0 => Shape::Point,
+            1 => Shape::Circle(i as u32),
+            _ => Shape::Rect {
+                w: i as u8,
+                h: i as u64,
+            },
+        });
+        check(|i| if i % 2 == 0 { None } else { Some(i as u16) });
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn render_json() {
+        use rkyv::render::{JsonOptions, NonFiniteFloats};
+
+        fn render<T>(value: &T, options: JsonOptions) -> String
+        where
+            T: Serialize<Strategy<DefaultSerializer, Failure>>,
+            T::Archived: rkyv::render::RenderJson,
+        {
+            let bytes = to_bytes::<_, 256, Failure>(value).unwrap();
+            let archived = unsafe { access_unchecked::<T::Archived>(&bytes) };
+            options.display(archived).to_string()
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(render_json)]
+        struct Unit;
+
+        #[derive(Archive, Serialize)]
+        #[archive(render_json)]
+        struct Newtype(u32);
+
+        #[derive(Archive, Serialize)]
+        #[archive(render_json)]
+        struct Pair(i8, char);
+
+        #[derive(Archive, Serialize)]
+        #[archive(render_json)]
+        enum Shape {
+            Point,
+            Circle(u32),
+            Line(i16, i16),
+            Rect { w: u8, h: u64 },
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(render_json)]
+        struct Labeled<T> {
+            r#type: String,
+            value: T,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(render_json)]
+        struct Document {
+            title: String,
+            unit: Unit,
+            newtype: Newtype,
+            pair: Pair,
+            shapes: Vec<Shape>,
+            parent: Option<Box<Labeled<bool>>>,
+            missing: Option<u8>,
+            scores: BTreeMap<u32, f32>,
+            grid: BTreeMap<(u8, String), ()>,
+            tags: BTreeSet<String>,
+        }
+
+        let default = JsonOptions::new();
+        let document = Document {
+            title: "quote \" backslash \\ tab \t bell \u{7} é".to_string(),
+            unit: Unit,
+            newtype: Newtype(7),
+            pair: Pair(-1, '\n'),
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle(3),
+                Shape::Line(-2, 2),
+                Shape::Rect { w: 1, h: 2 },
+            ],
+            parent: Some(Box::new(Labeled {
+                r#type: "flag".to_string(),
+                value: true,
+            })),
+            missing: None,
+            scores: [(1, 0.5), (20, -1.0)].into_iter().collect(),
+            grid: [((1, "a\"b".to_string()), ())].into_iter().collect(),
+            tags: ["x", "y"].into_iter().map(String::from).collect(),
+        };
+        assert_eq!(
+            render(&document, default),
+            concat!(
+                r#"{"title":"quote \" backslash \\ tab \t bell \u0007 é","#,
+                r#""unit":null,"newtype":7,"pair":[-1,"\n"],"#,
+                r#""shapes":["Point",{"Circle":3},{"Line":[-2,2]},"#,
+                r#"{"Rect":{"w":1,"h":2}}],"#,
+                r#""parent":{"type":"flag","value":true},"missing":null,"#,
+                r#""scores":{"1":0.5,"20":-1.0},"#,
+                r#""grid":{"[1,\"a\\\"b\"]":null},"tags":["x","y"]}"#,
+            ),
+        );
+
+        let floats = vec![1.5f64, f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+        assert_eq!(render(&floats, default), "[1.5,null,null,null]");
+        assert_eq!(
+            render(&floats, default.non_finite_floats(NonFiniteFloats::String)),
+            r#"[1.5,"NaN","Infinity","-Infinity"]"#,
+        );
+
+        let nested = vec![vec![vec![1u8]], vec![]];
+        assert_eq!(render(&nested, default), "[[[1]],[]]");
+        assert_eq!(render(&nested, default.max_depth(2)), r#"[["..."],[]]"#);
+        assert_eq!(render(&nested, default.max_depth(0)), r#""...""#);
+
+        let long = (0..10u32).collect::<Vec<_>>();
+        assert_eq!(
+            render(&long, default.ellipsis_after(3)),
+            r#"[0,1,2,"..."]"#
+        );
+        assert_eq!(
+            render(&long, default.ellipsis_after(10)),
+            render(&long, default)
+        );
+        let map = (0..4u32).map(|i| (i, i * i)).collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            render(&map, default.ellipsis_after(2)),
+            r#"{"0":0,"1":1,"...":"..."}"#,
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn project_fields() {
+        use rkyv::{
+            project,
+            project::{Project as _, ProjectedIter},
+            with::AsBigEndian,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(project)]
+        struct Wide {
+            id: u32,
+            payload: [u64; 12],
+            name: String,
+            #[with(AsBigEndian)]
+            score: u32,
+            tags: Vec<u8>,
+        }
+
+        fn wide(i: u32) -> Wide {
+            Wide {
+                id: i,
+                payload: [i as u64; 12],
+                name: format!("item {}", i),
+                score: i * 3,
+                tags: vec![i as u8; i as usize % 5],
+            }
+        }
+
+        for len in [0, 1, 2, 17, 100] {
+            let items = (0..len).map(wide).collect::<Vec<_>>();
+            let bytes = to_bytes::<_, 256, Failure>(&items).unwrap();
+            let archived =
+                unsafe { access_unchecked::<Archived<Vec<Wide>>>(&bytes) };
+
+            let naive = archived
+                .iter()
+                .map(|x| (&x.id, &x.score, &x.tags))
+                .collect::<Vec<_>>();
+            let projected: ProjectedIter<'_, ArchivedWide, _> =
+                project!(archived => (id, score, tags));
+            assert_eq!(projected.len(), len as usize);
+            let projected = projected.collect::<Vec<_>>();
+            assert_eq!(projected.len(), naive.len());
+            for ((id, score, tags), (n_id, n_score, n_tags)) in
+                projected.iter().zip(naive.iter())
+            {
+                // The projected references point at the fields themselves
+                assert!(core::ptr::eq(*id, *n_id));
+                assert!(core::ptr::eq(*score, *n_score));
+                assert!(core::ptr::eq(*tags, *n_tags));
+            }
+
+            let names = project!(archived => name)
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>();
+            let expected = items.iter().map(|x| x.name.as_str());
+            assert!(names.into_iter().eq(expected));
+        }
+
+        let fields = ArchivedWide::FIELDS;
+        let bytes = to_bytes::<_, 256, Failure>(&wide(7)).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedWide>(&bytes) };
+        assert_eq!(fields.id.get(archived).to_native(), 7);
+        assert_eq!(fields.score.get(archived).to_native(), 21);
+        assert_eq!(fields.payload.get(archived)[11].to_native(), 7);
+        let base = archived as *const ArchivedWide as usize;
+        assert_eq!(
+            fields.name.offset(),
+            &archived.name as *const _ as usize - base,
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn peek_fields() {
+        use core::mem::{offset_of, size_of};
+
+        use rkyv::{peek::PeekError, peek_field, with::AsBigEndian};
+
+        #[derive(Archive, Serialize)]
+        struct Packet {
+            kind: u32,
+            header: Header,
+            body: Vec<u8>,
+            trailer: Box<Route>,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Header {
+            version: u16,
+            urgent: bool,
+            route: Route,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Route {
+            id: u64,
+            weight: f32,
+            #[with(AsBigEndian)]
+            port: u16,
+            letter: char,
+        }
+
+        let packet = Packet {
+            kind: 0xdead_beef,
+            header: Header {
+                version: 3,
+                urgent: true,
+                route: Route {
+                    id: u64::MAX - 1,
+                    weight: 0.25,
+                    port: 8080,
+                    letter: 'λ',
+                },
+            },
+            body: vec![1, 2, 3],
+            trailer: Box::new(Route {
+                id: 42,
+                weight: -1.5,
+                port: 443,
+                letter: 'z',
+            }),
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&packet).unwrap();
+
+        // Peeking doesn't need the buffer to be aligned
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&bytes);
+        for bytes in [&bytes[..], &shifted[1..]] {
+            assert_eq!(peek_field!(bytes, Packet.kind), Ok(0xdead_beef));
+            assert_eq!(peek_field!(bytes, Packet.header.version), Ok(3));
+            assert_eq!(peek_field!(bytes, Packet.header.urgent), Ok(true));
+            assert_eq!(
+                peek_field!(bytes, Packet.header.route.id),
+                Ok(u64::MAX - 1),
+            );
+            assert_eq!(
+                peek_field!(bytes, Packet.header.route.weight),
+                Ok(0.25)
+            );
+            assert_eq!(peek_field!(bytes, Packet.header.route.port), Ok(8080));
+            assert_eq!(peek_field!(bytes, Packet.header.route.letter), Ok('λ'));
+
+            assert_eq!(peek_field!(bytes, Packet.trailer -> Route.id), Ok(42));
+            assert_eq!(
+                peek_field!(bytes, Packet.trailer -> Route.weight),
+                Ok(-1.5),
+            );
+            assert_eq!(
+                peek_field!(bytes, Packet.trailer -> Route.port),
+                Ok(443)
+            );
+            assert_eq!(
+                peek_field!(bytes, Packet.trailer -> Route.letter),
+                Ok('z'),
+            );
+        }
+
+        // Truncated buffers
+        let size = size_of::<ArchivedPacket>();
+        assert_eq!(
+            peek_field!(&bytes[..size - 1], Packet.kind),
+            Err(PeekError::BufferTooSmall {
+                size,
+                len: size - 1,
+            }),
+        );
+        assert_eq!(
+            peek_field!(&[], Packet.header.route.id),
+            Err(PeekError::BufferTooSmall { size, len: 0 }),
+        );
+        let root_only = &bytes[bytes.len() - size..];
+        assert_eq!(peek_field!(root_only, Packet.kind), Ok(0xdead_beef));
+        match peek_field!(root_only, Packet.trailer -> Route.id) {
+            Err(PeekError::OutOfBounds { target, size, len }) => {
+                assert!(target < 0);
+                assert_eq!(size, size_of::<ArchivedRoute>());
+                assert_eq!(len, root_only.len());
+            }
+            result => panic!("expected an out of bounds error: {:?}", result),
+        }
+
+        // Invalid values
+        let mut invalid = bytes.to_vec();
+        let pos =
+            invalid.len() - size + offset_of!(ArchivedPacket, header.urgent);
+        invalid[pos] = 2;
+        assert_eq!(
+            peek_field!(&invalid, Packet.header.urgent),
+            Err(PeekError::InvalidValue { pos }),
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn convenience_methods() {
+        use rkyv::{
+            de::pooling::Unify,
+            from_bytes_default_unchecked,
+            ser::{allocator::BumpAllocator, sharing, Composite},
+            to_bytes_default,
+            util::{DefaultDeserializer, DefaultSerializer},
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(convenience)]
+        struct Settings {
+            name: String,
+            tags: Vec<String>,
+        }
+
+        // A configuration with only 16 bytes of scratch space and no backup
+        // allocator.
+        struct TinyScratch;
+
+        impl DefaultSerializer for TinyScratch {
+            type Error = Failure;
+            type Serializer =
+                Composite<AlignedVec, BumpAllocator<16>, sharing::Unify>;
+
+            fn serializer() -> Self::Serializer {
+                Composite::new(
+                    AlignedVec::new(),
+                    BumpAllocator::new(),
+                    sharing::Unify::default(),
+                )
+            }
+
+            fn into_bytes(serializer: Self::Serializer) -> AlignedVec {
+                serializer.into_writer()
+            }
+        }
+
+        impl DefaultDeserializer for TinyScratch {
+            type Error = Failure;
+            type Deserializer = Unify;
+
+            fn deserializer() -> Self::Deserializer {
+                Unify::default()
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(convenience(config = TinyScratch))]
+        struct Tags(Vec<String>);
+
+        let value = Settings {
+            name: "settings".to_string(),
+            tags: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        };
+        let bytes = value.to_archive_bytes().unwrap();
+        assert_eq!(to_bytes_default(&value).unwrap().as_slice(), &*bytes);
+        #[cfg(feature = "bytecheck")]
+        {
+            assert_eq!(Settings::from_archive_bytes(&bytes).unwrap(), value);
+            assert_eq!(
+                rkyv::from_bytes_default::<Settings>(&bytes).unwrap(),
+                value,
+            );
+        }
+        // SAFETY: `bytes` is an archived `Settings` with its root at the end.
+        unsafe {
+            assert_eq!(
+                Settings::from_archive_bytes_unchecked(&bytes).unwrap(),
+                value,
+            );
+            assert_eq!(
+                from_bytes_default_unchecked::<Settings>(&bytes).unwrap(),
+                value,
+            );
+        }
+
+        let small = Tags(vec!["a".to_string()]);
+        let bytes = small.to_archive_bytes().unwrap();
+        #[cfg(feature = "bytecheck")]
+        assert_eq!(Tags::from_archive_bytes(&bytes).unwrap(), small);
+        // SAFETY: `bytes` is an archived `Tags` with its root at the end.
+        unsafe {
+            assert_eq!(
+                Tags::from_archive_bytes_unchecked(&bytes).unwrap(),
+                small
+            );
+        }
+
+        // The resolvers of the strings don't fit in 16 bytes of scratch space,
+        // but do fit in the default 1024 bytes.
+        let large = Tags((0..8).map(|i| i.to_string()).collect());
+        assert!(large.to_archive_bytes().is_err());
+        assert!(to_bytes_default(&large).is_ok());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn builder_buffer_handles() {
+        use rkyv::util::{access_pos_unchecked, BuilderBuffer, Handle};
+
+        #[derive(Archive, Serialize)]
+        struct Record {
+            id: u32,
+            name: String,
+            scores: Vec<u32>,
+        }
+
+        let record = |id: u32| Record {
+            id,
+            name: format!("record number {}", id),
+            scores: (0..id).collect(),
+        };
+
+        let mut buffer = BuilderBuffer::new();
+        let mut handles = Vec::new();
+        let mut moves = 0;
+        for id in 0..64 {
+            let ptr = buffer.as_slice().as_ptr();
+            let handle = buffer
+                .serialize_and_handle::<_, Failure>(&record(id))
+                .unwrap();
+            handles.push(handle);
+            if !buffer.is_empty() && buffer.as_slice().as_ptr() != ptr {
+                moves += 1;
+            }
+
+            // Mutate an earlier record after the buffer may have moved
+            let first = handles[0];
+            let archived = buffer.resolve_mut::<_, Failure>(first).unwrap();
+            // SAFETY: Assigning to `id` doesn't move any pinned data.
+            let id = unsafe { &mut archived.get_unchecked_mut().id };
+            *id = (id.to_native() + 1).into();
+        }
+        assert!(moves > 1);
+
+        for (id, handle) in handles.iter().enumerate().skip(1) {
+            let archived = buffer.resolve::<_, Failure>(*handle).unwrap();
+            assert_eq!(archived.id, id as u32);
+            assert_eq!(archived.name, format!("record number {}", id));
+            assert_eq!(archived.scores.len(), id);
+        }
+        assert_eq!(buffer.resolve::<_, Failure>(handles[0]).unwrap().id, 64);
+
+        // Handles from another buffer are stale
+        let mut other = BuilderBuffer::new();
+        other
+            .serialize_and_handle::<_, Failure>(&record(0))
+            .unwrap();
+        assert!(other.resolve::<_, Failure>(handles[0]).is_err());
+        assert!(other.resolve_mut::<_, Failure>(handles[0]).is_err());
+
+        // Truncating makes every handle stale, even to values which remain
+        let generation = buffer.generation();
+        buffer.truncate(handles[32].pos());
+        assert_ne!(buffer.generation(), generation);
+        assert!(buffer.resolve::<_, Failure>(handles[0]).is_err());
+        assert!(buffer.resolve_mut::<_, Failure>(handles[40]).is_err());
+
+        // Values serialized after truncating get fresh handles
+        let replaced = buffer
+            .serialize_and_handle::<_, Failure>(&record(100))
+            .unwrap();
+        assert_eq!(buffer.resolve::<_, Failure>(replaced).unwrap().id, 100);
+        buffer.clear();
+        assert!(buffer.resolve::<_, Failure>(replaced).is_err());
+
+        // Handle positions remain valid in the finished bytes
+        let mut buffer = BuilderBuffer::new();
+        let handles = (0..4)
+            .map(|id| buffer.serialize_and_handle::<_, Failure>(&record(id)))
+            .collect::<Result<Vec<Handle<Record>>, _>>()
+            .unwrap();
+        let bytes = buffer.into_inner();
+        for (id, handle) in handles.iter().enumerate() {
+            let archived = unsafe {
+                access_pos_unchecked::<ArchivedRecord>(&bytes, handle.pos())
+            };
+            assert_eq!(archived.name, format!("record number {}", id));
+        }
+    }
 }
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use std::{collections::HashMap, rc::Rc};
+
+    use rkyv::{
+        hash::xxh64,
+        rancor::{Failure, Fallible},
+        util::{Archiver, Checksum, Reader},
+        Archive, Archived, Deserialize, Serialize,
+    };
+    #[cfg(feature = "wasm")]
+    use wasm_bindgen_test::*;
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    #[archive(check_bytes)]
+    struct Test {
+        name: String,
+        values: Vec<u32>,
+        first: Rc<String>,
+        second: Rc<String>,
+        map: HashMap<String, u32>,
+    }
+
+    fn make_test() -> Test {
+        let shared = Rc::new("shared".to_string());
+        Test {
+            name: "test".to_string(),
+            values: (0..100).collect(),
+            first: shared.clone(),
+            second: shared,
+            map: (0..50).map(|i| (i.to_string(), i)).collect(),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn configuration_matrix() {
+        let value = make_test();
+
+        let mut outputs = Vec::new();
+        for scratch_capacity in [0, 16, 64 << 10] {
+            for share_pointers in [true, false] {
+                for deterministic in [true, false] {
+                    for checksum in [Checksum::None, Checksum::Xxh64] {
+                        let bytes = Archiver::new()
+                            .scratch_capacity(scratch_capacity)
+                            .share_pointers(share_pointers)
+                            .deterministic(deterministic)
+                            .checksum(checksum)
+                            .to_bytes::<_, Failure>(&value)
+                            .unwrap();
+
+                        let reader = Reader::new().checksum(checksum);
+                        let archived = reader
+                            .access::<ArchivedTest, Failure>(&bytes)
+                            .unwrap();
+                        assert_eq!(archived.name, value.name);
+                        assert_eq!(archived.values, value.values);
+                        assert_eq!(archived.map.len(), value.map.len());
+
+                        let shares_value = core::ptr::eq(
+                            archived.first.get(),
+                            archived.second.get(),
+                        );
+                        assert_eq!(shares_value, share_pointers);
+
+                        let deserialized =
+                            reader.from_bytes::<Test, Failure>(&bytes).unwrap();
+                        assert_eq!(deserialized, value);
+
+                        outputs.push((
+                            share_pointers,
+                            checksum,
+                            bytes.into_vec(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (share_pointers, checksum, bytes) in outputs.iter() {
+            for (other_share_pointers, other_checksum, other_bytes) in
+                outputs.iter()
+            {
+                // Scratch space and determinism checks never change the output
+                if share_pointers == other_share_pointers
+                    && checksum == other_checksum
+                {
+                    assert_eq!(bytes, other_bytes);
+                }
+
+                if share_pointers == other_share_pointers
+                    && *checksum == Checksum::Xxh64
+                    && *other_checksum == Checksum::None
+                {
+                    assert_eq!(bytes.len(), other_bytes.len() + 8);
+                    let (archive, trailer) = bytes.split_at(other_bytes.len());
+                    assert_eq!(archive, other_bytes.as_slice());
+                    assert_eq!(trailer, xxh64(archive, 0).to_le_bytes());
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn deterministic_hash_maps() {
+        let forward = (0..1000)
+            .map(|i| (i.to_string(), i))
+            .collect::<HashMap<_, _>>();
+        let mut backward = HashMap::new();
+        for i in (0..1000).rev() {
+            backward.insert(i.to_string(), i);
+        }
+
+        let archiver = Archiver::new().deterministic(true);
+        let forward_bytes = archiver.to_bytes::<_, Failure>(&forward).unwrap();
+        let backward_bytes =
+            archiver.to_bytes::<_, Failure>(&backward).unwrap();
+        assert_eq!(forward_bytes.as_slice(), backward_bytes.as_slice());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn detect_nondeterminism() {
+        struct Counter(Cell<u32>);
+
+        impl Archive for Counter {
+            type Archived = Archived<u32>;
+            type Resolver = ();
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                self.0.get().resolve(pos, resolver, out);
+            }
+        }
+
+        impl<S: Fallible + ?Sized> Serialize<S> for Counter {
+            fn serialize(&self, _: &mut S) -> Result<(), S::Error> {
+                self.0.set(self.0.get() + 1);
+                Ok(())
+            }
+        }
+
+        let counter = Counter(Cell::new(0));
+        Archiver::new().to_bytes::<_, Failure>(&counter).unwrap();
+        assert!(Archiver::new()
+            .deterministic(true)
+            .to_bytes::<_, Failure>(&counter)
+            .is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn reject_bad_checksums() {
+        let value = make_test();
+        let archiver = Archiver::new().checksum(Checksum::Xxh64);
+        let reader = Reader::new().checksum(Checksum::Xxh64);
+
+        let mut bytes = archiver.to_bytes::<_, Failure>(&value).unwrap();
+        reader.access::<ArchivedTest, Failure>(&bytes).unwrap();
+
+        // Corrupt a byte in the archive
+        bytes[0] ^= 1;
+        assert!(reader.verify::<Failure>(&bytes).is_err());
+        bytes[0] ^= 1;
+
+        // Corrupt a byte in the checksum
+        let len = bytes.len();
+        bytes[len - 1] ^= 1;
+        assert!(reader.verify::<Failure>(&bytes).is_err());
+
+        // Require a checksum for an archive without one
+        let bytes = Archiver::new().to_bytes::<_, Failure>(&value).unwrap();
+        assert!(reader.access::<ArchivedTest, Failure>(&bytes).is_err());
+        assert!(reader.verify::<Failure>(&[0; 4]).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use core::fmt::Debug;
+    use std::sync::Arc;
+
+    use arrow_array::Array;
+    use rkyv::{
+        access_unchecked,
+        arrow::{ArrowNative, ArrowPrimitive, CopyReason},
+        rancor::{Failure, Strategy},
+        ser::AllocSerializer,
+        to_bytes,
+        util::AlignedVec,
+        vec::ArchivedVec,
+        Archive, Archived, Serialize,
+    };
+
+    // The copy reason expected for multi-byte values in aligned archives
+    fn expected_reason() -> Option<CopyReason> {
+        if cfg!(feature = "big_endian") {
+            Some(CopyReason::Endianness)
+        } else {
+            None
+        }
+    }
+
+    fn check_zero_copy<T>(values: Vec<T>, expected: Option<CopyReason>)
+    where
+        T: Archive + Serialize<Strategy<AllocSerializer<256>, Failure>>,
+        T::Archived: ArrowPrimitive,
+        ArrowNative<T::Archived>: From<T> + Debug + PartialEq,
+    {
+        let bytes = Arc::new(to_bytes::<_, 256, Failure>(&values).unwrap());
+        let archived =
+            unsafe { access_unchecked::<ArchivedVec<T::Archived>>(&bytes) };
+        let payload = archived.as_ptr().cast::<u8>();
+
+        let array = archived.to_arrow_array(bytes.clone());
+        assert_eq!(array.copy_reason(), expected);
+        let array = array.into_inner();
+        let data = array.values().inner().as_ptr();
+        assert_eq!(data == payload, expected.is_none());
+
+        let expected = values
+            .into_iter()
+            .map(ArrowNative::<T::Archived>::from)
+            .collect::<Vec<_>>();
+        assert_eq!(array.values().as_ref(), expected.as_slice());
+        assert_eq!(array.null_count(), 0);
+    }
+
+    #[test]
+    fn zero_copy_primitives() {
+        check_zero_copy::<i8>(vec![-1, 0, 1, i8::MIN, i8::MAX], None);
+        check_zero_copy::<u8>(vec![0, 1, u8::MAX], None);
+
+        let reason = expected_reason();
+        check_zero_copy::<i16>(vec![-1, 0, 1, i16::MIN, i16::MAX], reason);
+        check_zero_copy::<i32>(vec![-1, 0, 1, i32::MIN, i32::MAX], reason);
+        check_zero_copy::<i64>(vec![-1, 0, 1, i64::MIN, i64::MAX], reason);
+        check_zero_copy::<u16>(vec![0, 1, u16::MAX], reason);
+        check_zero_copy::<u32>(vec![0, 1, u32::MAX], reason);
+        check_zero_copy::<u64>(vec![0, 1, u64::MAX], reason);
+        check_zero_copy::<f32>(vec![-1.5, 0.0, 1.5, f32::MAX], reason);
+        check_zero_copy::<f64>(vec![-1.5, 0.0, 1.5, f64::MAX], reason);
+        check_zero_copy::<u64>(Vec::new(), reason);
+    }
+
+    #[test]
+    fn buffer_keeps_archive_alive() {
+        let values = (0..1000u64).map(|i| i * i).collect::<Vec<_>>();
+        let bytes = Arc::new(to_bytes::<_, 256, Failure>(&values).unwrap());
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<u64>>>(&bytes) };
+        let payload = archived.as_ptr().cast::<u8>();
+
+        let buffer = archived.to_arrow_buffer(bytes.clone());
+        assert_eq!(buffer.copy_reason(), expected_reason());
+        let buffer = buffer.into_inner();
+        if expected_reason().is_none() {
+            assert_eq!(buffer.as_ptr(), payload);
+        }
+        assert_eq!(buffer.len(), values.len() * 8);
+
+        // The buffer holds the only remaining reference to the archive
+        drop(bytes);
+        assert_eq!(buffer.typed_data::<u64>(), values.as_slice());
+    }
+
+    #[test]
+    fn copy_when_not_owned() {
+        let values = vec![1u64, 2, 3];
+
+        // The values are not inside of the owner
+        let bytes = Arc::new(to_bytes::<_, 256, Failure>(&values).unwrap());
+        let other = Arc::new(AlignedVec::new());
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<u64>>>(&bytes) };
+        let array = archived.to_arrow_array(other);
+        if expected_reason().is_none() {
+            assert_eq!(array.copy_reason(), Some(CopyReason::NotOwned));
+        }
+        assert_eq!(array.get().values().as_ref(), values.as_slice());
+    }
+
+    #[test]
+    fn nullable_array() {
+        let values = vec![Some(1i32), None, Some(-3), None, Some(i32::MAX)];
+        let bytes = to_bytes::<_, 256, Failure>(&values).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<Option<i32>>>>(&bytes) };
+
+        let array = archived.to_arrow_nullable_array();
+        assert_eq!(array.len(), values.len());
+        assert_eq!(array.null_count(), 2);
+        let exported = (0..array.len())
+            .map(|i| array.is_valid(i).then(|| array.value(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(exported, values);
+
+        let bytes =
+            to_bytes::<_, 256, Failure>(&Vec::<Option<i32>>::new()).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<Option<i32>>>>(&bytes) };
+        assert!(archived.to_arrow_nullable_array().is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use core::hash::BuildHasherDefault;
+    use std::collections::HashMap;
+
+    use bumpalo::{collections::Vec as BumpVec, Bump};
+    use rkyv::{
+        access_unchecked, de::with_allocator, deserialize, hash::FxHasher64,
+        rancor::Failure, to_bytes, Archived,
+    };
+
+    use crate::util::counting_alloc::allocations;
+
+    type BumpHashMap<'a, K, V> =
+        hashbrown::HashMap<K, V, BuildHasherDefault<FxHasher64>, &'a Bump>;
+
+    #[test]
+    fn deserialize_into_bump() {
+        let mut value = Vec::new();
+        for i in 0..4u32 {
+            let mut map = HashMap::new();
+            for j in 0..i {
+                map.insert(j, (0..j).collect::<Vec<u32>>());
+            }
+            value.push(map);
+        }
+
+        let bytes = to_bytes::<_, 1024, Failure>(&value).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<Vec<HashMap<u32, Vec<u32>>>>>(
+                bytes.as_ref(),
+            )
+        };
+
+        // Allocate enough up front that the bump allocator doesn't need to grow
+        let bump = Bump::with_capacity(4096);
+        let mut deserializer = with_allocator((), &bump);
+
+        let before = allocations();
+        let deserialized = deserialize::<
+            BumpVec<'_, BumpHashMap<'_, u32, BumpVec<'_, u32>>>,
+            _,
+            Failure,
+        >(archived, &mut deserializer)
+        .unwrap();
+        assert_eq!(allocations(), before);
+
+        assert_eq!(deserialized.len(), value.len());
+        for (map, expected) in deserialized.iter().zip(value.iter()) {
+            assert_eq!(map.len(), expected.len());
+            for (key, values) in expected.iter() {
+                assert_eq!(map[key].as_slice(), values.as_slice());
+            }
+        }
+    }
+}
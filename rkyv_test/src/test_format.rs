@@ -0,0 +1,446 @@
+#[cfg(test)]
+mod tests {
+    use core::{
+        fmt::Write as _,
+        hash::Hash,
+        num::{NonZeroI64, NonZeroU32},
+    };
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        rc::Rc,
+        sync::Arc,
+    };
+
+    use rkyv::{
+        archived_offset_of, assert_archived_align, assert_archived_size,
+        collections::swiss_table::{
+            ArchivedHashMap, ArchivedHashSet, HashMapResolver, HashSetResolver,
+        },
+        hash::{hash_value, FxHasher64},
+        rancor::{Error, Failure, Fallible, Strategy},
+        ser::{AllocSerializer, Allocator, Writer},
+        stable::format_table,
+        to_bytes, Archive, Serialize,
+    };
+
+    // The endianness and pointer width that the fixtures were generated with.
+    // The fixtures for each combination are stored in a separate file.
+    const CONFIG: &str = if cfg!(feature = "big_endian") {
+        if cfg!(feature = "pointer_width_16") {
+            "be_16"
+        } else if cfg!(feature = "pointer_width_64") {
+            "be_64"
+        } else {
+            "be_32"
+        }
+    } else if cfg!(feature = "pointer_width_16") {
+        "le_16"
+    } else if cfg!(feature = "pointer_width_64") {
+        "le_64"
+    } else {
+        "le_32"
+    };
+
+    // Hash maps serialized from a fixed order of entries. The layout of an
+    // archived hash table depends on the order its entries are inserted when
+    // their probe sequences collide, and the iteration order of a `HashMap`
+    // may change between versions of the standard library.
+    struct OrderedMap<K, V>(Vec<(K, V)>);
+
+    impl<K, V> Archive for OrderedMap<K, V>
+    where
+        K: Archive + Hash + Eq,
+        V: Archive,
+    {
+        type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+        type Resolver = HashMapResolver;
+
+        unsafe fn resolve(
+            &self,
+            pos: usize,
+            resolver: Self::Resolver,
+            out: *mut Self::Archived,
+        ) {
+            ArchivedHashMap::resolve_from_len(
+                self.0.len(),
+                (7, 8),
+                pos,
+                resolver,
+                out,
+            );
+        }
+    }
+
+    impl<K, V, S> Serialize<S> for OrderedMap<K, V>
+    where
+        K: Serialize<S> + Hash + Eq,
+        V: Serialize<S>,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+    {
+        fn serialize(
+            &self,
+            serializer: &mut S,
+        ) -> Result<Self::Resolver, S::Error> {
+            ArchivedHashMap::<K::Archived, V::Archived>::serialize_from_iter(
+                self.0.iter().map(|(k, v)| (k, v)),
+                (7, 8),
+                serializer,
+            )
+        }
+    }
+
+    struct OrderedSet<K>(Vec<K>);
+
+    impl<K: Archive + Hash + Eq> Archive for OrderedSet<K> {
+        type Archived = ArchivedHashSet<K::Archived>;
+        type Resolver = HashSetResolver;
+
+        unsafe fn resolve(
+            &self,
+            pos: usize,
+            resolver: Self::Resolver,
+            out: *mut Self::Archived,
+        ) {
+            ArchivedHashSet::resolve_from_len(
+                self.0.len(),
+                (7, 8),
+                pos,
+                resolver,
+                out,
+            );
+        }
+    }
+
+    impl<K, S> Serialize<S> for OrderedSet<K>
+    where
+        K: Serialize<S> + Hash + Eq,
+        S: Fallible + Writer + Allocator + ?Sized,
+        S::Error: Error,
+    {
+        fn serialize(
+            &self,
+            serializer: &mut S,
+        ) -> Result<Self::Resolver, S::Error> {
+            ArchivedHashSet::<K::Archived>::serialize_from_iter(
+                self.0.iter(),
+                (7, 8),
+                serializer,
+            )
+        }
+    }
+
+    #[derive(Archive, Serialize)]
+    #[archive(format_stable)]
+    struct Record {
+        flag: bool,
+        id: u64,
+        name: String,
+        code: u16,
+        scores: Vec<f32>,
+    }
+
+    #[derive(Archive, Serialize)]
+    #[archive(format_stable)]
+    #[allow(dead_code)]
+    enum Event {
+        Empty,
+        Tuple(u8, u32),
+        Struct { id: u16, record: Record },
+    }
+
+    // The size and alignment of types without pointers or multibyte enum tags
+    // don't depend on the configuration, so they can always be pinned.
+    #[derive(Archive, Serialize)]
+    #[archive(format_stable(size = 12, align = 4))]
+    struct Sample {
+        a: u8,
+        b: u32,
+        c: u16,
+        d: [u8; 3],
+    }
+
+    mod layout {
+        use rkyv::Archive;
+
+        // The fields of these archived types are private to this module
+        #[derive(Archive)]
+        #[archive(stable_layout)]
+        #[allow(dead_code)]
+        pub struct Header<T> {
+            magic: u32,
+            name: String,
+            values: Vec<T>,
+            extra: Option<u32>,
+        }
+
+        #[derive(Archive)]
+        #[archive(stable_layout)]
+        #[allow(dead_code)]
+        pub struct Pair(pub u32, u8);
+    }
+
+    assert_archived_size!(layout::Pair, 8);
+    assert_archived_align!(layout::Pair, 4);
+    const _: () = assert!(archived_offset_of!(layout::Pair, 1) == 4);
+
+    #[derive(Default)]
+    struct Fixtures {
+        text: String,
+    }
+
+    impl Fixtures {
+        fn add<T>(&mut self, name: &str, value: &T)
+        where
+            T: Serialize<Strategy<AllocSerializer<256>, Failure>>,
+        {
+            let bytes = to_bytes::<_, 256, Failure>(value).unwrap();
+            self.add_bytes(name, &bytes);
+        }
+
+        fn add_bytes(&mut self, name: &str, bytes: &[u8]) {
+            write!(self.text, "{}", name).unwrap();
+            for byte in bytes {
+                write!(self.text, " {:02x}", byte).unwrap();
+            }
+            self.text.push('\n');
+        }
+    }
+
+    fn record(id: u64) -> Record {
+        Record {
+            flag: true,
+            id,
+            name: format!("record number {}", id),
+            code: 0xabcd,
+            scores: vec![0.5, -1.25],
+        }
+    }
+
+    fn fixtures() -> String {
+        let mut f = Fixtures::default();
+
+        // Primitives
+        f.add("unit", &());
+        f.add("bool_false", &false);
+        f.add("bool_true", &true);
+        f.add("i8", &-2i8);
+        f.add("u8", &0xabu8);
+        f.add("i16", &-0x0102i16);
+        f.add("u16", &0x0102u16);
+        f.add("i32", &-0x01020304i32);
+        f.add("u32", &0x01020304u32);
+        f.add("i64", &-0x0102030405060708i64);
+        f.add("u64", &0x0102030405060708u64);
+        f.add("i128", &-0x0102030405060708090a0b0c0d0e0f10i128);
+        f.add("u128", &0x0102030405060708090a0b0c0d0e0f10u128);
+        f.add("isize", &-0x0102isize);
+        f.add("usize", &0x0102usize);
+        f.add("f32", &1.5f32);
+        f.add("f64", &-2.25f64);
+        f.add("char", &'\u{1f980}');
+        f.add("nonzero_u32", &NonZeroU32::new(0x01020304).unwrap());
+        f.add("nonzero_i64", &NonZeroI64::new(-5).unwrap());
+
+        // Strings
+        f.add("string_empty", &String::new());
+        f.add("string_inline", &"rkyv".to_string());
+        f.add("string_inline_full", &"eightchr".to_string());
+        f.add(
+            "string_out_of_line",
+            &"a string too long to inline".to_string(),
+        );
+
+        // Vecs and boxes
+        f.add("vec_empty", &Vec::<u32>::new());
+        f.add("vec_u32", &vec![1u32, 2, 3]);
+        f.add("vec_string", &vec!["a".to_string(), "b".repeat(12)]);
+        f.add("box_u32", &Box::new(0x01020304u32));
+        f.add("box_slice", &vec![1u16, 2, 3].into_boxed_slice());
+        f.add("box_str", &"boxed".to_string().into_boxed_str());
+
+        // Options and results
+        f.add("option_some", &Some(0x01020304u32));
+        f.add("option_none", &None::<u32>);
+        f.add("option_box_some", &Some(Box::new(7u8)));
+        f.add("option_box_none", &None::<Box<u8>>);
+        f.add("option_nonzero", &NonZeroU32::new(9));
+        f.add("result_ok", &Ok::<u32, String>(0x01020304));
+        f.add("result_err", &Err::<u32, String>("error".to_string()));
+
+        // Tuples and arrays
+        f.add("tuple_2", &(1u8, 0x01020304u32));
+        f.add("tuple_4", &(true, 0x0102u16, 0x0102030405060708u64, 'x'));
+        f.add("array_u16", &[1u16, 2, 3]);
+        f.add("array_nested", &[[1u8, 2], [3, 4]]);
+        f.add("array_empty", &[0u32; 0]);
+
+        // Swiss tables pin the hashes of their keys, the layout of their
+        // control bytes, and the probe order of their entries.
+        for key in [0u32, 1, 0x01020304] {
+            f.add_bytes(
+                &format!("hash_u32_{}", key),
+                &hash_value::<u32, FxHasher64>(&key).to_le_bytes(),
+            );
+        }
+        for key in ["", "a", "hello world"] {
+            f.add_bytes(
+                &format!("hash_str_{:?}", key),
+                &hash_value::<str, FxHasher64>(key).to_le_bytes(),
+            );
+        }
+        f.add("hash_map_empty", &OrderedMap::<u32, u32>(Vec::new()));
+        f.add(
+            "hash_map_small",
+            &OrderedMap(vec![(1u32, 10u32), (2, 20), (3, 30)]),
+        );
+        f.add(
+            "hash_map_large",
+            &OrderedMap((0..40u32).map(|i| (i * 7, i)).collect()),
+        );
+        f.add(
+            "hash_map_strings",
+            &OrderedMap(vec![
+                ("apple".to_string(), 1u8),
+                ("banana".to_string(), 2),
+                ("a much longer key".to_string(), 3),
+            ]),
+        );
+        f.add("hash_set", &OrderedSet((0..20u16).map(|i| i * 3).collect()));
+
+        // B-trees
+        f.add("btree_map_empty", &BTreeMap::<u32, u32>::new());
+        f.add(
+            "btree_map_small",
+            &(0..3u32).map(|i| (i, i * 10)).collect::<BTreeMap<_, _>>(),
+        );
+        f.add(
+            "btree_map_large",
+            &(0..100u32)
+                .map(|i| (i, format!("value {}", i)))
+                .collect::<BTreeMap<_, _>>(),
+        );
+        f.add("btree_set", &(0..20u16).collect::<BTreeSet<_>>());
+
+        // Shared pointers
+        f.add("rc", &Rc::new(0x01020304u32));
+        let shared = Rc::new("shared".to_string());
+        f.add("rc_shared", &vec![shared.clone(), shared]);
+        f.add("arc", &Arc::new(0x0102u16));
+
+        // Derived types
+        f.add(
+            "derived_sample",
+            &Sample {
+                a: 1,
+                b: 0x02030405,
+                c: 0x0607,
+                d: [8, 9, 10],
+            },
+        );
+        f.add("derived_struct", &record(42));
+        f.add("derived_enum_unit", &Event::Empty);
+        f.add("derived_enum_tuple", &Event::Tuple(1, 0x01020304));
+        f.add(
+            "derived_enum_struct",
+            &Event::Struct {
+                id: 7,
+                record: record(43),
+            },
+        );
+
+        f.text
+    }
+
+    // Fails if the bytes of any fixture differ from the golden fixtures for
+    // the current configuration. Run with `RKYV_BLESS=1` to regenerate them.
+    #[test]
+    fn golden_fixtures() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/golden/format")
+            .join(format!("{}.hex", CONFIG));
+        let actual = fixtures();
+
+        if std::env::var_os("RKYV_BLESS").is_some() {
+            std::fs::write(&path, &actual).unwrap();
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+            panic!(
+                "missing golden fixtures at {}, run with RKYV_BLESS=1 to \
+                 generate them",
+                path.display(),
+            )
+        });
+        let mut expected_lines = expected.lines();
+        for actual_line in actual.lines() {
+            let name = actual_line.split(' ').next().unwrap();
+            let expected_line = expected_lines.next().unwrap_or_else(|| {
+                panic!("fixture `{}` is missing from the golden file", name)
+            });
+            assert_eq!(
+                actual_line, expected_line,
+                "the archived bytes of fixture `{}` changed",
+                name,
+            );
+        }
+        assert_eq!(expected_lines.next(), None, "extra golden fixtures");
+    }
+
+    #[test]
+    fn format_stable_pinned_table() {
+        assert_eq!(
+            format_table::<ArchivedSample>(),
+            "size 12\nalign 4\na 6\nb 0\nc 4\nd 7\n",
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "little_endian", feature = "pointer_width_32"))]
+    fn format_stable_tables() {
+        assert_eq!(
+            format_table::<ArchivedRecord>(),
+            "size 32\nalign 8\nflag 26\nid 16\nname 0\ncode 24\nscores 8\n",
+        );
+        assert_eq!(
+            format_table::<ArchivedEvent>(),
+            "size 40\nalign 8\nTuple._0 1\nTuple._1 4\nStruct.id 2\n\
+             Struct.record 8\n",
+        );
+    }
+
+    #[test]
+    fn stable_layout() {
+        use core::alloc::Layout;
+
+        use layout::{ArchivedHeader, ArchivedPair};
+
+        assert_eq!(ArchivedPair::LAYOUT, Layout::new::<ArchivedPair>());
+        assert_eq!(archived_offset_of!(layout::Pair, 0), 0);
+        assert_eq!(
+            ArchivedHeader::<u64>::LAYOUT,
+            Layout::new::<ArchivedHeader<u64>>(),
+        );
+        // The layout of `Vec<T>` doesn't depend on `T`
+        assert_eq!(
+            archived_offset_of!(layout::Header<u8>, extra),
+            archived_offset_of!(layout::Header<u64>, extra),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "little_endian", feature = "pointer_width_32"))]
+    fn stable_layout_pinned() {
+        use layout::{ArchivedHeader, Header};
+
+        assert_archived_size!(Header<u16>, 28);
+        assert_archived_align!(Header<u16>, 4);
+
+        const NAME: usize = archived_offset_of!(Header<u16>, name);
+        assert_eq!(NAME, 0);
+        assert_eq!(archived_offset_of!(Header<u16>, values), 8);
+        assert_eq!(archived_offset_of!(Header<u16>, extra), 16);
+        assert_eq!(archived_offset_of!(Header<u16>, magic), 24);
+        assert_eq!(ArchivedHeader::<u16>::LAYOUT.size(), 28);
+    }
+}
@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "wasm")]
+    use wasm_bindgen_test::*;
+
+    // Re-exports rkyv the way a facade crate would.
+    mod facade {
+        pub use ::rkyv;
+    }
+
+    // Nothing from the prelude is in scope here, so every path emitted by the
+    // derives must be fully qualified.
+    #[no_implicit_prelude]
+    mod no_prelude {
+        #[derive(
+            ::core::fmt::Debug,
+            ::core::cmp::PartialEq,
+            ::rkyv::Archive,
+            ::rkyv::Serialize,
+            ::rkyv::Deserialize,
+        )]
+        #[archive(compare(PartialEq))]
+        #[archive_attr(derive(::core::fmt::Debug))]
+        pub struct Struct {
+            pub a: u32,
+            pub b: ::core::option::Option<Tuple>,
+            pub c: [u8; 3],
+        }
+
+        #[derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)]
+        #[archive(compare(PartialEq))]
+        pub struct Generic<T> {
+            pub value: T,
+        }
+
+        #[derive(
+            ::core::fmt::Debug,
+            ::core::cmp::PartialEq,
+            ::rkyv::Archive,
+            ::rkyv::Serialize,
+            ::rkyv::Deserialize,
+        )]
+        #[archive(compare(PartialEq, PartialOrd))]
+        #[archive_attr(derive(::core::fmt::Debug))]
+        pub struct Tuple(pub u16, pub bool);
+
+        #[derive(
+            ::core::fmt::Debug,
+            ::core::cmp::PartialEq,
+            ::rkyv::Archive,
+            ::rkyv::Serialize,
+            ::rkyv::Deserialize,
+        )]
+        #[archive(compare(PartialEq, PartialOrd))]
+        #[archive_attr(derive(::core::fmt::Debug))]
+        pub struct Unit;
+
+        #[derive(
+            ::core::fmt::Debug,
+            ::core::cmp::PartialEq,
+            ::rkyv::Archive,
+            ::rkyv::Serialize,
+            ::rkyv::Deserialize,
+        )]
+        #[archive(compare(PartialEq))]
+        #[archive_attr(derive(::core::fmt::Debug))]
+        pub enum Enum {
+            A { value: u32 },
+            B(i8, char),
+            C,
+        }
+
+        #[cfg(feature = "alloc")]
+        #[derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)]
+        #[archive(columnar)]
+        pub struct Columnar {
+            pub a: u32,
+            pub b: Tuple,
+        }
+
+        #[derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)]
+        #[archive(project)]
+        pub struct Projected {
+            pub a: u32,
+            pub b: Tuple,
+        }
+
+        #[cfg(feature = "alloc")]
+        #[derive(::rkyv::Archive, ::rkyv::Serialize, ::rkyv::Deserialize)]
+        #[archive(structural_hash)]
+        pub enum StructurallyHashed {
+            A(u32),
+            B { c: u8 },
+        }
+
+        #[derive(::rkyv::Portable)]
+        #[archive(crate = ::rkyv)]
+        #[repr(C)]
+        #[allow(dead_code)]
+        pub struct Portable {
+            pub a: ::rkyv::Archived<u32>,
+        }
+    }
+
+    // `rkyv` is not in scope here, and the derives are only reachable through
+    // the facade.
+    #[no_implicit_prelude]
+    mod through_facade {
+        use super::facade::rkyv as facade_rkyv;
+
+        #[derive(
+            ::core::fmt::Debug,
+            ::core::cmp::PartialEq,
+            facade_rkyv::Archive,
+            facade_rkyv::Serialize,
+            facade_rkyv::Deserialize,
+        )]
+        #[rkyv(crate = "crate::test_paths::tests::facade::rkyv")]
+        #[archive(compare(PartialEq))]
+        #[archive_attr(derive(::core::fmt::Debug))]
+        pub struct Struct {
+            pub a: u32,
+            pub b: super::no_prelude::Enum,
+        }
+    }
+
+    // `CheckBytes` is derived by bytecheck, which still relies on the prelude.
+    #[cfg(feature = "bytecheck")]
+    mod check_bytes_through_facade {
+        // Shadows the extern prelude entry for `rkyv`.
+        #[allow(dead_code)]
+        mod rkyv {}
+
+        #[derive(
+            Debug,
+            PartialEq,
+            super::facade::rkyv::Archive,
+            super::facade::rkyv::Serialize,
+        )]
+        #[rkyv(crate = "super::facade::rkyv", check_bytes)]
+        pub struct Struct {
+            pub a: u32,
+            pub b: (u8, bool),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn derive_without_prelude() {
+        use no_prelude::{Enum, Struct, Tuple, Unit};
+
+        use crate::util::core::test_archive;
+
+        test_archive(&Struct {
+            a: 42,
+            b: Some(Tuple(7, true)),
+            c: [1, 2, 3],
+        });
+        test_archive(&Struct {
+            a: 1,
+            b: None,
+            c: [0; 3],
+        });
+        test_archive(&Unit);
+        test_archive(&Enum::A { value: 10 });
+        test_archive(&Enum::B(-1, 'x'));
+        test_archive(&Enum::C);
+        test_archive(&through_facade::Struct {
+            a: 3,
+            b: Enum::B(4, 'y'),
+        });
+    }
+
+    #[test]
+    #[cfg(all(feature = "bytecheck", feature = "alloc"))]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_bytes_through_facade() {
+        use check_bytes_through_facade::{ArchivedStruct, Struct};
+        use rkyv::{access, rancor::Failure, to_bytes};
+
+        let value = Struct {
+            a: 42,
+            b: (1, true),
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<ArchivedStruct, Failure>(&bytes).unwrap();
+        assert_eq!(archived.a, 42);
+        assert_eq!(archived.b.0, 1);
+        assert!(archived.b.1);
+    }
+}
@@ -0,0 +1,178 @@
+// These tests exercise every way of reaching archived data through pointers,
+// and are meant to be run under Miri with strict provenance:
+//
+// MIRIFLAGS="-Zmiri-strict-provenance -Zmiri-tree-borrows" \
+//     cargo miri test -p rkyv_test provenance
+
+#[cfg(test)]
+mod tests {
+    use core::pin::Pin;
+    use std::collections::{BTreeMap, HashMap};
+
+    use rkyv::{
+        access, access_mut, access_unchecked, boxed::ArchivedBox,
+        collections::swiss_table::ArchivedHashMap, from_bytes, rancor::Failure,
+        to_bytes, vec::ArchivedVec, Archive, Archived, Deserialize, Serialize,
+    };
+    #[cfg(feature = "wasm")]
+    use wasm_bindgen_test::*;
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    #[archive(check_bytes)]
+    struct Document {
+        id: u32,
+        // Short enough to be stored inline
+        title: String,
+        // Long enough to be stored out of line
+        body: String,
+        counts: Vec<u32>,
+        blob: Box<[u8]>,
+        scores: HashMap<String, u32>,
+        index: BTreeMap<u32, String>,
+        parent: Option<Box<u64>>,
+    }
+
+    impl ArchivedDocument {
+        fn counts(
+            self: Pin<&mut Self>,
+        ) -> Pin<&mut ArchivedVec<Archived<u32>>> {
+            unsafe { self.map_unchecked_mut(|s| &mut s.counts) }
+        }
+
+        fn scores(
+            self: Pin<&mut Self>,
+        ) -> Pin<&mut ArchivedHashMap<Archived<String>, Archived<u32>>>
+        {
+            unsafe { self.map_unchecked_mut(|s| &mut s.scores) }
+        }
+
+        fn parent(self: Pin<&mut Self>) -> Option<Pin<&mut Archived<u64>>> {
+            let parent = unsafe { self.map_unchecked_mut(|s| &mut s.parent) };
+            parent.as_pin_mut().map(ArchivedBox::get_pin_mut)
+        }
+    }
+
+    fn document() -> Document {
+        Document {
+            id: 7,
+            title: "Notes".to_string(),
+            body: "a body which is too long to be stored inline".to_string(),
+            counts: (0..32).collect(),
+            blob: vec![0xaa; 17].into_boxed_slice(),
+            scores: (0..20).map(|i| (format!("key {}", i), i * 10)).collect(),
+            index: (0..40).map(|i| (i, format!("entry {}", i))).collect(),
+            parent: Some(Box::new(u64::MAX)),
+        }
+    }
+
+    fn check_archived(archived: &ArchivedDocument, value: &Document) {
+        assert_eq!(archived.id, value.id);
+        assert_eq!(archived.title, value.title);
+        assert_eq!(archived.body, value.body);
+        assert_eq!(archived.counts.len(), value.counts.len());
+        assert!(archived
+            .counts
+            .iter()
+            .zip(&value.counts)
+            .all(|(a, b)| a == b));
+        assert_eq!(archived.blob.as_ref(), value.blob.as_ref());
+        assert_eq!(archived.scores.len(), value.scores.len());
+        for (key, score) in archived.scores.iter() {
+            assert_eq!(value.scores[key.as_str()], *score);
+        }
+        for (key, score) in value.scores.iter() {
+            assert_eq!(archived.scores[key.as_str()], *score);
+        }
+        assert_eq!(archived.index.len(), value.index.len());
+        assert!(archived
+            .index
+            .iter()
+            .zip(value.index.iter())
+            .all(|((ak, av), (k, v))| ak == k && av == v));
+        assert_eq!(
+            archived.parent.as_ref().map(|p| p.to_native()),
+            value.parent.as_deref().copied(),
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn provenance_access_and_iterate() {
+        let value = document();
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let archived = unsafe { access_unchecked::<ArchivedDocument>(&bytes) };
+        check_archived(archived, &value);
+
+        let archived = access::<ArchivedDocument, Failure>(&bytes).unwrap();
+        check_archived(archived, &value);
+
+        // Every out-of-line payload must be resolved inside of the buffer
+        let range = bytes.as_ptr_range();
+        assert!(range.contains(&archived.body.as_ptr()));
+        assert!(range.contains(&archived.counts.as_ptr().cast()));
+        assert!(range.contains(&archived.blob.as_ptr()));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn provenance_pinned_mutation() {
+        let mut value = document();
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let mut archived =
+            access_mut::<ArchivedDocument, Failure>(&mut bytes).unwrap();
+        for count in archived.as_mut().counts().as_mut_slice() {
+            *count = Archived::<u32>::from_native(count.to_native() * 2);
+        }
+        for mut score in archived.as_mut().scores().values_mut() {
+            *score = Archived::<u32>::from_native(score.to_native() + 1);
+        }
+        for (key, mut score) in archived.as_mut().scores().iter_mut() {
+            if key == "key 3" {
+                *score = Archived::<u32>::from_native(0);
+            }
+        }
+        let scores = archived.as_mut().scores();
+        let mut score = ArchivedHashMap::get_mut(scores, "key 5").unwrap();
+        *score = Archived::<u32>::from_native(score.to_native() * 3);
+        *archived.as_mut().parent().unwrap() = Archived::<u64>::from_native(1);
+
+        for count in value.counts.iter_mut() {
+            *count *= 2;
+        }
+        for score in value.scores.values_mut() {
+            *score += 1;
+        }
+        *value.scores.get_mut("key 3").unwrap() = 0;
+        *value.scores.get_mut("key 5").unwrap() *= 3;
+        value.parent = Some(Box::new(1));
+
+        let archived = access::<ArchivedDocument, Failure>(&bytes).unwrap();
+        check_archived(archived, &value);
+        assert_eq!(from_bytes::<Document, Failure>(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn provenance_validation_stays_in_bounds() {
+        let value = document();
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        // Truncating the archive moves relative pointers out of bounds
+        for len in (0..bytes.len()).step_by(13) {
+            let mut truncated = rkyv::util::AlignedVec::new();
+            let start = bytes.len() - len;
+            truncated.extend_from_slice(&bytes[start..]);
+            let _ = access::<ArchivedDocument, Failure>(&truncated);
+        }
+
+        // Corrupting each byte of the root must never read out of bounds
+        let root = bytes.len() - core::mem::size_of::<ArchivedDocument>();
+        for pos in root..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[pos] ^= 0x80;
+            let _ = access::<ArchivedDocument, Failure>(&corrupted);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use rkyv::test_util::{
+        arbitrary_roundtrip, roundtrip, roundtrip_without_archived_eq,
+    };
+
+    #[test]
+    fn roundtrip_hash_map() {
+        arbitrary_roundtrip::<HashMap<u8, Vec<u32>>>();
+        arbitrary_roundtrip::<HashMap<u8, Option<String>>>();
+    }
+
+    #[test]
+    fn roundtrip_hash_map_string_keys() {
+        let mut value = HashMap::new();
+        roundtrip_without_archived_eq(&value);
+
+        for i in 0..100 {
+            value.insert(i.to_string(), format!("value {}", i));
+            roundtrip_without_archived_eq(&value);
+        }
+    }
+
+    #[test]
+    fn roundtrip_btree_map() {
+        arbitrary_roundtrip::<BTreeMap<String, u32>>();
+        arbitrary_roundtrip::<BTreeMap<u32, Option<String>>>();
+    }
+
+    #[test]
+    fn roundtrip_nested() {
+        roundtrip(&vec![Some(vec![1u8, 2, 3]), None, Some(Vec::new())]);
+        arbitrary_roundtrip::<Vec<Option<Vec<u16>>>>();
+    }
+}
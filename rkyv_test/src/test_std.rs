@@ -4,8 +4,8 @@ mod tests {
 
     use rkyv::{
         access_unchecked, rancor::Failure, ser::writer::IoWriter, serialize,
-        to_bytes, util::AlignedBytes, Archive, Archived, Deserialize,
-        Serialize,
+        to_bytes, util::serialize_into, util::AlignedBytes, Archive, Archived,
+        Deserialize, Serialize,
     };
     #[cfg(feature = "wasm")]
     use wasm_bindgen_test::*;
@@ -54,6 +54,38 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_hash_map_endian_keys() {
+        use rkyv::rend::{u32_be, u32_le};
+
+        let be_map = (0..100)
+            .map(|i| (u32_be::from_native(i), i.to_string()))
+            .collect::<HashMap<_, _>>();
+        let buf = to_bytes::<_, 256, Failure>(&be_map).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<HashMap<u32_be, String>>>(buf.as_ref())
+        };
+        assert_eq!(archived.len(), 100);
+        for i in 0..100 {
+            assert_eq!(archived[&u32_be::from_native(i)], i.to_string());
+        }
+        assert!(archived.get(&u32_be::from_native(100)).is_none());
+
+        let le_map = (0..100)
+            .map(|i| (u32_le::from_native(i), i.to_string()))
+            .collect::<HashMap<_, _>>();
+        let buf = to_bytes::<_, 256, Failure>(&le_map).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<HashMap<u32_le, String>>>(buf.as_ref())
+        };
+        assert_eq!(archived.len(), 100);
+        for i in 0..100 {
+            assert_eq!(archived[&u32_le::from_native(i)], i.to_string());
+        }
+        assert!(archived.get(&u32_le::from_native(100)).is_none());
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn archive_hash_map_tuple_retrieved_by_get_with() {
@@ -128,6 +160,145 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn with_map_kv() {
+        use std::{
+            collections::BTreeMap,
+            hash::{Hash, Hasher},
+            time::{Duration, SystemTime, UNIX_EPOCH},
+        };
+
+        use rkyv::{
+            deserialize,
+            rancor::Fallible,
+            ser::Writer,
+            string::{ArchivedString, StringResolver},
+            with::{
+                ArchiveWith, DeserializeWith, HashWith, MapK, MapKV, MapV,
+                SerializeWith, UnixTimestamp,
+            },
+        };
+
+        // Archives strings in lowercase
+        struct CaseFolded;
+
+        impl ArchiveWith<String> for CaseFolded {
+            type Archived = ArchivedString;
+            type Resolver = StringResolver;
+
+            unsafe fn resolve_with(
+                field: &String,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedString::resolve_from_str(
+                    &field.to_lowercase(),
+                    pos,
+                    resolver,
+                    out,
+                );
+            }
+        }
+
+        impl<S: Fallible + Writer + ?Sized> SerializeWith<String, S> for CaseFolded {
+            fn serialize_with(
+                field: &String,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedString::serialize_from_str(
+                    &field.to_lowercase(),
+                    serializer,
+                )
+            }
+        }
+
+        impl<D: Fallible + ?Sized> DeserializeWith<ArchivedString, String, D>
+            for CaseFolded
+        {
+            fn deserialize_with(
+                field: &ArchivedString,
+                _: &mut D,
+            ) -> Result<String, D::Error> {
+                Ok(field.as_str().to_string())
+            }
+        }
+
+        impl HashWith<String> for CaseFolded {
+            fn hash_with<H: Hasher>(field: &String, state: &mut H) {
+                field.to_lowercase().as_str().hash(state);
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Config {
+            #[with(MapKV<CaseFolded, UnixTimestamp>)]
+            modified: HashMap<String, SystemTime>,
+            #[with(MapV<UnixTimestamp>)]
+            created: BTreeMap<String, SystemTime>,
+            #[with(MapK<CaseFolded>)]
+            aliases: Vec<(String, u32)>,
+        }
+
+        let time = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        let value = Config {
+            modified: [
+                ("README.md", 100),
+                ("Cargo.toml", 200),
+                ("src/Lib.rs", 300),
+                ("a file name that is too long to inline", 400),
+            ]
+            .into_iter()
+            .map(|(name, secs)| (name.to_string(), time(secs)))
+            .collect(),
+            created: [("Cargo.toml", 10), ("README.md", 20)]
+                .into_iter()
+                .map(|(name, secs)| (name.to_string(), time(secs)))
+                .collect(),
+            aliases: vec![("Main".to_string(), 1), ("LIB".to_string(), 2)],
+        };
+
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedConfig>(&buf) };
+
+        // Keys are folded and can be looked up by their archived form
+        assert_eq!(archived.modified.len(), 4);
+        assert_eq!(archived.modified["readme.md"].as_secs(), 100);
+        assert_eq!(archived.modified["cargo.toml"].as_secs(), 200);
+        assert_eq!(archived.modified["src/lib.rs"].as_secs(), 300);
+        assert_eq!(
+            archived.modified["a file name that is too long to inline"]
+                .as_secs(),
+            400
+        );
+        assert!(archived.modified.get("README.md").is_none());
+
+        assert_eq!(archived.created.len(), 2);
+        assert_eq!(archived.created.get("Cargo.toml").unwrap().as_secs(), 10);
+        assert_eq!(archived.created.get("README.md").unwrap().as_secs(), 20);
+
+        assert_eq!(archived.aliases.len(), 2);
+        assert_eq!(archived.aliases[0].0, "main");
+        assert_eq!(archived.aliases[0].1, 1);
+        assert_eq!(archived.aliases[1].0, "lib");
+        assert_eq!(archived.aliases[1].1, 2);
+
+        // Deserialized keys keep their folded form
+        let deserialized =
+            deserialize::<Config, _, Failure>(archived, &mut ()).unwrap();
+        let folded = Config {
+            modified: value
+                .modified
+                .iter()
+                .map(|(name, time)| (name.to_lowercase(), *time))
+                .collect(),
+            created: value.created.clone(),
+            aliases: vec![("main".to_string(), 1), ("lib".to_string(), 2)],
+        };
+        assert_eq!(deserialized, folded);
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn archive_hash_set() {
@@ -241,63 +412,100 @@ mod tests {
         test_archive(&value);
     }
 
-    // TODO: figure out errors
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn mutex() {
+        use std::sync::Mutex;
 
-    // #[test]
-    // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    // fn mutex() {
-    //     use rkyv::with::Lock;
-    //     use std::sync::Mutex;
+        use rkyv::{util::deserialize, with::Lock};
 
-    //     #[derive(Archive, Serialize, Deserialize)]
-    //     struct Test {
-    //         #[with(Lock)]
-    //         value: Mutex<i32>,
-    //     }
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[with(Lock)]
+            value: Mutex<String>,
+        }
 
-    //     let value = Test {
-    //         value: Mutex::new(10),
-    //     };
-    //     let mut serializer = AlignedSerializer::new(AlignedVec::new());
-    //     serializer.serialize_value(&value).unwrap();
-    //     let result = serializer.into_inner();
-    //     let archived = unsafe { archived_root::<Test>(result.as_slice()) };
+        let value = Test {
+            value: Mutex::new("hello world".to_string()),
+        };
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedTest>(&buf) };
+        assert_eq!(*archived.value.value(), "hello world");
 
-    //     assert_eq!(*archived.value, 10);
+        let deserialized =
+            deserialize::<Test, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(*deserialized.value.lock().unwrap(), "hello world");
+    }
 
-    //     let deserialized: Test = archived.deserialize(&mut
-    // Infallible).unwrap();
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn rwlock() {
+        use std::sync::RwLock;
 
-    //     assert_eq!(*deserialized.value.lock().unwrap(), 10);
-    // }
+        use rkyv::{util::deserialize, with::Lock};
 
-    // #[test]
-    // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    // fn rwlock() {
-    //     use rkyv::with::Lock;
-    //     use std::sync::RwLock;
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[with(Lock)]
+            value: RwLock<Vec<i32>>,
+        }
 
-    //     #[derive(Archive, Serialize, Deserialize)]
-    //     struct Test {
-    //         #[with(Lock)]
-    //         value: RwLock<i32>,
-    //     }
+        let value = Test {
+            value: RwLock::new(vec![1, 2, 3]),
+        };
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedTest>(&buf) };
+        assert_eq!(*archived.value.value(), [1, 2, 3]);
+
+        // Read locks may be held while serializing
+        let guard = value.value.read().unwrap();
+        to_bytes::<_, 256, Failure>(&value).unwrap();
+        drop(guard);
+
+        let deserialized =
+            deserialize::<Test, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(*deserialized.value.read().unwrap(), [1, 2, 3]);
+    }
 
-    //     let value = Test {
-    //         value: RwLock::new(10),
-    //     };
-    //     let mut serializer = AlignedSerializer::new(AlignedVec::new());
-    //     serializer.serialize_value(&value).unwrap();
-    //     let result = serializer.into_inner();
-    //     let archived = unsafe { archived_root::<Test>(result.as_slice()) };
+    #[test]
+    fn poisoned_lock() {
+        use std::{
+            panic::{catch_unwind, AssertUnwindSafe},
+            sync::{Mutex, RwLock},
+        };
 
-    //     assert_eq!(*archived.value, 10);
+        use rkyv::{rancor::BoxedError, with::Lock};
 
-    //     let deserialized: Test = archived.deserialize(&mut
-    // Infallible).unwrap();
+        #[derive(Archive, Serialize)]
+        struct Test {
+            #[with(Lock)]
+            mutex: Mutex<i32>,
+            #[with(Lock)]
+            rwlock: RwLock<i32>,
+        }
 
-    //     assert_eq!(*deserialized.value.read().unwrap(), 10);
-    // }
+        let value = Test {
+            mutex: Mutex::new(1),
+            rwlock: RwLock::new(2),
+        };
+
+        let _ = catch_unwind(AssertUnwindSafe(|| {
+            let _guard = value.mutex.lock().unwrap();
+            panic!("poisoning the mutex");
+        }));
+        let error = to_bytes::<_, 256, BoxedError>(&value).unwrap_err();
+        assert!(error.to_string().contains("lock poisoned"));
+
+        value.mutex.clear_poison();
+        to_bytes::<_, 256, BoxedError>(&value).unwrap();
+
+        let _ = catch_unwind(AssertUnwindSafe(|| {
+            let _guard = value.rwlock.write().unwrap();
+            panic!("poisoning the rwlock");
+        }));
+        let error = to_bytes::<_, 256, BoxedError>(&value).unwrap_err();
+        assert!(error.to_string().contains("lock poisoned"));
+    }
 
     // #[test]
     // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
@@ -379,4 +587,3324 @@ mod tests {
         value.insert(());
         test_archive(&value);
     }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_io_error_kind() {
+        use std::io::ErrorKind;
+
+        for kind in [
+            ErrorKind::Other,
+            ErrorKind::NotFound,
+            ErrorKind::PermissionDenied,
+            ErrorKind::ConnectionRefused,
+            ErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted,
+            ErrorKind::NotConnected,
+            ErrorKind::AddrInUse,
+            ErrorKind::AddrNotAvailable,
+            ErrorKind::BrokenPipe,
+            ErrorKind::AlreadyExists,
+            ErrorKind::WouldBlock,
+            ErrorKind::InvalidInput,
+            ErrorKind::InvalidData,
+            ErrorKind::TimedOut,
+            ErrorKind::WriteZero,
+            ErrorKind::Interrupted,
+            ErrorKind::UnexpectedEof,
+            ErrorKind::Unsupported,
+            ErrorKind::OutOfMemory,
+        ] {
+            test_archive(&kind);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_unknown_io_error_kind() {
+        use std::io::{self, ErrorKind};
+
+        use rkyv::{io::ArchivedErrorKind, util::deserialize};
+
+        // Unrecognized OS errors have an unstable error kind which does not
+        // have an archived representation.
+        let kind = io::Error::from_raw_os_error(i32::MAX).kind();
+        assert_ne!(kind, ErrorKind::Other);
+
+        let buf = to_bytes::<_, 256, Failure>(&kind).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<ErrorKind>>(buf.as_ref()) };
+        assert_eq!(*archived, ArchivedErrorKind::Other);
+
+        let deserialized =
+            deserialize::<ErrorKind, _, Failure>(archived, &mut ()).unwrap();
+        assert_eq!(deserialized, ErrorKind::Other);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archived_collections_into_iter() {
+        use std::{
+            collections::{hash_map::RandomState, BTreeMap},
+            iter::FusedIterator,
+        };
+
+        fn sum<'a, I>(iter: I) -> u32
+        where
+            I: IntoIterator<Item = &'a Archived<u32>>,
+        {
+            iter.into_iter().map(|x| x.to_native()).sum()
+        }
+
+        fn sum_values<'a, K: 'a, I>(iter: I) -> u32
+        where
+            I: IntoIterator<Item = (&'a K, &'a Archived<u32>)>,
+        {
+            iter.into_iter().map(|(_, v)| v.to_native()).sum()
+        }
+
+        fn exact_len<I>(iter: I) -> usize
+        where
+            I: IntoIterator,
+            I::IntoIter: ExactSizeIterator + FusedIterator,
+        {
+            iter.into_iter().len()
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Collections {
+            vec: Vec<u32>,
+            boxed: Box<[u32]>,
+            some: Option<u32>,
+            none: Option<u32>,
+            hash_map: HashMap<String, u32>,
+            btree_map: BTreeMap<String, u32>,
+        }
+
+        let value = Collections {
+            vec: vec![1, 2, 3, 4],
+            boxed: vec![5, 6, 7].into_boxed_slice(),
+            some: Some(8),
+            none: None,
+            hash_map: [("a", 9), ("b", 10), ("c", 11)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+            btree_map: [("d", 12), ("e", 13)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        };
+
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived =
+            unsafe { access_unchecked::<ArchivedCollections>(buf.as_ref()) };
+
+        assert_eq!(sum(&archived.vec), 10);
+        assert_eq!(sum(&archived.boxed), 18);
+        assert_eq!(sum(&archived.some), 8);
+        assert_eq!(sum(&archived.none), 0);
+        assert_eq!(sum_values(&archived.hash_map), 30);
+        assert_eq!(sum_values(&archived.btree_map), 25);
+
+        assert_eq!(exact_len(&archived.vec), 4);
+        assert_eq!(exact_len(&archived.boxed), 3);
+        assert_eq!(exact_len(&archived.some), 1);
+        assert_eq!(exact_len(&archived.none), 0);
+        assert_eq!(exact_len(&archived.hash_map), 3);
+        assert_eq!(exact_len(&archived.btree_map), 2);
+
+        let hash_map = archived
+            .hash_map
+            .to_hashmap::<String, u32, RandomState, _, Failure>(&mut ())
+            .unwrap();
+        assert_eq!(hash_map, value.hash_map);
+
+        let mut merged = hash_map;
+        archived
+            .btree_map
+            .collect_into_hashmap::<_, _, _, _, Failure>(&mut merged, &mut ())
+            .unwrap();
+        assert_eq!(merged.len(), 5);
+        assert_eq!(merged["e"], 13);
+    }
+
+    #[test]
+    #[cfg(all(feature = "little_endian", feature = "pointer_width_32"))]
+    fn export_layout_c_header() {
+        use rkyv::layout::{describe, to_c_header, LayoutKind};
+
+        #[derive(Archive)]
+        #[archive(export_layout)]
+        struct Point {
+            x: f32,
+            y: f32,
+        }
+
+        #[derive(Archive)]
+        #[archive(export_layout)]
+        #[allow(dead_code)]
+        enum Shape {
+            Circle { center: Point, radius: f32 },
+            Polygon(Vec<Point>),
+            Empty,
+        }
+
+        #[derive(Archive)]
+        #[archive(export_layout)]
+        #[allow(dead_code)]
+        struct Drawing {
+            id: u64,
+            visible: bool,
+            name: String,
+            color: [u8; 3],
+            shapes: Vec<Shape>,
+            tags: HashMap<String, u32>,
+        }
+
+        const GOLDEN: &str = "src/golden/drawing.h";
+
+        let header = to_c_header::<ArchivedDrawing>();
+        if std::env::var_os("RKYV_BLESS").is_some() {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+            std::fs::write(path.join(GOLDEN), &header).unwrap();
+        }
+        assert_eq!(header, include_str!("golden/drawing.h"));
+
+        let layouts = describe::<ArchivedShape>();
+        let shape = layouts.last().unwrap();
+        match shape.kind {
+            LayoutKind::Enum {
+                ref tag,
+                ref variants,
+            } => {
+                assert_eq!(tag, "u8");
+                assert_eq!(variants.len(), 3);
+                assert_eq!(variants[1].name, "Polygon");
+                assert_eq!(variants[1].tag, 1);
+                assert_eq!(variants[1].fields[0].name, "_0");
+                assert_eq!(variants[1].fields[0].offset, 4);
+                assert_eq!(
+                    variants[1].fields[0].ty,
+                    "ArchivedVec_ArchivedPoint"
+                );
+            }
+            _ => panic!("expected an enum layout"),
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "reflect",
+        feature = "little_endian",
+        feature = "pointer_width_32"
+    ))]
+    fn reflect_dump() {
+        use std::rc::Rc;
+
+        use rkyv::reflect::{dump, dump_with, DumpOptions, Reflect};
+
+        #[derive(Archive, Serialize)]
+        #[archive(reflect)]
+        struct Owner {
+            name: String,
+            since: u16,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(reflect)]
+        enum Status {
+            Active,
+            Moved(u32),
+            Retired { reason: String },
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(reflect)]
+        struct Item {
+            sku: u32,
+            price: f64,
+            status: Status,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(reflect)]
+        struct Inventory {
+            id: u64,
+            name: String,
+            owner: Option<Box<Owner>>,
+            previous: Option<Box<Owner>>,
+            codes: [char; 2],
+            items: Vec<Item>,
+            samples: Box<[i16]>,
+            notes: Rc<str>,
+            stock: HashMap<String, u32>,
+            regions: HashSet<u8>,
+            checked: bool,
+        }
+
+        let value = Inventory {
+            id: 1234,
+            name: "The warehouse on the corner".to_string(),
+            owner: Some(Box::new(Owner {
+                name: "Ada".to_string(),
+                since: 1843,
+            })),
+            previous: None,
+            codes: ['r', '\u{1f980}'],
+            items: (0..10)
+                .map(|i| Item {
+                    sku: 100 + i,
+                    price: i as f64 * 1.5,
+                    status: match i % 3 {
+                        0 => Status::Active,
+                        1 => Status::Moved(i),
+                        _ => Status::Retired {
+                            reason: format!("batch {}", i),
+                        },
+                    },
+                })
+                .collect(),
+            samples: vec![-1, 0, 1].into_boxed_slice(),
+            notes: "fragile".into(),
+            stock: [("bolts", 40), ("nuts", 25)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+            regions: [3, 7].into_iter().collect(),
+            checked: true,
+        };
+
+        const GOLDEN: &str = "src/golden/inventory.txt";
+
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let mut tree = String::new();
+        dump::<Failure>(&bytes, &ArchivedInventory::DESCRIPTOR, &mut tree)
+            .unwrap();
+        if std::env::var_os("RKYV_BLESS").is_some() {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+            std::fs::write(path.join(GOLDEN), &tree).unwrap();
+        }
+        assert_eq!(tree, include_str!("golden/inventory.txt"));
+
+        // Collections with more than twice the edge elements are summarized.
+        let options = DumpOptions {
+            edge_elements: 1,
+            ..DumpOptions::default()
+        };
+        let mut summary = String::new();
+        dump_with::<Failure>(
+            &bytes,
+            &ArchivedInventory::DESCRIPTOR,
+            &options,
+            &mut summary,
+        )
+        .unwrap();
+        assert!(summary.contains("... 8 more\n"));
+
+        // Archives which don't match the descriptor fail to print.
+        let mut truncated = String::new();
+        assert!(dump::<Failure>(
+            &bytes[bytes.len() - 8..],
+            &ArchivedInventory::DESCRIPTOR,
+            &mut truncated,
+        )
+        .is_err());
+        let mut corrupted = bytes.to_vec();
+        let root = bytes.len() - core::mem::size_of::<ArchivedInventory>();
+        corrupted[root + core::mem::offset_of!(ArchivedInventory, checked)] = 2;
+        let mut output = String::new();
+        assert!(dump::<Failure>(
+            &corrupted,
+            &ArchivedInventory::DESCRIPTOR,
+            &mut output,
+        )
+        .is_err());
+        assert!(output.ends_with("    checked: bool @ 0x220 = "));
+
+        // B-tree maps are printed in order across all of their leaves.
+        let map = (0..2000u32)
+            .map(|i| (i, i.to_string()))
+            .collect::<std::collections::BTreeMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+        let mut tree = String::new();
+        dump_with::<Failure>(
+            &bytes,
+            &<Archived<std::collections::BTreeMap<u32, String>>>::DESCRIPTOR,
+            &options,
+            &mut tree,
+        )
+        .unwrap();
+        let lines = tree.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("BTreeMap<u32, String> @ "));
+        assert!(lines[0].ends_with("(len 2000)"));
+        assert!(lines[2].ends_with("= 0"));
+        assert!(lines[3].ends_with("= \"0\" (len 1)"));
+        assert_eq!(lines[4], "    ... 1998 more");
+        assert!(lines[6].ends_with("= 1999"));
+        assert!(lines[7].ends_with("= \"1999\" (len 4)"));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn padding_is_zeroed() {
+        use rkyv::ser::{
+            allocator::GlobalAllocator, sharing::Unify, writer::BufferWriter,
+            Composite, Positional,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive_attr(repr(C, align(64)))]
+        struct Aligned {
+            x: u8,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[allow(dead_code)]
+        enum Value {
+            Small(u8),
+            Large(u64, u16),
+            Empty,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Example {
+            a: u8,
+            b: u64,
+            c: u16,
+            value: Value,
+            option: Option<u64>,
+            aligned: Vec<Aligned>,
+            map: HashMap<u8, u32>,
+            values: Vec<Value>,
+        }
+
+        let value = Example {
+            a: 1,
+            b: 2,
+            c: 3,
+            value: Value::Small(4),
+            option: Some(5),
+            aligned: vec![Aligned { x: 6 }, Aligned { x: 7 }],
+            map: (0..20).map(|i| (i, i as u32 * 3)).collect(),
+            values: vec![Value::Empty, Value::Large(8, 9), Value::Small(10)],
+        };
+
+        fn serialize_dirty(value: &Example, fill: u8) -> Vec<u8> {
+            let mut buffer = AlignedBytes([fill; 1024]);
+            let serializer = Composite::new(
+                BufferWriter::new(&mut buffer),
+                GlobalAllocator::default(),
+                Unify::default(),
+            );
+            let len = serialize_into::<_, _, Failure>(value, serializer)
+                .unwrap()
+                .into_writer()
+                .pos();
+            buffer[..len].to_vec()
+        }
+
+        let first = serialize_dirty(&value, 0xaa);
+        let second = serialize_dirty(&value, 0x55);
+        assert_eq!(first, second);
+        assert_eq!(
+            first,
+            to_bytes::<_, 256, Failure>(&value).unwrap().as_slice()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archived_std_traits_match_native() {
+        use core::hash::{Hash, Hasher};
+        use std::collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet};
+
+        use rkyv::{rancor::Strategy, util::AlignedVec};
+
+        fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        fn archive_all<T>(values: &[T]) -> Vec<AlignedVec>
+        where
+            T: Serialize<Strategy<DefaultSerializer, Failure>>,
+        {
+            values
+                .iter()
+                .map(|v| to_bytes::<_, 256, Failure>(v).unwrap())
+                .collect()
+        }
+
+        fn check_partial_ord<T>(values: &[T])
+        where
+            T: PartialOrd + Serialize<Strategy<DefaultSerializer, Failure>>,
+            T::Archived: PartialOrd,
+        {
+            let bytes = archive_all(values);
+            for (a, a_bytes) in values.iter().zip(bytes.iter()) {
+                let archived_a =
+                    unsafe { access_unchecked::<T::Archived>(a_bytes) };
+                for (b, b_bytes) in values.iter().zip(bytes.iter()) {
+                    let archived_b =
+                        unsafe { access_unchecked::<T::Archived>(b_bytes) };
+                    assert_eq!(archived_a == archived_b, a == b);
+                    assert_eq!(
+                        archived_a.partial_cmp(archived_b),
+                        a.partial_cmp(b),
+                    );
+                }
+            }
+        }
+
+        fn check<T>(values: &[T])
+        where
+            T: Hash + Ord + Serialize<Strategy<DefaultSerializer, Failure>>,
+            T::Archived: Hash + Ord,
+        {
+            check_partial_ord(values);
+
+            let bytes = archive_all(values);
+            for (a, a_bytes) in values.iter().zip(bytes.iter()) {
+                let archived_a =
+                    unsafe { access_unchecked::<T::Archived>(a_bytes) };
+                assert_eq!(hash(archived_a), hash(a));
+                for (b, b_bytes) in values.iter().zip(bytes.iter()) {
+                    let archived_b =
+                        unsafe { access_unchecked::<T::Archived>(b_bytes) };
+                    assert_eq!(archived_a.cmp(archived_b), a.cmp(b));
+                }
+            }
+        }
+
+        #[derive(Archive, Serialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        #[archive(derive_std)]
+        struct Record {
+            flag: u8,
+            id: u64,
+            name: String,
+            tags: Vec<u16>,
+        }
+
+        #[derive(Archive, Serialize, PartialEq, PartialOrd)]
+        #[archive(derive_std)]
+        struct Point {
+            x: f32,
+            y: f32,
+        }
+
+        #[derive(Archive, Serialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        #[archive(derive_std)]
+        enum Shape {
+            Empty,
+            Circle(u32),
+            Polygon { sides: u16, name: String },
+        }
+
+        check(&[
+            "b".to_string(),
+            "a".to_string(),
+            "a longer string".to_string(),
+        ]);
+        check(&[vec![1u32, 2], vec![], vec![1], vec![0, 5, 6]]);
+        check(&[Box::<str>::from("box"), Box::from("a")]);
+        check(&[Some(3u32), None, Some(1)]);
+        check(&[
+            (2u32, "x".to_string()),
+            (1, "y".to_string()),
+            (1, "x".to_string()),
+        ]);
+        check(&[
+            BTreeMap::from([("a".to_string(), 1u32), ("b".to_string(), 2)]),
+            BTreeMap::new(),
+            BTreeMap::from([("a".to_string(), 2u32)]),
+        ]);
+        check(&[BTreeSet::from([1u32, 2, 3]), BTreeSet::from([2u32])]);
+        check(&[
+            Record {
+                flag: 2,
+                id: 1,
+                name: "b".to_string(),
+                tags: vec![1],
+            },
+            Record {
+                flag: 1,
+                id: 2,
+                name: "a".to_string(),
+                tags: vec![],
+            },
+            Record {
+                flag: 1,
+                id: 2,
+                name: "a".to_string(),
+                tags: vec![0, 1],
+            },
+        ]);
+        check(&[
+            Shape::Polygon {
+                sides: 3,
+                name: "tri".to_string(),
+            },
+            Shape::Circle(2),
+            Shape::Empty,
+            Shape::Circle(1),
+        ]);
+        check_partial_ord(&[
+            Point { x: 1.0, y: 2.0 },
+            Point {
+                x: 1.0,
+                y: f32::NAN,
+            },
+            Point { x: 0.5, y: 3.0 },
+        ]);
+
+        let bytes = to_bytes::<_, 256, Failure>(&Record {
+            flag: 1,
+            id: 2,
+            name: "a".to_string(),
+            tags: vec![3],
+        })
+        .unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedRecord>(&bytes) };
+        assert_eq!(
+            format!("{:?}", archived),
+            "ArchivedRecord { flag: 1, id: 2, name: \"a\", tags: [3] }",
+        );
+
+        let bytes = to_bytes::<_, 256, Failure>(&Shape::Circle(4)).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedShape>(&bytes) };
+        assert_eq!(format!("{:?}", archived), "Circle(4)");
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn deserialized_size_estimate_matches_allocations() {
+        use core::mem::size_of;
+        use std::collections::{BTreeMap, HashSet};
+
+        use rkyv::{
+            deep_size::ArchivedDeepSize, deserialize, rancor::Strategy,
+            util::deserialized_size_estimate, Deserialize,
+        };
+
+        use crate::util::counting_alloc::live_bytes;
+
+        // Checks that the estimated heap size is within `tolerance` percent of
+        // the heap memory actually allocated by deserializing the value.
+        fn check<T>(value: &T, tolerance: usize)
+        where
+            T: Serialize<Strategy<DefaultSerializer, Failure>>,
+            T::Archived: ArchivedDeepSize<Deserialized = T>
+                + Deserialize<T, Strategy<DefaultDeserializer, Failure>>,
+        {
+            let bytes = to_bytes::<_, 256, Failure>(value).unwrap();
+            let archived = unsafe { access_unchecked::<T::Archived>(&bytes) };
+            let estimate = deserialized_size_estimate(archived);
+            assert_eq!(
+                estimate,
+                size_of::<T>() + archived.deserialized_heap_size()
+            );
+
+            let mut deserializer = DefaultDeserializer::default();
+            let before = live_bytes();
+            let deserialized =
+                deserialize::<T, _, Failure>(archived, &mut deserializer)
+                    .unwrap();
+            let allocated = live_bytes().wrapping_sub(before);
+            drop(deserialized);
+
+            let estimated = estimate - size_of::<T>();
+            let difference = estimated.abs_diff(allocated);
+            assert!(
+                difference * 100 <= allocated * tolerance,
+                "estimated {} heap bytes but {} were allocated",
+                estimated,
+                allocated,
+            );
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(deep_size)]
+        struct Item {
+            id: u32,
+            name: String,
+            tags: Vec<u16>,
+            parent: Option<Box<(u64, String)>>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(deep_size)]
+        enum Event {
+            Ping,
+            Message(String),
+            Batch {
+                items: Vec<Item>,
+                weights: Box<[f32]>,
+            },
+        }
+
+        fn item(i: u32) -> Item {
+            Item {
+                id: i,
+                name: format!("item number {}", i),
+                tags: (0..i as u16 % 7).collect(),
+                parent: (i % 3 == 0)
+                    .then(|| Box::new((i as u64, "parent".to_string()))),
+            }
+        }
+
+        // Strings, vectors, and boxes are estimated exactly
+        check(&"a string of some length".to_string(), 0);
+        check(&(0..100u64).collect::<Vec<_>>(), 0);
+        check(&(0..50).map(item).collect::<Vec<_>>(), 0);
+        check(
+            &Event::Batch {
+                items: (0..20).map(item).collect(),
+                weights: vec![0.5; 13].into_boxed_slice(),
+            },
+            0,
+        );
+        check(&Event::Message("message".to_string()), 0);
+        check(&Event::Ping, 0);
+
+        // Maps and sets are estimated within 10%
+        check(
+            &(0..200)
+                .map(|i| (i, format!("value {}", i)))
+                .collect::<HashMap<u32, String>>(),
+            10,
+        );
+        check(&(0..1000u64).collect::<HashSet<_>>(), 10);
+        check(
+            &(0..1000)
+                .map(|i| (i, format!("value {}", i)))
+                .collect::<BTreeMap<u32, String>>(),
+            10,
+        );
+        check(&(0..10u64).map(|i| (i, i)).collect::<BTreeMap<_, _>>(), 10);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn deserialized_size_estimate_saturates() {
+        use core::mem::size_of;
+
+        use rkyv::{
+            deep_size::ArchivedDeepSize, util::deserialized_size_estimate,
+            vec::ArchivedVec, Archived, Portable,
+        };
+
+        // Archived as a zero-sized type, but 1 TiB once deserialized
+        struct Huge;
+
+        unsafe impl Portable for Huge {}
+
+        impl ArchivedDeepSize for Huge {
+            type Deserialized = [u8; 1 << 40];
+
+            fn deserialized_heap_size(&self) -> usize {
+                0
+            }
+        }
+
+        fn estimate_with_len(len: usize) -> usize {
+            let mut bytes = to_bytes::<_, 256, Failure>(&vec![(); 2]).unwrap();
+            let pos = bytes.len() - size_of::<Archived<usize>>();
+            unsafe {
+                bytes
+                    .as_mut_ptr()
+                    .add(pos)
+                    .cast::<Archived<usize>>()
+                    .write_unaligned(Archived::<usize>::from_native(len as _));
+            }
+            let archived =
+                unsafe { access_unchecked::<ArchivedVec<Huge>>(&bytes) };
+            assert_eq!(archived.len(), len);
+            deserialized_size_estimate(archived)
+        }
+
+        let max_len = usize::MAX / size_of::<[u8; 1 << 40]>();
+        assert_eq!(
+            estimate_with_len(max_len),
+            size_of::<Vec<[u8; 1 << 40]>>() + max_len * (1 << 40),
+        );
+        assert_eq!(estimate_with_len(max_len + 1), usize::MAX);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn migrate_vec_under_memory_cap() {
+        use core::mem::size_of;
+
+        use rkyv::{
+            access,
+            util::{migrate, migrate_vec},
+            vec::ArchivedVec,
+        };
+
+        use crate::util::counting_alloc::{peak_bytes, reset_peak};
+
+        const LEN: usize = 1_000_000;
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct RecordV1 {
+            id: u32,
+            name: String,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct RecordV2 {
+            id: u64,
+            name: String,
+            score: f32,
+        }
+
+        fn upgrade(old: &ArchivedRecordV1) -> Result<RecordV2, Failure> {
+            Ok(RecordV2 {
+                id: old.id.to_native().into(),
+                name: old.name.to_string(),
+                score: 0.5,
+            })
+        }
+
+        let old = (0..LEN as u32)
+            .map(|id| RecordV1 {
+                id,
+                name: format!("record {}", id),
+            })
+            .collect::<Vec<_>>();
+        let old_bytes = to_bytes::<_, 256, Failure>(&old).unwrap();
+        drop(old);
+
+        reset_peak();
+        let new_bytes = unsafe {
+            migrate_vec::<RecordV1, RecordV2, Failure>(&old_bytes, upgrade)
+        }
+        .unwrap();
+        let peak = peak_bytes();
+
+        // Only the output, the resolvers, and a small amount of scratch space
+        // may be allocated at once. Holding all of the new elements would take
+        // more than this on its own.
+        let cap = new_bytes.capacity()
+            + LEN * size_of::<RecordV2Resolver>()
+            + 64 * 1024;
+        assert!(LEN * size_of::<RecordV2>() > cap - new_bytes.capacity());
+        assert!(
+            peak <= cap,
+            "peak allocation was {} bytes but the cap is {}",
+            peak,
+            cap,
+        );
+
+        let new = access::<ArchivedVec<ArchivedRecordV2>, Failure>(&new_bytes)
+            .unwrap();
+        assert_eq!(new.len(), LEN);
+        for id in [0, 1, LEN / 2, LEN - 1] {
+            assert_eq!(new[id].id, id as u64);
+            assert_eq!(new[id].name, format!("record {}", id));
+            assert_eq!(new[id].score, 0.5);
+        }
+
+        // Migrating the whole vec at once produces the same archive
+        let small = to_bytes::<_, 256, Failure>(
+            &(0..100)
+                .map(|id| RecordV1 {
+                    id,
+                    name: format!("record {}", id),
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let streamed = unsafe {
+            migrate_vec::<RecordV1, RecordV2, Failure>(&small, upgrade)
+        }
+        .unwrap();
+        let whole =
+            migrate::<Vec<RecordV1>, Vec<RecordV2>, Failure>(&small, |old| {
+                old.iter().map(upgrade).collect()
+            })
+            .unwrap();
+        assert_eq!(&streamed[..], &whole[..]);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_ordered_hash_map() {
+        use core::{hash::Hash, mem::size_of};
+
+        use indexmap::{IndexMap, IndexSet};
+        use rkyv::{
+            access,
+            collections::swiss_table::{
+                ArchivedHashMap, ArchivedHashSet, HashMapResolver,
+                HashSetResolver,
+            },
+            primitive::ArchivedUsize,
+            rancor::{Error, Fallible},
+            ser::{Allocator, Writer},
+            string::ArchivedString,
+        };
+
+        struct OrderedMap<K, V>(IndexMap<K, V>);
+
+        impl<K: Archive + Hash + Eq, V: Archive> Archive for OrderedMap<K, V> {
+            type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+            type Resolver = HashMapResolver;
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedHashMap::resolve_from_len(
+                    self.0.len(),
+                    (7, 8),
+                    pos,
+                    resolver,
+                    out,
+                );
+            }
+        }
+
+        impl<K, V, S> Serialize<S> for OrderedMap<K, V>
+        where
+            K: Serialize<S> + Hash + Eq,
+            V: Serialize<S>,
+            S: Fallible + Writer + Allocator + ?Sized,
+            S::Error: Error,
+        {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedHashMap::<_, _>::serialize_from_iter_ordered(
+                    self.0.iter(),
+                    (7, 8),
+                    serializer,
+                )
+            }
+        }
+
+        struct OrderedSet<K>(IndexSet<K>);
+
+        impl<K: Archive + Hash + Eq> Archive for OrderedSet<K> {
+            type Archived = ArchivedHashSet<K::Archived>;
+            type Resolver = HashSetResolver;
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedHashSet::resolve_from_len(
+                    self.0.len(),
+                    (7, 8),
+                    pos,
+                    resolver,
+                    out,
+                );
+            }
+        }
+
+        impl<K, S> Serialize<S> for OrderedSet<K>
+        where
+            K: Serialize<S> + Hash + Eq,
+            S: Fallible + Writer + Allocator + ?Sized,
+            S::Error: Error,
+        {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedHashSet::<_>::serialize_from_iter_ordered(
+                    self.0.iter(),
+                    (7, 8),
+                    serializer,
+                )
+            }
+        }
+
+        type Map = ArchivedHashMap<ArchivedString, Archived<u32>>;
+
+        let index_map = (0..200u32)
+            .map(|i| (i * 37) % 200)
+            .map(|i| (format!("key {}", i), i))
+            .collect::<IndexMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&OrderedMap(index_map.clone()))
+            .unwrap();
+        let ordered = access::<Map, Failure>(&bytes).unwrap();
+
+        assert!(ordered.is_ordered());
+        assert_eq!(ordered.len(), index_map.len());
+        let iter = ordered.iter_ordered().unwrap();
+        assert_eq!(iter.len(), index_map.len());
+        assert!(iter
+            .map(|(k, v)| (k.as_str(), v.to_native()))
+            .eq(index_map.iter().map(|(k, v)| (k.as_str(), *v))));
+        for (n, (key, value)) in index_map.iter().enumerate() {
+            let (k, v) = ordered.get_index_ordered(n).unwrap();
+            assert_eq!(k, key);
+            assert_eq!(v, value);
+        }
+        assert!(ordered.get_index_ordered(index_map.len()).is_none());
+        assert!(ordered
+            .iter_ordered()
+            .unwrap()
+            .rev()
+            .map(|(k, _)| k.as_str())
+            .eq(index_map.keys().rev().map(String::as_str)));
+
+        // Lookups are the same as for a hash map without an order
+        let hash_map = index_map.clone().into_iter().collect::<HashMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&hash_map).unwrap();
+        let unordered = access::<Map, Failure>(&bytes).unwrap();
+        assert!(!unordered.is_ordered());
+        assert!(unordered.iter_ordered().is_none());
+        assert!(unordered.get_index_ordered(0).is_none());
+        assert_eq!(unordered.capacity(), ordered.capacity());
+        for i in 0..250u32 {
+            let key = format!("key {}", i);
+            assert_eq!(ordered.get(key.as_str()), unordered.get(key.as_str()));
+        }
+
+        // Empty ordered hash maps have no order indices
+        let bytes = to_bytes::<_, 256, Failure>(&OrderedMap(IndexMap::<
+            String,
+            u32,
+        >::new()))
+        .unwrap();
+        let empty = access::<Map, Failure>(&bytes).unwrap();
+        assert!(empty.is_ordered());
+        assert_eq!(empty.iter_ordered().unwrap().len(), 0);
+
+        // Marking a hash map without order indices as ordered fails
+        // validation because the order indices would overlap the root
+        let mut bytes = to_bytes::<_, 256, Failure>(&hash_map).unwrap();
+        let cap = bytes.len() - size_of::<ArchivedUsize>();
+        if cfg!(feature = "big_endian") {
+            bytes[cap] |= 0x80;
+        } else {
+            bytes[cap + size_of::<ArchivedUsize>() - 1] |= 0x80;
+        }
+        assert!(access::<Map, Failure>(&bytes).is_err());
+
+        let index_set = [5u16, 3, 9, 1, 7].into_iter().collect::<IndexSet<_>>();
+        let bytes = to_bytes::<_, 256, Failure>(&OrderedSet(index_set.clone()))
+            .unwrap();
+        let set =
+            access::<ArchivedHashSet<Archived<u16>>, Failure>(&bytes).unwrap();
+        assert!(set.is_ordered());
+        assert!(set
+            .iter_ordered()
+            .unwrap()
+            .map(|k| k.to_native())
+            .eq(index_set.iter().copied()));
+        assert_eq!(set.get_index_ordered(2).map(|k| k.to_native()), Some(9));
+        for key in 0..10u16 {
+            assert_eq!(
+                set.contains(&Archived::<u16>::from_native(key)),
+                index_set.contains(&key),
+            );
+        }
+    }
+
+    #[test]
+    fn fallible_serialization() {
+        use rkyv::{
+            from_bytes,
+            util::{try_to_bytes, AlignedVec},
+        };
+
+        use crate::util::counting_alloc::fail_after;
+
+        let mut vec = AlignedVec::new();
+        vec.extend_from_slice(&[1, 2, 3]);
+        fail_after(Some(0));
+        let reserved = vec.try_reserve(1024);
+        let with_capacity = AlignedVec::try_with_capacity(16);
+        fail_after(None);
+        assert!(reserved.is_err());
+        assert!(with_capacity.is_err());
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+
+        let value = (0..500).map(|i| format!("item {}", i)).collect::<Vec<_>>();
+        let mut failures = 0;
+        for budget in (0..32 * 1024).step_by(256) {
+            fail_after(Some(budget));
+            let result = try_to_bytes::<_, 256, Failure>(&value);
+            fail_after(None);
+            match result {
+                Ok(bytes) => assert_eq!(
+                    from_bytes::<Vec<String>, Failure>(&bytes).unwrap(),
+                    value
+                ),
+                Err(_) => failures += 1,
+            }
+        }
+        assert!(failures > 0);
+
+        fail_after(Some(1 << 20));
+        let result = try_to_bytes::<_, 256, Failure>(&value);
+        fail_after(None);
+        let bytes = result.unwrap();
+        assert_eq!(from_bytes::<Vec<String>, Failure>(&bytes).unwrap(), value);
+    }
+
+    #[cfg(feature = "fallible_alloc")]
+    #[test]
+    fn fallible_deserialization() {
+        use rkyv::{
+            access,
+            bytecheck::CheckBytes,
+            de::{Limited, Limits, Unify},
+            deserialize,
+            rancor::Strategy,
+            validation::validators::DefaultValidator,
+        };
+
+        use crate::util::counting_alloc::fail_after;
+
+        fn check<T>(value: &T)
+        where
+            T: PartialEq
+                + core::fmt::Debug
+                + Serialize<Strategy<DefaultSerializer, Failure>>,
+            T::Archived: CheckBytes<Strategy<DefaultValidator, Failure>>
+                + Deserialize<T, Strategy<Unify, Failure>>
+                + Deserialize<T, Strategy<Limited<Unify>, Failure>>,
+        {
+            let bytes = to_bytes::<_, 256, Failure>(value).unwrap();
+            let archived = access::<T::Archived, Failure>(&bytes).unwrap();
+
+            // Deserializers which don't handle allocation failures reserve
+            // memory infallibly
+            let deserialized =
+                deserialize::<T, _, Failure>(archived, &mut Unify::default())
+                    .unwrap();
+            assert_eq!(&deserialized, value);
+
+            let mut failures = 0;
+            for budget in (0..16 * 1024).step_by(128) {
+                let mut deserializer =
+                    Limited::new(Unify::default(), Limits::default());
+                fail_after(Some(budget));
+                let result =
+                    deserialize::<T, _, Failure>(archived, &mut deserializer);
+                fail_after(None);
+                match result {
+                    Ok(deserialized) => assert_eq!(&deserialized, value),
+                    Err(_) => failures += 1,
+                }
+            }
+            assert!(failures > 0);
+        }
+
+        check(&(0..500).map(|i| format!("item {}", i)).collect::<Vec<_>>());
+        check(&"hello world".repeat(100));
+        check(
+            &(0..200)
+                .map(|i| (i, format!("value {}", i)))
+                .collect::<HashMap<u32, String>>(),
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn rearchive_sub_object() {
+        use std::rc::Rc;
+
+        use rkyv::access;
+
+        #[derive(Archive, Serialize, Clone, Debug, PartialEq)]
+        #[archive(check_bytes, rearchive)]
+        enum Status {
+            Active,
+            Paused(u32),
+            Failed { code: i16, reason: String },
+        }
+
+        #[derive(Archive, Serialize, Clone, Debug, PartialEq)]
+        #[archive(check_bytes, rearchive)]
+        struct Inner {
+            values: Vec<u64>,
+            label: Option<String>,
+            status: Status,
+        }
+
+        #[derive(Archive, Serialize, Clone, Debug, PartialEq)]
+        #[archive(check_bytes, rearchive)]
+        struct Entry {
+            id: u32,
+            name: String,
+            tags: Vec<String>,
+            inner: Inner,
+            attributes: HashMap<String, Vec<u8>>,
+            owner: Rc<String>,
+            editor: Rc<String>,
+        }
+
+        fn entry(i: u32) -> Entry {
+            let owner = Rc::new(format!("owner of entry number {}", i / 10));
+            Entry {
+                id: i,
+                name: format!("entry {}", i),
+                tags: (0..i % 4).map(|t| format!("tag-{}-{}", i, t)).collect(),
+                inner: Inner {
+                    values: (0..i % 7).map(u64::from).collect(),
+                    label: (i % 2 == 0).then(|| format!("label {}", i)),
+                    status: match i % 3 {
+                        0 => Status::Active,
+                        1 => Status::Paused(i),
+                        _ => Status::Failed {
+                            code: -(i as i16),
+                            reason: "a reason which is not inline".into(),
+                        },
+                    },
+                },
+                attributes: (0..i % 3)
+                    .map(|a| (format!("attr {}", a), vec![a as u8; a as usize]))
+                    .collect(),
+                editor: owner.clone(),
+                owner,
+            }
+        }
+
+        let entries = (0..10_000)
+            .map(|i| (format!("key {}", i), entry(i)))
+            .collect::<HashMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&entries).unwrap();
+        let archived =
+            access::<Archived<HashMap<String, Entry>>, Failure>(&bytes)
+                .unwrap();
+
+        for i in [0, 1, 2, 5, 1234, 9999] {
+            let sub = archived.get(format!("key {}", i).as_str()).unwrap();
+            let rearchived = to_bytes::<_, 256, Failure>(sub).unwrap();
+            let expected = entry(i);
+
+            let value = access::<ArchivedEntry, Failure>(&rearchived).unwrap();
+            assert_eq!(value.id, expected.id);
+            assert_eq!(value.name, expected.name);
+            assert_eq!(value.tags, expected.tags);
+            assert_eq!(value.inner.values, expected.inner.values);
+            assert_eq!(value.inner.label, expected.inner.label);
+            assert_eq!(value.attributes.len(), expected.attributes.len());
+            for (key, value) in value.attributes.iter() {
+                assert_eq!(*value, expected.attributes[key.as_str()]);
+            }
+            assert!(core::ptr::eq(value.owner.get(), value.editor.get()));
+
+            // Hash maps with more than one entry may be laid out differently
+            // depending on the iteration order of the original map.
+            if expected.attributes.len() <= 1 {
+                assert_eq!(
+                    rearchived[..],
+                    to_bytes::<_, 256, Failure>(&expected).unwrap()[..]
+                );
+            }
+
+            let inner = to_bytes::<_, 256, Failure>(&sub.inner).unwrap();
+            assert_eq!(
+                inner[..],
+                to_bytes::<_, 256, Failure>(&expected.inner).unwrap()[..]
+            );
+            let inner = access::<ArchivedInner, Failure>(&inner).unwrap();
+            let status = to_bytes::<_, 256, Failure>(&inner.status).unwrap();
+            assert_eq!(
+                status[..],
+                to_bytes::<_, 256, Failure>(&expected.inner.status).unwrap()[..]
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn envelope_round_trip() {
+        use std::fs;
+
+        use rkyv::{
+            envelope::{
+                peek_envelope, read_enveloped, write_enveloped_with_checksum,
+                TypeHash, HEADER_SIZE,
+            },
+            util::{AlignedVec, Checksum},
+        };
+
+        #[derive(Archive, Serialize, Debug, PartialEq)]
+        #[archive(check_bytes, type_hash)]
+        struct Document {
+            title: String,
+            pages: Vec<u32>,
+        }
+
+        let value = Document {
+            title: "manual".to_string(),
+            pages: vec![1, 2, 3, 5, 8],
+        };
+
+        let path = std::env::temp_dir().join(format!(
+            "rkyv_envelope_round_trip_{}.bin",
+            std::process::id()
+        ));
+        let mut file = fs::File::create(&path).unwrap();
+        write_enveloped_with_checksum::<_, _, Failure>(
+            &value,
+            Checksum::Xxh64,
+            &mut file,
+        )
+        .unwrap();
+        drop(file);
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&fs::read(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+
+        let info = peek_envelope(&bytes).unwrap();
+        assert_eq!(info.type_hash, Document::TYPE_HASH);
+        assert_eq!(info.checksum, Checksum::Xxh64);
+        assert_eq!(info.payload_len as usize, bytes.len() - HEADER_SIZE);
+
+        let archived = read_enveloped::<Document, Failure>(&bytes).unwrap();
+        assert_eq!(archived.title, "manual");
+        assert_eq!(archived.pages, [1, 2, 3, 5, 8]);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn self_describing_root() {
+        use rkyv::{
+            trailer::{
+                access_self_describing, access_self_describing_unchecked,
+                access_self_describing_with_padding, find_trailer,
+                is_self_describing, to_bytes_with_root_pointer, MAGIC,
+                TRAILER_SIZE,
+            },
+            util::AlignedVec,
+        };
+
+        const BLOCK: usize = 4096;
+
+        #[derive(Archive, Serialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        #[archive_attr(derive(Debug))]
+        struct Document {
+            title: String,
+            pages: Vec<u32>,
+        }
+
+        let value = Document {
+            title: "a title which is stored out of line".to_string(),
+            pages: (0..100).collect(),
+        };
+        let archive =
+            to_bytes_with_root_pointer::<_, 256, Failure>(&value).unwrap();
+        assert!(is_self_describing(&archive));
+        assert!(!is_self_describing(
+            &to_bytes::<_, 256, Failure>(&value).unwrap()
+        ));
+
+        // Embed the archive after a block of other data and pad it out to the
+        // block size. The padding contains a stray copy of the magic bytes.
+        let mut file = AlignedVec::new();
+        file.extend_from_slice(&[0xab; BLOCK]);
+        file.extend_from_slice(&archive);
+        file.extend_from_slice(&MAGIC);
+        file.resize(file.len().next_multiple_of(BLOCK), 0);
+        assert_eq!(file.len(), 2 * BLOCK);
+
+        let found = find_trailer(&file, BLOCK).unwrap();
+        assert_eq!(found.archive, BLOCK..BLOCK + archive.len() - TRAILER_SIZE);
+        let archived =
+            access_self_describing::<ArchivedDocument, Failure>(&file).unwrap();
+        assert_eq!(archived.title, value.title);
+        assert_eq!(archived.pages, value.pages);
+        let archived = unsafe {
+            access_self_describing_unchecked::<ArchivedDocument, Failure>(&file)
+                .unwrap()
+        };
+        assert_eq!(archived.title, value.title);
+
+        // Padding past the window is not scanned
+        let mut padded = file.clone();
+        padded.resize(padded.len() + BLOCK, 0);
+        assert!(!is_self_describing(&padded));
+        access_self_describing::<ArchivedDocument, Failure>(&padded)
+            .expect_err("expected trailer not to be found");
+        let archived = access_self_describing_with_padding::<
+            ArchivedDocument,
+            Failure,
+        >(&padded, 2 * BLOCK)
+        .unwrap();
+        assert_eq!(archived.pages, value.pages);
+
+        // Corrupt trailers are not used
+        let mut corrupted = file.clone();
+        corrupted[BLOCK + archive.len() - TRAILER_SIZE + 8] ^= 1;
+        assert!(!is_self_describing(&corrupted));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn validation_proofs() {
+        use rkyv::{
+            util::AlignedVec,
+            validation::proof::{
+                access_with_proof, access_with_proof_strict, ProofHash,
+                ValidationProof, PROOF_SIZE,
+            },
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes, type_hash)]
+        struct Catalog {
+            name: String,
+            ids: Vec<u32>,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes, type_hash)]
+        struct Inventory {
+            label: String,
+            counts: Vec<u32>,
+        }
+
+        let value = Catalog {
+            name: "spring catalog".to_string(),
+            ids: vec![3, 1, 4, 1, 5],
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let proof =
+            ValidationProof::generate::<Catalog, Failure>(&bytes).unwrap();
+        let stored = proof.to_bytes();
+        assert_eq!(stored.len(), PROOF_SIZE);
+        let read = ValidationProof::from_bytes::<Failure>(&stored).unwrap();
+        assert_eq!(read, proof);
+        assert!(ValidationProof::from_bytes::<Failure>(&stored[1..]).is_err());
+        let mut bad_magic = stored;
+        bad_magic[0] = b'X';
+        assert!(ValidationProof::from_bytes::<Failure>(&bad_magic).is_err());
+
+        let archived = unsafe {
+            access_with_proof_strict::<Catalog, Failure>(&bytes, &read)
+        }
+        .unwrap();
+        assert_eq!(archived.name, "spring catalog");
+        assert_eq!(archived.ids, [3, 1, 4, 1, 5]);
+
+        // Changing a single byte invalidates the proof
+        let pos = bytes.windows(6).position(|w| w == b"spring").unwrap();
+        let mut modified = AlignedVec::new();
+        modified.extend_from_slice(&bytes);
+        modified[pos] = b'S';
+        assert!(proof.verify::<Catalog, Failure>(&modified).is_err());
+        assert!(unsafe {
+            access_with_proof_strict::<Catalog, Failure>(&modified, &proof)
+        }
+        .is_err());
+        // The modified archive is still valid, so it can be accessed by
+        // falling back to validation
+        let archived =
+            unsafe { access_with_proof::<Catalog, Failure>(&modified, &proof) }
+                .unwrap();
+        assert_eq!(archived.name, "Spring catalog");
+
+        // A reader with a different type hash rejects the proof even though
+        // the layouts are the same
+        assert!(unsafe {
+            access_with_proof_strict::<Inventory, Failure>(&bytes, &proof)
+        }
+        .is_err());
+        let archived =
+            unsafe { access_with_proof::<Inventory, Failure>(&bytes, &proof) }
+                .unwrap();
+        assert_eq!(archived.label, "spring catalog");
+
+        // Every enabled hash function can be used, and the strongest one is
+        // used by default
+        let hash_fns = [
+            ProofHash::Xxh64,
+            #[cfg(feature = "xxh3")]
+            ProofHash::Xxh3,
+            #[cfg(feature = "blake3")]
+            ProofHash::Blake3,
+        ];
+        assert_eq!(proof.hash_fn, hash_fns[hash_fns.len() - 1]);
+        for hash_fn in hash_fns {
+            let proof = ValidationProof::generate_with::<Catalog, Failure>(
+                &bytes, hash_fn,
+            )
+            .unwrap();
+            let read =
+                ValidationProof::from_bytes::<Failure>(&proof.to_bytes())
+                    .unwrap();
+            assert_eq!(read, proof);
+            assert!(read.verify::<Catalog, Failure>(&bytes).is_ok());
+            assert!(read.verify::<Catalog, Failure>(&modified).is_err());
+        }
+
+        // Proofs which use unknown or disabled hash functions are rejected
+        let mut unknown_hash = stored;
+        unknown_hash[5] = 0xff;
+        assert!(ValidationProof::from_bytes::<Failure>(&unknown_hash).is_err());
+        #[cfg(not(feature = "blake3"))]
+        {
+            unknown_hash[5] = 3;
+            assert!(
+                ValidationProof::from_bytes::<Failure>(&unknown_hash).is_err()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn envelope_mismatches() {
+        use rkyv::{
+            envelope::{
+                read_enveloped, write_enveloped, write_enveloped_with_checksum,
+                Endianness, EnvelopeError, TypeHash,
+            },
+            util::{AlignedVec, Checksum},
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes, type_hash)]
+        struct Point {
+            x: u32,
+            y: u32,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes, type_hash)]
+        struct Size {
+            width: u32,
+            height: u32,
+        }
+
+        assert_ne!(Point::TYPE_HASH, Size::TYPE_HASH);
+
+        let mut bytes = AlignedVec::new();
+        write_enveloped::<_, _, Failure>(&Point { x: 1, y: 2 }, &mut bytes)
+            .unwrap();
+        read_enveloped::<Point, Failure>(&bytes).unwrap();
+
+        let tampered = |offset: usize, byte: u8| {
+            let mut result = AlignedVec::new();
+            result.extend_from_slice(&bytes);
+            result[offset] = byte;
+            result
+        };
+
+        match read_enveloped::<Size, Failure>(&bytes) {
+            Err(EnvelopeError::WrongType { expected, found }) => {
+                assert_eq!(expected, Size::TYPE_HASH);
+                assert_eq!(found, Point::TYPE_HASH);
+            }
+            r => panic!("expected WrongType, got {:?}", r.map(|_| ())),
+        }
+
+        let flipped = tampered(5, bytes[5] ^ 1);
+        match read_enveloped::<Point, Failure>(&flipped) {
+            Err(EnvelopeError::EndiannessMismatch { expected, found }) => {
+                assert_eq!(expected, Endianness::NATIVE);
+                assert_ne!(found, Endianness::NATIVE);
+            }
+            r => {
+                panic!("expected EndiannessMismatch, got {:?}", r.map(|_| ()))
+            }
+        }
+
+        let width = if bytes[6] == 8 { 4 } else { 8 };
+        match read_enveloped::<Point, Failure>(&tampered(6, width)) {
+            Err(EnvelopeError::WidthMismatch { expected, found }) => {
+                assert_eq!(expected, bytes[6]);
+                assert_eq!(found, width);
+            }
+            r => panic!("expected WidthMismatch, got {:?}", r.map(|_| ())),
+        }
+
+        match read_enveloped::<Point, Failure>(&tampered(0, b'X')) {
+            Err(EnvelopeError::BadMagic { .. }) => (),
+            r => panic!("expected BadMagic, got {:?}", r.map(|_| ())),
+        }
+
+        match read_enveloped::<Point, Failure>(&bytes[..bytes.len() - 1]) {
+            Err(EnvelopeError::LengthMismatch { .. }) => (),
+            r => panic!("expected LengthMismatch, got {:?}", r.map(|_| ())),
+        }
+
+        let mut checked = AlignedVec::new();
+        write_enveloped_with_checksum::<_, _, Failure>(
+            &Point { x: 1, y: 2 },
+            Checksum::Xxh64,
+            &mut checked,
+        )
+        .unwrap();
+        let last = checked.len() - 1;
+        checked[last] ^= 0xff;
+        match read_enveloped::<Point, Failure>(&checked) {
+            Err(EnvelopeError::ChecksumMismatch { .. }) => (),
+            r => panic!("expected ChecksumMismatch, got {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archived_hash_map_set_algebra() {
+        use rkyv::{
+            access,
+            collections::swiss_table::{
+                map::DiffItem, ArchivedHashMap, ArchivedHashSet,
+                HashMapResolver,
+            },
+            rancor::{Error, Fallible},
+            ser::{Allocator, Writer},
+        };
+
+        // A hash map serialized with its entries in a given order and with a
+        // given load factor.
+        struct Table {
+            entries: Vec<(u32, u32)>,
+            load_factor: (usize, usize),
+        }
+
+        impl Archive for Table {
+            type Archived = ArchivedHashMap<Archived<u32>, Archived<u32>>;
+            type Resolver = HashMapResolver;
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedHashMap::resolve_from_len(
+                    self.entries.len(),
+                    self.load_factor,
+                    pos,
+                    resolver,
+                    out,
+                );
+            }
+        }
+
+        impl<S> Serialize<S> for Table
+        where
+            S: Fallible + Writer + Allocator + ?Sized,
+            S::Error: Error,
+        {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedHashMap::<_, _>::serialize_from_iter(
+                    self.entries.iter().map(|(k, v)| (k, v)),
+                    self.load_factor,
+                    serializer,
+                )
+            }
+        }
+
+        type Map = ArchivedHashMap<Archived<u32>, Archived<u32>>;
+
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        enum Expected {
+            OnlyLeft(u32, u32),
+            OnlyRight(u32, u32),
+            Changed(u32, u32, u32),
+        }
+
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound
+        };
+        let load_factors = [(7, 8), (1, 2), (1, 1)];
+
+        for _ in 0..200 {
+            let random_map = |next: &mut dyn FnMut(u64) -> u64| {
+                let len = next(40) as usize;
+                let mut entries = (0..len)
+                    .map(|_| (next(64) as u32, next(3) as u32))
+                    .collect::<HashMap<_, _>>()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                entries.sort_by_cached_key(|_| next(1000));
+                let load_factor = load_factors[next(3) as usize];
+                let map = entries.iter().copied().collect::<HashMap<_, _>>();
+                (
+                    map,
+                    Table {
+                        entries,
+                        load_factor,
+                    },
+                )
+            };
+            let (left, left_table) = random_map(&mut next);
+            let (right, right_table) = random_map(&mut next);
+
+            let left_bytes = to_bytes::<_, 256, Failure>(&left_table).unwrap();
+            let right_bytes =
+                to_bytes::<_, 256, Failure>(&right_table).unwrap();
+            let archived_left = access::<Map, Failure>(&left_bytes).unwrap();
+            let archived_right = access::<Map, Failure>(&right_bytes).unwrap();
+
+            let mut expected = Vec::new();
+            for (&key, &value) in left.iter() {
+                match right.get(&key) {
+                    None => expected.push(Expected::OnlyLeft(key, value)),
+                    Some(&other) if other != value => {
+                        expected.push(Expected::Changed(key, value, other))
+                    }
+                    Some(_) => (),
+                }
+            }
+            for (&key, &value) in right.iter() {
+                if !left.contains_key(&key) {
+                    expected.push(Expected::OnlyRight(key, value));
+                }
+            }
+            expected.sort();
+
+            let mut diff = archived_left.diff(archived_right);
+            let mut actual = diff
+                .by_ref()
+                .map(|item| match item {
+                    DiffItem::OnlyLeft(k, v) => {
+                        Expected::OnlyLeft(k.to_native(), v.to_native())
+                    }
+                    DiffItem::OnlyRight(k, v) => {
+                        Expected::OnlyRight(k.to_native(), v.to_native())
+                    }
+                    DiffItem::Changed(k, l, r) => Expected::Changed(
+                        k.to_native(),
+                        l.to_native(),
+                        r.to_native(),
+                    ),
+                })
+                .collect::<Vec<_>>();
+            assert!(diff.next().is_none());
+            actual.sort();
+            assert_eq!(actual, expected);
+
+            let mut expected = left
+                .keys()
+                .filter(|k| right.contains_key(k))
+                .copied()
+                .collect::<Vec<_>>();
+            expected.sort();
+            for (a, b) in [
+                (archived_left, archived_right),
+                (archived_right, archived_left),
+            ] {
+                let mut keys = a.intersection_keys(b);
+                let mut actual =
+                    keys.by_ref().map(|k| k.to_native()).collect::<Vec<_>>();
+                assert!(keys.next().is_none());
+                actual.sort();
+                assert_eq!(actual, expected);
+
+                let union = left.keys().chain(right.keys());
+                let union_len = union.collect::<HashSet<_>>().len();
+                assert_eq!(a.union_len(b), union_len);
+            }
+
+            // Sets are wrappers around maps
+            let left_set = left.keys().copied().collect::<HashSet<_>>();
+            let right_set = right.keys().copied().collect::<HashSet<_>>();
+            let left_bytes = to_bytes::<_, 256, Failure>(&left_set).unwrap();
+            let right_bytes = to_bytes::<_, 256, Failure>(&right_set).unwrap();
+            let archived_left =
+                access::<ArchivedHashSet<Archived<u32>>, Failure>(&left_bytes)
+                    .unwrap();
+            let archived_right =
+                access::<ArchivedHashSet<Archived<u32>>, Failure>(&right_bytes)
+                    .unwrap();
+
+            let mut expected =
+                left_set.difference(&right_set).copied().collect::<Vec<_>>();
+            expected.sort();
+            let mut actual = archived_left
+                .difference(archived_right)
+                .map(|k| k.to_native())
+                .collect::<Vec<_>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+
+            let mut expected = left_set
+                .intersection(&right_set)
+                .copied()
+                .collect::<Vec<_>>();
+            expected.sort();
+            let mut actual = archived_left
+                .intersection(archived_right)
+                .map(|k| k.to_native())
+                .collect::<Vec<_>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+            assert_eq!(
+                archived_left.union_len(archived_right),
+                left_set.union(&right_set).count(),
+            );
+        }
+    }
+
+    #[test]
+    fn iterate_long_chain() {
+        use rkyv::{
+            access,
+            chain::{drop_chain, ChainLink},
+            util::deserialize,
+            with::{IterateChain, Skip},
+        };
+
+        const LEN: u32 = 1_000_000;
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(check_bytes)]
+        struct Node {
+            value: u32,
+            #[with(Skip)]
+            next: Option<Box<Node>>,
+        }
+
+        impl ChainLink for Node {
+            fn link(&self) -> &Option<Box<Self>> {
+                &self.next
+            }
+
+            fn link_mut(&mut self) -> &mut Option<Box<Self>> {
+                &mut self.next
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(check_bytes)]
+        struct List {
+            #[with(IterateChain)]
+            head: Option<Box<Node>>,
+        }
+
+        impl Drop for List {
+            fn drop(&mut self) {
+                drop_chain(&mut self.head);
+            }
+        }
+
+        // Recursing once per node would overflow this stack many times over.
+        std::thread::Builder::new()
+            .stack_size(512 * 1024)
+            .spawn(|| {
+                let mut list = List { head: None };
+                for value in (0..LEN).rev() {
+                    list.head = Some(Box::new(Node {
+                        value,
+                        next: list.head.take(),
+                    }));
+                }
+
+                let bytes = to_bytes::<_, 256, Failure>(&list).unwrap();
+                let archived = access::<ArchivedList, Failure>(&bytes).unwrap();
+                assert_eq!(archived.head.len(), LEN as usize);
+
+                let mut cursor = archived.head.head();
+                let mut expected = 0;
+                while let Some(node) = cursor {
+                    assert_eq!(node.value, expected);
+                    expected += 1;
+                    cursor = node.next();
+                }
+                assert_eq!(expected, LEN);
+
+                let deserialized =
+                    deserialize::<List, _, Failure>(archived, &mut ()).unwrap();
+                let mut node = deserialized.head.as_deref();
+                let mut expected = 0;
+                while let Some(current) = node {
+                    assert_eq!(current.value, expected);
+                    expected += 1;
+                    node = current.next.as_deref();
+                }
+                assert_eq!(expected, LEN);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn external_blobs() {
+        use std::hash::{Hash, Hasher};
+
+        use rkyv::{
+            access,
+            de::BlobDeserializer,
+            external::{BlobHandle, MissingBlob},
+            rancor::BoxedError,
+            ser::{AllocSerializer, BlobSerializer, BlobSink},
+            util::{deserialize, AlignedVec},
+            with::External,
+        };
+
+        #[derive(Default)]
+        struct MemoryStore {
+            blobs: HashMap<BlobHandle, Vec<u8>>,
+            stores: usize,
+        }
+
+        impl<E> BlobSink<E> for MemoryStore {
+            fn store_blob(&mut self, bytes: &[u8]) -> Result<BlobHandle, E> {
+                let mut handle = [0; 32];
+                for (i, chunk) in handle.chunks_mut(8).enumerate() {
+                    let mut hasher = std::hash::DefaultHasher::new();
+                    (i, bytes).hash(&mut hasher);
+                    chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+                }
+                self.blobs.insert(handle, bytes.to_vec());
+                self.stores += 1;
+                Ok(handle)
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Asset {
+            name: String,
+            #[with(External)]
+            data: Vec<u8>,
+            #[with(External)]
+            thumbnail: Box<[u8]>,
+            #[with(External)]
+            description: String,
+        }
+
+        fn archive_asset(value: &Asset, store: &mut MemoryStore) -> AlignedVec {
+            serialize_into::<_, _, Failure>(
+                value,
+                BlobSerializer::new(AllocSerializer::<256>::default(), store),
+            )
+            .unwrap()
+            .into_inner()
+            .into_writer()
+        }
+
+        let value = Asset {
+            name: "texture".to_string(),
+            data: (0..10_000u32).map(|i| i as u8).collect(),
+            thumbnail: vec![1, 2, 3, 4].into_boxed_slice(),
+            description: "a large texture".to_string(),
+        };
+
+        let mut store = MemoryStore::default();
+        let bytes = archive_asset(&value, &mut store);
+        assert!(bytes.len() < 256);
+        assert_eq!(store.stores, 3);
+
+        // Archives share the blobs they have in common
+        let other = archive_asset(&value, &mut store);
+        assert_eq!(store.stores, 6);
+        assert_eq!(store.blobs.len(), 3);
+        assert_eq!(bytes.as_slice(), other.as_slice());
+
+        let archived = access::<ArchivedAsset, Failure>(&bytes).unwrap();
+        assert_eq!(archived.name, "texture");
+        assert_eq!(
+            archived.data.load::<Failure>(&store.blobs).unwrap(),
+            value.data.as_slice(),
+        );
+        assert_eq!(
+            archived.description.load::<Failure>(&store.blobs).unwrap(),
+            b"a large texture",
+        );
+
+        let deserialized = deserialize::<Asset, _, Failure>(
+            archived,
+            &mut BlobDeserializer::new((), &store.blobs),
+        )
+        .unwrap();
+        assert_eq!(deserialized, value);
+
+        let handle = *archived.thumbnail.handle();
+        store.blobs.remove(&handle);
+        let missing = MissingBlob { handle }.to_string();
+        let error = archived
+            .thumbnail
+            .load::<BoxedError>(&store.blobs)
+            .unwrap_err();
+        assert_eq!(error.to_string(), missing);
+        let error = deserialize::<Asset, _, BoxedError>(
+            archived,
+            &mut BlobDeserializer::new((), &store.blobs),
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), format!("{}trace: .thumbnail", missing));
+    }
+
+    #[test]
+    #[cfg(all(feature = "little_endian", feature = "pointer_width_32"))]
+    fn hash_table_bucket_counts() {
+        use rkyv::{access, collections::swiss_table::Density, with::Dense};
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct DenseSet {
+            #[with(Dense)]
+            set: HashSet<u32>,
+        }
+
+        // Lengths around the multiples of 7 and 15 where the bucket counts
+        // step, and around powers of two.
+        // (len, balanced buckets, balanced size, dense buckets, dense size)
+        const CASES: [(usize, usize, usize, usize, usize); 21] = [
+            (1, 2, 48, 2, 40),
+            (6, 7, 92, 7, 64),
+            (7, 8, 100, 8, 68),
+            (8, 10, 120, 9, 72),
+            (14, 16, 172, 15, 104),
+            (15, 18, 192, 16, 108),
+            (16, 19, 200, 18, 120),
+            (17, 20, 208, 19, 124),
+            (28, 32, 316, 30, 180),
+            (29, 34, 336, 31, 184),
+            (56, 64, 604, 60, 328),
+            (57, 66, 624, 61, 332),
+            (63, 72, 676, 68, 368),
+            (64, 74, 696, 69, 372),
+            (65, 75, 704, 70, 380),
+            (127, 146, 1344, 136, 708),
+            (128, 147, 1352, 137, 712),
+            (129, 148, 1360, 138, 720),
+            (1023, 1170, 10560, 1092, 5488),
+            (1024, 1171, 10568, 1093, 5492),
+            (1025, 1172, 10576, 1094, 5500),
+        ];
+
+        for (len, buckets, size, dense_buckets, dense_size) in CASES {
+            let map = (0..len as u32)
+                .map(|i| (i, i * 3))
+                .collect::<HashMap<_, _>>();
+            let bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+            let archived =
+                access::<Archived<HashMap<u32, u32>>, Failure>(&bytes).unwrap();
+            assert_eq!(archived.buckets(), buckets, "len {}", len);
+            assert_eq!(archived.capacity(), buckets, "len {}", len);
+            assert_eq!(bytes.len(), size, "len {}", len);
+            for i in 0..len as u32 {
+                assert_eq!(archived.get(&i.into()), Some(&(i * 3).into()));
+            }
+            assert!(archived.get(&(len as u32).into()).is_none());
+
+            let set = DenseSet {
+                set: (0..len as u32).collect(),
+            };
+            let bytes = to_bytes::<_, 256, Failure>(&set).unwrap();
+            let archived = access::<ArchivedDenseSet, Failure>(&bytes).unwrap();
+            assert_eq!(archived.set.buckets(), dense_buckets, "len {}", len);
+            assert_eq!(bytes.len(), dense_size, "len {}", len);
+            for i in 0..len as u32 {
+                assert!(archived.set.contains(&i.into()));
+            }
+            assert!(!archived.set.contains(&(len as u32).into()));
+
+            // Bucket counts are the smallest which satisfy the load factor
+            for (density, count) in
+                [(Density::Balanced, buckets), (Density::Max, dense_buckets)]
+            {
+                let (n, d) = density.load_factor();
+                assert!(len * d <= count * n);
+                assert!(count == len + 1 || len * d > (count - 1) * n);
+            }
+        }
+    }
+
+    #[test]
+    fn deserialize_error_paths() {
+        use std::collections::BTreeMap;
+
+        use rkyv::{
+            access,
+            de::{LimitExceeded, Limited, Limits, PathError, PathSegment},
+            util::deserialize,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        enum Payload {
+            Empty,
+            Data(Vec<u8>),
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Item {
+            r#type: Vec<u32>,
+            payload: Payload,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Root {
+            items: Vec<Item>,
+            index: BTreeMap<u32, Vec<u32>>,
+            keys: HashMap<Vec<u8>, u32>,
+        }
+
+        fn deserialize_limited(
+            root: &Root,
+            max_len: usize,
+        ) -> Result<Root, PathError> {
+            let bytes = to_bytes::<_, 256, Failure>(root).unwrap();
+            let archived = access::<ArchivedRoot, Failure>(&bytes).unwrap();
+            let limits = Limits {
+                max_len,
+                ..Limits::default()
+            };
+            deserialize(archived, &mut Limited::new((), limits))
+        }
+
+        fn item(len: usize, payload: Payload) -> Item {
+            Item {
+                r#type: (0..len as u32).collect(),
+                payload,
+            }
+        }
+
+        let exceeded = |len| {
+            format!(
+                "deserialization collection length limit exceeded: requested \
+                 {} but the limit is 4",
+                len,
+            )
+        };
+
+        // Struct fields and vec indices
+        let mut root = Root {
+            items: vec![
+                item(1, Payload::Empty),
+                item(2, Payload::Data(vec![1, 2])),
+                item(3, Payload::Empty),
+                item(5, Payload::Empty),
+            ],
+            index: BTreeMap::new(),
+            keys: HashMap::new(),
+        };
+        let error = deserialize_limited(&root, 4).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "failed to deserialize root.items[3].type: {}",
+                exceeded(5)
+            ),
+        );
+        assert_eq!(
+            error.path().copied().collect::<Vec<_>>(),
+            [
+                PathSegment::Field("items"),
+                PathSegment::Index(3),
+                PathSegment::Field("type"),
+            ],
+        );
+        assert!(error.cause().downcast_ref::<LimitExceeded>().is_some());
+
+        // Enum variant fields
+        root.items[3] = item(4, Payload::Empty);
+        root.items[1].payload = Payload::Data(vec![0; 6]);
+        assert_eq!(
+            deserialize_limited(&root, 4).unwrap_err().to_string(),
+            format!(
+                "failed to deserialize root.items[1].payload.0: {}",
+                exceeded(6),
+            ),
+        );
+
+        // Map values and keys
+        root.items[1].payload = Payload::Empty;
+        root.index.insert(1, vec![1]);
+        root.index.insert(2, vec![0; 7]);
+        assert_eq!(
+            deserialize_limited(&root, 4).unwrap_err().to_string(),
+            format!("failed to deserialize root.index[#1]: {}", exceeded(7)),
+        );
+        root.index.remove(&2);
+        root.keys.insert(vec![0; 5], 1);
+        assert_eq!(
+            deserialize_limited(&root, 4).unwrap_err().to_string(),
+            format!("failed to deserialize root.keys[key #0]: {}", exceeded(5)),
+        );
+
+        // Nothing is traced when deserializing succeeds
+        root.keys.clear();
+        assert_eq!(deserialize_limited(&root, 4).unwrap(), root);
+    }
+
+    #[test]
+    #[cfg(feature = "runtime-simd")]
+    fn runtime_scanners_agree() {
+        use rkyv::{
+            access, collections::swiss_table::Scanner, with::Dense, Archived,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Tables {
+            sparse: HashMap<u32, u32>,
+            #[with(Dense)]
+            dense: HashMap<u32, u32>,
+        }
+
+        let supported = Scanner::ALL
+            .iter()
+            .copied()
+            .filter(|scanner| scanner.is_supported())
+            .collect::<Vec<_>>();
+        assert!(supported.contains(&Scanner::Generic));
+
+        for len in (0..64).chain([100, 255, 256, 1000, 4097]) {
+            let map = (0..len).map(|i| (i * 7, i)).collect::<HashMap<_, _>>();
+            let tables = Tables {
+                sparse: map.clone(),
+                dense: map,
+            };
+            let bytes = to_bytes::<_, 256, Failure>(&tables).unwrap();
+            let archived = access::<ArchivedTables, Failure>(&bytes).unwrap();
+
+            // Look up every key as well as keys that are missing
+            let lookup = |map: &Archived<HashMap<u32, u32>>| {
+                (0..len * 7 + 7)
+                    .map(|key| map.get(&key.into()).map(|v| v.to_native()))
+                    .collect::<Vec<_>>()
+            };
+            let expected = (0..len * 7 + 7)
+                .map(|key| (key % 7 == 0 && key < len * 7).then_some(key / 7))
+                .collect::<Vec<_>>();
+            for &scanner in supported.iter() {
+                scanner.select();
+                assert_eq!(lookup(&archived.sparse), expected, "{:?}", scanner);
+                assert_eq!(lookup(&archived.dense), expected, "{:?}", scanner);
+            }
+        }
+
+        Scanner::detect().select();
+    }
+
+    #[test]
+    fn prehashed_hash_map() {
+        use rkyv::{access, deserialize, hash::Hashed, with::Prehashed};
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(check_bytes)]
+        struct Index {
+            #[with(Prehashed)]
+            map: HashMap<Hashed<String>, u32>,
+        }
+
+        for len in [0, 1, 15, 16, 1000, 100_000] {
+            let map = (0..len)
+                .map(|i| (format!("key {}", i), i))
+                .collect::<HashMap<_, _>>();
+            let value = Index {
+                map: map
+                    .iter()
+                    .map(|(key, value)| (Hashed::new(key.clone()), *value))
+                    .collect(),
+            };
+
+            // The cached hashes place every entry in the same bucket as
+            // rehashing the keys does. Larger maps have keys with the same
+            // hash, which may be placed in either order.
+            let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+            if len <= 1000 {
+                let expected = to_bytes::<_, 256, Failure>(&map).unwrap();
+                assert_eq!(
+                    bytes.as_slice(),
+                    expected.as_slice(),
+                    "len {}",
+                    len
+                );
+            }
+
+            let archived = access::<ArchivedIndex, Failure>(&bytes).unwrap();
+            assert_eq!(archived.map.len(), len as usize);
+            for (key, value) in map.iter() {
+                assert_eq!(
+                    archived.map.get(key.as_str()).map(|v| v.to_native()),
+                    Some(*value),
+                );
+            }
+            assert!(archived.map.get("missing").is_none());
+
+            let deserialized =
+                deserialize::<Index, _, Failure>(archived, &mut ()).unwrap();
+            assert_eq!(deserialized.map, value.map);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "little_endian")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn access_as_compatible_types() {
+        use rkyv::{
+            compat::{
+                access_as_compatible, access_as_compatible_unchecked,
+                CompatError, TransmuteCompatible,
+            },
+            layout::StructuralHash,
+        };
+
+        mod a {
+            use std::collections::HashMap;
+
+            use rkyv::{Archive, Serialize};
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub struct Item {
+                pub name: String,
+                pub count: u32,
+            }
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub enum Status {
+                Open,
+                Closed { reason: String },
+            }
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub struct Inventory {
+                pub items: Vec<Item>,
+                pub status: Status,
+                pub tags: HashMap<String, [u8; 2]>,
+            }
+        }
+
+        mod b {
+            use std::collections::HashMap;
+
+            use rkyv::{Archive, Serialize};
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub struct Item {
+                pub name: String,
+                pub count: u32,
+            }
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub enum Status {
+                Open,
+                Closed { reason: String },
+            }
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub struct Inventory {
+                pub items: Vec<Item>,
+                pub status: Status,
+                pub tags: HashMap<String, [u8; 2]>,
+            }
+        }
+
+        mod wide {
+            use std::collections::HashMap;
+
+            use rkyv::{Archive, Serialize};
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub struct Item {
+                pub name: String,
+                pub count: u64,
+            }
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub enum Status {
+                Open,
+                Closed { reason: String },
+            }
+
+            #[derive(Archive, Serialize)]
+            #[archive(check_bytes, structural_hash)]
+            pub struct Inventory {
+                pub items: Vec<Item>,
+                pub status: Status,
+                pub tags: HashMap<String, [u8; 2]>,
+            }
+        }
+
+        let value = a::Inventory {
+            items: vec![
+                a::Item {
+                    name: "bolt".to_string(),
+                    count: 12,
+                },
+                a::Item {
+                    name: "nut".to_string(),
+                    count: 30,
+                },
+            ],
+            status: a::Status::Closed {
+                reason: "sold out".to_string(),
+            },
+            tags: [("size".to_string(), [4, 6])].into_iter().collect(),
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        assert_eq!(
+            a::ArchivedInventory::STRUCTURAL_HASH,
+            b::ArchivedInventory::STRUCTURAL_HASH,
+        );
+        let archived =
+            access_as_compatible::<a::Inventory, b::Inventory, Failure>(&bytes)
+                .unwrap();
+        assert_eq!(archived.items.len(), 2);
+        assert_eq!(archived.items[1].name, "nut");
+        assert_eq!(archived.items[1].count, 30);
+        match archived.status {
+            b::ArchivedStatus::Closed { ref reason } => {
+                assert_eq!(reason, "sold out")
+            }
+            b::ArchivedStatus::Open => panic!("expected a closed status"),
+        }
+        assert_eq!(archived.tags.get("size"), Some(&[4, 6]));
+
+        // Compatible types can be accessed as each other with every variant
+        let statuses = [
+            (a::Status::Open, b::Status::Open),
+            (
+                a::Status::Closed {
+                    reason: "restocking".to_string(),
+                },
+                b::Status::Closed {
+                    reason: "restocking".to_string(),
+                },
+            ),
+        ];
+        for (a_status, b_status) in statuses {
+            let a_bytes = to_bytes::<_, 256, Failure>(&a::Inventory {
+                items: Vec::new(),
+                status: a_status,
+                tags: Default::default(),
+            })
+            .unwrap();
+            let b_bytes = to_bytes::<_, 256, Failure>(&b::Inventory {
+                items: Vec::new(),
+                status: b_status,
+                tags: Default::default(),
+            })
+            .unwrap();
+            let from_a =
+                access_as_compatible::<a::Inventory, b::Inventory, Failure>(
+                    &a_bytes,
+                )
+                .unwrap();
+            let from_b =
+                access_as_compatible::<b::Inventory, a::Inventory, Failure>(
+                    &b_bytes,
+                )
+                .unwrap();
+            match (&from_a.status, &from_b.status) {
+                (b::ArchivedStatus::Open, a::ArchivedStatus::Open) => (),
+                (
+                    b::ArchivedStatus::Closed { reason: x },
+                    a::ArchivedStatus::Closed { reason: y },
+                ) => assert_eq!(x, y),
+                _ => panic!("expected matching statuses"),
+            }
+        }
+
+        assert_ne!(
+            a::ArchivedInventory::STRUCTURAL_HASH,
+            wide::ArchivedInventory::STRUCTURAL_HASH,
+        );
+        let error = match access_as_compatible::<
+            a::Inventory,
+            wide::Inventory,
+            Failure,
+        >(&bytes)
+        {
+            Err(CompatError::Mismatch(error)) => error,
+            r => panic!("expected a mismatch, got {:?}", r.map(|_| ())),
+        };
+        assert_eq!(
+            error.path.as_deref(),
+            Some("(*ArchivedInventory.items.ptr).count"),
+        );
+        assert_eq!(
+            error.to_string(),
+            "`ArchivedInventory` is not structurally compatible with \
+             `ArchivedInventory`; first mismatch at \
+             `(*ArchivedInventory.items.ptr).count`:\n\
+             - primitive u32_le (size 4, align 4)\n\
+             + primitive u64_le (size 8, align 8)",
+        );
+        for status in [
+            wide::Status::Open,
+            wide::Status::Closed {
+                reason: "discontinued".to_string(),
+            },
+        ] {
+            let bytes = to_bytes::<_, 256, Failure>(&wide::Inventory {
+                items: Vec::new(),
+                status,
+                tags: Default::default(),
+            })
+            .unwrap();
+            let result =
+                access_as_compatible::<wide::Inventory, b::Inventory, Failure>(
+                    &bytes,
+                );
+            assert!(matches!(result, Err(CompatError::Mismatch(_))));
+        }
+
+        // Types which can't be hashed can be asserted to be compatible
+        #[derive(Archive, Serialize)]
+        struct Meters(f32);
+
+        #[derive(Archive)]
+        struct Length(f32);
+
+        unsafe impl TransmuteCompatible<ArchivedLength> for ArchivedMeters {}
+
+        let bytes = to_bytes::<_, 256, Failure>(&Meters(2.5)).unwrap();
+        let length =
+            unsafe { access_as_compatible_unchecked::<Meters, Length>(&bytes) }
+                .unwrap();
+        assert_eq!(length.0, 2.5);
+    }
+
+    #[test]
+    #[cfg(feature = "instrument")]
+    fn profiling_attributes_bytes() {
+        use std::any::type_name;
+
+        use rkyv::ser::{instrument::Profiling, AllocSerializer};
+
+        #[derive(Archive, Serialize)]
+        struct Item {
+            name: String,
+            count: u32,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Inventory {
+            title: String,
+            items: Vec<Item>,
+        }
+
+        let value = Inventory {
+            title: "hardware store".to_string(),
+            items: (0..10)
+                .map(|i| Item {
+                    name: format!("item no. {}", i),
+                    count: i,
+                })
+                .collect(),
+        };
+
+        let serializer = serialize_into::<_, _, Failure>(
+            &value,
+            Profiling::new(AllocSerializer::<256>::default()),
+        )
+        .unwrap();
+        let report = serializer.report();
+        let total = serializer.into_inner().into_writer().len() as u64;
+
+        // Every byte is either attributed to a type or written outside of one
+        assert_eq!(report.total_bytes, total);
+        assert_eq!(
+            report.attributed_bytes() + report.unattributed_bytes,
+            total,
+        );
+        // Only the root object and the padding before it are unattributed
+        let root_size = core::mem::size_of::<ArchivedInventory>() as u64;
+        let root_align = core::mem::align_of::<ArchivedInventory>() as u64;
+        assert!(report.unattributed_bytes >= root_size);
+        assert!(report.unattributed_bytes - root_size < root_align);
+
+        // Each item writes its name out-of-line
+        let item = report.get(type_name::<Item>()).unwrap();
+        assert_eq!(item.count, 10);
+        let names: usize = value.items.iter().map(|i| i.name.len()).sum();
+        assert_eq!(item.bytes, names as u64);
+
+        // The vec writes its archived items, plus padding to align them
+        let items = report.get(type_name::<Vec<Item>>()).unwrap();
+        assert_eq!(items.count, 1);
+        let item_size = core::mem::size_of::<ArchivedItem>() as u64;
+        let item_align = core::mem::align_of::<ArchivedItem>() as u64;
+        assert!(items.bytes >= 10 * item_size);
+        assert!(items.bytes - 10 * item_size < item_align);
+
+        let inventory = report.get(type_name::<Inventory>()).unwrap();
+        assert_eq!(inventory.count, 1);
+        assert_eq!(inventory.bytes, value.title.len() as u64);
+
+        // Types are sorted by the number of bytes attributed to them
+        assert_eq!(report.types[0].type_name, type_name::<Vec<Item>>());
+        let rendered = report.to_string();
+        let line = |name: &str| rendered.find(&format!("  {}\n", name));
+        let vec_line = line(type_name::<Vec<Item>>()).unwrap();
+        let item_line = line(type_name::<Item>()).unwrap();
+        assert!(vec_line < item_line);
+        assert!(rendered.ends_with("total"));
+
+        let serializer = serialize_into::<_, _, Failure>(
+            &value,
+            Profiling::without_timing(AllocSerializer::<256>::default()),
+        )
+        .unwrap();
+        let untimed = serializer.report();
+        assert_eq!(untimed.types.len(), report.types.len());
+        for (a, b) in untimed.types.iter().zip(report.types.iter()) {
+            assert_eq!((a.type_name, a.bytes), (b.type_name, b.bytes));
+            assert_eq!(a.nanos, 0);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn deserialize_maps_into_other_collections() {
+        use std::collections::BTreeMap;
+
+        use rkyv::{
+            access_unchecked, deserialize, rancor::Strategy, to_bytes,
+            Archived, Deserialize,
+        };
+
+        let hash_map = (0..100u32)
+            .map(|i| (format!("key {}", i), vec![i; i as usize % 5]))
+            .collect::<HashMap<_, _>>();
+        let btree_map =
+            hash_map.clone().into_iter().collect::<BTreeMap<_, _>>();
+        let sorted = btree_map.clone().into_iter().collect::<Vec<_>>();
+
+        let bytes = to_bytes::<_, 256, Failure>(&hash_map).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<HashMap<String, Vec<u32>>>>(&bytes)
+        };
+
+        // Entries are deserialized in the order the hash map iterates them
+        let entries = Deserialize::<Vec<(String, Vec<u32>)>, _>::deserialize(
+            archived,
+            Strategy::<_, Failure>::wrap(&mut ()),
+        )
+        .unwrap();
+        assert_eq!(entries.capacity(), archived.len());
+        assert_eq!(entries.len(), archived.len());
+        for ((key, value), (archived_key, archived_value)) in
+            entries.iter().zip(archived.iter())
+        {
+            assert_eq!(archived_key, key);
+            assert_eq!(archived_value, value);
+        }
+        let mut entries = entries;
+        entries.sort();
+        assert_eq!(entries, sorted);
+
+        let deserialized =
+            Deserialize::<BTreeMap<String, Vec<u32>>, _>::deserialize(
+                archived,
+                Strategy::<_, Failure>::wrap(&mut ()),
+            )
+            .unwrap();
+        assert_eq!(deserialized, btree_map);
+
+        let deserialized: HashMap<String, Vec<u32>> =
+            deserialize::<HashMap<_, _>, _, Failure>(archived, &mut ())
+                .unwrap();
+        assert_eq!(deserialized, hash_map);
+
+        let bytes = to_bytes::<_, 256, Failure>(&btree_map).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<String, Vec<u32>>>>(&bytes)
+        };
+
+        // Entries are deserialized in ascending order of their keys
+        let entries = Deserialize::<Vec<(String, Vec<u32>)>, _>::deserialize(
+            archived,
+            Strategy::<_, Failure>::wrap(&mut ()),
+        )
+        .unwrap();
+        assert_eq!(entries.capacity(), archived.len());
+        assert_eq!(entries, sorted);
+
+        let deserialized =
+            Deserialize::<HashMap<String, Vec<u32>>, _>::deserialize(
+                archived,
+                Strategy::<_, Failure>::wrap(&mut ()),
+            )
+            .unwrap();
+        assert_eq!(deserialized, hash_map);
+
+        let deserialized: BTreeMap<String, Vec<u32>> =
+            deserialize::<BTreeMap<_, _>, _, Failure>(archived, &mut ())
+                .unwrap();
+        assert_eq!(deserialized, btree_map);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn lazily_deserialize_entries() {
+        use std::{cell::Cell, collections::BTreeMap, fmt, rc::Rc};
+
+        use rkyv::{
+            access_unchecked,
+            de::Unify,
+            rancor::{Fallible, Strategy},
+            to_bytes, Archived,
+        };
+
+        thread_local! {
+            static DESERIALIZED: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(Debug)]
+        struct Poisoned;
+
+        impl fmt::Display for Poisoned {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "deserialized a poisoned sentinel")
+            }
+        }
+
+        impl std::error::Error for Poisoned {}
+
+        #[derive(Archive, Serialize, Debug, PartialEq)]
+        struct Sentinel(u32);
+
+        impl<D> Deserialize<Sentinel, D> for ArchivedSentinel
+        where
+            D: Fallible + ?Sized,
+            D::Error: rkyv::rancor::Error,
+        {
+            fn deserialize(&self, _: &mut D) -> Result<Sentinel, D::Error> {
+                DESERIALIZED.with(|count| count.set(count.get() + 1));
+                if self.0 == u32::MAX {
+                    rkyv::rancor::fail!(Poisoned);
+                }
+                Ok(Sentinel(self.0.to_native()))
+            }
+        }
+
+        fn deserialized() -> usize {
+            DESERIALIZED.with(|count| count.replace(0))
+        }
+
+        const LEN: u32 = 1000;
+
+        // Only the entries which are reached get deserialized
+        let hash_map = (0..LEN)
+            .map(|i| (i, Sentinel(i)))
+            .collect::<HashMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&hash_map).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<HashMap<u32, Sentinel>>>(&bytes)
+        };
+        deserialized();
+        let mut deserializer = Unify::default();
+        {
+            let strategy = Strategy::<_, Failure>::wrap(&mut deserializer);
+            let mut entries =
+                archived.entries_deserialized::<u32, Sentinel, _>(strategy);
+            assert_eq!(entries.len(), LEN as usize);
+            for _ in 0..LEN / 2 {
+                let (key, value) = entries.next().unwrap().unwrap();
+                assert_eq!(value, Sentinel(key));
+            }
+        }
+        assert_eq!(deserialized(), LEN as usize / 2);
+
+        let collected = archived
+            .entries_deserialized(Strategy::<_, Failure>::wrap(&mut ()))
+            .collect_deserialized::<HashMap<u32, Sentinel>>()
+            .unwrap();
+        assert_eq!(collected, hash_map);
+        assert_eq!(deserialized(), LEN as usize);
+
+        // Collecting stops at the first entry which fails to deserialize
+        let btree_map = (0..LEN)
+            .map(|i| (i, Sentinel(if i == LEN / 4 { u32::MAX } else { i })))
+            .collect::<BTreeMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&btree_map).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<u32, Sentinel>>>(&bytes)
+        };
+        deserialized();
+        let result = archived
+            .entries_deserialized(Strategy::<_, Failure>::wrap(&mut ()))
+            .collect_deserialized::<BTreeMap<u32, Sentinel>>();
+        assert!(result.is_err());
+        assert_eq!(deserialized(), LEN as usize / 4 + 1);
+
+        // Errors are reported per entry, and iteration can continue past them
+        let results = archived
+            .entries_deserialized::<u32, Sentinel, _>(
+                Strategy::<_, Failure>::wrap(&mut ()),
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), LEN as usize);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.is_err(), i == LEN as usize / 4);
+        }
+
+        let vec = (0..LEN).map(Sentinel).collect::<Vec<_>>();
+        let bytes = to_bytes::<_, 256, Failure>(&vec).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<Sentinel>>>(&bytes) };
+        deserialized();
+        let first_half = archived
+            .iter_deserialized(Strategy::<_, Failure>::wrap(&mut ()))
+            .take(LEN as usize / 2)
+            .collect::<Result<Vec<Sentinel>, _>>()
+            .unwrap();
+        assert_eq!(first_half, vec[..LEN as usize / 2]);
+        assert_eq!(deserialized(), LEN as usize / 2);
+
+        // Shared pointers are deduplicated across entries by the deserializer
+        let shared = Rc::new("shared".to_string());
+        let rc_map = (0..10u32)
+            .map(|i| (i, shared.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&rc_map).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<u32, Rc<String>>>>(&bytes)
+        };
+        let mut deserializer = Unify::default();
+        let strategy = Strategy::<_, Failure>::wrap(&mut deserializer);
+        let mut entries =
+            archived.entries_deserialized::<u32, Rc<String>, _>(strategy);
+        let (_, first) = entries.next().unwrap().unwrap();
+        for entry in entries {
+            let (_, value) = entry.unwrap();
+            assert!(Rc::ptr_eq(&first, &value));
+        }
+        assert_eq!(*first, "shared");
+
+        let rc_vec = vec![shared.clone(); 10];
+        let bytes = to_bytes::<_, 256, Failure>(&rc_vec).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<Rc<String>>>>(&bytes) };
+        let values = archived
+            .iter_deserialized(Strategy::<_, Failure>::wrap(&mut deserializer))
+            .collect_deserialized::<Vec<Rc<String>>>()
+            .unwrap();
+        assert!(values.iter().all(|value| Rc::ptr_eq(value, &values[0])));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn cyclic_shared_pointers() {
+        use std::{
+            any::Any,
+            cell::RefCell,
+            error::Error as StdError,
+            fmt,
+            rc::Rc,
+            sync::{Arc, Mutex},
+        };
+
+        use rkyv::{
+            rancor::{Error, Trace},
+            ser::{sharing::CyclicSharedPointer, Sharing, Writer},
+            with::Lock,
+        };
+
+        // Records the shared pointer which formed a cycle, if any.
+        #[derive(Debug)]
+        struct Caught {
+            cycle: Option<(&'static str, usize)>,
+            message: String,
+        }
+
+        impl fmt::Display for Caught {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.message)
+            }
+        }
+
+        impl StdError for Caught {}
+
+        impl Trace for Caught {
+            fn trace<R>(self, _: R) -> Self {
+                self
+            }
+        }
+
+        impl Error for Caught {
+            fn new<T: StdError + Send + Sync + 'static>(source: T) -> Self {
+                let cycle = (&source as &dyn Any)
+                    .downcast_ref::<CyclicSharedPointer>()
+                    .map(|e| (e.type_name(), e.address()));
+                Self {
+                    cycle,
+                    message: source.to_string(),
+                }
+            }
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(serialize_bounds(__S: Writer + Sharing, __S::Error: Error))]
+        struct ArcNode {
+            id: u32,
+            #[with(Lock)]
+            #[omit_bounds]
+            next: Mutex<Option<Arc<ArcNode>>>,
+        }
+
+        // Two nodes which point to each other
+        let b = Arc::new(ArcNode {
+            id: 1,
+            next: Mutex::new(None),
+        });
+        let a = Arc::new_cyclic(|_| ArcNode {
+            id: 0,
+            next: Mutex::new(Some(b.clone())),
+        });
+        *b.next.lock().unwrap() = Some(a.clone());
+
+        let error = to_bytes::<_, 256, Caught>(&a).unwrap_err();
+        let (type_name, address) = error.cycle.unwrap();
+        assert!(type_name.ends_with("ArcNode"));
+        assert_eq!(address, Arc::as_ptr(&a) as usize);
+        assert!(error.message.contains("cyclic shared pointer"));
+        b.next.lock().unwrap().take();
+
+        #[derive(Archive, Serialize)]
+        #[archive(serialize_bounds(__S: Writer + Sharing, __S::Error: Error))]
+        struct RcNode {
+            #[with(Lock)]
+            #[omit_bounds]
+            next: RefCell<Option<Rc<RcNode>>>,
+        }
+
+        // A node which points to itself
+        let node = Rc::new(RcNode {
+            next: RefCell::new(None),
+        });
+        *node.next.borrow_mut() = Some(node.clone());
+
+        let error = to_bytes::<_, 256, Caught>(&node).unwrap_err();
+        let (type_name, address) = error.cycle.unwrap();
+        assert!(type_name.ends_with("RcNode"));
+        assert_eq!(address, Rc::as_ptr(&node) as usize);
+        node.next.borrow_mut().take();
+
+        #[derive(Archive, Serialize)]
+        #[archive(serialize_bounds(__S: Writer + Sharing))]
+        struct Diamond {
+            id: u32,
+            #[omit_bounds]
+            left: Option<Rc<Diamond>>,
+            #[omit_bounds]
+            right: Option<Rc<Diamond>>,
+        }
+
+        // Two paths from the top to the bottom, which isn't a cycle
+        let bottom = Rc::new(Diamond {
+            id: 3,
+            left: None,
+            right: None,
+        });
+        let left = Rc::new(Diamond {
+            id: 1,
+            left: Some(bottom.clone()),
+            right: None,
+        });
+        let right = Rc::new(Diamond {
+            id: 2,
+            left: None,
+            right: Some(bottom),
+        });
+        let top = Rc::new(Diamond {
+            id: 0,
+            left: Some(left),
+            right: Some(right),
+        });
+
+        let bytes = to_bytes::<_, 256, Failure>(&top).unwrap();
+        let archived =
+            unsafe { access_unchecked::<Archived<Rc<Diamond>>>(&bytes) };
+        assert_eq!(archived.id, 0);
+        let left = archived.left.as_ref().unwrap();
+        let right = archived.right.as_ref().unwrap();
+        assert_eq!(left.id, 1);
+        assert_eq!(right.id, 2);
+        let from_left = left.left.as_ref().unwrap();
+        let from_right = right.right.as_ref().unwrap();
+        assert_eq!(from_left.id, 3);
+        assert!(core::ptr::eq(from_left.get(), from_right.get()));
+    }
+
+    #[test]
+    #[cfg(feature = "limits")]
+    fn limited_stops_deep_and_wide_values() {
+        use std::{any::Any, cell::Cell, error::Error as StdError, fmt};
+
+        use rkyv::{
+            rancor::{Error, Fallible, Trace},
+            ser::{
+                limits::{Limit, Limited, Limits, SerializationLimitExceeded},
+                AllocSerializer, Writer,
+            },
+            string::{ArchivedString, StringResolver},
+            util::{serialize, to_bytes_limited},
+            SerializeUnsized,
+        };
+
+        // Records which limit was exceeded, if any.
+        #[derive(Debug)]
+        struct Caught {
+            exceeded: Option<(Limit, usize, usize)>,
+            message: String,
+        }
+
+        impl fmt::Display for Caught {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.message)
+            }
+        }
+
+        impl StdError for Caught {}
+
+        impl Trace for Caught {
+            fn trace<R>(self, _: R) -> Self {
+                self
+            }
+        }
+
+        impl Error for Caught {
+            fn new<T: StdError + Send + Sync + 'static>(source: T) -> Self {
+                let exceeded = (&source as &dyn Any)
+                    .downcast_ref::<SerializationLimitExceeded>()
+                    .map(|e| (e.limit(), e.max(), e.requested()));
+                Self {
+                    exceeded,
+                    message: source.to_string(),
+                }
+            }
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(serialize_bounds(__S: Writer))]
+        enum Nested {
+            Leaf,
+            Node(#[omit_bounds] Box<Nested>),
+        }
+
+        // Deeper than the stack could serialize without the limit
+        let mut deep = Nested::Leaf;
+        for _ in 0..20_000 {
+            deep = Nested::Node(Box::new(deep));
+        }
+        let limits = Limits {
+            max_depth: 32,
+            ..Limits::default()
+        };
+        let mut serializer =
+            Limited::new(AllocSerializer::<256>::default(), limits);
+        let error =
+            serialize::<_, _, Caught>(&deep, &mut serializer).unwrap_err();
+        assert_eq!(error.exceeded, Some((Limit::Depth, 32, 33)));
+        assert_eq!(serializer.depth(), 32);
+        assert_eq!(serializer.written_bytes(), 0);
+
+        // Dropping the value recursively could also overflow the stack
+        while let Nested::Node(next) = deep {
+            deep = *next;
+        }
+
+        thread_local! {
+            static SERIALIZED: Cell<usize> = Cell::new(0);
+        }
+
+        // Counts the number of strings which were serialized
+        struct Counted(String);
+
+        impl Archive for Counted {
+            type Archived = ArchivedString;
+            type Resolver = StringResolver;
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                self.0.resolve(pos, resolver, out);
+            }
+        }
+
+        impl<S: Fallible + ?Sized> Serialize<S> for Counted
+        where
+            str: SerializeUnsized<S>,
+        {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                SERIALIZED.with(|count| count.set(count.get() + 1));
+                self.0.serialize(serializer)
+            }
+        }
+
+        let wide = (0..100_000)
+            .map(|i| Counted(format!("element number {}", i)))
+            .collect::<Vec<_>>();
+        let limits = Limits {
+            max_bytes: 4096,
+            ..Limits::default()
+        };
+        let error =
+            to_bytes_limited::<_, 256, Caught>(&wide, limits).unwrap_err();
+        let (limit, max, requested) = error.exceeded.unwrap();
+        assert_eq!((limit, max), (Limit::Bytes, 4096));
+        assert!(requested > 4096);
+        let serialized = SERIALIZED.with(|count| count.get());
+        assert!(serialized < 4096 / "element number".len());
+
+        let wide = (0..10_000)
+            .map(|i| vec![format!("element number {}", i)])
+            .collect::<Vec<_>>();
+        let limits = Limits {
+            max_allocations: 16,
+            ..Limits::default()
+        };
+        let mut serializer =
+            Limited::new(AllocSerializer::<256>::default(), limits);
+        let error =
+            serialize::<_, _, Caught>(&wide, &mut serializer).unwrap_err();
+        assert_eq!(error.exceeded, Some((Limit::Allocations, 16, 17)));
+        assert_eq!(serializer.allocations(), 16);
+
+        let limits = Limits {
+            max_bytes: 1 << 20,
+            max_allocations: 20_000,
+            max_depth: 2,
+        };
+        let bytes = to_bytes_limited::<_, 256, Caught>(&wide, limits).unwrap();
+        let archived = unsafe {
+            rkyv::access_unchecked::<Archived<Vec<Vec<String>>>>(&bytes)
+        };
+        assert_eq!(archived[9_999][0], "element number 9999");
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn stable_hash_properties() {
+        use std::{collections::BTreeMap, rc::Rc};
+
+        use rkyv::{
+            ser::AllocSerializer,
+            stable_hash::{stable_hash, StableHasher},
+            with::Dense,
+        };
+
+        #[derive(Archive, Serialize, Clone, Copy, PartialEq)]
+        #[archive(stable_hash)]
+        enum Kind {
+            Empty,
+            Small(u8),
+            Large(u8),
+        }
+
+        #[derive(Archive, Serialize, Clone)]
+        #[archive(stable_hash)]
+        struct Record {
+            id: u32,
+            name: String,
+            tags: Vec<String>,
+            score: Option<f64>,
+            counts: HashMap<String, u32>,
+            ordered: BTreeMap<u8, String>,
+            pair: (i16, char),
+            kind: Kind,
+            first: Rc<String>,
+            second: Rc<String>,
+        }
+
+        // The same fields as `Record`, but `counts` is archived with a
+        // different load factor
+        #[derive(Archive, Serialize)]
+        #[archive(stable_hash)]
+        struct DenseRecord {
+            id: u32,
+            name: String,
+            tags: Vec<String>,
+            score: Option<f64>,
+            #[with(Dense)]
+            counts: HashMap<String, u32>,
+            ordered: BTreeMap<u8, String>,
+            pair: (i16, char),
+            kind: Kind,
+            first: Rc<String>,
+            second: Rc<String>,
+        }
+
+        struct Rng(u64);
+
+        impl Rng {
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+
+            fn below(&mut self, n: u64) -> u64 {
+                self.next() % n
+            }
+
+            fn string(&mut self) -> String {
+                let len = self.below(40);
+                (0..len)
+                    .map(|_| (b'a' + self.below(26) as u8) as char)
+                    .collect()
+            }
+        }
+
+        fn random_record(rng: &mut Rng) -> Record {
+            let shared = Rc::new(rng.string());
+            Record {
+                id: rng.next() as u32,
+                name: rng.string(),
+                tags: (0..rng.below(5)).map(|_| rng.string()).collect(),
+                score: match rng.below(4) {
+                    0 => None,
+                    1 => Some(-0.0),
+                    _ => Some(rng.next() as f64 / 3.0),
+                },
+                counts: (0..rng.below(50))
+                    .map(|i| (format!("key {}", i), rng.next() as u32))
+                    .collect(),
+                ordered: (0..rng.below(20))
+                    .map(|_| (rng.next() as u8, rng.string()))
+                    .collect(),
+                pair: (
+                    rng.next() as i16,
+                    ['a', 'é', '🦀'][rng.below(3) as usize],
+                ),
+                kind: match rng.below(3) {
+                    0 => Kind::Empty,
+                    1 => Kind::Small(rng.next() as u8),
+                    _ => Kind::Large(rng.next() as u8),
+                },
+                first: shared.clone(),
+                second: shared,
+            }
+        }
+
+        fn hash_record(bytes: &[u8]) -> u128 {
+            stable_hash(unsafe { access_unchecked::<ArchivedRecord>(bytes) })
+        }
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100 {
+            let value = random_record(&mut rng);
+            let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+            let hash = hash_record(&bytes);
+
+            // Rebuild the hash map with a different capacity and insertion
+            // order, unshare the `Rc`s, and pad the start of the archive
+            let mut counts = value.counts.iter().collect::<Vec<_>>();
+            counts.reverse();
+            let mut rebuilt = HashMap::with_capacity(4 * counts.len());
+            rebuilt.extend(counts.into_iter().map(|(k, v)| (k.clone(), *v)));
+            let unshared = Record {
+                counts: rebuilt,
+                second: Rc::new((*value.first).clone()),
+                ..value.clone()
+            };
+            let mut serializer = AllocSerializer::<0>::default();
+            let padding = vec![0xff; rng.below(64) as usize + 1];
+            serializer.writer.extend_from_slice(&padding);
+            let padded = serialize_into::<_, _, Failure>(&unshared, serializer)
+                .unwrap()
+                .into_writer();
+            assert_ne!(bytes.as_slice(), padded.as_slice());
+            assert_eq!(hash_record(&padded), hash);
+
+            let dense = DenseRecord {
+                id: value.id,
+                name: value.name.clone(),
+                tags: value.tags.clone(),
+                score: value.score.map(|s| if s == 0.0 { 0.0 } else { s }),
+                counts: value.counts.clone(),
+                ordered: value.ordered.clone(),
+                pair: value.pair,
+                kind: value.kind,
+                first: value.first.clone(),
+                second: value.second.clone(),
+            };
+            let dense_bytes = to_bytes::<_, 256, Failure>(&dense).unwrap();
+            let archived = unsafe {
+                access_unchecked::<ArchivedDenseRecord>(&dense_bytes)
+            };
+            assert_eq!(stable_hash(archived), hash);
+
+            let mut perturbed = value.clone();
+            match rng.below(8) {
+                0 => perturbed.id = perturbed.id.wrapping_add(1),
+                1 => perturbed.name.push('!'),
+                2 => perturbed.tags.push(String::new()),
+                3 => {
+                    perturbed.score =
+                        Some(perturbed.score.map_or(1.0, |s| s * 2.0 + 1.0))
+                }
+                4 => {
+                    *perturbed
+                        .counts
+                        .entry("perturbed".to_string())
+                        .or_default() += 1
+                }
+                5 => perturbed.pair.1 = 'z',
+                6 => {
+                    perturbed.kind = match perturbed.kind {
+                        Kind::Small(x) => Kind::Large(x),
+                        _ => Kind::Small(0),
+                    }
+                }
+                _ => perturbed.second = Rc::new(format!("{}?", value.second)),
+            }
+            let perturbed = to_bytes::<_, 256, Failure>(&perturbed).unwrap();
+            assert_ne!(hash_record(&perturbed), hash);
+        }
+
+        // Moving bytes between adjacent strings changes the hash
+        let split = to_bytes::<_, 256, Failure>(&vec![
+            "ab".to_string(),
+            "c".to_string(),
+        ])
+        .unwrap();
+        let moved = to_bytes::<_, 256, Failure>(&vec![
+            "a".to_string(),
+            "bc".to_string(),
+        ])
+        .unwrap();
+        let hash_strings = |bytes: &[u8]| {
+            stable_hash(unsafe {
+                access_unchecked::<Archived<Vec<String>>>(bytes)
+            })
+        };
+        assert_ne!(hash_strings(&split), hash_strings(&moved));
+
+        // Digests are fixed by the scheme
+        assert_eq!(
+            StableHasher::new().finish(),
+            0x6c62272e07bb014262b821756295c58d
+        );
+        assert_eq!(stable_hash("hello"), 0x2e5b502d462502fc5e5dcb2c452ccbb8);
+    }
 }
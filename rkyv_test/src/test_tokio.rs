@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use std::io;
+
+    use rkyv::{
+        rancor::Failure,
+        to_bytes,
+        util::{to_bytes_async, to_writer_async},
+    };
+    use tokio::{
+        io::AsyncWrite,
+        runtime::{Builder, Runtime},
+    };
+
+    // Counts the number of times the wrapped future yields to the runtime.
+    struct CountYields<F> {
+        future: Pin<Box<F>>,
+        yields: usize,
+    }
+
+    impl<F: Future> Future for CountYields<F> {
+        type Output = (F::Output, usize);
+
+        fn poll(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Self::Output> {
+            match self.future.as_mut().poll(cx) {
+                Poll::Ready(output) => Poll::Ready((output, self.yields)),
+                Poll::Pending => {
+                    self.yields += 1;
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    fn block_on_counting<F: Future>(future: F) -> (F::Output, usize) {
+        runtime().block_on(CountYields {
+            future: Box::pin(future),
+            yields: 0,
+        })
+    }
+
+    // Records the largest write made to the inner writer.
+    #[derive(Default)]
+    struct RecordWrites {
+        inner: Vec<u8>,
+        max_write: usize,
+    }
+
+    impl AsyncWrite for RecordWrites {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.max_write = self.max_write.max(buf.len());
+            self.inner.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn to_bytes_async_large() {
+        // 100 MiB of copy-optimized elements
+        let value = (0..1600u32)
+            .map(|i| vec![i as u8; 64 * 1024])
+            .collect::<Vec<_>>();
+        let expected = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let (bytes, yields) =
+            block_on_counting(to_bytes_async::<_, 256, Failure>(&value));
+        assert_eq!(bytes.unwrap().as_slice(), expected.as_slice());
+        assert!(yields >= 1600 / 64, "only yielded {} times", yields);
+
+        let (writer, yields) =
+            block_on_counting(to_writer_async::<_, _, 256, Failure>(
+                &value,
+                RecordWrites::default(),
+            ));
+        let writer = writer.unwrap();
+        assert_eq!(writer.inner, expected.as_slice());
+        assert!(writer.max_write <= 64 * 1024);
+        assert!(yields >= 1600 / 64, "only yielded {} times", yields);
+    }
+
+    #[test]
+    fn to_writer_async_strings() {
+        let value = (0..20_000)
+            .map(|i| format!("out-of-line string number {}", i))
+            .collect::<Vec<_>>();
+        let expected = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let (bytes, yields) =
+            block_on_counting(to_writer_async::<_, _, 256, Failure>(
+                &value,
+                Vec::new(),
+            ));
+        assert_eq!(bytes.unwrap(), expected.as_slice());
+        assert!(yields > 0);
+    }
+
+    #[test]
+    fn to_bytes_async_small() {
+        let runtime = runtime();
+
+        let empty = Vec::<String>::new();
+        let bytes = runtime
+            .block_on(to_bytes_async::<_, 256, Failure>(&empty))
+            .unwrap();
+        let expected = to_bytes::<_, 256, Failure>(&empty).unwrap();
+        assert_eq!(bytes.as_slice(), expected.as_slice());
+
+        let value = vec![vec![1u32, 2, 3], vec![], vec![4]];
+        let bytes = runtime
+            .block_on(to_bytes_async::<_, 256, Failure>(&value))
+            .unwrap();
+        let expected = to_bytes::<_, 256, Failure>(&value).unwrap();
+        assert_eq!(bytes.as_slice(), expected.as_slice());
+    }
+}
@@ -123,3 +123,123 @@ pub mod alloc {
         test_archive_with(value, |a, b| b == a);
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+pub mod counting_alloc {
+    use core::cell::Cell;
+    use std::alloc::{GlobalAlloc, Layout, System};
+
+    // Only allocations made on the current thread are counted so that tests
+    // running in parallel don't interfere with each other.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        static LIVE_BYTES: Cell<usize> = const { Cell::new(0) };
+        static PEAK_BASE: Cell<usize> = const { Cell::new(0) };
+        static PEAK_BYTES: Cell<usize> = const { Cell::new(0) };
+        static BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+
+    // Returns whether an allocation of `size` bytes fits in the budget, and
+    // takes it out of the budget if it does.
+    fn take_budget(size: usize) -> bool {
+        BUDGET
+            .try_with(|budget| match budget.get() {
+                Some(remaining) if size > remaining => false,
+                Some(remaining) => {
+                    budget.set(Some(remaining - size));
+                    true
+                }
+                None => true,
+            })
+            .unwrap_or(true)
+    }
+
+    fn record(allocated: usize, deallocated: usize) {
+        let _ = ALLOCATIONS.try_with(|count| {
+            if allocated > 0 {
+                count.set(count.get() + 1);
+            }
+        });
+        let _ = LIVE_BYTES.try_with(|bytes| {
+            bytes.set(
+                bytes
+                    .get()
+                    .wrapping_add(allocated)
+                    .wrapping_sub(deallocated),
+            );
+            let _ = PEAK_BASE.try_with(|base| {
+                let _ = PEAK_BYTES.try_with(|peak| {
+                    let live = bytes.get().wrapping_sub(base.get()) as isize;
+                    if live > peak.get() as isize {
+                        peak.set(live as usize);
+                    }
+                });
+            });
+        });
+    }
+
+    /// Returns the number of allocations made on the current thread.
+    pub fn allocations() -> usize {
+        ALLOCATIONS.with(|count| count.get())
+    }
+
+    /// Returns the number of bytes allocated on the current thread which have
+    /// not been deallocated. This may wrap if memory allocated on another
+    /// thread is deallocated on this one, so only differences are meaningful.
+    pub fn live_bytes() -> usize {
+        LIVE_BYTES.with(|bytes| bytes.get())
+    }
+
+    /// Starts tracking the peak number of live bytes on the current thread.
+    pub fn reset_peak() {
+        PEAK_BASE.with(|base| base.set(live_bytes()));
+        PEAK_BYTES.with(|peak| peak.set(0));
+    }
+
+    /// Returns the largest number of bytes allocated on the current thread and
+    /// not deallocated at once since [`reset_peak`] was last called.
+    pub fn peak_bytes() -> usize {
+        PEAK_BYTES.with(|peak| peak.get())
+    }
+
+    /// Makes allocations on the current thread fail once `bytes` more bytes
+    /// have been allocated, or removes the limit if `bytes` is `None`.
+    ///
+    /// Reallocations count the full new size against the limit.
+    pub fn fail_after(bytes: Option<usize>) {
+        BUDGET.with(|budget| budget.set(bytes));
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            if !take_budget(layout.size()) {
+                return core::ptr::null_mut();
+            }
+            record(layout.size(), 0);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            record(0, layout.size());
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(
+            &self,
+            ptr: *mut u8,
+            layout: Layout,
+            new_size: usize,
+        ) -> *mut u8 {
+            if !take_budget(new_size) {
+                return core::ptr::null_mut();
+            }
+            record(new_size, layout.size());
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+}
@@ -1,6 +1,7 @@
 mod bytecheck_reexport;
 #[cfg(feature = "alloc")]
 mod test_alloc;
+mod test_no_alloc;
 #[cfg(feature = "std")]
 mod test_std;
 
@@ -9,18 +9,25 @@ mod tests {
         vec,
         vec::Vec,
     };
+    use core::{fmt::Debug, mem::size_of, num::NonZeroU16};
     #[cfg(feature = "std")]
-    use std::rc::Rc;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        rc::Rc,
+    };
 
     use rkyv::{
         access,
         bytecheck::CheckBytes,
-        rancor::{Error, Failure},
-        ser::Writer,
+        collections::btree_map::ArchivedBTreeMap,
+        de::Unify,
+        from_bytes,
+        rancor::{Error, Failure, Strategy},
+        ser::{AllocSerializer, Writer},
         to_bytes,
         util::{serialize_into, AlignedBytes},
-        validation::util::access_pos,
-        Archive, Archived, Serialize,
+        validation::{util::access_pos, validators::DefaultValidator},
+        Archive, Archived, Deserialize, Serialize,
     };
     #[cfg(feature = "wasm")]
     use wasm_bindgen_test::*;
@@ -172,6 +179,114 @@ mod tests {
         result.unwrap_err();
     }
 
+    #[cfg(feature = "pointer_width_32")]
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn overflowing_metadata() {
+        let vec_with = |offset: i32, len: u32| {
+            let mut bytes = AlignedBytes([0u8; 16]);
+            bytes.0[8..12].copy_from_slice(&offset.to_le_bytes());
+            bytes.0[12..16].copy_from_slice(&len.to_le_bytes());
+            bytes
+        };
+
+        // The length is valid, and the vec points to the start of the buffer
+        let bytes = vec_with(-8, 1);
+        access_pos::<Archived<Vec<u64>>, Failure>(bytes.as_ref(), 8).unwrap();
+
+        // Lengths whose size in bytes wraps a 32-bit or 64-bit usize
+        for len in [0x2000_0000, 0x2000_0001, u32::MAX / 8 + 1, u32::MAX] {
+            let bytes = vec_with(-8, len);
+            access_pos::<Archived<Vec<u64>>, Failure>(bytes.as_ref(), 8)
+                .unwrap_err();
+            access_pos::<Archived<Vec<[u64; 4]>>, Failure>(bytes.as_ref(), 8)
+                .unwrap_err();
+        }
+        let bytes = vec_with(-8, u32::MAX);
+        access_pos::<Archived<String>, Failure>(bytes.as_ref(), 8).unwrap_err();
+
+        // Offsets at the extremes of the offset type
+        for offset in [i32::MIN, i32::MIN + 8, i32::MAX - 8, i32::MAX] {
+            let bytes = vec_with(offset, 1);
+            access_pos::<Archived<Vec<u64>>, Failure>(bytes.as_ref(), 8)
+                .unwrap_err();
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn invalid_core_enum_tags() {
+        use core::{
+            cmp::Ordering, convert::Infallible, num::FpCategory, sync::atomic,
+        };
+
+        for tag in 0..3 {
+            access::<Archived<Ordering>, Failure>(&[tag]).unwrap();
+        }
+        assert!(access::<Archived<Ordering>, Failure>(&[3]).is_err());
+
+        for tag in 0..5 {
+            access::<Archived<atomic::Ordering>, Failure>(&[tag]).unwrap();
+        }
+        assert!(access::<Archived<atomic::Ordering>, Failure>(&[5]).is_err());
+
+        for tag in 0..5 {
+            access::<Archived<FpCategory>, Failure>(&[tag]).unwrap();
+        }
+        assert!(access::<Archived<FpCategory>, Failure>(&[5]).is_err());
+
+        assert!(access::<Archived<Infallible>, Failure>(&[]).is_err());
+        assert!(access::<Archived<Result<u8, Infallible>>, Failure>(&[1, 0])
+            .is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn invalid_control_flow_and_poll_tags() {
+        use core::{ops::ControlFlow, task::Poll};
+
+        // Continue is tag 0 and Break is tag 1
+        let archived =
+            access::<Archived<ControlFlow<u8, u8>>, Failure>(&[0, 7]).unwrap();
+        assert_eq!(*archived, ControlFlow::<u8, u8>::Continue(7));
+        let archived =
+            access::<Archived<ControlFlow<u8, u8>>, Failure>(&[1, 7]).unwrap();
+        assert_eq!(*archived, ControlFlow::<u8, u8>::Break(7));
+        for tag in 2..=255 {
+            assert!(access::<Archived<ControlFlow<u8, u8>>, Failure>(&[
+                tag, 0
+            ])
+            .is_err());
+        }
+
+        // Ready is tag 0 and Pending is tag 1
+        let archived = access::<Archived<Poll<u8>>, Failure>(&[0, 7]).unwrap();
+        assert_eq!(*archived, Poll::Ready(7u8));
+        let archived = access::<Archived<Poll<u8>>, Failure>(&[1, 0]).unwrap();
+        assert_eq!(*archived, Poll::<u8>::Pending);
+        for tag in 2..=255 {
+            assert!(access::<Archived<Poll<u8>>, Failure>(&[tag, 0]).is_err());
+        }
+
+        // Invalid payloads are rejected too
+        assert!(access::<Archived<ControlFlow<bool, u8>>, Failure>(&[1, 2])
+            .is_err());
+        assert!(access::<Archived<Poll<bool>>, Failure>(&[0, 2]).is_err());
+
+        let value = (
+            ControlFlow::<String, Vec<u32>>::Break("stop".to_string()),
+            Poll::Ready(vec!["ready".to_string()]),
+        );
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<
+            Archived<(ControlFlow<String, Vec<u32>>, Poll<Vec<String>>)>,
+            Failure,
+        >(&bytes)
+        .unwrap();
+        assert_eq!(archived.0, value.0);
+        assert_eq!(archived.1, value.1);
+    }
+
     #[cfg(feature = "pointer_width_32")]
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
@@ -295,6 +410,86 @@ mod tests {
         });
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn derive_reordered_struct() {
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Test {
+            a: u8,
+            b: u64,
+            c: bool,
+            d: String,
+            e: u16,
+        }
+
+        let value = Test {
+            a: 1,
+            b: 2,
+            c: true,
+            d: "hello world".to_string(),
+            e: 3,
+        };
+        serialize_and_check::<_, Failure>(&value);
+
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<ArchivedTest, Failure>(buf.as_ref()).unwrap();
+        assert_eq!(archived.a, 1);
+        assert_eq!(archived.b, 2);
+        assert!(archived.c);
+        assert_eq!(archived.d, "hello world");
+        assert_eq!(archived.e, 3);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_pack_flags() {
+        #[derive(Archive, Serialize, Debug, PartialEq)]
+        #[archive(pack_flags, check_bytes)]
+        enum Mode {
+            Off,
+            Low,
+            High,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(pack_flags, check_bytes)]
+        struct Test {
+            a: bool,
+            #[archive(bits = 2)]
+            mode: Mode,
+            b: bool,
+        }
+
+        let value = Test {
+            a: true,
+            mode: Mode::High,
+            b: false,
+        };
+        serialize_and_check::<_, Failure>(&value);
+
+        let mut buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        assert_eq!(buf.len(), 1);
+        let archived = access::<ArchivedTest, Failure>(buf.as_ref()).unwrap();
+        assert!(archived.a());
+        assert_eq!(archived.mode(), Mode::High);
+        assert!(!archived.b());
+
+        // Mode only has three values
+        buf[0] = 0b0111;
+        assert!(access::<ArchivedTest, Failure>(buf.as_ref()).is_err());
+
+        // Only the lowest four bits are used
+        buf[0] = 0b1_0011;
+        assert!(access::<ArchivedTest, Failure>(buf.as_ref()).is_err());
+
+        buf[0] = 0b1011;
+        let archived = access::<ArchivedTest, Failure>(buf.as_ref()).unwrap();
+        assert!(archived.a());
+        assert_eq!(archived.mode(), Mode::Low);
+        assert!(archived.b());
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn derive_tuple_struct() {
@@ -382,102 +577,337 @@ mod tests {
         access::<ArchivedTest, Failure>(buf.as_ref()).unwrap();
     }
 
-    // TODO: re-enable after btreemap validation is fixed
-    // #[test]
-    // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    // fn check_b_tree() {
-    //     let mut value = BTreeMap::new();
-    //     value.insert("foo".to_string(), 10);
-    //     value.insert("bar".to_string(), 20);
-    //     value.insert("baz".to_string(), 40);
-    //     value.insert("bat".to_string(), 80);
-
-    //     let mut serializer = DefaultSerializer::default();
-    //     serializer.serialize_value(&value).unwrap();
-    //     let buf = serializer.into_serializer().into_inner();
-
-    //     access::<BTreeMap<String, i32>, Failure>(buf.as_ref()).unwrap();
-    // }
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_shared_ptr_fixed_validator() {
+        use rkyv::{
+            rancor::BoxedError,
+            validation::{
+                util::access_with_context,
+                validators::{FixedValidator, SharedClaim},
+            },
+        };
 
-    // #[test]
-    // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    // fn check_invalid_b_tree_set() {
-    //     let data = AlignedBytes([
-    //         0, 0, 0, 0, 253, 6, 239, 6, 255, 255, 255, 252, 0, 0, 0, 0, 0, 0,
-    //         0, 0, 1, 0, 0, 5, 0, 0, 0, 0, 240, 255, 255, 255, 1, 128, 0, 249,
-    //         220, 255, 255, 255, 4, 0, 0, 96, 0, 0, 0, 249, 232, 255, 255,
-    // 255,     ]);
-
-    //     rkyv::from_bytes::<BTreeSet<u8>, Failure>(&data.0).unwrap_err();
-
-    //     let data = AlignedBytes([
-    //         1, 29, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 253, 0, 0, 116, 255, 255,
-    // 40,         0, 8, 0, 0, 0, 236, 255, 255, 255, 1, 128, 72, 0, 220,
-    // 255, 255,         255, 236, 255, 255, 255, 0, 0, 0, 0, 32, 0, 255,
-    // 254, 255, 0, 94,         2, 33, 0, 0, 0, 0, 0, 0, 0, 61, 1, 38, 0, 0,
-    // 32, 0, 255, 255, 1, 0,         1, 255, 255, 0, 184, 4, 0, 28, 0, 8,
-    // 0, 2, 142, 255, 255, 255, 3,         1, 255, 251, 0, 184, 255, 255,
-    // 255,     ]);
-
-    //     rkyv::from_bytes::<BTreeSet<Box<u8>>, Failure>(&data.0).unwrap_err();
-    // }
+        let shared = Rc::new(10u32);
+        let value = vec![
+            shared.clone(),
+            Rc::new(20),
+            shared.clone(),
+            Rc::new(30),
+            shared,
+        ];
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
 
-    // #[test]
-    // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    // fn check_empty_b_tree() {
-    //     let value = BTreeMap::<u8, ()>::new();
+        // Each distinct shared pointer needs one claim
+        let mut claims = [SharedClaim::UNINIT; 3];
+        let mut validator = FixedValidator::new(&bytes, &mut claims);
+        let archived =
+            access_with_context::<Archived<Vec<Rc<u32>>>, _, Failure>(
+                &bytes,
+                &mut validator,
+            )
+            .unwrap();
+        assert_eq!(validator.claims().len(), 3);
+        assert_eq!(*archived[4], 10);
 
-    //     let mut serializer = DefaultSerializer::default();
-    //     serializer.serialize_value(&value).unwrap();
-    //     let buf = serializer.into_serializer().into_inner();
+        let mut claims = [SharedClaim::UNINIT; 2];
+        let mut validator = FixedValidator::new(&bytes, &mut claims);
+        let result = access_with_context::<Archived<Vec<Rc<u32>>>, _, BoxedError>(
+            &bytes,
+            &mut validator,
+        );
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("more than 2 shared pointers"), "{}", error);
+    }
 
-    //     access::<BTreeMap<u8, ()>, Failure>(buf.as_ref()).unwrap();
-    // }
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_weak_tombstone() {
+        #[cfg(not(feature = "std"))]
+        use alloc::rc::Weak;
+        #[cfg(feature = "std")]
+        use std::rc::Weak;
 
-    // #[test]
-    // // This test is unfortunately too slow to run through miri
-    // #[cfg_attr(miri, ignore)]
-    // // This test creates structures too big to fit in 16-bit offsets
-    // #[cfg(not(feature = "pointer_width_16"))]
-    // fn check_b_tree_large() {
-    //     let mut value = BTreeMap::new();
-    //     for i in 0..100_000 {
-    //         value.insert(i.to_string(), i);
-    //     }
-
-    //     let mut serializer = DefaultSerializer::default();
-    //     serializer.serialize_value(&value).unwrap();
-    //     let buf = serializer.into_serializer().into_inner();
-
-    //     access::<BTreeMap<String, i32>, Failure>(buf.as_ref()).unwrap();
-    // }
+        use rkyv::{
+            rc::{ArchivedOptionRcWeak, RcFlavor},
+            with::WeakTombstone,
+        };
 
-    // #[test]
-    // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    // fn b_tree_struct_member() {
-    //     #[derive(Archive, Serialize, Deserialize, Debug, Default)]
-    //     #[archive(check_bytes)]
-    //     pub struct MyType {
-    //         pub some_list: BTreeMap<String, Vec<f32>>,
-    //         pub values: Vec<f32>,
-    //     }
-
-    //     let mut value = MyType::default();
-
-    //     value
-    //         .some_list
-    //         .entry("Asdf".to_string())
-    //         .and_modify(|e| e.push(1.0))
-    //         .or_insert_with(|| vec![2.0]);
-
-    //     let serializer = serialize_into::<_, _, Failure>(
-    //         &value,
-    //         DefaultSerializer::default(),
-    //     ).unwrap();
-    //     let buf = serializer.into_serializer().into_inner();
-
-    //     let _ = from_bytes::<MyType, Failure>(&buf).unwrap();
-    // }
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Old {
+            a: Rc<u32>,
+            b: Weak<u32>,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct New {
+            a: Rc<u32>,
+            #[with(WeakTombstone)]
+            b: Option<Weak<u32>>,
+        }
+
+        type Tombstone = ArchivedOptionRcWeak<Archived<u32>, RcFlavor>;
+
+        let shared = Rc::new(10);
+        for b in [
+            Some(Rc::downgrade(&shared)),
+            Some(Rc::downgrade(&Rc::new(20))),
+            None,
+        ] {
+            serialize_and_check::<_, Failure>(&New {
+                a: shared.clone(),
+                b,
+            });
+        }
+
+        // Tags for none and dangling weak pointers are valid, other tags are
+        // not
+        let mut bytes = AlignedBytes([0u8; size_of::<Tombstone>()]);
+        assert!(access::<Tombstone, Failure>(bytes.as_ref())
+            .unwrap()
+            .is_none());
+        bytes[0] = 2;
+        assert!(access::<Tombstone, Failure>(bytes.as_ref())
+            .unwrap()
+            .is_dangling());
+        bytes[0] = 3;
+        access::<Tombstone, Failure>(bytes.as_ref()).unwrap_err();
+
+        // Archived weak pointers can be read with the tombstone format
+        let buf = serialize_into::<_, _, Failure>(
+            &Old {
+                a: shared.clone(),
+                b: Rc::downgrade(&shared),
+            },
+            DefaultSerializer::default(),
+        )
+        .unwrap()
+        .into_writer();
+        let archived = access::<ArchivedNew, Failure>(buf.as_ref()).unwrap();
+        assert_eq!(**archived.b.upgrade().unwrap(), 10);
+
+        let buf = serialize_into::<_, _, Failure>(
+            &Old {
+                a: shared.clone(),
+                b: Weak::new(),
+            },
+            DefaultSerializer::default(),
+        )
+        .unwrap()
+        .into_writer();
+        let archived = access::<ArchivedNew, Failure>(buf.as_ref()).unwrap();
+        assert!(archived.b.is_none());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_columnar() {
+        use rkyv::{
+            primitive::ArchivedUsize, rancor::BoxedError, with::AsColumns,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes, columnar)]
+        struct Record {
+            id: u32,
+            name: String,
+            location: Point,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Table {
+            #[with(AsColumns)]
+            records: Vec<Record>,
+        }
+
+        let value = Table {
+            records: (0..3)
+                .map(|i| Record {
+                    id: i,
+                    name: i.to_string(),
+                    location: Point {
+                        x: i as i32,
+                        y: i as i32,
+                    },
+                })
+                .collect(),
+        };
+        serialize_and_check::<_, Failure>(&value);
+
+        let mut buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<ArchivedTable, Failure>(&buf).unwrap();
+        assert_eq!(archived.records.len(), 3);
+        assert_eq!(archived.records.row(2).name(), "2");
+
+        // The number of rows is the first field of the columns, which are the
+        // root of the archive
+        let pos = buf.len() - size_of::<ArchivedTable>();
+        let len = ArchivedUsize::from_native(4);
+        let len_bytes = unsafe {
+            core::slice::from_raw_parts(
+                (&len as *const ArchivedUsize).cast::<u8>(),
+                size_of::<ArchivedUsize>(),
+            )
+        };
+        buf[pos..pos + len_bytes.len()].copy_from_slice(len_bytes);
+        let error = access::<ArchivedTable, BoxedError>(&buf).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("column 'id' has length 3 but there are 4 rows"));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_b_tree() {
+        let mut value = BTreeMap::new();
+        value.insert("foo".to_string(), 10);
+        value.insert("bar".to_string(), 20);
+        value.insert("baz".to_string(), 40);
+        value.insert("bat".to_string(), 80);
+
+        serialize_and_check::<_, Failure>(&value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_empty_b_tree() {
+        let value = BTreeMap::<u8, ()>::new();
+
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        // Empty maps don't allocate any nodes
+        assert_eq!(buf.len(), size_of::<Archived<BTreeMap<u8, ()>>>());
+
+        let archived =
+            access::<Archived<BTreeMap<u8, ()>>, Failure>(buf.as_ref())
+                .unwrap();
+        assert!(archived.is_empty());
+        assert!(archived.iter().next().is_none());
+        assert!(archived.get(&0).is_none());
+    }
+
+    #[test]
+    // This test is unfortunately too slow to run through miri
+    #[cfg_attr(miri, ignore)]
+    // This test creates structures too big to fit in 16-bit offsets
+    #[cfg(not(feature = "pointer_width_16"))]
+    fn check_b_tree_large() {
+        let mut value = BTreeMap::new();
+        for i in 0..100_000 {
+            value.insert(i.to_string(), i);
+        }
+
+        serialize_and_check::<_, Failure>(&value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn b_tree_struct_member() {
+        #[derive(
+            Archive, Serialize, Deserialize, Debug, Default, PartialEq,
+        )]
+        #[archive(check_bytes)]
+        pub struct MyType {
+            pub some_list: BTreeMap<String, Vec<f32>>,
+            pub values: Vec<f32>,
+        }
+
+        let mut value = MyType::default();
+
+        value
+            .some_list
+            .entry("Asdf".to_string())
+            .and_modify(|e| e.push(1.0))
+            .or_insert_with(|| vec![2.0]);
+
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let deserialized = from_bytes::<MyType, Failure>(&buf).unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    // Serializes, validates, and deserializes B-tree maps with each number of
+    // entries from 0 to 5, checking every entry along the way.
+    fn check_small_b_trees<K, V>(entry: impl Fn(u32) -> (K, V))
+    where
+        K: Archive + Debug + Ord,
+        K::Archived: Ord + PartialEq<K>,
+        V: Archive + Debug + PartialEq,
+        V::Archived: PartialEq<V>,
+        BTreeMap<K, V>: Archive<Archived = ArchivedBTreeMap<K::Archived, V::Archived>>
+            + Serialize<Strategy<AllocSerializer<256>, Failure>>,
+        ArchivedBTreeMap<K::Archived, V::Archived>: CheckBytes<Strategy<DefaultValidator, Failure>>
+            + Deserialize<BTreeMap<K, V>, Strategy<Unify, Failure>>,
+    {
+        for len in 0..=5 {
+            let value = (0..len).map(&entry).collect::<BTreeMap<K, V>>();
+            let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+            let archived =
+                access::<Archived<BTreeMap<K, V>>, Failure>(buf.as_ref())
+                    .unwrap();
+
+            assert_eq!(archived.len(), value.len());
+            assert_eq!(archived.is_empty(), value.is_empty());
+            assert_eq!(archived.iter().len(), value.len());
+            for ((ak, av), (k, v)) in archived.iter().zip(value.iter()) {
+                assert!(ak == k);
+                assert!(av == v);
+                assert!(archived.get(ak).is_some_and(|x| x == v));
+            }
+
+            let deserialized =
+                from_bytes::<BTreeMap<K, V>, Failure>(&buf).unwrap();
+            assert_eq!(deserialized, value);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_small_b_trees_zst_values() {
+        check_small_b_trees(|i| (i, ()));
+        check_small_b_trees(|i| (i.to_string(), ()));
+        check_small_b_trees(|i| ((), i));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_small_b_trees_sized_values() {
+        check_small_b_trees(|i| (i, i.to_string()));
+        check_small_b_trees(|i| (i.to_string(), i as u64));
+        check_small_b_trees(|i| (i as u8, [i; 3]));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_small_b_tree_sets() {
+        for len in 0..=5 {
+            let value =
+                (0..len).map(|i| i.to_string()).collect::<BTreeSet<_>>();
+            let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+            let archived =
+                access::<Archived<BTreeSet<String>>, Failure>(buf.as_ref())
+                    .unwrap();
+
+            assert_eq!(archived.len(), value.len());
+            assert!(archived.iter().eq(value.iter()));
+            for key in value.iter() {
+                assert!(archived.contains_key(key.as_str()));
+            }
+            assert!(!archived.contains_key("wrong!"));
+
+            let deserialized =
+                from_bytes::<BTreeSet<String>, Failure>(&buf).unwrap();
+            assert_eq!(deserialized, value);
+        }
+    }
 
     // #[test]
     // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
@@ -487,20 +917,2354 @@ mod tests {
     //     access::<Duration, Failure>(&[0xFF, 16]).unwrap_err();
     // }
 
-    // #[test]
-    // #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    // fn check_invalid_btreemap() {
-    //     let data = AlignedBytes([
-    //         0, 0, 0, 0, 0, 0, 0, 0, 0, 0x30, 0, 0x00, 0x00, 0x00, 0x0c, 0xa5,
-    //         0xf0, 0xff, 0xff, 0xff,
-    //     ]);
-    //     rkyv::from_bytes::<BTreeMap<u8, Box<u8>>,
-    // Failure>(&data.0).unwrap_err(); }
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_invalid_b_tree() {
+        use rkyv::{access_unchecked, util::AlignedVec};
+
+        type ArchivedSet = Archived<BTreeSet<u32>>;
+
+        let value = (0..100).collect::<BTreeSet<u32>>();
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        access::<ArchivedSet, Failure>(buf.as_ref()).unwrap();
+
+        // Unsorted keys
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(buf.as_ref());
+        let archived = unsafe { access_unchecked::<ArchivedSet>(&bytes) };
+        let key = archived.iter().nth(50).unwrap() as *const Archived<u32>;
+        let offset = key as usize - bytes.as_ptr() as usize;
+        unsafe {
+            bytes
+                .as_mut_ptr()
+                .add(offset)
+                .cast::<Archived<u32>>()
+                .write(Archived::<u32>::from_native(10));
+        }
+        assert!(access::<ArchivedSet, Failure>(&bytes).is_err());
+
+        // Incorrect length
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(buf.as_ref());
+        let offset = bytes.len() - size_of::<ArchivedSet>();
+        unsafe {
+            bytes
+                .as_mut_ptr()
+                .add(offset)
+                .cast::<Archived<usize>>()
+                .write(Archived::<usize>::from_native(101));
+        }
+        assert!(access::<ArchivedSet, Failure>(&bytes).is_err());
+
+        // Nonempty length with a null root
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(&vec![0; size_of::<ArchivedSet>()]);
+        unsafe {
+            bytes
+                .as_mut_ptr()
+                .cast::<Archived<usize>>()
+                .write(Archived::<usize>::from_native(1));
+        }
+        assert!(access::<ArchivedSet, Failure>(&bytes).is_err());
+    }
+
+    // Makes the first occurrence of `text` in `bytes` invalid UTF-8
+    fn corrupt_str(bytes: &mut [u8], text: &str) {
+        let pos = bytes
+            .windows(text.len())
+            .position(|window| window == text.as_bytes())
+            .unwrap();
+        bytes[pos] = 0xff;
+    }
+
+    fn lazy_entry_name(i: u32) -> String {
+        format!("entry {} with a name that is too long to inline", i)
+    }
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn check_invalid_string() {
-        let data = AlignedBytes([0x10; 16]);
-        rkyv::from_bytes::<String, Failure>(&data.0).unwrap_err();
+    fn check_lazy_vec() {
+        use rkyv::validation::lazy::LazyArchive;
+
+        let value = (0..10).map(lazy_entry_name).collect::<Vec<_>>();
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        corrupt_str(&mut bytes, &lazy_entry_name(7));
+        assert!(access::<Archived<Vec<String>>, Failure>(&bytes).is_err());
+
+        let archive =
+            LazyArchive::<Archived<Vec<String>>>::new::<Failure>(&bytes)
+                .unwrap();
+        let root = archive.root();
+        assert_eq!(root.len(), 10);
+        assert_eq!(archive.validated_count(), 0);
+
+        // The corrupted entry is never accessed
+        for i in [0, 3, 9] {
+            let element = root.element::<Failure>(i).unwrap().unwrap();
+            assert_eq!(
+                element.get::<Failure>().unwrap(),
+                &lazy_entry_name(i as u32)
+            );
+        }
+        assert!(root.element::<Failure>(10).unwrap().is_none());
+        // The layout of the vec and each accessed element are validated once
+        assert_eq!(archive.validated_count(), 4);
+
+        // Accessing validated elements again does not validate them again
+        for _ in 0..3 {
+            let element = root.element::<Failure>(3).unwrap().unwrap();
+            assert_eq!(element.get::<Failure>().unwrap(), &lazy_entry_name(3));
+        }
+        assert_eq!(archive.validated_count(), 4);
+
+        // Accessing the corrupted entry fails every time
+        let element = root.element::<Failure>(7).unwrap().unwrap();
+        assert!(element.get::<Failure>().is_err());
+        assert!(element.get::<Failure>().is_err());
+        assert_eq!(archive.validated_count(), 4);
+
+        // Validating the whole vec also fails
+        assert!(root.get::<Failure>().is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_lazy_struct() {
+        use core::ptr::addr_of;
+
+        use rkyv::validation::lazy::LazyArchive;
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Test {
+            name: String,
+            entries: Vec<String>,
+        }
+
+        let value = Test {
+            name: lazy_entry_name(100),
+            entries: (0..4).map(lazy_entry_name).collect(),
+        };
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        corrupt_str(&mut bytes, &lazy_entry_name(100));
+
+        let archive =
+            LazyArchive::<ArchivedTest>::new::<Failure>(&bytes).unwrap();
+        let root = archive.root();
+        let entries = root.project(|test| unsafe { addr_of!((*test).entries) });
+        let entry = entries.element::<Failure>(2).unwrap().unwrap();
+        assert_eq!(entry.get::<Failure>().unwrap(), &lazy_entry_name(2));
+        assert_eq!(entries.get::<Failure>().unwrap().len(), 4);
+
+        let name = root.project(|test| unsafe { addr_of!((*test).name) });
+        assert!(name.get::<Failure>().is_err());
+        assert!(root.get::<Failure>().is_err());
+    }
+
+    #[test]
+    #[should_panic = "projected pointer must point to a field"]
+    fn check_lazy_project_out_of_bounds() {
+        use rkyv::validation::lazy::LazyArchive;
+
+        let bytes = to_bytes::<_, 256, Failure>(&42u32).unwrap();
+        let archive =
+            LazyArchive::<Archived<u32>>::new::<Failure>(&bytes).unwrap();
+        archive.root().project(|value| value.wrapping_add(1));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_lazy_b_tree() {
+        use rkyv::validation::lazy::LazyArchive;
+
+        let key = Archived::<u32>::from_native;
+
+        let value = (0..1000)
+            .map(|i| (i, lazy_entry_name(i)))
+            .collect::<BTreeMap<_, _>>();
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        corrupt_str(&mut bytes, &lazy_entry_name(500));
+        assert!(
+            access::<Archived<BTreeMap<u32, String>>, Failure>(&bytes).is_err()
+        );
+
+        let archive = LazyArchive::<Archived<BTreeMap<u32, String>>>::new::<
+            Failure,
+        >(&bytes)
+        .unwrap();
+        let root = archive.root();
+        assert_eq!(root.len(), 1000);
+
+        for i in [0, 1, 250, 499, 501, 999] {
+            let value = root.value::<_, Failure>(&key(i)).unwrap().unwrap();
+            assert_eq!(value.get::<Failure>().unwrap(), &lazy_entry_name(i));
+        }
+        assert!(root.value::<_, Failure>(&key(1000)).unwrap().is_none());
+
+        // Only the searched nodes and keys are validated
+        let validated = archive.validated_count();
+        assert!(validated < 200);
+        let value = root.value::<_, Failure>(&key(250)).unwrap().unwrap();
+        value.get::<Failure>().unwrap();
+        assert_eq!(archive.validated_count(), validated);
+
+        let value = root.value::<_, Failure>(&key(500)).unwrap().unwrap();
+        assert!(value.get::<Failure>().is_err());
+
+        let empty =
+            to_bytes::<_, 256, Failure>(&BTreeMap::<u32, u32>::new()).unwrap();
+        let archive =
+            LazyArchive::<Archived<BTreeMap<u32, u32>>>::new::<Failure>(&empty)
+                .unwrap();
+        assert!(archive
+            .root()
+            .value::<_, Failure>(&key(0))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_lazy_invalid_root() {
+        use rkyv::validation::lazy::LazyArchive;
+
+        let bytes = AlignedBytes([0u8; 4]);
+        assert!(
+            LazyArchive::<Archived<Vec<u32>>>::new::<Failure>(&bytes[..])
+                .is_err()
+        );
+
+        // A vec pointing past the end of the archive fails when its elements
+        // are accessed
+        let mut bytes = to_bytes::<_, 256, Failure>(&vec![1u32, 2, 3]).unwrap();
+        let offset = to_bytes::<_, 16, Failure>(&0x1000isize).unwrap();
+        let root = bytes.len() - size_of::<Archived<Vec<u32>>>();
+        bytes[root..root + offset.len()].copy_from_slice(&offset);
+        let archive =
+            LazyArchive::<Archived<Vec<u32>>>::new::<Failure>(&bytes).unwrap();
+        assert!(archive.root().element::<Failure>(0).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_compact() {
+        use rkyv::{access_unchecked, util::AlignedVec, with::Compact};
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Test {
+            #[with(Compact)]
+            values: Vec<u32>,
+            #[with(Compact)]
+            names: Vec<String>,
+            #[with(Compact)]
+            name: String,
+            #[with(Compact)]
+            boxed: Box<u64>,
+            #[with(Compact)]
+            boxed_str: Box<str>,
+        }
+
+        let value = Test {
+            values: vec![1, 2, 3, 4],
+            names: vec!["a".to_string(), "hello world".to_string()],
+            name: "hello world".to_string(),
+            boxed: Box::new(42),
+            boxed_str: "hello world".into(),
+        };
+        serialize_and_check::<_, Failure>(&value);
+
+        // Invalid UTF-8 in a compact string
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(buf.as_ref());
+        let archived = unsafe { access_unchecked::<ArchivedTest>(&bytes) };
+        let offset = archived.name.as_ptr() as usize - bytes.as_ptr() as usize;
+        bytes[offset] = 0xff;
+        assert!(access::<ArchivedTest, Failure>(&bytes).is_err());
+    }
+
+    // A reading archived with big-endian primitives. This is what a program
+    // built with the `big_endian` feature produces for `Reading`.
+    const BE_READING: [u8; 32] = [
+        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // id
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, // offset
+        0xde, 0xad, 0xbe, 0xef, // count
+        0x3f, 0xc0, 0x00, 0x00, // temperature
+        0x12, 0x34, // code
+        0xab, 0xcd, // flags
+        0x00, 0x00, 0x00, 0x52, // letter
+    ];
+
+    // The same reading archived with little-endian primitives.
+    const LE_READING: [u8; 32] = [
+        0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, // id
+        0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // offset
+        0xef, 0xbe, 0xad, 0xde, // count
+        0x00, 0x00, 0xc0, 0x3f, // temperature
+        0x34, 0x12, // code
+        0xcd, 0xab, // flags
+        0x52, 0x00, 0x00, 0x00, // letter
+    ];
+
+    macro_rules! define_reading {
+        ($name:ident $(, $wrapper:ident)?) => {
+            #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+            #[archive(check_bytes, preserve_order)]
+            struct $name {
+                $(#[with($wrapper)])?
+                id: u64,
+                $(#[with($wrapper)])?
+                offset: i64,
+                $(#[with($wrapper)])?
+                count: u32,
+                $(#[with($wrapper)])?
+                temperature: f32,
+                $(#[with($wrapper)])?
+                code: NonZeroU16,
+                $(#[with($wrapper)])?
+                flags: u16,
+                $(#[with($wrapper)])?
+                letter: char,
+            }
+
+            impl $name {
+                fn new() -> Self {
+                    Self {
+                        id: 0x0102_0304_0506_0708,
+                        offset: -2,
+                        count: 0xdead_beef,
+                        temperature: 1.5,
+                        code: NonZeroU16::new(0x1234).unwrap(),
+                        flags: 0xabcd,
+                        letter: 'R',
+                    }
+                }
+            }
+        };
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_foreign_endian() {
+        use rkyv::with::{AsBigEndian, AsLittleEndian};
+
+        define_reading!(BigEndianReading, AsBigEndian);
+        define_reading!(LittleEndianReading, AsLittleEndian);
+
+        // Read archives with explicit endianness
+        let archived = access::<ArchivedBigEndianReading, Failure>(
+            &AlignedBytes(BE_READING)[..],
+        )
+        .unwrap();
+        assert_eq!(archived.id, 0x0102_0304_0506_0708);
+        assert_eq!(archived.offset, -2);
+        assert_eq!(archived.count, 0xdead_beef);
+        assert_eq!(archived.temperature, 1.5);
+        assert_eq!(archived.code.get(), 0x1234);
+        assert_eq!(archived.flags, 0xabcd);
+        assert_eq!(archived.letter, 'R');
+        let deserialized = from_bytes::<BigEndianReading, Failure>(
+            &AlignedBytes(BE_READING)[..],
+        )
+        .unwrap();
+        assert_eq!(deserialized, BigEndianReading::new());
+
+        let archived = access::<ArchivedLittleEndianReading, Failure>(
+            &AlignedBytes(LE_READING)[..],
+        )
+        .unwrap();
+        assert_eq!(archived.id, 0x0102_0304_0506_0708);
+        assert_eq!(archived.letter, 'R');
+        let deserialized = from_bytes::<LittleEndianReading, Failure>(
+            &AlignedBytes(LE_READING)[..],
+        )
+        .unwrap();
+        assert_eq!(deserialized, LittleEndianReading::new());
+
+        // Write archives with explicit endianness
+        let buf =
+            to_bytes::<_, 256, Failure>(&BigEndianReading::new()).unwrap();
+        assert_eq!(buf.as_slice(), &BE_READING);
+        let buf =
+            to_bytes::<_, 256, Failure>(&LittleEndianReading::new()).unwrap();
+        assert_eq!(buf.as_slice(), &LE_READING);
+
+        // Invalid chars are still caught
+        let mut invalid = AlignedBytes(BE_READING);
+        invalid[28] = 0xff;
+        assert!(
+            access::<ArchivedBigEndianReading, Failure>(&invalid[..]).is_err()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_native_endian() {
+        define_reading!(Reading);
+
+        // The native representation matches one of the fixtures, so programs
+        // with the opposite endianness can read it with the wrappers
+        #[cfg(not(feature = "big_endian"))]
+        let (native, foreign) = (LE_READING, BE_READING);
+        #[cfg(feature = "big_endian")]
+        let (native, foreign) = (BE_READING, LE_READING);
+
+        let buf = to_bytes::<_, 256, Failure>(&Reading::new()).unwrap();
+        assert_eq!(buf.as_slice(), &native);
+        let native = AlignedBytes(native);
+        let archived = access::<ArchivedReading, Failure>(&native[..]).unwrap();
+        assert_eq!(archived.id, 0x0102_0304_0506_0708);
+        assert_eq!(archived.letter, 'R');
+
+        // Foreign archives are not valid native archives in general
+        let foreign = AlignedBytes(foreign);
+        assert!(access::<ArchivedReading, Failure>(&foreign[..]).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_invalid_string() {
+        let data = AlignedBytes([0x10; 16]);
+        rkyv::from_bytes::<String, Failure>(&data.0).unwrap_err();
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_cold_region() {
+        use rkyv::{
+            util::to_bytes_with_regions,
+            validation::util::access_with_cold_region, with::Cold,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Test {
+            name: String,
+            #[with(Cold)]
+            blob: Vec<u8>,
+            tags: Vec<u32>,
+        }
+
+        let value = Test {
+            name: "a hot string that lives out of line".to_string(),
+            blob: vec![0xAA; 100],
+            tags: vec![1, 2, 3, 4],
+        };
+
+        let (bytes, cold) =
+            to_bytes_with_regions::<_, 256, Failure>(&value).unwrap();
+        assert!(!cold.is_empty());
+
+        let archived = access_with_cold_region::<ArchivedTest, Failure>(
+            &bytes,
+            cold.clone(),
+        )
+        .unwrap();
+        assert_eq!(archived.name, value.name);
+        assert_eq!(archived.blob.as_slice(), value.blob.as_slice());
+        assert_eq!(archived.tags.as_slice(), value.tags.as_slice());
+
+        let offset_of = |ptr: *const u8| ptr as usize - bytes.as_ptr() as usize;
+        let name_end =
+            offset_of(archived.name.as_str().as_ptr()) + archived.name.len();
+        let tags_end = offset_of(archived.tags.as_ptr().cast())
+            + archived.tags.len() * core::mem::size_of::<u32>();
+        let blob_start = offset_of(archived.blob.as_ptr());
+        assert!(name_end <= cold.start);
+        assert!(tags_end <= cold.start);
+        assert!(blob_start >= cold.start);
+        assert!(blob_start + archived.blob.len() <= cold.end);
+
+        // Without the cold region, the blob violates the validator's ordering
+        assert!(access::<ArchivedTest, Failure>(&bytes).is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    mod string_dict {
+        use rkyv::{
+            collections::string_dict::{
+                ArchivedStringDict, StringDictResolver,
+            },
+            rancor::{Error, Fallible},
+            ser::Writer,
+            Archive, Serialize,
+        };
+
+        pub struct Dict<'a>(pub &'a [&'a str]);
+
+        impl Archive for Dict<'_> {
+            type Archived = ArchivedStringDict;
+            type Resolver = StringDictResolver;
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedStringDict::resolve_from_resolver(pos, resolver, out);
+            }
+        }
+
+        impl<S> Serialize<S> for Dict<'_>
+        where
+            S: Fallible + Writer + ?Sized,
+            S::Error: Error,
+        {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedStringDict::serialize_from_iter(
+                    self.0.iter().copied(),
+                    serializer,
+                )
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_string_dict() {
+        use rkyv::{
+            collections::string_dict::ArchivedStringDict, rancor::Strategy,
+            util::AlignedVec,
+        };
+
+        use self::string_dict::Dict;
+
+        const COLORS: [&str; 4] = ["red", "green", "blue", "cyan"];
+
+        let words = (0..1000)
+            .map(|i| COLORS[(i * 3) % COLORS.len()])
+            .collect::<Vec<_>>();
+
+        let mut writer = AlignedVec::new();
+        let (_, codes) = ArchivedStringDict::serialize_dict_returning_codes(
+            words.iter().copied(),
+            Strategy::<_, Failure>::wrap(&mut writer),
+        )
+        .unwrap();
+        assert_eq!(codes.len(), words.len());
+
+        let buf = to_bytes::<_, 256, Failure>(&Dict(&words)).unwrap();
+        // Each distinct string is only written once
+        assert!(buf.len() < 128);
+
+        let archived =
+            access::<ArchivedStringDict, Failure>(buf.as_ref()).unwrap();
+        assert_eq!(archived.len(), 4);
+        assert_eq!(
+            archived.iter().collect::<Vec<_>>(),
+            ["red", "cyan", "blue", "green"],
+        );
+        for (word, code) in words.iter().zip(codes.iter()) {
+            assert_eq!(archived.get(*code), Some(*word));
+            assert_eq!(archived.lookup(word), Some(*code));
+        }
+        assert_eq!(archived.get(4), None);
+        assert_eq!(archived.lookup("magenta"), None);
+        assert_eq!(archived.lookup(""), None);
+
+        let buf = to_bytes::<_, 256, Failure>(&Dict(&[])).unwrap();
+        let archived =
+            access::<ArchivedStringDict, Failure>(buf.as_ref()).unwrap();
+        assert!(archived.is_empty());
+        assert_eq!(archived.get(0), None);
+        assert_eq!(archived.lookup(""), None);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_invalid_string_dict() {
+        use rkyv::{
+            collections::string_dict::ArchivedStringDict, util::AlignedVec,
+        };
+
+        use self::string_dict::Dict;
+
+        let buf = to_bytes::<_, 256, Failure>(&Dict(&["b", "a", "b"])).unwrap();
+        access::<ArchivedStringDict, Failure>(buf.as_ref()).unwrap();
+
+        // The string bytes are written first, starting with "b"
+        let mut invalid_utf8 = AlignedVec::new();
+        invalid_utf8.extend_from_slice(buf.as_ref());
+        invalid_utf8[0] = 0xff;
+        assert!(access::<ArchivedStringDict, Failure>(&invalid_utf8).is_err());
+
+        let mut unsorted = AlignedVec::new();
+        unsorted.extend_from_slice(buf.as_ref());
+        unsorted[0] = b'0';
+        assert!(access::<ArchivedStringDict, Failure>(&unsorted).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_exhaustive() {
+        use rkyv::{
+            access_unchecked, util::AlignedVec,
+            validation::util::access_exhaustive,
+        };
+
+        const BAD: [usize; 3] = [17, 500, 999];
+
+        let value = (0..1000)
+            .map(|i| format!("out-of-line string number {}", i))
+            .collect::<Vec<_>>();
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(buf.as_ref());
+        let archived =
+            unsafe { access_unchecked::<Archived<Vec<String>>>(&bytes) };
+        let offsets = BAD.map(|i| {
+            archived[i].as_str().as_ptr() as usize - bytes.as_ptr() as usize
+        });
+        for offset in offsets {
+            bytes[offset] = 0xff;
+        }
+
+        assert!(access::<Archived<Vec<String>>, Failure>(&bytes).is_err());
+
+        let (archived, report) = unsafe {
+            access_exhaustive::<Archived<Vec<String>>, Failure>(&bytes, 10)
+        }
+        .unwrap();
+        let indices = report.errors().iter().map(|e| e.index());
+        assert_eq!(indices.collect::<Vec<_>>(), BAD);
+        assert_eq!(archived.iter_valid(&report).count(), 997);
+        let expected = value
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !BAD.contains(i))
+            .map(|(_, v)| v);
+        assert!(archived.iter_valid(&report).eq(expected));
+
+        // Reports which fill up stop validation
+        assert!(unsafe {
+            access_exhaustive::<Archived<Vec<String>>, Failure>(&bytes, 2)
+        }
+        .is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_exhaustive_btree_map() {
+        use std::collections::BTreeMap;
+
+        use rkyv::{
+            access_unchecked, util::AlignedVec,
+            validation::util::access_exhaustive,
+        };
+
+        const BAD: u32 = 57;
+
+        let value = (0..200)
+            .map(|i| (i, format!("out-of-line string number {}", i)))
+            .collect::<BTreeMap<u32, String>>();
+        let buf = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let mut bytes = AlignedVec::new();
+        bytes.extend_from_slice(buf.as_ref());
+        let archived = unsafe {
+            access_unchecked::<Archived<BTreeMap<u32, String>>>(&bytes)
+        };
+        let offset = archived
+            .get(&Archived::<u32>::from_native(BAD))
+            .unwrap()
+            .as_str()
+            .as_ptr() as usize
+            - bytes.as_ptr() as usize;
+        bytes[offset] = 0xff;
+
+        assert!(
+            access::<Archived<BTreeMap<u32, String>>, Failure>(&bytes).is_err()
+        );
+
+        let (archived, report) = unsafe {
+            access_exhaustive::<Archived<BTreeMap<u32, String>>, Failure>(
+                &bytes, 10,
+            )
+        }
+        .unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report.errors()[0].index(), BAD as usize);
+        assert_eq!(archived.len(), 200);
+
+        let valid = archived
+            .iter()
+            .filter(|(key, _)| report.is_valid(*key))
+            .map(|(key, value)| (key.to_native(), value.as_str()));
+        let expected = value
+            .iter()
+            .filter(|(key, _)| **key != BAD)
+            .map(|(key, value)| (*key, value.as_str()));
+        assert!(valid.eq(expected));
+    }
+
+    // The report and the offsets written below assume 32-bit little-endian
+    // relative pointers
+    #[test]
+    #[cfg(not(any(
+        feature = "pointer_width_16",
+        feature = "pointer_width_64",
+        feature = "big_endian",
+    )))]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_audit() {
+        use rkyv::util::audit;
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Example {
+            name: String,
+            bytes: Vec<u8>,
+            // Boxing a vec audits a chain of relative pointers
+            #[allow(clippy::box_collection)]
+            nested: Box<Vec<u8>>,
+        }
+
+        let value = Example {
+            name: "a name which is stored out of line".to_string(),
+            bytes: vec![1, 2, 3, 4, 5],
+            nested: Box::new(vec![6, 7]),
+        };
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        let report = audit::<ArchivedExample, Failure>(&bytes);
+        assert!(report.is_clean());
+        assert_eq!(
+            report.to_string(),
+            "root at 0x0034\n\
+             0x0034 -> 0x0000 str (size 34, align 1)\n\
+             0x003c -> 0x0022 [u8] (size 5, align 1)\n\
+             0x0044 -> 0x002c rkyv::vec::ArchivedVec<u8> (size 8, align 4)\n\
+             0x002c -> 0x0027 [u8] (size 2, align 1)\n",
+        );
+
+        // Point the vec into the middle of the string
+        let source = report.entries()[1].source();
+        let offset = (1 - source as i32).to_le_bytes();
+        bytes[source..source + 4].copy_from_slice(&offset);
+
+        let report = audit::<ArchivedExample, Failure>(&bytes);
+        assert!(report.error().is_some());
+        let flagged = report.flagged().collect::<Vec<_>>();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].source(), source);
+        assert_eq!(flagged[0].target(), 1);
+        assert!(flagged[0].is_overlapping());
+        assert!(!flagged[0].is_out_of_bounds());
+
+        // Point the vec across the start of the root
+        let offset = (0x30 - source as i32).to_le_bytes();
+        bytes[source..source + 4].copy_from_slice(&offset);
+
+        let report = audit::<ArchivedExample, Failure>(&bytes);
+        assert!(report.error().is_some());
+        let last = report.entries().last().unwrap();
+        assert!(last.crosses_root());
+        assert!(last.is_overlapping());
+
+        // Point the vec past the end of the archive
+        let offset = 0x100i32.to_le_bytes();
+        bytes[source..source + 4].copy_from_slice(&offset);
+
+        let report = audit::<ArchivedExample, Failure>(&bytes);
+        assert!(report.error().is_some());
+        let last = report.entries().last().unwrap();
+        assert!(last.is_out_of_bounds());
+        assert_eq!(last.target(), source as isize + 0x100);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn redact_strings_in_place() {
+        use core::pin::Pin;
+
+        use rkyv::{access_mut, string::ArchivedString};
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct User {
+            id: u32,
+            name: String,
+            email: String,
+            notes: Vec<String>,
+        }
+
+        impl ArchivedUser {
+            fn name(self: Pin<&mut Self>) -> Pin<&mut ArchivedString> {
+                unsafe { self.map_unchecked_mut(|s| &mut s.name) }
+            }
+
+            fn email(self: Pin<&mut Self>) -> Pin<&mut ArchivedString> {
+                unsafe { self.map_unchecked_mut(|s| &mut s.email) }
+            }
+        }
+
+        let value = User {
+            id: 42,
+            // Short enough to be stored inline
+            name: "Ada".to_string(),
+            // Long enough to be stored out of line
+            email: "ada.lovelace@example.com".to_string(),
+            notes: vec!["first note".to_string(), "second note".to_string()],
+        };
+
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let original = bytes.to_vec();
+        let mut user = access_mut::<ArchivedUser, Failure>(&mut bytes).unwrap();
+
+        user.as_mut().name().fill_char('*').unwrap();
+        user.as_mut().email().edit_in_place(|s| {
+            let at = s.find('@').unwrap();
+            // SAFETY: Only ASCII bytes are overwritten with ASCII bytes.
+            unsafe { s[..at].as_bytes_mut().fill(b'*') };
+        });
+        user.as_mut().email().make_ascii_uppercase();
+
+        // Multibyte chars must evenly fill the string
+        assert!(user.as_mut().name().fill_char('é').is_err());
+        assert_eq!(user.name, "***");
+        user.as_mut().email().make_ascii_lowercase();
+
+        let changed = bytes
+            .iter()
+            .zip(original.iter())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert_eq!(changed, 3 + 12);
+
+        let user = from_bytes::<User, Failure>(&bytes).unwrap();
+        assert_eq!(
+            user,
+            User {
+                id: 42,
+                name: "***".to_string(),
+                email: "************@example.com".to_string(),
+                notes: value.notes.clone(),
+            }
+        );
+
+        // Two-byte chars fill strings with an even length
+        let mut bytes =
+            to_bytes::<_, 256, Failure>(&"abcdef".to_string()).unwrap();
+        let mut string =
+            access_mut::<ArchivedString, Failure>(&mut bytes).unwrap();
+        string.as_mut().fill_char('é').unwrap();
+        assert_eq!(*string, "ééé");
+        access::<ArchivedString, Failure>(&bytes).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn dispatch_archived_callables() {
+        use rkyv::{
+            callable::{ArchivedCallable, Callable, CallableBox},
+            callable_registry,
+            vec::ArchivedVec,
+        };
+
+        callable_registry! {
+            struct Rules: fn(u32) -> u32 = [Discount, Surcharge];
+        }
+
+        callable_registry! {
+            struct OtherRules: fn(u32) -> u32 = [Double];
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes, callable(registry = Rules, function = discount))]
+        struct Discount {
+            percent: u32,
+        }
+
+        fn discount(rule: &ArchivedDiscount, price: u32) -> u32 {
+            price - price * rule.percent.to_native() / 100
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(
+            check_bytes,
+            callable(registry = "Rules", function = "surcharge")
+        )]
+        struct Surcharge {
+            reason: String,
+            amount: u32,
+        }
+
+        fn surcharge(rule: &ArchivedSurcharge, price: u32) -> u32 {
+            price + rule.amount.to_native()
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(check_bytes, callable(registry = OtherRules, function = double))]
+        struct Double;
+
+        fn double(_: &ArchivedDouble, price: u32) -> u32 {
+            price * 2
+        }
+
+        assert_ne!(
+            <Discount as Callable<Rules>>::ID,
+            <Surcharge as Callable<Rules>>::ID,
+        );
+
+        let rules = vec![
+            CallableBox::<Rules>::new(Discount { percent: 20 }),
+            CallableBox::new(Surcharge {
+                reason: "shipping to a remote location".to_string(),
+                amount: 15,
+            }),
+            CallableBox::new(Discount { percent: 50 }),
+        ];
+        let bytes = to_bytes::<_, 256, Failure>(&rules).unwrap();
+        let archived =
+            access::<ArchivedVec<ArchivedCallable<Rules>>, Failure>(&bytes)
+                .unwrap();
+
+        assert_eq!(archived.len(), 3);
+        assert_eq!(archived[0].id(), <Discount as Callable<Rules>>::ID);
+        assert_eq!(archived[1].id(), <Surcharge as Callable<Rules>>::ID);
+        assert_eq!(archived[0].call(100), 80);
+        assert_eq!(archived[1].call(100), 115);
+        assert_eq!(
+            archived.iter().fold(200, |price, rule| rule.call(price)),
+            88,
+        );
+        let surcharge = archived[1].downcast_ref::<Surcharge>().unwrap();
+        assert_eq!(surcharge.reason, "shipping to a remote location");
+        assert!(archived[1].downcast_ref::<Discount>().is_none());
+
+        let deserialized =
+            from_bytes::<Vec<CallableBox<Rules>>, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized.len(), 3);
+        assert_eq!(
+            deserialized[2].downcast_ref::<Discount>(),
+            Some(&Discount { percent: 50 }),
+        );
+        assert_eq!(deserialized[1].id(), <Surcharge as Callable<Rules>>::ID,);
+
+        // Callables from a different registry have IDs which are unknown to
+        // `Rules` and are rejected during validation.
+        let other = vec![CallableBox::<OtherRules>::new(Double)];
+        let bytes = to_bytes::<_, 256, Failure>(&other).unwrap();
+        access::<ArchivedVec<ArchivedCallable<OtherRules>>, Failure>(&bytes)
+            .unwrap();
+        access::<ArchivedVec<ArchivedCallable<Rules>>, Failure>(&bytes)
+            .expect_err("unknown callable IDs should fail validation");
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn aligned_bytes_payloads() {
+        use rkyv::{
+            util::{serialize_into, AlignedVec},
+            with::AlignTo,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct PayloadFirst {
+            #[with(AlignTo<64>)]
+            payload: Vec<u8>,
+            id: u8,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct PayloadAfterString {
+            id: u8,
+            name: String,
+            #[with(AlignTo<64>)]
+            payload: Vec<u8>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct TwoPayloads {
+            #[with(AlignTo<32>)]
+            small: Box<[u8]>,
+            name: String,
+            #[with(AlignTo<64>)]
+            large: Vec<u8>,
+        }
+
+        fn check<T>(value: &T) -> AlignedVec
+        where
+            T: Serialize<Strategy<AlignedVec, Failure>>,
+            T::Archived:
+                for<'a> CheckBytes<Strategy<DefaultValidator, Failure>>,
+        {
+            let bytes = serialize_into::<_, _, Failure>(
+                value,
+                AlignedVec::with_alignment(64),
+            )
+            .unwrap();
+            assert_eq!(bytes.as_ptr().align_offset(64), 0);
+            access::<T::Archived, Failure>(&bytes).unwrap();
+            bytes
+        }
+
+        let payload = (0..100).collect::<Vec<u8>>();
+
+        let value = PayloadFirst {
+            payload: payload.clone(),
+            id: 1,
+        };
+        let bytes = check(&value);
+        let archived = access::<ArchivedPayloadFirst, Failure>(&bytes).unwrap();
+        assert_eq!(archived.payload.as_ptr().align_offset(64), 0);
+        assert_eq!(archived.payload.as_slice(), payload.as_slice());
+        let deserialized = from_bytes::<PayloadFirst, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized, value);
+
+        let value = PayloadAfterString {
+            id: 2,
+            name: "a name which is too long to inline".to_string(),
+            payload: payload.clone(),
+        };
+        let bytes = check(&value);
+        let archived =
+            access::<ArchivedPayloadAfterString, Failure>(&bytes).unwrap();
+        assert_eq!(archived.payload.as_ptr().align_offset(64), 0);
+        assert_eq!(archived.payload.as_slice(), payload.as_slice());
+
+        let value = TwoPayloads {
+            small: vec![1, 2, 3].into_boxed_slice(),
+            name: "another name that is stored out of line".to_string(),
+            large: payload.clone(),
+        };
+        let bytes = check(&value);
+        let archived = access::<ArchivedTwoPayloads, Failure>(&bytes).unwrap();
+        assert_eq!(archived.small.as_ptr().align_offset(32), 0);
+        assert_eq!(archived.large.as_ptr().align_offset(64), 0);
+        let deserialized = from_bytes::<TwoPayloads, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized, value);
+
+        // Payloads are aligned relative to the start of the archive even if
+        // the buffer itself has a smaller alignment.
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<ArchivedTwoPayloads, Failure>(&bytes).unwrap();
+        let offset = archived.large.as_ptr() as usize - bytes.as_ptr() as usize;
+        assert_eq!(offset % 64, 0);
+
+        // Bytes which were not written with `AlignTo` fail validation
+        // when they are not aligned.
+        #[derive(Archive, Serialize)]
+        struct Unaligned {
+            id: u8,
+            name: String,
+            #[with(rkyv::with::CopyOptimize)]
+            payload: Vec<u8>,
+        }
+
+        let bytes = serialize_into::<_, _, Failure>(
+            &Unaligned {
+                id: 3,
+                name: "a name which is too long to inline".to_string(),
+                payload,
+            },
+            AlignedVec::with_alignment(64),
+        )
+        .unwrap();
+        assert!(
+            access::<ArchivedPayloadAfterString, Failure>(&bytes).is_err(),
+            "unaligned payloads should fail validation",
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn canonical_floats() {
+        use core::cmp::Ordering;
+
+        use rkyv::{
+            primitive::{ArchivedF32, ArchivedF64, ArchivedFloat},
+            with::CanonicalFloat,
+        };
+
+        // The fields of both structs must be laid out in the same order so
+        // that plain archives can be accessed as canonical ones
+        #[derive(Archive, Serialize, Deserialize, Debug)]
+        #[archive(check_bytes, preserve_order)]
+        struct Plain {
+            a: f32,
+            b: f64,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug)]
+        #[archive(check_bytes, preserve_order)]
+        struct Canonical {
+            #[with(CanonicalFloat)]
+            a: f32,
+            #[with(CanonicalFloat)]
+            b: f64,
+        }
+
+        // A signaling NaN, a negative NaN, and a NaN with a payload
+        let nans_32 = [
+            f32::NAN,
+            f32::from_bits(0x7f80_0001),
+            -f32::NAN,
+            f32::from_bits(0x7fc0_1234),
+        ];
+        let nans_64 = [
+            f64::NAN,
+            f64::from_bits(0x7ff0_0000_0000_0001),
+            -f64::NAN,
+            f64::from_bits(0x7ff8_0000_dead_beef),
+        ];
+
+        let plain =
+            |a, b| to_bytes::<_, 256, Failure>(&Plain { a, b }).unwrap();
+        let canonical =
+            |a, b| to_bytes::<_, 256, Failure>(&Canonical { a, b }).unwrap();
+
+        // Without the wrapper, NaN payloads and signed zeros are preserved
+        let bytes = plain(nans_32[0], nans_64[0]);
+        for (a, b) in nans_32.into_iter().zip(nans_64).skip(1) {
+            assert_ne!(bytes.as_slice(), plain(a, b).as_slice());
+        }
+        assert_ne!(plain(0.0, 0.0).as_slice(), plain(-0.0, -0.0).as_slice());
+
+        let bytes = plain(-0.0, nans_64[1]);
+        let archived = access::<ArchivedPlain, Failure>(&bytes).unwrap();
+        assert_eq!(archived.a, 0.0);
+        assert_eq!(archived.a.to_bits(), 0x8000_0000);
+        assert!(archived.b.is_nan());
+        assert!(!archived.b.is_finite());
+        assert!(!archived.b.is_canonical());
+        assert_eq!(archived.b.to_bits(), 0x7ff0_0000_0000_0001);
+
+        // With the wrapper, all NaNs and zeros are byte-identical
+        let bytes = canonical(nans_32[0], nans_64[0]);
+        for (a, b) in nans_32.into_iter().zip(nans_64) {
+            assert_eq!(bytes.as_slice(), canonical(a, b).as_slice());
+        }
+        assert_eq!(
+            canonical(0.0, 0.0).as_slice(),
+            canonical(-0.0, -0.0).as_slice(),
+        );
+
+        let archived = access::<ArchivedCanonical, Failure>(&bytes).unwrap();
+        assert!(archived.a.is_nan());
+        assert_eq!(archived.a.to_bits(), f32::NAN.to_bits());
+        assert_eq!(archived.b.to_bits(), f64::NAN.to_bits());
+        let other = canonical(nans_32[2], nans_64[3]);
+        let other = access::<ArchivedCanonical, Failure>(&other).unwrap();
+        assert_eq!(archived.a, other.a);
+        assert_eq!(archived.b, other.b);
+
+        let deserialized =
+            from_bytes::<Canonical, Failure>(&canonical(-0.0, 1.5)).unwrap();
+        assert_eq!(deserialized.a.to_bits(), 0);
+        assert_eq!(deserialized.b, 1.5);
+
+        // Non-canonical floats fail validation as canonical floats
+        for (a, b) in [(-0.0, 0.0), (0.0, -0.0), (nans_32[1], 0.0)] {
+            let bytes = plain(a, b);
+            access::<ArchivedPlain, Failure>(&bytes).unwrap();
+            assert!(access::<ArchivedCanonical, Failure>(&bytes).is_err());
+        }
+        access::<ArchivedCanonical, Failure>(&plain(f32::NAN, 2.0)).unwrap();
+
+        // Total ordering
+        let values = [-f32::NAN, -1.0, -0.0, 0.0, 1.0, f32::NAN];
+        for (i, a) in values.into_iter().enumerate() {
+            for (j, b) in values.into_iter().enumerate() {
+                let (a, b) =
+                    (ArchivedF32::from_native(a), ArchivedF32::from_native(b));
+                assert_eq!(a.total_cmp(&b), i.cmp(&j));
+            }
+        }
+        assert_eq!(
+            ArchivedF64::from_native(f64::NAN)
+                .total_cmp(&ArchivedF64::from_native(f64::INFINITY)),
+            Ordering::Greater,
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn limited_deserialization() {
+        use rkyv::{
+            de::{AllocationLimiter, Limited, Limits},
+            from_bytes_limited,
+            rancor::BoxedError,
+            vec::ArchivedVec,
+            with::Skip,
+        };
+
+        // Archived as a zero-sized type, but much larger when deserialized
+        #[derive(Archive, Serialize, Deserialize, Clone, Debug, Default)]
+        #[archive(check_bytes)]
+        struct Padding {
+            #[with(Skip)]
+            _data: [u64; 32],
+        }
+
+        let mut bytes =
+            to_bytes::<_, 256, Failure>(&vec![Padding::default(); 2]).unwrap();
+        // Claim that the vec has 2^22 elements, which would be 1 GiB once
+        // deserialized
+        let pos = bytes.len() - size_of::<Archived<usize>>();
+        unsafe {
+            bytes
+                .as_mut_ptr()
+                .add(pos)
+                .cast::<Archived<usize>>()
+                .write_unaligned(Archived::<usize>::from_native(1 << 22));
+        }
+        let archived =
+            access::<ArchivedVec<ArchivedPadding>, Failure>(&bytes).unwrap();
+        assert_eq!(archived.len(), 1 << 22);
+
+        let limits = Limits {
+            max_bytes: 1 << 20,
+            ..Limits::default()
+        };
+        let error =
+            from_bytes_limited::<Vec<Padding>, BoxedError>(&bytes, limits)
+                .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("deserialization total bytes limit exceeded"));
+
+        let limits = Limits {
+            max_len: 1000,
+            ..Limits::default()
+        };
+        let error =
+            from_bytes_limited::<Vec<Padding>, BoxedError>(&bytes, limits)
+                .unwrap_err();
+        assert!(error.to_string().contains(
+            "deserialization collection length limit exceeded: requested"
+        ));
+
+        // Nested collections count toward the totals
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Document {
+            title: String,
+            tags: BTreeMap<String, Vec<u32>>,
+            body: Box<[String]>,
+        }
+
+        let value = Document {
+            title: "limits".to_string(),
+            tags: [
+                ("a".to_string(), vec![1, 2, 3]),
+                ("b".to_string(), vec![4]),
+            ]
+            .into_iter()
+            .collect(),
+            body: vec!["first".to_string(), "second".to_string()]
+                .into_boxed_slice(),
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        // 6 + 1 + 1 + 5 + 6 string bytes, 2 map entries, 3 + 1 u32s, and 2
+        // boxed strings
+        let archived = access::<ArchivedDocument, Failure>(&bytes).unwrap();
+        let mut deserializer = Limited::new(Unify::new(), Limits::default());
+        let deserialized = rkyv::deserialize::<Document, _, Failure>(
+            archived,
+            &mut deserializer,
+        )
+        .unwrap();
+        assert_eq!(deserialized, value);
+        assert_eq!(deserializer.allocated_elements(), 27);
+        let bytes_used = 19
+            + 2 * size_of::<(String, Vec<u32>)>()
+            + 4 * size_of::<u32>()
+            + 2 * size_of::<String>();
+        assert_eq!(deserializer.allocated_bytes(), bytes_used);
+
+        let exact = Limits {
+            max_bytes: bytes_used,
+            max_len: 6,
+            max_elements: 27,
+        };
+        assert_eq!(
+            from_bytes_limited::<Document, Failure>(&bytes, exact).unwrap(),
+            value,
+        );
+        for limits in [
+            Limits {
+                max_bytes: bytes_used - 1,
+                ..exact
+            },
+            Limits {
+                max_len: 5,
+                ..exact
+            },
+            Limits {
+                max_elements: 26,
+                ..exact
+            },
+        ] {
+            assert!(from_bytes_limited::<Document, Failure>(&bytes, limits)
+                .is_err());
+        }
+
+        // Deserializers which don't limit allocations only need an empty
+        // implementation
+        struct Unlimited;
+
+        impl<E> AllocationLimiter<E> for Unlimited {}
+
+        let deserialized =
+            rkyv::deserialize::<Document, _, Failure>(archived, &mut Unlimited)
+                .unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_matrix() {
+        use rkyv::{rancor::BoxedError, with::AsMatrix};
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Grid {
+            name: String,
+            #[with(AsMatrix)]
+            cells: Vec<Vec<u16>>,
+        }
+
+        let value = Grid {
+            name: "grid".to_string(),
+            cells: vec![vec![1, 2, 3], vec![4, 5, 6]],
+        };
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<ArchivedGrid, Failure>(&bytes).unwrap();
+        assert_eq!(archived.cells.shape(), (2, 3));
+        assert_eq!(archived.cells.len(), 6);
+        assert_eq!(archived.cells.get(1, 2).unwrap().to_native(), 6);
+        assert!(archived.cells.get(2, 0).is_none());
+        assert!(archived.cells.get(0, 3).is_none());
+        assert_eq!(archived.cells.row(1), &[4, 5, 6]);
+        assert_eq!(archived.cells.iter_rows().next_back().unwrap(), &[4, 5, 6]);
+        assert_eq!(archived.cells, value.cells);
+        assert_eq!(from_bytes::<Grid, Failure>(&bytes).unwrap(), value);
+
+        // Matrices with no columns still keep their rows
+        let empty = Grid {
+            name: "empty".to_string(),
+            cells: vec![Vec::new(); 3],
+        };
+        let empty_bytes = to_bytes::<_, 256, Failure>(&empty).unwrap();
+        let archived = access::<ArchivedGrid, Failure>(&empty_bytes).unwrap();
+        assert_eq!(archived.cells.shape(), (3, 0));
+        assert_eq!(archived.cells.iter_rows().count(), 3);
+        assert_eq!(from_bytes::<Grid, Failure>(&empty_bytes).unwrap(), empty);
+
+        // Ragged rows fail to serialize
+        let ragged = Grid {
+            name: "ragged".to_string(),
+            cells: vec![vec![1, 2], vec![3, 4], vec![5]],
+        };
+        let error = to_bytes::<_, 256, BoxedError>(&ragged).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("matrix row 2 has 1 columns but the first row has 2"));
+
+        // Shapes which don't match the number of elements fail validation
+        let archived = access::<ArchivedGrid, Failure>(&bytes).unwrap();
+        let rows =
+            &archived.cells as *const _ as usize - bytes.as_ptr() as usize;
+        unsafe {
+            bytes
+                .as_mut_ptr()
+                .add(rows)
+                .cast::<Archived<usize>>()
+                .write_unaligned(Archived::<usize>::from_native(3));
+        }
+        assert!(access::<ArchivedGrid, Failure>(&bytes).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_dense_strings() {
+        use rkyv::{vec::ArchivedDenseStrings, with::DenseStrings};
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Dense {
+            #[with(DenseStrings)]
+            strings: Vec<String>,
+        }
+
+        type Plain = Archived<Vec<String>>;
+
+        let strings = vec![
+            "x".repeat(15) + "z",
+            "short".to_string(),
+            "y".repeat(16),
+            String::new(),
+            "non-ascii text: \u{e9}\u{1f980}".to_string(),
+        ];
+        let value = Dense {
+            strings: strings.clone(),
+        };
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+
+        // Dense strings have the same format as a plain vec of strings.
+        let plain_bytes = to_bytes::<_, 256, Failure>(&strings).unwrap();
+        assert_eq!(bytes.as_slice(), plain_bytes.as_slice());
+        let archived = access::<ArchivedDense, Failure>(&bytes).unwrap();
+        assert_eq!(archived.strings.as_slice(), strings.as_slice());
+        assert_eq!(from_bytes::<Dense, Failure>(&bytes).unwrap(), value);
+
+        // A character split across two strings is invalid even though the
+        // bytes of the strings together are valid UTF-8.
+        let adjacent = strings[0].clone() + &strings[2];
+        let pos = bytes
+            .windows(adjacent.len())
+            .position(|w| w == adjacent.as_bytes())
+            .unwrap();
+        bytes[pos + 15] = 0xc3;
+        bytes[pos + 16] = 0xa9;
+        assert!(access::<Plain, Failure>(&bytes).is_err());
+        assert!(access::<ArchivedDense, Failure>(&bytes).is_err());
+
+        // Differential check against validating each string on its own
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+        let pieces =
+            ["a", "bc", "\u{e9}", "\u{20ac}", "\u{1f980}", "0123456789"];
+        for _ in 0..2_000 {
+            let strings = (0..next(8))
+                .map(|_| {
+                    (0..next(6))
+                        .map(|_| pieces[next(pieces.len() as u64) as usize])
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+            let mut bytes = to_bytes::<_, 256, Failure>(&strings).unwrap();
+            for _ in 0..next(3) {
+                let i = next(bytes.len() as u64) as usize;
+                bytes[i] = next(256) as u8;
+            }
+
+            let plain = access::<Plain, Failure>(&bytes);
+            let dense = access::<ArchivedDenseStrings, Failure>(&bytes);
+            assert_eq!(plain.is_ok(), dense.is_ok(), "{:?}", strings);
+            if let (Ok(plain), Ok(dense)) = (plain, dense) {
+                assert_eq!(plain.as_slice(), dense.as_slice());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn niche_option() {
+        use core::num::{
+            NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8,
+            NonZeroU128, NonZeroU32, NonZeroU64, NonZeroU8,
+        };
+
+        use rkyv::{
+            niche::niche_option::ArchivedNicheOption, with::NicheOption,
+        };
+
+        #[derive(
+            Archive, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+        )]
+        #[archive(check_bytes, niche)]
+        enum Color {
+            Red,
+            Green,
+            Blue,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Niched {
+            #[with(NicheOption)]
+            i8: Option<NonZeroI8>,
+            #[with(NicheOption)]
+            i16: Option<NonZeroI16>,
+            #[with(NicheOption)]
+            i32: Option<NonZeroI32>,
+            #[with(NicheOption)]
+            i64: Option<NonZeroI64>,
+            #[with(NicheOption)]
+            i128: Option<NonZeroI128>,
+            #[with(NicheOption)]
+            u8: Option<NonZeroU8>,
+            #[with(NicheOption)]
+            u16: Option<NonZeroU16>,
+            #[with(NicheOption)]
+            u32: Option<NonZeroU32>,
+            #[with(NicheOption)]
+            u64: Option<NonZeroU64>,
+            #[with(NicheOption)]
+            u128: Option<NonZeroU128>,
+            #[with(NicheOption)]
+            bool: Option<bool>,
+            #[with(NicheOption)]
+            color: Option<Color>,
+        }
+
+        assert_eq!(size_of::<ArchivedNicheOption<Archived<NonZeroU64>>>(), 8);
+        assert_eq!(
+            size_of::<ArchivedNicheOption<Archived<NonZeroU64>>>(),
+            size_of::<Archived<NonZeroU64>>()
+        );
+        assert_eq!(size_of::<ArchivedNicheOption<Archived<NonZeroU32>>>(), 4);
+        assert_eq!(size_of::<ArchivedNicheOption<Archived<NonZeroI8>>>(), 1);
+        assert_eq!(size_of::<ArchivedNicheOption<Archived<bool>>>(), 1);
+        assert_eq!(size_of::<ArchivedNicheOption<ArchivedColor>>(), 1);
+        assert!(size_of::<Archived<Option<NonZeroU64>>>() > 8);
+
+        fn check(value: &Niched) {
+            let bytes = to_bytes::<_, 256, Failure>(value).unwrap();
+            let archived = access::<ArchivedNiched, Failure>(&bytes).unwrap();
+            assert_eq!(archived.i8, value.i8);
+            assert_eq!(archived.u8, value.u8);
+            assert_eq!(archived.bool, value.bool);
+            assert_eq!(archived.u64.is_some(), value.u64.is_some());
+            assert_eq!(
+                archived.u64.as_ref().map(|x| x.get()),
+                value.u64.map(|x| x.get())
+            );
+            assert_eq!(archived.color.is_none(), value.color.is_none());
+            assert_eq!(from_bytes::<Niched, Failure>(&bytes).unwrap(), *value);
+        }
+
+        check(&Niched {
+            i8: None,
+            i16: None,
+            i32: None,
+            i64: None,
+            i128: None,
+            u8: None,
+            u16: None,
+            u32: None,
+            u64: None,
+            u128: None,
+            bool: None,
+            color: None,
+        });
+        for (i, color) in [Color::Red, Color::Green, Color::Blue]
+            .into_iter()
+            .enumerate()
+        {
+            check(&Niched {
+                i8: NonZeroI8::new(i as i8 - 1),
+                i16: NonZeroI16::new(i16::MIN),
+                i32: NonZeroI32::new(-1),
+                i64: NonZeroI64::new(i64::MAX),
+                i128: NonZeroI128::new(1 << 100),
+                u8: NonZeroU8::new(u8::MAX),
+                u16: NonZeroU16::new(1),
+                u32: NonZeroU32::new(u32::MAX),
+                u64: NonZeroU64::new(1 << 40),
+                u128: NonZeroU128::new(u128::MAX),
+                bool: Some(i % 2 == 0),
+                color: Some(color),
+            });
+        }
+
+        // The niche validates as `None`, but other invalid values are
+        // rejected.
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Single {
+            #[with(NicheOption)]
+            bool: Option<bool>,
+            #[with(NicheOption)]
+            color: Option<Color>,
+        }
+
+        let mut bytes = to_bytes::<_, 256, Failure>(&Single {
+            bool: Some(true),
+            color: Some(Color::Blue),
+        })
+        .unwrap();
+        assert_eq!(bytes[..], [1, 2]);
+        bytes[0] = 2;
+        bytes[1] = 3;
+        let archived = access::<ArchivedSingle, Failure>(&bytes).unwrap();
+        assert!(archived.bool.is_none());
+        assert!(archived.color.is_none());
+        bytes[0] = 3;
+        assert!(access::<ArchivedSingle, Failure>(&bytes).is_err());
+        bytes[0] = 0;
+        bytes[1] = 4;
+        assert!(access::<ArchivedSingle, Failure>(&bytes).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn run_length() {
+        use rkyv::{
+            primitive::{ArchivedU32, ArchivedUsize},
+            vec::ArchivedRun,
+            with::RunLength,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Column {
+            #[with(RunLength)]
+            values: Vec<u32>,
+        }
+
+        fn check(values: Vec<u32>, runs: usize) -> usize {
+            let value = Column { values };
+            let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+            let archived = access::<ArchivedColumn, Failure>(&bytes).unwrap();
+            assert_eq!(archived.values.len(), value.values.len());
+            assert_eq!(archived.values.runs().len(), runs);
+            assert_eq!(archived.values.iter().len(), value.values.len());
+            assert_eq!(archived.values, *value.values.as_slice());
+            for (i, x) in value.values.iter().enumerate() {
+                assert_eq!(*archived.values.get(i).unwrap(), *x);
+            }
+            assert!(archived.values.get(value.values.len()).is_none());
+            assert_eq!(from_bytes::<Column, Failure>(&bytes).unwrap(), value);
+            bytes.len()
+        }
+
+        check(Vec::new(), 0);
+        check(vec![5], 1);
+        let runs = [vec![1; 5000], vec![2; 3], vec![1; 5000], vec![0; 1]];
+        let size = check(runs.concat(), 4);
+        let run_size = size_of::<ArchivedRun<ArchivedU32>>();
+        assert_eq!(size, 4 * run_size + size_of::<ArchivedColumn>());
+
+        // No two adjacent values are equal, so every run has one element.
+        let alternating = (0..1000).map(|i| i % 2).collect::<Vec<_>>();
+        let size = check(alternating.clone(), 1000);
+        let plain = to_bytes::<_, 256, Failure>(&alternating).unwrap();
+        assert!(size > plain.len());
+
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = move |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+        for _ in 0..100 {
+            let mut values = Vec::new();
+            let mut runs = 0;
+            for _ in 0..next(20) {
+                let value = next(4) as u32;
+                if values.last() != Some(&value) {
+                    runs += 1;
+                }
+                let count = next(50) as usize + 1;
+                values.extend(core::iter::repeat_n(value, count));
+            }
+            check(values, runs);
+        }
+
+        // Run lengths must add up to the length.
+        let value = Column {
+            values: [vec![3; 10], vec![4; 20]].concat(),
+        };
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<ArchivedColumn, Failure>(&bytes).unwrap();
+        let count_pos = &archived.values.runs()[1] as *const _ as usize
+            - bytes.as_ptr() as usize
+            + 4;
+        for count in [19, 21, usize::MAX] {
+            let count = ArchivedUsize::from_native(count as _);
+            unsafe {
+                bytes
+                    .as_mut_ptr()
+                    .add(count_pos)
+                    .cast::<ArchivedUsize>()
+                    .write_unaligned(count);
+            }
+            assert!(access::<ArchivedColumn, Failure>(&bytes).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn packed_ints() {
+        use rkyv::{
+            primitive::ArchivedUsize,
+            vec::PACKED_BLOCK_LEN,
+            with::{DeltaVarint, Varint},
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Columns {
+            #[with(Varint)]
+            unsigned: Vec<u64>,
+            #[with(Varint)]
+            signed: Vec<i64>,
+            #[with(DeltaVarint)]
+            delta_unsigned: Vec<u64>,
+            #[with(DeltaVarint)]
+            delta_signed: Vec<i64>,
+        }
+
+        fn columns(unsigned: Vec<u64>) -> Columns {
+            let signed = unsigned
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| {
+                    if i % 3 == 0 {
+                        (x as i64).wrapping_neg()
+                    } else {
+                        x as i64
+                    }
+                })
+                .collect();
+            Columns {
+                delta_unsigned: unsigned.clone(),
+                delta_signed: Vec::clone(&signed),
+                signed,
+                unsigned,
+            }
+        }
+
+        fn check(value: &Columns) -> rkyv::util::AlignedVec {
+            let bytes = to_bytes::<_, 256, Failure>(value).unwrap();
+            let archived = access::<ArchivedColumns, Failure>(&bytes).unwrap();
+            assert_eq!(archived.unsigned, *value.unsigned.as_slice());
+            assert_eq!(archived.signed, *value.signed.as_slice());
+            assert_eq!(
+                archived.delta_unsigned,
+                *value.delta_unsigned.as_slice()
+            );
+            assert_eq!(archived.delta_signed, *value.delta_signed.as_slice());
+            let len = value.unsigned.len();
+            assert_eq!(
+                archived.unsigned.blocks().len(),
+                len.div_ceil(PACKED_BLOCK_LEN)
+            );
+            macro_rules! check_get {
+                ($($field:ident),*) => {$(
+                    for (i, x) in value.$field.iter().enumerate() {
+                        assert_eq!(archived.$field.get(i), Some(*x));
+                    }
+                    let len = value.$field.len();
+                    assert_eq!(archived.$field.get(len), None);
+                )*};
+            }
+            check_get!(unsigned, signed, delta_unsigned, delta_signed);
+            assert_eq!(from_bytes::<Columns, Failure>(&bytes).unwrap(), *value);
+            bytes
+        }
+
+        for len in [0, 1, 63, 64, 65, 128, 200] {
+            check(&columns((0..len).collect()));
+        }
+
+        // Extreme values take up ten bytes each, and alternate between the
+        // largest positive and negative deltas.
+        let extremes = [u64::MAX, 0, u64::MAX, 1 << 63, (1 << 63) - 1, 0];
+        let value =
+            columns(extremes.iter().copied().cycle().take(150).collect());
+        check(&value);
+        let value = Columns {
+            signed: vec![i64::MIN, i64::MAX, -1, 0, 1, i64::MIN],
+            delta_signed: vec![i64::MIN, i64::MAX, -1, 0, 1, i64::MIN],
+            ..columns(vec![u64::MAX; 6])
+        };
+        check(&value);
+
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..20 {
+            let bits = next() % 64;
+            let len = (next() % 300) as usize;
+            check(&columns((0..len).map(|_| next() >> bits).collect()));
+        }
+
+        // Small values and close timestamps take up much less space than
+        // plain vectors.
+        let small = (0..10_000u64).map(|i| i % 100).collect::<Vec<_>>();
+        let timestamps = (0..10_000u64)
+            .map(|i| 1_700_000_000_000 + i * 1000 + i % 7)
+            .collect::<Vec<_>>();
+        let value = Columns {
+            unsigned: small.clone(),
+            signed: Vec::new(),
+            delta_unsigned: timestamps.clone(),
+            delta_signed: Vec::new(),
+        };
+        let bytes = check(&value);
+        let archived = access::<ArchivedColumns, Failure>(&bytes).unwrap();
+        assert_eq!(archived.unsigned.encoded_bytes().len(), 10_000);
+        assert!(archived.delta_unsigned.encoded_bytes().len() <= 20_010);
+        let plain = to_bytes::<_, 256, Failure>(&(small, timestamps)).unwrap();
+        assert!(bytes.len() * 3 < plain.len());
+
+        // Varint streams must end in bounds and match the skip index.
+        let value = columns((0..100).map(|i| i * 1000).collect());
+        let bytes = check(&value);
+        let archived = access::<ArchivedColumns, Failure>(&bytes).unwrap();
+        let offset_of = |ptr: *const u8| ptr as usize - bytes.as_ptr() as usize;
+        let last_byte = offset_of(archived.unsigned.encoded_bytes().as_ptr())
+            + archived.unsigned.encoded_bytes().len()
+            - 1;
+        let block_offset =
+            offset_of(&archived.signed.blocks()[1] as *const _ as *const u8);
+
+        let mut corrupt = bytes.clone();
+        corrupt[last_byte] |= 0x80;
+        assert!(access::<ArchivedColumns, Failure>(&corrupt).is_err());
+
+        let mut corrupt = bytes.clone();
+        let offset = archived.signed.blocks()[1].offset() + 1;
+        unsafe {
+            corrupt
+                .as_mut_ptr()
+                .add(block_offset)
+                .cast::<ArchivedUsize>()
+                .write_unaligned(ArchivedUsize::from_native(offset as _));
+        }
+        assert!(access::<ArchivedColumns, Failure>(&corrupt).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn unsized_root() {
+        use core::{
+            alloc::{Layout, LayoutError},
+            mem::align_of,
+            ptr::addr_of,
+        };
+
+        use rkyv::{
+            bytecheck::rancor::{Fallible, Trace},
+            primitive::ArchivedUsize,
+            ptr_meta::{self, Pointee},
+            ser::WriterExt as _,
+            util::{access_unsized_unchecked, to_bytes_unsized},
+            validation::{util::access_unsized, LayoutRaw},
+            ArchivePointee, ArchiveUnsized, ArchivedMetadata, Portable,
+            SerializeUnsized,
+        };
+
+        // A user-defined unsized type with a header and a trailing slice.
+        #[derive(ptr_meta::Pointee)]
+        #[repr(C)]
+        struct Block<H, T> {
+            head: H,
+            tail: [T],
+        }
+
+        unsafe impl<H: Portable, T: Portable> Portable for Block<H, T> {}
+
+        impl<H, T> ArchivePointee for Block<H, T> {
+            type ArchivedMetadata = ArchivedUsize;
+
+            fn pointer_metadata(
+                archived: &Self::ArchivedMetadata,
+            ) -> <Self as Pointee>::Metadata {
+                archived.to_native() as usize
+            }
+        }
+
+        impl<H: Archive, T: Archive> ArchiveUnsized for Block<H, T> {
+            type Archived = Block<H::Archived, T::Archived>;
+
+            fn archived_metadata(&self) -> ArchivedMetadata<Self> {
+                ArchivedUsize::from_native(self.tail.len() as _)
+            }
+        }
+
+        impl<H, T, S> SerializeUnsized<S> for Block<H, T>
+        where
+            H: Serialize<S>,
+            T: Serialize<S>,
+            S: Fallible + Writer + ?Sized,
+        {
+            fn serialize_unsized(
+                &self,
+                serializer: &mut S,
+            ) -> Result<usize, S::Error> {
+                let head = self.head.serialize(serializer)?;
+                let tail = self
+                    .tail
+                    .iter()
+                    .map(|value| value.serialize(serializer))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let pos = serializer.align(
+                    align_of::<H::Archived>().max(align_of::<T::Archived>()),
+                )?;
+                unsafe {
+                    serializer.resolve_aligned(&self.head, head)?;
+                }
+                serializer.align_for::<T::Archived>()?;
+                for (value, resolver) in self.tail.iter().zip(tail) {
+                    unsafe {
+                        serializer.resolve_aligned(value, resolver)?;
+                    }
+                }
+                Ok(pos)
+            }
+        }
+
+        unsafe impl<H, T, C> CheckBytes<C> for Block<H, T>
+        where
+            H: CheckBytes<C>,
+            T: CheckBytes<C>,
+            C: Fallible + ?Sized,
+            C::Error: Trace,
+        {
+            unsafe fn check_bytes(
+                value: *const Self,
+                context: &mut C,
+            ) -> Result<(), C::Error> {
+                H::check_bytes(addr_of!((*value).head), context)?;
+                <[T]>::check_bytes(addr_of!((*value).tail), context)
+            }
+        }
+
+        impl<H, T> LayoutRaw for Block<H, T> {
+            fn layout_raw(
+                metadata: <Self as Pointee>::Metadata,
+            ) -> Result<Layout, LayoutError> {
+                let (layout, _) =
+                    Layout::new::<H>().extend(Layout::array::<T>(metadata)?)?;
+                Ok(layout.pad_to_align())
+            }
+        }
+
+        #[repr(C)]
+        struct SizedBlock<const N: usize> {
+            head: u32,
+            tail: [u16; N],
+        }
+
+        fn block<const N: usize>(value: &SizedBlock<N>) -> &Block<u32, u16> {
+            let ptr = (value as *const SizedBlock<N>).cast::<()>();
+            unsafe { &*ptr_meta::from_raw_parts(ptr, N) }
+        }
+
+        fn round_trip_str(value: &str) {
+            let bytes = to_bytes_unsized::<_, 256, Failure>(value).unwrap();
+            assert_eq!(access_unsized::<str, Failure>(&bytes).unwrap(), value);
+            let archived = unsafe { access_unsized_unchecked::<str>(&bytes) };
+            assert_eq!(archived, value);
+        }
+
+        fn round_trip_slice<T>(value: &[T])
+        where
+            T: Debug + Serialize<Strategy<AllocSerializer<256>, Failure>>,
+            T::Archived: Debug
+                + PartialEq<T>
+                + CheckBytes<Strategy<DefaultValidator, Failure>>,
+        {
+            let bytes = to_bytes_unsized::<_, 256, Failure>(value).unwrap();
+            let archived =
+                access_unsized::<[T::Archived], Failure>(&bytes).unwrap();
+            assert_eq!(archived, value);
+            let archived =
+                unsafe { access_unsized_unchecked::<[T::Archived]>(&bytes) };
+            assert_eq!(archived, value);
+        }
+
+        round_trip_str("");
+        round_trip_str("hello world");
+        round_trip_str(
+            &"a string which is longer than inline strings".repeat(8),
+        );
+        round_trip_slice::<u8>(&[]);
+        round_trip_slice::<u8>(&[1, 2, 3, 4, 5]);
+        round_trip_slice::<u64>(&[]);
+        round_trip_slice::<u64>(&[1, u64::MAX, 3]);
+        round_trip_slice(&["a".to_string(), "b".repeat(100)]);
+
+        let value = block(&SizedBlock {
+            head: 0x01020304,
+            tail: [1, 2, 3],
+        });
+        let bytes = to_bytes_unsized::<_, 256, Failure>(value).unwrap();
+        let archived = access_unsized::<
+            Block<Archived<u32>, Archived<u16>>,
+            Failure,
+        >(&bytes)
+        .unwrap();
+        assert_eq!(archived.head, 0x01020304);
+        assert_eq!(archived.tail, [1, 2, 3]);
+
+        let value = block(&SizedBlock { head: 7, tail: [] });
+        let bytes = to_bytes_unsized::<_, 256, Failure>(value).unwrap();
+        let archived = access_unsized::<
+            Block<Archived<u32>, Archived<u16>>,
+            Failure,
+        >(&bytes)
+        .unwrap();
+        assert_eq!(archived.head, 7);
+        assert!(archived.tail.is_empty());
+
+        // The root stores the length of the value, which must be in bounds.
+        let values: &[u64] = &[1, 2, 3];
+        let mut bytes = to_bytes_unsized::<_, 256, Failure>(values).unwrap();
+        let len = bytes.len();
+        unsafe {
+            bytes
+                .as_mut_ptr()
+                .add(len - size_of::<ArchivedUsize>())
+                .cast::<ArchivedUsize>()
+                .write_unaligned(ArchivedUsize::from_native(4));
+        }
+        assert!(access_unsized::<[Archived<u64>], Failure>(&bytes).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn validated_invariants() {
+        use core::num::NonZeroU32;
+
+        use rkyv::invariant::{as_native, as_native_slice};
+
+        fn check<T>(bytes: [u8; 4]) -> Option<&'static T>
+        where
+            T: rkyv::Portable + CheckBytes<Strategy<DefaultValidator, Failure>>,
+        {
+            let bytes: &'static AlignedBytes<4> =
+                Box::leak(Box::new(AlignedBytes(bytes)));
+            access_pos::<T, Failure>(bytes.as_ref(), 0).ok()
+        }
+
+        fn word(value: u32) -> [u8; 4] {
+            #[cfg(feature = "big_endian")]
+            let bytes = value.to_be_bytes();
+            #[cfg(not(feature = "big_endian"))]
+            let bytes = value.to_le_bytes();
+            bytes
+        }
+
+        // Every byte other than 0 and 1 is an invalid bool
+        for byte in 0..=255u8 {
+            let checked = check::<bool>([byte, 0, 0, 0]);
+            assert_eq!(checked.is_some(), byte <= 1, "bool {:#04x}", byte);
+            if let Some(checked) = checked {
+                assert_eq!(*as_native(checked), byte == 1);
+            }
+        }
+
+        // Surrogates and code points past the last char are invalid chars
+        for code in [
+            0xd800,
+            0xdbff,
+            0xdc00,
+            0xdfff,
+            0x11_0000,
+            0x7fff_ffff,
+            0x8000_0000,
+            u32::MAX,
+        ] {
+            assert!(
+                check::<Archived<char>>(word(code)).is_none(),
+                "char {:#x}",
+                code,
+            );
+        }
+        for c in ['\0', 'a', '\u{d7ff}', '\u{e000}', char::MAX] {
+            let checked = check::<Archived<char>>(word(c as u32)).unwrap();
+            assert_eq!(checked.to_native(), c);
+            #[cfg(all(not(feature = "big_endian"), target_endian = "little"))]
+            assert_eq!(*as_native(checked), c);
+        }
+
+        // Zero is an invalid NonZero
+        assert!(check::<Archived<NonZeroU32>>(word(0)).is_none());
+        for value in [1, 0x100, 0x8000_0000, u32::MAX] {
+            let checked = check::<Archived<NonZeroU32>>(word(value)).unwrap();
+            assert_eq!(checked.to_native().get(), value);
+        }
+
+        // Enum tags past the last variant are invalid
+        #[derive(Archive, Serialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        #[archive_attr(derive(Debug, PartialEq))]
+        enum Level {
+            Low,
+            Mid,
+            High,
+        }
+
+        for level in [Level::Low, Level::Mid, Level::High] {
+            serialize_and_check::<_, Failure>(&level);
+        }
+        for tag in 0..=255u8 {
+            let checked = check::<ArchivedLevel>([tag, 0, 0, 0]);
+            assert_eq!(checked.is_some(), tag < 3, "tag {}", tag);
+        }
+        assert_eq!(
+            check::<ArchivedLevel>([2, 0, 0, 0]),
+            Some(&ArchivedLevel::High),
+        );
+
+        // A single invalid element invalidates a slice
+        let value = vec![true, false, true, true];
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access::<Archived<Vec<bool>>, Failure>(&bytes).unwrap();
+        assert_eq!(as_native_slice(archived.as_slice()), value.as_slice());
+        bytes[2] = 2;
+        access::<Archived<Vec<bool>>, Failure>(&bytes)
+            .expect_err("expected invalid bool error");
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn stream_forward_only() {
+        use rkyv::{
+            util::to_bytes_forward_only,
+            validation::{
+                stream::{StreamStatus, StreamValidator},
+                util::access_forward_only,
+            },
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        #[archive_attr(derive(Debug))]
+        struct Record {
+            id: u32,
+            name: String,
+            tags: Vec<String>,
+            scores: Vec<u32>,
+        }
+
+        const RECORDS: u32 = 2_000;
+        const CHUNK: usize = 61;
+
+        let records = (0..RECORDS)
+            .map(|id| Record {
+                id,
+                name: format!("record number {} with an out of line name", id),
+                tags: (0..id % 4)
+                    .map(|i| format!("tag {} of record number {}", i, id))
+                    .collect(),
+                scores: (0..id % 7).map(|i| id * i).collect(),
+            })
+            .collect::<Vec<_>>();
+        let bytes = to_bytes_forward_only::<_, 256, Failure>(&records).unwrap();
+
+        // Streaming in small chunks completes with the last chunk
+        let mut validator = StreamValidator::<ArchivedRecord>::new();
+        let chunks = bytes.chunks(CHUNK).collect::<Vec<_>>();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let status = validator.push::<Failure>(chunk).unwrap();
+            if i + 1 < chunks.len() {
+                assert_eq!(status, StreamStatus::Pending);
+            } else {
+                assert!(matches!(status, StreamStatus::Complete(_)));
+            }
+        }
+        assert_eq!(validator.validated(), RECORDS as usize);
+        assert_eq!(validator.expected_len(), Some(bytes.len()));
+        let archived = validator.archive().unwrap();
+        assert_eq!(archived.len(), records.len());
+        assert_eq!(archived[1234].name, records[1234].name);
+        assert_eq!(archived[1234].tags[1], records[1234].tags[1]);
+
+        // Records are validated as their bytes arrive
+        let mut validator = StreamValidator::<ArchivedRecord>::new();
+        for chunk in bytes[..bytes.len() / 2].chunks(CHUNK) {
+            validator.push::<Failure>(chunk).unwrap();
+        }
+        assert!(validator.validated() > 0);
+        assert!(validator.validated() < RECORDS as usize);
+        validator
+            .finish::<Failure>()
+            .expect_err("expected incomplete archive error");
+
+        // Corruption in an early record is rejected before the rest arrives
+        let name = records[0].name.as_bytes();
+        let name_pos = bytes
+            .windows(name.len())
+            .position(|window| window == name)
+            .unwrap();
+        let mut corrupted = bytes.clone();
+        corrupted[name_pos] = 0xff;
+        let mut validator = StreamValidator::<ArchivedRecord>::new();
+        let mut rejected_at = None;
+        for chunk in corrupted.chunks(CHUNK) {
+            if validator.push::<Failure>(chunk).is_err() {
+                rejected_at = Some(validator.received());
+                break;
+            }
+        }
+        let rejected_at = rejected_at.expect("expected invalid UTF-8 error");
+        assert!(rejected_at < bytes.len() / 100);
+        access_forward_only::<ArchivedRecord, Failure>(&corrupted)
+            .expect_err("expected invalid UTF-8 error");
+
+        // Readers are not read past the end of the archive
+        #[cfg(feature = "std")]
+        {
+            let mut input = bytes.to_vec();
+            input.extend_from_slice(&[0xff; 16]);
+            let mut reader = std::io::Cursor::new(input);
+            let mut validator = StreamValidator::<ArchivedRecord>::new();
+            validator.read_from::<_, Failure>(&mut reader).unwrap();
+            assert_eq!(reader.position() as usize, bytes.len());
+            assert_eq!(validator.archive().unwrap().len(), records.len());
+        }
+
+        // Bytes past the end of the archive are rejected
+        let mut validator = StreamValidator::<ArchivedRecord>::new();
+        validator.push::<Failure>(&bytes).unwrap();
+        validator
+            .push::<Failure>(&[0])
+            .expect_err("expected trailing bytes error");
+
+        // Forward-only archives round-trip, including as regular archives
+        let records = records.into_iter().map(Box::new).collect::<Vec<_>>();
+        let archived =
+            access_forward_only::<ArchivedRecord, Failure>(&bytes).unwrap();
+        let deserialized = rkyv::deserialize::<Vec<Box<Record>>, _, Failure>(
+            archived,
+            &mut (),
+        )
+        .unwrap();
+        assert_eq!(deserialized, records);
+        let deserialized =
+            from_bytes::<Vec<Box<Record>>, Failure>(&bytes).unwrap();
+        assert_eq!(deserialized, records);
+
+        let empty = to_bytes_forward_only::<Record, 256, Failure>(&[]).unwrap();
+        let archived =
+            access_forward_only::<ArchivedRecord, Failure>(&empty).unwrap();
+        assert!(archived.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_string_inline_policy() {
+        use core::cmp::Ordering;
+
+        use rkyv::{
+            rancor::Fallible,
+            string::{ArchivedString, InlinePolicy, StringResolver},
+            SerializeUnsized,
+        };
+
+        const CAPACITY: usize = ArchivedString::INLINE_CAPACITY;
+
+        // A string which is archived with the given inline policy
+        struct Stored(String, InlinePolicy);
+
+        impl PartialEq for Stored {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for Stored {}
+
+        impl PartialOrd for Stored {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Stored {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        impl Archive for Stored {
+            type Archived = ArchivedString;
+            type Resolver = StringResolver;
+
+            unsafe fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+                out: *mut Self::Archived,
+            ) {
+                ArchivedString::resolve_from_str(&self.0, pos, resolver, out);
+            }
+        }
+
+        impl<S: Fallible + ?Sized> Serialize<S> for Stored
+        where
+            str: SerializeUnsized<S>,
+        {
+            fn serialize(
+                &self,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                ArchivedString::serialize_from_str_with_policy(
+                    &self.0, self.1, serializer,
+                )
+            }
+        }
+
+        fn text(len: usize) -> String {
+            (0..len).map(|i| (b'a' + (i % 26) as u8) as char).collect()
+        }
+
+        type Value = (Vec<Stored>, BTreeMap<Stored, u32>);
+
+        // The lengths around the inline capacity
+        let lens = [0, 1, CAPACITY - 1, CAPACITY, CAPACITY + 1, CAPACITY + 8];
+        let serialize = |policy| {
+            let value: Value = (
+                lens.iter().map(|&len| Stored(text(len), policy)).collect(),
+                lens.iter()
+                    .map(|&len| (Stored(text(len), policy), len as u32))
+                    .collect(),
+            );
+            to_bytes::<_, 256, Failure>(&value).unwrap()
+        };
+
+        let default_bytes = serialize(InlinePolicy::default());
+        let never_bytes = serialize(InlinePolicy::Never);
+        let up_to_bytes = serialize(InlinePolicy::UpTo(1));
+        let default =
+            access::<Archived<Value>, Failure>(&default_bytes).unwrap();
+        let never = access::<Archived<Value>, Failure>(&never_bytes).unwrap();
+        let up_to = access::<Archived<Value>, Failure>(&up_to_bytes).unwrap();
+
+        for (i, &len) in lens.iter().enumerate() {
+            let text = text(len);
+            let key = text.as_str();
+
+            assert_eq!(default.0[i].is_inline(), len <= CAPACITY);
+            assert_eq!(never.0[i].is_inline(), len == 0);
+            assert_eq!(up_to.0[i].is_inline(), len <= 1);
+
+            // The representation doesn't affect equality or lookups
+            for archived in [default, never, up_to] {
+                assert_eq!(archived.0[i], key);
+                assert_eq!(archived.0[i], default.0[i]);
+                assert_eq!(archived.0[i].cmp(&never.0[i]), Ordering::Equal);
+                assert_eq!(
+                    archived.1.get(key).unwrap().to_native(),
+                    len as u32
+                );
+            }
+        }
+
+        // Short out-of-line strings are still checked for valid UTF-8
+        let mut bytes = never_bytes.clone();
+        corrupt_str(&mut bytes, &text(CAPACITY - 1));
+        assert!(access::<Archived<Value>, Failure>(&bytes).is_err());
+
+        let mut bytes = default_bytes.clone();
+        corrupt_str(&mut bytes, &text(CAPACITY - 1));
+        assert!(access::<Archived<Value>, Failure>(&bytes).is_err());
     }
 }
@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod tests {
+    use core::any::TypeId;
+
+    use rkyv::{
+        rancor::{Failure, Fallible},
+        ser::{Allocator, CoreSerializer, Positional as _, Writer},
+        string::{ArchivedString, StringResolver},
+        util::serialize_into,
+        validation::{
+            util::access_with_context,
+            validators::{FixedSharedValidator, FixedValidator, SharedClaim},
+            SharedContext,
+        },
+        vec::{ArchivedVec, VecResolver},
+        Archive, Serialize, SerializeUnsized,
+    };
+    #[cfg(feature = "wasm")]
+    use wasm_bindgen_test::*;
+
+    // Stand-ins for `String` and `Vec` which can be serialized without an
+    // allocator.
+    struct Text(&'static str);
+
+    impl Archive for Text {
+        type Archived = ArchivedString;
+        type Resolver = StringResolver;
+
+        unsafe fn resolve(
+            &self,
+            pos: usize,
+            resolver: Self::Resolver,
+            out: *mut Self::Archived,
+        ) {
+            ArchivedString::resolve_from_str(self.0, pos, resolver, out);
+        }
+    }
+
+    impl<S: Fallible + ?Sized> Serialize<S> for Text
+    where
+        str: SerializeUnsized<S>,
+    {
+        fn serialize(
+            &self,
+            serializer: &mut S,
+        ) -> Result<Self::Resolver, S::Error> {
+            ArchivedString::serialize_from_str(self.0, serializer)
+        }
+    }
+
+    struct List<T: 'static>(&'static [T]);
+
+    impl<T: Archive> Archive for List<T> {
+        type Archived = ArchivedVec<T::Archived>;
+        type Resolver = VecResolver;
+
+        unsafe fn resolve(
+            &self,
+            pos: usize,
+            resolver: Self::Resolver,
+            out: *mut Self::Archived,
+        ) {
+            ArchivedVec::resolve_from_slice(self.0, pos, resolver, out);
+        }
+    }
+
+    impl<T, S> Serialize<S> for List<T>
+    where
+        T: Serialize<S>,
+        S: Fallible + Allocator + Writer + ?Sized,
+    {
+        fn serialize(
+            &self,
+            serializer: &mut S,
+        ) -> Result<Self::Resolver, S::Error> {
+            ArchivedVec::<T::Archived>::serialize_from_iter::<T, _, _>(
+                self.0.iter(),
+                serializer,
+            )
+        }
+    }
+
+    #[derive(Archive, Serialize)]
+    #[archive(check_bytes)]
+    struct Sensor {
+        name: Text,
+        samples: List<u16>,
+    }
+
+    #[derive(Archive, Serialize)]
+    #[archive(check_bytes)]
+    struct Device {
+        id: u32,
+        label: Text,
+        sensors: List<Sensor>,
+    }
+
+    static DEVICE: Device = Device {
+        id: 42,
+        label: Text("a device label which is stored out of line"),
+        sensors: List(&[
+            Sensor {
+                name: Text("temperature sensor"),
+                samples: List(&[20, 21, 23, 22]),
+            },
+            Sensor {
+                name: Text("pressure"),
+                samples: List(&[1013, 1012]),
+            },
+            Sensor {
+                name: Text("an idle sensor with no samples"),
+                samples: List(&[]),
+            },
+        ]),
+    };
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn fixed_validator() {
+        let serializer = serialize_into::<_, _, Failure>(
+            &DEVICE,
+            CoreSerializer::<1024, 256>::default(),
+        )
+        .unwrap();
+        let end = serializer.pos();
+        let buffer = serializer.into_writer().into_inner();
+        let bytes = &buffer[..end];
+
+        let mut claims = [SharedClaim::UNINIT; 16];
+        let mut validator = FixedValidator::new(bytes, &mut claims);
+        let archived = access_with_context::<ArchivedDevice, _, Failure>(
+            bytes,
+            &mut validator,
+        )
+        .unwrap();
+        assert!(validator.claims().is_empty());
+
+        assert_eq!(archived.id, 42);
+        assert_eq!(archived.label, DEVICE.label.0);
+        assert_eq!(archived.sensors.len(), 3);
+        for (archived, sensor) in archived.sensors.iter().zip(DEVICE.sensors.0)
+        {
+            assert_eq!(archived.name, sensor.name.0);
+            assert_eq!(archived.samples.as_slice(), sensor.samples.0);
+        }
+
+        // Archives without shared pointers don't need any claims
+        let mut validator = FixedValidator::new(bytes, &mut []);
+        access_with_context::<ArchivedDevice, _, Failure>(
+            bytes,
+            &mut validator,
+        )
+        .unwrap();
+
+        // Truncated archives still fail validation
+        let truncated = &buffer[..end - 4];
+        let mut validator = FixedValidator::new(truncated, &mut claims);
+        assert!(access_with_context::<ArchivedDevice, _, Failure>(
+            truncated,
+            &mut validator,
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn fixed_shared_validator() {
+        fn register(
+            validator: &mut FixedSharedValidator<'_>,
+            address: usize,
+            type_id: TypeId,
+        ) -> Result<bool, Failure> {
+            validator.register_shared_ptr(address, type_id)
+        }
+
+        let mut claims = [SharedClaim::UNINIT; 2];
+        let mut validator = FixedSharedValidator::new(&mut claims);
+        assert_eq!(validator.capacity(), 2);
+
+        let u32_id = TypeId::of::<u32>();
+        assert!(register(&mut validator, 16, u32_id).unwrap());
+        assert!(register(&mut validator, 32, u32_id).unwrap());
+        // Registering the same pointer again doesn't use another claim
+        assert!(!register(&mut validator, 16, u32_id).unwrap());
+        assert!(register(&mut validator, 16, TypeId::of::<u64>()).is_err());
+        assert!(register(&mut validator, 48, u32_id).is_err());
+
+        let addresses = validator.claims().iter().map(|c| c.address());
+        assert!(addresses.eq([16, 32]));
+    }
+}
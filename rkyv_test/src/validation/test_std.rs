@@ -27,4 +27,997 @@ mod tests {
         set.insert("baz".to_string());
         serialize_and_check::<_, Failure>(&set);
     }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn preserve_order_map_keys() {
+        use std::collections::BTreeMap;
+
+        use rkyv::{access, to_bytes, Archive, Archived, Serialize};
+
+        // Derived `Hash` and `Ord` on the archived key must visit its fields in
+        // the same order as the key, so its fields can't be reordered.
+        #[derive(
+            Archive,
+            Serialize,
+            Clone,
+            Copy,
+            Eq,
+            Hash,
+            PartialEq,
+            PartialOrd,
+            Ord,
+        )]
+        #[rkyv(check_bytes, preserve_order)]
+        #[archive_attr(derive(Eq, Hash, PartialEq, PartialOrd, Ord))]
+        struct Key {
+            a: u8,
+            b: u64,
+        }
+
+        let keys = (0..16u8)
+            .map(|i| Key {
+                a: i % 4,
+                b: u64::from(15 - i),
+            })
+            .collect::<Vec<_>>();
+        let hash_map = keys
+            .iter()
+            .map(|&key| (key, u32::from(key.a)))
+            .collect::<HashMap<_, _>>();
+        let btree_map = keys
+            .iter()
+            .map(|&key| (key, u32::from(key.a)))
+            .collect::<BTreeMap<_, _>>();
+
+        let bytes =
+            to_bytes::<_, 256, Failure>(&(hash_map, btree_map)).unwrap();
+        let archived = access::<
+            Archived<(HashMap<Key, u32>, BTreeMap<Key, u32>)>,
+            Failure,
+        >(&bytes)
+        .unwrap();
+
+        for key in keys {
+            let archived_key = ArchivedKey {
+                a: key.a,
+                b: key.b.into(),
+            };
+            let value = u32::from(key.a);
+            assert_eq!(archived.0.get(&archived_key).unwrap(), &value);
+            assert_eq!(archived.1.get(&archived_key).unwrap(), &value);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn invalid_io_error_kind_tag() {
+        use std::io::ErrorKind;
+
+        use rkyv::{access, Archived};
+
+        for tag in 0..20 {
+            access::<Archived<ErrorKind>, Failure>(&[tag]).unwrap();
+        }
+        assert!(access::<Archived<ErrorKind>, Failure>(&[20]).is_err());
+        assert!(access::<Archived<ErrorKind>, Failure>(&[255]).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn lazy_hashmap() {
+        use rkyv::{access, to_bytes, validation::lazy::LazyArchive, Archived};
+
+        let key = Archived::<u32>::from_native;
+        let name = |i| format!("value {} with a name that is too long", i);
+        let map = (0..100).map(|i| (i, name(i))).collect::<HashMap<_, _>>();
+        let mut bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+        let corrupted = name(42);
+        let pos = bytes
+            .windows(corrupted.len())
+            .position(|window| window == corrupted.as_bytes())
+            .unwrap();
+        bytes[pos] = 0xff;
+        assert!(
+            access::<Archived<HashMap<u32, String>>, Failure>(&bytes).is_err()
+        );
+
+        let archive = LazyArchive::<Archived<HashMap<u32, String>>>::new::<
+            Failure,
+        >(&bytes)
+        .unwrap();
+        let root = archive.root();
+        assert_eq!(root.len(), 100);
+
+        for i in [0, 41, 43, 99] {
+            let value = root.value::<_, Failure>(&key(i)).unwrap().unwrap();
+            assert_eq!(value.get::<Failure>().unwrap(), &name(i));
+        }
+        assert!(root.value::<_, Failure>(&key(100)).unwrap().is_none());
+
+        let validated = archive.validated_count();
+        assert!(validated < 20);
+        let value = root.value::<_, Failure>(&key(41)).unwrap().unwrap();
+        value.get::<Failure>().unwrap();
+        assert_eq!(archive.validated_count(), validated);
+
+        let value = root.value::<_, Failure>(&key(42)).unwrap().unwrap();
+        assert!(value.get::<Failure>().is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn check_hash_table_invariants() {
+        use core::mem::size_of;
+
+        use rkyv::{
+            access, access_unchecked,
+            rancor::BoxedError,
+            to_bytes,
+            util::AlignedVec,
+            validation::{
+                util::access_with_context, validators::DefaultValidator,
+            },
+            Archived, RawRelPtr,
+        };
+
+        type Map = Archived<HashMap<u32, u32>>;
+
+        // The number of control bytes after the first `cap` which repeat the
+        // first control bytes.
+        const TAIL: usize = 15;
+        const ENTRY_SIZE: usize = 2 * size_of::<Archived<u32>>();
+
+        let map = (0..100).map(|i| (i, i * 2)).collect::<HashMap<_, _>>();
+        let bytes = to_bytes::<_, 256, Failure>(&map).unwrap();
+        // SAFETY: `bytes` was just serialized from a `HashMap<u32, u32>`.
+        let archived = unsafe { access_unchecked::<Map>(&bytes) };
+        let cap = archived.capacity();
+        // The relative pointer to the control bytes is the first field of the
+        // archived hash map.
+        let ptr = unsafe { &*(archived as *const Map).cast::<RawRelPtr>() };
+        let control = ptr.as_ptr_wrapping() as usize - bytes.as_ptr() as usize;
+
+        let is_full = |i: usize| bytes[control + i] & 0x80 == 0;
+        let full = (TAIL..cap).find(|&i| is_full(i)).unwrap();
+        let other_full = (full + 1..cap).find(|&i| is_full(i)).unwrap();
+        let empty = (TAIL..cap).find(|&i| !is_full(i)).unwrap();
+        let entry = |i: usize| control - (i + 1) * ENTRY_SIZE;
+
+        let check = |bytes: &AlignedVec| {
+            access::<Map, BoxedError>(bytes)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        };
+        let check_structure = |bytes: &AlignedVec| {
+            let mut validator = DefaultValidator::new(bytes);
+            validator.set_check_hashes(false);
+            access_with_context::<Map, _, BoxedError>(bytes, &mut validator)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        };
+        let assert_invalid = |bytes: &AlignedVec, message: &str| {
+            let error = check(bytes).unwrap_err();
+            assert!(error.contains(message), "unexpected error: {}", error);
+        };
+
+        check(&bytes).unwrap();
+        check_structure(&bytes).unwrap();
+
+        // A full bucket which is not counted in the length
+        let mut extra = bytes.clone();
+        extra[control + empty] = 0x00;
+        assert_invalid(&extra, "does not match its number of full buckets");
+        assert!(check_structure(&extra).is_err());
+
+        // A repeated control byte which differs from the one it repeats
+        let mut unwrapped = bytes.clone();
+        unwrapped[control + cap] ^= 0x01;
+        assert_invalid(&unwrapped, "unwrapped control byte");
+        assert!(check_structure(&unwrapped).is_err());
+
+        // A control byte which doesn't match the hash of its key
+        let mut mismatched = bytes.clone();
+        mismatched[control + full] = (mismatched[control + full] + 1) & 0x7f;
+        assert_invalid(&mismatched, "does not match the hash of its entry");
+        check_structure(&mismatched).unwrap();
+
+        // A duplicate key, only one of which can be found
+        let mut duplicate = bytes.clone();
+        duplicate[control + other_full] = duplicate[control + full];
+        let (from, to) = (entry(full), entry(other_full));
+        duplicate.copy_within(from..from + ENTRY_SIZE, to);
+        assert_invalid(&duplicate, "can not be found by probing for it");
+        check_structure(&duplicate).unwrap();
+    }
+
+    #[test]
+    fn owned_archive_across_threads() {
+        use std::{sync::Arc, thread};
+
+        use rkyv::{
+            to_bytes,
+            util::{OwnedArchive, OwnedView},
+            Archive, Serialize,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Item {
+            name: String,
+            count: u32,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Inventory {
+            owner: String,
+            items: Vec<Item>,
+        }
+
+        let value = Inventory {
+            owner: "a warehouse in the north".to_string(),
+            items: vec![
+                Item {
+                    name: "bolts".to_string(),
+                    count: 500,
+                },
+                Item {
+                    name: "brackets for the shelves".to_string(),
+                    count: 12,
+                },
+            ],
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let buffer = Arc::<[u8]>::from(&bytes[..]);
+
+        let archive =
+            OwnedArchive::<Inventory, Arc<[u8]>>::new::<Failure>(buffer)
+                .unwrap();
+        assert_eq!(archive.owner, "a warehouse in the north");
+
+        let shared = archive.clone();
+        let total = thread::spawn(move || {
+            shared
+                .items
+                .iter()
+                .map(|item| item.count.to_native())
+                .sum::<u32>()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(total, 512);
+
+        let item: OwnedView<ArchivedItem, Arc<[u8]>> =
+            archive.clone().map(|inventory| &inventory.items[1]);
+        drop(archive);
+
+        let start = item.buffer().as_ptr() as usize;
+        let ptr = &*item as *const ArchivedItem as usize;
+        assert!(ptr >= start && ptr < start + item.buffer().len());
+
+        let name = thread::spawn(move || {
+            item.map(|item| item.name.as_str()).to_string()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(name, "brackets for the shelves");
+
+        let bytes = Arc::<[u8]>::from(&bytes[..]);
+        let archive =
+            OwnedArchive::<Inventory, _>::new::<Failure>(bytes).unwrap();
+        let missing = archive
+            .try_map(|inventory| inventory.items.get(2).ok_or("no item"));
+        assert_eq!(missing.err(), Some("no item"));
+
+        let truncated = Arc::<[u8]>::from(&value.owner.as_bytes()[..3]);
+        assert!(
+            OwnedArchive::<Inventory, _>::new::<Failure>(truncated).is_err()
+        );
+    }
+
+    #[test]
+    fn seqlock_publish_and_read() {
+        use rkyv::{
+            seqlock::{SeqLockArchive, HEADER_SIZE},
+            util::AlignedVec,
+            Archive, Serialize,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Config {
+            name: String,
+            limits: Vec<u32>,
+        }
+
+        let mut region = AlignedVec::new();
+        region.resize(HEADER_SIZE + 128, 0);
+        assert!(SeqLockArchive::<Config>::new::<Failure>(
+            &mut region.as_mut_slice()[1..]
+        )
+        .is_err());
+        assert!(SeqLockArchive::<Config>::new::<Failure>(
+            &mut region.as_mut_slice()[..HEADER_SIZE - 1]
+        )
+        .is_err());
+
+        let archive =
+            SeqLockArchive::<Config>::new::<Failure>(region.as_mut_slice())
+                .unwrap();
+        assert_eq!(archive.capacity(), 128);
+        assert_eq!(archive.sequence(), 0);
+        assert!(archive.read::<Failure>().unwrap().is_none());
+
+        let sequence = archive
+            .publish::<Failure>(&Config {
+                name: "primary".to_string(),
+                limits: vec![1, 2, 3],
+            })
+            .unwrap();
+        assert_eq!(sequence, 2);
+        assert_eq!(archive.sequence(), 2);
+
+        let snapshot = archive.read::<Failure>().unwrap().unwrap();
+        assert_eq!(snapshot.name, "primary");
+        assert_eq!(snapshot.limits, [1, 2, 3]);
+
+        // SAFETY: Nothing is published while the archive is borrowed.
+        let total = unsafe {
+            archive.read_with::<Failure, _>(|config| {
+                config.limits.iter().map(|l| l.to_native()).sum::<u32>()
+            })
+        };
+        assert_eq!(total.unwrap(), Some(6));
+
+        // The snapshot owns a copy of the archive and is not affected by
+        // later publishes.
+        archive
+            .publish::<Failure>(&Config {
+                name: "secondary".to_string(),
+                limits: Vec::new(),
+            })
+            .unwrap();
+        assert_eq!(snapshot.name, "primary");
+        assert_eq!(
+            archive.read::<Failure>().unwrap().unwrap().name,
+            "secondary"
+        );
+
+        // Failing to publish leaves the region empty.
+        let result = archive.publish::<Failure>(&Config {
+            name: "x".repeat(200),
+            limits: Vec::new(),
+        });
+        assert!(result.is_err());
+        assert_eq!(archive.sequence(), 6);
+        assert!(archive.read::<Failure>().unwrap().is_none());
+        // SAFETY: Nothing is published while the archive is borrowed.
+        let empty = unsafe { archive.read_with::<Failure, _>(|_| ()) };
+        assert_eq!(empty.unwrap(), None);
+
+        archive
+            .publish::<Failure>(&Config {
+                name: "restored".to_string(),
+                limits: vec![4],
+            })
+            .unwrap();
+        assert_eq!(
+            archive.read::<Failure>().unwrap().unwrap().name,
+            "restored"
+        );
+    }
+
+    #[test]
+    fn seqlock_concurrent_publish_and_read() {
+        use std::thread;
+
+        use rkyv::{
+            seqlock::{SeqLockArchive, HEADER_SIZE},
+            util::AlignedVec,
+            Archive, Serialize,
+        };
+
+        #[derive(Archive, Serialize)]
+        #[archive(check_bytes)]
+        struct Frame {
+            generation: u64,
+            label: String,
+            values: Vec<u64>,
+        }
+
+        fn frame(generation: u64) -> Frame {
+            Frame {
+                generation,
+                label: format!("frame number {}", generation),
+                values: vec![generation; generation as usize % 256 + 1],
+            }
+        }
+
+        const LAST: u64 = 50_000;
+        const READERS: usize = 4;
+
+        let mut region = AlignedVec::new();
+        region.resize(HEADER_SIZE + 4096, 0);
+        let archive =
+            SeqLockArchive::<Frame>::new::<Failure>(region.as_mut_slice())
+                .unwrap();
+
+        thread::scope(|scope| {
+            let archive = &archive;
+            for _ in 0..READERS {
+                scope.spawn(move || {
+                    let mut last = 0;
+                    while last != LAST {
+                        let snapshot = match archive.read::<Failure>() {
+                            Ok(Some(snapshot)) => snapshot,
+                            Ok(None) => continue,
+                            Err(e) => panic!("failed to read frame: {}", e),
+                        };
+                        let generation = snapshot.generation.to_native();
+                        assert!(generation >= last);
+                        assert_eq!(
+                            snapshot.label,
+                            format!("frame number {}", generation)
+                        );
+                        assert_eq!(
+                            snapshot.values.len(),
+                            generation as usize % 256 + 1
+                        );
+                        assert!(snapshot
+                            .values
+                            .iter()
+                            .all(|v| v.to_native() == generation));
+                        last = generation;
+                    }
+                });
+            }
+
+            scope.spawn(move || {
+                for generation in 1..=LAST {
+                    let sequence =
+                        archive.publish::<Failure>(&frame(generation)).unwrap();
+                    assert_eq!(sequence, generation * 2);
+                }
+            });
+        });
+
+        // Publishing from two threads at once never corrupts the archive.
+        thread::scope(|scope| {
+            let archive = &archive;
+            for offset in 0..2 {
+                scope.spawn(move || {
+                    let mut generation = LAST + 1 + offset;
+                    while generation <= 2 * LAST {
+                        if archive
+                            .publish::<Failure>(&frame(generation))
+                            .is_ok()
+                        {
+                            generation += 2;
+                        }
+                    }
+                });
+            }
+            scope.spawn(move || {
+                for _ in 0..1000 {
+                    let snapshot = archive.read::<Failure>().unwrap().unwrap();
+                    let generation = snapshot.generation.to_native();
+                    assert_eq!(
+                        snapshot.values.len(),
+                        generation as usize % 256 + 1
+                    );
+                }
+            });
+        });
+        assert_eq!(archive.sequence(), 4 * LAST);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_value() {
+        use rkyv::{
+            deserialize, to_bytes,
+            value::{access_value, ArchivedValue, Value, MAX_DEPTH},
+        };
+
+        let value = Value::Map(HashMap::from([
+            ("null".to_string(), Value::Null),
+            ("enabled".to_string(), Value::Bool(true)),
+            ("count".to_string(), Value::Int(-42)),
+            ("ratio".to_string(), Value::Float(0.25)),
+            ("bytes".to_string(), Value::Bytes(vec![1, 2, 3])),
+            (
+                "a".to_string(),
+                Value::Map(HashMap::from([(
+                    "b".to_string(),
+                    Value::List(vec![
+                        Value::Int(0),
+                        Value::String("one".to_string()),
+                        Value::Map(HashMap::from([(
+                            "c".to_string(),
+                            Value::String("found".to_string()),
+                        )])),
+                        Value::List(vec![Value::List(vec![Value::Int(7)])]),
+                    ]),
+                )])),
+            ),
+        ]));
+
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = access_value::<Failure>(&bytes).unwrap();
+        assert_eq!(*archived, value);
+        assert_eq!(value, *archived);
+        assert_eq!(
+            deserialize::<Value, _, Failure>(archived, &mut ()).unwrap(),
+            value
+        );
+
+        assert!(archived.get("null").unwrap().is_null());
+        assert_eq!(archived.get("enabled").unwrap().as_bool(), Some(true));
+        assert_eq!(archived.get("count").unwrap().as_i64(), Some(-42));
+        assert_eq!(archived.get("count").unwrap().as_f64(), None);
+        assert_eq!(archived.get("ratio").unwrap().as_f64(), Some(0.25));
+        assert_eq!(
+            archived.get("bytes").unwrap().as_bytes(),
+            Some([1, 2, 3].as_slice())
+        );
+        assert!(archived.get("missing").is_none());
+
+        assert_eq!(
+            archived.get_path("").map(|v| v as *const _),
+            Some(archived as *const _)
+        );
+        assert_eq!(archived.get_path("a.b[1]").unwrap().as_str(), Some("one"));
+        assert_eq!(
+            archived.get_path("a.b[2].c").unwrap().as_str(),
+            Some("found")
+        );
+        assert_eq!(
+            archived.get_path("a.b[3][0][0]").unwrap().as_i64(),
+            Some(7)
+        );
+        assert_eq!(
+            archived.get_path("a.b").unwrap().as_list().unwrap().len(),
+            4
+        );
+        assert!(archived.get_path("a.b[4]").is_none());
+        assert!(archived.get_path("a.b[x]").is_none());
+        assert!(archived.get_path("a.b[1").is_none());
+        assert!(archived.get_path("a..b").is_none());
+        assert!(archived.get_path("count.b").is_none());
+
+        let list = Value::List(vec![Value::Map(HashMap::from([(
+            "a".to_string(),
+            Value::Null,
+        )]))]);
+        let bytes = to_bytes::<_, 256, Failure>(&list).unwrap();
+        let archived = access_value::<Failure>(&bytes).unwrap();
+        assert!(archived.get_path("[0].a").unwrap().is_null());
+        assert!(archived.get("a").is_none());
+
+        fn nested(depth: usize) -> Value {
+            let mut value = Value::Int(1);
+            for _ in 0..depth {
+                value = Value::List(vec![value]);
+            }
+            value
+        }
+
+        let shallow = nested(MAX_DEPTH / 2);
+        let bytes = to_bytes::<_, 256, Failure>(&shallow).unwrap();
+        assert_eq!(*access_value::<Failure>(&bytes).unwrap(), shallow);
+
+        let deep = nested(MAX_DEPTH * 2);
+        let bytes = to_bytes::<_, 256, Failure>(&deep).unwrap();
+        assert!(access_value::<Failure>(&bytes).is_err());
+        assert!(rkyv::access::<ArchivedValue, Failure>(&bytes).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn value_serde_json() {
+        use rkyv::value::Value;
+
+        let json = serde_json::json!({
+            "name": "widget",
+            "sizes": [1, 2.5, -3],
+            "nested": { "ok": true, "none": null },
+        });
+        let value = Value::from(json.clone());
+        assert_eq!(
+            value,
+            Value::Map(HashMap::from([
+                ("name".to_string(), Value::String("widget".to_string())),
+                (
+                    "sizes".to_string(),
+                    Value::List(vec![
+                        Value::Int(1),
+                        Value::Float(2.5),
+                        Value::Int(-3)
+                    ]),
+                ),
+                (
+                    "nested".to_string(),
+                    Value::Map(HashMap::from([
+                        ("ok".to_string(), Value::Bool(true)),
+                        ("none".to_string(), Value::Null),
+                    ])),
+                ),
+            ]))
+        );
+        assert_eq!(serde_json::Value::from(value), json);
+
+        assert_eq!(
+            serde_json::Value::from(Value::Bytes(vec![1, 2])),
+            serde_json::json!([1, 2])
+        );
+        assert_eq!(
+            serde_json::Value::from(Value::Float(f64::NAN)),
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn mutate_unpinned() {
+        use core::pin::Pin;
+
+        use rkyv::{
+            access, access_mut, from_bytes,
+            option::ArchivedOption,
+            to_bytes,
+            unpin::{unpin_mut, ArchivedNoRelPtrs},
+            vec::ArchivedVec,
+            Archive, Archived, Deserialize, Serialize,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq, Clone)]
+        #[archive(check_bytes)]
+        struct Reading {
+            value: f32,
+            flags: [u8; 4],
+            limit: Option<u16>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Station {
+            name: String,
+            readings: Vec<Reading>,
+            by_id: HashMap<u32, Reading>,
+            calibration: Option<(i32, i32)>,
+        }
+
+        fn assert_no_rel_ptrs<T: ArchivedNoRelPtrs + ?Sized>() {}
+        assert_no_rel_ptrs::<Archived<Reading>>();
+        assert_no_rel_ptrs::<[Archived<Reading>]>();
+        assert_no_rel_ptrs::<Archived<Option<(i32, i32)>>>();
+
+        impl ArchivedStation {
+            fn readings(
+                self: Pin<&mut Self>,
+            ) -> Pin<&mut ArchivedVec<ArchivedReading>> {
+                unsafe { self.map_unchecked_mut(|s| &mut s.readings) }
+            }
+
+            fn by_id(
+                self: Pin<&mut Self>,
+            ) -> Pin<&mut Archived<HashMap<u32, Reading>>> {
+                unsafe { self.map_unchecked_mut(|s| &mut s.by_id) }
+            }
+
+            fn calibration(
+                self: Pin<&mut Self>,
+            ) -> Pin<&mut Archived<Option<(i32, i32)>>> {
+                unsafe { self.map_unchecked_mut(|s| &mut s.calibration) }
+            }
+        }
+
+        let reading = |i: u16| Reading {
+            value: i as f32,
+            flags: [i as u8; 4],
+            limit: (i % 2 == 0).then_some(i * 10),
+        };
+        let value = Station {
+            name: "a station with a long name".to_string(),
+            readings: (0..8).map(reading).collect(),
+            by_id: (0..8).map(|i| (i as u32, reading(i))).collect(),
+            calibration: Some((-1, 1)),
+        };
+
+        let mut bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let mut station =
+            access_mut::<ArchivedStation, Failure>(&mut bytes).unwrap();
+
+        for r in station.as_mut().readings().as_mut_slice() {
+            r.value = (r.value.to_native() * 2.0).into();
+            r.flags.reverse();
+            if let Some(limit) = r.limit.as_mut() {
+                *limit = (limit.to_native() + 1).into();
+            }
+        }
+        station.as_mut().readings().as_mut_slice().swap(0, 7);
+
+        let entry = station
+            .as_mut()
+            .by_id()
+            .get_mut_unpinned(&Archived::<u32>::from_native(3))
+            .unwrap();
+        entry.limit = ArchivedOption::Some(7.into());
+        assert!(station
+            .as_mut()
+            .by_id()
+            .get_mut_unpinned(&Archived::<u32>::from_native(8))
+            .is_none());
+
+        let calibration = station.as_mut().calibration().as_mut_unpinned();
+        let calibration = calibration.unwrap();
+        core::mem::swap(&mut calibration.0, &mut calibration.1);
+        *unpin_mut(station.as_mut().calibration()) = ArchivedOption::None;
+        assert!(station.calibration.is_none());
+
+        // The archive is still valid after being mutated.
+        access::<ArchivedStation, Failure>(&bytes).unwrap();
+        let station = from_bytes::<Station, Failure>(&bytes).unwrap();
+
+        let mut readings = (0..8)
+            .map(|i| {
+                let mut r = reading(i);
+                r.value *= 2.0;
+                r.limit = r.limit.map(|l| l + 1);
+                r
+            })
+            .collect::<Vec<_>>();
+        readings.swap(0, 7);
+        let mut by_id = value.by_id.clone();
+        by_id.get_mut(&3).unwrap().limit = Some(7);
+        assert_eq!(
+            station,
+            Station {
+                name: value.name.clone(),
+                readings,
+                by_id,
+                calibration: None,
+            }
+        );
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn extract_nested_with_sharing() {
+        use std::{collections::BTreeMap, rc::Rc};
+
+        use rkyv::{
+            access, access_unchecked, from_bytes, to_bytes, util::extract,
+            Archive, Archived, Deserialize, Serialize,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Leaf {
+            name: String,
+            tags: Vec<u16>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Branch {
+            id: u32,
+            label: String,
+            first: Rc<Leaf>,
+            second: Rc<Leaf>,
+            by_name: HashMap<String, Vec<u32>>,
+            ordered: BTreeMap<u32, String>,
+            blob: Box<[u8]>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Tree {
+            title: String,
+            branches: Vec<Branch>,
+        }
+
+        let shared = Rc::new(Leaf {
+            name: "a leaf shared by every branch".to_string(),
+            tags: vec![1, 2, 3],
+        });
+        let branch = |id: u32| Branch {
+            id,
+            label: format!("branch number {} of the tree", id),
+            first: shared.clone(),
+            second: if id % 2 == 0 {
+                shared.clone()
+            } else {
+                Rc::new(Leaf {
+                    name: format!("leaf {}", id),
+                    tags: (0..id as u16).collect(),
+                })
+            },
+            by_name: (0..id)
+                .map(|i| (format!("key {}", i), (0..i).collect()))
+                .collect(),
+            ordered: (0..10 * id)
+                .map(|i| (i, format!("entry {}", i)))
+                .collect(),
+            blob: vec![id as u8; id as usize].into_boxed_slice(),
+        };
+        let value = Tree {
+            title: "a tree with a very long title".to_string(),
+            branches: (0..6).map(branch).collect(),
+        };
+        let bytes = to_bytes::<_, 256, Failure>(&value).unwrap();
+        let archived = unsafe { access_unchecked::<ArchivedTree>(&bytes) };
+
+        for (i, branch) in archived.branches.iter().enumerate() {
+            let extracted = extract::<Branch, Failure>(&bytes, branch).unwrap();
+            assert!(extracted.len() < bytes.len());
+
+            let copy = access::<ArchivedBranch, Failure>(&extracted).unwrap();
+            assert_eq!(copy.label, branch.label);
+            let is_shared = core::ptr::eq(&*copy.first, &*copy.second);
+            assert_eq!(is_shared, i % 2 == 0);
+            let copied = from_bytes::<Branch, Failure>(&extracted).unwrap();
+            assert_eq!(copied, value.branches[i]);
+        }
+
+        let ordered = &archived.branches[5].ordered;
+        let extracted =
+            extract::<BTreeMap<u32, String>, Failure>(&bytes, ordered).unwrap();
+        let copy =
+            from_bytes::<BTreeMap<u32, String>, Failure>(&extracted).unwrap();
+        assert_eq!(copy, value.branches[5].ordered);
+
+        let leaf = &*archived.branches[3].second;
+        let extracted = extract::<Leaf, Failure>(&bytes, leaf).unwrap();
+        let copy = from_bytes::<Leaf, Failure>(&extracted).unwrap();
+        assert_eq!(&copy, &*value.branches[3].second);
+
+        // The whole archive can be extracted from its root
+        let extracted = extract::<Tree, Failure>(&bytes, archived).unwrap();
+        assert!(extracted.len() <= bytes.len());
+        assert_eq!(from_bytes::<Tree, Failure>(&extracted).unwrap(), value);
+
+        // Shared values inside of B-tree nodes can't be moved
+        let shared = Rc::new("shared".to_string());
+        let pair = (shared.clone(), BTreeMap::from([(1u32, shared)]));
+        let bytes = to_bytes::<_, 256, Failure>(&pair).unwrap();
+        let archived = unsafe {
+            access_unchecked::<Archived<(Rc<String>, BTreeMap<u32, Rc<String>>)>>(
+                &bytes,
+            )
+        };
+        let map = &archived.1;
+        assert!(
+            extract::<BTreeMap<u32, Rc<String>>, Failure>(&bytes, map).is_err()
+        );
+
+        // Values outside of the archive can't be extracted
+        let other = Archived::<u32>::from_native(1);
+        assert!(extract::<u32, Failure>(&bytes, &other).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn extract_random_structures() {
+        use std::{collections::BTreeMap, rc::Rc};
+
+        use rkyv::{
+            access, access_unchecked, from_bytes, to_bytes, util::extract,
+            vec::ArchivedVec, Archive, Deserialize, Serialize,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(check_bytes)]
+        struct Record {
+            name: String,
+            values: Vec<u32>,
+            children: Vec<Vec<String>>,
+            lookup: HashMap<u16, String>,
+            index: BTreeMap<u32, Vec<u8>>,
+            first: Option<Rc<String>>,
+            second: Option<Rc<String>>,
+            boxed: Option<Box<[u64]>>,
+        }
+
+        // A small xorshift generator so that failures are reproducible
+        struct Rng(u64);
+
+        impl Rng {
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+
+            fn below(&mut self, n: u64) -> usize {
+                (self.next() % n) as usize
+            }
+
+            fn string(&mut self) -> String {
+                let len = self.below(40);
+                (0..len)
+                    .map(|_| (b'a' + self.below(26) as u8) as char)
+                    .collect()
+            }
+        }
+
+        fn record(rng: &mut Rng, pool: &[Rc<String>]) -> Record {
+            let shared = |rng: &mut Rng| {
+                (rng.below(3) != 0).then(|| pool[rng.below(3)].clone())
+            };
+            Record {
+                name: rng.string(),
+                values: (0..rng.below(20)).map(|_| rng.next() as u32).collect(),
+                children: (0..rng.below(5))
+                    .map(|_| (0..rng.below(5)).map(|_| rng.string()).collect())
+                    .collect(),
+                lookup: (0..rng.below(30))
+                    .map(|_| (rng.next() as u16, rng.string()))
+                    .collect(),
+                index: (0..rng.below(50))
+                    .map(|_| {
+                        let len = rng.below(10);
+                        (rng.next() as u32, vec![rng.next() as u8; len])
+                    })
+                    .collect(),
+                first: shared(rng),
+                second: shared(rng),
+                boxed: (rng.below(2) == 0)
+                    .then(|| (0..rng.below(8)).map(|_| rng.next()).collect()),
+            }
+        }
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..20 {
+            let pool =
+                (0..3).map(|_| Rc::new(rng.string())).collect::<Vec<_>>();
+            let records = (0..rng.below(8) + 1)
+                .map(|_| record(&mut rng, &pool))
+                .collect::<Vec<_>>();
+            let bytes = to_bytes::<_, 256, Failure>(&records).unwrap();
+            let archived = unsafe {
+                access_unchecked::<ArchivedVec<ArchivedRecord>>(&bytes)
+            };
+
+            for (record, value) in archived.iter().zip(records.iter()) {
+                let extracted =
+                    extract::<Record, Failure>(&bytes, record).unwrap();
+                let copy =
+                    access::<ArchivedRecord, Failure>(&extracted).unwrap();
+                let shares = |a: &ArchivedRecord| match (
+                    a.first.as_ref(),
+                    a.second.as_ref(),
+                ) {
+                    (Some(x), Some(y)) => core::ptr::eq(&**x, &**y),
+                    _ => false,
+                };
+                assert_eq!(shares(copy), shares(record));
+                let copied = from_bytes::<Record, Failure>(&extracted).unwrap();
+                assert_eq!(&copied, value);
+
+                for (children, expected) in
+                    record.children.iter().zip(value.children.iter())
+                {
+                    let extracted =
+                        extract::<Vec<String>, Failure>(&bytes, children)
+                            .unwrap();
+                    let copy =
+                        from_bytes::<Vec<String>, Failure>(&extracted).unwrap();
+                    assert_eq!(&copy, expected);
+                }
+
+                let extracted = extract::<BTreeMap<u32, Vec<u8>>, Failure>(
+                    &bytes,
+                    &record.index,
+                )
+                .unwrap();
+                let copy =
+                    from_bytes::<BTreeMap<u32, Vec<u8>>, Failure>(&extracted)
+                        .unwrap();
+                assert_eq!(copy, value.index);
+            }
+        }
+    }
 }
@@ -0,0 +1,121 @@
+//! Reads archives from C through the functions generated by `c_api`.
+
+#![cfg(feature = "c_api")]
+
+use std::{
+    collections::HashMap,
+    ffi::{c_int, c_void},
+    fs,
+    path::Path,
+};
+
+use rkyv::{c_api, rancor::Failure, to_bytes, Archive, Serialize};
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes, c_api(prefix = "point"))]
+struct Point {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Archive, Serialize)]
+#[archive(check_bytes, c_api(prefix = "record"))]
+struct Record {
+    id: u32,
+    score: i64,
+    active: bool,
+    name: String,
+    values: Vec<u32>,
+    origin: Point,
+    counts: HashMap<String, u32>,
+    places: HashMap<String, Point>,
+}
+
+#[link(name = "c_api_host", kind = "static")]
+extern "C" {
+    fn host_run(bytes: *const u8, len: usize) -> c_int;
+}
+
+// The generated functions are only reachable through their symbols
+extern "C" {
+    fn record_check(ptr: *const u8, len: usize) -> c_int;
+    fn record_root(
+        ptr: *const u8,
+        len: usize,
+        out: *mut *const c_void,
+    ) -> c_int;
+}
+
+fn header() -> String {
+    c_api::CHeader::new("RKYV_TEST_C_API_H")
+        .add::<ArchivedPoint>()
+        .add::<ArchivedRecord>()
+        .finish()
+}
+
+fn record() -> Record {
+    Record {
+        id: 42,
+        score: -7,
+        active: true,
+        name: "hello".to_string(),
+        values: vec![1, 2, 3],
+        origin: Point { x: 1.5, y: -2.0 },
+        counts: [("a".to_string(), 1), ("b".to_string(), 2)]
+            .into_iter()
+            .collect(),
+        places: [("home".to_string(), Point { x: 3.0, y: 4.0 })]
+            .into_iter()
+            .collect(),
+    }
+}
+
+// The C host is compiled against the committed header. Run with
+// `RKYV_BLESS=1` to regenerate it after changing the types above.
+#[test]
+fn generated_header() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("c_api")
+        .join("generated.h");
+    let header = header();
+    if std::env::var_os("RKYV_BLESS").is_some() {
+        fs::write(&path, &header).unwrap();
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        header, expected,
+        "generated header changed, run with RKYV_BLESS=1 to regenerate it",
+    );
+}
+
+#[test]
+fn read_from_c() {
+    let bytes = to_bytes::<_, 256, Failure>(&record()).unwrap();
+    let result = unsafe { host_run(bytes.as_ptr(), bytes.len()) };
+    assert_eq!(result, 0, "check on line {} of host.c failed", result);
+}
+
+#[test]
+fn invalid_archives() {
+    let mut bytes = to_bytes::<_, 256, Failure>(&record()).unwrap();
+    assert_eq!(
+        unsafe { record_check(bytes.as_ptr(), bytes.len()) },
+        c_api::OK,
+    );
+
+    // Point the name past the end of the buffer
+    let len = bytes.len();
+    bytes[len - 4..].fill(0x7f);
+    assert_eq!(
+        unsafe { record_check(bytes.as_ptr(), bytes.len()) },
+        c_api::ERROR_INVALID,
+    );
+
+    let mut handle = std::ptr::null();
+    assert_eq!(
+        unsafe { record_root(bytes.as_ptr(), bytes.len(), &mut handle) },
+        c_api::ERROR_INVALID,
+    );
+    assert!(handle.is_null());
+}
@@ -0,0 +1,53 @@
+/* Generated by rkyv. Do not edit. */
+#ifndef RKYV_TEST_C_API_H
+#define RKYV_TEST_C_API_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef RKYV_C_API
+#define RKYV_C_API
+
+#define RKYV_OK 0
+#define RKYV_ERROR_NULL -1
+#define RKYV_ERROR_ALIGN -2
+#define RKYV_ERROR_INVALID -3
+#define RKYV_ERROR_NOT_FOUND -4
+
+typedef struct rkyv_slice {
+    const void *ptr;
+    size_t len;
+} rkyv_slice;
+
+#endif /* RKYV_C_API */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* ArchivedPoint */
+int point_check(const uint8_t *ptr, size_t len);
+int point_root(const uint8_t *ptr, size_t len, const void **out);
+int point_get_x(const void *handle, float *out);
+int point_get_y(const void *handle, float *out);
+
+/* ArchivedRecord */
+int record_check(const uint8_t *ptr, size_t len);
+int record_root(const uint8_t *ptr, size_t len, const void **out);
+int record_get_id(const void *handle, uint32_t *out);
+int record_get_score(const void *handle, int64_t *out);
+int record_get_active(const void *handle, bool *out);
+int record_get_name(const void *handle, rkyv_slice *out);
+int record_get_values(const void *handle, rkyv_slice *out);
+int record_get_origin(const void *handle, rkyv_slice *out);
+int record_get_counts(const void *handle, rkyv_slice *out);
+int record_counts_get(const void *table, const uint8_t *key, size_t key_len, uint32_t *out);
+int record_get_places(const void *handle, rkyv_slice *out);
+int record_places_get(const void *table, const uint8_t *key, size_t key_len, rkyv_slice *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RKYV_TEST_C_API_H */
@@ -0,0 +1,91 @@
+// A C host which reads archives through the functions generated by
+// `#[archive(c_api(...))]` in `tests/c_api.rs`.
+
+#include <string.h>
+
+#include "generated.h"
+
+#define CHECK(cond) \
+    do { \
+        if (!(cond)) { \
+            return __LINE__; \
+        } \
+    } while (0)
+
+// Expands to the pointer and length arguments for a string literal key
+#define KEY(s) (const uint8_t *)(s), sizeof(s) - 1
+
+static uint32_t read_u32_le(const uint8_t *bytes) {
+    return (uint32_t)bytes[0] | (uint32_t)bytes[1] << 8
+        | (uint32_t)bytes[2] << 16 | (uint32_t)bytes[3] << 24;
+}
+
+// Returns 0 if every check passes, or the line of the first failed check.
+int host_run(const uint8_t *bytes, size_t len) {
+    const void *record = NULL;
+    const void *place = NULL;
+    rkyv_slice slice;
+    uint32_t u32_value;
+    int64_t i64_value;
+    float float_value;
+    bool bool_value;
+
+    CHECK(record_check(bytes, len) == RKYV_OK);
+    CHECK(record_check(NULL, len) == RKYV_ERROR_NULL);
+    CHECK(record_check(bytes + 1, len - 1) == RKYV_ERROR_ALIGN);
+    CHECK(record_check(bytes, 1) == RKYV_ERROR_INVALID);
+
+    CHECK(record_root(bytes, len, NULL) == RKYV_ERROR_NULL);
+    CHECK(record_root(bytes, len, &record) == RKYV_OK);
+    CHECK(record != NULL);
+
+    CHECK(record_get_id(record, &u32_value) == RKYV_OK);
+    CHECK(u32_value == 42);
+    CHECK(record_get_score(record, &i64_value) == RKYV_OK);
+    CHECK(i64_value == -7);
+    CHECK(record_get_active(record, &bool_value) == RKYV_OK);
+    CHECK(bool_value);
+
+    CHECK(record_get_name(record, &slice) == RKYV_OK);
+    CHECK(slice.len == 5 && memcmp(slice.ptr, "hello", 5) == 0);
+
+    CHECK(record_get_values(record, &slice) == RKYV_OK);
+    CHECK(slice.len == 3);
+    CHECK(read_u32_le((const uint8_t *)slice.ptr) == 1);
+    CHECK(read_u32_le((const uint8_t *)slice.ptr + 8) == 3);
+
+    CHECK(record_get_origin(record, &slice) == RKYV_OK);
+    CHECK(point_get_x(slice.ptr, &float_value) == RKYV_OK);
+    CHECK(float_value == 1.5f);
+    CHECK(point_get_y(slice.ptr, &float_value) == RKYV_OK);
+    CHECK(float_value == -2.0f);
+
+    CHECK(record_get_counts(record, &slice) == RKYV_OK);
+    CHECK(slice.len == 2);
+    CHECK(record_counts_get(slice.ptr, KEY("b"), &u32_value) == RKYV_OK);
+    CHECK(u32_value == 2);
+    CHECK(
+        record_counts_get(slice.ptr, KEY("c"), &u32_value)
+            == RKYV_ERROR_NOT_FOUND
+    );
+    CHECK(
+        record_counts_get(slice.ptr, KEY("\xff"), &u32_value)
+            == RKYV_ERROR_NOT_FOUND
+    );
+    CHECK(record_counts_get(slice.ptr, NULL, 1, &u32_value) == RKYV_ERROR_NULL);
+
+    CHECK(record_get_places(record, &slice) == RKYV_OK);
+    CHECK(record_places_get(slice.ptr, KEY("home"), &slice) == RKYV_OK);
+    place = slice.ptr;
+    CHECK(point_get_y(place, &float_value) == RKYV_OK);
+    CHECK(float_value == 4.0f);
+
+    CHECK(record_get_id(NULL, &u32_value) == RKYV_ERROR_NULL);
+    CHECK(record_get_id(record, NULL) == RKYV_ERROR_NULL);
+    CHECK(
+        record_get_id((const uint8_t *)record + 1, &u32_value)
+            == RKYV_ERROR_ALIGN
+    );
+
+    return 0;
+}